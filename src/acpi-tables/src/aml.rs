@@ -6,9 +6,18 @@
 
 #![allow(missing_debug_implementations)]
 
-use std::marker::PhantomData;
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::vec::Vec;
+use alloc::{format, vec};
+use core::marker::PhantomData;
 
-#[derive(Debug, Clone, thiserror::Error, displaydoc::Display)]
+use crate::MemoryAffinity;
+
+pub mod disasm;
+
+#[derive(Debug, Clone, displaydoc::Display)]
+#[cfg_attr(feature = "std", derive(thiserror::Error))]
 pub enum AmlError {
     /// Aml Path is empty
     NameEmpty,
@@ -16,8 +25,23 @@ pub enum AmlError {
     InvalidPartLength,
     /// Invalid address range
     AddressRange,
+    /// AML ends in the middle of a term
+    Truncated,
+    /// Unknown AML opcode {0:#04x} at offset {1}
+    UnknownOpcode(u8, usize),
+    /// Invalid field flags {0:#04x}
+    InvalidFieldFlags(u8),
+    /// Invalid name segment {0:?}
+    InvalidNameSeg(String),
+    /// {0} is defined more than once
+    DuplicateName(String),
+    /// {0} referenced from {1} does not resolve to an object
+    UnresolvedPath(String, String),
 }
 
+#[cfg(not(feature = "std"))]
+impl core::error::Error for AmlError {}
+
 pub trait Aml {
     fn append_aml_bytes(&self, _v: &mut Vec<u8>) -> Result<(), AmlError>;
 
@@ -58,6 +82,7 @@ impl Aml for Ones {
     }
 }
 
+#[derive(Clone)]
 pub struct Path {
     root: bool,
     name_parts: Vec<[u8; 4]>,
@@ -493,7 +518,7 @@ macro_rules! impl_address_space {
     ($type:ty, $descriptor:expr) => {
         impl Aml for AddressSpace<$type> {
             fn append_aml_bytes(&self, bytes: &mut Vec<u8>) -> Result<(), AmlError> {
-                const FIELD_SIZE: usize = std::mem::size_of::<$type>();
+                const FIELD_SIZE: usize = core::mem::size_of::<$type>();
                 self.push_header(
                     bytes,
                     $descriptor,
@@ -552,6 +577,7 @@ pub struct Interrupt {
     edge_triggered: bool,
     active_low: bool,
     shared: bool,
+    wake_capable: bool,
     number: u32,
 }
 
@@ -568,16 +594,24 @@ impl Interrupt {
             edge_triggered,
             active_low,
             shared,
+            wake_capable: false,
             number,
         }
     }
+
+    /// Mark the interrupt as able to wake the system from a sleep state.
+    pub fn wake_capable(mut self) -> Self {
+        self.wake_capable = true;
+        self
+    }
 }
 
 impl Aml for Interrupt {
     fn append_aml_bytes(&self, bytes: &mut Vec<u8>) -> Result<(), AmlError> {
         bytes.push(0x89); // Extended IRQ Descriptor
         bytes.extend_from_slice(&6u16.to_le_bytes());
-        let flags = (u8::from(self.shared) << 3)
+        let flags = (u8::from(self.wake_capable) << 4)
+            | (u8::from(self.shared) << 3)
             | (u8::from(self.active_low) << 2)
             | (u8::from(self.edge_triggered) << 1)
             | u8::from(self.consumer);
@@ -588,6 +622,490 @@ impl Aml for Interrupt {
     }
 }
 
+pub struct FixedIo {
+    base: u16,
+    length: u8,
+}
+
+impl FixedIo {
+    /// Create a fixed I/O port descriptor, of `length` ports from `base`, which only decodes
+    /// the 10 lower bits of the addresses.
+    pub fn new(base: u16, length: u8) -> Self {
+        FixedIo { base, length }
+    }
+}
+
+impl Aml for FixedIo {
+    fn append_aml_bytes(&self, bytes: &mut Vec<u8>) -> Result<(), AmlError> {
+        bytes.push(0x4b); // Fixed Location IO Port Descriptor
+        bytes.extend_from_slice(&self.base.to_le_bytes());
+        bytes.push(self.length);
+        Ok(())
+    }
+}
+
+#[derive(Copy, Clone)]
+pub enum DmaChannelSpeed {
+    Compatibility,
+    TypeA,
+    TypeB,
+    TypeF,
+}
+
+#[derive(Copy, Clone)]
+pub enum DmaTransferSize {
+    Transfer8,
+    Transfer8And16,
+    Transfer16,
+}
+
+pub struct Dma {
+    speed: DmaChannelSpeed,
+    bus_master: bool,
+    transfer_size: DmaTransferSize,
+    channel_mask: u8,
+}
+
+impl Dma {
+    /// Create a legacy DMA descriptor, for the channels whose bits are set in `channel_mask`.
+    pub fn new(
+        speed: DmaChannelSpeed,
+        bus_master: bool,
+        transfer_size: DmaTransferSize,
+        channel_mask: u8,
+    ) -> Self {
+        Dma {
+            speed,
+            bus_master,
+            transfer_size,
+            channel_mask,
+        }
+    }
+}
+
+impl Aml for Dma {
+    fn append_aml_bytes(&self, bytes: &mut Vec<u8>) -> Result<(), AmlError> {
+        bytes.push(0x2a); // DMA Descriptor
+        bytes.push(self.channel_mask);
+        let flags =
+            ((self.speed as u8) << 5) | (u8::from(self.bus_master) << 2) | self.transfer_size as u8;
+        bytes.push(flags);
+        Ok(())
+    }
+}
+
+#[derive(Copy, Clone)]
+pub enum GpioPinConfig {
+    Default,
+    PullUp,
+    PullDown,
+    NoPull,
+}
+
+#[derive(Copy, Clone)]
+pub enum GpioIoRestriction {
+    None,
+    InputOnly,
+    OutputOnly,
+    NoneAndPreserve,
+}
+
+// Fields shared by the GpioInt and GpioIo connection descriptors.
+struct GpioConnection {
+    connection_type: u8,
+    flags: u16,
+    pin_config: GpioPinConfig,
+    drive_strength: u16,
+    debounce_timeout: u16,
+    pins: Vec<u16>,
+    resource_source: String,
+}
+
+impl Aml for GpioConnection {
+    fn append_aml_bytes(&self, bytes: &mut Vec<u8>) -> Result<(), AmlError> {
+        const HEADER_LENGTH: usize = 23;
+        let resource_source_offset = HEADER_LENGTH + 2 * self.pins.len();
+        let vendor_data_offset = resource_source_offset + self.resource_source.len() + 1;
+        let word = |value: usize| TryInto::<u16>::try_into(value).unwrap().to_le_bytes();
+
+        bytes.push(0x8c); // GPIO Connection Descriptor
+        bytes.extend_from_slice(&word(vendor_data_offset - 3));
+        bytes.push(1); // Revision ID
+        bytes.push(self.connection_type);
+        bytes.extend_from_slice(&1u16.to_le_bytes()); // ResourceConsumer
+        bytes.extend_from_slice(&self.flags.to_le_bytes());
+        bytes.push(self.pin_config as u8);
+        bytes.extend_from_slice(&self.drive_strength.to_le_bytes());
+        bytes.extend_from_slice(&self.debounce_timeout.to_le_bytes());
+        bytes.extend_from_slice(&word(HEADER_LENGTH)); // Pin Table Offset
+        bytes.push(0); // Resource Source Index
+        bytes.extend_from_slice(&word(resource_source_offset));
+        bytes.extend_from_slice(&word(vendor_data_offset));
+        bytes.extend_from_slice(&0u16.to_le_bytes()); // Vendor Data Length
+        for pin in &self.pins {
+            bytes.extend_from_slice(&pin.to_le_bytes());
+        }
+        bytes.extend_from_slice(self.resource_source.as_bytes());
+        bytes.push(0);
+        Ok(())
+    }
+}
+
+/// GPIO interrupt connection to the `pins` of the GPIO controller `resource_source`
+pub struct GpioInt {
+    connection: GpioConnection,
+}
+
+impl GpioInt {
+    pub fn new(
+        edge_triggered: bool,
+        active_low: bool,
+        shared: bool,
+        resource_source: &str,
+        pins: Vec<u16>,
+    ) -> Self {
+        let flags =
+            (u16::from(shared) << 3) | (u16::from(active_low) << 1) | u16::from(edge_triggered);
+        GpioInt {
+            connection: GpioConnection {
+                connection_type: 0,
+                flags,
+                pin_config: GpioPinConfig::Default,
+                drive_strength: 0,
+                debounce_timeout: 0,
+                pins,
+                resource_source: resource_source.to_owned(),
+            },
+        }
+    }
+
+    /// Mark the interrupt as able to wake the system from a sleep state.
+    pub fn wake_capable(mut self) -> Self {
+        self.connection.flags |= 1 << 4;
+        self
+    }
+
+    pub fn pin_config(mut self, pin_config: GpioPinConfig) -> Self {
+        self.connection.pin_config = pin_config;
+        self
+    }
+
+    /// Set the debounce timeout of the pins, in hundredths of milliseconds.
+    pub fn debounce_timeout(mut self, debounce_timeout: u16) -> Self {
+        self.connection.debounce_timeout = debounce_timeout;
+        self
+    }
+}
+
+impl Aml for GpioInt {
+    fn append_aml_bytes(&self, bytes: &mut Vec<u8>) -> Result<(), AmlError> {
+        self.connection.append_aml_bytes(bytes)
+    }
+}
+
+/// GPIO I/O connection to the `pins` of the GPIO controller `resource_source`
+pub struct GpioIo {
+    connection: GpioConnection,
+}
+
+impl GpioIo {
+    pub fn new(
+        shared: bool,
+        restriction: GpioIoRestriction,
+        resource_source: &str,
+        pins: Vec<u16>,
+    ) -> Self {
+        GpioIo {
+            connection: GpioConnection {
+                connection_type: 1,
+                flags: (u16::from(shared) << 3) | restriction as u16,
+                pin_config: GpioPinConfig::Default,
+                drive_strength: 0,
+                debounce_timeout: 0,
+                pins,
+                resource_source: resource_source.to_owned(),
+            },
+        }
+    }
+
+    pub fn pin_config(mut self, pin_config: GpioPinConfig) -> Self {
+        self.connection.pin_config = pin_config;
+        self
+    }
+
+    /// Set the output drive strength of the pins, in hundredths of milliamperes.
+    pub fn drive_strength(mut self, drive_strength: u16) -> Self {
+        self.connection.drive_strength = drive_strength;
+        self
+    }
+}
+
+impl Aml for GpioIo {
+    fn append_aml_bytes(&self, bytes: &mut Vec<u8>) -> Result<(), AmlError> {
+        self.connection.append_aml_bytes(bytes)
+    }
+}
+
+// Append a GenericSerialBus Connection Descriptor, of a device consuming the controller-initiated
+// connection to the serial bus controller `resource_source`.
+fn append_serial_bus(
+    bytes: &mut Vec<u8>,
+    bus_type: u8,
+    type_flags: u16,
+    type_data: &[u8],
+    resource_source: &str,
+) {
+    let length = 9 + type_data.len() + resource_source.len() + 1;
+    bytes.push(0x8e); // GenericSerialBus Connection Descriptor
+    bytes.extend_from_slice(&TryInto::<u16>::try_into(length).unwrap().to_le_bytes());
+    bytes.push(2); // Revision ID
+    bytes.push(0); // Resource Source Index
+    bytes.push(bus_type);
+    bytes.push(1 << 1); // ResourceConsumer, ControllerInitiated, Exclusive
+    bytes.extend_from_slice(&type_flags.to_le_bytes());
+    bytes.push(1); // Type Specific Revision ID
+    bytes.extend_from_slice(
+        &TryInto::<u16>::try_into(type_data.len())
+            .unwrap()
+            .to_le_bytes(),
+    );
+    bytes.extend_from_slice(type_data);
+    bytes.extend_from_slice(resource_source.as_bytes());
+    bytes.push(0);
+}
+
+/// Connection of an I2C device, at `slave_address` on the I2C controller `resource_source`
+pub struct I2cSerialBus {
+    slave_address: u16,
+    connection_speed: u32,
+    ten_bit_addressing: bool,
+    resource_source: String,
+}
+
+impl I2cSerialBus {
+    pub fn new(
+        slave_address: u16,
+        connection_speed: u32,
+        ten_bit_addressing: bool,
+        resource_source: &str,
+    ) -> Self {
+        I2cSerialBus {
+            slave_address,
+            connection_speed,
+            ten_bit_addressing,
+            resource_source: resource_source.to_owned(),
+        }
+    }
+}
+
+impl Aml for I2cSerialBus {
+    fn append_aml_bytes(&self, bytes: &mut Vec<u8>) -> Result<(), AmlError> {
+        let mut type_data = self.connection_speed.to_le_bytes().to_vec();
+        type_data.extend_from_slice(&self.slave_address.to_le_bytes());
+        append_serial_bus(
+            bytes,
+            1, // I2C
+            self.ten_bit_addressing.into(),
+            &type_data,
+            &self.resource_source,
+        );
+        Ok(())
+    }
+}
+
+/// Connection of a four-wire SPI device, whose chip select is active low, on the SPI controller
+/// `resource_source`
+pub struct SpiSerialBus {
+    device_selection: u16,
+    connection_speed: u32,
+    data_bit_length: u8,
+    clock_phase_second: bool,
+    clock_polarity_high: bool,
+    resource_source: String,
+}
+
+impl SpiSerialBus {
+    pub fn new(
+        device_selection: u16,
+        connection_speed: u32,
+        data_bit_length: u8,
+        clock_phase_second: bool,
+        clock_polarity_high: bool,
+        resource_source: &str,
+    ) -> Self {
+        SpiSerialBus {
+            device_selection,
+            connection_speed,
+            data_bit_length,
+            clock_phase_second,
+            clock_polarity_high,
+            resource_source: resource_source.to_owned(),
+        }
+    }
+}
+
+impl Aml for SpiSerialBus {
+    fn append_aml_bytes(&self, bytes: &mut Vec<u8>) -> Result<(), AmlError> {
+        let mut type_data = self.connection_speed.to_le_bytes().to_vec();
+        type_data.push(self.data_bit_length);
+        type_data.push(self.clock_phase_second.into());
+        type_data.push(self.clock_polarity_high.into());
+        type_data.extend_from_slice(&self.device_selection.to_le_bytes());
+        append_serial_bus(bytes, 2, 0, &type_data, &self.resource_source);
+        Ok(())
+    }
+}
+
+#[derive(Copy, Clone)]
+pub enum UartDataBits {
+    Five,
+    Six,
+    Seven,
+    Eight,
+    Nine,
+}
+
+#[derive(Copy, Clone)]
+pub enum UartStopBits {
+    None,
+    One,
+    OnePlusHalf,
+    Two,
+}
+
+#[derive(Copy, Clone)]
+pub enum UartParity {
+    None,
+    Even,
+    Odd,
+    Mark,
+    Space,
+}
+
+#[derive(Copy, Clone)]
+pub enum UartFlowControl {
+    None,
+    Hardware,
+    XonXoff,
+}
+
+/// Connection of a device to the UART `resource_source`
+///
+/// The line is set to 8 data bits, no parity and one stop bit, without flow control, and both
+/// FIFOs hold 16 bytes until set with the methods below.
+pub struct UartSerialBus {
+    baud_rate: u32,
+    data_bits: UartDataBits,
+    parity: UartParity,
+    stop_bits: UartStopBits,
+    flow_control: UartFlowControl,
+    rx_fifo_size: u16,
+    tx_fifo_size: u16,
+    resource_source: String,
+}
+
+impl UartSerialBus {
+    pub fn new(baud_rate: u32, resource_source: &str) -> Self {
+        UartSerialBus {
+            baud_rate,
+            data_bits: UartDataBits::Eight,
+            parity: UartParity::None,
+            stop_bits: UartStopBits::One,
+            flow_control: UartFlowControl::None,
+            rx_fifo_size: 16,
+            tx_fifo_size: 16,
+            resource_source: resource_source.to_owned(),
+        }
+    }
+
+    pub fn format(
+        mut self,
+        data_bits: UartDataBits,
+        parity: UartParity,
+        stop_bits: UartStopBits,
+    ) -> Self {
+        self.data_bits = data_bits;
+        self.parity = parity;
+        self.stop_bits = stop_bits;
+        self
+    }
+
+    /// Set the flow control, which enables the RTS and CTS lines when done by the hardware.
+    pub fn flow_control(mut self, flow_control: UartFlowControl) -> Self {
+        self.flow_control = flow_control;
+        self
+    }
+
+    pub fn fifo_sizes(mut self, rx_fifo_size: u16, tx_fifo_size: u16) -> Self {
+        self.rx_fifo_size = rx_fifo_size;
+        self.tx_fifo_size = tx_fifo_size;
+        self
+    }
+}
+
+impl Aml for UartSerialBus {
+    fn append_aml_bytes(&self, bytes: &mut Vec<u8>) -> Result<(), AmlError> {
+        let type_flags = ((self.data_bits as u16) << 4)
+            | ((self.stop_bits as u16) << 2)
+            | self.flow_control as u16;
+        let serial_lines = match self.flow_control {
+            UartFlowControl::Hardware => (1 << 7) | (1 << 6), // RTS and CTS
+            _ => 0,
+        };
+
+        let mut type_data = self.baud_rate.to_le_bytes().to_vec();
+        type_data.extend_from_slice(&self.rx_fifo_size.to_le_bytes());
+        type_data.extend_from_slice(&self.tx_fifo_size.to_le_bytes());
+        type_data.push(self.parity as u8);
+        type_data.push(serial_lines);
+        append_serial_bus(bytes, 3, type_flags, &type_data, &self.resource_source);
+        Ok(())
+    }
+}
+
+/// Address space of a register in the Platform Communications Channel, whose access size is the
+/// subspace identifier.
+pub const REGISTER_SPACE_PCC: u8 = 0x0a;
+
+pub struct Register {
+    address_space_id: u8,
+    bit_width: u8,
+    bit_offset: u8,
+    access_size: u8,
+    address: u64,
+}
+
+impl Register {
+    pub fn new(
+        address_space_id: u8,
+        bit_width: u8,
+        bit_offset: u8,
+        access_size: u8,
+        address: u64,
+    ) -> Self {
+        Register {
+            address_space_id,
+            bit_width,
+            bit_offset,
+            access_size,
+            address,
+        }
+    }
+}
+
+impl Aml for Register {
+    fn append_aml_bytes(&self, bytes: &mut Vec<u8>) -> Result<(), AmlError> {
+        bytes.push(0x82); // Generic Register Descriptor
+        bytes.extend_from_slice(&12u16.to_le_bytes());
+        bytes.push(self.address_space_id);
+        bytes.push(self.bit_width);
+        bytes.push(self.bit_offset);
+        bytes.push(self.access_size);
+        bytes.extend_from_slice(&self.address.to_le_bytes());
+        Ok(())
+    }
+}
+
 pub struct Device<'a> {
     path: Path,
     children: Vec<&'a dyn Aml>,
@@ -618,6 +1136,82 @@ impl<'a> Device<'a> {
     }
 }
 
+pub struct ThermalZone<'a> {
+    path: Path,
+    children: Vec<&'a dyn Aml>,
+}
+
+impl Aml for ThermalZone<'_> {
+    fn append_aml_bytes(&self, bytes: &mut Vec<u8>) -> Result<(), AmlError> {
+        let mut tmp = Vec::new();
+        self.path.append_aml_bytes(&mut tmp)?;
+        for child in &self.children {
+            child.append_aml_bytes(&mut tmp)?;
+        }
+
+        let pkg_length = create_pkg_length(&tmp, true);
+
+        bytes.push(0x5b); // ExtOpPrefix
+        bytes.push(0x85); // ThermalZoneOp
+        bytes.extend_from_slice(&pkg_length);
+        bytes.extend_from_slice(&tmp);
+        Ok(())
+    }
+}
+
+impl<'a> ThermalZone<'a> {
+    pub fn new(path: Path, children: Vec<&'a dyn Aml>) -> Self {
+        ThermalZone { path, children }
+    }
+}
+
+/// Power resource, turned on and off through its `_ON` and `_OFF` methods
+///
+/// The resource is needed in the `system_level` sleep state and the shallower ones. Resources
+/// are turned on in increasing `resource_order`, and off in decreasing order.
+pub struct PowerResource<'a> {
+    path: Path,
+    system_level: u8,
+    resource_order: u16,
+    children: Vec<&'a dyn Aml>,
+}
+
+impl Aml for PowerResource<'_> {
+    fn append_aml_bytes(&self, bytes: &mut Vec<u8>) -> Result<(), AmlError> {
+        let mut tmp = Vec::new();
+        self.path.append_aml_bytes(&mut tmp)?;
+        tmp.push(self.system_level);
+        tmp.extend_from_slice(&self.resource_order.to_le_bytes());
+        for child in &self.children {
+            child.append_aml_bytes(&mut tmp)?;
+        }
+
+        let pkg_length = create_pkg_length(&tmp, true);
+
+        bytes.push(0x5b); // ExtOpPrefix
+        bytes.push(0x84); // PowerResOp
+        bytes.extend_from_slice(&pkg_length);
+        bytes.extend_from_slice(&tmp);
+        Ok(())
+    }
+}
+
+impl<'a> PowerResource<'a> {
+    pub fn new(
+        path: Path,
+        system_level: u8,
+        resource_order: u16,
+        children: Vec<&'a dyn Aml>,
+    ) -> Self {
+        PowerResource {
+            path,
+            system_level,
+            resource_order,
+            children,
+        }
+    }
+}
+
 pub struct Scope<'a> {
     path: Path,
     children: Vec<&'a dyn Aml>,
@@ -711,6 +1305,12 @@ pub enum FieldAccessType {
     Buffer,
 }
 
+#[derive(Clone, Copy)]
+pub enum FieldLockRule {
+    NoLock = 0,
+    Lock = 1,
+}
+
 #[derive(Clone, Copy)]
 pub enum FieldUpdateRule {
     Preserve = 0,
@@ -723,11 +1323,36 @@ pub enum FieldEntry {
     Reserved(usize),
 }
 
+// Append the flags and the list of entries shared by all the kinds of fields.
+fn append_field_list(
+    access_type: FieldAccessType,
+    lock_rule: FieldLockRule,
+    update_rule: FieldUpdateRule,
+    fields: &[FieldEntry],
+    bytes: &mut Vec<u8>,
+) {
+    bytes.push(access_type as u8 | ((lock_rule as u8) << 4) | ((update_rule as u8) << 5));
+
+    for field in fields.iter() {
+        match field {
+            FieldEntry::Named(name, length) => {
+                bytes.extend_from_slice(name);
+                bytes.extend_from_slice(&create_pkg_length(&vec![0; *length], false));
+            }
+            FieldEntry::Reserved(length) => {
+                bytes.push(0x0);
+                bytes.extend_from_slice(&create_pkg_length(&vec![0; *length], false));
+            }
+        }
+    }
+}
+
 pub struct Field {
     path: Path,
 
     fields: Vec<FieldEntry>,
     access_type: FieldAccessType,
+    lock_rule: FieldLockRule,
     update_rule: FieldUpdateRule,
 }
 
@@ -742,31 +1367,30 @@ impl Field {
             path,
             fields,
             access_type,
+            lock_rule: FieldLockRule::NoLock,
             update_rule,
         }
     }
+
+    /// Set whether the global lock is acquired when accessing the field, which is not the case
+    /// by default.
+    pub fn lock_rule(mut self, lock_rule: FieldLockRule) -> Self {
+        self.lock_rule = lock_rule;
+        self
+    }
 }
 
 impl Aml for Field {
     fn append_aml_bytes(&self, bytes: &mut Vec<u8>) -> Result<(), AmlError> {
         let mut tmp = Vec::new();
         self.path.append_aml_bytes(&mut tmp)?;
-
-        let flags: u8 = self.access_type as u8 | ((self.update_rule as u8) << 5);
-        tmp.push(flags);
-
-        for field in self.fields.iter() {
-            match field {
-                FieldEntry::Named(name, length) => {
-                    tmp.extend_from_slice(name);
-                    tmp.extend_from_slice(&create_pkg_length(&vec![0; *length], false));
-                }
-                FieldEntry::Reserved(length) => {
-                    tmp.push(0x0);
-                    tmp.extend_from_slice(&create_pkg_length(&vec![0; *length], false));
-                }
-            }
-        }
+        append_field_list(
+            self.access_type,
+            self.lock_rule,
+            self.update_rule,
+            &self.fields,
+            &mut tmp,
+        );
 
         let pkg_length = create_pkg_length(&tmp, true);
 
@@ -778,18 +1402,133 @@ impl Aml for Field {
     }
 }
 
-#[derive(Clone, Copy)]
-pub enum OpRegionSpace {
-    SystemMemory,
-    SystemIo,
-    PConfig,
-    EmbeddedControl,
-    Smbus,
-    SystemCmos,
-    PciBarTarget,
-    Ipmi,
-    GeneralPurposeIo,
-    GenericSerialBus,
+/// Fields accessed through an index register and a data register
+///
+/// Accessing a field writes its offset to the index register, then reads or writes the data
+/// register, both of them being fields of an operation region.
+pub struct IndexField {
+    index: Path,
+    data: Path,
+
+    fields: Vec<FieldEntry>,
+    access_type: FieldAccessType,
+    lock_rule: FieldLockRule,
+    update_rule: FieldUpdateRule,
+}
+
+impl IndexField {
+    pub fn new(
+        index: Path,
+        data: Path,
+        access_type: FieldAccessType,
+        lock_rule: FieldLockRule,
+        update_rule: FieldUpdateRule,
+        fields: Vec<FieldEntry>,
+    ) -> Self {
+        IndexField {
+            index,
+            data,
+            fields,
+            access_type,
+            lock_rule,
+            update_rule,
+        }
+    }
+}
+
+impl Aml for IndexField {
+    fn append_aml_bytes(&self, bytes: &mut Vec<u8>) -> Result<(), AmlError> {
+        let mut tmp = Vec::new();
+        self.index.append_aml_bytes(&mut tmp)?;
+        self.data.append_aml_bytes(&mut tmp)?;
+        append_field_list(
+            self.access_type,
+            self.lock_rule,
+            self.update_rule,
+            &self.fields,
+            &mut tmp,
+        );
+
+        let pkg_length = create_pkg_length(&tmp, true);
+
+        bytes.push(0x5b); // ExtOpPrefix
+        bytes.push(0x86); // IndexFieldOp
+        bytes.extend_from_slice(&pkg_length);
+        bytes.extend_from_slice(&tmp);
+        Ok(())
+    }
+}
+
+/// Fields of an operation region, selected by writing `bank_value` to the bank register
+pub struct BankField<'a> {
+    region: Path,
+    bank: Path,
+    bank_value: &'a dyn Aml,
+
+    fields: Vec<FieldEntry>,
+    access_type: FieldAccessType,
+    lock_rule: FieldLockRule,
+    update_rule: FieldUpdateRule,
+}
+
+impl<'a> BankField<'a> {
+    pub fn new(
+        region: Path,
+        bank: Path,
+        bank_value: &'a dyn Aml,
+        access_type: FieldAccessType,
+        lock_rule: FieldLockRule,
+        update_rule: FieldUpdateRule,
+        fields: Vec<FieldEntry>,
+    ) -> Self {
+        BankField {
+            region,
+            bank,
+            bank_value,
+            fields,
+            access_type,
+            lock_rule,
+            update_rule,
+        }
+    }
+}
+
+impl Aml for BankField<'_> {
+    fn append_aml_bytes(&self, bytes: &mut Vec<u8>) -> Result<(), AmlError> {
+        let mut tmp = Vec::new();
+        self.region.append_aml_bytes(&mut tmp)?;
+        self.bank.append_aml_bytes(&mut tmp)?;
+        self.bank_value.append_aml_bytes(&mut tmp)?;
+        append_field_list(
+            self.access_type,
+            self.lock_rule,
+            self.update_rule,
+            &self.fields,
+            &mut tmp,
+        );
+
+        let pkg_length = create_pkg_length(&tmp, true);
+
+        bytes.push(0x5b); // ExtOpPrefix
+        bytes.push(0x87); // BankFieldOp
+        bytes.extend_from_slice(&pkg_length);
+        bytes.extend_from_slice(&tmp);
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum OpRegionSpace {
+    SystemMemory,
+    SystemIo,
+    PConfig,
+    EmbeddedControl,
+    Smbus,
+    SystemCmos,
+    PciBarTarget,
+    Ipmi,
+    GeneralPurposeIo,
+    GenericSerialBus,
 }
 
 pub struct OpRegion {
@@ -853,6 +1592,33 @@ impl Aml for If<'_> {
     }
 }
 
+/// Terms evaluated when the predicate of the [`If`] right before it is false
+pub struct Else<'a> {
+    else_children: Vec<&'a dyn Aml>,
+}
+
+impl<'a> Else<'a> {
+    pub fn new(else_children: Vec<&'a dyn Aml>) -> Self {
+        Else { else_children }
+    }
+}
+
+impl Aml for Else<'_> {
+    fn append_aml_bytes(&self, bytes: &mut Vec<u8>) -> Result<(), AmlError> {
+        let mut tmp = Vec::new();
+        for child in self.else_children.iter() {
+            child.append_aml_bytes(&mut tmp)?;
+        }
+
+        let pkg_length = create_pkg_length(&tmp, true);
+
+        bytes.push(0xa1); // ElseOp
+        bytes.extend_from_slice(&pkg_length);
+        bytes.extend_from_slice(&tmp);
+        Ok(())
+    }
+}
+
 pub struct Equal<'a> {
     left: &'a dyn Aml,
     right: &'a dyn Aml,
@@ -998,6 +1764,83 @@ impl Aml for Release {
     }
 }
 
+pub struct Event {
+    path: Path,
+}
+
+impl Event {
+    pub fn new(path: Path) -> Self {
+        Event { path }
+    }
+}
+
+impl Aml for Event {
+    fn append_aml_bytes(&self, bytes: &mut Vec<u8>) -> Result<(), AmlError> {
+        bytes.push(0x5b); // ExtOpPrefix
+        bytes.push(0x02); // EventOp
+        self.path.append_aml_bytes(bytes)
+    }
+}
+
+pub struct Signal {
+    event: Path,
+}
+
+impl Signal {
+    pub fn new(event: Path) -> Self {
+        Signal { event }
+    }
+}
+
+impl Aml for Signal {
+    fn append_aml_bytes(&self, bytes: &mut Vec<u8>) -> Result<(), AmlError> {
+        bytes.push(0x5b); // ExtOpPrefix
+        bytes.push(0x24); // SignalOp
+        self.event.append_aml_bytes(bytes)
+    }
+}
+
+pub struct Reset {
+    event: Path,
+}
+
+impl Reset {
+    pub fn new(event: Path) -> Self {
+        Reset { event }
+    }
+}
+
+impl Aml for Reset {
+    fn append_aml_bytes(&self, bytes: &mut Vec<u8>) -> Result<(), AmlError> {
+        bytes.push(0x5b); // ExtOpPrefix
+        bytes.push(0x26); // ResetOp
+        self.event.append_aml_bytes(bytes)
+    }
+}
+
+/// Wait for an event to be signaled, for at most `timeout` milliseconds
+///
+/// This evaluates to a non-zero value when the timeout expired.
+pub struct Wait<'a> {
+    event: Path,
+    timeout: &'a dyn Aml,
+}
+
+impl<'a> Wait<'a> {
+    pub fn new(event: Path, timeout: &'a dyn Aml) -> Self {
+        Wait { event, timeout }
+    }
+}
+
+impl Aml for Wait<'_> {
+    fn append_aml_bytes(&self, bytes: &mut Vec<u8>) -> Result<(), AmlError> {
+        bytes.push(0x5b); // ExtOpPrefix
+        bytes.push(0x25); // WaitOp
+        self.event.append_aml_bytes(bytes)?;
+        self.timeout.append_aml_bytes(bytes)
+    }
+}
+
 pub struct Notify<'a> {
     object: &'a dyn Aml,
     value: &'a dyn Aml,
@@ -1091,6 +1934,94 @@ binary_op!(Mod, 0x85);
 binary_op!(Index, 0x88);
 binary_op!(ToString, 0x9C);
 
+macro_rules! convert_op {
+    ($name:ident, $opcode:expr) => {
+        pub struct $name<'a> {
+            operand: &'a dyn Aml,
+            target: &'a dyn Aml,
+        }
+
+        impl<'a> $name<'a> {
+            pub fn new(target: &'a dyn Aml, operand: &'a dyn Aml) -> Self {
+                $name { operand, target }
+            }
+        }
+
+        impl<'a> Aml for $name<'a> {
+            fn append_aml_bytes(&self, bytes: &mut Vec<u8>) -> Result<(), AmlError> {
+                bytes.push($opcode); // Op for the conversion operator
+                self.operand.append_aml_bytes(bytes)?;
+                self.target.append_aml_bytes(bytes)
+            }
+        }
+    };
+}
+
+// conversion operators: TermArg Target
+convert_op!(ToBuffer, 0x96);
+convert_op!(ToDecimalString, 0x97);
+convert_op!(ToHexString, 0x98);
+convert_op!(ToInteger, 0x99);
+
+macro_rules! object_op {
+    ($name:ident, $opcode:expr) => {
+        pub struct $name<'a> {
+            object: &'a dyn Aml,
+        }
+
+        impl<'a> $name<'a> {
+            pub fn new(object: &'a dyn Aml) -> Self {
+                $name { object }
+            }
+        }
+
+        impl<'a> Aml for $name<'a> {
+            fn append_aml_bytes(&self, bytes: &mut Vec<u8>) -> Result<(), AmlError> {
+                bytes.push($opcode); // Op for the object operator
+                self.object.append_aml_bytes(bytes)
+            }
+        }
+    };
+}
+
+// object operators: SuperName
+object_op!(SizeOf, 0x87);
+object_op!(ObjectType, 0x8E);
+
+/// Portion of `length` bytes or characters of a buffer or string, starting at `index`
+pub struct Mid<'a> {
+    source: &'a dyn Aml,
+    index: &'a dyn Aml,
+    length: &'a dyn Aml,
+    target: &'a dyn Aml,
+}
+
+impl<'a> Mid<'a> {
+    pub fn new(
+        target: &'a dyn Aml,
+        source: &'a dyn Aml,
+        index: &'a dyn Aml,
+        length: &'a dyn Aml,
+    ) -> Self {
+        Mid {
+            source,
+            index,
+            length,
+            target,
+        }
+    }
+}
+
+impl Aml for Mid<'_> {
+    fn append_aml_bytes(&self, bytes: &mut Vec<u8>) -> Result<(), AmlError> {
+        bytes.push(0x9e); // MidOp
+        self.source.append_aml_bytes(bytes)?;
+        self.index.append_aml_bytes(bytes)?;
+        self.length.append_aml_bytes(bytes)?;
+        self.target.append_aml_bytes(bytes)
+    }
+}
+
 pub struct MethodCall<'a> {
     name: Path,
     args: Vec<&'a dyn Aml>,
@@ -1173,8 +2104,807 @@ impl Aml for CreateField<'_, u32> {
     }
 }
 
+impl Aml for CreateField<'_, u16> {
+    fn append_aml_bytes(&self, bytes: &mut Vec<u8>) -> Result<(), AmlError> {
+        bytes.push(0x8b); // CreateWordFieldOp
+        self.buffer.append_aml_bytes(bytes)?;
+        self.offset.append_aml_bytes(bytes)?;
+        self.field.append_aml_bytes(bytes)
+    }
+}
+
+impl Aml for CreateField<'_, u8> {
+    fn append_aml_bytes(&self, bytes: &mut Vec<u8>) -> Result<(), AmlError> {
+        bytes.push(0x8c); // CreateByteFieldOp
+        self.buffer.append_aml_bytes(bytes)?;
+        self.offset.append_aml_bytes(bytes)?;
+        self.field.append_aml_bytes(bytes)
+    }
+}
+
+/// Create a field of `num_bits` bits, starting at bit `bit_index` of a buffer
+///
+/// This is the ASL CreateField operator. The byte, word, double word and quad word fields,
+/// whose offset is in bytes, are created with [`CreateField`].
+pub struct CreateBitsField<'a> {
+    buffer: &'a dyn Aml,
+    bit_index: &'a dyn Aml,
+    num_bits: &'a dyn Aml,
+    field: Path,
+}
+
+impl<'a> CreateBitsField<'a> {
+    pub fn new(
+        buffer: &'a dyn Aml,
+        bit_index: &'a dyn Aml,
+        num_bits: &'a dyn Aml,
+        field: Path,
+    ) -> Self {
+        CreateBitsField {
+            buffer,
+            bit_index,
+            num_bits,
+            field,
+        }
+    }
+}
+
+impl Aml for CreateBitsField<'_> {
+    fn append_aml_bytes(&self, bytes: &mut Vec<u8>) -> Result<(), AmlError> {
+        bytes.push(0x5b); // ExtOpPrefix
+        bytes.push(0x13); // CreateFieldOp
+        self.buffer.append_aml_bytes(bytes)?;
+        self.bit_index.append_aml_bytes(bytes)?;
+        self.num_bits.append_aml_bytes(bytes)?;
+        self.field.append_aml_bytes(bytes)
+    }
+}
+
+pub struct DerefOf<'a> {
+    object: &'a dyn Aml,
+}
+
+impl<'a> DerefOf<'a> {
+    pub fn new(object: &'a dyn Aml) -> Self {
+        DerefOf { object }
+    }
+}
+
+impl Aml for DerefOf<'_> {
+    fn append_aml_bytes(&self, bytes: &mut Vec<u8>) -> Result<(), AmlError> {
+        bytes.push(0x83); // DerefOfOp
+        self.object.append_aml_bytes(bytes)
+    }
+}
+
+// Length of the notification register of a Generic Event Device
+const GED_REGISTER_LENGTH: usize = 4;
+
+/// Generic Event Device (GED) dispatching events to other objects of the namespace
+///
+/// The device owns an interrupt and a 32-bit notification register in system memory. When the
+/// interrupt is raised, its `_EVT` method reads the register and notifies the objects of the
+/// events whose bit is set.
+pub struct GenericEventDevice {
+    path: Path,
+    gsi: u32,
+    register: usize,
+    notifications: Vec<(u32, Path, u8)>,
+}
+
+impl GenericEventDevice {
+    pub fn new(path: Path, gsi: u32, register: usize) -> Self {
+        GenericEventDevice {
+            path,
+            gsi,
+            register,
+            notifications: Vec::new(),
+        }
+    }
+
+    /// Notify `object` with `value` when `bit` of the notification register is set.
+    pub fn notify(mut self, bit: u8, object: Path, value: u8) -> Self {
+        self.notifications.push((1 << bit, object, value));
+        self
+    }
+}
+
+impl Aml for GenericEventDevice {
+    fn append_aml_bytes(&self, bytes: &mut Vec<u8>) -> Result<(), AmlError> {
+        let interrupt = Interrupt::new(true, true, false, false, self.gsi);
+        let region = OpRegion::new(
+            "GDST".try_into()?,
+            OpRegionSpace::SystemMemory,
+            self.register,
+            GED_REGISTER_LENGTH,
+        );
+        let field = Field::new(
+            "GDST".try_into()?,
+            FieldAccessType::DWord,
+            FieldUpdateRule::WriteAsZeroes,
+            vec![FieldEntry::Named(*b"GDAT", 32)],
+        );
+
+        // Read the register once, then test the bit of every event.
+        let status = Local(0);
+        let register = Path::new("GDAT")?;
+        let store = Store::new(&status, &register);
+        let masks: Vec<_> = self
+            .notifications
+            .iter()
+            .map(|(mask, _, _)| *mask as usize)
+            .collect();
+        let ands: Vec<_> = masks
+            .iter()
+            .map(|mask| And::new(&ZERO, &status, mask))
+            .collect();
+        let equals: Vec<_> = ands
+            .iter()
+            .zip(&masks)
+            .map(|(and, mask)| Equal::new(and, mask))
+            .collect();
+        let notifies: Vec<_> = self
+            .notifications
+            .iter()
+            .map(|(_, object, value)| Notify::new(object, value))
+            .collect();
+        let ifs: Vec<_> = equals
+            .iter()
+            .zip(&notifies)
+            .map(|(equal, notify)| If::new(equal, vec![notify]))
+            .collect();
+        let mut events: Vec<&dyn Aml> = vec![&store];
+        events.extend(ifs.iter().map(|event| event as &dyn Aml));
+
+        Device::new(
+            self.path.clone(),
+            vec![
+                &Name::new("_HID".try_into()?, &"ACPI0013")?,
+                &Name::new("_CRS".try_into()?, &ResourceTemplate::new(vec![&interrupt]))?,
+                &region,
+                &field,
+                &Method::new("_EVT".try_into()?, 1, true, events),
+            ],
+        )
+        .append_aml_bytes(bytes)
+    }
+}
+
+/// Length of the register blocks through which the AML of [`CpuHotplug`] and [`MemoryHotplug`]
+/// controls the hot-pluggable slots
+///
+/// A block holds the index of the selected slot as a 32-bit register at offset 0, followed by
+/// its status at offset 4: bit 0 is set when the slot is enabled, bits 1 and 2 when it is being
+/// inserted or removed, in which case writing 1 acknowledges the event, and writing 1 to bit 3
+/// ejects the slot.
+pub const HOTPLUG_REGISTERS_LENGTH: usize = 8;
+
+// Device of a hot-pluggable slot, whose status and ejection go through its container
+struct HotplugSlot {
+    prefix: u8,
+    id: usize,
+    names: Vec<Name>,
+}
+
+impl Aml for HotplugSlot {
+    fn append_aml_bytes(&self, bytes: &mut Vec<u8>) -> Result<(), AmlError> {
+        let prefix = char::from(self.prefix);
+        let status = MethodCall::new(Path::new(&format!("{prefix}STA"))?, vec![&self.id]);
+        let return_status = Return::new(&status);
+        let eject = MethodCall::new(Path::new(&format!("{prefix}EJ0"))?, vec![&self.id]);
+        let status_method = Method::new("_STA".try_into()?, 0, false, vec![&return_status]);
+        let eject_method = Method::new("_EJ0".try_into()?, 1, false, vec![&eject]);
+
+        let mut children: Vec<&dyn Aml> = self.names.iter().map(|name| name as &dyn Aml).collect();
+        children.push(&status_method);
+        children.push(&eject_method);
+        Device::new(Path::new(&format!("{prefix}{:03X}", self.id))?, children)
+            .append_aml_bytes(bytes)
+    }
+}
+
+// Container of hot-pluggable slots, with the methods going through their register block. The
+// names of the objects it defines all start with `prefix`.
+struct HotplugContainer {
+    path: Path,
+    prefix: u8,
+    registers: usize,
+    names: Vec<Name>,
+    slots: Vec<HotplugSlot>,
+}
+
+impl Aml for HotplugContainer {
+    fn append_aml_bytes(&self, bytes: &mut Vec<u8>) -> Result<(), AmlError> {
+        let path = |suffix: &str| Path::new(&format!("{}{suffix}", char::from(self.prefix)));
+        let field = |suffix: &[u8], bits| {
+            let mut name = [self.prefix; 4];
+            name[1..].copy_from_slice(suffix);
+            FieldEntry::Named(name, bits)
+        };
+
+        let mutex = Mutex::new(path("LCK")?, 0);
+        let region = OpRegion::new(
+            path("REG")?,
+            OpRegionSpace::SystemMemory,
+            self.registers,
+            HOTPLUG_REGISTERS_LENGTH,
+        );
+        let selection_field = Field::new(
+            path("REG")?,
+            FieldAccessType::DWord,
+            FieldUpdateRule::Preserve,
+            vec![field(b"SEL", 32)],
+        );
+        let status_field = Field::new(
+            path("REG")?,
+            FieldAccessType::Byte,
+            FieldUpdateRule::WriteAsZeroes,
+            vec![
+                FieldEntry::Reserved(32),
+                field(b"EN_", 1),
+                field(b"INS", 1),
+                field(b"RMV", 1),
+                field(b"EJF", 1),
+            ],
+        );
+
+        let selection = path("SEL")?;
+        let enabled = path("EN_")?;
+        let inserting = path("INS")?;
+        let removing = path("RMV")?;
+        let ejecting = path("EJF")?;
+        let acquire = Acquire::new(path("LCK")?, 0xffff);
+        let release = Release::new(path("LCK")?);
+        let clear_local = Store::new(&Local(0), &ZERO);
+
+        // xSTA (Arg0): status of slot Arg0, for its _STA method
+        let select_arg = Store::new(&selection, &Arg(0));
+        let is_enabled = Equal::new(&enabled, &ONE);
+        let set_present = Store::new(&Local(0), &0x0fusize);
+        let if_enabled = If::new(&is_enabled, vec![&set_present]);
+        let return_status = Return::new(&Local(0));
+        let status = Method::new(
+            path("STA")?,
+            1,
+            true,
+            vec![
+                &acquire,
+                &select_arg,
+                &clear_local,
+                &if_enabled,
+                &release,
+                &return_status,
+            ],
+        );
+
+        // xEJ0 (Arg0): eject slot Arg0, for its _EJ0 method
+        let eject_selected = Store::new(&ejecting, &ONE);
+        let eject = Method::new(
+            path("EJ0")?,
+            1,
+            true,
+            vec![&acquire, &select_arg, &eject_selected, &release],
+        );
+
+        // xTFY (Arg0, Arg1): notify slot Arg0 with Arg1
+        let ids: Vec<usize> = self.slots.iter().map(|slot| slot.id).collect();
+        let paths = ids
+            .iter()
+            .map(|id| path(&format!("{id:03X}")))
+            .collect::<Result<Vec<_>, _>>()?;
+        let equals: Vec<_> = ids.iter().map(|id| Equal::new(&Arg(0), id)).collect();
+        let notifies: Vec<_> = paths
+            .iter()
+            .map(|path| Notify::new(path, &Arg(1)))
+            .collect();
+        let ifs: Vec<_> = equals
+            .iter()
+            .zip(&notifies)
+            .map(|(equal, notify)| If::new(equal, vec![notify]))
+            .collect();
+        let notify = Method::new(
+            path("TFY")?,
+            2,
+            true,
+            ifs.iter().map(|event| event as &dyn Aml).collect(),
+        );
+
+        // xSCN: notify the slots being inserted (device check) or removed (eject request)
+        let slots = self.slots.len();
+        let (device_check, eject_request) = (1usize, 3usize);
+        let select_local = Store::new(&selection, &Local(0));
+        let is_inserting = Equal::new(&inserting, &ONE);
+        let notify_insertion = MethodCall::new(path("TFY")?, vec![&Local(0), &device_check]);
+        let ack_insertion = Store::new(&inserting, &ONE);
+        let if_inserting = If::new(&is_inserting, vec![&notify_insertion, &ack_insertion]);
+        let is_removing = Equal::new(&removing, &ONE);
+        let notify_removal = MethodCall::new(path("TFY")?, vec![&Local(0), &eject_request]);
+        let ack_removal = Store::new(&removing, &ONE);
+        let if_removing = If::new(&is_removing, vec![&notify_removal, &ack_removal]);
+        let next = Add::new(&Local(0), &Local(0), &ONE);
+        let more = LessThan::new(&Local(0), &slots);
+        let scan_loop = While::new(
+            &more,
+            vec![&select_local, &if_inserting, &if_removing, &next],
+        );
+        let scan = Method::new(
+            path("SCN")?,
+            0,
+            true,
+            vec![&acquire, &clear_local, &scan_loop, &release],
+        );
+
+        let mut children: Vec<&dyn Aml> = self.names.iter().map(|name| name as &dyn Aml).collect();
+        children.extend([
+            &mutex as &dyn Aml,
+            &region,
+            &selection_field,
+            &status_field,
+            &status,
+            &eject,
+            &notify,
+            &scan,
+        ]);
+        children.extend(self.slots.iter().map(|slot| slot as &dyn Aml));
+        Device::new(self.path.clone(), children).append_aml_bytes(bytes)
+    }
+}
+
+/// Container of the hot-pluggable vCPUs, `\_SB_.CPUS`
+///
+/// Every vCPU up to the maximum count gets a processor device, whose status is read from the
+/// register block described by [`HOTPLUG_REGISTERS_LENGTH`]. The `CSCN` method of the container
+/// scans the vCPUs and notifies those being inserted or removed: the VMM calls it when the set
+/// of vCPUs changes, e.g. from the `_EVT` method of a Generic Event Device.
+pub struct CpuHotplug {
+    max_vcpus: u8,
+    registers: usize,
+}
+
+impl CpuHotplug {
+    pub fn new(max_vcpus: u8, registers: usize) -> Self {
+        CpuHotplug {
+            max_vcpus,
+            registers,
+        }
+    }
+}
+
+impl Aml for CpuHotplug {
+    fn append_aml_bytes(&self, bytes: &mut Vec<u8>) -> Result<(), AmlError> {
+        let mut slots = Vec::new();
+        for id in 0..usize::from(self.max_vcpus) {
+            slots.push(HotplugSlot {
+                prefix: b'C',
+                id,
+                names: vec![
+                    Name::new("_HID".try_into()?, &"ACPI0007")?,
+                    Name::new("_UID".try_into()?, &id)?,
+                ],
+            });
+        }
+
+        HotplugContainer {
+            path: "\\_SB_.CPUS".try_into()?,
+            prefix: b'C',
+            registers: self.registers,
+            names: vec![
+                Name::new("_HID".try_into()?, &"ACPI0010")?,
+                Name::new("_CID".try_into()?, &EisaName::new("PNP0A05")?)?,
+            ],
+            slots,
+        }
+        .append_aml_bytes(bytes)
+    }
+}
+
+/// Container of the hot-pluggable memory devices, `\_SB_.MHPC`
+///
+/// The memory range starting at `base` is split in slots of `slot_size` bytes, each of them
+/// described by a memory device. As for [`CpuHotplug`], their status is read from a register
+/// block and the `MSCN` method of the container notifies the slots being inserted or removed.
+pub struct MemoryHotplug {
+    base: u64,
+    slot_size: u64,
+    slots: u8,
+    registers: usize,
+}
+
+impl MemoryHotplug {
+    pub fn new(base: u64, slot_size: u64, slots: u8, registers: usize) -> Self {
+        MemoryHotplug {
+            base,
+            slot_size,
+            slots,
+            registers,
+        }
+    }
+
+    /// SRAT structure attaching the hot-pluggable memory range to `proximity_domain`.
+    pub fn memory_affinity(&self, proximity_domain: u32) -> MemoryAffinity {
+        let size = self.slot_size * u64::from(self.slots);
+        MemoryAffinity::new(self.base, size, proximity_domain, true)
+    }
+}
+
+impl Aml for MemoryHotplug {
+    fn append_aml_bytes(&self, bytes: &mut Vec<u8>) -> Result<(), AmlError> {
+        let mut slots = Vec::new();
+        for slot in 0..self.slots {
+            let id = usize::from(slot);
+            let base = self.base + u64::from(slot) * self.slot_size;
+            let range = AddressSpace::new_memory(
+                AddressSpaceCacheable::Cacheable,
+                true,
+                base,
+                base + self.slot_size - 1,
+            )?;
+            slots.push(HotplugSlot {
+                prefix: b'M',
+                id,
+                names: vec![
+                    Name::new("_HID".try_into()?, &EisaName::new("PNP0C80")?)?,
+                    Name::new("_UID".try_into()?, &id)?,
+                    Name::new("_CRS".try_into()?, &ResourceTemplate::new(vec![&range]))?,
+                ],
+            });
+        }
+
+        HotplugContainer {
+            path: "\\_SB_.MHPC".try_into()?,
+            prefix: b'M',
+            registers: self.registers,
+            names: vec![
+                Name::new("_HID".try_into()?, &EisaName::new("PNP0A06")?)?,
+                Name::new("_UID".try_into()?, &"Memory Hotplug Controller")?,
+            ],
+            slots,
+        }
+        .append_aml_bytes(bytes)
+    }
+}
+
+/// Length of the register block of the [`PciHotplugController`]
+///
+/// The block holds four 32-bit registers: the bitmaps of the slots of the selected segment being
+/// inserted (offset 0) and removed (offset 4), the bitmap of the slots to eject, written by the
+/// guest (offset 8), and the selected segment (offset 12).
+pub const PCI_HOTPLUG_REGISTERS_LENGTH: usize = 16;
+
+/// Controller of the hot-pluggable PCI slots, `\_SB_.PHPR`
+///
+/// The `PCEJ` method ejects the slots of every segment, and the `PSCN` method notifies the
+/// slots being inserted or removed: the VMM calls it when it changes the devices of a segment,
+/// e.g. from the `_EVT` method of a Generic Event Device.
+pub struct PciHotplugController {
+    registers: usize,
+    segments: u16,
+}
+
+impl PciHotplugController {
+    /// Create the controller of the slots of the segments 0 to `segments` - 1, whose registers
+    /// are at the address `registers`.
+    pub fn new(registers: usize, segments: u16) -> Self {
+        PciHotplugController {
+            registers,
+            segments,
+        }
+    }
+}
+
+impl Aml for PciHotplugController {
+    fn append_aml_bytes(&self, bytes: &mut Vec<u8>) -> Result<(), AmlError> {
+        let region = OpRegion::new(
+            "PCST".try_into()?,
+            OpRegionSpace::SystemMemory,
+            self.registers,
+            PCI_HOTPLUG_REGISTERS_LENGTH,
+        );
+        let field = Field::new(
+            "PCST".try_into()?,
+            FieldAccessType::DWord,
+            FieldUpdateRule::WriteAsZeroes,
+            vec![
+                FieldEntry::Named(*b"PCIU", 32),
+                FieldEntry::Named(*b"PCID", 32),
+                FieldEntry::Named(*b"B0EJ", 32),
+                FieldEntry::Named(*b"PSEG", 32),
+            ],
+        );
+
+        // PCEJ (Arg0, Arg1): eject slot Arg0 of segment Arg1
+        let segment = Path::new("PSEG")?;
+        let eject = Path::new("B0EJ")?;
+        let acquire = Acquire::new("BLCK".try_into()?, 0xffff);
+        let select_segment = Store::new(&segment, &Arg(1));
+        let eject_slot = ShiftLeft::new(&eject, &ONE, &Arg(0));
+        let release = Release::new("BLCK".try_into()?);
+        let return_zero = Return::new(&ZERO);
+        let pcej = Method::new(
+            "PCEJ".try_into()?,
+            2,
+            true,
+            vec![
+                &acquire,
+                &select_segment,
+                &eject_slot,
+                &release,
+                &return_zero,
+            ],
+        );
+
+        // PSCN: notify the slots of every segment
+        let notifications = (0..self.segments)
+            .map(|id| Path::new(&format!("\\_SB_.PC{id:02X}.PCNT")))
+            .collect::<Result<Vec<_>, _>>()?;
+        let calls: Vec<_> = notifications
+            .into_iter()
+            .map(|path| MethodCall::new(path, vec![]))
+            .collect();
+        let pscn = Method::new(
+            "PSCN".try_into()?,
+            0,
+            true,
+            calls.iter().map(|call| call as &dyn Aml).collect(),
+        );
+
+        Device::new(
+            "\\_SB_.PHPR".try_into()?,
+            vec![
+                &Name::new("_HID".try_into()?, &EisaName::new("PNP0A06")?)?,
+                &Name::new("_STA".try_into()?, &0x0bu8)?,
+                &Name::new("_UID".try_into()?, &"PCI Hotplug Controller")?,
+                &Mutex::new("BLCK".try_into()?, 0),
+                &region,
+                &field,
+                &pcej,
+                &pscn,
+            ],
+        )
+        .append_aml_bytes(bytes)
+    }
+}
+
+/// Hot-pluggable slot of a PCI root bridge, named after its device number
+///
+/// Ejecting the device of the slot goes through the [`PciHotplugController`].
+pub struct PciSlot {
+    device: u8,
+    proximity_domain: Option<u32>,
+}
+
+impl PciSlot {
+    /// Create the slot of the device number `device`, in the proximity domain of its root
+    /// bridge.
+    pub fn new(device: u8) -> Self {
+        PciSlot {
+            device,
+            proximity_domain: None,
+        }
+    }
+
+    /// Override the proximity domain of the root bridge for the device of the slot, which the
+    /// slot then reports in its `_PXM` object.
+    pub fn proximity_domain(mut self, proximity_domain: u32) -> Self {
+        self.proximity_domain = Some(proximity_domain);
+        self
+    }
+}
+
+impl Aml for PciSlot {
+    fn append_aml_bytes(&self, bytes: &mut Vec<u8>) -> Result<(), AmlError> {
+        let adr = u32::from(self.device) << 16;
+        let sun = Name::new("_SUN".try_into()?, &self.device)?;
+        let adr = Name::new("_ADR".try_into()?, &adr)?;
+        let sun_path = Path::new("_SUN")?;
+        let seg_path = Path::new("_SEG")?;
+        let pcej = MethodCall::new("\\_SB_.PHPR.PCEJ".try_into()?, vec![&sun_path, &seg_path]);
+        let ej0 = Method::new("_EJ0".try_into()?, 1, true, vec![&pcej]);
+        let mut slot_data: Vec<&dyn Aml> = vec![&sun, &adr, &ej0];
+
+        let pxm = match self.proximity_domain {
+            Some(proximity_domain) => Some(Name::new("_PXM".try_into()?, &proximity_domain)?),
+            None => None,
+        };
+        if let Some(pxm) = &pxm {
+            slot_data.push(pxm);
+        }
+
+        Device::new(Path::new(&format!("S{:03}", self.device))?, slot_data).append_aml_bytes(bytes)
+    }
+}
+
+// Notification of the slot of a device, when its bit is set in the bitmap of Arg0
+struct PciSlotNotify {
+    device: u8,
+}
+
+impl Aml for PciSlotNotify {
+    fn append_aml_bytes(&self, bytes: &mut Vec<u8>) -> Result<(), AmlError> {
+        let device_mask: u32 = 1 << self.device;
+        let object = Path::new(&format!("S{:03}", self.device))?;
+        And::new(&Local(0), &Arg(0), &device_mask).append_aml_bytes(bytes)?;
+        If::new(
+            &Equal::new(&Local(0), &device_mask),
+            vec![&Notify::new(&object, &Arg(1))],
+        )
+        .append_aml_bytes(bytes)
+    }
+}
+
+/// Methods of a PCI root bridge notifying its [`PciSlot`]s
+///
+/// `PCNT` notifies the slots of the segment being inserted (device check) or removed (eject
+/// request), as read from the registers of the [`PciHotplugController`].
+pub struct PciSlotNotifications {
+    devices: Vec<u8>,
+}
+
+impl PciSlotNotifications {
+    /// Create the methods notifying the given slots, which the root bridge must declare.
+    pub fn new(slots: &[PciSlot]) -> Self {
+        PciSlotNotifications {
+            devices: slots.iter().map(|slot| slot.device).collect(),
+        }
+    }
+}
+
+impl Aml for PciSlotNotifications {
+    fn append_aml_bytes(&self, bytes: &mut Vec<u8>) -> Result<(), AmlError> {
+        let notifies: Vec<_> = self
+            .devices
+            .iter()
+            .map(|&device| PciSlotNotify { device })
+            .collect();
+        let notifies: Vec<&dyn Aml> = notifies.iter().map(|notify| notify as &dyn Aml).collect();
+
+        Method::new("DVNT".try_into()?, 2, true, notifies).append_aml_bytes(bytes)?;
+        Method::new(
+            "PCNT".try_into()?,
+            0,
+            true,
+            vec![
+                &Acquire::new("\\_SB_.PHPR.BLCK".try_into()?, 0xffff),
+                &Store::new(&Path::new("\\_SB_.PHPR.PSEG")?, &Path::new("_SEG")?),
+                &MethodCall::new(
+                    "DVNT".try_into()?,
+                    vec![&Path::new("\\_SB_.PHPR.PCIU")?, &ONE],
+                ),
+                &MethodCall::new(
+                    "DVNT".try_into()?,
+                    vec![&Path::new("\\_SB_.PHPR.PCID")?, &3usize],
+                ),
+                &Release::new("\\_SB_.PHPR.BLCK".try_into()?),
+            ],
+        )
+        .append_aml_bytes(bytes)
+    }
+}
+
+/// Check the namespace built by `aml`
+///
+/// The names of the objects must be legal and unique in their scope, and every name referenced
+/// must resolve to an object defined by `aml` or by the specification. This catches mistakes
+/// which guests would otherwise only report at boot.
+pub fn validate(aml: &dyn Aml) -> Result<(), AmlError> {
+    disasm::check_namespace(&aml.to_aml_bytes()?)
+}
+
+#[doc(hidden)]
+pub fn children<const N: usize>(children: [&dyn Aml; N]) -> Vec<&dyn Aml> {
+    Vec::from(children)
+}
+
+#[doc(hidden)]
+pub fn build(
+    append: impl FnOnce(&mut Vec<u8>) -> Result<(), AmlError>,
+) -> Result<Vec<u8>, AmlError> {
+    let mut bytes = Vec::new();
+    append(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Generate the AML of ASL-like statements
+///
+/// `Scope`, `Device`, `Method`, `If`, `Else`, `While`, `Name` and `Return` are written as in
+/// ASL, with string literals for the paths and blocks for the children of the objects. Any other
+/// term is an expression of a type implementing [`Aml`], as are the predicates and the values.
+/// Statements which are not blocks end with a semicolon, e.g.:
+///
+/// ```text
+/// aml! {
+///     Device ("_SB_.PWRB") {
+///         Name ("_HID", EisaName::new("PNP0C0C")?);
+///         Method ("_STA", 0, NotSerialized) {
+///             If (Equal::new(&Local(0), &ZERO)) {
+///                 Return (ZERO);
+///             } Else {
+///                 Return (0x0fu8);
+///             }
+///         }
+///     }
+/// }
+/// ```
+///
+/// The macro evaluates to the bytecode of the statements, or to the first error encountered
+/// while generating it, as a `Result<Vec<u8>, AmlError>`.
+#[macro_export]
+macro_rules! aml {
+    (@serialized Serialized) => {
+        true
+    };
+    (@serialized NotSerialized) => {
+        false
+    };
+    (@children [$($child:expr,)*]) => {
+        $crate::aml::children([$(&$child as &dyn $crate::aml::Aml),*])
+    };
+    (@children [$($child:expr,)*] Scope ($path:expr) { $($body:tt)* } $($rest:tt)*) => {
+        $crate::aml!(@children [$($child,)* $crate::aml::Scope::new(
+            $crate::aml::Path::new($path)?,
+            $crate::aml!(@children [] $($body)*),
+        ),] $($rest)*)
+    };
+    (@children [$($child:expr,)*] Device ($path:expr) { $($body:tt)* } $($rest:tt)*) => {
+        $crate::aml!(@children [$($child,)* $crate::aml::Device::new(
+            $crate::aml::Path::new($path)?,
+            $crate::aml!(@children [] $($body)*),
+        ),] $($rest)*)
+    };
+    (
+        @children [$($child:expr,)*]
+        Method ($path:expr, $args:expr, $serialized:ident) { $($body:tt)* } $($rest:tt)*
+    ) => {
+        $crate::aml!(@children [$($child,)* $crate::aml::Method::new(
+            $crate::aml::Path::new($path)?,
+            $args,
+            $crate::aml!(@serialized $serialized),
+            $crate::aml!(@children [] $($body)*),
+        ),] $($rest)*)
+    };
+    (@children [$($child:expr,)*] If ($predicate:expr) { $($body:tt)* } $($rest:tt)*) => {
+        $crate::aml!(@children [$($child,)* $crate::aml::If::new(
+            &$predicate,
+            $crate::aml!(@children [] $($body)*),
+        ),] $($rest)*)
+    };
+    (@children [$($child:expr,)*] Else { $($body:tt)* } $($rest:tt)*) => {
+        $crate::aml!(@children [$($child,)* $crate::aml::Else::new(
+            $crate::aml!(@children [] $($body)*),
+        ),] $($rest)*)
+    };
+    (@children [$($child:expr,)*] While ($predicate:expr) { $($body:tt)* } $($rest:tt)*) => {
+        $crate::aml!(@children [$($child,)* $crate::aml::While::new(
+            &$predicate,
+            $crate::aml!(@children [] $($body)*),
+        ),] $($rest)*)
+    };
+    (@children [$($child:expr,)*] Name ($path:expr, $value:expr); $($rest:tt)*) => {
+        $crate::aml!(@children [$($child,)* $crate::aml::Name::new(
+            $crate::aml::Path::new($path)?,
+            &$value,
+        )?,] $($rest)*)
+    };
+    (@children [$($child:expr,)*] Return ($value:expr); $($rest:tt)*) => {
+        $crate::aml!(@children [$($child,)* $crate::aml::Return::new(&$value),] $($rest)*)
+    };
+    (@children [$($child:expr,)*] $term:expr; $($rest:tt)*) => {
+        $crate::aml!(@children [$($child,)* $term,] $($rest)*)
+    };
+    ($($statements:tt)*) => {
+        $crate::aml::build(|bytes| {
+            for child in $crate::aml!(@children [] $($statements)*) {
+                $crate::aml::Aml::append_aml_bytes(child, bytes)?;
+            }
+            Ok(())
+        })
+    };
+}
+
 #[cfg(test)]
 mod tests {
+    use zerocopy::IntoBytes;
+
     use super::*;
 
     #[test]
@@ -1227,6 +2957,27 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_thermal_zone() {
+        // ThermalZone (_TZ.TZ00)
+        // {
+        // Name (_CRT, 0x0E94)  // _CRT: Critical Temperature
+        // }
+        let thermal_zone = [
+            0x5B, 0x85, 0x12, 0x2E, 0x5F, 0x54, 0x5A, 0x5F, 0x54, 0x5A, 0x30, 0x30, 0x08, 0x5F,
+            0x43, 0x52, 0x54, 0x0B, 0x94, 0x0E,
+        ];
+        assert_eq!(
+            ThermalZone::new(
+                "_TZ_.TZ00".try_into().unwrap(),
+                vec![&Name::new("_CRT".try_into().unwrap(), &0x0e94usize).unwrap()],
+            )
+            .to_aml_bytes()
+            .unwrap(),
+            &thermal_zone[..]
+        );
+    }
+
     #[test]
     fn test_scope() {
         // Scope (_SB.MBRD)
@@ -1808,6 +3559,295 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_index_field() {
+        // IndexField (INDX, DATA, ByteAcc, NoLock, Preserve)
+        // {
+        // Offset (0x10),
+        // RTCA,   8
+        // }
+
+        let field_data = [
+            0x5Bu8, 0x86, 0x12, 0x49, 0x4E, 0x44, 0x58, 0x44, 0x41, 0x54, 0x41, 0x01, 0x00, 0x40,
+            0x08, 0x52, 0x54, 0x43, 0x41, 0x08,
+        ];
+
+        assert_eq!(
+            IndexField::new(
+                "INDX".try_into().unwrap(),
+                "DATA".try_into().unwrap(),
+                FieldAccessType::Byte,
+                FieldLockRule::NoLock,
+                FieldUpdateRule::Preserve,
+                vec![FieldEntry::Reserved(128), FieldEntry::Named(*b"RTCA", 8)]
+            )
+            .to_aml_bytes()
+            .unwrap(),
+            &field_data[..]
+        );
+    }
+
+    #[test]
+    fn test_bank_field() {
+        // BankField (REG0, BNK0, 0x02, DWordAcc, Lock, WriteAsOnes)
+        // {
+        // FLD0,   32
+        // }
+
+        let field_data = [
+            0x5Bu8, 0x87, 0x11, 0x52, 0x45, 0x47, 0x30, 0x42, 0x4E, 0x4B, 0x30, 0x0A, 0x02, 0x33,
+            0x46, 0x4C, 0x44, 0x30, 0x20,
+        ];
+
+        assert_eq!(
+            BankField::new(
+                "REG0".try_into().unwrap(),
+                "BNK0".try_into().unwrap(),
+                &2u8,
+                FieldAccessType::DWord,
+                FieldLockRule::Lock,
+                FieldUpdateRule::WriteAsOnes,
+                vec![FieldEntry::Named(*b"FLD0", 32)]
+            )
+            .to_aml_bytes()
+            .unwrap(),
+            &field_data[..]
+        );
+    }
+
+    #[test]
+    fn test_power_resource() {
+        // PowerResource (PRS0, 0x00, 0x0001)
+        // {
+        // Method (_STA, 0, NotSerialized)  // _STA: Status
+        // {
+        // Return (One)
+        // }
+        // Method (_ON, 0, NotSerialized)  // _ON_: Power On
+        // {
+        // }
+        // Method (_OFF, 0, NotSerialized)  // _OFF: Power Off
+        // {
+        // }
+        // }
+
+        let power_resource_data = [
+            0x5B, 0x84, 0x1F, 0x50, 0x52, 0x53, 0x30, 0x00, 0x01, 0x00, 0x14, 0x08, 0x5F, 0x53,
+            0x54, 0x41, 0x00, 0xA4, 0x01, 0x14, 0x06, 0x5F, 0x4F, 0x4E, 0x5F, 0x00, 0x14, 0x06,
+            0x5F, 0x4F, 0x46, 0x46, 0x00,
+        ];
+
+        assert_eq!(
+            PowerResource::new(
+                "PRS0".try_into().unwrap(),
+                0,
+                1,
+                vec![
+                    &Method::new(
+                        "_STA".try_into().unwrap(),
+                        0,
+                        false,
+                        vec![&Return::new(&ONE)]
+                    ),
+                    &Method::new("_ON_".try_into().unwrap(), 0, false, vec![]),
+                    &Method::new("_OFF".try_into().unwrap(), 0, false, vec![]),
+                ]
+            )
+            .to_aml_bytes()
+            .unwrap(),
+            &power_resource_data[..]
+        );
+    }
+
+    #[test]
+    fn test_legacy_resources() {
+        // ResourceTemplate ()
+        // {
+        // FixedIO (0x0080, 0x01, )
+        // DMA (Compatibility, NotBusMaster, Transfer8, ) {2}
+        // Interrupt (ResourceConsumer, Level, ActiveHigh, ExclusiveAndWake, ,, ) {5}
+        // }
+
+        let resources_data = [
+            0x11, 0x15, 0x0A, 0x12, 0x4B, 0x80, 0x00, 0x01, 0x2A, 0x04, 0x00, 0x89, 0x06, 0x00,
+            0x11, 0x01, 0x05, 0x00, 0x00, 0x00, 0x79, 0x00,
+        ];
+
+        assert_eq!(
+            ResourceTemplate::new(vec![
+                &FixedIo::new(0x80, 1),
+                &Dma::new(
+                    DmaChannelSpeed::Compatibility,
+                    false,
+                    DmaTransferSize::Transfer8,
+                    1 << 2
+                ),
+                &Interrupt::new(true, false, false, false, 5).wake_capable(),
+            ])
+            .to_aml_bytes()
+            .unwrap(),
+            &resources_data[..]
+        );
+    }
+
+    #[test]
+    fn test_gpio_connections() {
+        // GpioInt (Edge, ActiveLow, Exclusive, PullUp, 0x0000, "GPI0", 0x00, ResourceConsumer, ,)
+        // {
+        //     0x0003
+        // }
+        let gpio_int_data = [
+            0x8C, 0x1B, 0x00, 0x01, 0x00, 0x01, 0x00, 0x03, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00,
+            0x17, 0x00, 0x00, 0x19, 0x00, 0x1E, 0x00, 0x00, 0x00, 0x03, 0x00, 0x47, 0x50, 0x49,
+            0x30, 0x00,
+        ];
+        assert_eq!(
+            GpioInt::new(true, true, false, "GPI0", vec![3])
+                .pin_config(GpioPinConfig::PullUp)
+                .to_aml_bytes()
+                .unwrap(),
+            &gpio_int_data[..]
+        );
+
+        // GpioIo (Shared, PullDefault, 0x0000, 0x0000, IoRestrictionOutputOnly, "GPI0", 0x00,
+        //     ResourceConsumer, ,)
+        // {
+        //     0x0001,
+        //     0x0002
+        // }
+        let gpio_io_data = [
+            0x8C, 0x1D, 0x00, 0x01, 0x01, 0x01, 0x00, 0x0A, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x17, 0x00, 0x00, 0x1B, 0x00, 0x20, 0x00, 0x00, 0x00, 0x01, 0x00, 0x02, 0x00, 0x47,
+            0x50, 0x49, 0x30, 0x00,
+        ];
+        assert_eq!(
+            GpioIo::new(true, GpioIoRestriction::OutputOnly, "GPI0", vec![1, 2])
+                .to_aml_bytes()
+                .unwrap(),
+            &gpio_io_data[..]
+        );
+    }
+
+    #[test]
+    fn test_serial_bus_connections() {
+        // I2cSerialBusV2 (0x001A, ControllerInitiated, 0x00061A80, AddressingMode7Bit, "I2C0",
+        //     0x00, ResourceConsumer, , Exclusive,)
+        let i2c_data = [
+            0x8E, 0x14, 0x00, 0x02, 0x00, 0x01, 0x02, 0x00, 0x00, 0x01, 0x06, 0x00, 0x80, 0x1A,
+            0x06, 0x00, 0x1A, 0x00, 0x49, 0x32, 0x43, 0x30, 0x00,
+        ];
+        assert_eq!(
+            I2cSerialBus::new(0x1a, 400_000, false, "I2C0")
+                .to_aml_bytes()
+                .unwrap(),
+            &i2c_data[..]
+        );
+
+        // SpiSerialBusV2 (0x0000, PolarityLow, FourWireMode, 0x08, ControllerInitiated,
+        //     0x000F4240, ClockPolarityLow, ClockPhaseFirst, "SPI0", 0x00, ResourceConsumer, ,
+        //     Exclusive,)
+        let spi_data = [
+            0x8E, 0x17, 0x00, 0x02, 0x00, 0x02, 0x02, 0x00, 0x00, 0x01, 0x09, 0x00, 0x40, 0x42,
+            0x0F, 0x00, 0x08, 0x00, 0x00, 0x00, 0x00, 0x53, 0x50, 0x49, 0x30, 0x00,
+        ];
+        assert_eq!(
+            SpiSerialBus::new(0, 1_000_000, 8, false, false, "SPI0")
+                .to_aml_bytes()
+                .unwrap(),
+            &spi_data[..]
+        );
+
+        // UartSerialBusV2 (0x0001C200, DataBitsEight, StopBitsOne, 0xC0, LittleEndian,
+        //     ParityTypeNone, FlowControlHardware, 0x0010, 0x0010, "COM0", 0x00,
+        //     ResourceConsumer, , Exclusive,)
+        let uart_data = [
+            0x8E, 0x18, 0x00, 0x02, 0x00, 0x03, 0x02, 0x35, 0x00, 0x01, 0x0A, 0x00, 0x00, 0xC2,
+            0x01, 0x00, 0x10, 0x00, 0x10, 0x00, 0x00, 0xC0, 0x43, 0x4F, 0x4D, 0x30, 0x00,
+        ];
+        assert_eq!(
+            UartSerialBus::new(115_200, "COM0")
+                .flow_control(UartFlowControl::Hardware)
+                .to_aml_bytes()
+                .unwrap(),
+            &uart_data[..]
+        );
+    }
+
+    #[test]
+    fn test_string_operators() {
+        // Method (TEST, 1, NotSerialized)
+        // {
+        // Local0 = SizeOf (Arg0)
+        // Local1 = ObjectType (Arg0)
+        // ToHexString (Arg0, Local2)
+        // ToBuffer (Arg0, Local3)
+        // Mid (Arg0, One, 0x02, Local4)
+        // Concatenate (Local2, Local4, Local5)
+        // }
+
+        let data = [
+            0x14, 0x1E, 0x54, 0x45, 0x53, 0x54, 0x01, 0x70, 0x87, 0x68, 0x60, 0x70, 0x8E, 0x68,
+            0x61, 0x98, 0x68, 0x62, 0x96, 0x68, 0x63, 0x9E, 0x68, 0x01, 0x0A, 0x02, 0x64, 0x73,
+            0x62, 0x64, 0x65,
+        ];
+
+        assert_eq!(
+            Method::new(
+                "TEST".try_into().unwrap(),
+                1,
+                false,
+                vec![
+                    &Store::new(&Local(0), &SizeOf::new(&Arg(0))),
+                    &Store::new(&Local(1), &ObjectType::new(&Arg(0))),
+                    &ToHexString::new(&Local(2), &Arg(0)),
+                    &ToBuffer::new(&Local(3), &Arg(0)),
+                    &Mid::new(&Local(4), &Arg(0), &ONE, &2u8),
+                    &Concat::new(&Local(5), &Local(2), &Local(4)),
+                ]
+            )
+            .to_aml_bytes()
+            .unwrap(),
+            &data[..]
+        );
+    }
+
+    #[test]
+    fn test_event() {
+        // Event (EVT0)
+        // Method (TEST, 0, NotSerialized)
+        // {
+        // Signal (EVT0)
+        // Local0 = Wait (EVT0, 0xFFFF)
+        // Reset (EVT0)
+        // }
+
+        let event_data = [
+            0x5B, 0x02, 0x45, 0x56, 0x54, 0x30, 0x14, 0x1D, 0x54, 0x45, 0x53, 0x54, 0x00, 0x5B,
+            0x24, 0x45, 0x56, 0x54, 0x30, 0x70, 0x5B, 0x25, 0x45, 0x56, 0x54, 0x30, 0x0B, 0xFF,
+            0xFF, 0x60, 0x5B, 0x26, 0x45, 0x56, 0x54, 0x30,
+        ];
+
+        let mut aml = Event::new("EVT0".try_into().unwrap())
+            .to_aml_bytes()
+            .unwrap();
+        Method::new(
+            "TEST".try_into().unwrap(),
+            0,
+            false,
+            vec![
+                &Signal::new("EVT0".try_into().unwrap()),
+                &Store::new(
+                    &Local(0),
+                    &Wait::new("EVT0".try_into().unwrap(), &0xffffu16),
+                ),
+                &Reset::new("EVT0".try_into().unwrap()),
+            ],
+        )
+        .append_aml_bytes(&mut aml)
+        .unwrap();
+        assert_eq!(aml, &event_data[..]);
+    }
+
     #[test]
     fn test_notify() {
         // Device (_SB.MHPC)
@@ -1963,6 +4003,30 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_register() {
+        // ResourceTemplate ()
+        // {
+        // Register (PCCSpace,
+        // 0x20,               // Bit Width
+        // 0x00,               // Bit Offset
+        // 0x0000000000000008, // Address
+        // 0x00,               // Access Size
+        // )
+        // }
+        let register_data = [
+            0x11, 0x14, 0x0A, 0x11, 0x82, 0x0C, 0x00, 0x0A, 0x20, 0x00, 0x00, 0x08, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x79, 0x00,
+        ];
+
+        assert_eq!(
+            ResourceTemplate::new(vec![&Register::new(REGISTER_SPACE_PCC, 32, 0, 0, 8)])
+                .to_aml_bytes()
+                .unwrap(),
+            &register_data[..]
+        );
+    }
+
     #[test]
     fn test_create_field() {
         // Method (MCRS, 0, Serialized)
@@ -2032,4 +4096,245 @@ mod tests {
             &data[..]
         );
     }
+
+    #[test]
+    fn test_create_fields() {
+        // Method (TEST, 1, NotSerialized)
+        // {
+        // CreateByteField (Arg0, 0x01, BYT0)
+        // CreateWordField (Arg0, 0x02, WRD0)
+        // CreateField (Arg0, 0x20, 0x03, BITS)
+        // Local0 = DerefOf (Arg0 [0x04])
+        // }
+
+        let data = [
+            0x14, 0x29, 0x54, 0x45, 0x53, 0x54, 0x01, 0x8C, 0x68, 0x0A, 0x01, 0x42, 0x59, 0x54,
+            0x30, 0x8B, 0x68, 0x0A, 0x02, 0x57, 0x52, 0x44, 0x30, 0x5B, 0x13, 0x68, 0x0A, 0x20,
+            0x0A, 0x03, 0x42, 0x49, 0x54, 0x53, 0x70, 0x83, 0x88, 0x68, 0x0A, 0x04, 0x00, 0x60,
+        ];
+
+        let element = Index::new(&ZERO, &Arg(0), &4usize);
+        assert_eq!(
+            Method::new(
+                "TEST".try_into().unwrap(),
+                1,
+                false,
+                vec![
+                    &CreateField::<u8>::new(&Arg(0), &1usize, "BYT0".try_into().unwrap()),
+                    &CreateField::<u16>::new(&Arg(0), &2usize, "WRD0".try_into().unwrap()),
+                    &CreateBitsField::new(&Arg(0), &32usize, &3usize, "BITS".try_into().unwrap()),
+                    &Store::new(&Local(0), &DerefOf::new(&element)),
+                ]
+            )
+            .to_aml_bytes()
+            .unwrap(),
+            &data[..]
+        );
+    }
+
+    #[test]
+    fn test_aml_macro() {
+        // Device (_SB.PWRB)
+        // {
+        // Name (_HID, EisaId ("PNP0C0C"))
+        // Method (_STA, 1, NotSerialized)
+        // {
+        // If ((Arg0 == One))
+        // {
+        // Return (0x0F)
+        // }
+        // Else
+        // {
+        // Return (Zero)
+        // }
+        // }
+        // }
+        let hid = EisaName::new("PNP0C0C").unwrap();
+        let name = Name::new("_HID".try_into().unwrap(), &hid).unwrap();
+        let is_one = Equal::new(&Arg(0), &ONE);
+        let return_present = Return::new(&0x0fu8);
+        let return_absent = Return::new(&ZERO);
+        let if_one = If::new(&is_one, vec![&return_present]);
+        let otherwise = Else::new(vec![&return_absent]);
+        let sta = Method::new(
+            "_STA".try_into().unwrap(),
+            1,
+            false,
+            vec![&if_one, &otherwise],
+        );
+        let expected = Device::new("_SB_.PWRB".try_into().unwrap(), vec![&name, &sta]);
+
+        let bytes = crate::aml! {
+            Device ("_SB_.PWRB") {
+                Name ("_HID", EisaName::new("PNP0C0C")?);
+                Method ("_STA", 1, NotSerialized) {
+                    If (Equal::new(&Arg(0), &ONE)) {
+                        Return (0x0fu8);
+                    } Else {
+                        Return (ZERO);
+                    }
+                }
+            }
+        };
+        assert_eq!(bytes.unwrap(), expected.to_aml_bytes().unwrap());
+
+        let bytes = crate::aml! {
+            Name ("_UID", ZERO);
+            Store::new(&Local(0), &ONE);
+        };
+        assert_eq!(
+            bytes.unwrap(),
+            [0x08, b'_', b'U', b'I', b'D', 0x00, 0x70, 0x01, 0x60]
+        );
+
+        // Empty scopes are fine, but their names must still be made of 4 characters parts.
+        let scope = Scope::new("_SB_".try_into().unwrap(), vec![]);
+        let bytes = crate::aml! { Scope ("_SB_") {} };
+        assert_eq!(bytes.unwrap(), scope.to_aml_bytes().unwrap());
+        assert!(matches!(
+            crate::aml! { Scope ("FOO") {} },
+            Err(AmlError::InvalidPartLength)
+        ));
+    }
+
+    #[test]
+    fn test_validate() {
+        let uid = Name::new("_UID".try_into().unwrap(), &ZERO).unwrap();
+        let mutex = Mutex::new("MLCK".try_into().unwrap(), 0);
+        let acquire = Acquire::new("MLCK".try_into().unwrap(), 0xffff);
+        let pwrb = Path::new("\\_SB_.PWRB").unwrap();
+        let notify = Notify::new(&pwrb, &0x80u8);
+        let method = Method::new("TEST".try_into().unwrap(), 0, true, vec![&acquire, &notify]);
+        let pwrb_children: Vec<&dyn Aml> = vec![&uid, &mutex, &method];
+        let device = Device::new("_SB_.PWRB".try_into().unwrap(), pwrb_children);
+        validate(&device).unwrap();
+
+        let device = Device::new("_SB_.PWRB".try_into().unwrap(), vec![&uid, &uid]);
+        assert!(matches!(
+            validate(&device),
+            Err(AmlError::DuplicateName(name)) if name == "\\_SB_.PWRB._UID"
+        ));
+
+        let release = Release::new("MLCX".try_into().unwrap());
+        let method = Method::new("TEST".try_into().unwrap(), 0, true, vec![&release]);
+        let pwrb_children: Vec<&dyn Aml> = vec![&mutex, &method];
+        let device = Device::new("_SB_.PWRB".try_into().unwrap(), pwrb_children);
+        assert!(matches!(
+            validate(&device),
+            Err(AmlError::UnresolvedPath(name, scope))
+                if name == "MLCX" && scope == "\\_SB_.PWRB.TEST"
+        ));
+
+        let uid = Name::new("_uid".try_into().unwrap(), &ZERO).unwrap();
+        assert!(matches!(
+            validate(&uid),
+            Err(AmlError::InvalidNameSeg(name)) if name == "_uid"
+        ));
+    }
+
+    #[test]
+    fn test_generic_event_device() {
+        let ged = GenericEventDevice::new("_SB_.GED_".try_into().unwrap(), 5, 0xfe00_0000)
+            .notify(0, "\\_SB_.CPUS".try_into().unwrap(), 0x80)
+            .notify(3, "\\_SB_.MHPC".try_into().unwrap(), 0x80);
+
+        let expected = "\
+Device (_SB_.GED_)
+{
+    Name (_HID, \"ACPI0013\")
+    Name (_CRS, Buffer (0x0B) {0x89, 0x06, 0x00, 0x03, 0x01, 0x05, 0x00, 0x00, 0x00, 0x79, 0x00})
+    OperationRegion (GDST, 0x00, 0xFE000000, 0x04)
+    Field (GDST, DWordAcc, NoLock, WriteAsZeros)
+    {
+        GDAT, 32
+    }
+    Method (_EVT, 1, Serialized)
+    {
+        Store (GDAT, Local0)
+        If (LEqual (And (Local0, 0x01), 0x01))
+        {
+            Notify (\\_SB_.CPUS, 0x80)
+        }
+        If (LEqual (And (Local0, 0x08), 0x08))
+        {
+            Notify (\\_SB_.MHPC, 0x80)
+        }
+    }
+}
+";
+        let aml = ged.to_aml_bytes().unwrap();
+        assert_eq!(disasm::disassemble(&aml).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_cpu_hotplug() {
+        let cpus = CpuHotplug::new(2, 0xfe00_1000);
+        validate(&cpus).unwrap();
+
+        let text = disasm::disassemble(&cpus.to_aml_bytes().unwrap()).unwrap();
+        assert!(text.starts_with("Device (\\_SB_.CPUS)\n"));
+        assert!(text.contains("OperationRegion (CREG, 0x00, 0xFE001000, 0x08)"));
+        for method in ["CSTA, 1", "CEJ0, 1", "CTFY, 2", "CSCN, 0"] {
+            assert!(text.contains(&format!("Method ({method}, Serialized)")));
+        }
+        assert!(text.contains("    Device (C000)\n"));
+        assert!(text.contains("    Device (C001)\n"));
+        assert!(!text.contains("C002"));
+    }
+
+    #[test]
+    fn test_memory_hotplug() {
+        let memory = MemoryHotplug::new(0x1_0000_0000, 0x800_0000, 3, 0xfe00_2000);
+        validate(&memory).unwrap();
+
+        let text = disasm::disassemble(&memory.to_aml_bytes().unwrap()).unwrap();
+        assert!(text.starts_with("Device (\\_SB_.MHPC)\n"));
+        for method in ["MSTA, 1", "MEJ0, 1", "MTFY, 2", "MSCN, 0"] {
+            assert!(text.contains(&format!("Method ({method}, Serialized)")));
+        }
+        assert!(text.contains("    Device (M002)\n"));
+        assert!(!text.contains("M003"));
+
+        let affinity = memory.memory_affinity(1);
+        let bytes = affinity.as_bytes();
+        assert_eq!(&bytes[8..16], &0x1_0000_0000u64.to_le_bytes());
+        assert_eq!(&bytes[16..24], &0x1800_0000u64.to_le_bytes());
+        // Enabled and hot pluggable.
+        assert_eq!(&bytes[28..32], &3u32.to_le_bytes());
+    }
+
+    #[test]
+    fn test_pci_hotplug() {
+        let controller = PciHotplugController::new(0xfe00_3000, 1);
+        let seg = Name::new("_SEG".try_into().unwrap(), &ZERO).unwrap();
+        let slots = [PciSlot::new(1), PciSlot::new(2).proximity_domain(1)];
+        let notifications = PciSlotNotifications::new(&slots);
+        let bridge = Device::new(
+            "\\_SB_.PC00".try_into().unwrap(),
+            vec![&seg, &slots[0], &slots[1], &notifications],
+        );
+        let scope = Scope::new("\\_SB_".try_into().unwrap(), vec![&controller, &bridge]);
+        validate(&scope).unwrap();
+
+        let text = disasm::disassemble(&scope.to_aml_bytes().unwrap()).unwrap();
+        // Only the slots of the root bridge are notified.
+        assert!(!text.contains("S000"));
+        let expected = "
+        Device (S001)
+        {
+            Name (_SUN, 0x01)
+            Name (_ADR, 0x00010000)
+            Method (_EJ0, 1, Serialized)
+            {
+                \\_SB_.PHPR.PCEJ
+                _SUN
+                _SEG
+            }
+        }
+";
+        assert!(text.contains(expected));
+        assert!(text.contains("Name (_PXM, 0x00000001)"));
+        assert!(text.contains("OperationRegion (PCST, 0x00, 0xFE003000, 0x10)"));
+        assert!(text.contains("\\_SB_.PC00.PCNT"));
+    }
 }
@@ -0,0 +1,620 @@
+// Copyright 2024 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Disassembler of the AML bytecode generated by this crate, into ASL-like text
+//!
+//! It covers the objects and operators the [`Aml`](super::Aml) implementations of this crate
+//! emit. A name found where a term is expected is printed as is: since the number of arguments
+//! of a method is not known without resolving its name, the arguments of a method call appear
+//! as separate terms after it.
+//!
+//! The same decoder checks the namespace built by the AML: the names of the objects must be
+//! legal and unique in their scope, and the names referenced must resolve to an object.
+
+use alloc::borrow::ToOwned;
+use alloc::collections::BTreeSet;
+use alloc::string::String;
+use alloc::vec::Vec;
+use alloc::{format, vec};
+use core::fmt::Write;
+
+use super::AmlError;
+
+const ACCESS_TYPES: [&str; 6] = [
+    "AnyAcc",
+    "ByteAcc",
+    "WordAcc",
+    "DWordAcc",
+    "QWordAcc",
+    "BufferAcc",
+];
+const UPDATE_RULES: [&str; 3] = ["Preserve", "WriteAsOnes", "WriteAsZeros"];
+// Objects of the namespace defined by the specification rather than by the definition blocks
+const PREDEFINED: [&str; 10] = [
+    "\\", "\\_GPE", "\\_PR_", "\\_SB_", "\\_SI_", "\\_TZ_", "\\_GL_", "\\_OS_", "\\_OSI", "\\_REV",
+];
+
+/// Disassemble a sequence of AML terms, such as the definition block of a DSDT
+pub fn disassemble(aml: &[u8]) -> Result<String, AmlError> {
+    let mut disassembler = Disassembler::new(aml);
+    disassembler.term_list(aml.len())?;
+    Ok(disassembler.out)
+}
+
+// Check that the names referenced by a sequence of AML terms resolve to the objects they define.
+pub(super) fn check_namespace(aml: &[u8]) -> Result<(), AmlError> {
+    let mut disassembler = Disassembler::new(aml);
+    disassembler.term_list(aml.len())?;
+    for (scope, name) in &disassembler.references {
+        let resolved = if name.contains(['\\', '^', '.']) {
+            disassembler.is_defined(&resolve(scope, name))
+        } else {
+            // A single name segment is searched in the scope and then in its parents.
+            (0..=scope.len())
+                .rev()
+                .any(|len| disassembler.is_defined(&resolve(&scope[..len], name)))
+        };
+        if !resolved {
+            return Err(AmlError::UnresolvedPath(name.clone(), path(scope)));
+        }
+    }
+    Ok(())
+}
+
+// Absolute path of `name` in `scope`, as a list of name segments.
+fn resolve(scope: &[String], name: &str) -> Vec<String> {
+    let mut segs = scope.to_vec();
+    let mut name = name;
+    if let Some(absolute) = name.strip_prefix('\\') {
+        segs.clear();
+        name = absolute;
+    }
+    while let Some(parent) = name.strip_prefix('^') {
+        segs.pop();
+        name = parent;
+    }
+    segs.extend(
+        name.split('.')
+            .filter(|seg| !seg.is_empty())
+            .map(str::to_owned),
+    );
+    segs
+}
+
+fn path(segs: &[String]) -> String {
+    format!("\\{}", segs.join("."))
+}
+
+struct Disassembler<'a> {
+    aml: &'a [u8],
+    pos: usize,
+    depth: usize,
+    out: String,
+    // Absolute path of the scope of the terms being decoded
+    scope: Vec<String>,
+    // Whether the terms are part of a method, whose objects are only created when it runs
+    in_method: bool,
+    names: BTreeSet<String>,
+    references: Vec<(Vec<String>, String)>,
+}
+
+impl<'a> Disassembler<'a> {
+    fn new(aml: &'a [u8]) -> Self {
+        Disassembler {
+            aml,
+            pos: 0,
+            depth: 0,
+            out: String::new(),
+            scope: Vec::new(),
+            in_method: false,
+            names: BTreeSet::new(),
+            references: Vec::new(),
+        }
+    }
+
+    fn peek(&self) -> Result<u8, AmlError> {
+        self.aml.get(self.pos).copied().ok_or(AmlError::Truncated)
+    }
+
+    fn byte(&mut self) -> Result<u8, AmlError> {
+        let byte = self.peek()?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], AmlError> {
+        let bytes = self
+            .aml
+            .get(self.pos..self.pos + len)
+            .ok_or(AmlError::Truncated)?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn integer(&mut self, len: usize) -> Result<u64, AmlError> {
+        let bytes = self.bytes(len)?;
+        Ok(bytes
+            .iter()
+            .rev()
+            .fold(0, |value, byte| (value << 8) | u64::from(*byte)))
+    }
+
+    fn pkg_length(&mut self) -> Result<usize, AmlError> {
+        let lead = self.byte()?;
+        let follow = usize::from(lead >> 6);
+        if follow == 0 {
+            return Ok(usize::from(lead & 0x3f));
+        }
+        let length = self.integer(follow)?;
+        Ok(usize::from(lead & 0xf) | (usize::try_from(length).unwrap() << 4))
+    }
+
+    // Decode the PkgLength of the object being disassembled, returning the offset of its end.
+    fn pkg_end(&mut self) -> Result<usize, AmlError> {
+        let start = self.pos;
+        let end = start + self.pkg_length()?;
+        if end > self.aml.len() {
+            return Err(AmlError::Truncated);
+        }
+        Ok(end)
+    }
+
+    fn name_seg(&mut self) -> Result<String, AmlError> {
+        let seg = self.bytes(4)?;
+        let name = String::from_utf8_lossy(seg).into_owned();
+        let lead = matches!(seg[0], b'A'..=b'Z' | b'_');
+        let rest = seg[1..]
+            .iter()
+            .all(|c| matches!(c, b'A'..=b'Z' | b'0'..=b'9' | b'_'));
+        if !lead || !rest {
+            return Err(AmlError::InvalidNameSeg(name));
+        }
+        Ok(name)
+    }
+
+    fn name_string(&mut self) -> Result<String, AmlError> {
+        let mut name = String::new();
+        while matches!(self.peek()?, b'\\' | b'^') {
+            name.push(char::from(self.byte()?));
+        }
+        let segs = match self.peek()? {
+            0x00 => {
+                self.pos += 1;
+                0
+            }
+            0x2e => {
+                self.pos += 1;
+                2
+            }
+            0x2f => {
+                self.pos += 1;
+                self.byte()?
+            }
+            _ => 1,
+        };
+        let segs = (0..segs)
+            .map(|_| self.name_seg())
+            .collect::<Result<Vec<_>, _>>()?;
+        name.push_str(&segs.join("."));
+        Ok(name)
+    }
+
+    fn is_defined(&self, segs: &[String]) -> bool {
+        let path = path(segs);
+        PREDEFINED.contains(&path.as_str()) || self.names.contains(&path)
+    }
+
+    // Record the definition of an object, returning its absolute path.
+    fn define(&mut self, name: &str) -> Result<Vec<String>, AmlError> {
+        let segs = resolve(&self.scope, name);
+        // Objects created in the branches of a method may have the same name.
+        if !self.names.insert(path(&segs)) && !self.in_method {
+            return Err(AmlError::DuplicateName(path(&segs)));
+        }
+        Ok(segs)
+    }
+
+    fn reference(&mut self, name: String) -> String {
+        if !name.is_empty() {
+            self.references.push((self.scope.clone(), name.clone()));
+        }
+        name
+    }
+
+    fn line(&mut self, text: &str) {
+        writeln!(self.out, "{:width$}{text}", "", width = 4 * self.depth).unwrap();
+    }
+
+    fn term_list(&mut self, end: usize) -> Result<(), AmlError> {
+        while self.pos < end {
+            self.statement()?;
+        }
+        Ok(())
+    }
+
+    fn block(&mut self, header: &str, end: usize) -> Result<(), AmlError> {
+        self.line(header);
+        self.line("{");
+        self.depth += 1;
+        self.term_list(end)?;
+        self.depth -= 1;
+        self.line("}");
+        Ok(())
+    }
+
+    fn scoped_block(
+        &mut self,
+        header: &str,
+        scope: Vec<String>,
+        end: usize,
+    ) -> Result<(), AmlError> {
+        let parent = core::mem::replace(&mut self.scope, scope);
+        self.block(header, end)?;
+        self.scope = parent;
+        Ok(())
+    }
+
+    // Disassemble a term, on its own lines when it opens a scope.
+    fn statement(&mut self) -> Result<(), AmlError> {
+        let start = self.pos;
+        match (self.byte()?, self.aml.get(self.pos).copied()) {
+            (0x10, _) => {
+                let end = self.pkg_end()?;
+                let name = self.name_string()?;
+                self.reference(name.clone());
+                let scope = resolve(&self.scope, &name);
+                self.scoped_block(&format!("Scope ({name})"), scope, end)
+            }
+            (0x14, _) => {
+                let end = self.pkg_end()?;
+                let name = self.name_string()?;
+                let scope = self.define(&name)?;
+                let flags = self.byte()?;
+                let serialized = if flags & 0x08 != 0 {
+                    "Serialized"
+                } else {
+                    "NotSerialized"
+                };
+                let header = format!("Method ({name}, {}, {serialized})", flags & 0x07);
+                let in_method = core::mem::replace(&mut self.in_method, true);
+                self.scoped_block(&header, scope, end)?;
+                self.in_method = in_method;
+                Ok(())
+            }
+            (opcode @ (0xa0 | 0xa2), _) => {
+                let end = self.pkg_end()?;
+                let predicate = self.term()?;
+                let keyword = if opcode == 0xa0 { "If" } else { "While" };
+                self.block(&format!("{keyword} ({predicate})"), end)
+            }
+            (0xa1, _) => {
+                let end = self.pkg_end()?;
+                self.block("Else", end)
+            }
+            (0x5b, Some(opcode @ (0x81 | 0x82 | 0x84 | 0x85 | 0x86 | 0x87))) => {
+                self.pos += 1;
+                let end = self.pkg_end()?;
+                let name = self.name_string()?;
+                match opcode {
+                    0x81 => {
+                        let region = self.reference(name);
+                        self.field_list("Field", vec![region], end)
+                    }
+                    0x82 => {
+                        let scope = self.define(&name)?;
+                        self.scoped_block(&format!("Device ({name})"), scope, end)
+                    }
+                    0x84 => {
+                        let scope = self.define(&name)?;
+                        let system_level = self.byte()?;
+                        let resource_order = self.integer(2)?;
+                        let header = format!(
+                            "PowerResource ({name}, 0x{system_level:02X}, 0x{resource_order:04X})"
+                        );
+                        self.scoped_block(&header, scope, end)
+                    }
+                    0x85 => {
+                        let scope = self.define(&name)?;
+                        self.scoped_block(&format!("ThermalZone ({name})"), scope, end)
+                    }
+                    0x86 => {
+                        let index = self.reference(name);
+                        let data = self.name_string()?;
+                        let data = self.reference(data);
+                        self.field_list("IndexField", vec![index, data], end)
+                    }
+                    _ => {
+                        let name = self.reference(name);
+                        let bank = self.name_string()?;
+                        let bank = self.reference(bank);
+                        let bank_value = self.term()?;
+                        self.field_list("BankField", vec![name, bank, bank_value], end)
+                    }
+                }
+            }
+            _ => {
+                self.pos = start;
+                let term = self.term()?;
+                self.line(&term);
+                Ok(())
+            }
+        }
+    }
+
+    fn field_list(
+        &mut self,
+        keyword: &str,
+        mut args: Vec<String>,
+        end: usize,
+    ) -> Result<(), AmlError> {
+        let flags = self.byte()?;
+        let access_type = ACCESS_TYPES.get(usize::from(flags & 0x0f));
+        let update_rule = UPDATE_RULES.get(usize::from((flags >> 5) & 0x03));
+        let (Some(access_type), Some(update_rule)) = (access_type, update_rule) else {
+            return Err(AmlError::InvalidFieldFlags(flags));
+        };
+        let lock_rule = if flags & 0x10 != 0 { "Lock" } else { "NoLock" };
+        args.extend([access_type, lock_rule, update_rule].map(str::to_owned));
+
+        self.line(&format!("{keyword} ({})", args.join(", ")));
+        self.line("{");
+        self.depth += 1;
+        while self.pos < end {
+            let name = if self.peek()? == 0x00 {
+                self.pos += 1;
+                String::new()
+            } else {
+                let name = self.name_seg()?;
+                self.define(&name)?;
+                name
+            };
+            let bits = self.pkg_length()?;
+            self.line(&format!("{name}, {bits}"));
+        }
+        self.depth -= 1;
+        self.line("}");
+        Ok(())
+    }
+
+    // Disassemble a target, which is empty when the result of an operator is not stored.
+    fn target(&mut self) -> Result<String, AmlError> {
+        if self.peek()? == 0x00 {
+            self.pos += 1;
+            return Ok(String::new());
+        }
+        self.term()
+    }
+
+    fn call(&mut self, name: &str, args: usize, targets: usize) -> Result<String, AmlError> {
+        let mut operands = Vec::new();
+        for _ in 0..args {
+            operands.push(self.term()?);
+        }
+        for _ in 0..targets {
+            operands.push(self.target()?);
+        }
+        while operands.last().is_some_and(String::is_empty) {
+            operands.pop();
+        }
+        Ok(format!("{name} ({})", operands.join(", ")))
+    }
+
+    fn term(&mut self) -> Result<String, AmlError> {
+        let start = self.pos;
+        let term = match self.byte()? {
+            0x00 => "Zero".to_owned(),
+            0x01 => "One".to_owned(),
+            0xff => "Ones".to_owned(),
+            0x0a => format!("0x{:02X}", self.integer(1)?),
+            0x0b => format!("0x{:04X}", self.integer(2)?),
+            0x0c => format!("0x{:08X}", self.integer(4)?),
+            0x0e => format!("0x{:016X}", self.integer(8)?),
+            0x0d => {
+                let len = self.aml[self.pos..]
+                    .iter()
+                    .position(|byte| *byte == 0)
+                    .ok_or(AmlError::Truncated)?;
+                let string = String::from_utf8_lossy(self.bytes(len)?).into_owned();
+                self.pos += 1;
+                format!("{string:?}")
+            }
+            0x08 => {
+                let name = self.name_string()?;
+                self.define(&name)?;
+                let value = self.term()?;
+                format!("Name ({name}, {value})")
+            }
+            0x11 => {
+                let end = self.pkg_end()?;
+                let size = self.term()?;
+                let data: Vec<_> = self
+                    .bytes(end.saturating_sub(self.pos))?
+                    .iter()
+                    .map(|byte| format!("0x{byte:02X}"))
+                    .collect();
+                format!("Buffer ({size}) {{{}}}", data.join(", "))
+            }
+            0x12 => {
+                let end = self.pkg_end()?;
+                let count = self.byte()?;
+                let mut elements = Vec::new();
+                while self.pos < end {
+                    elements.push(self.term()?);
+                }
+                format!("Package (0x{count:02X}) {{{}}}", elements.join(", "))
+            }
+            opcode @ 0x60..=0x67 => format!("Local{}", opcode - 0x60),
+            opcode @ 0x68..=0x6e => format!("Arg{}", opcode - 0x68),
+            0x70 => self.call("Store", 2, 0)?,
+            0x72 => self.call("Add", 2, 1)?,
+            0x73 => self.call("Concatenate", 2, 1)?,
+            0x74 => self.call("Subtract", 2, 1)?,
+            0x75 => self.call("Increment", 1, 0)?,
+            0x76 => self.call("Decrement", 1, 0)?,
+            0x77 => self.call("Multiply", 2, 1)?,
+            0x78 => self.call("Divide", 2, 2)?,
+            0x79 => self.call("ShiftLeft", 2, 1)?,
+            0x7a => self.call("ShiftRight", 2, 1)?,
+            0x7b => self.call("And", 2, 1)?,
+            0x7c => self.call("NAnd", 2, 1)?,
+            0x7d => self.call("Or", 2, 1)?,
+            0x7e => self.call("NOr", 2, 1)?,
+            0x7f => self.call("XOr", 2, 1)?,
+            0x80 => self.call("Not", 1, 1)?,
+            0x83 => self.call("DerefOf", 1, 0)?,
+            0x84 => self.call("ConcatenateResTemplate", 2, 1)?,
+            0x85 => self.call("Mod", 2, 1)?,
+            0x86 => self.call("Notify", 2, 0)?,
+            0x87 => self.call("SizeOf", 1, 0)?,
+            0x88 => self.call("Index", 2, 1)?,
+            0x8e => self.call("ObjectType", 1, 0)?,
+            opcode @ (0x8a | 0x8b | 0x8c | 0x8f) => {
+                let keyword = match opcode {
+                    0x8a => "CreateDWordField",
+                    0x8b => "CreateWordField",
+                    0x8c => "CreateByteField",
+                    _ => "CreateQWordField",
+                };
+                let buffer = self.term()?;
+                let index = self.term()?;
+                let name = self.name_string()?;
+                self.define(&name)?;
+                format!("{keyword} ({buffer}, {index}, {name})")
+            }
+            0x90 => self.call("LAnd", 2, 0)?,
+            0x91 => self.call("LOr", 2, 0)?,
+            0x92 => self.call("LNot", 1, 0)?,
+            0x93 => self.call("LEqual", 2, 0)?,
+            0x94 => self.call("LGreater", 2, 0)?,
+            0x95 => self.call("LLess", 2, 0)?,
+            0x96 => self.call("ToBuffer", 1, 1)?,
+            0x97 => self.call("ToDecimalString", 1, 1)?,
+            0x98 => self.call("ToHexString", 1, 1)?,
+            0x99 => self.call("ToInteger", 1, 1)?,
+            0x9c => self.call("ToString", 2, 1)?,
+            0x9e => self.call("Mid", 3, 1)?,
+            0xa4 => self.call("Return", 1, 0)?,
+            0xa5 => "Break".to_owned(),
+            0x5b => self.ext_term(start)?,
+            b'\\' | b'^' | b'_' | b'A'..=b'Z' | 0x2e | 0x2f => {
+                self.pos = start;
+                let name = self.name_string()?;
+                self.reference(name)
+            }
+            opcode => return Err(AmlError::UnknownOpcode(opcode, start)),
+        };
+        Ok(term)
+    }
+
+    fn ext_term(&mut self, start: usize) -> Result<String, AmlError> {
+        let term = match self.byte()? {
+            0x01 => {
+                let name = self.name_string()?;
+                self.define(&name)?;
+                format!("Mutex ({name}, 0x{:02X})", self.byte()?)
+            }
+            0x02 => {
+                let name = self.name_string()?;
+                self.define(&name)?;
+                format!("Event ({name})")
+            }
+            0x13 => {
+                let buffer = self.term()?;
+                let bit_index = self.term()?;
+                let num_bits = self.term()?;
+                let name = self.name_string()?;
+                self.define(&name)?;
+                format!("CreateField ({buffer}, {bit_index}, {num_bits}, {name})")
+            }
+            0x23 => {
+                let mutex = self.term()?;
+                format!("Acquire ({mutex}, 0x{:04X})", self.integer(2)?)
+            }
+            0x24 => self.call("Signal", 1, 0)?,
+            0x25 => self.call("Wait", 2, 0)?,
+            0x26 => self.call("Reset", 1, 0)?,
+            0x27 => self.call("Release", 1, 0)?,
+            0x80 => {
+                let name = self.name_string()?;
+                self.define(&name)?;
+                let space = self.byte()?;
+                let offset = self.term()?;
+                let length = self.term()?;
+                format!("OperationRegion ({name}, 0x{space:02X}, {offset}, {length})")
+            }
+            opcode => return Err(AmlError::UnknownOpcode(opcode, start + 1)),
+        };
+        Ok(term)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aml::*;
+
+    #[test]
+    fn test_disassemble() {
+        let uid = Name::new("_UID".try_into().unwrap(), &0u8).unwrap();
+        let mutex = Mutex::new("MLCK".try_into().unwrap(), 0);
+        let is_one = Equal::new(&Arg(0), &ONE);
+        let return_one = Return::new(&"one");
+        let if_one = If::new(&is_one, vec![&return_one]);
+        let add = Add::new(&ZERO, &Arg(0), &2u8);
+        let store = Store::new(&Local(0), &add);
+        let method = Method::new("TEST".try_into().unwrap(), 1, true, vec![&if_one, &store]);
+        let device = Device::new("_SB_.MHPC".try_into().unwrap(), vec![&uid, &mutex, &method]);
+
+        let expected = "\
+Device (_SB_.MHPC)
+{
+    Name (_UID, 0x00)
+    Mutex (MLCK, 0x00)
+    Method (TEST, 1, Serialized)
+    {
+        If (LEqual (Arg0, One))
+        {
+            Return (\"one\")
+        }
+        Store (Add (Arg0, 0x02), Local0)
+    }
+}
+";
+        let aml = device.to_aml_bytes().unwrap();
+        assert_eq!(disassemble(&aml).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_disassemble_field() {
+        let field = Field::new(
+            "PRST".try_into().unwrap(),
+            FieldAccessType::Byte,
+            FieldUpdateRule::WriteAsZeroes,
+            vec![FieldEntry::Reserved(32), FieldEntry::Named(*b"CPEN", 1)],
+        );
+        let expected = "\
+Field (PRST, ByteAcc, NoLock, WriteAsZeros)
+{
+    , 32
+    CPEN, 1
+}
+";
+        let aml = field.to_aml_bytes().unwrap();
+        assert_eq!(disassemble(&aml).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_disassemble_invalid() {
+        let return_one = Return::new(&ONE);
+        let method = Method::new("TEST".try_into().unwrap(), 0, false, vec![&return_one]);
+        let aml = method.to_aml_bytes().unwrap();
+        assert!(matches!(
+            disassemble(&aml[..aml.len() - 1]),
+            Err(AmlError::Truncated)
+        ));
+        assert!(matches!(
+            disassemble(&[0x02]),
+            Err(AmlError::UnknownOpcode(0x02, 0))
+        ));
+    }
+}
@@ -0,0 +1,158 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use vm_memory::{Bytes, GuestAddress, GuestMemory};
+use zerocopy::{Immutable, IntoBytes};
+
+#[cfg(feature = "std")]
+use crate::AcpiError;
+use crate::endian::{U32, U64};
+use crate::{Result, Sdt, SdtHeader, checksum};
+
+/// Boot Error Record Table (BERT)
+///
+/// This table points to the Boot Error Region, which holds a Generic Error Status Block with the
+/// errors that happened before the guest booted, such as the ones the host detected in its memory,
+/// so that the guest can log them once it is up. More information about this table can be found
+/// in the ACPI specification:
+/// https://uefi.org/specs/ACPI/6.5/18_Platform_Error_Interfaces.html#boot-error-record-table-bert
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Bert {
+    header: SdtHeader,
+    region_length: U32,
+    region_address: U64,
+}
+
+assert_layout!(Bert, 48);
+
+impl Bert {
+    /// Create a BERT table pointing to the Boot Error Region of `region_length` bytes at
+    /// `region_address`.
+    pub fn new(
+        oem_id: [u8; 6],
+        oem_table_id: [u8; 8],
+        oem_revision: u32,
+        region_address: u64,
+        region_length: u32,
+    ) -> Self {
+        let header = SdtHeader::new(
+            *b"BERT",
+            core::mem::size_of::<Bert>().try_into().unwrap(),
+            1,
+            oem_id,
+            oem_table_id,
+            oem_revision,
+        );
+
+        let mut bert = Bert {
+            header,
+            region_length: U32::new(region_length),
+            region_address: U64::new(region_address),
+        };
+
+        bert.header.checksum = checksum(&[bert.as_bytes()]);
+
+        bert
+    }
+
+    /// Write the Generic Error Status Block `status`, such as a [`crate::MemoryErrorStatus`], at
+    /// the start of the Boot Error Region of `region_length` bytes at `region_address`, and create
+    /// a BERT table pointing to the region.
+    #[cfg(feature = "std")]
+    pub fn with_error_status<M: GuestMemory, S: IntoBytes + Immutable>(
+        oem_id: [u8; 6],
+        oem_table_id: [u8; 8],
+        oem_revision: u32,
+        mem: &M,
+        region_address: GuestAddress,
+        region_length: u32,
+        status: &S,
+    ) -> Result<Self> {
+        let status = status.as_bytes();
+        if status.len() > usize::try_from(region_length).unwrap() {
+            return Err(AcpiError::InvalidBertRegion);
+        }
+        mem.write_slice(status, region_address)?;
+
+        Ok(Bert::new(
+            oem_id,
+            oem_table_id,
+            oem_revision,
+            region_address.0,
+            region_length,
+        ))
+    }
+}
+
+impl Sdt for Bert {
+    fn len(&self) -> usize {
+        self.header.length.get().try_into().unwrap()
+    }
+
+    fn header(&self) -> Option<&SdtHeader> {
+        Some(&self.header)
+    }
+
+    fn to_bytes(&mut self) -> Result<Vec<u8>> {
+        Ok(self.as_bytes().to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vm_memory::GuestMemoryMmap;
+
+    use super::*;
+    use crate::MemoryErrorStatus;
+
+    #[test]
+    fn test_bert() {
+        let mem: GuestMemoryMmap =
+            GuestMemoryMmap::from_ranges(&[(GuestAddress(0), 0x1000)]).unwrap();
+        let status = MemoryErrorStatus::new(0x1234_5000, 0x1000);
+        let mut bert = Bert::with_error_status(
+            *b"FOOBAR",
+            *b"FOOBARBE",
+            0,
+            &mem,
+            GuestAddress(0x800),
+            0x400,
+            &status,
+        )
+        .unwrap();
+        assert_eq!(bert.len(), 48);
+
+        bert.write_to_guest(&mem, GuestAddress(0)).unwrap();
+        let mut bytes = vec![0u8; bert.len()];
+        mem.read_slice(&mut bytes, GuestAddress(0)).unwrap();
+        assert_eq!(checksum(&[&bytes]), 0);
+        assert_eq!(&bytes[..4], b"BERT");
+        assert_eq!(&bytes[36..40], &0x400u32.to_le_bytes());
+        assert_eq!(&bytes[40..48], &0x800u64.to_le_bytes());
+
+        let mut region = vec![0u8; status.as_bytes().len()];
+        mem.read_slice(&mut region, GuestAddress(0x800)).unwrap();
+        assert_eq!(region, status.as_bytes());
+
+        // The region must hold the whole block.
+        assert!(matches!(
+            Bert::with_error_status(
+                *b"FOOBAR",
+                *b"FOOBARBE",
+                0,
+                &mem,
+                GuestAddress(0x800),
+                0x80,
+                &status
+            ),
+            Err(AcpiError::InvalidBertRegion)
+        ));
+    }
+}
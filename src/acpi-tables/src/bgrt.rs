@@ -0,0 +1,233 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use vm_memory::{Bytes, GuestAddress, GuestMemory};
+use zerocopy::{Immutable, IntoBytes};
+
+use crate::endian::{U16, U32, U64};
+use crate::{AcpiError, Result, Sdt, SdtHeader, checksum};
+
+// The image is being displayed, in its original orientation.
+const BGRT_STATUS_DISPLAYED: u8 = 1 << 0;
+const BGRT_IMAGE_TYPE_BITMAP: u8 = 0;
+// Size of the file header and of the smallest information header of BMP images, and offsets of
+// the width and height of the image in the information header.
+const BMP_HEADERS_LENGTH: usize = 14 + 40;
+const BMP_WIDTH_OFFSET: usize = 18;
+const BMP_HEIGHT_OFFSET: usize = 22;
+
+// Dimensions, in pixels, of a BMP image. A negative height means the rows are stored top-down.
+fn bmp_dimensions(image: &[u8]) -> Result<(u32, u32)> {
+    if image.len() < BMP_HEADERS_LENGTH || !image.starts_with(b"BM") {
+        return Err(AcpiError::InvalidBgrtImage);
+    }
+    let read_dimension = |offset: usize| {
+        i32::from_le_bytes(image[offset..offset + 4].try_into().unwrap()).unsigned_abs()
+    };
+    Ok((
+        read_dimension(BMP_WIDTH_OFFSET),
+        read_dimension(BMP_HEIGHT_OFFSET),
+    ))
+}
+
+/// Boot Graphics Resource Table (BGRT)
+///
+/// This table points to the logo displayed while booting, so that the guest can keep it on screen
+/// until its own graphics take over. The image is a BMP file which must be in memory the guest
+/// can reclaim once booted. More information about this table can be found in the ACPI
+/// specification:
+/// https://uefi.org/specs/ACPI/6.5/05_ACPI_Software_Programming_Model.html#boot-graphics-resource-table-bgrt
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Bgrt {
+    header: SdtHeader,
+    version: U16,
+    status: u8,
+    image_type: u8,
+    image_address: U64,
+    image_offset_x: U32,
+    image_offset_y: U32,
+}
+
+assert_layout!(Bgrt, 56, image_address: 40);
+
+impl Bgrt {
+    /// Create a BGRT table pointing to the BMP image at `image_address`, displayed with its
+    /// upper left corner at (`offset_x`, `offset_y`) pixels on the screen.
+    pub fn new(
+        oem_id: [u8; 6],
+        oem_table_id: [u8; 8],
+        oem_revision: u32,
+        image_address: u64,
+        offset_x: u32,
+        offset_y: u32,
+    ) -> Self {
+        let header = SdtHeader::new(
+            *b"BGRT",
+            core::mem::size_of::<Bgrt>().try_into().unwrap(),
+            1,
+            oem_id,
+            oem_table_id,
+            oem_revision,
+        );
+
+        let mut bgrt = Bgrt {
+            header,
+            version: U16::new(1),
+            status: BGRT_STATUS_DISPLAYED,
+            image_type: BGRT_IMAGE_TYPE_BITMAP,
+            image_address: U64::new(image_address),
+            image_offset_x: U32::new(offset_x),
+            image_offset_y: U32::new(offset_y),
+        };
+
+        bgrt.header.checksum = checksum(&[bgrt.as_bytes()]);
+
+        bgrt
+    }
+
+    /// Create a BGRT table pointing to the BMP `image` at `image_address`, with the image
+    /// centered on a screen of `(width, height)` pixels.
+    pub fn centered(
+        oem_id: [u8; 6],
+        oem_table_id: [u8; 8],
+        oem_revision: u32,
+        image_address: u64,
+        image: &[u8],
+        screen: (u32, u32),
+    ) -> Result<Self> {
+        let (width, height) = bmp_dimensions(image)?;
+        let (screen_width, screen_height) = screen;
+        if width > screen_width || height > screen_height {
+            return Err(AcpiError::InvalidBgrtImage);
+        }
+
+        Ok(Bgrt::new(
+            oem_id,
+            oem_table_id,
+            oem_revision,
+            image_address,
+            (screen_width - width) / 2,
+            (screen_height - height) / 2,
+        ))
+    }
+
+    /// Write the BMP `image` in guest memory at `image_address`, and create a BGRT table pointing
+    /// to it, with the image centered on a screen of `(width, height)` pixels.
+    #[cfg(feature = "std")]
+    pub fn with_image<M: GuestMemory>(
+        oem_id: [u8; 6],
+        oem_table_id: [u8; 8],
+        oem_revision: u32,
+        mem: &M,
+        image_address: GuestAddress,
+        image: &[u8],
+        screen: (u32, u32),
+    ) -> Result<Self> {
+        let bgrt = Bgrt::centered(
+            oem_id,
+            oem_table_id,
+            oem_revision,
+            image_address.0,
+            image,
+            screen,
+        )?;
+        mem.write_slice(image, image_address)?;
+        Ok(bgrt)
+    }
+}
+
+impl Sdt for Bgrt {
+    fn len(&self) -> usize {
+        self.header.length.get().try_into().unwrap()
+    }
+
+    fn header(&self) -> Option<&SdtHeader> {
+        Some(&self.header)
+    }
+
+    fn to_bytes(&mut self) -> Result<Vec<u8>> {
+        Ok(self.as_bytes().to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vm_memory::GuestMemoryMmap;
+
+    use super::*;
+
+    fn bmp(width: i32, height: i32) -> Vec<u8> {
+        let mut image = vec![0u8; BMP_HEADERS_LENGTH];
+        image[..2].copy_from_slice(b"BM");
+        image[BMP_WIDTH_OFFSET..BMP_WIDTH_OFFSET + 4].copy_from_slice(&width.to_le_bytes());
+        image[BMP_HEIGHT_OFFSET..BMP_HEIGHT_OFFSET + 4].copy_from_slice(&height.to_le_bytes());
+        image
+    }
+
+    #[test]
+    fn test_bgrt() {
+        let mem: GuestMemoryMmap =
+            GuestMemoryMmap::from_ranges(&[(GuestAddress(0), 0x1000)]).unwrap();
+        let image = bmp(200, -100);
+        let mut bgrt = Bgrt::with_image(
+            *b"FOOBAR",
+            *b"FOOBARBG",
+            0,
+            &mem,
+            GuestAddress(0x800),
+            &image,
+            (1024, 768),
+        )
+        .unwrap();
+        assert_eq!(bgrt.len(), 56);
+
+        bgrt.write_to_guest(&mem, GuestAddress(0)).unwrap();
+        let mut bytes = vec![0u8; bgrt.len()];
+        mem.read_slice(&mut bytes, GuestAddress(0)).unwrap();
+        assert_eq!(checksum(&[&bytes]), 0);
+        assert_eq!(&bytes[..4], b"BGRT");
+        // Version, displayed status and BMP image type.
+        assert_eq!(&bytes[36..40], &[1, 0, 1, 0]);
+        assert_eq!(&bytes[40..48], &0x800u64.to_le_bytes());
+        assert_eq!(&bytes[48..52], &412u32.to_le_bytes());
+        assert_eq!(&bytes[52..56], &334u32.to_le_bytes());
+
+        let mut written = vec![0u8; image.len()];
+        mem.read_slice(&mut written, GuestAddress(0x800)).unwrap();
+        assert_eq!(written, image);
+    }
+
+    #[test]
+    fn test_bgrt_invalid_image() {
+        let mem: GuestMemoryMmap =
+            GuestMemoryMmap::from_ranges(&[(GuestAddress(0), 0x1000)]).unwrap();
+        let image = bmp(200, 100);
+        let mut not_bmp = image.clone();
+        not_bmp[0] = b'P';
+        for (image, screen) in [
+            (not_bmp.as_slice(), (1024, 768)),
+            (&image[..BMP_HEADERS_LENGTH - 1], (1024, 768)),
+            (image.as_slice(), (100, 100)),
+        ] {
+            assert!(matches!(
+                Bgrt::with_image(
+                    *b"FOOBAR",
+                    *b"FOOBARBG",
+                    0,
+                    &mem,
+                    GuestAddress(0),
+                    image,
+                    screen
+                ),
+                Err(AcpiError::InvalidBgrtImage)
+            ));
+        }
+    }
+}
@@ -0,0 +1,431 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Decode a raw ACPI table, e.g. one extracted from a guest, field by field.
+//!
+//! The tables the crate knows are decoded through their `serde` implementations, read from their
+//! encoding in memory. The others are shown as their header followed by a hexdump.
+
+use std::fmt;
+use std::io::Read;
+use std::mem::size_of;
+use std::process::ExitCode;
+
+use acpi_tables::aml::disasm::disassemble;
+use acpi_tables::{
+    Bert, Bgrt, Cedt, Dbg2, Dmar, Einj, Erst, Facs, Fadt, Fbpt, Fpdt, Hest, Hmat, Hpet, Iort, Ivrs,
+    Lpit, Madt, Mcfg, Nfit, Pcct, Phat, Pptt, Rsdp, Rsdt, SdtHeader, Slit, Srat, Tpm2, Wdat, Xsdt,
+};
+use serde::de::{DeserializeOwned, DeserializeSeed, SeqAccess, Visitor};
+use serde::{Deserializer, Serialize};
+use serde_json::Value;
+
+const USAGE: &str = "Usage: acpi-dump [FILE]
+
+Decode the ACPI table in FILE, or in the standard input if FILE is missing or -.";
+const RSDP_SIGNATURE: &[u8] = b"RSD PTR ";
+const HEADER_LENGTH: usize = size_of::<SdtHeader>();
+const BYTES_PER_LINE: usize = 16;
+
+/// Error decoding a table from its encoding in memory
+#[derive(Debug)]
+struct DecodeError(String);
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+impl serde::de::Error for DecodeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        DecodeError(msg.to_string())
+    }
+}
+
+/// Deserializer of the fields of a table, in order, as little-endian integers without padding
+///
+/// The trailing sequence of a table, such as its entries, spans the rest of its bytes.
+struct RawDeserializer<'de> {
+    bytes: &'de [u8],
+}
+
+impl RawDeserializer<'_> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], DecodeError> {
+        let (value, rest) = self
+            .bytes
+            .split_first_chunk::<N>()
+            .ok_or_else(|| DecodeError("the table is truncated".to_string()))?;
+        self.bytes = rest;
+        Ok(*value)
+    }
+}
+
+impl<'de> Deserializer<'de> for &mut RawDeserializer<'de> {
+    type Error = DecodeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, DecodeError> {
+        Err(DecodeError(
+            "tables are only made of unsigned integers".to_string(),
+        ))
+    }
+
+    fn deserialize_u8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DecodeError> {
+        visitor.visit_u8(u8::from_le_bytes(self.take()?))
+    }
+
+    fn deserialize_u16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DecodeError> {
+        visitor.visit_u16(u16::from_le_bytes(self.take()?))
+    }
+
+    fn deserialize_u32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DecodeError> {
+        visitor.visit_u32(u32::from_le_bytes(self.take()?))
+    }
+
+    fn deserialize_u64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DecodeError> {
+        visitor.visit_u64(u64::from_le_bytes(self.take()?))
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DecodeError> {
+        visitor.visit_seq(Elements {
+            deserializer: self,
+            remaining: None,
+        })
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, DecodeError> {
+        visitor.visit_seq(Elements {
+            deserializer: self,
+            remaining: Some(len),
+        })
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DecodeError> {
+        self.deserialize_tuple(fields.len(), visitor)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u128 f32 f64 char str string bytes byte_buf option unit
+        unit_struct newtype_struct tuple_struct map enum identifier ignored_any
+    }
+}
+
+/// Elements of an array or fields of a structure, or elements of the trailing sequence of a table
+/// if there is no fixed number of them
+struct Elements<'a, 'de> {
+    deserializer: &'a mut RawDeserializer<'de>,
+    remaining: Option<usize>,
+}
+
+impl<'de> SeqAccess<'de> for Elements<'_, 'de> {
+    type Error = DecodeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, DecodeError> {
+        match &mut self.remaining {
+            Some(0) => return Ok(None),
+            Some(remaining) => *remaining -= 1,
+            None if self.deserializer.bytes.is_empty() => return Ok(None),
+            None => {}
+        }
+        seed.deserialize(&mut *self.deserializer).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.remaining
+    }
+}
+
+/// Decode a table from its encoding in memory into the values of its fields.
+fn decode<T: DeserializeOwned + Serialize>(bytes: &[u8]) -> Result<Value, DecodeError> {
+    let mut deserializer = RawDeserializer { bytes };
+    let table = T::deserialize(&mut deserializer)?;
+    if !deserializer.bytes.is_empty() {
+        return Err(DecodeError(format!(
+            "{} trailing bytes",
+            deserializer.bytes.len()
+        )));
+    }
+    serde_json::to_value(&table).map_err(|err| DecodeError(err.to_string()))
+}
+
+/// Decode a table of fixed size, whose older revisions leave out its last fields, which are then
+/// shown as zeroes.
+fn decode_fixed<T: DeserializeOwned + Serialize>(bytes: &[u8]) -> Result<Value, DecodeError> {
+    let mut padded = bytes.to_vec();
+    if padded.len() < size_of::<T>() {
+        padded.resize(size_of::<T>(), 0);
+    }
+    decode::<T>(&padded)
+}
+
+/// Decode a table the crate knows, from its signature.
+fn decode_table(bytes: &[u8]) -> Option<Result<Value, DecodeError>> {
+    if bytes.starts_with(RSDP_SIGNATURE) {
+        return Some(decode_fixed::<Rsdp>(bytes));
+    }
+
+    let decoded = match bytes.get(..4)? {
+        b"APIC" => decode::<Madt>(bytes),
+        b"BERT" => decode::<Bert>(bytes),
+        b"BGRT" => decode::<Bgrt>(bytes),
+        b"CEDT" => decode::<Cedt>(bytes),
+        b"DBG2" => decode::<Dbg2>(bytes),
+        b"DMAR" => decode::<Dmar>(bytes),
+        b"EINJ" => decode::<Einj>(bytes),
+        b"ERST" => decode::<Erst>(bytes),
+        b"FACP" => decode_fixed::<Fadt>(bytes),
+        b"FACS" => decode::<Facs>(bytes),
+        b"FBPT" => decode::<Fbpt>(bytes),
+        b"FPDT" => decode::<Fpdt>(bytes),
+        b"HEST" => decode::<Hest>(bytes),
+        b"HMAT" => decode::<Hmat>(bytes),
+        b"HPET" => decode::<Hpet>(bytes),
+        b"IORT" => decode::<Iort>(bytes),
+        b"IVRS" => decode::<Ivrs>(bytes),
+        b"LPIT" => decode::<Lpit>(bytes),
+        b"MCFG" => decode::<Mcfg>(bytes),
+        b"NFIT" => decode::<Nfit>(bytes),
+        b"PCCT" => decode::<Pcct>(bytes),
+        b"PHAT" => decode::<Phat>(bytes),
+        b"PPTT" => decode::<Pptt>(bytes),
+        b"RSDT" => decode::<Rsdt>(bytes),
+        b"SLIT" => decode::<Slit>(bytes),
+        b"SRAT" => decode::<Srat>(bytes),
+        b"TPM2" => decode_fixed::<Tpm2>(bytes),
+        b"WDAT" => decode::<Wdat>(bytes),
+        b"XSDT" => decode::<Xsdt>(bytes),
+        _ => return None,
+    };
+    Some(decoded)
+}
+
+/// Check the length and the checksum of a table, if it has some.
+fn validate(bytes: &[u8]) -> Result<(), String> {
+    if bytes.starts_with(RSDP_SIGNATURE) {
+        return Rsdp::parse(bytes).map(drop).map_err(|err| err.to_string());
+    }
+    // Neither the FACS nor the FBPT have a checksum, so that they can be updated in place.
+    if bytes.starts_with(b"FACS") || bytes.starts_with(b"FBPT") {
+        return Ok(());
+    }
+
+    let signature = bytes
+        .first_chunk::<4>()
+        .ok_or_else(|| "the table is truncated".to_string())?;
+    SdtHeader::parse(bytes, signature)
+        .map(drop)
+        .map_err(|err| err.to_string())
+}
+
+fn printable(byte: u8) -> bool {
+    byte.is_ascii_graphic() || byte == b' '
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Print `bytes` 16 per line, after their offset from `offset` and before their characters.
+fn hexdump(bytes: &[u8], offset: usize) {
+    for (index, line) in bytes.chunks(BYTES_PER_LINE).enumerate() {
+        let text: String = line
+            .iter()
+            .map(|&byte| {
+                if printable(byte) {
+                    char::from(byte)
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+        println!(
+            "  {:04x}: {:<width$}  {text}",
+            offset + index * BYTES_PER_LINE,
+            hex(line),
+            width = 3 * BYTES_PER_LINE - 1,
+        );
+    }
+}
+
+/// Print an array of bytes, as a string if they are all printable characters.
+fn print_bytes(path: &str, bytes: &[u8]) {
+    if !bytes.is_empty() && bytes.iter().all(|&byte| printable(byte)) {
+        println!("{path}: \"{}\"", String::from_utf8_lossy(bytes));
+    } else if bytes.len() <= BYTES_PER_LINE {
+        println!("{path}: {}", hex(bytes));
+    } else {
+        println!("{path}:");
+        hexdump(bytes, 0);
+    }
+}
+
+/// Print the fields of a decoded structure, one per line, prefixed by their path in it.
+fn print_fields(path: &str, value: &Value) {
+    match value {
+        Value::Object(fields) => {
+            for (name, field) in fields {
+                if path.is_empty() {
+                    print_fields(name, field);
+                } else {
+                    print_fields(&format!("{path}.{name}"), field);
+                }
+            }
+        }
+        Value::Array(elements) => {
+            let bytes: Option<Vec<u8>> = elements
+                .iter()
+                .map(|element| element.as_u64()?.try_into().ok())
+                .collect();
+            match bytes {
+                Some(bytes) => print_bytes(path, &bytes),
+                None => {
+                    for (index, element) in elements.iter().enumerate() {
+                        print_fields(&format!("{path}[{index}]"), element);
+                    }
+                }
+            }
+        }
+        Value::Number(number) => match number.as_u64() {
+            Some(number) => println!("{path}: {number} ({number:#x})"),
+            None => println!("{path}: {number}"),
+        },
+        _ => println!("{path}: {value}"),
+    }
+}
+
+/// Print the fields of a table, or its header followed by the disassembly of its definition block
+/// or by a hexdump of the rest of it.
+fn dump(bytes: &[u8]) {
+    match decode_table(bytes) {
+        Some(Ok(table)) => {
+            print_fields("", &table);
+            return;
+        }
+        Some(Err(err)) => eprintln!("acpi-dump: cannot decode the table: {err}"),
+        None => {}
+    }
+
+    let Some((header, contents)) = bytes.split_at_checked(HEADER_LENGTH) else {
+        hexdump(bytes, 0);
+        return;
+    };
+    if let Ok(header) = decode::<SdtHeader>(header) {
+        print_fields("header", &header);
+    }
+    if matches!(&bytes[..4], b"DSDT" | b"SSDT") {
+        match disassemble(contents) {
+            Ok(asl) => {
+                print!("{asl}");
+                return;
+            }
+            Err(err) => eprintln!("acpi-dump: cannot disassemble the definition block: {err}"),
+        }
+    }
+    hexdump(contents, HEADER_LENGTH);
+}
+
+fn read_stdin() -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    std::io::stdin()
+        .read_to_end(&mut bytes)
+        .map_err(|err| format!("cannot read the standard input: {err}"))?;
+    Ok(bytes)
+}
+
+fn run() -> Result<(), String> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let bytes = match args.as_slice() {
+        [] => read_stdin()?,
+        [arg] if arg == "-h" || arg == "--help" => {
+            println!("{USAGE}");
+            return Ok(());
+        }
+        [arg] if arg == "-" => read_stdin()?,
+        [path] => std::fs::read(path).map_err(|err| format!("cannot read {path}: {err}"))?,
+        _ => return Err(USAGE.to_string()),
+    };
+
+    // Show the table even if it is invalid, as that is when it needs to be looked at.
+    let validation = validate(&bytes);
+    dump(&bytes);
+    validation.map_err(|err| format!("invalid table: {err}"))
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("acpi-dump: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use acpi_tables::Sdt;
+
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        let mut mcfg = Mcfg::new(*b"FOOBAR", *b"FOOBARMC", 0, 0xe000_0000);
+        mcfg.add_segment(0xf000_0000, 1, 0, 0xff);
+        let bytes = mcfg.to_bytes().unwrap();
+        assert!(validate(&bytes).is_ok());
+        assert_eq!(
+            decode_table(&bytes).unwrap().unwrap(),
+            serde_json::to_value(&mcfg).unwrap()
+        );
+
+        let mut xsdt = Xsdt::new(*b"FOOBAR", *b"FOOBARXS", 0, vec![0x1000]);
+        let bytes = xsdt.to_bytes().unwrap();
+        assert_eq!(
+            decode_table(&bytes).unwrap().unwrap()["tables"],
+            serde_json::json!(0x1000u64.to_le_bytes())
+        );
+        // A table of fixed size is neither truncated nor followed by more bytes.
+        let mut bert = Bert::new(*b"FOOBAR", *b"FOOBARBE", 0, 0x100, 0x1000);
+        let bytes = bert.to_bytes().unwrap();
+        assert!(decode_table(&bytes[..bytes.len() - 1]).unwrap().is_err());
+        assert!(
+            decode_table(&[bytes.as_slice(), &[0]].concat())
+                .unwrap()
+                .is_err()
+        );
+        assert!(decode_table(b"UNKNOWN").is_none());
+    }
+
+    #[test]
+    fn test_decode_legacy_rsdp() {
+        let mut rsdp = Rsdp::new_legacy(*b"FOOBAR", 0x1000);
+        let bytes = rsdp.to_bytes().unwrap();
+        assert_eq!(bytes.len(), 20);
+        assert!(validate(&bytes).is_ok());
+        let decoded = decode_table(&bytes).unwrap().unwrap();
+        assert_eq!(decoded["rsdt_addr"], 0x1000);
+        assert_eq!(decoded["xsdt_addr"], 0);
+
+        let mut corrupted = bytes.clone();
+        corrupted[9] ^= 0xff;
+        assert!(validate(&corrupted).is_err());
+    }
+}
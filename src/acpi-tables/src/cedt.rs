@@ -0,0 +1,298 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use alloc::vec::Vec;
+use core::mem::size_of;
+
+use zerocopy::{Immutable, IntoBytes};
+
+use crate::endian::{U16, U32, U64};
+use crate::{AcpiError, Result, Sdt, SdtHeader, checksum};
+
+const CEDT_CHBS: u8 = 0;
+const CEDT_CFMWS: u8 = 1;
+// Size of the register blocks of CXL 1.1 and CXL 2.0 host bridges.
+const CHBS_RCRB_LENGTH: u64 = 0x2000;
+const CHBS_CHBCR_LENGTH: u64 = 0x1_0000;
+// Fixed memory windows are aligned on, and a multiple of, 256 MiB.
+const CFMWS_WINDOW_ALIGNMENT: u64 = 0x1000_0000;
+// Interleave granularities, in bytes, of the host bridges of a window.
+const CFMWS_MIN_GRANULARITY: u32 = 256;
+const CFMWS_MAX_GRANULARITY: u32 = 16 * 1024;
+
+/// The window can hold device coherent memory, of CXL type 2 devices.
+pub const CFMWS_RESTRICTION_TYPE2: u16 = 1 << 0;
+/// The window can hold host-only coherent memory, of CXL type 3 devices.
+pub const CFMWS_RESTRICTION_TYPE3: u16 = 1 << 1;
+/// The window can hold volatile memory.
+pub const CFMWS_RESTRICTION_VOLATILE: u16 = 1 << 2;
+/// The window can hold persistent memory.
+pub const CFMWS_RESTRICTION_PERSISTENT: u16 = 1 << 3;
+/// The configuration of the window cannot be changed.
+pub const CFMWS_RESTRICTION_FIXED: u16 = 1 << 4;
+
+/// Version of the CXL specification a host bridge complies with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u32)]
+pub enum ChbsVersion {
+    /// Restricted CXL host, whose registers are in a Root Complex Register Block.
+    Cxl11 = 0,
+    /// CXL 2.0 host bridge, whose registers are in a Host Bridge Component Register block.
+    Cxl20 = 1,
+}
+
+// clippy doesn't understand that we actually "use" the fields of this struct when we serialize
+// them as bytes in guest memory, so here we just ignore dead code to avoid having to name
+// everything with an underscore prefix
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Copy, Clone, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Chbs {
+    r#type: u8,
+    _reserved1: u8,
+    length: U16,
+    uid: U32,
+    cxl_version: U32,
+    _reserved2: U32,
+    base: U64,
+    base_length: U64,
+}
+
+assert_layout!(Chbs, 32);
+
+impl Chbs {
+    /// Create a CXL Host Bridge Structure for the host bridge whose `_UID` in the ACPI namespace
+    /// is `uid`, with its register block at `base`.
+    pub fn new(uid: u32, cxl_version: ChbsVersion, base: u64) -> Self {
+        let base_length = match cxl_version {
+            ChbsVersion::Cxl11 => CHBS_RCRB_LENGTH,
+            ChbsVersion::Cxl20 => CHBS_CHBCR_LENGTH,
+        };
+        Chbs {
+            r#type: CEDT_CHBS,
+            _reserved1: 0,
+            length: U16::new(size_of::<Self>().try_into().unwrap()),
+            uid: U32::new(uid),
+            cxl_version: U32::new(cxl_version as u32),
+            _reserved2: U32::ZERO,
+            base: U64::new(base),
+            base_length: U64::new(base_length),
+        }
+    }
+}
+
+// clippy doesn't understand that we actually "use" the fields of this struct when we serialize
+// them as bytes in guest memory, so here we just ignore dead code to avoid having to name
+// everything with an underscore prefix
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Copy, Clone, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct CfmwsHeader {
+    r#type: u8,
+    _reserved1: u8,
+    length: U16,
+    _reserved2: U32,
+    base: U64,
+    size: U64,
+    interleave_ways: u8,
+    interleave_arithmetic: u8,
+    _reserved3: U16,
+    granularity: U32,
+    restrictions: U16,
+    qtg_id: U16,
+}
+
+assert_layout!(CfmwsHeader, 36);
+
+/// CXL Fixed Memory Window Structure (CFMWS)
+///
+/// This structure describes a window of the physical address space the memory of CXL devices is
+/// mapped in, interleaved across the host bridges it targets.
+#[derive(Clone, Debug)]
+pub struct Cfmws {
+    header: CfmwsHeader,
+    targets: Vec<U32>,
+}
+
+impl Cfmws {
+    /// Create a structure describing the window of `size` bytes at `base`, interleaved across the
+    /// host bridges whose `_UID` are `targets`, every `granularity` bytes.
+    ///
+    /// The window must be aligned on 256 MiB, there must be 1, 2, 4, 8 or 16 targets, or 3, 6 or
+    /// 12, and the granularity must be a power of 2 from 256 bytes to 16 KiB. `restrictions` is a
+    /// combination of the `CFMWS_RESTRICTION_*` flags.
+    pub fn new(
+        base: u64,
+        size: u64,
+        targets: &[u32],
+        granularity: u32,
+        restrictions: u16,
+        qtg_id: u16,
+    ) -> Result<Self> {
+        if size == 0
+            || !base.is_multiple_of(CFMWS_WINDOW_ALIGNMENT)
+            || !size.is_multiple_of(CFMWS_WINDOW_ALIGNMENT)
+        {
+            return Err(AcpiError::InvalidCfmwsWindow);
+        }
+        // Interleave ways are encoded as their base 2 logarithm, plus 8 for multiples of 3.
+        let interleave_ways = match targets.len() {
+            ways @ (1 | 2 | 4 | 8 | 16) => ways.trailing_zeros(),
+            ways @ (3 | 6 | 12) => 8 + (ways / 3).trailing_zeros(),
+            ways => return Err(AcpiError::InvalidCfmwsTargets(ways)),
+        };
+        if !granularity.is_power_of_two()
+            || !(CFMWS_MIN_GRANULARITY..=CFMWS_MAX_GRANULARITY).contains(&granularity)
+        {
+            return Err(AcpiError::InvalidCfmwsGranularity(granularity));
+        }
+        // Granularities are encoded as their base 2 logarithm, minus 8.
+        let granularity = granularity.trailing_zeros() - CFMWS_MIN_GRANULARITY.trailing_zeros();
+
+        let length = size_of::<CfmwsHeader>() + targets.len() * size_of::<U32>();
+        let header = CfmwsHeader {
+            r#type: CEDT_CFMWS,
+            length: U16::new(length.try_into().unwrap()),
+            base: U64::new(base),
+            size: U64::new(size),
+            interleave_ways: interleave_ways.try_into().unwrap(),
+            // Standard modulo arithmetic.
+            interleave_arithmetic: 0,
+            granularity: U32::new(granularity),
+            restrictions: U16::new(restrictions),
+            qtg_id: U16::new(qtg_id),
+            ..Default::default()
+        };
+        Ok(Cfmws {
+            header,
+            targets: targets.iter().copied().map(U32::new).collect(),
+        })
+    }
+
+    /// Serialize the structure, to be appended to the structures of a [`Cedt`].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        [self.header.as_bytes(), self.targets.as_bytes()].concat()
+    }
+}
+
+/// CXL Early Discovery Table (CEDT)
+///
+/// This table describes the CXL host bridges of the platform and the fixed memory windows the
+/// memory of CXL devices can be mapped in, which the guest needs before it can enumerate the CXL
+/// hierarchy. More information about this table can be found in the CXL specification:
+/// https://computeexpresslink.org/cxl-specification/
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Cedt {
+    header: SdtHeader,
+    structures: Vec<u8>,
+}
+
+impl Cedt {
+    /// Create a CEDT table holding the given CHBS and CFMWS structures.
+    pub fn new(
+        oem_id: [u8; 6],
+        oem_table_id: [u8; 8],
+        oem_revision: u32,
+        structures: Vec<u8>,
+    ) -> Self {
+        let length = size_of::<SdtHeader>() + structures.len();
+        let mut header = SdtHeader::new(
+            *b"CEDT",
+            length.try_into().unwrap(),
+            1,
+            oem_id,
+            oem_table_id,
+            oem_revision,
+        );
+        header.checksum = checksum(&[header.as_bytes(), structures.as_bytes()]);
+
+        Cedt { header, structures }
+    }
+}
+
+impl Sdt for Cedt {
+    fn len(&self) -> usize {
+        self.header.length.get().try_into().unwrap()
+    }
+
+    fn header(&self) -> Option<&SdtHeader> {
+        Some(&self.header)
+    }
+
+    fn to_bytes(&mut self) -> Result<Vec<u8>> {
+        Ok([self.header.as_bytes(), self.structures.as_bytes()].concat())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vm_memory::{Bytes, GuestAddress, GuestMemoryMmap};
+
+    use super::*;
+
+    #[test]
+    fn test_cedt() {
+        let chbs = Chbs::new(0, ChbsVersion::Cxl20, 0x1_0000_0000);
+        let cfmws = Cfmws::new(
+            0x10_0000_0000,
+            0x4000_0000,
+            &[0, 1, 2],
+            4096,
+            CFMWS_RESTRICTION_TYPE3 | CFMWS_RESTRICTION_VOLATILE,
+            0,
+        )
+        .unwrap();
+        let structures = [chbs.as_bytes().to_vec(), cfmws.to_bytes()].concat();
+        let mut cedt = Cedt::new(*b"FOOBAR", *b"FOOBARCE", 0, structures);
+        assert_eq!(cedt.len(), 36 + 32 + 36 + 12);
+
+        let mem: GuestMemoryMmap =
+            GuestMemoryMmap::from_ranges(&[(GuestAddress(0), 0x1000)]).unwrap();
+        cedt.write_to_guest(&mem, GuestAddress(0)).unwrap();
+        let mut bytes = vec![0u8; cedt.len()];
+        mem.read_slice(&mut bytes, GuestAddress(0)).unwrap();
+        assert_eq!(checksum(&[&bytes]), 0);
+        assert_eq!(&bytes[..4], b"CEDT");
+
+        let chbs = &bytes[36..68];
+        assert_eq!(&chbs[..4], &[0, 0, 32, 0]);
+        // UID and CXL 2.0 host bridge.
+        assert_eq!(&chbs[4..12], &[0, 0, 0, 0, 1, 0, 0, 0]);
+        assert_eq!(&chbs[16..24], &0x1_0000_0000u64.to_le_bytes());
+        assert_eq!(&chbs[24..32], &0x1_0000u64.to_le_bytes());
+
+        let cfmws = &bytes[68..];
+        assert_eq!(&cfmws[..4], &[1, 0, 48, 0]);
+        assert_eq!(&cfmws[8..16], &0x10_0000_0000u64.to_le_bytes());
+        assert_eq!(&cfmws[16..24], &0x4000_0000u64.to_le_bytes());
+        // Three interleave ways, with a granularity of 4 KiB.
+        assert_eq!(cfmws[24], 8);
+        assert_eq!(&cfmws[28..32], &4u32.to_le_bytes());
+        assert_eq!(&cfmws[32..36], &[0x6, 0, 0, 0]);
+        assert_eq!(&cfmws[36..40], &0u32.to_le_bytes());
+        assert_eq!(&cfmws[44..48], &2u32.to_le_bytes());
+    }
+
+    #[test]
+    fn test_cfmws_validation() {
+        assert!(matches!(
+            Cfmws::new(0x1000, 0x1000_0000, &[0], 256, 0, 0),
+            Err(AcpiError::InvalidCfmwsWindow)
+        ));
+        assert!(matches!(
+            Cfmws::new(0x1000_0000, 0x1000_0000, &[0; 5], 256, 0, 0),
+            Err(AcpiError::InvalidCfmwsTargets(5))
+        ));
+        assert!(matches!(
+            Cfmws::new(0x1000_0000, 0x1000_0000, &[0, 1], 128, 0, 0),
+            Err(AcpiError::InvalidCfmwsGranularity(128))
+        ));
+        let cfmws = Cfmws::new(0x1000_0000, 0x1000_0000, &[0; 16], 16384, 0, 0).unwrap();
+        assert_eq!(cfmws.header.interleave_ways, 4);
+        assert_eq!(cfmws.header.granularity.get(), 6);
+    }
+}
@@ -0,0 +1,260 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use alloc::vec::Vec;
+use core::mem::size_of;
+
+use zerocopy::{Immutable, IntoBytes};
+
+use crate::endian::{U16, U32};
+use crate::{AcpiError, GenericAddressStructure, Result, Sdt, SdtHeader, checksum};
+
+// Port type of serial debug devices.
+const DBG2_PORT_TYPE_SERIAL: u16 = 0x8000;
+// Namespace string of debug devices which are not described in the ACPI namespace.
+const DBG2_NO_NAMESPACE: &str = ".";
+
+/// Subtype of a serial debug device.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u16)]
+pub enum Dbg2SerialSubtype {
+    /// Fully 16550-compatible UART.
+    Full16550 = 0x0000,
+    /// ARM PL011 UART.
+    ArmPl011 = 0x0003,
+}
+
+// clippy doesn't understand that we actually "use" the fields of this struct when we serialize
+// them as bytes in guest memory, so here we just ignore dead code to avoid having to name
+// everything with an underscore prefix
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct DebugDeviceInfoHeader {
+    revision: u8,
+    length: U16,
+    address_count: u8,
+    namespace_length: U16,
+    namespace_offset: U16,
+    oem_data_length: U16,
+    oem_data_offset: U16,
+    port_type: U16,
+    port_subtype: U16,
+    _reserved: U16,
+    base_address_offset: U16,
+    address_size_offset: U16,
+}
+
+assert_layout!(DebugDeviceInfoHeader, 22);
+
+/// Debug Device Information structure
+///
+/// This structure describes a debug port through the registers it is made of and, if it has one,
+/// the path of its device in the ACPI namespace.
+#[derive(Clone, Debug)]
+pub struct DebugDeviceInfo {
+    header: DebugDeviceInfoHeader,
+    base_addresses: Vec<GenericAddressStructure>,
+    address_sizes: Vec<U32>,
+    namespace: Vec<u8>,
+}
+
+impl DebugDeviceInfo {
+    /// Create a structure describing a serial port of the given `subtype`, made of the `(address,
+    /// size)` register `regions`, whose device is at `namespace`, such as `\_SB.COM1`, in the ACPI
+    /// namespace. An empty `namespace` means the device is not described there.
+    pub fn new(
+        subtype: Dbg2SerialSubtype,
+        regions: &[(GenericAddressStructure, u32)],
+        namespace: &str,
+    ) -> Result<Self> {
+        let namespace = if namespace.is_empty() {
+            DBG2_NO_NAMESPACE
+        } else {
+            namespace
+        };
+        if !namespace.bytes().all(|byte| byte.is_ascii_graphic()) {
+            return Err(AcpiError::InvalidDbg2Namespace);
+        }
+        let mut namespace = namespace.as_bytes().to_vec();
+        namespace.push(0);
+
+        let base_address_offset = size_of::<DebugDeviceInfoHeader>();
+        let address_size_offset =
+            base_address_offset + regions.len() * size_of::<GenericAddressStructure>();
+        let namespace_offset = address_size_offset + regions.len() * size_of::<U32>();
+        let length = namespace_offset + namespace.len();
+        let to_u16 =
+            |value: usize| u16::try_from(value).map_err(|_| AcpiError::InvalidDbg2DeviceLength);
+
+        let header = DebugDeviceInfoHeader {
+            revision: 0,
+            length: U16::new(to_u16(length)?),
+            address_count: regions
+                .len()
+                .try_into()
+                .map_err(|_| AcpiError::InvalidDbg2DeviceLength)?,
+            namespace_length: U16::new(to_u16(namespace.len())?),
+            namespace_offset: U16::new(to_u16(namespace_offset)?),
+            oem_data_length: U16::ZERO,
+            oem_data_offset: U16::ZERO,
+            port_type: U16::new(DBG2_PORT_TYPE_SERIAL),
+            port_subtype: U16::new(subtype as u16),
+            _reserved: U16::ZERO,
+            base_address_offset: U16::new(to_u16(base_address_offset)?),
+            address_size_offset: U16::new(to_u16(address_size_offset)?),
+        };
+        Ok(DebugDeviceInfo {
+            header,
+            base_addresses: regions.iter().map(|(address, _)| *address).collect(),
+            address_sizes: regions.iter().map(|(_, size)| U32::new(*size)).collect(),
+            namespace,
+        })
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        [
+            self.header.as_bytes(),
+            self.base_addresses.as_bytes(),
+            self.address_sizes.as_bytes(),
+            &self.namespace,
+        ]
+        .concat()
+    }
+}
+
+// clippy doesn't understand that we actually "use" the fields of this struct when we serialize
+// them as bytes in guest memory, so here we just ignore dead code to avoid having to name
+// everything with an underscore prefix
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Clone, Debug, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Dbg2Header {
+    sdt: SdtHeader,
+    devices_offset: U32,
+    devices_count: U32,
+}
+
+assert_layout!(Dbg2Header, 44);
+
+/// Debug Port Table 2 (DBG2)
+///
+/// This table lists the debug ports of the platform, which Windows uses for kernel debugging and
+/// Linux for its early console. More information about this table can be found in the Microsoft
+/// specification:
+/// https://learn.microsoft.com/en-us/windows-hardware/drivers/bringup/acpi-debug-port-table
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Dbg2 {
+    header: Dbg2Header,
+    devices: Vec<u8>,
+}
+
+impl Dbg2 {
+    /// Create a DBG2 table describing the given debug devices.
+    pub fn new(
+        oem_id: [u8; 6],
+        oem_table_id: [u8; 8],
+        oem_revision: u32,
+        devices: &[DebugDeviceInfo],
+    ) -> Self {
+        let devices_count = devices.len();
+        let devices: Vec<u8> = devices.iter().flat_map(DebugDeviceInfo::to_bytes).collect();
+        let length = size_of::<Dbg2Header>() + devices.len();
+        let sdt_header = SdtHeader::new(
+            *b"DBG2",
+            length.try_into().unwrap(),
+            0,
+            oem_id,
+            oem_table_id,
+            oem_revision,
+        );
+
+        let mut header = Dbg2Header {
+            sdt: sdt_header,
+            devices_offset: U32::new(size_of::<Dbg2Header>().try_into().unwrap()),
+            devices_count: U32::new(devices_count.try_into().unwrap()),
+        };
+        header.sdt.checksum = checksum(&[header.as_bytes(), devices.as_bytes()]);
+
+        Dbg2 { header, devices }
+    }
+}
+
+impl Sdt for Dbg2 {
+    fn len(&self) -> usize {
+        self.header.sdt.length.get().try_into().unwrap()
+    }
+
+    fn header(&self) -> Option<&SdtHeader> {
+        Some(&self.header.sdt)
+    }
+
+    fn to_bytes(&mut self) -> Result<Vec<u8>> {
+        Ok([self.header.as_bytes(), self.devices.as_bytes()].concat())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vm_memory::{Bytes, GuestAddress, GuestMemoryMmap};
+
+    use super::*;
+
+    #[test]
+    fn test_dbg2() {
+        let uart = GenericAddressStructure::from_raw(1, 8, 0, 1, 0x3f8);
+        let com1 =
+            DebugDeviceInfo::new(Dbg2SerialSubtype::Full16550, &[(uart, 8)], "\\_SB.COM1").unwrap();
+        let pl011 = GenericAddressStructure::from_raw(0, 32, 0, 3, 0x900_0000);
+        let console =
+            DebugDeviceInfo::new(Dbg2SerialSubtype::ArmPl011, &[(pl011, 0x1000)], "").unwrap();
+        let mut dbg2 = Dbg2::new(*b"FOOBAR", *b"FOOBARDB", 0, &[com1, console]);
+        assert_eq!(dbg2.len(), 44 + (22 + 16 + 10) + (22 + 16 + 2));
+
+        let mem: GuestMemoryMmap =
+            GuestMemoryMmap::from_ranges(&[(GuestAddress(0), 0x1000)]).unwrap();
+        dbg2.write_to_guest(&mem, GuestAddress(0)).unwrap();
+        let mut bytes = vec![0u8; dbg2.len()];
+        mem.read_slice(&mut bytes, GuestAddress(0)).unwrap();
+        assert_eq!(checksum(&[&bytes]), 0);
+        assert_eq!(&bytes[..4], b"DBG2");
+        assert_eq!(&bytes[36..40], &44u32.to_le_bytes());
+        assert_eq!(&bytes[40..44], &2u32.to_le_bytes());
+
+        let com1 = &bytes[44..92];
+        // Revision, length and number of registers.
+        assert_eq!(&com1[..4], &[0, 48, 0, 1]);
+        // Namespace string length and offset, no OEM data.
+        assert_eq!(&com1[4..12], &[10, 0, 38, 0, 0, 0, 0, 0]);
+        // Serial port, 16550 subtype.
+        assert_eq!(&com1[12..16], &[0x00, 0x80, 0x00, 0x00]);
+        // Offsets of the base addresses and of their sizes.
+        assert_eq!(&com1[18..22], &[22, 0, 34, 0]);
+        assert_eq!(&com1[22..34], uart.as_bytes());
+        assert_eq!(&com1[34..38], &8u32.to_le_bytes());
+        assert_eq!(&com1[38..], b"\\_SB.COM1\0");
+
+        let console = &bytes[92..];
+        assert_eq!(&console[1..3], &40u16.to_le_bytes());
+        assert_eq!(&console[14..16], &3u16.to_le_bytes());
+        assert_eq!(&console[38..], b".\0");
+    }
+
+    #[test]
+    fn test_dbg2_validation() {
+        let uart = GenericAddressStructure::from_raw(1, 8, 0, 1, 0x3f8);
+        assert!(matches!(
+            DebugDeviceInfo::new(Dbg2SerialSubtype::Full16550, &[(uart, 8)], "COM 1"),
+            Err(AcpiError::InvalidDbg2Namespace)
+        ));
+        let name = "A".repeat(usize::from(u16::MAX));
+        assert!(matches!(
+            DebugDeviceInfo::new(Dbg2SerialSubtype::Full16550, &[(uart, 8)], &name),
+            Err(AcpiError::InvalidDbg2DeviceLength)
+        ));
+    }
+}
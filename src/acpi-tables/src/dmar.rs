@@ -0,0 +1,523 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use alloc::vec::Vec;
+use core::mem::size_of;
+
+use zerocopy::{Immutable, IntoBytes};
+
+use crate::endian::{U16, U64};
+use crate::{AcpiError, Result, Sdt, SdtHeader, checksum};
+
+/// The platform supports interrupt remapping.
+pub const DMAR_FLAG_INTR_REMAP: u8 = 1 << 0;
+/// The platform asks the guest not to enable x2APIC mode.
+pub const DMAR_FLAG_X2APIC_OPT_OUT: u8 = 1 << 1;
+/// The platform asks the guest to keep DMA remapping on for the devices it initialized.
+pub const DMAR_FLAG_DMA_CTRL_PLATFORM_OPT_IN: u8 = 1 << 2;
+
+// Remapping structure types.
+const DMAR_TYPE_DRHD: u16 = 0;
+const DMAR_TYPE_RMRR: u16 = 1;
+const DMAR_TYPE_ATSR: u16 = 2;
+// The hardware unit covers all the PCI devices of its segment which no other unit covers.
+const DRHD_FLAG_INCLUDE_PCI_ALL: u8 = 1 << 0;
+// All the root ports of the segment support ATS transactions.
+const ATSR_FLAG_ALL_PORTS: u8 = 1 << 0;
+// Remapped memory is made of 4 KiB pages.
+const DMAR_PAGE_SIZE: u64 = 0x1000;
+// Size of a device scope entry without its path.
+const DEVICE_SCOPE_HEADER_LENGTH: usize = 6;
+
+/// Type of the device of a device scope entry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum DmarDeviceScopeType {
+    /// PCI endpoint device.
+    PciEndpoint = 1,
+    /// PCI-PCI bridge, along with the whole hierarchy below it.
+    PciSubHierarchy = 2,
+    /// I/O APIC, whose enumeration ID is its ID.
+    IoApic = 3,
+    /// MSI capable HPET, whose enumeration ID is its number.
+    MsiCapableHpet = 4,
+}
+
+/// Device scope entry of a remapping structure
+///
+/// Device scope entries tell the guest which devices a remapping structure applies to, through
+/// the path to the device in the PCI hierarchy, as `(device, function)` pairs walked from the
+/// bus `start_bus`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DmarDeviceScope {
+    r#type: DmarDeviceScopeType,
+    enumeration_id: u8,
+    start_bus: u8,
+    path: Vec<(u8, u8)>,
+}
+
+impl DmarDeviceScope {
+    /// Create an entry for the device of the given type at the end of `path`, starting from the
+    /// bus `start_bus`. `enumeration_id` is the ID of I/O APICs and the number of HPETs, and 0
+    /// for PCI devices.
+    pub fn new(
+        r#type: DmarDeviceScopeType,
+        enumeration_id: u8,
+        start_bus: u8,
+        path: &[(u8, u8)],
+    ) -> Self {
+        DmarDeviceScope {
+            r#type,
+            enumeration_id,
+            start_bus,
+            path: path.to_vec(),
+        }
+    }
+
+    /// Create an entry for the PCI endpoint at `device`.`function` on the bus `bus`.
+    pub fn pci_endpoint(bus: u8, device: u8, function: u8) -> Self {
+        Self::new(
+            DmarDeviceScopeType::PciEndpoint,
+            0,
+            bus,
+            &[(device, function)],
+        )
+    }
+
+    fn encode(&self, bytes: &mut Vec<u8>) -> Result<()> {
+        if self.path.is_empty() {
+            return Err(AcpiError::InvalidDmarDeviceScope);
+        }
+        let length = DEVICE_SCOPE_HEADER_LENGTH + 2 * self.path.len();
+        let length: u8 = length
+            .try_into()
+            .map_err(|_| AcpiError::InvalidDmarDeviceScope)?;
+
+        bytes.extend_from_slice(&[
+            self.r#type as u8,
+            length,
+            0,
+            0,
+            self.enumeration_id,
+            self.start_bus,
+        ]);
+        for &(device, function) in &self.path {
+            bytes.extend_from_slice(&[device, function]);
+        }
+        Ok(())
+    }
+}
+
+// Encode a remapping structure, made of its fixed part followed by its device scope entries, and
+// set its length, which is always at the same offset.
+fn encode_structure(header: &[u8], devices: &[DmarDeviceScope]) -> Result<Vec<u8>> {
+    let mut bytes = header.to_vec();
+    for device in devices {
+        device.encode(&mut bytes)?;
+    }
+    let length: u16 = bytes
+        .len()
+        .try_into()
+        .map_err(|_| AcpiError::InvalidDmarStructureLength)?;
+    bytes[2..4].copy_from_slice(&length.to_le_bytes());
+    Ok(bytes)
+}
+
+// clippy doesn't understand that we actually "use" the fields of this struct when we serialize
+// them as bytes in guest memory, so here we just ignore dead code to avoid having to name
+// everything with an underscore prefix
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct DrhdHeader {
+    r#type: U16,
+    length: U16,
+    flags: u8,
+    size: u8,
+    segment: U16,
+    register_base_address: U64,
+}
+
+assert_layout!(DrhdHeader, 16);
+
+/// DMA Remapping Hardware Unit Definition structure (DRHD)
+///
+/// This structure describes a remapping hardware unit of the PCI segment `segment`, along with
+/// the devices whose DMA requests it translates.
+#[derive(Clone, Debug)]
+pub struct Drhd {
+    header: DrhdHeader,
+    devices: Vec<DmarDeviceScope>,
+}
+
+impl Drhd {
+    /// Create a structure describing a remapping hardware unit, with no device, whose registers
+    /// are at `register_base_address`.
+    pub fn new(segment: u16, register_base_address: u64) -> Self {
+        Drhd {
+            header: DrhdHeader {
+                r#type: U16::new(DMAR_TYPE_DRHD),
+                segment: U16::new(segment),
+                register_base_address: U64::new(register_base_address),
+                ..Default::default()
+            },
+            devices: Vec::new(),
+        }
+    }
+
+    /// Make the unit cover all the PCI devices of its segment which no other unit covers. Its
+    /// device scope entries then only list the I/O APICs and HPETs it covers.
+    pub fn include_pci_all(mut self) -> Self {
+        self.header.flags |= DRHD_FLAG_INCLUDE_PCI_ALL;
+        self
+    }
+
+    /// Set the size of the register set of the unit, as a power of 2 of 4 KiB pages.
+    pub fn size(mut self, size: u8) -> Self {
+        self.header.size = size;
+        self
+    }
+
+    /// Add a device scope entry to the structure.
+    pub fn device(mut self, device: DmarDeviceScope) -> Self {
+        self.devices.push(device);
+        self
+    }
+
+    fn includes_pci_all(&self) -> bool {
+        self.header.flags & DRHD_FLAG_INCLUDE_PCI_ALL != 0
+    }
+
+    fn to_bytes(&self) -> Result<Vec<u8>> {
+        encode_structure(self.header.as_bytes(), &self.devices)
+    }
+}
+
+// clippy doesn't understand that we actually "use" the fields of this struct when we serialize
+// them as bytes in guest memory, so here we just ignore dead code to avoid having to name
+// everything with an underscore prefix
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct RmrrHeader {
+    r#type: U16,
+    length: U16,
+    _reserved: U16,
+    segment: U16,
+    base_address: U64,
+    limit_address: U64,
+}
+
+assert_layout!(RmrrHeader, 24);
+
+/// Reserved Memory Region Reporting structure (RMRR)
+///
+/// This structure describes a region of memory which devices of the PCI segment `segment` keep
+/// accessing through DMA, such as the framebuffer of a passed-through GPU, so that the guest keeps
+/// it identity mapped for them.
+#[derive(Clone, Debug)]
+pub struct Rmrr {
+    header: RmrrHeader,
+    devices: Vec<DmarDeviceScope>,
+}
+
+impl Rmrr {
+    /// Create a structure describing the inclusive region of memory from `base_address` to
+    /// `limit_address`, with no device.
+    pub fn new(segment: u16, base_address: u64, limit_address: u64) -> Self {
+        Rmrr {
+            header: RmrrHeader {
+                r#type: U16::new(DMAR_TYPE_RMRR),
+                segment: U16::new(segment),
+                base_address: U64::new(base_address),
+                limit_address: U64::new(limit_address),
+                ..Default::default()
+            },
+            devices: Vec::new(),
+        }
+    }
+
+    /// Add a device scope entry to the structure.
+    pub fn device(mut self, device: DmarDeviceScope) -> Self {
+        self.devices.push(device);
+        self
+    }
+
+    fn to_bytes(&self) -> Result<Vec<u8>> {
+        let base = self.header.base_address.get();
+        let limit = self.header.limit_address.get();
+        // The region must be made of whole pages.
+        if limit < base
+            || !base.is_multiple_of(DMAR_PAGE_SIZE)
+            || !limit.wrapping_add(1).is_multiple_of(DMAR_PAGE_SIZE)
+        {
+            return Err(AcpiError::InvalidDmarReservedRegion(base, limit));
+        }
+        encode_structure(self.header.as_bytes(), &self.devices)
+    }
+}
+
+// clippy doesn't understand that we actually "use" the fields of this struct when we serialize
+// them as bytes in guest memory, so here we just ignore dead code to avoid having to name
+// everything with an underscore prefix
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct AtsrHeader {
+    r#type: U16,
+    length: U16,
+    flags: u8,
+    _reserved: u8,
+    segment: U16,
+}
+
+assert_layout!(AtsrHeader, 8);
+
+/// Root Port ATS Capability Reporting structure (ATSR)
+///
+/// This structure lists the PCI root ports of the segment `segment` which support Address
+/// Translation Services, so that the guest lets the devices below them use ATS.
+#[derive(Clone, Debug)]
+pub struct Atsr {
+    header: AtsrHeader,
+    devices: Vec<DmarDeviceScope>,
+}
+
+impl Atsr {
+    /// Create a structure with no root port.
+    pub fn new(segment: u16) -> Self {
+        Atsr {
+            header: AtsrHeader {
+                r#type: U16::new(DMAR_TYPE_ATSR),
+                segment: U16::new(segment),
+                ..Default::default()
+            },
+            devices: Vec::new(),
+        }
+    }
+
+    /// Report that all the root ports of the segment support ATS, in which case the structure
+    /// lists no root port.
+    pub fn all_ports(mut self) -> Self {
+        self.header.flags |= ATSR_FLAG_ALL_PORTS;
+        self
+    }
+
+    /// Add the device scope entry of a root port, of type
+    /// [`DmarDeviceScopeType::PciSubHierarchy`], to the structure.
+    pub fn device(mut self, device: DmarDeviceScope) -> Self {
+        self.devices.push(device);
+        self
+    }
+
+    fn to_bytes(&self) -> Result<Vec<u8>> {
+        encode_structure(self.header.as_bytes(), &self.devices)
+    }
+}
+
+// clippy doesn't understand that we actually "use" the fields of this struct when we serialize
+// them as bytes in guest memory, so here we just ignore dead code to avoid having to name
+// everything with an underscore prefix
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Clone, Debug, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct DmarHeader {
+    sdt: SdtHeader,
+    host_address_width: u8,
+    flags: u8,
+    _reserved: [u8; 10],
+}
+
+assert_layout!(DmarHeader, 48);
+
+/// DMA Remapping Reporting table (DMAR)
+///
+/// This table describes the Intel VT-d remapping hardware units of the platform and the devices
+/// behind them. More information about this table can be found in the Intel Virtualization
+/// Technology for Directed I/O specification:
+/// https://www.intel.com/content/www/us/en/content-details/774206/intel-virtualization-technology-for-directed-i-o-architecture-specification.html
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Dmar {
+    header: DmarHeader,
+    structures: Vec<u8>,
+}
+
+impl Sdt for Dmar {
+    fn len(&self) -> usize {
+        self.header.sdt.length.get().try_into().unwrap()
+    }
+
+    fn header(&self) -> Option<&SdtHeader> {
+        Some(&self.header.sdt)
+    }
+
+    fn to_bytes(&mut self) -> Result<Vec<u8>> {
+        Ok([self.header.as_bytes(), self.structures.as_bytes()].concat())
+    }
+}
+
+/// Builder of a [`Dmar`], one remapping structure at a time
+#[derive(Clone, Debug)]
+pub struct DmarBuilder {
+    host_address_width: u8,
+    flags: u8,
+    drhds: Vec<Drhd>,
+    rmrrs: Vec<Rmrr>,
+    atsrs: Vec<Atsr>,
+}
+
+impl DmarBuilder {
+    /// Create a builder with no remapping structure, for remapping hardware units translating
+    /// DMA requests to `host_address_width` bits wide addresses.
+    pub fn new(host_address_width: u8) -> Self {
+        DmarBuilder {
+            host_address_width,
+            flags: 0,
+            drhds: Vec::new(),
+            rmrrs: Vec::new(),
+            atsrs: Vec::new(),
+        }
+    }
+
+    /// Set the flags of the table, such as [`DMAR_FLAG_INTR_REMAP`].
+    pub fn flags(mut self, flags: u8) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    /// Add a DRHD structure.
+    pub fn drhd(mut self, drhd: Drhd) -> Self {
+        self.drhds.push(drhd);
+        self
+    }
+
+    /// Add an RMRR structure.
+    pub fn rmrr(mut self, rmrr: Rmrr) -> Self {
+        self.rmrrs.push(rmrr);
+        self
+    }
+
+    /// Add an ATSR structure.
+    pub fn atsr(mut self, atsr: Atsr) -> Self {
+        self.atsrs.push(atsr);
+        self
+    }
+
+    /// Build the DMAR table, computing the length of every remapping structure and encoding its
+    /// device scope entries.
+    ///
+    /// The structures are listed by type, as the specification requires, with the DRHDs including
+    /// all the PCI devices of their segment after the other ones.
+    pub fn build(
+        mut self,
+        oem_id: [u8; 6],
+        oem_table_id: [u8; 8],
+        oem_revision: u32,
+    ) -> Result<Dmar> {
+        self.drhds.sort_by_key(Drhd::includes_pci_all);
+        let mut structures = Vec::new();
+        for drhd in &self.drhds {
+            structures.extend(drhd.to_bytes()?);
+        }
+        for rmrr in &self.rmrrs {
+            structures.extend(rmrr.to_bytes()?);
+        }
+        for atsr in &self.atsrs {
+            structures.extend(atsr.to_bytes()?);
+        }
+
+        let length = size_of::<DmarHeader>() + structures.len();
+        let sdt_header = SdtHeader::new(
+            *b"DMAR",
+            length.try_into().unwrap(),
+            1,
+            oem_id,
+            oem_table_id,
+            oem_revision,
+        );
+
+        let mut header = DmarHeader {
+            sdt: sdt_header,
+            // The width is reported minus one.
+            host_address_width: self.host_address_width.saturating_sub(1),
+            flags: self.flags,
+            _reserved: [0; 10],
+        };
+        header.sdt.checksum = checksum(&[header.as_bytes(), structures.as_bytes()]);
+
+        Ok(Dmar { header, structures })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vm_memory::{Bytes, GuestAddress, GuestMemoryMmap};
+
+    use super::*;
+
+    #[test]
+    fn test_dmar() {
+        let io_apic = DmarDeviceScope::new(DmarDeviceScopeType::IoApic, 0x21, 0xf0, &[(0x1f, 0)]);
+        let catch_all = Drhd::new(0, 0xfed9_0000).include_pci_all().device(io_apic);
+        let gpu = Drhd::new(0, 0xfed9_1000).device(DmarDeviceScope::pci_endpoint(0, 2, 0));
+        let framebuffer =
+            Rmrr::new(0, 0x7c00_0000, 0x7c7f_ffff).device(DmarDeviceScope::pci_endpoint(0, 2, 0));
+        let mut dmar = DmarBuilder::new(48)
+            .flags(DMAR_FLAG_INTR_REMAP)
+            .atsr(Atsr::new(0).all_ports())
+            .rmrr(framebuffer)
+            .drhd(catch_all)
+            .drhd(gpu)
+            .build(*b"FOOBAR", *b"FOOBARDM", 0)
+            .unwrap();
+        assert_eq!(dmar.len(), 48 + 24 + 24 + 32 + 8);
+
+        let mem: GuestMemoryMmap =
+            GuestMemoryMmap::from_ranges(&[(GuestAddress(0), 0x1000)]).unwrap();
+        dmar.write_to_guest(&mem, GuestAddress(0)).unwrap();
+        let mut bytes = vec![0u8; dmar.len()];
+        mem.read_slice(&mut bytes, GuestAddress(0)).unwrap();
+        assert_eq!(checksum(&[&bytes]), 0);
+        assert_eq!(&bytes[..4], b"DMAR");
+        assert_eq!(&bytes[36..38], &[47, DMAR_FLAG_INTR_REMAP]);
+
+        // The DRHD including all the PCI devices comes after the other one.
+        assert_eq!(&bytes[48..53], &[0, 0, 24, 0, 0]);
+        assert_eq!(&bytes[56..64], &0xfed9_1000u64.to_le_bytes());
+        assert_eq!(&bytes[64..72], &[1, 8, 0, 0, 0, 0, 2, 0]);
+        assert_eq!(&bytes[72..77], &[0, 0, 24, 0, 1]);
+        assert_eq!(&bytes[88..96], &[3, 8, 0, 0, 0x21, 0xf0, 0x1f, 0]);
+
+        // Then the RMRR and the ATSR.
+        assert_eq!(&bytes[96..100], &[1, 0, 32, 0]);
+        assert_eq!(&bytes[104..112], &0x7c00_0000u64.to_le_bytes());
+        assert_eq!(&bytes[112..120], &0x7c7f_ffffu64.to_le_bytes());
+        assert_eq!(&bytes[120..128], &[1, 8, 0, 0, 0, 0, 2, 0]);
+        assert_eq!(&bytes[128..], &[2, 0, 8, 0, 1, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_dmar_invalid_structures() {
+        let empty_path = DmarDeviceScope::new(DmarDeviceScopeType::PciEndpoint, 0, 0, &[]);
+        assert!(matches!(
+            DmarBuilder::new(48)
+                .drhd(Drhd::new(0, 0xfed9_0000).device(empty_path))
+                .build(*b"FOOBAR", *b"FOOBARDM", 0),
+            Err(AcpiError::InvalidDmarDeviceScope)
+        ));
+
+        for (base, limit) in [(0x1000, 0xfff), (0x1800, 0x1fff), (0x1000, 0x1ffe)] {
+            let builder = DmarBuilder::new(48).rmrr(Rmrr::new(0, base, limit));
+            assert!(matches!(
+                builder.build(*b"FOOBAR", *b"FOOBARDM", 0),
+                Err(AcpiError::InvalidDmarReservedRegion(..))
+            ));
+        }
+    }
+}
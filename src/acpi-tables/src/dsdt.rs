@@ -1,12 +1,12 @@
 // Copyright 2024 Amazon.com, Inc. or its affiliates. All Rights Reserved.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::mem::size_of;
+use alloc::vec::Vec;
+use core::mem::size_of;
 
-use vm_memory::{Address, Bytes, GuestAddress, GuestMemory};
 use zerocopy::IntoBytes;
 
-use crate::{AcpiError, Result, Sdt, SdtHeader, checksum};
+use crate::{Result, Sdt, SdtHeader, checksum};
 
 /// Differentiated System Description Table (DSDT)
 ///
@@ -14,6 +14,7 @@ use crate::{AcpiError, Result, Sdt, SdtHeader, checksum};
 /// More information about this table can be found in the ACPI specification:
 /// https://uefi.org/specs/ACPI/6.5/05_ACPI_Software_Programming_Model.html#differentiated-system-description-table-dsdt
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Dsdt {
     header: SdtHeader,
     definition_block: Vec<u8>,
@@ -55,13 +56,11 @@ impl Sdt for Dsdt {
         self.header.length.get() as usize
     }
 
-    fn write_to_guest<AS: GuestMemory>(&mut self, mem: &AS, address: GuestAddress) -> Result<()> {
-        mem.write_slice(self.header.as_bytes(), address)?;
-        let address = address
-            .checked_add(size_of::<SdtHeader>() as u64)
-            .ok_or(AcpiError::InvalidGuestAddress)?;
-        mem.write_slice(self.definition_block.as_slice(), address)?;
+    fn header(&self) -> Option<&SdtHeader> {
+        Some(&self.header)
+    }
 
-        Ok(())
+    fn to_bytes(&mut self) -> Result<Vec<u8>> {
+        Ok([self.header.as_bytes(), self.definition_block.as_slice()].concat())
     }
 }
@@ -0,0 +1,314 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use alloc::vec::Vec;
+use core::mem::size_of;
+
+use zerocopy::{Immutable, IntoBytes};
+
+use crate::endian::U32;
+use crate::erst::{
+    INSTRUCTION_NOOP, INSTRUCTION_READ_REGISTER, INSTRUCTION_READ_REGISTER_VALUE,
+    INSTRUCTION_WRITE_REGISTER, INSTRUCTION_WRITE_REGISTER_VALUE, InstructionEntry,
+    register_interface_entries,
+};
+use crate::{GenericAddressStructure, Result, Sdt, SdtHeader, checksum};
+
+/// Action of the error injection interface.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum EinjAction {
+    /// Starts an injection.
+    BeginInjectionOperation = 0x0,
+    /// Returns the address of the table of actions triggering the injected error.
+    GetTriggerErrorActionTable = 0x1,
+    /// Sets the type of the error to inject.
+    SetErrorType = 0x2,
+    /// Returns the types of errors which can be injected.
+    GetErrorType = 0x3,
+    /// Ends the current injection.
+    EndOperation = 0x4,
+    /// Injects the error.
+    ExecuteOperation = 0x5,
+    /// Returns whether the injection is still in progress.
+    CheckBusyStatus = 0x6,
+    /// Returns the status of the last injection.
+    GetCommandStatus = 0x7,
+    /// Sets the type and the location of the error to inject.
+    SetErrorTypeWithAddress = 0x8,
+    /// Returns the nominal and maximum time an injection takes.
+    GetExecuteOperationTimings = 0x9,
+    /// Triggers the injected error, from the trigger error action table.
+    TriggerError = 0xff,
+}
+
+// Actions of the SET_ERROR_TYPE/EXECUTE_OPERATION flow, in the order their instructions are
+// listed in.
+const EINJ_REGISTER_INTERFACE_ACTIONS: [EinjAction; 8] = [
+    EinjAction::BeginInjectionOperation,
+    EinjAction::GetTriggerErrorActionTable,
+    EinjAction::SetErrorType,
+    EinjAction::GetErrorType,
+    EinjAction::EndOperation,
+    EinjAction::ExecuteOperation,
+    EinjAction::CheckBusyStatus,
+    EinjAction::GetCommandStatus,
+];
+
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct EinjHeader {
+    header_length: U32,
+    flags: u8,
+    _reserved: [u8; 3],
+    entries: U32,
+}
+
+assert_layout!(EinjHeader, 12);
+
+/// Error Injection Table (EINJ)
+///
+/// This table describes the interface the guest uses to inject hardware errors, for testing its
+/// error handling, as a list of actions each made of instructions reading or writing registers.
+/// More information about this table can be found in the ACPI specification:
+/// https://uefi.org/specs/ACPI/6.5/18_Platform_Error_Interfaces.html#error-injection
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Einj {
+    header: SdtHeader,
+    einj_header: EinjHeader,
+    entries: Vec<InstructionEntry>,
+}
+
+impl Sdt for Einj {
+    fn len(&self) -> usize {
+        self.header.length.get().try_into().unwrap()
+    }
+
+    fn header(&self) -> Option<&SdtHeader> {
+        Some(&self.header)
+    }
+
+    fn to_bytes(&mut self) -> Result<Vec<u8>> {
+        Ok([
+            self.header.as_bytes(),
+            self.einj_header.as_bytes(),
+            self.entries.as_bytes(),
+        ]
+        .concat())
+    }
+}
+
+/// Builder of an [`Einj`], one instruction at a time
+///
+/// The instructions of an action are executed in the order they are added in.
+#[derive(Clone, Debug, Default)]
+pub struct EinjBuilder {
+    entries: Vec<InstructionEntry>,
+}
+
+impl EinjBuilder {
+    /// Create a builder with no instruction.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a builder with the instructions of the SET_ERROR_TYPE/EXECUTE_OPERATION flow,
+    /// through a register interface.
+    ///
+    /// Every action writes its code to `action_register`. SET_ERROR_TYPE first writes the error
+    /// type to `value_register`, and the actions returning an output then read it from
+    /// `value_register`. The busy status is bit 0 of `value_register`.
+    pub fn register_interface(
+        action_register: GenericAddressStructure,
+        value_register: GenericAddressStructure,
+    ) -> Self {
+        let mut builder = Self::new();
+        for action in EINJ_REGISTER_INTERFACE_ACTIONS {
+            let input = action == EinjAction::SetErrorType;
+            let output = matches!(
+                action,
+                EinjAction::GetTriggerErrorActionTable
+                    | EinjAction::GetErrorType
+                    | EinjAction::GetCommandStatus
+            );
+            builder.entries.extend(register_interface_entries(
+                action as u8,
+                action_register,
+                value_register,
+                input,
+                output,
+            ));
+            if action == EinjAction::CheckBusyStatus {
+                builder = builder.read_register_value(action, value_register, 1, 1);
+            }
+        }
+        builder
+    }
+
+    fn entry(
+        mut self,
+        action: EinjAction,
+        instruction: u8,
+        register: GenericAddressStructure,
+        value: u64,
+        mask: u64,
+    ) -> Self {
+        self.entries.push(InstructionEntry::new(
+            action as u8,
+            instruction,
+            register,
+            value,
+            mask,
+        ));
+        self
+    }
+
+    /// Read `register` and return its value, masked with `mask`.
+    pub fn read_register(
+        self,
+        action: EinjAction,
+        register: GenericAddressStructure,
+        mask: u64,
+    ) -> Self {
+        self.entry(action, INSTRUCTION_READ_REGISTER, register, 0, mask)
+    }
+
+    /// Read `register` and return whether its value, masked with `mask`, is `value`.
+    pub fn read_register_value(
+        self,
+        action: EinjAction,
+        register: GenericAddressStructure,
+        value: u64,
+        mask: u64,
+    ) -> Self {
+        self.entry(
+            action,
+            INSTRUCTION_READ_REGISTER_VALUE,
+            register,
+            value,
+            mask,
+        )
+    }
+
+    /// Write the input of the action, masked with `mask`, to `register`.
+    pub fn write_register(
+        self,
+        action: EinjAction,
+        register: GenericAddressStructure,
+        mask: u64,
+    ) -> Self {
+        self.entry(action, INSTRUCTION_WRITE_REGISTER, register, 0, mask)
+    }
+
+    /// Write `value`, masked with `mask`, to `register`.
+    pub fn write_register_value(
+        self,
+        action: EinjAction,
+        register: GenericAddressStructure,
+        value: u64,
+        mask: u64,
+    ) -> Self {
+        self.entry(
+            action,
+            INSTRUCTION_WRITE_REGISTER_VALUE,
+            register,
+            value,
+            mask,
+        )
+    }
+
+    /// Do nothing, for actions which need no instruction.
+    pub fn noop(self, action: EinjAction) -> Self {
+        self.entry(
+            action,
+            INSTRUCTION_NOOP,
+            GenericAddressStructure::default(),
+            0,
+            0,
+        )
+    }
+
+    /// Build the EINJ table.
+    pub fn build(self, oem_id: [u8; 6], oem_table_id: [u8; 8], oem_revision: u32) -> Einj {
+        let length = size_of::<SdtHeader>()
+            + size_of::<EinjHeader>()
+            + self.entries.len() * size_of::<InstructionEntry>();
+        let header = SdtHeader::new(
+            *b"EINJ",
+            length.try_into().unwrap(),
+            1,
+            oem_id,
+            oem_table_id,
+            oem_revision,
+        );
+        let einj_header = EinjHeader {
+            // Unlike the ERST one, the injection header is counted without the standard header.
+            header_length: U32::new(size_of::<EinjHeader>().try_into().unwrap()),
+            flags: 0,
+            _reserved: [0; 3],
+            entries: U32::new(self.entries.len().try_into().unwrap()),
+        };
+
+        let mut einj = Einj {
+            header,
+            einj_header,
+            entries: self.entries,
+        };
+
+        einj.header.checksum = checksum(&[
+            einj.header.as_bytes(),
+            einj.einj_header.as_bytes(),
+            einj.entries.as_bytes(),
+        ]);
+
+        einj
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vm_memory::{Bytes, GuestAddress, GuestMemoryMmap};
+
+    use super::*;
+
+    #[test]
+    fn test_einj() {
+        let action = GenericAddressStructure::from_raw(0, 8, 0, 1, 0xd000_0000);
+        let value = GenericAddressStructure::from_raw(0, 64, 0, 4, 0xd000_0008);
+        let mut einj = EinjBuilder::register_interface(action, value)
+            .noop(EinjAction::TriggerError)
+            .build(*b"FOOBAR", *b"FOOBARER", 0);
+        // Every action writes its code, one takes an input, 3 return an output and one checks
+        // the busy status.
+        let entries = 8 + 1 + 3 + 1 + 1;
+        assert_eq!(einj.len(), 48 + entries * 32);
+
+        let mem: GuestMemoryMmap =
+            GuestMemoryMmap::from_ranges(&[(GuestAddress(0), 0x1000)]).unwrap();
+        einj.write_to_guest(&mem, GuestAddress(0)).unwrap();
+        let mut bytes = vec![0u8; einj.len()];
+        mem.read_slice(&mut bytes, GuestAddress(0)).unwrap();
+        assert_eq!(checksum(&[&bytes]), 0);
+        assert_eq!(&bytes[..4], b"EINJ");
+        assert_eq!(&bytes[36..40], &12u32.to_le_bytes());
+        assert_eq!(
+            &bytes[44..48],
+            &u32::try_from(entries).unwrap().to_le_bytes()
+        );
+
+        // SET_ERROR_TYPE writes the error type, then its action code.
+        let set_error_type = &bytes[48 + 3 * 32..48 + 5 * 32];
+        assert_eq!(&set_error_type[..4], [0x2, 0x2, 0, 0]);
+        assert_eq!(&set_error_type[4..16], value.as_bytes());
+        assert_eq!(&set_error_type[32..36], [0x2, 0x3, 0, 0]);
+        assert_eq!(&set_error_type[36..48], action.as_bytes());
+        assert_eq!(&set_error_type[48..56], &2u64.to_le_bytes());
+
+        let trigger_error = &bytes[bytes.len() - 32..];
+        assert_eq!(&trigger_error[..4], [0xff, 0x4, 0, 0]);
+    }
+}
@@ -0,0 +1,84 @@
+// Copyright 2024 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Little-endian integers of the tables
+//!
+//! They wrap the byte order aware integers of `zerocopy`, with no alignment requirement, so that
+//! the tables embedding them can implement the `serde` traits, through which they appear as
+//! native integers.
+
+use core::fmt;
+
+use zerocopy::{FromBytes, Immutable, IntoBytes, KnownLayout, Unaligned};
+
+macro_rules! little_endian_integer {
+    ($name:ident, $native:ty) => {
+        #[doc = concat!("Little-endian `", stringify!($native), "`")]
+        #[repr(transparent)]
+        #[derive(
+            Clone,
+            Copy,
+            Default,
+            PartialEq,
+            Eq,
+            Hash,
+            IntoBytes,
+            FromBytes,
+            Immutable,
+            KnownLayout,
+            Unaligned,
+        )]
+        pub struct $name(zerocopy::little_endian::$name);
+
+        impl $name {
+            /// The value 0
+            pub const ZERO: Self = Self(zerocopy::little_endian::$name::ZERO);
+
+            /// Store `value` in little-endian byte order.
+            pub const fn new(value: $native) -> Self {
+                Self(zerocopy::little_endian::$name::new(value))
+            }
+
+            /// Value in the native byte order
+            pub fn get(self) -> $native {
+                self.0.get()
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Debug::fmt(&self.get(), f)
+            }
+        }
+
+        impl From<$native> for $name {
+            fn from(value: $native) -> Self {
+                Self::new(value)
+            }
+        }
+
+        impl From<$name> for $native {
+            fn from(value: $name) -> Self {
+                value.get()
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl serde::Serialize for $name {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serde::Serialize::serialize(&self.get(), serializer)
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl<'de> serde::Deserialize<'de> for $name {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                <$native as serde::Deserialize>::deserialize(deserializer).map(Self::new)
+            }
+        }
+    };
+}
+
+little_endian_integer!(U16, u16);
+little_endian_integer!(U32, u32);
+little_endian_integer!(U64, u64);
@@ -0,0 +1,450 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use alloc::vec::Vec;
+use core::mem::size_of;
+
+use zerocopy::{Immutable, IntoBytes};
+
+use crate::endian::{U32, U64};
+use crate::{GenericAddressStructure, Result, Sdt, SdtHeader, checksum};
+
+// Instructions of the serialization and injection instruction entries.
+pub(crate) const INSTRUCTION_READ_REGISTER: u8 = 0x0;
+pub(crate) const INSTRUCTION_READ_REGISTER_VALUE: u8 = 0x1;
+pub(crate) const INSTRUCTION_WRITE_REGISTER: u8 = 0x2;
+pub(crate) const INSTRUCTION_WRITE_REGISTER_VALUE: u8 = 0x3;
+pub(crate) const INSTRUCTION_NOOP: u8 = 0x4;
+
+/// Action of the error record serialization interface.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ErstAction {
+    /// Starts writing a record to the store.
+    BeginWrite = 0x0,
+    /// Starts reading a record from the store.
+    BeginRead = 0x1,
+    /// Starts clearing a record from the store.
+    BeginClear = 0x2,
+    /// Ends the current operation.
+    EndOperation = 0x3,
+    /// Sets the offset of the record in the error log address range.
+    SetRecordOffset = 0x4,
+    /// Executes the current operation.
+    ExecuteOperation = 0x5,
+    /// Returns whether the current operation is still in progress.
+    CheckBusyStatus = 0x6,
+    /// Returns the status of the last operation.
+    GetCommandStatus = 0x7,
+    /// Returns the identifier of the next record of the store.
+    GetRecordIdentifier = 0x8,
+    /// Sets the identifier of the record to read or clear.
+    SetRecordIdentifier = 0x9,
+    /// Returns the number of records of the store.
+    GetRecordCount = 0xa,
+    /// Starts a write operation which does not change the store.
+    BeginDummyWrite = 0xb,
+    /// Returns the address of the error log address range.
+    GetErrorLogAddressRange = 0xd,
+    /// Returns the length of the error log address range.
+    GetErrorLogAddressRangeLength = 0xe,
+    /// Returns the attributes of the error log address range.
+    GetErrorLogAddressRangeAttributes = 0xf,
+    /// Returns the nominal and maximum time an operation takes.
+    GetExecuteOperationTimings = 0x10,
+}
+
+// All the actions, in the order their instructions are listed in.
+const ERST_ACTIONS: [ErstAction; 16] = [
+    ErstAction::BeginWrite,
+    ErstAction::BeginRead,
+    ErstAction::BeginClear,
+    ErstAction::EndOperation,
+    ErstAction::SetRecordOffset,
+    ErstAction::ExecuteOperation,
+    ErstAction::CheckBusyStatus,
+    ErstAction::GetCommandStatus,
+    ErstAction::GetRecordIdentifier,
+    ErstAction::SetRecordIdentifier,
+    ErstAction::GetRecordCount,
+    ErstAction::BeginDummyWrite,
+    ErstAction::GetErrorLogAddressRange,
+    ErstAction::GetErrorLogAddressRangeLength,
+    ErstAction::GetErrorLogAddressRangeAttributes,
+    ErstAction::GetExecuteOperationTimings,
+];
+
+/// An instruction of an error serialization or injection action.
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct InstructionEntry {
+    action: u8,
+    instruction: u8,
+    flags: u8,
+    _reserved: u8,
+    register: GenericAddressStructure,
+    value: U64,
+    mask: U64,
+}
+
+assert_layout!(InstructionEntry, 32);
+
+impl InstructionEntry {
+    pub(crate) fn new(
+        action: u8,
+        instruction: u8,
+        register: GenericAddressStructure,
+        value: u64,
+        mask: u64,
+    ) -> Self {
+        InstructionEntry {
+            action,
+            instruction,
+            flags: 0,
+            _reserved: 0,
+            register,
+            value: U64::new(value),
+            mask: U64::new(mask),
+        }
+    }
+}
+
+// Instructions of an action of the register interface, which writes the action to
+// `action_register` and passes its input or output through `value_register`.
+pub(crate) fn register_interface_entries(
+    action: u8,
+    action_register: GenericAddressStructure,
+    value_register: GenericAddressStructure,
+    input: bool,
+    output: bool,
+) -> Vec<InstructionEntry> {
+    let mut entries = Vec::new();
+    if input {
+        entries.push(InstructionEntry::new(
+            action,
+            INSTRUCTION_WRITE_REGISTER,
+            value_register,
+            0,
+            u64::MAX,
+        ));
+    }
+    entries.push(InstructionEntry::new(
+        action,
+        INSTRUCTION_WRITE_REGISTER_VALUE,
+        action_register,
+        u64::from(action),
+        u64::from(u8::MAX),
+    ));
+    if output {
+        entries.push(InstructionEntry::new(
+            action,
+            INSTRUCTION_READ_REGISTER,
+            value_register,
+            0,
+            u64::MAX,
+        ));
+    }
+    entries
+}
+
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct ErstHeader {
+    header_length: U32,
+    _reserved: U32,
+    entries: U32,
+}
+
+assert_layout!(ErstHeader, 12);
+
+/// Error Record Serialization Table (ERST)
+///
+/// This table describes the interface the guest uses to save error records, such as the logs of
+/// a crash, to a store which survives a reboot, as a list of actions each made of instructions
+/// reading or writing registers. More information about this table can be found in the ACPI
+/// specification:
+/// https://uefi.org/specs/ACPI/6.5/18_Platform_Error_Interfaces.html#error-serialization
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Erst {
+    header: SdtHeader,
+    erst_header: ErstHeader,
+    entries: Vec<InstructionEntry>,
+}
+
+impl Sdt for Erst {
+    fn len(&self) -> usize {
+        self.header.length.get().try_into().unwrap()
+    }
+
+    fn header(&self) -> Option<&SdtHeader> {
+        Some(&self.header)
+    }
+
+    fn to_bytes(&mut self) -> Result<Vec<u8>> {
+        Ok([
+            self.header.as_bytes(),
+            self.erst_header.as_bytes(),
+            self.entries.as_bytes(),
+        ]
+        .concat())
+    }
+}
+
+/// Builder of an [`Erst`], one instruction at a time
+///
+/// The instructions of an action are executed in the order they are added in.
+#[derive(Clone, Debug, Default)]
+pub struct ErstBuilder {
+    entries: Vec<InstructionEntry>,
+}
+
+impl ErstBuilder {
+    /// Create a builder with no instruction.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a builder with the instructions of all the actions of a register interface.
+    ///
+    /// Every action writes its code to `action_register`. Actions taking an input, the record
+    /// offset or identifier, first write it to `value_register`, and actions returning an output
+    /// then read it from `value_register`. The busy status is bit 0 of `value_register`.
+    pub fn register_interface(
+        action_register: GenericAddressStructure,
+        value_register: GenericAddressStructure,
+    ) -> Self {
+        let mut builder = Self::new();
+        for action in ERST_ACTIONS {
+            let input = matches!(
+                action,
+                ErstAction::SetRecordOffset | ErstAction::SetRecordIdentifier
+            );
+            let output = matches!(
+                action,
+                ErstAction::GetCommandStatus
+                    | ErstAction::GetRecordIdentifier
+                    | ErstAction::GetRecordCount
+                    | ErstAction::GetErrorLogAddressRange
+                    | ErstAction::GetErrorLogAddressRangeLength
+                    | ErstAction::GetErrorLogAddressRangeAttributes
+                    | ErstAction::GetExecuteOperationTimings
+            );
+            builder.entries.extend(register_interface_entries(
+                action as u8,
+                action_register,
+                value_register,
+                input,
+                output,
+            ));
+            if action == ErstAction::CheckBusyStatus {
+                builder = builder.read_register_value(action, value_register, 1, 1);
+            }
+        }
+        builder
+    }
+
+    fn entry(
+        mut self,
+        action: ErstAction,
+        instruction: u8,
+        register: GenericAddressStructure,
+        value: u64,
+        mask: u64,
+    ) -> Self {
+        self.entries.push(InstructionEntry::new(
+            action as u8,
+            instruction,
+            register,
+            value,
+            mask,
+        ));
+        self
+    }
+
+    /// Read `register` and return its value, masked with `mask`.
+    pub fn read_register(
+        self,
+        action: ErstAction,
+        register: GenericAddressStructure,
+        mask: u64,
+    ) -> Self {
+        self.entry(action, INSTRUCTION_READ_REGISTER, register, 0, mask)
+    }
+
+    /// Read `register` and return whether its value, masked with `mask`, is `value`.
+    pub fn read_register_value(
+        self,
+        action: ErstAction,
+        register: GenericAddressStructure,
+        value: u64,
+        mask: u64,
+    ) -> Self {
+        self.entry(
+            action,
+            INSTRUCTION_READ_REGISTER_VALUE,
+            register,
+            value,
+            mask,
+        )
+    }
+
+    /// Write the input of the action, masked with `mask`, to `register`.
+    pub fn write_register(
+        self,
+        action: ErstAction,
+        register: GenericAddressStructure,
+        mask: u64,
+    ) -> Self {
+        self.entry(action, INSTRUCTION_WRITE_REGISTER, register, 0, mask)
+    }
+
+    /// Write `value`, masked with `mask`, to `register`.
+    pub fn write_register_value(
+        self,
+        action: ErstAction,
+        register: GenericAddressStructure,
+        value: u64,
+        mask: u64,
+    ) -> Self {
+        self.entry(
+            action,
+            INSTRUCTION_WRITE_REGISTER_VALUE,
+            register,
+            value,
+            mask,
+        )
+    }
+
+    /// Do nothing, for actions which need no instruction.
+    pub fn noop(self, action: ErstAction) -> Self {
+        self.entry(
+            action,
+            INSTRUCTION_NOOP,
+            GenericAddressStructure::default(),
+            0,
+            0,
+        )
+    }
+
+    /// Build the ERST table.
+    pub fn build(self, oem_id: [u8; 6], oem_table_id: [u8; 8], oem_revision: u32) -> Erst {
+        let length = size_of::<SdtHeader>()
+            + size_of::<ErstHeader>()
+            + self.entries.len() * size_of::<InstructionEntry>();
+        let header = SdtHeader::new(
+            *b"ERST",
+            length.try_into().unwrap(),
+            1,
+            oem_id,
+            oem_table_id,
+            oem_revision,
+        );
+        let erst_header = ErstHeader {
+            // The serialization header is counted along with the standard header.
+            header_length: U32::new(
+                (size_of::<SdtHeader>() + size_of::<ErstHeader>())
+                    .try_into()
+                    .unwrap(),
+            ),
+            _reserved: U32::ZERO,
+            entries: U32::new(self.entries.len().try_into().unwrap()),
+        };
+
+        let mut erst = Erst {
+            header,
+            erst_header,
+            entries: self.entries,
+        };
+
+        erst.header.checksum = checksum(&[
+            erst.header.as_bytes(),
+            erst.erst_header.as_bytes(),
+            erst.entries.as_bytes(),
+        ]);
+
+        erst
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vm_memory::{Bytes, GuestAddress, GuestMemoryMmap};
+
+    use super::*;
+
+    #[test]
+    fn test_erst() {
+        let register = GenericAddressStructure::from_raw(0, 64, 0, 4, 0xd000_0000);
+        let mut erst = ErstBuilder::new()
+            .write_register_value(ErstAction::BeginWrite, register, 1, 0xff)
+            .write_register(ErstAction::SetRecordOffset, register, u64::MAX)
+            .read_register_value(ErstAction::CheckBusyStatus, register, 1, 1)
+            .noop(ErstAction::EndOperation)
+            .build(*b"FOOBAR", *b"FOOBARER", 0);
+        assert_eq!(erst.len(), 48 + 4 * 32);
+
+        let mem: GuestMemoryMmap =
+            GuestMemoryMmap::from_ranges(&[(GuestAddress(0), 0x1000)]).unwrap();
+        erst.write_to_guest(&mem, GuestAddress(0)).unwrap();
+        let mut bytes = vec![0u8; erst.len()];
+        mem.read_slice(&mut bytes, GuestAddress(0)).unwrap();
+        assert_eq!(checksum(&[&bytes]), 0);
+        assert_eq!(&bytes[..4], b"ERST");
+        assert_eq!(&bytes[36..40], &48u32.to_le_bytes());
+        assert_eq!(&bytes[44..48], &4u32.to_le_bytes());
+
+        // Action, instruction and flags of every entry.
+        let entries: Vec<_> = bytes[48..].chunks(32).map(|entry| &entry[..4]).collect();
+        assert_eq!(
+            entries,
+            [
+                [0x0, 0x3, 0, 0],
+                [0x4, 0x2, 0, 0],
+                [0x6, 0x1, 0, 0],
+                [0x3, 0x4, 0, 0]
+            ]
+        );
+        let check_busy = &bytes[112..144];
+        assert_eq!(&check_busy[4..16], register.as_bytes());
+        assert_eq!(&check_busy[16..24], &1u64.to_le_bytes());
+        assert_eq!(&check_busy[24..32], &1u64.to_le_bytes());
+    }
+
+    #[test]
+    fn test_erst_register_interface() {
+        let action = GenericAddressStructure::from_raw(0, 8, 0, 1, 0xd000_0000);
+        let value = GenericAddressStructure::from_raw(0, 64, 0, 4, 0xd000_0008);
+        let erst =
+            ErstBuilder::register_interface(action, value).build(*b"FOOBAR", *b"FOOBARER", 0);
+        // Every action writes its code, 2 take an input, 7 return an output and one checks the
+        // busy status.
+        assert_eq!(erst.entries.len(), 16 + 2 + 7 + 1);
+
+        let set_record_offset = &erst.entries[4..6];
+        assert_eq!(set_record_offset[0].instruction, INSTRUCTION_WRITE_REGISTER);
+        assert_eq!(set_record_offset[0].register.as_bytes(), value.as_bytes());
+        assert_eq!(
+            set_record_offset[1].instruction,
+            INSTRUCTION_WRITE_REGISTER_VALUE
+        );
+        assert_eq!(set_record_offset[1].value.get(), 0x4);
+
+        let check_busy_status = &erst.entries[7..9];
+        assert_eq!(check_busy_status[0].action, 0x6);
+        assert_eq!(
+            check_busy_status[1].instruction,
+            INSTRUCTION_READ_REGISTER_VALUE
+        );
+        assert_eq!(check_busy_status[1].mask.get(), 1);
+
+        let get_command_status = &erst.entries[9..11];
+        assert_eq!(get_command_status[1].action, 0x7);
+        assert_eq!(get_command_status[1].instruction, INSTRUCTION_READ_REGISTER);
+    }
+}
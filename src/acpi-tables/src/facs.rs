@@ -0,0 +1,119 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use alloc::vec::Vec;
+
+use zerocopy::{Immutable, IntoBytes};
+
+use crate::endian::{U32, U64};
+use crate::{Result, Sdt};
+
+/// Alignment of the FACS in guest memory.
+pub const FACS_ALIGNMENT: u64 = 64;
+/// Flag telling the guest that the 64-bit waking vector can be used in 64-bit mode.
+pub const FACS_F_64BIT_WAKE_SUPPORTED: u32 = 1 << 1;
+
+/// Firmware ACPI Control Structure (FACS)
+///
+/// This structure, pointed to by the FADT, holds the waking vector the guest sets before going
+/// to sleep, for the platform to jump to when it resumes, and the hardware signature the guest
+/// compares across a resume to tell whether the hardware changed while it was asleep. Unlike
+/// tables, it has no standard header and no checksum, and it must be aligned on 64 bytes. More
+/// information about this structure can be found in the ACPI specification:
+/// https://uefi.org/specs/ACPI/6.5/05_ACPI_Software_Programming_Model.html#firmware-acpi-control-structure-facs
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Facs {
+    signature: [u8; 4],
+    length: U32,
+    hardware_signature: U32,
+    firmware_waking_vector: U32,
+    global_lock: U32,
+    flags: U32,
+    x_firmware_waking_vector: U64,
+    version: u8,
+    _reserved1: [u8; 3],
+    ospm_flags: U32,
+    _reserved2: [u8; 24],
+}
+
+assert_layout!(Facs, 64, x_firmware_waking_vector: 24, ospm_flags: 36);
+
+impl Facs {
+    /// Create a FACS with the given hardware signature and no waking vector.
+    pub fn new(hardware_signature: u32) -> Self {
+        Facs {
+            signature: *b"FACS",
+            length: U32::new(core::mem::size_of::<Self>().try_into().unwrap()),
+            hardware_signature: U32::new(hardware_signature),
+            version: 2,
+            ..Default::default()
+        }
+    }
+
+    /// Set the FACS flags
+    pub fn set_flags(&mut self, flags: u32) {
+        self.flags = U32::new(flags);
+    }
+
+    /// Set the real mode address the platform jumps to when resuming
+    pub fn set_firmware_waking_vector(&mut self, addr: u32) {
+        self.firmware_waking_vector = U32::new(addr);
+    }
+
+    /// Set the address the platform jumps to when resuming, in the mode the guest requested
+    ///
+    /// This takes precedence over the real mode waking vector when it is not zero
+    pub fn set_x_firmware_waking_vector(&mut self, addr: u64) {
+        self.x_firmware_waking_vector = U64::new(addr);
+    }
+}
+
+impl Sdt for Facs {
+    fn len(&self) -> usize {
+        self.length.get().try_into().unwrap()
+    }
+
+    fn to_bytes(&mut self) -> Result<Vec<u8>> {
+        Ok(self.as_bytes().to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vm_memory::{Bytes, GuestAddress, GuestMemoryMmap};
+
+    use super::*;
+    use crate::Fadt;
+
+    #[test]
+    fn test_facs() {
+        let mut facs = Facs::new(0x1234_5678);
+        facs.set_flags(FACS_F_64BIT_WAKE_SUPPORTED);
+        facs.set_firmware_waking_vector(0x9_0000);
+        facs.set_x_firmware_waking_vector(0x1_0000_0000);
+        assert_eq!(facs.len(), 64);
+
+        let mem: GuestMemoryMmap =
+            GuestMemoryMmap::from_ranges(&[(GuestAddress(0), 0x1000)]).unwrap();
+        facs.write_to_guest(&mem, GuestAddress(0x40)).unwrap();
+        let mut bytes = vec![0u8; facs.len()];
+        mem.read_slice(&mut bytes, GuestAddress(0x40)).unwrap();
+        assert_eq!(&bytes[..8], &[b'F', b'A', b'C', b'S', 64, 0, 0, 0]);
+        assert_eq!(&bytes[8..12], &0x1234_5678u32.to_le_bytes());
+        assert_eq!(&bytes[12..16], &0x9_0000u32.to_le_bytes());
+        assert_eq!(&bytes[20..24], &2u32.to_le_bytes());
+        assert_eq!(&bytes[24..32], &0x1_0000_0000u64.to_le_bytes());
+        assert_eq!(bytes[32], 2);
+
+        // The FADT points to the FACS.
+        let mut fadt = Fadt::new(*b"FOOBAR", *b"FOOBARFA", 0);
+        fadt.set_firmware_ctrl(0x40);
+        fadt.set_x_firmware_ctrl(0x1_0000_0040);
+        assert_eq!(&fadt.as_bytes()[36..40], &0x40u32.to_le_bytes());
+        assert_eq!(&fadt.as_bytes()[132..140], &0x1_0000_0040u64.to_le_bytes());
+    }
+}
@@ -3,12 +3,20 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
-use vm_memory::{Bytes, GuestAddress, GuestMemory};
-use zerocopy::little_endian::{U16, U32, U64};
-use zerocopy::{Immutable, IntoBytes};
+use alloc::vec::Vec;
 
-use crate::{GenericAddressStructure, Result, Sdt, SdtHeader, checksum};
+use zerocopy::{FromBytes, FromZeros, Immutable, IntoBytes};
 
+use crate::endian::{U16, U32, U64};
+use crate::{
+    AccessSize, AcpiError, AddressSpace, GenericAddressStructure, Result, Sdt, SdtHeader,
+    SpecRevision, checksum,
+};
+
+#[cfg(target_arch = "x86_64")]
+pub const IAPC_BOOT_ARG_FLAGS_LEGACY_DEVICES: u16 = 0;
+#[cfg(target_arch = "x86_64")]
+pub const IAPC_BOOT_ARG_FLAGS_8042: u16 = 1;
 #[cfg(target_arch = "x86_64")]
 pub const IAPC_BOOT_ARG_FLAGS_VGA_NOT_PRESENT: u16 = 2;
 #[cfg(target_arch = "x86_64")]
@@ -16,6 +24,13 @@ pub const IAPC_BOOT_ARG_FLAGS_MSI_NOT_PRESENT: u16 = 3;
 #[cfg(target_arch = "x86_64")]
 pub const IAPC_BOOT_ARG_FLAGS_PCI_ASPM: u16 = 4;
 
+/// The platform implements PSCI.
+#[cfg(target_arch = "aarch64")]
+pub const ARM_BOOT_ARCH_PSCI_COMPLIANT: u16 = 0;
+/// PSCI is called with HVC instead of SMC.
+#[cfg(target_arch = "aarch64")]
+pub const ARM_BOOT_ARCH_PSCI_USE_HVC: u16 = 1;
+
 // ACPI Flags. Reading from the specification here:
 // https://uefi.org/specs/ACPI/6.5/05_ACPI_Software_Programming_Model.html#fixed-acpi-description-table-fixed-feature-flags
 
@@ -27,9 +42,37 @@ pub const FADT_F_PWR_BUTTON: u8 = 4;
 /// If the system does not have a sleep button, this value would be “1” and no power button device
 /// would be present
 pub const FADT_F_SLP_BUTTON: u8 = 5;
+/// Flag for the reset register support.
+/// If set, the system supports resetting through the register described by RESET_REG
+pub const FADT_F_RESET_REG_SUP: u8 = 10;
 /// Flag for Hardware Reduced API. If enabled, software-only alternatives are used for supported
 /// fixed features.
 pub const FADT_F_HW_REDUCED_ACPI: u8 = 20;
+/// Flag for Low Power S0 Idle. If set, the platform supports low power idle states within S0
+/// that are as efficient as S3, so the OS should favor them when suspending.
+pub const FADT_F_LOW_POWER_S0_IDLE_CAPABLE: u8 = 21;
+
+// Lengths, in bytes, of the fixed hardware register blocks.
+// Length of the FADT up to ACPI 5.1, before the hypervisor vendor ID.
+const FADT_5_LENGTH: usize = 268;
+
+const PM1_EVT_LEN: u8 = 4;
+const PM1_CNT_LEN: u8 = 2;
+const PM_TMR_LEN: u8 = 4;
+
+// Describe the register block of `len` bytes at the I/O port `port`, for the X_* fields of the
+// FADT. The accesses to the fixed hardware blocks are left undefined, so any length up to 31
+// bytes is valid.
+fn io_block(port: u16, len: u8) -> GenericAddressStructure {
+    GenericAddressStructure::new(
+        AddressSpace::SystemIo,
+        len * 8,
+        0,
+        AccessSize::Undefined,
+        port.into(),
+    )
+    .unwrap()
+}
 
 // clippy doesn't understand that we actually "use" the fields of this struct when we serialize
 // them as bytes in guest memory, so here we just ignore dead code to avoid having to name
@@ -42,7 +85,8 @@ pub const FADT_F_HW_REDUCED_ACPI: u8 = 20;
 /// More information about this table can be found in the ACPI specification:
 /// https://uefi.org/specs/ACPI/6.5/05_ACPI_Software_Programming_Model.html#fixed-acpi-description-table-fadt
 #[repr(C, packed)]
-#[derive(Debug, Copy, Clone, Default, IntoBytes, Immutable)]
+#[derive(Debug, Copy, Clone, Default, IntoBytes, FromBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Fadt {
     header: SdtHeader,
     firmware_control: U32,
@@ -105,24 +149,92 @@ pub struct Fadt {
     hypervisor_vendor_id: [u8; 8],
 }
 
+assert_layout!(
+    Fadt,
+    276,
+    dsdt: 40,
+    flags: 112,
+    x_dsdt: 140,
+    sleep_control_reg: 244,
+    hypervisor_vendor_id: 268,
+);
+
 impl Fadt {
     pub fn new(oem_id: [u8; 6], oem_table_id: [u8; 8], oem_revision: u32) -> Self {
         let header = SdtHeader::new(
             *b"FACP",
             // It's fine to unwrap here, we know that the size of the Fadt structure fits in 32
             // bits.
-            std::mem::size_of::<Self>().try_into().unwrap(),
+            core::mem::size_of::<Self>().try_into().unwrap(),
             6, // revision 6
             oem_id,
             oem_table_id,
             oem_revision,
         );
 
-        Fadt {
+        let mut fadt = Fadt {
             header,
             fadt_minor_version: 5,
             ..Default::default()
+        };
+        fadt.update_checksum();
+        fadt
+    }
+
+    // Refold the checksum, for the table to stay valid after each change of its fields.
+    fn update_checksum(&mut self) {
+        self.header.checksum = 0;
+        self.header.checksum = checksum(&[&self.as_bytes()[..self.len()]]);
+    }
+
+    /// Target the given revision of the ACPI specification
+    ///
+    /// This sets the major and minor versions of the FADT. Before ACPI 6.0, the table is
+    /// truncated before the hypervisor vendor ID, and the ARM boot flags and minor version are
+    /// cleared before ACPI 5.1. Fields are only cleared here, so this is meant to be called once
+    /// the others are set.
+    pub fn set_spec_revision(&mut self, revision: SpecRevision) {
+        let (major_version, minor_version) = match revision {
+            SpecRevision::Acpi5_0 => (5, 0),
+            SpecRevision::Acpi5_1 => (5, 1),
+            SpecRevision::Acpi6_0 => (6, 0),
+            SpecRevision::Acpi6_1 => (6, 1),
+            SpecRevision::Acpi6_2 => (6, 2),
+            SpecRevision::Acpi6_3 => (6, 3),
+            SpecRevision::Acpi6_4 => (6, 4),
+            SpecRevision::Acpi6_5 => (6, 5),
+        };
+        let length = if major_version < 6 {
+            FADT_5_LENGTH
+        } else {
+            core::mem::size_of::<Self>()
+        };
+        self.header.revision = major_version;
+        self.header.length = U32::new(length.try_into().unwrap());
+        self.fadt_minor_version = minor_version;
+        if revision < SpecRevision::Acpi6_0 {
+            self.hypervisor_vendor_id = [0; 8];
+        }
+        if revision < SpecRevision::Acpi5_1 {
+            self.arm_boot_arch = U16::ZERO;
         }
+        self.update_checksum();
+    }
+
+    /// Set the 32-bit address of the FACS
+    ///
+    /// This must be left to zero when the 64bit variant is set
+    pub fn set_firmware_ctrl(&mut self, addr: u32) {
+        self.firmware_control = U32::new(addr);
+        self.update_checksum();
+    }
+
+    /// Set the address of the FACS
+    ///
+    /// This sets the 64bit variant, X_FIRMWARE_CTRL field of the FADT table
+    pub fn set_x_firmware_ctrl(&mut self, addr: u64) {
+        self.x_firmware_ctrl = U64::new(addr);
+        self.update_checksum();
     }
 
     /// Set the address of the DSDT table
@@ -130,21 +242,235 @@ impl Fadt {
     /// This sets the 64bit variant, X_DSDT field of the FADT table
     pub fn set_x_dsdt(&mut self, addr: u64) {
         self.x_dsdt = U64::new(addr);
+        self.update_checksum();
     }
 
     /// Set the FADT flags
     pub fn set_flags(&mut self, flags: u32) {
         self.flags = U32::new(flags);
+        self.update_checksum();
+    }
+
+    /// Set the reset register and the value to write to it to reset the system
+    ///
+    /// This also sets the RESET_REG_SUP flag
+    pub fn set_reset_reg(&mut self, reset_reg: GenericAddressStructure, reset_value: u8) {
+        self.reset_reg = reset_reg;
+        self.reset_value = reset_value;
+        self.flags = U32::new(self.flags.get() | (1 << FADT_F_RESET_REG_SUP));
+        self.update_checksum();
     }
 
     /// Set the IA-PC specific flags
     pub fn setup_iapc_flags(&mut self, flags: u16) {
         self.iapc_boot_arch = U16::new(flags);
+        self.update_checksum();
+    }
+
+    /// Set the SCI interrupt, the GSI on which the fixed hardware events are signaled
+    pub fn set_sci_int(&mut self, sci_int: u16) {
+        self.sci_int = U16::new(sci_int);
+        self.update_checksum();
+    }
+
+    /// Set the I/O port of the PM1a event block, made of the 16-bit status and enable registers
+    ///
+    /// This sets both the 32-bit PM1A_EVT_BLK field and its 64bit variant, X_PM1a_EVT_BLK.
+    pub fn set_pm1a_evt_blk(&mut self, port: u16) {
+        self.pm1a_evt_blk = U32::new(port.into());
+        self.pm1_evt_len = PM1_EVT_LEN;
+        self.x_pm1a_evt_blk = io_block(port, PM1_EVT_LEN);
+        self.update_checksum();
+    }
+
+    /// Set the I/O port of the 16-bit PM1a control block
+    ///
+    /// This sets both the 32-bit PM1A_CNT_BLK field and its 64bit variant, X_PM1a_CNT_BLK.
+    pub fn set_pm1a_cnt_blk(&mut self, port: u16) {
+        self.pm1a_cnt_blk = U32::new(port.into());
+        self.pm1_cnt_len = PM1_CNT_LEN;
+        self.x_pm1a_cnt_blk = io_block(port, PM1_CNT_LEN);
+        self.update_checksum();
+    }
+
+    /// Set the I/O port of the 32-bit PM timer
+    ///
+    /// This sets both the 32-bit PM_TMR_BLK field and its 64bit variant, X_PM_TMR_BLK.
+    pub fn set_pm_tmr_blk(&mut self, port: u16) {
+        self.pm_tmr_blk = U32::new(port.into());
+        self.pm_tmr_len = PM_TMR_LEN;
+        self.x_pm_tmr_blk = io_block(port, PM_TMR_LEN);
+        self.update_checksum();
+    }
+
+    /// Set the I/O port and the length of the GPE0 block
+    ///
+    /// The block is made of the status registers followed by the enable registers, both half of
+    /// its length, which must thus be even. This sets both the 32-bit GPE0_BLK field and its
+    /// 64bit variant, X_GPE0_BLK.
+    pub fn set_gpe0_blk(&mut self, port: u16, len: u8) -> Result<()> {
+        if !len.is_multiple_of(2) || len > u8::MAX / 8 {
+            return Err(AcpiError::InvalidRegisterSize);
+        }
+        self.gpe0_blk = U32::new(port.into());
+        self.gpe0_blk_len = len;
+        self.x_gpe0_blk = io_block(port, len);
+        self.update_checksum();
+        Ok(())
+    }
+
+    /// Set the ARM specific flags
+    pub fn setup_arm_flags(&mut self, flags: u16) {
+        self.arm_boot_arch = U16::new(flags);
+        self.update_checksum();
+    }
+
+    /// Set the minor version of the FADT
+    ///
+    /// The major version is the revision of the table header, 6.
+    pub fn set_minor_version(&mut self, minor_version: u8) {
+        self.fadt_minor_version = minor_version;
+        self.update_checksum();
     }
 
     /// Set the hypervisor vendor ID
     pub fn set_hypervisor_vendor_id(&mut self, hypervisor_vendor_id: [u8; 8]) {
         self.hypervisor_vendor_id = hypervisor_vendor_id;
+        self.update_checksum();
+    }
+
+    /// Parse an FADT, checking its signature, length and checksum
+    ///
+    /// Both the current, 276 bytes long, revision of the table and the 268 bytes long one of
+    /// ACPI 5.x are supported.
+    pub fn parse(bytes: &[u8]) -> Result<Self> {
+        SdtHeader::parse(bytes, b"FACP")?;
+        if bytes.len() != FADT_5_LENGTH && bytes.len() != core::mem::size_of::<Self>() {
+            return Err(AcpiError::InvalidTableLength);
+        }
+        let mut fadt = Fadt::new_zeroed();
+        fadt.as_mut_bytes()[..bytes.len()].copy_from_slice(bytes);
+        Ok(fadt)
+    }
+
+    /// FADT flags
+    pub fn flags(&self) -> u32 {
+        self.flags.get()
+    }
+
+    /// Address of the DSDT table, from the X_DSDT field
+    pub fn x_dsdt(&self) -> u64 {
+        self.x_dsdt.get()
+    }
+
+    /// Address of the FACS, from the X_FIRMWARE_CTRL field
+    pub fn x_firmware_ctrl(&self) -> u64 {
+        self.x_firmware_ctrl.get()
+    }
+
+    /// Hypervisor vendor ID
+    pub fn hypervisor_vendor_id(&self) -> [u8; 8] {
+        self.hypervisor_vendor_id
+    }
+}
+
+/// Builder of a hardware-reduced [`Fadt`]
+///
+/// In hardware-reduced mode, the fixed hardware register blocks are ignored, and the system
+/// enters sleep states through the SLEEP_CONTROL_REG and SLEEP_STATUS_REG registers instead.
+#[derive(Debug)]
+pub struct FadtBuilder {
+    fadt: Fadt,
+    flags: u32,
+    sleep_registers: Option<(GenericAddressStructure, GenericAddressStructure)>,
+    spec_revision: SpecRevision,
+}
+
+impl FadtBuilder {
+    /// Create a builder for a hardware-reduced FADT, identifying the hypervisor with
+    /// `hypervisor_vendor_id`.
+    pub fn hw_reduced(
+        oem_id: [u8; 6],
+        oem_table_id: [u8; 8],
+        oem_revision: u32,
+        hypervisor_vendor_id: [u8; 8],
+    ) -> Self {
+        let mut fadt = Fadt::new(oem_id, oem_table_id, oem_revision);
+        fadt.set_hypervisor_vendor_id(hypervisor_vendor_id);
+        FadtBuilder {
+            fadt,
+            flags: 1 << FADT_F_HW_REDUCED_ACPI,
+            sleep_registers: None,
+            spec_revision: SpecRevision::default(),
+        }
+    }
+
+    /// Set the address of the DSDT table.
+    pub fn x_dsdt(mut self, addr: u64) -> Self {
+        self.fadt.set_x_dsdt(addr);
+        self
+    }
+
+    /// Set the sleep control and status registers, both 8 bits wide.
+    pub fn sleep_registers(
+        mut self,
+        control: GenericAddressStructure,
+        status: GenericAddressStructure,
+    ) -> Self {
+        self.sleep_registers = Some((control, status));
+        self
+    }
+
+    /// Set the reset register and the value to write to it to reset the system.
+    pub fn reset_reg(mut self, reset_reg: GenericAddressStructure, reset_value: u8) -> Self {
+        self.fadt.set_reset_reg(reset_reg, reset_value);
+        self
+    }
+
+    /// Describe the power and sleep buttons as control method devices of the DSDT, instead of
+    /// leaving the system without them.
+    pub fn control_method_buttons(mut self, power_button: bool, sleep_button: bool) -> Self {
+        // The flags are set when the fixed feature buttons are absent, which they always are in
+        // hardware-reduced mode.
+        self.flags &= !((1 << FADT_F_PWR_BUTTON) | (1 << FADT_F_SLP_BUTTON));
+        if !power_button {
+            self.flags |= 1 << FADT_F_PWR_BUTTON;
+        }
+        if !sleep_button {
+            self.flags |= 1 << FADT_F_SLP_BUTTON;
+        }
+        self
+    }
+
+    /// Advertise low power idle states within S0 as efficient as S3.
+    pub fn low_power_s0_idle(mut self) -> Self {
+        self.flags |= 1 << FADT_F_LOW_POWER_S0_IDLE_CAPABLE;
+        self
+    }
+
+    /// Target an older revision of the ACPI specification than the latest one.
+    pub fn spec_revision(mut self, revision: SpecRevision) -> Self {
+        self.spec_revision = revision;
+        self
+    }
+
+    /// Build the FADT, checking the sleep registers.
+    pub fn build(self) -> Result<Fadt> {
+        let mut fadt = self.fadt;
+        if let Some((control, status)) = self.sleep_registers {
+            for register in [control, status] {
+                if register.register_bit_width != 8 || register.address.get() == 0 {
+                    return Err(AcpiError::InvalidFadtSleepRegister);
+                }
+            }
+            fadt.sleep_control_reg = control;
+            fadt.sleep_status_reg = status;
+        }
+        // Keep the RESET_REG_SUP flag set along with the reset register. This also refolds the
+        // checksum over the sleep registers.
+        fadt.set_flags(fadt.flags.get() | self.flags);
+        fadt.set_spec_revision(self.spec_revision);
+        Ok(fadt)
     }
 }
 
@@ -153,9 +479,147 @@ impl Sdt for Fadt {
         self.header.length.get().try_into().unwrap()
     }
 
-    fn write_to_guest<M: GuestMemory>(&mut self, mem: &M, address: GuestAddress) -> Result<()> {
-        self.header.checksum = checksum(&[self.as_bytes()]);
-        mem.write_slice(self.as_bytes(), address)?;
-        Ok(())
+    fn header(&self) -> Option<&SdtHeader> {
+        Some(&self.header)
+    }
+
+    fn to_bytes(&mut self) -> Result<Vec<u8>> {
+        Ok(self.as_bytes()[..self.len()].to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verify_checksum;
+
+    #[test]
+    fn test_fadt_builder() {
+        let control = GenericAddressStructure::from_raw(1, 8, 0, 1, 0x3c0);
+        let status = GenericAddressStructure::from_raw(1, 8, 0, 1, 0x3c1);
+        let fadt = FadtBuilder::hw_reduced(*b"FOOBAR", *b"FOOBARFA", 0, *b"FCVMFADT")
+            .x_dsdt(0x1000)
+            .sleep_registers(control, status)
+            .reset_reg(GenericAddressStructure::from_raw(1, 8, 0, 1, 0x64), 0xfe)
+            .control_method_buttons(true, false)
+            .build()
+            .unwrap();
+        let flags = fadt.flags.get();
+        assert_eq!(
+            flags,
+            (1 << FADT_F_HW_REDUCED_ACPI) | (1 << FADT_F_RESET_REG_SUP) | (1 << FADT_F_SLP_BUTTON)
+        );
+        assert_eq!(fadt.sleep_control_reg.as_bytes(), control.as_bytes());
+        assert_eq!(fadt.sleep_status_reg.as_bytes(), status.as_bytes());
+        assert_eq!(&fadt.hypervisor_vendor_id, b"FCVMFADT");
+        assert_eq!(fadt.x_dsdt.get(), 0x1000);
+
+        // The sleep registers are 8 bits wide.
+        let wide = GenericAddressStructure::from_raw(1, 16, 0, 2, 0x3c0);
+        let err = FadtBuilder::hw_reduced(*b"FOOBAR", *b"FOOBARFA", 0, *b"FCVMFADT")
+            .sleep_registers(wide, status)
+            .build()
+            .unwrap_err();
+        assert!(matches!(err, AcpiError::InvalidFadtSleepRegister));
+    }
+
+    #[test]
+    fn test_fadt_pm_blocks() {
+        let mut fadt = Fadt::new(*b"FOOBAR", *b"FOOBARFA", 0);
+        fadt.set_sci_int(9);
+        fadt.set_pm1a_evt_blk(0x600);
+        fadt.set_pm1a_cnt_blk(0x604);
+        fadt.set_pm_tmr_blk(0x608);
+        fadt.set_gpe0_blk(0x620, 16).unwrap();
+        assert!(matches!(
+            fadt.set_gpe0_blk(0x620, 3),
+            Err(AcpiError::InvalidRegisterSize)
+        ));
+        assert!(matches!(
+            fadt.set_gpe0_blk(0x620, 32),
+            Err(AcpiError::InvalidRegisterSize)
+        ));
+
+        let bytes = fadt.as_bytes();
+        assert_eq!(&bytes[46..48], &9u16.to_le_bytes());
+        assert_eq!(&bytes[56..60], &0x600u32.to_le_bytes());
+        assert_eq!(&bytes[64..68], &0x604u32.to_le_bytes());
+        assert_eq!(&bytes[76..80], &0x608u32.to_le_bytes());
+        assert_eq!(&bytes[80..84], &0x620u32.to_le_bytes());
+        assert_eq!(&bytes[88..93], &[4, 2, 0, 4, 16]);
+        // The X_* variants, in the System I/O space.
+        assert_eq!(&bytes[148..152], &[1, 32, 0, 0]);
+        assert_eq!(&bytes[152..160], &0x600u64.to_le_bytes());
+        assert_eq!(&bytes[172..176], &[1, 16, 0, 0]);
+        assert_eq!(&bytes[208..212], &[1, 32, 0, 0]);
+        assert_eq!(&bytes[220..224], &[1, 128, 0, 0]);
+        assert_eq!(&bytes[224..232], &0x620u64.to_le_bytes());
+    }
+
+    #[test]
+    fn test_fadt_parse() {
+        let mut fadt = FadtBuilder::hw_reduced(*b"FOOBAR", *b"FOOBARFA", 0, *b"FCVMFADT")
+            .x_dsdt(0x1000)
+            .build()
+            .unwrap();
+        let bytes = fadt.to_bytes().unwrap();
+        assert_eq!(bytes.len(), 276);
+        let parsed = Fadt::parse(&bytes).unwrap();
+        assert_eq!(parsed.flags(), 1 << FADT_F_HW_REDUCED_ACPI);
+        assert_eq!(parsed.x_dsdt(), 0x1000);
+        assert_eq!(&parsed.hypervisor_vendor_id(), b"FCVMFADT");
+
+        assert!(matches!(
+            Fadt::parse(&bytes[..244]),
+            Err(AcpiError::InvalidTableLength)
+        ));
+    }
+
+    #[test]
+    fn test_fadt_setters_update_checksum() {
+        let mut fadt = Fadt::new(*b"FOOBAR", *b"FOOBARFA", 0);
+        assert!(verify_checksum(fadt.as_bytes()));
+        fadt.set_x_dsdt(0x1000);
+        fadt.set_sci_int(9);
+        assert!(verify_checksum(fadt.as_bytes()));
+        fadt.set_gpe0_blk(0x620, 16).unwrap();
+        assert!(verify_checksum(fadt.as_bytes()));
+
+        // Writing the table again must not break the checksum.
+        let first = fadt.to_bytes().unwrap();
+        assert_eq!(fadt.to_bytes().unwrap(), first);
+        assert!(verify_checksum(&first));
+    }
+
+    #[test]
+    fn test_fadt_spec_revision() {
+        let mut fadt = FadtBuilder::hw_reduced(*b"FOOBAR", *b"FOOBARFA", 0, *b"FCVMFADT")
+            .spec_revision(SpecRevision::Acpi5_1)
+            .build()
+            .unwrap();
+        let bytes = fadt.to_bytes().unwrap();
+        assert_eq!(bytes.len(), 268);
+        assert_eq!(bytes[8], 5);
+        assert_eq!(bytes[131], 1);
+        let parsed = Fadt::parse(&bytes).unwrap();
+        assert_eq!(parsed.hypervisor_vendor_id(), [0; 8]);
+
+        fadt.set_spec_revision(SpecRevision::Acpi6_3);
+        let bytes = fadt.to_bytes().unwrap();
+        assert_eq!(bytes.len(), 276);
+        assert_eq!((bytes[8], bytes[131]), (6, 3));
+        assert!(verify_checksum(&bytes));
+    }
+
+    #[test]
+    fn test_fadt_arm_flags() {
+        let mut fadt = Fadt::new(*b"FOOBAR", *b"FOOBARFA", 0);
+        assert_eq!(fadt.fadt_minor_version, 5);
+        // PSCI compliant, through HVC.
+        fadt.setup_arm_flags(0b11);
+        fadt.set_minor_version(3);
+        let bytes = fadt.as_bytes();
+        assert_eq!(&bytes[129..131], &3u16.to_le_bytes());
+        assert_eq!(bytes[131], 3);
     }
 }
@@ -0,0 +1,229 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use alloc::vec::Vec;
+use core::mem::size_of;
+
+use zerocopy::{Immutable, IntoBytes};
+
+use crate::endian::{U16, U32, U64};
+use crate::{Result, Sdt, SdtHeader, checksum};
+
+const FBPT_POINTER_RECORD_TYPE: u16 = 0;
+const FBPT_POINTER_RECORD_REVISION: u8 = 1;
+const BASIC_BOOT_RECORD_TYPE: u16 = 2;
+const BASIC_BOOT_RECORD_REVISION: u8 = 2;
+
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct PerformanceRecordHeader {
+    record_type: U16,
+    length: u8,
+    revision: u8,
+}
+
+assert_layout!(PerformanceRecordHeader, 4);
+
+impl PerformanceRecordHeader {
+    fn new(record_type: u16, length: usize, revision: u8) -> Self {
+        PerformanceRecordHeader {
+            record_type: U16::new(record_type),
+            length: length.try_into().unwrap(),
+            revision,
+        }
+    }
+}
+
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct FbptPointerRecord {
+    header: PerformanceRecordHeader,
+    _reserved: u32,
+    fbpt_address: U64,
+}
+
+assert_layout!(FbptPointerRecord, 16);
+
+/// Firmware Performance Data Table (FPDT)
+///
+/// This table points to the Firmware Basic Boot Performance Table (FBPT), which records how long
+/// the firmware took to hand over control to the OS. More information about this table can be
+/// found in the ACPI specification:
+/// https://uefi.org/specs/ACPI/6.5/05_ACPI_Software_Programming_Model.html#firmware-performance-data-table-fpdt
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Fpdt {
+    header: SdtHeader,
+    fbpt_pointer: FbptPointerRecord,
+}
+
+assert_layout!(Fpdt, 52);
+
+impl Fpdt {
+    pub fn new(
+        oem_id: [u8; 6],
+        oem_table_id: [u8; 8],
+        oem_revision: u32,
+        fbpt_address: u64,
+    ) -> Self {
+        let header = SdtHeader::new(
+            *b"FPDT",
+            size_of::<Fpdt>().try_into().unwrap(),
+            1,
+            oem_id,
+            oem_table_id,
+            oem_revision,
+        );
+
+        let mut fpdt = Fpdt {
+            header,
+            fbpt_pointer: FbptPointerRecord {
+                header: PerformanceRecordHeader::new(
+                    FBPT_POINTER_RECORD_TYPE,
+                    size_of::<FbptPointerRecord>(),
+                    FBPT_POINTER_RECORD_REVISION,
+                ),
+                _reserved: 0,
+                fbpt_address: U64::new(fbpt_address),
+            },
+        };
+
+        fpdt.header.checksum = checksum(&[fpdt.as_bytes()]);
+
+        fpdt
+    }
+}
+
+impl Sdt for Fpdt {
+    fn len(&self) -> usize {
+        self.as_bytes().len()
+    }
+
+    fn header(&self) -> Option<&SdtHeader> {
+        Some(&self.header)
+    }
+
+    fn to_bytes(&mut self) -> Result<Vec<u8>> {
+        Ok(self.as_bytes().to_vec())
+    }
+}
+
+/// Timestamps of the firmware boot phases, in nanoseconds since the platform reset.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BasicBootTimestamps {
+    /// The platform came out of reset and started running the firmware.
+    pub reset_end: u64,
+    /// The firmware started loading the OS loader.
+    pub os_loader_load_image_start: u64,
+    /// The firmware started running the OS loader.
+    pub os_loader_start_image_start: u64,
+    /// The OS loader asked the firmware to exit its boot services.
+    pub exit_boot_services_entry: u64,
+    /// The firmware handed over control to the OS.
+    pub exit_boot_services_exit: u64,
+}
+
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct BasicBootRecord {
+    header: PerformanceRecordHeader,
+    _reserved: u32,
+    reset_end: U64,
+    os_loader_load_image_start: U64,
+    os_loader_start_image_start: U64,
+    exit_boot_services_entry: U64,
+    exit_boot_services_exit: U64,
+}
+
+assert_layout!(BasicBootRecord, 48);
+
+/// Firmware Basic Boot Performance Table (FBPT)
+///
+/// This table is referenced by the FPDT rather than by the XSDT. Unlike the other tables, it has
+/// no standard header and no checksum, so that it can be updated after it is written in guest
+/// memory.
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Fbpt {
+    signature: [u8; 4],
+    length: U32,
+    record: BasicBootRecord,
+}
+
+assert_layout!(Fbpt, 56);
+
+impl Fbpt {
+    pub fn new(timestamps: &BasicBootTimestamps) -> Self {
+        Fbpt {
+            signature: *b"FBPT",
+            length: U32::new(size_of::<Fbpt>().try_into().unwrap()),
+            record: BasicBootRecord {
+                header: PerformanceRecordHeader::new(
+                    BASIC_BOOT_RECORD_TYPE,
+                    size_of::<BasicBootRecord>(),
+                    BASIC_BOOT_RECORD_REVISION,
+                ),
+                _reserved: 0,
+                reset_end: U64::new(timestamps.reset_end),
+                os_loader_load_image_start: U64::new(timestamps.os_loader_load_image_start),
+                os_loader_start_image_start: U64::new(timestamps.os_loader_start_image_start),
+                exit_boot_services_entry: U64::new(timestamps.exit_boot_services_entry),
+                exit_boot_services_exit: U64::new(timestamps.exit_boot_services_exit),
+            },
+        }
+    }
+}
+
+impl Sdt for Fbpt {
+    fn len(&self) -> usize {
+        self.as_bytes().len()
+    }
+
+    fn to_bytes(&mut self) -> Result<Vec<u8>> {
+        Ok(self.as_bytes().to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fpdt() {
+        let fpdt = Fpdt::new(*b"FOOBAR", *b"FOOBARFP", 0, 0x1234);
+        assert_eq!(fpdt.len(), 52);
+        assert_eq!(checksum(&[fpdt.as_bytes()]), 0);
+        // The pointer record follows the header.
+        assert_eq!(&fpdt.as_bytes()[36..40], &[0, 0, 16, 1]);
+        assert_eq!(&fpdt.as_bytes()[44..], &0x1234u64.to_le_bytes());
+    }
+
+    #[test]
+    fn test_fbpt() {
+        let timestamps = BasicBootTimestamps {
+            reset_end: 1,
+            os_loader_load_image_start: 2,
+            os_loader_start_image_start: 3,
+            exit_boot_services_entry: 4,
+            exit_boot_services_exit: 5,
+        };
+        let fbpt = Fbpt::new(&timestamps);
+        let bytes = fbpt.as_bytes();
+        assert_eq!(fbpt.len(), 56);
+        assert_eq!(&bytes[..8], b"FBPT\x38\0\0\0");
+        assert_eq!(&bytes[8..12], &[2, 0, 48, 2]);
+        assert_eq!(&bytes[16..24], &1u64.to_le_bytes());
+        assert_eq!(&bytes[48..], &5u64.to_le_bytes());
+    }
+}
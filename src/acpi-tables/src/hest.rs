@@ -0,0 +1,443 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use alloc::vec::Vec;
+use core::mem::size_of;
+
+use zerocopy::{Immutable, IntoBytes};
+
+use crate::endian::{U16, U32, U64};
+use crate::{AccessSize, AddressSpace, GenericAddressStructure, Result, Sdt, SdtHeader, checksum};
+
+/// The error source is signaled with a non-maskable interrupt.
+pub const HEST_NOTIFY_NMI: u8 = 4;
+/// The error source is signaled with a global system interrupt, through the Hardware Error
+/// Device (PNP0C33).
+pub const HEST_NOTIFY_GSIV: u8 = 10;
+
+const HEST_SOURCE_GHES: u16 = 9;
+const HEST_SOURCE_GHES_V2: u16 = 10;
+// The error source has no related source.
+const HEST_NO_RELATED_SOURCE: u16 = 0xffff;
+
+// A 64-bit register accessed with a single qword access, which is always valid.
+fn qword_register(address: u64) -> GenericAddressStructure {
+    GenericAddressStructure::new(
+        AddressSpace::SystemMemory,
+        64,
+        0,
+        AccessSize::QWord,
+        address,
+    )
+    .unwrap()
+}
+
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct HardwareErrorNotification {
+    notification_type: u8,
+    length: u8,
+    configuration_write_enable: U16,
+    poll_interval: U32,
+    vector: U32,
+    switch_to_polling_threshold_value: U32,
+    switch_to_polling_threshold_window: U32,
+    error_threshold_value: U32,
+    error_threshold_window: U32,
+}
+
+assert_layout!(HardwareErrorNotification, 28);
+
+/// Generic Hardware Error Source (GHES)
+///
+/// The guest reads the errors of this source from a Generic Error Status Block, whose address
+/// is stored at `error_status_address`, and acknowledges them by clearing the block status.
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Ghes {
+    source_type: U16,
+    source_id: U16,
+    related_source_id: U16,
+    flags: u8,
+    enabled: u8,
+    number_of_records_to_preallocate: U32,
+    max_sections_per_record: U32,
+    max_raw_data_length: U32,
+    error_status_address: GenericAddressStructure,
+    notification: HardwareErrorNotification,
+    error_status_block_length: U32,
+}
+
+assert_layout!(Ghes, 64, error_status_address: 20, notification: 32, error_status_block_length: 60);
+
+impl Ghes {
+    pub fn new(
+        source_id: u16,
+        notification_type: u8,
+        vector: u32,
+        error_status_address: u64,
+        error_status_block_length: u32,
+    ) -> Self {
+        Ghes {
+            source_type: U16::new(HEST_SOURCE_GHES),
+            source_id: U16::new(source_id),
+            related_source_id: U16::new(HEST_NO_RELATED_SOURCE),
+            flags: 0,
+            enabled: 1,
+            number_of_records_to_preallocate: U32::new(1),
+            max_sections_per_record: U32::new(1),
+            max_raw_data_length: U32::new(0),
+            error_status_address: qword_register(error_status_address),
+            notification: HardwareErrorNotification {
+                notification_type,
+                length: size_of::<HardwareErrorNotification>().try_into().unwrap(),
+                vector: U32::new(vector),
+                ..Default::default()
+            },
+            error_status_block_length: U32::new(error_status_block_length),
+        }
+    }
+}
+
+/// Generic Hardware Error Source, version 2 (GHESv2)
+///
+/// The guest reads the errors of this source from a Generic Error Status Block, whose address
+/// is stored at `error_status_address`, and acknowledges them by setting bit 0 of the register
+/// at `read_ack_address`.
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GhesV2 {
+    ghes: Ghes,
+    read_ack_register: GenericAddressStructure,
+    read_ack_preserve: U64,
+    read_ack_write: U64,
+}
+
+assert_layout!(GhesV2, 92);
+
+impl GhesV2 {
+    pub fn new(
+        source_id: u16,
+        notification_type: u8,
+        vector: u32,
+        error_status_address: u64,
+        error_status_block_length: u32,
+        read_ack_address: u64,
+    ) -> Self {
+        let mut ghes = Ghes::new(
+            source_id,
+            notification_type,
+            vector,
+            error_status_address,
+            error_status_block_length,
+        );
+        ghes.source_type = U16::new(HEST_SOURCE_GHES_V2);
+        GhesV2 {
+            ghes,
+            read_ack_register: qword_register(read_ack_address),
+            // The guest acknowledges an error by setting bit 0, preserving the other bits.
+            read_ack_preserve: U64::new(!1),
+            read_ack_write: U64::new(1),
+        }
+    }
+}
+
+/// Error source of a [`Hest`]
+#[derive(Clone, Copy, Debug)]
+pub enum HestErrorSource {
+    /// Generic Hardware Error Source.
+    Ghes(Ghes),
+    /// Generic Hardware Error Source, version 2.
+    GhesV2(GhesV2),
+}
+
+impl HestErrorSource {
+    fn as_bytes(&self) -> &[u8] {
+        match self {
+            HestErrorSource::Ghes(ghes) => ghes.as_bytes(),
+            HestErrorSource::GhesV2(ghes) => ghes.as_bytes(),
+        }
+    }
+}
+
+impl From<Ghes> for HestErrorSource {
+    fn from(ghes: Ghes) -> Self {
+        HestErrorSource::Ghes(ghes)
+    }
+}
+
+impl From<GhesV2> for HestErrorSource {
+    fn from(ghes: GhesV2) -> Self {
+        HestErrorSource::GhesV2(ghes)
+    }
+}
+
+/// Hardware Error Source Table (HEST)
+///
+/// This table lists the sources of hardware errors the guest is notified about. More information
+/// about this table can be found in the ACPI specification:
+/// https://uefi.org/specs/ACPI/6.5/18_Platform_Error_Interfaces.html#acpi-error-source
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Hest {
+    header: SdtHeader,
+    error_source_count: U32,
+    sources: Vec<u8>,
+}
+
+impl Hest {
+    pub fn new(
+        oem_id: [u8; 6],
+        oem_table_id: [u8; 8],
+        oem_revision: u32,
+        sources: Vec<HestErrorSource>,
+    ) -> Self {
+        let error_source_count = sources.len();
+        let sources: Vec<u8> = sources
+            .iter()
+            .flat_map(HestErrorSource::as_bytes)
+            .copied()
+            .collect();
+        let length = size_of::<SdtHeader>() + size_of::<U32>() + sources.len();
+        let header = SdtHeader::new(
+            *b"HEST",
+            length.try_into().unwrap(),
+            1,
+            oem_id,
+            oem_table_id,
+            oem_revision,
+        );
+
+        let mut hest = Hest {
+            header,
+            error_source_count: U32::new(error_source_count.try_into().unwrap()),
+            sources,
+        };
+
+        hest.header.checksum = checksum(&[
+            hest.header.as_bytes(),
+            hest.error_source_count.as_bytes(),
+            hest.sources.as_bytes(),
+        ]);
+
+        hest
+    }
+}
+
+impl Sdt for Hest {
+    fn len(&self) -> usize {
+        self.header.length.get().try_into().unwrap()
+    }
+
+    fn header(&self) -> Option<&SdtHeader> {
+        Some(&self.header)
+    }
+
+    fn to_bytes(&mut self) -> Result<Vec<u8>> {
+        Ok([
+            self.header.as_bytes(),
+            self.error_source_count.as_bytes(),
+            self.sources.as_bytes(),
+        ]
+        .concat())
+    }
+}
+
+// GUID of the UEFI Platform Memory Error section, in its mixed-endian binary form.
+const CPER_SEC_PLATFORM_MEM: [u8; 16] = [
+    0x14, 0x11, 0xbc, 0xa5, 0x64, 0x6f, 0xde, 0x4e, 0xb8, 0x63, 0x3e, 0x83, 0xed, 0x7c, 0x83, 0xb1,
+];
+// The error was not corrected, but the guest can recover from it.
+const CPER_SEV_RECOVERABLE: u32 = 0;
+// The block holds an uncorrectable error, in a single data entry.
+const BLOCK_STATUS_UNCORRECTABLE: u32 = 1 << 0;
+const BLOCK_STATUS_ONE_ENTRY: u32 = 1 << 4;
+const GENERIC_ERROR_DATA_REVISION: u16 = 0x300;
+const CPER_MEM_VALID_PA: u64 = 1 << 1;
+const CPER_MEM_VALID_PA_MASK: u64 = 1 << 2;
+const CPER_MEM_VALID_ERROR_TYPE: u64 = 1 << 14;
+const CPER_MEM_ERROR_TYPE_MULTI_ECC: u8 = 3;
+
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct GenericErrorStatus {
+    block_status: U32,
+    raw_data_offset: U32,
+    raw_data_length: U32,
+    data_length: U32,
+    error_severity: U32,
+}
+
+assert_layout!(GenericErrorStatus, 20);
+
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct GenericErrorDataEntry {
+    section_type: [u8; 16],
+    error_severity: U32,
+    revision: U16,
+    validation_bits: u8,
+    flags: u8,
+    error_data_length: U32,
+    fru_id: [u8; 16],
+    fru_text: [u8; 20],
+    timestamp: U64,
+}
+
+assert_layout!(GenericErrorDataEntry, 72, timestamp: 64);
+
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct MemoryErrorSection {
+    validation_bits: U64,
+    error_status: U64,
+    physical_address: U64,
+    physical_address_mask: U64,
+    node: U16,
+    card: U16,
+    module: U16,
+    bank: U16,
+    device: U16,
+    row: U16,
+    column: U16,
+    bit_position: U16,
+    requestor_id: U64,
+    responder_id: U64,
+    target_id: U64,
+    error_type: u8,
+    extended: u8,
+    rank_number: U16,
+    card_handle: U16,
+    module_handle: U16,
+}
+
+assert_layout!(MemoryErrorSection, 80);
+
+/// Generic Error Status Block reporting an uncorrected error in a page of memory
+///
+/// This holds a single Common Platform Error Record (CPER) section, described in the UEFI
+/// specification:
+/// https://uefi.org/specs/UEFI/2.10/Apx_N_Common_Platform_Error_Record.html#memory-error-section
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MemoryErrorStatus {
+    status: GenericErrorStatus,
+    entry: GenericErrorDataEntry,
+    section: MemoryErrorSection,
+}
+
+assert_layout!(MemoryErrorStatus, 172);
+
+impl MemoryErrorStatus {
+    /// Create a block reporting an error in the page of `page_size` bytes at `physical_address`.
+    pub fn new(physical_address: u64, page_size: u64) -> Self {
+        let section_length = size_of::<MemoryErrorSection>();
+        let data_length = size_of::<GenericErrorDataEntry>() + section_length;
+        MemoryErrorStatus {
+            status: GenericErrorStatus {
+                block_status: U32::new(BLOCK_STATUS_UNCORRECTABLE | BLOCK_STATUS_ONE_ENTRY),
+                data_length: U32::new(data_length.try_into().unwrap()),
+                error_severity: U32::new(CPER_SEV_RECOVERABLE),
+                ..Default::default()
+            },
+            entry: GenericErrorDataEntry {
+                section_type: CPER_SEC_PLATFORM_MEM,
+                error_severity: U32::new(CPER_SEV_RECOVERABLE),
+                revision: U16::new(GENERIC_ERROR_DATA_REVISION),
+                error_data_length: U32::new(section_length.try_into().unwrap()),
+                ..Default::default()
+            },
+            section: MemoryErrorSection {
+                validation_bits: U64::new(
+                    CPER_MEM_VALID_PA | CPER_MEM_VALID_PA_MASK | CPER_MEM_VALID_ERROR_TYPE,
+                ),
+                physical_address: U64::new(physical_address),
+                physical_address_mask: U64::new(!(page_size - 1)),
+                error_type: CPER_MEM_ERROR_TYPE_MULTI_ECC,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vm_memory::{Bytes, GuestAddress, GuestMemoryMmap};
+
+    use super::*;
+
+    #[test]
+    fn test_hest() {
+        let ghes = GhesV2::new(0, HEST_NOTIFY_GSIV, 5, 0x1000, 0x400, 0x1008);
+        assert_eq!(ghes.as_bytes().len(), 92);
+        let ghes_v1 = Ghes::new(1, HEST_NOTIFY_NMI, 0, 0x2000, 0x400);
+        assert_eq!(ghes_v1.as_bytes().len(), 64);
+        let mut hest = Hest::new(
+            *b"FOOBAR",
+            *b"FOOBARHE",
+            0,
+            vec![ghes.into(), ghes_v1.into()],
+        );
+        assert_eq!(hest.len(), 36 + 4 + 92 + 64);
+
+        let mem: GuestMemoryMmap =
+            GuestMemoryMmap::from_ranges(&[(GuestAddress(0), 0x1000)]).unwrap();
+        hest.write_to_guest(&mem, GuestAddress(0)).unwrap();
+        let mut bytes = vec![0u8; hest.len()];
+        mem.read_slice(&mut bytes, GuestAddress(0)).unwrap();
+        assert_eq!(checksum(&[&bytes]), 0);
+        assert_eq!(&bytes[..4], b"HEST");
+        assert_eq!(&bytes[36..40], &2u32.to_le_bytes());
+        // A GHESv2 source, with no related source, which is enabled.
+        assert_eq!(&bytes[40..48], &[10, 0, 0, 0, 0xff, 0xff, 0, 1]);
+        // The error status address register.
+        assert_eq!(&bytes[60..64], &[0, 64, 0, 4]);
+        assert_eq!(&bytes[64..72], &0x1000u64.to_le_bytes());
+        // The notification structure.
+        assert_eq!(&bytes[72..74], &[HEST_NOTIFY_GSIV, 28]);
+        assert_eq!(&bytes[80..84], &5u32.to_le_bytes());
+        assert_eq!(&bytes[100..104], &0x400u32.to_le_bytes());
+        // The read ack register.
+        assert_eq!(&bytes[108..116], &0x1008u64.to_le_bytes());
+        assert_eq!(&bytes[116..124], &(!1u64).to_le_bytes());
+        assert_eq!(&bytes[124..132], &1u64.to_le_bytes());
+        // A GHES source, without read ack register.
+        assert_eq!(&bytes[132..136], &[9, 0, 1, 0]);
+        assert_eq!(&bytes[156..164], &0x2000u64.to_le_bytes());
+        assert_eq!(&bytes[164..166], &[HEST_NOTIFY_NMI, 28]);
+        assert_eq!(&bytes[192..196], &0x400u32.to_le_bytes());
+    }
+
+    #[test]
+    fn test_memory_error_status() {
+        let status = MemoryErrorStatus::new(0x1234_5000, 0x1000);
+        let bytes = status.as_bytes();
+        assert_eq!(bytes.len(), 20 + 72 + 80);
+        // One uncorrectable entry, of 152 bytes.
+        assert_eq!(&bytes[..4], &0x11u32.to_le_bytes());
+        assert_eq!(&bytes[12..16], &152u32.to_le_bytes());
+        assert_eq!(&bytes[20..36], &CPER_SEC_PLATFORM_MEM);
+        assert_eq!(&bytes[40..42], &0x300u16.to_le_bytes());
+        assert_eq!(&bytes[44..48], &80u32.to_le_bytes());
+        // The memory error section.
+        assert_eq!(&bytes[92..100], &0x4006u64.to_le_bytes());
+        assert_eq!(&bytes[108..116], &0x1234_5000u64.to_le_bytes());
+        assert_eq!(&bytes[116..124], &0xffff_ffff_ffff_f000u64.to_le_bytes());
+        assert_eq!(bytes[164], CPER_MEM_ERROR_TYPE_MULTI_ECC);
+    }
+}
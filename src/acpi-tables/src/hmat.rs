@@ -0,0 +1,434 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use alloc::vec::Vec;
+use core::mem::size_of;
+
+use zerocopy::{Immutable, IntoBytes};
+
+use crate::endian::{U16, U32, U64};
+use crate::{AcpiError, Result, Sdt, SdtHeader, checksum};
+
+const HMAT_MEMORY_PROXIMITY_DOMAIN_ATTRIBUTES: u16 = 0;
+const HMAT_SYSTEM_LOCALITY_INFO: u16 = 1;
+const HMAT_MEMORY_SIDE_CACHE_INFO: u16 = 2;
+// The initiator proximity domain of the memory proximity domain attributes is valid.
+const HMAT_INITIATOR_VALID_FLAG: u16 = 1 << 0;
+// Caches are described up to the third level.
+const HMAT_MAX_CACHE_LEVEL: u8 = 3;
+
+/// Level of the memory hierarchy the entries of a [`SystemLocalityInfo`] structure describe.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum MemoryHierarchy {
+    /// The memory of the target proximity domains.
+    Memory = 0,
+    /// The first level memory side cache of the target proximity domains.
+    FirstLevelCache = 1,
+    /// The second level memory side cache of the target proximity domains.
+    SecondLevelCache = 2,
+    /// The third level memory side cache of the target proximity domains.
+    ThirdLevelCache = 3,
+}
+
+/// Attribute the entries of a [`SystemLocalityInfo`] structure hold.
+///
+/// Latencies are in picoseconds and bandwidths in MB/s, once multiplied by the entry base unit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum LocalityDataType {
+    /// Latency of both reads and writes.
+    AccessLatency = 0,
+    /// Latency of reads.
+    ReadLatency = 1,
+    /// Latency of writes.
+    WriteLatency = 2,
+    /// Bandwidth of both reads and writes.
+    AccessBandwidth = 3,
+    /// Bandwidth of reads.
+    ReadBandwidth = 4,
+    /// Bandwidth of writes.
+    WriteBandwidth = 5,
+}
+
+/// Associativity of a memory side cache.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum CacheAssociativity {
+    /// The associativity is not reported.
+    None = 0,
+    /// The cache is direct mapped.
+    DirectMapped = 1,
+    /// The cache uses a complex indexing scheme.
+    ComplexCacheIndexing = 2,
+}
+
+/// Write policy of a memory side cache.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum CacheWritePolicy {
+    /// The write policy is not reported.
+    None = 0,
+    /// Writes are only propagated to memory on evictions.
+    WriteBack = 1,
+    /// Writes are propagated to memory immediately.
+    WriteThrough = 2,
+}
+
+// clippy doesn't understand that we actually "use" the fields of this struct when we serialize
+// them as bytes in guest memory, so here we just ignore dead code to avoid having to name
+// everything with an underscore prefix
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Copy, Clone, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MemoryProximityDomainAttributes {
+    r#type: U16,
+    _reserved1: U16,
+    length: U32,
+    flags: U16,
+    _reserved2: U16,
+    initiator_proximity_domain: U32,
+    memory_proximity_domain: U32,
+    _reserved3: U32,
+    _reserved4: U64,
+    _reserved5: U64,
+}
+
+assert_layout!(MemoryProximityDomainAttributes, 40);
+
+impl MemoryProximityDomainAttributes {
+    /// Create a structure attaching the memory of `memory_proximity_domain` to the proximity
+    /// domain of its initiator, such as the processors of the node it belongs to, if any.
+    pub fn new(memory_proximity_domain: u32, initiator_proximity_domain: Option<u32>) -> Self {
+        let flags = match initiator_proximity_domain {
+            Some(_) => HMAT_INITIATOR_VALID_FLAG,
+            None => 0,
+        };
+        MemoryProximityDomainAttributes {
+            r#type: U16::new(HMAT_MEMORY_PROXIMITY_DOMAIN_ATTRIBUTES),
+            _reserved1: U16::ZERO,
+            length: U32::new(size_of::<Self>().try_into().unwrap()),
+            flags: U16::new(flags),
+            _reserved2: U16::ZERO,
+            initiator_proximity_domain: U32::new(initiator_proximity_domain.unwrap_or_default()),
+            memory_proximity_domain: U32::new(memory_proximity_domain),
+            _reserved3: U32::ZERO,
+            _reserved4: U64::ZERO,
+            _reserved5: U64::ZERO,
+        }
+    }
+}
+
+// clippy doesn't understand that we actually "use" the fields of this struct when we serialize
+// them as bytes in guest memory, so here we just ignore dead code to avoid having to name
+// everything with an underscore prefix
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Copy, Clone, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct SystemLocalityInfoHeader {
+    r#type: U16,
+    _reserved1: U16,
+    length: U32,
+    flags: u8,
+    data_type: u8,
+    min_transfer_size: u8,
+    _reserved2: u8,
+    initiators: U32,
+    targets: U32,
+    _reserved3: U32,
+    entry_base_unit: U64,
+}
+
+assert_layout!(SystemLocalityInfoHeader, 32);
+
+/// System Locality Latency and Bandwidth Information structure
+///
+/// This structure holds the latency or the bandwidth between every initiator proximity domain and
+/// every target proximity domain, for one level of the memory hierarchy. An entry of 0 means the
+/// target is not reachable from the initiator.
+#[derive(Clone, Debug)]
+pub struct SystemLocalityInfo {
+    header: SystemLocalityInfoHeader,
+    initiators: Vec<U32>,
+    targets: Vec<U32>,
+    entries: Vec<U16>,
+}
+
+impl SystemLocalityInfo {
+    /// Create the structure, whose `entries` hold the attribute from each initiator to all the
+    /// targets, in units of `entry_base_unit`.
+    pub fn new(
+        hierarchy: MemoryHierarchy,
+        data_type: LocalityDataType,
+        entry_base_unit: u64,
+        initiators: &[u32],
+        targets: &[u32],
+        entries: &[u16],
+    ) -> Result<Self> {
+        if initiators.len() * targets.len() != entries.len() {
+            return Err(AcpiError::InvalidHmatLocalityEntries);
+        }
+        let length = size_of::<SystemLocalityInfoHeader>()
+            + (initiators.len() + targets.len()) * size_of::<U32>()
+            + entries.len() * size_of::<U16>();
+        let header = SystemLocalityInfoHeader {
+            r#type: U16::new(HMAT_SYSTEM_LOCALITY_INFO),
+            _reserved1: U16::ZERO,
+            length: U32::new(length.try_into().unwrap()),
+            flags: hierarchy as u8,
+            data_type: data_type as u8,
+            min_transfer_size: 0,
+            _reserved2: 0,
+            initiators: U32::new(initiators.len().try_into().unwrap()),
+            targets: U32::new(targets.len().try_into().unwrap()),
+            _reserved3: U32::ZERO,
+            entry_base_unit: U64::new(entry_base_unit),
+        };
+        Ok(SystemLocalityInfo {
+            header,
+            initiators: initiators.iter().copied().map(U32::new).collect(),
+            targets: targets.iter().copied().map(U32::new).collect(),
+            entries: entries.iter().copied().map(U16::new).collect(),
+        })
+    }
+
+    /// Serialize the structure, to be appended to the structures of an [`Hmat`].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        [
+            self.header.as_bytes(),
+            self.initiators.as_bytes(),
+            self.targets.as_bytes(),
+            self.entries.as_bytes(),
+        ]
+        .concat()
+    }
+}
+
+// clippy doesn't understand that we actually "use" the fields of this struct when we serialize
+// them as bytes in guest memory, so here we just ignore dead code to avoid having to name
+// everything with an underscore prefix
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Copy, Clone, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MemorySideCacheInfo {
+    r#type: U16,
+    _reserved1: U16,
+    length: U32,
+    memory_proximity_domain: U32,
+    _reserved2: U32,
+    cache_size: U64,
+    cache_attributes: U32,
+    _reserved3: U16,
+    smbios_handles: U16,
+}
+
+assert_layout!(MemorySideCacheInfo, 32);
+
+impl MemorySideCacheInfo {
+    /// Create a structure describing the memory side cache at `level`, out of `total_levels`, in
+    /// front of the memory of `memory_proximity_domain`.
+    pub fn new(
+        memory_proximity_domain: u32,
+        cache_size: u64,
+        total_levels: u8,
+        level: u8,
+        associativity: CacheAssociativity,
+        write_policy: CacheWritePolicy,
+        line_size: u16,
+    ) -> Result<Self> {
+        if total_levels > HMAT_MAX_CACHE_LEVEL || level == 0 || level > total_levels {
+            return Err(AcpiError::InvalidHmatCacheLevel(level));
+        }
+        let cache_attributes = u32::from(total_levels)
+            | u32::from(level) << 4
+            | (associativity as u32) << 8
+            | (write_policy as u32) << 12
+            | u32::from(line_size) << 16;
+        Ok(MemorySideCacheInfo {
+            r#type: U16::new(HMAT_MEMORY_SIDE_CACHE_INFO),
+            _reserved1: U16::ZERO,
+            length: U32::new(size_of::<Self>().try_into().unwrap()),
+            memory_proximity_domain: U32::new(memory_proximity_domain),
+            _reserved2: U32::ZERO,
+            cache_size: U64::new(cache_size),
+            cache_attributes: U32::new(cache_attributes),
+            _reserved3: U16::ZERO,
+            smbios_handles: U16::ZERO,
+        })
+    }
+}
+
+// clippy doesn't understand that we actually "use" the fields of this struct when we serialize
+// them as bytes in guest memory, so here we just ignore dead code to avoid having to name
+// everything with an underscore prefix
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Clone, Debug, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct HmatHeader {
+    sdt: SdtHeader,
+    _reserved: U32,
+}
+
+assert_layout!(HmatHeader, 40);
+
+/// Heterogeneous Memory Attribute Table (HMAT)
+///
+/// This table describes the performance of the memory of the proximity domains of the SRAT as
+/// seen from the initiators, such as processors, and the memory side caches in front of it. The
+/// guest uses it to tell fast memory from slower tiers. More information about this table can be
+/// found in the ACPI specification:
+/// https://uefi.org/specs/ACPI/6.5/05_ACPI_Software_Programming_Model.html#heterogeneous-memory-attribute-table-hmat
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Hmat {
+    header: HmatHeader,
+    structures: Vec<u8>,
+}
+
+impl Hmat {
+    /// Create an HMAT table holding the given memory proximity domain attributes, system locality
+    /// and memory side cache structures.
+    pub fn new(
+        oem_id: [u8; 6],
+        oem_table_id: [u8; 8],
+        oem_revision: u32,
+        structures: Vec<u8>,
+    ) -> Self {
+        let length = size_of::<HmatHeader>() + structures.len();
+        let sdt_header = SdtHeader::new(
+            *b"HMAT",
+            length.try_into().unwrap(),
+            2,
+            oem_id,
+            oem_table_id,
+            oem_revision,
+        );
+
+        let mut header = HmatHeader {
+            sdt: sdt_header,
+            _reserved: U32::ZERO,
+        };
+        header.sdt.checksum = checksum(&[header.as_bytes(), structures.as_bytes()]);
+
+        Hmat { header, structures }
+    }
+}
+
+impl Sdt for Hmat {
+    fn len(&self) -> usize {
+        self.header.sdt.length.get().try_into().unwrap()
+    }
+
+    fn header(&self) -> Option<&SdtHeader> {
+        Some(&self.header.sdt)
+    }
+
+    fn to_bytes(&mut self) -> Result<Vec<u8>> {
+        Ok([self.header.as_bytes(), self.structures.as_bytes()].concat())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vm_memory::{Bytes, GuestAddress, GuestMemoryMmap};
+
+    use super::*;
+
+    #[test]
+    fn test_hmat() {
+        let mut structures = Vec::new();
+        structures.extend_from_slice(MemoryProximityDomainAttributes::new(0, Some(0)).as_bytes());
+        structures.extend_from_slice(MemoryProximityDomainAttributes::new(1, None).as_bytes());
+        let latency = SystemLocalityInfo::new(
+            MemoryHierarchy::Memory,
+            LocalityDataType::AccessLatency,
+            1000,
+            &[0],
+            &[0, 1],
+            &[100, 300],
+        )
+        .unwrap();
+        structures.extend_from_slice(&latency.to_bytes());
+        let cache = MemorySideCacheInfo::new(
+            1,
+            0x4000_0000,
+            1,
+            1,
+            CacheAssociativity::DirectMapped,
+            CacheWritePolicy::WriteBack,
+            64,
+        )
+        .unwrap();
+        structures.extend_from_slice(cache.as_bytes());
+        let mut hmat = Hmat::new(*b"FOOBAR", *b"FOOBARHM", 0, structures);
+        assert_eq!(hmat.len(), 40 + 2 * 40 + (32 + 12 + 4) + 32);
+
+        let mem: GuestMemoryMmap =
+            GuestMemoryMmap::from_ranges(&[(GuestAddress(0), 0x1000)]).unwrap();
+        hmat.write_to_guest(&mem, GuestAddress(0)).unwrap();
+        let mut bytes = vec![0u8; hmat.len()];
+        mem.read_slice(&mut bytes, GuestAddress(0)).unwrap();
+        assert_eq!(checksum(&[&bytes]), 0);
+        assert_eq!(&bytes[..4], b"HMAT");
+        assert_eq!(bytes[8], 2);
+
+        // The first memory attributes have a valid initiator, the second ones do not.
+        let attributes = &bytes[40..80];
+        assert_eq!(&attributes[..8], &[0, 0, 0, 0, 40, 0, 0, 0]);
+        assert_eq!(&attributes[8..10], &1u16.to_le_bytes());
+        assert_eq!(&bytes[88..90], &0u16.to_le_bytes());
+        assert_eq!(&bytes[96..100], &1u32.to_le_bytes());
+
+        let locality = &bytes[120..168];
+        assert_eq!(&locality[..8], &[1, 0, 0, 0, 48, 0, 0, 0]);
+        // Memory hierarchy and data type.
+        assert_eq!(&locality[8..10], &[0, 0]);
+        assert_eq!(&locality[12..16], &1u32.to_le_bytes());
+        assert_eq!(&locality[16..20], &2u32.to_le_bytes());
+        assert_eq!(&locality[24..32], &1000u64.to_le_bytes());
+        assert_eq!(&locality[32..36], &0u32.to_le_bytes());
+        assert_eq!(&locality[36..44], &[0, 0, 0, 0, 1, 0, 0, 0]);
+        assert_eq!(&locality[44..48], &[100, 0, 44, 1]);
+
+        let cache = &bytes[168..200];
+        assert_eq!(&cache[..8], &[2, 0, 0, 0, 32, 0, 0, 0]);
+        assert_eq!(&cache[8..12], &1u32.to_le_bytes());
+        assert_eq!(&cache[16..24], &0x4000_0000u64.to_le_bytes());
+        // One level, direct mapped and write back, with 64 bytes lines.
+        assert_eq!(&cache[24..28], &0x0040_1111u32.to_le_bytes());
+    }
+
+    #[test]
+    fn test_hmat_validation() {
+        assert!(matches!(
+            SystemLocalityInfo::new(
+                MemoryHierarchy::Memory,
+                LocalityDataType::ReadBandwidth,
+                1,
+                &[0, 1],
+                &[0, 1],
+                &[10, 20, 30],
+            ),
+            Err(AcpiError::InvalidHmatLocalityEntries)
+        ));
+        for (total_levels, level) in [(1, 0), (1, 2), (4, 4)] {
+            assert!(matches!(
+                MemorySideCacheInfo::new(
+                    0,
+                    0x1000,
+                    total_levels,
+                    level,
+                    CacheAssociativity::None,
+                    CacheWritePolicy::None,
+                    64,
+                ),
+                Err(AcpiError::InvalidHmatCacheLevel(_))
+            ));
+        }
+    }
+}
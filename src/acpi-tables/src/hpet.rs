@@ -0,0 +1,131 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use alloc::vec::Vec;
+use core::mem::size_of;
+
+use zerocopy::{Immutable, IntoBytes};
+
+use crate::endian::{U16, U32};
+use crate::{GenericAddressStructure, Result, Sdt, SdtHeader, checksum};
+
+/// The HPET provides no page protection.
+pub const HPET_PAGE_PROTECTION_NONE: u8 = 0;
+/// The HPET registers are alone in a 4 KiB page.
+pub const HPET_PAGE_PROTECTION_4K: u8 = 1;
+/// The HPET registers are alone in a 64 KiB page.
+pub const HPET_PAGE_PROTECTION_64K: u8 = 2;
+
+/// High Precision Event Timer Table (HPET)
+///
+/// This table describes the event timer block of an HPET, which the guest can use as a clock
+/// source and for timer interrupts. More information about this table can be found in the IA-PC
+/// HPET specification:
+/// https://www.intel.com/content/dam/www/public/us/en/documents/technical-specifications/software-developers-hpet-spec-1-0a.pdf
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Hpet {
+    header: SdtHeader,
+    event_timer_block_id: U32,
+    base_address: GenericAddressStructure,
+    hpet_number: u8,
+    min_clock_tick: U16,
+    page_protection: u8,
+}
+
+assert_layout!(Hpet, 56, base_address: 40, min_clock_tick: 53);
+
+impl Hpet {
+    /// Create an HPET table for the event timer block at `base_address`.
+    ///
+    /// `event_timer_block_id` mirrors the low 32 bits of the General Capabilities and ID register
+    /// of the block, `min_clock_tick` is the minimum number of main counter ticks the guest may
+    /// program in periodic mode, and `page_protection` is one of the `HPET_PAGE_PROTECTION_*`
+    /// values, along with OEM attributes in its upper bits.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        oem_id: [u8; 6],
+        oem_table_id: [u8; 8],
+        oem_revision: u32,
+        event_timer_block_id: u32,
+        base_address: GenericAddressStructure,
+        hpet_number: u8,
+        min_clock_tick: u16,
+        page_protection: u8,
+    ) -> Self {
+        let header = SdtHeader::new(
+            *b"HPET",
+            size_of::<Hpet>().try_into().unwrap(),
+            1,
+            oem_id,
+            oem_table_id,
+            oem_revision,
+        );
+
+        let mut hpet = Hpet {
+            header,
+            event_timer_block_id: U32::new(event_timer_block_id),
+            base_address,
+            hpet_number,
+            min_clock_tick: U16::new(min_clock_tick),
+            page_protection,
+        };
+
+        hpet.header.checksum = checksum(&[hpet.as_bytes()]);
+
+        hpet
+    }
+}
+
+impl Sdt for Hpet {
+    fn len(&self) -> usize {
+        self.as_bytes().len()
+    }
+
+    fn header(&self) -> Option<&SdtHeader> {
+        Some(&self.header)
+    }
+
+    fn to_bytes(&mut self) -> Result<Vec<u8>> {
+        Ok(self.as_bytes().to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vm_memory::{Bytes, GuestAddress, GuestMemoryMmap};
+
+    use super::*;
+
+    #[test]
+    fn test_hpet() {
+        let base_address = GenericAddressStructure::from_raw(0, 64, 0, 0, 0xfed0_0000);
+        let mut hpet = Hpet::new(
+            *b"FOOBAR",
+            *b"FOOBARHP",
+            0,
+            0x8086_a201,
+            base_address,
+            0,
+            0x80,
+            HPET_PAGE_PROTECTION_4K,
+        );
+        assert_eq!(hpet.len(), 56);
+
+        let mem: GuestMemoryMmap =
+            GuestMemoryMmap::from_ranges(&[(GuestAddress(0), 0x1000)]).unwrap();
+        hpet.write_to_guest(&mem, GuestAddress(0)).unwrap();
+        let mut bytes = vec![0u8; hpet.len()];
+        mem.read_slice(&mut bytes, GuestAddress(0)).unwrap();
+        assert_eq!(checksum(&[&bytes]), 0);
+        assert_eq!(&bytes[..4], b"HPET");
+        assert_eq!(&bytes[36..40], &0x8086_a201u32.to_le_bytes());
+        assert_eq!(&bytes[40..52], base_address.as_bytes());
+        assert_eq!(bytes[52], 0);
+        assert_eq!(&bytes[53..55], &0x80u16.to_le_bytes());
+        assert_eq!(bytes[55], HPET_PAGE_PROTECTION_4K);
+    }
+}
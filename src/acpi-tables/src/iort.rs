@@ -0,0 +1,571 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use alloc::vec::Vec;
+use core::mem::size_of;
+
+use zerocopy::{Immutable, IntoBytes};
+
+use crate::endian::{U16, U32, U64};
+use crate::{AcpiError, Result, Sdt, SdtHeader, checksum};
+
+const IORT_NODE_ITS_GROUP: u8 = 0;
+const IORT_NODE_NAMED_COMPONENT: u8 = 1;
+const IORT_NODE_PCI_ROOT_COMPLEX: u8 = 2;
+const IORT_NODE_SMMU_V3: u8 = 4;
+// Memory access properties of a cache coherent device.
+const IORT_CCA_COHERENT: u32 = 1;
+const IORT_MEMORY_ACCESS_COHERENT: u8 = 1;
+// The SMMUv3 accesses memory coherently.
+const IORT_SMMU_V3_COHACC_OVERRIDE: u32 = 1;
+// The ID mapping maps all the requests of the node to a single output ID.
+const IORT_ID_MAPPING_SINGLE: u32 = 1;
+// Number of requester IDs of a PCI segment with a single bus.
+const PCI_SEGMENT_REQUESTER_IDS: u32 = 0x100;
+
+// clippy doesn't understand that we actually "use" the fields of this struct when we serialize
+// them as bytes in guest memory, so here we just ignore dead code to avoid having to name
+// everything with an underscore prefix
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Copy, Clone, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct IortNodeHeader {
+    r#type: u8,
+    length: U16,
+    revision: u8,
+    identifier: U32,
+    id_mapping_count: U32,
+    id_mapping_offset: U32,
+}
+
+assert_layout!(IortNodeHeader, 16);
+
+// clippy doesn't understand that we actually "use" the fields of this struct when we serialize
+// them as bytes in guest memory, so here we just ignore dead code to avoid having to name
+// everything with an underscore prefix
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Copy, Clone, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct IdMapping {
+    input_base: U32,
+    // Number of IDs in the range, minus one.
+    id_count: U32,
+    output_base: U32,
+    output_reference: U32,
+    flags: U32,
+}
+
+assert_layout!(IdMapping, 20);
+
+// clippy doesn't understand that we actually "use" the fields of this struct when we serialize
+// them as bytes in guest memory, so here we just ignore dead code to avoid having to name
+// everything with an underscore prefix
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Copy, Clone, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct NamedComponentData {
+    flags: U32,
+    cache_coherent: U32,
+    allocation_hints: u8,
+    _reserved: U16,
+    memory_access_flags: u8,
+    memory_address_size_limit: u8,
+}
+
+assert_layout!(NamedComponentData, 13);
+
+// clippy doesn't understand that we actually "use" the fields of this struct when we serialize
+// them as bytes in guest memory, so here we just ignore dead code to avoid having to name
+// everything with an underscore prefix
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Copy, Clone, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct RootComplexData {
+    cache_coherent: U32,
+    allocation_hints: u8,
+    _reserved1: U16,
+    memory_access_flags: u8,
+    ats_attribute: U32,
+    pci_segment: U32,
+    memory_address_size_limit: u8,
+    _reserved2: [u8; 3],
+}
+
+assert_layout!(RootComplexData, 20);
+
+// clippy doesn't understand that we actually "use" the fields of this struct when we serialize
+// them as bytes in guest memory, so here we just ignore dead code to avoid having to name
+// everything with an underscore prefix
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Copy, Clone, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Smmuv3Data {
+    base_address: U64,
+    flags: U32,
+    _reserved: U32,
+    vatos_address: U64,
+    model: U32,
+    event_gsiv: U32,
+    pri_gsiv: U32,
+    gerr_gsiv: U32,
+    sync_gsiv: U32,
+    proximity_domain: U32,
+    device_id_mapping_index: U32,
+}
+
+assert_layout!(Smmuv3Data, 52);
+
+/// Reference to a node of an IORT table, returned by the [`IortBuilder`] the node is added to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IortNodeRef(usize);
+
+/// Range of IDs of the requests of a node, mapped to IDs of another node
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IortIdMapping {
+    input_base: u32,
+    id_count: u32,
+    output_base: u32,
+    output: IortNodeRef,
+    flags: u32,
+}
+
+impl IortIdMapping {
+    /// Map the `id_count` IDs starting at `input_base` to the IDs starting at `output_base` of
+    /// the `output` node, which must be an ITS group or an SMMUv3.
+    pub fn new(input_base: u32, id_count: u32, output_base: u32, output: IortNodeRef) -> Self {
+        IortIdMapping {
+            input_base,
+            id_count,
+            output_base,
+            output,
+            flags: 0,
+        }
+    }
+
+    /// Map the requests of the node which have no input ID, such as the MSIs of an SMMUv3, to
+    /// the ID `output_id` of the `output` node.
+    pub fn single(output_id: u32, output: IortNodeRef) -> Self {
+        IortIdMapping {
+            input_base: 0,
+            id_count: 1,
+            output_base: output_id,
+            output,
+            flags: IORT_ID_MAPPING_SINGLE,
+        }
+    }
+}
+
+/// Wired interrupts of an SMMUv3, as GSIVs
+///
+/// An interrupt left to 0 is signaled through an MSI instead.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Smmuv3Interrupts {
+    /// Event queue interrupt.
+    pub event: u32,
+    /// PRI queue interrupt.
+    pub pri: u32,
+    /// Global error interrupt.
+    pub gerr: u32,
+    /// CMD_SYNC completion interrupt.
+    pub sync: u32,
+}
+
+#[derive(Clone, Debug)]
+struct IortNode {
+    r#type: u8,
+    revision: u8,
+    // Node data following the header, padded to a multiple of 4 bytes.
+    data: Vec<u8>,
+    id_mappings: Vec<IortIdMapping>,
+}
+
+impl IortNode {
+    fn len(&self) -> usize {
+        size_of::<IortNodeHeader>()
+            + self.data.len()
+            + self.id_mappings.len() * size_of::<IdMapping>()
+    }
+}
+
+// clippy doesn't understand that we actually "use" the fields of this struct when we serialize
+// them as bytes in guest memory, so here we just ignore dead code to avoid having to name
+// everything with an underscore prefix
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Debug, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct IortHeader {
+    sdt: SdtHeader,
+    node_count: U32,
+    node_offset: U32,
+    _reserved: U32,
+}
+
+assert_layout!(IortHeader, 48);
+
+/// IO Remapping Table (IORT)
+///
+/// This table describes how the requester IDs of the PCI root complexes are translated into the
+/// device IDs of the GIC Interrupt Translation Services, which is how an Arm guest routes the MSIs
+/// of its PCI devices when booted through ACPI. The ITSs themselves are described in the MADT.
+/// More information about this table can be found in the Arm specification:
+/// https://developer.arm.com/documentation/den0049/latest
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Iort {
+    header: IortHeader,
+    nodes: Vec<u8>,
+}
+
+impl Iort {
+    /// Create an IORT table with a single ITS group holding the ITSs with identifiers `its_ids`,
+    /// and a root complex per PCI segment in `pci_segments`.
+    ///
+    /// The requester IDs of a segment are mapped to the device IDs starting at the segment
+    /// number in bits [31:16].
+    pub fn new(
+        oem_id: [u8; 6],
+        oem_table_id: [u8; 8],
+        oem_revision: u32,
+        its_ids: &[u32],
+        pci_segments: &[u16],
+    ) -> Self {
+        let mut builder = IortBuilder::new();
+        let its_group = builder.its_group(its_ids);
+        for segment in pci_segments {
+            let id_mapping = IortIdMapping::new(
+                0,
+                PCI_SEGMENT_REQUESTER_IDS,
+                u32::from(*segment) << 16,
+                its_group,
+            );
+            builder.root_complex(*segment, &[id_mapping]);
+        }
+        // The ID mappings all cover a whole PCI segment and output to the ITS group.
+        builder.build(oem_id, oem_table_id, oem_revision).unwrap()
+    }
+}
+
+impl Sdt for Iort {
+    fn len(&self) -> usize {
+        self.header.sdt.length.get().try_into().unwrap()
+    }
+
+    fn header(&self) -> Option<&SdtHeader> {
+        Some(&self.header.sdt)
+    }
+
+    fn to_bytes(&mut self) -> Result<Vec<u8>> {
+        Ok([self.header.as_bytes(), self.nodes.as_bytes()].concat())
+    }
+}
+
+/// Builder of an [`Iort`], one node at a time
+///
+/// Adding a node returns a reference to it, which the ID mappings of the nodes added after it use
+/// as their output. The offsets of the nodes and the references of the ID mappings are resolved
+/// when the table is built.
+#[derive(Clone, Debug, Default)]
+pub struct IortBuilder {
+    nodes: Vec<IortNode>,
+}
+
+impl IortBuilder {
+    /// Create a builder with no node.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn node(
+        &mut self,
+        r#type: u8,
+        revision: u8,
+        mut data: Vec<u8>,
+        id_mappings: &[IortIdMapping],
+    ) -> IortNodeRef {
+        data.resize(data.len().next_multiple_of(4), 0);
+        self.nodes.push(IortNode {
+            r#type,
+            revision,
+            data,
+            id_mappings: id_mappings.to_vec(),
+        });
+        IortNodeRef(self.nodes.len() - 1)
+    }
+
+    /// Add an ITS group holding the ITSs with identifiers `its_ids`, as described in the MADT.
+    pub fn its_group(&mut self, its_ids: &[u32]) -> IortNodeRef {
+        let mut data = U32::new(its_ids.len().try_into().unwrap())
+            .as_bytes()
+            .to_vec();
+        for its_id in its_ids {
+            data.extend_from_slice(U32::new(*its_id).as_bytes());
+        }
+        self.node(IORT_NODE_ITS_GROUP, 1, data, &[])
+    }
+
+    /// Add a cache coherent named component, the device at `name`, such as `\_SB.DMA0`, in the
+    /// ACPI namespace.
+    pub fn named_component(
+        &mut self,
+        name: &str,
+        id_mappings: &[IortIdMapping],
+    ) -> Result<IortNodeRef> {
+        if name.is_empty() || !name.bytes().all(|byte| byte.is_ascii_graphic()) {
+            return Err(AcpiError::InvalidIortName);
+        }
+        let named_component = NamedComponentData {
+            cache_coherent: U32::new(IORT_CCA_COHERENT),
+            memory_access_flags: IORT_MEMORY_ACCESS_COHERENT,
+            // The device can address the whole 64-bit space.
+            memory_address_size_limit: 64,
+            ..Default::default()
+        };
+        let data = [named_component.as_bytes(), name.as_bytes(), &[0]].concat();
+        Ok(self.node(IORT_NODE_NAMED_COMPONENT, 4, data, id_mappings))
+    }
+
+    /// Add a cache coherent root complex for the PCI segment `pci_segment`, whose requester IDs
+    /// are the input IDs of `id_mappings`.
+    pub fn root_complex(&mut self, pci_segment: u16, id_mappings: &[IortIdMapping]) -> IortNodeRef {
+        let root_complex = RootComplexData {
+            cache_coherent: U32::new(IORT_CCA_COHERENT),
+            memory_access_flags: IORT_MEMORY_ACCESS_COHERENT,
+            pci_segment: U32::new(u32::from(pci_segment)),
+            // The root complex can address the whole 64-bit space.
+            memory_address_size_limit: 64,
+            ..Default::default()
+        };
+        let data = root_complex.as_bytes().to_vec();
+        self.node(IORT_NODE_PCI_ROOT_COMPLEX, 1, data, id_mappings)
+    }
+
+    /// Add a cache coherent SMMUv3 whose registers are at `base_address`. The stream IDs of the
+    /// SMMU are the input IDs of `id_mappings`.
+    pub fn smmu_v3(
+        &mut self,
+        base_address: u64,
+        interrupts: Smmuv3Interrupts,
+        id_mappings: &[IortIdMapping],
+    ) -> IortNodeRef {
+        let smmu = Smmuv3Data {
+            base_address: U64::new(base_address),
+            flags: U32::new(IORT_SMMU_V3_COHACC_OVERRIDE),
+            event_gsiv: U32::new(interrupts.event),
+            pri_gsiv: U32::new(interrupts.pri),
+            gerr_gsiv: U32::new(interrupts.gerr),
+            sync_gsiv: U32::new(interrupts.sync),
+            ..Default::default()
+        };
+        let data = smmu.as_bytes().to_vec();
+        self.node(IORT_NODE_SMMU_V3, 4, data, id_mappings)
+    }
+
+    /// Build the IORT table, after checking that every ID mapping covers at least one ID and
+    /// outputs to an ITS group or an SMMUv3 of the table.
+    pub fn build(self, oem_id: [u8; 6], oem_table_id: [u8; 8], oem_revision: u32) -> Result<Iort> {
+        let mut offsets = Vec::with_capacity(self.nodes.len());
+        let mut offset = size_of::<IortHeader>();
+        for node in &self.nodes {
+            offsets.push(offset);
+            offset += node.len();
+        }
+
+        let mut nodes = Vec::new();
+        for (identifier, node) in self.nodes.iter().enumerate() {
+            let id_mapping_offset = if node.id_mappings.is_empty() {
+                0
+            } else {
+                size_of::<IortNodeHeader>() + node.data.len()
+            };
+            let header = IortNodeHeader {
+                r#type: node.r#type,
+                length: U16::new(
+                    node.len()
+                        .try_into()
+                        .map_err(|_| AcpiError::InvalidIortNodeLength)?,
+                ),
+                revision: node.revision,
+                identifier: U32::new(identifier.try_into().unwrap()),
+                id_mapping_count: U32::new(node.id_mappings.len().try_into().unwrap()),
+                id_mapping_offset: U32::new(id_mapping_offset.try_into().unwrap()),
+            };
+            nodes.extend_from_slice(header.as_bytes());
+            nodes.extend_from_slice(&node.data);
+
+            for id_mapping in &node.id_mappings {
+                let IortNodeRef(output) = id_mapping.output;
+                let output_type = self.nodes.get(output).map(|node| node.r#type);
+                if !matches!(output_type, Some(IORT_NODE_ITS_GROUP | IORT_NODE_SMMU_V3)) {
+                    return Err(AcpiError::InvalidIortIdMappingOutput(output));
+                }
+                if id_mapping.id_count == 0 {
+                    return Err(AcpiError::InvalidIortIdCount);
+                }
+                let id_mapping = IdMapping {
+                    input_base: U32::new(id_mapping.input_base),
+                    id_count: U32::new(id_mapping.id_count - 1),
+                    output_base: U32::new(id_mapping.output_base),
+                    output_reference: U32::new(offsets[output].try_into().unwrap()),
+                    flags: U32::new(id_mapping.flags),
+                };
+                nodes.extend_from_slice(id_mapping.as_bytes());
+            }
+        }
+
+        let length = size_of::<IortHeader>() + nodes.len();
+        let sdt_header = SdtHeader::new(
+            *b"IORT",
+            length.try_into().unwrap(),
+            3,
+            oem_id,
+            oem_table_id,
+            oem_revision,
+        );
+
+        let mut header = IortHeader {
+            sdt: sdt_header,
+            node_count: U32::new(self.nodes.len().try_into().unwrap()),
+            node_offset: U32::new(size_of::<IortHeader>().try_into().unwrap()),
+            _reserved: U32::ZERO,
+        };
+
+        header.sdt.checksum = checksum(&[header.as_bytes(), nodes.as_bytes()]);
+
+        Ok(Iort { header, nodes })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vm_memory::{Bytes, GuestAddress, GuestMemoryMmap};
+
+    use super::*;
+
+    fn read_table(iort: &mut Iort) -> Vec<u8> {
+        let mem: GuestMemoryMmap =
+            GuestMemoryMmap::from_ranges(&[(GuestAddress(0), 0x1000)]).unwrap();
+        iort.write_to_guest(&mem, GuestAddress(0)).unwrap();
+        let mut bytes = vec![0u8; iort.len()];
+        mem.read_slice(&mut bytes, GuestAddress(0)).unwrap();
+        assert_eq!(checksum(&[&bytes]), 0);
+        bytes
+    }
+
+    #[test]
+    fn test_iort() {
+        let mut iort = Iort::new(*b"FOOBAR", *b"FOOBARIO", 0, &[0], &[0, 1]);
+        // Header, ITS group with a single ITS and two root complexes.
+        assert_eq!(iort.len(), 48 + 24 + 2 * 56);
+
+        let bytes = read_table(&mut iort);
+        assert_eq!(&bytes[..4], b"IORT");
+        assert_eq!(&bytes[36..40], &3u32.to_le_bytes());
+        assert_eq!(&bytes[40..44], &48u32.to_le_bytes());
+
+        let its_group = &bytes[48..72];
+        assert_eq!(&its_group[..4], &[0, 24, 0, 1]);
+        // One ITS, with identifier 0.
+        assert_eq!(&its_group[16..24], &[1, 0, 0, 0, 0, 0, 0, 0]);
+
+        let root_complex = &bytes[128..184];
+        assert_eq!(&root_complex[..4], &[2, 56, 0, 1]);
+        assert_eq!(&root_complex[8..16], &[1, 0, 0, 0, 36, 0, 0, 0]);
+        // PCI segment 1.
+        assert_eq!(&root_complex[28..32], &1u32.to_le_bytes());
+        let id_mapping = &root_complex[36..56];
+        assert_eq!(&id_mapping[4..8], &0xffu32.to_le_bytes());
+        assert_eq!(&id_mapping[8..12], &0x1_0000u32.to_le_bytes());
+        assert_eq!(&id_mapping[12..16], &48u32.to_le_bytes());
+    }
+
+    #[test]
+    fn test_iort_builder() {
+        let mut builder = IortBuilder::new();
+        let its_group = builder.its_group(&[0, 1]);
+        let smmu = builder.smmu_v3(
+            0x900_0000,
+            Smmuv3Interrupts {
+                event: 74,
+                gerr: 77,
+                sync: 76,
+                ..Default::default()
+            },
+            &[IortIdMapping::new(0, 0x1_0000, 0, its_group)],
+        );
+        builder.root_complex(0, &[IortIdMapping::new(0, 0x100, 0, smmu)]);
+        builder
+            .named_component("\\_SB.DMA0", &[IortIdMapping::single(0x200, smmu)])
+            .unwrap();
+        let mut iort = builder.build(*b"FOOBAR", *b"FOOBARIO", 0).unwrap();
+        // Header, ITS group with two ITSs, SMMUv3, root complex and named component.
+        assert_eq!(iort.len(), 48 + 28 + 88 + 56 + 60);
+
+        let bytes = read_table(&mut iort);
+        assert_eq!(&bytes[36..40], &4u32.to_le_bytes());
+
+        let smmu = &bytes[76..164];
+        assert_eq!(&smmu[..4], &[4, 88, 0, 4]);
+        // Identifier, one ID mapping after the SMMU data.
+        assert_eq!(&smmu[4..16], &[1, 0, 0, 0, 1, 0, 0, 0, 68, 0, 0, 0]);
+        assert_eq!(&smmu[16..24], &0x900_0000u64.to_le_bytes());
+        // Event, PRI, global error and sync interrupts.
+        assert_eq!(
+            &smmu[44..60],
+            &[74, 0, 0, 0, 0, 0, 0, 0, 77, 0, 0, 0, 76, 0, 0, 0]
+        );
+        // The stream IDs are mapped to the ITS group.
+        assert_eq!(&smmu[72..76], &0xffffu32.to_le_bytes());
+        assert_eq!(&smmu[80..84], &48u32.to_le_bytes());
+
+        let root_complex = &bytes[164..220];
+        assert_eq!(&root_complex[..4], &[2, 56, 0, 1]);
+        assert_eq!(&root_complex[48..52], &76u32.to_le_bytes());
+
+        let named_component = &bytes[220..];
+        assert_eq!(&named_component[..4], &[1, 60, 0, 4]);
+        assert_eq!(&named_component[12..16], &40u32.to_le_bytes());
+        // Name, padded to a multiple of 4 bytes.
+        assert_eq!(&named_component[29..40], b"\\_SB.DMA0\0\0");
+        let id_mapping = &named_component[40..];
+        assert_eq!(&id_mapping[4..12], &[0, 0, 0, 0, 0, 2, 0, 0]);
+        assert_eq!(&id_mapping[12..16], &76u32.to_le_bytes());
+        assert_eq!(&id_mapping[16..20], &1u32.to_le_bytes());
+    }
+
+    #[test]
+    fn test_iort_validation() {
+        let mut builder = IortBuilder::new();
+        let its_group = builder.its_group(&[0]);
+        let root_complex = builder.root_complex(0, &[]);
+        assert!(matches!(
+            builder.named_component("DMA 0", &[]),
+            Err(AcpiError::InvalidIortName)
+        ));
+
+        let mut invalid = builder.clone();
+        invalid.root_complex(1, &[IortIdMapping::new(0, 0x100, 0, root_complex)]);
+        assert!(matches!(
+            invalid.build(*b"FOOBAR", *b"FOOBARIO", 0),
+            Err(AcpiError::InvalidIortIdMappingOutput(1))
+        ));
+        let mut invalid = builder.clone();
+        invalid.root_complex(1, &[IortIdMapping::new(0, 0x100, 0, IortNodeRef(5))]);
+        assert!(matches!(
+            invalid.build(*b"FOOBAR", *b"FOOBARIO", 0),
+            Err(AcpiError::InvalidIortIdMappingOutput(5))
+        ));
+        let mut invalid = builder.clone();
+        invalid.root_complex(1, &[IortIdMapping::new(0, 0, 0, its_group)]);
+        assert!(matches!(
+            invalid.build(*b"FOOBAR", *b"FOOBARIO", 0),
+            Err(AcpiError::InvalidIortIdCount)
+        ));
+    }
+}
@@ -0,0 +1,488 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use alloc::vec::Vec;
+use core::mem::size_of;
+
+use zerocopy::{Immutable, IntoBytes};
+
+use crate::endian::{U16, U32, U64};
+use crate::{AcpiError, Result, Sdt, SdtHeader, checksum};
+
+// Device entry types.
+const IVHD_ENTRY_ALL: u8 = 0x01;
+const IVHD_ENTRY_SELECT: u8 = 0x02;
+const IVHD_ENTRY_RANGE_START: u8 = 0x03;
+const IVHD_ENTRY_RANGE_END: u8 = 0x04;
+const IVHD_ENTRY_ALIAS_SELECT: u8 = 0x42;
+const IVHD_ENTRY_EXTENDED_SELECT: u8 = 0x46;
+const IVHD_ENTRY_SPECIAL: u8 = 0x48;
+
+/// Type of an I/O Virtualization Hardware Definition block.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum IvhdType {
+    /// Type 10h block, which reports a subset of the IOMMU features.
+    Type10h = 0x10,
+    /// Type 11h block, which holds an image of the Extended Feature Register of the IOMMU.
+    Type11h = 0x11,
+}
+
+/// Kind of device described by a special device entry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum IvhdSpecialDevice {
+    /// I/O APIC, whose handle is its ID.
+    IoApic = 1,
+    /// HPET, whose handle is its number.
+    Hpet = 2,
+}
+
+/// Device entry of an I/O Virtualization Hardware Definition block
+///
+/// Device entries tell the guest which devices are behind the IOMMU, the `dte` field holding the
+/// settings of their device table entries (INITPass, EIntPass, NMIPass, SysMgt, Lint0Pass and
+/// Lint1Pass).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IvhdDeviceEntry {
+    /// All the devices of the PCI segment.
+    All {
+        /// Device table entry settings.
+        dte: u8,
+    },
+    /// A single device.
+    Select {
+        /// Requester ID of the device.
+        device_id: u16,
+        /// Device table entry settings.
+        dte: u8,
+    },
+    /// The devices in the inclusive range of requester IDs from `start` to `end`.
+    Range {
+        /// First requester ID of the range.
+        start: u16,
+        /// Last requester ID of the range.
+        end: u16,
+        /// Device table entry settings.
+        dte: u8,
+    },
+    /// A device whose requests are issued with the requester ID of another device.
+    Alias {
+        /// Requester ID of the device.
+        device_id: u16,
+        /// Requester ID the requests of the device are issued with.
+        source_id: u16,
+        /// Device table entry settings.
+        dte: u8,
+    },
+    /// A single device, with extended settings such as ATS being disallowed.
+    ExtendedSelect {
+        /// Requester ID of the device.
+        device_id: u16,
+        /// Device table entry settings.
+        dte: u8,
+        /// Extended settings.
+        extended: u32,
+    },
+    /// An I/O APIC or HPET, which is not a PCI device.
+    Special {
+        /// Kind of the device.
+        variety: IvhdSpecialDevice,
+        /// ID of the I/O APIC or number of the HPET.
+        handle: u8,
+        /// Requester ID the device issues its requests with.
+        device_id: u16,
+        /// Device table entry settings.
+        dte: u8,
+    },
+}
+
+impl IvhdDeviceEntry {
+    fn encode(&self, bytes: &mut Vec<u8>) -> Result<()> {
+        match *self {
+            IvhdDeviceEntry::All { dte } => {
+                bytes.extend_from_slice(&[IVHD_ENTRY_ALL, 0, 0, dte]);
+            }
+            IvhdDeviceEntry::Select { device_id, dte } => {
+                bytes.push(IVHD_ENTRY_SELECT);
+                bytes.extend_from_slice(&device_id.to_le_bytes());
+                bytes.push(dte);
+            }
+            IvhdDeviceEntry::Range { start, end, dte } => {
+                if end < start {
+                    return Err(AcpiError::InvalidIvhdDeviceRange(start, end));
+                }
+                bytes.push(IVHD_ENTRY_RANGE_START);
+                bytes.extend_from_slice(&start.to_le_bytes());
+                bytes.push(dte);
+                bytes.push(IVHD_ENTRY_RANGE_END);
+                bytes.extend_from_slice(&end.to_le_bytes());
+                bytes.push(0);
+            }
+            IvhdDeviceEntry::Alias {
+                device_id,
+                source_id,
+                dte,
+            } => {
+                bytes.push(IVHD_ENTRY_ALIAS_SELECT);
+                bytes.extend_from_slice(&device_id.to_le_bytes());
+                bytes.extend_from_slice(&[dte, 0]);
+                bytes.extend_from_slice(&source_id.to_le_bytes());
+                bytes.push(0);
+            }
+            IvhdDeviceEntry::ExtendedSelect {
+                device_id,
+                dte,
+                extended,
+            } => {
+                bytes.push(IVHD_ENTRY_EXTENDED_SELECT);
+                bytes.extend_from_slice(&device_id.to_le_bytes());
+                bytes.push(dte);
+                bytes.extend_from_slice(&extended.to_le_bytes());
+            }
+            IvhdDeviceEntry::Special {
+                variety,
+                handle,
+                device_id,
+                dte,
+            } => {
+                bytes.extend_from_slice(&[IVHD_ENTRY_SPECIAL, 0, 0, dte, handle]);
+                bytes.extend_from_slice(&device_id.to_le_bytes());
+                bytes.push(variety as u8);
+            }
+        }
+        Ok(())
+    }
+}
+
+// clippy doesn't understand that we actually "use" the fields of this struct when we serialize
+// them as bytes in guest memory, so here we just ignore dead code to avoid having to name
+// everything with an underscore prefix
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct IvhdHeader {
+    r#type: u8,
+    flags: u8,
+    length: U16,
+    device_id: U16,
+    capability_offset: U16,
+    base_address: U64,
+    pci_segment: U16,
+    iommu_info: U16,
+    // IOMMU feature reporting for type 10h blocks, IOMMU attributes for type 11h ones.
+    attributes: U32,
+}
+
+assert_layout!(IvhdHeader, 24);
+
+// clippy doesn't understand that we actually "use" the fields of this struct when we serialize
+// them as bytes in guest memory, so here we just ignore dead code to avoid having to name
+// everything with an underscore prefix
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct IvhdEfrImage {
+    efr: U64,
+    efr2: U64,
+}
+
+assert_layout!(IvhdEfrImage, 16);
+
+/// I/O Virtualization Hardware Definition block (IVHD)
+///
+/// This block describes an IOMMU, found on the PCI segment `pci_segment` with the requester ID
+/// `device_id`, along with the devices it translates the requests of.
+#[derive(Clone, Debug)]
+pub struct Ivhd {
+    r#type: IvhdType,
+    header: IvhdHeader,
+    efr_image: IvhdEfrImage,
+    devices: Vec<IvhdDeviceEntry>,
+}
+
+impl Ivhd {
+    /// Create a block describing an IOMMU, with no device, whose capability block is at
+    /// `capability_offset` in its configuration space and whose registers are at `base_address`.
+    pub fn new(
+        r#type: IvhdType,
+        device_id: u16,
+        capability_offset: u16,
+        base_address: u64,
+        pci_segment: u16,
+    ) -> Self {
+        Ivhd {
+            r#type,
+            header: IvhdHeader {
+                r#type: r#type as u8,
+                device_id: U16::new(device_id),
+                capability_offset: U16::new(capability_offset),
+                base_address: U64::new(base_address),
+                pci_segment: U16::new(pci_segment),
+                ..Default::default()
+            },
+            efr_image: IvhdEfrImage::default(),
+            devices: Vec::new(),
+        }
+    }
+
+    /// Set the flags of the block, such as whether coherent requests are supported.
+    pub fn flags(mut self, flags: u8) -> Self {
+        self.header.flags = flags;
+        self
+    }
+
+    /// Set the MSI number and unit ID of the IOMMU.
+    pub fn iommu_info(mut self, iommu_info: u16) -> Self {
+        self.header.iommu_info = U16::new(iommu_info);
+        self
+    }
+
+    /// Set the IOMMU feature reporting of a type 10h block, or the IOMMU attributes of a type 11h
+    /// one.
+    pub fn attributes(mut self, attributes: u32) -> Self {
+        self.header.attributes = U32::new(attributes);
+        self
+    }
+
+    /// Set the image of the Extended Feature Registers of the IOMMU. It is only part of type 11h
+    /// blocks.
+    pub fn efr(mut self, efr: u64, efr2: u64) -> Self {
+        self.efr_image = IvhdEfrImage {
+            efr: U64::new(efr),
+            efr2: U64::new(efr2),
+        };
+        self
+    }
+
+    /// Add a device entry to the block.
+    pub fn device(mut self, device: IvhdDeviceEntry) -> Self {
+        self.devices.push(device);
+        self
+    }
+
+    fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut devices = Vec::new();
+        for device in &self.devices {
+            device.encode(&mut devices)?;
+        }
+
+        let mut header = self.header;
+        let efr_image = match self.r#type {
+            IvhdType::Type10h => &[][..],
+            IvhdType::Type11h => self.efr_image.as_bytes(),
+        };
+        let length = size_of::<IvhdHeader>() + efr_image.len() + devices.len();
+        header.length = U16::new(
+            length
+                .try_into()
+                .map_err(|_| AcpiError::InvalidIvhdLength)?,
+        );
+
+        Ok([header.as_bytes(), efr_image, &devices].concat())
+    }
+}
+
+// clippy doesn't understand that we actually "use" the fields of this struct when we serialize
+// them as bytes in guest memory, so here we just ignore dead code to avoid having to name
+// everything with an underscore prefix
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Clone, Debug, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct IvrsHeader {
+    sdt: SdtHeader,
+    iv_info: U32,
+    _reserved: U64,
+}
+
+assert_layout!(IvrsHeader, 48);
+
+/// I/O Virtualization Reporting Structure (IVRS)
+///
+/// This table describes the AMD IOMMUs of the platform and the devices behind them. More
+/// information about this table can be found in the AMD I/O Virtualization Technology (IOMMU)
+/// specification:
+/// https://www.amd.com/content/dam/amd/en/documents/processor-tech-docs/specifications/48882_IOMMU.pdf
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Ivrs {
+    header: IvrsHeader,
+    blocks: Vec<u8>,
+}
+
+impl Sdt for Ivrs {
+    fn len(&self) -> usize {
+        self.header.sdt.length.get().try_into().unwrap()
+    }
+
+    fn header(&self) -> Option<&SdtHeader> {
+        Some(&self.header.sdt)
+    }
+
+    fn to_bytes(&mut self) -> Result<Vec<u8>> {
+        Ok([self.header.as_bytes(), self.blocks.as_bytes()].concat())
+    }
+}
+
+/// Builder of an [`Ivrs`], one IVHD block at a time
+#[derive(Clone, Debug)]
+pub struct IvrsBuilder {
+    iv_info: u32,
+    blocks: Vec<Ivhd>,
+}
+
+impl IvrsBuilder {
+    /// Create a builder with no block. `iv_info` holds the virtual, physical and guest virtual
+    /// address sizes supported by the IOMMUs.
+    pub fn new(iv_info: u32) -> Self {
+        IvrsBuilder {
+            iv_info,
+            blocks: Vec::new(),
+        }
+    }
+
+    /// Add an IVHD block.
+    pub fn ivhd(mut self, ivhd: Ivhd) -> Self {
+        self.blocks.push(ivhd);
+        self
+    }
+
+    /// Build the IVRS table, computing the length of every block and encoding its device entries.
+    pub fn build(self, oem_id: [u8; 6], oem_table_id: [u8; 8], oem_revision: u32) -> Result<Ivrs> {
+        let blocks = self
+            .blocks
+            .iter()
+            .map(Ivhd::to_bytes)
+            .collect::<Result<Vec<_>>>()?
+            .concat();
+        // Revision 2 of the table is needed for type 11h blocks.
+        let type11h = |ivhd: &Ivhd| ivhd.r#type == IvhdType::Type11h;
+        let revision = if self.blocks.iter().any(type11h) {
+            2
+        } else {
+            1
+        };
+        let length = size_of::<IvrsHeader>() + blocks.len();
+        let sdt_header = SdtHeader::new(
+            *b"IVRS",
+            length.try_into().unwrap(),
+            revision,
+            oem_id,
+            oem_table_id,
+            oem_revision,
+        );
+
+        let mut header = IvrsHeader {
+            sdt: sdt_header,
+            iv_info: U32::new(self.iv_info),
+            _reserved: U64::ZERO,
+        };
+        header.sdt.checksum = checksum(&[header.as_bytes(), blocks.as_bytes()]);
+
+        Ok(Ivrs { header, blocks })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vm_memory::{Bytes, GuestAddress, GuestMemoryMmap};
+
+    use super::*;
+
+    #[test]
+    fn test_ivrs() {
+        let iommu = Ivhd::new(IvhdType::Type10h, 0x0002, 0x40, 0xfeb8_0000, 0)
+            .flags(0xb0)
+            .device(IvhdDeviceEntry::Select {
+                device_id: 0x0008,
+                dte: 0,
+            })
+            .device(IvhdDeviceEntry::Range {
+                start: 0x0010,
+                end: 0x00ff,
+                dte: 0,
+            })
+            .device(IvhdDeviceEntry::Special {
+                variety: IvhdSpecialDevice::IoApic,
+                handle: 0x21,
+                device_id: 0x00a0,
+                dte: 0xd7,
+            });
+        let efr_iommu = Ivhd::new(IvhdType::Type11h, 0x0002, 0x40, 0xfeb8_0000, 0)
+            .efr(0x1234, 0)
+            .device(IvhdDeviceEntry::All { dte: 0 });
+        let mut ivrs = IvrsBuilder::new(0x0020_3040)
+            .ivhd(iommu)
+            .ivhd(efr_iommu)
+            .build(*b"FOOBAR", *b"FOOBARIV", 0)
+            .unwrap();
+        assert_eq!(ivrs.len(), 48 + (24 + 4 + 8 + 8) + (40 + 4));
+
+        let mem: GuestMemoryMmap =
+            GuestMemoryMmap::from_ranges(&[(GuestAddress(0), 0x1000)]).unwrap();
+        ivrs.write_to_guest(&mem, GuestAddress(0)).unwrap();
+        let mut bytes = vec![0u8; ivrs.len()];
+        mem.read_slice(&mut bytes, GuestAddress(0)).unwrap();
+        assert_eq!(checksum(&[&bytes]), 0);
+        assert_eq!(&bytes[..4], b"IVRS");
+        assert_eq!(bytes[8], 2);
+        assert_eq!(&bytes[36..40], &0x0020_3040u32.to_le_bytes());
+
+        let iommu = &bytes[48..92];
+        // Type, flags and length.
+        assert_eq!(&iommu[..4], &[0x10, 0xb0, 44, 0]);
+        assert_eq!(&iommu[8..16], &0xfeb8_0000u64.to_le_bytes());
+        assert_eq!(&iommu[24..28], &[0x02, 0x08, 0x00, 0x00]);
+        // Start and end of the range.
+        assert_eq!(
+            &iommu[28..36],
+            &[0x03, 0x10, 0x00, 0x00, 0x04, 0xff, 0x00, 0x00]
+        );
+        assert_eq!(&iommu[36..], &[0x48, 0, 0, 0xd7, 0x21, 0xa0, 0x00, 0x01]);
+
+        let efr_iommu = &bytes[92..];
+        assert_eq!(&efr_iommu[..4], &[0x11, 0, 44, 0]);
+        assert_eq!(&efr_iommu[24..32], &0x1234u64.to_le_bytes());
+        assert_eq!(&efr_iommu[40..], &[0x01, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_ivrs_validation() {
+        let ivhd = Ivhd::new(IvhdType::Type10h, 0x0002, 0x40, 0xfeb8_0000, 0).device(
+            IvhdDeviceEntry::Range {
+                start: 0x0010,
+                end: 0x0008,
+                dte: 0,
+            },
+        );
+        assert!(matches!(
+            IvrsBuilder::new(0)
+                .ivhd(ivhd)
+                .build(*b"FOOBAR", *b"FOOBARIV", 0),
+            Err(AcpiError::InvalidIvhdDeviceRange(0x0010, 0x0008))
+        ));
+
+        let mut ivhd = Ivhd::new(IvhdType::Type10h, 0x0002, 0x40, 0xfeb8_0000, 0);
+        for device_id in 0..=u16::MAX {
+            ivhd = ivhd.device(IvhdDeviceEntry::Select { device_id, dte: 0 });
+        }
+        assert!(matches!(
+            IvrsBuilder::new(0)
+                .ivhd(ivhd)
+                .build(*b"FOOBAR", *b"FOOBARIV", 0),
+            Err(AcpiError::InvalidIvhdLength)
+        ));
+
+        // Type 10h blocks do not hold the image of the Extended Feature Registers.
+        let ivhd = Ivhd::new(IvhdType::Type10h, 0x0002, 0x40, 0xfeb8_0000, 0).efr(0x1234, 0);
+        let ivrs = IvrsBuilder::new(0)
+            .ivhd(ivhd)
+            .build(*b"FOOBAR", *b"FOOBARIV", 0)
+            .unwrap();
+        assert_eq!(ivrs.len(), 48 + 24);
+    }
+}
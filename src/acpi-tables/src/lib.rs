@@ -4,25 +4,127 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
-use vm_memory::{GuestAddress, GuestMemory, GuestMemoryError};
+//! Builders of ACPI tables and of AML bytecode
+//!
+//! The crate only needs `alloc`. Writing the tables to guest memory or to a `std::io::Write`
+//! implementation, as well as laying them out with `AcpiTableWriter`, require the `std` feature,
+//! which is enabled by default.
+//!
+//! With the `serde` feature, the headers and the tables implement `Serialize` and `Deserialize`,
+//! field by field. The variable parts of the tables made of structures of several types, such as
+//! the interrupt controllers of the MADT, are kept as their encoded bytes.
+//!
+//! The `dump` feature builds `acpi-dump`, which prints the fields of a raw table read from a file
+//! or from the standard input, after checking its checksum.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use vm_memory::{Bytes, GuestAddress, GuestMemory, GuestMemoryError};
+
+/// Check at compile time the size of a structure against the ACPI specification, along with the
+/// offsets of the given fields.
+macro_rules! assert_layout {
+    ($type:ty, $size:expr $(, $field:ident: $offset:expr)* $(,)?) => {
+        const _: () = {
+            assert!(core::mem::size_of::<$type>() == $size);
+            $(assert!(core::mem::offset_of!($type, $field) == $offset);)*
+        };
+    };
+}
 
 pub mod aml;
+pub mod bert;
+pub mod bgrt;
+pub mod cedt;
+pub mod dbg2;
+pub mod dmar;
 pub mod dsdt;
+pub mod einj;
+pub mod endian;
+pub mod erst;
+pub mod facs;
 pub mod fadt;
+pub mod fpdt;
+pub mod hest;
+pub mod hmat;
+pub mod hpet;
+pub mod iort;
+pub mod ivrs;
+pub mod lpit;
 pub mod madt;
 pub mod mcfg;
+pub mod nfit;
+pub mod pcct;
+pub mod phat;
+pub mod pptt;
 pub mod rsdp;
+pub mod rsdt;
+pub mod slit;
+pub mod srat;
+pub mod ssdt;
+pub mod tpm2;
+pub mod wdat;
+#[cfg(feature = "std")]
+pub mod writer;
 pub mod xsdt;
 
 pub use aml::Aml;
+pub use bert::Bert;
+pub use bgrt::Bgrt;
+pub use cedt::{
+    CFMWS_RESTRICTION_FIXED, CFMWS_RESTRICTION_PERSISTENT, CFMWS_RESTRICTION_TYPE2,
+    CFMWS_RESTRICTION_TYPE3, CFMWS_RESTRICTION_VOLATILE, Cedt, Cfmws, Chbs, ChbsVersion,
+};
+pub use dbg2::{Dbg2, Dbg2SerialSubtype, DebugDeviceInfo};
+pub use dmar::{
+    Atsr, DMAR_FLAG_DMA_CTRL_PLATFORM_OPT_IN, DMAR_FLAG_INTR_REMAP, DMAR_FLAG_X2APIC_OPT_OUT, Dmar,
+    DmarBuilder, DmarDeviceScope, DmarDeviceScopeType, Drhd, Rmrr,
+};
 pub use dsdt::Dsdt;
-pub use fadt::Fadt;
-pub use madt::Madt;
-pub use mcfg::Mcfg;
+pub use einj::{Einj, EinjAction, EinjBuilder};
+pub use erst::{Erst, ErstAction, ErstBuilder};
+pub use facs::Facs;
+pub use fadt::{Fadt, FadtBuilder};
+pub use fpdt::{BasicBootTimestamps, Fbpt, Fpdt};
+pub use hest::{Ghes, GhesV2, Hest, HestErrorSource, MemoryErrorStatus};
+pub use hmat::{
+    CacheAssociativity, CacheWritePolicy, Hmat, LocalityDataType, MemoryHierarchy,
+    MemoryProximityDomainAttributes, MemorySideCacheInfo, SystemLocalityInfo,
+};
+pub use hpet::Hpet;
+pub use iort::{Iort, IortBuilder, IortIdMapping, IortNodeRef, Smmuv3Interrupts};
+pub use ivrs::{Ivhd, IvhdDeviceEntry, IvhdSpecialDevice, IvhdType, Ivrs, IvrsBuilder};
+pub use lpit::{LPI_FLAG_COUNTER_UNAVAILABLE, LPI_FLAG_DISABLED, LpiNativeCState, Lpit};
+pub use madt::{Madt, MadtBuilder};
+pub use mcfg::{Mcfg, PciRangeEntry};
+pub use nfit::{ControlRegion, Nfit, RegionMapping, SpaRange, single_nvdimm_structures};
+pub use pcct::{
+    GenericSubspace, HwReducedSubspace, HwReducedSubspaceV2, PCC_INTERRUPT_ACTIVE_LOW,
+    PCC_INTERRUPT_EDGE_TRIGGERED, Pcct, PcctSubspace,
+};
+pub use phat::{
+    FirmwareHealthRecord, FirmwareVersionElement, FirmwareVersionRecord, Phat, PhatHealthStatus,
+    PhatRecord,
+};
+pub use pptt::{Pptt, PpttBuilder, PpttCache, PpttCacheType, PpttRef};
 pub use rsdp::Rsdp;
+pub use rsdt::Rsdt;
+pub use slit::{Slit, SlitBuilder};
+pub use srat::{MemoryAffinity, ProcessorAffinity, Srat, X2ApicAffinity};
+pub use ssdt::Ssdt;
+pub use tpm2::{Tpm2, Tpm2PlatformClass, Tpm2StartMethod};
+pub use wdat::{Wdat, WdatEntry};
+#[cfg(feature = "std")]
+pub use writer::{AcpiTableAddresses, AcpiTableWriter};
 pub use xsdt::Xsdt;
-use zerocopy::little_endian::{U32, U64};
-use zerocopy::{Immutable, IntoBytes};
+use zerocopy::{FromBytes, Immutable, IntoBytes};
+
+use crate::endian::{U32, U64};
 
 // This is the creator ID that we will embed in ACPI tables that are created using this crate.
 const FC_ACPI_CREATOR_ID: [u8; 4] = *b"FCAT";
@@ -46,18 +148,156 @@ fn checksum(buf: &[&[u8]]) -> u8 {
     sum.wrapping_neg()
 }
 
-#[derive(Debug, thiserror::Error, displaydoc::Display)]
+/// Check the ACPI checksum of a whole table
+///
+/// A table is valid when the sum of all its bytes, including the checksum byte, is zero.
+pub fn verify_checksum(bytes: &[u8]) -> bool {
+    checksum(&[bytes]) == 0
+}
+
+#[derive(Debug, displaydoc::Display)]
+#[cfg_attr(feature = "std", derive(thiserror::Error))]
 pub enum AcpiError {
     /// Guest memory error: {0}
+    #[cfg(feature = "std")]
     GuestMemory(#[from] GuestMemoryError),
     /// Invalid guest address
     InvalidGuestAddress,
     /// Invalid register size
     InvalidRegisterSize,
+    /// Invalid table signature
+    InvalidSignature,
+    /// Table length does not match the size of its buffer
+    InvalidTableLength,
+    /// Invalid table checksum
+    InvalidChecksum,
+    /// SLIT distances must form a non-empty square matrix
+    InvalidSlitMatrix,
+    /// SLIT distance of locality {0} to itself must be 10
+    InvalidSlitLocalDistance(usize),
+    /// SLIT distance from locality {0} to locality {1} must be at least 10
+    InvalidSlitDistance(usize, usize),
+    /// HMAT locality structure must hold one entry per initiator and target proximity domain
+    InvalidHmatLocalityEntries,
+    /// HMAT memory side cache level {0} is invalid
+    InvalidHmatCacheLevel(u8),
+    /// DBG2 namespace string must be made of printable ASCII characters
+    InvalidDbg2Namespace,
+    /// DBG2 debug device information structure is too long
+    InvalidDbg2DeviceLength,
+    /// DMAR device scope entry must have a path of 1 to 124 PCI devices
+    InvalidDmarDeviceScope,
+    /// DMAR remapping structure is too long
+    InvalidDmarStructureLength,
+    /// DMAR reserved memory region from {0:#x} to {1:#x} is not made of whole pages
+    InvalidDmarReservedRegion(u64, u64),
+    /// IVHD device range from {0:#06x} to {1:#06x} is empty
+    InvalidIvhdDeviceRange(u16, u16),
+    /// IVHD block is too long
+    InvalidIvhdLength,
+    /// IORT named component name must be made of printable ASCII characters
+    InvalidIortName,
+    /// IORT node is too long
+    InvalidIortNodeLength,
+    /// IORT ID mapping must cover at least one ID
+    InvalidIortIdCount,
+    /// IORT ID mapping output, node {0}, is not an ITS group or an SMMUv3 of the table
+    InvalidIortIdMappingOutput(usize),
+    /// PPTT reference to structure {0} does not point to a structure of the expected type
+    InvalidPpttReference(usize),
+    /// PPTT processor hierarchy node has too many private resources
+    InvalidPpttNodeLength,
+    /// NFIT structure index must not be 0
+    InvalidNfitIndex,
+    /// CFMWS window must be a non-empty multiple of 256 MiB, aligned on 256 MiB
+    InvalidCfmwsWindow,
+    /// CFMWS window cannot be interleaved across {0} host bridges
+    InvalidCfmwsTargets(usize),
+    /// CFMWS interleave granularity of {0} bytes is invalid
+    InvalidCfmwsGranularity(u32),
+    /// BGRT image must be a BMP image which fits on the screen
+    InvalidBgrtImage,
+    /// BERT boot error region is too small for its error status block
+    InvalidBertRegion,
+    /// FADT sleep control and status registers must be 8 bits wide, at a non-zero address
+    InvalidFadtSleepRegister,
+    /// Unable to allocate a buffer of {0} bytes for the table
+    BufferAllocation(usize),
+    /// MADT interrupt controller structures are malformed
+    InvalidMadtStructure,
+    /// Unable to write the table: {0}
+    #[cfg(feature = "std")]
+    Write(std::io::Error),
+    /// Not enough space left in guest memory to place a table of {0} bytes
+    OutOfSpace(usize),
+    /// PHAT record is too long
+    InvalidPhatRecordLength,
 }
 
 /// Result type for ACPI operations
-pub type Result<T> = std::result::Result<T, AcpiError>;
+pub type Result<T> = core::result::Result<T, AcpiError>;
+
+#[cfg(not(feature = "std"))]
+impl core::error::Error for AcpiError {}
+
+/// Address space of the register of a [`GenericAddressStructure`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum AddressSpace {
+    /// System Memory
+    SystemMemory = 0,
+    /// System I/O
+    SystemIo = 1,
+    /// PCI Configuration Space
+    PciConfig = 2,
+    /// Embedded Controller
+    EmbeddedController = 3,
+    /// SMBus
+    Smbus = 4,
+    /// System CMOS
+    SystemCmos = 5,
+    /// PCI BAR Target
+    PciBarTarget = 6,
+    /// IPMI
+    Ipmi = 7,
+    /// General Purpose I/O
+    GeneralPurposeIo = 8,
+    /// Generic Serial Bus
+    GenericSerialBus = 9,
+    /// Platform Communications Channel
+    PlatformCommunicationsChannel = 0xa,
+    /// Functional Fixed Hardware
+    FunctionalFixedHardware = 0x7f,
+}
+
+/// Size of the accesses to the register of a [`GenericAddressStructure`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum AccessSize {
+    /// Undefined, for legacy reasons
+    Undefined = 0,
+    /// Byte access
+    Byte = 1,
+    /// Word access
+    Word = 2,
+    /// DWord access
+    DWord = 3,
+    /// QWord access
+    QWord = 4,
+}
+
+impl AccessSize {
+    // Number of bits of an access, if defined.
+    fn bits(self) -> Option<u16> {
+        match self {
+            AccessSize::Undefined => None,
+            AccessSize::Byte => Some(8),
+            AccessSize::Word => Some(16),
+            AccessSize::DWord => Some(32),
+            AccessSize::QWord => Some(64),
+        }
+    }
+}
 
 /// Generic Address Structure (GAS) - ACPI type representing memory/IO addresses
 ///
@@ -69,13 +309,16 @@ pub type Result<T> = std::result::Result<T, AcpiError>;
 ///
 /// # Examples
 /// ```ignore
-/// use acpi_tables::GenericAddressStructure;
+/// use acpi_tables::{AccessSize, AddressSpace, GenericAddressStructure};
 ///
-/// // System Memory address at 0x1000
-/// let gas = GenericAddressStructure::new(0, 32, 0, 3, 0x1000);
+/// // 32-bit register in System Memory at 0x1000
+/// let gas =
+///     GenericAddressStructure::new(AddressSpace::SystemMemory, 32, 0, AccessSize::DWord, 0x1000)
+///         .unwrap();
 /// ```
 #[repr(C, packed)]
-#[derive(IntoBytes, Immutable, Clone, Copy, Debug, Default)]
+#[derive(IntoBytes, FromBytes, Immutable, Clone, Copy, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GenericAddressStructure {
     /// Address space where the register exists (0=System Memory, 1=System I/O, etc.)
     pub address_space_id: u8,
@@ -89,8 +332,45 @@ pub struct GenericAddressStructure {
     pub address: U64,
 }
 
+assert_layout!(GenericAddressStructure, 12, address: 4);
+
 impl GenericAddressStructure {
+    /// Describe the register of `register_bit_width` bits at `register_bit_offset` of
+    /// `address`, in `address_space`.
+    ///
+    /// The register must fit in a single access, or span whole accesses starting at bit 0.
+    ///
+    /// # Errors
+    /// Returns `AcpiError::InvalidRegisterSize` if the register is empty, or doesn't fit the
+    /// access size.
     pub fn new(
+        address_space: AddressSpace,
+        register_bit_width: u8,
+        register_bit_offset: u8,
+        access_size: AccessSize,
+        address: u64,
+    ) -> Result<Self> {
+        let width = u16::from(register_bit_width);
+        let end = u16::from(register_bit_offset) + width;
+        let valid = match access_size.bits() {
+            Some(bits) if end > bits => register_bit_offset == 0 && width.is_multiple_of(bits),
+            Some(_) => true,
+            None => end <= u16::from(u8::MAX),
+        };
+        if register_bit_width == 0 || !valid {
+            return Err(AcpiError::InvalidRegisterSize);
+        }
+        Ok(Self::from_raw(
+            address_space as u8,
+            register_bit_width,
+            register_bit_offset,
+            access_size as u8,
+            address,
+        ))
+    }
+
+    /// Describe a register from the raw values of the fields of the structure, unchecked.
+    pub fn from_raw(
         address_space_id: u8,
         register_bit_width: u8,
         register_bit_offset: u8,
@@ -119,7 +399,8 @@ impl GenericAddressStructure {
 /// The checksum byte is calculated such that the sum of all bytes in the entire table
 /// (including this header) equals zero when wrapped in u8 arithmetic.
 #[repr(C, packed)]
-#[derive(Clone, Debug, Copy, Default, IntoBytes, Immutable)]
+#[derive(Clone, Debug, Copy, Default, IntoBytes, FromBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SdtHeader {
     /// Table signature (e.g., b"XSDT", b"FACP", b"APIC")
     pub signature: [u8; 4],
@@ -141,7 +422,11 @@ pub struct SdtHeader {
     pub creator_revision: U32,
 }
 
+assert_layout!(SdtHeader, 36, length: 4, checksum: 9, oem_table_id: 16, creator_revision: 32);
+
 impl SdtHeader {
+    // The creator identity is always the one of this crate, embedders replacing it by stamping
+    // the written tables with an `AcpiConfig`.
     pub(crate) fn new(
         signature: [u8; 4],
         length: u32,
@@ -162,6 +447,112 @@ impl SdtHeader {
             creator_revision: U32::new(FC_ACPI_CREATOR_REVISION),
         }
     }
+
+    /// Parse the header of the table held by `bytes`, whose signature must be `signature`
+    ///
+    /// The length field of the header must match the size of the buffer, and the checksum of the
+    /// whole table must be valid. Returns the header along with the rest of the table.
+    ///
+    /// # Errors
+    /// Returns `AcpiError::InvalidSignature`, `AcpiError::InvalidTableLength` or
+    /// `AcpiError::InvalidChecksum` if the table is not valid
+    pub fn parse<'a>(bytes: &'a [u8], signature: &[u8; 4]) -> Result<(Self, &'a [u8])> {
+        let (header, rest) =
+            SdtHeader::read_from_prefix(bytes).map_err(|_| AcpiError::InvalidTableLength)?;
+
+        if &header.signature != signature {
+            return Err(AcpiError::InvalidSignature);
+        }
+        if header.length.get() as usize != bytes.len() {
+            return Err(AcpiError::InvalidTableLength);
+        }
+        if !verify_checksum(bytes) {
+            return Err(AcpiError::InvalidChecksum);
+        }
+
+        Ok((header, rest))
+    }
+}
+
+/// Revision of the ACPI specification targeted by a table
+///
+/// Tables target the latest revision by default. Targeting an older one selects the revision
+/// byte of their header and leaves out the fields it does not define, for the guests which
+/// predate them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SpecRevision {
+    /// ACPI 5.0
+    Acpi5_0,
+    /// ACPI 5.1
+    Acpi5_1,
+    /// ACPI 6.0
+    Acpi6_0,
+    /// ACPI 6.1
+    Acpi6_1,
+    /// ACPI 6.2
+    Acpi6_2,
+    /// ACPI 6.3
+    Acpi6_3,
+    /// ACPI 6.4
+    Acpi6_4,
+    /// ACPI 6.5
+    #[default]
+    Acpi6_5,
+}
+
+/// Identity of the vendor of the tables, stamped in their headers
+///
+/// Tables are created with the OEM ID they are given and with the creator ID and revision of this
+/// crate, which their constructors do not take. Stamping the written tables is the only supported
+/// way for embedders to put their own identity in them: [`AcpiTableWriter::config`] stamps every
+/// table it writes, except the RSDP and the FACS, which have no standard header.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AcpiConfig {
+    /// OEM ID of the tables
+    pub oem_id: [u8; 6],
+    /// OEM revision of the tables
+    pub oem_revision: u32,
+    /// Vendor ID of the tool that created the tables
+    pub creator_id: [u8; 4],
+    /// Revision of the tool that created the tables
+    pub creator_revision: u32,
+}
+
+impl AcpiConfig {
+    /// Create a configuration with the given OEM identity, and the creator ID and revision of
+    /// this crate.
+    pub fn new(oem_id: [u8; 6], oem_revision: u32) -> Self {
+        AcpiConfig {
+            oem_id,
+            oem_revision,
+            creator_id: FC_ACPI_CREATOR_ID,
+            creator_revision: FC_ACPI_CREATOR_REVISION,
+        }
+    }
+
+    /// Set the creator ID and revision, identifying the tool that created the tables.
+    pub fn creator(mut self, creator_id: [u8; 4], creator_revision: u32) -> Self {
+        self.creator_id = creator_id;
+        self.creator_revision = creator_revision;
+        self
+    }
+
+    /// Stamp the identity in the header of a serialized table, and refold its checksum
+    ///
+    /// The OEM table ID of the table is left untouched. This applies to all the tables starting
+    /// with the standard header, thus neither to the RSDP nor to the FACS.
+    pub fn stamp(&self, table: &mut [u8]) -> Result<()> {
+        let (mut header, _) =
+            SdtHeader::read_from_prefix(table).map_err(|_| AcpiError::InvalidTableLength)?;
+        header.checksum = 0;
+        header.oem_id = self.oem_id;
+        header.oem_revision = U32::new(self.oem_revision);
+        header.creator_id = self.creator_id;
+        header.creator_revision = U32::new(self.creator_revision);
+        table[..core::mem::size_of::<SdtHeader>()].copy_from_slice(header.as_bytes());
+        table[core::mem::offset_of!(SdtHeader, checksum)] = checksum(&[table]);
+        Ok(())
+    }
 }
 
 /// Trait for ACPI System Descriptor Table operations
@@ -184,6 +575,19 @@ pub trait Sdt {
         self.len() == 0
     }
 
+    /// Get the standard header of the table
+    ///
+    /// Returns `None` for the structures without one, namely the RSDP, the FACS and the FBPT.
+    fn header(&self) -> Option<&SdtHeader> {
+        None
+    }
+
+    /// Serialize the complete table to a buffer
+    ///
+    /// # Errors
+    /// Returns an error if the table cannot be serialized
+    fn to_bytes(&mut self) -> Result<Vec<u8>>;
+
     /// Write the complete table to guest memory at the specified address
     ///
     /// # Arguments
@@ -192,12 +596,27 @@ pub trait Sdt {
     ///
     /// # Errors
     /// Returns `AcpiError::GuestMemory` if writing to guest memory fails
-    fn write_to_guest<M: GuestMemory>(&mut self, mem: &M, address: GuestAddress) -> Result<()>;
+    #[cfg(feature = "std")]
+    fn write_to_guest<M: GuestMemory>(&mut self, mem: &M, address: GuestAddress) -> Result<()> {
+        mem.write_slice(&self.to_bytes()?, address)?;
+        Ok(())
+    }
+
+    /// Write the complete table to `writer`, such as a file
+    ///
+    /// # Errors
+    /// Returns `AcpiError::Write` if writing to `writer` fails
+    #[cfg(feature = "std")]
+    fn write_to<W: std::io::Write>(&mut self, writer: &mut W) -> Result<()> {
+        writer
+            .write_all(&self.to_bytes()?)
+            .map_err(AcpiError::Write)
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::checksum;
+    use super::*;
 
     #[test]
     fn test_checksum() {
@@ -210,4 +629,137 @@ mod tests {
         assert_eq!(checksum(&[&[255]]), 1u8);
         assert_eq!(checksum(&[&[1, 2], &[3], &[250], &[255]]), 1u8);
     }
+
+    #[test]
+    fn test_verify_checksum() {
+        assert!(verify_checksum(&[]));
+        assert!(verify_checksum(&[1, 2, 3, 250]));
+        assert!(!verify_checksum(&[1, 2, 3]));
+    }
+
+    #[test]
+    fn test_acpi_config_stamp() {
+        let mut xsdt = Xsdt::new(*b"FOOBAR", *b"FOOBARXS", 0, vec![0x1000]);
+        let mut bytes = xsdt.to_bytes().unwrap();
+        let config = AcpiConfig::new(*b"VENDOR", 7).creator(*b"VTLS", 0x1234);
+        config.stamp(&mut bytes).unwrap();
+
+        let (header, _) = SdtHeader::parse(&bytes, b"XSDT").unwrap();
+        assert_eq!(&header.oem_id, b"VENDOR");
+        assert_eq!(&header.oem_table_id, b"FOOBARXS");
+        assert_eq!(header.oem_revision.get(), 7);
+        assert_eq!(&header.creator_id, b"VTLS");
+        assert_eq!(header.creator_revision.get(), 0x1234);
+
+        assert!(matches!(
+            config.stamp(&mut bytes[..35]),
+            Err(AcpiError::InvalidTableLength)
+        ));
+    }
+    #[test]
+    fn test_sdt_to_bytes() {
+        let mut xsdt = Xsdt::new(*b"FOOBAR", *b"FOOBARXS", 0, vec![0x1000]);
+        let bytes = xsdt.to_bytes().unwrap();
+        assert_eq!(bytes.len(), xsdt.len());
+        assert_eq!(&bytes[..4], b"XSDT");
+        assert_eq!(checksum(&[&bytes]), 0);
+
+        let mut written = Vec::new();
+        xsdt.write_to(&mut written).unwrap();
+        assert_eq!(written, bytes);
+
+        let mut full = [0u8; 8];
+        let err = xsdt.write_to(&mut full.as_mut_slice()).unwrap_err();
+        assert!(matches!(err, AcpiError::Write(_)));
+    }
+
+    #[test]
+    fn test_sdt_header() {
+        fn check_header<T: Sdt>(table: &T, signature: &[u8; 4]) {
+            let header = table.header().unwrap();
+            assert_eq!(&header.signature, signature);
+            assert_eq!(header.length.get() as usize, table.len());
+        }
+
+        let mut mcfg = Mcfg::new(*b"FOOBAR", *b"FOOBARMC", 0, 0xe000_0000);
+        mcfg.add_segment(0xf000_0000, 1, 0, 0xff);
+        check_header(&mcfg, b"MCFG");
+        let io_apic = madt::IoAPIC::new(0, 0xfec0_0000);
+        let madt = Madt::new(
+            *b"FOOBAR",
+            *b"FOOBARMA",
+            0,
+            0xfee0_0000,
+            io_apic.as_bytes().to_vec(),
+        );
+        check_header(&madt, b"APIC");
+        let xsdt = Xsdt::new(*b"FOOBAR", *b"FOOBARXS", 0, vec![0x1000, 0x2000]);
+        check_header(&xsdt, b"XSDT");
+
+        assert!(Facs::new(0).header().is_none());
+        assert!(Rsdp::new(*b"FOOBAR", 0x1000).header().is_none());
+    }
+
+    #[test]
+    fn test_generic_address_structure() {
+        let gas =
+            GenericAddressStructure::new(AddressSpace::SystemIo, 8, 0, AccessSize::Byte, 0x3f8)
+                .unwrap();
+        let raw = GenericAddressStructure::from_raw(1, 8, 0, 1, 0x3f8);
+        assert_eq!(gas.as_bytes(), raw.as_bytes());
+
+        let valid = [
+            (32, 0, AccessSize::DWord),
+            (1, 3, AccessSize::Byte),
+            (128, 0, AccessSize::Byte),
+            (64, 0, AccessSize::DWord),
+            (64, 0, AccessSize::Undefined),
+        ];
+        for (width, offset, access_size) in valid {
+            let gas = GenericAddressStructure::new(
+                AddressSpace::SystemMemory,
+                width,
+                offset,
+                access_size,
+                0,
+            );
+            assert!(gas.is_ok(), "{width} {offset} {access_size:?}");
+        }
+
+        let invalid = [
+            (0, 0, AccessSize::DWord),
+            (48, 0, AccessSize::DWord),
+            (32, 4, AccessSize::DWord),
+            (12, 0, AccessSize::Byte),
+            (255, 1, AccessSize::Undefined),
+        ];
+        for (width, offset, access_size) in invalid {
+            let gas = GenericAddressStructure::new(
+                AddressSpace::SystemMemory,
+                width,
+                offset,
+                access_size,
+                0,
+            );
+            assert!(
+                matches!(gas, Err(AcpiError::InvalidRegisterSize)),
+                "{width} {offset} {access_size:?}"
+            );
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let mut mcfg = Mcfg::new(*b"FOOBAR", *b"FOOBARMC", 0, 0xe000_0000);
+        mcfg.add_segment(0xf000_0000, 1, 0, 0xff);
+        let json = serde_json::to_value(&mcfg).unwrap();
+        assert_eq!(json["header"]["signature"], serde_json::json!(b"MCFG"));
+        assert_eq!(json["header"]["length"], 76);
+        assert_eq!(json["pci_range_entries"][1]["base_address"], 0xf000_0000u64);
+        assert_eq!(json["pci_range_entries"][1]["end"], 0xff);
+
+        let mut restored: Mcfg = serde_json::from_value(json).unwrap();
+        assert_eq!(restored.to_bytes().unwrap(), mcfg.to_bytes().unwrap());
+    }
 }
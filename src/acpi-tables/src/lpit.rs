@@ -0,0 +1,178 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use alloc::vec::Vec;
+use core::mem::size_of;
+
+use zerocopy::{Immutable, IntoBytes};
+
+use crate::endian::{U16, U32, U64};
+use crate::{GenericAddressStructure, Result, Sdt, SdtHeader, checksum};
+
+// Type of a native C-state based low power idle state.
+const LPI_TYPE_NATIVE_CSTATE: u32 = 0;
+
+/// The low power idle state is disabled.
+pub const LPI_FLAG_DISABLED: u32 = 1 << 0;
+/// The residency counter of the low power idle state is not available.
+pub const LPI_FLAG_COUNTER_UNAVAILABLE: u32 = 1 << 1;
+
+/// A native C-state based low power idle state.
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LpiNativeCState {
+    lpi_type: U32,
+    length: U32,
+    unique_id: U16,
+    _reserved: U16,
+    flags: U32,
+    entry_trigger: GenericAddressStructure,
+    residency: U32,
+    latency: U32,
+    residency_counter: GenericAddressStructure,
+    residency_counter_frequency: U64,
+}
+
+assert_layout!(LpiNativeCState, 56, residency_counter: 36);
+
+impl LpiNativeCState {
+    /// Create a low power idle state whose residency is counted by `residency_counter`.
+    ///
+    /// A `residency_counter_frequency` of 0 means the counter runs at the TSC frequency.
+    pub fn new(
+        unique_id: u16,
+        entry_trigger: GenericAddressStructure,
+        min_residency_us: u32,
+        latency_us: u32,
+        residency_counter: GenericAddressStructure,
+        residency_counter_frequency: u64,
+    ) -> Self {
+        LpiNativeCState {
+            lpi_type: U32::new(LPI_TYPE_NATIVE_CSTATE),
+            length: U32::new(size_of::<LpiNativeCState>().try_into().unwrap()),
+            unique_id: U16::new(unique_id),
+            _reserved: U16::new(0),
+            flags: U32::new(0),
+            entry_trigger,
+            residency: U32::new(min_residency_us),
+            latency: U32::new(latency_us),
+            residency_counter,
+            residency_counter_frequency: U64::new(residency_counter_frequency),
+        }
+    }
+
+    /// Set the flags of the low power idle state, made of `LPI_FLAG_*` bits.
+    pub fn flags(mut self, flags: u32) -> Self {
+        self.flags = U32::new(flags);
+        self
+    }
+}
+
+/// Low Power Idle Table (LPIT)
+///
+/// This table describes the platform low power idle states, such as the one the platform
+/// enters when all processors are idle in suspend-to-idle, along with the counters reporting
+/// how long they were resident in them. More information about this table can be found in the
+/// Intel specification:
+/// https://uefi.org/sites/default/files/resources/Intel_ACPI_Low_Power_S0_Idle.pdf
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Lpit {
+    header: SdtHeader,
+    states: Vec<LpiNativeCState>,
+}
+
+impl Lpit {
+    /// Create an LPIT table describing the given low power idle states.
+    pub fn new(
+        oem_id: [u8; 6],
+        oem_table_id: [u8; 8],
+        oem_revision: u32,
+        states: Vec<LpiNativeCState>,
+    ) -> Self {
+        let length = size_of::<SdtHeader>() + states.len() * size_of::<LpiNativeCState>();
+        let header = SdtHeader::new(
+            *b"LPIT",
+            length.try_into().unwrap(),
+            1,
+            oem_id,
+            oem_table_id,
+            oem_revision,
+        );
+
+        let mut lpit = Lpit { header, states };
+        lpit.header.checksum = checksum(&[lpit.header.as_bytes(), lpit.states.as_bytes()]);
+        lpit
+    }
+}
+
+impl Sdt for Lpit {
+    fn len(&self) -> usize {
+        self.header.length.get().try_into().unwrap()
+    }
+
+    fn header(&self) -> Option<&SdtHeader> {
+        Some(&self.header)
+    }
+
+    fn to_bytes(&mut self) -> Result<Vec<u8>> {
+        Ok([self.header.as_bytes(), self.states.as_bytes()].concat())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vm_memory::{Bytes, GuestAddress, GuestMemoryMmap};
+
+    use super::*;
+
+    #[test]
+    fn test_lpit() {
+        let counter = GenericAddressStructure::from_raw(0, 64, 0, 4, 0xd000_0000);
+        let state = LpiNativeCState::new(
+            0,
+            GenericAddressStructure::default(),
+            1000,
+            100,
+            counter,
+            1_000_000,
+        );
+        let mut lpit = Lpit::new(*b"FOOBAR", *b"FOOBARLP", 0, vec![state]);
+        assert_eq!(lpit.len(), 36 + 56);
+
+        let mem: GuestMemoryMmap =
+            GuestMemoryMmap::from_ranges(&[(GuestAddress(0), 0x1000)]).unwrap();
+        lpit.write_to_guest(&mem, GuestAddress(0)).unwrap();
+        let mut bytes = vec![0u8; lpit.len()];
+        mem.read_slice(&mut bytes, GuestAddress(0)).unwrap();
+        assert_eq!(checksum(&[&bytes]), 0);
+        assert_eq!(&bytes[..4], b"LPIT");
+        // A native C-state, 56 bytes long and enabled.
+        assert_eq!(&bytes[36..44], &[0, 0, 0, 0, 56, 0, 0, 0]);
+        assert_eq!(&bytes[48..52], &[0; 4]);
+        assert_eq!(&bytes[64..68], &1000u32.to_le_bytes());
+        assert_eq!(&bytes[72..84], counter.as_bytes());
+        assert_eq!(&bytes[84..92], &1_000_000u64.to_le_bytes());
+    }
+
+    #[test]
+    fn test_lpit_flags() {
+        let state = LpiNativeCState::new(
+            1,
+            GenericAddressStructure::default(),
+            1000,
+            100,
+            GenericAddressStructure::default(),
+            0,
+        )
+        .flags(LPI_FLAG_COUNTER_UNAVAILABLE);
+        let lpit = Lpit::new(*b"FOOBAR", *b"FOOBARLP", 0, vec![state]);
+        let bytes = lpit.states.as_bytes();
+        assert_eq!(checksum(&[lpit.header.as_bytes(), bytes]), 0);
+        assert_eq!(&bytes[8..10], &1u16.to_le_bytes());
+        assert_eq!(&bytes[12..16], &2u32.to_le_bytes());
+    }
+}
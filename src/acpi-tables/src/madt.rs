@@ -3,15 +3,30 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
-use std::mem::size_of;
+use alloc::vec::Vec;
+use core::mem::size_of;
 
-use vm_memory::{Address, Bytes, GuestAddress, GuestMemory};
-use zerocopy::little_endian::U32;
-use zerocopy::{Immutable, IntoBytes};
+use zerocopy::{FromBytes, Immutable, IntoBytes};
 
-use crate::{AcpiError, Result, Sdt, SdtHeader, checksum};
+use crate::endian::{U16, U32, U64};
+use crate::{AcpiError, Result, Sdt, SdtHeader, SpecRevision, checksum};
 
 const MADT_CPU_ENABLE_FLAG: u32 = 0;
+const MADT_CPU_ONLINE_CAPABLE_FLAG: u32 = 1;
+/// The platform also has dual 8259 PICs, which must be disabled to use the APICs.
+pub const MADT_F_PCAT_COMPAT: u32 = 0;
+
+// MPS INTI flags, describing the polarity and the trigger mode of an interrupt. Both default to
+// the ones of the bus the interrupt comes from, ISA interrupts are edge triggered and active
+// high.
+/// The interrupt is active high.
+pub const MPS_INTI_POLARITY_ACTIVE_HIGH: u16 = 0b01;
+/// The interrupt is active low.
+pub const MPS_INTI_POLARITY_ACTIVE_LOW: u16 = 0b11;
+/// The interrupt is edge triggered.
+pub const MPS_INTI_TRIGGER_EDGE: u16 = 0b01 << 2;
+/// The interrupt is level triggered.
+pub const MPS_INTI_TRIGGER_LEVEL: u16 = 0b11 << 2;
 
 // clippy doesn't understand that we actually "use" the fields of this struct when we serialize
 // them as bytes in guest memory, so here we just ignore dead code to avoid having to name
@@ -19,6 +34,7 @@ const MADT_CPU_ENABLE_FLAG: u32 = 0;
 #[allow(dead_code)]
 #[repr(C, packed)]
 #[derive(Copy, Clone, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LocalAPIC {
     r#type: u8,
     length: u8,
@@ -27,6 +43,8 @@ pub struct LocalAPIC {
     flags: U32,
 }
 
+assert_layout!(LocalAPIC, 8);
+
 impl LocalAPIC {
     pub fn new(cpu_id: u8) -> Self {
         Self {
@@ -37,6 +55,12 @@ impl LocalAPIC {
             flags: U32::new(1u32 << MADT_CPU_ENABLE_FLAG),
         }
     }
+
+    /// Mark the processor as not enabled at boot, but able to be brought online later.
+    pub fn online_capable(mut self) -> Self {
+        self.flags = U32::new(1u32 << MADT_CPU_ONLINE_CAPABLE_FLAG);
+        self
+    }
 }
 
 // clippy doesn't understand that we actually "use" the fields of this struct when we serialize
@@ -45,6 +69,80 @@ impl LocalAPIC {
 #[allow(dead_code)]
 #[repr(C, packed)]
 #[derive(Copy, Clone, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LocalX2Apic {
+    r#type: u8,
+    length: u8,
+    _reserved: U16,
+    x2apic_id: U32,
+    flags: U32,
+    processor_uid: U32,
+}
+
+assert_layout!(LocalX2Apic, 16);
+
+impl LocalX2Apic {
+    /// Create a Processor Local x2APIC structure, for processors whose APIC ID does not fit the
+    /// 8 bits of a Processor Local APIC structure.
+    pub fn new(cpu_id: u32) -> Self {
+        Self {
+            r#type: 9,
+            length: 16,
+            _reserved: U16::ZERO,
+            x2apic_id: U32::new(cpu_id),
+            flags: U32::new(1u32 << MADT_CPU_ENABLE_FLAG),
+            processor_uid: U32::new(cpu_id),
+        }
+    }
+
+    /// Mark the processor as not enabled at boot, but able to be brought online later.
+    pub fn online_capable(mut self) -> Self {
+        self.flags = U32::new(1u32 << MADT_CPU_ONLINE_CAPABLE_FLAG);
+        self
+    }
+}
+
+/// Serialize the structure describing the processor whose APIC ID is `cpu_id`.
+///
+/// This is a Processor Local APIC structure if the ID fits its 8 bits, and a Processor Local
+/// x2APIC structure otherwise. The ID 0xff, which is the xAPIC broadcast ID, also needs the
+/// latter.
+pub fn local_apic_structure(cpu_id: u32) -> Vec<u8> {
+    processor_structure(cpu_id, true)
+}
+
+// Serialize the structure describing the processor whose APIC ID is `cpu_id`, enabled if it is
+// `present` and online capable otherwise.
+fn processor_structure(cpu_id: u32, present: bool) -> Vec<u8> {
+    match u8::try_from(cpu_id) {
+        Ok(cpu_id) if cpu_id != u8::MAX => {
+            let local_apic = LocalAPIC::new(cpu_id);
+            let local_apic = if present {
+                local_apic
+            } else {
+                local_apic.online_capable()
+            };
+            local_apic.as_bytes().to_vec()
+        }
+        _ => {
+            let x2apic = LocalX2Apic::new(cpu_id);
+            let x2apic = if present {
+                x2apic
+            } else {
+                x2apic.online_capable()
+            };
+            x2apic.as_bytes().to_vec()
+        }
+    }
+}
+
+// clippy doesn't understand that we actually "use" the fields of this struct when we serialize
+// them as bytes in guest memory, so here we just ignore dead code to avoid having to name
+// everything with an underscore prefix
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Copy, Clone, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct IoAPIC {
     r#type: u8,
     length: u8,
@@ -54,6 +152,8 @@ pub struct IoAPIC {
     gsi_base: U32,
 }
 
+assert_layout!(IoAPIC, 12);
+
 impl IoAPIC {
     pub fn new(ioapic_id: u8, apic_address: u32) -> Self {
         IoAPIC {
@@ -72,19 +172,381 @@ impl IoAPIC {
 // everything with an underscore prefix
 #[allow(dead_code)]
 #[repr(C, packed)]
-#[derive(Debug, IntoBytes, Immutable)]
+#[derive(Copy, Clone, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InterruptSourceOverride {
+    r#type: u8,
+    length: u8,
+    bus: u8,
+    source: u8,
+    gsi: U32,
+    flags: U16,
+}
+
+assert_layout!(InterruptSourceOverride, 10);
+
+impl InterruptSourceOverride {
+    /// Create an Interrupt Source Override structure, routing the ISA IRQ `source` to `gsi`.
+    ///
+    /// `flags` are made of `MPS_INTI_*` bits, 0 keeping the defaults of the ISA bus.
+    pub fn new(source: u8, gsi: u32, flags: u16) -> Self {
+        InterruptSourceOverride {
+            r#type: 2,
+            length: 10,
+            // The only bus whose interrupts can be overridden is ISA.
+            bus: 0,
+            source,
+            gsi: U32::new(gsi),
+            flags: U16::new(flags),
+        }
+    }
+}
+
+// clippy doesn't understand that we actually "use" the fields of this struct when we serialize
+// them as bytes in guest memory, so here we just ignore dead code to avoid having to name
+// everything with an underscore prefix
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Copy, Clone, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NmiSource {
+    r#type: u8,
+    length: u8,
+    flags: U16,
+    gsi: U32,
+}
+
+assert_layout!(NmiSource, 8);
+
+impl NmiSource {
+    /// Create a Non-Maskable Interrupt Source structure, for the I/O APIC input `gsi` wired to
+    /// an NMI.
+    pub fn new(gsi: u32, flags: u16) -> Self {
+        NmiSource {
+            r#type: 3,
+            length: 8,
+            flags: U16::new(flags),
+            gsi: U32::new(gsi),
+        }
+    }
+}
+
+/// Processor UID of a Local APIC NMI structure applying to all the processors.
+pub const LOCAL_APIC_NMI_ALL_PROCESSORS: u8 = 0xff;
+
+// clippy doesn't understand that we actually "use" the fields of this struct when we serialize
+// them as bytes in guest memory, so here we just ignore dead code to avoid having to name
+// everything with an underscore prefix
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Copy, Clone, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LocalApicNmi {
+    r#type: u8,
+    length: u8,
+    processor_uid: u8,
+    flags: U16,
+    lint: u8,
+}
+
+assert_layout!(LocalApicNmi, 6);
+
+impl LocalApicNmi {
+    /// Create a Local APIC NMI structure, for the `lint` input (0 or 1) of the local APIC of
+    /// the processor `processor_uid` wired to an NMI.
+    pub fn new(processor_uid: u8, lint: u8, flags: u16) -> Self {
+        LocalApicNmi {
+            r#type: 4,
+            length: 6,
+            processor_uid,
+            flags: U16::new(flags),
+            lint,
+        }
+    }
+}
+
+// Bits of the flags of a GICC structure.
+const GICC_PERFORMANCE_INTERRUPT_EDGE_FLAG: u32 = 1;
+const GICC_VGIC_MAINTENANCE_INTERRUPT_EDGE_FLAG: u32 = 2;
+const GICC_ONLINE_CAPABLE_FLAG: u32 = 3;
+
+// clippy doesn't understand that we actually "use" the fields of this struct when we serialize
+// them as bytes in guest memory, so here we just ignore dead code to avoid having to name
+// everything with an underscore prefix
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Copy, Clone, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Gicc {
+    r#type: u8,
+    length: u8,
+    _reserved1: U16,
+    cpu_interface_number: U32,
+    processor_uid: U32,
+    flags: U32,
+    parking_protocol_version: U32,
+    performance_interrupt_gsiv: U32,
+    parked_address: U64,
+    physical_base_address: U64,
+    gicv: U64,
+    gich: U64,
+    vgic_maintenance_interrupt: U32,
+    gicr_base_address: U64,
+    mpidr: U64,
+    processor_power_efficiency_class: u8,
+    _reserved2: u8,
+    spe_overflow_interrupt: U16,
+    trbe_interrupt: U16,
+}
+
+assert_layout!(Gicc, 82, mpidr: 68, spe_overflow_interrupt: 78, trbe_interrupt: 80);
+
+impl Gicc {
+    /// Create a GIC CPU Interface structure, 82 bytes long, for the enabled processor whose
+    /// affinity is `mpidr`.
+    ///
+    /// The processor has no performance, SPE or TRBE interrupt, no GICv2 CPU interface and no
+    /// virtualization extensions until set with the methods below. Its redistributor is
+    /// described by a GICR structure.
+    pub fn new(cpu_interface_number: u32, processor_uid: u32, mpidr: u64) -> Self {
+        Gicc {
+            r#type: 0xb,
+            length: 82,
+            cpu_interface_number: U32::new(cpu_interface_number),
+            processor_uid: U32::new(processor_uid),
+            flags: U32::new(1u32 << MADT_CPU_ENABLE_FLAG),
+            mpidr: U64::new(mpidr),
+            ..Default::default()
+        }
+    }
+
+    /// Set the GSIV of the performance monitoring interrupt.
+    pub fn performance_interrupt(mut self, gsiv: u32, edge_triggered: bool) -> Self {
+        self.performance_interrupt_gsiv = U32::new(gsiv);
+        self.set_flag(GICC_PERFORMANCE_INTERRUPT_EDGE_FLAG, edge_triggered);
+        self
+    }
+
+    /// Set the address of the GICv2 CPU interface, for GICs without system registers.
+    pub fn physical_base_address(mut self, address: u64) -> Self {
+        self.physical_base_address = U64::new(address);
+        self
+    }
+
+    /// Set the addresses of the virtual CPU interface and the virtual interface control
+    /// block, and the GSIV of the virtual GIC maintenance interrupt.
+    pub fn vgic(mut self, gicv: u64, gich: u64, gsiv: u32, edge_triggered: bool) -> Self {
+        self.gicv = U64::new(gicv);
+        self.gich = U64::new(gich);
+        self.vgic_maintenance_interrupt = U32::new(gsiv);
+        self.set_flag(GICC_VGIC_MAINTENANCE_INTERRUPT_EDGE_FLAG, edge_triggered);
+        self
+    }
+
+    /// Set the address of the redistributor of the processor, when it isn't described by a
+    /// GICR structure.
+    pub fn gicr_base_address(mut self, address: u64) -> Self {
+        self.gicr_base_address = U64::new(address);
+        self
+    }
+
+    /// Set the GSIV of the Statistical Profiling Extension buffer overflow interrupt.
+    pub fn spe_overflow_interrupt(mut self, gsiv: u16) -> Self {
+        self.spe_overflow_interrupt = U16::new(gsiv);
+        self
+    }
+
+    /// Set the GSIV of the Trace Buffer Extension interrupt.
+    pub fn trbe_interrupt(mut self, gsiv: u16) -> Self {
+        self.trbe_interrupt = U16::new(gsiv);
+        self
+    }
+
+    /// Mark the processor as not enabled at boot, but able to be brought online later.
+    pub fn online_capable(mut self) -> Self {
+        self.set_flag(MADT_CPU_ENABLE_FLAG, false);
+        self.set_flag(GICC_ONLINE_CAPABLE_FLAG, true);
+        self
+    }
+
+    fn set_flag(&mut self, bit: u32, value: bool) {
+        let flags = self.flags.get() & !(1 << bit);
+        self.flags = U32::new(flags | (u32::from(value) << bit));
+    }
+}
+
+// clippy doesn't understand that we actually "use" the fields of this struct when we serialize
+// them as bytes in guest memory, so here we just ignore dead code to avoid having to name
+// everything with an underscore prefix
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Copy, Clone, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Gicd {
+    r#type: u8,
+    length: u8,
+    _reserved1: U16,
+    gic_id: U32,
+    physical_base_address: U64,
+    system_vector_base: U32,
+    gic_version: u8,
+    _reserved2: [u8; 3],
+}
+
+assert_layout!(Gicd, 24);
+
+impl Gicd {
+    /// Create a GIC Distributor structure for the distributor at `base_address`, of a GIC whose
+    /// architecture version is `gic_version`, such as 3 for a GICv3.
+    pub fn new(base_address: u64, gic_version: u8) -> Self {
+        Gicd {
+            r#type: 0xc,
+            length: 24,
+            _reserved1: U16::ZERO,
+            gic_id: U32::ZERO,
+            physical_base_address: U64::new(base_address),
+            system_vector_base: U32::ZERO,
+            gic_version,
+            _reserved2: [0; 3],
+        }
+    }
+}
+
+// Bit of the flags of a GIC MSI Frame structure selecting its SPI count and base over the ones
+// of its registers.
+const GIC_MSI_FRAME_SPI_COUNT_BASE_SELECT_FLAG: u32 = 0;
+
+// clippy doesn't understand that we actually "use" the fields of this struct when we serialize
+// them as bytes in guest memory, so here we just ignore dead code to avoid having to name
+// everything with an underscore prefix
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Copy, Clone, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GicMsiFrame {
+    r#type: u8,
+    length: u8,
+    _reserved: U16,
+    msi_frame_id: U32,
+    physical_base_address: U64,
+    flags: U32,
+    spi_count: U16,
+    spi_base: U16,
+}
+
+assert_layout!(GicMsiFrame, 24);
+
+impl GicMsiFrame {
+    /// Create a GIC MSI Frame structure for the GICv2m frame at `base_address`.
+    pub fn new(msi_frame_id: u32, base_address: u64) -> Self {
+        GicMsiFrame {
+            r#type: 0xd,
+            length: 24,
+            _reserved: U16::ZERO,
+            msi_frame_id: U32::new(msi_frame_id),
+            physical_base_address: U64::new(base_address),
+            flags: U32::ZERO,
+            spi_count: U16::ZERO,
+            spi_base: U16::ZERO,
+        }
+    }
+
+    /// Set the SPIs of the frame, overriding the ones its registers report.
+    pub fn spi_range(mut self, spi_base: u16, spi_count: u16) -> Self {
+        self.flags = U32::new(1u32 << GIC_MSI_FRAME_SPI_COUNT_BASE_SELECT_FLAG);
+        self.spi_base = U16::new(spi_base);
+        self.spi_count = U16::new(spi_count);
+        self
+    }
+}
+
+// clippy doesn't understand that we actually "use" the fields of this struct when we serialize
+// them as bytes in guest memory, so here we just ignore dead code to avoid having to name
+// everything with an underscore prefix
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Copy, Clone, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Gicr {
+    r#type: u8,
+    length: u8,
+    _reserved: U16,
+    discovery_range_base_address: U64,
+    discovery_range_length: U32,
+}
+
+assert_layout!(Gicr, 16);
+
+impl Gicr {
+    /// Create a GIC Redistributor structure for the redistributors of the range of
+    /// `length` bytes at `base_address`.
+    pub fn new(base_address: u64, length: u32) -> Self {
+        Gicr {
+            r#type: 0xe,
+            length: 16,
+            _reserved: U16::ZERO,
+            discovery_range_base_address: U64::new(base_address),
+            discovery_range_length: U32::new(length),
+        }
+    }
+}
+
+// clippy doesn't understand that we actually "use" the fields of this struct when we serialize
+// them as bytes in guest memory, so here we just ignore dead code to avoid having to name
+// everything with an underscore prefix
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Copy, Clone, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GicIts {
+    r#type: u8,
+    length: u8,
+    _reserved1: U16,
+    its_id: U32,
+    base_address: U64,
+    _reserved2: U32,
+}
+
+assert_layout!(GicIts, 20);
+
+impl GicIts {
+    /// Create a GIC Interrupt Translation Service structure for the ITS at `base_address`.
+    ///
+    /// `its_id` is the identifier the IORT ITS group nodes refer to it with.
+    pub fn new(its_id: u32, base_address: u64) -> Self {
+        GicIts {
+            r#type: 0xf,
+            length: 20,
+            _reserved1: U16::ZERO,
+            its_id: U32::new(its_id),
+            base_address: U64::new(base_address),
+            _reserved2: U32::ZERO,
+        }
+    }
+}
+
+// clippy doesn't understand that we actually "use" the fields of this struct when we serialize
+// them as bytes in guest memory, so here we just ignore dead code to avoid having to name
+// everything with an underscore prefix
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Debug, IntoBytes, FromBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct MadtHeader {
     sdt: SdtHeader,
     base_address: U32,
     flags: U32,
 }
 
+assert_layout!(MadtHeader, 44);
+
 /// Multiple APIC Description Table (MADT)
 ///
 /// This table includes information about the interrupt controllers of the device.
 /// More information about this table can be found in the ACPI specification:
 /// https://uefi.org/specs/ACPI/6.5/05_ACPI_Software_Programming_Model.html#multiple-apic-description-table-madt
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Madt {
     header: MadtHeader,
     interrupt_controllers: Vec<u8>,
@@ -123,6 +585,153 @@ impl Madt {
             interrupt_controllers,
         }
     }
+
+    /// Set the MADT flags
+    pub fn set_flags(&mut self, flags: u32) {
+        self.header.flags = U32::new(flags);
+        self.header.sdt.checksum = 0;
+        self.header.sdt.checksum = checksum(&[
+            self.header.as_bytes(),
+            self.interrupt_controllers.as_bytes(),
+        ]);
+    }
+
+    /// Parse a MADT, checking its signature, length and checksum, along with the lengths of its
+    /// interrupt controller structures.
+    pub fn parse(bytes: &[u8]) -> Result<Self> {
+        SdtHeader::parse(bytes, b"APIC")?;
+        let (header, interrupt_controllers) =
+            MadtHeader::read_from_prefix(bytes).map_err(|_| AcpiError::InvalidTableLength)?;
+
+        let madt = Madt {
+            header,
+            interrupt_controllers: interrupt_controllers.to_vec(),
+        };
+        let len: usize = madt.structures().map(<[u8]>::len).sum();
+        if len != madt.interrupt_controllers.len() {
+            return Err(AcpiError::InvalidMadtStructure);
+        }
+        Ok(madt)
+    }
+
+    /// Address of the local interrupt controllers
+    pub fn base_address(&self) -> u32 {
+        self.header.base_address.get()
+    }
+
+    /// MADT flags
+    pub fn flags(&self) -> u32 {
+        self.header.flags.get()
+    }
+
+    /// Interrupt controller structures, each starting with its type and length
+    pub fn structures(&self) -> impl Iterator<Item = &[u8]> {
+        let mut rest = self.interrupt_controllers.as_slice();
+        core::iter::from_fn(move || {
+            let len = usize::from(*rest.get(1)?);
+            // A malformed structure ends the iteration.
+            if len < 2 || len > rest.len() {
+                return None;
+            }
+            let (structure, tail) = rest.split_at(len);
+            rest = tail;
+            Some(structure)
+        })
+    }
+}
+
+/// Builder of a [`Madt`], one interrupt controller structure at a time
+///
+/// The processors which are present at boot are enabled, while the other possible ones are
+/// online capable, so that the guest can bring them up once they are hot-plugged.
+#[derive(Clone, Debug, Default)]
+pub struct MadtBuilder {
+    base_address: u32,
+    flags: u32,
+    spec_revision: SpecRevision,
+    interrupt_controllers: Vec<u8>,
+}
+
+impl MadtBuilder {
+    /// Create a builder for a MADT whose local interrupt controllers are at `base_address`.
+    pub fn new(base_address: u32) -> Self {
+        MadtBuilder {
+            base_address,
+            ..Default::default()
+        }
+    }
+
+    /// Set the MADT flags.
+    pub fn flags(mut self, flags: u32) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    /// Target an older revision of the ACPI specification than the latest one.
+    ///
+    /// This selects the revision of the MADT, and the length of the GIC CPU interfaces added
+    /// afterwards, which lack the fields defined by later revisions.
+    pub fn spec_revision(mut self, revision: SpecRevision) -> Self {
+        self.spec_revision = revision;
+        self
+    }
+
+    /// Add an interrupt controller structure, such as an [`IoAPIC`] or a [`Gicd`].
+    pub fn structure<T: IntoBytes + Immutable>(mut self, structure: &T) -> Self {
+        self.interrupt_controllers
+            .extend_from_slice(structure.as_bytes());
+        self
+    }
+
+    /// Add the local APIC of the processor whose APIC ID is `cpu_id`, as an xAPIC or x2APIC
+    /// structure depending on the ID.
+    pub fn local_apic(mut self, cpu_id: u32, present: bool) -> Self {
+        self.interrupt_controllers
+            .extend(processor_structure(cpu_id, present));
+        self
+    }
+
+    /// Add the local APICs of `possible` processors, of which the first `present` ones are
+    /// present at boot. The APIC IDs of the processors are their indexes.
+    pub fn local_apics(self, present: u32, possible: u32) -> Self {
+        (0..possible).fold(self, |builder, cpu_id| {
+            builder.local_apic(cpu_id, cpu_id < present)
+        })
+    }
+
+    /// Add the GIC CPU interface of a processor.
+    pub fn gicc(mut self, gicc: Gicc, present: bool) -> Self {
+        let mut gicc = if present { gicc } else { gicc.online_capable() };
+        gicc.length = match self.spec_revision {
+            SpecRevision::Acpi5_0 => 40,
+            SpecRevision::Acpi5_1 => 76,
+            SpecRevision::Acpi6_5 => 82,
+            _ => 80,
+        };
+        self.interrupt_controllers
+            .extend_from_slice(&gicc.as_bytes()[..usize::from(gicc.length)]);
+        self
+    }
+
+    /// Build the MADT.
+    pub fn build(self, oem_id: [u8; 6], oem_table_id: [u8; 8], oem_revision: u32) -> Madt {
+        let mut madt = Madt::new(
+            oem_id,
+            oem_table_id,
+            oem_revision,
+            self.base_address,
+            self.interrupt_controllers,
+        );
+        madt.header.sdt.revision = match self.spec_revision {
+            SpecRevision::Acpi5_0 | SpecRevision::Acpi5_1 => 3,
+            SpecRevision::Acpi6_0 | SpecRevision::Acpi6_1 | SpecRevision::Acpi6_2 => 4,
+            SpecRevision::Acpi6_3 | SpecRevision::Acpi6_4 => 5,
+            SpecRevision::Acpi6_5 => 6,
+        };
+        // This also refolds the checksum over the revision.
+        madt.set_flags(self.flags);
+        madt
+    }
 }
 
 impl Sdt for Madt {
@@ -130,13 +739,205 @@ impl Sdt for Madt {
         self.header.sdt.length.get().try_into().unwrap()
     }
 
-    fn write_to_guest<M: GuestMemory>(&mut self, mem: &M, address: GuestAddress) -> Result<()> {
-        mem.write_slice(self.header.as_bytes(), address)?;
-        let address = address
-            .checked_add(size_of::<MadtHeader>() as u64)
-            .ok_or(AcpiError::InvalidGuestAddress)?;
-        mem.write_slice(self.interrupt_controllers.as_bytes(), address)?;
+    fn header(&self) -> Option<&SdtHeader> {
+        Some(&self.header.sdt)
+    }
+
+    fn to_bytes(&mut self) -> Result<Vec<u8>> {
+        Ok([
+            self.header.as_bytes(),
+            self.interrupt_controllers.as_bytes(),
+        ]
+        .concat())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_local_x2apic() {
+        let x2apic = LocalX2Apic::new(0x1_0000);
+        let bytes = x2apic.as_bytes();
+        assert_eq!(bytes.len(), 16);
+        assert_eq!(&bytes[..4], &[9, 16, 0, 0]);
+        assert_eq!(&bytes[4..8], &0x1_0000u32.to_le_bytes());
+        assert_eq!(&bytes[8..12], &1u32.to_le_bytes());
+        assert_eq!(&bytes[12..16], &0x1_0000u32.to_le_bytes());
+    }
+
+    #[test]
+    fn test_local_apic_structure() {
+        assert_eq!(local_apic_structure(3), LocalAPIC::new(3).as_bytes());
+        assert_eq!(local_apic_structure(0xfe).len(), 8);
+        assert_eq!(
+            local_apic_structure(0xff),
+            LocalX2Apic::new(0xff).as_bytes()
+        );
+        assert_eq!(
+            local_apic_structure(0x100),
+            LocalX2Apic::new(0x100).as_bytes()
+        );
+    }
+
+    #[test]
+    fn test_interrupt_source_override_and_nmi() {
+        let flags = MPS_INTI_POLARITY_ACTIVE_HIGH | MPS_INTI_TRIGGER_EDGE;
+        let iso = InterruptSourceOverride::new(0, 2, flags);
+        let bytes = iso.as_bytes();
+        assert_eq!(bytes.len(), 10);
+        assert_eq!(&bytes[..4], &[2, 10, 0, 0]);
+        assert_eq!(&bytes[4..8], &2u32.to_le_bytes());
+        assert_eq!(&bytes[8..10], &5u16.to_le_bytes());
+
+        let nmi = NmiSource::new(4, 0);
+        assert_eq!(nmi.as_bytes(), &[3, 8, 0, 0, 4, 0, 0, 0]);
+
+        let lapic_nmi = LocalApicNmi::new(LOCAL_APIC_NMI_ALL_PROCESSORS, 1, 0);
+        assert_eq!(lapic_nmi.as_bytes(), &[4, 6, 0xff, 0, 0, 1]);
+    }
+
+    #[test]
+    fn test_madt_builder() {
+        let madt = MadtBuilder::new(0xfee0_0000)
+            .flags(1 << MADT_F_PCAT_COMPAT)
+            .structure(&IoAPIC::new(0, 0xfec0_0000))
+            .local_apics(2, 4)
+            .local_apic(0x100, false)
+            .build(*b"FOOBAR", *b"FOOBARMA", 0);
+        assert_eq!(madt.len(), 44 + 12 + 4 * 8 + 16);
+        assert_eq!(madt.header.flags.get(), 1);
+        assert_eq!(
+            checksum(&[
+                madt.header.as_bytes(),
+                madt.interrupt_controllers.as_bytes()
+            ]),
+            0
+        );
+
+        // The flags of the present processors are Enabled, the ones of the others Online
+        // Capable.
+        let local_apics = &madt.interrupt_controllers[12..44];
+        let flags: Vec<_> = local_apics.chunks(8).map(|apic| apic[4]).collect();
+        assert_eq!(flags, [1, 1, 2, 2]);
+        let x2apic = &madt.interrupt_controllers[44..];
+        assert_eq!(x2apic, LocalX2Apic::new(0x100).online_capable().as_bytes());
+
+        let madt = MadtBuilder::new(0)
+            .gicc(Gicc::new(0, 0, 0), true)
+            .gicc(Gicc::new(1, 1, 1), false)
+            .build(*b"FOOBAR", *b"FOOBARMA", 0);
+        assert_eq!(madt.interrupt_controllers[12], 1);
+        assert_eq!(madt.interrupt_controllers[82 + 12], 1 << 3);
+    }
+
+    #[test]
+    fn test_madt_spec_revision() {
+        let madt = MadtBuilder::new(0)
+            .spec_revision(SpecRevision::Acpi6_0)
+            .gicc(Gicc::new(0, 0, 0).trbe_interrupt(30), true)
+            .gicc(Gicc::new(1, 1, 1), true)
+            .build(*b"FOOBAR", *b"FOOBARMA", 0);
+        assert_eq!(madt.header.sdt.revision, 4);
+        assert_eq!(madt.len(), 44 + 2 * 80);
+        let lengths: Vec<_> = madt.structures().map(<[u8]>::len).collect();
+        assert_eq!(lengths, [80, 80]);
+        assert_eq!(
+            checksum(&[
+                madt.header.as_bytes(),
+                madt.interrupt_controllers.as_bytes()
+            ]),
+            0
+        );
+    }
+
+    #[test]
+    fn test_madt_parse() {
+        let mut madt = MadtBuilder::new(0xfee0_0000)
+            .structure(&IoAPIC::new(0, 0xfec0_0000))
+            .local_apics(2, 2)
+            .build(*b"FOOBAR", *b"FOOBARMA", 0);
+        let mut bytes = madt.to_bytes().unwrap();
+        let parsed = Madt::parse(&bytes).unwrap();
+        assert_eq!(parsed.base_address(), 0xfee0_0000);
+        let types: Vec<_> = parsed.structures().map(|structure| structure[0]).collect();
+        assert_eq!(types, [1, 0, 0]);
+
+        // A structure longer than the table.
+        bytes[44 + 12 + 8 + 1] = 9;
+        bytes[9] = bytes[9].wrapping_sub(1);
+        assert!(matches!(
+            Madt::parse(&bytes),
+            Err(AcpiError::InvalidMadtStructure)
+        ));
+    }
+
+    #[test]
+    fn test_madt_flags() {
+        let mut madt = Madt::new(*b"FOOBAR", *b"FOOBARMA", 0, 0xfee0_0000, vec![]);
+        madt.set_flags(1 << MADT_F_PCAT_COMPAT);
+        assert_eq!(madt.header.flags.get(), 1);
+        assert_eq!(
+            checksum(&[
+                madt.header.as_bytes(),
+                madt.interrupt_controllers.as_bytes()
+            ]),
+            0
+        );
+    }
+
+    #[test]
+    fn test_gicc() {
+        let gicc = Gicc::new(1, 1, 0x8000_0001)
+            .performance_interrupt(23, false)
+            .vgic(0x2c02_0000, 0x2c01_0000, 25, true)
+            .trbe_interrupt(30);
+        let bytes = gicc.as_bytes();
+        assert_eq!(bytes.len(), 82);
+        assert_eq!(&bytes[..4], &[0xb, 82, 0, 0]);
+        assert_eq!(&bytes[4..8], &1u32.to_le_bytes());
+        // Enabled, with an edge triggered maintenance interrupt.
+        assert_eq!(&bytes[12..16], &0b101u32.to_le_bytes());
+        assert_eq!(&bytes[20..24], &23u32.to_le_bytes());
+        assert_eq!(&bytes[40..48], &0x2c02_0000u64.to_le_bytes());
+        assert_eq!(&bytes[48..56], &0x2c01_0000u64.to_le_bytes());
+        assert_eq!(&bytes[56..60], &25u32.to_le_bytes());
+        assert_eq!(&bytes[60..68], &[0; 8]);
+        assert_eq!(&bytes[68..76], &0x8000_0001u64.to_le_bytes());
+        assert_eq!(&bytes[80..82], &30u16.to_le_bytes());
+    }
+
+    #[test]
+    fn test_gic_distributor_structures() {
+        let gicd = Gicd::new(0x0800_0000, 3);
+        let bytes = gicd.as_bytes();
+        assert_eq!(bytes.len(), 24);
+        assert_eq!(&bytes[..2], &[0xc, 24]);
+        assert_eq!(&bytes[8..16], &0x0800_0000u64.to_le_bytes());
+        assert_eq!(bytes[20], 3);
+
+        let frame = GicMsiFrame::new(0, 0x0802_0000).spi_range(64, 32);
+        let bytes = frame.as_bytes();
+        assert_eq!(bytes.len(), 24);
+        assert_eq!(&bytes[..2], &[0xd, 24]);
+        assert_eq!(&bytes[16..24], &[1, 0, 0, 0, 32, 0, 64, 0]);
+
+        let gicr = Gicr::new(0x080a_0000, 0x40_0000);
+        let bytes = gicr.as_bytes();
+        assert_eq!(bytes.len(), 16);
+        assert_eq!(&bytes[..2], &[0xe, 16]);
+        assert_eq!(&bytes[4..12], &0x080a_0000u64.to_le_bytes());
+        assert_eq!(&bytes[12..16], &0x40_0000u32.to_le_bytes());
+    }
 
-        Ok(())
+    #[test]
+    fn test_gic_its() {
+        let its = GicIts::new(1, 0x0800_0000);
+        let bytes = its.as_bytes();
+        assert_eq!(bytes.len(), 20);
+        assert_eq!(&bytes[..4], &[0xf, 20, 0, 0]);
+        assert_eq!(&bytes[4..8], &1u32.to_le_bytes());
+        assert_eq!(&bytes[8..16], &0x0800_0000u64.to_le_bytes());
     }
 }
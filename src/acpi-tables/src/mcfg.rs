@@ -4,17 +4,20 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
-use std::mem::size_of;
+use alloc::vec::Vec;
+use core::mem::size_of;
 
-use vm_memory::{Bytes, GuestAddress, GuestMemory};
-use zerocopy::{Immutable, IntoBytes};
+use zerocopy::{FromBytes, Immutable, IntoBytes};
 
-use crate::{Result, Sdt, SdtHeader, checksum};
+use crate::endian::U32;
+use crate::{AcpiError, Result, Sdt, SdtHeader, checksum};
 
+/// An ECAM allocation structure, describing the configuration space of a range of buses
 #[allow(dead_code)]
 #[repr(C, packed)]
-#[derive(Default, Debug, IntoBytes, Clone, Copy, Immutable)]
-struct PciRangeEntry {
+#[derive(Default, Debug, IntoBytes, FromBytes, Clone, Copy, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PciRangeEntry {
     pub base_address: u64,
     pub segment: u16,
     pub start: u8,
@@ -22,13 +25,18 @@ struct PciRangeEntry {
     _reserved: u32,
 }
 
-#[allow(dead_code)]
-#[repr(C, packed)]
-#[derive(Clone, Copy, Debug, Default, IntoBytes, Immutable)]
+assert_layout!(PciRangeEntry, 16);
+
+/// PCI Express Memory-mapped Configuration Space base address description table (MCFG)
+///
+/// This table describes the ECAM regions through which the guest accesses the configuration
+/// space of the PCI segments, one allocation structure per segment and range of buses.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Mcfg {
     header: SdtHeader,
     _reserved: u64,
-    pci_range_entry: PciRangeEntry,
+    pci_range_entries: Vec<PciRangeEntry>,
 }
 
 impl Mcfg {
@@ -38,40 +46,114 @@ impl Mcfg {
         oem_revision: u32,
         pci_mmio_config_addr: u64,
     ) -> Self {
-        let header = SdtHeader::new(
-            *b"MCFG",
-            size_of::<Mcfg>().try_into().unwrap(),
-            1,
-            oem_id,
-            oem_table_id,
-            oem_revision,
-        );
+        let header = SdtHeader::new(*b"MCFG", 0, 1, oem_id, oem_table_id, oem_revision);
 
         let mut mcfg = Mcfg {
             header,
-            pci_range_entry: PciRangeEntry {
-                base_address: pci_mmio_config_addr,
-                segment: 0,
-                start: 0,
-                end: 0,
-                ..Default::default()
-            },
-            ..Default::default()
+            _reserved: 0,
+            pci_range_entries: Vec::new(),
         };
-
-        mcfg.header.checksum = checksum(&[mcfg.as_bytes()]);
+        mcfg.add_segment(pci_mmio_config_addr, 0, 0, 0);
 
         mcfg
     }
+
+    /// Add the ECAM region at `base_address` of the buses `start_bus` to `end_bus` of `segment`.
+    pub fn add_segment(&mut self, base_address: u64, segment: u16, start_bus: u8, end_bus: u8) {
+        self.pci_range_entries.push(PciRangeEntry {
+            base_address,
+            segment,
+            start: start_bus,
+            end: end_bus,
+            ..Default::default()
+        });
+
+        let length = size_of::<SdtHeader>()
+            + size_of::<u64>()
+            + self.pci_range_entries.len() * size_of::<PciRangeEntry>();
+        self.header.length = U32::new(length.try_into().unwrap());
+        self.header.checksum = 0;
+        self.header.checksum = checksum(&[
+            self.header.as_bytes(),
+            self._reserved.as_bytes(),
+            self.pci_range_entries.as_bytes(),
+        ]);
+    }
+
+    /// Parse an MCFG, checking its signature, length and checksum.
+    pub fn parse(bytes: &[u8]) -> Result<Self> {
+        let (header, rest) = SdtHeader::parse(bytes, b"MCFG")?;
+        let entries = rest
+            .get(size_of::<u64>()..)
+            .filter(|entries| entries.len().is_multiple_of(size_of::<PciRangeEntry>()))
+            .ok_or(AcpiError::InvalidTableLength)?;
+
+        Ok(Mcfg {
+            header,
+            _reserved: 0,
+            pci_range_entries: entries
+                .chunks_exact(size_of::<PciRangeEntry>())
+                .map(|entry| PciRangeEntry::read_from_bytes(entry).unwrap())
+                .collect(),
+        })
+    }
+
+    /// ECAM allocation structures of the table
+    pub fn segments(&self) -> &[PciRangeEntry] {
+        &self.pci_range_entries
+    }
 }
 
 impl Sdt for Mcfg {
     fn len(&self) -> usize {
-        self.as_bytes().len()
+        self.header.length.get().try_into().unwrap()
+    }
+
+    fn header(&self) -> Option<&SdtHeader> {
+        Some(&self.header)
+    }
+
+    fn to_bytes(&mut self) -> Result<Vec<u8>> {
+        Ok([
+            self.header.as_bytes(),
+            self._reserved.as_bytes(),
+            self.pci_range_entries.as_bytes(),
+        ]
+        .concat())
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use vm_memory::{Bytes, GuestAddress, GuestMemoryMmap};
+
+    use super::*;
+
+    #[test]
+    fn test_mcfg() {
+        let mut mcfg = Mcfg::new(*b"FOOBAR", *b"FOOBARMC", 0, 0xe000_0000);
+        assert_eq!(mcfg.len(), 44 + 16);
+        mcfg.add_segment(0xf000_0000, 1, 0, 0xff);
+        assert_eq!(mcfg.len(), 44 + 2 * 16);
+
+        let mem: GuestMemoryMmap =
+            GuestMemoryMmap::from_ranges(&[(GuestAddress(0), 0x1000)]).unwrap();
+        mcfg.write_to_guest(&mem, GuestAddress(0)).unwrap();
+        let mut bytes = vec![0u8; mcfg.len()];
+        mem.read_slice(&mut bytes, GuestAddress(0)).unwrap();
+        assert_eq!(checksum(&[&bytes]), 0);
+        assert_eq!(&bytes[4..8], &76u32.to_le_bytes());
+        assert_eq!(&bytes[44..52], &0xe000_0000u64.to_le_bytes());
+
+        let segment = &bytes[60..];
+        assert_eq!(&segment[..8], &0xf000_0000u64.to_le_bytes());
+        assert_eq!(&segment[8..12], &[1, 0, 0, 0xff]);
 
-    fn write_to_guest<M: GuestMemory>(&mut self, mem: &M, address: GuestAddress) -> Result<()> {
-        mem.write_slice(self.as_bytes(), address)?;
-        Ok(())
+        let parsed = Mcfg::parse(&bytes).unwrap();
+        let segments = parsed.segments();
+        assert_eq!(segments.len(), 2);
+        assert_eq!({ segments[1].base_address }, 0xf000_0000);
+        assert_eq!({ segments[1].segment }, 1);
+        assert_eq!(segments[1].end, 0xff);
     }
 }
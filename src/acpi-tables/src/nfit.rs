@@ -0,0 +1,358 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use alloc::vec::Vec;
+use core::mem::size_of;
+
+use zerocopy::{Immutable, IntoBytes};
+
+use crate::endian::{U16, U32, U64};
+use crate::{AcpiError, Result, Sdt, SdtHeader, checksum};
+
+const NFIT_SPA_RANGE: u16 = 0;
+const NFIT_REGION_MAPPING: u16 = 1;
+const NFIT_CONTROL_REGION: u16 = 4;
+// The proximity domain of the SPA range is valid.
+const NFIT_SPA_PROXIMITY_DOMAIN_VALID: u16 = 1 << 1;
+// Address range type GUID of persistent memory, 66F0D379-B4F3-4074-AC43-0D3318B78CDB.
+const NFIT_SPA_PERSISTENT_MEMORY: [u8; 16] = [
+    0x79, 0xd3, 0xf0, 0x66, 0xf3, 0xb4, 0x74, 0x40, 0xac, 0x43, 0x0d, 0x33, 0x18, 0xb7, 0x8c, 0xdb,
+];
+// The memory is cacheable with write-back (EFI_MEMORY_WB) and non-volatile (EFI_MEMORY_NV).
+const NFIT_SPA_MEMORY_ATTRIBUTES: u64 = 0x8 | 0x8000;
+// Identification of the emulated NVDIMMs, matching the one of other VMMs so guests bind their
+// generic drivers.
+const NFIT_CONTROL_REGION_VENDOR_ID: u16 = 0x8086;
+const NFIT_CONTROL_REGION_DEVICE_ID: u16 = 0x0007;
+const NFIT_CONTROL_REGION_REVISION_ID: u16 = 1;
+// Byte addressable NVDIMM without energy backed persistence.
+const NFIT_CONTROL_REGION_FORMAT_INTERFACE: u16 = 0x0301;
+
+fn validate_index(index: u16) -> Result<()> {
+    // Index 0 is reserved.
+    if index == 0 {
+        return Err(AcpiError::InvalidNfitIndex);
+    }
+    Ok(())
+}
+
+// clippy doesn't understand that we actually "use" the fields of this struct when we serialize
+// them as bytes in guest memory, so here we just ignore dead code to avoid having to name
+// everything with an underscore prefix
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Copy, Clone, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpaRange {
+    r#type: U16,
+    length: U16,
+    index: U16,
+    flags: U16,
+    _reserved: U32,
+    proximity_domain: U32,
+    address_range_type: [u8; 16],
+    base_address: U64,
+    range_length: U64,
+    memory_mapping_attributes: U64,
+}
+
+assert_layout!(SpaRange, 56, base_address: 32);
+
+impl SpaRange {
+    /// Create a System Physical Address Range structure describing the persistent memory of
+    /// `length` bytes at `base_address`, which belongs to `proximity_domain` if any.
+    ///
+    /// `index` identifies the range in the region mappings and must not be 0.
+    pub fn persistent_memory(
+        index: u16,
+        base_address: u64,
+        length: u64,
+        proximity_domain: Option<u32>,
+    ) -> Result<Self> {
+        validate_index(index)?;
+        let flags = match proximity_domain {
+            Some(_) => NFIT_SPA_PROXIMITY_DOMAIN_VALID,
+            None => 0,
+        };
+        Ok(SpaRange {
+            r#type: U16::new(NFIT_SPA_RANGE),
+            length: U16::new(size_of::<Self>().try_into().unwrap()),
+            index: U16::new(index),
+            flags: U16::new(flags),
+            _reserved: U32::ZERO,
+            proximity_domain: U32::new(proximity_domain.unwrap_or_default()),
+            address_range_type: NFIT_SPA_PERSISTENT_MEMORY,
+            base_address: U64::new(base_address),
+            range_length: U64::new(length),
+            memory_mapping_attributes: U64::new(NFIT_SPA_MEMORY_ATTRIBUTES),
+        })
+    }
+}
+
+// clippy doesn't understand that we actually "use" the fields of this struct when we serialize
+// them as bytes in guest memory, so here we just ignore dead code to avoid having to name
+// everything with an underscore prefix
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Copy, Clone, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RegionMapping {
+    r#type: U16,
+    length: U16,
+    device_handle: U32,
+    physical_id: U16,
+    region_id: U16,
+    spa_range_index: U16,
+    control_region_index: U16,
+    region_size: U64,
+    region_offset: U64,
+    physical_address_base: U64,
+    interleave_index: U16,
+    interleave_ways: U16,
+    state_flags: U16,
+    _reserved: U16,
+}
+
+assert_layout!(RegionMapping, 48);
+
+impl RegionMapping {
+    /// Create an NVDIMM Region Mapping structure, mapping the `region_size` bytes at
+    /// `region_offset` of the SPA range `spa_range_index` to the NVDIMM with the handle
+    /// `device_handle`, starting at `physical_address_base` in the NVDIMM.
+    ///
+    /// The region is not interleaved with other NVDIMMs.
+    pub fn new(
+        device_handle: u32,
+        spa_range_index: u16,
+        control_region_index: u16,
+        region_size: u64,
+        region_offset: u64,
+        physical_address_base: u64,
+    ) -> Result<Self> {
+        validate_index(spa_range_index)?;
+        validate_index(control_region_index)?;
+        Ok(RegionMapping {
+            r#type: U16::new(NFIT_REGION_MAPPING),
+            length: U16::new(size_of::<Self>().try_into().unwrap()),
+            device_handle: U32::new(device_handle),
+            spa_range_index: U16::new(spa_range_index),
+            control_region_index: U16::new(control_region_index),
+            region_size: U64::new(region_size),
+            region_offset: U64::new(region_offset),
+            physical_address_base: U64::new(physical_address_base),
+            // A single way, not described by an interleave structure.
+            interleave_ways: U16::new(1),
+            ..Default::default()
+        })
+    }
+}
+
+// clippy doesn't understand that we actually "use" the fields of this struct when we serialize
+// them as bytes in guest memory, so here we just ignore dead code to avoid having to name
+// everything with an underscore prefix
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Copy, Clone, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ControlRegion {
+    r#type: U16,
+    length: U16,
+    index: U16,
+    vendor_id: U16,
+    device_id: U16,
+    revision_id: U16,
+    subsystem_vendor_id: U16,
+    subsystem_device_id: U16,
+    subsystem_revision_id: U16,
+    valid_fields: u8,
+    manufacturing_location: u8,
+    manufacturing_date: U16,
+    _reserved1: U16,
+    serial_number: U32,
+    format_interface_code: U16,
+    block_control_windows: U16,
+    block_control_window_size: U64,
+    command_register_offset: U64,
+    command_register_size: U64,
+    status_register_offset: U64,
+    status_register_size: U64,
+    flags: U16,
+    _reserved2: [u8; 6],
+}
+
+assert_layout!(ControlRegion, 80);
+
+impl ControlRegion {
+    /// Create an NVDIMM Control Region structure for a byte addressable NVDIMM without block
+    /// control windows.
+    ///
+    /// `index` identifies the control region in the region mappings and must not be 0.
+    pub fn new(index: u16, serial_number: u32) -> Result<Self> {
+        validate_index(index)?;
+        Ok(ControlRegion {
+            r#type: U16::new(NFIT_CONTROL_REGION),
+            length: U16::new(size_of::<Self>().try_into().unwrap()),
+            index: U16::new(index),
+            vendor_id: U16::new(NFIT_CONTROL_REGION_VENDOR_ID),
+            device_id: U16::new(NFIT_CONTROL_REGION_DEVICE_ID),
+            revision_id: U16::new(NFIT_CONTROL_REGION_REVISION_ID),
+            serial_number: U32::new(serial_number),
+            format_interface_code: U16::new(NFIT_CONTROL_REGION_FORMAT_INTERFACE),
+            ..Default::default()
+        })
+    }
+}
+
+/// Create the structures describing the persistent memory of `size` bytes at `base_address`,
+/// backed as a whole by a single NVDIMM.
+///
+/// `handle` is the device handle of the NVDIMM, which is also the `_ADR` of its device in the
+/// ACPI namespace, as well as the index of its SPA range and control region. It must not be 0.
+pub fn single_nvdimm_structures(
+    handle: u16,
+    base_address: u64,
+    size: u64,
+    proximity_domain: Option<u32>,
+) -> Result<Vec<u8>> {
+    let spa_range = SpaRange::persistent_memory(handle, base_address, size, proximity_domain)?;
+    let region_mapping = RegionMapping::new(u32::from(handle), handle, handle, size, 0, 0)?;
+    let control_region = ControlRegion::new(handle, u32::from(handle))?;
+    Ok([
+        spa_range.as_bytes(),
+        region_mapping.as_bytes(),
+        control_region.as_bytes(),
+    ]
+    .concat())
+}
+
+// clippy doesn't understand that we actually "use" the fields of this struct when we serialize
+// them as bytes in guest memory, so here we just ignore dead code to avoid having to name
+// everything with an underscore prefix
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Clone, Debug, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct NfitHeader {
+    sdt: SdtHeader,
+    _reserved: U32,
+}
+
+assert_layout!(NfitHeader, 40);
+
+/// NVDIMM Firmware Interface Table (NFIT)
+///
+/// This table describes the NVDIMMs of the platform and the ranges of persistent memory they
+/// back, which the guest exposes as pmem devices. The NVDIMMs themselves are described in the
+/// ACPI namespace, under the NVDIMM root device. More information about this table can be found
+/// in the ACPI specification:
+/// https://uefi.org/specs/ACPI/6.5/05_ACPI_Software_Programming_Model.html#nvdimm-firmware-interface-table-nfit
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Nfit {
+    header: NfitHeader,
+    structures: Vec<u8>,
+}
+
+impl Nfit {
+    /// Create an NFIT table holding the given SPA range, region mapping and control region
+    /// structures.
+    pub fn new(
+        oem_id: [u8; 6],
+        oem_table_id: [u8; 8],
+        oem_revision: u32,
+        structures: Vec<u8>,
+    ) -> Self {
+        let length = size_of::<NfitHeader>() + structures.len();
+        let sdt_header = SdtHeader::new(
+            *b"NFIT",
+            length.try_into().unwrap(),
+            1,
+            oem_id,
+            oem_table_id,
+            oem_revision,
+        );
+
+        let mut header = NfitHeader {
+            sdt: sdt_header,
+            _reserved: U32::ZERO,
+        };
+        header.sdt.checksum = checksum(&[header.as_bytes(), structures.as_bytes()]);
+
+        Nfit { header, structures }
+    }
+}
+
+impl Sdt for Nfit {
+    fn len(&self) -> usize {
+        self.header.sdt.length.get().try_into().unwrap()
+    }
+
+    fn header(&self) -> Option<&SdtHeader> {
+        Some(&self.header.sdt)
+    }
+
+    fn to_bytes(&mut self) -> Result<Vec<u8>> {
+        Ok([self.header.as_bytes(), self.structures.as_bytes()].concat())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vm_memory::{Bytes, GuestAddress, GuestMemoryMmap};
+
+    use super::*;
+
+    #[test]
+    fn test_nfit() {
+        assert_eq!(size_of::<SpaRange>(), 56);
+        assert_eq!(size_of::<RegionMapping>(), 48);
+        assert_eq!(size_of::<ControlRegion>(), 80);
+
+        let structures = single_nvdimm_structures(1, 0x1_0000_0000, 0x4000_0000, Some(1)).unwrap();
+        let mut nfit = Nfit::new(*b"FOOBAR", *b"FOOBARNF", 0, structures);
+        assert_eq!(nfit.len(), 40 + 56 + 48 + 80);
+
+        let mem: GuestMemoryMmap =
+            GuestMemoryMmap::from_ranges(&[(GuestAddress(0), 0x1000)]).unwrap();
+        nfit.write_to_guest(&mem, GuestAddress(0)).unwrap();
+        let mut bytes = vec![0u8; nfit.len()];
+        mem.read_slice(&mut bytes, GuestAddress(0)).unwrap();
+        assert_eq!(checksum(&[&bytes]), 0);
+        assert_eq!(&bytes[..4], b"NFIT");
+
+        let spa_range = &bytes[40..96];
+        // Type, length, index and valid proximity domain.
+        assert_eq!(&spa_range[..8], &[0, 0, 56, 0, 1, 0, 2, 0]);
+        assert_eq!(&spa_range[12..16], &1u32.to_le_bytes());
+        assert_eq!(&spa_range[16..20], &[0x79, 0xd3, 0xf0, 0x66]);
+        assert_eq!(&spa_range[32..40], &0x1_0000_0000u64.to_le_bytes());
+        assert_eq!(&spa_range[40..48], &0x4000_0000u64.to_le_bytes());
+        assert_eq!(&spa_range[48..56], &0x8008u64.to_le_bytes());
+
+        let region_mapping = &bytes[96..144];
+        assert_eq!(&region_mapping[..8], &[1, 0, 48, 0, 1, 0, 0, 0]);
+        // SPA range and control region indexes.
+        assert_eq!(&region_mapping[12..16], &[1, 0, 1, 0]);
+        assert_eq!(&region_mapping[16..24], &0x4000_0000u64.to_le_bytes());
+        // A single interleave way.
+        assert_eq!(&region_mapping[42..44], &1u16.to_le_bytes());
+
+        let control_region = &bytes[144..];
+        assert_eq!(&control_region[..6], &[4, 0, 80, 0, 1, 0]);
+        assert_eq!(&control_region[6..8], &0x8086u16.to_le_bytes());
+        assert_eq!(&control_region[28..30], &0x0301u16.to_le_bytes());
+    }
+
+    #[test]
+    fn test_nfit_validation() {
+        assert!(matches!(
+            single_nvdimm_structures(0, 0x1_0000_0000, 0x4000_0000, None),
+            Err(AcpiError::InvalidNfitIndex)
+        ));
+        assert!(matches!(
+            RegionMapping::new(0, 1, 0, 0x4000_0000, 0, 0),
+            Err(AcpiError::InvalidNfitIndex)
+        ));
+    }
+}
@@ -0,0 +1,360 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use alloc::vec::Vec;
+use core::mem::size_of;
+
+use zerocopy::{Immutable, IntoBytes};
+
+use crate::endian::{U16, U32, U64};
+use crate::{GenericAddressStructure, Result, Sdt, SdtHeader, checksum};
+
+// Types of the communications subspaces.
+const PCC_SUBSPACE_TYPE_GENERIC: u8 = 0;
+const PCC_SUBSPACE_TYPE_HW_REDUCED: u8 = 1;
+const PCC_SUBSPACE_TYPE_HW_REDUCED_V2: u8 = 2;
+
+/// The platform interrupt of a subspace is active low.
+pub const PCC_INTERRUPT_ACTIVE_LOW: u8 = 1 << 0;
+/// The platform interrupt of a subspace is edge triggered.
+pub const PCC_INTERRUPT_EDGE_TRIGGERED: u8 = 1 << 1;
+
+/// Signature of the shared memory region of the subspace `id`.
+pub const fn pcc_signature(id: u8) -> u32 {
+    0x5043_4300 | id as u32
+}
+
+/// A generic communications subspace.
+///
+/// The shared memory region of the subspace starts with a header holding its signature, a 16-bit
+/// command and a 16-bit status, followed by the communication space.
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GenericSubspace {
+    subspace_type: u8,
+    length: u8,
+    _reserved: [u8; 6],
+    base_address: U64,
+    memory_range_length: U64,
+    doorbell_register: GenericAddressStructure,
+    doorbell_preserve: U64,
+    doorbell_write: U64,
+    nominal_latency: U32,
+    max_periodic_access_rate: U32,
+    min_request_turnaround_time: U16,
+}
+
+assert_layout!(GenericSubspace, 62);
+
+impl GenericSubspace {
+    /// Create a subspace whose shared memory region is `memory_range_length` bytes at
+    /// `base_address`.
+    ///
+    /// The doorbell is rung by writing `doorbell_write` to `doorbell_register`, preserving the
+    /// bits of `doorbell_preserve`. Commands complete in `nominal_latency_us` microseconds.
+    pub fn new(
+        base_address: u64,
+        memory_range_length: u64,
+        doorbell_register: GenericAddressStructure,
+        doorbell_preserve: u64,
+        doorbell_write: u64,
+        nominal_latency_us: u32,
+    ) -> Self {
+        GenericSubspace {
+            subspace_type: PCC_SUBSPACE_TYPE_GENERIC,
+            length: size_of::<GenericSubspace>().try_into().unwrap(),
+            _reserved: [0; 6],
+            base_address: U64::new(base_address),
+            memory_range_length: U64::new(memory_range_length),
+            doorbell_register,
+            doorbell_preserve: U64::new(doorbell_preserve),
+            doorbell_write: U64::new(doorbell_write),
+            nominal_latency: U32::new(nominal_latency_us),
+            // There is no limit on the rate of the commands, nor a delay between them.
+            max_periodic_access_rate: U32::new(0),
+            min_request_turnaround_time: U16::new(0),
+        }
+    }
+}
+
+/// A HW-reduced communications subspace.
+///
+/// Unlike a generic subspace, the platform signals the completion of the commands with an
+/// interrupt, a global system interrupt, instead of letting the guest poll for it.
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HwReducedSubspace {
+    subspace_type: u8,
+    length: u8,
+    platform_interrupt: U32,
+    platform_interrupt_flags: u8,
+    _reserved: u8,
+    base_address: U64,
+    memory_range_length: U64,
+    doorbell_register: GenericAddressStructure,
+    doorbell_preserve: U64,
+    doorbell_write: U64,
+    nominal_latency: U32,
+    max_periodic_access_rate: U32,
+    min_request_turnaround_time: U16,
+}
+
+assert_layout!(HwReducedSubspace, 62);
+
+impl HwReducedSubspace {
+    /// Create a subspace whose shared memory region is `memory_range_length` bytes at
+    /// `base_address`, and which raises `platform_interrupt` when a command completes.
+    ///
+    /// The interrupt is level triggered and active high unless `platform_interrupt_flags` has
+    /// `PCC_INTERRUPT_*` bits set. The doorbell is rung as for a [`GenericSubspace`].
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        platform_interrupt: u32,
+        platform_interrupt_flags: u8,
+        base_address: u64,
+        memory_range_length: u64,
+        doorbell_register: GenericAddressStructure,
+        doorbell_preserve: u64,
+        doorbell_write: u64,
+        nominal_latency_us: u32,
+    ) -> Self {
+        HwReducedSubspace {
+            subspace_type: PCC_SUBSPACE_TYPE_HW_REDUCED,
+            length: size_of::<HwReducedSubspace>().try_into().unwrap(),
+            platform_interrupt: U32::new(platform_interrupt),
+            platform_interrupt_flags,
+            _reserved: 0,
+            base_address: U64::new(base_address),
+            memory_range_length: U64::new(memory_range_length),
+            doorbell_register,
+            doorbell_preserve: U64::new(doorbell_preserve),
+            doorbell_write: U64::new(doorbell_write),
+            nominal_latency: U32::new(nominal_latency_us),
+            max_periodic_access_rate: U32::new(0),
+            min_request_turnaround_time: U16::new(0),
+        }
+    }
+}
+
+/// A HW-reduced communications subspace whose platform interrupt the guest acknowledges.
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HwReducedSubspaceV2 {
+    subspace: HwReducedSubspace,
+    platform_ack_register: GenericAddressStructure,
+    platform_ack_preserve: U64,
+    platform_ack_write: U64,
+}
+
+assert_layout!(HwReducedSubspaceV2, 90);
+
+impl HwReducedSubspaceV2 {
+    /// Extend `subspace` so that the guest acknowledges its interrupt by writing
+    /// `platform_ack_write` to `platform_ack_register`, preserving the bits of
+    /// `platform_ack_preserve`.
+    pub fn new(
+        mut subspace: HwReducedSubspace,
+        platform_ack_register: GenericAddressStructure,
+        platform_ack_preserve: u64,
+        platform_ack_write: u64,
+    ) -> Self {
+        subspace.subspace_type = PCC_SUBSPACE_TYPE_HW_REDUCED_V2;
+        subspace.length = size_of::<HwReducedSubspaceV2>().try_into().unwrap();
+        HwReducedSubspaceV2 {
+            subspace,
+            platform_ack_register,
+            platform_ack_preserve: U64::new(platform_ack_preserve),
+            platform_ack_write: U64::new(platform_ack_write),
+        }
+    }
+}
+
+/// A communications subspace of the PCCT.
+#[derive(Clone, Copy, Debug)]
+pub enum PcctSubspace {
+    /// Generic communications subspace.
+    Generic(GenericSubspace),
+    /// HW-reduced communications subspace.
+    HwReduced(HwReducedSubspace),
+    /// HW-reduced communications subspace, with an acknowledged interrupt.
+    HwReducedV2(HwReducedSubspaceV2),
+}
+
+impl PcctSubspace {
+    fn as_bytes(&self) -> &[u8] {
+        match self {
+            PcctSubspace::Generic(subspace) => subspace.as_bytes(),
+            PcctSubspace::HwReduced(subspace) => subspace.as_bytes(),
+            PcctSubspace::HwReducedV2(subspace) => subspace.as_bytes(),
+        }
+    }
+}
+
+impl From<GenericSubspace> for PcctSubspace {
+    fn from(subspace: GenericSubspace) -> Self {
+        PcctSubspace::Generic(subspace)
+    }
+}
+
+impl From<HwReducedSubspace> for PcctSubspace {
+    fn from(subspace: HwReducedSubspace) -> Self {
+        PcctSubspace::HwReduced(subspace)
+    }
+}
+
+impl From<HwReducedSubspaceV2> for PcctSubspace {
+    fn from(subspace: HwReducedSubspaceV2) -> Self {
+        PcctSubspace::HwReducedV2(subspace)
+    }
+}
+
+// clippy doesn't understand that we actually "use" the fields of this struct when we serialize
+// them as bytes in guest memory, so here we just ignore dead code to avoid having to name
+// everything with an underscore prefix
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct PcctHeader {
+    sdt: SdtHeader,
+    flags: U32,
+    _reserved: U64,
+}
+
+assert_layout!(PcctHeader, 48);
+
+/// Platform Communications Channel Table (PCCT)
+///
+/// This table describes the subspaces of the Platform Communications Channel, the mailboxes
+/// through which the guest exchanges commands with the platform, such as the ones backing the
+/// CPPC registers. The guest polls the commands of the generic subspaces for completion, while
+/// the platform interrupts it for the ones of the HW-reduced subspaces. More information about
+/// this table can be found in the ACPI specification:
+/// https://uefi.org/specs/ACPI/6.5/14_Platform_Communications_Channel.html
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Pcct {
+    header: PcctHeader,
+    subspaces: Vec<u8>,
+}
+
+impl Pcct {
+    /// Create a PCCT table describing the given subspaces, whose identifiers are their indexes.
+    pub fn new(
+        oem_id: [u8; 6],
+        oem_table_id: [u8; 8],
+        oem_revision: u32,
+        subspaces: Vec<PcctSubspace>,
+    ) -> Self {
+        let subspaces: Vec<u8> = subspaces
+            .iter()
+            .flat_map(PcctSubspace::as_bytes)
+            .copied()
+            .collect();
+        let length = size_of::<PcctHeader>() + subspaces.len();
+        let sdt = SdtHeader::new(
+            *b"PCCT",
+            length.try_into().unwrap(),
+            2,
+            oem_id,
+            oem_table_id,
+            oem_revision,
+        );
+
+        let mut header = PcctHeader {
+            sdt,
+            flags: U32::ZERO,
+            _reserved: U64::ZERO,
+        };
+        header.sdt.checksum = checksum(&[header.as_bytes(), subspaces.as_bytes()]);
+        Pcct { header, subspaces }
+    }
+}
+
+impl Sdt for Pcct {
+    fn len(&self) -> usize {
+        self.header.sdt.length.get().try_into().unwrap()
+    }
+
+    fn header(&self) -> Option<&SdtHeader> {
+        Some(&self.header.sdt)
+    }
+
+    fn to_bytes(&mut self) -> Result<Vec<u8>> {
+        Ok([self.header.as_bytes(), self.subspaces.as_bytes()].concat())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vm_memory::{Bytes, GuestAddress, GuestMemoryMmap};
+
+    use super::*;
+
+    #[test]
+    fn test_pcct() {
+        let doorbell = GenericAddressStructure::from_raw(0, 32, 0, 3, 0xd000_0000);
+        let subspace = GenericSubspace::new(0x9fc00, 0x400, doorbell, 0, 1, 10);
+        let mut pcct = Pcct::new(*b"FOOBAR", *b"FOOBARPC", 0, vec![subspace.into()]);
+        assert_eq!(pcct.len(), 48 + 62);
+
+        let mem: GuestMemoryMmap =
+            GuestMemoryMmap::from_ranges(&[(GuestAddress(0), 0x1000)]).unwrap();
+        pcct.write_to_guest(&mem, GuestAddress(0)).unwrap();
+        let mut bytes = vec![0u8; pcct.len()];
+        mem.read_slice(&mut bytes, GuestAddress(0)).unwrap();
+        assert_eq!(checksum(&[&bytes]), 0);
+        assert_eq!(&bytes[..4], b"PCCT");
+
+        let subspace = &bytes[48..];
+        // A generic subspace, 62 bytes long.
+        assert_eq!(&subspace[..2], &[0, 62]);
+        assert_eq!(&subspace[8..16], &0x9fc00u64.to_le_bytes());
+        assert_eq!(&subspace[16..24], &0x400u64.to_le_bytes());
+        assert_eq!(&subspace[28..36], &0xd000_0000u64.to_le_bytes());
+        assert_eq!(&subspace[44..52], &1u64.to_le_bytes());
+        assert_eq!(&subspace[52..56], &10u32.to_le_bytes());
+
+        assert_eq!(pcc_signature(0), 0x5043_4300);
+    }
+
+    #[test]
+    fn test_pcct_hw_reduced() {
+        let doorbell = GenericAddressStructure::from_raw(0, 32, 0, 3, 0xd000_0000);
+        let ack = GenericAddressStructure::from_raw(0, 32, 0, 3, 0xd000_0004);
+        let flags = PCC_INTERRUPT_EDGE_TRIGGERED;
+        let subspace = HwReducedSubspace::new(40, flags, 0x9fc00, 0x400, doorbell, 0, 1, 10);
+        let subspace_v2 = HwReducedSubspaceV2::new(subspace, ack, 0, 1);
+        let subspaces = vec![subspace.into(), subspace_v2.into()];
+        let mut pcct = Pcct::new(*b"FOOBAR", *b"FOOBARPC", 0, subspaces);
+        assert_eq!(pcct.len(), 48 + 62 + 90);
+
+        let mem: GuestMemoryMmap =
+            GuestMemoryMmap::from_ranges(&[(GuestAddress(0), 0x1000)]).unwrap();
+        pcct.write_to_guest(&mem, GuestAddress(0)).unwrap();
+        let mut bytes = vec![0u8; pcct.len()];
+        mem.read_slice(&mut bytes, GuestAddress(0)).unwrap();
+        assert_eq!(checksum(&[&bytes]), 0);
+
+        let subspace = &bytes[48..110];
+        // A HW-reduced subspace, 62 bytes long, raising GSI 40 on its edges.
+        assert_eq!(&subspace[..2], &[1, 62]);
+        assert_eq!(&subspace[2..6], &40u32.to_le_bytes());
+        assert_eq!(subspace[6], 2);
+        assert_eq!(&subspace[8..16], &0x9fc00u64.to_le_bytes());
+        assert_eq!(&subspace[28..36], &0xd000_0000u64.to_le_bytes());
+
+        let subspace_v2 = &bytes[110..];
+        assert_eq!(&subspace_v2[..2], &[2, 90]);
+        assert_eq!(&subspace_v2[2..62], &subspace[2..]);
+        assert_eq!(&subspace_v2[62..74], ack.as_bytes());
+        assert_eq!(&subspace_v2[82..90], &1u64.to_le_bytes());
+    }
+}
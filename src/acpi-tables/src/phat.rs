@@ -0,0 +1,350 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use alloc::vec::Vec;
+use core::mem::{size_of, size_of_val};
+
+use zerocopy::{Immutable, IntoBytes};
+
+use crate::endian::{U16, U32, U64};
+use crate::{AcpiError, Result, Sdt, SdtHeader, checksum};
+
+const PHAT_RECORD_FIRMWARE_VERSION: u16 = 0;
+const PHAT_RECORD_FIRMWARE_HEALTH: u16 = 1;
+const PHAT_RECORD_REVISION: u8 = 1;
+
+/// Health of a device, as reported by a firmware health data record.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum PhatHealthStatus {
+    /// The device encountered errors.
+    Errors = 0,
+    /// The device encountered no errors.
+    Healthy = 1,
+    /// The health of the device is unknown.
+    Unknown = 2,
+    /// The device is working, but needs attention.
+    Advisory = 3,
+}
+
+/// Version of a firmware component of the platform.
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FirmwareVersionElement {
+    component_id: [u8; 16],
+    version: U64,
+    producer_id: [u8; 4],
+}
+
+assert_layout!(FirmwareVersionElement, 28, version: 16, producer_id: 24);
+
+impl FirmwareVersionElement {
+    /// Create a version element for the component identified by the GUID `component_id`, in its
+    /// mixed-endian binary form, and made by the vendor whose ACPI ID is `producer_id`.
+    pub fn new(component_id: [u8; 16], version: u64, producer_id: [u8; 4]) -> Self {
+        FirmwareVersionElement {
+            component_id,
+            version: U64::new(version),
+            producer_id,
+        }
+    }
+}
+
+// clippy doesn't understand that we actually "use" the fields of this struct when we serialize
+// them as bytes in guest memory, so here we just ignore dead code to avoid having to name
+// everything with an underscore prefix
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct FirmwareVersionRecordHeader {
+    record_type: U16,
+    length: U16,
+    revision: u8,
+    _reserved: [u8; 3],
+    element_count: U32,
+}
+
+assert_layout!(FirmwareVersionRecordHeader, 12);
+
+/// Firmware Version Data Record
+///
+/// This record lists the versions of the firmware components of the platform.
+#[derive(Clone, Debug)]
+pub struct FirmwareVersionRecord {
+    header: FirmwareVersionRecordHeader,
+    elements: Vec<FirmwareVersionElement>,
+}
+
+impl FirmwareVersionRecord {
+    /// Create a record listing the given firmware component versions.
+    pub fn new(elements: &[FirmwareVersionElement]) -> Result<Self> {
+        let length = size_of::<FirmwareVersionRecordHeader>() + size_of_val(elements);
+        let length = u16::try_from(length).map_err(|_| AcpiError::InvalidPhatRecordLength)?;
+        let header = FirmwareVersionRecordHeader {
+            record_type: U16::new(PHAT_RECORD_FIRMWARE_VERSION),
+            length: U16::new(length),
+            revision: PHAT_RECORD_REVISION,
+            _reserved: [0; 3],
+            element_count: U32::new(elements.len().try_into().unwrap()),
+        };
+        Ok(FirmwareVersionRecord {
+            header,
+            elements: elements.to_vec(),
+        })
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        [self.header.as_bytes(), self.elements.as_bytes()].concat()
+    }
+}
+
+// clippy doesn't understand that we actually "use" the fields of this struct when we serialize
+// them as bytes in guest memory, so here we just ignore dead code to avoid having to name
+// everything with an underscore prefix
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct FirmwareHealthRecordHeader {
+    record_type: U16,
+    length: U16,
+    revision: u8,
+    _reserved: U16,
+    am_healthy: u8,
+    device_signature: [u8; 16],
+    device_data_offset: U32,
+}
+
+assert_layout!(FirmwareHealthRecordHeader, 28, device_signature: 8, device_data_offset: 24);
+
+/// Firmware Health Data Record
+///
+/// This record reports the health of a device, identified by the GUID of the format of its
+/// device-specific data, along with the path of the device in the UEFI device path format.
+#[derive(Clone, Debug)]
+pub struct FirmwareHealthRecord {
+    header: FirmwareHealthRecordHeader,
+    device_path: Vec<U16>,
+    device_data: Vec<u8>,
+}
+
+impl FirmwareHealthRecord {
+    /// Create a record reporting the health `status` of the device at `device_path`, such as
+    /// `PciRoot(0x0)/Pci(0x1,0x0)`, along with its `device_data`, whose format is identified by
+    /// the GUID `device_signature`, in its mixed-endian binary form.
+    pub fn new(
+        status: PhatHealthStatus,
+        device_signature: [u8; 16],
+        device_path: &str,
+        device_data: &[u8],
+    ) -> Result<Self> {
+        // The device path is a null-terminated UTF-16 string.
+        let device_path: Vec<U16> = device_path
+            .encode_utf16()
+            .chain([0])
+            .map(U16::new)
+            .collect();
+        let data_offset = size_of::<FirmwareHealthRecordHeader>() + device_path.as_bytes().len();
+        let length = u16::try_from(data_offset + device_data.len())
+            .map_err(|_| AcpiError::InvalidPhatRecordLength)?;
+
+        let header = FirmwareHealthRecordHeader {
+            record_type: U16::new(PHAT_RECORD_FIRMWARE_HEALTH),
+            length: U16::new(length),
+            revision: PHAT_RECORD_REVISION,
+            _reserved: U16::ZERO,
+            am_healthy: status as u8,
+            device_signature,
+            // There is no offset when there is no device-specific data.
+            device_data_offset: if device_data.is_empty() {
+                U32::ZERO
+            } else {
+                U32::new(data_offset.try_into().unwrap())
+            },
+        };
+        Ok(FirmwareHealthRecord {
+            header,
+            device_path,
+            device_data: device_data.to_vec(),
+        })
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        [
+            self.header.as_bytes(),
+            self.device_path.as_bytes(),
+            &self.device_data,
+        ]
+        .concat()
+    }
+}
+
+/// A platform telemetry record of the PHAT.
+#[derive(Clone, Debug)]
+pub enum PhatRecord {
+    /// Firmware version data record.
+    FirmwareVersion(FirmwareVersionRecord),
+    /// Firmware health data record.
+    FirmwareHealth(FirmwareHealthRecord),
+}
+
+impl PhatRecord {
+    fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            PhatRecord::FirmwareVersion(record) => record.to_bytes(),
+            PhatRecord::FirmwareHealth(record) => record.to_bytes(),
+        }
+    }
+}
+
+impl From<FirmwareVersionRecord> for PhatRecord {
+    fn from(record: FirmwareVersionRecord) -> Self {
+        PhatRecord::FirmwareVersion(record)
+    }
+}
+
+impl From<FirmwareHealthRecord> for PhatRecord {
+    fn from(record: FirmwareHealthRecord) -> Self {
+        PhatRecord::FirmwareHealth(record)
+    }
+}
+
+/// Platform Health Assessment Table (PHAT)
+///
+/// This table holds the telemetry of the platform firmware, such as the versions of its
+/// components and the health of the devices it initialized, so that the guest can gather it along
+/// with its own. More information about this table can be found in the ACPI specification:
+/// https://uefi.org/specs/ACPI/6.5/05_ACPI_Software_Programming_Model.html#platform-health-assessment-table-phat
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Phat {
+    header: SdtHeader,
+    records: Vec<u8>,
+}
+
+impl Phat {
+    /// Create a PHAT table holding the given records.
+    pub fn new(
+        oem_id: [u8; 6],
+        oem_table_id: [u8; 8],
+        oem_revision: u32,
+        records: &[PhatRecord],
+    ) -> Self {
+        let records: Vec<u8> = records.iter().flat_map(PhatRecord::to_bytes).collect();
+        let length = size_of::<SdtHeader>() + records.len();
+        let mut header = SdtHeader::new(
+            *b"PHAT",
+            length.try_into().unwrap(),
+            1,
+            oem_id,
+            oem_table_id,
+            oem_revision,
+        );
+        header.checksum = checksum(&[header.as_bytes(), records.as_bytes()]);
+
+        Phat { header, records }
+    }
+}
+
+impl Sdt for Phat {
+    fn len(&self) -> usize {
+        self.header.length.get().try_into().unwrap()
+    }
+
+    fn header(&self) -> Option<&SdtHeader> {
+        Some(&self.header)
+    }
+
+    fn to_bytes(&mut self) -> Result<Vec<u8>> {
+        Ok([self.header.as_bytes(), self.records.as_bytes()].concat())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vm_memory::{Bytes, GuestAddress, GuestMemoryMmap};
+
+    use super::*;
+
+    const COMPONENT_ID: [u8; 16] = [0x11; 16];
+    const DEVICE_SIGNATURE: [u8; 16] = [0x22; 16];
+
+    #[test]
+    fn test_phat() {
+        let versions = FirmwareVersionRecord::new(&[
+            FirmwareVersionElement::new(COMPONENT_ID, 0x0102_0304, *b"FOOB"),
+            FirmwareVersionElement::new(COMPONENT_ID, 5, *b"BARF"),
+        ])
+        .unwrap();
+        let health = FirmwareHealthRecord::new(
+            PhatHealthStatus::Healthy,
+            DEVICE_SIGNATURE,
+            "VenHw()",
+            &[0xaa, 0xbb],
+        )
+        .unwrap();
+        let mut phat = Phat::new(
+            *b"FOOBAR",
+            *b"FOOBARPH",
+            0,
+            &[versions.into(), health.into()],
+        );
+        // The device path holds 8 UTF-16 characters, including its terminator.
+        assert_eq!(phat.len(), 36 + (12 + 2 * 28) + (28 + 16 + 2));
+
+        let mem: GuestMemoryMmap =
+            GuestMemoryMmap::from_ranges(&[(GuestAddress(0), 0x1000)]).unwrap();
+        phat.write_to_guest(&mem, GuestAddress(0)).unwrap();
+        let mut bytes = vec![0u8; phat.len()];
+        mem.read_slice(&mut bytes, GuestAddress(0)).unwrap();
+        assert_eq!(checksum(&[&bytes]), 0);
+        assert_eq!(&bytes[..4], b"PHAT");
+
+        let versions = &bytes[36..104];
+        // A firmware version data record, 68 bytes long, with 2 elements.
+        assert_eq!(&versions[..5], &[0, 0, 68, 0, 1]);
+        assert_eq!(&versions[8..12], &2u32.to_le_bytes());
+        assert_eq!(&versions[12..28], &COMPONENT_ID);
+        assert_eq!(&versions[28..36], &0x0102_0304u64.to_le_bytes());
+        assert_eq!(&versions[36..40], b"FOOB");
+        assert_eq!(&versions[64..68], b"BARF");
+
+        let health = &bytes[104..];
+        // A firmware health data record, 46 bytes long, of a healthy device.
+        assert_eq!(&health[..5], &[1, 0, 46, 0, 1]);
+        assert_eq!(health[7], 1);
+        assert_eq!(&health[8..24], &DEVICE_SIGNATURE);
+        assert_eq!(&health[24..28], &44u32.to_le_bytes());
+        assert_eq!(&health[28..32], &[b'V', 0, b'e', 0]);
+        assert_eq!(&health[42..44], &[0, 0]);
+        assert_eq!(&health[44..], &[0xaa, 0xbb]);
+    }
+
+    #[test]
+    fn test_phat_record_length() {
+        let health =
+            FirmwareHealthRecord::new(PhatHealthStatus::Unknown, DEVICE_SIGNATURE, "", &[])
+                .unwrap();
+        // No device-specific data, hence no offset to it.
+        assert_eq!(&health.to_bytes()[24..], &[0, 0, 0, 0, 0, 0]);
+
+        let elements = vec![FirmwareVersionElement::default(); 0x10000 / 28 + 1];
+        assert!(matches!(
+            FirmwareVersionRecord::new(&elements),
+            Err(AcpiError::InvalidPhatRecordLength)
+        ));
+        assert!(matches!(
+            FirmwareHealthRecord::new(
+                PhatHealthStatus::Errors,
+                DEVICE_SIGNATURE,
+                "",
+                &[0; 0x10000]
+            ),
+            Err(AcpiError::InvalidPhatRecordLength)
+        ));
+    }
+}
@@ -0,0 +1,464 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use alloc::vec::Vec;
+use core::mem::size_of;
+
+use zerocopy::{Immutable, IntoBytes};
+
+use crate::endian::{U16, U32};
+use crate::{AcpiError, Result, Sdt, SdtHeader, checksum};
+
+const PPTT_PROCESSOR_HIERARCHY_NODE: u8 = 0;
+const PPTT_CACHE_TYPE: u8 = 1;
+// Flags of processor hierarchy nodes.
+const PPTT_PHYSICAL_PACKAGE: u32 = 1 << 0;
+const PPTT_ACPI_PROCESSOR_ID_VALID: u32 = 1 << 1;
+const PPTT_PROCESSOR_IS_THREAD: u32 = 1 << 2;
+const PPTT_NODE_IS_LEAF: u32 = 1 << 3;
+// All the properties of cache type structures, up to the cache ID, are valid.
+const PPTT_CACHE_ALL_VALID: u32 = 0xff;
+// Caches allocate on both reads and writes, and are write-back.
+const PPTT_CACHE_ALLOCATE_READ_WRITE: u8 = 0x2;
+
+/// Type of the cache described by a [`PpttCache`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum PpttCacheType {
+    /// Data cache.
+    Data = 0,
+    /// Instruction cache.
+    Instruction = 1,
+    /// Cache holding both data and instructions.
+    Unified = 2,
+}
+
+/// Reference to a structure of a PPTT table, returned by the [`PpttBuilder`] the structure is
+/// added to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PpttRef(usize);
+
+/// Description of a processor cache
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PpttCache {
+    cache_type: PpttCacheType,
+    size: u32,
+    sets: u32,
+    associativity: u8,
+    line_size: u16,
+    id: u32,
+}
+
+impl PpttCache {
+    /// Create the description of a write-back cache of `size` bytes, made of `sets` sets of
+    /// `associativity` lines of `line_size` bytes. `id` must be unique among the caches of the
+    /// table.
+    pub fn new(
+        cache_type: PpttCacheType,
+        size: u32,
+        sets: u32,
+        associativity: u8,
+        line_size: u16,
+        id: u32,
+    ) -> Self {
+        PpttCache {
+            cache_type,
+            size,
+            sets,
+            associativity,
+            line_size,
+            id,
+        }
+    }
+}
+
+// clippy doesn't understand that we actually "use" the fields of this struct when we serialize
+// them as bytes in guest memory, so here we just ignore dead code to avoid having to name
+// everything with an underscore prefix
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Copy, Clone, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct ProcessorHierarchyNode {
+    r#type: u8,
+    length: u8,
+    _reserved: U16,
+    flags: U32,
+    parent: U32,
+    acpi_processor_id: U32,
+    private_resource_count: U32,
+}
+
+assert_layout!(ProcessorHierarchyNode, 20);
+
+// clippy doesn't understand that we actually "use" the fields of this struct when we serialize
+// them as bytes in guest memory, so here we just ignore dead code to avoid having to name
+// everything with an underscore prefix
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Copy, Clone, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct CacheTypeStructure {
+    r#type: u8,
+    length: u8,
+    _reserved: U16,
+    flags: U32,
+    next_level_of_cache: U32,
+    size: U32,
+    number_of_sets: U32,
+    associativity: u8,
+    attributes: u8,
+    line_size: U16,
+    cache_id: U32,
+}
+
+assert_layout!(CacheTypeStructure, 28, cache_id: 24);
+
+#[derive(Clone, Debug)]
+enum PpttStructure {
+    Processor {
+        flags: u32,
+        parent: Option<PpttRef>,
+        acpi_processor_id: u32,
+        caches: Vec<PpttRef>,
+    },
+    Cache {
+        cache: PpttCache,
+        next_level: Option<PpttRef>,
+    },
+}
+
+impl PpttStructure {
+    fn len(&self) -> usize {
+        match self {
+            PpttStructure::Processor { caches, .. } => {
+                size_of::<ProcessorHierarchyNode>() + caches.len() * size_of::<U32>()
+            }
+            PpttStructure::Cache { .. } => size_of::<CacheTypeStructure>(),
+        }
+    }
+}
+
+/// Processor Properties Topology Table (PPTT)
+///
+/// This table describes the topology of the processors, from the packages down to the threads,
+/// along with the caches each level of the hierarchy holds. More information about this table can
+/// be found in the ACPI specification:
+/// https://uefi.org/specs/ACPI/6.5/05_ACPI_Software_Programming_Model.html#processor-properties-topology-table-pptt
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Pptt {
+    header: SdtHeader,
+    structures: Vec<u8>,
+}
+
+impl Sdt for Pptt {
+    fn len(&self) -> usize {
+        self.header.length.get().try_into().unwrap()
+    }
+
+    fn header(&self) -> Option<&SdtHeader> {
+        Some(&self.header)
+    }
+
+    fn to_bytes(&mut self) -> Result<Vec<u8>> {
+        Ok([self.header.as_bytes(), self.structures.as_bytes()].concat())
+    }
+}
+
+/// Builder of a [`Pptt`], one processor hierarchy node or cache at a time
+///
+/// Adding a structure returns a reference to it, which the structures added after it use to point
+/// to their parent, their private caches or their next level of cache. The offsets these
+/// references are replaced with, and which nodes are leaves, are computed when the table is built.
+#[derive(Clone, Debug, Default)]
+pub struct PpttBuilder {
+    structures: Vec<PpttStructure>,
+}
+
+impl PpttBuilder {
+    /// Create a builder with no structure.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn processor(
+        &mut self,
+        flags: u32,
+        parent: Option<PpttRef>,
+        acpi_processor_id: Option<u32>,
+        caches: &[PpttRef],
+    ) -> PpttRef {
+        let flags = match acpi_processor_id {
+            Some(_) => flags | PPTT_ACPI_PROCESSOR_ID_VALID,
+            None => flags,
+        };
+        self.structures.push(PpttStructure::Processor {
+            flags,
+            parent,
+            acpi_processor_id: acpi_processor_id.unwrap_or_default(),
+            caches: caches.to_vec(),
+        });
+        PpttRef(self.structures.len() - 1)
+    }
+
+    /// Add a physical package, holding the given private caches.
+    pub fn package(&mut self, caches: &[PpttRef]) -> PpttRef {
+        self.processor(PPTT_PHYSICAL_PACKAGE, None, None, caches)
+    }
+
+    /// Add a group of cores, such as a cluster or a die, to its parent.
+    pub fn cluster(&mut self, parent: PpttRef, caches: &[PpttRef]) -> PpttRef {
+        self.processor(0, Some(parent), None, caches)
+    }
+
+    /// Add a core to its parent. A core without threads is the processor whose UID, in the MADT
+    /// and in the namespace, is `acpi_processor_id`.
+    pub fn core(
+        &mut self,
+        parent: PpttRef,
+        acpi_processor_id: Option<u32>,
+        caches: &[PpttRef],
+    ) -> PpttRef {
+        self.processor(0, Some(parent), acpi_processor_id, caches)
+    }
+
+    /// Add a thread to its core, as the processor whose UID is `acpi_processor_id`.
+    pub fn thread(
+        &mut self,
+        parent: PpttRef,
+        acpi_processor_id: u32,
+        caches: &[PpttRef],
+    ) -> PpttRef {
+        self.processor(
+            PPTT_PROCESSOR_IS_THREAD,
+            Some(parent),
+            Some(acpi_processor_id),
+            caches,
+        )
+    }
+
+    /// Add a cache, backed by the `next_level` cache if any.
+    pub fn cache(&mut self, cache: PpttCache, next_level: Option<PpttRef>) -> PpttRef {
+        self.structures
+            .push(PpttStructure::Cache { cache, next_level });
+        PpttRef(self.structures.len() - 1)
+    }
+
+    // Offset of the structure `reference` points to, if it is one of the expected kind.
+    fn offset(&self, offsets: &[usize], reference: PpttRef, processor: bool) -> Result<U32> {
+        let PpttRef(index) = reference;
+        match self.structures.get(index) {
+            Some(PpttStructure::Processor { .. }) if processor => (),
+            Some(PpttStructure::Cache { .. }) if !processor => (),
+            _ => return Err(AcpiError::InvalidPpttReference(index)),
+        }
+        Ok(U32::new(offsets[index].try_into().unwrap()))
+    }
+
+    /// Build the PPTT table, after checking that parents are processor hierarchy nodes and that
+    /// private resources and next levels are caches.
+    pub fn build(self, oem_id: [u8; 6], oem_table_id: [u8; 8], oem_revision: u32) -> Result<Pptt> {
+        let mut offsets = Vec::with_capacity(self.structures.len());
+        let mut offset = size_of::<SdtHeader>();
+        for structure in &self.structures {
+            offsets.push(offset);
+            offset += structure.len();
+        }
+
+        let mut structures = Vec::new();
+        for (index, structure) in self.structures.iter().enumerate() {
+            match structure {
+                PpttStructure::Processor {
+                    flags,
+                    parent,
+                    acpi_processor_id,
+                    caches,
+                } => {
+                    let is_parent = self.structures.iter().any(|structure| {
+                        matches!(
+                            structure,
+                            PpttStructure::Processor {
+                                parent: Some(PpttRef(parent)),
+                                ..
+                            } if *parent == index
+                        )
+                    });
+                    let flags = if is_parent {
+                        *flags
+                    } else {
+                        flags | PPTT_NODE_IS_LEAF
+                    };
+                    let node = ProcessorHierarchyNode {
+                        r#type: PPTT_PROCESSOR_HIERARCHY_NODE,
+                        length: structure
+                            .len()
+                            .try_into()
+                            .map_err(|_| AcpiError::InvalidPpttNodeLength)?,
+                        flags: U32::new(flags),
+                        parent: match parent {
+                            Some(parent) => self.offset(&offsets, *parent, true)?,
+                            None => U32::ZERO,
+                        },
+                        acpi_processor_id: U32::new(*acpi_processor_id),
+                        private_resource_count: U32::new(caches.len().try_into().unwrap()),
+                        ..Default::default()
+                    };
+                    structures.extend_from_slice(node.as_bytes());
+                    for cache in caches {
+                        let offset = self.offset(&offsets, *cache, false)?;
+                        structures.extend_from_slice(offset.as_bytes());
+                    }
+                }
+                PpttStructure::Cache { cache, next_level } => {
+                    let cache_type = CacheTypeStructure {
+                        r#type: PPTT_CACHE_TYPE,
+                        length: structure.len().try_into().unwrap(),
+                        flags: U32::new(PPTT_CACHE_ALL_VALID),
+                        next_level_of_cache: match next_level {
+                            Some(next_level) => self.offset(&offsets, *next_level, false)?,
+                            None => U32::ZERO,
+                        },
+                        size: U32::new(cache.size),
+                        number_of_sets: U32::new(cache.sets),
+                        associativity: cache.associativity,
+                        attributes: PPTT_CACHE_ALLOCATE_READ_WRITE
+                            | ((cache.cache_type as u8) << 2),
+                        line_size: U16::new(cache.line_size),
+                        cache_id: U32::new(cache.id),
+                        ..Default::default()
+                    };
+                    structures.extend_from_slice(cache_type.as_bytes());
+                }
+            }
+        }
+
+        let length = size_of::<SdtHeader>() + structures.len();
+        let mut header = SdtHeader::new(
+            *b"PPTT",
+            length.try_into().unwrap(),
+            3,
+            oem_id,
+            oem_table_id,
+            oem_revision,
+        );
+
+        header.checksum = checksum(&[header.as_bytes(), structures.as_bytes()]);
+
+        Ok(Pptt { header, structures })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vm_memory::{Bytes, GuestAddress, GuestMemoryMmap};
+
+    use super::*;
+
+    #[test]
+    fn test_pptt() {
+        let mut builder = PpttBuilder::new();
+        let l3 = builder.cache(
+            PpttCache::new(PpttCacheType::Unified, 0x100_0000, 0x4000, 16, 64, 3),
+            None,
+        );
+        let package = builder.package(&[l3]);
+        let l2 = builder.cache(
+            PpttCache::new(PpttCacheType::Unified, 0x10_0000, 0x800, 8, 64, 2),
+            Some(l3),
+        );
+        let core = builder.core(package, None, &[l2]);
+        builder.thread(core, 0, &[]);
+        builder.thread(core, 1, &[]);
+        let mut pptt = builder.build(*b"FOOBAR", *b"FOOBARPP", 0).unwrap();
+        // Header, caches, package and core with a cache each, two threads.
+        assert_eq!(pptt.len(), 36 + 2 * 28 + 2 * 24 + 2 * 20);
+
+        let mem: GuestMemoryMmap =
+            GuestMemoryMmap::from_ranges(&[(GuestAddress(0), 0x1000)]).unwrap();
+        pptt.write_to_guest(&mem, GuestAddress(0)).unwrap();
+        let mut bytes = vec![0u8; pptt.len()];
+        mem.read_slice(&mut bytes, GuestAddress(0)).unwrap();
+        assert_eq!(checksum(&[&bytes]), 0);
+        assert_eq!(&bytes[..4], b"PPTT");
+        assert_eq!(bytes[8], 3);
+
+        let l3 = &bytes[36..64];
+        assert_eq!(&l3[..8], &[1, 28, 0, 0, 0xff, 0, 0, 0]);
+        assert_eq!(&l3[8..12], &0u32.to_le_bytes());
+        assert_eq!(&l3[12..16], &0x100_0000u32.to_le_bytes());
+        // Associativity, unified read/write allocate write-back cache, line size and ID.
+        assert_eq!(&l3[20..28], &[16, 0xa, 64, 0, 3, 0, 0, 0]);
+
+        let package = &bytes[64..88];
+        // Physical package, with no parent.
+        assert_eq!(&package[..12], &[0, 24, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0]);
+        // The L3 cache is private to the package.
+        assert_eq!(&package[16..24], &[1, 0, 0, 0, 36, 0, 0, 0]);
+
+        let l2 = &bytes[88..116];
+        assert_eq!(&l2[8..12], &36u32.to_le_bytes());
+
+        let core = &bytes[116..140];
+        assert_eq!(&core[4..12], &[0, 0, 0, 0, 64, 0, 0, 0]);
+        assert_eq!(&core[20..24], &88u32.to_le_bytes());
+
+        let thread = &bytes[160..180];
+        // Leaf thread with a valid processor ID, whose parent is the core.
+        assert_eq!(&thread[..4], &[0, 20, 0, 0]);
+        assert_eq!(&thread[4..8], &0xeu32.to_le_bytes());
+        assert_eq!(&thread[8..12], &116u32.to_le_bytes());
+        assert_eq!(&thread[12..16], &1u32.to_le_bytes());
+    }
+
+    #[test]
+    fn test_pptt_root_processor() {
+        // Packages have no parent, and a package without cores is a leaf.
+        let mut builder = PpttBuilder::new();
+        builder.package(&[]);
+        let package = builder.package(&[]);
+        builder.core(package, Some(0), &[]);
+        let mut pptt = builder.build(*b"FOOBAR", *b"FOOBARPP", 0).unwrap();
+        assert_eq!(pptt.len(), 36 + 3 * 20);
+
+        let bytes = pptt.to_bytes().unwrap();
+        assert_eq!(&bytes[36..48], &[0, 20, 0, 0, 9, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(&bytes[56..68], &[0, 20, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(&bytes[76..88], &[0, 20, 0, 0, 0xa, 0, 0, 0, 56, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_pptt_validation() {
+        let mut builder = PpttBuilder::new();
+        let package = builder.package(&[]);
+        let mut invalid = builder.clone();
+        invalid.core(package, Some(0), &[package]);
+        assert!(matches!(
+            invalid.build(*b"FOOBAR", *b"FOOBARPP", 0),
+            Err(AcpiError::InvalidPpttReference(0))
+        ));
+
+        let mut invalid = builder.clone();
+        let l1 = invalid.cache(
+            PpttCache::new(PpttCacheType::Data, 0x8000, 64, 8, 64, 1),
+            None,
+        );
+        invalid.core(l1, Some(0), &[]);
+        assert!(matches!(
+            invalid.build(*b"FOOBAR", *b"FOOBARPP", 0),
+            Err(AcpiError::InvalidPpttReference(1))
+        ));
+
+        let mut invalid = builder.clone();
+        let l1 = invalid.cache(
+            PpttCache::new(PpttCacheType::Data, 0x8000, 64, 8, 64, 1),
+            None,
+        );
+        invalid.core(package, Some(0), &[l1; 64]);
+        assert!(matches!(
+            invalid.build(*b"FOOBAR", *b"FOOBARPP", 0),
+            Err(AcpiError::InvalidPpttNodeLength)
+        ));
+    }
+}
@@ -4,11 +4,12 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
-use vm_memory::{Bytes, GuestAddress, GuestMemory};
-use zerocopy::little_endian::{U32, U64};
-use zerocopy::{Immutable, IntoBytes};
+use alloc::vec::Vec;
 
-use crate::{Result, Sdt, checksum};
+use zerocopy::{FromBytes, Immutable, IntoBytes};
+
+use crate::endian::{U32, U64};
+use crate::{AcpiError, Result, Sdt, checksum};
 
 // clippy doesn't understand that we actually "use" the fields of this struct when we serialize
 // them as bytes in guest memory, so here we just ignore dead code to avoid having to name
@@ -18,11 +19,12 @@ use crate::{Result, Sdt, checksum};
 ///
 /// This is the root pointer to the ACPI hierarchy. This is what OSs
 /// are looking for in the memory when initializing ACPI. It includes
-/// a pointer to XSDT
+/// a pointer to XSDT, or to RSDT for the ACPI 1.0 revision of the structure
 /// More information about this structure can be found in the ACPI specification:
 /// https://uefi.org/specs/ACPI/6.5/05_ACPI_Software_Programming_Model.html#root-system-description-pointer-rsdp
 #[repr(C, packed)]
-#[derive(Clone, Copy, Debug, Default, IntoBytes, Immutable)]
+#[derive(Clone, Copy, Debug, Default, IntoBytes, FromBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Rsdp {
     signature: [u8; 8],
     checksum: u8,
@@ -35,13 +37,15 @@ pub struct Rsdp {
     reserved: [u8; 3],
 }
 
+assert_layout!(Rsdp, 36, rsdt_addr: 16, length: 20, xsdt_addr: 24);
+
 impl Rsdp {
     /// RSDP structure size for checksum calculation
     const RSDP_CHECKSUM_LENGTH: usize = 20;
     
     pub fn new(oem_id: [u8; 6], xsdt_addr: u64) -> Self {
         // Performance optimization: Use const size expression
-        const RSDP_SIZE: u32 = std::mem::size_of::<Rsdp>() as u32;
+        const RSDP_SIZE: u32 = core::mem::size_of::<Rsdp>() as u32;
         
         let mut rsdp = Rsdp {
             // Space in the end of string is needed!
@@ -60,15 +64,142 @@ impl Rsdp {
         rsdp.extended_checksum = checksum(&[rsdp.as_bytes()]);
         rsdp
     }
+
+    /// Create an ACPI 1.0 RSDP, pointing to an RSDT.
+    ///
+    /// This revision of the structure stops after the RSDT address, it doesn't have the length,
+    /// XSDT address and extended checksum fields.
+    pub fn new_legacy(oem_id: [u8; 6], rsdt_addr: u32) -> Self {
+        let mut rsdp = Rsdp {
+            signature: *b"RSD PTR ",
+            checksum: 0,
+            oem_id,
+            revision: 0,
+            rsdt_addr: U32::new(rsdt_addr),
+            ..Default::default()
+        };
+
+        rsdp.checksum = checksum(&[&rsdp.as_bytes()[..Self::RSDP_CHECKSUM_LENGTH]]);
+        rsdp
+    }
+
+    /// Parse an RSDP, checking its signature and checksums
+    ///
+    /// An ACPI 1.0 RSDP is parsed from the first 20 bytes of `bytes`, while the length field of
+    /// later revisions must match the size of the buffer.
+    pub fn parse(bytes: &[u8]) -> Result<Self> {
+        let legacy = bytes
+            .get(..Self::RSDP_CHECKSUM_LENGTH)
+            .ok_or(AcpiError::InvalidTableLength)?;
+        if &legacy[..8] != b"RSD PTR " {
+            return Err(AcpiError::InvalidSignature);
+        }
+        if checksum(&[legacy]) != 0 {
+            return Err(AcpiError::InvalidChecksum);
+        }
+
+        let mut rsdp = Rsdp::default();
+        rsdp.as_mut_bytes()[..Self::RSDP_CHECKSUM_LENGTH].copy_from_slice(legacy);
+        if rsdp.revision == 0 {
+            return Ok(rsdp);
+        }
+
+        let rsdp = Rsdp::read_from_bytes(bytes).map_err(|_| AcpiError::InvalidTableLength)?;
+        if rsdp.length.get() as usize != bytes.len() {
+            return Err(AcpiError::InvalidTableLength);
+        }
+        if checksum(&[bytes]) != 0 {
+            return Err(AcpiError::InvalidChecksum);
+        }
+        Ok(rsdp)
+    }
+
+    /// Revision of the structure, 0 for ACPI 1.0
+    pub fn revision(&self) -> u8 {
+        self.revision
+    }
+
+    /// Address of the RSDT
+    pub fn rsdt_addr(&self) -> u32 {
+        self.rsdt_addr.get()
+    }
+
+    /// Address of the XSDT, absent from ACPI 1.0 RSDPs
+    pub fn xsdt_addr(&self) -> Option<u64> {
+        (self.revision != 0).then_some(self.xsdt_addr.get())
+    }
 }
 
 impl Sdt for Rsdp {
     fn len(&self) -> usize {
-        self.as_bytes().len()
+        if self.revision == 0 {
+            Self::RSDP_CHECKSUM_LENGTH
+        } else {
+            self.as_bytes().len()
+        }
+    }
+
+    fn to_bytes(&mut self) -> Result<Vec<u8>> {
+        Ok(self.as_bytes()[..self.len()].to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vm_memory::{Bytes, GuestAddress, GuestMemoryMmap};
+
+    use super::*;
+
+    #[test]
+    fn test_rsdp() {
+        let mem: GuestMemoryMmap =
+            GuestMemoryMmap::from_ranges(&[(GuestAddress(0), 0x1000)]).unwrap();
+
+        let mut rsdp = Rsdp::new(*b"FOOBAR", 0x1000);
+        assert_eq!(rsdp.len(), 36);
+        rsdp.write_to_guest(&mem, GuestAddress(0)).unwrap();
+        let mut bytes = [0u8; 36];
+        mem.read_slice(&mut bytes, GuestAddress(0)).unwrap();
+        assert_eq!(checksum(&[&bytes[..20]]), 0);
+        assert_eq!(checksum(&[&bytes]), 0);
+        assert_eq!(&bytes[24..32], &0x1000u64.to_le_bytes());
+
+        let mut rsdp = Rsdp::new_legacy(*b"FOOBAR", 0x2000);
+        assert_eq!(rsdp.len(), 20);
+        rsdp.write_to_guest(&mem, GuestAddress(0x100)).unwrap();
+        let mut bytes = [0u8; 36];
+        mem.read_slice(&mut bytes, GuestAddress(0x100)).unwrap();
+        assert_eq!(&bytes[..8], b"RSD PTR ");
+        assert_eq!(checksum(&[&bytes[..20]]), 0);
+        assert_eq!(bytes[15], 0);
+        assert_eq!(&bytes[16..20], &0x2000u32.to_le_bytes());
+        // Only the ACPI 1.0 fields are written.
+        assert_eq!(&bytes[20..], &[0; 16]);
     }
 
-    fn write_to_guest<M: GuestMemory>(&mut self, mem: &M, address: GuestAddress) -> Result<()> {
-        mem.write_slice(self.as_bytes(), address)?;
-        Ok(())
+    #[test]
+    fn test_rsdp_parse() {
+        let rsdp = Rsdp::parse(Rsdp::new(*b"FOOBAR", 0x1000).as_bytes()).unwrap();
+        assert_eq!(rsdp.revision(), 2);
+        assert_eq!(rsdp.xsdt_addr(), Some(0x1000));
+
+        // The bytes past an ACPI 1.0 RSDP are ignored.
+        let mut bytes = Rsdp::new_legacy(*b"FOOBAR", 0x2000).as_bytes().to_vec();
+        bytes[20] = 0xff;
+        let rsdp = Rsdp::parse(&bytes).unwrap();
+        assert_eq!(rsdp.rsdt_addr(), 0x2000);
+        assert_eq!(rsdp.xsdt_addr(), None);
+
+        bytes[0] = b'X';
+        assert!(matches!(
+            Rsdp::parse(&bytes),
+            Err(AcpiError::InvalidSignature)
+        ));
+        let mut bytes = Rsdp::new(*b"FOOBAR", 0x1000).as_bytes().to_vec();
+        bytes[30] ^= 0xff;
+        assert!(matches!(
+            Rsdp::parse(&bytes),
+            Err(AcpiError::InvalidChecksum)
+        ));
     }
 }
@@ -0,0 +1,85 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use alloc::vec::Vec;
+use core::mem::size_of;
+
+use zerocopy::IntoBytes;
+
+use crate::endian::U32;
+use crate::{Result, Sdt, SdtHeader, checksum};
+
+/// Root System Description Table (RSDT)
+///
+/// This table is the ACPI 1.0 counterpart of the XSDT, providing 32bit addresses to the rest of
+/// the ACPI tables defined by the platform, for the guests which don't look for an XSDT.
+/// More information about this table can be found in the ACPI specification:
+/// https://uefi.org/specs/ACPI/6.5/05_ACPI_Software_Programming_Model.html#root-system-description-table-rsdt
+#[derive(Clone, Default, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rsdt {
+    header: SdtHeader,
+    tables: Vec<U32>,
+}
+
+impl Rsdt {
+    pub fn new(
+        oem_id: [u8; 6],
+        oem_table_id: [u8; 8],
+        oem_revision: u32,
+        tables: Vec<u32>,
+    ) -> Self {
+        let tables: Vec<U32> = tables.into_iter().map(U32::new).collect();
+        let length = size_of::<SdtHeader>() + tables.len() * size_of::<U32>();
+        let header = SdtHeader::new(
+            *b"RSDT",
+            length.try_into().unwrap(),
+            1,
+            oem_id,
+            oem_table_id,
+            oem_revision,
+        );
+
+        let mut rsdt = Rsdt { header, tables };
+        rsdt.header.checksum = checksum(&[rsdt.header.as_bytes(), rsdt.tables.as_bytes()]);
+        rsdt
+    }
+}
+
+impl Sdt for Rsdt {
+    fn len(&self) -> usize {
+        self.header.length.get().try_into().unwrap()
+    }
+
+    fn header(&self) -> Option<&SdtHeader> {
+        Some(&self.header)
+    }
+
+    fn to_bytes(&mut self) -> Result<Vec<u8>> {
+        Ok([self.header.as_bytes(), self.tables.as_bytes()].concat())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vm_memory::{Bytes, GuestAddress, GuestMemoryMmap};
+
+    use super::*;
+
+    #[test]
+    fn test_rsdt() {
+        let mut rsdt = Rsdt::new(*b"FOOBAR", *b"FOOBARRS", 0, vec![0x1000, 0x2000]);
+        assert_eq!(rsdt.len(), 36 + 2 * 4);
+
+        let mem: GuestMemoryMmap =
+            GuestMemoryMmap::from_ranges(&[(GuestAddress(0), 0x1000)]).unwrap();
+        rsdt.write_to_guest(&mem, GuestAddress(0)).unwrap();
+        let mut bytes = vec![0u8; rsdt.len()];
+        mem.read_slice(&mut bytes, GuestAddress(0)).unwrap();
+        assert_eq!(checksum(&[&bytes]), 0);
+        assert_eq!(&bytes[..4], b"RSDT");
+        assert_eq!(&bytes[36..40], &0x1000u32.to_le_bytes());
+        assert_eq!(&bytes[40..44], &0x2000u32.to_le_bytes());
+    }
+}
@@ -0,0 +1,202 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use alloc::vec::Vec;
+use core::mem::size_of;
+
+use zerocopy::{Immutable, IntoBytes};
+
+use crate::endian::U64;
+use crate::{AcpiError, Result, Sdt, SdtHeader, checksum};
+
+/// Distance of a locality to itself.
+pub const SLIT_LOCAL_DISTANCE: u8 = 10;
+/// Distance between localities which cannot reach each other.
+pub const SLIT_UNREACHABLE_DISTANCE: u8 = 0xff;
+
+// clippy doesn't understand that we actually "use" the fields of this struct when we serialize
+// them as bytes in guest memory, so here we just ignore dead code to avoid having to name
+// everything with an underscore prefix
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Clone, Debug, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct SlitHeader {
+    sdt: SdtHeader,
+    localities: U64,
+}
+
+assert_layout!(SlitHeader, 44);
+
+/// System Locality Information Table (SLIT)
+///
+/// This table holds the relative distances between the proximity domains of the SRAT, which the
+/// guest uses to prefer closer NUMA nodes. The distance of a locality to itself is 10, and the
+/// others are relative to it. More information about this table can be found in the ACPI
+/// specification:
+/// https://uefi.org/specs/ACPI/6.5/05_ACPI_Software_Programming_Model.html#system-locality-information-table-slit
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Slit {
+    header: SlitHeader,
+    distances: Vec<u8>,
+}
+
+impl Slit {
+    fn new(
+        oem_id: [u8; 6],
+        oem_table_id: [u8; 8],
+        oem_revision: u32,
+        localities: usize,
+        distances: Vec<u8>,
+    ) -> Self {
+        let length = size_of::<SlitHeader>() + distances.len();
+        let sdt_header = SdtHeader::new(
+            *b"SLIT",
+            length.try_into().unwrap(),
+            1,
+            oem_id,
+            oem_table_id,
+            oem_revision,
+        );
+
+        let mut header = SlitHeader {
+            sdt: sdt_header,
+            localities: U64::new(localities.try_into().unwrap()),
+        };
+        header.sdt.checksum = checksum(&[header.as_bytes(), distances.as_bytes()]);
+
+        Slit { header, distances }
+    }
+}
+
+impl Sdt for Slit {
+    fn len(&self) -> usize {
+        self.header.sdt.length.get().try_into().unwrap()
+    }
+
+    fn header(&self) -> Option<&SdtHeader> {
+        Some(&self.header.sdt)
+    }
+
+    fn to_bytes(&mut self) -> Result<Vec<u8>> {
+        Ok([self.header.as_bytes(), self.distances.as_bytes()].concat())
+    }
+}
+
+/// Builder of a [`Slit`], one locality at a time
+///
+/// The row of a locality holds its distance to every locality, in the order they are added in,
+/// which is the order of their proximity domains.
+#[derive(Clone, Debug, Default)]
+pub struct SlitBuilder {
+    distances: Vec<Vec<u8>>,
+}
+
+impl SlitBuilder {
+    /// Create a builder with no locality.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a locality, along with its distance to every locality.
+    pub fn locality(mut self, distances: Vec<u8>) -> Self {
+        self.distances.push(distances);
+        self
+    }
+
+    /// Build the SLIT table, after checking the distances form a square matrix, that localities
+    /// are at a distance of 10 of themselves and that no locality is closer than that to another.
+    pub fn build(self, oem_id: [u8; 6], oem_table_id: [u8; 8], oem_revision: u32) -> Result<Slit> {
+        let localities = self.distances.len();
+        if localities == 0 || self.distances.iter().any(|row| row.len() != localities) {
+            return Err(AcpiError::InvalidSlitMatrix);
+        }
+        for (from, row) in self.distances.iter().enumerate() {
+            for (to, &distance) in row.iter().enumerate() {
+                if from == to && distance != SLIT_LOCAL_DISTANCE {
+                    return Err(AcpiError::InvalidSlitLocalDistance(from));
+                }
+                if distance < SLIT_LOCAL_DISTANCE {
+                    return Err(AcpiError::InvalidSlitDistance(from, to));
+                }
+            }
+        }
+
+        Ok(Slit::new(
+            oem_id,
+            oem_table_id,
+            oem_revision,
+            localities,
+            self.distances.concat(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vm_memory::{Bytes, GuestAddress, GuestMemoryMmap};
+
+    use super::*;
+
+    #[test]
+    fn test_slit() {
+        let mut slit = SlitBuilder::new()
+            .locality(vec![10, 20, 0xff])
+            .locality(vec![20, 10, 30])
+            .locality(vec![0xff, 30, 10])
+            .build(*b"FOOBAR", *b"FOOBARSL", 0)
+            .unwrap();
+        assert_eq!(slit.len(), 44 + 9);
+
+        let mem: GuestMemoryMmap =
+            GuestMemoryMmap::from_ranges(&[(GuestAddress(0), 0x1000)]).unwrap();
+        slit.write_to_guest(&mem, GuestAddress(0)).unwrap();
+        let mut bytes = vec![0u8; slit.len()];
+        mem.read_slice(&mut bytes, GuestAddress(0)).unwrap();
+        assert_eq!(checksum(&[&bytes]), 0);
+        assert_eq!(&bytes[..4], b"SLIT");
+        assert_eq!(&bytes[36..44], &3u64.to_le_bytes());
+        assert_eq!(&bytes[44..], &[10, 20, 0xff, 20, 10, 30, 0xff, 30, 10]);
+    }
+
+    #[test]
+    fn test_slit_validation() {
+        let build = |builder: SlitBuilder| builder.build(*b"FOOBAR", *b"FOOBARSL", 0);
+
+        assert!(matches!(
+            build(SlitBuilder::new()),
+            Err(AcpiError::InvalidSlitMatrix)
+        ));
+        assert!(matches!(
+            build(SlitBuilder::new().locality(vec![10, 20])),
+            Err(AcpiError::InvalidSlitMatrix)
+        ));
+        assert!(matches!(
+            build(
+                SlitBuilder::new()
+                    .locality(vec![10, 20])
+                    .locality(vec![20, 10, 20])
+            ),
+            Err(AcpiError::InvalidSlitMatrix)
+        ));
+        assert!(matches!(
+            build(
+                SlitBuilder::new()
+                    .locality(vec![10, 20])
+                    .locality(vec![20, 11])
+            ),
+            Err(AcpiError::InvalidSlitLocalDistance(1))
+        ));
+        assert!(matches!(
+            build(
+                SlitBuilder::new()
+                    .locality(vec![10, 9])
+                    .locality(vec![20, 10])
+            ),
+            Err(AcpiError::InvalidSlitDistance(0, 1))
+        ));
+        build(SlitBuilder::new().locality(vec![10])).unwrap();
+    }
+}
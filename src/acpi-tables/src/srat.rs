@@ -0,0 +1,266 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use alloc::vec::Vec;
+use core::mem::size_of;
+
+use zerocopy::{Immutable, IntoBytes};
+
+use crate::endian::{U16, U32, U64};
+use crate::{Result, Sdt, SdtHeader, checksum};
+
+const SRAT_ENABLED_FLAG: u32 = 0;
+const SRAT_HOT_PLUGGABLE_FLAG: u32 = 1;
+
+// clippy doesn't understand that we actually "use" the fields of this struct when we serialize
+// them as bytes in guest memory, so here we just ignore dead code to avoid having to name
+// everything with an underscore prefix
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Copy, Clone, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProcessorAffinity {
+    r#type: u8,
+    length: u8,
+    proximity_domain_lo: u8,
+    apic_id: u8,
+    flags: U32,
+    local_sapic_eid: u8,
+    proximity_domain_hi: [u8; 3],
+    clock_domain: U32,
+}
+
+assert_layout!(ProcessorAffinity, 16);
+
+impl ProcessorAffinity {
+    /// Create an affinity structure attaching the processor with `apic_id` to `proximity_domain`.
+    pub fn new(apic_id: u8, proximity_domain: u32) -> Self {
+        let [lo, hi @ ..] = proximity_domain.to_le_bytes();
+        ProcessorAffinity {
+            r#type: 0,
+            length: 16,
+            proximity_domain_lo: lo,
+            apic_id,
+            flags: U32::new(1u32 << SRAT_ENABLED_FLAG),
+            local_sapic_eid: 0,
+            proximity_domain_hi: hi,
+            clock_domain: U32::ZERO,
+        }
+    }
+}
+
+// clippy doesn't understand that we actually "use" the fields of this struct when we serialize
+// them as bytes in guest memory, so here we just ignore dead code to avoid having to name
+// everything with an underscore prefix
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Copy, Clone, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct X2ApicAffinity {
+    r#type: u8,
+    length: u8,
+    _reserved1: U16,
+    proximity_domain: U32,
+    x2apic_id: U32,
+    flags: U32,
+    clock_domain: U32,
+    _reserved2: U32,
+}
+
+assert_layout!(X2ApicAffinity, 24);
+
+impl X2ApicAffinity {
+    /// Create an affinity structure attaching the processor with `x2apic_id` to
+    /// `proximity_domain`, for processors whose APIC ID does not fit the 8 bits of a
+    /// [`ProcessorAffinity`] structure.
+    pub fn new(x2apic_id: u32, proximity_domain: u32) -> Self {
+        X2ApicAffinity {
+            r#type: 2,
+            length: 24,
+            _reserved1: U16::ZERO,
+            proximity_domain: U32::new(proximity_domain),
+            x2apic_id: U32::new(x2apic_id),
+            flags: U32::new(1u32 << SRAT_ENABLED_FLAG),
+            clock_domain: U32::ZERO,
+            _reserved2: U32::ZERO,
+        }
+    }
+}
+
+// clippy doesn't understand that we actually "use" the fields of this struct when we serialize
+// them as bytes in guest memory, so here we just ignore dead code to avoid having to name
+// everything with an underscore prefix
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Copy, Clone, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MemoryAffinity {
+    r#type: u8,
+    length: u8,
+    proximity_domain: U32,
+    _reserved1: U16,
+    base_address: U64,
+    size: U64,
+    _reserved2: U32,
+    flags: U32,
+    _reserved3: U64,
+}
+
+assert_layout!(MemoryAffinity, 40, base_address: 8, flags: 28);
+
+impl MemoryAffinity {
+    /// Create an affinity structure attaching the memory range at `base_address` to
+    /// `proximity_domain`. A `hot_pluggable` range may only be populated at runtime.
+    pub fn new(base_address: u64, size: u64, proximity_domain: u32, hot_pluggable: bool) -> Self {
+        let mut flags = 1u32 << SRAT_ENABLED_FLAG;
+        if hot_pluggable {
+            flags |= 1u32 << SRAT_HOT_PLUGGABLE_FLAG;
+        }
+        MemoryAffinity {
+            r#type: 1,
+            length: 40,
+            proximity_domain: U32::new(proximity_domain),
+            _reserved1: U16::ZERO,
+            base_address: U64::new(base_address),
+            size: U64::new(size),
+            _reserved2: U32::ZERO,
+            flags: U32::new(flags),
+            _reserved3: U64::ZERO,
+        }
+    }
+}
+
+// clippy doesn't understand that we actually "use" the fields of this struct when we serialize
+// them as bytes in guest memory, so here we just ignore dead code to avoid having to name
+// everything with an underscore prefix
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Debug, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct SratHeader {
+    sdt: SdtHeader,
+    // Reserved, must be 1 for backward compatibility.
+    table_revision: U32,
+    _reserved: U64,
+}
+
+assert_layout!(SratHeader, 48);
+
+/// System Resource Affinity Table (SRAT)
+///
+/// This table attaches the processors and memory ranges of the platform to proximity domains,
+/// which is how the guest learns about its NUMA nodes. More information about this table can be
+/// found in the ACPI specification:
+/// https://uefi.org/specs/ACPI/6.5/05_ACPI_Software_Programming_Model.html#system-resource-affinity-table-srat
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Srat {
+    header: SratHeader,
+    affinities: Vec<u8>,
+}
+
+impl Srat {
+    /// Create an SRAT table holding the given processor, x2APIC and memory affinity structures.
+    pub fn new(
+        oem_id: [u8; 6],
+        oem_table_id: [u8; 8],
+        oem_revision: u32,
+        affinities: Vec<u8>,
+    ) -> Self {
+        let length = size_of::<SratHeader>() + affinities.len();
+        let sdt_header = SdtHeader::new(
+            *b"SRAT",
+            length.try_into().unwrap(),
+            3,
+            oem_id,
+            oem_table_id,
+            oem_revision,
+        );
+
+        let mut header = SratHeader {
+            sdt: sdt_header,
+            table_revision: U32::new(1),
+            _reserved: U64::ZERO,
+        };
+
+        header.sdt.checksum = checksum(&[header.as_bytes(), affinities.as_bytes()]);
+
+        Srat { header, affinities }
+    }
+}
+
+impl Sdt for Srat {
+    fn len(&self) -> usize {
+        self.header.sdt.length.get().try_into().unwrap()
+    }
+
+    fn header(&self) -> Option<&SdtHeader> {
+        Some(&self.header.sdt)
+    }
+
+    fn to_bytes(&mut self) -> Result<Vec<u8>> {
+        Ok([self.header.as_bytes(), self.affinities.as_bytes()].concat())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vm_memory::{Bytes, GuestAddress, GuestMemoryMmap};
+
+    use super::*;
+
+    #[test]
+    fn test_srat() {
+        let mut affinities = Vec::new();
+        affinities.extend_from_slice(ProcessorAffinity::new(1, 0x0102_0304).as_bytes());
+        affinities.extend_from_slice(MemoryAffinity::new(0x1000, 0x2000, 1, true).as_bytes());
+        let mut srat = Srat::new(*b"FOOBAR", *b"FOOBARSR", 0, affinities);
+        assert_eq!(srat.len(), 48 + 16 + 40);
+
+        let mem: GuestMemoryMmap =
+            GuestMemoryMmap::from_ranges(&[(GuestAddress(0), 0x1000)]).unwrap();
+        srat.write_to_guest(&mem, GuestAddress(0)).unwrap();
+        let mut bytes = vec![0u8; srat.len()];
+        mem.read_slice(&mut bytes, GuestAddress(0)).unwrap();
+        assert_eq!(checksum(&[&bytes]), 0);
+        assert_eq!(&bytes[..4], b"SRAT");
+        assert_eq!(&bytes[36..40], &1u32.to_le_bytes());
+
+        // The processor affinity splits the proximity domain around the APIC ID.
+        let cpu = &bytes[48..64];
+        assert_eq!(&cpu[..4], &[0, 16, 0x04, 1]);
+        assert_eq!(&cpu[4..8], &1u32.to_le_bytes());
+        assert_eq!(&cpu[9..12], &[0x03, 0x02, 0x01]);
+
+        let memory = &bytes[64..104];
+        assert_eq!(&memory[..6], &[1, 40, 1, 0, 0, 0]);
+        assert_eq!(&memory[8..16], &0x1000u64.to_le_bytes());
+        assert_eq!(&memory[16..24], &0x2000u64.to_le_bytes());
+        // Enabled and hot pluggable.
+        assert_eq!(&memory[28..32], &3u32.to_le_bytes());
+    }
+
+    #[test]
+    fn test_x2apic_affinity() {
+        let affinity = X2ApicAffinity::new(0x1_0000, 2);
+        let bytes = affinity.as_bytes();
+        assert_eq!(bytes.len(), 24);
+        assert_eq!(&bytes[..4], &[2, 24, 0, 0]);
+        assert_eq!(&bytes[4..8], &2u32.to_le_bytes());
+        assert_eq!(&bytes[8..12], &0x1_0000u32.to_le_bytes());
+        // Enabled.
+        assert_eq!(&bytes[12..16], &1u32.to_le_bytes());
+        assert_eq!(&bytes[16..24], &[0; 8]);
+
+        let mut srat = Srat::new(*b"FOOBAR", *b"FOOBARSR", 0, bytes.to_vec());
+        assert_eq!(srat.len(), 48 + 24);
+        let mem: GuestMemoryMmap =
+            GuestMemoryMmap::from_ranges(&[(GuestAddress(0), 0x1000)]).unwrap();
+        srat.write_to_guest(&mem, GuestAddress(0)).unwrap();
+        let mut table = vec![0u8; srat.len()];
+        mem.read_slice(&mut table, GuestAddress(0)).unwrap();
+        assert_eq!(checksum(&[&table]), 0);
+        assert_eq!(&table[48..], bytes);
+    }
+}
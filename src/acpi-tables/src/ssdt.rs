@@ -0,0 +1,161 @@
+// Copyright 2024 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use alloc::vec::Vec;
+use core::mem::size_of;
+
+use zerocopy::IntoBytes;
+
+use crate::{Result, Sdt, SdtHeader, checksum};
+
+/// Secondary System Description Table (SSDT)
+///
+/// Table that includes additional hardware definition blocks, complementing the DSDT.
+/// More information about this table can be found in the ACPI specification:
+/// https://uefi.org/specs/ACPI/6.5/05_ACPI_Software_Programming_Model.html#secondary-system-description-table-ssdt
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Ssdt {
+    header: SdtHeader,
+    definition_block: Vec<u8>,
+}
+
+impl Ssdt {
+    /// Create an SSDT holding the given definition block, e.g. the AML of a single device.
+    ///
+    /// The SSDTs of a system are told apart by their `oem_table_id`.
+    pub fn new(
+        oem_id: [u8; 6],
+        oem_table_id: [u8; 8],
+        oem_revision: u32,
+        definition_block: Vec<u8>,
+    ) -> Self {
+        let header = SdtHeader::new(
+            *b"SSDT",
+            (size_of::<SdtHeader>() + definition_block.len())
+                .try_into()
+                .unwrap(),
+            2,
+            oem_id,
+            oem_table_id,
+            oem_revision,
+        );
+
+        let mut ssdt = Ssdt {
+            header,
+            definition_block,
+        };
+
+        ssdt.header.checksum =
+            checksum(&[ssdt.header.as_bytes(), ssdt.definition_block.as_slice()]);
+        ssdt
+    }
+
+    /// Create an SSDT from an already compiled table, e.g. the output of `iasl`.
+    ///
+    /// The buffer needs to hold a complete table: a header with the "SSDT" signature whose
+    /// length field matches the size of the buffer, followed by the definition block. The
+    /// checksum of the table is verified.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let (header, definition_block) = SdtHeader::parse(bytes, b"SSDT")?;
+
+        Ok(Ssdt {
+            header,
+            definition_block: definition_block.to_vec(),
+        })
+    }
+
+    /// OEM table ID of this table
+    pub fn oem_table_id(&self) -> [u8; 8] {
+        self.header.oem_table_id
+    }
+}
+
+impl Sdt for Ssdt {
+    fn len(&self) -> usize {
+        self.header.length.get() as usize
+    }
+
+    fn header(&self) -> Option<&SdtHeader> {
+        Some(&self.header)
+    }
+
+    fn to_bytes(&mut self) -> Result<Vec<u8>> {
+        Ok([self.header.as_bytes(), self.definition_block.as_slice()].concat())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vm_memory::{Bytes, GuestAddress, GuestMemoryMmap};
+
+    use super::*;
+    use crate::AcpiError;
+
+    fn ssdt_bytes(signature: &[u8; 4], definition_block: &[u8]) -> Vec<u8> {
+        let mut header = SdtHeader::new(
+            *signature,
+            (size_of::<SdtHeader>() + definition_block.len())
+                .try_into()
+                .unwrap(),
+            2,
+            *b"FCTEST",
+            *b"FCTSSSDT",
+            0,
+        );
+        header.checksum = checksum(&[header.as_bytes(), definition_block]);
+        let mut bytes = header.as_bytes().to_vec();
+        bytes.extend_from_slice(definition_block);
+        bytes
+    }
+
+    #[test]
+    fn test_ssdt_new() {
+        let definition_block = vec![0x10, 0x05, 0x5c, 0x5f, 0x53, 0x42, 0x5f];
+        let mut ssdt = Ssdt::new(*b"FCTEST", *b"FCTSSSDT", 0, definition_block.clone());
+        assert_eq!(ssdt.len(), 36 + definition_block.len());
+        assert_eq!(&ssdt.oem_table_id(), b"FCTSSSDT");
+
+        let mem: GuestMemoryMmap =
+            GuestMemoryMmap::from_ranges(&[(GuestAddress(0), 0x1000)]).unwrap();
+        ssdt.write_to_guest(&mem, GuestAddress(0)).unwrap();
+        let mut bytes = vec![0u8; ssdt.len()];
+        mem.read_slice(&mut bytes, GuestAddress(0)).unwrap();
+        assert_eq!(bytes, ssdt_bytes(b"SSDT", &definition_block));
+
+        // The table round-trips through its bytes.
+        let ssdt = Ssdt::from_bytes(&bytes).unwrap();
+        assert_eq!(ssdt.definition_block, definition_block);
+    }
+
+    #[test]
+    fn test_ssdt_from_bytes() {
+        let bytes = ssdt_bytes(b"SSDT", &[0x10, 0x05, 0x5c, 0x5f, 0x53, 0x42, 0x5f]);
+        let ssdt = Ssdt::from_bytes(&bytes).unwrap();
+        assert_eq!(ssdt.len(), bytes.len());
+        assert_eq!(&ssdt.oem_table_id(), b"FCTSSSDT");
+
+        // Too short to even hold a header
+        assert!(matches!(
+            Ssdt::from_bytes(&bytes[..10]),
+            Err(AcpiError::InvalidTableLength)
+        ));
+        // Length in the header does not match the buffer
+        assert!(matches!(
+            Ssdt::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(AcpiError::InvalidTableLength)
+        ));
+        // Not an SSDT
+        assert!(matches!(
+            Ssdt::from_bytes(&ssdt_bytes(b"DSDT", &[])),
+            Err(AcpiError::InvalidSignature)
+        ));
+        // Corrupted definition block
+        let mut corrupted = bytes.clone();
+        *corrupted.last_mut().unwrap() ^= 0xff;
+        assert!(matches!(
+            Ssdt::from_bytes(&corrupted),
+            Err(AcpiError::InvalidChecksum)
+        ));
+    }
+}
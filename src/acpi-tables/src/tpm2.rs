@@ -0,0 +1,176 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use alloc::vec::Vec;
+use core::mem::size_of;
+
+use zerocopy::{Immutable, IntoBytes};
+
+use crate::endian::{U16, U32, U64};
+use crate::{Result, Sdt, SdtHeader, checksum};
+
+// Size of the table without the optional log area fields.
+const TPM2_LENGTH_WITHOUT_LOG_AREA: usize = 64;
+
+/// Class of the platform the TPM belongs to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u16)]
+pub enum Tpm2PlatformClass {
+    /// Client platform.
+    Client = 0,
+    /// Server platform.
+    Server = 1,
+}
+
+/// Interface the guest uses to start TPM commands.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u32)]
+pub enum Tpm2StartMethod {
+    /// Commands are started through the ACPI start method of the TPM device.
+    Acpi = 2,
+    /// Commands are sent through the FIFO registers of the TIS interface.
+    Tis = 6,
+    /// Commands are started through the Command Response Buffer interface.
+    Crb = 7,
+    /// Commands are started through both the Command Response Buffer interface and the ACPI
+    /// start method.
+    CrbWithAcpi = 8,
+}
+
+/// Trusted Platform Module 2 Table (TPM2)
+///
+/// This table describes the interface of a TPM 2.0 and, optionally, the area holding the log of
+/// the measurements taken before the guest booted. More information about this table can be found
+/// in the TCG ACPI specification:
+/// https://trustedcomputinggroup.org/resource/tcg-acpi-specification/
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Tpm2 {
+    header: SdtHeader,
+    platform_class: U16,
+    _reserved: U16,
+    control_area_address: U64,
+    start_method: U32,
+    start_method_parameters: [u8; 12],
+    log_area_min_length: U32,
+    log_area_start_address: U64,
+}
+
+assert_layout!(Tpm2, 76, start_method: 48, log_area_min_length: 64);
+
+impl Tpm2 {
+    /// Create a TPM2 table for a TPM whose control area, or registers for the TIS interface, is
+    /// at `control_area_address`.
+    ///
+    /// The optional `log_area` holds the start address and length of the event log area.
+    pub fn new(
+        oem_id: [u8; 6],
+        oem_table_id: [u8; 8],
+        oem_revision: u32,
+        platform_class: Tpm2PlatformClass,
+        control_area_address: u64,
+        start_method: Tpm2StartMethod,
+        log_area: Option<(u64, u32)>,
+    ) -> Self {
+        let length = match log_area {
+            Some(_) => size_of::<Tpm2>(),
+            None => TPM2_LENGTH_WITHOUT_LOG_AREA,
+        };
+        let (log_area_start_address, log_area_min_length) = log_area.unwrap_or_default();
+        let header = SdtHeader::new(
+            *b"TPM2",
+            length.try_into().unwrap(),
+            4,
+            oem_id,
+            oem_table_id,
+            oem_revision,
+        );
+
+        let mut tpm2 = Tpm2 {
+            header,
+            platform_class: U16::new(platform_class as u16),
+            _reserved: U16::ZERO,
+            control_area_address: U64::new(control_area_address),
+            start_method: U32::new(start_method as u32),
+            start_method_parameters: [0; 12],
+            log_area_min_length: U32::new(log_area_min_length),
+            log_area_start_address: U64::new(log_area_start_address),
+        };
+
+        tpm2.header.checksum = checksum(&[&tpm2.as_bytes()[..length]]);
+
+        tpm2
+    }
+}
+
+impl Sdt for Tpm2 {
+    fn len(&self) -> usize {
+        self.header.length.get().try_into().unwrap()
+    }
+
+    fn header(&self) -> Option<&SdtHeader> {
+        Some(&self.header)
+    }
+
+    fn to_bytes(&mut self) -> Result<Vec<u8>> {
+        Ok(self.as_bytes()[..self.len()].to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vm_memory::{Bytes, GuestAddress, GuestMemoryMmap};
+
+    use super::*;
+
+    fn read_table(tpm2: &mut Tpm2) -> Vec<u8> {
+        let mem: GuestMemoryMmap =
+            GuestMemoryMmap::from_ranges(&[(GuestAddress(0), 0x1000)]).unwrap();
+        tpm2.write_to_guest(&mem, GuestAddress(0)).unwrap();
+        let mut bytes = vec![0u8; tpm2.len()];
+        mem.read_slice(&mut bytes, GuestAddress(0)).unwrap();
+        assert_eq!(checksum(&[&bytes]), 0);
+        bytes
+    }
+
+    #[test]
+    fn test_tpm2() {
+        let mut tpm2 = Tpm2::new(
+            *b"FOOBAR",
+            *b"FOOBARTP",
+            0,
+            Tpm2PlatformClass::Client,
+            0xfed4_0040,
+            Tpm2StartMethod::Crb,
+            None,
+        );
+        assert_eq!(tpm2.len(), 64);
+        let bytes = read_table(&mut tpm2);
+        assert_eq!(&bytes[..4], b"TPM2");
+        assert_eq!(bytes[8], 4);
+        assert_eq!(&bytes[36..40], &[0, 0, 0, 0]);
+        assert_eq!(&bytes[40..48], &0xfed4_0040u64.to_le_bytes());
+        assert_eq!(&bytes[48..52], &7u32.to_le_bytes());
+        assert_eq!(&bytes[52..64], &[0; 12]);
+
+        // The log area fields are only present when there is a log area.
+        let mut tpm2 = Tpm2::new(
+            *b"FOOBAR",
+            *b"FOOBARTP",
+            0,
+            Tpm2PlatformClass::Server,
+            0xfed4_0000,
+            Tpm2StartMethod::Tis,
+            Some((0x1000_0000, 0x1_0000)),
+        );
+        assert_eq!(tpm2.len(), 76);
+        let bytes = read_table(&mut tpm2);
+        assert_eq!(&bytes[36..38], &1u16.to_le_bytes());
+        assert_eq!(&bytes[48..52], &6u32.to_le_bytes());
+        assert_eq!(&bytes[64..68], &0x1_0000u32.to_le_bytes());
+        assert_eq!(&bytes[68..76], &0x1000_0000u64.to_le_bytes());
+    }
+}
@@ -0,0 +1,234 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use alloc::vec::Vec;
+use core::mem::size_of;
+
+use zerocopy::{Immutable, IntoBytes};
+
+use crate::endian::{U16, U32};
+use crate::{GenericAddressStructure, Result, Sdt, SdtHeader, checksum};
+
+/// Restarts the countdown of the watchdog.
+pub const WDAT_ACTION_RESET: u8 = 0x1;
+/// Reads the remaining count of the watchdog.
+pub const WDAT_ACTION_QUERY_CURRENT_COUNTDOWN_PERIOD: u8 = 0x4;
+/// Reads the count the watchdog is restarted with.
+pub const WDAT_ACTION_QUERY_COUNTDOWN_PERIOD: u8 = 0x5;
+/// Sets the count the watchdog is restarted with.
+pub const WDAT_ACTION_SET_COUNTDOWN_PERIOD: u8 = 0x6;
+/// Checks whether the watchdog is running.
+pub const WDAT_ACTION_QUERY_RUNNING_STATE: u8 = 0x8;
+/// Starts the watchdog.
+pub const WDAT_ACTION_SET_RUNNING_STATE: u8 = 0x9;
+/// Checks whether the watchdog is stopped.
+pub const WDAT_ACTION_QUERY_STOPPED_STATE: u8 = 0xa;
+/// Stops the watchdog.
+pub const WDAT_ACTION_SET_STOPPED_STATE: u8 = 0xb;
+/// Checks whether the watchdog expired before the platform was reset.
+pub const WDAT_ACTION_QUERY_WATCHDOG_STATUS: u8 = 0x20;
+/// Clears the expiry status of the watchdog.
+pub const WDAT_ACTION_SET_WATCHDOG_STATUS: u8 = 0x21;
+
+/// Reads the register and compares the masked result with the value of the entry.
+pub const WDAT_INSTRUCTION_READ_VALUE: u8 = 0x0;
+/// Reads the register and returns the masked result as a count.
+pub const WDAT_INSTRUCTION_READ_COUNTDOWN: u8 = 0x1;
+/// Writes the masked value of the entry in the register.
+pub const WDAT_INSTRUCTION_WRITE_VALUE: u8 = 0x2;
+/// Writes the masked count given by the OS in the register.
+pub const WDAT_INSTRUCTION_WRITE_COUNTDOWN: u8 = 0x3;
+
+// The watchdog is enabled.
+const WDAT_FLAG_ENABLED: u8 = 1 << 0;
+// Value of the PCI location fields for a watchdog that is not a PCI device.
+const WDAT_NO_PCI: u8 = 0xff;
+
+/// An instruction of a watchdog action.
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WdatEntry {
+    action: u8,
+    instruction: u8,
+    _reserved: U16,
+    register: GenericAddressStructure,
+    value: U32,
+    mask: U32,
+}
+
+assert_layout!(WdatEntry, 24);
+
+impl WdatEntry {
+    pub fn new(
+        action: u8,
+        instruction: u8,
+        register: GenericAddressStructure,
+        value: u32,
+        mask: u32,
+    ) -> Self {
+        WdatEntry {
+            action,
+            instruction,
+            _reserved: U16::new(0),
+            register,
+            value: U32::new(value),
+            mask: U32::new(mask),
+        }
+    }
+}
+
+#[allow(dead_code)]
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, Default, IntoBytes, Immutable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct WdatHeader {
+    header_length: U32,
+    pci_segment: U16,
+    pci_bus: u8,
+    pci_device: u8,
+    pci_function: u8,
+    _reserved: [u8; 3],
+    timer_period: U32,
+    max_count: U32,
+    min_count: U32,
+    flags: u8,
+    _reserved2: [u8; 3],
+    entries: U32,
+}
+
+assert_layout!(WdatHeader, 32);
+
+/// Watchdog Action Table (WDAT)
+///
+/// This table describes a hardware watchdog as a list of actions, each made of instructions
+/// reading or writing its registers. More information about this table can be found in the
+/// Microsoft specification:
+/// https://download.microsoft.com/download/a/f/7/af7777e5-7dcd-4800-8a0a-b18336565f5b/hardwarewdtspec.doc
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Wdat {
+    header: SdtHeader,
+    wdat_header: WdatHeader,
+    entries: Vec<WdatEntry>,
+}
+
+impl Wdat {
+    /// Create a WDAT table for a watchdog counting `timer_period_ms` milliseconds per count.
+    pub fn new(
+        oem_id: [u8; 6],
+        oem_table_id: [u8; 8],
+        oem_revision: u32,
+        timer_period_ms: u32,
+        min_count: u32,
+        max_count: u32,
+        entries: Vec<WdatEntry>,
+    ) -> Self {
+        let length = size_of::<SdtHeader>()
+            + size_of::<WdatHeader>()
+            + entries.len() * size_of::<WdatEntry>();
+        let header = SdtHeader::new(
+            *b"WDAT",
+            length.try_into().unwrap(),
+            1,
+            oem_id,
+            oem_table_id,
+            oem_revision,
+        );
+        let wdat_header = WdatHeader {
+            header_length: U32::new(size_of::<WdatHeader>().try_into().unwrap()),
+            pci_segment: U16::new(u16::from(WDAT_NO_PCI)),
+            pci_bus: WDAT_NO_PCI,
+            pci_device: WDAT_NO_PCI,
+            pci_function: WDAT_NO_PCI,
+            timer_period: U32::new(timer_period_ms),
+            max_count: U32::new(max_count),
+            min_count: U32::new(min_count),
+            flags: WDAT_FLAG_ENABLED,
+            entries: U32::new(entries.len().try_into().unwrap()),
+            ..Default::default()
+        };
+
+        let mut wdat = Wdat {
+            header,
+            wdat_header,
+            entries,
+        };
+
+        wdat.header.checksum = checksum(&[
+            wdat.header.as_bytes(),
+            wdat.wdat_header.as_bytes(),
+            wdat.entries.as_bytes(),
+        ]);
+
+        wdat
+    }
+}
+
+impl Sdt for Wdat {
+    fn len(&self) -> usize {
+        self.header.length.get().try_into().unwrap()
+    }
+
+    fn header(&self) -> Option<&SdtHeader> {
+        Some(&self.header)
+    }
+
+    fn to_bytes(&mut self) -> Result<Vec<u8>> {
+        Ok([
+            self.header.as_bytes(),
+            self.wdat_header.as_bytes(),
+            self.entries.as_bytes(),
+        ]
+        .concat())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vm_memory::{Bytes, GuestAddress, GuestMemoryMmap};
+
+    use super::*;
+
+    #[test]
+    fn test_wdat() {
+        let register = GenericAddressStructure::from_raw(0, 32, 0, 3, 0xd000_0000);
+        let entries = vec![
+            WdatEntry::new(
+                WDAT_ACTION_RESET,
+                WDAT_INSTRUCTION_WRITE_VALUE,
+                register,
+                1,
+                1,
+            ),
+            WdatEntry::new(
+                WDAT_ACTION_SET_COUNTDOWN_PERIOD,
+                WDAT_INSTRUCTION_WRITE_COUNTDOWN,
+                register,
+                0,
+                0xffff_ffff,
+            ),
+        ];
+        let mut wdat = Wdat::new(*b"FOOBAR", *b"FOOBARWD", 0, 1000, 1, 600, entries);
+        assert_eq!(wdat.len(), 36 + 32 + 2 * 24);
+
+        let mem: GuestMemoryMmap =
+            GuestMemoryMmap::from_ranges(&[(GuestAddress(0), 0x1000)]).unwrap();
+        wdat.write_to_guest(&mem, GuestAddress(0)).unwrap();
+        let mut bytes = vec![0u8; wdat.len()];
+        mem.read_slice(&mut bytes, GuestAddress(0)).unwrap();
+        assert_eq!(checksum(&[&bytes]), 0);
+        assert_eq!(&bytes[..4], b"WDAT");
+        // Not a PCI device, with a period of 1s and 2 entries.
+        assert_eq!(&bytes[40..45], &[0xff, 0, 0xff, 0xff, 0xff]);
+        assert_eq!(&bytes[48..52], &1000u32.to_le_bytes());
+        assert_eq!(bytes[60], WDAT_FLAG_ENABLED);
+        assert_eq!(&bytes[64..68], &2u32.to_le_bytes());
+        // The second entry.
+        assert_eq!(&bytes[92..96], &[WDAT_ACTION_SET_COUNTDOWN_PERIOD, 3, 0, 0]);
+        assert_eq!(&bytes[96..108], register.as_bytes());
+        assert_eq!(&bytes[112..116], &[0xff; 4]);
+    }
+}
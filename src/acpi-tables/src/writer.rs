@@ -0,0 +1,234 @@
+// Copyright 2024 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use vm_memory::{Bytes, GuestAddress, GuestMemory};
+
+use crate::{AcpiConfig, AcpiError, Dsdt, Facs, Fadt, Result, Rsdp, Sdt, Xsdt};
+
+// Alignment of the tables in guest memory, large enough for their 64-bit fields.
+const TABLE_ALIGNMENT: u64 = 8;
+// The FACS must be aligned on a 64-byte boundary.
+const FACS_ALIGNMENT: u64 = 64;
+
+/// Addresses in guest memory of the tables written by an [`AcpiTableWriter`]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AcpiTableAddresses {
+    /// Address of the RSDP
+    pub rsdp: u64,
+    /// Address of the XSDT
+    pub xsdt: u64,
+    /// Address of the FADT, if one was written
+    pub fadt: Option<u64>,
+    /// Address of the DSDT, if one was written
+    pub dsdt: Option<u64>,
+    /// Address of the FACS, if one was written
+    pub facs: Option<u64>,
+    /// Addresses of the other tables the XSDT points to, in the order they were written
+    pub tables: Vec<u64>,
+}
+
+/// Lay out ACPI tables in a range of guest memory
+///
+/// The tables are written one after the other, each of them aligned on 8 bytes and the FACS on
+/// 64 bytes. The writer links them together: the FADT points to the DSDT and the FACS, the XSDT
+/// to the FADT and to every other table, and the RSDP to the XSDT.
+#[derive(Debug)]
+pub struct AcpiTableWriter<'a, M: GuestMemory> {
+    mem: &'a M,
+    next: u64,
+    end: u64,
+    config: Option<AcpiConfig>,
+    addresses: AcpiTableAddresses,
+}
+
+impl<'a, M: GuestMemory> AcpiTableWriter<'a, M> {
+    /// Create a writer placing the tables in the `size` bytes of `mem` starting at `start`.
+    pub fn new(mem: &'a M, start: GuestAddress, size: u64) -> Self {
+        AcpiTableWriter {
+            mem,
+            next: start.0,
+            end: start.0.saturating_add(size),
+            config: None,
+            addresses: AcpiTableAddresses::default(),
+        }
+    }
+
+    /// Stamp the identity of `config` over the one the tables were created with.
+    pub fn config(mut self, config: AcpiConfig) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    fn place<S: Sdt>(&mut self, table: &mut S, alignment: u64) -> Result<u64> {
+        let len = table.len();
+        let addr = self
+            .next
+            .checked_next_multiple_of(alignment)
+            .ok_or(AcpiError::OutOfSpace(len))?;
+        let end = addr
+            .checked_add(len as u64)
+            .filter(|end| *end <= self.end)
+            .ok_or(AcpiError::OutOfSpace(len))?;
+        table.write_to_guest(self.mem, GuestAddress(addr))?;
+        self.next = end;
+        Ok(addr)
+    }
+
+    // Place a table starting with the standard header, stamping it with the configured identity.
+    fn place_sdt<S: Sdt>(&mut self, table: &mut S) -> Result<u64> {
+        let addr = self.place(table, TABLE_ALIGNMENT)?;
+        if let Some(config) = &self.config {
+            let mut bytes = vec![0u8; table.len()];
+            self.mem.read_slice(&mut bytes, GuestAddress(addr))?;
+            config.stamp(&mut bytes)?;
+            self.mem.write_slice(&bytes, GuestAddress(addr))?;
+        }
+        Ok(addr)
+    }
+
+    /// Write the FADT, along with the DSDT and the FACS it points to.
+    ///
+    /// The X_DSDT and X_FIRMWARE_CTRL fields of the FADT are set to the addresses at which the
+    /// DSDT and the FACS were written. Returns the address of the FADT.
+    pub fn write_fadt(
+        &mut self,
+        fadt: &mut Fadt,
+        dsdt: &mut Dsdt,
+        facs: Option<&mut Facs>,
+    ) -> Result<u64> {
+        let dsdt_addr = self.place_sdt(dsdt)?;
+        self.addresses.dsdt = Some(dsdt_addr);
+        fadt.set_x_dsdt(dsdt_addr);
+        if let Some(facs) = facs {
+            let facs_addr = self.place(facs, FACS_ALIGNMENT)?;
+            self.addresses.facs = Some(facs_addr);
+            fadt.set_x_firmware_ctrl(facs_addr);
+        }
+
+        let fadt_addr = self.place_sdt(fadt)?;
+        self.addresses.fadt = Some(fadt_addr);
+        Ok(fadt_addr)
+    }
+
+    /// Write a table the XSDT points to, such as the MADT or an SSDT.
+    ///
+    /// Returns the address of the table.
+    pub fn write_table<S: Sdt>(&mut self, table: &mut S) -> Result<u64> {
+        let addr = self.place_sdt(table)?;
+        self.addresses.tables.push(addr);
+        Ok(addr)
+    }
+
+    /// Write the XSDT, pointing to the FADT followed by the other tables, and the RSDP pointing
+    /// to it at `rsdp_addr`.
+    ///
+    /// The RSDP lives at a location defined by each architecture, which is usually outside of
+    /// the range of the other tables. The OEM identity of the configuration, if any, takes
+    /// precedence over the one given here. Returns the addresses of all the tables.
+    pub fn finish(
+        mut self,
+        oem_id: [u8; 6],
+        oem_table_id: [u8; 8],
+        oem_revision: u32,
+        rsdp_addr: GuestAddress,
+    ) -> Result<AcpiTableAddresses> {
+        let oem_id = self.config.map_or(oem_id, |config| config.oem_id);
+        let entries = self
+            .addresses
+            .fadt
+            .into_iter()
+            .chain(self.addresses.tables.iter().copied())
+            .collect();
+        let mut xsdt = Xsdt::new(oem_id, oem_table_id, oem_revision, entries);
+        self.addresses.xsdt = self.place_sdt(&mut xsdt)?;
+
+        Rsdp::new(oem_id, self.addresses.xsdt).write_to_guest(self.mem, rsdp_addr)?;
+        self.addresses.rsdp = rsdp_addr.0;
+        Ok(self.addresses)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vm_memory::GuestMemoryMmap;
+
+    use super::*;
+    use crate::{Mcfg, SdtHeader};
+
+    fn read_table(mem: &GuestMemoryMmap, addr: u64, len: usize) -> Vec<u8> {
+        let mut bytes = vec![0u8; len];
+        mem.read_slice(&mut bytes, GuestAddress(addr)).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_acpi_table_writer() {
+        let mem: GuestMemoryMmap =
+            GuestMemoryMmap::from_ranges(&[(GuestAddress(0), 0x2000)]).unwrap();
+        let mut writer = AcpiTableWriter::new(&mem, GuestAddress(0x1001), 0xfff);
+
+        let mut fadt = Fadt::new(*b"FOOBAR", *b"FOOBARFA", 0);
+        let mut dsdt = Dsdt::new(*b"FOOBAR", *b"FOOBARDS", 0, vec![0xa5; 3]);
+        let mut facs = Facs::new(0);
+        let fadt_addr = writer
+            .write_fadt(&mut fadt, &mut dsdt, Some(&mut facs))
+            .unwrap();
+        let mut mcfg = Mcfg::new(*b"FOOBAR", *b"FOOBARMC", 0, 0xe000_0000);
+        let mcfg_addr = writer.write_table(&mut mcfg).unwrap();
+        let addresses = writer
+            .finish(*b"FOOBAR", *b"FOOBARXS", 0, GuestAddress(0))
+            .unwrap();
+
+        // The DSDT is 39 bytes long, so the FACS is pushed to the next 64-byte boundary.
+        assert_eq!(addresses.dsdt, Some(0x1008));
+        assert_eq!(addresses.facs, Some(0x1040));
+        assert_eq!(addresses.fadt, Some(fadt_addr));
+        assert_eq!(fadt_addr, 0x1080);
+        assert_eq!(addresses.tables, [mcfg_addr]);
+        assert!(mcfg_addr.is_multiple_of(8) && addresses.xsdt.is_multiple_of(8));
+
+        let rsdp = Rsdp::parse(&read_table(&mem, 0, 36)).unwrap();
+        assert_eq!(rsdp.xsdt_addr(), Some(addresses.xsdt));
+        let xsdt = Xsdt::parse(&read_table(&mem, addresses.xsdt, 52)).unwrap();
+        assert_eq!(xsdt.entries(), [fadt_addr, mcfg_addr]);
+        let fadt = Fadt::parse(&read_table(&mem, fadt_addr, 276)).unwrap();
+        assert_eq!(fadt.x_dsdt(), 0x1008);
+        assert_eq!(fadt.x_firmware_ctrl(), 0x1040);
+    }
+
+    #[test]
+    fn test_acpi_table_writer_config() {
+        let mem: GuestMemoryMmap =
+            GuestMemoryMmap::from_ranges(&[(GuestAddress(0), 0x1000)]).unwrap();
+        let config = AcpiConfig::new(*b"VENDOR", 1).creator(*b"VTLS", 2);
+        let mut writer = AcpiTableWriter::new(&mem, GuestAddress(0x100), 0xf00).config(config);
+        let mut mcfg = Mcfg::new(*b"FOOBAR", *b"FOOBARMC", 0, 0xe000_0000);
+        let mcfg_addr = writer.write_table(&mut mcfg).unwrap();
+        let addresses = writer
+            .finish(*b"FOOBAR", *b"FOOBARXS", 0, GuestAddress(0))
+            .unwrap();
+
+        for (addr, len, signature) in [(mcfg_addr, 60, b"MCFG"), (addresses.xsdt, 44, b"XSDT")] {
+            let bytes = read_table(&mem, addr, len);
+            let (header, _) = SdtHeader::parse(&bytes, signature).unwrap();
+            assert_eq!(&header.oem_id, b"VENDOR");
+            assert_eq!(&header.creator_id, b"VTLS");
+            assert_eq!(header.creator_revision.get(), 2);
+        }
+        let rsdp = read_table(&mem, 0, 36);
+        assert_eq!(&rsdp[9..15], b"VENDOR");
+    }
+
+    #[test]
+    fn test_acpi_table_writer_out_of_space() {
+        let mem: GuestMemoryMmap =
+            GuestMemoryMmap::from_ranges(&[(GuestAddress(0), 0x1000)]).unwrap();
+        let mut writer = AcpiTableWriter::new(&mem, GuestAddress(0), 100);
+        let mut mcfg = Mcfg::new(*b"FOOBAR", *b"FOOBARMC", 0, 0xe000_0000);
+        assert_eq!(writer.write_table(&mut mcfg).unwrap(), 0);
+        assert!(matches!(
+            writer.write_table(&mut mcfg),
+            Err(AcpiError::OutOfSpace(60))
+        ));
+    }
+}
@@ -3,11 +3,12 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
-use std::mem::size_of;
+use alloc::vec::Vec;
+use core::mem::size_of;
 
-use vm_memory::{Address, Bytes, GuestAddress, GuestMemory};
 use zerocopy::IntoBytes;
 
+use crate::endian::U32;
 use crate::{AcpiError, Result, Sdt, SdtHeader, checksum};
 
 /// Extended System Description Table (XSDT)
@@ -16,6 +17,7 @@ use crate::{AcpiError, Result, Sdt, SdtHeader, checksum};
 /// More information about this table can be found in the ACPI specification:
 /// https://uefi.org/specs/ACPI/6.5/05_ACPI_Software_Programming_Model.html#extended-system-description-table-xsdt
 #[derive(Clone, Default, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Xsdt {
     header: SdtHeader,
     tables: Vec<u8>,
@@ -35,7 +37,7 @@ impl Xsdt {
         }
 
         // Performance optimization: Use const size
-        const HEADER_SIZE: usize = std::mem::size_of::<SdtHeader>();
+        const HEADER_SIZE: usize = core::mem::size_of::<SdtHeader>();
         let total_size = (HEADER_SIZE + tables_bytes.len()) as u32;
         
         let header = SdtHeader::new(
@@ -57,19 +59,112 @@ impl Xsdt {
 
         xsdt
     }
+
+    /// Add the address of a table, updating the length and the checksum of the XSDT.
+    pub fn add_entry(&mut self, addr: u64) {
+        self.tables.extend_from_slice(&addr.to_le_bytes());
+        self.update_header();
+    }
+
+    /// Remove the address of a table, e.g. of a device which was unplugged, updating the length
+    /// and the checksum of the XSDT.
+    ///
+    /// Returns whether the XSDT pointed to the table.
+    pub fn remove_entry(&mut self, addr: u64) -> bool {
+        let Some(index) = self.entries().iter().position(|entry| *entry == addr) else {
+            return false;
+        };
+        let offset = index * size_of::<u64>();
+        self.tables.drain(offset..offset + size_of::<u64>());
+        self.update_header();
+        true
+    }
+
+    fn update_header(&mut self) {
+        self.header.length = U32::new(self.len().try_into().unwrap());
+        self.header.checksum = 0;
+        self.header.checksum = checksum(&[self.header.as_bytes(), self.tables.as_slice()]);
+    }
+
+    /// Parse an XSDT, checking its signature, length and checksum.
+    pub fn parse(bytes: &[u8]) -> Result<Self> {
+        let (header, tables) = SdtHeader::parse(bytes, b"XSDT")?;
+        if !tables.len().is_multiple_of(size_of::<u64>()) {
+            return Err(AcpiError::InvalidTableLength);
+        }
+
+        Ok(Xsdt {
+            header,
+            tables: tables.to_vec(),
+        })
+    }
+
+    /// Addresses of the tables the XSDT points to
+    pub fn entries(&self) -> Vec<u64> {
+        self.tables
+            .chunks_exact(size_of::<u64>())
+            .map(|entry| u64::from_le_bytes(entry.try_into().unwrap()))
+            .collect()
+    }
 }
 
 impl Sdt for Xsdt {
     fn len(&self) -> usize {
-        std::mem::size_of::<SdtHeader>() + self.tables.len()
+        core::mem::size_of::<SdtHeader>() + self.tables.len()
+    }
+
+    fn header(&self) -> Option<&SdtHeader> {
+        Some(&self.header)
+    }
+
+    fn to_bytes(&mut self) -> Result<Vec<u8>> {
+        Ok([self.header.as_bytes(), self.tables.as_slice()].concat())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verify_checksum;
+
+    #[test]
+    fn test_xsdt_parse() {
+        let mut xsdt = Xsdt::new(*b"FOOBAR", *b"FOOBARXS", 0, vec![0x1000, 0x2000]);
+        let bytes = xsdt.to_bytes().unwrap();
+        let parsed = Xsdt::parse(&bytes).unwrap();
+        assert_eq!(parsed.entries(), [0x1000, 0x2000]);
+        assert_eq!(&parsed.header.oem_table_id, b"FOOBARXS");
+
+        assert!(matches!(
+            Xsdt::parse(&bytes[..bytes.len() - 1]),
+            Err(AcpiError::InvalidTableLength)
+        ));
+        let mut corrupted = bytes.clone();
+        corrupted[40] ^= 0xff;
+        assert!(matches!(
+            Xsdt::parse(&corrupted),
+            Err(AcpiError::InvalidChecksum)
+        ));
+    }
+
+    #[test]
+    fn test_xsdt_add_entry() {
+        let mut xsdt = Xsdt::new(*b"FOOBAR", *b"FOOBARXS", 0, vec![0x1000]);
+        xsdt.add_entry(0x2000);
+        let bytes = xsdt.to_bytes().unwrap();
+        assert_eq!(bytes.len(), 36 + 2 * 8);
+        assert!(verify_checksum(&bytes));
+        assert_eq!(Xsdt::parse(&bytes).unwrap().entries(), [0x1000, 0x2000]);
     }
 
-    fn write_to_guest<M: GuestMemory>(&mut self, mem: &M, address: GuestAddress) -> Result<()> {
-        mem.write_slice(self.header.as_bytes(), address)?;
-        let address = address
-            .checked_add(size_of::<SdtHeader>() as u64)
-            .ok_or(AcpiError::InvalidGuestAddress)?;
-        mem.write_slice(self.tables.as_slice(), address)?;
-        Ok(())
+    #[test]
+    fn test_xsdt_remove_entry() {
+        let mut xsdt = Xsdt::new(*b"FOOBAR", *b"FOOBARXS", 0, vec![0x1000, 0x2000, 0x3000]);
+        assert!(xsdt.remove_entry(0x2000));
+        assert!(!xsdt.remove_entry(0x4000));
+        let bytes = xsdt.to_bytes().unwrap();
+        assert_eq!(bytes.len(), 36 + 2 * 8);
+        assert!(verify_checksum(&bytes));
+        assert_eq!(Xsdt::parse(&bytes).unwrap().entries(), [0x1000, 0x3000]);
     }
 }
@@ -15,13 +15,17 @@ use std::time::Duration;
 
 pub use micro_http::{Body, HttpServer, Request, Response, ServerError, StatusCode, Version};
 use parsed_request::{ParsedRequest, RequestAction};
+use request::guest::GuestAgentRequest;
 use serde_json::json;
 use utils::time::{ClockType, get_time_us};
+use vmm::guest_agent::{GuestAgentClient, GuestAgentError};
 use vmm::logger::{
-    METRICS, ProcessTimeReporter, debug, error, info, update_metric_with_elapsed_time, warn,
+    IncMetric, METRICS, ProcessTimeReporter, debug, error, info, update_metric_with_elapsed_time,
+    warn,
 };
-use vmm::rpc_interface::{ApiRequest, ApiResponse, VmmAction};
+use vmm::rpc_interface::{ApiRequest, ApiResponse, VmmAction, VmmActionError, VmmData};
 use vmm::seccomp::BpfProgramRef;
+use vmm::vmm_config::instance_info::VmState;
 use vmm::vmm_config::snapshot::SnapshotType;
 use vmm_sys_util::eventfd::EventFd;
 
@@ -130,6 +134,9 @@ impl ApiServer {
                     RequestAction::Sync(vmm_action) => {
                         self.serve_vmm_action_request(vmm_action, request_processing_start_us)
                     }
+                    RequestAction::GuestAgent(guest_agent_request) => {
+                        self.serve_guest_agent_request(guest_agent_request)
+                    }
                 };
                 if let Some(message) = parsing_info.take_deprecation_message() {
                     warn!("{}", message);
@@ -168,32 +175,94 @@ impl ApiServer {
             _ => None,
         };
 
+        let Some(vmm_outcome) = self.dispatch_vmm_action(vmm_action) else {
+            return Response::new(Version::Http11, StatusCode::InternalServerError);
+        };
+        let response = ParsedRequest::convert_to_response(&vmm_outcome);
+
+        if vmm_outcome.is_ok()
+            && let Some((metric, action)) = metric_with_action
+        {
+            let elapsed_time_us =
+                update_metric_with_elapsed_time(metric, request_processing_start_us);
+            info!("'{}' API request took {} us.", action, elapsed_time_us);
+        }
+        response
+    }
+
+    /// Sends an action to the VMM, and waits for its outcome.
+    ///
+    /// Returns `None` if the VMM didn't answer in time.
+    fn dispatch_vmm_action(
+        &mut self,
+        vmm_action: Box<VmmAction>,
+    ) -> Option<Result<VmmData, VmmActionError>> {
         self.api_request_sender
             .send(vmm_action)
             .expect("Failed to send VMM message");
         self.to_vmm_fd.write(1).expect("Cannot update send VMM fd");
         
         // Performance optimization: Add timeout to prevent deadlocks
-        let vmm_outcome = match self.vmm_response_receiver.recv_timeout(Duration::from_secs(30)) {
-            Ok(outcome) => *outcome,
+        match self
+            .vmm_response_receiver
+            .recv_timeout(Duration::from_secs(30))
+        {
+            Ok(outcome) => Some(*outcome),
             Err(RecvTimeoutError::Timeout) => {
                 error!("VMM request timeout after 30s");
-                return Response::new(Version::Http11, StatusCode::InternalServerError);
+                None
             }
             Err(RecvTimeoutError::Disconnected) => {
                 panic!("VMM channel disconnected");
             }
-        };
-        let response = ParsedRequest::convert_to_response(&vmm_outcome);
+        }
+    }
 
-        if vmm_outcome.is_ok()
-            && let Some((metric, action)) = metric_with_action
-        {
-            let elapsed_time_us =
-                update_metric_with_elapsed_time(metric, request_processing_start_us);
-            info!("'{}' API request took {} us.", action, elapsed_time_us);
+    /// Serves a request through the guest agent.
+    ///
+    /// The guest agent is reached from the API thread, since the VMM thread must keep running
+    /// the vsock device while the guest answers.
+    fn serve_guest_agent_request(&mut self, request: GuestAgentRequest) -> Response {
+        let outcome = self.guest_agent_uds_path().and_then(|uds_path| {
+            let mut client = GuestAgentClient::connect(&uds_path)?;
+            match &request {
+                GuestAgentRequest::Exec(config) => client
+                    .exec(config)
+                    .map(|output| ParsedRequest::success_response_with_data(&output)),
+                GuestAgentRequest::Info => client
+                    .info()
+                    .map(|info| ParsedRequest::success_response_with_data(&info)),
+            }
+        });
+
+        outcome.unwrap_or_else(|err| {
+            if let GuestAgentRequest::Exec(_) = request {
+                METRICS.put_api_requests.guest_exec_fails.inc();
+            }
+            let status = match err {
+                GuestAgentError::NoVsockDevice | GuestAgentError::NotRunning => {
+                    StatusCode::BadRequest
+                }
+                _ => StatusCode::InternalServerError,
+            };
+            error!("Guest agent request failed: {}", err);
+            Self::json_response(status, Self::json_fault_message(err.to_string()))
+        })
+    }
+
+    /// Looks up the Unix socket backing the vsock device of the running microVM.
+    fn guest_agent_uds_path(&mut self) -> Result<String, GuestAgentError> {
+        match self.dispatch_vmm_action(Box::new(VmmAction::GetVmInstanceInfo)) {
+            Some(Ok(VmmData::InstanceInformation(info))) if info.state == VmState::Running => (),
+            _ => return Err(GuestAgentError::NotRunning),
+        }
+        match self.dispatch_vmm_action(Box::new(VmmAction::GetFullVmConfig)) {
+            Some(Ok(VmmData::FullVmConfig(config))) => config
+                .vsock()
+                .map(|vsock| vsock.uds_path.clone())
+                .ok_or(GuestAgentError::NoVsockDevice),
+            _ => Err(GuestAgentError::NoVsockDevice),
         }
-        response
     }
 
     /// An HTTP response which also includes a body.
@@ -309,6 +378,37 @@ mod tests {
         assert_eq!(METRICS.latencies_us.full_create_snapshot.fetch(), 0);
     }
 
+    #[test]
+    fn test_serve_guest_agent_request() {
+        let to_vmm_fd = EventFd::new(libc::EFD_NONBLOCK).unwrap();
+        let (api_request_sender, _from_api) = channel();
+        let (to_api, vmm_response_receiver) = channel();
+
+        let mut api_server = ApiServer::new(api_request_sender, vmm_response_receiver, to_vmm_fd);
+
+        // The guest agent can't be reached before the microVM starts.
+        to_api
+            .send(Box::new(Ok(VmmData::InstanceInformation(
+                InstanceInfo::default(),
+            ))))
+            .unwrap();
+        let response = api_server.serve_guest_agent_request(GuestAgentRequest::Info);
+        assert_eq!(response.status(), StatusCode::BadRequest);
+
+        // Nor without a vsock device.
+        to_api
+            .send(Box::new(Ok(VmmData::InstanceInformation(InstanceInfo {
+                state: VmState::Running,
+                ..Default::default()
+            }))))
+            .unwrap();
+        to_api
+            .send(Box::new(Ok(VmmData::FullVmConfig(Default::default()))))
+            .unwrap();
+        let response = api_server.serve_guest_agent_request(GuestAgentRequest::Info);
+        assert_eq!(response.status(), StatusCode::BadRequest);
+    }
+
     #[test]
     fn test_handle_request() {
         let to_vmm_fd = EventFd::new(libc::EFD_NONBLOCK).unwrap();
@@ -16,24 +16,47 @@ thread_local! {
 }
 
 use super::ApiServer;
+use super::request::acpi::{parse_get_acpi_tables, parse_put_acpi_tables};
 use super::request::actions::parse_put_actions;
+use super::request::apei::parse_put_apei;
 use super::request::balloon::{parse_get_balloon, parse_patch_balloon, parse_put_balloon};
 use super::request::boot_source::parse_put_boot_source;
+use super::request::cppc::parse_put_cppc;
 use super::request::cpu_configuration::parse_put_cpu_config;
 use super::request::drive::{parse_patch_drive, parse_put_drive};
 use super::request::entropy::parse_put_entropy;
+use super::request::fw_cfg::parse_put_fw_cfg;
+use super::request::guest::{GuestAgentRequest, parse_get_guest, parse_put_guest};
 use super::request::instance_info::parse_get_instance_info;
 use super::request::logger::parse_put_logger;
 use super::request::machine_configuration::{
-    parse_get_machine_config, parse_patch_machine_config, parse_put_machine_config,
+    parse_get_cpu_features, parse_get_machine_config, parse_patch_machine_config,
+    parse_put_machine_config,
 };
+use super::request::memory_map::parse_put_memory_map;
 use super::request::metrics::parse_put_metrics;
 use super::request::mmds::{parse_get_mmds, parse_patch_mmds, parse_put_mmds};
 use super::request::net::{parse_patch_net, parse_put_net};
+use super::request::nvme::parse_put_nvme;
 use super::request::pmem::parse_put_pmem;
+use super::request::pmu::parse_put_pmu;
+use super::request::power_supply::{parse_patch_power_supply, parse_put_power_supply};
+use super::request::processor_aggregator::{
+    parse_get_processor_aggregator, parse_patch_processor_aggregator,
+    parse_put_processor_aggregator,
+};
+use super::request::reboot::parse_put_reboot;
+use super::request::s2idle::parse_put_s2idle;
+use super::request::scheduled_actions::parse_put_scheduled_actions;
+use super::request::sgx::parse_put_sgx;
+use super::request::smbios::parse_put_smbios;
 use super::request::snapshot::{parse_patch_vm_state, parse_put_snapshot};
+use super::request::thermal_zone::{parse_patch_thermal_zone, parse_put_thermal_zone};
+use super::request::usb::parse_put_usb;
 use super::request::version::parse_get_version;
+use super::request::vfio::{parse_put_vfio, parse_put_vfio_vf};
 use super::request::vsock::parse_put_vsock;
+use super::request::watchdog::{parse_get_watchdog, parse_put_watchdog};
 use crate::api_server::request::hotplug::memory::{
     parse_get_memory_hotplug, parse_patch_memory_hotplug, parse_put_memory_hotplug,
 };
@@ -42,6 +65,7 @@ use crate::api_server::request::serial::parse_put_serial;
 #[derive(Debug)]
 pub(crate) enum RequestAction {
     Sync(Box<VmmAction>),
+    GuestAgent(GuestAgentRequest),
 }
 
 #[derive(Debug, Default, PartialEq)]
@@ -89,33 +113,66 @@ impl TryFrom<&Request> for ParsedRequest {
         match (request.method(), path, request.body.as_ref()) {
             (Method::Get, "", None) => parse_get_instance_info(),
             (Method::Get, "balloon", None) => parse_get_balloon(path_tokens),
+            (Method::Get, "debug", None) if path_tokens.next() == Some("acpi") => {
+                parse_get_acpi_tables()
+            }
+            (Method::Get, "guest", None) => parse_get_guest(path_tokens),
             (Method::Get, "version", None) => parse_get_version(),
             (Method::Get, "vm", None) if path_tokens.next() == Some("config") => {
                 Ok(ParsedRequest::new_sync(VmmAction::GetFullVmConfig))
             }
+            (Method::Get, "machine-config", None) if path_tokens.next() == Some("cpu-features") => {
+                parse_get_cpu_features()
+            }
             (Method::Get, "machine-config", None) => parse_get_machine_config(),
             (Method::Get, "mmds", None) => parse_get_mmds(),
             (Method::Get, "hotplug", None) if path_tokens.next() == Some("memory") => {
                 parse_get_memory_hotplug()
             }
+            (Method::Get, "processor-aggregator", None) => parse_get_processor_aggregator(),
+            (Method::Get, "watchdog", None) => parse_get_watchdog(),
             (Method::Get, _, Some(_)) => method_to_error(Method::Get),
+            (Method::Put, "acpi", Some(body)) if path_tokens.next() == Some("tables") => {
+                parse_put_acpi_tables(body)
+            }
             (Method::Put, "actions", Some(body)) => parse_put_actions(body),
+            (Method::Put, "apei", Some(body)) => parse_put_apei(body),
             (Method::Put, "balloon", Some(body)) => parse_put_balloon(body),
             (Method::Put, "boot-source", Some(body)) => parse_put_boot_source(body),
             (Method::Put, "cpu-config", Some(body)) => parse_put_cpu_config(body),
+            (Method::Put, "cppc", Some(body)) => parse_put_cppc(body),
             (Method::Put, "drives", Some(body)) => parse_put_drive(body, path_tokens.next()),
+            (Method::Put, "fw-cfg", Some(body)) => parse_put_fw_cfg(body),
+            (Method::Put, "guest", Some(body)) => parse_put_guest(body, path_tokens),
             (Method::Put, "pmem", Some(body)) => parse_put_pmem(body, path_tokens.next()),
             (Method::Put, "logger", Some(body)) => parse_put_logger(body),
             (Method::Put, "serial", Some(body)) => parse_put_serial(body),
             (Method::Put, "machine-config", Some(body)) => parse_put_machine_config(body),
+            (Method::Put, "memory-map", Some(body)) => parse_put_memory_map(body),
             (Method::Put, "metrics", Some(body)) => parse_put_metrics(body),
             (Method::Put, "mmds", Some(body)) => parse_put_mmds(body, path_tokens.next()),
             (Method::Put, "network-interfaces", Some(body)) => {
                 parse_put_net(body, path_tokens.next())
             }
+            (Method::Put, "nvme", Some(body)) => parse_put_nvme(body, path_tokens.next()),
+            (Method::Put, "pmu", Some(body)) => parse_put_pmu(body),
+            (Method::Put, "power-supply", Some(body)) => parse_put_power_supply(body),
+            (Method::Put, "processor-aggregator", Some(body)) => {
+                parse_put_processor_aggregator(body)
+            }
+            (Method::Put, "reboot", Some(body)) => parse_put_reboot(body),
+            (Method::Put, "s2idle", Some(body)) => parse_put_s2idle(body),
+            (Method::Put, "scheduled-actions", Some(body)) => parse_put_scheduled_actions(body),
+            (Method::Put, "sgx", Some(body)) => parse_put_sgx(body),
+            (Method::Put, "smbios", Some(body)) => parse_put_smbios(body),
             (Method::Put, "snapshot", Some(body)) => parse_put_snapshot(body, path_tokens.next()),
+            (Method::Put, "thermal-zone", Some(body)) => parse_put_thermal_zone(body),
+            (Method::Put, "usb", Some(body)) => parse_put_usb(body, path_tokens.next()),
+            (Method::Put, "vfio", Some(body)) => parse_put_vfio(body, path_tokens.next()),
+            (Method::Put, "vfio-vf", Some(body)) => parse_put_vfio_vf(body, path_tokens.next()),
             (Method::Put, "vsock", Some(body)) => parse_put_vsock(body),
             (Method::Put, "entropy", Some(body)) => parse_put_entropy(body),
+            (Method::Put, "watchdog", Some(body)) => parse_put_watchdog(body),
             (Method::Put, "hotplug", Some(body)) if path_tokens.next() == Some("memory") => {
                 parse_put_memory_hotplug(body)
             }
@@ -127,6 +184,11 @@ impl TryFrom<&Request> for ParsedRequest {
             (Method::Patch, "network-interfaces", Some(body)) => {
                 parse_patch_net(body, path_tokens.next())
             }
+            (Method::Patch, "power-supply", Some(body)) => parse_patch_power_supply(body),
+            (Method::Patch, "processor-aggregator", Some(body)) => {
+                parse_patch_processor_aggregator(body)
+            }
+            (Method::Patch, "thermal-zone", Some(body)) => parse_patch_thermal_zone(body),
             (Method::Patch, "vm", Some(body)) => parse_patch_vm_state(body),
             (Method::Patch, "hotplug", Some(body)) if path_tokens.next() == Some("memory") => {
                 parse_patch_memory_hotplug(body)
@@ -211,10 +273,17 @@ impl ParsedRequest {
                     Self::success_response_with_data(balloon_config)
                 }
                 VmmData::BalloonStats(stats) => Self::success_response_with_data(stats),
+                VmmData::CpuFeatures(features) => Self::success_response_with_data(features),
                 VmmData::VirtioMemStatus(data) => Self::success_response_with_data(data),
                 VmmData::HintingStatus(hinting_status) => {
                     Self::success_response_with_data(hinting_status)
                 }
+                VmmData::WatchdogStatus(status) => Self::success_response_with_data(status),
+                VmmData::ProcessorAggregatorStatus(status) => {
+                    Self::success_response_with_data(status)
+                }
+                #[cfg(target_arch = "x86_64")]
+                VmmData::AcpiTables(tables) => Self::success_response_with_data(tables),
                 VmmData::InstanceInformation(info) => Self::success_response_with_data(info),
                 VmmData::VmmVersion(version) => Self::success_response_with_data(
                     &serde_json::json!({ "clawdbox_version": version.as_str() }),
@@ -384,6 +453,10 @@ pub mod tests {
                 (RequestAction::Sync(sync_req), RequestAction::Sync(other_sync_req)) => {
                     sync_req == other_sync_req
                 }
+                (RequestAction::GuestAgent(req), RequestAction::GuestAgent(other_req)) => {
+                    req == other_req
+                }
+                _ => false,
             }
         }
     }
@@ -391,6 +464,7 @@ pub mod tests {
     pub(crate) fn vmm_action_from_request(req: ParsedRequest) -> VmmAction {
         match req.action {
             RequestAction::Sync(vmm_action) => *vmm_action,
+            RequestAction::GuestAgent(_) => panic!("Not a VMM action."),
         }
     }
 
@@ -403,6 +477,7 @@ pub mod tests {
                 assert_eq!(req_msg, msg);
                 *vmm_action
             }
+            RequestAction::GuestAgent(_) => panic!("Not a VMM action."),
         }
     }
 
@@ -611,12 +686,25 @@ pub mod tests {
                 VmmData::BalloonStats(stats) => {
                     http_response(&serde_json::to_string(stats).unwrap(), 200)
                 }
+                VmmData::CpuFeatures(features) => {
+                    http_response(&serde_json::to_string(features).unwrap(), 200)
+                }
                 VmmData::VirtioMemStatus(data) => {
                     http_response(&serde_json::to_string(data).unwrap(), 200)
                 }
                 VmmData::HintingStatus(status) => {
                     http_response(&serde_json::to_string(status).unwrap(), 200)
                 }
+                VmmData::WatchdogStatus(status) => {
+                    http_response(&serde_json::to_string(status).unwrap(), 200)
+                }
+                VmmData::ProcessorAggregatorStatus(status) => {
+                    http_response(&serde_json::to_string(status).unwrap(), 200)
+                }
+                #[cfg(target_arch = "x86_64")]
+                VmmData::AcpiTables(tables) => {
+                    http_response(&serde_json::to_string(tables).unwrap(), 200)
+                }
                 VmmData::Empty => http_response("", 204),
                 VmmData::FullVmConfig(cfg) => {
                     http_response(&serde_json::to_string(cfg).unwrap(), 200)
@@ -727,6 +815,21 @@ pub mod tests {
         ParsedRequest::try_from(&req).unwrap();
     }
 
+    #[test]
+    fn test_try_from_get_cpu_features() {
+        let (mut sender, receiver) = UnixStream::pair().unwrap();
+        let mut connection = HttpConnection::new(receiver);
+        sender
+            .write_all(http_request("GET", "/machine-config/cpu-features", None).as_bytes())
+            .unwrap();
+        connection.try_read().unwrap();
+        let req = connection.pop_parsed_request().unwrap();
+        assert_eq!(
+            vmm_action_from_request(ParsedRequest::try_from(&req).unwrap()),
+            VmmAction::GetCpuFeatures
+        );
+    }
+
     #[test]
     fn test_try_from_get_mmds() {
         let (mut sender, receiver) = UnixStream::pair().unwrap();
@@ -793,6 +896,19 @@ pub mod tests {
         ParsedRequest::try_from(&req).unwrap();
     }
 
+    #[test]
+    fn test_try_from_put_acpi_tables() {
+        let (mut sender, receiver) = UnixStream::pair().unwrap();
+        let mut connection = HttpConnection::new(receiver);
+        let body = "{ \"tables\": [ { \"path_on_host\": \"ssdt.aml\" } ] }";
+        sender
+            .write_all(http_request("PUT", "/acpi/tables", Some(body)).as_bytes())
+            .unwrap();
+        connection.try_read().unwrap();
+        let req = connection.pop_parsed_request().unwrap();
+        ParsedRequest::try_from(&req).unwrap();
+    }
+
     #[test]
     fn test_try_from_put_boot() {
         let (mut sender, receiver) = UnixStream::pair().unwrap();
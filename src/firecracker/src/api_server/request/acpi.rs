@@ -0,0 +1,72 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use micro_http::Body;
+use vmm::logger::{IncMetric, METRICS};
+use vmm::rpc_interface::VmmAction;
+use vmm::vmm_config::acpi::AcpiTablesConfig;
+
+use crate::api_server::parsed_request::{ParsedRequest, RequestError};
+
+pub(crate) fn parse_put_acpi_tables(body: &Body) -> Result<ParsedRequest, RequestError> {
+    METRICS.put_api_requests.acpi_tables_count.inc();
+    let config = serde_json::from_slice::<AcpiTablesConfig>(body.raw()).inspect_err(|_| {
+        METRICS.put_api_requests.acpi_tables_fails.inc();
+    })?;
+    Ok(ParsedRequest::new_sync(VmmAction::SetAcpiTables(config)))
+}
+
+pub(crate) fn parse_get_acpi_tables() -> Result<ParsedRequest, RequestError> {
+    METRICS.get_api_requests.acpi_tables_count.inc();
+    #[cfg(target_arch = "aarch64")]
+    return Err(RequestError::Generic(
+        micro_http::StatusCode::BadRequest,
+        "ACPI tables are only supported on x86_64.".to_string(),
+    ));
+
+    #[cfg(target_arch = "x86_64")]
+    Ok(ParsedRequest::new_sync(VmmAction::GetAcpiTables))
+}
+
+#[cfg(test)]
+mod tests {
+    use vmm::vmm_config::acpi::AcpiTableConfig;
+
+    use super::*;
+    use crate::api_server::parsed_request::tests::vmm_action_from_request;
+
+    #[test]
+    fn test_parse_put_acpi_tables_request() {
+        parse_put_acpi_tables(&Body::new("invalid_payload")).unwrap_err();
+
+        // PUT with unknown fields.
+        let body = r#"{
+            "tables": [ { "path_on_host": "ssdt.aml", "signature": "SSDT" } ]
+        }"#;
+        parse_put_acpi_tables(&Body::new(body)).unwrap_err();
+
+        let body = r#"{
+            "tables": [ { "path_on_host": "ssdt.aml" } ]
+        }"#;
+        let expected_config = AcpiTablesConfig {
+            tables: vec![AcpiTableConfig {
+                path_on_host: "ssdt.aml".to_string(),
+            }],
+        };
+        assert_eq!(
+            vmm_action_from_request(parse_put_acpi_tables(&Body::new(body)).unwrap()),
+            VmmAction::SetAcpiTables(expected_config)
+        );
+    }
+
+    #[test]
+    fn test_parse_get_acpi_tables_request() {
+        #[cfg(target_arch = "x86_64")]
+        assert_eq!(
+            vmm_action_from_request(parse_get_acpi_tables().unwrap()),
+            VmmAction::GetAcpiTables
+        );
+        #[cfg(target_arch = "aarch64")]
+        parse_get_acpi_tables().unwrap_err();
+    }
+}
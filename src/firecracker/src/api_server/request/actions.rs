@@ -4,18 +4,20 @@
 use serde::{Deserialize, Serialize};
 use vmm::logger::{IncMetric, METRICS};
 use vmm::rpc_interface::VmmAction;
+use vmm::vmm_config::core_dump::CoreDumpParams;
+use vmm::vmm_config::nmi::InjectNmiParams;
 
 use super::super::parsed_request::{ParsedRequest, RequestError};
-use super::Body;
-#[cfg(target_arch = "aarch64")]
-use super::StatusCode;
+use super::{Body, StatusCode};
 
 // The names of the members from this enum must precisely correspond (as a string) to the possible
 // values of "action_type" from the json request body. This is useful to get a strongly typed
 // struct from the Serde deserialization process.
 #[derive(Debug, Deserialize, Serialize)]
 enum ActionType {
+    DumpGuestCore,
     FlushMetrics,
+    InjectNmi,
     InstanceStart,
     SendCtrlAltDel,
 }
@@ -26,6 +28,12 @@ enum ActionType {
 #[serde(deny_unknown_fields)]
 struct ActionBody {
     action_type: ActionType,
+    // The parameters of the `DumpGuestCore` action.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    core_dump: Option<CoreDumpParams>,
+    // The parameters of the `InjectNmi` action.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    nmi: Option<InjectNmiParams>,
 }
 
 pub(crate) fn parse_put_actions(body: &Body) -> Result<ParsedRequest, RequestError> {
@@ -34,8 +42,48 @@ pub(crate) fn parse_put_actions(body: &Body) -> Result<ParsedRequest, RequestErr
         METRICS.put_api_requests.actions_fails.inc();
     })?;
 
+    if action_body.core_dump.is_some()
+        && !matches!(action_body.action_type, ActionType::DumpGuestCore)
+    {
+        METRICS.put_api_requests.actions_fails.inc();
+        return Err(RequestError::Generic(
+            StatusCode::BadRequest,
+            "The core_dump parameters are only valid for DumpGuestCore.".to_string(),
+        ));
+    }
+    if action_body.nmi.is_some() && !matches!(action_body.action_type, ActionType::InjectNmi) {
+        METRICS.put_api_requests.actions_fails.inc();
+        return Err(RequestError::Generic(
+            StatusCode::BadRequest,
+            "The nmi parameters are only valid for InjectNmi.".to_string(),
+        ));
+    }
+
     match action_body.action_type {
+        ActionType::DumpGuestCore => match action_body.core_dump {
+            Some(params) => Ok(ParsedRequest::new_sync(VmmAction::DumpGuestCore(params))),
+            None => {
+                METRICS.put_api_requests.actions_fails.inc();
+                Err(RequestError::Generic(
+                    StatusCode::BadRequest,
+                    "DumpGuestCore requires the core_dump parameters.".to_string(),
+                ))
+            }
+        },
         ActionType::FlushMetrics => Ok(ParsedRequest::new_sync(VmmAction::FlushMetrics)),
+        ActionType::InjectNmi => {
+            // There is no way to inject an NMI or an SDEI event through KVM on aarch64.
+            #[cfg(target_arch = "aarch64")]
+            return Err(RequestError::Generic(
+                StatusCode::BadRequest,
+                "InjectNmi is not supported on aarch64.".to_string(),
+            ));
+
+            #[cfg(target_arch = "x86_64")]
+            Ok(ParsedRequest::new_sync(VmmAction::InjectNmi(
+                action_body.nmi.unwrap_or_default(),
+            )))
+        }
         ActionType::InstanceStart => Ok(ParsedRequest::new_sync(VmmAction::StartMicroVm)),
         ActionType::SendCtrlAltDel => {
             // SendCtrlAltDel not supported on aarch64.
@@ -99,5 +147,75 @@ mod tests {
             let result = parse_put_actions(&Body::new(json));
             assert_eq!(result.unwrap(), req);
         }
+
+        {
+            let json = r#"{
+                "action_type": "DumpGuestCore",
+                "core_dump": {
+                    "dump_path": "vmcore",
+                    "exclude_balloon_pages": true
+                }
+            }"#;
+
+            let req: ParsedRequest =
+                ParsedRequest::new_sync(VmmAction::DumpGuestCore(CoreDumpParams {
+                    dump_path: "vmcore".into(),
+                    exclude_balloon_pages: true,
+                }));
+            let result = parse_put_actions(&Body::new(json));
+            assert_eq!(result.unwrap(), req);
+
+            // The parameters are mandatory.
+            let json = r#"{
+                "action_type": "DumpGuestCore"
+            }"#;
+            parse_put_actions(&Body::new(json)).unwrap_err();
+
+            // The parameters are only valid for DumpGuestCore.
+            let json = r#"{
+                "action_type": "FlushMetrics",
+                "core_dump": {
+                    "dump_path": "vmcore"
+                }
+            }"#;
+            parse_put_actions(&Body::new(json)).unwrap_err();
+        }
+
+        #[cfg(target_arch = "x86_64")]
+        {
+            let json = r#"{
+                "action_type": "InjectNmi"
+            }"#;
+            let req: ParsedRequest =
+                ParsedRequest::new_sync(VmmAction::InjectNmi(InjectNmiParams { vcpu: None }));
+            let result = parse_put_actions(&Body::new(json));
+            assert_eq!(result.unwrap(), req);
+
+            let json = r#"{
+                "action_type": "InjectNmi",
+                "nmi": {
+                    "vcpu": 1
+                }
+            }"#;
+            let req: ParsedRequest =
+                ParsedRequest::new_sync(VmmAction::InjectNmi(InjectNmiParams { vcpu: Some(1) }));
+            let result = parse_put_actions(&Body::new(json));
+            assert_eq!(result.unwrap(), req);
+
+            // The parameters are only valid for InjectNmi.
+            let json = r#"{
+                "action_type": "FlushMetrics",
+                "nmi": {}
+            }"#;
+            parse_put_actions(&Body::new(json)).unwrap_err();
+        }
+
+        #[cfg(target_arch = "aarch64")]
+        {
+            let json = r#"{
+                "action_type": "InjectNmi"
+            }"#;
+            parse_put_actions(&Body::new(json)).unwrap_err();
+        }
     }
 }
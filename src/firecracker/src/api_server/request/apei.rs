@@ -0,0 +1,49 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use micro_http::Body;
+use vmm::logger::{IncMetric, METRICS};
+use vmm::rpc_interface::VmmAction;
+use vmm::vmm_config::apei::ApeiConfig;
+
+use crate::api_server::parsed_request::{ParsedRequest, RequestError};
+
+pub(crate) fn parse_put_apei(body: &Body) -> Result<ParsedRequest, RequestError> {
+    METRICS.put_api_requests.apei_count.inc();
+    let config = serde_json::from_slice::<ApeiConfig>(body.raw()).inspect_err(|_| {
+        METRICS.put_api_requests.apei_fails.inc();
+    })?;
+    Ok(ParsedRequest::new_sync(VmmAction::SetApei(config)))
+}
+
+#[cfg(test)]
+mod tests {
+    use vmm::vmm_config::apei::GhesNotification;
+
+    use super::*;
+    use crate::api_server::parsed_request::tests::vmm_action_from_request;
+
+    #[test]
+    fn test_parse_put_apei_request() {
+        parse_put_apei(&Body::new("invalid_payload")).unwrap_err();
+
+        // PUT with an unknown notification.
+        let body = r#"{ "notification": "sci" }"#;
+        parse_put_apei(&Body::new(body)).unwrap_err();
+
+        // PUT with the default notification.
+        assert_eq!(
+            vmm_action_from_request(parse_put_apei(&Body::new("{}")).unwrap()),
+            VmmAction::SetApei(ApeiConfig::default())
+        );
+
+        let body = r#"{ "notification": "nmi" }"#;
+        let expected_config = ApeiConfig {
+            notification: GhesNotification::Nmi,
+        };
+        assert_eq!(
+            vmm_action_from_request(parse_put_apei(&Body::new(body)).unwrap()),
+            VmmAction::SetApei(expected_config)
+        );
+    }
+}
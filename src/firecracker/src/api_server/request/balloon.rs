@@ -275,5 +275,23 @@ mod tests {
             "free_page_reporting": true
         }"#;
         parse_put_balloon(&Body::new(body)).unwrap();
+
+        // PUT with valid host reclaim
+        let body = r#"{
+            "amount_mib": 1000,
+            "deflate_on_oom": true,
+            "free_page_reporting": true,
+            "free_page_advice": "free",
+            "ksm": true
+        }"#;
+        parse_put_balloon(&Body::new(body)).unwrap();
+
+        // PUT with invalid free page advice
+        let body = r#"{
+            "amount_mib": 1000,
+            "deflate_on_oom": true,
+            "free_page_advice": "cold"
+        }"#;
+        parse_put_balloon(&Body::new(body)).unwrap_err();
     }
 }
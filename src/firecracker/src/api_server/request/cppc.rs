@@ -0,0 +1,34 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use micro_http::Body;
+use vmm::logger::{IncMetric, METRICS};
+use vmm::rpc_interface::VmmAction;
+use vmm::vmm_config::cppc::CppcConfig;
+
+use crate::api_server::parsed_request::{ParsedRequest, RequestError};
+
+pub(crate) fn parse_put_cppc(body: &Body) -> Result<ParsedRequest, RequestError> {
+    METRICS.put_api_requests.cppc_count.inc();
+    let config = serde_json::from_slice::<CppcConfig>(body.raw()).inspect_err(|_| {
+        METRICS.put_api_requests.cppc_fails.inc();
+    })?;
+    Ok(ParsedRequest::new_sync(VmmAction::SetCppc(config)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api_server::parsed_request::tests::vmm_action_from_request;
+
+    #[test]
+    fn test_parse_put_cppc_request() {
+        parse_put_cppc(&Body::new("invalid_payload")).unwrap_err();
+        parse_put_cppc(&Body::new(r#"{ "highest_perf": 1024 }"#)).unwrap_err();
+
+        assert_eq!(
+            vmm_action_from_request(parse_put_cppc(&Body::new("{}")).unwrap()),
+            VmmAction::SetCppc(CppcConfig::default())
+        );
+    }
+}
@@ -0,0 +1,50 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use micro_http::Body;
+use vmm::logger::{IncMetric, METRICS};
+use vmm::rpc_interface::VmmAction;
+use vmm::vmm_config::fw_cfg::FwCfgConfig;
+
+use crate::api_server::parsed_request::{ParsedRequest, RequestError};
+
+pub(crate) fn parse_put_fw_cfg(body: &Body) -> Result<ParsedRequest, RequestError> {
+    METRICS.put_api_requests.fw_cfg_count.inc();
+    let config = serde_json::from_slice::<FwCfgConfig>(body.raw()).inspect_err(|_| {
+        METRICS.put_api_requests.fw_cfg_fails.inc();
+    })?;
+    Ok(ParsedRequest::new_sync(VmmAction::SetFwCfg(config)))
+}
+
+#[cfg(test)]
+mod tests {
+    use vmm::vmm_config::fw_cfg::FwCfgFileConfig;
+
+    use super::*;
+    use crate::api_server::parsed_request::tests::vmm_action_from_request;
+
+    #[test]
+    fn test_parse_put_fw_cfg_request() {
+        parse_put_fw_cfg(&Body::new("invalid_payload")).unwrap_err();
+
+        // PUT with unknown fields.
+        let body = r#"{
+            "files": [ { "name": "bootorder", "path_on_host": "bootorder", "writable": true } ]
+        }"#;
+        parse_put_fw_cfg(&Body::new(body)).unwrap_err();
+
+        let body = r#"{
+            "files": [ { "name": "opt/com.coreos/config", "path_on_host": "config.ign" } ]
+        }"#;
+        let expected_config = FwCfgConfig {
+            files: vec![FwCfgFileConfig {
+                name: "opt/com.coreos/config".to_string(),
+                path_on_host: "config.ign".to_string(),
+            }],
+        };
+        assert_eq!(
+            vmm_action_from_request(parse_put_fw_cfg(&Body::new(body)).unwrap()),
+            VmmAction::SetFwCfg(expected_config)
+        );
+    }
+}
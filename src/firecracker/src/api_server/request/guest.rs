@@ -0,0 +1,130 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use vmm::guest_agent::GuestExecConfig;
+use vmm::logger::{IncMetric, METRICS};
+
+use super::super::parsed_request::{ParsedRequest, RequestAction, RequestError};
+use super::{Body, StatusCode};
+
+/// Request served by the guest agent, rather than by the VMM.
+#[derive(Debug, PartialEq)]
+pub(crate) enum GuestAgentRequest {
+    /// Run a command in the guest.
+    Exec(GuestExecConfig),
+    /// Fetch the health information of the guest.
+    Info,
+}
+
+pub(crate) fn parse_get_guest<'a, T>(mut path_tokens: T) -> Result<ParsedRequest, RequestError>
+where
+    T: Iterator<Item = &'a str>,
+{
+    match path_tokens.next() {
+        Some("info") => {
+            METRICS.get_api_requests.guest_info_count.inc();
+            Ok(ParsedRequest::new(RequestAction::GuestAgent(
+                GuestAgentRequest::Info,
+            )))
+        }
+        Some(path) => Err(RequestError::Generic(
+            StatusCode::BadRequest,
+            format!("Unrecognized GET request path `/guest/{path}`."),
+        )),
+        None => Err(RequestError::Generic(
+            StatusCode::BadRequest,
+            "Unrecognized GET request path `/guest/`.".to_string(),
+        )),
+    }
+}
+
+pub(crate) fn parse_put_guest<'a, T>(
+    body: &Body,
+    mut path_tokens: T,
+) -> Result<ParsedRequest, RequestError>
+where
+    T: Iterator<Item = &'a str>,
+{
+    match path_tokens.next() {
+        Some("exec") => {
+            METRICS.put_api_requests.guest_exec_count.inc();
+            let config =
+                serde_json::from_slice::<GuestExecConfig>(body.raw()).inspect_err(|_| {
+                    METRICS.put_api_requests.guest_exec_fails.inc();
+                })?;
+            config.validate().map_err(|err| {
+                METRICS.put_api_requests.guest_exec_fails.inc();
+                RequestError::Generic(StatusCode::BadRequest, err.to_string())
+            })?;
+            Ok(ParsedRequest::new(RequestAction::GuestAgent(
+                GuestAgentRequest::Exec(config),
+            )))
+        }
+        Some(path) => Err(RequestError::Generic(
+            StatusCode::BadRequest,
+            format!("Unrecognized PUT request path `/guest/{path}`."),
+        )),
+        None => Err(RequestError::Generic(
+            StatusCode::BadRequest,
+            "Unrecognized PUT request path `/guest/`.".to_string(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn guest_agent_request(request: ParsedRequest) -> GuestAgentRequest {
+        match request.into_parts() {
+            (RequestAction::GuestAgent(request), _) => request,
+            _ => panic!("Test failed."),
+        }
+    }
+
+    #[test]
+    fn test_parse_get_guest_request() {
+        parse_get_guest([].into_iter()).unwrap_err();
+        parse_get_guest(["exec"].into_iter()).unwrap_err();
+        assert_eq!(
+            guest_agent_request(parse_get_guest(["info"].into_iter()).unwrap()),
+            GuestAgentRequest::Info
+        );
+    }
+
+    #[test]
+    fn test_parse_put_guest_request() {
+        let body = r#"{
+            "path": "/bin/uname",
+            "args": ["-r"],
+            "timeout_s": 5
+        }"#;
+        parse_put_guest(&Body::new(body), ["info"].into_iter()).unwrap_err();
+        let expected = GuestExecConfig {
+            path: "/bin/uname".to_string(),
+            args: vec!["-r".to_string()],
+            env: vec![],
+            input_data: None,
+            timeout_s: 5,
+        };
+        assert_eq!(
+            guest_agent_request(parse_put_guest(&Body::new(body), ["exec"].into_iter()).unwrap()),
+            GuestAgentRequest::Exec(expected)
+        );
+
+        // PUT with invalid fields.
+        let body = r#"{
+            "path": "/bin/uname",
+            "command": "uname"
+        }"#;
+        parse_put_guest(&Body::new(body), ["exec"].into_iter()).unwrap_err();
+
+        // PUT with an invalid timeout.
+        let body = r#"{
+            "path": "/bin/sleep",
+            "args": ["1000"],
+            "timeout_s": 1000
+        }"#;
+        parse_put_guest(&Body::new(body), ["exec"].into_iter()).unwrap_err();
+    }
+}
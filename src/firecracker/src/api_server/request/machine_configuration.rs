@@ -13,6 +13,11 @@ pub(crate) fn parse_get_machine_config() -> Result<ParsedRequest, RequestError>
     Ok(ParsedRequest::new_sync(VmmAction::GetVmMachineConfig))
 }
 
+pub(crate) fn parse_get_cpu_features() -> Result<ParsedRequest, RequestError> {
+    METRICS.get_api_requests.cpu_features_count.inc();
+    Ok(ParsedRequest::new_sync(VmmAction::GetCpuFeatures))
+}
+
 pub(crate) fn parse_put_machine_config(body: &Body) -> Result<ParsedRequest, RequestError> {
     METRICS.put_api_requests.machine_cfg_count.inc();
     let config = serde_json::from_slice::<MachineConfig>(body.raw()).inspect_err(|_| {
@@ -85,6 +90,15 @@ mod tests {
         assert!(METRICS.get_api_requests.machine_cfg_count.count() > 0);
     }
 
+    #[test]
+    fn test_parse_get_cpu_features_request() {
+        assert_eq!(
+            vmm_action_from_request(parse_get_cpu_features().unwrap()),
+            VmmAction::GetCpuFeatures
+        );
+        assert!(METRICS.get_api_requests.cpu_features_count.count() > 0);
+    }
+
     #[test]
     fn test_parse_put_machine_config_request() {
         // 1. Test case for invalid payload.
@@ -123,6 +137,9 @@ mod tests {
                 cpu_template: None,
                 track_dirty_pages: Some(false),
                 huge_pages: Some(expected),
+                nested_virtualization: Some(false),
+                split_irqchip: Some(false),
+                legacy_free: Some(false),
                 #[cfg(feature = "gdb")]
                 gdb_socket_path: None,
             };
@@ -144,6 +161,9 @@ mod tests {
             cpu_template: Some(StaticCpuTemplate::None),
             track_dirty_pages: Some(false),
             huge_pages: Some(HugePageConfig::None),
+            nested_virtualization: Some(false),
+            split_irqchip: Some(false),
+            legacy_free: Some(false),
             #[cfg(feature = "gdb")]
             gdb_socket_path: None,
         };
@@ -165,6 +185,9 @@ mod tests {
             cpu_template: None,
             track_dirty_pages: Some(true),
             huge_pages: Some(HugePageConfig::None),
+            nested_virtualization: Some(false),
+            split_irqchip: Some(false),
+            legacy_free: Some(false),
             #[cfg(feature = "gdb")]
             gdb_socket_path: None,
         };
@@ -190,6 +213,9 @@ mod tests {
                 cpu_template: Some(StaticCpuTemplate::T2),
                 track_dirty_pages: Some(true),
                 huge_pages: Some(HugePageConfig::None),
+                nested_virtualization: Some(false),
+                split_irqchip: Some(false),
+                legacy_free: Some(false),
                 #[cfg(feature = "gdb")]
                 gdb_socket_path: None,
             };
@@ -217,6 +243,9 @@ mod tests {
             cpu_template: None,
             track_dirty_pages: Some(true),
             huge_pages: Some(HugePageConfig::None),
+            nested_virtualization: Some(false),
+            split_irqchip: Some(false),
+            legacy_free: Some(false),
             #[cfg(feature = "gdb")]
             gdb_socket_path: None,
         };
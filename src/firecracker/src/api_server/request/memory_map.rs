@@ -0,0 +1,64 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use micro_http::Body;
+use vmm::logger::{IncMetric, METRICS};
+use vmm::rpc_interface::VmmAction;
+use vmm::vmm_config::memory_map::MemoryMapConfig;
+
+use crate::api_server::parsed_request::{ParsedRequest, RequestError};
+
+pub(crate) fn parse_put_memory_map(body: &Body) -> Result<ParsedRequest, RequestError> {
+    METRICS.put_api_requests.memory_map_count.inc();
+    let config = serde_json::from_slice::<MemoryMapConfig>(body.raw()).inspect_err(|_| {
+        METRICS.put_api_requests.memory_map_fails.inc();
+    })?;
+    Ok(ParsedRequest::new_sync(VmmAction::SetMemoryMap(config)))
+}
+
+#[cfg(test)]
+mod tests {
+    use vmm::vmm_config::memory_map::{MemoryMapRegion, MemoryMapRegionType};
+
+    use super::*;
+    use crate::api_server::parsed_request::tests::vmm_action_from_request;
+
+    #[test]
+    fn test_parse_put_memory_map_request() {
+        parse_put_memory_map(&Body::new("invalid_payload")).unwrap_err();
+
+        // PUT without regions.
+        parse_put_memory_map(&Body::new("{}")).unwrap_err();
+
+        // PUT with an unknown region type.
+        let body = r#"{
+            "regions": [{ "guest_address": 1048576, "size": 4096, "type": "ram" }]
+        }"#;
+        parse_put_memory_map(&Body::new(body)).unwrap_err();
+
+        let body = r#"{
+            "regions": [
+                { "guest_address": 1048576, "size": 4096, "type": "reserved" },
+                { "guest_address": 2097152, "size": 1048576, "type": "pmem" }
+            ]
+        }"#;
+        let expected_config = MemoryMapConfig {
+            regions: vec![
+                MemoryMapRegion {
+                    guest_address: 0x10_0000,
+                    size: 0x1000,
+                    region_type: MemoryMapRegionType::Reserved,
+                },
+                MemoryMapRegion {
+                    guest_address: 0x20_0000,
+                    size: 0x10_0000,
+                    region_type: MemoryMapRegionType::Pmem,
+                },
+            ],
+        };
+        assert_eq!(
+            vmm_action_from_request(parse_put_memory_map(&Body::new(body)).unwrap()),
+            VmmAction::SetMemoryMap(expected_config)
+        );
+    }
+}
@@ -1,22 +1,41 @@
 // Copyright 2018 Amazon.com, Inc. or its affiliates. All Rights Reserved.
 // SPDX-License-Identifier: Apache-2.0
 
+pub mod acpi;
 pub mod actions;
+pub mod apei;
 pub mod balloon;
 pub mod boot_source;
+pub mod cppc;
 pub mod cpu_configuration;
 pub mod drive;
 pub mod entropy;
+pub mod fw_cfg;
+pub mod guest;
 pub mod hotplug;
 pub mod instance_info;
 pub mod logger;
 pub mod machine_configuration;
+pub mod memory_map;
 pub mod metrics;
 pub mod mmds;
 pub mod net;
+pub mod nvme;
 pub mod pmem;
+pub mod pmu;
+pub mod power_supply;
+pub mod processor_aggregator;
+pub mod reboot;
+pub mod s2idle;
+pub mod scheduled_actions;
 pub mod serial;
+pub mod sgx;
+pub mod smbios;
 pub mod snapshot;
+pub mod thermal_zone;
+pub mod usb;
 pub mod version;
+pub mod vfio;
 pub mod vsock;
+pub mod watchdog;
 pub use micro_http::{Body, Method, StatusCode};
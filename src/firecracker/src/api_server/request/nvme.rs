@@ -0,0 +1,83 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use vmm::logger::{IncMetric, METRICS};
+use vmm::rpc_interface::VmmAction;
+use vmm::vmm_config::nvme::NvmeDeviceConfig;
+
+use super::super::parsed_request::{ParsedRequest, RequestError, checked_id};
+use super::{Body, StatusCode};
+
+pub(crate) fn parse_put_nvme(
+    body: &Body,
+    id_from_path: Option<&str>,
+) -> Result<ParsedRequest, RequestError> {
+    METRICS.put_api_requests.nvme_count.inc();
+    let id = if let Some(id) = id_from_path {
+        checked_id(id)?
+    } else {
+        METRICS.put_api_requests.nvme_fails.inc();
+        return Err(RequestError::EmptyID);
+    };
+
+    let device_cfg = serde_json::from_slice::<NvmeDeviceConfig>(body.raw()).inspect_err(|_| {
+        METRICS.put_api_requests.nvme_fails.inc();
+    })?;
+
+    if id != device_cfg.id {
+        METRICS.put_api_requests.nvme_fails.inc();
+        Err(RequestError::Generic(
+            StatusCode::BadRequest,
+            "The id from the path does not match the id from the body!".to_string(),
+        ))
+    } else {
+        Ok(ParsedRequest::new_sync(VmmAction::InsertNvmeDevice(
+            device_cfg,
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vmm::devices::virtio::block::CacheType;
+    use vmm::vmm_config::drive::FileEngineType;
+
+    use super::*;
+    use crate::api_server::parsed_request::tests::vmm_action_from_request;
+
+    #[test]
+    fn test_parse_put_nvme_request() {
+        parse_put_nvme(&Body::new("invalid_payload"), None).unwrap_err();
+        parse_put_nvme(&Body::new("invalid_payload"), Some("id")).unwrap_err();
+
+        let body = r#"{
+            "id": "disk0",
+            "path_on_host": "/disks/disk0.img"
+        }"#;
+        parse_put_nvme(&Body::new(body), Some("disk1")).unwrap_err();
+        let body = r#"{
+            "id": "disk0",
+            "path_on_host": "/disks/disk0.img",
+            "foo": "bar"
+        }"#;
+        parse_put_nvme(&Body::new(body), Some("disk0")).unwrap_err();
+
+        let body = r#"{
+            "id": "disk0",
+            "path_on_host": "/disks/disk0.img",
+            "is_read_only": true,
+            "cache_type": "Writeback",
+            "io_engine": "Async"
+        }"#;
+        let r = vmm_action_from_request(parse_put_nvme(&Body::new(body), Some("disk0")).unwrap());
+
+        let expected_config = NvmeDeviceConfig {
+            id: "disk0".to_string(),
+            path_on_host: "/disks/disk0.img".to_string(),
+            is_read_only: true,
+            cache_type: CacheType::Writeback,
+            file_engine_type: FileEngineType::Async,
+        };
+        assert_eq!(r, VmmAction::InsertNvmeDevice(expected_config));
+    }
+}
@@ -0,0 +1,48 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use micro_http::Body;
+use vmm::logger::{IncMetric, METRICS};
+use vmm::rpc_interface::VmmAction;
+use vmm::vmm_config::pmu::{PmuConfig, PmuEvent};
+
+use crate::api_server::parsed_request::{ParsedRequest, RequestError};
+
+pub(crate) fn parse_put_pmu(body: &Body) -> Result<ParsedRequest, RequestError> {
+    METRICS.put_api_requests.pmu_count.inc();
+    let config = serde_json::from_slice::<PmuConfig>(body.raw()).inspect_err(|_| {
+        METRICS.put_api_requests.pmu_fails.inc();
+    })?;
+    Ok(ParsedRequest::new_sync(VmmAction::SetPmu(config)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api_server::parsed_request::tests::vmm_action_from_request;
+
+    #[test]
+    fn test_parse_put_pmu_request() {
+        parse_put_pmu(&Body::new("invalid_payload")).unwrap_err();
+
+        // Event without its event select.
+        parse_put_pmu(&Body::new(r#"{ "allowed_events": [{ "unit_mask": 1 }] }"#)).unwrap_err();
+
+        assert_eq!(
+            vmm_action_from_request(parse_put_pmu(&Body::new("{}")).unwrap()),
+            VmmAction::SetPmu(PmuConfig::default())
+        );
+
+        let body = r#"{ "allowed_events": [{ "event_select": 196, "unit_mask": 0 }] }"#;
+        let expected_config = PmuConfig {
+            allowed_events: Some(vec![PmuEvent {
+                event_select: 0xc4,
+                unit_mask: 0,
+            }]),
+        };
+        assert_eq!(
+            vmm_action_from_request(parse_put_pmu(&Body::new(body)).unwrap()),
+            VmmAction::SetPmu(expected_config)
+        );
+    }
+}
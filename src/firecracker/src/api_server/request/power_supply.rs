@@ -0,0 +1,85 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use micro_http::Body;
+use vmm::logger::{IncMetric, METRICS};
+use vmm::rpc_interface::VmmAction;
+use vmm::vmm_config::power_supply::{PowerSupplyConfig, PowerSupplyUpdateConfig};
+
+use crate::api_server::parsed_request::{ParsedRequest, RequestError};
+
+pub(crate) fn parse_put_power_supply(body: &Body) -> Result<ParsedRequest, RequestError> {
+    METRICS.put_api_requests.power_supply_count.inc();
+    let config = serde_json::from_slice::<PowerSupplyConfig>(body.raw()).inspect_err(|_| {
+        METRICS.put_api_requests.power_supply_fails.inc();
+    })?;
+    Ok(ParsedRequest::new_sync(VmmAction::SetPowerSupply(config)))
+}
+
+pub(crate) fn parse_patch_power_supply(body: &Body) -> Result<ParsedRequest, RequestError> {
+    METRICS.patch_api_requests.power_supply_count.inc();
+    let update =
+        serde_json::from_slice::<PowerSupplyUpdateConfig>(body.raw()).inspect_err(|_| {
+            METRICS.patch_api_requests.power_supply_fails.inc();
+        })?;
+    Ok(ParsedRequest::new_sync(VmmAction::UpdatePowerSupply(
+        update,
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use vmm::vmm_config::power_supply::{BatteryState, BatteryUpdateConfig};
+
+    use super::*;
+    use crate::api_server::parsed_request::tests::vmm_action_from_request;
+
+    #[test]
+    fn test_parse_put_power_supply_request() {
+        parse_put_power_supply(&Body::new("invalid_payload")).unwrap_err();
+
+        // PUT with an unknown field.
+        let body = r#"{ "ac_online": true, "solar": true }"#;
+        parse_put_power_supply(&Body::new(body)).unwrap_err();
+
+        // PUT with the default configuration.
+        assert_eq!(
+            vmm_action_from_request(parse_put_power_supply(&Body::new("{}")).unwrap()),
+            VmmAction::SetPowerSupply(PowerSupplyConfig::default())
+        );
+
+        let body = r#"{ "ac_online": false, "battery": { "charge_percent": 50 } }"#;
+        let mut expected_config = PowerSupplyConfig {
+            ac_online: false,
+            ..Default::default()
+        };
+        expected_config.battery.charge_percent = 50;
+        assert_eq!(
+            vmm_action_from_request(parse_put_power_supply(&Body::new(body)).unwrap()),
+            VmmAction::SetPowerSupply(expected_config)
+        );
+    }
+
+    #[test]
+    fn test_parse_patch_power_supply_request() {
+        parse_patch_power_supply(&Body::new("invalid_payload")).unwrap_err();
+
+        // PATCH with a field which cannot be updated.
+        let body = r#"{ "battery": { "design_capacity_mwh": 1000 } }"#;
+        parse_patch_power_supply(&Body::new(body)).unwrap_err();
+
+        let body = r#"{ "battery": { "charge_percent": 20, "state": "charging" } }"#;
+        let expected_update = PowerSupplyUpdateConfig {
+            ac_online: None,
+            battery: Some(BatteryUpdateConfig {
+                charge_percent: Some(20),
+                state: Some(BatteryState::Charging),
+                ..Default::default()
+            }),
+        };
+        assert_eq!(
+            vmm_action_from_request(parse_patch_power_supply(&Body::new(body)).unwrap()),
+            VmmAction::UpdatePowerSupply(expected_update)
+        );
+    }
+}
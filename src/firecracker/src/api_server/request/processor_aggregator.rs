@@ -0,0 +1,88 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use micro_http::Body;
+use vmm::logger::{IncMetric, METRICS};
+use vmm::rpc_interface::VmmAction;
+use vmm::vmm_config::processor_aggregator::{
+    ProcessorAggregatorConfig, ProcessorAggregatorUpdateConfig,
+};
+
+use crate::api_server::parsed_request::{ParsedRequest, RequestError};
+
+pub(crate) fn parse_put_processor_aggregator(body: &Body) -> Result<ParsedRequest, RequestError> {
+    METRICS.put_api_requests.processor_aggregator_count.inc();
+    let config =
+        serde_json::from_slice::<ProcessorAggregatorConfig>(body.raw()).inspect_err(|_| {
+            METRICS.put_api_requests.processor_aggregator_fails.inc();
+        })?;
+    Ok(ParsedRequest::new_sync(VmmAction::SetProcessorAggregator(
+        config,
+    )))
+}
+
+pub(crate) fn parse_patch_processor_aggregator(body: &Body) -> Result<ParsedRequest, RequestError> {
+    METRICS.patch_api_requests.processor_aggregator_count.inc();
+    let update = serde_json::from_slice::<ProcessorAggregatorUpdateConfig>(body.raw())
+        .inspect_err(|_| {
+            METRICS.patch_api_requests.processor_aggregator_fails.inc();
+        })?;
+    Ok(ParsedRequest::new_sync(
+        VmmAction::UpdateProcessorAggregator(update),
+    ))
+}
+
+pub(crate) fn parse_get_processor_aggregator() -> Result<ParsedRequest, RequestError> {
+    METRICS.get_api_requests.processor_aggregator_count.inc();
+    Ok(ParsedRequest::new_sync(
+        VmmAction::GetProcessorAggregatorStatus,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api_server::parsed_request::tests::vmm_action_from_request;
+
+    #[test]
+    fn test_parse_put_processor_aggregator_request() {
+        parse_put_processor_aggregator(&Body::new("invalid_payload")).unwrap_err();
+
+        // PUT with a negative number of vCPUs.
+        let body = r#"{ "idle_cpus": -1 }"#;
+        parse_put_processor_aggregator(&Body::new(body)).unwrap_err();
+
+        assert_eq!(
+            vmm_action_from_request(parse_put_processor_aggregator(&Body::new("{}")).unwrap()),
+            VmmAction::SetProcessorAggregator(ProcessorAggregatorConfig::default())
+        );
+
+        let body = r#"{ "idle_cpus": 2 }"#;
+        assert_eq!(
+            vmm_action_from_request(parse_put_processor_aggregator(&Body::new(body)).unwrap()),
+            VmmAction::SetProcessorAggregator(ProcessorAggregatorConfig { idle_cpus: 2 })
+        );
+    }
+
+    #[test]
+    fn test_parse_patch_processor_aggregator_request() {
+        parse_patch_processor_aggregator(&Body::new("invalid_payload")).unwrap_err();
+
+        // PATCH without the number of vCPUs.
+        parse_patch_processor_aggregator(&Body::new("{}")).unwrap_err();
+
+        let body = r#"{ "idle_cpus": 3 }"#;
+        assert_eq!(
+            vmm_action_from_request(parse_patch_processor_aggregator(&Body::new(body)).unwrap()),
+            VmmAction::UpdateProcessorAggregator(ProcessorAggregatorUpdateConfig { idle_cpus: 3 })
+        );
+    }
+
+    #[test]
+    fn test_parse_get_processor_aggregator_request() {
+        assert_eq!(
+            vmm_action_from_request(parse_get_processor_aggregator().unwrap()),
+            VmmAction::GetProcessorAggregatorStatus
+        );
+    }
+}
@@ -0,0 +1,49 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use micro_http::Body;
+use vmm::logger::{IncMetric, METRICS};
+use vmm::rpc_interface::VmmAction;
+use vmm::vmm_config::reboot::RebootConfig;
+
+use crate::api_server::parsed_request::{ParsedRequest, RequestError};
+
+pub(crate) fn parse_put_reboot(body: &Body) -> Result<ParsedRequest, RequestError> {
+    METRICS.put_api_requests.reboot_count.inc();
+    let config = serde_json::from_slice::<RebootConfig>(body.raw()).inspect_err(|_| {
+        METRICS.put_api_requests.reboot_fails.inc();
+    })?;
+    Ok(ParsedRequest::new_sync(VmmAction::SetReboot(config)))
+}
+
+#[cfg(test)]
+mod tests {
+    use vmm::vmm_config::reboot::RebootPolicy;
+
+    use super::*;
+    use crate::api_server::parsed_request::tests::vmm_action_from_request;
+
+    #[test]
+    fn test_parse_put_reboot_request() {
+        parse_put_reboot(&Body::new("invalid_payload")).unwrap_err();
+
+        // PUT with an unknown policy.
+        let body = r#"{ "policy": "halt" }"#;
+        parse_put_reboot(&Body::new(body)).unwrap_err();
+
+        // PUT with the default policy.
+        assert_eq!(
+            vmm_action_from_request(parse_put_reboot(&Body::new("{}")).unwrap()),
+            VmmAction::SetReboot(RebootConfig::default())
+        );
+
+        let body = r#"{ "policy": "reset" }"#;
+        let expected_config = RebootConfig {
+            policy: RebootPolicy::Reset,
+        };
+        assert_eq!(
+            vmm_action_from_request(parse_put_reboot(&Body::new(body)).unwrap()),
+            VmmAction::SetReboot(expected_config)
+        );
+    }
+}
@@ -0,0 +1,34 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use micro_http::Body;
+use vmm::logger::{IncMetric, METRICS};
+use vmm::rpc_interface::VmmAction;
+use vmm::vmm_config::s2idle::S2idleConfig;
+
+use crate::api_server::parsed_request::{ParsedRequest, RequestError};
+
+pub(crate) fn parse_put_s2idle(body: &Body) -> Result<ParsedRequest, RequestError> {
+    METRICS.put_api_requests.s2idle_count.inc();
+    let config = serde_json::from_slice::<S2idleConfig>(body.raw()).inspect_err(|_| {
+        METRICS.put_api_requests.s2idle_fails.inc();
+    })?;
+    Ok(ParsedRequest::new_sync(VmmAction::SetS2idle(config)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api_server::parsed_request::tests::vmm_action_from_request;
+
+    #[test]
+    fn test_parse_put_s2idle_request() {
+        parse_put_s2idle(&Body::new("invalid_payload")).unwrap_err();
+        parse_put_s2idle(&Body::new(r#"{ "residency": 0 }"#)).unwrap_err();
+
+        assert_eq!(
+            vmm_action_from_request(parse_put_s2idle(&Body::new("{}")).unwrap()),
+            VmmAction::SetS2idle(S2idleConfig::default())
+        );
+    }
+}
@@ -0,0 +1,53 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use micro_http::Body;
+use vmm::logger::{IncMetric, METRICS};
+use vmm::rpc_interface::VmmAction;
+use vmm::vmm_config::scheduled_actions::ScheduledActionsConfig;
+
+use crate::api_server::parsed_request::{ParsedRequest, RequestError};
+
+pub(crate) fn parse_put_scheduled_actions(body: &Body) -> Result<ParsedRequest, RequestError> {
+    METRICS.put_api_requests.scheduled_actions_count.inc();
+    let config =
+        serde_json::from_slice::<ScheduledActionsConfig>(body.raw()).inspect_err(|_| {
+            METRICS.put_api_requests.scheduled_actions_fails.inc();
+        })?;
+    Ok(ParsedRequest::new_sync(VmmAction::SetScheduledActions(
+        config,
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use vmm::vmm_config::scheduled_actions::{ActionTrigger, ScheduledAction, ScheduledActionKind};
+
+    use super::*;
+    use crate::api_server::parsed_request::tests::vmm_action_from_request;
+
+    #[test]
+    fn test_parse_put_scheduled_actions_request() {
+        parse_put_scheduled_actions(&Body::new("invalid_payload")).unwrap_err();
+        parse_put_scheduled_actions(&Body::new(
+            r#"{"actions": [{"trigger": "reboot", "action": "pause"}]}"#,
+        ))
+        .unwrap_err();
+
+        let body = r#"{"actions": [{"trigger": "timer", "interval_s": 30, "action": "pause"}]}"#;
+        assert_eq!(
+            vmm_action_from_request(parse_put_scheduled_actions(&Body::new(body)).unwrap()),
+            VmmAction::SetScheduledActions(ScheduledActionsConfig {
+                actions: vec![ScheduledAction {
+                    trigger: ActionTrigger::Timer,
+                    interval_s: Some(30),
+                    action: ScheduledActionKind::Pause,
+                    snapshot_path: None,
+                    mem_file_path: None,
+                    target_available_mib: None,
+                    step_mib: None,
+                }],
+            })
+        );
+    }
+}
@@ -30,6 +30,18 @@ mod tests {
 
         let expected_config = SerialConfig {
             serial_out_path: Some(PathBuf::from("serial")),
+            ..Default::default()
+        };
+        assert_eq!(
+            vmm_action_from_request(parse_put_serial(&Body::new(body)).unwrap()),
+            VmmAction::ConfigureSerial(expected_config)
+        );
+
+        let body = r#"{"serial_socket_path": "serial.sock", "scrollback_bytes": 4096}"#;
+        let expected_config = SerialConfig {
+            serial_out_path: None,
+            serial_socket_path: Some(PathBuf::from("serial.sock")),
+            scrollback_bytes: Some(4096),
         };
         assert_eq!(
             vmm_action_from_request(parse_put_serial(&Body::new(body)).unwrap()),
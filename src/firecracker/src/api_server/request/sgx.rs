@@ -0,0 +1,41 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use micro_http::Body;
+use vmm::logger::{IncMetric, METRICS};
+use vmm::rpc_interface::VmmAction;
+use vmm::vmm_config::sgx::SgxConfig;
+
+use crate::api_server::parsed_request::{ParsedRequest, RequestError};
+
+pub(crate) fn parse_put_sgx(body: &Body) -> Result<ParsedRequest, RequestError> {
+    METRICS.put_api_requests.sgx_count.inc();
+    let config = serde_json::from_slice::<SgxConfig>(body.raw()).inspect_err(|_| {
+        METRICS.put_api_requests.sgx_fails.inc();
+    })?;
+    Ok(ParsedRequest::new_sync(VmmAction::SetSgx(config)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api_server::parsed_request::tests::vmm_action_from_request;
+
+    #[test]
+    fn test_parse_put_sgx_request() {
+        parse_put_sgx(&Body::new("invalid_payload")).unwrap_err();
+
+        // PUT without the EPC size.
+        parse_put_sgx(&Body::new(r#"{ "prefault": true }"#)).unwrap_err();
+
+        let body = r#"{ "epc_size_mib": 64, "prefault": true }"#;
+        let expected_config = SgxConfig {
+            epc_size_mib: 64,
+            prefault: true,
+        };
+        assert_eq!(
+            vmm_action_from_request(parse_put_sgx(&Body::new(body)).unwrap()),
+            VmmAction::SetSgx(expected_config)
+        );
+    }
+}
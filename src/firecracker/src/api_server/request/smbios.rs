@@ -0,0 +1,55 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use micro_http::Body;
+use vmm::logger::{IncMetric, METRICS};
+use vmm::rpc_interface::VmmAction;
+use vmm::vmm_config::smbios::SmbiosConfig;
+
+use crate::api_server::parsed_request::{ParsedRequest, RequestError};
+
+pub(crate) fn parse_put_smbios(body: &Body) -> Result<ParsedRequest, RequestError> {
+    METRICS.put_api_requests.smbios_count.inc();
+    let config = serde_json::from_slice::<SmbiosConfig>(body.raw()).inspect_err(|_| {
+        METRICS.put_api_requests.smbios_fails.inc();
+    })?;
+    Ok(ParsedRequest::new_sync(VmmAction::SetSmbios(config)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api_server::parsed_request::tests::vmm_action_from_request;
+
+    #[test]
+    fn test_parse_put_smbios_request() {
+        parse_put_smbios(&Body::new("invalid_payload")).unwrap_err();
+
+        // PUT with an unknown field.
+        let body = r#"{ "bios_vendor": "acme" }"#;
+        parse_put_smbios(&Body::new(body)).unwrap_err();
+
+        assert_eq!(
+            vmm_action_from_request(parse_put_smbios(&Body::new("{}")).unwrap()),
+            VmmAction::SetSmbios(SmbiosConfig::default())
+        );
+
+        let body = r#"{
+            "manufacturer": "Acme",
+            "product_name": "Roadrunner",
+            "serial_number": "1234",
+            "uuid": "e5c937d0-3553-4d7a-9117-ea4d19c3434d"
+        }"#;
+        let expected_config = SmbiosConfig {
+            manufacturer: Some("Acme".to_string()),
+            product_name: Some("Roadrunner".to_string()),
+            serial_number: Some("1234".to_string()),
+            uuid: Some("e5c937d0-3553-4d7a-9117-ea4d19c3434d".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            vmm_action_from_request(parse_put_smbios(&Body::new(body)).unwrap()),
+            VmmAction::SetSmbios(expected_config)
+        );
+    }
+}
@@ -0,0 +1,82 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use micro_http::Body;
+use vmm::logger::{IncMetric, METRICS};
+use vmm::rpc_interface::VmmAction;
+use vmm::vmm_config::thermal_zone::{ThermalZoneConfig, ThermalZoneUpdateConfig};
+
+use crate::api_server::parsed_request::{ParsedRequest, RequestError};
+
+pub(crate) fn parse_put_thermal_zone(body: &Body) -> Result<ParsedRequest, RequestError> {
+    METRICS.put_api_requests.thermal_zone_count.inc();
+    let config = serde_json::from_slice::<ThermalZoneConfig>(body.raw()).inspect_err(|_| {
+        METRICS.put_api_requests.thermal_zone_fails.inc();
+    })?;
+    Ok(ParsedRequest::new_sync(VmmAction::SetThermalZone(config)))
+}
+
+pub(crate) fn parse_patch_thermal_zone(body: &Body) -> Result<ParsedRequest, RequestError> {
+    METRICS.patch_api_requests.thermal_zone_count.inc();
+    let update =
+        serde_json::from_slice::<ThermalZoneUpdateConfig>(body.raw()).inspect_err(|_| {
+            METRICS.patch_api_requests.thermal_zone_fails.inc();
+        })?;
+    Ok(ParsedRequest::new_sync(VmmAction::UpdateThermalZone(
+        update,
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api_server::parsed_request::tests::vmm_action_from_request;
+
+    #[test]
+    fn test_parse_put_thermal_zone_request() {
+        parse_put_thermal_zone(&Body::new("invalid_payload")).unwrap_err();
+
+        // PUT with an unknown field.
+        let body = r#"{ "passive_mc": 70000 }"#;
+        parse_put_thermal_zone(&Body::new(body)).unwrap_err();
+
+        // PUT with the default configuration.
+        assert_eq!(
+            vmm_action_from_request(parse_put_thermal_zone(&Body::new("{}")).unwrap()),
+            VmmAction::SetThermalZone(ThermalZoneConfig::default())
+        );
+
+        let body = r#"{
+            "host_sensor": "/sys/class/hwmon/hwmon0/temp1_input",
+            "poll_interval_ms": 500,
+            "critical_mc": 95000
+        }"#;
+        let expected_config = ThermalZoneConfig {
+            host_sensor: Some("/sys/class/hwmon/hwmon0/temp1_input".into()),
+            poll_interval_ms: 500,
+            critical_mc: Some(95_000),
+            ..Default::default()
+        };
+        assert_eq!(
+            vmm_action_from_request(parse_put_thermal_zone(&Body::new(body)).unwrap()),
+            VmmAction::SetThermalZone(expected_config)
+        );
+    }
+
+    #[test]
+    fn test_parse_patch_thermal_zone_request() {
+        parse_patch_thermal_zone(&Body::new("invalid_payload")).unwrap_err();
+
+        // PATCH with a field which cannot be updated.
+        let body = r#"{ "critical_mc": 95000 }"#;
+        parse_patch_thermal_zone(&Body::new(body)).unwrap_err();
+
+        let body = r#"{ "temperature_mc": 85000 }"#;
+        assert_eq!(
+            vmm_action_from_request(parse_patch_thermal_zone(&Body::new(body)).unwrap()),
+            VmmAction::UpdateThermalZone(ThermalZoneUpdateConfig {
+                temperature_mc: 85_000,
+            })
+        );
+    }
+}
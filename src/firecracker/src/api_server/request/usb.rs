@@ -0,0 +1,77 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use vmm::logger::{IncMetric, METRICS};
+use vmm::rpc_interface::VmmAction;
+use vmm::vmm_config::usb::UsbDeviceConfig;
+
+use super::super::parsed_request::{ParsedRequest, RequestError, checked_id};
+use super::{Body, StatusCode};
+
+pub(crate) fn parse_put_usb(
+    body: &Body,
+    id_from_path: Option<&str>,
+) -> Result<ParsedRequest, RequestError> {
+    METRICS.put_api_requests.usb_count.inc();
+    let id = if let Some(id) = id_from_path {
+        checked_id(id)?
+    } else {
+        METRICS.put_api_requests.usb_fails.inc();
+        return Err(RequestError::EmptyID);
+    };
+
+    let device_cfg = serde_json::from_slice::<UsbDeviceConfig>(body.raw()).inspect_err(|_| {
+        METRICS.put_api_requests.usb_fails.inc();
+    })?;
+
+    if id != device_cfg.id {
+        METRICS.put_api_requests.usb_fails.inc();
+        Err(RequestError::Generic(
+            StatusCode::BadRequest,
+            "The id from the path does not match the id from the body!".to_string(),
+        ))
+    } else {
+        Ok(ParsedRequest::new_sync(VmmAction::InsertUsbDevice(
+            device_cfg,
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::api_server::parsed_request::tests::vmm_action_from_request;
+
+    #[test]
+    fn test_parse_put_usb_request() {
+        parse_put_usb(&Body::new("invalid_payload"), None).unwrap_err();
+        parse_put_usb(&Body::new("invalid_payload"), Some("id")).unwrap_err();
+
+        let body = r#"{
+            "id": "key0",
+            "hidraw_path": "/dev/hidraw0"
+        }"#;
+        parse_put_usb(&Body::new(body), Some("key1")).unwrap_err();
+        let body = r#"{
+            "id": "key0",
+            "hidraw_path": "/dev/hidraw0",
+            "foo": "bar"
+        }"#;
+        parse_put_usb(&Body::new(body), Some("key0")).unwrap_err();
+
+        let body = r#"{
+            "id": "key0",
+            "hidraw_path": "/dev/hidraw0"
+        }"#;
+        let r = vmm_action_from_request(parse_put_usb(&Body::new(body), Some("key0")).unwrap());
+
+        let expected_config = UsbDeviceConfig {
+            id: "key0".to_string(),
+            usbfs_path: None,
+            hidraw_path: Some(PathBuf::from("/dev/hidraw0")),
+        };
+        assert_eq!(r, VmmAction::InsertUsbDevice(expected_config));
+    }
+}
@@ -0,0 +1,124 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use vmm::logger::{IncMetric, METRICS};
+use vmm::rpc_interface::VmmAction;
+use vmm::vmm_config::vfio::{VfioConfig, VfioVfConfig};
+
+use super::super::parsed_request::{ParsedRequest, RequestError, checked_id};
+use super::{Body, StatusCode};
+
+pub(crate) fn parse_put_vfio(
+    body: &Body,
+    id_from_path: Option<&str>,
+) -> Result<ParsedRequest, RequestError> {
+    METRICS.put_api_requests.vfio_count.inc();
+    let id = if let Some(id) = id_from_path {
+        checked_id(id)?
+    } else {
+        METRICS.put_api_requests.vfio_fails.inc();
+        return Err(RequestError::EmptyID);
+    };
+
+    let device_cfg = serde_json::from_slice::<VfioConfig>(body.raw()).inspect_err(|_| {
+        METRICS.put_api_requests.vfio_fails.inc();
+    })?;
+
+    if id != device_cfg.id {
+        METRICS.put_api_requests.vfio_fails.inc();
+        Err(RequestError::Generic(
+            StatusCode::BadRequest,
+            "The id from the path does not match the id from the body!".to_string(),
+        ))
+    } else {
+        Ok(ParsedRequest::new_sync(VmmAction::InsertVfioDevice(
+            device_cfg,
+        )))
+    }
+}
+
+pub(crate) fn parse_put_vfio_vf(
+    body: &Body,
+    id_from_path: Option<&str>,
+) -> Result<ParsedRequest, RequestError> {
+    METRICS.put_api_requests.vfio_vf_count.inc();
+    let id = if let Some(id) = id_from_path {
+        checked_id(id)?
+    } else {
+        METRICS.put_api_requests.vfio_vf_fails.inc();
+        return Err(RequestError::EmptyID);
+    };
+
+    let vf_cfg = serde_json::from_slice::<VfioVfConfig>(body.raw()).inspect_err(|_| {
+        METRICS.put_api_requests.vfio_vf_fails.inc();
+    })?;
+
+    if id != vf_cfg.id {
+        METRICS.put_api_requests.vfio_vf_fails.inc();
+        Err(RequestError::Generic(
+            StatusCode::BadRequest,
+            "The id from the path does not match the id from the body!".to_string(),
+        ))
+    } else {
+        Ok(ParsedRequest::new_sync(VmmAction::InsertVfioVf(vf_cfg)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::api_server::parsed_request::tests::vmm_action_from_request;
+
+    #[test]
+    fn test_parse_put_vfio_request() {
+        parse_put_vfio(&Body::new("invalid_payload"), None).unwrap_err();
+        parse_put_vfio(&Body::new("invalid_payload"), Some("id")).unwrap_err();
+
+        let body = r#"{
+            "id": "gpu0",
+            "path": "/sys/bus/pci/devices/0000:01:00.0"
+        }"#;
+        parse_put_vfio(&Body::new(body), Some("gpu1")).unwrap_err();
+        let body = r#"{
+            "id": "gpu0",
+            "path": "/sys/bus/pci/devices/0000:01:00.0",
+            "foo": "bar"
+        }"#;
+        parse_put_vfio(&Body::new(body), Some("gpu0")).unwrap_err();
+
+        let body = r#"{
+            "id": "gpu0",
+            "path": "/sys/bus/pci/devices/0000:01:00.0"
+        }"#;
+        let r = vmm_action_from_request(parse_put_vfio(&Body::new(body), Some("gpu0")).unwrap());
+
+        let expected_config = VfioConfig {
+            id: "gpu0".to_string(),
+            path: PathBuf::from("/sys/bus/pci/devices/0000:01:00.0"),
+        };
+        assert_eq!(r, VmmAction::InsertVfioDevice(expected_config));
+    }
+
+    #[test]
+    fn test_parse_put_vfio_vf_request() {
+        parse_put_vfio_vf(&Body::new("invalid_payload"), None).unwrap_err();
+        parse_put_vfio_vf(&Body::new("invalid_payload"), Some("id")).unwrap_err();
+
+        let body = r#"{
+            "id": "nic0",
+            "pf_bdf": "0000:3b:00.0",
+            "vf_index": 3
+        }"#;
+        parse_put_vfio_vf(&Body::new(body), Some("nic1")).unwrap_err();
+
+        let r = vmm_action_from_request(parse_put_vfio_vf(&Body::new(body), Some("nic0")).unwrap());
+        let expected_config = VfioVfConfig {
+            id: "nic0".to_string(),
+            pf_bdf: "0000:3b:00.0".to_string(),
+            vf_index: 3,
+        };
+        assert_eq!(r, VmmAction::InsertVfioVf(expected_config));
+    }
+}
@@ -0,0 +1,70 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use micro_http::Body;
+use vmm::logger::{IncMetric, METRICS};
+use vmm::rpc_interface::VmmAction;
+use vmm::vmm_config::watchdog::WatchdogConfig;
+
+use crate::api_server::parsed_request::{ParsedRequest, RequestError};
+
+pub(crate) fn parse_put_watchdog(body: &Body) -> Result<ParsedRequest, RequestError> {
+    METRICS.put_api_requests.watchdog_count.inc();
+    let config = serde_json::from_slice::<WatchdogConfig>(body.raw()).inspect_err(|_| {
+        METRICS.put_api_requests.watchdog_fails.inc();
+    })?;
+    Ok(ParsedRequest::new_sync(VmmAction::SetWatchdog(config)))
+}
+
+pub(crate) fn parse_get_watchdog() -> Result<ParsedRequest, RequestError> {
+    METRICS.get_api_requests.watchdog_count.inc();
+    Ok(ParsedRequest::new_sync(VmmAction::GetWatchdogStatus))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use vmm::vmm_config::watchdog::WatchdogAction;
+
+    use super::*;
+    use crate::api_server::parsed_request::tests::vmm_action_from_request;
+
+    #[test]
+    fn test_parse_put_watchdog_request() {
+        parse_put_watchdog(&Body::new("invalid_payload")).unwrap_err();
+
+        // PUT with an unknown action.
+        let body = r#"{ "action": "poweroff" }"#;
+        parse_put_watchdog(&Body::new(body)).unwrap_err();
+
+        // PUT with the default action.
+        assert_eq!(
+            vmm_action_from_request(parse_put_watchdog(&Body::new("{}")).unwrap()),
+            VmmAction::SetWatchdog(WatchdogConfig::default())
+        );
+
+        let body = r#"{
+            "action": "snapshot",
+            "snapshot_path": "vmstate",
+            "mem_file_path": "mem"
+        }"#;
+        let expected_config = WatchdogConfig {
+            action: WatchdogAction::Snapshot,
+            snapshot_path: Some(PathBuf::from("vmstate")),
+            mem_file_path: Some(PathBuf::from("mem")),
+        };
+        assert_eq!(
+            vmm_action_from_request(parse_put_watchdog(&Body::new(body)).unwrap()),
+            VmmAction::SetWatchdog(expected_config)
+        );
+    }
+
+    #[test]
+    fn test_parse_get_watchdog_request() {
+        assert_eq!(
+            vmm_action_from_request(parse_get_watchdog().unwrap()),
+            VmmAction::GetWatchdogStatus
+        );
+    }
+}
@@ -8,6 +8,7 @@ mod metrics;
 mod seccomp;
 
 use std::fs::{self, File};
+use std::os::fd::{FromRawFd, RawFd};
 use std::path::PathBuf;
 use std::process::ExitCode;
 use std::str::FromStr;
@@ -22,7 +23,7 @@ use utils::validators::validate_instance_id;
 use vmm::arch::host_page_size;
 use vmm::builder::StartMicrovmError;
 use vmm::logger::{
-    LOGGER, LoggerConfig, METRICS, ProcessTimeReporter, StoreMetric, debug, error, info,
+    LOGGER, LoggerConfig, METRICS, ProcessTimeReporter, StoreMetric, debug, error, info, warn,
 };
 use vmm::persist::SNAPSHOT_VERSION;
 use vmm::resources::VmResources;
@@ -167,12 +168,22 @@ fn main_exec() -> Result<(), MainError> {
             .arg(
                 Argument::new("no-seccomp")
                     .takes_value(false)
-                    .forbids(vec!["seccomp-filter"])
+                    .forbids(vec!["seccomp-filter", "seccomp-log-only"])
                     .help(
                         "Optional parameter which allows starting and using a microVM without \
                          seccomp filtering. Not recommended.",
                     ),
             )
+            .arg(
+                Argument::new("seccomp-log-only")
+                    .takes_value(false)
+                    .forbids(vec!["no-seccomp"])
+                    .help(
+                        "Optional parameter which makes the seccomp filters log the syscalls they \
+                         would deny instead of denying them. For validating custom filters, not \
+                         for production use.",
+                    ),
+            )
             .arg(
                 Argument::new("start-time-us").takes_value(true).help(
                     "Process start time (wall clock, microseconds). This parameter is optional.",
@@ -184,11 +195,13 @@ fn main_exec() -> Result<(), MainError> {
             .arg(Argument::new("parent-cpu-time-us").takes_value(true).help(
                 "Parent process CPU time (wall clock, microseconds). This parameter is optional.",
             ))
-            .arg(
-                Argument::new("config-file")
-                    .takes_value(true)
-                    .help("Path to a file that contains the microVM configuration in JSON format."),
-            )
+            .arg(Argument::new("vcpu-cgroup-fd").takes_value(true).help(
+                "File descriptor of the cgroup.threads file of the cgroup the vCPU threads are \
+                 moved to. Passed by the jailer, this parameter is optional.",
+            ))
+            .arg(Argument::new("config-file").takes_value(true).help(
+                "Path to a file that contains the microVM configuration in JSON or YAML format.",
+            ))
             .arg(
                 Argument::new(MMDS_CONTENT_ARG).takes_value(true).help(
                     "Path to a file that contains metadata in JSON format to add to the mmds.",
@@ -362,6 +375,20 @@ fn main_exec() -> Result<(), MainError> {
     )
     .and_then(seccomp::get_filters)
     .map_err(MainError::SeccompFilter)?;
+    if arguments.flag_present("seccomp-log-only") {
+        warn!("Seccomp filters are in log-only mode, denied syscalls are only logged.");
+        seccomp_filters = seccomp::into_log_only(seccomp_filters);
+    }
+
+    if let Some(fd) = arguments.single_value("vcpu-cgroup-fd") {
+        let fd = fd
+            .parse::<RawFd>()
+            .expect("'vcpu-cgroup-fd' parameter expected to be a file descriptor.");
+        // SAFETY: The descriptor is inherited from the jailer and is owned by nothing else in
+        // this process.
+        let cgroup_threads = unsafe { File::from_raw_fd(fd) };
+        vmm::vstate::vcpu::set_vcpu_cgroup(cgroup_threads);
+    }
 
     let vmm_config_json = arguments
         .single_value("config-file")
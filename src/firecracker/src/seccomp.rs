@@ -4,8 +4,12 @@ use std::fmt::Debug;
 use std::fs::File;
 use std::io::{BufReader, Read};
 use std::path::Path;
+use std::sync::Arc;
 
-use vmm::seccomp::{BpfThreadMap, DeserializationError, deserialize_binary, get_empty_filters};
+use vmm::seccomp::{
+    BpfThreadMap, DeserializationError, ValidationError, deserialize_binary, get_empty_filters,
+    to_log_only, validate_filter,
+};
 
 const THREAD_CATEGORIES: [&str; 3] = ["vmm", "api", "vcpu"];
 
@@ -20,6 +24,8 @@ pub enum FilterError {
     MissingThreadCategory(String),
     /// Filter file open error: {0}
     FileOpen(std::io::Error),
+    /// Invalid filter for thread category {0}: {1}
+    InvalidFilter(String, ValidationError),
 }
 
 /// Seccomp filter configuration.
@@ -72,8 +78,32 @@ fn get_default_filters() -> Result<BpfThreadMap, FilterError> {
 
 /// Retrieve custom seccomp filters.
 fn get_custom_filters<R: Read + Debug>(reader: R) -> Result<BpfThreadMap, FilterError> {
-    let map = deserialize_binary(BufReader::new(reader)).map_err(FilterError::Deserialization)?;
-    filter_thread_categories(map)
+    let custom =
+        deserialize_binary(BufReader::new(reader)).map_err(FilterError::Deserialization)?;
+    override_default_filters(custom)
+}
+
+/// Override the default filters per thread category with the custom ones, so thread categories
+/// missing from the custom filters keep their default filter.
+fn override_default_filters(custom: BpfThreadMap) -> Result<BpfThreadMap, FilterError> {
+    let mut map = get_default_filters()?;
+    map.extend(custom);
+    let filters = filter_thread_categories(map)?;
+
+    for (category, filter) in filters.iter() {
+        validate_filter(filter).map_err(|err| FilterError::InvalidFilter(category.clone(), err))?;
+    }
+
+    Ok(filters)
+}
+
+/// Turn the filters into log-only filters, which report the syscalls the original filters
+/// would deny instead of denying them.
+pub fn into_log_only(filters: BpfThreadMap) -> BpfThreadMap {
+    filters
+        .into_iter()
+        .map(|(category, filter)| (category, Arc::new(to_log_only(&filter))))
+        .collect()
 }
 
 /// Return an error if the BpfThreadMap contains invalid thread categories.
@@ -168,6 +198,64 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_override_default_filters() {
+        let defaults = get_default_filters().unwrap();
+
+        // Categories missing from the custom filters keep their default filter.
+        let mut custom = BpfThreadMap::new();
+        custom.insert("vcpu".to_string(), Arc::new(vec![0x7fff_0000_0000_0006]));
+        let filters = override_default_filters(custom).unwrap();
+        assert_eq!(filters.len(), 3);
+        assert_eq!(*filters["vcpu"], vec![0x7fff_0000_0000_0006]);
+        assert_eq!(filters["vmm"], defaults["vmm"]);
+        assert_eq!(filters["api"], defaults["api"]);
+
+        // Invalid category.
+        let mut custom = BpfThreadMap::new();
+        custom.insert("thread1".to_string(), Arc::new(vec![0x7fff_0000_0000_0006]));
+        match override_default_filters(custom).unwrap_err() {
+            FilterError::ThreadCategories(err) => assert_eq!(err, "thread1"),
+            _ => panic!("Expected ThreadCategories error."),
+        }
+
+        // Device emulation runs on the vmm thread, so there is no separate device category.
+        let mut custom = BpfThreadMap::new();
+        custom.insert("device".to_string(), Arc::new(vec![0x7fff_0000_0000_0006]));
+        match override_default_filters(custom).unwrap_err() {
+            FilterError::ThreadCategories(err) => assert_eq!(err, "device"),
+            _ => panic!("Expected ThreadCategories error."),
+        }
+
+        // Invalid filter.
+        let mut custom = BpfThreadMap::new();
+        custom.insert("api".to_string(), Arc::new(vec![0x20]));
+        match override_default_filters(custom).unwrap_err() {
+            FilterError::InvalidFilter(name, err) => {
+                assert_eq!(name, "api");
+                assert_eq!(err, ValidationError::MissingReturn);
+            }
+            _ => panic!("Expected InvalidFilter error."),
+        }
+    }
+
+    #[test]
+    fn test_into_log_only() {
+        let mut map = BpfThreadMap::new();
+        map.insert(
+            "vcpu".to_string(),
+            Arc::new(vec![0x7fff_0000_0000_0006, 0x0000_0000_0000_0006]),
+        );
+        map.insert("api".to_string(), Arc::new(vec![]));
+
+        let filters = into_log_only(map);
+        assert_eq!(
+            *filters["vcpu"],
+            vec![0x7fff_0000_0000_0006, 0x7ffc_0000_0000_0006]
+        );
+        assert!(filters["api"].is_empty());
+    }
+
     #[test]
     fn test_seccomp_config() {
         assert!(matches!(
@@ -13,6 +13,13 @@ use regex::Regex;
 
 use crate::{JailerError, readln_special, writeln_special};
 
+// Controllers enabled in threaded cgroups. Only threaded controllers can be enabled
+// for a threaded subtree.
+const THREADED_CONTROLLERS: [&str; 2] = ["cpu", "cpuset"];
+
+// Keys accepted by the io.max cgroupv2 knob.
+const IO_MAX_KEYS: [&str; 4] = ["rbps", "wbps", "riops", "wiops"];
+
 // Holds information on a cgroup mount point discovered on the system
 #[derive(Debug)]
 struct CgroupMountPoint {
@@ -180,6 +187,28 @@ impl CgroupConfigurationBuilder {
         }
     }
 
+    // Adds a threaded child cgroup to the microVM cgroup. Only supported with cgroupsv2.
+    pub fn add_threaded_cgroup(
+        &mut self,
+        name: &str,
+        id: &str,
+        parent_cg: &Path,
+    ) -> Result<(), JailerError> {
+        match self.cgroup_conf {
+            CgroupConfiguration::V1(_) => Err(JailerError::CgroupRequiresV2(format!(
+                "Threaded cgroup {name}"
+            ))),
+            CgroupConfiguration::V2(ref mut cgroup_conf_v2) => {
+                let path = self.hierarchies.get_v2_hierarchy_path()?;
+                let cgroup = cgroup_conf_v2
+                    .entry(String::from("unified"))
+                    .or_insert(CgroupV2::new(id, parent_cg, path)?);
+                cgroup.threaded_children.push(name.to_string());
+                Ok(())
+            }
+        }
+    }
+
     pub fn build(self) -> CgroupConfiguration {
         self.cgroup_conf
     }
@@ -212,6 +241,7 @@ pub struct CgroupV1 {
 pub struct CgroupV2 {
     base: CgroupBase,
     available_controllers: HashSet<String>,
+    threaded_children: Vec<String>, // names of the threaded child cgroups.
 }
 
 pub trait Cgroup: Debug {
@@ -238,6 +268,18 @@ impl CgroupConfiguration {
             Self::V2(conf) => setup_cgroup_conf(conf),
         }
     }
+
+    // Returns the location of the threaded child cgroup with the given name, if it is part of
+    // the configuration. Threaded cgroups only exist with cgroupsv2.
+    pub fn threaded_cgroup_path(&self, name: &str) -> Option<PathBuf> {
+        match self {
+            Self::V1(_) => None,
+            Self::V2(conf) => conf
+                .values()
+                .find(|cgroup| cgroup.threaded_children.iter().any(|child| child == name))
+                .map(|cgroup| cgroup.base.location.join(name)),
+        }
+    }
 }
 
 // If we call inherit_from_parent_aux(.../A/B/C, file, condition), the following will happen:
@@ -434,6 +476,7 @@ impl CgroupV2 {
                 location: path,
             },
             available_controllers: Self::detect_available_controllers(unified_path),
+            threaded_children: Vec::new(),
         })
     }
 }
@@ -472,6 +515,21 @@ impl Cgroup for CgroupV2 {
             writeln_special(&self.base.location.join(&property.file), &property.value)?;
         }
 
+        for child in self.threaded_children.iter() {
+            let child_location = self.base.location.join(child);
+            fs::create_dir_all(&child_location)
+                .map_err(|err| JailerError::CreateDir(child_location.clone(), err))?;
+            // Making the child threaded turns the microVM cgroup into a threaded domain, which
+            // holds the process while its threads can be moved to the threaded children.
+            writeln_special(&child_location.join("cgroup.type"), "threaded")?;
+            for controller in THREADED_CONTROLLERS
+                .iter()
+                .filter(|c| self.available_controllers.contains(**c))
+            {
+                CgroupV2::write_all_subtree_control(&self.base.location, controller)?;
+            }
+        }
+
         Ok(())
     }
 
@@ -485,6 +543,68 @@ impl Cgroup for CgroupV2 {
     }
 }
 
+// Detects the cgroup version used on the system. Systems with any cgroupv1 hierarchy mounted,
+// including hybrid ones, have their controllers attached to the v1 hierarchies, so they use
+// cgroupsv1. Systems with only the unified hierarchy mounted use cgroupsv2.
+pub fn detect_cgroup_version(proc_mounts_path: &str) -> Result<u8, JailerError> {
+    match CgroupHierarchies::new(1, proc_mounts_path) {
+        Ok(_) => Ok(1),
+        Err(JailerError::CgroupHierarchyMissing(_)) => {
+            CgroupHierarchies::new(2, proc_mounts_path).map(|_| 2)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+// Checks a cpu.max value, which has the format "<quota|max> [<period>]".
+pub fn validate_cpu_max(value: &str) -> Result<(), JailerError> {
+    let mut fields = value.split_whitespace();
+    let quota_ok = fields
+        .next()
+        .is_some_and(|quota| quota == "max" || quota.parse::<u64>().is_ok());
+    let period_ok = fields
+        .next()
+        .is_none_or(|period| period.parse::<u64>().is_ok());
+    if quota_ok && period_ok && fields.next().is_none() {
+        Ok(())
+    } else {
+        Err(JailerError::CgroupFormat(format!("cpu.max={value}")))
+    }
+}
+
+// Checks a memory.max value, which has the format "<bytes|max>", where bytes can have
+// a K, M, G or T suffix.
+pub fn validate_memory_max(value: &str) -> Result<(), JailerError> {
+    let bytes = value.trim_end_matches(['K', 'M', 'G', 'T', 'k', 'm', 'g', 't']);
+    let suffix_len = value.len() - bytes.len();
+    if value == "max" || (suffix_len <= 1 && bytes.parse::<u64>().is_ok()) {
+        Ok(())
+    } else {
+        Err(JailerError::CgroupFormat(format!("memory.max={value}")))
+    }
+}
+
+// Checks an io.max value, which has the format "<major>:<minor> <key>=<value>...".
+pub fn validate_io_max(value: &str) -> Result<(), JailerError> {
+    let mut fields = value.split_whitespace();
+    let device_ok = fields
+        .next()
+        .and_then(|device| device.split_once(':'))
+        .is_some_and(|(major, minor)| major.parse::<u32>().is_ok() && minor.parse::<u32>().is_ok());
+    let mut limits = fields.peekable();
+    let limits_ok = limits.peek().is_some()
+        && limits.all(|limit| {
+            limit.split_once('=').is_some_and(|(key, val)| {
+                IO_MAX_KEYS.contains(&key) && (val == "max" || val.parse::<u64>().is_ok())
+            })
+        });
+    if device_ok && limits_ok {
+        Ok(())
+    } else {
+        Err(JailerError::CgroupFormat(format!("io.max={value}")))
+    }
+}
+
 pub fn setup_cgroup_conf(conf: &HashMap<String, impl Cgroup>) -> Result<(), JailerError> {
     // cgroups are iterated two times as some cgroups may require others (e.g cpuset requires
     // cpuset.mems and cpuset.cpus) to be set before attaching any pid.
@@ -834,6 +954,117 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_cgroup_conf_v2_threaded_cgroup() {
+        let mut mock_cgroups = MockCgroupFs::new().unwrap();
+        mock_cgroups.add_v2_mounts().unwrap();
+
+        let mut builder =
+            CgroupConfigurationBuilder::new(2, mock_cgroups.proc_mounts_path.to_str().unwrap())
+                .unwrap();
+        builder
+            .add_cgroup_property(
+                "memory.max".to_string(),
+                "512M".to_string(),
+                "101",
+                Path::new("fc_test_cgv2"),
+            )
+            .unwrap();
+        builder
+            .add_threaded_cgroup("vcpus", "101", Path::new("fc_test_cgv2"))
+            .unwrap();
+        let cg_conf = builder.build();
+
+        let cg_root = mock_cgroups.sys_cgroups_path.join("unified");
+        fs::create_dir_all(cg_root.join("fc_test_cgv2/101")).unwrap();
+        MockCgroupFs::create_file_with_contents(
+            cg_root.join("fc_test_cgv2/cgroup.subtree_control"),
+            "",
+        )
+        .unwrap();
+        MockCgroupFs::create_file_with_contents(
+            cg_root.join("fc_test_cgv2/101/cgroup.subtree_control"),
+            "",
+        )
+        .unwrap();
+
+        cg_conf.setup().unwrap();
+
+        assert_eq!(
+            read_first_line(cg_root.join("fc_test_cgv2/101/vcpus/cgroup.type")).unwrap(),
+            "threaded\n"
+        );
+        assert_eq!(
+            cg_conf.threaded_cgroup_path("vcpus").unwrap(),
+            cg_root.join("fc_test_cgv2/101/vcpus")
+        );
+        assert!(cg_conf.threaded_cgroup_path("other").is_none());
+        // The mock writes overwrite each other, so only the last controller is visible.
+        assert_eq!(
+            read_first_line(cg_root.join("fc_test_cgv2/101/cgroup.subtree_control")).unwrap(),
+            "+cpuset\n"
+        );
+
+        // Threaded cgroups are not supported with cgroups v1.
+        let mut mock_cgroups = MockCgroupFs::new().unwrap();
+        mock_cgroups.add_v1_mounts().unwrap();
+        let mut builder =
+            CgroupConfigurationBuilder::new(1, mock_cgroups.proc_mounts_path.to_str().unwrap())
+                .unwrap();
+        assert!(matches!(
+            builder.add_threaded_cgroup("vcpus", "101", Path::new("fc_test_cgv1")),
+            Err(JailerError::CgroupRequiresV2(_))
+        ));
+    }
+
+    #[test]
+    fn test_detect_cgroup_version() {
+        let mock_cgroups = MockCgroupFs::new().unwrap();
+        detect_cgroup_version(mock_cgroups.proc_mounts_path.to_str().unwrap()).unwrap_err();
+
+        let mut mock_cgroups = MockCgroupFs::new().unwrap();
+        mock_cgroups.add_v2_mounts().unwrap();
+        assert_eq!(
+            detect_cgroup_version(mock_cgroups.proc_mounts_path.to_str().unwrap()).unwrap(),
+            2
+        );
+
+        // Hybrid systems use the v1 hierarchies.
+        mock_cgroups.add_v1_mounts().unwrap();
+        assert_eq!(
+            detect_cgroup_version(mock_cgroups.proc_mounts_path.to_str().unwrap()).unwrap(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_validate_cgroup_v2_knobs() {
+        validate_cpu_max("max").unwrap();
+        validate_cpu_max("max 100000").unwrap();
+        validate_cpu_max("50000 100000").unwrap();
+        validate_cpu_max("").unwrap_err();
+        validate_cpu_max("half").unwrap_err();
+        validate_cpu_max("50000 max").unwrap_err();
+        validate_cpu_max("50000 100000 1").unwrap_err();
+
+        validate_memory_max("max").unwrap();
+        validate_memory_max("536870912").unwrap();
+        validate_memory_max("512M").unwrap();
+        validate_memory_max("1g").unwrap();
+        validate_memory_max("").unwrap_err();
+        validate_memory_max("M").unwrap_err();
+        validate_memory_max("512MM").unwrap_err();
+        validate_memory_max("512MB").unwrap_err();
+
+        validate_io_max("8:16 rbps=2097152").unwrap();
+        validate_io_max("8:16 rbps=max wbps=1048576 riops=max wiops=120").unwrap();
+        validate_io_max("8:16").unwrap_err();
+        validate_io_max("8 rbps=2097152").unwrap_err();
+        validate_io_max("8:16 rbps").unwrap_err();
+        validate_io_max("8:16 bps=2097152").unwrap_err();
+        validate_io_max("8:16 rbps=fast").unwrap_err();
+    }
+
     #[test]
     fn test_inherit_from_parent() {
         // 1. If parent file does not exist, return an error.
@@ -16,10 +16,15 @@ use utils::time::{ClockType, get_time_us};
 use utils::{arg_parser, validators};
 use vmm_sys_util::syscall::SyscallReturnCode;
 
-use crate::JailerError;
-use crate::cgroup::{CgroupConfiguration, CgroupConfigurationBuilder};
+use crate::cgroup::{
+    CgroupConfiguration, CgroupConfigurationBuilder, detect_cgroup_version, validate_cpu_max,
+    validate_io_max, validate_memory_max,
+};
 use crate::chroot::chroot;
+use crate::landlock::{LandlockAccess, LandlockRules, READ_ONLY_ARG, READ_WRITE_ARG};
+use crate::network::{NetworkConfig, VethConfig, validate_ifname};
 use crate::resource_limits::{FSIZE_ARG, NO_FILE_ARG, ResourceLimits};
+use crate::{JailerError, userns};
 
 pub const PROC_MOUNTS: &str = "/proc/mounts";
 
@@ -46,6 +51,11 @@ const DEV_URANDOM: &CStr = c"/dev/urandom";
 const DEV_URANDOM_MAJOR: u32 = 1;
 const DEV_URANDOM_MINOR: u32 = 9;
 
+// The vhost-net device has a fixed misc minor, see VHOST_NET_MINOR in include/linux/miscdevice.h.
+const DEV_VHOST_NET: &CStr = c"/dev/vhost-net";
+const DEV_VHOST_NET_MAJOR: u32 = 10;
+const DEV_VHOST_NET_MINOR: u32 = 238;
+
 // Userfault file descriptor device path. This is a misc character device
 // with a MISC_DYNAMIC_MINOR minor device:
 // https://elixir.bootlin.com/linux/v6.1.51/source/fs/userfaultfd.c#L2176.
@@ -57,6 +67,9 @@ const DEV_URANDOM_MINOR: u32 = 9;
 const DEV_UFFD_PATH: &CStr = c"/dev/userfaultfd";
 const DEV_UFFD_MAJOR: u32 = 10;
 
+// Name of the threaded cgroup created for the vCPU threads under the microVM cgroup.
+const VCPU_THREADED_CGROUP: &str = "vcpus";
+
 // Relevant folders inside the jail that we create or/and for which we change ownership.
 // We need /dev in order to be able to create /dev/kvm and /dev/net/tun device.
 // We need /run for the default location of the api socket.
@@ -122,14 +135,19 @@ pub struct Env {
     uid: u32,
     gid: u32,
     netns: Option<String>,
+    network: NetworkConfig,
     daemonize: bool,
     new_pid_ns: bool,
+    new_user_ns: bool,
     start_time_us: u64,
     start_time_cpu_us: u64,
     jailer_cpu_time_us: u64,
     extra_args: Vec<String>,
     cgroup_conf: Option<CgroupConfiguration>,
+    parent_cgroup_move: Option<PathBuf>,
+    vcpu_cgroup: Option<File>,
     resource_limits: ResourceLimits,
+    landlock_rules: Option<LandlockRules>,
     uffd_dev_minor: Option<u32>,
 }
 
@@ -183,10 +201,34 @@ impl Env {
 
         let netns = arguments.single_value("netns").cloned();
 
+        let tap = arguments.single_value("tap").cloned();
+        if let Some(ref tap) = tap {
+            validate_ifname(tap)?;
+        }
+        let veth = arguments
+            .single_value("veth")
+            .map(|veth| {
+                VethConfig::new(
+                    veth,
+                    arguments.single_value("veth-addrs").map(String::as_str),
+                )
+            })
+            .transpose()?;
+        let network = NetworkConfig {
+            new_netns: arguments.flag_present("new-netns"),
+            tap,
+            veth,
+        };
+
         let daemonize = arguments.flag_present("daemonize");
 
         let new_pid_ns = arguments.flag_present("new-pid-ns");
 
+        let new_user_ns = arguments.flag_present("new-user-ns");
+        if new_user_ns {
+            userns::check_ids(uid, gid)?;
+        }
+
         // Optional arguments.
         let mut cgroup_conf = None;
         let parent_cgroup = match arguments.single_value("parent-cgroup") {
@@ -203,27 +245,50 @@ impl Env {
         let cgroup_ver = arguments.single_value("cgroup-version").ok_or_else(|| {
             JailerError::ArgumentParsing(MissingValue("cgroup-version".to_string()))
         })?;
-        let cgroup_ver = cgroup_ver
-            .parse::<u8>()
-            .map_err(|_| JailerError::CgroupInvalidVersion(cgroup_ver.to_string()))?;
+        let cgroup_ver = match cgroup_ver.as_str() {
+            "auto" => detect_cgroup_version(proc_mounts)?,
+            ver => ver
+                .parse::<u8>()
+                .map_err(|_| JailerError::CgroupInvalidVersion(ver.to_string()))?,
+        };
 
         let cgroups_args: &[String] = arguments.multiple_values("cgroup").unwrap_or_default();
 
+        // cgroupsv2 knobs which have a dedicated argument.
+        let mut cgroup_knobs: Vec<(&str, &String)> = Vec::new();
+        if let Some(value) = arguments.single_value("cpu-max") {
+            validate_cpu_max(value)?;
+            cgroup_knobs.push(("cpu.max", value));
+        }
+        if let Some(value) = arguments.single_value("memory-max") {
+            validate_memory_max(value)?;
+            cgroup_knobs.push(("memory.max", value));
+        }
+        for value in arguments.multiple_values("io-max").unwrap_or_default() {
+            validate_io_max(value)?;
+            cgroup_knobs.push(("io.max", value));
+        }
+        if let Some((file, _)) = cgroup_knobs.first().filter(|_| cgroup_ver != 2) {
+            return Err(JailerError::CgroupRequiresV2(file.to_string()));
+        }
+
+        let vcpu_threaded_cgroup = arguments.flag_present("vcpu-threaded-cgroup");
+
         // If the --parent-cgroup exists, and we have no other cgroups,
         // then the intent is to move the process to that cgroup.
         // Only applies to cgroupsv2 since it's a unified hierarchy
-        if cgroups_args.is_empty() && cgroup_ver == 2 {
+        let mut parent_cgroup_move = None;
+        if cgroups_args.is_empty()
+            && cgroup_knobs.is_empty()
+            && !vcpu_threaded_cgroup
+            && cgroup_ver == 2
+        {
             let builder = CgroupConfigurationBuilder::new(cgroup_ver, proc_mounts)?;
-            let cg_parent = builder.get_v2_hierarchy_path()?.join(parent_cgroup);
-            let cg_parent_procs = cg_parent.join("cgroup.procs");
-            if cg_parent.exists() {
-                fs::write(cg_parent_procs, std::process::id().to_string())
-                    .map_err(|_| JailerError::CgroupMove(cg_parent, io::Error::last_os_error()))?;
-            }
+            parent_cgroup_move = Some(builder.get_v2_hierarchy_path()?.join(parent_cgroup));
         }
 
         // cgroup format: <cgroup_controller>.<cgroup_property>=<value>,...
-        if let Some(cgroups_args) = arguments.multiple_values("cgroup") {
+        if !cgroups_args.is_empty() || !cgroup_knobs.is_empty() || vcpu_threaded_cgroup {
             let mut builder = CgroupConfigurationBuilder::new(cgroup_ver, proc_mounts)?;
             for cg in cgroups_args {
                 let aux: Vec<&str> = cg.split('=').collect();
@@ -244,6 +309,17 @@ impl Env {
                     parent_cgroup,
                 )?;
             }
+            for (file, value) in cgroup_knobs {
+                builder.add_cgroup_property(
+                    file.to_string(),
+                    value.to_string(),
+                    id,
+                    parent_cgroup,
+                )?;
+            }
+            if vcpu_threaded_cgroup {
+                builder.add_threaded_cgroup(VCPU_THREADED_CGROUP, id, parent_cgroup)?;
+            }
             cgroup_conf = Some(builder.build());
         }
 
@@ -252,6 +328,14 @@ impl Env {
             Env::parse_resource_limits(&mut resource_limits, args)?;
         }
 
+        let mut landlock_rules = None;
+        if arguments.flag_present("landlock") {
+            let args = arguments
+                .multiple_values("landlock-rule")
+                .unwrap_or_default();
+            landlock_rules = Some(Env::parse_landlock_rules(args)?);
+        }
+
         let uffd_dev_minor = Self::get_userfaultfd_minor_dev_number().ok();
 
         Ok(Env {
@@ -261,18 +345,34 @@ impl Env {
             uid,
             gid,
             netns,
+            network,
             daemonize,
             new_pid_ns,
+            new_user_ns,
             start_time_us,
             start_time_cpu_us,
             jailer_cpu_time_us: 0,
             extra_args: arguments.extra_args(),
             cgroup_conf,
+            parent_cgroup_move,
+            vcpu_cgroup: None,
             resource_limits,
+            landlock_rules,
             uffd_dev_minor,
         })
     }
 
+    fn move_to_cgroup(cg_parent: &Path) -> Result<(), JailerError> {
+        if cg_parent.exists() {
+            fs::write(
+                cg_parent.join("cgroup.procs"),
+                std::process::id().to_string(),
+            )
+            .map_err(|err| JailerError::CgroupMove(cg_parent.to_path_buf(), err))?;
+        }
+        Ok(())
+    }
+
     pub fn chroot_dir(&self) -> &Path {
         self.chroot_dir.as_path()
     }
@@ -304,6 +404,27 @@ impl Env {
         Ok((exec_file_path, exec_file_name))
     }
 
+    fn parse_landlock_rules(args: &[String]) -> Result<LandlockRules, JailerError> {
+        let mut landlock_rules = LandlockRules::default();
+        for arg in args {
+            let (path, access) = arg
+                .split_once('=')
+                .ok_or_else(|| JailerError::LandlockRuleFormat(arg.to_string()))?;
+            let access = match access {
+                READ_ONLY_ARG => LandlockAccess::ReadOnly,
+                READ_WRITE_ARG => LandlockAccess::ReadWrite,
+                _ => return Err(JailerError::LandlockRuleFormat(arg.to_string())),
+            };
+            // Rules are installed after chrooting, so paths are relative to the jail root.
+            let path = Path::new(path);
+            if path.as_os_str().is_empty() || path.components().any(|c| c == Component::ParentDir) {
+                return Err(JailerError::LandlockRuleFormat(arg.to_string()));
+            }
+            landlock_rules.add_rule(Path::new("/").join(path), access);
+        }
+        Ok(landlock_rules)
+    }
+
     fn parse_resource_limits(
         resource_limits: &mut ResourceLimits,
         args: &[String],
@@ -370,6 +491,7 @@ impl Env {
                         .into_empty_result()
                         .map_err(JailerError::SetSid)?;
                 }
+                self.install_landlock(&chroot_exec_file)?;
                 Err(JailerError::Exec(self.exec_command(chroot_exec_file)))
             }
             child_pid => {
@@ -451,6 +573,85 @@ impl Env {
             })
     }
 
+    fn mknod_devices(&self) -> Result<(), JailerError> {
+        // Here we are creating the /dev/kvm and /dev/net/tun devices inside the jailer.
+        // Following commands can be translated into bash like this:
+        // $: mkdir -p $chroot_dir/dev/net
+        // $: dev_net_tun_path={$chroot_dir}/"tun"
+        // $: mknod $dev_net_tun_path c 10 200
+        // www.kernel.org/doc/Documentation/networking/tuntap.txt specifies 10 and 200 as the major
+        // and minor for the /dev/net/tun device.
+        self.mknod_and_own_dev(DEV_NET_TUN, DEV_NET_TUN_MAJOR, DEV_NET_TUN_MINOR)?;
+        // Do the same for /dev/kvm with (major, minor) = (10, 232).
+        self.mknod_and_own_dev(DEV_KVM, DEV_KVM_MAJOR, DEV_KVM_MINOR)?;
+        // And for /dev/urandom with (major, minor) = (1, 9).
+        // If the device is not accessible on the host, output a warning to inform user that MMDS
+        // version 2 will not be available to use.
+        let _ = self
+            .mknod_and_own_dev(DEV_URANDOM, DEV_URANDOM_MAJOR, DEV_URANDOM_MINOR)
+            .map_err(|err| {
+                println!(
+                    "Warning! Could not create /dev/urandom device inside jailer: {}.",
+                    err
+                );
+                println!("MMDS version 2 will not be available to use.");
+            });
+        // And for /dev/vhost-net with (major, minor) = (10, 238), without which net devices fall
+        // back to the userspace dataplane.
+        let _ = self
+            .mknod_and_own_dev(DEV_VHOST_NET, DEV_VHOST_NET_MAJOR, DEV_VHOST_NET_MINOR)
+            .map_err(|err| {
+                println!(
+                    "Warning! Could not create /dev/vhost-net device inside jailer: {}.",
+                    err
+                );
+            });
+
+        // If we have a minor version for /dev/userfaultfd the device is present on the host.
+        // Expose the device in the jailed environment.
+        if let Some(minor) = self.uffd_dev_minor {
+            self.mknod_and_own_dev(DEV_UFFD_PATH, DEV_UFFD_MAJOR, minor)?;
+        }
+
+        Ok(())
+    }
+
+    // Rootless counterpart of `mknod_devices`, which bind mounts the devices of the host inside
+    // the jail. It has to run before chrooting, while the devices of the host are reachable.
+    fn bind_mount_devices(&self) -> Result<(), JailerError> {
+        userns::bind_mount_dev(DEV_KVM, self.chroot_dir())?;
+        let _ = userns::check_dev_access(DEV_NET_TUN)
+            .and_then(|_| userns::bind_mount_dev(DEV_NET_TUN, self.chroot_dir()))
+            .map_err(|err| {
+                println!(
+                    "Warning! Could not bind mount /dev/net/tun inside jailer: {}.",
+                    err
+                );
+                println!("Network devices will not be available to use.");
+            });
+        let _ = userns::check_dev_access(DEV_URANDOM)
+            .and_then(|_| userns::bind_mount_dev(DEV_URANDOM, self.chroot_dir()))
+            .map_err(|err| {
+                println!(
+                    "Warning! Could not bind mount /dev/urandom inside jailer: {}.",
+                    err
+                );
+                println!("MMDS version 2 will not be available to use.");
+            });
+        let _ = userns::check_dev_access(DEV_VHOST_NET)
+            .and_then(|_| userns::bind_mount_dev(DEV_VHOST_NET, self.chroot_dir()))
+            .map_err(|err| {
+                println!(
+                    "Warning! Could not bind mount /dev/vhost-net inside jailer: {}.",
+                    err
+                );
+            });
+        if self.uffd_dev_minor.is_some() && userns::check_dev_access(DEV_UFFD_PATH).is_ok() {
+            userns::bind_mount_dev(DEV_UFFD_PATH, self.chroot_dir())?;
+        }
+        Ok(())
+    }
+
     fn setup_jailed_folder(&self, folder: impl AsRef<Path>) -> Result<(), JailerError> {
         let folder_path = folder.as_ref();
         fs::create_dir_all(folder_path)
@@ -528,6 +729,60 @@ impl Env {
             .map_err(JailerError::SetNetNs)
     }
 
+    // Opens the cgroup.threads file of the vCPU threaded cgroup, if one was requested, and lets
+    // the exec'd process inherit it. The cgroup hierarchy is out of reach once chrooted, so
+    // clawdbox moves its vCPU threads through this descriptor. The kernel checks the write
+    // permissions against the credentials of the opener, i.e. the jailer, so the file does not
+    // need to be owned by the jailed user.
+    fn open_vcpu_cgroup(&mut self) -> Result<(), JailerError> {
+        let Some(path) = self
+            .cgroup_conf
+            .as_ref()
+            .and_then(|conf| conf.threaded_cgroup_path(VCPU_THREADED_CGROUP))
+        else {
+            return Ok(());
+        };
+        let path = path.join("cgroup.threads");
+        let file = OpenOptions::new()
+            .write(true)
+            .open(&path)
+            .map_err(|err| JailerError::FileOpen(path, err))?;
+
+        // SAFETY: Safe because the file descriptor is valid.
+        let flags = SyscallReturnCode(unsafe { libc::fcntl(file.as_raw_fd(), libc::F_GETFD) })
+            .into_result()
+            .map_err(JailerError::GetOldFdFlags)?;
+        // SAFETY: Safe because the file descriptor is valid.
+        SyscallReturnCode(unsafe {
+            libc::fcntl(file.as_raw_fd(), libc::F_SETFD, flags & !libc::FD_CLOEXEC)
+        })
+        .into_empty_result()
+        .map_err(JailerError::UnsetCloexec)?;
+
+        self.vcpu_cgroup = Some(file);
+        Ok(())
+    }
+
+    // Landlock is installed last, right before exec, and therefore after chrooting. This is
+    // deliberate: the jailer itself still needs to create the jail folders and device nodes and
+    // to write the PID file, which the jailed process is not allowed to. Installing the rules
+    // from inside the jail also resolves their paths against the jail root, so that they cannot
+    // grant access to anything outside of it. Landlock then adds to the chroot, it does not
+    // replace it. Without explicit rules, the jailed process is restricted to what the jail was
+    // populated with, which is only known once the jail is set up.
+    fn install_landlock(&self, chroot_exec_file: &Path) -> Result<(), JailerError> {
+        match self.landlock_rules {
+            Some(ref rules) if rules.is_empty() => {
+                let mut pid_file = chroot_exec_file.as_os_str().to_owned();
+                pid_file.push(PID_FILE_EXTENSION);
+                let skip = [chroot_exec_file.to_path_buf(), PathBuf::from(pid_file)];
+                LandlockRules::from_jail_root(Path::new("/"), &skip)?.install(chroot_exec_file)
+            }
+            Some(ref rules) => rules.install(chroot_exec_file),
+            None => Ok(()),
+        }
+    }
+
     fn exec_command(&self, chroot_exec_file: PathBuf) -> io::Error {
         Command::new(chroot_exec_file)
             .args(["--id", &self.id])
@@ -537,6 +792,11 @@ impl Env {
                 &get_time_us(ClockType::ProcessCpu).to_string(),
             ])
             .args(["--parent-cpu-time-us", &self.jailer_cpu_time_us.to_string()])
+            .args(
+                self.vcpu_cgroup.iter().flat_map(|file| {
+                    ["--vcpu-cgroup-fd".to_string(), file.as_raw_fd().to_string()]
+                }),
+            )
             .stdin(Stdio::inherit())
             .stdout(Stdio::inherit())
             .stderr(Stdio::inherit())
@@ -642,18 +902,35 @@ impl Env {
         let exec_file_name = self.copy_exec_to_chroot()?;
         let chroot_exec_file = PathBuf::from("/").join(exec_file_name);
 
+        // Enter the user namespace first, as the following steps require the capabilities the
+        // process only has inside of it.
+        if self.new_user_ns {
+            userns::check_dev_access(DEV_KVM)?;
+            userns::enter_user_ns(self.uid(), self.gid())?;
+        }
+
         // Join the specified network namespace, if applicable.
         if let Some(ref path) = self.netns {
             Env::join_netns(path)?;
         }
 
+        // Create the network namespace and the network devices of the microVM, if applicable.
+        if !self.network.is_empty() {
+            self.network.setup(self.uid(), self.gid())?;
+        }
+
         // Set limits on resources.
         self.resource_limits.install()?;
 
         // We have to setup cgroups at this point, because we can't do it anymore after chrooting.
+        // This also makes sure the process is moved to its cgroup before dropping privileges.
+        if let Some(ref cg_parent) = self.parent_cgroup_move {
+            Env::move_to_cgroup(cg_parent)?;
+        }
         if let Some(ref conf) = self.cgroup_conf {
             conf.setup()?;
         }
+        self.open_vcpu_cgroup()?;
 
         // If daemonization was requested, open /dev/null before chrooting.
         let dev_null = if self.daemonize {
@@ -669,6 +946,10 @@ impl Env {
         #[cfg(target_arch = "aarch64")]
         self.copy_midr_el1_info()?;
 
+        if self.new_user_ns {
+            self.bind_mount_devices()?;
+        }
+
         // Jail self.
         chroot(self.chroot_dir())?;
 
@@ -678,33 +959,9 @@ impl Env {
             .iter()
             .try_for_each(|f| self.setup_jailed_folder(f))?;
 
-        // Here we are creating the /dev/kvm and /dev/net/tun devices inside the jailer.
-        // Following commands can be translated into bash like this:
-        // $: mkdir -p $chroot_dir/dev/net
-        // $: dev_net_tun_path={$chroot_dir}/"tun"
-        // $: mknod $dev_net_tun_path c 10 200
-        // www.kernel.org/doc/Documentation/networking/tuntap.txt specifies 10 and 200 as the major
-        // and minor for the /dev/net/tun device.
-        self.mknod_and_own_dev(DEV_NET_TUN, DEV_NET_TUN_MAJOR, DEV_NET_TUN_MINOR)?;
-        // Do the same for /dev/kvm with (major, minor) = (10, 232).
-        self.mknod_and_own_dev(DEV_KVM, DEV_KVM_MAJOR, DEV_KVM_MINOR)?;
-        // And for /dev/urandom with (major, minor) = (1, 9).
-        // If the device is not accessible on the host, output a warning to inform user that MMDS
-        // version 2 will not be available to use.
-        let _ = self
-            .mknod_and_own_dev(DEV_URANDOM, DEV_URANDOM_MAJOR, DEV_URANDOM_MINOR)
-            .map_err(|err| {
-                println!(
-                    "Warning! Could not create /dev/urandom device inside jailer: {}.",
-                    err
-                );
-                println!("MMDS version 2 will not be available to use.");
-            });
-
-        // If we have a minor version for /dev/userfaultfd the device is present on the host.
-        // Expose the device in the jailed environment.
-        if let Some(minor) = self.uffd_dev_minor {
-            self.mknod_and_own_dev(DEV_UFFD_PATH, DEV_UFFD_MAJOR, minor)?;
+        // Devices cannot be created from a user namespace, they were bind mounted instead.
+        if !self.new_user_ns {
+            self.mknod_devices()?;
         }
 
         self.jailer_cpu_time_us = get_time_us(ClockType::ProcessCpu) - self.start_time_cpu_us;
@@ -767,6 +1024,7 @@ impl Env {
             self.exec_into_new_pid_ns(chroot_exec_file)
         } else {
             self.save_exec_file_pid(id().try_into().unwrap(), chroot_exec_file.clone())?;
+            self.install_landlock(&chroot_exec_file)?;
             Err(JailerError::Exec(self.exec_command(chroot_exec_file)))
         }
     }
@@ -1355,6 +1613,279 @@ mod tests {
         Env::new(&args, 0, 0, mock_cgroups.proc_mounts_path.to_str().unwrap()).unwrap();
     }
 
+    #[test]
+    fn test_new_user_ns_ids() {
+        let arg_parser = build_arg_parser();
+        let pseudo_exec_file_path = get_pseudo_exec_file_path();
+        let mut mock_cgroups = MockCgroupFs::new().unwrap();
+        mock_cgroups.add_v1_mounts().unwrap();
+        let euid = unsafe { libc::geteuid() }.to_string();
+        let egid = unsafe { libc::getegid() }.to_string();
+
+        // The ids of the calling user are accepted.
+        let arg_vals = ArgVals {
+            uid: &euid,
+            gid: &egid,
+            ..ArgVals::new(pseudo_exec_file_path.as_str())
+        };
+        let mut args = arg_parser.arguments().clone();
+        let mut arg_vec = make_args(&arg_vals);
+        arg_vec.push("--new-user-ns".to_string());
+        args.parse(&arg_vec).unwrap();
+        let env = Env::new(&args, 0, 0, mock_cgroups.proc_mounts_path.to_str().unwrap()).unwrap();
+        assert!(env.new_user_ns);
+
+        // Other ids cannot be mapped into the user namespace.
+        let other_uid = (unsafe { libc::geteuid() } + 1).to_string();
+        let arg_vals = ArgVals {
+            uid: &other_uid,
+            ..arg_vals
+        };
+        let mut args = arg_parser.arguments().clone();
+        let mut arg_vec = make_args(&arg_vals);
+        arg_vec.push("--new-user-ns".to_string());
+        args.parse(&arg_vec).unwrap();
+        assert!(matches!(
+            Env::new(&args, 0, 0, mock_cgroups.proc_mounts_path.to_str().unwrap()),
+            Err(JailerError::UserNsIds(..))
+        ));
+    }
+
+    #[test]
+    fn test_network_parsing() {
+        let arg_parser = build_arg_parser();
+        let pseudo_exec_file_path = get_pseudo_exec_file_path();
+        let mut mock_cgroups = MockCgroupFs::new().unwrap();
+        mock_cgroups.add_v1_mounts().unwrap();
+        let arg_vals = ArgVals {
+            netns: None,
+            ..ArgVals::new(pseudo_exec_file_path.as_str())
+        };
+
+        let mut args = arg_parser.arguments().clone();
+        args.parse(&make_args(&arg_vals)).unwrap();
+        let env = Env::new(&args, 0, 0, mock_cgroups.proc_mounts_path.to_str().unwrap()).unwrap();
+        assert!(env.network.is_empty());
+
+        let mut args = arg_parser.arguments().clone();
+        let mut arg_vec = make_args(&arg_vals);
+        arg_vec.extend(
+            [
+                "--new-netns",
+                "--tap",
+                "tap0",
+                "--veth",
+                "veth-vm0",
+                "--veth-addrs",
+                "10.0.0.1/30,10.0.0.2/30",
+            ]
+            .map(String::from),
+        );
+        args.parse(&arg_vec).unwrap();
+        let env = Env::new(&args, 0, 0, mock_cgroups.proc_mounts_path.to_str().unwrap()).unwrap();
+        assert_eq!(
+            env.network,
+            NetworkConfig {
+                new_netns: true,
+                tap: Some("tap0".to_string()),
+                veth: Some(VethConfig::new("veth-vm0", Some("10.0.0.1/30,10.0.0.2/30")).unwrap()),
+            }
+        );
+
+        // The names of the interfaces are validated.
+        let mut args = arg_parser.arguments().clone();
+        let mut arg_vec = make_args(&arg_vals);
+        arg_vec.extend(["--tap", "tap/0"].map(String::from));
+        args.parse(&arg_vec).unwrap();
+        assert!(matches!(
+            Env::new(&args, 0, 0, mock_cgroups.proc_mounts_path.to_str().unwrap()),
+            Err(JailerError::NetworkArgument(_))
+        ));
+
+        // The veth pair can only be created along with a new network namespace.
+        let mut args = arg_parser.arguments().clone();
+        let mut arg_vec = make_args(&arg_vals);
+        arg_vec.extend(["--veth", "veth-vm0"].map(String::from));
+        args.parse(&arg_vec).unwrap_err();
+
+        // A new network namespace cannot be created when joining an existing one.
+        let mut args = arg_parser.arguments().clone();
+        let mut arg_vec = make_args(&ArgVals {
+            netns: Some("zzzns"),
+            ..arg_vals
+        });
+        arg_vec.push("--new-netns".to_string());
+        args.parse(&arg_vec).unwrap_err();
+    }
+
+    #[test]
+    fn test_parse_landlock_rules() {
+        let args = vec!["/rootfs.ext4=rw".to_string(), "vmlinux=ro".to_string()];
+        Env::parse_landlock_rules(&args).unwrap();
+
+        for arg in [
+            "/rootfs.ext4",
+            "/rootfs.ext4=rx",
+            "=ro",
+            "/../rootfs.ext4=rw",
+        ] {
+            assert!(matches!(
+                Env::parse_landlock_rules(&[arg.to_string()]),
+                Err(JailerError::LandlockRuleFormat(_))
+            ));
+        }
+
+        let arg_parser = build_arg_parser();
+        let pseudo_exec_file_path = get_pseudo_exec_file_path();
+        let arg_vals = ArgVals::new(pseudo_exec_file_path.as_str());
+        let mut mock_cgroups = MockCgroupFs::new().unwrap();
+        mock_cgroups.add_v1_mounts().unwrap();
+
+        let mut args = arg_parser.arguments().clone();
+        args.parse(&make_args(&arg_vals)).unwrap();
+        let env = Env::new(&args, 0, 0, mock_cgroups.proc_mounts_path.to_str().unwrap()).unwrap();
+        assert!(env.landlock_rules.is_none());
+
+        let mut args = arg_parser.arguments().clone();
+        let mut arg_vec = make_args(&arg_vals);
+        arg_vec.extend(
+            ["--landlock", "--landlock-rule", "/vmlinux=ro"]
+                .iter()
+                .map(|arg| arg.to_string()),
+        );
+        args.parse(&arg_vec).unwrap();
+        let env = Env::new(&args, 0, 0, mock_cgroups.proc_mounts_path.to_str().unwrap()).unwrap();
+        assert!(env.landlock_rules.is_some());
+    }
+
+    #[test]
+    fn test_cgroup_v2_knobs_parsing() {
+        let arg_parser = build_arg_parser();
+        let pseudo_exec_file_path = get_pseudo_exec_file_path();
+        let arg_vals = ArgVals {
+            cgroups: Vec::new(),
+            ..ArgVals::new(pseudo_exec_file_path.as_str())
+        };
+        let mut mock_cgroups_v1 = MockCgroupFs::new().unwrap();
+        mock_cgroups_v1.add_v1_mounts().unwrap();
+        let mut mock_cgroups_v2 = MockCgroupFs::new().unwrap();
+        mock_cgroups_v2.add_v2_mounts().unwrap();
+
+        let parse = |extra_args: &[&str], mock_cgroups: &MockCgroupFs| {
+            let mut args = arg_parser.arguments().clone();
+            let mut arg_vec = make_args(&arg_vals);
+            arg_vec.extend(extra_args.iter().map(|arg| arg.to_string()));
+            args.parse(&arg_vec).unwrap();
+            Env::new(&args, 0, 0, mock_cgroups.proc_mounts_path.to_str().unwrap())
+        };
+
+        // Valid knobs, with the cgroup version detected from the mounts.
+        let env = parse(
+            &[
+                "--cgroup-version",
+                "auto",
+                "--cpu-max",
+                "50000 100000",
+                "--memory-max",
+                "512M",
+                "--io-max",
+                "8:16 rbps=2097152 wiops=120",
+                "--io-max",
+                "8:0 wbps=max",
+                "--vcpu-threaded-cgroup",
+            ],
+            &mock_cgroups_v2,
+        )
+        .unwrap();
+        assert!(matches!(env.cgroup_conf, Some(CgroupConfiguration::V2(_))));
+        assert!(env.parent_cgroup_move.is_none());
+
+        // Without any cgroup, the process is moved to the parent cgroup.
+        let env = parse(&["--cgroup-version", "2"], &mock_cgroups_v2).unwrap();
+        assert!(env.cgroup_conf.is_none());
+        assert!(env.parent_cgroup_move.is_some());
+
+        // Invalid knob values.
+        parse(
+            &["--cgroup-version", "2", "--cpu-max", "50000 100000 1"],
+            &mock_cgroups_v2,
+        )
+        .unwrap_err();
+        parse(
+            &["--cgroup-version", "2", "--memory-max", "512MB"],
+            &mock_cgroups_v2,
+        )
+        .unwrap_err();
+        parse(
+            &["--cgroup-version", "2", "--io-max", "8:16 rbps"],
+            &mock_cgroups_v2,
+        )
+        .unwrap_err();
+
+        // Knobs are only supported with cgroups v2.
+        assert!(matches!(
+            parse(
+                &["--cgroup-version", "auto", "--memory-max", "max"],
+                &mock_cgroups_v1
+            ),
+            Err(JailerError::CgroupRequiresV2(_))
+        ));
+        assert!(matches!(
+            parse(
+                &["--cgroup-version", "1", "--vcpu-threaded-cgroup"],
+                &mock_cgroups_v1
+            ),
+            Err(JailerError::CgroupRequiresV2(_))
+        ));
+    }
+
+    #[test]
+    fn test_open_vcpu_cgroup() {
+        let arg_parser = build_arg_parser();
+        let pseudo_exec_file_path = get_pseudo_exec_file_path();
+        let arg_vals = ArgVals {
+            cgroups: Vec::new(),
+            ..ArgVals::new(pseudo_exec_file_path.as_str())
+        };
+        let mut mock_cgroups = MockCgroupFs::new().unwrap();
+        mock_cgroups.add_v2_mounts().unwrap();
+
+        let parse = |extra_args: &[&str]| {
+            let mut args = arg_parser.arguments().clone();
+            let mut arg_vec = make_args(&arg_vals);
+            arg_vec.extend(extra_args.iter().map(|arg| arg.to_string()));
+            args.parse(&arg_vec).unwrap();
+            Env::new(&args, 0, 0, mock_cgroups.proc_mounts_path.to_str().unwrap()).unwrap()
+        };
+
+        // Without a threaded cgroup, there is nothing to open.
+        let mut env = parse(&["--cgroup-version", "2", "--memory-max", "512M"]);
+        env.open_vcpu_cgroup().unwrap();
+        assert!(env.vcpu_cgroup.is_none());
+
+        let mut env = parse(&["--cgroup-version", "2", "--vcpu-threaded-cgroup"]);
+        let vcpu_cgroup = env
+            .cgroup_conf
+            .as_ref()
+            .unwrap()
+            .threaded_cgroup_path(VCPU_THREADED_CGROUP)
+            .unwrap();
+        // The cgroup.threads file only exists once the cgroup is created.
+        assert!(matches!(
+            env.open_vcpu_cgroup(),
+            Err(JailerError::FileOpen(_, _))
+        ));
+
+        create_dir_all(&vcpu_cgroup).unwrap();
+        MockCgroupFs::create_file_with_contents(vcpu_cgroup.join("cgroup.threads"), "").unwrap();
+        env.open_vcpu_cgroup().unwrap();
+
+        // The descriptor is passed on to the exec'd process, so it must survive exec.
+        let fd = env.vcpu_cgroup.as_ref().unwrap().as_raw_fd();
+        let flags = unsafe { libc::fcntl(fd, libc::F_GETFD) };
+        assert_eq!(flags & libc::FD_CLOEXEC, 0);
+    }
+
     #[test]
     fn test_parse_resource_limits() {
         let mut resource_limits = ResourceLimits::default();
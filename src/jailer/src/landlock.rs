@@ -0,0 +1,398 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::fs::{self, OpenOptions};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+
+use vmm_sys_util::syscall::SyscallReturnCode;
+
+use super::JailerError;
+
+// Landlock filesystem access rights, as defined in include/uapi/linux/landlock.h.
+const ACCESS_FS_EXECUTE: u64 = 1 << 0;
+const ACCESS_FS_WRITE_FILE: u64 = 1 << 1;
+const ACCESS_FS_READ_FILE: u64 = 1 << 2;
+const ACCESS_FS_READ_DIR: u64 = 1 << 3;
+const ACCESS_FS_REMOVE_DIR: u64 = 1 << 4;
+const ACCESS_FS_REMOVE_FILE: u64 = 1 << 5;
+const ACCESS_FS_MAKE_CHAR: u64 = 1 << 6;
+const ACCESS_FS_MAKE_DIR: u64 = 1 << 7;
+const ACCESS_FS_MAKE_REG: u64 = 1 << 8;
+const ACCESS_FS_MAKE_SOCK: u64 = 1 << 9;
+const ACCESS_FS_MAKE_FIFO: u64 = 1 << 10;
+const ACCESS_FS_MAKE_BLOCK: u64 = 1 << 11;
+const ACCESS_FS_MAKE_SYM: u64 = 1 << 12;
+// Available since Landlock ABI version 2.
+const ACCESS_FS_REFER: u64 = 1 << 13;
+// Available since Landlock ABI version 3.
+const ACCESS_FS_TRUNCATE: u64 = 1 << 14;
+
+// Access rights which apply to files. Other access rights only apply to directories.
+const ACCESS_FILE: u64 =
+    ACCESS_FS_EXECUTE | ACCESS_FS_WRITE_FILE | ACCESS_FS_READ_FILE | ACCESS_FS_TRUNCATE;
+
+const LANDLOCK_CREATE_RULESET_VERSION: u32 = 1 << 0;
+const LANDLOCK_RULE_PATH_BENEATH: u32 = 1;
+
+// Read-only access rule argument value.
+pub(crate) const READ_ONLY_ARG: &str = "ro";
+// Read-write access rule argument value.
+pub(crate) const READ_WRITE_ARG: &str = "rw";
+
+// Paths inside the jail which are always accessible to the jailed process, if they exist: the
+// device nodes created by the jailer, the default location of the API socket and the host CPU
+// information copied by the jailer on aarch64.
+const DEFAULT_RULES: [(&str, LandlockAccess); 3] = [
+    ("/dev", LandlockAccess::ReadWrite),
+    ("/run", LandlockAccess::ReadWrite),
+    ("/sys", LandlockAccess::ReadOnly),
+];
+
+#[repr(C)]
+struct LandlockRulesetAttr {
+    handled_access_fs: u64,
+}
+
+#[repr(C, packed)]
+struct LandlockPathBeneathAttr {
+    allowed_access: u64,
+    parent_fd: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LandlockAccess {
+    // Read files and list directories.
+    ReadOnly,
+    // Read, write, create and remove files and directories, except device nodes.
+    ReadWrite,
+    // Read and execute files.
+    Execute,
+}
+
+impl LandlockAccess {
+    fn access_fs(self) -> u64 {
+        match self {
+            LandlockAccess::ReadOnly => ACCESS_FS_READ_FILE | ACCESS_FS_READ_DIR,
+            LandlockAccess::ReadWrite => {
+                ACCESS_FS_READ_FILE
+                    | ACCESS_FS_READ_DIR
+                    | ACCESS_FS_WRITE_FILE
+                    | ACCESS_FS_REMOVE_DIR
+                    | ACCESS_FS_REMOVE_FILE
+                    | ACCESS_FS_MAKE_DIR
+                    | ACCESS_FS_MAKE_REG
+                    | ACCESS_FS_MAKE_SOCK
+                    | ACCESS_FS_MAKE_FIFO
+                    | ACCESS_FS_MAKE_SYM
+                    | ACCESS_FS_REFER
+                    | ACCESS_FS_TRUNCATE
+            }
+            LandlockAccess::Execute => ACCESS_FS_EXECUTE | ACCESS_FS_READ_FILE,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LandlockRules {
+    rules: Vec<(PathBuf, LandlockAccess)>,
+}
+
+impl LandlockRules {
+    // Allows the access to the path and everything beneath it.
+    pub fn add_rule(&mut self, path: PathBuf, access: LandlockAccess) {
+        self.rules.push((path, access));
+    }
+
+    // Returns whether no rule was added.
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    // Returns the rules allowing the access to the entries of the jail root, i.e. the files the
+    // jail was populated with for the jailed process (kernel, rootfs, sockets...), except for the
+    // paths to skip and those with a default rule. Read-only files get a read-only rule, and
+    // everything else a read-write rule.
+    pub fn from_jail_root(root: &Path, skip: &[PathBuf]) -> Result<Self, JailerError> {
+        let dir_open =
+            |err: std::io::Error| JailerError::DirOpen(root.display().to_string(), err.to_string());
+        let mut rules = LandlockRules::default();
+        for entry in fs::read_dir(root).map_err(dir_open)? {
+            let path = entry.map_err(dir_open)?.path();
+            let is_default = DEFAULT_RULES
+                .iter()
+                .any(|(default, _)| Path::new(default).file_name() == path.file_name());
+            if is_default || skip.contains(&path) {
+                continue;
+            }
+            let metadata =
+                fs::metadata(&path).map_err(|err| JailerError::Metadata(path.clone(), err))?;
+            let access = if metadata.is_file() && metadata.permissions().readonly() {
+                LandlockAccess::ReadOnly
+            } else {
+                LandlockAccess::ReadWrite
+            };
+            rules.add_rule(path, access);
+        }
+        Ok(rules)
+    }
+
+    // Restricts the filesystem access of the current thread, and of the processes it executes, to
+    // the paths of the rules, the default paths and the executable file. Files other than the
+    // executable file cannot be executed and device nodes cannot be created. This fails closed:
+    // nothing is left unrestricted when there is no rule or when the kernel does not support
+    // Landlock.
+    pub fn install(&self, exec_file: &Path) -> Result<(), JailerError> {
+        if self.rules.is_empty() {
+            return Err(JailerError::LandlockNoRules);
+        }
+        let abi = abi_version().ok_or(JailerError::LandlockUnsupported)?;
+        let handled_access_fs = handled_access_fs(abi);
+
+        let attr = LandlockRulesetAttr { handled_access_fs };
+        // SAFETY: Safe because `attr` is a valid ruleset attribute of the size passed.
+        let ruleset_fd = SyscallReturnCode(unsafe {
+            libc::syscall(
+                libc::SYS_landlock_create_ruleset,
+                &attr as *const LandlockRulesetAttr,
+                std::mem::size_of::<LandlockRulesetAttr>(),
+                0,
+            )
+        })
+        .into_result()
+        .map_err(JailerError::LandlockCreateRuleset)?;
+        // SAFETY: Safe because the file descriptor was just created and is owned by no one else.
+        #[allow(clippy::cast_possible_truncation)]
+        let ruleset_fd = unsafe { OwnedFd::from_raw_fd(ruleset_fd as i32) };
+
+        add_path_rule(
+            &ruleset_fd,
+            exec_file,
+            LandlockAccess::Execute,
+            handled_access_fs,
+        )?;
+        for (path, access) in DEFAULT_RULES.iter() {
+            if Path::new(path).exists() {
+                add_path_rule(&ruleset_fd, Path::new(path), *access, handled_access_fs)?;
+            }
+        }
+        for (path, access) in self.rules.iter() {
+            add_path_rule(&ruleset_fd, path, *access, handled_access_fs)?;
+        }
+
+        // Landlock requires either no_new_privs or CAP_SYS_ADMIN to restrict a thread. Setting
+        // no_new_privs does not prevent dropping privileges before exec.
+        // SAFETY: Safe because the parameters are valid.
+        SyscallReturnCode(unsafe { libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) })
+            .into_empty_result()
+            .map_err(JailerError::LandlockRestrictSelf)?;
+        // SAFETY: Safe because `ruleset_fd` is a valid Landlock ruleset file descriptor.
+        SyscallReturnCode(unsafe {
+            libc::syscall(libc::SYS_landlock_restrict_self, ruleset_fd.as_raw_fd(), 0)
+        })
+        .into_empty_result()
+        .map_err(JailerError::LandlockRestrictSelf)
+    }
+}
+
+// Returns the Landlock ABI version supported by the kernel, if any.
+fn abi_version() -> Option<i64> {
+    // SAFETY: Safe because querying the ABI version takes no ruleset attribute.
+    let abi = unsafe {
+        libc::syscall(
+            libc::SYS_landlock_create_ruleset,
+            std::ptr::null::<LandlockRulesetAttr>(),
+            0,
+            LANDLOCK_CREATE_RULESET_VERSION,
+        )
+    };
+    (abi > 0).then_some(abi)
+}
+
+// Returns the access rights handled by the ruleset, i.e. denied unless allowed by a rule, for a
+// Landlock ABI version.
+fn handled_access_fs(abi: i64) -> u64 {
+    // Access rights of the first ABI version.
+    let mut access = (ACCESS_FS_MAKE_SYM << 1) - 1;
+    if abi >= 2 {
+        access |= ACCESS_FS_REFER;
+    }
+    if abi >= 3 {
+        access |= ACCESS_FS_TRUNCATE;
+    }
+    // The ioctl access right on devices (ABI version 5) is not handled, as the jailed process
+    // needs to issue ioctls on all the devices it can open.
+    access
+}
+
+// Returns the access rights allowed on the path, given the access rights handled by the ruleset.
+fn allowed_access_fs(access: LandlockAccess, handled_access_fs: u64, is_dir: bool) -> u64 {
+    let allowed = access.access_fs() & handled_access_fs;
+    if is_dir {
+        allowed
+    } else {
+        allowed & ACCESS_FILE
+    }
+}
+
+fn add_path_rule(
+    ruleset_fd: &OwnedFd,
+    path: &Path,
+    access: LandlockAccess,
+    handled_access_fs: u64,
+) -> Result<(), JailerError> {
+    let is_dir = fs::metadata(path)
+        .map_err(|err| JailerError::Metadata(path.to_path_buf(), err))?
+        .is_dir();
+    let parent = OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_PATH | libc::O_CLOEXEC)
+        .open(path)
+        .map_err(|err| JailerError::FileOpen(path.to_path_buf(), err))?;
+
+    let attr = LandlockPathBeneathAttr {
+        allowed_access: allowed_access_fs(access, handled_access_fs, is_dir),
+        parent_fd: parent.as_raw_fd(),
+    };
+    // SAFETY: Safe because `ruleset_fd` is a valid Landlock ruleset file descriptor and `attr`
+    // is a valid path beneath attribute.
+    SyscallReturnCode(unsafe {
+        libc::syscall(
+            libc::SYS_landlock_add_rule,
+            ruleset_fd.as_raw_fd(),
+            LANDLOCK_RULE_PATH_BENEATH,
+            &attr as *const LandlockPathBeneathAttr,
+            0,
+        )
+    })
+    .into_empty_result()
+    .map_err(|err| JailerError::LandlockAddRule(path.to_path_buf(), err))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::thread;
+
+    use vmm_sys_util::tempdir::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn test_handled_access_fs() {
+        assert_eq!(handled_access_fs(1), 0x1fff);
+        assert_eq!(handled_access_fs(2), 0x3fff);
+        assert_eq!(handled_access_fs(3), 0x7fff);
+        assert_eq!(handled_access_fs(5), 0x7fff);
+    }
+
+    #[test]
+    fn test_allowed_access_fs() {
+        let handled = handled_access_fs(3);
+
+        assert_eq!(
+            allowed_access_fs(LandlockAccess::ReadOnly, handled, true),
+            ACCESS_FS_READ_FILE | ACCESS_FS_READ_DIR
+        );
+        assert_eq!(
+            allowed_access_fs(LandlockAccess::ReadOnly, handled, false),
+            ACCESS_FS_READ_FILE
+        );
+        assert_eq!(
+            allowed_access_fs(LandlockAccess::ReadWrite, handled, false),
+            ACCESS_FS_READ_FILE | ACCESS_FS_WRITE_FILE | ACCESS_FS_TRUNCATE
+        );
+        assert_eq!(
+            allowed_access_fs(LandlockAccess::Execute, handled, false),
+            ACCESS_FS_EXECUTE | ACCESS_FS_READ_FILE
+        );
+
+        // Device nodes can never be created.
+        let read_write = allowed_access_fs(LandlockAccess::ReadWrite, handled, true);
+        assert_eq!(read_write & (ACCESS_FS_MAKE_CHAR | ACCESS_FS_MAKE_BLOCK), 0);
+        assert_eq!(read_write & ACCESS_FS_EXECUTE, 0);
+
+        // Access rights not handled by the kernel are not allowed.
+        let read_write = allowed_access_fs(LandlockAccess::ReadWrite, handled_access_fs(1), true);
+        assert_eq!(read_write & (ACCESS_FS_REFER | ACCESS_FS_TRUNCATE), 0);
+    }
+
+    #[test]
+    fn test_from_jail_root() {
+        let root = TempDir::new().unwrap();
+        let root_path = root.as_path();
+        for name in ["clawdbox", "clawdbox.pid", "vmlinux", "rootfs.ext4"] {
+            File::create(root_path.join(name)).unwrap();
+        }
+        for name in ["dev", "run", "sys", "snapshots"] {
+            fs::create_dir(root_path.join(name)).unwrap();
+        }
+        let mut permissions = fs::metadata(root_path.join("vmlinux"))
+            .unwrap()
+            .permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(root_path.join("vmlinux"), permissions).unwrap();
+
+        let skip = [root_path.join("clawdbox"), root_path.join("clawdbox.pid")];
+        let mut rules = LandlockRules::from_jail_root(root_path, &skip)
+            .unwrap()
+            .rules;
+        rules.sort();
+        assert_eq!(
+            rules,
+            vec![
+                (root_path.join("rootfs.ext4"), LandlockAccess::ReadWrite),
+                (root_path.join("snapshots"), LandlockAccess::ReadWrite),
+                (root_path.join("vmlinux"), LandlockAccess::ReadOnly),
+            ]
+        );
+
+        // An empty jail gives no rule, which cannot be installed.
+        let root = TempDir::new().unwrap();
+        let rules = LandlockRules::from_jail_root(root.as_path(), &[]).unwrap();
+        assert!(rules.is_empty());
+        assert!(matches!(
+            rules.install(Path::new("/bin/true")),
+            Err(JailerError::LandlockNoRules)
+        ));
+    }
+
+    #[test]
+    fn test_install() {
+        if abi_version().is_none() {
+            let mut rules = LandlockRules::default();
+            rules.add_rule(PathBuf::from("/"), LandlockAccess::ReadOnly);
+            assert!(matches!(
+                rules.install(Path::new("/bin/true")),
+                Err(JailerError::LandlockUnsupported)
+            ));
+            return;
+        }
+
+        let allowed_dir = TempDir::new().unwrap();
+        let denied_dir = TempDir::new().unwrap();
+        let allowed_path = allowed_dir.as_path().to_path_buf();
+        let denied_path = denied_dir.as_path().to_path_buf();
+
+        // Landlock restricts the calling thread only.
+        thread::spawn(move || {
+            let mut rules = LandlockRules::default();
+            rules.add_rule(allowed_path.clone(), LandlockAccess::ReadWrite);
+            rules.install(Path::new("/bin/true")).unwrap();
+
+            File::create(allowed_path.join("file")).unwrap();
+            File::create(denied_path.join("file")).unwrap_err();
+        })
+        .join()
+        .unwrap();
+
+        // Rules on missing paths are rejected before restricting anything.
+        let mut rules = LandlockRules::default();
+        rules.add_rule(PathBuf::from("/invalid/path"), LandlockAccess::ReadOnly);
+        assert!(matches!(
+            rules.install(Path::new("/bin/true")),
+            Err(JailerError::Metadata(..))
+        ));
+    }
+}
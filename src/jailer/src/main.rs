@@ -17,7 +17,11 @@ use crate::env::Env;
 mod cgroup;
 mod chroot;
 mod env;
+mod landlock;
+mod netlink;
+mod network;
 mod resource_limits;
+mod userns;
 
 const JAILER_VERSION: &str = env!("CARGO_PKG_VERSION");
 
@@ -48,6 +52,8 @@ pub enum JailerError {
          cgroup under {0}, pass any --cgroup parameters."
     )]
     CgroupMove(PathBuf, io::Error),
+    #[error("{0} is only supported with cgroups v2")]
+    CgroupRequiresV2(String),
     #[error("Failed to change owner for {0}: {1}")]
     ChangeFileOwner(PathBuf, io::Error),
     #[error("Failed to chdir into chroot directory: {0}")]
@@ -70,6 +76,11 @@ pub enum JailerError {
     CStringParsing(NulError),
     #[error("Failed to daemonize: {0}")]
     Daemonize(io::Error),
+    #[error(
+        "Cannot access {0} as the calling user: {1}.\nHint: The user needs read and write \
+         permissions on {0}, e.g. by being a member of the group owning it."
+    )]
+    DevAccess(String, io::Error),
     #[error("Failed to open directory {0}: {1}")]
     DirOpen(String, String),
     #[error("Failed to duplicate fd: {0}")]
@@ -94,6 +105,18 @@ pub enum JailerError {
     HardLink(PathBuf),
     #[error("Invalid instance ID: {0}")]
     InvalidInstanceId(validators::ValidatorError),
+    #[error("{}", format!("Failed to add Landlock rule for {:?}: {}", .0, .1).replace('\"', ""))]
+    LandlockAddRule(PathBuf, io::Error),
+    #[error("Failed to create Landlock ruleset: {0}")]
+    LandlockCreateRuleset(io::Error),
+    #[error("No Landlock rule to restrict the jailed process to")]
+    LandlockNoRules,
+    #[error("Failed to restrict the process with Landlock: {0}")]
+    LandlockRestrictSelf(io::Error),
+    #[error("Invalid format for Landlock rule: {0}")]
+    LandlockRuleFormat(String),
+    #[error("Landlock is not supported by the host kernel")]
+    LandlockUnsupported,
     #[error("Cannot get metadata for a file: {0}: {1}")]
     Metadata(PathBuf, io::Error),
     #[error("{}", format!("File {:?} doesn't have a parent", .0).replace('\"', ""))]
@@ -104,8 +127,14 @@ pub enum JailerError {
     MknodDev(io::Error, String),
     #[error("Failed to bind mount the jail root directory: {0}")]
     MountBind(io::Error),
+    #[error("Failed to bind mount {0} inside the jail: {1}")]
+    MountBindDev(String, io::Error),
     #[error("Failed to change the propagation type to slave: {0}")]
     MountPropagationSlave(io::Error),
+    #[error("Failed to {0} through netlink: {1}")]
+    Netlink(String, io::Error),
+    #[error("Invalid network argument: {0}")]
+    NetworkArgument(String),
     #[error("{}", format!("{:?} is not a file", .0).replace('\"', ""))]
     NotAFile(PathBuf),
     #[error("{}", format!("{:?} is not a directory", .0).replace('\"', ""))]
@@ -136,6 +165,8 @@ pub enum JailerError {
     SetNetNs(io::Error),
     #[error("Failed to set limit for resource: {0}")]
     Setrlimit(String),
+    #[error("Failed to create the TAP device {0}: {1}")]
+    Tap(String, io::Error),
     #[error("Failed to daemonize: setsid: {0}")]
     SetSid(io::Error),
     #[error("Invalid uid: {0}")]
@@ -144,9 +175,18 @@ pub enum JailerError {
     UmountOldRoot(io::Error),
     #[error("Unexpected value for the socket listener fd: {0}")]
     UnexpectedListenerFd(i32),
+    #[error("Failed to unshare into new network namespace: {0}")]
+    UnshareNewNetNs(io::Error),
+    #[error("Failed to unshare into new user namespace: {0}")]
+    UnshareNewUserNs(io::Error),
+    #[error(
+        "The uid and gid must be the ones of the calling user ({0}:{1}) when using a new user \
+         namespace"
+    )]
+    UserNsIds(u32, u32),
     #[error("Failed to unshare into new mount namespace: {0}")]
     UnshareNewNs(io::Error),
-    #[error("Failed to unset the O_CLOEXEC flag on the socket fd: {0}")]
+    #[error("Failed to unset the O_CLOEXEC flag on the vCPU cgroup fd: {0}")]
     UnsetCloexec(io::Error),
     #[error("Slice contains invalid UTF-8 data : {0}")]
     UTF8Parsing(std::str::Utf8Error),
@@ -193,6 +233,36 @@ pub fn build_arg_parser() -> ArgParser<'static> {
                 .takes_value(true)
                 .help("Path to the network namespace this microVM should join."),
         )
+        .arg(
+            Argument::new("new-netns")
+                .takes_value(false)
+                .forbids(vec!["netns"])
+                .help("Run the microVM in a new network namespace, created by the jailer."),
+        )
+        .arg(Argument::new("tap").takes_value(true).help(
+            "Name of a TAP device to create in the network namespace of the microVM. The device \
+             is persistent and owned by the uid and gid of the jailed process.",
+        ))
+        .arg(
+            Argument::new("veth")
+                .takes_value(true)
+                .requires("new-netns")
+                .help(
+                    "Name of the host end of a veth pair to create between the host and the new \
+                     network namespace, in which the other end is named veth0.",
+                ),
+        )
+        .arg(
+            Argument::new("veth-addrs")
+                .takes_value(true)
+                .requires("veth")
+                .help(
+                    "IPv4 addresses of the host and namespace ends of the veth pair, following \
+                     this format: <host_cidr>,<netns_cidr> (e.g 10.0.0.1/30,10.0.0.2/30). The \
+                     host end becomes the default gateway of the network namespace, and \
+                     forwarding is enabled inside of it.",
+                ),
+        )
         .arg(Argument::new("daemonize").takes_value(false).help(
             "Daemonize the jailer before exec, by invoking setsid(), and redirecting the standard \
              I/O file descriptors to /dev/null.",
@@ -202,6 +272,12 @@ pub fn build_arg_parser() -> ArgParser<'static> {
                 .takes_value(false)
                 .help("Exec into a new PID namespace."),
         )
+        .arg(Argument::new("new-user-ns").takes_value(false).help(
+            "Run the jailer without privileges, from a new user namespace. The uid and gid must \
+             be the ones of the calling user, which owns the jail and the backing files of the \
+             microVM. The devices of the host are bind mounted in the jail instead of being \
+             created, so the calling user needs read and write access to /dev/kvm.",
+        ))
         .arg(Argument::new("cgroup").allow_multiple(true).help(
             "Cgroup and value to be set by the jailer. It must follow this format: \
              <cgroup_file>=<value> (e.g cpu.shares=10). This argument can be used multiple times \
@@ -215,11 +291,57 @@ pub fn build_arg_parser() -> ArgParser<'static> {
              value one greater than the maximum file descriptor number that can be opened by this \
              process.",
         ))
+        .arg(Argument::new("landlock").takes_value(false).help(
+            "Restrict the filesystem access of the jailed process with Landlock. By default, the \
+             jailed process can only access the files and directories the jail root contains \
+             right before exec, such as the kernel, the rootfs and the sockets. Files other than \
+             the exec file cannot be executed and device nodes cannot be created. The jailer \
+             fails if the host kernel does not support Landlock or if there is nothing to \
+             restrict the jailed process to.",
+        ))
+        .arg(
+            Argument::new("landlock-rule")
+                .allow_multiple(true)
+                .requires("landlock")
+                .help(
+                    "Path inside the jail to which the jailed process is restricted, with its \
+                     access. It must follow this format: <path>=<ro|rw> (e.g /rootfs.ext4=rw). \
+                     When used, the jailed process can only access the paths of the rules, /dev, \
+                     /run and /sys, instead of the contents of the jail root. This argument can \
+                     be used multiple times to add multiple paths.",
+                ),
+        )
         .arg(
             Argument::new("cgroup-version")
                 .takes_value(true)
                 .default_value("1")
-                .help("Select the cgroup version used by the jailer."),
+                .help(
+                    "Select the cgroup version used by the jailer: 1, 2, or auto to detect it \
+                     from the cgroup hierarchies mounted on the host.",
+                ),
+        )
+        .arg(Argument::new("cpu-max").takes_value(true).help(
+            "Value of the cpu.max cgroupv2 knob of the microVM, following this format: \
+             <quota|max> [<period>] (e.g 50000 100000), both in microseconds.",
+        ))
+        .arg(Argument::new("memory-max").takes_value(true).help(
+            "Value of the memory.max cgroupv2 knob of the microVM, following this format: \
+             <bytes|max>, where bytes can have a K, M, G or T suffix (e.g 512M).",
+        ))
+        .arg(Argument::new("io-max").allow_multiple(true).help(
+            "Value of the io.max cgroupv2 knob of the microVM for one device, following this \
+             format: <major>:<minor> <key>=<value>... where key is one of rbps, wbps, riops and \
+             wiops (e.g \"8:16 rbps=2097152 wiops=120\"). This argument can be used multiple \
+             times to limit multiple devices.",
+        ))
+        .arg(
+            Argument::new("vcpu-threaded-cgroup")
+                .takes_value(false)
+                .help(
+                    "Create a threaded cgroup named vcpus under the cgroup of the microVM, with \
+                     the threaded controllers enabled, and move the vCPU threads to it when they \
+                     start. Only supported with cgroups v2.",
+                ),
         )
         .arg(
             Argument::new("parent-cgroup")
@@ -0,0 +1,383 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::io;
+use std::net::Ipv4Addr;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+
+use vmm_sys_util::syscall::SyscallReturnCode;
+
+use super::JailerError;
+
+// Netlink and rtnetlink definitions, as found in include/uapi/linux/{netlink,rtnetlink,
+// if_link,if_addr,veth}.h.
+const NETLINK_ROUTE: i32 = 0;
+const NLMSG_ERROR: u16 = 2;
+const NLM_F_REQUEST: u16 = 0x1;
+const NLM_F_ACK: u16 = 0x4;
+const NLM_F_EXCL: u16 = 0x200;
+const NLM_F_CREATE: u16 = 0x400;
+const RTM_NEWLINK: u16 = 16;
+const RTM_GETLINK: u16 = 18;
+const RTM_NEWADDR: u16 = 20;
+const RTM_NEWROUTE: u16 = 24;
+const IFLA_IFNAME: u16 = 3;
+const IFLA_LINKINFO: u16 = 18;
+const IFLA_NET_NS_FD: u16 = 28;
+const IFLA_INFO_KIND: u16 = 1;
+const IFLA_INFO_DATA: u16 = 2;
+const VETH_INFO_PEER: u16 = 1;
+const IFA_ADDRESS: u16 = 1;
+const IFA_LOCAL: u16 = 2;
+const RTA_GATEWAY: u16 = 5;
+const RT_TABLE_MAIN: u8 = 254;
+const RTPROT_BOOT: u8 = 3;
+const RT_SCOPE_UNIVERSE: u8 = 0;
+const RTN_UNICAST: u8 = 1;
+const IFF_UP: u32 = 0x1;
+
+// Sizes of the netlink header and of the rtnetlink family headers.
+const NLMSG_HDR_LEN: usize = 16;
+const IFINFOMSG_LEN: usize = 16;
+const IFADDRMSG_LEN: usize = 8;
+const RTMSG_LEN: usize = 12;
+
+// Size of the buffer netlink replies are received in.
+const RECV_BUF_LEN: usize = 8192;
+
+// Rounds a length up to the 4 bytes alignment of netlink messages and attributes.
+fn align(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+// Builds the `ifinfomsg` header of link messages.
+fn ifinfomsg(index: i32, flags: u32, change: u32) -> [u8; IFINFOMSG_LEN] {
+    let mut msg = [0u8; IFINFOMSG_LEN];
+    // The address family and device type are left unspecified.
+    msg[4..8].copy_from_slice(&index.to_ne_bytes());
+    msg[8..12].copy_from_slice(&flags.to_ne_bytes());
+    msg[12..16].copy_from_slice(&change.to_ne_bytes());
+    msg
+}
+
+// Netlink request, built by appending the family header and the attributes to the netlink
+// header.
+#[derive(Debug)]
+struct NetlinkMessage {
+    buf: Vec<u8>,
+    // Offsets of the nested attributes which are not complete yet.
+    nested: Vec<usize>,
+}
+
+impl NetlinkMessage {
+    fn new(msg_type: u16, flags: u16) -> Self {
+        let mut buf = vec![0u8; NLMSG_HDR_LEN];
+        buf[4..6].copy_from_slice(&msg_type.to_ne_bytes());
+        buf[6..8].copy_from_slice(&(flags | NLM_F_REQUEST | NLM_F_ACK).to_ne_bytes());
+        NetlinkMessage {
+            buf,
+            nested: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+        self.buf.resize(align(self.buf.len()), 0);
+    }
+
+    fn push_attr(&mut self, attr_type: u16, payload: &[u8]) {
+        // Attribute lengths fit in 16 bits since they are bounded by the interface names
+        // and addresses.
+        let len = u16::try_from(4 + payload.len()).unwrap();
+        self.buf.extend_from_slice(&len.to_ne_bytes());
+        self.buf.extend_from_slice(&attr_type.to_ne_bytes());
+        self.push(payload);
+    }
+
+    fn push_str_attr(&mut self, attr_type: u16, value: &str) {
+        let mut payload = value.as_bytes().to_vec();
+        payload.push(0);
+        self.push_attr(attr_type, &payload);
+    }
+
+    fn begin_nested(&mut self, attr_type: u16) {
+        self.nested.push(self.buf.len());
+        self.buf.extend_from_slice(&[0, 0]);
+        self.buf.extend_from_slice(&attr_type.to_ne_bytes());
+    }
+
+    fn end_nested(&mut self) {
+        // Safe to unwrap as nested attributes are always begun before being ended.
+        let start = self.nested.pop().unwrap();
+        let len = u16::try_from(self.buf.len() - start).unwrap();
+        self.buf[start..start + 2].copy_from_slice(&len.to_ne_bytes());
+    }
+
+    fn finish(mut self, seq: u32) -> Vec<u8> {
+        let len = u32::try_from(self.buf.len()).unwrap();
+        self.buf[0..4].copy_from_slice(&len.to_ne_bytes());
+        self.buf[8..12].copy_from_slice(&seq.to_ne_bytes());
+        self.buf
+    }
+}
+
+// Netlink reply, holding the message type and the payload following the netlink header.
+#[derive(Debug)]
+struct NetlinkReply {
+    msg_type: u16,
+    payload: Vec<u8>,
+}
+
+// Splits a buffer of netlink messages into replies. Returns whether the acknowledgement of the
+// request was found, or the error the request failed with.
+fn parse_replies(buf: &[u8], replies: &mut Vec<NetlinkReply>) -> io::Result<bool> {
+    let mut offset = 0;
+    while offset + NLMSG_HDR_LEN <= buf.len() {
+        let len = u32::from_ne_bytes(buf[offset..offset + 4].try_into().unwrap()) as usize;
+        let msg_type = u16::from_ne_bytes(buf[offset + 4..offset + 6].try_into().unwrap());
+        if len < NLMSG_HDR_LEN || offset + len > buf.len() {
+            return Err(io::Error::from(io::ErrorKind::InvalidData));
+        }
+        let payload = &buf[offset + NLMSG_HDR_LEN..offset + len];
+        if msg_type == NLMSG_ERROR {
+            let errno = payload
+                .get(0..4)
+                .map(|errno| i32::from_ne_bytes(errno.try_into().unwrap()))
+                .ok_or_else(|| io::Error::from(io::ErrorKind::InvalidData))?;
+            // An error code of 0 acknowledges the request.
+            return match errno {
+                0 => Ok(true),
+                errno => Err(io::Error::from_raw_os_error(-errno)),
+            };
+        }
+        replies.push(NetlinkReply {
+            msg_type,
+            payload: payload.to_vec(),
+        });
+        offset += align(len);
+    }
+    Ok(false)
+}
+
+// Route netlink socket. The socket operates on the network namespace it was created in, even
+// after the process moves to another network namespace.
+#[derive(Debug)]
+pub struct NetlinkSocket {
+    fd: OwnedFd,
+    seq: u32,
+}
+
+impl NetlinkSocket {
+    pub fn new() -> Result<Self, JailerError> {
+        // SAFETY: Safe because the parameters are valid.
+        let fd = SyscallReturnCode(unsafe {
+            libc::socket(
+                libc::AF_NETLINK,
+                libc::SOCK_RAW | libc::SOCK_CLOEXEC,
+                NETLINK_ROUTE,
+            )
+        })
+        .into_result()
+        .map_err(|err| JailerError::Netlink("open socket".to_string(), err))?;
+        // SAFETY: Safe because the file descriptor was just created and is owned by no one else.
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+        Ok(NetlinkSocket { fd, seq: 0 })
+    }
+
+    // Sends a request and waits for its acknowledgement, returning the replies received before.
+    fn request(&mut self, msg: NetlinkMessage, op: &str) -> Result<Vec<NetlinkReply>, JailerError> {
+        let map_err = |err| JailerError::Netlink(op.to_string(), err);
+
+        self.seq = self.seq.wrapping_add(1);
+        let buf = msg.finish(self.seq);
+        // SAFETY: Safe because the buffer is valid for its length.
+        SyscallReturnCode(unsafe {
+            libc::send(self.fd.as_raw_fd(), buf.as_ptr().cast(), buf.len(), 0)
+        })
+        .into_result()
+        .map_err(map_err)?;
+
+        let mut replies = Vec::new();
+        let mut recv_buf = vec![0u8; RECV_BUF_LEN];
+        loop {
+            // SAFETY: Safe because the buffer is valid for its length.
+            let len = SyscallReturnCode(unsafe {
+                libc::recv(
+                    self.fd.as_raw_fd(),
+                    recv_buf.as_mut_ptr().cast(),
+                    recv_buf.len(),
+                    0,
+                )
+            })
+            .into_result()
+            .map_err(map_err)?;
+            // Safe to unwrap as the length was checked to be positive.
+            let len = usize::try_from(len).unwrap();
+            if parse_replies(&recv_buf[..len], &mut replies).map_err(map_err)? {
+                return Ok(replies);
+            }
+        }
+    }
+
+    // Returns the index of the interface with the given name.
+    pub fn link_index(&mut self, name: &str) -> Result<i32, JailerError> {
+        let op = format!("get the index of {name}");
+        let mut msg = NetlinkMessage::new(RTM_GETLINK, 0);
+        msg.push(&ifinfomsg(0, 0, 0));
+        msg.push_str_attr(IFLA_IFNAME, name);
+        self.request(msg, &op)?
+            .iter()
+            .find(|reply| reply.msg_type == RTM_NEWLINK && reply.payload.len() >= IFINFOMSG_LEN)
+            .map(|reply| i32::from_ne_bytes(reply.payload[4..8].try_into().unwrap()))
+            .ok_or_else(|| JailerError::Netlink(op, io::Error::from(io::ErrorKind::NotFound)))
+    }
+
+    // Brings the interface with the given index up.
+    pub fn set_link_up(&mut self, index: i32) -> Result<(), JailerError> {
+        let mut msg = NetlinkMessage::new(RTM_NEWLINK, 0);
+        msg.push(&ifinfomsg(index, IFF_UP, IFF_UP));
+        self.request(msg, "bring the interface up").map(|_| ())
+    }
+
+    // Creates a veth pair, whose peer is created in the network namespace referred to by
+    // `peer_netns_fd`.
+    pub fn create_veth(
+        &mut self,
+        name: &str,
+        peer_name: &str,
+        peer_netns_fd: RawFd,
+    ) -> Result<(), JailerError> {
+        let mut msg = NetlinkMessage::new(RTM_NEWLINK, NLM_F_CREATE | NLM_F_EXCL);
+        msg.push(&ifinfomsg(0, 0, 0));
+        msg.push_str_attr(IFLA_IFNAME, name);
+        msg.begin_nested(IFLA_LINKINFO);
+        msg.push_str_attr(IFLA_INFO_KIND, "veth");
+        msg.begin_nested(IFLA_INFO_DATA);
+        msg.begin_nested(VETH_INFO_PEER);
+        msg.push(&ifinfomsg(0, 0, 0));
+        msg.push_str_attr(IFLA_IFNAME, peer_name);
+        msg.push_attr(IFLA_NET_NS_FD, &peer_netns_fd.to_ne_bytes());
+        msg.end_nested();
+        msg.end_nested();
+        msg.end_nested();
+        self.request(msg, "create the veth pair").map(|_| ())
+    }
+
+    // Assigns an IPv4 address to the interface with the given index.
+    pub fn add_address(
+        &mut self,
+        index: i32,
+        addr: Ipv4Addr,
+        prefix_len: u8,
+    ) -> Result<(), JailerError> {
+        let mut msg = NetlinkMessage::new(RTM_NEWADDR, NLM_F_CREATE | NLM_F_EXCL);
+        let mut ifaddrmsg = [0u8; IFADDRMSG_LEN];
+        // Safe to unwrap as AF_INET fits in a byte.
+        ifaddrmsg[0] = u8::try_from(libc::AF_INET).unwrap();
+        ifaddrmsg[1] = prefix_len;
+        ifaddrmsg[3] = RT_SCOPE_UNIVERSE;
+        ifaddrmsg[4..8].copy_from_slice(&index.to_ne_bytes());
+        msg.push(&ifaddrmsg);
+        msg.push_attr(IFA_LOCAL, &addr.octets());
+        msg.push_attr(IFA_ADDRESS, &addr.octets());
+        self.request(msg, "assign the address").map(|_| ())
+    }
+
+    // Adds an IPv4 default route through the given gateway.
+    pub fn add_default_route(&mut self, gateway: Ipv4Addr) -> Result<(), JailerError> {
+        let mut msg = NetlinkMessage::new(RTM_NEWROUTE, NLM_F_CREATE | NLM_F_EXCL);
+        let mut rtmsg = [0u8; RTMSG_LEN];
+        rtmsg[0] = u8::try_from(libc::AF_INET).unwrap();
+        rtmsg[4] = RT_TABLE_MAIN;
+        rtmsg[5] = RTPROT_BOOT;
+        rtmsg[6] = RT_SCOPE_UNIVERSE;
+        rtmsg[7] = RTN_UNICAST;
+        msg.push(&rtmsg);
+        msg.push_attr(RTA_GATEWAY, &gateway.octets());
+        self.request(msg, "add the default route").map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_netlink_message() {
+        let mut msg = NetlinkMessage::new(RTM_NEWLINK, NLM_F_CREATE);
+        msg.push(&ifinfomsg(3, IFF_UP, IFF_UP));
+        msg.begin_nested(IFLA_LINKINFO);
+        msg.push_str_attr(IFLA_INFO_KIND, "veth");
+        msg.end_nested();
+        let buf = msg.finish(7);
+
+        // Netlink header.
+        assert_eq!(buf.len(), 48);
+        assert_eq!(u32::from_ne_bytes(buf[0..4].try_into().unwrap()), 48);
+        assert_eq!(
+            u16::from_ne_bytes(buf[4..6].try_into().unwrap()),
+            RTM_NEWLINK
+        );
+        assert_eq!(
+            u16::from_ne_bytes(buf[6..8].try_into().unwrap()),
+            NLM_F_REQUEST | NLM_F_ACK | NLM_F_CREATE
+        );
+        assert_eq!(u32::from_ne_bytes(buf[8..12].try_into().unwrap()), 7);
+        // Link header.
+        assert_eq!(i32::from_ne_bytes(buf[20..24].try_into().unwrap()), 3);
+        assert_eq!(u32::from_ne_bytes(buf[24..28].try_into().unwrap()), IFF_UP);
+        // Nested attribute, holding a padded string attribute.
+        assert_eq!(u16::from_ne_bytes(buf[32..34].try_into().unwrap()), 16);
+        assert_eq!(
+            u16::from_ne_bytes(buf[34..36].try_into().unwrap()),
+            IFLA_LINKINFO
+        );
+        assert_eq!(u16::from_ne_bytes(buf[36..38].try_into().unwrap()), 9);
+        assert_eq!(
+            u16::from_ne_bytes(buf[38..40].try_into().unwrap()),
+            IFLA_INFO_KIND
+        );
+        assert_eq!(&buf[40..44], b"veth");
+    }
+
+    fn nlmsg(msg_type: u16, payload: &[u8]) -> Vec<u8> {
+        let mut msg = NetlinkMessage::new(msg_type, 0);
+        msg.push(payload);
+        msg.finish(1)
+    }
+
+    #[test]
+    fn test_parse_replies() {
+        let mut replies = Vec::new();
+
+        // Reply followed by the acknowledgement.
+        let mut buf = nlmsg(RTM_NEWLINK, &ifinfomsg(5, 0, 0));
+        buf.extend(nlmsg(NLMSG_ERROR, &0i32.to_ne_bytes()));
+        assert!(parse_replies(&buf, &mut replies).unwrap());
+        assert_eq!(replies.len(), 1);
+        assert_eq!(replies[0].msg_type, RTM_NEWLINK);
+        assert_eq!(replies[0].payload, ifinfomsg(5, 0, 0));
+
+        // No acknowledgement yet.
+        replies.clear();
+        let buf = nlmsg(RTM_NEWLINK, &ifinfomsg(5, 0, 0));
+        assert!(!parse_replies(&buf, &mut replies).unwrap());
+
+        // Error.
+        let buf = nlmsg(NLMSG_ERROR, &(-libc::EEXIST).to_ne_bytes());
+        assert_eq!(
+            parse_replies(&buf, &mut replies)
+                .unwrap_err()
+                .raw_os_error(),
+            Some(libc::EEXIST)
+        );
+
+        // Truncated message.
+        let buf = nlmsg(RTM_NEWLINK, &ifinfomsg(5, 0, 0));
+        assert_eq!(
+            parse_replies(&buf[..20], &mut replies).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+}
@@ -0,0 +1,278 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::net::Ipv4Addr;
+use std::os::fd::AsRawFd;
+use std::os::raw::{c_int, c_short, c_uint, c_ulong};
+use std::str::FromStr;
+
+use vmm_sys_util::ioctl::{ioctl_with_mut_ref, ioctl_with_val};
+use vmm_sys_util::ioctl_iow_nr;
+use vmm_sys_util::syscall::SyscallReturnCode;
+
+use super::{JailerError, writeln_special};
+use crate::netlink::NetlinkSocket;
+
+// Name of the end of the veth pair which lives in the network namespace of the microVM.
+pub const VETH_NS_IFNAME: &str = "veth0";
+
+// As defined in the Linux UAPI, include/uapi/linux/if.h.
+const IFNAMSIZ: usize = 16;
+
+// TUN/TAP definitions, as found in include/uapi/linux/if_tun.h. The TAP device is configured
+// the same way clawdbox opens it.
+const TUNTAP: c_uint = 84;
+ioctl_iow_nr!(TUNSETIFF, TUNTAP, 202, c_int);
+ioctl_iow_nr!(TUNSETPERSIST, TUNTAP, 203, c_int);
+ioctl_iow_nr!(TUNSETOWNER, TUNTAP, 204, c_int);
+ioctl_iow_nr!(TUNSETGROUP, TUNTAP, 206, c_int);
+const IFF_TAP: c_short = 0x0002;
+const IFF_NO_PI: c_short = 0x1000;
+const IFF_VNET_HDR: c_short = 0x4000;
+
+const DEV_NET_TUN: &str = "/dev/net/tun";
+const HOST_NETNS: &str = "/proc/self/ns/net";
+const IP_FORWARD: &str = "/proc/sys/net/ipv4/ip_forward";
+
+// Layout of `struct ifreq`, restricted to the fields used by the TUN/TAP ioctls.
+#[repr(C)]
+#[derive(Debug, Default)]
+struct IfReq {
+    name: [u8; IFNAMSIZ],
+    flags: c_short,
+    _pad: [u8; 22],
+}
+
+// IPv4 address along with the length of the prefix of its network, in CIDR notation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ipv4Cidr {
+    addr: Ipv4Addr,
+    prefix_len: u8,
+}
+
+impl FromStr for Ipv4Cidr {
+    type Err = JailerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || JailerError::NetworkArgument(format!("Invalid IPv4 CIDR: {s}"));
+        let (addr, prefix_len) = s.split_once('/').ok_or_else(err)?;
+        let addr = addr.parse::<Ipv4Addr>().map_err(|_| err())?;
+        let prefix_len = prefix_len
+            .parse::<u8>()
+            .ok()
+            .filter(|len| *len <= 32)
+            .ok_or_else(err)?;
+        Ok(Ipv4Cidr { addr, prefix_len })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VethConfig {
+    host_ifname: String,
+    // Addresses of the host end and of the namespace end of the pair.
+    addrs: Option<(Ipv4Cidr, Ipv4Cidr)>,
+}
+
+impl VethConfig {
+    pub fn new(host_ifname: &str, addrs: Option<&str>) -> Result<Self, JailerError> {
+        validate_ifname(host_ifname)?;
+        let addrs = match addrs {
+            Some(addrs) => {
+                let (host, ns) = addrs.split_once(',').ok_or_else(|| {
+                    JailerError::NetworkArgument(format!(
+                        "The veth addresses must follow the <host_cidr>,<netns_cidr> format: \
+                         {addrs}"
+                    ))
+                })?;
+                Some((host.parse()?, ns.parse()?))
+            }
+            None => None,
+        };
+        Ok(VethConfig {
+            host_ifname: host_ifname.to_string(),
+            addrs,
+        })
+    }
+}
+
+// Network resources provisioned by the jailer for the microVM.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct NetworkConfig {
+    pub new_netns: bool,
+    pub tap: Option<String>,
+    pub veth: Option<VethConfig>,
+}
+
+impl NetworkConfig {
+    pub fn is_empty(&self) -> bool {
+        !self.new_netns && self.tap.is_none() && self.veth.is_none()
+    }
+
+    // Moves the process into a new network namespace if requested, then creates the veth pair
+    // to the host and the TAP device of the microVM inside of the current network namespace.
+    pub fn setup(&self, uid: u32, gid: u32) -> Result<(), JailerError> {
+        // The host end of the veth pair has to be configured from the host network namespace,
+        // so both a netlink socket and a handle on it are opened before leaving it.
+        let host = match self.veth {
+            Some(_) => Some((
+                NetlinkSocket::new()?,
+                File::open(HOST_NETNS)
+                    .map_err(|err| JailerError::FileOpen(HOST_NETNS.into(), err))?,
+            )),
+            None => None,
+        };
+
+        if self.new_netns {
+            // SAFETY: Safe because we are passing valid parameters.
+            SyscallReturnCode(unsafe { libc::unshare(libc::CLONE_NEWNET) })
+                .into_empty_result()
+                .map_err(JailerError::UnshareNewNetNs)?;
+        }
+
+        let mut netlink = NetlinkSocket::new()?;
+        if self.new_netns {
+            let lo = netlink.link_index("lo")?;
+            netlink.set_link_up(lo)?;
+        }
+
+        if let (Some(veth), Some((mut host_netlink, host_netns))) = (&self.veth, host) {
+            netlink.create_veth(VETH_NS_IFNAME, &veth.host_ifname, host_netns.as_raw_fd())?;
+
+            let host_index = host_netlink.link_index(&veth.host_ifname)?;
+            let ns_index = netlink.link_index(VETH_NS_IFNAME)?;
+            if let Some((host_cidr, ns_cidr)) = veth.addrs {
+                host_netlink.add_address(host_index, host_cidr.addr, host_cidr.prefix_len)?;
+                netlink.add_address(ns_index, ns_cidr.addr, ns_cidr.prefix_len)?;
+            }
+            host_netlink.set_link_up(host_index)?;
+            netlink.set_link_up(ns_index)?;
+            if let Some((host_cidr, _)) = veth.addrs {
+                netlink.add_default_route(host_cidr.addr)?;
+                // Traffic of the microVM is routed from its TAP device to the veth pair.
+                writeln_special(&IP_FORWARD, 1)?;
+            }
+        }
+
+        if let Some(ref tap) = self.tap {
+            create_tap(tap, uid, gid)?;
+            let index = netlink.link_index(tap)?;
+            netlink.set_link_up(index)?;
+        }
+
+        Ok(())
+    }
+}
+
+// Checks the name is a valid network interface name.
+pub fn validate_ifname(name: &str) -> Result<(), JailerError> {
+    if name.is_empty()
+        || name.len() >= IFNAMSIZ
+        || name == "."
+        || name == ".."
+        || name
+            .chars()
+            .any(|c| c == '/' || c == ':' || c.is_whitespace())
+    {
+        return Err(JailerError::NetworkArgument(format!(
+            "Invalid interface name: {name}"
+        )));
+    }
+    Ok(())
+}
+
+// Creates a persistent TAP device, owned by the user the microVM runs as, so that it can be
+// opened by the jailed process without any privileges.
+fn create_tap(name: &str, uid: u32, gid: u32) -> Result<(), JailerError> {
+    let map_err = |err| JailerError::Tap(name.to_string(), err);
+
+    let tun = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(DEV_NET_TUN)
+        .map_err(map_err)?;
+
+    let mut ifreq = IfReq {
+        flags: IFF_TAP | IFF_NO_PI | IFF_VNET_HDR,
+        ..Default::default()
+    };
+    ifreq.name[..name.len()].copy_from_slice(name.as_bytes());
+
+    // SAFETY: Safe because the fd is valid, and ifreq lives for the duration of the call.
+    if unsafe { ioctl_with_mut_ref(&tun, TUNSETIFF(), &mut ifreq) } < 0 {
+        return Err(map_err(io::Error::last_os_error()));
+    }
+    for (ioctl, value) in [
+        (TUNSETOWNER(), c_ulong::from(uid)),
+        (TUNSETGROUP(), c_ulong::from(gid)),
+        (TUNSETPERSIST(), 1),
+    ] {
+        // SAFETY: Safe because the fd is valid, and these ioctls take their argument by value.
+        if unsafe { ioctl_with_val(&tun, ioctl, value) } < 0 {
+            return Err(map_err(io::Error::last_os_error()));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ipv4_cidr() {
+        assert_eq!(
+            "10.0.0.1/30".parse::<Ipv4Cidr>().unwrap(),
+            Ipv4Cidr {
+                addr: Ipv4Addr::new(10, 0, 0, 1),
+                prefix_len: 30
+            }
+        );
+        for cidr in ["10.0.0.1", "10.0.0.1/33", "10.0.0/24", "10.0.0.1/", "/24"] {
+            assert!(
+                matches!(
+                    cidr.parse::<Ipv4Cidr>(),
+                    Err(JailerError::NetworkArgument(_))
+                ),
+                "{cidr}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_ifname() {
+        validate_ifname("tap0").unwrap();
+        validate_ifname("a23456789012345").unwrap();
+        for name in ["", "a234567890123456", "tap/0", "tap:0", "tap 0", ".", ".."] {
+            assert!(
+                matches!(validate_ifname(name), Err(JailerError::NetworkArgument(_))),
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_veth_config() {
+        let veth = VethConfig::new("veth-vm0", Some("10.0.0.1/30,10.0.0.2/30")).unwrap();
+        assert_eq!(veth.host_ifname, "veth-vm0");
+        assert_eq!(
+            veth.addrs,
+            Some((
+                "10.0.0.1/30".parse().unwrap(),
+                "10.0.0.2/30".parse().unwrap()
+            ))
+        );
+        assert_eq!(VethConfig::new("veth-vm0", None).unwrap().addrs, None);
+
+        VethConfig::new("veth-vm0", Some("10.0.0.1/30")).unwrap_err();
+        VethConfig::new("veth-vm0", Some("10.0.0.1/30,10.0.0.2")).unwrap_err();
+        VethConfig::new("", None).unwrap_err();
+    }
+
+    #[test]
+    fn test_ifreq_layout() {
+        assert_eq!(std::mem::size_of::<IfReq>(), 40);
+    }
+}
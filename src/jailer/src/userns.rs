@@ -0,0 +1,132 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::ffi::CStr;
+use std::fs::{self, File};
+use std::path::Path;
+use std::ptr::null;
+
+use vmm_sys_util::syscall::SyscallReturnCode;
+
+use super::{JailerError, to_cstring, writeln_special};
+
+const ROOT_DIR: &CStr = c"/";
+
+// Checks the calling user has read and write access to a device of the host.
+pub fn check_dev_access(dev_path: &CStr) -> Result<(), JailerError> {
+    // SAFETY: Safe because dev_path is CStr, and hence null-terminated.
+    SyscallReturnCode(unsafe { libc::access(dev_path.as_ptr(), libc::R_OK | libc::W_OK) })
+        .into_empty_result()
+        // Safe to unwrap as we provided valid file names.
+        .map_err(|err| JailerError::DevAccess(dev_path.to_str().unwrap().to_owned(), err))
+}
+
+// Checks the uid and gid of the jailed process are the ones of the calling user. Those are
+// the only ids mapped into the user namespace, and the ones owning the jail and the backing
+// files of the microVM.
+pub fn check_ids(uid: u32, gid: u32) -> Result<(), JailerError> {
+    // SAFETY: Safe because these calls don't take any input parameters and always succeed.
+    let (euid, egid) = unsafe { (libc::geteuid(), libc::getegid()) };
+    if uid == euid && gid == egid {
+        Ok(())
+    } else {
+        Err(JailerError::UserNsIds(euid, egid))
+    }
+}
+
+// Moves the process into new user and mount namespaces, in which it has the capabilities
+// required to build the jail, without being privileged on the host. The calling user is mapped
+// to itself, so files keep being owned by the same user inside and outside of the namespace.
+pub fn enter_user_ns(uid: u32, gid: u32) -> Result<(), JailerError> {
+    // SAFETY: The call is safe because we're invoking a C library
+    // function with valid parameters.
+    SyscallReturnCode(unsafe { libc::unshare(libc::CLONE_NEWUSER | libc::CLONE_NEWNS) })
+        .into_empty_result()
+        .map_err(JailerError::UnshareNewUserNs)?;
+
+    writeln_special(&"/proc/self/uid_map", id_map(uid))?;
+    // Unprivileged processes have to deny setgroups before being able to write the gid map.
+    writeln_special(&"/proc/self/setgroups", "deny")?;
+    writeln_special(&"/proc/self/gid_map", id_map(gid))?;
+
+    // Make sure the mounts done from now on don't propagate back to the host.
+    // SAFETY: Safe because we provide valid parameters.
+    SyscallReturnCode(unsafe {
+        libc::mount(
+            null(),
+            ROOT_DIR.as_ptr(),
+            null(),
+            libc::MS_SLAVE | libc::MS_REC,
+            null(),
+        )
+    })
+    .into_empty_result()
+    .map_err(JailerError::MountPropagationSlave)
+}
+
+// Bind mounts a device of the host at the same path inside the jail, since device nodes cannot
+// be created from a user namespace.
+pub fn bind_mount_dev(dev_path: &CStr, chroot_dir: &Path) -> Result<(), JailerError> {
+    // Safe to unwrap as we provided valid file names.
+    let dev_path_str = dev_path.to_str().unwrap();
+    let target = chroot_dir.join(dev_path_str.trim_start_matches('/'));
+    // Safe to unwrap as the target is inside the jail.
+    let target_dir = target.parent().unwrap();
+    fs::create_dir_all(target_dir)
+        .map_err(|err| JailerError::CreateDir(target_dir.to_owned(), err))?;
+    File::create(&target).map_err(|err| JailerError::FileOpen(target.clone(), err))?;
+
+    let target_cstr = to_cstring(&target)?;
+    // SAFETY: Safe because we provide valid parameters.
+    SyscallReturnCode(unsafe {
+        libc::mount(
+            dev_path.as_ptr(),
+            target_cstr.as_ptr(),
+            null(),
+            libc::MS_BIND,
+            null(),
+        )
+    })
+    .into_empty_result()
+    .map_err(|err| JailerError::MountBindDev(dev_path_str.to_owned(), err))
+}
+
+// Maps an id of the parent user namespace to the same id in the new user namespace.
+fn id_map(id: u32) -> String {
+    format!("{id} {id} 1")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_id_map() {
+        assert_eq!(id_map(1001), "1001 1001 1");
+    }
+
+    #[test]
+    fn test_check_ids() {
+        // SAFETY: Safe because these calls don't take any input parameters.
+        let (euid, egid) = unsafe { (libc::geteuid(), libc::getegid()) };
+
+        check_ids(euid, egid).unwrap();
+        assert!(matches!(
+            check_ids(euid.wrapping_add(1), egid),
+            Err(JailerError::UserNsIds(uid, gid)) if uid == euid && gid == egid
+        ));
+        assert!(matches!(
+            check_ids(euid, egid.wrapping_add(1)),
+            Err(JailerError::UserNsIds(..))
+        ));
+    }
+
+    #[test]
+    fn test_check_dev_access() {
+        check_dev_access(c"/dev/null").unwrap();
+        assert!(matches!(
+            check_dev_access(c"/dev/invalid_device"),
+            Err(JailerError::DevAccess(..))
+        ));
+    }
+}
@@ -0,0 +1,102 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use acpi_tables::fadt::{ARM_BOOT_ARCH_PSCI_COMPLIANT, ARM_BOOT_ARCH_PSCI_USE_HVC};
+use acpi_tables::madt::{GicIts, Gicc, Gicd, Gicr};
+use acpi_tables::{Fadt, MadtBuilder};
+use vm_memory::GuestAddress;
+
+use crate::arch::aarch64::gic::GICDevice;
+use crate::arch::aarch64::layout;
+
+/// Identifier of the ITS of the GIC, which the ITS group of the IORT refers to.
+pub(crate) const ITS_ID: u32 = 0;
+
+// Affinity fields of the MPIDR, the only ones a GIC CPU interface structure holds.
+const MPIDR_AFFINITY_MASK: u64 = 0xff_00ff_ffff;
+
+/// Add the GIC structures to the MADT: the CPU interface of every vCPU, the distributor and,
+/// with a GICv3, the redistributors and the ITS.
+pub(crate) fn setup_interrupt_controllers(
+    madt: MadtBuilder,
+    gic: &GICDevice,
+    vcpu_mpidrs: &[u64],
+) -> MadtBuilder {
+    // The addresses of the distributor, then of the CPU interfaces with a GICv2 or of the
+    // redistributors with a GICv3, with their sizes.
+    let properties = gic.device_properties();
+    let madt = vcpu_mpidrs
+        .iter()
+        .zip(0u32..)
+        .fold(madt, |madt, (mpidr, index)| {
+            let gicc = Gicc::new(index, index, mpidr & MPIDR_AFFINITY_MASK);
+            let gicc = match gic {
+                GICDevice::V2(_) => gicc.physical_base_address(properties[2]),
+                GICDevice::V3(_) => gicc,
+            };
+            madt.gicc(gicc, true)
+        });
+    match gic {
+        GICDevice::V2(_) => madt.structure(&Gicd::new(properties[0], 2)),
+        GICDevice::V3(_) => {
+            let madt = madt
+                .structure(&Gicd::new(properties[0], 3))
+                .structure(&Gicr::new(properties[2], properties[3].try_into().unwrap()));
+            match gic.msi_properties() {
+                Some([its_addr, _]) => madt.structure(&GicIts::new(ITS_ID, *its_addr)),
+                None => madt,
+            }
+        }
+    }
+}
+
+#[inline(always)]
+pub(crate) fn setup_arch_fadt(fadt: &mut Fadt, _legacy_free: bool) {
+    // vCPUs are brought up and powered off through PSCI, whose calls trap to KVM through HVC.
+    fadt.setup_arm_flags((1 << ARM_BOOT_ARCH_PSCI_COMPLIANT) | (1 << ARM_BOOT_ARCH_PSCI_USE_HVC));
+}
+
+pub(crate) const fn rsdp_addr() -> GuestAddress {
+    GuestAddress(layout::RSDP_ADDR)
+}
+
+#[cfg(test)]
+mod tests {
+    use acpi_tables::Sdt;
+
+    use super::*;
+    use crate::arch::aarch64::gic::create_gic;
+    use crate::{Kvm, Vm};
+
+    #[test]
+    fn test_setup_interrupt_controllers() {
+        let kvm = Kvm::new(vec![]).unwrap();
+        let vm = Vm::new(&kvm).unwrap();
+        let gic = create_gic(vm.fd(), 2, None).unwrap();
+        let mpidrs = [0x8000_0000, 0x8000_0001];
+
+        let mut madt = setup_interrupt_controllers(MadtBuilder::new(0), &gic, &mpidrs).build(
+            *b"FIRECK",
+            *b"FCVMMADT",
+            0,
+        );
+        let bytes = madt.to_bytes().unwrap();
+        // Walk the structures, which follow the 44 bytes of the header, by their type.
+        let mut types = Vec::new();
+        let mut offset = 44;
+        while offset < bytes.len() {
+            types.push(bytes[offset]);
+            offset += usize::from(bytes[offset + 1]);
+        }
+        match gic {
+            // GICC, GICC, GICD, GICR, then the ITS.
+            GICDevice::V3(_) => assert_eq!(types, [0xb, 0xb, 0xc, 0xe, 0xf]),
+            GICDevice::V2(_) => assert_eq!(types, [0xb, 0xb, 0xc]),
+        }
+        // The MPIDR of the second GICC holds the affinity fields only.
+        assert_eq!(
+            u64::from_le_bytes(bytes[44 + 82 + 68..44 + 82 + 76].try_into().unwrap()),
+            1
+        );
+    }
+}
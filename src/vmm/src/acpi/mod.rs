@@ -1,20 +1,84 @@
 // Copyright 2024 Amazon.com, Inc. or its affiliates. All Rights Reserved.
 // SPDX-License-Identifier: Apache-2.0
 
-use acpi_tables::fadt::{FADT_F_HW_REDUCED_ACPI, FADT_F_PWR_BUTTON, FADT_F_SLP_BUTTON};
-use acpi_tables::{Aml, Dsdt, Fadt, Madt, Mcfg, Rsdp, Sdt, Xsdt, aml};
+use acpi_tables::fadt::{
+    FADT_F_HW_REDUCED_ACPI, FADT_F_LOW_POWER_S0_IDLE_CAPABLE, FADT_F_PWR_BUTTON, FADT_F_SLP_BUTTON,
+};
+#[cfg(target_arch = "x86_64")]
+use acpi_tables::hest::{HEST_NOTIFY_GSIV, HEST_NOTIFY_NMI};
+#[cfg(target_arch = "x86_64")]
+use acpi_tables::madt::MADT_F_PCAT_COMPAT;
+#[cfg(target_arch = "x86_64")]
+use acpi_tables::{
+    Aml, BasicBootTimestamps, DMAR_FLAG_INTR_REMAP, DmarBuilder, DmarDeviceScope,
+    DmarDeviceScopeType, Drhd, Fbpt, Fpdt, GhesV2, Hest, Lpit, Madt, MemoryAffinity, Pcct,
+    ProcessorAffinity, Srat, Ssdt, Wdat,
+};
+use acpi_tables::{Dsdt, Fadt, Mcfg, Rsdp, Sdt, Xsdt, aml};
+#[cfg(target_arch = "aarch64")]
+use acpi_tables::{IortBuilder, IortIdMapping, MadtBuilder};
+use base64::Engine;
 use log::{debug, error};
+#[cfg(target_arch = "x86_64")]
+use pci::PciBdf;
+use serde::{Deserialize, Serialize};
 use vm_allocator::AllocPolicy;
+use vm_memory::GuestMemoryError;
+#[cfg(target_arch = "x86_64")]
+use vm_memory::{Address, GuestMemoryRegion};
+#[cfg(target_arch = "x86_64")]
+use zerocopy::IntoBytes;
 
+#[cfg(target_arch = "x86_64")]
 use crate::Vcpu;
+#[cfg(target_arch = "aarch64")]
+use crate::acpi::aarch64::{ITS_ID, rsdp_addr, setup_arch_fadt, setup_interrupt_controllers};
+#[cfg(target_arch = "x86_64")]
 use crate::acpi::x86_64::{
     apic_addr, rsdp_addr, setup_arch_dsdt, setup_arch_fadt, setup_interrupt_controllers,
 };
+#[cfg(target_arch = "aarch64")]
+use crate::arch::aarch64::gic::GICDevice;
+#[cfg(target_arch = "aarch64")]
+use crate::arch::aarch64::layout;
+#[cfg(target_arch = "x86_64")]
 use crate::arch::x86_64::layout;
+#[cfg(target_arch = "x86_64")]
+use crate::arch::x86_64::sgx::EpcSection;
 use crate::device_manager::DeviceManager;
-use crate::vstate::memory::{GuestAddress, GuestMemoryMmap};
+#[cfg(target_arch = "x86_64")]
+use crate::devices::acpi::cppc::Cppc;
+#[cfg(target_arch = "x86_64")]
+use crate::devices::acpi::ghes::{GHES_ERROR_STATUS_BLOCK_LENGTH, Ghes};
+#[cfg(target_arch = "x86_64")]
+use crate::devices::acpi::s2idle::S2idle;
+#[cfg(target_arch = "x86_64")]
+use crate::devices::acpi::watchdog::{
+    WATCHDOG_MAX_COUNT, WATCHDOG_MIN_COUNT, WATCHDOG_PERIOD_MS, Watchdog,
+};
+#[cfg(target_arch = "x86_64")]
+use crate::devices::legacy::iommu::IOMMU_ADDRESS_WIDTH;
+#[cfg(target_arch = "x86_64")]
+use crate::devices::pci::PciSegment;
+#[cfg(target_arch = "x86_64")]
+use crate::devices::pseudo::BootTimer;
+#[cfg(target_arch = "x86_64")]
+use crate::utils::mib_to_bytes;
+use crate::utils::usize_to_u64;
+#[cfg(target_arch = "x86_64")]
+use crate::vmm_config::apei::GhesNotification;
+#[cfg(target_arch = "x86_64")]
+use crate::vmm_config::memory_map::MemoryMapRegion;
+#[cfg(target_arch = "x86_64")]
+use crate::vmm_config::numa::NumaNodeConfig;
+use crate::vstate::memory::{Bytes, GuestAddress, GuestMemoryMmap};
+#[cfg(target_arch = "x86_64")]
+use crate::vstate::memory::{GuestMemory, GuestRegionType};
 use crate::vstate::resources::ResourceAllocator;
 
+#[cfg(target_arch = "aarch64")]
+mod aarch64;
+#[cfg(target_arch = "x86_64")]
 mod x86_64;
 
 // Our (Original Equipment Manufacturer" (OEM) name. OEM is how ACPI names the manufacturer of the
@@ -30,6 +94,20 @@ const OEM_REVISION: u32 = 0;
 // guest know that it runs within a clawdbox microVM.
 const HYPERVISOR_VENDOR_ID: [u8; 8] = *b"FIRECKVM";
 
+// Size of the RSDP, and of the header common to all the other tables.
+const RSDP_LENGTH: usize = 36;
+const SDT_HEADER_LENGTH: usize = 36;
+// Offsets of the XSDT address in the RSDP, and of the DSDT address in the FADT.
+const RSDP_XSDT_ADDR_OFFSET: usize = 24;
+const FADT_X_DSDT_OFFSET: usize = 140;
+// Bound on the length of the tables read back from guest memory, in case the guest
+// overwrote their headers.
+const MAX_TABLE_LENGTH: usize = 1 << 20;
+// The IOAPIC has no PCI function of its own. The DMAR reports it on a bus the PCI segment does not
+// use, which gives it the source ID its remapped interrupts would carry.
+#[cfg(target_arch = "x86_64")]
+const IOAPIC_SOURCE_BUS: u8 = 0xff;
+
 #[derive(Debug, thiserror::Error, displaydoc::Display)]
 /// Error type for ACPI related operations
 pub enum AcpiError {
@@ -39,6 +117,29 @@ pub enum AcpiError {
     AcpiTables(#[from] acpi_tables::AcpiError),
     /// Error creating AML bytecode: {0}
     AmlError(#[from] aml::AmlError),
+    /// Could not read ACPI table from guest memory: {0}
+    GuestMemory(#[from] GuestMemoryError),
+    /// Invalid ACPI table at address {0:#x}
+    InvalidTable(u64),
+}
+
+/// An ACPI table, as found in guest memory.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct AcpiTableDump {
+    /// Signature of the table, such as `XSDT` or `DSDT`. The RSDP is reported as `RSDP`.
+    pub signature: String,
+    /// Guest physical address of the table.
+    pub guest_address: u64,
+    /// Base64-encoded bytes of the table.
+    pub data: String,
+}
+
+/// The body of a GET /debug/acpi response.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct AcpiTablesDump {
+    /// The RSDP, followed by the XSDT and the tables it points to, each of them followed by the
+    /// tables it points to in turn.
+    pub tables: Vec<AcpiTableDump>,
 }
 
 /// Helper type that holds the guest memory in which we write the tables in and a resource
@@ -80,10 +181,13 @@ impl AcpiTableWriter<'_> {
     }
 
     /// Build the DSDT table for the guest
+    #[cfg(target_arch = "x86_64")]
     fn build_dsdt(
         &mut self,
         device_manager: &mut DeviceManager,
         resource_allocator: &mut ResourceAllocator,
+        memory_map: &[MemoryMapRegion],
+        sgx_epc: Option<&EpcSection>,
     ) -> Result<u64, AcpiError> {
         let mut dsdt_data = Vec::new();
 
@@ -99,8 +203,16 @@ impl AcpiTableWriter<'_> {
             pci_segment.append_aml_bytes(&mut dsdt_data)?;
         }
 
+        // Memory map regions which are not part of guest memory
+        append_memory_map_aml(self.mem, memory_map, &mut dsdt_data)?;
+
+        // SGX enclave page cache
+        if let Some(sgx_epc) = sgx_epc {
+            sgx_epc.append_aml_bytes(&mut dsdt_data)?;
+        }
+
         // Architecture specific DSDT data
-        setup_arch_dsdt(&mut dsdt_data)?;
+        setup_arch_dsdt(&device_manager.legacy_devices, &mut dsdt_data)?;
 
         let mut dsdt = Dsdt::new(OEM_ID, *b"FCVMDSDT", OEM_REVISION, dsdt_data);
         self.write_acpi_table(resource_allocator, &mut dsdt)
@@ -108,29 +220,39 @@ impl AcpiTableWriter<'_> {
 
     /// Build the FADT table for the guest
     ///
-    /// This includes a pointer with the location of the DSDT in guest memory
+    /// This includes a pointer with the location of the DSDT in guest memory. When suspend-to-idle
+    /// is enabled, the guest is told to favor it over other sleep states. On legacy-free platforms,
+    /// the guest is told there is no PIT, PIC nor i8042 controller.
     fn build_fadt(
         &mut self,
         resource_allocator: &mut ResourceAllocator,
         dsdt_addr: u64,
+        low_power_s0_idle: bool,
+        legacy_free: bool,
     ) -> Result<u64, AcpiError> {
         let mut fadt = Fadt::new(OEM_ID, *b"FCVMFADT", OEM_REVISION);
         fadt.set_hypervisor_vendor_id(HYPERVISOR_VENDOR_ID);
         fadt.set_x_dsdt(dsdt_addr);
-        fadt.set_flags(
-            (1 << FADT_F_HW_REDUCED_ACPI) | (1 << FADT_F_PWR_BUTTON) | (1 << FADT_F_SLP_BUTTON),
-        );
-        setup_arch_fadt(&mut fadt);
+        let mut flags =
+            (1 << FADT_F_HW_REDUCED_ACPI) | (1 << FADT_F_PWR_BUTTON) | (1 << FADT_F_SLP_BUTTON);
+        if low_power_s0_idle {
+            flags |= 1 << FADT_F_LOW_POWER_S0_IDLE_CAPABLE;
+        }
+        fadt.set_flags(flags);
+        setup_arch_fadt(&mut fadt, legacy_free);
         self.write_acpi_table(resource_allocator, &mut fadt)
     }
 
     /// Build the MADT table for the guest
     ///
-    /// This includes information about the interrupt controllers supported in the platform
+    /// This includes information about the interrupt controllers supported in the platform. The
+    /// 8259 PICs are only there when the platform is not legacy-free.
+    #[cfg(target_arch = "x86_64")]
     fn build_madt(
         &mut self,
         resource_allocator: &mut ResourceAllocator,
         nr_vcpus: u8,
+        legacy_free: bool,
     ) -> Result<u64, AcpiError> {
         let mut madt = Madt::new(
             OEM_ID,
@@ -139,25 +261,207 @@ impl AcpiTableWriter<'_> {
             apic_addr(),
             setup_interrupt_controllers(nr_vcpus),
         );
+        if !legacy_free {
+            madt.set_flags(1 << MADT_F_PCAT_COMPAT);
+        }
         self.write_acpi_table(resource_allocator, &mut madt)
     }
 
+    /// Write the user-supplied SSDTs in guest memory
+    ///
+    /// Returns the addresses of the tables, in the order they were supplied.
+    #[cfg(target_arch = "x86_64")]
+    fn build_ssdts(
+        &mut self,
+        resource_allocator: &mut ResourceAllocator,
+        ssdts: &[Ssdt],
+    ) -> Result<Vec<u64>, AcpiError> {
+        ssdts
+            .iter()
+            .map(|ssdt| self.write_acpi_table(resource_allocator, &mut ssdt.clone()))
+            .collect()
+    }
+
+    /// Build the FPDT table for the guest
+    ///
+    /// This includes a pointer to the FBPT, which is written with empty timestamps for the boot
+    /// timer to fill in once the microVM is about to run.
+    #[cfg(target_arch = "x86_64")]
+    fn build_fpdt(
+        &mut self,
+        resource_allocator: &mut ResourceAllocator,
+        boot_timer: &mut BootTimer,
+    ) -> Result<u64, AcpiError> {
+        let mut fbpt = Fbpt::new(&BasicBootTimestamps::default());
+        let fbpt_addr = self.write_acpi_table(resource_allocator, &mut fbpt)?;
+        boot_timer.fbpt_addr = Some(GuestAddress(fbpt_addr));
+        let mut fpdt = Fpdt::new(OEM_ID, *b"FCVMFPDT", OEM_REVISION, fbpt_addr);
+        self.write_acpi_table(resource_allocator, &mut fpdt)
+    }
+
+    /// Build the WDAT table for the guest
+    ///
+    /// This describes the registers of the watchdog device, so that the guest can drive it
+    /// with its generic WDAT driver.
+    #[cfg(target_arch = "x86_64")]
+    fn build_wdat(
+        &mut self,
+        resource_allocator: &mut ResourceAllocator,
+        watchdog: &Watchdog,
+    ) -> Result<u64, AcpiError> {
+        let mut wdat = Wdat::new(
+            OEM_ID,
+            *b"FCVMWDAT",
+            OEM_REVISION,
+            WATCHDOG_PERIOD_MS,
+            WATCHDOG_MIN_COUNT,
+            WATCHDOG_MAX_COUNT,
+            watchdog.wdat_entries(),
+        );
+        self.write_acpi_table(resource_allocator, &mut wdat)
+    }
+
+    /// Build the HEST table for the guest
+    ///
+    /// This describes the GHESv2 error source through which host memory errors are reported to
+    /// the guest.
+    #[cfg(target_arch = "x86_64")]
+    fn build_hest(
+        &mut self,
+        resource_allocator: &mut ResourceAllocator,
+        ghes: &Ghes,
+    ) -> Result<u64, AcpiError> {
+        let notification_type = match ghes.notification {
+            GhesNotification::Ged => HEST_NOTIFY_GSIV,
+            GhesNotification::Nmi => HEST_NOTIFY_NMI,
+        };
+        let source = GhesV2::new(
+            0,
+            notification_type,
+            ghes.gsi.unwrap_or(0),
+            ghes.error_status_address().0,
+            GHES_ERROR_STATUS_BLOCK_LENGTH,
+            ghes.read_ack_address().0,
+        );
+        let mut hest = Hest::new(OEM_ID, *b"FCVMHEST", OEM_REVISION, vec![source.into()]);
+        self.write_acpi_table(resource_allocator, &mut hest)
+    }
+
+    /// Build the LPIT table for the guest
+    ///
+    /// This describes suspend-to-idle and the counter of the time the guest spent in it.
+    #[cfg(target_arch = "x86_64")]
+    fn build_lpit(
+        &mut self,
+        resource_allocator: &mut ResourceAllocator,
+        s2idle: &S2idle,
+    ) -> Result<u64, AcpiError> {
+        let mut lpit = Lpit::new(
+            OEM_ID,
+            *b"FCVMLPIT",
+            OEM_REVISION,
+            vec![s2idle.lpit_state()],
+        );
+        self.write_acpi_table(resource_allocator, &mut lpit)
+    }
+
+    /// Build the SRAT table for the guest
+    ///
+    /// This attaches the vCPUs and memory of the guest to its NUMA nodes.
+    #[cfg(target_arch = "x86_64")]
+    fn build_srat(
+        &mut self,
+        resource_allocator: &mut ResourceAllocator,
+        numa_nodes: &[NumaNodeConfig],
+    ) -> Result<u64, AcpiError> {
+        let mut srat = Srat::new(
+            OEM_ID,
+            *b"FCVMSRAT",
+            OEM_REVISION,
+            numa_affinities(self.mem, numa_nodes),
+        );
+        self.write_acpi_table(resource_allocator, &mut srat)
+    }
+
+    /// Build the PCCT table for the guest
+    ///
+    /// This describes the PCC subspace backing the CPPC registers of the vCPUs.
+    #[cfg(target_arch = "x86_64")]
+    fn build_pcct(
+        &mut self,
+        resource_allocator: &mut ResourceAllocator,
+        cppc: &Cppc,
+    ) -> Result<u64, AcpiError> {
+        let subspaces = vec![cppc.pcc_subspace().into()];
+        let mut pcct = Pcct::new(OEM_ID, *b"FCVMPCCT", OEM_REVISION, subspaces);
+        self.write_acpi_table(resource_allocator, &mut pcct)
+    }
+
+    /// Build the DMAR table for the guest
+    ///
+    /// This describes the interrupt remapping unit, which covers the IOAPIC and the PCI devices
+    /// given in `pci_devices`, the VFIO passthrough devices among them.
+    #[cfg(target_arch = "x86_64")]
+    fn build_dmar(
+        &mut self,
+        resource_allocator: &mut ResourceAllocator,
+        pci_devices: &[PciBdf],
+    ) -> Result<u64, AcpiError> {
+        let ioapic =
+            DmarDeviceScope::new(DmarDeviceScopeType::IoApic, 0, IOAPIC_SOURCE_BUS, &[(0, 0)]);
+        // The devices are listed explicitly, since a DRHD including all the PCI devices of its
+        // segment may only have IOAPIC and HPET device scope entries.
+        let drhd = pci_devices.iter().fold(
+            Drhd::new(0, u64::from(layout::IOMMU_ADDR)).device(ioapic),
+            |drhd, bdf| {
+                drhd.device(DmarDeviceScope::pci_endpoint(
+                    bdf.bus(),
+                    bdf.device(),
+                    bdf.function(),
+                ))
+            },
+        );
+        let mut dmar = DmarBuilder::new(IOMMU_ADDRESS_WIDTH - 1)
+            .flags(DMAR_FLAG_INTR_REMAP)
+            .drhd(drhd)
+            .build(OEM_ID, *b"FCVMDMAR", OEM_REVISION)?;
+        self.write_acpi_table(resource_allocator, &mut dmar)
+    }
+
     /// Build the XSDT table for the guest
     ///
-    /// Currently, we pass to the guest just FADT and MADT tables.
+    /// Currently, we pass to the guest the FADT, MADT and MCFG tables, the FPDT table when the
+    /// boot timer is enabled, the WDAT table when the watchdog is enabled, the HEST table when
+    /// memory errors are forwarded, the LPIT table when suspend-to-idle is enabled, the SRAT
+    /// table when the guest has NUMA nodes, the PCCT table when CPPC is enabled, the DMAR table
+    /// when the interrupt remapping unit is present, followed by any user-supplied SSDTs.
+    #[cfg(target_arch = "x86_64")]
+    #[allow(clippy::too_many_arguments)]
     fn build_xsdt(
         &mut self,
         resource_allocator: &mut ResourceAllocator,
         fadt_addr: u64,
         madt_addr: u64,
         mcfg_addr: u64,
+        fpdt_addr: Option<u64>,
+        wdat_addr: Option<u64>,
+        hest_addr: Option<u64>,
+        lpit_addr: Option<u64>,
+        srat_addr: Option<u64>,
+        pcct_addr: Option<u64>,
+        dmar_addr: Option<u64>,
+        ssdt_addrs: &[u64],
     ) -> Result<u64, AcpiError> {
-        let mut xsdt = Xsdt::new(
-            OEM_ID,
-            *b"FCMVXSDT",
-            OEM_REVISION,
-            vec![fadt_addr, madt_addr, mcfg_addr],
-        );
+        let mut tables = vec![fadt_addr, madt_addr, mcfg_addr];
+        tables.extend(fpdt_addr);
+        tables.extend(wdat_addr);
+        tables.extend(hest_addr);
+        tables.extend(lpit_addr);
+        tables.extend(srat_addr);
+        tables.extend(pcct_addr);
+        tables.extend(dmar_addr);
+        tables.extend_from_slice(ssdt_addrs);
+        let mut xsdt = Xsdt::new(OEM_ID, *b"FCMVXSDT", OEM_REVISION, tables);
         self.write_acpi_table(resource_allocator, &mut xsdt)
     }
 
@@ -190,35 +494,382 @@ impl AcpiTableWriter<'_> {
     }
 }
 
+#[cfg(target_arch = "aarch64")]
+impl AcpiTableWriter<'_> {
+    /// Build the MADT table for the guest
+    ///
+    /// This describes the GIC, with its ITS when it has one, and the CPU interface of every vCPU.
+    fn build_madt(
+        &mut self,
+        resource_allocator: &mut ResourceAllocator,
+        gic: &GICDevice,
+        vcpu_mpidrs: &[u64],
+    ) -> Result<u64, AcpiError> {
+        let mut madt = setup_interrupt_controllers(MadtBuilder::new(0), gic, vcpu_mpidrs).build(
+            OEM_ID,
+            *b"FCVMMADT",
+            OEM_REVISION,
+        );
+        self.write_acpi_table(resource_allocator, &mut madt)
+    }
+
+    /// Build the IORT table for the guest
+    ///
+    /// This routes the MSIs of the PCI segment `pci_segment` to the ITS. Their device IDs are the
+    /// requester IDs of the devices, with the segment number in bits [31:16], as in the MSI
+    /// routes of the devices.
+    fn build_iort(
+        &mut self,
+        resource_allocator: &mut ResourceAllocator,
+        pci_segment: u16,
+    ) -> Result<u64, AcpiError> {
+        let mut iort = IortBuilder::new();
+        let its_group = iort.its_group(&[ITS_ID]);
+        // The segment has a single bus, hence 256 requester IDs.
+        let id_mapping = IortIdMapping::new(0, 0x100, u32::from(pci_segment) << 16, its_group);
+        iort.root_complex(pci_segment, &[id_mapping]);
+        let mut iort = iort.build(OEM_ID, *b"FCVMIORT", OEM_REVISION)?;
+        self.write_acpi_table(resource_allocator, &mut iort)
+    }
+
+    /// Build the XSDT table for the guest
+    ///
+    /// We pass to the guest the FADT and MADT tables, followed by the MCFG table when the guest
+    /// has a PCI segment and the IORT table when the MSIs of the segment go through the ITS.
+    fn build_xsdt(
+        &mut self,
+        resource_allocator: &mut ResourceAllocator,
+        fadt_addr: u64,
+        madt_addr: u64,
+        mcfg_addr: Option<u64>,
+        iort_addr: Option<u64>,
+    ) -> Result<u64, AcpiError> {
+        let mut tables = vec![fadt_addr, madt_addr];
+        tables.extend(mcfg_addr);
+        tables.extend(iort_addr);
+        let mut xsdt = Xsdt::new(OEM_ID, *b"FCMVXSDT", OEM_REVISION, tables);
+        self.write_acpi_table(resource_allocator, &mut xsdt)
+    }
+}
+
+/// Describe the memory map regions which are not backed by guest memory as motherboard resources,
+/// so that the guest does not assign them to devices.
+#[cfg(target_arch = "x86_64")]
+fn append_memory_map_aml(
+    mem: &GuestMemoryMmap,
+    memory_map: &[MemoryMapRegion],
+    v: &mut Vec<u8>,
+) -> Result<(), aml::AmlError> {
+    let ranges = memory_map
+        .iter()
+        .filter(|region| !mem.address_in_range(GuestAddress(region.guest_address)))
+        .map(|region| {
+            aml::AddressSpace::new_memory(
+                aml::AddressSpaceCacheable::NotCacheable,
+                true,
+                region.guest_address,
+                region.end() - 1,
+            )
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if ranges.is_empty() {
+        return Ok(());
+    }
+    let resources: Vec<&dyn Aml> = ranges.iter().map(|range| range as &dyn Aml).collect();
+
+    aml::Device::new(
+        "_SB_.MRES".try_into()?,
+        vec![
+            &aml::Name::new("_HID".try_into()?, &aml::EisaName::new("PNP0C02")?)?,
+            &aml::Name::new("_UID".try_into()?, &aml::ZERO)?,
+            &aml::Name::new("_CRS".try_into()?, &aml::ResourceTemplate::new(resources))?,
+        ],
+    )
+    .append_aml_bytes(v)
+}
+
+/// Describe the affinity of the vCPUs and memory of the guest to its NUMA nodes.
+///
+/// The memory of the nodes is laid out over guest DRAM in the order of the nodes, and the
+/// hotpluggable memory belongs to the node declared to hold it, if any.
+#[cfg(target_arch = "x86_64")]
+fn numa_affinities(mem: &GuestMemoryMmap, numa_nodes: &[NumaNodeConfig]) -> Vec<u8> {
+    let mut affinities = Vec::new();
+    for node in numa_nodes {
+        for &vcpu in &node.vcpus {
+            affinities.extend_from_slice(ProcessorAffinity::new(vcpu, node.node_id).as_bytes());
+        }
+    }
+
+    let mut dram = mem
+        .iter()
+        .filter(|region| region.region_type == GuestRegionType::Dram)
+        .map(|region| (region.start_addr().raw_value(), region.len()));
+    let mut current = dram.next();
+    for node in numa_nodes {
+        let mut remaining = usize_to_u64(mib_to_bytes(node.mem_size_mib));
+        while remaining > 0 {
+            let Some((start, len)) = current.as_mut() else {
+                break;
+            };
+            let size = remaining.min(*len);
+            affinities.extend_from_slice(
+                MemoryAffinity::new(*start, size, node.node_id, false).as_bytes(),
+            );
+            *start += size;
+            *len -= size;
+            remaining -= size;
+            if *len == 0 {
+                current = dram.next();
+            }
+        }
+    }
+
+    if let Some(node) = numa_nodes.iter().find(|node| node.memory_hotplug) {
+        for region in mem
+            .iter()
+            .filter(|region| region.region_type == GuestRegionType::Hotpluggable)
+        {
+            affinities.extend_from_slice(
+                MemoryAffinity::new(
+                    region.start_addr().raw_value(),
+                    region.len(),
+                    node.node_id,
+                    true,
+                )
+                .as_bytes(),
+            );
+        }
+    }
+    affinities
+}
+
 /// Create ACPI tables for the guest
 ///
 /// This will create the ACPI tables needed to describe to the guest OS the available hardware,
-/// such as interrupt controllers, vCPUs and VirtIO devices.
+/// such as interrupt controllers, vCPUs and VirtIO devices. When the boot timer is enabled, the
+/// boot performance of the microVM is described as well, and so are the watchdog and the hardware
+/// error source when they are enabled, as is the SGX enclave page cache when it is exposed,
+/// suspend-to-idle when it is enabled, the NUMA nodes of the guest when it has some and the
+/// interrupt remapping unit when the irqchip is split. User-supplied SSDTs are appended to the XSDT
+/// after the tables we generate.
+#[cfg(target_arch = "x86_64")]
 pub(crate) fn create_acpi_tables(
     mem: &GuestMemoryMmap,
     device_manager: &mut DeviceManager,
     resource_allocator: &mut ResourceAllocator,
     vcpus: &[Vcpu],
+    ssdts: &[Ssdt],
+    memory_map: &[MemoryMapRegion],
+    sgx_epc: Option<&EpcSection>,
+    numa_nodes: &[NumaNodeConfig],
 ) -> Result<(), AcpiError> {
     let mut writer = AcpiTableWriter { mem };
-    let dsdt_addr = writer.build_dsdt(device_manager, resource_allocator)?;
+    let dsdt_addr = writer.build_dsdt(device_manager, resource_allocator, memory_map, sgx_epc)?;
 
-    let fadt_addr = writer.build_fadt(resource_allocator, dsdt_addr)?;
-    let madt_addr = writer.build_madt(resource_allocator, vcpus.len().try_into().unwrap())?;
+    let low_power_s0_idle = device_manager.acpi_devices.s2idle.is_some();
+    // Legacy-free platforms have no i8042 controller, nor PIT and PIC.
+    let legacy_free = device_manager.legacy_devices.i8042.is_none();
+    let fadt_addr = writer.build_fadt(
+        resource_allocator,
+        dsdt_addr,
+        low_power_s0_idle,
+        legacy_free,
+    )?;
+    let madt_addr = writer.build_madt(
+        resource_allocator,
+        vcpus.len().try_into().unwrap(),
+        legacy_free,
+    )?;
     let mcfg_addr = writer.build_mcfg(resource_allocator, layout::PCI_MMCONFIG_START)?;
-    let xsdt_addr = writer.build_xsdt(resource_allocator, fadt_addr, madt_addr, mcfg_addr)?;
+    let fpdt_addr = match &device_manager.mmio_devices.boot_timer {
+        Some(boot_timer) => Some(writer.build_fpdt(
+            resource_allocator,
+            &mut boot_timer.inner.lock().expect("Poisoned lock"),
+        )?),
+        None => None,
+    };
+    let wdat_addr = match &device_manager.acpi_devices.watchdog {
+        Some(watchdog) => {
+            Some(writer.build_wdat(resource_allocator, &watchdog.lock().expect("Poisoned lock"))?)
+        }
+        None => None,
+    };
+    let hest_addr = match &device_manager.acpi_devices.ghes {
+        Some(ghes) => Some(writer.build_hest(resource_allocator, ghes)?),
+        None => None,
+    };
+    let lpit_addr = match &device_manager.acpi_devices.s2idle {
+        Some(s2idle) => {
+            Some(writer.build_lpit(resource_allocator, &s2idle.lock().expect("Poisoned lock"))?)
+        }
+        None => None,
+    };
+    let srat_addr = if numa_nodes.is_empty() {
+        None
+    } else {
+        Some(writer.build_srat(resource_allocator, numa_nodes)?)
+    };
+    let pcct_addr = match &device_manager.acpi_devices.cppc {
+        Some(cppc) => {
+            Some(writer.build_pcct(resource_allocator, &cppc.lock().expect("Poisoned lock"))?)
+        }
+        None => None,
+    };
+    let dmar_addr = match &device_manager.iommu {
+        Some(_) => {
+            let pci_devices = device_manager
+                .pci_devices
+                .pci_segment
+                .as_ref()
+                .map_or_else(Vec::new, PciSegment::device_bdfs);
+            Some(writer.build_dmar(resource_allocator, &pci_devices)?)
+        }
+        None => None,
+    };
+    let ssdt_addrs = writer.build_ssdts(resource_allocator, ssdts)?;
+    let xsdt_addr = writer.build_xsdt(
+        resource_allocator,
+        fadt_addr,
+        madt_addr,
+        mcfg_addr,
+        fpdt_addr,
+        wdat_addr,
+        hest_addr,
+        lpit_addr,
+        srat_addr,
+        pcct_addr,
+        dmar_addr,
+        &ssdt_addrs,
+    )?;
+    writer.build_rsdp(xsdt_addr)
+}
+
+/// Create ACPI tables for the guest
+///
+/// This describes the vCPUs and the GIC, with the ITS which translates the MSIs of the PCI devices
+/// when the guest has a PCI segment. The other devices are only described by the device tree,
+/// which guests boot with. The tables are found through the RSDP at [`layout::RSDP_ADDR`].
+#[cfg(target_arch = "aarch64")]
+pub(crate) fn create_acpi_tables(
+    mem: &GuestMemoryMmap,
+    device_manager: &DeviceManager,
+    resource_allocator: &mut ResourceAllocator,
+    gic: &GICDevice,
+    vcpu_mpidrs: &[u64],
+) -> Result<(), AcpiError> {
+    let mut writer = AcpiTableWriter { mem };
+    // Keep the other tables away from the RSDP, which lies in the system memory.
+    resource_allocator.allocate_system_memory(
+        usize_to_u64(RSDP_LENGTH),
+        1,
+        AllocPolicy::ExactMatch(rsdp_addr().0),
+    )?;
+
+    let mut dsdt = Dsdt::new(OEM_ID, *b"FCVMDSDT", OEM_REVISION, Vec::new());
+    let dsdt_addr = writer.write_acpi_table(resource_allocator, &mut dsdt)?;
+    let fadt_addr = writer.build_fadt(resource_allocator, dsdt_addr, false, true)?;
+    let madt_addr = writer.build_madt(resource_allocator, gic, vcpu_mpidrs)?;
+    let (mcfg_addr, iort_addr) = match &device_manager.pci_devices.pci_segment {
+        Some(pci_segment) => {
+            let mcfg_addr = writer.build_mcfg(resource_allocator, layout::PCI_MMCONFIG_START)?;
+            let iort_addr = match gic.msi_properties() {
+                Some(_) => Some(writer.build_iort(resource_allocator, pci_segment.id)?),
+                None => None,
+            };
+            (Some(mcfg_addr), iort_addr)
+        }
+        None => (None, None),
+    };
+    let xsdt_addr = writer.build_xsdt(
+        resource_allocator,
+        fadt_addr,
+        madt_addr,
+        mcfg_addr,
+        iort_addr,
+    )?;
     writer.build_rsdp(xsdt_addr)
 }
 
-#[cfg(test)]
+fn read_u64(bytes: &[u8], offset: usize) -> Option<u64> {
+    let bytes = bytes.get(offset..offset + 8)?;
+    Some(u64::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+fn dump_table(signature: &[u8], guest_address: u64, bytes: &[u8]) -> AcpiTableDump {
+    AcpiTableDump {
+        signature: String::from_utf8_lossy(signature).into_owned(),
+        guest_address,
+        data: base64::engine::general_purpose::STANDARD.encode(bytes),
+    }
+}
+
+/// Reads the table at `addr` in guest memory, whose length is given by its header.
+fn read_sdt(mem: &GuestMemoryMmap, addr: u64) -> Result<Vec<u8>, AcpiError> {
+    let mut header = [0u8; SDT_HEADER_LENGTH];
+    mem.read_slice(&mut header, GuestAddress(addr))?;
+    let length = usize::try_from(u32::from_le_bytes(header[4..8].try_into().unwrap())).unwrap();
+    if !(SDT_HEADER_LENGTH..=MAX_TABLE_LENGTH).contains(&length) {
+        return Err(AcpiError::InvalidTable(addr));
+    }
+    let mut bytes = vec![0u8; length];
+    mem.read_slice(&mut bytes, GuestAddress(addr))?;
+    Ok(bytes)
+}
+
+/// Read back the ACPI tables of the guest
+///
+/// The tables are found by following the RSDP, the XSDT and the FADT, so they are reported as
+/// they currently are in guest memory, including any change the guest made to them.
+pub fn dump_acpi_tables(mem: &GuestMemoryMmap) -> Result<AcpiTablesDump, AcpiError> {
+    let mut tables = Vec::new();
+
+    let mut rsdp = [0u8; RSDP_LENGTH];
+    mem.read_slice(&mut rsdp, rsdp_addr())?;
+    if &rsdp[..8] != b"RSD PTR " {
+        return Err(AcpiError::InvalidTable(rsdp_addr().0));
+    }
+    tables.push(dump_table(b"RSDP", rsdp_addr().0, &rsdp));
+
+    let xsdt_addr = read_u64(&rsdp, RSDP_XSDT_ADDR_OFFSET).unwrap();
+    let xsdt = read_sdt(mem, xsdt_addr)?;
+    if &xsdt[..4] != b"XSDT" {
+        return Err(AcpiError::InvalidTable(xsdt_addr));
+    }
+    tables.push(dump_table(b"XSDT", xsdt_addr, &xsdt));
+
+    for entry in xsdt[SDT_HEADER_LENGTH..].chunks_exact(8) {
+        let addr = read_u64(entry, 0).unwrap();
+        let table = read_sdt(mem, addr)?;
+        tables.push(dump_table(&table[..4], addr, &table));
+        if &table[..4] == b"FACP" {
+            let dsdt_addr =
+                read_u64(&table, FADT_X_DSDT_OFFSET).ok_or(AcpiError::InvalidTable(addr))?;
+            let dsdt = read_sdt(mem, dsdt_addr)?;
+            tables.push(dump_table(&dsdt[..4], dsdt_addr, &dsdt));
+        }
+    }
+    Ok(AcpiTablesDump { tables })
+}
+
+#[cfg(all(test, target_arch = "x86_64"))]
 mod tests {
-    use acpi_tables::Sdt;
-    use vm_memory::Bytes;
+    use acpi_tables::{DMAR_FLAG_INTR_REMAP, MemoryAffinity, ProcessorAffinity, Sdt};
+    use base64::Engine;
+    use vm_memory::{Bytes, GuestAddress};
+    use zerocopy::IntoBytes;
 
-    use crate::acpi::{AcpiError, AcpiTableWriter};
-    use crate::arch::x86_64::layout::{SYSTEM_MEM_SIZE, SYSTEM_MEM_START};
+    use crate::acpi::{
+        AcpiError, AcpiTableWriter, OEM_ID, OEM_REVISION, append_memory_map_aml, dump_acpi_tables,
+        numa_affinities, read_sdt, rsdp_addr,
+    };
+    use crate::arch::x86_64::layout::{IOMMU_ADDR, SYSTEM_MEM_SIZE, SYSTEM_MEM_START};
     use crate::builder::tests::default_vmm;
+    use crate::devices::legacy::iommu::IOMMU_ADDRESS_WIDTH;
+    use crate::test_utils::{multi_region_mem, single_region_mem};
     use crate::utils::u64_to_usize;
+    use crate::vmm_config::memory_map::{MemoryMapRegion, MemoryMapRegionType};
+    use crate::vmm_config::numa::NumaNodeConfig;
     use crate::vstate::resources::ResourceAllocator;
     use crate::vstate::vm::tests::setup_vm_with_memory;
 
@@ -229,13 +880,8 @@ mod tests {
             self.0.len()
         }
 
-        fn write_to_guest<M: vm_memory::GuestMemory>(
-            &mut self,
-            mem: &M,
-            address: vm_memory::GuestAddress,
-        ) -> acpi_tables::Result<()> {
-            mem.write_slice(&self.0, address)?;
-            Ok(())
+        fn to_bytes(&mut self) -> acpi_tables::Result<Vec<u8>> {
+            Ok(self.0.clone())
         }
     }
 
@@ -333,4 +979,169 @@ mod tests {
             err
         );
     }
+
+    #[test]
+    fn test_memory_map_aml() {
+        let mem = single_region_mem(0x40_0000);
+        let region = |guest_address| MemoryMapRegion {
+            guest_address,
+            size: 0x1000,
+            region_type: MemoryMapRegionType::Reserved,
+        };
+
+        // Regions carved out of guest memory are only described by the e820 map.
+        let mut aml = Vec::new();
+        append_memory_map_aml(&mem, &[region(0x20_0000)], &mut aml).unwrap();
+        assert!(aml.is_empty());
+
+        append_memory_map_aml(&mem, &[region(0x20_0000), region(0xd000_0000)], &mut aml).unwrap();
+        assert!(aml.windows(4).any(|window| window == b"MRES"));
+    }
+
+    #[test]
+    fn test_numa_affinities() {
+        let mem = multi_region_mem(&[
+            (GuestAddress(0), 0x10_0000),
+            (GuestAddress(0x40_0000), 0x30_0000),
+        ]);
+        let node = |node_id, vcpus: &[u8], mem_size_mib| NumaNodeConfig {
+            node_id,
+            vcpus: vcpus.to_vec(),
+            mem_size_mib,
+            memory_hotplug: false,
+        };
+
+        // The memory of node 1 spans both regions.
+        let affinities = numa_affinities(&mem, &[node(0, &[0], 0), node(1, &[1, 2], 4)]);
+        let mut expected = Vec::new();
+        expected.extend_from_slice(ProcessorAffinity::new(0, 0).as_bytes());
+        expected.extend_from_slice(ProcessorAffinity::new(1, 1).as_bytes());
+        expected.extend_from_slice(ProcessorAffinity::new(2, 1).as_bytes());
+        expected.extend_from_slice(MemoryAffinity::new(0, 0x10_0000, 1, false).as_bytes());
+        expected.extend_from_slice(MemoryAffinity::new(0x40_0000, 0x30_0000, 1, false).as_bytes());
+        assert_eq!(affinities, expected);
+
+        let affinities = numa_affinities(&mem, &[node(0, &[0], 2), node(1, &[1], 2)]);
+        let mut expected = Vec::new();
+        expected.extend_from_slice(ProcessorAffinity::new(0, 0).as_bytes());
+        expected.extend_from_slice(ProcessorAffinity::new(1, 1).as_bytes());
+        expected.extend_from_slice(MemoryAffinity::new(0, 0x10_0000, 0, false).as_bytes());
+        expected.extend_from_slice(MemoryAffinity::new(0x40_0000, 0x10_0000, 0, false).as_bytes());
+        expected.extend_from_slice(MemoryAffinity::new(0x50_0000, 0x20_0000, 1, false).as_bytes());
+        assert_eq!(affinities, expected);
+    }
+
+    #[test]
+    fn test_build_dmar() {
+        let vmm = default_vmm();
+        let mem = vmm.vm.guest_memory();
+        let mut writer = AcpiTableWriter { mem };
+        let mut resource_allocator = vmm.vm.resource_allocator();
+
+        let dmar_addr = writer.build_dmar(&mut resource_allocator, &[]).unwrap();
+        let dmar = read_sdt(mem, dmar_addr).unwrap();
+        assert_eq!(&dmar[..4], b"DMAR");
+        // Host address width, then flags.
+        assert_eq!(dmar[36], IOMMU_ADDRESS_WIDTH - 1);
+        assert_eq!(dmar[37], DMAR_FLAG_INTR_REMAP);
+        // Without PCI devices, the only device scope entry of the DRHD is the IOAPIC.
+        assert_eq!(&dmar[48..50], &[0, 0]);
+        assert_eq!(dmar[52], 0);
+        assert_eq!(
+            u64::from_le_bytes(dmar[56..64].try_into().unwrap()),
+            u64::from(IOMMU_ADDR)
+        );
+        assert_eq!(&dmar[64..], &[3, 8, 0, 0, 0, 0xff, 0, 0]);
+
+        // The PCI devices follow the IOAPIC, as PCI endpoints.
+        let pci_devices = [PciBdf::new(0, 0, 1, 0), PciBdf::new(0, 0, 4, 0)];
+        let dmar_addr = writer
+            .build_dmar(&mut resource_allocator, &pci_devices)
+            .unwrap();
+        let dmar = read_sdt(mem, dmar_addr).unwrap();
+        assert_eq!(u16::from_le_bytes(dmar[50..52].try_into().unwrap()), 40);
+        assert_eq!(
+            &dmar[64..],
+            &[
+                3, 8, 0, 0, 0, 0xff, 0, 0, //
+                1, 8, 0, 0, 0, 0, 1, 0, //
+                1, 8, 0, 0, 0, 0, 4, 0,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dump_acpi_tables() {
+        let vmm = default_vmm();
+        let mem = vmm.vm.guest_memory();
+        let mut writer = AcpiTableWriter { mem };
+        let mut resource_allocator = vmm.vm.resource_allocator();
+
+        // Nothing to dump before the tables are written.
+        assert!(matches!(
+            dump_acpi_tables(mem),
+            Err(AcpiError::InvalidTable(addr)) if addr == rsdp_addr().0
+        ));
+
+        let mut dsdt = acpi_tables::Dsdt::new(OEM_ID, *b"FCVMDSDT", OEM_REVISION, vec![]);
+        let dsdt_addr = writer
+            .write_acpi_table(&mut resource_allocator, &mut dsdt)
+            .unwrap();
+        let dsdt_len = dsdt.len();
+        let fadt_addr = writer
+            .build_fadt(&mut resource_allocator, dsdt_addr, false, false)
+            .unwrap();
+        let madt_addr = writer
+            .build_madt(&mut resource_allocator, 1, false)
+            .unwrap();
+        let mcfg_addr = writer.build_mcfg(&mut resource_allocator, 0).unwrap();
+        let xsdt_addr = writer
+            .build_xsdt(
+                &mut resource_allocator,
+                fadt_addr,
+                madt_addr,
+                mcfg_addr,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                &[],
+            )
+            .unwrap();
+        writer.build_rsdp(xsdt_addr).unwrap();
+
+        let dump = dump_acpi_tables(mem).unwrap();
+        let tables: Vec<_> = dump
+            .tables
+            .iter()
+            .map(|table| (table.signature.as_str(), table.guest_address))
+            .collect();
+        assert_eq!(
+            tables,
+            [
+                ("RSDP", rsdp_addr().0),
+                ("XSDT", xsdt_addr),
+                ("FACP", fadt_addr),
+                ("DSDT", dsdt_addr),
+                ("APIC", madt_addr),
+                ("MCFG", mcfg_addr),
+            ]
+        );
+        let dsdt = base64::engine::general_purpose::STANDARD
+            .decode(&dump.tables[3].data)
+            .unwrap();
+        assert_eq!(dsdt.len(), dsdt_len);
+        assert_eq!(&dsdt[..4], b"DSDT");
+
+        // Tables whose header got overwritten are reported as invalid.
+        mem.write_obj(0u32, vm_memory::GuestAddress(madt_addr + 4))
+            .unwrap();
+        assert!(matches!(
+            dump_acpi_tables(mem),
+            Err(AcpiError::InvalidTable(addr)) if addr == madt_addr
+        ));
+    }
 }
@@ -3,15 +3,25 @@
 
 use std::mem::size_of;
 
-use acpi_tables::fadt::IAPC_BOOT_ARG_FLAGS_VGA_NOT_PRESENT;
-use acpi_tables::madt::{IoAPIC, LocalAPIC};
-use acpi_tables::{Fadt, aml};
+use acpi_tables::fadt::{
+    IAPC_BOOT_ARG_FLAGS_8042, IAPC_BOOT_ARG_FLAGS_LEGACY_DEVICES,
+    IAPC_BOOT_ARG_FLAGS_VGA_NOT_PRESENT,
+};
+use acpi_tables::madt::{IoAPIC, LocalAPIC, local_apic_structure};
+use acpi_tables::{AccessSize, AddressSpace, Fadt, GenericAddressStructure, aml};
 use vm_memory::GuestAddress;
 use zerocopy::IntoBytes;
 
 use crate::arch::x86_64::layout;
 use crate::device_manager::legacy::PortIODeviceManager;
+use crate::devices::legacy::reset_register::RESET_VALUE;
 
+/// Serialize the I/O APIC and processor structures of the MADT.
+///
+/// Processors whose APIC ID does not fit a Processor Local APIC structure are described by a
+/// Processor Local x2APIC structure. Guests route device interrupts to them through the interrupt
+/// remapping unit which comes with a split irqchip, whose remapping table entries hold 32-bit
+/// x2APIC destinations. See [`crate::devices::legacy::Iommu`].
 #[inline(always)]
 pub(crate) fn setup_interrupt_controllers(nr_vcpus: u8) -> Vec<u8> {
     let mut ic =
@@ -19,23 +29,44 @@ pub(crate) fn setup_interrupt_controllers(nr_vcpus: u8) -> Vec<u8> {
 
     ic.extend_from_slice(IoAPIC::new(0, layout::IOAPIC_ADDR).as_bytes());
     for i in 0..nr_vcpus {
-        ic.extend_from_slice(LocalAPIC::new(i).as_bytes());
+        ic.extend_from_slice(&local_apic_structure(u32::from(i)));
     }
     ic
 }
 
 #[inline(always)]
-pub(crate) fn setup_arch_fadt(fadt: &mut Fadt) {
+pub(crate) fn setup_arch_fadt(fadt: &mut Fadt, legacy_free: bool) {
     // Let the guest kernel know that there is not VGA hardware present
     // neither do we support ASPM, or MSI type of interrupts.
+    // The PIT, the PIC and the i8042 controller are advertised unless the platform is
+    // legacy-free.
     // More info here:
     // https://uefi.org/specs/ACPI/6.5/05_ACPI_Software_Programming_Model.html?highlight=0a06#ia-pc-boot-architecture-flags
-    fadt.setup_iapc_flags(1 << IAPC_BOOT_ARG_FLAGS_VGA_NOT_PRESENT);
+    let mut iapc_flags = 1 << IAPC_BOOT_ARG_FLAGS_VGA_NOT_PRESENT;
+    if !legacy_free {
+        iapc_flags |= (1 << IAPC_BOOT_ARG_FLAGS_LEGACY_DEVICES) | (1 << IAPC_BOOT_ARG_FLAGS_8042);
+    }
+    fadt.setup_iapc_flags(iapc_flags);
+    // Point the guest to the reset register, a byte wide register in the System I/O space.
+    fadt.set_reset_reg(
+        GenericAddressStructure::new(
+            AddressSpace::SystemIo,
+            8,
+            0,
+            AccessSize::Byte,
+            PortIODeviceManager::RESET_REGISTER_ADDRESS,
+        )
+        .unwrap(),
+        RESET_VALUE,
+    );
 }
 
 #[inline(always)]
-pub(crate) fn setup_arch_dsdt(dsdt_data: &mut Vec<u8>) -> Result<(), aml::AmlError> {
-    PortIODeviceManager::append_aml_bytes(dsdt_data)
+pub(crate) fn setup_arch_dsdt(
+    legacy_devices: &PortIODeviceManager,
+    dsdt_data: &mut Vec<u8>,
+) -> Result<(), aml::AmlError> {
+    legacy_devices.append_aml_bytes(dsdt_data)
 }
 
 pub(crate) const fn apic_addr() -> u32 {
@@ -20,6 +20,7 @@ use crate::arch::{
 use crate::device_manager::DeviceManager;
 use crate::device_manager::mmio::MMIODeviceInfo;
 use crate::device_manager::pci_mngr::PciDevices;
+use crate::devices::acpi::fw_cfg::{FW_CFG_REGS_SIZE, FwCfg};
 use crate::devices::acpi::vmclock::{VMCLOCK_SIZE, VmClock};
 use crate::devices::acpi::vmgenid::{VMGENID_MEM_SIZE, VmGenId};
 use crate::initrd::InitrdConfig;
@@ -99,6 +100,9 @@ pub fn create_fdt(
     create_devices_node(&mut fdt_writer, device_manager)?;
     create_vmgenid_node(&mut fdt_writer, &device_manager.acpi_devices.vmgenid)?;
     create_vmclock_node(&mut fdt_writer, &device_manager.acpi_devices.vmclock)?;
+    if let Some(fw_cfg) = &device_manager.acpi_devices.fw_cfg {
+        create_fw_cfg_node(&mut fdt_writer, &fw_cfg.lock().expect("Poisoned lock"))?;
+    }
     create_pci_nodes(&mut fdt_writer, &device_manager.pci_devices)?;
 
     // End Header node.
@@ -301,6 +305,15 @@ fn create_vmclock_node(fdt: &mut FdtWriter, vmclock: &VmClock) -> Result<(), Fdt
     Ok(())
 }
 
+fn create_fw_cfg_node(fdt: &mut FdtWriter, fw_cfg: &FwCfg) -> Result<(), FdtError> {
+    let fw_cfg_node = fdt.begin_node(&format!("fw-cfg@{:x}", fw_cfg.addr))?;
+    fdt.property_string("compatible", "qemu,fw-cfg-mmio")?;
+    fdt.property_array_u64("reg", &[fw_cfg.addr, FW_CFG_REGS_SIZE])?;
+    fdt.property_null("dma-coherent")?;
+    fdt.end_node(fw_cfg_node)?;
+    Ok(())
+}
+
 fn create_gic_node(fdt: &mut FdtWriter, gic_device: &GICDevice) -> Result<(), FdtError> {
     let interrupt = fdt.begin_node("intc")?;
     fdt.property_string("compatible", gic_device.fdt_compatibility())?;
@@ -559,6 +572,7 @@ mod tests {
     use crate::device_manager::mmio::tests::DummyDevice;
     use crate::device_manager::tests::default_device_manager;
     use crate::test_utils::arch_mem;
+    use crate::vmm_config::serial::SerialConfig;
     use crate::vstate::memory::GuestAddress;
     use crate::{EventManager, Kvm, Vm};
 
@@ -583,7 +597,12 @@ mod tests {
         cmdline.insert("console", "/dev/tty0").unwrap();
 
         device_manager
-            .attach_legacy_devices_aarch64(&vm, &mut event_manager, &mut cmdline, None)
+            .attach_legacy_devices_aarch64(
+                &vm,
+                &mut event_manager,
+                &mut cmdline,
+                &SerialConfig::default(),
+            )
             .unwrap();
         let dummy = Arc::new(Mutex::new(DummyDevice::new()));
         device_manager
@@ -64,6 +64,10 @@ pub const SYSTEM_MEM_START: u64 = DRAM_MEM_START;
 /// image, which needs to be 2MB aligned.
 pub const SYSTEM_MEM_SIZE: u64 = 0x20_0000;
 
+/// Address of the RSDP, the root of the ACPI tables, in the last page of the system memory. It is
+/// reserved along with the other tables, which are allocated from the start of the system memory.
+pub const RSDP_ADDR: u64 = SYSTEM_MEM_START + SYSTEM_MEM_SIZE - 0x1000;
+
 /// Kernel command line maximum size.
 /// As per `arch/arm64/include/uapi/asm/setup.h`.
 pub const CMDLINE_MAX_SIZE: usize = 2048;
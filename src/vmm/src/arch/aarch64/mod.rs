@@ -20,16 +20,21 @@ use std::cmp::min;
 use std::fmt::Debug;
 use std::fs::File;
 
+use acpi_tables::Ssdt;
 use linux_loader::loader::pe::PE as Loader;
 use linux_loader::loader::{Cmdline, KernelLoader};
 use vm_memory::{GuestMemoryError, GuestMemoryRegion};
 
+use crate::acpi::create_acpi_tables;
 use crate::arch::{BootProtocol, EntryPoint, arch_memory_regions_with_gap};
 use crate::cpu_config::aarch64::{CpuConfiguration, CpuConfigurationError};
 use crate::cpu_config::templates::CustomCpuTemplate;
 use crate::initrd::InitrdConfig;
 use crate::utils::{align_up, u64_to_usize, usize_to_u64};
 use crate::vmm_config::machine_config::MachineConfig;
+use crate::vmm_config::memory_map::MemoryMapRegion;
+use crate::vmm_config::numa::NumaNodeConfig;
+use crate::vmm_config::smbios::SmbiosConfig;
 use crate::vstate::memory::{
     Address, Bytes, GuestAddress, GuestMemory, GuestMemoryMmap, GuestRegionType,
 };
@@ -51,6 +56,8 @@ pub enum ConfigurationError {
     VcpuConfig(#[from] CpuConfigurationError),
     /// Error configuring the vcpu: {0}
     VcpuConfigure(#[from] KvmVcpuError),
+    /// Error configuring ACPI: {0}
+    Acpi(#[from] crate::acpi::AcpiError),
 }
 
 /// Returns a Vec of the valid memory addresses for aarch64.
@@ -95,6 +102,14 @@ pub fn configure_system_for_boot(
     entry_point: EntryPoint,
     initrd: &Option<InitrdConfig>,
     boot_cmdline: Cmdline,
+    // Custom ACPI tables are rejected on aarch64 when configured.
+    _acpi_tables: &[Ssdt],
+    // SMBIOS tables are found through EFI on aarch64, which we do not support.
+    _smbios: &SmbiosConfig,
+    // Custom memory map regions are rejected on aarch64 when configured.
+    _memory_map: &[MemoryMapRegion],
+    // So are NUMA topologies.
+    _numa_nodes: &[NumaNodeConfig],
 ) -> Result<(), ConfigurationError> {
     // Construct the base CpuConfiguration to apply CPU template onto.
     let cpu_config = CpuConfiguration::new(cpu_template, vcpus)?;
@@ -105,6 +120,7 @@ pub fn configure_system_for_boot(
     let vcpu_config = VcpuConfig {
         vcpu_count: machine_config.vcpu_count,
         smt: machine_config.smt,
+        pmu: false,
         cpu_config,
     };
 
@@ -123,6 +139,16 @@ pub fn configure_system_for_boot(
         .map(|cpu| cpu.kvm_vcpu.get_mpidr())
         .collect::<Result<Vec<_>, _>>()
         .map_err(KvmVcpuError::ConfigureRegisters)?;
+
+    // Describe the vCPUs and the GIC, with its ITS, in ACPI tables alongside the device tree.
+    create_acpi_tables(
+        vm.guest_memory(),
+        device_manager,
+        &mut vm.resource_allocator(),
+        vm.get_irqchip(),
+        &vcpu_mpidr,
+    )?;
+
     let cmdline = boot_cmdline
         .as_cstring()
         .expect("Cannot create cstring from cmdline string");
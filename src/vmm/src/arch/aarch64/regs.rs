@@ -82,12 +82,19 @@ macro_rules! arm64_sys_reg {
 // https://elixir.bootlin.com/linux/v4.20.17/source/arch/arm64/include/asm/sysreg.h#L135
 arm64_sys_reg!(MPIDR_EL1, 3, 0, 0, 0, 5);
 arm64_sys_reg!(MIDR_EL1, 3, 0, 0, 0, 0);
+arm64_sys_reg!(REVIDR_EL1, 3, 0, 0, 0, 6);
 
 // ID registers that represent cpu capabilities.
-// Needed for static cpu templates.
+// Needed for static and custom cpu templates.
 arm64_sys_reg!(ID_AA64PFR0_EL1, 3, 0, 0, 4, 0);
+arm64_sys_reg!(ID_AA64PFR1_EL1, 3, 0, 0, 4, 1);
+arm64_sys_reg!(ID_AA64ZFR0_EL1, 3, 0, 0, 4, 4);
+arm64_sys_reg!(ID_AA64DFR0_EL1, 3, 0, 0, 5, 0);
+arm64_sys_reg!(ID_AA64DFR1_EL1, 3, 0, 0, 5, 1);
 arm64_sys_reg!(ID_AA64ISAR0_EL1, 3, 0, 0, 6, 0);
 arm64_sys_reg!(ID_AA64ISAR1_EL1, 3, 0, 0, 6, 1);
+arm64_sys_reg!(ID_AA64ISAR2_EL1, 3, 0, 0, 6, 2);
+arm64_sys_reg!(ID_AA64MMFR1_EL1, 3, 0, 0, 7, 1);
 arm64_sys_reg!(ID_AA64MMFR2_EL1, 3, 0, 0, 7, 2);
 
 // Counter-timer Virtual Timer CompareValue register.
@@ -596,6 +596,7 @@ mod tests {
         let vcpu_config = VcpuConfig {
             vcpu_count: 1,
             smt: false,
+            pmu: false,
             cpu_config: CpuConfiguration::default(),
         };
 
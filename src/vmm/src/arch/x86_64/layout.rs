@@ -60,9 +60,15 @@ pub const APIC_ADDR: u32 = 0xfee0_0000;
 /// IOAPIC address
 pub const IOAPIC_ADDR: u32 = 0xfec0_0000;
 
+/// Address of the registers of the interrupt remapping unit
+pub const IOMMU_ADDR: u32 = 0xfed9_0000;
+
 /// Location of RSDP pointer in x86 machines
 pub const RSDP_ADDR: u64 = 0x000e_0000;
 
+/// Location of the SMBIOS entry point, at the start of the range guests scan for it.
+pub const SMBIOS_START: u64 = 0x000f_0000;
+
 /// Start of memory region we will use for system data (MPTable, ACPI, etc). We are putting its
 /// start address where EBDA normally starts, i.e. in the last 1 KiB of the first 640KiB of memory
 pub const SYSTEM_MEM_START: u64 = 0x9fc00;
@@ -19,8 +19,14 @@ pub mod layout;
 mod mptable;
 /// Logic for configuring x86_64 model specific registers (MSRs).
 pub mod msr;
+/// Logic for exposing the virtual PMU.
+pub mod pmu;
 /// Logic for configuring x86_64 registers.
 pub mod regs;
+/// Logic for exposing the SGX enclave page cache.
+pub mod sgx;
+/// Logic for generating the SMBIOS tables.
+mod smbios;
 /// Architecture specific vCPU code
 pub mod vcpu;
 /// Architecture specific VM state code
@@ -34,6 +40,7 @@ pub mod generated;
 use std::cmp::max;
 use std::fs::File;
 
+use acpi_tables::Ssdt;
 use kvm::Kvm;
 use layout::{
     CMDLINE_START, MMIO32_MEM_SIZE, MMIO32_MEM_START, MMIO64_MEM_SIZE, MMIO64_MEM_START,
@@ -55,13 +62,18 @@ use crate::acpi::create_acpi_tables;
 use crate::arch::{BootProtocol, SYSTEM_MEM_SIZE, SYSTEM_MEM_START, arch_memory_regions_with_gap};
 use crate::cpu_config::templates::{CustomCpuTemplate, GuestConfigError};
 use crate::cpu_config::x86_64::CpuConfiguration;
+use crate::cpu_config::x86_64::cpuid::{Cpuid, CpuidKey};
 use crate::device_manager::DeviceManager;
 use crate::initrd::InitrdConfig;
 use crate::utils::{align_down, u64_to_usize, usize_to_u64};
 use crate::vmm_config::machine_config::MachineConfig;
+use crate::vmm_config::memory_map::{MemoryMapRegion, MemoryMapRegionType};
+use crate::vmm_config::numa::NumaNodeConfig;
+use crate::vmm_config::smbios::SmbiosConfig;
 use crate::vstate::memory::{
     Address, GuestAddress, GuestMemory, GuestMemoryMmap, GuestMemoryRegion, GuestRegionType,
 };
+use crate::vstate::resources::AcpiNvsRegion;
 use crate::vstate::vcpu::KvmVcpuConfigureError;
 use crate::{Vcpu, VcpuConfig, Vm, logger};
 
@@ -71,8 +83,23 @@ const E820_RAM: u32 = 1;
 
 // Reserved area that should be avoided during memory allocations
 const E820_RESERVED: u32 = 2;
+
+// ACPI non-volatile storage, preserved across sleep states
+const E820_NVS: u32 = 4;
+
+// Persistent memory which is not described by an NFIT, as created by `memmap=X!Y`
+const E820_PRAM: u32 = 12;
 const MEMMAP_TYPE_RAM: u32 = 1;
 
+// CPUID.01H:ECX[5] (Mnemonic: VMX)
+const CPUID_1_ECX_VMX_BIT: u32 = 1 << 5;
+// CPUID.80000001H:ECX[2] (Mnemonic: SVM)
+const CPUID_80000001_ECX_SVM_BIT: u32 = 1 << 2;
+
+// IA32_FEATURE_CONTROL bits firmware sets to let the OS use VMX.
+const FEAT_CTL_LOCKED: u64 = 1 << 0;
+const FEAT_CTL_VMX_ENABLED_OUTSIDE_SMX: u64 = 1 << 2;
+
 /// Errors thrown while configuring x86_64 system.
 #[derive(Debug, thiserror::Error, displaydoc::Display)]
 pub enum ConfigurationError {
@@ -80,6 +107,8 @@ pub enum ConfigurationError {
     E820Configuration,
     /// Error writing MP table to memory: {0}
     MpTableSetup(#[from] mptable::MptableError),
+    /// Error writing SMBIOS tables to memory: {0}
+    SmbiosSetup(#[from] smbios::SmbiosError),
     /// Error writing the zero page of guest memory.
     ZeroPageSetup,
     /// Error writing module entry to guest memory.
@@ -100,6 +129,12 @@ pub enum ConfigurationError {
     VcpuConfigure(#[from] KvmVcpuConfigureError),
     /// Error configuring ACPI: {0}
     Acpi(#[from] crate::acpi::AcpiError),
+    /// Error exposing the SGX enclave page cache: {0}
+    Sgx(#[from] sgx::SgxError),
+    /// Nested virtualization is not supported by the host KVM
+    NestedVirtualizationUnsupported,
+    /// Error exposing the virtual PMU: {0}
+    Pmu(#[from] pmu::PmuError),
 }
 
 /// Returns a Vec of the valid memory addresses.
@@ -183,15 +218,39 @@ pub fn configure_system_for_boot(
     entry_point: EntryPoint,
     initrd: &Option<InitrdConfig>,
     boot_cmdline: Cmdline,
+    acpi_tables: &[Ssdt],
+    smbios: &SmbiosConfig,
+    memory_map: &[MemoryMapRegion],
+    numa_nodes: &[NumaNodeConfig],
 ) -> Result<(), ConfigurationError> {
     // Construct the base CpuConfiguration to apply CPU template onto.
     let cpu_config = CpuConfiguration::new(kvm.supported_cpuid.clone(), cpu_template, &vcpus[0])?;
+    let nested_supported = supports_nested_virtualization(&cpu_config.cpuid);
+    // The PMU is described by the host CPUID, which the template may hide.
+    let pmu_base = vm.pmu().map(|_| cpu_config.cpuid.clone());
     // Apply CPU template to the base CpuConfiguration.
-    let cpu_config = CpuConfiguration::apply_template(cpu_config, cpu_template)?;
+    let mut cpu_config = CpuConfiguration::apply_template(cpu_config, cpu_template)?;
+    // Whether hardware virtualization is exposed is up to the machine configuration only.
+    if machine_config.nested_virtualization && !nested_supported {
+        return Err(ConfigurationError::NestedVirtualizationUnsupported);
+    }
+    configure_nested_virtualization(&mut cpu_config, machine_config.nested_virtualization);
+    // Advertise the EPC section on top of the template, which may hide SGX from the guest.
+    let sgx_epc = vm.sgx_epc().map(|epc| epc.section);
+    if let Some(section) = &sgx_epc {
+        section.update_cpuid(&mut cpu_config.cpuid)?;
+    }
+    if let Some(base) = &pmu_base {
+        if !pmu::supports_pmu(base) {
+            return Err(pmu::PmuError::Unsupported.into());
+        }
+        pmu::configure_cpuid(&mut cpu_config.cpuid, base, true);
+    }
 
     let vcpu_config = VcpuConfig {
         vcpu_count: machine_config.vcpu_count,
         smt: machine_config.smt,
+        pmu: pmu_base.is_some(),
         cpu_config,
     };
 
@@ -223,9 +282,19 @@ pub fn configure_system_for_boot(
     )
     .map_err(ConfigurationError::MpTableSetup)?;
 
+    // The SMBIOS tables go right below the kernel, where guests look for them.
+    smbios::setup_smbios(vm.guest_memory(), smbios, machine_config)?;
+
+    let acpi_nvs = vm.resource_allocator().acpi_nvs_regions.clone();
     match entry_point.protocol {
         BootProtocol::PvhBoot => {
-            configure_pvh(vm.guest_memory(), GuestAddress(CMDLINE_START), initrd)?;
+            configure_pvh(
+                vm.guest_memory(),
+                GuestAddress(CMDLINE_START),
+                initrd,
+                memory_map,
+                &acpi_nvs,
+            )?;
         }
         BootProtocol::LinuxBoot => {
             configure_64bit_boot(
@@ -233,6 +302,8 @@ pub fn configure_system_for_boot(
                 GuestAddress(CMDLINE_START),
                 cmdline_size,
                 initrd,
+                memory_map,
+                &acpi_nvs,
             )?;
         }
     }
@@ -244,14 +315,59 @@ pub fn configure_system_for_boot(
         device_manager,
         &mut vm.resource_allocator(),
         vcpus,
+        acpi_tables,
+        memory_map,
+        sgx_epc.as_ref(),
+        numa_nodes,
     )?;
     Ok(())
 }
 
+/// Returns the CPUID leaf and `ECX` bit advertising hardware virtualization: VMX on Intel and SVM
+/// on AMD.
+fn virtualization_feature(cpuid: &Cpuid) -> (CpuidKey, u32) {
+    match cpuid {
+        Cpuid::Intel(_) => (CpuidKey::leaf(0x1), CPUID_1_ECX_VMX_BIT),
+        Cpuid::Amd(_) => (CpuidKey::leaf(0x8000_0001), CPUID_80000001_ECX_SVM_BIT),
+    }
+}
+
+/// Whether the CPUID advertises hardware virtualization.
+fn supports_nested_virtualization(cpuid: &Cpuid) -> bool {
+    let (key, bit) = virtualization_feature(cpuid);
+    cpuid
+        .inner()
+        .get(&key)
+        .is_some_and(|entry| entry.result.ecx & bit != 0)
+}
+
+/// Exposes or hides hardware virtualization to the guest.
+///
+/// On Intel, IA32_FEATURE_CONTROL is also locked with VMX enabled, as firmware does, so that the
+/// guest can use VMX right away.
+fn configure_nested_virtualization(cpu_config: &mut CpuConfiguration, enabled: bool) {
+    let (key, bit) = virtualization_feature(&cpu_config.cpuid);
+    if let Some(entry) = cpu_config.cpuid.inner_mut().get_mut(&key) {
+        if enabled {
+            entry.result.ecx |= bit;
+        } else {
+            entry.result.ecx &= !bit;
+        }
+    }
+    if enabled && matches!(cpu_config.cpuid, Cpuid::Intel(_)) {
+        cpu_config.msrs.insert(
+            generated::msr_index::MSR_IA32_FEAT_CTL,
+            FEAT_CTL_LOCKED | FEAT_CTL_VMX_ENABLED_OUTSIDE_SMX,
+        );
+    }
+}
+
 fn configure_pvh(
     guest_mem: &GuestMemoryMmap,
     cmdline_addr: GuestAddress,
     initrd: &Option<InitrdConfig>,
+    memory_map: &[MemoryMapRegion],
+    acpi_nvs: &[AcpiNvsRegion],
 ) -> Result<(), ConfigurationError> {
     const XEN_HVM_START_MAGIC_VALUE: u32 = 0x336e_c578;
     let himem_start = GuestAddress(layout::HIMEM_START);
@@ -279,12 +395,14 @@ fn configure_pvh(
         type_: MEMMAP_TYPE_RAM,
         ..Default::default()
     });
-    memmap.push(hvm_memmap_table_entry {
-        addr: SYSTEM_MEM_START,
-        size: SYSTEM_MEM_SIZE,
-        type_: E820_RESERVED,
-        ..Default::default()
-    });
+    for (addr, size, type_) in system_memory_entries(acpi_nvs) {
+        memmap.push(hvm_memmap_table_entry {
+            addr,
+            size,
+            type_,
+            ..Default::default()
+        });
+    }
     memmap.push(hvm_memmap_table_entry {
         addr: PCI_MMCONFIG_START,
         size: PCI_MMCONFIG_SIZE,
@@ -298,10 +416,21 @@ fn configure_pvh(
     {
         // the first 1MB is reserved for the kernel
         let addr = max(himem_start, region.start_addr());
+        for (start, end) in ram_ranges(addr, region.last_addr(), memory_map) {
+            memmap.push(hvm_memmap_table_entry {
+                addr: start,
+                size: end - start,
+                type_: MEMMAP_TYPE_RAM,
+                ..Default::default()
+            });
+        }
+    }
+
+    for region in memory_map {
         memmap.push(hvm_memmap_table_entry {
-            addr: addr.raw_value(),
-            size: region.last_addr().unchecked_offset_from(addr) + 1,
-            type_: MEMMAP_TYPE_RAM,
+            addr: region.guest_address,
+            size: region.size,
+            type_: e820_type(region.region_type),
             ..Default::default()
         });
     }
@@ -345,6 +474,8 @@ fn configure_64bit_boot(
     cmdline_addr: GuestAddress,
     cmdline_size: usize,
     initrd: &Option<InitrdConfig>,
+    memory_map: &[MemoryMapRegion],
+    acpi_nvs: &[AcpiNvsRegion],
 ) -> Result<(), ConfigurationError> {
     const KERNEL_BOOT_FLAG_MAGIC: u16 = 0xaa55;
     const KERNEL_HDR_MAGIC: u32 = 0x5372_6448;
@@ -371,15 +502,12 @@ fn configure_64bit_boot(
     }
 
     // We mark first [0x0, SYSTEM_MEM_START) region as usable RAM and the subsequent
-    // [SYSTEM_MEM_START, (SYSTEM_MEM_START + SYSTEM_MEM_SIZE)) as reserved (note
-    // SYSTEM_MEM_SIZE + SYSTEM_MEM_SIZE == HIMEM_START).
+    // [SYSTEM_MEM_START, (SYSTEM_MEM_START + SYSTEM_MEM_SIZE)) as reserved, apart from its ACPI NVS
+    // ranges (note SYSTEM_MEM_SIZE + SYSTEM_MEM_SIZE == HIMEM_START).
     add_e820_entry(&mut params, 0, layout::SYSTEM_MEM_START, E820_RAM)?;
-    add_e820_entry(
-        &mut params,
-        layout::SYSTEM_MEM_START,
-        layout::SYSTEM_MEM_SIZE,
-        E820_RESERVED,
-    )?;
+    for (addr, size, mem_type) in system_memory_entries(acpi_nvs) {
+        add_e820_entry(&mut params, addr, size, mem_type)?;
+    }
     add_e820_entry(
         &mut params,
         PCI_MMCONFIG_START,
@@ -393,11 +521,17 @@ fn configure_64bit_boot(
     {
         // the first 1MB is reserved for the kernel
         let addr = max(himem_start, region.start_addr());
+        for (start, end) in ram_ranges(addr, region.last_addr(), memory_map) {
+            add_e820_entry(&mut params, start, end - start, E820_RAM)?;
+        }
+    }
+
+    for region in memory_map {
         add_e820_entry(
             &mut params,
-            addr.raw_value(),
-            region.last_addr().unchecked_offset_from(addr) + 1,
-            E820_RAM,
+            region.guest_address,
+            region.size,
+            e820_type(region.region_type),
         )?;
     }
 
@@ -408,6 +542,63 @@ fn configure_64bit_boot(
     .map_err(|_| ConfigurationError::ZeroPageSetup)
 }
 
+/// Splits the system memory range around the ACPI NVS regions allocated in it, returning the
+/// `(addr, size, type)` entries which describe it in the memory map.
+fn system_memory_entries(acpi_nvs: &[AcpiNvsRegion]) -> Vec<(u64, u64, u32)> {
+    let end = SYSTEM_MEM_START + SYSTEM_MEM_SIZE;
+    let mut regions: Vec<_> = acpi_nvs
+        .iter()
+        .filter(|region| SYSTEM_MEM_START <= region.guest_address && region.end() <= end)
+        .collect();
+    regions.sort_by_key(|region| region.guest_address);
+
+    let mut entries = Vec::new();
+    let mut addr = SYSTEM_MEM_START;
+    for region in regions {
+        if addr < region.guest_address {
+            entries.push((addr, region.guest_address - addr, E820_RESERVED));
+        }
+        entries.push((region.guest_address, region.size, E820_NVS));
+        addr = region.end();
+    }
+    if addr < end {
+        entries.push((addr, end - addr, E820_RESERVED));
+    }
+    entries
+}
+
+/// Splits the guest memory range `[addr, last_addr]` around the memory map regions, returning the
+/// `[start, end)` ranges which are still usable RAM.
+fn ram_ranges(
+    addr: GuestAddress,
+    last_addr: GuestAddress,
+    memory_map: &[MemoryMapRegion],
+) -> Vec<(u64, u64)> {
+    let mut ranges = vec![(addr.raw_value(), last_addr.raw_value() + 1)];
+    for region in memory_map {
+        ranges = ranges
+            .into_iter()
+            .flat_map(|(start, end)| {
+                [
+                    (start, end.min(region.guest_address)),
+                    (start.max(region.end()), end),
+                ]
+            })
+            .filter(|(start, end)| start < end)
+            .collect();
+    }
+    ranges
+}
+
+/// Returns the e820 type a memory map region is reported with.
+fn e820_type(region_type: MemoryMapRegionType) -> u32 {
+    match region_type {
+        MemoryMapRegionType::Reserved => E820_RESERVED,
+        MemoryMapRegionType::Nvs => E820_NVS,
+        MemoryMapRegionType::Pmem => E820_PRAM,
+    }
+}
+
 /// Add an e820 region to the e820 map.
 /// Returns Ok(()) if successful, or an error if there is no space left in the map.
 fn add_e820_entry(
@@ -536,9 +727,10 @@ mod tests {
     use linux_loader::loader::bootparam::boot_e820_entry;
 
     use super::*;
-    use crate::arch::x86_64::layout::FIRST_ADDR_PAST_32BITS;
+    use crate::arch::x86_64::layout::{FIRST_ADDR_PAST_32BITS, ZERO_PAGE_START};
     use crate::test_utils::{arch_mem, single_region_mem};
     use crate::utils::mib_to_bytes;
+    use crate::vstate::memory::Bytes;
     use crate::vstate::resources::ResourceAllocator;
 
     #[test]
@@ -583,24 +775,111 @@ mod tests {
         let gm = arch_mem(mem_size);
         let mut resource_allocator = ResourceAllocator::new();
         mptable::setup_mptable(&gm, &mut resource_allocator, no_vcpus).unwrap();
-        configure_64bit_boot(&gm, GuestAddress(0), 0, &None).unwrap();
-        configure_pvh(&gm, GuestAddress(0), &None).unwrap();
+        configure_64bit_boot(&gm, GuestAddress(0), 0, &None, &[], &[]).unwrap();
+        configure_pvh(&gm, GuestAddress(0), &None, &[], &[]).unwrap();
 
         // Now assigning some memory that is equal to the start of the 32bit memory hole.
         let mem_size = mib_to_bytes(3328);
         let gm = arch_mem(mem_size);
         let mut resource_allocator = ResourceAllocator::new();
         mptable::setup_mptable(&gm, &mut resource_allocator, no_vcpus).unwrap();
-        configure_64bit_boot(&gm, GuestAddress(0), 0, &None).unwrap();
-        configure_pvh(&gm, GuestAddress(0), &None).unwrap();
+        configure_64bit_boot(&gm, GuestAddress(0), 0, &None, &[], &[]).unwrap();
+        configure_pvh(&gm, GuestAddress(0), &None, &[], &[]).unwrap();
 
         // Now assigning some memory that falls after the 32bit memory hole.
         let mem_size = mib_to_bytes(3330);
         let gm = arch_mem(mem_size);
         let mut resource_allocator = ResourceAllocator::new();
         mptable::setup_mptable(&gm, &mut resource_allocator, no_vcpus).unwrap();
-        configure_64bit_boot(&gm, GuestAddress(0), 0, &None).unwrap();
-        configure_pvh(&gm, GuestAddress(0), &None).unwrap();
+        configure_64bit_boot(&gm, GuestAddress(0), 0, &None, &[], &[]).unwrap();
+        configure_pvh(&gm, GuestAddress(0), &None, &[], &[]).unwrap();
+    }
+
+    #[test]
+    fn test_memory_map_regions() {
+        let region = |guest_address, size, region_type| MemoryMapRegion {
+            guest_address,
+            size,
+            region_type,
+        };
+        let memory_map = [
+            region(0x20_0000, 0x10_0000, MemoryMapRegionType::Pmem),
+            region(0x40_0000, 0x1000, MemoryMapRegionType::Nvs),
+            region(0x1_0000_0000, 0x1000, MemoryMapRegionType::Reserved),
+        ];
+        assert_eq!(
+            ram_ranges(
+                GuestAddress(0x10_0000),
+                GuestAddress(0x7f_ffff),
+                &memory_map
+            ),
+            vec![
+                (0x10_0000, 0x20_0000),
+                (0x30_0000, 0x40_0000),
+                (0x40_1000, 0x80_0000)
+            ]
+        );
+
+        let gm = arch_mem(0x80_0000);
+        configure_64bit_boot(&gm, GuestAddress(0), 0, &None, &memory_map, &[]).unwrap();
+        let params: boot_params = gm.read_obj(GuestAddress(ZERO_PAGE_START)).unwrap();
+        let entries: Vec<_> = params.e820_table[..params.e820_entries as usize]
+            .iter()
+            .map(|entry| (entry.addr, entry.size, entry.type_))
+            .collect();
+        assert_eq!(
+            entries[3..],
+            [
+                (0x10_0000, 0x10_0000, E820_RAM),
+                (0x30_0000, 0x10_0000, E820_RAM),
+                (0x40_1000, 0x3f_f000, E820_RAM),
+                (0x20_0000, 0x10_0000, E820_PRAM),
+                (0x40_0000, 0x1000, E820_NVS),
+                (0x1_0000_0000, 0x1000, E820_RESERVED),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_acpi_nvs_regions() {
+        let region = |guest_address, size| AcpiNvsRegion {
+            guest_address,
+            size,
+        };
+        let end = SYSTEM_MEM_START + SYSTEM_MEM_SIZE;
+        assert_eq!(
+            system_memory_entries(&[]),
+            [(SYSTEM_MEM_START, SYSTEM_MEM_SIZE, E820_RESERVED)]
+        );
+        // Regions outside of the system memory are reported through the memory map instead.
+        let acpi_nvs = [
+            region(end - 0x400, 0x400),
+            region(SYSTEM_MEM_START + 0x1000, 0x800),
+            region(0x40_0000, 0x1000),
+        ];
+        assert_eq!(
+            system_memory_entries(&acpi_nvs),
+            [
+                (SYSTEM_MEM_START, 0x1000, E820_RESERVED),
+                (SYSTEM_MEM_START + 0x1000, 0x800, E820_NVS),
+                (
+                    SYSTEM_MEM_START + 0x1800,
+                    SYSTEM_MEM_SIZE - 0x1c00,
+                    E820_RESERVED
+                ),
+                (end - 0x400, 0x400, E820_NVS),
+            ]
+        );
+
+        let gm = arch_mem(0x80_0000);
+        configure_64bit_boot(&gm, GuestAddress(0), 0, &None, &[], &acpi_nvs).unwrap();
+        let params: boot_params = gm.read_obj(GuestAddress(ZERO_PAGE_START)).unwrap();
+        let entries: Vec<_> = params.e820_table[..params.e820_entries as usize]
+            .iter()
+            .map(|entry| (entry.addr, entry.size, entry.type_))
+            .collect();
+        assert_eq!(entries[2], (SYSTEM_MEM_START + 0x1000, 0x800, E820_NVS));
+        assert_eq!(entries[4], (end - 0x400, 0x400, E820_NVS));
     }
 
     #[test]
@@ -644,4 +923,35 @@ mod tests {
             .is_err()
         );
     }
+
+    #[test]
+    fn test_configure_nested_virtualization() {
+        use std::collections::BTreeMap;
+
+        use crate::cpu_config::x86_64::cpuid::{AmdCpuid, CpuidEntry, IntelCpuid};
+
+        let leaf = |leaf: u32| (CpuidKey::leaf(leaf), CpuidEntry::default());
+        let mut intel = CpuConfiguration {
+            cpuid: Cpuid::Intel(IntelCpuid(BTreeMap::from([leaf(0x1)]))),
+            msrs: BTreeMap::new(),
+        };
+        assert!(!supports_nested_virtualization(&intel.cpuid));
+        configure_nested_virtualization(&mut intel, true);
+        assert!(supports_nested_virtualization(&intel.cpuid));
+        assert_eq!(
+            intel.msrs[&generated::msr_index::MSR_IA32_FEAT_CTL],
+            FEAT_CTL_LOCKED | FEAT_CTL_VMX_ENABLED_OUTSIDE_SMX
+        );
+
+        let mut amd = CpuConfiguration {
+            cpuid: Cpuid::Amd(AmdCpuid(BTreeMap::from([leaf(0x1), leaf(0x8000_0001)]))),
+            msrs: BTreeMap::new(),
+        };
+        configure_nested_virtualization(&mut amd, true);
+        assert!(supports_nested_virtualization(&amd.cpuid));
+        assert_eq!(amd.cpuid.inner()[&CpuidKey::leaf(0x1)].result.ecx, 0);
+        assert!(amd.msrs.is_empty());
+        configure_nested_virtualization(&mut amd, false);
+        assert!(!supports_nested_virtualization(&amd.cpuid));
+    }
 }
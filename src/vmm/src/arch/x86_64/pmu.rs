@@ -0,0 +1,303 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Virtual PMU exposed to the guest.
+//!
+//! KVM emulates the PMU of the host: the architectural performance monitoring leaf 0xA on Intel
+//! and the core performance counter extensions on AMD. Both are hidden from the guest unless the
+//! PMU is configured, in which case the events the guest can count are optionally restricted by
+//! a KVM event filter.
+
+use kvm_bindings::KVMIO;
+use serde::{Deserialize, Serialize};
+use vmm_sys_util::ioctl::ioctl_with_ptr;
+use vmm_sys_util::ioctl_iow_nr;
+
+use crate::arch::x86_64::generated::msr_index::{
+    MSR_CORE_PERF_GLOBAL_CTRL, MSR_CORE_PERF_GLOBAL_OVF_CTRL, MSR_CORE_PERF_GLOBAL_STATUS,
+    MSR_F15H_PERF_CTL0,
+};
+use crate::arch::x86_64::generated::perf_event::{
+    MSR_ARCH_PERFMON_EVENTSEL0, MSR_ARCH_PERFMON_FIXED_CTR_CTRL, MSR_ARCH_PERFMON_FIXED_CTR0,
+    MSR_ARCH_PERFMON_PERFCTR0,
+};
+use crate::cpu_config::x86_64::cpuid::{Cpuid, CpuidKey};
+use crate::vmm_config::pmu::{PmuConfig, PmuEvent};
+
+// Architectural performance monitoring leaf, on Intel.
+const CPUID_PMU_LEAF: u32 = 0xA;
+// CPUID.80000001H:ECX[23] (Mnemonic: PerfCtrExtCore), on AMD.
+const CPUID_80000001_ECX_PERFCTR_CORE_BIT: u32 = 1 << 23;
+// Number of counters of the core performance counter extensions, on AMD.
+const AMD_CORE_COUNTERS: u32 = 6;
+
+// Action of the filter, letting the guest count only the listed events.
+const KVM_PMU_EVENT_ALLOW: u32 = 0;
+
+// Events counted by the Intel fixed counters, by index: instructions retired, core cycles and
+// reference cycles. A fixed counter is allowed along with its event.
+const FIXED_COUNTER_EVENTS: [PmuEvent; 3] = [
+    PmuEvent {
+        event_select: 0xc0,
+        unit_mask: 0x00,
+    },
+    PmuEvent {
+        event_select: 0x3c,
+        unit_mask: 0x00,
+    },
+    PmuEvent {
+        event_select: 0x00,
+        unit_mask: 0x03,
+    },
+];
+
+/// Errors associated with exposing the PMU to the guest.
+#[derive(Debug, PartialEq, Eq, thiserror::Error, displaydoc::Display)]
+pub enum PmuError {
+    /// The host KVM does not expose a PMU to guests
+    Unsupported,
+    /// Cannot set the PMU event filter: {0}
+    SetEventFilter(kvm_ioctls::Error),
+}
+
+// `struct kvm_pmu_event_filter` of the KVM API, which is followed by the events.
+#[repr(C)]
+#[derive(Debug, Default)]
+struct KvmPmuEventFilter {
+    action: u32,
+    nevents: u32,
+    fixed_counter_bitmap: u32,
+    flags: u32,
+    pad: [u32; 4],
+}
+
+ioctl_iow_nr!(KVM_SET_PMU_EVENT_FILTER, KVMIO, 0xb2, KvmPmuEventFilter);
+
+/// Events the guest is allowed to count, in the encoding of KVM.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PmuEventFilter {
+    events: Vec<u64>,
+    fixed_counter_bitmap: u32,
+}
+
+impl PmuEventFilter {
+    /// Creates the filter allowing `events` only.
+    pub fn new(events: &[PmuEvent]) -> Self {
+        let fixed_counter_bitmap = FIXED_COUNTER_EVENTS
+            .iter()
+            .enumerate()
+            .filter(|(_, event)| events.contains(event))
+            .fold(0, |bitmap, (index, _)| bitmap | (1 << index));
+        PmuEventFilter {
+            events: events.iter().map(Self::encode).collect(),
+            fixed_counter_bitmap,
+        }
+    }
+
+    // Bits [7:0] of the event select go in bits [7:0] and bits [11:8] in bits [35:32], the unit
+    // mask in bits [15:8], as in the event select registers.
+    fn encode(event: &PmuEvent) -> u64 {
+        let event_select = u64::from(event.event_select);
+        (event_select & 0xff) | ((event_select & 0xf00) << 24) | (u64::from(event.unit_mask) << 8)
+    }
+
+    /// Installs the filter in the VM.
+    pub fn apply(&self, vm_fd: &kvm_ioctls::VmFd) -> Result<(), PmuError> {
+        let header_words = std::mem::size_of::<KvmPmuEventFilter>() / std::mem::size_of::<u64>();
+        let mut buffer = vec![0u64; header_words + self.events.len()];
+        buffer[header_words..].copy_from_slice(&self.events);
+        // SAFETY: The buffer starts with enough room for the header, and is aligned for it.
+        unsafe {
+            buffer
+                .as_mut_ptr()
+                .cast::<KvmPmuEventFilter>()
+                .write(KvmPmuEventFilter {
+                    action: KVM_PMU_EVENT_ALLOW,
+                    nevents: u32::try_from(self.events.len()).unwrap(),
+                    fixed_counter_bitmap: self.fixed_counter_bitmap,
+                    ..Default::default()
+                });
+        }
+        // SAFETY: The buffer holds a `struct kvm_pmu_event_filter` followed by its events.
+        let ret = unsafe { ioctl_with_ptr(vm_fd, KVM_SET_PMU_EVENT_FILTER(), buffer.as_ptr()) };
+        if ret < 0 {
+            return Err(PmuError::SetEventFilter(vmm_sys_util::errno::Error::last()));
+        }
+        Ok(())
+    }
+}
+
+/// The virtual PMU of the guest.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Pmu {
+    /// Events the guest is allowed to count, if restricted.
+    pub event_filter: Option<PmuEventFilter>,
+}
+
+impl Pmu {
+    /// Creates the PMU of `config` and installs its event filter in the VM.
+    pub fn new(vm_fd: &kvm_ioctls::VmFd, config: &PmuConfig) -> Result<Self, PmuError> {
+        let pmu = Pmu {
+            event_filter: config.allowed_events.as_deref().map(PmuEventFilter::new),
+        };
+        pmu.apply(vm_fd)?;
+        Ok(pmu)
+    }
+
+    /// Installs the event filter in the VM, which KVM does not save with the VM state.
+    pub fn apply(&self, vm_fd: &kvm_ioctls::VmFd) -> Result<(), PmuError> {
+        match &self.event_filter {
+            Some(event_filter) => event_filter.apply(vm_fd),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Whether the CPUID advertises a PMU: leaf 0xA with a non-zero version on Intel, the core
+/// performance counter extensions on AMD.
+pub fn supports_pmu(cpuid: &Cpuid) -> bool {
+    match cpuid {
+        Cpuid::Intel(_) => cpuid
+            .inner()
+            .get(&CpuidKey::leaf(CPUID_PMU_LEAF))
+            .is_some_and(|entry| entry.result.eax & 0xff != 0),
+        Cpuid::Amd(_) => cpuid
+            .inner()
+            .get(&CpuidKey::leaf(0x8000_0001))
+            .is_some_and(|entry| entry.result.ecx & CPUID_80000001_ECX_PERFCTR_CORE_BIT != 0),
+    }
+}
+
+/// Exposes or hides the PMU in the normalized CPUID of a vCPU.
+///
+/// Normalization clears leaf 0xA on Intel, which is then taken back from `base`, the CPUID before
+/// normalization.
+pub fn configure_cpuid(cpuid: &mut Cpuid, base: &Cpuid, enabled: bool) {
+    match cpuid {
+        Cpuid::Intel(_) => {
+            let key = CpuidKey::leaf(CPUID_PMU_LEAF);
+            if enabled && let Some(entry) = base.inner().get(&key) {
+                cpuid.inner_mut().insert(key, entry.clone());
+            }
+        }
+        Cpuid::Amd(_) => {
+            if let Some(entry) = cpuid.inner_mut().get_mut(&CpuidKey::leaf(0x8000_0001)) {
+                if enabled {
+                    entry.result.ecx |= CPUID_80000001_ECX_PERFCTR_CORE_BIT;
+                } else {
+                    entry.result.ecx &= !CPUID_80000001_ECX_PERFCTR_CORE_BIT;
+                }
+            }
+        }
+    }
+}
+
+/// MSRs holding the state of the PMU advertised by the normalized CPUID of a vCPU.
+///
+/// They are not part of the MSRs saved by default, since they cannot be read when the PMU is
+/// hidden from the guest.
+pub fn msrs_to_save(cpuid: &Cpuid) -> Vec<u32> {
+    let mut msrs = Vec::new();
+    match cpuid {
+        Cpuid::Intel(_) => {
+            let Some(leaf_a) = cpuid.inner().get(&CpuidKey::leaf(CPUID_PMU_LEAF)) else {
+                return msrs;
+            };
+            if leaf_a.result.eax & 0xff == 0 {
+                return msrs;
+            }
+            let gp_counters = (leaf_a.result.eax >> 8) & 0xff;
+            let fixed_counters = leaf_a.result.edx & 0x1f;
+            msrs.extend((0..gp_counters).map(|i| MSR_ARCH_PERFMON_PERFCTR0 + i));
+            msrs.extend((0..gp_counters).map(|i| MSR_ARCH_PERFMON_EVENTSEL0 + i));
+            msrs.extend((0..fixed_counters).map(|i| MSR_ARCH_PERFMON_FIXED_CTR0 + i));
+            msrs.extend([
+                MSR_ARCH_PERFMON_FIXED_CTR_CTRL,
+                MSR_CORE_PERF_GLOBAL_STATUS,
+                MSR_CORE_PERF_GLOBAL_CTRL,
+                MSR_CORE_PERF_GLOBAL_OVF_CTRL,
+            ]);
+        }
+        Cpuid::Amd(_) => {
+            if supports_pmu(cpuid) {
+                // Control and counter MSRs are interleaved.
+                msrs.extend((0..2 * AMD_CORE_COUNTERS).map(|i| MSR_F15H_PERF_CTL0 + i));
+            }
+        }
+    }
+    msrs
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::cpu_config::x86_64::cpuid::{AmdCpuid, CpuidEntry, CpuidRegisters, IntelCpuid};
+
+    #[test]
+    fn test_event_filter() {
+        let events = [
+            PmuEvent {
+                event_select: 0x3c,
+                unit_mask: 0,
+            },
+            PmuEvent {
+                event_select: 0x1c2,
+                unit_mask: 0x3f,
+            },
+        ];
+        let filter = PmuEventFilter::new(&events);
+        assert_eq!(filter.events, [0x3c, 0x1_0000_3fc2]);
+        assert_eq!(filter.fixed_counter_bitmap, 0b10);
+        assert_eq!(PmuEventFilter::new(&[]), PmuEventFilter::default());
+    }
+
+    #[test]
+    fn test_configure_cpuid() {
+        let entry = |eax, ecx, edx| CpuidEntry {
+            result: CpuidRegisters {
+                eax,
+                ecx,
+                edx,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let base = Cpuid::Intel(IntelCpuid(BTreeMap::from([(
+            CpuidKey::leaf(0xA),
+            entry(0x0830_0805, 0, 0x603),
+        )])));
+        assert!(supports_pmu(&base));
+        let mut intel = Cpuid::Intel(IntelCpuid(BTreeMap::from([(
+            CpuidKey::leaf(0xA),
+            entry(0, 0, 0),
+        )])));
+        assert!(!supports_pmu(&intel));
+        configure_cpuid(&mut intel, &base, false);
+        assert!(!supports_pmu(&intel));
+        assert!(msrs_to_save(&intel).is_empty());
+        configure_cpuid(&mut intel, &base, true);
+        assert_eq!(intel, base);
+        // 8 general purpose counters and 3 fixed counters.
+        let msrs = msrs_to_save(&intel);
+        assert_eq!(msrs.len(), 2 * 8 + 3 + 4);
+        assert!(msrs.contains(&0x18d));
+        assert!(msrs.contains(&0x30b));
+        assert!(!msrs.contains(&0x30c));
+
+        let base = Cpuid::Amd(AmdCpuid(BTreeMap::from([(
+            CpuidKey::leaf(0x8000_0001),
+            entry(0, CPUID_80000001_ECX_PERFCTR_CORE_BIT, 0),
+        )])));
+        assert!(supports_pmu(&base));
+        let mut amd = base.clone();
+        configure_cpuid(&mut amd, &base, false);
+        assert!(!supports_pmu(&amd));
+        assert!(msrs_to_save(&amd).is_empty());
+        configure_cpuid(&mut amd, &base, true);
+        assert_eq!(amd, base);
+        assert_eq!(msrs_to_save(&amd).last(), Some(&0xc001_020b));
+    }
+}
@@ -0,0 +1,257 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! SGX enclave page cache (EPC) exposed to the guest.
+//!
+//! The EPC section is backed by the host virtual EPC device, mapped in the guest physical address
+//! space past the 64-bit MMIO window, and advertised through CPUID leaf 0x12 and the `INT0E0C`
+//! ACPI device, like on physical machines.
+
+use std::fs::{File, OpenOptions};
+use std::os::fd::AsRawFd;
+
+use acpi_tables::{Aml, aml};
+use kvm_bindings::kvm_userspace_memory_region;
+use log::debug;
+use vm_allocator::AllocPolicy;
+
+use crate::arch::GUEST_PAGE_SIZE;
+use crate::cpu_config::x86_64::cpuid::{
+    Cpuid, CpuidEntry, CpuidKey, CpuidRegisters, KvmCpuidFlags,
+};
+use crate::utils::{mib_to_bytes, u64_to_usize, usize_to_u64};
+use crate::vmm_config::sgx::SgxConfig;
+use crate::vstate::vm::{Vm, VmError};
+
+/// Path of the host device backing the EPC of guests.
+const SGX_VEPC_PATH: &str = "/dev/sgx_vepc";
+
+// CPUID.(EAX=07H,ECX=0):EBX[2] (Mnemonic: SGX)
+const CPUID_7_EBX_SGX_BIT: u32 = 1 << 2;
+// Leaf enumerating the SGX capabilities, the EPC sections start at subleaf 2.
+const CPUID_SGX_LEAF: u32 = 0x12;
+const CPUID_SGX_EPC_SUBLEAF: u32 = 2;
+// Subleaf type of a valid EPC section, in EAX[3:0].
+const EPC_SECTION_VALID: u32 = 0x1;
+// Section property reported in ECX[3:0]: confidentiality, integrity and replay protection.
+const EPC_SECTION_PROTECTED: u32 = 0x1;
+
+/// Errors associated with exposing the EPC to the guest.
+#[derive(Debug, thiserror::Error, displaydoc::Display)]
+pub enum SgxError {
+    /// Cannot open the host virtual EPC device: {0}
+    OpenVepc(std::io::Error),
+    /// Cannot map the host virtual EPC device: {0}
+    MmapVepc(std::io::Error),
+    /// Cannot allocate a guest address for the EPC section: {0}
+    Allocate(#[from] vm_allocator::Error),
+    /// Unable to allocate a KVM slot for the EPC section
+    NoKvmSlotAvailable,
+    /// Cannot set the EPC memory region: {0}
+    SetUserMemoryRegion(#[from] VmError),
+    /// The guest CPUID does not enumerate SGX capabilities in leaf 0x12
+    MissingSgxLeaf,
+}
+
+/// Location of an EPC section in the guest physical address space.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EpcSection {
+    /// Guest physical address of the section.
+    pub guest_address: u64,
+    /// Size of the section in bytes.
+    pub size: u64,
+}
+
+impl EpcSection {
+    /// Advertises SGX and the EPC section in the guest CPUID.
+    pub fn update_cpuid(&self, cpuid: &mut Cpuid) -> Result<(), SgxError> {
+        let guest_cpuid = cpuid.inner_mut();
+        if !guest_cpuid.contains_key(&CpuidKey::subleaf(CPUID_SGX_LEAF, 0)) {
+            return Err(SgxError::MissingSgxLeaf);
+        }
+        if let Some(leaf_7_0) = guest_cpuid.get_mut(&CpuidKey::subleaf(0x7, 0)) {
+            leaf_7_0.result.ebx |= CPUID_7_EBX_SGX_BIT;
+        }
+
+        // Bits [31:12] of the address and size go in EAX and ECX, bits [51:32] in EBX and EDX.
+        let low = |value: u64| u32::try_from(value & 0xffff_f000).unwrap();
+        let high = |value: u64| u32::try_from((value >> 32) & 0x000f_ffff).unwrap();
+        guest_cpuid.insert(
+            CpuidKey::subleaf(CPUID_SGX_LEAF, CPUID_SGX_EPC_SUBLEAF),
+            CpuidEntry {
+                flags: KvmCpuidFlags::SIGNIFICANT_INDEX,
+                result: CpuidRegisters {
+                    eax: low(self.guest_address) | EPC_SECTION_VALID,
+                    ebx: high(self.guest_address),
+                    ecx: low(self.size) | EPC_SECTION_PROTECTED,
+                    edx: high(self.size),
+                },
+            },
+        );
+        Ok(())
+    }
+}
+
+impl Aml for EpcSection {
+    fn append_aml_bytes(&self, v: &mut Vec<u8>) -> Result<(), aml::AmlError> {
+        aml::Device::new(
+            "_SB_.EPC_".try_into()?,
+            vec![
+                &aml::Name::new("_HID".try_into()?, &aml::EisaName::new("INT0E0C")?)?,
+                &aml::Name::new("_UID".try_into()?, &aml::ZERO)?,
+                &aml::Name::new(
+                    "_CRS".try_into()?,
+                    &aml::ResourceTemplate::new(vec![&aml::AddressSpace::new_memory(
+                        aml::AddressSpaceCacheable::NotCacheable,
+                        true,
+                        self.guest_address,
+                        self.guest_address + self.size - 1,
+                    )?]),
+                )?,
+                &aml::Method::new(
+                    "_STA".try_into()?,
+                    0,
+                    false,
+                    vec![&aml::Return::new(&0x0fu8)],
+                ),
+            ],
+        )
+        .append_aml_bytes(v)
+    }
+}
+
+/// EPC section backed by the host virtual EPC device.
+#[derive(Debug)]
+pub struct SgxEpc {
+    // Keeps the virtual EPC allocated for as long as the guest uses it.
+    _file: File,
+    host_addr: u64,
+    /// Location of the section in the guest.
+    pub section: EpcSection,
+}
+
+impl SgxEpc {
+    /// Allocates an EPC section from the host and maps it in the guest physical address space.
+    pub fn new(vm: &Vm, config: &SgxConfig) -> Result<Self, SgxError> {
+        let size = usize_to_u64(mib_to_bytes(config.epc_size_mib));
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(SGX_VEPC_PATH)
+            .map_err(SgxError::OpenVepc)?;
+
+        let mut flags = libc::MAP_SHARED;
+        if config.prefault {
+            flags |= libc::MAP_POPULATE;
+        }
+        // SAFETY: We are calling the system call with valid arguments and checking the returned
+        // value
+        let host_addr = unsafe {
+            let r = libc::mmap(
+                std::ptr::null_mut(),
+                u64_to_usize(size),
+                libc::PROT_READ | libc::PROT_WRITE,
+                flags,
+                file.as_raw_fd(),
+                0,
+            );
+            if r == libc::MAP_FAILED {
+                return Err(SgxError::MmapVepc(std::io::Error::last_os_error()));
+            }
+            r as u64
+        };
+        // From here on, dropping the section unmaps it.
+        let mut epc = SgxEpc {
+            _file: file,
+            host_addr,
+            section: EpcSection {
+                guest_address: 0,
+                size,
+            },
+        };
+
+        epc.section.guest_address = vm
+            .resource_allocator()
+            .past_mmio64_memory
+            .allocate(size, usize_to_u64(GUEST_PAGE_SIZE), AllocPolicy::FirstMatch)?
+            .start();
+        let slot = vm.next_kvm_slot(1).ok_or(SgxError::NoKvmSlotAvailable)?;
+        vm.set_user_memory_region(kvm_userspace_memory_region {
+            slot,
+            guest_phys_addr: epc.section.guest_address,
+            memory_size: size,
+            userspace_addr: host_addr,
+            flags: 0,
+        })?;
+        debug!(
+            "sgx: EPC section of {size:#x} bytes at {:#x}",
+            epc.section.guest_address
+        );
+        Ok(epc)
+    }
+}
+
+impl Drop for SgxEpc {
+    fn drop(&mut self) {
+        // SAFETY: `host_addr` is a valid mapping of `section.size` bytes, as created in `new`.
+        unsafe {
+            _ = libc::munmap(
+                self.host_addr as *mut libc::c_void,
+                u64_to_usize(self.section.size),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::cpu_config::x86_64::cpuid::IntelCpuid;
+
+    #[test]
+    fn test_update_cpuid() {
+        let section = EpcSection {
+            guest_address: 0x41_0000_0000,
+            size: 0x400_0000,
+        };
+        let mut cpuid = Cpuid::Intel(IntelCpuid(BTreeMap::from([(
+            CpuidKey::subleaf(0x7, 0),
+            CpuidEntry::default(),
+        )])));
+        assert!(matches!(
+            section.update_cpuid(&mut cpuid),
+            Err(SgxError::MissingSgxLeaf)
+        ));
+
+        cpuid
+            .inner_mut()
+            .insert(CpuidKey::subleaf(0x12, 0), CpuidEntry::default());
+        section.update_cpuid(&mut cpuid).unwrap();
+        let leaf_7_0 = &cpuid.inner()[&CpuidKey::subleaf(0x7, 0)];
+        assert_eq!(leaf_7_0.result.ebx, CPUID_7_EBX_SGX_BIT);
+        let epc = &cpuid.inner()[&CpuidKey::subleaf(0x12, 2)];
+        assert_eq!(epc.flags, KvmCpuidFlags::SIGNIFICANT_INDEX);
+        assert_eq!(
+            epc.result,
+            CpuidRegisters {
+                eax: 0x1,
+                ebx: 0x41,
+                ecx: 0x400_0001,
+                edx: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_epc_aml() {
+        let section = EpcSection {
+            guest_address: 0x41_0000_0000,
+            size: 0x400_0000,
+        };
+        let mut aml = Vec::new();
+        section.append_aml_bytes(&mut aml).unwrap();
+        assert!(aml.windows(4).any(|name| name == b"EPC_"));
+    }
+}
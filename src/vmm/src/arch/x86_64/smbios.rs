@@ -0,0 +1,499 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! SMBIOS tables, as defined by the DMTF System Management BIOS Reference Specification 3.x.
+//!
+//! The 64-bit entry point is written at the start of the conventional `0xF0000-0xFFFFF` range
+//! that guests scan for it, and the structure table right after it.
+
+use log::debug;
+use uuid::Uuid;
+use vm_memory::GuestMemoryError;
+
+use crate::arch::x86_64::layout::{HIMEM_START, SMBIOS_START};
+use crate::cpu_config::x86_64::cpuid::common::get_vendor_id_from_host;
+use crate::utils::usize_to_u64;
+use crate::vmm_config::machine_config::MachineConfig;
+use crate::vmm_config::smbios::SmbiosConfig;
+use crate::vstate::memory::{Bytes, GuestAddress, GuestMemoryMmap};
+
+#[derive(Debug, thiserror::Error, displaydoc::Display)]
+pub enum SmbiosError {
+    /// The SMBIOS structures do not fit in the memory reserved for them.
+    NotEnoughMemory,
+    /// Failure to generate the system UUID: {0}
+    Uuid(aws_lc_rs::error::Unspecified),
+    /// Failure to write the SMBIOS entry point: {0}
+    WriteEntryPoint(GuestMemoryError),
+    /// Failure to write the SMBIOS structures: {0}
+    WriteTable(GuestMemoryError),
+}
+
+const SM3_ANCHOR: &[u8; 5] = b"_SM3_";
+const SM3_ENTRY_POINT_LENGTH: u8 = 0x18;
+const SMBIOS_MAJOR_VERSION: u8 = 3;
+const SMBIOS_MINOR_VERSION: u8 = 2;
+const SM3_ENTRY_POINT_REVISION: u8 = 1;
+
+// The structure table follows the entry point, aligned on 16 bytes.
+const TABLE_OFFSET: u64 = 0x20;
+
+const DEFAULT_MANUFACTURER: &str = "clawdbox";
+const DEFAULT_PRODUCT_NAME: &str = "microVM";
+const DEFAULT_VERSION: &str = "0";
+const DEFAULT_STRING: &str = "Not Specified";
+
+const TYPE_BIOS_INFORMATION: u8 = 0;
+const TYPE_SYSTEM_INFORMATION: u8 = 1;
+const TYPE_BASEBOARD_INFORMATION: u8 = 2;
+const TYPE_SYSTEM_ENCLOSURE: u8 = 3;
+const TYPE_PROCESSOR_INFORMATION: u8 = 4;
+const TYPE_PHYSICAL_MEMORY_ARRAY: u8 = 16;
+const TYPE_MEMORY_DEVICE: u8 = 17;
+const TYPE_SYSTEM_BOOT_INFORMATION: u8 = 32;
+const TYPE_END_OF_TABLE: u8 = 127;
+
+// Handles of the structures referenced by other structures.
+const HANDLE_CHASSIS: u16 = 0x0300;
+const HANDLE_MEMORY_ARRAY: u16 = 0x1000;
+// Handle value for "no structure" in references.
+const HANDLE_NONE: u16 = 0xffff;
+// Handle value for "not provided" in memory error information references.
+const HANDLE_NOT_PROVIDED: u16 = 0xfffe;
+
+// BIOS characteristics: "BIOS characteristics not supported".
+const BIOS_CHARACTERISTICS_NOT_SUPPORTED: u64 = 1 << 3;
+// BIOS characteristics extension byte 2: "SMBIOS table describes a virtual machine".
+const BIOS_CHARACTERISTICS_EXT2_VIRTUAL_MACHINE: u8 = 1 << 4;
+
+// Value of enumerated fields for "Other" and "None".
+const ENUM_OTHER: u8 = 0x01;
+const ENUM_NONE: u8 = 0x03;
+
+/// An SMBIOS structure: its formatted area followed by its strings.
+#[derive(Debug)]
+struct Structure {
+    formatted: Vec<u8>,
+    strings: Vec<String>,
+}
+
+impl Structure {
+    fn new(structure_type: u8, handle: u16) -> Self {
+        let mut formatted = vec![structure_type, 0];
+        formatted.extend_from_slice(&handle.to_le_bytes());
+        Self {
+            formatted,
+            strings: Vec::new(),
+        }
+    }
+
+    fn u8(mut self, value: u8) -> Self {
+        self.formatted.push(value);
+        self
+    }
+
+    fn u16(mut self, value: u16) -> Self {
+        self.formatted.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    fn u32(mut self, value: u32) -> Self {
+        self.formatted.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    fn u64(mut self, value: u64) -> Self {
+        self.formatted.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    fn bytes(mut self, value: &[u8]) -> Self {
+        self.formatted.extend_from_slice(value);
+        self
+    }
+
+    // Adds a string to the string set, and its 1-based number to the formatted area.
+    fn string(mut self, value: &str) -> Self {
+        self.strings.push(value.to_string());
+        // The structures we build hold a handful of strings.
+        let number = u8::try_from(self.strings.len()).unwrap();
+        self.u8(number)
+    }
+
+    fn append_to(mut self, table: &mut Vec<u8>) {
+        self.formatted[1] = u8::try_from(self.formatted.len()).unwrap();
+        table.extend_from_slice(&self.formatted);
+        if self.strings.is_empty() {
+            table.push(0);
+        }
+        for string in &self.strings {
+            table.extend_from_slice(string.as_bytes());
+            table.push(0);
+        }
+        table.push(0);
+    }
+}
+
+fn checksum(bytes: &[u8]) -> u8 {
+    bytes
+        .iter()
+        .fold(0u8, |sum, byte| sum.wrapping_add(*byte))
+        .wrapping_neg()
+}
+
+// Builds the structure table describing the microVM.
+fn build_table(config: &SmbiosConfig, machine_config: &MachineConfig, uuid: Uuid) -> Vec<u8> {
+    let manufacturer = config
+        .manufacturer
+        .as_deref()
+        .unwrap_or(DEFAULT_MANUFACTURER);
+    let product_name = config
+        .product_name
+        .as_deref()
+        .unwrap_or(DEFAULT_PRODUCT_NAME);
+    let version = config.version.as_deref().unwrap_or(DEFAULT_VERSION);
+    let serial_number = config.serial_number.as_deref().unwrap_or(DEFAULT_STRING);
+    let sku_number = config.sku_number.as_deref().unwrap_or(DEFAULT_STRING);
+    let family = config.family.as_deref().unwrap_or(DEFAULT_STRING);
+    let asset_tag = config.asset_tag.as_deref().unwrap_or(DEFAULT_STRING);
+
+    let vendor_id = get_vendor_id_from_host().ok();
+    let processor_manufacturer = vendor_id
+        .as_ref()
+        .and_then(|vendor_id| std::str::from_utf8(vendor_id).ok())
+        .unwrap_or("Unknown");
+    let thread_count = machine_config.vcpu_count;
+    let core_count = if machine_config.smt {
+        thread_count.div_ceil(2)
+    } else {
+        thread_count
+    };
+    let mem_size_mib = u32::try_from(machine_config.mem_size_mib).unwrap_or(u32::MAX);
+    let mem_size_bytes = u64::from(mem_size_mib) << 20;
+
+    let mut table = Vec::new();
+
+    Structure::new(TYPE_BIOS_INFORMATION, 0x0000)
+        .string(manufacturer)
+        .string(DEFAULT_VERSION)
+        // The BIOS starting address segment, which is meaningless without a BIOS.
+        .u16(0xe800)
+        .string(DEFAULT_STRING)
+        // ROM size.
+        .u8(0)
+        .u64(BIOS_CHARACTERISTICS_NOT_SUPPORTED)
+        .u8(0)
+        .u8(BIOS_CHARACTERISTICS_EXT2_VIRTUAL_MACHINE)
+        // System BIOS release.
+        .u8(0)
+        .u8(0)
+        // No embedded controller.
+        .u8(0xff)
+        .u8(0xff)
+        .append_to(&mut table);
+
+    Structure::new(TYPE_SYSTEM_INFORMATION, 0x0100)
+        .string(manufacturer)
+        .string(product_name)
+        .string(version)
+        .string(serial_number)
+        .bytes(&uuid.to_bytes_le())
+        // Wake-up type: power switch.
+        .u8(0x06)
+        .string(sku_number)
+        .string(family)
+        .append_to(&mut table);
+
+    Structure::new(TYPE_BASEBOARD_INFORMATION, 0x0200)
+        .string(manufacturer)
+        .string(product_name)
+        .string(version)
+        .string(serial_number)
+        .string(asset_tag)
+        // Feature flags: hosting board.
+        .u8(0x01)
+        .string(DEFAULT_STRING)
+        .u16(HANDLE_CHASSIS)
+        // Board type: motherboard.
+        .u8(0x0a)
+        // No contained object handles.
+        .u8(0)
+        .append_to(&mut table);
+
+    Structure::new(TYPE_SYSTEM_ENCLOSURE, HANDLE_CHASSIS)
+        .string(manufacturer)
+        .u8(ENUM_OTHER)
+        .string(version)
+        .string(serial_number)
+        .string(asset_tag)
+        // Boot-up, power supply and thermal states: safe.
+        .u8(0x03)
+        .u8(0x03)
+        .u8(0x03)
+        // Security status: none.
+        .u8(0x03)
+        // OEM-defined.
+        .u32(0)
+        // Height and number of power cords: unspecified.
+        .u8(0)
+        .u8(0)
+        // No contained elements.
+        .u8(0)
+        .u8(0)
+        .append_to(&mut table);
+
+    Structure::new(TYPE_PROCESSOR_INFORMATION, 0x0400)
+        .string("CPU 0")
+        // Processor type: central processor.
+        .u8(0x03)
+        .u8(ENUM_OTHER)
+        .string(processor_manufacturer)
+        // Processor ID.
+        .u64(0)
+        .string(DEFAULT_STRING)
+        // Voltage, external clock, max and current speed: unknown.
+        .u8(0)
+        .u16(0)
+        .u16(0)
+        .u16(0)
+        // Status: socket populated, CPU enabled.
+        .u8(0x41)
+        .u8(ENUM_OTHER)
+        // No cache information.
+        .u16(HANDLE_NONE)
+        .u16(HANDLE_NONE)
+        .u16(HANDLE_NONE)
+        .string(DEFAULT_STRING)
+        .string(DEFAULT_STRING)
+        .string(DEFAULT_STRING)
+        .u8(core_count)
+        .u8(core_count)
+        .u8(thread_count)
+        // Characteristics: 64-bit capable.
+        .u16(1 << 2)
+        // Processor family 2.
+        .u16(u16::from(ENUM_OTHER))
+        .u16(u16::from(core_count))
+        .u16(u16::from(core_count))
+        .u16(u16::from(thread_count))
+        .append_to(&mut table);
+
+    Structure::new(TYPE_PHYSICAL_MEMORY_ARRAY, HANDLE_MEMORY_ARRAY)
+        // Location: system board.
+        .u8(0x03)
+        // Use: system memory.
+        .u8(0x03)
+        // Error correction: none.
+        .u8(ENUM_NONE)
+        // The maximum capacity is in the extended field.
+        .u32(0x8000_0000)
+        .u16(HANDLE_NOT_PROVIDED)
+        // Number of memory devices.
+        .u16(1)
+        .u64(mem_size_bytes)
+        .append_to(&mut table);
+
+    Structure::new(TYPE_MEMORY_DEVICE, 0x1100)
+        .u16(HANDLE_MEMORY_ARRAY)
+        .u16(HANDLE_NOT_PROVIDED)
+        // Total and data width: unknown.
+        .u16(0xffff)
+        .u16(0xffff)
+        // The size is in the extended field.
+        .u16(0x7fff)
+        // Form factor: DIMM.
+        .u8(0x09)
+        // Device set: none.
+        .u8(0)
+        .string("DIMM 0")
+        .string(DEFAULT_STRING)
+        // Memory type: RAM.
+        .u8(0x07)
+        // Type detail: unknown.
+        .u16(1 << 2)
+        // Speed: unknown.
+        .u16(0)
+        .string(manufacturer)
+        .string(DEFAULT_STRING)
+        .string(DEFAULT_STRING)
+        .string(DEFAULT_STRING)
+        // Attributes: unknown rank.
+        .u8(0)
+        .u32(mem_size_mib)
+        // Configured speed and voltages: unknown.
+        .u16(0)
+        .u16(0)
+        .u16(0)
+        .u16(0)
+        .append_to(&mut table);
+
+    Structure::new(TYPE_SYSTEM_BOOT_INFORMATION, 0x2000)
+        // Reserved.
+        .bytes(&[0; 6])
+        // Boot status: no errors detected.
+        .u8(0)
+        .append_to(&mut table);
+
+    Structure::new(TYPE_END_OF_TABLE, 0x7f00).append_to(&mut table);
+
+    table
+}
+
+// Builds the 64-bit entry point for a structure table of `table_len` bytes.
+fn build_entry_point(table_len: u32) -> [u8; SM3_ENTRY_POINT_LENGTH as usize] {
+    let mut entry_point = [0u8; SM3_ENTRY_POINT_LENGTH as usize];
+    entry_point[..5].copy_from_slice(SM3_ANCHOR);
+    entry_point[6] = SM3_ENTRY_POINT_LENGTH;
+    entry_point[7] = SMBIOS_MAJOR_VERSION;
+    entry_point[8] = SMBIOS_MINOR_VERSION;
+    entry_point[10] = SM3_ENTRY_POINT_REVISION;
+    entry_point[12..16].copy_from_slice(&table_len.to_le_bytes());
+    entry_point[16..24].copy_from_slice(&(SMBIOS_START + TABLE_OFFSET).to_le_bytes());
+    entry_point[5] = checksum(&entry_point);
+    entry_point
+}
+
+/// Writes the SMBIOS entry point and structure table describing the microVM to guest memory.
+pub fn setup_smbios(
+    mem: &GuestMemoryMmap,
+    config: &SmbiosConfig,
+    machine_config: &MachineConfig,
+) -> Result<(), SmbiosError> {
+    // The configuration was validated when it was set.
+    let uuid = match config.uuid().ok().flatten() {
+        Some(uuid) => uuid,
+        None => {
+            let mut bytes = [0u8; 16];
+            aws_lc_rs::rand::fill(&mut bytes).map_err(SmbiosError::Uuid)?;
+            uuid::Builder::from_random_bytes(bytes).into_uuid()
+        }
+    };
+    let table = build_table(config, machine_config, uuid);
+    if SMBIOS_START + TABLE_OFFSET + usize_to_u64(table.len()) > HIMEM_START {
+        return Err(SmbiosError::NotEnoughMemory);
+    }
+    let entry_point = build_entry_point(u32::try_from(table.len()).unwrap());
+
+    debug!("smbios: writing {} bytes of structures", table.len());
+    mem.write_slice(&table, GuestAddress(SMBIOS_START + TABLE_OFFSET))
+        .map_err(SmbiosError::WriteTable)?;
+    mem.write_slice(&entry_point, GuestAddress(SMBIOS_START))
+        .map_err(SmbiosError::WriteEntryPoint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::single_region_mem;
+
+    // Returns the formatted area and the strings of each structure of `table`.
+    fn parse_table(table: &[u8]) -> Vec<(&[u8], Vec<&str>)> {
+        let mut structures = Vec::new();
+        let mut offset = 0;
+        while offset < table.len() {
+            let formatted = &table[offset..offset + table[offset + 1] as usize];
+            offset += formatted.len();
+            let end = offset
+                + table[offset..]
+                    .windows(2)
+                    .position(|window| window == [0, 0])
+                    .unwrap();
+            let strings = std::str::from_utf8(&table[offset..end])
+                .unwrap()
+                .split('\0')
+                .filter(|string| !string.is_empty())
+                .collect();
+            structures.push((formatted, strings));
+            offset = end + 2;
+        }
+        structures
+    }
+
+    #[test]
+    fn test_smbios_table() {
+        let config = SmbiosConfig {
+            product_name: Some("test product".to_string()),
+            serial_number: Some("1234".to_string()),
+            ..Default::default()
+        };
+        let machine_config = MachineConfig {
+            vcpu_count: 4,
+            smt: true,
+            mem_size_mib: 8192,
+            ..Default::default()
+        };
+        let uuid = Uuid::parse_str("e5c937d0-3553-4d7a-9117-ea4d19c3434d").unwrap();
+        let table = build_table(&config, &machine_config, uuid);
+        let structures = parse_table(&table);
+
+        let types: Vec<_> = structures
+            .iter()
+            .map(|(formatted, _)| formatted[0])
+            .collect();
+        assert_eq!(types, [0, 1, 2, 3, 4, 16, 17, 32, 127]);
+        let lengths: Vec<_> = structures
+            .iter()
+            .map(|(formatted, _)| formatted[1])
+            .collect();
+        assert_eq!(
+            lengths,
+            [0x18, 0x1b, 0x0f, 0x15, 0x30, 0x17, 0x28, 0x0b, 0x04]
+        );
+
+        // System information.
+        let (system, strings) = &structures[1];
+        assert_eq!(
+            strings[..4],
+            [
+                DEFAULT_MANUFACTURER,
+                "test product",
+                DEFAULT_VERSION,
+                "1234"
+            ]
+        );
+        // The first three fields of the UUID are little endian.
+        assert_eq!(
+            system[8..24],
+            [
+                0xd0, 0x37, 0xc9, 0xe5, 0x53, 0x35, 0x7a, 0x4d, 0x91, 0x17, 0xea, 0x4d, 0x19, 0xc3,
+                0x43, 0x4d
+            ]
+        );
+
+        // Processor information: 2 cores with 2 threads each.
+        let (processor, _) = &structures[4];
+        assert_eq!(processor[0x23..0x26], [2, 2, 4]);
+
+        // Memory device: the extended size is in MiB.
+        let (memory, _) = &structures[6];
+        assert_eq!(memory[0x0c..0x0e], 0x7fffu16.to_le_bytes());
+        assert_eq!(memory[0x1c..0x20], 8192u32.to_le_bytes());
+    }
+
+    #[test]
+    fn test_setup_smbios() {
+        let mem = single_region_mem(usize::try_from(HIMEM_START).unwrap());
+        setup_smbios(&mem, &SmbiosConfig::default(), &MachineConfig::default()).unwrap();
+
+        let mut entry_point = [0u8; SM3_ENTRY_POINT_LENGTH as usize];
+        mem.read_slice(&mut entry_point, GuestAddress(SMBIOS_START))
+            .unwrap();
+        assert_eq!(&entry_point[..5], SM3_ANCHOR);
+        assert_eq!(checksum(&entry_point), 0);
+        let table_len = u32::from_le_bytes(entry_point[12..16].try_into().unwrap());
+        let table_addr = u64::from_le_bytes(entry_point[16..24].try_into().unwrap());
+        assert_eq!(table_addr, SMBIOS_START + TABLE_OFFSET);
+
+        let mut table = vec![0u8; table_len as usize];
+        mem.read_slice(&mut table, GuestAddress(table_addr))
+            .unwrap();
+        let structures = parse_table(&table);
+        assert_eq!(structures.last().unwrap().0[0], TYPE_END_OF_TABLE);
+        // A random version 4 UUID is generated when none is configured.
+        assert_eq!(structures[1].0[8 + 7] >> 4, 4);
+
+        // Without guest memory at the conventional address, the tables cannot be written.
+        let mem = single_region_mem(usize::try_from(SMBIOS_START).unwrap());
+        setup_smbios(&mem, &SmbiosConfig::default(), &MachineConfig::default()).unwrap_err();
+    }
+}
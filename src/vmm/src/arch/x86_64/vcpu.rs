@@ -7,7 +7,7 @@
 
 use std::collections::BTreeMap;
 use std::fmt::Debug;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 use kvm_bindings::{
     CpuId, KVM_MAX_CPUID_ENTRIES, KVM_MAX_MSR_ENTRIES, Msrs, Xsave, kvm_debugregs, kvm_lapic_state,
@@ -20,10 +20,12 @@ use vmm_sys_util::fam::{self, FamStruct};
 
 use crate::arch::EntryPoint;
 use crate::arch::x86_64::generated::msr_index::{MSR_IA32_TSC, MSR_IA32_TSC_DEADLINE};
-use crate::arch::x86_64::interrupts;
 use crate::arch::x86_64::msr::{MsrError, create_boot_msr_entries};
 use crate::arch::x86_64::regs::{SetupFpuError, SetupRegistersError, SetupSpecialRegistersError};
+use crate::arch::x86_64::{interrupts, pmu};
 use crate::cpu_config::x86_64::{CpuConfiguration, cpuid};
+use crate::devices::acpi::cppc::Cppc;
+use crate::devices::legacy::Ioapic;
 use crate::logger::{IncMetric, METRICS};
 use crate::vstate::bus::Bus;
 use crate::vstate::memory::GuestMemoryMmap;
@@ -164,6 +166,12 @@ pub struct Peripherals {
     pub pio_bus: Option<Arc<Bus>>,
     /// Mmio bus.
     pub mmio_bus: Option<Arc<Bus>>,
+    /// Whether a triple fault resets the microVM instead of failing the vCPU.
+    pub reset_on_triple_fault: bool,
+    /// IOAPIC emulated in userspace, notified of the end of level-triggered interrupts.
+    pub ioapic: Option<Arc<Mutex<Ioapic>>>,
+    /// CPPC device, applying the performance requests of the guest to the vCPU thread.
+    pub cppc: Option<Arc<Mutex<Cppc>>>,
 }
 
 impl KvmVcpu {
@@ -214,6 +222,11 @@ impl KvmVcpu {
             // The number of bits needed to enumerate logical CPUs per core.
             u8::from(vcpu_config.vcpu_count > 1 && vcpu_config.smt),
         )?;
+        // Normalization hides the PMU, which is exposed again if configured.
+        pmu::configure_cpuid(&mut cpuid, &vcpu_config.cpu_config.cpuid, vcpu_config.pmu);
+        if vcpu_config.pmu {
+            self.msrs_to_save.extend(pmu::msrs_to_save(&cpuid));
+        }
 
         // Set CPUID.
         let kvm_cpuid = kvm_bindings::CpuId::try_from(cpuid)?;
@@ -649,7 +662,7 @@ impl KvmVcpu {
     }
 
     /// Use provided state to populate KVM internal state.
-    pub fn restore_state(&self, state: &VcpuState) -> Result<(), KvmVcpuError> {
+    pub fn restore_state(&mut self, state: &VcpuState) -> Result<(), KvmVcpuError> {
         // Ordering requirements:
         //
         // KVM_GET_VCPU_EVENTS/KVM_SET_VCPU_EVENTS is unsafe if other vCPUs are
@@ -709,6 +722,12 @@ impl KvmVcpu {
                 return Err(KvmVcpuError::VcpuSetMsrsIncomplete);
             }
         }
+        // Keep saving the MSRs added when the vCPU was configured, such as the PMU ones.
+        for entry in state.saved_msrs.iter().flat_map(|msrs| msrs.as_slice()) {
+            if !self.msrs_to_save.contains(&entry.index) {
+                self.msrs_to_save.push(entry.index);
+            }
+        }
         self.fd
             .set_vcpu_events(&state.vcpu_events)
             .map_err(KvmVcpuError::VcpuSetVcpuEvents)?;
@@ -744,6 +763,20 @@ impl Peripherals {
                 }
                 Ok(VcpuEmulation::Handled)
             }
+            // With a split irqchip, KVM exits when the guest acknowledges a level-triggered
+            // interrupt of the IOAPIC.
+            VcpuExit::IoapicEoi(vector) => {
+                if let Some(ioapic) = &self.ioapic {
+                    ioapic
+                        .lock()
+                        .expect("Poisoned lock")
+                        .end_of_interrupt(vector);
+                }
+                Ok(VcpuEmulation::Handled)
+            }
+            // A triple fault resets the CPU on real hardware, and is the last resort of guests
+            // trying to reboot.
+            VcpuExit::Shutdown if self.reset_on_triple_fault => Ok(VcpuEmulation::Reset),
             unexpected_exit => {
                 METRICS.vcpu.failures.inc();
                 error!("Unexpected exit reason on vcpu run: {:?}", unexpected_exit);
@@ -866,6 +899,7 @@ mod tests {
         Ok(VcpuConfig {
             vcpu_count: 1,
             smt: false,
+            pmu: false,
             cpu_config,
         })
     }
@@ -976,7 +1010,7 @@ mod tests {
 
     #[test]
     fn test_vcpu_cpuid_restore() {
-        let (kvm, _, vcpu) = setup_vcpu(0x10000);
+        let (kvm, _, mut vcpu) = setup_vcpu(0x10000);
         vcpu.fd.set_cpuid2(&kvm.supported_cpuid).unwrap();
 
         // Mutate the CPUID.
@@ -994,7 +1028,7 @@ mod tests {
         drop(vcpu);
 
         // Restore the state into a new vcpu.
-        let (_, _vm, vcpu) = setup_vcpu(0x10000);
+        let (_, _vm, mut vcpu) = setup_vcpu(0x10000);
         let result2 = vcpu.restore_state(&state);
         assert!(result2.is_ok(), "{}", result2.unwrap_err());
 
@@ -1018,6 +1052,7 @@ mod tests {
         let vcpu_config = VcpuConfig {
             vcpu_count: 1,
             smt: false,
+            pmu: false,
             cpu_config: CpuConfiguration {
                 cpuid: Cpuid::try_from(kvm.supported_cpuid.clone()).unwrap(),
                 msrs: BTreeMap::new(),
@@ -1087,6 +1122,7 @@ mod tests {
         let vcpu_config = VcpuConfig {
             vcpu_count: 1,
             smt: false,
+            pmu: false,
             cpu_config: CpuConfiguration {
                 cpuid: Cpuid::try_from(kvm.supported_cpuid.clone()).unwrap(),
                 msrs: BTreeMap::new(),
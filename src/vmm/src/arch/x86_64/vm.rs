@@ -4,18 +4,28 @@
 use std::fmt;
 use std::sync::{Arc, Mutex};
 
+use crc64::crc64;
 use kvm_bindings::{
-    KVM_CLOCK_TSC_STABLE, KVM_IRQCHIP_IOAPIC, KVM_IRQCHIP_PIC_MASTER, KVM_IRQCHIP_PIC_SLAVE,
-    KVM_PIT_SPEAKER_DUMMY, MsrList, kvm_clock_data, kvm_irqchip, kvm_pit_config, kvm_pit_state2,
+    KVM_CAP_SPLIT_IRQCHIP, KVM_CAP_X2APIC_API, KVM_CLOCK_TSC_STABLE, KVM_IRQ_ROUTING_MSI,
+    KVM_IRQCHIP_IOAPIC, KVM_IRQCHIP_PIC_MASTER, KVM_IRQCHIP_PIC_SLAVE, KVM_PIT_SPEAKER_DUMMY,
+    KVM_X2APIC_API_DISABLE_BROADCAST_QUIRK, KVM_X2APIC_API_USE_32BIT_IDS, MsrList, kvm_clock_data,
+    kvm_enable_cap, kvm_irq_routing_entry, kvm_irqchip, kvm_pit_config, kvm_pit_state2,
 };
 use kvm_ioctls::Cap;
 use serde::{Deserialize, Serialize};
 
 use crate::arch::x86_64::msr::MsrError;
+use crate::arch::x86_64::pmu::{Pmu, PmuError};
+use crate::arch::x86_64::sgx::{SgxEpc, SgxError};
+use crate::devices::legacy::ioapic::IOAPIC_NUM_PINS;
+use crate::devices::legacy::iommu::InterruptRemappingTable;
 use crate::snapshot::Persist;
-use crate::utils::u64_to_usize;
+use crate::utils::{u64_to_usize, usize_to_u64};
+use crate::vmm_config::pmu::PmuConfig;
+use crate::vmm_config::sgx::SgxConfig;
 use crate::vstate::bus::Bus;
-use crate::vstate::memory::{GuestMemoryExtension, GuestMemoryState};
+use crate::vstate::interrupts::MsixVectorConfig;
+use crate::vstate::memory::{Bytes, GuestAddress, GuestMemoryExtension, GuestMemoryState};
 use crate::vstate::resources::ResourceAllocator;
 use crate::vstate::vm::{VmCommon, VmError};
 
@@ -44,10 +54,22 @@ pub enum ArchVmError {
     VmGetIrqChip(kvm_ioctls::Error),
     /// Failed to set KVM vm irqchip: {0}
     VmSetIrqChip(kvm_ioctls::Error),
+    /// Failed to split the KVM vm irqchip: {0}
+    VmSplitIrqChip(kvm_ioctls::Error),
+    /// Failed to enable the x2APIC API of KVM: {0}
+    VmX2apicApi(kvm_ioctls::Error),
     /// Failed to get MSR index list to save into snapshots: {0}
     GetMsrsToSave(MsrError),
     /// Failed during KVM_SET_TSS_ADDRESS: {0}
     SetTssAddress(kvm_ioctls::Error),
+    /// Failed to restore the virtual PMU: {0}
+    Pmu(#[from] PmuError),
+    /// ACPI NVS region {0:#x} is not in guest memory
+    AcpiNvsAccess(u64),
+    /// The ACPI NVS regions of the snapshot do not match the ones of the microVM
+    AcpiNvsMismatch,
+    /// ACPI NVS region {0:#x} is corrupted in the snapshot
+    AcpiNvsChecksum(u64),
 }
 
 /// Structure representing the current architecture's understand of what a "virtual machine" is.
@@ -62,6 +84,15 @@ pub struct ArchVm {
     xsave2_size: Option<usize>,
     /// Port IO bus
     pub pio_bus: Arc<Bus>,
+    /// SGX enclave page cache section exposed to the guest, if any.
+    sgx_epc: Option<SgxEpc>,
+    /// Virtual PMU exposed to the guest, if any.
+    pmu: Option<Pmu>,
+    /// Whether KVM only emulates the local APICs, leaving the IOAPIC to userspace.
+    split_irqchip: bool,
+    /// Interrupt remapping table the MSI messages go through, if the guest enabled interrupt
+    /// remapping.
+    interrupt_remapping: Mutex<Option<InterruptRemappingTable>>,
 }
 
 impl ArchVm {
@@ -103,6 +134,10 @@ impl ArchVm {
             msrs_to_save,
             xsave2_size,
             pio_bus,
+            sgx_epc: None,
+            pmu: None,
+            split_irqchip: false,
+            interrupt_remapping: Mutex::new(None),
         })
     }
 
@@ -117,6 +152,76 @@ impl ArchVm {
         Ok(())
     }
 
+    /// Makes [`ArchVm::setup_irqchip`] split the irqchip: KVM only emulates the local APICs, and
+    /// the IOAPIC is emulated in userspace. There is no PIC nor PIT then.
+    ///
+    /// Must be called before the vCPUs are created.
+    pub fn set_split_irqchip(&mut self, split_irqchip: bool) {
+        self.split_irqchip = split_irqchip;
+    }
+
+    /// Whether the IOAPIC is emulated in userspace.
+    pub fn split_irqchip(&self) -> bool {
+        self.split_irqchip
+    }
+
+    /// Makes the MSI messages go through the interrupt remapping table `table`, or be delivered as
+    /// they are if it is `None`.
+    pub fn set_interrupt_remapping(&self, table: Option<InterruptRemappingTable>) {
+        *self.interrupt_remapping.lock().expect("Poisoned lock") = table;
+    }
+
+    /// Returns the MSI message delivering the interrupt `config` describes, or `None` if
+    /// interrupt remapping blocks it.
+    pub fn remap_msi(&self, config: MsixVectorConfig) -> Option<MsixVectorConfig> {
+        match *self.interrupt_remapping.lock().expect("Poisoned lock") {
+            Some(table) => table.remap(self.guest_memory(), config),
+            None => Some(config),
+        }
+    }
+
+    /// Returns the route KVM delivers the interrupts of `entry` through, or `None` if interrupt
+    /// remapping blocks them.
+    pub fn remap_route(&self, mut entry: kvm_irq_routing_entry) -> Option<kvm_irq_routing_entry> {
+        if entry.type_ != KVM_IRQ_ROUTING_MSI {
+            return Some(entry);
+        }
+        // SAFETY: The routes of type MSI hold an MSI message.
+        let msi = unsafe { entry.u.msi };
+        let config = self.remap_msi(MsixVectorConfig {
+            high_addr: msi.address_hi,
+            low_addr: msi.address_lo,
+            data: msi.data,
+            devid: 0,
+        })?;
+        entry.u.msi.address_lo = config.low_addr;
+        entry.u.msi.address_hi = config.high_addr;
+        entry.u.msi.data = config.data;
+        Some(entry)
+    }
+
+    /// Allocates an SGX enclave page cache section and maps it in the guest.
+    pub fn setup_sgx_epc(&mut self, config: &SgxConfig) -> Result<(), SgxError> {
+        self.sgx_epc = Some(SgxEpc::new(self, config)?);
+        Ok(())
+    }
+
+    /// Returns the SGX enclave page cache section exposed to the guest, if any.
+    pub fn sgx_epc(&self) -> Option<&SgxEpc> {
+        self.sgx_epc.as_ref()
+    }
+
+    /// Exposes the virtual PMU to the guest, restricted to the allowed events.
+    pub fn setup_pmu(&mut self, config: &PmuConfig) -> Result<(), PmuError> {
+        self.pmu = Some(Pmu::new(self.fd(), config)?);
+        Ok(())
+    }
+
+    /// Returns the virtual PMU exposed to the guest, if any.
+    pub fn pmu(&self) -> Option<&Pmu> {
+        self.pmu.as_ref()
+    }
+
     /// Restores the KVM VM state.
     ///
     /// # Errors
@@ -128,12 +233,46 @@ impl ArchVm {
     /// - [`kvm_ioctls::VmFd::set_irqchip`] errors.
     /// - [`kvm_ioctls::VmFd::set_irqchip`] errors.
     pub fn restore_state(&mut self, state: &VmState) -> Result<(), ArchVmError> {
-        self.fd()
-            .set_pit2(&state.pitstate)
-            .map_err(ArchVmError::SetPit2)?;
         self.fd()
             .set_clock(&state.clock)
             .map_err(ArchVmError::SetClock)?;
+        self.restore_irqchip_state(state)?;
+        self.common.resource_allocator = Mutex::new(state.resource_allocator.clone());
+        self.restore_acpi_nvs(state)?;
+        if let Some(pmu) = &state.pmu {
+            pmu.apply(self.fd())?;
+        }
+        self.pmu = state.pmu.clone();
+        Ok(())
+    }
+
+    /// Writes the ACPI NVS regions saved in `state` back to guest memory, at the addresses of the
+    /// regions of its resource allocator.
+    fn restore_acpi_nvs(&self, state: &VmState) -> Result<(), ArchVmError> {
+        let regions = &state.resource_allocator.acpi_nvs_regions;
+        if regions.len() != state.acpi_nvs.len() {
+            return Err(ArchVmError::AcpiNvsMismatch);
+        }
+        for (region, saved) in regions.iter().zip(&state.acpi_nvs) {
+            let addr = region.guest_address;
+            if saved.guest_address != addr || usize_to_u64(saved.data.len()) != region.size {
+                return Err(ArchVmError::AcpiNvsMismatch);
+            }
+            if crc64(0, &saved.data) != saved.crc {
+                return Err(ArchVmError::AcpiNvsChecksum(addr));
+            }
+            self.guest_memory()
+                .write_slice(&saved.data, GuestAddress(addr))
+                .map_err(|_| ArchVmError::AcpiNvsAccess(addr))?;
+        }
+        Ok(())
+    }
+
+    /// Restores the state of the PIT, the PICs and the IOAPIC only.
+    pub fn restore_irqchip_state(&self, state: &VmState) -> Result<(), ArchVmError> {
+        self.fd()
+            .set_pit2(&state.pitstate)
+            .map_err(ArchVmError::SetPit2)?;
         self.fd()
             .set_irqchip(&state.pic_master)
             .map_err(ArchVmError::SetIrqChipPicMaster)?;
@@ -143,12 +282,34 @@ impl ArchVm {
         self.fd()
             .set_irqchip(&state.ioapic)
             .map_err(ArchVmError::SetIrqChipIoAPIC)?;
-        self.common.resource_allocator = Mutex::new(state.resource_allocator.clone());
         Ok(())
     }
 
     /// Creates the irq chip and an in-kernel device model for the PIT.
+    ///
+    /// With a split irqchip, only the local APICs are created, and the routes of the IOAPIC pins
+    /// are reserved for the userspace IOAPIC. The MSI messages then carry 32-bit x2APIC IDs, which
+    /// guests with interrupt remapping can target.
     pub fn setup_irqchip(&self) -> Result<(), ArchVmError> {
+        if self.split_irqchip {
+            let mut cap = kvm_enable_cap {
+                cap: KVM_CAP_SPLIT_IRQCHIP,
+                ..Default::default()
+            };
+            cap.args[0] = IOAPIC_NUM_PINS as u64;
+            self.fd()
+                .enable_cap(&cap)
+                .map_err(ArchVmError::VmSplitIrqChip)?;
+
+            let mut cap = kvm_enable_cap {
+                cap: KVM_CAP_X2APIC_API,
+                ..Default::default()
+            };
+            cap.args[0] =
+                u64::from(KVM_X2APIC_API_USE_32BIT_IDS | KVM_X2APIC_API_DISABLE_BROADCAST_QUIRK);
+            return self.fd().enable_cap(&cap).map_err(ArchVmError::VmX2apicApi);
+        }
+
         self.fd()
             .create_irq_chip()
             .map_err(ArchVmError::VmSetIrqChip)?;
@@ -198,14 +359,36 @@ impl ArchVm {
         Ok(VmState {
             memory: self.common.guest_memory.describe(),
             resource_allocator: self.resource_allocator().save(),
+            acpi_nvs: self.save_acpi_nvs()?,
             pitstate,
             clock,
             pic_master,
             pic_slave,
             ioapic,
+            pmu: self.pmu.clone(),
         })
     }
 
+    // Reads the contents of the ACPI NVS regions, which guests expect to be preserved.
+    fn save_acpi_nvs(&self) -> Result<Vec<AcpiNvsRegionState>, ArchVmError> {
+        self.resource_allocator()
+            .acpi_nvs_regions
+            .iter()
+            .map(|region| {
+                let addr = region.guest_address;
+                let mut data = vec![0; u64_to_usize(region.size)];
+                self.guest_memory()
+                    .read_slice(&mut data, GuestAddress(addr))
+                    .map_err(|_| ArchVmError::AcpiNvsAccess(addr))?;
+                Ok(AcpiNvsRegionState {
+                    guest_address: addr,
+                    crc: crc64(0, &data),
+                    data,
+                })
+            })
+            .collect()
+    }
+
     /// Gets the list of MSRs to save when creating snapshots
     pub fn msrs_to_save(&self) -> &[u32] {
         self.msrs_to_save.as_slice()
@@ -217,6 +400,17 @@ impl ArchVm {
     }
 }
 
+/// Contents of an ACPI NVS region saved in a snapshot.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AcpiNvsRegionState {
+    /// Guest physical address of the start of the region.
+    pub guest_address: u64,
+    /// Contents of the region.
+    pub data: Vec<u8>,
+    /// CRC64 of the contents, checked before restoring them.
+    pub crc: u64,
+}
+
 #[derive(Default, Deserialize, Serialize)]
 /// Structure holding VM kvm state.
 pub struct VmState {
@@ -224,6 +418,8 @@ pub struct VmState {
     pub memory: GuestMemoryState,
     /// resource allocator
     pub resource_allocator: ResourceAllocator,
+    /// contents of the ACPI NVS regions
+    pub acpi_nvs: Vec<AcpiNvsRegionState>,
     pitstate: kvm_pit_state2,
     clock: kvm_clock_data,
     // TODO: rename this field to adopt inclusive language once Linux updates it, too.
@@ -231,6 +427,7 @@ pub struct VmState {
     // TODO: rename this field to adopt inclusive language once Linux updates it, too.
     pic_slave: kvm_irqchip,
     ioapic: kvm_irqchip,
+    pmu: Option<Pmu>,
 }
 
 impl fmt::Debug for VmState {
@@ -253,8 +450,8 @@ mod tests {
     };
 
     use crate::snapshot::Snapshot;
-    use crate::vstate::vm::VmState;
     use crate::vstate::vm::tests::{setup_vm, setup_vm_with_memory};
+    use crate::vstate::vm::{ArchVmError, VmState};
 
     #[cfg(target_arch = "x86_64")]
     #[test]
@@ -282,6 +479,44 @@ mod tests {
         vm.restore_state(&vm_state).unwrap();
     }
 
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_vm_save_restore_acpi_nvs() {
+        use crate::vstate::memory::{Bytes, GuestAddress};
+
+        let (_, vm) = setup_vm_with_memory(0x1000);
+        vm.setup_irqchip().unwrap();
+        vm.resource_allocator().mark_acpi_nvs(0x800, 0x10);
+        vm.guest_memory()
+            .write_slice(&[0xaa; 0x10], GuestAddress(0x800))
+            .unwrap();
+        let mut vm_state = vm.save_state().unwrap();
+        assert_eq!(vm_state.acpi_nvs.len(), 1);
+        assert_eq!(vm_state.acpi_nvs[0].data, [0xaa; 0x10]);
+
+        // The regions are written back at the same addresses.
+        let (_, mut vm) = setup_vm_with_memory(0x1000);
+        vm.setup_irqchip().unwrap();
+        vm.restore_state(&vm_state).unwrap();
+        let mut data = [0; 0x10];
+        vm.guest_memory()
+            .read_slice(&mut data, GuestAddress(0x800))
+            .unwrap();
+        assert_eq!(data, [0xaa; 0x10]);
+
+        // Corrupted regions are rejected.
+        vm_state.acpi_nvs[0].data[0] = 0;
+        assert_eq!(
+            vm.restore_state(&vm_state),
+            Err(ArchVmError::AcpiNvsChecksum(0x800))
+        );
+        vm_state.acpi_nvs.clear();
+        assert_eq!(
+            vm.restore_state(&vm_state),
+            Err(ArchVmError::AcpiNvsMismatch)
+        );
+    }
+
     #[cfg(target_arch = "x86_64")]
     #[test]
     fn test_vm_save_restore_state_bad_irqchip() {
@@ -21,7 +21,9 @@ use crate::Vcpu;
 use crate::arch::{ConfigurationError, configure_system_for_boot, load_kernel};
 #[cfg(target_arch = "aarch64")]
 use crate::construct_kvm_mpidrs;
-use crate::cpu_config::templates::{GetCpuTemplate, GetCpuTemplateError, GuestConfigError};
+use crate::cpu_config::templates::{
+    CpuConfiguration, GetCpuTemplate, GetCpuTemplateError, GuestConfigError,
+};
 #[cfg(target_arch = "x86_64")]
 use crate::device_manager;
 use crate::device_manager::pci_mngr::PciManagerError;
@@ -29,6 +31,7 @@ use crate::device_manager::{
     AttachDeviceError, DeviceManager, DeviceManagerCreateError, DevicePersistError,
     DeviceRestoreArgs,
 };
+use crate::devices::pseudo::BootMilestones;
 use crate::devices::virtio::balloon::Balloon;
 use crate::devices::virtio::block::device::Block;
 use crate::devices::virtio::device::VirtioDevice;
@@ -41,16 +44,22 @@ use crate::devices::virtio::vsock::{Vsock, VsockUnixBackend};
 use crate::gdb;
 use crate::initrd::{InitrdConfig, InitrdError};
 use crate::logger::debug;
-use crate::persist::{MicrovmState, MicrovmStateError};
+use crate::persist::{MicrovmState, MicrovmStateError, VmInfo};
+#[cfg(target_arch = "x86_64")]
+use crate::reset::BootImage;
 use crate::resources::VmResources;
+use crate::scheduled_actions::ScheduledActions;
 use crate::seccomp::BpfThreadMap;
 use crate::snapshot::Persist;
 use crate::utils::mib_to_bytes;
 use crate::vmm_config::instance_info::InstanceInfo;
 use crate::vmm_config::machine_config::MachineConfigError;
 use crate::vmm_config::memory_hotplug::MemoryHotplugConfig;
+use crate::vmm_config::memory_map::{MemoryMapRegion, MemoryMapRegionType};
 use crate::vstate::kvm::{Kvm, KvmError};
-use crate::vstate::memory::GuestRegionMmap;
+use crate::vstate::memory::{
+    Address, GuestMemory, GuestMemoryRegion, GuestRegionMmap, GuestRegionType,
+};
 #[cfg(target_arch = "aarch64")]
 use crate::vstate::resources::ResourceAllocator;
 use crate::vstate::vcpu::VcpuError;
@@ -124,6 +133,31 @@ pub enum StartMicrovmError {
     VcpuFdCloneError(#[from] crate::vstate::vcpu::CopyKvmFdError),
     /// Error with the Vm object: {0}
     Vm(#[from] VmError),
+    /// VFIO devices can only be attached when PCI is enabled
+    VfioRequiresPci,
+    /// VFIO devices pin the guest memory, so can't be used along with a balloon or memory hotplug
+    VfioPinnedMemory,
+    /// USB devices can only be attached when PCI is enabled
+    UsbRequiresPci,
+    /// NVMe controllers can only be attached when PCI is enabled
+    NvmeRequiresPci,
+    /// In place resets on reboot are not supported with VFIO, USB, NVMe, hotpluggable memory, SGX
+    /// or a split irqchip
+    ResetUnsupportedDevices,
+    /// Cannot capture the boot state of the microVM: {0}
+    #[cfg(target_arch = "x86_64")]
+    CaptureBootImage(crate::reset::ResetError),
+    /// Memory map region {0:#x} must either be part of a single guest memory region or lie outside
+    /// of guest memory and fixed device ranges
+    MemoryMapRegion(u64),
+    /// Persistent memory map region {0:#x} must be part of guest memory
+    MemoryMapPmem(u64),
+    /// Cannot expose the SGX enclave page cache: {0}
+    #[cfg(target_arch = "x86_64")]
+    Sgx(#[from] crate::arch::x86_64::sgx::SgxError),
+    /// Cannot expose the virtual PMU: {0}
+    #[cfg(target_arch = "x86_64")]
+    Pmu(#[from] crate::arch::x86_64::pmu::PmuError),
 }
 
 /// It's convenient to automatically convert `linux_loader::cmdline::Error`s
@@ -134,6 +168,65 @@ impl std::convert::From<linux_loader::cmdline::Error> for StartMicrovmError {
     }
 }
 
+/// Computes the CPU configuration the vCPUs of a microVM built from `vm_resources` would be
+/// configured with, after the CPU template is applied.
+///
+/// A throwaway VM with a single vCPU is created for this purpose. On x86_64, CPUID is normalized
+/// as it would be for the first vCPU. On aarch64, the ID registers are reported on top of the
+/// registers modified by the template.
+pub fn build_guest_cpu_config(
+    vm_resources: &VmResources,
+) -> Result<CpuConfiguration, StartMicrovmError> {
+    let cpu_template = vm_resources
+        .machine_config
+        .cpu_template
+        .get_cpu_template()?;
+
+    let kvm = Kvm::new(cpu_template.kvm_capabilities.clone())?;
+    let mut vm = Vm::new(&kvm)?;
+    #[allow(unused_mut)]
+    let (mut vcpus, _) = vm.create_vcpus(1)?;
+
+    #[cfg(target_arch = "x86_64")]
+    {
+        let cpu_config =
+            CpuConfiguration::new(kvm.supported_cpuid.clone(), &cpu_template, &vcpus[0])?;
+        let mut cpu_config = CpuConfiguration::apply_template(cpu_config, &cpu_template)?;
+        let machine_config = &vm_resources.machine_config;
+        cpu_config
+            .cpuid
+            .normalize(
+                0,
+                machine_config.vcpu_count,
+                u8::from(machine_config.vcpu_count > 1 && machine_config.smt),
+            )
+            .map_err(|err| ConfigurationError::VcpuConfigure(err.into()))?;
+        Ok(cpu_config)
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    {
+        use crate::arch::aarch64::regs::Aarch64RegisterVec;
+        use crate::arch::aarch64::vcpu::get_registers;
+        use crate::cpu_config::aarch64::custom_cpu_template::NAMED_ID_REGISTERS;
+
+        let cpu_config = CpuConfiguration::new(&cpu_template, &mut vcpus)?;
+        let mut cpu_config = cpu_config.apply_template(&cpu_template);
+
+        let template_regs = cpu_config.register_ids();
+        let id_regs: Vec<u64> = NAMED_ID_REGISTERS
+            .iter()
+            .map(|(_, id)| *id)
+            .filter(|id| !template_regs.contains(id))
+            .collect();
+        let mut regs = Aarch64RegisterVec::default();
+        get_registers(&vcpus[0].kvm_vcpu.fd, &id_regs, &mut regs)
+            .map_err(GuestConfigError::VcpuGetRegs)?;
+        regs.iter().for_each(|reg| cpu_config.regs.push(reg));
+        Ok(cpu_config)
+    }
+}
+
 /// Builds and starts a microVM based on the current clawdbox VmResources configuration.
 ///
 /// The built microVM and all the created vCPUs start off in the paused state.
@@ -171,8 +264,20 @@ pub fn build_microvm_for_boot(
     // Set up Kvm Vm and register memory regions.
     // Build custom CPU config if a custom template is provided.
     let mut vm = Vm::new(&kvm)?;
+    #[cfg(target_arch = "x86_64")]
+    vm.set_split_irqchip(vm_resources.machine_config.split_irqchip);
     let (mut vcpus, vcpus_exit_evt) = vm.create_vcpus(vm_resources.machine_config.vcpu_count)?;
     vm.register_dram_memory_regions(guest_memory)?;
+    reserve_memory_map_regions(&vm, vm_resources.memory_map_regions())?;
+    #[cfg(target_arch = "x86_64")]
+    if let Some(sgx) = &vm_resources.sgx {
+        vm.setup_sgx_epc(sgx)?;
+    }
+    #[cfg(target_arch = "x86_64")]
+    if let Some(pmu) = &vm_resources.pmu {
+        vm.setup_pmu(pmu)?;
+    }
+    let vm_created_ts = TimestampUs::default();
 
     // Allocate memory as soon as possible to make hotpluggable memory available to all consumers,
     // before they clone the GuestMemoryMmap object
@@ -190,17 +295,52 @@ pub fn build_microvm_for_boot(
         None
     };
 
+    if !vm_resources.vfio_devices.is_empty() {
+        if !vm_resources.pci_enabled {
+            return Err(StartMicrovmError::VfioRequiresPci);
+        }
+        if vm_resources.balloon.get().is_some() || vm_resources.memory_hotplug.is_some() {
+            return Err(StartMicrovmError::VfioPinnedMemory);
+        }
+    }
+
+    if !vm_resources.usb_devices.is_empty() && !vm_resources.pci_enabled {
+        return Err(StartMicrovmError::UsbRequiresPci);
+    }
+    if !vm_resources.nvme_devices.is_empty() && !vm_resources.pci_enabled {
+        return Err(StartMicrovmError::NvmeRequiresPci);
+    }
+    if vm_resources.reset_on_reboot()
+        && (!vm_resources.vfio_devices.is_empty()
+            || !vm_resources.usb_devices.is_empty()
+            || !vm_resources.nvme_devices.is_empty()
+            || vm_resources.memory_hotplug.is_some()
+            || vm_resources.sgx.is_some()
+            || vm_resources.machine_config.split_irqchip)
+    {
+        return Err(StartMicrovmError::ResetUnsupportedDevices);
+    }
+
     let mut device_manager = DeviceManager::new(
         event_manager,
         &vcpus_exit_evt,
         &vm,
-        vm_resources.serial_out_path.as_ref(),
+        &vm_resources.serial,
+        vm_resources.machine_config.legacy_free,
     )?;
 
     let vm = Arc::new(vm);
 
+    #[cfg(target_arch = "x86_64")]
+    if vm.split_irqchip() {
+        device_manager.attach_ioapic(&vm)?;
+        device_manager.attach_iommu(&vm)?;
+    }
+
+    let kernel_load_start_ts = TimestampUs::default();
     let entry_point = load_kernel(&boot_config.kernel_file, vm.guest_memory())?;
     let initrd = InitrdConfig::from_config(boot_config, vm.guest_memory())?;
+    let kernel_load_end_ts = TimestampUs::default();
 
     if vm_resources.pci_enabled {
         device_manager.enable_pci(&vm)?;
@@ -246,6 +386,15 @@ pub fn build_microvm_for_boot(
         vm_resources.pmem.devices.iter(),
         event_manager,
     )?;
+    for vfio_config in &vm_resources.vfio_devices {
+        device_manager.attach_vfio_device(&vm, vfio_config)?;
+    }
+    if !vm_resources.usb_devices.is_empty() {
+        device_manager.attach_usb_devices(&vm, &vm_resources.usb_devices, event_manager)?;
+    }
+    for nvme_config in &vm_resources.nvme_devices {
+        device_manager.attach_nvme_device(&vm, nvme_config, event_manager)?;
+    }
 
     if let Some(unix_vsock) = vm_resources.vsock.get() {
         attach_unixsock_vsock_device(
@@ -284,11 +433,42 @@ pub fn build_microvm_for_boot(
         &vm,
         event_manager,
         &mut boot_cmdline,
-        vm_resources.serial_out_path.as_ref(),
+        &vm_resources.serial,
     )?;
 
     device_manager.attach_vmgenid_device(&vm)?;
     device_manager.attach_vmclock_device(&vm)?;
+    if let Some(watchdog) = &vm_resources.watchdog {
+        device_manager.attach_watchdog_device(&vm, watchdog.clone(), VmInfo::from(vm_resources))?;
+    }
+    if let Some(apei) = &vm_resources.apei {
+        device_manager.attach_ghes_device(&vm, apei)?;
+    }
+    if let Some(power_supply) = &vm_resources.power_supply {
+        device_manager.attach_power_supply_device(&vm, power_supply.clone())?;
+    }
+    if let Some(aggregator) = &vm_resources.processor_aggregator {
+        device_manager.attach_processor_aggregator_device(&vm, aggregator)?;
+    }
+    if vm_resources.s2idle.is_some() {
+        device_manager.attach_s2idle_device(&vm)?;
+    }
+    if vm_resources
+        .scheduled_actions
+        .as_ref()
+        .is_some_and(|config| config.has_panic_trigger())
+    {
+        device_manager.attach_pvpanic_device(&vm)?;
+    }
+    if vm_resources.cppc.is_some() {
+        device_manager.attach_cppc_device(&vm, vm_resources.machine_config.vcpu_count)?;
+    }
+    if let Some(thermal_zone) = &vm_resources.thermal_zone {
+        device_manager.attach_thermal_zone_device(&vm, thermal_zone.clone())?;
+    }
+    if vm_resources.fw_cfg.config().is_some() {
+        device_manager.attach_fw_cfg_device(&vm, vm_resources.fw_cfg.files().to_vec())?;
+    }
 
     #[cfg(target_arch = "aarch64")]
     if vcpus[0].kvm_vcpu.supports_pvtime() {
@@ -297,6 +477,7 @@ pub fn build_microvm_for_boot(
         log::warn!("Vcpus do not support pvtime, steal time will not be reported to guest");
     }
 
+    let system_config_start_ts = TimestampUs::default();
     configure_system_for_boot(
         &kvm,
         &vm,
@@ -307,8 +488,36 @@ pub fn build_microvm_for_boot(
         entry_point,
         &initrd,
         boot_cmdline,
+        vm_resources.acpi_tables.tables(),
+        &vm_resources.smbios.clone().unwrap_or_default(),
+        vm_resources.memory_map_regions(),
+        &vm_resources.numa_nodes,
     )?;
 
+    // The vCPUs are ready to run the kernel, report how long it took to get here to the guest.
+    if let Some(boot_timer) = &device_manager.mmio_devices.boot_timer {
+        let milestones = BootMilestones {
+            vm_created: vm_created_ts,
+            kernel_load_start: kernel_load_start_ts,
+            kernel_load_end: kernel_load_end_ts,
+            system_config_start: system_config_start_ts,
+            vcpus_start: TimestampUs::default(),
+        };
+        boot_timer
+            .inner
+            .lock()
+            .expect("Poisoned lock")
+            .report_boot_milestones(vm.guest_memory(), &milestones);
+    }
+
+    // Capture the state the guest boots from, to go back to it when the guest reboots.
+    #[cfg(target_arch = "x86_64")]
+    let boot_image = vm_resources
+        .reset_on_reboot()
+        .then(|| BootImage::capture(&vm, &vcpus))
+        .transpose()
+        .map_err(StartMicrovmError::CaptureBootImage)?;
+
     let vmm = Vmm {
         instance_info: instance_info.clone(),
         shutdown_exit_code: None,
@@ -318,6 +527,9 @@ pub fn build_microvm_for_boot(
         vcpus_handles: Vec::new(),
         vcpus_exit_evt,
         device_manager,
+        #[cfg(target_arch = "x86_64")]
+        boot_image,
+        scheduled_actions: scheduled_actions(vm_resources, VmInfo::from(vm_resources)),
     };
     let vmm = Arc::new(Mutex::new(vmm));
 
@@ -518,6 +730,9 @@ pub fn build_microvm_from_snapshot(
         vcpus_handles: Vec::new(),
         vcpus_exit_evt,
         device_manager,
+        #[cfg(target_arch = "x86_64")]
+        boot_image: None,
+        scheduled_actions: None,
     };
 
     // Move vcpus to their own threads and start their state machine in the 'Paused' state.
@@ -544,6 +759,21 @@ pub fn build_microvm_from_snapshot(
     Ok(vmm)
 }
 
+/// Arms the timers of the scheduled actions, if any, against the balloon configured for the
+/// microVM.
+fn scheduled_actions(vm_resources: &VmResources, vm_info: VmInfo) -> Option<ScheduledActions> {
+    let config = vm_resources.scheduled_actions.clone()?;
+    let stats_polling_interval_s = vm_resources
+        .balloon
+        .get_config()
+        .map_or(0, |balloon| balloon.stats_polling_interval_s);
+    Some(ScheduledActions::new(
+        config,
+        vm_info,
+        stats_polling_interval_s,
+    ))
+}
+
 /// 64 bytes due to alignment requirement in 3.1 of https://www.kernel.org/doc/html/v5.8/virt/kvm/devices/vcpu.html#attribute-kvm-arm-vcpu-pvtime-ipa
 #[cfg(target_arch = "aarch64")]
 const STEALTIME_STRUCT_MEM_SIZE: u64 = 64;
@@ -604,6 +834,78 @@ fn attach_entropy_device(
     device_manager.attach_virtio_device(vm, id, entropy_device.clone(), cmdline, false)
 }
 
+/// Reserves the memory map regions which lie outside of guest memory in the device address spaces,
+/// so that no device gets allocated there. Regions within guest memory are carved out of the RAM
+/// reported to the guest instead, and the ACPI NVS ones among them are saved in snapshots.
+fn reserve_memory_map_regions(
+    vm: &Vm,
+    memory_map: &[MemoryMapRegion],
+) -> Result<(), StartMicrovmError> {
+    let mut resource_allocator = vm.resource_allocator();
+    let resource_allocator = &mut *resource_allocator;
+    for region in memory_map {
+        let (start, last) = (region.guest_address, region.end() - 1);
+        let dram = vm
+            .guest_memory()
+            .find_region(GuestAddress(start))
+            .filter(|dram| dram.region_type == GuestRegionType::Dram);
+        if let Some(dram) = dram {
+            if dram.last_addr().raw_value() < last {
+                return Err(StartMicrovmError::MemoryMapRegion(start));
+            }
+            if region.region_type == MemoryMapRegionType::Nvs {
+                resource_allocator.mark_acpi_nvs(start, region.size);
+            }
+            continue;
+        }
+        if region.region_type == MemoryMapRegionType::Pmem {
+            return Err(StartMicrovmError::MemoryMapPmem(start));
+        }
+        let overlaps = |range_start: u64, range_size: u64| {
+            start < range_start + range_size && range_start <= last
+        };
+        if vm
+            .guest_memory()
+            .iter()
+            .any(|mem| overlaps(mem.start_addr().raw_value(), mem.len()))
+        {
+            return Err(StartMicrovmError::MemoryMapRegion(start));
+        }
+
+        let windows = [
+            (
+                &mut resource_allocator.mmio32_memory,
+                crate::arch::MEM_32BIT_DEVICES_START,
+                crate::arch::MEM_32BIT_DEVICES_SIZE,
+            ),
+            (
+                &mut resource_allocator.mmio64_memory,
+                crate::arch::MEM_64BIT_DEVICES_START,
+                crate::arch::MEM_64BIT_DEVICES_SIZE,
+            ),
+            (
+                &mut resource_allocator.past_mmio64_memory,
+                crate::arch::FIRST_ADDR_PAST_64BITS_MMIO,
+                crate::arch::PAST_64BITS_MMIO_SIZE,
+            ),
+        ];
+        match windows
+            .into_iter()
+            .find(|(_, window_start, window_size)| overlaps(*window_start, *window_size))
+        {
+            Some((allocator, _, _)) => {
+                allocator.allocate(region.size, 1, AllocPolicy::ExactMatch(start))?;
+            }
+            // The rest of the 32-bit MMIO gap holds devices at fixed addresses.
+            None if overlaps(crate::arch::MMIO32_MEM_START, crate::arch::MMIO32_MEM_SIZE) => {
+                return Err(StartMicrovmError::MemoryMapRegion(start));
+            }
+            None => {}
+        }
+    }
+    Ok(())
+}
+
 fn allocate_virtio_mem_address(
     vm: &Vm,
     total_size_mib: usize,
@@ -759,7 +1061,9 @@ pub(crate) mod tests {
     use crate::mmds::data_store::{Mmds, MmdsVersion};
     use crate::mmds::ns::MmdsNetworkStack;
     use crate::utils::mib_to_bytes;
-    use crate::vmm_config::balloon::{BALLOON_DEV_ID, BalloonBuilder, BalloonDeviceConfig};
+    use crate::vmm_config::balloon::{
+        BALLOON_DEV_ID, BalloonBuilder, BalloonDeviceConfig, FreePageAdvice,
+    };
     use crate::vmm_config::boot_source::DEFAULT_KERNEL_CMDLINE;
     use crate::vmm_config::drive::{BlockBuilder, BlockDeviceConfig};
     use crate::vmm_config::entropy::{EntropyDeviceBuilder, EntropyDeviceConfig};
@@ -767,6 +1071,7 @@ pub(crate) mod tests {
     use crate::vmm_config::pmem::{PmemBuilder, PmemConfig};
     use crate::vmm_config::vsock::tests::default_config;
     use crate::vmm_config::vsock::{VsockBuilder, VsockDeviceConfig};
+    use crate::vstate::resources::AcpiNvsRegion;
     use crate::vstate::vm::tests::setup_vm_with_memory;
 
     #[derive(Debug)]
@@ -836,6 +1141,9 @@ pub(crate) mod tests {
             vcpus_handles: Vec::new(),
             vcpus_exit_evt,
             device_manager: default_device_manager(),
+            #[cfg(target_arch = "x86_64")]
+            boot_image: None,
+            scheduled_actions: None,
         }
     }
 
@@ -1060,6 +1368,7 @@ pub(crate) mod tests {
             guest_mac: None,
             rx_rate_limiter: None,
             tx_rate_limiter: None,
+            vhost: false,
         };
 
         let mut cmdline = default_kernel_cmdline();
@@ -1307,6 +1616,8 @@ pub(crate) mod tests {
             stats_polling_interval_s: 0,
             free_page_hinting: false,
             free_page_reporting: false,
+            free_page_advice: FreePageAdvice::DontNeed,
+            ksm: false,
         };
 
         let mut cmdline = default_kernel_cmdline();
@@ -1393,4 +1704,69 @@ pub(crate) mod tests {
             "virtio_mmio.device=4K@0xc0001000:5"
         ));
     }
+
+    #[test]
+    #[cfg(target_arch = "x86_64")]
+    fn test_reserve_memory_map_regions() {
+        let (_, vm) = setup_vm_with_memory(mib_to_bytes(128));
+        let region = |guest_address, size, region_type| MemoryMapRegion {
+            guest_address,
+            size,
+            region_type,
+        };
+
+        // Ranges of guest memory are not reserved in the device address spaces, but the ACPI NVS
+        // ones are tracked.
+        let memory_map = [
+            region(0x20_0000, 0x10_0000, MemoryMapRegionType::Pmem),
+            region(0x30_0000, 0x1000, MemoryMapRegionType::Nvs),
+            region(0x1_0000_0000_0000, 0x1000, MemoryMapRegionType::Reserved),
+        ];
+        reserve_memory_map_regions(&vm, &memory_map).unwrap();
+        assert_eq!(
+            vm.resource_allocator().acpi_nvs_regions,
+            [AcpiNvsRegion {
+                guest_address: 0x30_0000,
+                size: 0x1000,
+            }]
+        );
+
+        // Ranges in the device address spaces are reserved there.
+        let addr = crate::arch::MEM_64BIT_DEVICES_START;
+        let memory_map = [region(addr, 0x1000, MemoryMapRegionType::Nvs)];
+        reserve_memory_map_regions(&vm, &memory_map).unwrap();
+        vm.resource_allocator()
+            .mmio64_memory
+            .allocate(0x1000, 1, AllocPolicy::ExactMatch(addr))
+            .unwrap_err();
+        assert_eq!(vm.resource_allocator().acpi_nvs_regions.len(), 1);
+
+        let memory_map = [region(
+            mib_to_bytes(127) as u64,
+            0x20_0000,
+            MemoryMapRegionType::Reserved,
+        )];
+        assert!(matches!(
+            reserve_memory_map_regions(&vm, &memory_map),
+            Err(StartMicrovmError::MemoryMapRegion(_))
+        ));
+        let memory_map = [region(
+            crate::arch::MMIO32_MEM_START,
+            0x1000,
+            MemoryMapRegionType::Reserved,
+        )];
+        assert!(matches!(
+            reserve_memory_map_regions(&vm, &memory_map),
+            Err(StartMicrovmError::MemoryMapRegion(_))
+        ));
+        let memory_map = [region(
+            crate::arch::MEM_64BIT_DEVICES_START + 0x1000,
+            0x1000,
+            MemoryMapRegionType::Pmem,
+        )];
+        assert!(matches!(
+            reserve_memory_map_regions(&vm, &memory_map),
+            Err(StartMicrovmError::MemoryMapPmem(_))
+        ));
+    }
 }
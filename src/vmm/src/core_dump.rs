@@ -0,0 +1,542 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Writes ELF core dumps of the guest, in the format produced by QEMU's `dump-guest-memory`, so
+//! that they can be analyzed offline with tools like crash or drgn.
+//!
+//! The dump contains a `PT_NOTE` segment with the registers of every vCPU, followed by a
+//! `PT_LOAD` segment for every plugged KVM memory slot, with the guest physical address of the
+//! slot as physical address of the segment.
+
+use std::fs::OpenOptions;
+use std::io::{Seek, SeekFrom, Write};
+
+use vm_memory::{ByteValued, GuestMemoryError, WriteVolatile};
+
+use crate::arch::host_page_size;
+use crate::logger::info;
+use crate::persist::MicrovmStateError;
+use crate::vmm_config::core_dump::CoreDumpParams;
+use crate::vmm_config::instance_info::VmState;
+use crate::vstate::memory::{GuestMemory, GuestMemoryMmap};
+use crate::vstate::vcpu::VcpuState;
+use crate::vstate::vm::{VmError, mincore_bitmap};
+use crate::{Vmm, VmmError};
+
+/// Errors associated with writing a core dump of the guest.
+#[derive(Debug, thiserror::Error, displaydoc::Display)]
+pub enum CoreDumpError {
+    /// Cannot pause the microVM: {0}
+    Pause(VmmError),
+    /// Cannot resume the microVM: {0}
+    Resume(VmmError),
+    /// Cannot save the vCPU states: {0}
+    VcpuStates(MicrovmStateError),
+    /// Cannot {0} the core dump file: {1}
+    File(&'static str, std::io::Error),
+    /// Cannot write the guest memory to the core dump file: {0}
+    WriteMemory(#[from] GuestMemoryError),
+    /// Cannot get the resident guest pages: {0}
+    ResidentPages(#[from] VmError),
+    /// Too many memory segments in the core dump: {0}
+    TooManySegments(usize),
+}
+
+// ELF identification and constants, see include/uapi/linux/elf.h.
+const ELF_MAGIC: [u8; 4] = *b"\x7fELF";
+const ELFCLASS64: u8 = 2;
+const ELFDATA2LSB: u8 = 1;
+const EV_CURRENT: u8 = 1;
+const ET_CORE: u16 = 4;
+const PT_LOAD: u32 = 1;
+const PT_NOTE: u32 = 4;
+const NT_PRSTATUS: u32 = 1;
+#[cfg(target_arch = "x86_64")]
+const EM_MACHINE: u16 = 62;
+#[cfg(target_arch = "aarch64")]
+const EM_MACHINE: u16 = 183;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+struct Elf64Ehdr {
+    e_ident: [u8; 16],
+    e_type: u16,
+    e_machine: u16,
+    e_version: u32,
+    e_entry: u64,
+    e_phoff: u64,
+    e_shoff: u64,
+    e_flags: u32,
+    e_ehsize: u16,
+    e_phentsize: u16,
+    e_phnum: u16,
+    e_shentsize: u16,
+    e_shnum: u16,
+    e_shstrndx: u16,
+}
+
+// SAFETY: `Elf64Ehdr` is a POD with no implicit padding.
+unsafe impl ByteValued for Elf64Ehdr {}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+struct Elf64Phdr {
+    p_type: u32,
+    p_flags: u32,
+    p_offset: u64,
+    p_vaddr: u64,
+    p_paddr: u64,
+    p_filesz: u64,
+    p_memsz: u64,
+    p_align: u64,
+}
+
+// SAFETY: `Elf64Phdr` is a POD with no implicit padding.
+unsafe impl ByteValued for Elf64Phdr {}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+struct Elf64Nhdr {
+    n_namesz: u32,
+    n_descsz: u32,
+    n_type: u32,
+}
+
+// SAFETY: `Elf64Nhdr` is a POD with no implicit padding.
+unsafe impl ByteValued for Elf64Nhdr {}
+
+/// Appends an ELF note to `notes`.
+fn append_note(notes: &mut Vec<u8>, name: &str, note_type: u32, desc: &[u8]) {
+    let header = Elf64Nhdr {
+        // The name is NUL terminated.
+        n_namesz: u32::try_from(name.len() + 1).unwrap(),
+        n_descsz: u32::try_from(desc.len()).unwrap(),
+        n_type: note_type,
+    };
+    notes.extend_from_slice(header.as_slice());
+    notes.extend_from_slice(name.as_bytes());
+    notes.push(0);
+    notes.resize(notes.len().next_multiple_of(4), 0);
+    notes.extend_from_slice(desc);
+    notes.resize(notes.len().next_multiple_of(4), 0);
+}
+
+#[cfg(target_arch = "x86_64")]
+mod arch {
+    use kvm_bindings::{kvm_regs, kvm_segment, kvm_sregs};
+    use vm_memory::ByteValued;
+
+    use super::{NT_PRSTATUS, append_note};
+    use crate::vstate::vcpu::VcpuState;
+
+    const MSR_KERNEL_GS_BASE: u32 = 0xc000_0102;
+
+    // `struct elf_prstatus` of x86_64, with the fields we don't fill in as padding.
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug)]
+    pub(super) struct Prstatus {
+        pad1: [u8; 32],
+        pr_pid: u32,
+        pad2: [u8; 76],
+        // `struct user_regs_struct`
+        pr_reg: [u64; 27],
+        pad3: [u8; 8],
+    }
+
+    // SAFETY: `Prstatus` is a POD with no implicit padding.
+    unsafe impl ByteValued for Prstatus {}
+
+    impl Prstatus {
+        pub(super) fn new(pid: u32, regs: &kvm_regs, sregs: &kvm_sregs) -> Self {
+            let pr_reg = [
+                regs.r15,
+                regs.r14,
+                regs.r13,
+                regs.r12,
+                regs.rbp,
+                regs.rbx,
+                regs.r11,
+                regs.r10,
+                regs.r9,
+                regs.r8,
+                regs.rax,
+                regs.rcx,
+                regs.rdx,
+                regs.rsi,
+                regs.rdi,
+                // orig_rax
+                regs.rax,
+                regs.rip,
+                u64::from(sregs.cs.selector),
+                regs.rflags,
+                regs.rsp,
+                u64::from(sregs.ss.selector),
+                sregs.fs.base,
+                sregs.gs.base,
+                u64::from(sregs.ds.selector),
+                u64::from(sregs.es.selector),
+                u64::from(sregs.fs.selector),
+                u64::from(sregs.gs.selector),
+            ];
+            Self {
+                pad1: [0; 32],
+                pr_pid: pid,
+                pad2: [0; 76],
+                pr_reg,
+                pad3: [0; 8],
+            }
+        }
+    }
+
+    // `QEMUCPUSegment`
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug, Default)]
+    struct QemuCpuSegment {
+        selector: u32,
+        limit: u32,
+        flags: u32,
+        pad: u32,
+        base: u64,
+    }
+
+    impl From<&kvm_segment> for QemuCpuSegment {
+        fn from(segment: &kvm_segment) -> Self {
+            // The descriptor flags, laid out as in the second dword of a segment descriptor.
+            let flags = u32::from(segment.type_) << 8
+                | u32::from(segment.s) << 12
+                | u32::from(segment.dpl) << 13
+                | u32::from(segment.present) << 15
+                | u32::from(segment.avl) << 20
+                | u32::from(segment.l) << 21
+                | u32::from(segment.db) << 22
+                | u32::from(segment.g) << 23;
+            Self {
+                selector: u32::from(segment.selector),
+                limit: segment.limit,
+                flags,
+                pad: 0,
+                base: segment.base,
+            }
+        }
+    }
+
+    // `QEMUCPUState`, which crash reads the control registers from.
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug, Default)]
+    pub(super) struct QemuCpuState {
+        version: u32,
+        size: u32,
+        gprs: [u64; 16],
+        rip: u64,
+        rflags: u64,
+        segments: [QemuCpuSegment; 8],
+        gdt: QemuCpuSegment,
+        idt: QemuCpuSegment,
+        cr: [u64; 5],
+        kernel_gs_base: u64,
+    }
+
+    // SAFETY: `QemuCpuState` is a POD with no implicit padding.
+    unsafe impl ByteValued for QemuCpuState {}
+
+    impl QemuCpuState {
+        pub(super) fn new(regs: &kvm_regs, sregs: &kvm_sregs, kernel_gs_base: u64) -> Self {
+            let table = |base: u64, limit: u16| QemuCpuSegment {
+                limit: u32::from(limit),
+                base,
+                ..Default::default()
+            };
+            Self {
+                version: 1,
+                size: u32::try_from(std::mem::size_of::<Self>()).unwrap(),
+                gprs: [
+                    regs.rax, regs.rbx, regs.rcx, regs.rdx, regs.rsi, regs.rdi, regs.rsp, regs.rbp,
+                    regs.r8, regs.r9, regs.r10, regs.r11, regs.r12, regs.r13, regs.r14, regs.r15,
+                ],
+                rip: regs.rip,
+                rflags: regs.rflags,
+                segments: [
+                    &sregs.cs, &sregs.ds, &sregs.es, &sregs.fs, &sregs.gs, &sregs.ss, &sregs.ldt,
+                    &sregs.tr,
+                ]
+                .map(QemuCpuSegment::from),
+                gdt: table(sregs.gdt.base, sregs.gdt.limit),
+                idt: table(sregs.idt.base, sregs.idt.limit),
+                cr: [sregs.cr0, 0, sregs.cr2, sregs.cr3, sregs.cr4],
+                kernel_gs_base,
+            }
+        }
+    }
+
+    /// Appends the notes describing the registers of a vCPU.
+    pub(super) fn append_vcpu_notes(notes: &mut Vec<u8>, pid: u32, state: &VcpuState) {
+        let kernel_gs_base = state
+            .saved_msrs
+            .iter()
+            .flat_map(|msrs| msrs.as_slice())
+            .find(|msr| msr.index == MSR_KERNEL_GS_BASE)
+            .map_or(0, |msr| msr.data);
+        let prstatus = Prstatus::new(pid, &state.regs, &state.sregs);
+        append_note(notes, "CORE", NT_PRSTATUS, prstatus.as_slice());
+        let cpu_state = QemuCpuState::new(&state.regs, &state.sregs, kernel_gs_base);
+        append_note(notes, "QEMU", 0, cpu_state.as_slice());
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+mod arch {
+    use std::mem::offset_of;
+
+    use kvm_bindings::{KVM_REG_ARM_CORE, KVM_REG_ARM64, KVM_REG_SIZE_U64, user_pt_regs};
+    use vm_memory::ByteValued;
+
+    use super::{NT_PRSTATUS, append_note};
+    use crate::arch::aarch64::regs::{Aarch64RegisterVec, arm64_core_reg_id};
+    use crate::vstate::vcpu::VcpuState;
+
+    // x0-x30, sp, pc and pstate.
+    const USER_REG_COUNT: usize = 34;
+
+    // `struct elf_prstatus` of aarch64, with the fields we don't fill in as padding.
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug)]
+    pub(super) struct Prstatus {
+        pad1: [u8; 32],
+        pr_pid: u32,
+        pad2: [u8; 76],
+        // `struct user_pt_regs`
+        pr_reg: [u64; USER_REG_COUNT],
+        pr_fpvalid: u32,
+        pad3: [u8; 4],
+    }
+
+    // SAFETY: `Prstatus` is a POD with no implicit padding.
+    unsafe impl ByteValued for Prstatus {}
+
+    impl Prstatus {
+        pub(super) fn new(pid: u32, regs: &Aarch64RegisterVec) -> Self {
+            let mut pr_reg = [0; USER_REG_COUNT];
+            for (idx, value) in pr_reg.iter_mut().enumerate() {
+                let offset = offset_of!(user_pt_regs, regs) + idx * std::mem::size_of::<u64>();
+                let id = arm64_core_reg_id!(KVM_REG_SIZE_U64, offset);
+                if let Some(reg) = regs.iter().find(|reg| reg.id == id) {
+                    *value = reg.value::<u64, 8>();
+                }
+            }
+            Self {
+                pad1: [0; 32],
+                pr_pid: pid,
+                pad2: [0; 76],
+                pr_reg,
+                pr_fpvalid: 0,
+                pad3: [0; 4],
+            }
+        }
+    }
+
+    /// Appends the notes describing the registers of a vCPU.
+    pub(super) fn append_vcpu_notes(notes: &mut Vec<u8>, pid: u32, state: &VcpuState) {
+        let prstatus = Prstatus::new(pid, &state.regs);
+        append_note(notes, "CORE", NT_PRSTATUS, prstatus.as_slice());
+    }
+}
+
+/// Writes a core dump of the guest to `params.dump_path`.
+///
+/// The microVM is paused while the dump is written, and resumed afterwards if it was running.
+pub fn dump_guest_core(vmm: &mut Vmm, params: &CoreDumpParams) -> Result<(), CoreDumpError> {
+    let was_running = vmm.instance_info.state == VmState::Running;
+    if was_running {
+        vmm.pause_vm().map_err(CoreDumpError::Pause)?;
+    }
+
+    let result = vmm
+        .save_vcpu_states()
+        .map_err(CoreDumpError::VcpuStates)
+        .and_then(|vcpu_states| write_core_dump(vmm.vm.guest_memory(), &vcpu_states, params));
+
+    if was_running {
+        vmm.resume_vm().map_err(CoreDumpError::Resume)?;
+    }
+    result?;
+
+    info!(
+        "Wrote a core dump of the guest to {}",
+        params.dump_path.display()
+    );
+    Ok(())
+}
+
+fn write_core_dump(
+    mem: &GuestMemoryMmap,
+    vcpu_states: &[VcpuState],
+    params: &CoreDumpParams,
+) -> Result<(), CoreDumpError> {
+    use self::CoreDumpError::File as FileError;
+
+    let mut notes = Vec::new();
+    for (index, state) in vcpu_states.iter().enumerate() {
+        // Tools expect the vCPUs to be numbered from 1.
+        arch::append_vcpu_notes(&mut notes, u32::try_from(index + 1).unwrap(), state);
+    }
+
+    let slots: Vec<_> = mem
+        .iter()
+        .flat_map(|region| region.plugged_slots())
+        .collect();
+    let phnum =
+        u16::try_from(slots.len() + 1).map_err(|_| CoreDumpError::TooManySegments(slots.len()))?;
+
+    let ehdr_size = std::mem::size_of::<Elf64Ehdr>() as u64;
+    let phdr_size = std::mem::size_of::<Elf64Phdr>() as u64;
+    let notes_offset = ehdr_size + phdr_size * u64::from(phnum);
+    // Page align the memory, so that the pages which are not written are holes in the file.
+    let page_size = host_page_size();
+    let mut offset = (notes_offset + notes.len() as u64).next_multiple_of(page_size as u64);
+
+    let mut phdrs = vec![Elf64Phdr {
+        p_type: PT_NOTE,
+        p_offset: notes_offset,
+        p_filesz: notes.len() as u64,
+        ..Default::default()
+    }];
+    for slot in &slots {
+        let len = slot.slice.len() as u64;
+        phdrs.push(Elf64Phdr {
+            p_type: PT_LOAD,
+            p_offset: offset,
+            p_paddr: slot.guest_addr.0,
+            p_filesz: len,
+            p_memsz: len,
+            ..Default::default()
+        });
+        offset += len;
+    }
+
+    let mut e_ident = [0u8; 16];
+    e_ident[..4].copy_from_slice(&ELF_MAGIC);
+    e_ident[4] = ELFCLASS64;
+    e_ident[5] = ELFDATA2LSB;
+    e_ident[6] = EV_CURRENT;
+    let ehdr = Elf64Ehdr {
+        e_ident,
+        e_type: ET_CORE,
+        e_machine: EM_MACHINE,
+        e_version: u32::from(EV_CURRENT),
+        e_phoff: ehdr_size,
+        e_ehsize: u16::try_from(ehdr_size).unwrap(),
+        e_phentsize: u16::try_from(phdr_size).unwrap(),
+        e_phnum: phnum,
+        ..Default::default()
+    };
+
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&params.dump_path)
+        .map_err(|err| FileError("open", err))?;
+    file.set_len(offset)
+        .map_err(|err| FileError("set_length", err))?;
+    file.write_all(ehdr.as_slice())
+        .and_then(|()| {
+            phdrs
+                .iter()
+                .try_for_each(|phdr| file.write_all(phdr.as_slice()))
+        })
+        .and_then(|()| file.write_all(&notes))
+        .map_err(|err| FileError("write", err))?;
+
+    for (slot, phdr) in slots.iter().zip(&phdrs[1..]) {
+        file.seek(SeekFrom::Start(phdr.p_offset))
+            .map_err(|err| FileError("seek", err))?;
+        if params.exclude_balloon_pages {
+            // The pages released by the balloon are not resident anymore, so we only write the
+            // resident pages and leave holes in the file for the others.
+            let bitmap = mincore_bitmap(slot.slice.ptr_guard_mut().as_ptr(), slot.slice.len())?;
+            slot.dump_dirty(&mut file, &bitmap, page_size)?;
+        } else {
+            file.write_all_volatile(&slot.slice)
+                .map_err(GuestMemoryError::from)?;
+        }
+    }
+
+    file.sync_all().map_err(|err| FileError("sync_all", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use vm_memory::Bytes;
+    use vmm_sys_util::tempfile::TempFile;
+
+    use super::*;
+    use crate::test_utils::single_region_mem;
+    use crate::vstate::memory::GuestAddress;
+
+    fn read_obj<T: ByteValued>(data: &[u8], offset: usize) -> T {
+        T::from_slice(&data[offset..offset + std::mem::size_of::<T>()])
+            .copied()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_append_note() {
+        let mut notes = Vec::new();
+        append_note(&mut notes, "CORE", NT_PRSTATUS, &[1, 2, 3, 4, 5]);
+        // Header, then the name and the descriptor padded to 4 bytes.
+        assert_eq!(notes.len(), 12 + 8 + 8);
+        let header: Elf64Nhdr = read_obj(&notes, 0);
+        assert_eq!(header.n_namesz, 5);
+        assert_eq!(header.n_descsz, 5);
+        assert_eq!(header.n_type, NT_PRSTATUS);
+        assert_eq!(&notes[12..20], b"CORE\0\0\0\0");
+        assert_eq!(&notes[20..], &[1, 2, 3, 4, 5, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_prstatus_size() {
+        #[cfg(target_arch = "x86_64")]
+        {
+            assert_eq!(std::mem::size_of::<arch::Prstatus>(), 336);
+            assert_eq!(std::mem::size_of::<arch::QemuCpuState>(), 440);
+        }
+        #[cfg(target_arch = "aarch64")]
+        assert_eq!(std::mem::size_of::<arch::Prstatus>(), 392);
+    }
+
+    #[test]
+    fn test_write_core_dump() {
+        let mem = single_region_mem(0x10_0000);
+        mem.write_obj(0xdead_beef_u64, GuestAddress(0x2000))
+            .unwrap();
+        let file = TempFile::new().unwrap();
+
+        for exclude_balloon_pages in [false, true] {
+            let params = CoreDumpParams {
+                dump_path: file.as_path().to_path_buf(),
+                exclude_balloon_pages,
+            };
+            write_core_dump(&mem, &[], &params).unwrap();
+
+            let data = std::fs::read(file.as_path()).unwrap();
+            let ehdr: Elf64Ehdr = read_obj(&data, 0);
+            assert_eq!(ehdr.e_ident[..4], ELF_MAGIC);
+            assert_eq!(ehdr.e_type, ET_CORE);
+            assert_eq!(ehdr.e_machine, EM_MACHINE);
+            assert_eq!(ehdr.e_phnum, 2);
+
+            let note: Elf64Phdr = read_obj(&data, 64);
+            assert_eq!(note.p_type, PT_NOTE);
+            assert_eq!(note.p_filesz, 0);
+            let load: Elf64Phdr = read_obj(&data, 64 + 56);
+            assert_eq!(load.p_type, PT_LOAD);
+            assert_eq!(load.p_paddr, 0);
+            assert_eq!(load.p_filesz, 0x10_0000);
+            assert_eq!(load.p_offset % host_page_size() as u64, 0);
+            assert_eq!(data.len() as u64, load.p_offset + load.p_filesz);
+
+            let value: u64 = read_obj(&data, usize::try_from(load.p_offset).unwrap() + 0x2000);
+            assert_eq!(value, 0xdead_beef);
+        }
+    }
+}
@@ -5,10 +5,15 @@
 /// config templates.
 use std::borrow::Cow;
 
-use serde::de::Error;
-use serde::{Deserialize, Serialize};
-
-use crate::arch::aarch64::regs::{RegSize, reg_size};
+use kvm_bindings::{KVM_ARM_VCPU_PTRAUTH_ADDRESS, KVM_ARM_VCPU_PTRAUTH_GENERIC, KVM_ARM_VCPU_SVE};
+use serde::de::{Error, IntoDeserializer};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::arch::aarch64::regs::{
+    ID_AA64DFR0_EL1, ID_AA64DFR1_EL1, ID_AA64ISAR0_EL1, ID_AA64ISAR1_EL1, ID_AA64ISAR2_EL1,
+    ID_AA64MMFR0_EL1, ID_AA64MMFR1_EL1, ID_AA64MMFR2_EL1, ID_AA64PFR0_EL1, ID_AA64PFR1_EL1,
+    ID_AA64ZFR0_EL1, MIDR_EL1, REVIDR_EL1, RegSize, reg_size,
+};
 use crate::cpu_config::aarch64::static_cpu_templates::v1n1;
 use crate::cpu_config::templates::{
     CpuTemplateType, GetCpuTemplate, GetCpuTemplateError, KvmCapability, RegisterValueFilter,
@@ -43,12 +48,24 @@ pub struct CustomCpuTemplate {
     /// Modifiers of enabled vcpu features for vcpu.
     #[serde(default)]
     pub vcpu_features: Vec<VcpuFeatures>,
+    /// Named vcpu features to enable or disable. These are applied
+    /// after `vcpu_features`.
+    #[serde(default)]
+    pub feature_overrides: VcpuFeatureOverrides,
     /// Modifiers for registers on Aarch64 CPUs.
     #[serde(default)]
     pub reg_modifiers: Vec<RegisterModifier>,
 }
 
 impl CustomCpuTemplate {
+    /// Get the vcpu features modifiers, including the ones
+    /// derived from `feature_overrides`.
+    pub fn effective_vcpu_features(&self) -> Vec<VcpuFeatures> {
+        let mut vcpu_features = self.vcpu_features.clone();
+        vcpu_features.extend(self.feature_overrides.vcpu_features());
+        vcpu_features
+    }
+
     /// Get a list of register IDs that are modified by the CPU template.
     pub fn reg_list(&self) -> Vec<u64> {
         self.reg_modifiers
@@ -96,13 +113,87 @@ pub struct VcpuFeatures {
     pub bitmap: RegisterValueFilter<u32>,
 }
 
+/// Named vcpu features which can be toggled by a template.
+/// `None` leaves the feature as configured by `vcpu_features`.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VcpuFeatureOverrides {
+    /// Scalable Vector Extension.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sve: Option<bool>,
+    /// Pointer authentication. Both address and generic authentication
+    /// are toggled, since KVM requires them to be enabled together.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pac: Option<bool>,
+}
+
+impl VcpuFeatureOverrides {
+    /// Converts the overrides into modifiers of the
+    /// `kvm_bindings::kvm_vcpu_init.features` array.
+    pub fn vcpu_features(&self) -> Vec<VcpuFeatures> {
+        [
+            (self.sve, 1 << KVM_ARM_VCPU_SVE),
+            (
+                self.pac,
+                (1 << KVM_ARM_VCPU_PTRAUTH_ADDRESS) | (1 << KVM_ARM_VCPU_PTRAUTH_GENERIC),
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(enabled, mask)| {
+            enabled.map(|enabled| VcpuFeatures {
+                index: 0,
+                bitmap: RegisterValueFilter {
+                    filter: mask,
+                    value: if enabled { mask } else { 0 },
+                },
+            })
+        })
+        .collect()
+    }
+}
+
+/// ID registers which can be referenced by name in
+/// the `addr` field of a register modifier.
+pub(crate) const NAMED_ID_REGISTERS: [(&str, u64); 13] = [
+    ("MIDR_EL1", MIDR_EL1),
+    ("REVIDR_EL1", REVIDR_EL1),
+    ("ID_AA64PFR0_EL1", ID_AA64PFR0_EL1),
+    ("ID_AA64PFR1_EL1", ID_AA64PFR1_EL1),
+    ("ID_AA64ZFR0_EL1", ID_AA64ZFR0_EL1),
+    ("ID_AA64DFR0_EL1", ID_AA64DFR0_EL1),
+    ("ID_AA64DFR1_EL1", ID_AA64DFR1_EL1),
+    ("ID_AA64ISAR0_EL1", ID_AA64ISAR0_EL1),
+    ("ID_AA64ISAR1_EL1", ID_AA64ISAR1_EL1),
+    ("ID_AA64ISAR2_EL1", ID_AA64ISAR2_EL1),
+    ("ID_AA64MMFR0_EL1", ID_AA64MMFR0_EL1),
+    ("ID_AA64MMFR1_EL1", ID_AA64MMFR1_EL1),
+    ("ID_AA64MMFR2_EL1", ID_AA64MMFR2_EL1),
+];
+
+/// Deserializes a register address either from one of the
+/// `NAMED_ID_REGISTERS` or from a binary or hex string.
+fn deserialize_reg_addr<'de, D>(deserializer: D) -> Result<u64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let addr_str = String::deserialize(deserializer)?;
+    match NAMED_ID_REGISTERS
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(&addr_str))
+    {
+        Some((_, addr)) => Ok(*addr),
+        None => deserialize_from_str_u64(addr_str.as_str().into_deserializer()),
+    }
+}
+
 /// Wrapper of a mask defined as a bitmap to apply
 /// changes to a given register's value.
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize, Hash)]
 pub struct RegisterModifier {
-    /// Pointer of the location to be bit mapped.
+    /// Pointer of the location to be bit mapped. Can also be the
+    /// name of an ID register, e.g. "MIDR_EL1" or "ID_AA64PFR0_EL1".
     #[serde(
-        deserialize_with = "deserialize_from_str_u64",
+        deserialize_with = "deserialize_reg_addr",
         serialize_with = "serialize_to_hex_str"
     )]
     pub addr: u64,
@@ -271,6 +362,94 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_named_id_registers() {
+        let cpu_template = serde_json::from_str::<CustomCpuTemplate>(
+            r#"{
+                    "reg_modifiers":  [
+                        {
+                            "addr": "MIDR_EL1",
+                            "bitmap": "0b1xx1"
+                        },
+                        {
+                            "addr": "id_aa64pfr0_el1",
+                            "bitmap": "0b1x00"
+                        }
+                    ]
+                }"#,
+        )
+        .unwrap();
+        assert_eq!(cpu_template.reg_list(), vec![MIDR_EL1, ID_AA64PFR0_EL1]);
+
+        // Unknown register names are rejected.
+        let cpu_config_result = serde_json::from_str::<CustomCpuTemplate>(
+            r#"{
+                    "reg_modifiers":  [
+                        {
+                            "addr": "ID_AA64FOO_EL1",
+                            "bitmap": "0b1xx1"
+                        }
+                    ]
+                }"#,
+        );
+        assert!(
+            cpu_config_result
+                .unwrap_err()
+                .to_string()
+                .contains("No supported number system prefix found in value")
+        );
+    }
+
+    #[test]
+    fn test_feature_overrides() {
+        let cpu_template = serde_json::from_str::<CustomCpuTemplate>(
+            r#"{
+                    "vcpu_features":[{"index":0,"bitmap":"0b1110000"}],
+                    "feature_overrides": {"sve": false}
+                }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            cpu_template.effective_vcpu_features(),
+            vec![
+                VcpuFeatures {
+                    index: 0,
+                    bitmap: RegisterValueFilter {
+                        filter: 0b1110000,
+                        value: 0b1110000,
+                    },
+                },
+                VcpuFeatures {
+                    index: 0,
+                    bitmap: RegisterValueFilter {
+                        filter: 1 << KVM_ARM_VCPU_SVE,
+                        value: 0,
+                    },
+                },
+            ]
+        );
+
+        let overrides = VcpuFeatureOverrides {
+            sve: None,
+            pac: Some(true),
+        };
+        let pac = (1 << KVM_ARM_VCPU_PTRAUTH_ADDRESS) | (1 << KVM_ARM_VCPU_PTRAUTH_GENERIC);
+        assert_eq!(
+            overrides.vcpu_features(),
+            vec![VcpuFeatures {
+                index: 0,
+                bitmap: RegisterValueFilter {
+                    filter: pac,
+                    value: pac,
+                },
+            }]
+        );
+
+        // Unknown features are rejected.
+        serde_json::from_str::<CustomCpuTemplate>(r#"{"feature_overrides": {"sme": true}}"#)
+            .unwrap_err();
+    }
+
     #[test]
     fn test_deserialization_lifecycle() {
         let cpu_config = serde_json::from_str::<CustomCpuTemplate>(TEST_TEMPLATE_JSON)
@@ -37,7 +37,8 @@ impl CpuConfiguration {
         vcpus: &mut [Vcpu],
     ) -> Result<Self, CpuConfigurationError> {
         for vcpu in vcpus.iter_mut() {
-            vcpu.kvm_vcpu.init(&cpu_template.vcpu_features)?;
+            vcpu.kvm_vcpu
+                .init(&cpu_template.effective_vcpu_features())?;
         }
 
         let mut regs = Aarch64RegisterVec::default();
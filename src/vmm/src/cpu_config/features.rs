@@ -0,0 +1,207 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use serde::Serialize;
+
+use crate::DumpCpuConfigError;
+#[cfg(target_arch = "aarch64")]
+use crate::arch::aarch64::regs::RegSize;
+use crate::builder::StartMicrovmError;
+use crate::cpu_config::templates::CpuConfiguration;
+use crate::cpu_config::templates_serde::serialize_to_hex_str;
+
+/// Errors associated with reporting the guest CPU features.
+#[derive(Debug, thiserror::Error, displaydoc::Display)]
+pub enum CpuFeaturesError {
+    /// Failed to build the guest CPU configuration: {0}
+    Build(#[from] StartMicrovmError),
+    /// Failed to dump the guest CPU configuration: {0}
+    Dump(#[from] DumpCpuConfigError),
+}
+
+/// Value of a single register, as seen by the guest.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RegisterValue {
+    /// Address of the register (MSR index on x86_64, KVM register ID on aarch64).
+    #[serde(serialize_with = "serialize_to_hex_str")]
+    pub addr: u64,
+    /// Value of the register.
+    #[serde(serialize_with = "serialize_to_hex_str")]
+    pub value: u128,
+}
+
+/// Value of a single CPUID leaf, as seen by the guest.
+#[cfg(target_arch = "x86_64")]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CpuidLeafValue {
+    /// CPUID leaf.
+    #[serde(serialize_with = "serialize_to_hex_str")]
+    pub leaf: u32,
+    /// CPUID subleaf.
+    #[serde(serialize_with = "serialize_to_hex_str")]
+    pub subleaf: u32,
+    /// KVM flags of the leaf.
+    pub flags: u32,
+    /// EAX.
+    #[serde(serialize_with = "serialize_to_hex_str")]
+    pub eax: u32,
+    /// EBX.
+    #[serde(serialize_with = "serialize_to_hex_str")]
+    pub ebx: u32,
+    /// ECX.
+    #[serde(serialize_with = "serialize_to_hex_str")]
+    pub ecx: u32,
+    /// EDX.
+    #[serde(serialize_with = "serialize_to_hex_str")]
+    pub edx: u32,
+}
+
+/// The effective guest CPU configuration, after the CPU template is applied.
+///
+/// This is the body of a response to GET /machine-config/cpu-features.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CpuFeatures {
+    /// CPUID leaves.
+    #[cfg(target_arch = "x86_64")]
+    pub cpuid: Vec<CpuidLeafValue>,
+    /// Model specific registers.
+    #[cfg(target_arch = "x86_64")]
+    pub msrs: Vec<RegisterValue>,
+    /// System registers.
+    #[cfg(target_arch = "aarch64")]
+    pub regs: Vec<RegisterValue>,
+}
+
+#[cfg(target_arch = "x86_64")]
+impl From<&CpuConfiguration> for CpuFeatures {
+    fn from(cpu_config: &CpuConfiguration) -> Self {
+        let cpuid = cpu_config
+            .cpuid
+            .inner()
+            .iter()
+            .map(|(key, entry)| CpuidLeafValue {
+                leaf: key.leaf,
+                subleaf: key.subleaf,
+                flags: entry.flags.0,
+                eax: entry.result.eax,
+                ebx: entry.result.ebx,
+                ecx: entry.result.ecx,
+                edx: entry.result.edx,
+            })
+            .collect();
+        let msrs = cpu_config
+            .msrs
+            .iter()
+            .map(|(addr, value)| RegisterValue {
+                addr: u64::from(*addr),
+                value: u128::from(*value),
+            })
+            .collect();
+        CpuFeatures { cpuid, msrs }
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+impl From<&CpuConfiguration> for CpuFeatures {
+    fn from(cpu_config: &CpuConfiguration) -> Self {
+        let mut regs: Vec<RegisterValue> = cpu_config
+            .regs
+            .iter()
+            .filter_map(|reg| {
+                let value = match reg.size() {
+                    RegSize::U32 => u128::from(reg.value::<u32, 4>()),
+                    RegSize::U64 => u128::from(reg.value::<u64, 8>()),
+                    RegSize::U128 => reg.value::<u128, 16>(),
+                    _ => return None,
+                };
+                Some(RegisterValue {
+                    addr: reg.id,
+                    value,
+                })
+            })
+            .collect();
+        regs.sort_by_key(|reg| reg.addr);
+        CpuFeatures { regs }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_cpu_features_from_config() {
+        use std::collections::BTreeMap;
+
+        use crate::cpu_config::x86_64::cpuid::{
+            Cpuid, CpuidEntry, CpuidKey, CpuidRegisters, IntelCpuid, KvmCpuidFlags,
+        };
+
+        let cpu_config = CpuConfiguration {
+            cpuid: Cpuid::Intel(IntelCpuid(BTreeMap::from([(
+                CpuidKey {
+                    leaf: 0x1,
+                    subleaf: 0x0,
+                },
+                CpuidEntry {
+                    flags: KvmCpuidFlags::EMPTY,
+                    result: CpuidRegisters {
+                        eax: 0x1,
+                        ebx: 0x2,
+                        ecx: 0x3,
+                        edx: 0x4,
+                    },
+                },
+            )]))),
+            msrs: BTreeMap::from([(0x10a, 0xff)]),
+        };
+
+        let features = CpuFeatures::from(&cpu_config);
+        assert_eq!(
+            serde_json::to_value(&features).unwrap(),
+            serde_json::json!({
+                "cpuid": [{
+                    "leaf": "0x1",
+                    "subleaf": "0x0",
+                    "flags": 0,
+                    "eax": "0x1",
+                    "ebx": "0x2",
+                    "ecx": "0x3",
+                    "edx": "0x4"
+                }],
+                "msrs": [{ "addr": "0x10a", "value": "0xff" }]
+            })
+        );
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    #[test]
+    fn test_cpu_features_from_config() {
+        use crate::arch::aarch64::regs::{Aarch64RegisterRef, ID_AA64PFR0_EL1, MIDR_EL1};
+
+        let mut cpu_config = CpuConfiguration::default();
+        cpu_config.regs.push(Aarch64RegisterRef::new(
+            ID_AA64PFR0_EL1,
+            &0x11u64.to_le_bytes(),
+        ));
+        cpu_config
+            .regs
+            .push(Aarch64RegisterRef::new(MIDR_EL1, &0x22u64.to_le_bytes()));
+
+        let features = CpuFeatures::from(&cpu_config);
+        assert_eq!(
+            features.regs,
+            vec![
+                RegisterValue {
+                    addr: MIDR_EL1,
+                    value: 0x22,
+                },
+                RegisterValue {
+                    addr: ID_AA64PFR0_EL1,
+                    value: 0x11,
+                },
+            ]
+        );
+    }
+}
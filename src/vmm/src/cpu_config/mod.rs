@@ -1,6 +1,8 @@
 // Copyright 2023 Amazon.com, Inc. or its affiliates. All Rights Reserved.
 // SPDX-License-Identifier: Apache-2.0
 
+/// Module with the effective guest CPU configuration view
+pub mod features;
 /// Module with types used for custom CPU templates
 pub mod templates;
 /// Module with ser/de utils for custom CPU templates
@@ -1,13 +1,38 @@
 // Copyright 2024 Amazon.com, Inc. or its affiliates. All Rights Reserved.
 // SPDX-License-Identifier: Apache-2.0
 
+use std::sync::{Arc, Mutex};
+
 #[cfg(target_arch = "x86_64")]
 use acpi_tables::{Aml, aml};
+use vm_allocator::AllocPolicy;
 use vm_memory::GuestMemoryError;
 
 use crate::Vm;
+use crate::devices::acpi::cppc::{CPPC_MMIO_SIZE, Cppc};
+use crate::devices::acpi::fw_cfg::{FW_CFG_REGS_SIZE, FwCfg, FwCfgFile};
+use crate::devices::acpi::ghes::Ghes;
+use crate::devices::acpi::power_supply::PowerSupply;
+#[cfg(target_arch = "x86_64")]
+use crate::devices::acpi::power_supply::{NOTIFY_INFORMATION_CHANGED, NOTIFY_STATUS_CHANGED};
+#[cfg(target_arch = "x86_64")]
+use crate::devices::acpi::processor_aggregator::NOTIFY_PUR_CHANGED;
+use crate::devices::acpi::processor_aggregator::ProcessorAggregator;
+use crate::devices::acpi::pvpanic::{PVPANIC_MMIO_SIZE, PvPanic};
+use crate::devices::acpi::s2idle::{S2IDLE_MMIO_SIZE, S2idle};
+#[cfg(target_arch = "x86_64")]
+use crate::devices::acpi::thermal_zone::NOTIFY_TEMPERATURE_CHANGED;
+use crate::devices::acpi::thermal_zone::{ThermalZone, ThermalZoneError};
 use crate::devices::acpi::vmclock::VmClock;
 use crate::devices::acpi::vmgenid::VmGenId;
+use crate::devices::acpi::watchdog::{WATCHDOG_MMIO_SIZE, Watchdog};
+use crate::persist::VmInfo;
+use crate::vmm_config::apei::ApeiConfig;
+use crate::vmm_config::power_supply::PowerSupplyConfig;
+use crate::vmm_config::processor_aggregator::ProcessorAggregatorConfig;
+use crate::vmm_config::thermal_zone::ThermalZoneConfig;
+use crate::vmm_config::watchdog::WatchdogConfig;
+use crate::vstate::bus::BusError;
 use crate::vstate::resources::ResourceAllocator;
 
 #[derive(Debug, thiserror::Error, displaydoc::Display)]
@@ -16,6 +41,12 @@ pub enum ACPIDeviceError {
     RegisterIrq(#[from] kvm_ioctls::Error),
     /// Could not write to guest memory: {0}
     WriteGuestMemory(#[from] GuestMemoryError),
+    /// Could not allocate MMIO space: {0}
+    Allocator(#[from] vm_allocator::Error),
+    /// Could not insert device in the MMIO bus: {0}
+    Bus(#[from] BusError),
+    /// Could not create the thermal zone: {0}
+    ThermalZone(#[from] ThermalZoneError),
 }
 
 #[derive(Debug)]
@@ -24,6 +55,24 @@ pub struct ACPIDeviceManager {
     pub vmgenid: VmGenId,
     /// VMclock device
     pub vmclock: VmClock,
+    /// Watchdog device
+    pub watchdog: Option<Arc<Mutex<Watchdog>>>,
+    /// Generic Hardware Error Source device
+    pub ghes: Option<Ghes>,
+    /// Battery and AC adapter devices
+    pub power_supply: Option<PowerSupply>,
+    /// Processor aggregator device
+    pub processor_aggregator: Option<ProcessorAggregator>,
+    /// Suspend-to-idle device
+    pub s2idle: Option<Arc<Mutex<S2idle>>>,
+    /// pvpanic device
+    pub pvpanic: Option<Arc<Mutex<PvPanic>>>,
+    /// CPPC device
+    pub cppc: Option<Arc<Mutex<Cppc>>>,
+    /// Thermal zone device
+    pub thermal_zone: Option<ThermalZone>,
+    /// fw_cfg device
+    pub fw_cfg: Option<Arc<Mutex<FwCfg>>>,
 }
 
 impl ACPIDeviceManager {
@@ -32,6 +81,15 @@ impl ACPIDeviceManager {
         ACPIDeviceManager {
             vmgenid: VmGenId::new(resource_allocator),
             vmclock: VmClock::new(resource_allocator),
+            watchdog: None,
+            ghes: None,
+            power_supply: None,
+            processor_aggregator: None,
+            s2idle: None,
+            pvpanic: None,
+            cppc: None,
+            thermal_zone: None,
+            fw_cfg: None,
         }
     }
 
@@ -46,6 +104,203 @@ impl ACPIDeviceManager {
         self.vmclock.activate(vm.guest_memory())?;
         Ok(())
     }
+
+    pub fn attach_watchdog(
+        &mut self,
+        vm: &Vm,
+        config: WatchdogConfig,
+        vm_info: VmInfo,
+    ) -> Result<(), ACPIDeviceError> {
+        let mmio_addr = vm.resource_allocator().allocate_32bit_mmio_memory(
+            WATCHDOG_MMIO_SIZE,
+            WATCHDOG_MMIO_SIZE,
+            AllocPolicy::FirstMatch,
+        )?;
+        let watchdog = Arc::new(Mutex::new(Watchdog::new(mmio_addr, config, vm_info)));
+        self.insert_watchdog(vm, watchdog)
+    }
+
+    pub(crate) fn insert_watchdog(
+        &mut self,
+        vm: &Vm,
+        watchdog: Arc<Mutex<Watchdog>>,
+    ) -> Result<(), ACPIDeviceError> {
+        let mmio_addr = watchdog.lock().expect("Poisoned lock").mmio_addr;
+        vm.common
+            .mmio_bus
+            .insert(watchdog.clone(), mmio_addr, WATCHDOG_MMIO_SIZE)?;
+        self.watchdog = Some(watchdog);
+        Ok(())
+    }
+
+    pub fn attach_ghes(&mut self, vm: &Vm, config: &ApeiConfig) -> Result<(), ACPIDeviceError> {
+        let ghes = Ghes::new(&mut vm.resource_allocator(), config.notification)?;
+        ghes.activate(vm.guest_memory())?;
+        self.insert_ghes(vm, ghes)
+    }
+
+    pub(crate) fn insert_ghes(&mut self, vm: &Vm, ghes: Ghes) -> Result<(), ACPIDeviceError> {
+        if let Some(gsi) = ghes.gsi {
+            vm.register_irq(&ghes.interrupt_evt, gsi)?;
+        }
+        self.ghes = Some(ghes);
+        Ok(())
+    }
+
+    pub fn attach_power_supply(
+        &mut self,
+        vm: &Vm,
+        config: PowerSupplyConfig,
+    ) -> Result<(), ACPIDeviceError> {
+        let power_supply = PowerSupply::new(&mut vm.resource_allocator(), config)?;
+        power_supply.activate(vm.guest_memory())?;
+        self.insert_power_supply(vm, power_supply)
+    }
+
+    pub(crate) fn insert_power_supply(
+        &mut self,
+        vm: &Vm,
+        power_supply: PowerSupply,
+    ) -> Result<(), ACPIDeviceError> {
+        vm.register_irq(&power_supply.interrupt_evt, power_supply.gsi)?;
+        self.power_supply = Some(power_supply);
+        Ok(())
+    }
+
+    pub fn attach_processor_aggregator(
+        &mut self,
+        vm: &Vm,
+        config: &ProcessorAggregatorConfig,
+    ) -> Result<(), ACPIDeviceError> {
+        let aggregator = ProcessorAggregator::new(&mut vm.resource_allocator(), config.idle_cpus)?;
+        aggregator.activate(vm.guest_memory())?;
+        self.insert_processor_aggregator(vm, aggregator)
+    }
+
+    pub(crate) fn insert_processor_aggregator(
+        &mut self,
+        vm: &Vm,
+        aggregator: ProcessorAggregator,
+    ) -> Result<(), ACPIDeviceError> {
+        vm.register_irq(&aggregator.interrupt_evt, aggregator.gsi)?;
+        self.processor_aggregator = Some(aggregator);
+        Ok(())
+    }
+
+    pub fn attach_s2idle(&mut self, vm: &Vm) -> Result<(), ACPIDeviceError> {
+        let mmio_addr = vm.resource_allocator().allocate_32bit_mmio_memory(
+            S2IDLE_MMIO_SIZE,
+            S2IDLE_MMIO_SIZE,
+            AllocPolicy::FirstMatch,
+        )?;
+        self.insert_s2idle(vm, Arc::new(Mutex::new(S2idle::new(mmio_addr))))
+    }
+
+    pub(crate) fn insert_s2idle(
+        &mut self,
+        vm: &Vm,
+        s2idle: Arc<Mutex<S2idle>>,
+    ) -> Result<(), ACPIDeviceError> {
+        let mmio_addr = s2idle.lock().expect("Poisoned lock").mmio_addr;
+        vm.common
+            .mmio_bus
+            .insert(s2idle.clone(), mmio_addr, S2IDLE_MMIO_SIZE)?;
+        self.s2idle = Some(s2idle);
+        Ok(())
+    }
+
+    pub fn attach_pvpanic(&mut self, vm: &Vm) -> Result<(), ACPIDeviceError> {
+        let mmio_addr = vm.resource_allocator().allocate_32bit_mmio_memory(
+            PVPANIC_MMIO_SIZE,
+            PVPANIC_MMIO_SIZE,
+            AllocPolicy::FirstMatch,
+        )?;
+        self.insert_pvpanic(vm, Arc::new(Mutex::new(PvPanic::new(mmio_addr))))
+    }
+
+    pub(crate) fn insert_pvpanic(
+        &mut self,
+        vm: &Vm,
+        pvpanic: Arc<Mutex<PvPanic>>,
+    ) -> Result<(), ACPIDeviceError> {
+        let mmio_addr = pvpanic.lock().expect("Poisoned lock").mmio_addr;
+        vm.common
+            .mmio_bus
+            .insert(pvpanic.clone(), mmio_addr, PVPANIC_MMIO_SIZE)?;
+        self.pvpanic = Some(pvpanic);
+        Ok(())
+    }
+
+    pub fn attach_cppc(&mut self, vm: &Vm, nr_vcpus: u8) -> Result<(), ACPIDeviceError> {
+        let cppc = Cppc::new(
+            &mut vm.resource_allocator(),
+            vm.guest_memory().clone(),
+            nr_vcpus,
+        )?;
+        cppc.activate()?;
+        self.insert_cppc(vm, Arc::new(Mutex::new(cppc)))
+    }
+
+    pub(crate) fn insert_cppc(
+        &mut self,
+        vm: &Vm,
+        cppc: Arc<Mutex<Cppc>>,
+    ) -> Result<(), ACPIDeviceError> {
+        let mmio_addr = cppc.lock().expect("Poisoned lock").mmio_addr;
+        vm.common
+            .mmio_bus
+            .insert(cppc.clone(), mmio_addr, CPPC_MMIO_SIZE)?;
+        self.cppc = Some(cppc);
+        Ok(())
+    }
+
+    pub fn attach_thermal_zone(
+        &mut self,
+        vm: &Vm,
+        config: ThermalZoneConfig,
+    ) -> Result<(), ACPIDeviceError> {
+        let mut thermal_zone = ThermalZone::new(&mut vm.resource_allocator(), config)?;
+        thermal_zone.activate(vm.guest_memory())?;
+        self.insert_thermal_zone(vm, thermal_zone)
+    }
+
+    pub(crate) fn insert_thermal_zone(
+        &mut self,
+        vm: &Vm,
+        thermal_zone: ThermalZone,
+    ) -> Result<(), ACPIDeviceError> {
+        vm.register_irq(&thermal_zone.interrupt_evt, thermal_zone.gsi)?;
+        self.thermal_zone = Some(thermal_zone);
+        Ok(())
+    }
+
+    pub fn attach_fw_cfg(&mut self, vm: &Vm, files: Vec<FwCfgFile>) -> Result<(), ACPIDeviceError> {
+        #[cfg(target_arch = "x86_64")]
+        let fw_cfg = FwCfg::new(vm.guest_memory().clone(), files);
+        #[cfg(target_arch = "aarch64")]
+        let fw_cfg = FwCfg::new(
+            &mut vm.resource_allocator(),
+            vm.guest_memory().clone(),
+            files,
+        )?;
+        self.insert_fw_cfg(vm, Arc::new(Mutex::new(fw_cfg)))
+    }
+
+    pub(crate) fn insert_fw_cfg(
+        &mut self,
+        vm: &Vm,
+        fw_cfg: Arc<Mutex<FwCfg>>,
+    ) -> Result<(), ACPIDeviceError> {
+        let addr = fw_cfg.lock().expect("Poisoned lock").addr;
+        // The registers are I/O ports on x86_64, where guest firmware looks for them.
+        #[cfg(target_arch = "x86_64")]
+        let bus = &vm.pio_bus;
+        #[cfg(target_arch = "aarch64")]
+        let bus = &vm.common.mmio_bus;
+        bus.insert(fw_cfg.clone(), addr, FW_CFG_REGS_SIZE)?;
+        self.fw_cfg = Some(fw_cfg);
+        Ok(())
+    }
 }
 
 #[cfg(target_arch = "x86_64")]
@@ -55,46 +310,147 @@ impl Aml for ACPIDeviceManager {
         self.vmgenid.append_aml_bytes(v)?;
         // AML for [`VmClock`] device.
         self.vmclock.append_aml_bytes(v)?;
+        // AML for [`Ghes`] device.
+        if let Some(ghes) = &self.ghes {
+            ghes.append_aml_bytes(v)?;
+        }
+        // AML for [`PowerSupply`] devices.
+        if let Some(power_supply) = &self.power_supply {
+            power_supply.append_aml_bytes(v)?;
+        }
+        // AML for [`ProcessorAggregator`] device.
+        if let Some(aggregator) = &self.processor_aggregator {
+            aggregator.append_aml_bytes(v)?;
+        }
+        // AML for [`S2idle`] device.
+        if let Some(s2idle) = &self.s2idle {
+            s2idle.lock().expect("Poisoned lock").append_aml_bytes(v)?;
+        }
+        // AML for [`PvPanic`] device.
+        if let Some(pvpanic) = &self.pvpanic {
+            pvpanic.lock().expect("Poisoned lock").append_aml_bytes(v)?;
+        }
+        // AML for [`Cppc`] device.
+        if let Some(cppc) = &self.cppc {
+            cppc.lock().expect("Poisoned lock").append_aml_bytes(v)?;
+        }
+        // AML for [`ThermalZone`] device.
+        if let Some(thermal_zone) = &self.thermal_zone {
+            thermal_zone.append_aml_bytes(v)?;
+        }
+        // AML for [`FwCfg`] device.
+        if let Some(fw_cfg) = &self.fw_cfg {
+            fw_cfg.lock().expect("Poisoned lock").append_aml_bytes(v)?;
+        }
+
+        let vmgenid_irq = aml::Interrupt::new(true, true, false, false, self.vmgenid.gsi);
+        let vmclock_irq = aml::Interrupt::new(true, true, false, false, self.vmclock.gsi);
+        let mut irqs: Vec<&dyn Aml> = vec![&vmgenid_irq, &vmclock_irq];
+
+        // We know that the maximum IRQ number fits in a u8. We have up to
+        // 32 IRQs in x86 and up to 128 in ARM (look into
+        // `vmm::crate::arch::layout::GSI_LEGACY_END`). All the GSIs of the ACPI devices can
+        // safely be cast to `u8` without truncation, so we let clippy know.
+        #[allow(clippy::cast_possible_truncation)]
+        let (vmgenid_gsi, vmclock_gsi) = (self.vmgenid.gsi as u8, self.vmclock.gsi as u8);
+        let vmgenid_path = aml::Path::new("\\_SB_.VGEN")?;
+        let vmclock_path = aml::Path::new("\\_SB_.VCLK")?;
+        let vmgenid_equal = aml::Equal::new(&aml::Arg(0), &vmgenid_gsi);
+        let vmclock_equal = aml::Equal::new(&aml::Arg(0), &vmclock_gsi);
+        let vmgenid_notify = aml::Notify::new(&vmgenid_path, &0x80usize);
+        let vmclock_notify = aml::Notify::new(&vmclock_path, &0x80usize);
+        let vmgenid_if = aml::If::new(&vmgenid_equal, vec![&vmgenid_notify]);
+        let vmclock_if = aml::If::new(&vmclock_equal, vec![&vmclock_notify]);
+        let mut events: Vec<&dyn Aml> = vec![&vmgenid_if, &vmclock_if];
+
+        // The GHES notifies the Hardware Error Device only when it has a GSI.
+        let ghes_gsi = self.ghes.as_ref().and_then(|ghes| ghes.gsi);
+        let ghes_irq = ghes_gsi.map(|gsi| aml::Interrupt::new(true, true, false, false, gsi));
+        #[allow(clippy::cast_possible_truncation)]
+        let ghes_gsi = ghes_gsi.map(|gsi| gsi as u8);
+        let ghes_path = aml::Path::new("\\_SB_.HED_")?;
+        let ghes_equal = ghes_gsi
+            .as_ref()
+            .map(|gsi| aml::Equal::new(&aml::Arg(0), gsi));
+        let ghes_notify = aml::Notify::new(&ghes_path, &0x80usize);
+        let ghes_if = ghes_equal
+            .as_ref()
+            .map(|equal| aml::If::new(equal, vec![&ghes_notify]));
+        if let (Some(irq), Some(ghes_if)) = (&ghes_irq, &ghes_if) {
+            irqs.push(irq);
+            events.push(ghes_if);
+        }
+
+        // The power supply devices are notified of every update of their status.
+        let power_supply_gsi = self.power_supply.as_ref().map(|device| device.gsi);
+        let power_supply_irq =
+            power_supply_gsi.map(|gsi| aml::Interrupt::new(true, true, false, false, gsi));
+        #[allow(clippy::cast_possible_truncation)]
+        let power_supply_gsi = power_supply_gsi.map(|gsi| gsi as u8);
+        let power_supply_equal = power_supply_gsi
+            .as_ref()
+            .map(|gsi| aml::Equal::new(&aml::Arg(0), gsi));
+        let adapter_path = aml::Path::new("\\_SB_.ADP1")?;
+        let battery_path = aml::Path::new("\\_SB_.BAT0")?;
+        let adapter_notify = aml::Notify::new(&adapter_path, &NOTIFY_STATUS_CHANGED);
+        let battery_info_notify = aml::Notify::new(&battery_path, &NOTIFY_INFORMATION_CHANGED);
+        let battery_notify = aml::Notify::new(&battery_path, &NOTIFY_STATUS_CHANGED);
+        let power_supply_if = power_supply_equal.as_ref().map(|equal| {
+            aml::If::new(
+                equal,
+                vec![&adapter_notify, &battery_info_notify, &battery_notify],
+            )
+        });
+        if let (Some(irq), Some(power_supply_if)) = (&power_supply_irq, &power_supply_if) {
+            irqs.push(irq);
+            events.push(power_supply_if);
+        }
+
+        // The processor aggregator is notified when the number of vCPUs to idle changes.
+        let aggregator_gsi = self.processor_aggregator.as_ref().map(|device| device.gsi);
+        let aggregator_irq =
+            aggregator_gsi.map(|gsi| aml::Interrupt::new(true, true, false, false, gsi));
+        #[allow(clippy::cast_possible_truncation)]
+        let aggregator_gsi = aggregator_gsi.map(|gsi| gsi as u8);
+        let aggregator_path = aml::Path::new("\\_SB_.PAD0")?;
+        let aggregator_equal = aggregator_gsi
+            .as_ref()
+            .map(|gsi| aml::Equal::new(&aml::Arg(0), gsi));
+        let aggregator_notify = aml::Notify::new(&aggregator_path, &NOTIFY_PUR_CHANGED);
+        let aggregator_if = aggregator_equal
+            .as_ref()
+            .map(|equal| aml::If::new(equal, vec![&aggregator_notify]));
+        if let (Some(irq), Some(aggregator_if)) = (&aggregator_irq, &aggregator_if) {
+            irqs.push(irq);
+            events.push(aggregator_if);
+        }
+
+        // The thermal zone is notified when its temperature crosses a trip point.
+        let thermal_zone_gsi = self.thermal_zone.as_ref().map(|device| device.gsi);
+        let thermal_zone_irq =
+            thermal_zone_gsi.map(|gsi| aml::Interrupt::new(true, true, false, false, gsi));
+        #[allow(clippy::cast_possible_truncation)]
+        let thermal_zone_gsi = thermal_zone_gsi.map(|gsi| gsi as u8);
+        let thermal_zone_path = aml::Path::new("\\_TZ_.TZ00")?;
+        let thermal_zone_equal = thermal_zone_gsi
+            .as_ref()
+            .map(|gsi| aml::Equal::new(&aml::Arg(0), gsi));
+        let thermal_zone_notify = aml::Notify::new(&thermal_zone_path, &NOTIFY_TEMPERATURE_CHANGED);
+        let thermal_zone_if = thermal_zone_equal
+            .as_ref()
+            .map(|equal| aml::If::new(equal, vec![&thermal_zone_notify]));
+        if let (Some(irq), Some(thermal_zone_if)) = (&thermal_zone_irq, &thermal_zone_if) {
+            irqs.push(irq);
+            events.push(thermal_zone_if);
+        }
 
         // Create the AML for the GED interrupt handler
         aml::Device::new(
             "_SB_.GED_".try_into()?,
             vec![
                 &aml::Name::new("_HID".try_into()?, &"ACPI0013")?,
-                &aml::Name::new(
-                    "_CRS".try_into()?,
-                    &aml::ResourceTemplate::new(vec![
-                        &aml::Interrupt::new(true, true, false, false, self.vmgenid.gsi),
-                        &aml::Interrupt::new(true, true, false, false, self.vmclock.gsi),
-                    ]),
-                )?,
-                // We know that the maximum IRQ number fits in a u8. We have up to
-                // 32 IRQs in x86 and up to 128 in ARM (look into `vmm::crate::arch::layout::GSI_LEGACY_END`).
-                // Both `vmgenid.gsi` and `vmclock.gsi` can safely be cast to `u8`
-                // without truncation, so we let clippy know.
-                &aml::Method::new(
-                    "_EVT".try_into()?,
-                    1,
-                    true,
-                    vec![
-                        &aml::If::new(
-                            #[allow(clippy::cast_possible_truncation)]
-                            &aml::Equal::new(&aml::Arg(0), &(self.vmgenid.gsi as u8)),
-                            vec![&aml::Notify::new(
-                                &aml::Path::new("\\_SB_.VGEN")?,
-                                &0x80usize,
-                            )],
-                        ),
-                        &aml::If::new(
-                            #[allow(clippy::cast_possible_truncation)]
-                            &aml::Equal::new(&aml::Arg(0), &(self.vmclock.gsi as u8)),
-                            vec![&aml::Notify::new(
-                                &aml::Path::new("\\_SB_.VCLK")?,
-                                &0x80usize,
-                            )],
-                        ),
-                    ],
-                ),
+                &aml::Name::new("_CRS".try_into()?, &aml::ResourceTemplate::new(irqs))?,
+                &aml::Method::new("_EVT".try_into()?, 1, true, events),
             ],
         )
         .append_aml_bytes(v)
@@ -17,7 +17,9 @@ use vmm_sys_util::eventfd::EventFd;
 
 use crate::Vm;
 use crate::devices::legacy::serial::SerialOut;
-use crate::devices::legacy::{EventFdTrigger, I8042Device, SerialDevice, SerialEventsWrapper};
+use crate::devices::legacy::{
+    EventFdTrigger, I8042Device, ResetRegister, SerialDevice, SerialEventsWrapper,
+};
 use crate::vstate::bus::BusError;
 
 /// Errors corresponding to the `PortIODeviceManager`.
@@ -30,21 +32,24 @@ pub enum LegacyDeviceError {
 }
 
 /// The `PortIODeviceManager` is a wrapper that is used for registering legacy devices
-/// on an I/O Bus. It currently manages the uart and i8042 devices.
+/// on an I/O Bus. It currently manages the uart, i8042 and reset register devices. The i8042 is
+/// left out of legacy-free platforms.
 /// The `LegacyDeviceManger` should be initialized only by using the constructor.
 #[derive(Debug)]
 pub struct PortIODeviceManager {
     // BusDevice::Serial
     pub stdio_serial: Arc<Mutex<SerialDevice>>,
-    // BusDevice::I8042Device
-    pub i8042: Arc<Mutex<I8042Device>>,
+    // BusDevice::I8042Device, absent on legacy-free platforms
+    pub i8042: Option<Arc<Mutex<I8042Device>>>,
+    // BusDevice::ResetRegister
+    pub reset_register: Arc<Mutex<ResetRegister>>,
 
     // Communication event on ports 1 & 3.
     pub com_evt_1_3: EventFdTrigger,
     // Communication event on ports 2 & 4.
     pub com_evt_2_4: EventFdTrigger,
     // Keyboard event.
-    pub kbd_evt: EventFd,
+    pub kbd_evt: Option<EventFd>,
 }
 
 impl PortIODeviceManager {
@@ -69,11 +74,15 @@ impl PortIODeviceManager {
     const I8042_KDB_DATA_REGISTER_ADDRESS: u64 = 0x060;
     /// i8042 keyboard data register size.
     const I8042_KDB_DATA_REGISTER_SIZE: u64 = 0x5;
+    /// ACPI reset register address. The port belongs to the VGA range, which is unused as there
+    /// is no VGA hardware.
+    pub const RESET_REGISTER_ADDRESS: u64 = 0x3c0;
 
-    /// Create a new DeviceManager handling legacy devices (uart, i8042).
+    /// Create a new DeviceManager handling legacy devices (uart, i8042, reset register).
     pub fn new(
         stdio_serial: Arc<Mutex<SerialDevice>>,
-        i8042: Arc<Mutex<I8042Device>>,
+        i8042: Option<Arc<Mutex<I8042Device>>>,
+        reset_register: Arc<Mutex<ResetRegister>>,
     ) -> Result<Self, LegacyDeviceError> {
         let com_evt_1_3 = stdio_serial
             .lock()
@@ -83,14 +92,20 @@ impl PortIODeviceManager {
             .try_clone()?;
         let com_evt_2_4 = EventFdTrigger::new(EventFd::new(EFD_NONBLOCK)?);
         let kbd_evt = i8042
-            .lock()
-            .expect("Poisoned lock")
-            .kbd_interrupt_evt
-            .try_clone()?;
+            .as_ref()
+            .map(|i8042| {
+                i8042
+                    .lock()
+                    .expect("Poisoned lock")
+                    .kbd_interrupt_evt
+                    .try_clone()
+            })
+            .transpose()?;
 
         Ok(PortIODeviceManager {
             stdio_serial,
             i8042,
+            reset_register,
             com_evt_1_3,
             com_evt_2_4,
             kbd_evt,
@@ -141,11 +156,14 @@ impl PortIODeviceManager {
             Self::SERIAL_PORT_ADDRESSES[3],
             Self::SERIAL_PORT_SIZE,
         )?;
-        io_bus.insert(
-            self.i8042.clone(),
-            Self::I8042_KDB_DATA_REGISTER_ADDRESS,
-            Self::I8042_KDB_DATA_REGISTER_SIZE,
-        )?;
+        if let Some(i8042) = &self.i8042 {
+            io_bus.insert(
+                i8042.clone(),
+                Self::I8042_KDB_DATA_REGISTER_ADDRESS,
+                Self::I8042_KDB_DATA_REGISTER_SIZE,
+            )?;
+        }
+        io_bus.insert(self.reset_register.clone(), Self::RESET_REGISTER_ADDRESS, 1)?;
 
         vm.register_irq(&self.com_evt_1_3, Self::COM_EVT_1_3_GSI)
             .map_err(|e| {
@@ -155,15 +173,16 @@ impl PortIODeviceManager {
             .map_err(|e| {
                 LegacyDeviceError::EventFd(std::io::Error::from_raw_os_error(e.errno()))
             })?;
-        vm.register_irq(&self.kbd_evt, Self::KBD_EVT_GSI)
-            .map_err(|e| {
+        if let Some(kbd_evt) = &self.kbd_evt {
+            vm.register_irq(kbd_evt, Self::KBD_EVT_GSI).map_err(|e| {
                 LegacyDeviceError::EventFd(std::io::Error::from_raw_os_error(e.errno()))
             })?;
+        }
 
         Ok(())
     }
 
-    pub(crate) fn append_aml_bytes(bytes: &mut Vec<u8>) -> Result<(), AmlError> {
+    pub(crate) fn append_aml_bytes(&self, bytes: &mut Vec<u8>) -> Result<(), AmlError> {
         // Set up COM devices
         let gsi = [
             Self::COM_EVT_1_3_GSI,
@@ -199,6 +218,9 @@ impl PortIODeviceManager {
             )
             .append_aml_bytes(bytes)?;
         }
+        if self.i8042.is_none() {
+            return Ok(());
+        }
         // Setup i8042
         aml::Device::new(
             "_SB_.PS2_".try_into()?,
@@ -254,11 +276,43 @@ mod tests {
                 ),
                 input: None,
             })),
-            Arc::new(Mutex::new(
+            Some(Arc::new(Mutex::new(
                 I8042Device::new(EventFd::new(libc::EFD_NONBLOCK).unwrap()).unwrap(),
-            )),
+            ))),
+            Arc::new(Mutex::new(ResetRegister::new(
+                EventFd::new(libc::EFD_NONBLOCK).unwrap(),
+            ))),
         )
         .unwrap();
         ldm.register_devices(&vm).unwrap();
     }
+
+    #[test]
+    fn test_legacy_free_devices() {
+        let (_, vm) = setup_vm_with_memory(0x1000);
+        vm.setup_irqchip().unwrap();
+        let mut ldm = PortIODeviceManager::new(
+            Arc::new(Mutex::new(SerialDevice {
+                serial: Serial::with_events(
+                    EventFdTrigger::new(EventFd::new(EFD_NONBLOCK).unwrap()),
+                    SerialEventsWrapper {
+                        buffer_ready_event_fd: None,
+                    },
+                    SerialOut::Sink,
+                ),
+                input: None,
+            })),
+            None,
+            Arc::new(Mutex::new(ResetRegister::new(
+                EventFd::new(libc::EFD_NONBLOCK).unwrap(),
+            ))),
+        )
+        .unwrap();
+        ldm.register_devices(&vm).unwrap();
+        assert!(ldm.kbd_evt.is_none());
+
+        let mut aml = Vec::new();
+        ldm.append_aml_bytes(&mut aml).unwrap();
+        assert!(!aml.windows(4).any(|name| name == b"PS2_"));
+    }
 }
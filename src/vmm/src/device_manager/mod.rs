@@ -7,7 +7,6 @@
 
 use std::convert::Infallible;
 use std::fmt::Debug;
-use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 
 use acpi::ACPIDeviceManager;
@@ -23,19 +22,36 @@ use serde::{Deserialize, Serialize};
 use utils::time::TimestampUs;
 use vmm_sys_util::eventfd::EventFd;
 
-use crate::device_manager::acpi::ACPIDeviceError;
 #[cfg(target_arch = "x86_64")]
-use crate::devices::legacy::I8042Device;
+use crate::arch::x86_64::layout::{IOAPIC_ADDR, IOMMU_ADDR};
+use crate::device_manager::acpi::ACPIDeviceError;
+use crate::devices::acpi::fw_cfg::FwCfgFile;
 #[cfg(target_arch = "aarch64")]
 use crate::devices::legacy::RTCDevice;
+#[cfg(target_arch = "x86_64")]
+use crate::devices::legacy::ioapic::IOAPIC_MMIO_SIZE;
+#[cfg(target_arch = "x86_64")]
+use crate::devices::legacy::iommu::IOMMU_MMIO_SIZE;
 use crate::devices::legacy::serial::SerialOut;
-use crate::devices::legacy::{IER_RDA_BIT, IER_RDA_OFFSET, SerialDevice};
+#[cfg(target_arch = "x86_64")]
+use crate::devices::legacy::{I8042Device, Ioapic, Iommu, ResetRegister};
+use crate::devices::legacy::{IER_RDA_BIT, IER_RDA_OFFSET, SerialDevice, SerialSocket};
 use crate::devices::pseudo::BootTimer;
 use crate::devices::virtio::device::{VirtioDevice, VirtioDeviceType};
 use crate::devices::virtio::transport::mmio::{IrqTrigger, MmioTransport};
+use crate::persist::VmInfo;
 use crate::resources::VmResources;
 use crate::snapshot::Persist;
 use crate::utils::open_file_write_nonblock;
+use crate::vmm_config::apei::ApeiConfig;
+use crate::vmm_config::nvme::NvmeDeviceConfig;
+use crate::vmm_config::power_supply::PowerSupplyConfig;
+use crate::vmm_config::processor_aggregator::ProcessorAggregatorConfig;
+use crate::vmm_config::serial::SerialConfig;
+use crate::vmm_config::thermal_zone::ThermalZoneConfig;
+use crate::vmm_config::usb::UsbDeviceConfig;
+use crate::vmm_config::vfio::VfioConfig;
+use crate::vmm_config::watchdog::WatchdogConfig;
 use crate::vstate::bus::BusError;
 use crate::vstate::memory::GuestMemoryMmap;
 use crate::{EmulateSerialInitError, EventManager, Vm};
@@ -97,6 +113,12 @@ pub struct DeviceManager {
     #[cfg(target_arch = "x86_64")]
     /// Legacy devices
     pub legacy_devices: PortIODeviceManager,
+    #[cfg(target_arch = "x86_64")]
+    /// IOAPIC emulated in userspace, with a split irqchip
+    pub ioapic: Option<Arc<Mutex<Ioapic>>>,
+    #[cfg(target_arch = "x86_64")]
+    /// Interrupt remapping unit, with a split irqchip
+    pub iommu: Option<Arc<Mutex<Iommu>>>,
     /// ACPI devices
     pub acpi_devices: ACPIDeviceManager,
     /// PCIe devices
@@ -122,11 +144,15 @@ impl DeviceManager {
     /// Sets up the serial device.
     fn setup_serial_device(
         event_manager: &mut EventManager,
-        output: Option<&PathBuf>,
+        config: &SerialConfig,
     ) -> Result<Arc<Mutex<SerialDevice>>, std::io::Error> {
-        let (serial_in, serial_out) = match output {
-            Some(path) => (None, open_file_write_nonblock(path).map(SerialOut::File)?),
-            None => {
+        let (serial_in, serial_out) = match (&config.serial_out_path, &config.serial_socket_path) {
+            (Some(path), _) => (None, open_file_write_nonblock(path).map(SerialOut::File)?),
+            (None, Some(path)) => (
+                None,
+                SerialSocket::new(path, config.scrollback_bytes()).map(SerialOut::Socket)?,
+            ),
+            (None, None) => {
                 Self::set_stdout_nonblocking();
 
                 (Some(std::io::stdin()), SerialOut::Stdout(std::io::stdout()))
@@ -143,18 +169,29 @@ impl DeviceManager {
         event_manager: &mut EventManager,
         vcpus_exit_evt: &EventFd,
         vm: &Vm,
-        serial_output: Option<&PathBuf>,
+        serial_config: &SerialConfig,
+        legacy_free: bool,
     ) -> Result<PortIODeviceManager, DeviceManagerCreateError> {
         // Create serial device
-        let serial = Self::setup_serial_device(event_manager, serial_output)?;
+        let serial = Self::setup_serial_device(event_manager, serial_config)?;
         let reset_evt = vcpus_exit_evt
             .try_clone()
             .map_err(DeviceManagerCreateError::EventFd)?;
-        // Create keyboard emulator for reset event
-        let i8042 = Arc::new(Mutex::new(I8042Device::new(reset_evt)?));
+        // Create the ACPI reset register and the keyboard emulator, both signalling reset events
+        let reset_register = Arc::new(Mutex::new(ResetRegister::new(
+            reset_evt
+                .try_clone()
+                .map_err(DeviceManagerCreateError::EventFd)?,
+        )));
+        // Legacy-free platforms have no keyboard emulator
+        let i8042 = if legacy_free {
+            None
+        } else {
+            Some(Arc::new(Mutex::new(I8042Device::new(reset_evt)?)))
+        };
 
         // create pio dev manager with legacy devices
-        let mut legacy_devices = PortIODeviceManager::new(serial, i8042)?;
+        let mut legacy_devices = PortIODeviceManager::new(serial, i8042, reset_register)?;
         legacy_devices.register_devices(vm)?;
         Ok(legacy_devices)
     }
@@ -164,16 +201,26 @@ impl DeviceManager {
         event_manager: &mut EventManager,
         vcpus_exit_evt: &EventFd,
         vm: &Vm,
-        serial_output: Option<&PathBuf>,
+        serial_config: &SerialConfig,
+        legacy_free: bool,
     ) -> Result<Self, DeviceManagerCreateError> {
         #[cfg(target_arch = "x86_64")]
-        let legacy_devices =
-            Self::create_legacy_devices(event_manager, vcpus_exit_evt, vm, serial_output)?;
+        let legacy_devices = Self::create_legacy_devices(
+            event_manager,
+            vcpus_exit_evt,
+            vm,
+            serial_config,
+            legacy_free,
+        )?;
 
         Ok(DeviceManager {
             mmio_devices: MMIODeviceManager::new(),
             #[cfg(target_arch = "x86_64")]
             legacy_devices,
+            #[cfg(target_arch = "x86_64")]
+            ioapic: None,
+            #[cfg(target_arch = "x86_64")]
+            iommu: None,
             acpi_devices: ACPIDeviceManager::new(&mut vm.resource_allocator()),
             pci_devices: PciDevices::new(),
         })
@@ -218,6 +265,62 @@ impl DeviceManager {
         Ok(())
     }
 
+    /// Passes a host PCI device through to the VM. PCI needs to be enabled.
+    pub(crate) fn attach_vfio_device(
+        &mut self,
+        vm: &Arc<Vm>,
+        config: &VfioConfig,
+    ) -> Result<(), AttachDeviceError> {
+        self.pci_devices.attach_vfio_device(vm, config)?;
+        Ok(())
+    }
+
+    /// Attaches the USB devices to a new xHCI controller of the VM. PCI needs to be enabled.
+    pub(crate) fn attach_usb_devices(
+        &mut self,
+        vm: &Arc<Vm>,
+        configs: &[UsbDeviceConfig],
+        event_manager: &mut EventManager,
+    ) -> Result<(), AttachDeviceError> {
+        self.pci_devices
+            .attach_usb_devices(vm, configs, event_manager)?;
+        Ok(())
+    }
+
+    /// Attaches an emulated NVMe controller to the VM. PCI needs to be enabled.
+    pub(crate) fn attach_nvme_device(
+        &mut self,
+        vm: &Arc<Vm>,
+        config: &NvmeDeviceConfig,
+        event_manager: &mut EventManager,
+    ) -> Result<(), AttachDeviceError> {
+        self.pci_devices
+            .attach_nvme_device(vm, config, event_manager)?;
+        Ok(())
+    }
+
+    /// Attaches the userspace IOAPIC of a split irqchip to the VM.
+    #[cfg(target_arch = "x86_64")]
+    pub(crate) fn attach_ioapic(&mut self, vm: &Arc<Vm>) -> Result<(), AttachDeviceError> {
+        let ioapic = Arc::new(Mutex::new(Ioapic::new(vm.clone())));
+        vm.common
+            .mmio_bus
+            .insert(ioapic.clone(), u64::from(IOAPIC_ADDR), IOAPIC_MMIO_SIZE)?;
+        self.ioapic = Some(ioapic);
+        Ok(())
+    }
+
+    /// Attaches the interrupt remapping unit of a split irqchip to the VM.
+    #[cfg(target_arch = "x86_64")]
+    pub(crate) fn attach_iommu(&mut self, vm: &Arc<Vm>) -> Result<(), AttachDeviceError> {
+        let iommu = Arc::new(Mutex::new(Iommu::new(vm.clone())));
+        vm.common
+            .mmio_bus
+            .insert(iommu.clone(), u64::from(IOMMU_ADDR), IOMMU_MMIO_SIZE)?;
+        self.iommu = Some(iommu);
+        Ok(())
+    }
+
     /// Attaches a [`BootTimer`] to the VM
     pub(crate) fn attach_boot_timer_device(
         &mut self,
@@ -242,13 +345,87 @@ impl DeviceManager {
         Ok(())
     }
 
+    pub(crate) fn attach_watchdog_device(
+        &mut self,
+        vm: &Vm,
+        config: WatchdogConfig,
+        vm_info: VmInfo,
+    ) -> Result<(), AttachDeviceError> {
+        self.acpi_devices.attach_watchdog(vm, config, vm_info)?;
+        Ok(())
+    }
+
+    pub(crate) fn attach_ghes_device(
+        &mut self,
+        vm: &Vm,
+        config: &ApeiConfig,
+    ) -> Result<(), AttachDeviceError> {
+        self.acpi_devices.attach_ghes(vm, config)?;
+        Ok(())
+    }
+
+    pub(crate) fn attach_power_supply_device(
+        &mut self,
+        vm: &Vm,
+        config: PowerSupplyConfig,
+    ) -> Result<(), AttachDeviceError> {
+        self.acpi_devices.attach_power_supply(vm, config)?;
+        Ok(())
+    }
+
+    pub(crate) fn attach_processor_aggregator_device(
+        &mut self,
+        vm: &Vm,
+        config: &ProcessorAggregatorConfig,
+    ) -> Result<(), AttachDeviceError> {
+        self.acpi_devices.attach_processor_aggregator(vm, config)?;
+        Ok(())
+    }
+
+    pub(crate) fn attach_s2idle_device(&mut self, vm: &Vm) -> Result<(), AttachDeviceError> {
+        self.acpi_devices.attach_s2idle(vm)?;
+        Ok(())
+    }
+
+    pub(crate) fn attach_pvpanic_device(&mut self, vm: &Vm) -> Result<(), AttachDeviceError> {
+        self.acpi_devices.attach_pvpanic(vm)?;
+        Ok(())
+    }
+
+    pub(crate) fn attach_cppc_device(
+        &mut self,
+        vm: &Vm,
+        nr_vcpus: u8,
+    ) -> Result<(), AttachDeviceError> {
+        self.acpi_devices.attach_cppc(vm, nr_vcpus)?;
+        Ok(())
+    }
+
+    pub(crate) fn attach_thermal_zone_device(
+        &mut self,
+        vm: &Vm,
+        config: ThermalZoneConfig,
+    ) -> Result<(), AttachDeviceError> {
+        self.acpi_devices.attach_thermal_zone(vm, config)?;
+        Ok(())
+    }
+
+    pub(crate) fn attach_fw_cfg_device(
+        &mut self,
+        vm: &Vm,
+        files: Vec<FwCfgFile>,
+    ) -> Result<(), AttachDeviceError> {
+        self.acpi_devices.attach_fw_cfg(vm, files)?;
+        Ok(())
+    }
+
     #[cfg(target_arch = "aarch64")]
     pub(crate) fn attach_legacy_devices_aarch64(
         &mut self,
         vm: &Vm,
         event_manager: &mut EventManager,
         cmdline: &mut Cmdline,
-        serial_out_path: Option<&PathBuf>,
+        serial_config: &SerialConfig,
     ) -> Result<(), AttachDeviceError> {
         // Serial device setup.
         let cmdline_contains_console = cmdline
@@ -259,7 +436,7 @@ impl DeviceManager {
             .contains("console=");
 
         if cmdline_contains_console {
-            let serial = Self::setup_serial_device(event_manager, serial_out_path)?;
+            let serial = Self::setup_serial_device(event_manager, serial_config)?;
             self.mmio_devices.register_mmio_serial(vm, serial, None)?;
             self.mmio_devices.add_mmio_serial_to_cmdline(cmdline)?;
         }
@@ -299,6 +476,30 @@ impl DeviceManager {
         }
     }
 
+    /// Resets VirtIO devices as on a platform reset. On failure, returns the ID of a device that
+    /// does not support being reset.
+    pub fn reset_virtio_devices(&self) -> Result<(), String> {
+        // Go through MMIO VirtIO devices
+        self.mmio_devices.for_each_virtio_device(|_, id, device| {
+            if device.inner.lock().expect("Poisoned lock").system_reset() {
+                Ok(())
+            } else {
+                Err(id.clone())
+            }
+        })?;
+        // Go through PCI VirtIO devices
+        for ((_, id), virtio_pci_device) in &self.pci_devices.virtio_devices {
+            if !virtio_pci_device
+                .lock()
+                .expect("Poisoned lock")
+                .system_reset()
+            {
+                return Err(id.clone());
+            }
+        }
+        Ok(())
+    }
+
     fn do_mark_virtio_queue_memory_dirty(
         device: Arc<Mutex<dyn VirtioDevice>>,
         mem: &GuestMemoryMmap,
@@ -448,7 +649,9 @@ impl<'a> Persist<'a> for DeviceManager {
             constructor_args.event_manager,
             constructor_args.vcpus_exit_evt,
             constructor_args.vm,
-            constructor_args.vm_resources.serial_out_path.as_ref(),
+            &constructor_args.vm_resources.serial,
+            // Legacy-free platforms require a split irqchip, so they cannot be snapshotted.
+            false,
         )?;
 
         // Restore MMIO devices
@@ -482,6 +685,11 @@ impl<'a> Persist<'a> for DeviceManager {
             mmio_devices,
             #[cfg(target_arch = "x86_64")]
             legacy_devices,
+            // MicroVMs with a split irqchip cannot be snapshotted.
+            #[cfg(target_arch = "x86_64")]
+            ioapic: None,
+            #[cfg(target_arch = "x86_64")]
+            iommu: None,
             acpi_devices,
             pci_devices,
         };
@@ -552,9 +760,12 @@ pub(crate) mod tests {
             Arc::new(Mutex::new(
                 SerialDevice::new(None, SerialOut::Sink).unwrap(),
             )),
-            Arc::new(Mutex::new(
+            Some(Arc::new(Mutex::new(
                 I8042Device::new(EventFd::new(libc::EFD_NONBLOCK).unwrap()).unwrap(),
-            )),
+            ))),
+            Arc::new(Mutex::new(ResetRegister::new(
+                EventFd::new(libc::EFD_NONBLOCK).unwrap(),
+            ))),
         )
         .unwrap();
 
@@ -562,6 +773,10 @@ pub(crate) mod tests {
             mmio_devices,
             #[cfg(target_arch = "x86_64")]
             legacy_devices,
+            #[cfg(target_arch = "x86_64")]
+            ioapic: None,
+            #[cfg(target_arch = "x86_64")]
+            iommu: None,
             acpi_devices,
             pci_devices,
         }
@@ -577,7 +792,12 @@ pub(crate) mod tests {
         let mut cmdline = Cmdline::new(4096).unwrap();
         let mut event_manager = EventManager::new().unwrap();
         vmm.device_manager
-            .attach_legacy_devices_aarch64(&vmm.vm, &mut event_manager, &mut cmdline, None)
+            .attach_legacy_devices_aarch64(
+                &vmm.vm,
+                &mut event_manager,
+                &mut cmdline,
+                &SerialConfig::default(),
+            )
             .unwrap();
         assert!(vmm.device_manager.mmio_devices.rtc.is_some());
         assert!(vmm.device_manager.mmio_devices.serial.is_none());
@@ -585,7 +805,12 @@ pub(crate) mod tests {
         let mut vmm = default_vmm();
         cmdline.insert("console", "/dev/blah").unwrap();
         vmm.device_manager
-            .attach_legacy_devices_aarch64(&vmm.vm, &mut event_manager, &mut cmdline, None)
+            .attach_legacy_devices_aarch64(
+                &vmm.vm,
+                &mut event_manager,
+                &mut cmdline,
+                &SerialConfig::default(),
+            )
             .unwrap();
         assert!(vmm.device_manager.mmio_devices.rtc.is_some());
         assert!(vmm.device_manager.mmio_devices.serial.is_some());
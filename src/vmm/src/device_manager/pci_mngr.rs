@@ -11,7 +11,13 @@ use log::{debug, error, warn};
 use serde::{Deserialize, Serialize};
 
 use super::persist::MmdsState;
+use crate::devices::nvme::{NVME_BAR_SIZE, NvmeController, NvmeError};
 use crate::devices::pci::PciSegment;
+use crate::devices::pci::vfio::{VfioContainer, VfioDevice, VfioError, VfioPciDevice};
+use crate::devices::usb::hid::HidrawDevice;
+use crate::devices::usb::host::HostUsbDevice;
+use crate::devices::usb::xhci::{XHCI_BAR_SIZE, XhciController, XhciError};
+use crate::devices::usb::{UsbDevice, UsbError};
 use crate::devices::virtio::balloon::Balloon;
 use crate::devices::virtio::balloon::persist::{BalloonConstructorArgs, BalloonState};
 use crate::devices::virtio::block::device::Block;
@@ -37,6 +43,9 @@ use crate::resources::VmResources;
 use crate::snapshot::Persist;
 use crate::vmm_config::memory_hotplug::MemoryHotplugConfig;
 use crate::vmm_config::mmds::MmdsConfigError;
+use crate::vmm_config::nvme::NvmeDeviceConfig;
+use crate::vmm_config::usb::UsbDeviceConfig;
+use crate::vmm_config::vfio::VfioConfig;
 use crate::vstate::bus::BusError;
 use crate::vstate::interrupts::InterruptError;
 use crate::vstate::memory::GuestMemoryMmap;
@@ -48,6 +57,14 @@ pub struct PciDevices {
     pub pci_segment: Option<PciSegment>,
     /// All VirtIO PCI devices of the system
     pub virtio_devices: HashMap<(VirtioDeviceType, String), Arc<Mutex<VirtioPciDevice>>>,
+    /// All host PCI devices passed through with VFIO
+    pub vfio_devices: HashMap<String, Arc<Mutex<VfioPciDevice>>>,
+    /// VFIO container shared by all the VFIO devices, created along with the first of them
+    pub vfio_container: Option<VfioContainer>,
+    /// xHCI controller, if USB devices are attached
+    pub xhci_controller: Option<Arc<Mutex<XhciController>>>,
+    /// Emulated NVMe controllers, indexed by their ID
+    pub nvme_controllers: HashMap<String, Arc<Mutex<NvmeController>>>,
 }
 
 #[derive(Debug, thiserror::Error, displaydoc::Display)]
@@ -66,6 +83,14 @@ pub enum PciManagerError {
     Kvm(#[from] vmm_sys_util::errno::Error),
     /// MMDS error: {0}
     Mmds(#[from] MmdsConfigError),
+    /// VFIO device error: {0}
+    Vfio(#[from] VfioError),
+    /// USB device error: {0}
+    Usb(#[from] UsbError),
+    /// xHCI controller error: {0}
+    Xhci(#[from] XhciError),
+    /// NVMe controller error: {0}
+    Nvme(#[from] NvmeError),
 }
 
 impl PciDevices {
@@ -203,6 +228,142 @@ impl PciDevices {
         Ok(())
     }
 
+    pub(crate) fn attach_vfio_device(
+        &mut self,
+        vm: &Arc<Vm>,
+        config: &VfioConfig,
+    ) -> Result<(), PciManagerError> {
+        // We should only be reaching this point if PCI is enabled
+        let pci_segment = self.pci_segment.as_ref().unwrap();
+        let pci_device_bdf = pci_segment.next_device_bdf()?;
+        debug!(
+            "Allocating BDF: {pci_device_bdf:?} for VFIO device {:?}",
+            config.path
+        );
+
+        let container = match self.vfio_container.as_mut() {
+            Some(container) => container,
+            None => self.vfio_container.insert(VfioContainer::new()?),
+        };
+        let device = VfioDevice::new(&config.path, container, vm.guest_memory())?;
+        let numa_node = device.numa_node();
+        let vfio_device = Arc::new(Mutex::new(VfioPciDevice::new(
+            config.id.clone(),
+            vm,
+            device,
+            pci_device_bdf,
+        )?));
+
+        pci_segment
+            .pci_bus
+            .lock()
+            .expect("Poisoned lock")
+            .add_device(pci_device_bdf.device() as u32, vfio_device.clone());
+
+        let trapped_bars: Vec<_> = vfio_device
+            .lock()
+            .expect("Poisoned lock")
+            .trapped_bars()
+            .collect();
+        for (addr, size) in trapped_bars {
+            debug!("Inserting VFIO MMIO BAR region: {addr:#x}:{size:#x}");
+            vm.common.mmio_bus.insert(vfio_device.clone(), addr, size)?;
+        }
+
+        // Hint the guest about the locality of the host device, so that its driver allocates
+        // memory and spreads interrupts accordingly.
+        if let Some(numa_node) = numa_node {
+            debug!(
+                "VFIO device {} is attached to NUMA node {numa_node}",
+                config.id
+            );
+            self.pci_segment.as_mut().unwrap().slot_proximity_domains
+                [pci_device_bdf.device() as usize] = Some(numa_node);
+        }
+
+        self.vfio_devices.insert(config.id.clone(), vfio_device);
+        Ok(())
+    }
+
+    pub(crate) fn attach_usb_devices(
+        &mut self,
+        vm: &Arc<Vm>,
+        configs: &[UsbDeviceConfig],
+        event_manager: &mut EventManager,
+    ) -> Result<(), PciManagerError> {
+        let mut devices: Vec<Box<dyn UsbDevice>> = Vec::with_capacity(configs.len());
+        for config in configs {
+            debug!("Opening USB device {}", config.id);
+            match (&config.usbfs_path, &config.hidraw_path) {
+                (Some(path), _) => devices.push(Box::new(HostUsbDevice::open(path)?)),
+                (None, Some(path)) => devices.push(Box::new(HidrawDevice::open(path)?)),
+                (None, None) => unreachable!("USB device without backend"),
+            }
+        }
+
+        // We should only be reaching this point if PCI is enabled
+        let pci_segment = self.pci_segment.as_ref().unwrap();
+        let pci_device_bdf = pci_segment.next_device_bdf()?;
+        debug!("Allocating BDF: {pci_device_bdf:?} for the xHCI controller");
+
+        let xhci_controller = Arc::new(Mutex::new(XhciController::new(
+            vm,
+            pci_device_bdf,
+            devices,
+        )?));
+
+        pci_segment
+            .pci_bus
+            .lock()
+            .expect("Poisoned lock")
+            .add_device(pci_device_bdf.device() as u32, xhci_controller.clone());
+
+        let bar_addr = xhci_controller.lock().expect("Poisoned lock").bar_address();
+        debug!("Inserting xHCI MMIO BAR region: {bar_addr:#x}:{XHCI_BAR_SIZE:#x}");
+        vm.common
+            .mmio_bus
+            .insert(xhci_controller.clone(), bar_addr, XHCI_BAR_SIZE)?;
+
+        event_manager.add_subscriber(xhci_controller.clone());
+        self.xhci_controller = Some(xhci_controller);
+        Ok(())
+    }
+
+    pub(crate) fn attach_nvme_device(
+        &mut self,
+        vm: &Arc<Vm>,
+        config: &NvmeDeviceConfig,
+        event_manager: &mut EventManager,
+    ) -> Result<(), PciManagerError> {
+        // We should only be reaching this point if PCI is enabled
+        let pci_segment = self.pci_segment.as_ref().unwrap();
+        let pci_device_bdf = pci_segment.next_device_bdf()?;
+        debug!(
+            "Allocating BDF: {pci_device_bdf:?} for NVMe controller {}",
+            config.id
+        );
+
+        let nvme_controller =
+            Arc::new(Mutex::new(NvmeController::new(vm, pci_device_bdf, config)?));
+
+        pci_segment
+            .pci_bus
+            .lock()
+            .expect("Poisoned lock")
+            .add_device(pci_device_bdf.device() as u32, nvme_controller.clone());
+
+        let bar_addr = nvme_controller.lock().expect("Poisoned lock").bar_address();
+        debug!("Inserting NVMe MMIO BAR region: {bar_addr:#x}:{NVME_BAR_SIZE:#x}");
+        vm.common
+            .mmio_bus
+            .insert(nvme_controller.clone(), bar_addr, NVME_BAR_SIZE)?;
+
+        event_manager.add_subscriber(nvme_controller.clone());
+        self.nvme_controllers
+            .insert(config.id.clone(), nvme_controller);
+        Ok(())
+    }
+
     /// Gets the specified device.
     pub fn get_virtio_device(
         &self,
@@ -660,7 +821,7 @@ mod tests {
     use crate::mmds::data_store::MmdsVersion;
     use crate::resources::VmmConfig;
     use crate::snapshot::Snapshot;
-    use crate::vmm_config::balloon::BalloonDeviceConfig;
+    use crate::vmm_config::balloon::{BalloonDeviceConfig, FreePageAdvice};
     use crate::vmm_config::entropy::EntropyDeviceConfig;
     use crate::vmm_config::memory_hotplug::MemoryHotplugConfig;
     use crate::vmm_config::net::NetworkInterfaceConfig;
@@ -689,6 +850,8 @@ mod tests {
                 stats_polling_interval_s: 1,
                 free_page_hinting: false,
                 free_page_reporting: false,
+                free_page_advice: FreePageAdvice::DontNeed,
+                ksm: false,
             };
             insert_balloon_device(&mut vmm, &mut cmdline, &mut event_manager, balloon_cfg);
             // Add a block device.
@@ -709,6 +872,7 @@ mod tests {
                 guest_mac: None,
                 rx_rate_limiter: None,
                 tx_rate_limiter: None,
+                vhost: false,
             };
             insert_net_device_with_mmds(
                 &mut vmm,
@@ -15,8 +15,17 @@ use super::mmio::*;
 #[cfg(target_arch = "aarch64")]
 use crate::arch::DeviceType;
 use crate::device_manager::acpi::ACPIDeviceError;
+use crate::devices::acpi::cppc::{Cppc, CppcState};
+use crate::devices::acpi::fw_cfg::{FwCfg, FwCfgState};
+use crate::devices::acpi::ghes::{Ghes, GhesState};
+use crate::devices::acpi::power_supply::{PowerSupply, PowerSupplyState};
+use crate::devices::acpi::processor_aggregator::{ProcessorAggregator, ProcessorAggregatorState};
+use crate::devices::acpi::pvpanic::{PvPanic, PvPanicState};
+use crate::devices::acpi::s2idle::{S2idle, S2idleState};
+use crate::devices::acpi::thermal_zone::{ThermalZone, ThermalZoneState};
 use crate::devices::acpi::vmclock::{VmClock, VmClockState};
 use crate::devices::acpi::vmgenid::{VMGenIDState, VmGenId};
+use crate::devices::acpi::watchdog::{Watchdog, WatchdogState};
 #[cfg(target_arch = "aarch64")]
 use crate::devices::legacy::RTCDevice;
 use crate::devices::virtio::ActivateError;
@@ -168,6 +177,15 @@ impl fmt::Debug for MMIODevManagerConstructorArgs<'_> {
 pub struct ACPIDeviceManagerState {
     vmgenid: VMGenIDState,
     vmclock: VmClockState,
+    watchdog: Option<WatchdogState>,
+    ghes: Option<GhesState>,
+    power_supply: Option<PowerSupplyState>,
+    processor_aggregator: Option<ProcessorAggregatorState>,
+    s2idle: Option<S2idleState>,
+    pvpanic: Option<PvPanicState>,
+    cppc: Option<CppcState>,
+    thermal_zone: Option<ThermalZoneState>,
+    fw_cfg: Option<FwCfgState>,
 }
 
 impl<'a> Persist<'a> for ACPIDeviceManager {
@@ -179,15 +197,51 @@ impl<'a> Persist<'a> for ACPIDeviceManager {
         ACPIDeviceManagerState {
             vmgenid: self.vmgenid.save(),
             vmclock: self.vmclock.save(),
+            watchdog: self
+                .watchdog
+                .as_ref()
+                .map(|watchdog| watchdog.lock().expect("Poisoned lock").save()),
+            ghes: self.ghes.as_ref().map(Ghes::save),
+            power_supply: self.power_supply.as_ref().map(PowerSupply::save),
+            processor_aggregator: self
+                .processor_aggregator
+                .as_ref()
+                .map(ProcessorAggregator::save),
+            s2idle: self
+                .s2idle
+                .as_ref()
+                .map(|s2idle| s2idle.lock().expect("Poisoned lock").save()),
+            pvpanic: self
+                .pvpanic
+                .as_ref()
+                .map(|pvpanic| pvpanic.lock().expect("Poisoned lock").save()),
+            cppc: self
+                .cppc
+                .as_ref()
+                .map(|cppc| cppc.lock().expect("Poisoned lock").save()),
+            thermal_zone: self.thermal_zone.as_ref().map(ThermalZone::save),
+            fw_cfg: self
+                .fw_cfg
+                .as_ref()
+                .map(|fw_cfg| fw_cfg.lock().expect("Poisoned lock").save()),
         }
     }
 
     fn restore(vm: Self::ConstructorArgs, state: &Self::State) -> Result<Self, Self::Error> {
-        let acpi_devices = ACPIDeviceManager {
+        let mut acpi_devices = ACPIDeviceManager {
             // Safe to unwrap() here, this will never return an error.
             vmgenid: VmGenId::restore((), &state.vmgenid).unwrap(),
             // Safe to unwrap() here, this will never return an error.
             vmclock: VmClock::restore((), &state.vmclock).unwrap(),
+            watchdog: None,
+            ghes: None,
+            power_supply: None,
+            processor_aggregator: None,
+            s2idle: None,
+            pvpanic: None,
+            cppc: None,
+            thermal_zone: None,
+            fw_cfg: None,
         };
 
         vm.register_irq(
@@ -196,6 +250,58 @@ impl<'a> Persist<'a> for ACPIDeviceManager {
         )?;
 
         acpi_devices.attach_vmgenid(vm)?;
+
+        if let Some(watchdog_state) = &state.watchdog {
+            // Safe to unwrap() here, this will never return an error.
+            let watchdog = Watchdog::restore((), watchdog_state).unwrap();
+            acpi_devices.insert_watchdog(vm, Arc::new(Mutex::new(watchdog)))?;
+        }
+        if let Some(ghes_state) = &state.ghes {
+            // Safe to unwrap() here, this will never return an error. The error source memory
+            // is part of the guest memory, so it doesn't need to be activated again.
+            let ghes = Ghes::restore((), ghes_state).unwrap();
+            acpi_devices.insert_ghes(vm, ghes)?;
+        }
+        if let Some(power_supply_state) = &state.power_supply {
+            // Safe to unwrap() here, this will never return an error. The registers are part of
+            // the guest memory, so they don't need to be written again.
+            let power_supply = PowerSupply::restore((), power_supply_state).unwrap();
+            acpi_devices.insert_power_supply(vm, power_supply)?;
+        }
+        if let Some(aggregator_state) = &state.processor_aggregator {
+            // Safe to unwrap() here, this will never return an error. The registers are part of
+            // the guest memory, so they don't need to be written again.
+            let aggregator = ProcessorAggregator::restore((), aggregator_state).unwrap();
+            acpi_devices.insert_processor_aggregator(vm, aggregator)?;
+        }
+        if let Some(s2idle_state) = &state.s2idle {
+            // Safe to unwrap() here, this will never return an error.
+            let s2idle = S2idle::restore((), s2idle_state).unwrap();
+            acpi_devices.insert_s2idle(vm, Arc::new(Mutex::new(s2idle)))?;
+        }
+        if let Some(pvpanic_state) = &state.pvpanic {
+            // Safe to unwrap() here, this will never return an error.
+            let pvpanic = PvPanic::restore((), pvpanic_state).unwrap();
+            acpi_devices.insert_pvpanic(vm, Arc::new(Mutex::new(pvpanic)))?;
+        }
+        if let Some(cppc_state) = &state.cppc {
+            // Safe to unwrap() here, this will never return an error. The registers are part of
+            // the guest memory, so they don't need to be written again.
+            let cppc = Cppc::restore(vm.guest_memory().clone(), cppc_state).unwrap();
+            acpi_devices.insert_cppc(vm, Arc::new(Mutex::new(cppc)))?;
+        }
+        if let Some(thermal_zone_state) = &state.thermal_zone {
+            // Activating the device again refreshes the temperature from the host sensor and
+            // restarts its polling.
+            let mut thermal_zone = ThermalZone::restore((), thermal_zone_state)?;
+            thermal_zone.activate(vm.guest_memory())?;
+            acpi_devices.insert_thermal_zone(vm, thermal_zone)?;
+        }
+        if let Some(fw_cfg_state) = &state.fw_cfg {
+            // Safe to unwrap() here, this will never return an error.
+            let fw_cfg = FwCfg::restore(vm.guest_memory().clone(), fw_cfg_state).unwrap();
+            acpi_devices.insert_fw_cfg(vm, Arc::new(Mutex::new(fw_cfg)))?;
+        }
         Ok(acpi_devices)
     }
 }
@@ -374,7 +480,7 @@ impl<'a> Persist<'a> for MMIODeviceManager {
                 if state.type_ == DeviceType::Serial {
                     let serial = crate::DeviceManager::setup_serial_device(
                         constructor_args.event_manager,
-                        constructor_args.vm_resources.serial_out_path.as_ref(),
+                        &constructor_args.vm_resources.serial,
                     )?;
 
                     dev_manager.register_mmio_serial(vm, serial, Some(state.device_info))?;
@@ -632,7 +738,7 @@ mod tests {
     use crate::devices::virtio::block::CacheType;
     use crate::resources::VmmConfig;
     use crate::snapshot::Snapshot;
-    use crate::vmm_config::balloon::BalloonDeviceConfig;
+    use crate::vmm_config::balloon::{BalloonDeviceConfig, FreePageAdvice};
     use crate::vmm_config::entropy::EntropyDeviceConfig;
     use crate::vmm_config::memory_hotplug::MemoryHotplugConfig;
     use crate::vmm_config::net::NetworkInterfaceConfig;
@@ -701,6 +807,8 @@ mod tests {
                 stats_polling_interval_s: 1,
                 free_page_hinting: false,
                 free_page_reporting: false,
+                free_page_advice: FreePageAdvice::DontNeed,
+                ksm: false,
             };
             insert_balloon_device(&mut vmm, &mut cmdline, &mut event_manager, balloon_cfg);
             // Add a block device.
@@ -721,6 +829,7 @@ mod tests {
                 guest_mac: None,
                 rx_rate_limiter: None,
                 tx_rate_limiter: None,
+                vhost: false,
             };
             insert_net_device_with_mmds(
                 &mut vmm,
@@ -0,0 +1,581 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::{Arc, Barrier};
+use std::time::{Duration, Instant};
+
+use acpi_tables::aml::REGISTER_SPACE_PCC;
+use acpi_tables::pcct::pcc_signature;
+use acpi_tables::{AccessSize, AddressSpace, Aml, GenericAddressStructure, GenericSubspace, aml};
+use serde::{Deserialize, Serialize};
+use vm_memory::GuestMemoryError;
+use vmm_sys_util::syscall::SyscallReturnCode;
+
+use crate::logger::{IncMetric, METRICS, error, warn};
+use crate::snapshot::Persist;
+use crate::vstate::bus::BusDevice;
+use crate::vstate::memory::{Address, Bytes, GuestAddress, GuestMemoryMmap};
+use crate::vstate::resources::ResourceAllocator;
+
+/// Size of the MMIO region holding the doorbell of the PCC subspace.
+pub const CPPC_MMIO_SIZE: u64 = 0x1000;
+
+// Identifier of the PCC subspace backing the CPPC registers, which is the only one in the PCCT.
+const CPPC_SUBSPACE_ID: u8 = 0;
+// Header of the PCC shared memory region: a 32-bit signature, then the 16-bit command and status.
+const PCC_SIGNATURE: u64 = 0x0;
+const PCC_COMMAND: u64 = 0x4;
+const PCC_STATUS: u64 = 0x6;
+const PCC_HEADER_SIZE: u64 = 0x8;
+// Commands of the guest, as defined by the CPPC specification.
+const PCC_CMD_READ: u16 = 0;
+const PCC_CMD_WRITE: u16 = 1;
+// Bits of the status.
+const PCC_STATUS_COMPLETE: u16 = 1 << 0;
+const PCC_STATUS_ERROR: u16 = 1 << 2;
+// Latency of the commands, which are serviced as soon as the doorbell is rung, in microseconds.
+const PCC_NOMINAL_LATENCY_US: u32 = 100;
+
+// Registers of every vCPU in the communication space, after the header. The performance
+// requests are 32 bits wide and the counters 64 bits wide.
+const VCPU_REGS_SIZE: u64 = 0x20;
+const REG_DESIRED_PERF: u64 = 0x0;
+const REG_MIN_PERF: u64 = 0x4;
+const REG_MAX_PERF: u64 = 0x8;
+const REG_REFERENCE_COUNTER: u64 = 0x10;
+const REG_DELIVERED_COUNTER: u64 = 0x18;
+
+/// Highest performance level of the vCPUs. The performance scale is the one of the utilization
+/// clamps of the host scheduler, so that the levels the guest requests apply as they are.
+pub const CPPC_HIGHEST_PERF: u32 = 1024;
+/// Lowest performance level of the vCPUs.
+pub const CPPC_LOWEST_PERF: u32 = 128;
+
+// Flags of sched_setattr, keeping the policy and its parameters while updating the utilization
+// clamps of the thread.
+const SCHED_FLAG_KEEP_ALL: u64 = 0x18;
+const SCHED_FLAG_UTIL_CLAMP: u64 = 0x60;
+
+// The sched_attr structure of sched_setattr, which the libc crate doesn't define.
+#[repr(C)]
+#[derive(Debug, Default)]
+struct SchedAttr {
+    size: u32,
+    sched_policy: u32,
+    sched_flags: u64,
+    sched_nice: i32,
+    sched_priority: u32,
+    sched_runtime: u64,
+    sched_deadline: u64,
+    sched_period: u64,
+    sched_util_min: u32,
+    sched_util_max: u32,
+}
+
+/// Clamps the utilization the host scheduler assumes for the thread `tid` between `min` and
+/// `max`, which drives the frequency the host runs it at.
+fn set_utilization_clamps(tid: libc::pid_t, min: u32, max: u32) -> Result<(), std::io::Error> {
+    let attr = SchedAttr {
+        size: u32::try_from(std::mem::size_of::<SchedAttr>()).unwrap(),
+        sched_flags: SCHED_FLAG_KEEP_ALL | SCHED_FLAG_UTIL_CLAMP,
+        sched_util_min: min,
+        sched_util_max: max,
+        ..Default::default()
+    };
+    // SAFETY: Safe because `attr` is a valid sched_attr structure of the size it declares.
+    // https://man7.org/linux/man-pages/man2/sched_setattr.2.html
+    SyscallReturnCode(unsafe { libc::syscall(libc::SYS_sched_setattr, tid, &attr, 0) })
+        .into_empty_result()
+}
+
+/// Returns the CPU time the thread `tid` of this process consumed.
+fn thread_cpu_time(tid: libc::pid_t) -> Option<Duration> {
+    // The CPU-time clock of a thread, as built by the C library for pthread_getcpuclockid.
+    let clock_id = (!tid << 3) | 6;
+    let mut time = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    // SAFETY: Safe because `time` is a valid timespec structure.
+    if unsafe { libc::clock_gettime(clock_id, &mut time) } != 0 {
+        return None;
+    }
+    Some(Duration::new(
+        u64::try_from(time.tv_sec).ok()?,
+        u32::try_from(time.tv_nsec).ok()?,
+    ))
+}
+
+/// Returns the thread identifier of the calling thread.
+fn gettid() -> libc::pid_t {
+    // SAFETY: gettid takes no argument and always succeeds.
+    let tid = unsafe { libc::syscall(libc::SYS_gettid) };
+    libc::pid_t::try_from(tid).unwrap()
+}
+
+#[derive(Debug, Default)]
+struct CppcVcpu {
+    /// Thread running the vCPU, once it started.
+    tid: Option<libc::pid_t>,
+    /// Utilization clamps last applied to the thread.
+    clamps: Option<(u32, u32)>,
+    /// Wall-clock and CPU time of the thread when the counters were last updated.
+    sample: Option<(Instant, Duration)>,
+}
+
+/// CPPC device
+///
+/// This device services the Platform Communications Channel subspace backing the registers of
+/// the `_CPC` objects of the vCPUs. When the guest writes its performance requests, they are
+/// applied as utilization clamps of the vCPU threads, so that the host runs them at the
+/// frequency the guest asked for. When the guest reads the feedback counters, the reference
+/// counter reports the elapsed time and the delivered counter the CPU time of the vCPU thread,
+/// both in nanoseconds, so the delivered performance accounts for the time the host didn't run
+/// the vCPU.
+#[derive(Debug)]
+pub struct Cppc {
+    /// Guest address of the doorbell.
+    pub mmio_addr: u64,
+    /// Guest address of the PCC shared memory region.
+    pub shmem_addr: GuestAddress,
+    mem: GuestMemoryMmap,
+    vcpus: Vec<CppcVcpu>,
+}
+
+impl Cppc {
+    /// Create the device from its addresses.
+    pub fn from_parts(
+        mmio_addr: u64,
+        shmem_addr: GuestAddress,
+        mem: GuestMemoryMmap,
+        nr_vcpus: u8,
+    ) -> Self {
+        Cppc {
+            mmio_addr,
+            shmem_addr,
+            mem,
+            vcpus: (0..nr_vcpus).map(|_| CppcVcpu::default()).collect(),
+        }
+    }
+
+    /// Create the device
+    ///
+    /// Allocate the PCC shared memory region holding the registers of `nr_vcpus` vCPUs as ACPI
+    /// NVS, and the MMIO region of the doorbell.
+    pub fn new(
+        resource_allocator: &mut ResourceAllocator,
+        mem: GuestMemoryMmap,
+        nr_vcpus: u8,
+    ) -> Result<Self, vm_allocator::Error> {
+        let shmem_addr = resource_allocator.allocate_acpi_nvs_memory(
+            Self::shmem_size(nr_vcpus),
+            8,
+            vm_allocator::AllocPolicy::LastMatch,
+        )?;
+        let mmio_addr = resource_allocator.allocate_32bit_mmio_memory(
+            CPPC_MMIO_SIZE,
+            CPPC_MMIO_SIZE,
+            vm_allocator::AllocPolicy::FirstMatch,
+        )?;
+        Ok(Self::from_parts(
+            mmio_addr,
+            GuestAddress(shmem_addr),
+            mem,
+            nr_vcpus,
+        ))
+    }
+
+    fn shmem_size(nr_vcpus: u8) -> u64 {
+        PCC_HEADER_SIZE + u64::from(nr_vcpus) * VCPU_REGS_SIZE
+    }
+
+    // Guest address of the register at `offset` of the registers of `vcpu`.
+    fn reg_addr(&self, vcpu: usize, offset: u64) -> GuestAddress {
+        self.shmem_addr
+            .unchecked_add(PCC_HEADER_SIZE + u64::try_from(vcpu).unwrap() * VCPU_REGS_SIZE)
+            .unchecked_add(offset)
+    }
+
+    /// Write the header of the PCC shared memory region, ready for the first command.
+    pub fn activate(&self) -> Result<(), GuestMemoryError> {
+        self.mem.write_obj(
+            pcc_signature(CPPC_SUBSPACE_ID),
+            self.shmem_addr.unchecked_add(PCC_SIGNATURE),
+        )?;
+        self.mem.write_obj(
+            PCC_STATUS_COMPLETE,
+            self.shmem_addr.unchecked_add(PCC_STATUS),
+        )
+    }
+
+    /// Returns the PCC subspace to describe in the PCCT.
+    pub fn pcc_subspace(&self) -> GenericSubspace {
+        let doorbell = GenericAddressStructure::new(
+            AddressSpace::SystemMemory,
+            32,
+            0,
+            AccessSize::DWord,
+            self.mmio_addr,
+        )
+        .unwrap();
+        GenericSubspace::new(
+            self.shmem_addr.0,
+            Self::shmem_size(u8::try_from(self.vcpus.len()).unwrap()),
+            doorbell,
+            0,
+            1,
+            PCC_NOMINAL_LATENCY_US,
+        )
+    }
+
+    /// Records that the calling thread runs the vCPU `index`, and applies the performance
+    /// requests the guest made for it, if any, such as before a snapshot.
+    pub fn register_vcpu_thread(&mut self, index: u8) {
+        let index = usize::from(index);
+        let Some(vcpu) = self.vcpus.get_mut(index) else {
+            return;
+        };
+        vcpu.tid = Some(gettid());
+        vcpu.clamps = None;
+        vcpu.sample = None;
+        if let Err(err) = self.apply_requests(index) {
+            error!("cppc: Could not read the performance requests of vCPU {index}: {err}");
+        }
+    }
+
+    // Applies the performance requests of the vCPU `index` to its thread.
+    fn apply_requests(&mut self, index: usize) -> Result<(), GuestMemoryError> {
+        let desired: u32 = self.mem.read_obj(self.reg_addr(index, REG_DESIRED_PERF))?;
+        let min: u32 = self.mem.read_obj(self.reg_addr(index, REG_MIN_PERF))?;
+        let max: u32 = self.mem.read_obj(self.reg_addr(index, REG_MAX_PERF))?;
+        let vcpu = &mut self.vcpus[index];
+        // Nothing to apply until the vCPU thread started and the guest requested a performance
+        // level.
+        let Some(tid) = vcpu.tid else {
+            return Ok(());
+        };
+        if desired == 0 {
+            return Ok(());
+        }
+
+        // Unset limits are the ones of the performance scale.
+        let max = if max == 0 { CPPC_HIGHEST_PERF } else { max };
+        let max = max.clamp(CPPC_LOWEST_PERF, CPPC_HIGHEST_PERF);
+        let min = desired.max(min).clamp(CPPC_LOWEST_PERF, max);
+        if vcpu.clamps == Some((min, max)) {
+            return Ok(());
+        }
+        match set_utilization_clamps(tid, min, max) {
+            Ok(()) => vcpu.clamps = Some((min, max)),
+            Err(err) => {
+                METRICS.cppc.hint_fails.inc();
+                warn!("cppc: Could not apply the performance requests of vCPU {index}: {err}");
+            }
+        }
+        Ok(())
+    }
+
+    // Updates the feedback counters of the vCPUs.
+    fn update_counters(&mut self) -> Result<(), GuestMemoryError> {
+        for index in 0..self.vcpus.len() {
+            let Some(tid) = self.vcpus[index].tid else {
+                continue;
+            };
+            let Some(cpu_time) = thread_cpu_time(tid) else {
+                continue;
+            };
+            let now = Instant::now();
+            if let Some((since, last_cpu_time)) = self.vcpus[index].sample {
+                let elapsed = now.duration_since(since);
+                let delivered = cpu_time.saturating_sub(last_cpu_time);
+                for (offset, delta) in [
+                    (REG_REFERENCE_COUNTER, elapsed),
+                    (REG_DELIVERED_COUNTER, delivered),
+                ] {
+                    let addr = self.reg_addr(index, offset);
+                    let counter: u64 = self.mem.read_obj(addr)?;
+                    let delta = u64::try_from(delta.as_nanos()).unwrap_or(u64::MAX);
+                    self.mem.write_obj(counter.wrapping_add(delta), addr)?;
+                }
+            }
+            self.vcpus[index].sample = Some((now, cpu_time));
+        }
+        Ok(())
+    }
+
+    // Services the command the guest wrote in the shared memory region.
+    fn service_command(&mut self) -> Result<(), GuestMemoryError> {
+        let command: u16 = self
+            .mem
+            .read_obj(self.shmem_addr.unchecked_add(PCC_COMMAND))?;
+        let status = match command {
+            PCC_CMD_READ => {
+                self.update_counters()?;
+                PCC_STATUS_COMPLETE
+            }
+            PCC_CMD_WRITE => {
+                METRICS.cppc.perf_requests.inc();
+                for index in 0..self.vcpus.len() {
+                    self.apply_requests(index)?;
+                }
+                PCC_STATUS_COMPLETE
+            }
+            _ => PCC_STATUS_COMPLETE | PCC_STATUS_ERROR,
+        };
+        self.mem
+            .write_obj(status, self.shmem_addr.unchecked_add(PCC_STATUS))
+    }
+}
+
+impl BusDevice for Cppc {
+    fn read(&mut self, _base: u64, _offset: u64, data: &mut [u8]) {
+        data.fill(0);
+    }
+
+    fn write(&mut self, _base: u64, offset: u64, data: &[u8]) -> Option<Arc<Barrier>> {
+        // Any write of the doorbell rings it, the guest preserves no bit of it.
+        if offset == 0
+            && data.len() == 4
+            && let Err(err) = self.service_command()
+        {
+            error!("cppc: Could not service the command of the guest: {err}");
+        }
+        None
+    }
+}
+
+impl Aml for Cppc {
+    fn append_aml_bytes(&self, v: &mut Vec<u8>) -> Result<(), aml::AmlError> {
+        // The platform-wide _OSC only grants the guest the use of CPPC, in its first and second
+        // revisions.
+        let osc_uuid = aml::Buffer::new(OSC_PLATFORM_UUID.to_vec());
+        let capabilities = aml::Arg(3);
+        let cdw1 = aml::Path::new("CDW1")?;
+        let cdw2 = aml::Path::new("CDW2")?;
+        let cdw1_field =
+            aml::CreateField::<u32>::new(&capabilities, &aml::ZERO, "CDW1".try_into()?);
+        let cdw2_field = aml::CreateField::<u32>::new(&capabilities, &4u8, "CDW2".try_into()?);
+        let uuid_equal = aml::Equal::new(&aml::Arg(0), &osc_uuid);
+        let supported = aml::And::new(&cdw2, &cdw2, &OSC_SB_CPC_SUPPORT);
+        let unrecognized = aml::Or::new(&cdw1, &cdw1, &OSC_UNRECOGNIZED_UUID);
+        let ret = aml::Return::new(&capabilities);
+        aml::Method::new(
+            "_SB_._OSC".try_into()?,
+            4,
+            false,
+            vec![
+                &cdw1_field,
+                &cdw2_field,
+                &aml::If::new(&uuid_equal, vec![&supported, &ret]),
+                &unrecognized,
+                &ret,
+            ],
+        )
+        .append_aml_bytes(v)?;
+
+        // Registers the guest doesn't have.
+        let null = aml::Register::new(0, 0, 0, 0, 0);
+        let null = aml::ResourceTemplate::new(vec![&null]);
+        for index in 0..self.vcpus.len() {
+            let base = u64::try_from(index).unwrap() * VCPU_REGS_SIZE;
+            let pcc_register = |offset, width| {
+                aml::Register::new(
+                    REGISTER_SPACE_PCC,
+                    width,
+                    0,
+                    CPPC_SUBSPACE_ID,
+                    base + offset,
+                )
+            };
+            let desired = pcc_register(REG_DESIRED_PERF, 32);
+            let min = pcc_register(REG_MIN_PERF, 32);
+            let max = pcc_register(REG_MAX_PERF, 32);
+            let reference = pcc_register(REG_REFERENCE_COUNTER, 64);
+            let delivered = pcc_register(REG_DELIVERED_COUNTER, 64);
+            let desired = aml::ResourceTemplate::new(vec![&desired]);
+            let min = aml::ResourceTemplate::new(vec![&min]);
+            let max = aml::ResourceTemplate::new(vec![&max]);
+            let reference = aml::ResourceTemplate::new(vec![&reference]);
+            let delivered = aml::ResourceTemplate::new(vec![&delivered]);
+
+            // A revision 3 _CPC package, whose frequencies are unknown.
+            let cpc = aml::Package::new(vec![
+                &23u8,
+                &3u8,
+                // Highest, nominal, lowest nonlinear and lowest performance.
+                &CPPC_HIGHEST_PERF,
+                &CPPC_HIGHEST_PERF,
+                &CPPC_LOWEST_PERF,
+                &CPPC_LOWEST_PERF,
+                // Guaranteed performance.
+                &null,
+                &desired,
+                &min,
+                &max,
+                // Performance reduction tolerance and time window.
+                &null,
+                &null,
+                // Counter wraparound time, the counters never wrap around.
+                &aml::ZERO,
+                &reference,
+                &delivered,
+                // Performance limited and CPPC enable.
+                &null,
+                &null,
+                // Autonomous selection enable, activity window and energy performance
+                // preference.
+                &aml::ZERO,
+                &null,
+                &null,
+                // Reference performance, lowest and nominal frequencies.
+                &CPPC_HIGHEST_PERF,
+                &aml::ZERO,
+                &aml::ZERO,
+            ]);
+            aml::Device::new(
+                format!("_SB_.C{index:03X}").as_str().try_into()?,
+                vec![
+                    &aml::Name::new("_HID".try_into()?, &"ACPI0007")?,
+                    &aml::Name::new("_UID".try_into()?, &index)?,
+                    &aml::Name::new("_CPC".try_into()?, &cpc)?,
+                ],
+            )
+            .append_aml_bytes(v)?;
+        }
+        Ok(())
+    }
+}
+
+// UUID of the platform-wide _OSC, 0811b06e-4a27-44f9-8d60-3cbbc22e7b48.
+const OSC_PLATFORM_UUID: [u8; 16] = [
+    0x6e, 0xb0, 0x11, 0x08, 0x27, 0x4a, 0xf9, 0x44, 0x8d, 0x60, 0x3c, 0xbb, 0xc2, 0x2e, 0x7b, 0x48,
+];
+// Support of CPPC and of its second revision, in the second DWord of the capabilities.
+const OSC_SB_CPC_SUPPORT: u8 = (1 << 5) | (1 << 6);
+// Error reported in the first DWord of the capabilities.
+const OSC_UNRECOGNIZED_UUID: u8 = 1 << 2;
+
+/// Logic to save/restore the state of a [`Cppc`] device.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct CppcState {
+    mmio_addr: u64,
+    shmem_addr: u64,
+    nr_vcpus: u8,
+}
+
+impl<'a> Persist<'a> for Cppc {
+    type State = CppcState;
+    type ConstructorArgs = GuestMemoryMmap;
+    type Error = std::convert::Infallible;
+
+    fn save(&self) -> Self::State {
+        CppcState {
+            mmio_addr: self.mmio_addr,
+            shmem_addr: self.shmem_addr.0,
+            nr_vcpus: u8::try_from(self.vcpus.len()).unwrap(),
+        }
+    }
+
+    fn restore(mem: Self::ConstructorArgs, state: &Self::State) -> Result<Self, Self::Error> {
+        // The registers are part of the guest memory, and the requests are applied again once
+        // the vCPU threads start.
+        Ok(Cppc::from_parts(
+            state.mmio_addr,
+            GuestAddress(state.shmem_addr),
+            mem,
+            state.nr_vcpus,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::single_region_mem;
+
+    fn ring_doorbell(cppc: &mut Cppc, command: u16) -> u16 {
+        cppc.mem
+            .write_obj(command, cppc.shmem_addr.unchecked_add(PCC_COMMAND))
+            .unwrap();
+        cppc.mem
+            .write_obj(0u16, cppc.shmem_addr.unchecked_add(PCC_STATUS))
+            .unwrap();
+        cppc.write(0, 0, &1u32.to_le_bytes());
+        cppc.mem
+            .read_obj(cppc.shmem_addr.unchecked_add(PCC_STATUS))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_cppc_commands() {
+        let mem = single_region_mem(0x10_0000);
+        let mut cppc = Cppc::from_parts(0xd000_0000, GuestAddress(0x1000), mem, 2);
+        cppc.activate().unwrap();
+        assert_eq!(
+            cppc.mem.read_obj::<u32>(GuestAddress(0x1000)).unwrap(),
+            0x5043_4300
+        );
+        assert_eq!(
+            cppc.mem.read_obj::<u16>(GuestAddress(0x1006)).unwrap(),
+            PCC_STATUS_COMPLETE
+        );
+
+        // Without a vCPU thread, the commands complete without doing anything.
+        assert_eq!(ring_doorbell(&mut cppc, PCC_CMD_READ), PCC_STATUS_COMPLETE);
+        assert_eq!(ring_doorbell(&mut cppc, PCC_CMD_WRITE), PCC_STATUS_COMPLETE);
+        assert_eq!(
+            ring_doorbell(&mut cppc, 0x10),
+            PCC_STATUS_COMPLETE | PCC_STATUS_ERROR
+        );
+
+        // The counters of the vCPU running on this thread grow with the time.
+        cppc.register_vcpu_thread(1);
+        assert_eq!(ring_doorbell(&mut cppc, PCC_CMD_READ), PCC_STATUS_COMPLETE);
+        let start = Instant::now();
+        while start.elapsed() < Duration::from_millis(10) {}
+        assert_eq!(ring_doorbell(&mut cppc, PCC_CMD_READ), PCC_STATUS_COMPLETE);
+        let reference: u64 = cppc
+            .mem
+            .read_obj(cppc.reg_addr(1, REG_REFERENCE_COUNTER))
+            .unwrap();
+        let delivered: u64 = cppc
+            .mem
+            .read_obj(cppc.reg_addr(1, REG_DELIVERED_COUNTER))
+            .unwrap();
+        assert!(reference >= 10_000_000);
+        assert!(delivered > 0);
+        assert_eq!(
+            cppc.mem
+                .read_obj::<u64>(cppc.reg_addr(0, REG_REFERENCE_COUNTER))
+                .unwrap(),
+            0
+        );
+
+        let state = cppc.save();
+        let restored = Cppc::restore(cppc.mem.clone(), &state).unwrap();
+        assert_eq!(restored.mmio_addr, 0xd000_0000);
+        assert_eq!(restored.shmem_addr, GuestAddress(0x1000));
+        assert_eq!(restored.vcpus.len(), 2);
+        assert!(restored.vcpus[1].tid.is_none());
+    }
+
+    #[test]
+    fn test_cppc_aml() {
+        let cppc = Cppc::from_parts(
+            0xd000_0000,
+            GuestAddress(0x1000),
+            single_region_mem(0x10_0000),
+            2,
+        );
+        let mut aml = Vec::new();
+        cppc.append_aml_bytes(&mut aml).unwrap();
+        for name in ["_OSC", "C000", "C001", "_CPC"] {
+            assert!(aml.windows(4).any(|window| window == name.as_bytes()));
+        }
+        assert!(aml.windows(16).any(|uuid| uuid == OSC_PLATFORM_UUID));
+
+        let subspace = cppc.pcc_subspace().into();
+        let pcct = acpi_tables::Pcct::new(*b"FOOBAR", *b"FOOBARPC", 0, vec![subspace]);
+        assert_eq!(acpi_tables::Sdt::len(&pcct), 48 + 62);
+    }
+}
@@ -0,0 +1,523 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A device compatible with the QEMU firmware configuration interface (fw_cfg), through which
+//! guest firmware and early boot code read blobs from the VMM. The interface is described in
+//! <https://www.qemu.org/docs/master/specs/fw_cfg.html>.
+
+use std::sync::{Arc, Barrier};
+
+#[cfg(target_arch = "x86_64")]
+use acpi_tables::{Aml, aml};
+use serde::{Deserialize, Serialize};
+use vm_memory::GuestMemoryError;
+
+use crate::logger::{debug, error};
+use crate::snapshot::Persist;
+use crate::vstate::bus::BusDevice;
+use crate::vstate::memory::{Address, Bytes, GuestAddress, GuestMemoryMmap};
+#[cfg(target_arch = "aarch64")]
+use crate::vstate::resources::ResourceAllocator;
+
+/// I/O port of the registers of the device, where guest firmware looks for it.
+#[cfg(target_arch = "x86_64")]
+pub const FW_CFG_PORT: u64 = 0x510;
+/// Size of the registers of the device.
+#[cfg(target_arch = "x86_64")]
+pub const FW_CFG_REGS_SIZE: u64 = 0xc;
+/// Size of the registers of the device.
+#[cfg(target_arch = "aarch64")]
+pub const FW_CFG_REGS_SIZE: u64 = 0x18;
+
+// Offsets of the registers. The I/O port interface has an 8-bit data register and a
+// little-endian selector, the MMIO interface a data register up to 64 bits wide and a big-endian
+// selector. The DMA address register is big-endian in both.
+#[cfg(target_arch = "x86_64")]
+const REG_SELECTOR: u64 = 0x0;
+#[cfg(target_arch = "x86_64")]
+const REG_DATA: u64 = 0x1;
+#[cfg(target_arch = "x86_64")]
+const REG_DMA: u64 = 0x4;
+#[cfg(target_arch = "aarch64")]
+const REG_DATA: u64 = 0x0;
+#[cfg(target_arch = "aarch64")]
+const REG_SELECTOR: u64 = 0x8;
+#[cfg(target_arch = "aarch64")]
+const REG_DMA: u64 = 0x10;
+
+// Value read from the DMA address register, which tells the guest the DMA interface exists.
+const DMA_SIGNATURE: [u8; 8] = *b"QEMU CFG";
+
+// Selectors of the items.
+const FW_CFG_SIGNATURE: u16 = 0x00;
+const FW_CFG_ID: u16 = 0x01;
+const FW_CFG_FILE_DIR: u16 = 0x19;
+const FW_CFG_FILE_FIRST: u16 = 0x20;
+// Selectors at or above this value have flags the guest doesn't set for files.
+const FW_CFG_FILE_END: u16 = 0x4000;
+
+/// Maximum number of files of the device.
+pub const FW_CFG_MAX_FILES: usize = (FW_CFG_FILE_END - FW_CFG_FILE_FIRST) as usize;
+/// Maximum length of the name of a file, without its terminating null byte.
+pub const FW_CFG_MAX_NAME_LEN: usize = 55;
+
+// Contents of the signature and feature items: the traditional and the DMA interfaces.
+const SIGNATURE: [u8; 4] = *b"QEMU";
+const FEATURES: [u8; 4] = 0x3u32.to_le_bytes();
+
+// Bits of the control field of a DMA access.
+const DMA_CTL_ERROR: u32 = 1 << 0;
+const DMA_CTL_READ: u32 = 1 << 1;
+const DMA_CTL_SKIP: u32 = 1 << 2;
+const DMA_CTL_SELECT: u32 = 1 << 3;
+const DMA_CTL_WRITE: u32 = 1 << 4;
+// Size of a DMA access: the control and length, 32 bits each, and the address, 64 bits.
+const DMA_ACCESS_SIZE: usize = 16;
+// Zeroes padding the reads past the end of an item.
+const ZEROES: [u8; 0x1000] = [0; 0x1000];
+
+/// A named blob the guest reads through the device.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FwCfgFile {
+    /// Name of the file, such as `opt/org.example/config`.
+    pub name: String,
+    /// Contents of the file.
+    pub data: Vec<u8>,
+}
+
+/// fw_cfg device
+///
+/// The guest selects an item by writing its selector, then reads it either byte by byte from the
+/// data register, or through a DMA access whose address it writes to the DMA register. Besides the
+/// signature and the features of the device, the items are the configured files and the
+/// directory listing them. The files are read-only.
+#[derive(Debug)]
+pub struct FwCfg {
+    /// Address of the registers, an I/O port on x86_64.
+    pub addr: u64,
+    mem: GuestMemoryMmap,
+    files: Vec<FwCfgFile>,
+    file_dir: Vec<u8>,
+    selector: u16,
+    offset: u32,
+    dma_address: u64,
+}
+
+impl FwCfg {
+    /// Create the device from the address of its registers.
+    pub fn from_parts(addr: u64, mem: GuestMemoryMmap, files: Vec<FwCfgFile>) -> Self {
+        debug!(
+            "fw_cfg: building device. Address: {:#010x}. Files: {}",
+            addr,
+            files.len()
+        );
+        // The directory is the big-endian number of files, followed by the size, selector and
+        // null-terminated name of each file.
+        let mut file_dir = u32::try_from(files.len()).unwrap().to_be_bytes().to_vec();
+        for (selector, file) in (FW_CFG_FILE_FIRST..).zip(&files) {
+            let mut name = [0u8; FW_CFG_MAX_NAME_LEN + 1];
+            name[..file.name.len()].copy_from_slice(file.name.as_bytes());
+            file_dir.extend_from_slice(&u32::try_from(file.data.len()).unwrap().to_be_bytes());
+            file_dir.extend_from_slice(&selector.to_be_bytes());
+            file_dir.extend_from_slice(&[0; 2]);
+            file_dir.extend_from_slice(&name);
+        }
+        FwCfg {
+            addr,
+            mem,
+            files,
+            file_dir,
+            selector: FW_CFG_SIGNATURE,
+            offset: 0,
+            dma_address: 0,
+        }
+    }
+
+    /// Create the device, at the I/O port where guest firmware looks for it.
+    #[cfg(target_arch = "x86_64")]
+    pub fn new(mem: GuestMemoryMmap, files: Vec<FwCfgFile>) -> Self {
+        Self::from_parts(FW_CFG_PORT, mem, files)
+    }
+
+    /// Create the device
+    ///
+    /// Allocate the MMIO region of the registers.
+    #[cfg(target_arch = "aarch64")]
+    pub fn new(
+        resource_allocator: &mut ResourceAllocator,
+        mem: GuestMemoryMmap,
+        files: Vec<FwCfgFile>,
+    ) -> Result<Self, vm_allocator::Error> {
+        let addr = resource_allocator.allocate_32bit_mmio_memory(
+            FW_CFG_REGS_SIZE,
+            8,
+            vm_allocator::AllocPolicy::FirstMatch,
+        )?;
+        Ok(Self::from_parts(addr, mem, files))
+    }
+
+    // Contents of the selected item. Unknown items are empty.
+    fn item(&self) -> &[u8] {
+        match self.selector {
+            FW_CFG_SIGNATURE => &SIGNATURE,
+            FW_CFG_ID => &FEATURES,
+            FW_CFG_FILE_DIR => &self.file_dir,
+            selector if selector >= FW_CFG_FILE_FIRST => self
+                .files
+                .get(usize::from(selector - FW_CFG_FILE_FIRST))
+                .map(|file| file.data.as_slice())
+                .unwrap_or_default(),
+            _ => &[],
+        }
+    }
+
+    fn select(&mut self, selector: u16) {
+        self.selector = selector;
+        self.offset = 0;
+    }
+
+    // Returns the rest of the selected item, from the current offset.
+    fn remaining(&self) -> &[u8] {
+        let item = self.item();
+        let start = usize::try_from(self.offset).unwrap().min(item.len());
+        &item[start..]
+    }
+
+    // Reads the selected item from the current offset into `data`, padding it with zeroes past
+    // its end.
+    fn read_item(&mut self, data: &mut [u8]) {
+        let remaining = self.remaining();
+        let len = data.len().min(remaining.len());
+        data[..len].copy_from_slice(&remaining[..len]);
+        data[len..].fill(0);
+        self.skip(data.len());
+    }
+
+    // Reads `length` bytes of the selected item into the guest memory at `buffer`, padding them
+    // with zeroes past its end.
+    fn dma_read(&mut self, buffer: GuestAddress, length: usize) -> Result<(), GuestMemoryError> {
+        let remaining = self.remaining();
+        let len = length.min(remaining.len());
+        self.mem.write_slice(&remaining[..len], buffer)?;
+        // The length comes from the guest, so the padding is written without allocating it.
+        let mut addr = buffer;
+        let mut padding = length - len;
+        let mut chunk = len;
+        while padding > 0 {
+            addr = addr
+                .checked_add(u64::try_from(chunk).unwrap())
+                .ok_or(GuestMemoryError::InvalidGuestAddress(addr))?;
+            chunk = padding.min(ZEROES.len());
+            self.mem.write_slice(&ZEROES[..chunk], addr)?;
+            padding -= chunk;
+        }
+        self.skip(length);
+        Ok(())
+    }
+
+    fn skip(&mut self, len: usize) {
+        let len = u32::try_from(len).unwrap_or(u32::MAX);
+        self.offset = self.offset.saturating_add(len);
+    }
+
+    // Performs the DMA access at `address`, returning the control field to write back.
+    fn dma_transfer(&mut self, address: GuestAddress) -> Result<u32, GuestMemoryError> {
+        let mut access = [0u8; DMA_ACCESS_SIZE];
+        self.mem.read_slice(&mut access, address)?;
+        let control = u32::from_be_bytes(access[0..4].try_into().unwrap());
+        let length = u32::from_be_bytes(access[4..8].try_into().unwrap());
+        let buffer = GuestAddress(u64::from_be_bytes(access[8..16].try_into().unwrap()));
+
+        if control & DMA_CTL_SELECT != 0 {
+            self.select(u16::try_from(control >> 16).unwrap());
+        }
+        let length = usize::try_from(length).unwrap();
+        if control & DMA_CTL_WRITE != 0 {
+            // None of the items is writable.
+            return Ok(DMA_CTL_ERROR);
+        } else if control & DMA_CTL_READ != 0 {
+            self.dma_read(buffer, length)?;
+        } else if control & DMA_CTL_SKIP != 0 {
+            self.skip(length);
+        }
+        Ok(0)
+    }
+
+    // Runs the DMA access at `address` and reports its completion to the guest.
+    fn run_dma(&mut self, address: u64) {
+        let address = GuestAddress(address);
+        let control = self.dma_transfer(address).unwrap_or_else(|err| {
+            error!("fw_cfg: Could not perform the DMA access of the guest: {err}");
+            DMA_CTL_ERROR
+        });
+        if let Err(err) = self.mem.write_slice(&control.to_be_bytes(), address) {
+            error!("fw_cfg: Could not complete the DMA access of the guest: {err}");
+        }
+    }
+
+    fn write_selector(&mut self, data: &[u8]) {
+        let Ok(selector) = data.try_into() else {
+            return;
+        };
+        #[cfg(target_arch = "x86_64")]
+        self.select(u16::from_le_bytes(selector));
+        #[cfg(target_arch = "aarch64")]
+        self.select(u16::from_be_bytes(selector));
+    }
+
+    fn write_dma(&mut self, offset: u64, data: &[u8]) {
+        // The guest writes the high half of the address first, and the write of the low half
+        // starts the access.
+        match (offset, data.len()) {
+            (0, 8) => self.run_dma(u64::from_be_bytes(data.try_into().unwrap())),
+            (0, 4) => {
+                let high = u32::from_be_bytes(data.try_into().unwrap());
+                self.dma_address = u64::from(high) << 32;
+            }
+            (4, 4) => {
+                let low = u32::from_be_bytes(data.try_into().unwrap());
+                let address = self.dma_address | u64::from(low);
+                self.dma_address = 0;
+                self.run_dma(address);
+            }
+            _ => (),
+        }
+    }
+}
+
+impl BusDevice for FwCfg {
+    fn read(&mut self, _base: u64, offset: u64, data: &mut [u8]) {
+        match offset {
+            REG_DATA => self.read_item(data),
+            offset if (REG_DMA..REG_DMA + 8).contains(&offset) => {
+                let start = usize::try_from(offset - REG_DMA).unwrap();
+                let signature = DMA_SIGNATURE.get(start..start + data.len());
+                match signature {
+                    Some(signature) => data.copy_from_slice(signature),
+                    None => data.fill(0),
+                }
+            }
+            _ => data.fill(0),
+        }
+    }
+
+    fn write(&mut self, _base: u64, offset: u64, data: &[u8]) -> Option<Arc<Barrier>> {
+        match offset {
+            REG_SELECTOR => self.write_selector(data),
+            offset if (REG_DMA..REG_DMA + 8).contains(&offset) => {
+                self.write_dma(offset - REG_DMA, data)
+            }
+            // Writes to the data register are ignored.
+            _ => (),
+        }
+        None
+    }
+}
+
+/// Logic to save/restore the state of the fw_cfg device
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct FwCfgState {
+    /// Address of the registers.
+    pub addr: u64,
+    /// Files of the device.
+    pub files: Vec<FwCfgFile>,
+    /// Selected item.
+    pub selector: u16,
+    /// Offset of the next read in the selected item.
+    pub offset: u32,
+    /// High half of the address of the next DMA access.
+    pub dma_address: u64,
+}
+
+impl<'a> Persist<'a> for FwCfg {
+    type State = FwCfgState;
+    type ConstructorArgs = GuestMemoryMmap;
+    type Error = std::convert::Infallible;
+
+    fn save(&self) -> Self::State {
+        FwCfgState {
+            addr: self.addr,
+            files: self.files.clone(),
+            selector: self.selector,
+            offset: self.offset,
+            dma_address: self.dma_address,
+        }
+    }
+
+    fn restore(mem: Self::ConstructorArgs, state: &Self::State) -> Result<Self, Self::Error> {
+        // The files are saved with the device, so that the guest reads the same contents after
+        // the restore even if the files on the host changed.
+        let mut fw_cfg = FwCfg::from_parts(state.addr, mem, state.files.clone());
+        fw_cfg.selector = state.selector;
+        fw_cfg.offset = state.offset;
+        fw_cfg.dma_address = state.dma_address;
+        Ok(fw_cfg)
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+impl Aml for FwCfg {
+    fn append_aml_bytes(&self, v: &mut Vec<u8>) -> Result<(), aml::AmlError> {
+        let port = u16::try_from(self.addr).unwrap();
+        aml::Device::new(
+            "_SB_.FWCF".try_into()?,
+            vec![
+                &aml::Name::new("_HID".try_into()?, &"QEMU0002")?,
+                // Present, enabled and functioning, but hidden from the user interface.
+                &aml::Name::new("_STA".try_into()?, &0x0bu8)?,
+                &aml::Name::new(
+                    "_CRS".try_into()?,
+                    &aml::ResourceTemplate::new(vec![&aml::Io::new(
+                        port,
+                        port,
+                        1,
+                        u8::try_from(FW_CFG_REGS_SIZE).unwrap(),
+                    )]),
+                )?,
+            ],
+        )
+        .append_aml_bytes(v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::single_region_mem;
+
+    fn fw_cfg() -> FwCfg {
+        let files = vec![
+            FwCfgFile {
+                name: "bootorder".to_string(),
+                data: b"/pci@i0cf8/*@1\n".to_vec(),
+            },
+            FwCfgFile {
+                name: "opt/org.example/config".to_string(),
+                data: vec![0xab; 0x1000],
+            },
+        ];
+        FwCfg::from_parts(0, single_region_mem(0x10_0000), files)
+    }
+
+    fn select(fw_cfg: &mut FwCfg, selector: u16) {
+        #[cfg(target_arch = "x86_64")]
+        fw_cfg.write(0, REG_SELECTOR, &selector.to_le_bytes());
+        #[cfg(target_arch = "aarch64")]
+        fw_cfg.write(0, REG_SELECTOR, &selector.to_be_bytes());
+    }
+
+    fn read_data(fw_cfg: &mut FwCfg, len: usize) -> Vec<u8> {
+        (0..len)
+            .map(|_| {
+                let mut data = [0u8];
+                fw_cfg.read(0, REG_DATA, &mut data);
+                data[0]
+            })
+            .collect()
+    }
+
+    // Writes a DMA access at 0x1000 for a buffer at 0x2000, and runs it.
+    fn dma(fw_cfg: &mut FwCfg, control: u32, length: u32) -> u32 {
+        let mut access = control.to_be_bytes().to_vec();
+        access.extend_from_slice(&length.to_be_bytes());
+        access.extend_from_slice(&0x2000u64.to_be_bytes());
+        fw_cfg
+            .mem
+            .write_slice(&access, GuestAddress(0x1000))
+            .unwrap();
+        fw_cfg.write(0, REG_DMA, &0u32.to_be_bytes());
+        fw_cfg.write(0, REG_DMA + 4, &0x1000u32.to_be_bytes());
+        let mut control = [0u8; 4];
+        fw_cfg
+            .mem
+            .read_slice(&mut control, GuestAddress(0x1000))
+            .unwrap();
+        u32::from_be_bytes(control)
+    }
+
+    #[test]
+    fn test_fw_cfg_registers() {
+        let mut fw_cfg = fw_cfg();
+
+        // The signature is selected at reset.
+        assert_eq!(read_data(&mut fw_cfg, 4), b"QEMU");
+        select(&mut fw_cfg, FW_CFG_ID);
+        assert_eq!(read_data(&mut fw_cfg, 4), [3, 0, 0, 0]);
+        let mut signature = [0u8; 8];
+        fw_cfg.read(0, REG_DMA, &mut signature);
+        assert_eq!(&signature, b"QEMU CFG");
+
+        select(&mut fw_cfg, FW_CFG_FILE_DIR);
+        let dir = read_data(&mut fw_cfg, 4 + 2 * 64);
+        assert_eq!(dir[..4], 2u32.to_be_bytes());
+        let entry = &dir[4..68];
+        assert_eq!(entry[..4], 15u32.to_be_bytes());
+        assert_eq!(entry[4..6], FW_CFG_FILE_FIRST.to_be_bytes());
+        assert_eq!(&entry[8..17], b"bootorder");
+        assert!(entry[17..].iter().all(|byte| *byte == 0));
+        let entry = &dir[68..];
+        assert_eq!(entry[..4], 0x1000u32.to_be_bytes());
+        assert_eq!(entry[4..6], (FW_CFG_FILE_FIRST + 1).to_be_bytes());
+        assert_eq!(&entry[8..30], b"opt/org.example/config");
+
+        // Reads past the end of an item, or of an unknown item, return zeroes.
+        select(&mut fw_cfg, FW_CFG_FILE_FIRST);
+        assert_eq!(read_data(&mut fw_cfg, 16), b"/pci@i0cf8/*@1\n\0");
+        select(&mut fw_cfg, FW_CFG_FILE_FIRST + 2);
+        assert_eq!(read_data(&mut fw_cfg, 2), [0, 0]);
+        // Writes to the data register are ignored.
+        fw_cfg.write(0, REG_DATA, &[1]);
+    }
+
+    #[test]
+    fn test_fw_cfg_dma() {
+        let mut fw_cfg = fw_cfg();
+        let selector = u32::from(FW_CFG_FILE_FIRST + 1) << 16;
+
+        // Select the file, skip its first half and read the rest past its end.
+        let control = selector | DMA_CTL_SELECT | DMA_CTL_SKIP;
+        assert_eq!(dma(&mut fw_cfg, control, 0x800), 0);
+        fw_cfg
+            .mem
+            .write_slice(&[0xff; 0x810], GuestAddress(0x2000))
+            .unwrap();
+        assert_eq!(dma(&mut fw_cfg, DMA_CTL_READ, 0x810), 0);
+        let mut data = [0u8; 0x810];
+        fw_cfg
+            .mem
+            .read_slice(&mut data, GuestAddress(0x2000))
+            .unwrap();
+        assert!(data[..0x800].iter().all(|byte| *byte == 0xab));
+        assert!(data[0x800..].iter().all(|byte| *byte == 0));
+
+        // The files are read-only.
+        assert_eq!(dma(&mut fw_cfg, DMA_CTL_WRITE, 1), DMA_CTL_ERROR);
+
+        // A buffer outside of the guest memory fails the access.
+        let mut access = DMA_CTL_READ.to_be_bytes().to_vec();
+        access.extend_from_slice(&1u32.to_be_bytes());
+        access.extend_from_slice(&0x20_0000u64.to_be_bytes());
+        fw_cfg
+            .mem
+            .write_slice(&access, GuestAddress(0x1000))
+            .unwrap();
+        fw_cfg.write(0, REG_DMA, &0x1000u64.to_be_bytes());
+        let control: [u8; 4] = fw_cfg.mem.read_obj(GuestAddress(0x1000)).unwrap();
+        assert_eq!(u32::from_be_bytes(control), DMA_CTL_ERROR);
+    }
+
+    #[test]
+    fn test_fw_cfg_persistence() {
+        let mut fw_cfg = fw_cfg();
+        select(&mut fw_cfg, FW_CFG_FILE_FIRST);
+        read_data(&mut fw_cfg, 5);
+        fw_cfg.write(0, REG_DMA, &0x1u32.to_be_bytes());
+
+        let state = fw_cfg.save();
+        let mut restored = FwCfg::restore(single_region_mem(0x10_0000), &state).unwrap();
+        assert_eq!(restored.files, fw_cfg.files);
+        assert_eq!(restored.file_dir, fw_cfg.file_dir);
+        assert_eq!(restored.dma_address, 1 << 32);
+        assert_eq!(read_data(&mut restored, 4), b"i0cf");
+    }
+}
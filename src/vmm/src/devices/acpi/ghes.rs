@@ -0,0 +1,349 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::{BTreeSet, VecDeque};
+use std::convert::Infallible;
+use std::os::unix::io::AsRawFd;
+use std::sync::atomic::{AtomicI32, AtomicU64, Ordering};
+
+use acpi_tables::{Aml, MemoryErrorStatus, aml};
+use log::{debug, error, warn};
+use serde::{Deserialize, Serialize};
+use vm_memory::GuestMemoryError;
+use vm_superio::Trigger;
+use vmm_sys_util::eventfd::EventFd;
+use zerocopy::IntoBytes;
+
+use super::super::legacy::EventFdTrigger;
+use crate::snapshot::Persist;
+use crate::vmm_config::apei::GhesNotification;
+use crate::vstate::memory::{
+    Address, Bytes, GuestAddress, GuestMemory, GuestMemoryMmap, GuestMemoryRegion,
+    MemoryRegionAddress,
+};
+use crate::vstate::resources::ResourceAllocator;
+
+/// Bytes of memory we allocate for the error source.
+pub const GHES_MEM_SIZE: u64 = 0x400;
+// Layout of the memory of the error source: the address of the Generic Error Status Block, the
+// read ack register, then the block itself.
+const ERROR_STATUS_ADDRESS_OFFSET: u64 = 0x0;
+const READ_ACK_OFFSET: u64 = 0x8;
+const ERROR_STATUS_BLOCK_OFFSET: u64 = 0x10;
+/// Size of the Generic Error Status Block of the error source.
+pub const GHES_ERROR_STATUS_BLOCK_LENGTH: u32 = 0x3f0;
+// Bit of the read ack register set by the guest once it has read the last error.
+const READ_ACK: u64 = 1;
+// Memory errors are reported for whole guest pages.
+const GUEST_PAGE_SIZE: u64 = 0x1000;
+
+// Number of memory errors that can be queued by the SIGBUS handler until the VMM thread
+// processes them.
+const MEMORY_ERROR_SLOTS: usize = 16;
+// Host addresses of the queued memory errors, 0 for a free slot.
+static MEMORY_ERRORS: [AtomicU64; MEMORY_ERROR_SLOTS] =
+    [const { AtomicU64::new(0) }; MEMORY_ERROR_SLOTS];
+// Event fd signaled when a memory error is queued, -1 if memory errors are not forwarded.
+static MEMORY_ERROR_EVT: AtomicI32 = AtomicI32::new(-1);
+
+/// Queues a memory error reported by the host at `host_addr`, to be forwarded to the guest.
+///
+/// This is called from the `SIGBUS` handler, so it only does async-signal-safe operations.
+/// Returns `false` if memory errors are not forwarded to the guest or if too many of them are
+/// already queued, in which case the caller handles the error itself.
+pub(crate) fn queue_memory_error(host_addr: u64) -> bool {
+    let fd = MEMORY_ERROR_EVT.load(Ordering::Acquire);
+    if fd < 0 || host_addr == 0 {
+        return false;
+    }
+
+    // A vCPU retrying the access to a poisoned page reports the same address again.
+    let queued = MEMORY_ERRORS.iter().any(|slot| {
+        slot.load(Ordering::Acquire) == host_addr
+            || slot
+                .compare_exchange(0, host_addr, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+    });
+    if queued {
+        let value = 1u64;
+        // SAFETY: `write` is async-signal-safe and `value` is valid for 8 bytes.
+        unsafe { libc::write(fd, (&raw const value).cast(), std::mem::size_of::<u64>()) };
+    }
+    queued
+}
+
+/// Generic Hardware Error Source device
+///
+/// This device reports host memory errors affecting the guest memory as Common Platform Error
+/// Records, through a GHESv2 error source described in the HEST ACPI table. The guest is notified
+/// of new errors either through the GED, which notifies the Hardware Error Device, or with an
+/// NMI, so that it can offline the affected pages instead of being killed.
+#[derive(Debug)]
+pub struct Ghes {
+    /// How the guest is notified of new errors.
+    pub notification: GhesNotification,
+    /// Guest physical address of the memory of the error source.
+    pub guest_address: GuestAddress,
+    /// GSI number of the GED notification.
+    pub gsi: Option<u32>,
+    /// Interrupt line for the GED notification.
+    pub interrupt_evt: EventFdTrigger,
+    /// Signaled when the host reports a memory error.
+    pub memory_error_evt: EventFd,
+    /// Errors waiting for the guest to acknowledge the previous one.
+    pending: VecDeque<GuestAddress>,
+    /// Guest pages already reported to the guest.
+    reported: BTreeSet<u64>,
+}
+
+impl Ghes {
+    /// Create a new error source from its guest memory and GSI.
+    pub fn from_parts(
+        notification: GhesNotification,
+        guest_address: GuestAddress,
+        gsi: Option<u32>,
+    ) -> Self {
+        debug!(
+            "ghes: building error source. Address: {:#010x}. IRQ: {:?}",
+            guest_address.0, gsi
+        );
+        let interrupt_evt = EventFdTrigger::new(
+            EventFd::new(libc::EFD_NONBLOCK).expect("ghes: Could not create EventFd for GHES"),
+        );
+        let memory_error_evt =
+            EventFd::new(libc::EFD_NONBLOCK).expect("ghes: Could not create EventFd for GHES");
+        MEMORY_ERROR_EVT.store(memory_error_evt.as_raw_fd(), Ordering::Release);
+
+        Self {
+            notification,
+            guest_address,
+            gsi,
+            interrupt_evt,
+            memory_error_evt,
+            pending: VecDeque::new(),
+            reported: BTreeSet::new(),
+        }
+    }
+
+    /// Create a new error source
+    ///
+    /// Allocate ACPI NVS memory for the error status block and, if the guest is notified through
+    /// the GED, a GSI.
+    pub fn new(
+        resource_allocator: &mut ResourceAllocator,
+        notification: GhesNotification,
+    ) -> Result<Self, vm_allocator::Error> {
+        let gsi = match notification {
+            GhesNotification::Ged => Some(resource_allocator.allocate_gsi_legacy(1)?[0]),
+            GhesNotification::Nmi => None,
+        };
+        let addr = resource_allocator.allocate_acpi_nvs_memory(
+            GHES_MEM_SIZE,
+            8,
+            vm_allocator::AllocPolicy::LastMatch,
+        )?;
+        Ok(Self::from_parts(notification, GuestAddress(addr), gsi))
+    }
+
+    /// Address of the register holding the address of the Generic Error Status Block.
+    pub fn error_status_address(&self) -> GuestAddress {
+        self.guest_address
+            .unchecked_add(ERROR_STATUS_ADDRESS_OFFSET)
+    }
+
+    /// Address of the read ack register.
+    pub fn read_ack_address(&self) -> GuestAddress {
+        self.guest_address.unchecked_add(READ_ACK_OFFSET)
+    }
+
+    /// Initialize the memory of the error source, with no unacknowledged error.
+    pub fn activate(&self, mem: &GuestMemoryMmap) -> Result<(), GuestMemoryError> {
+        let block_address = self.guest_address.unchecked_add(ERROR_STATUS_BLOCK_OFFSET);
+        mem.write_obj(block_address.0, self.error_status_address())?;
+        mem.write_obj(READ_ACK, self.read_ack_address())
+    }
+
+    /// Reports the queued memory errors to the guest, returning whether it must be notified.
+    ///
+    /// The guest only reads one error at a time, so errors stay pending until it acknowledges
+    /// the previous one.
+    pub fn process_memory_errors(&mut self, mem: &GuestMemoryMmap) -> bool {
+        let _ = self.memory_error_evt.read();
+        for slot in &MEMORY_ERRORS {
+            let host_addr = slot.swap(0, Ordering::AcqRel);
+            if host_addr == 0 {
+                continue;
+            }
+            match guest_page(mem, host_addr) {
+                Some(page) if self.reported.insert(page.0) => {
+                    warn!(
+                        "ghes: host reported a memory error in guest page {:#x}",
+                        page.0
+                    );
+                    self.pending.push_back(page);
+                }
+                Some(_) => (),
+                None => warn!("ghes: ignoring a memory error outside of guest memory"),
+            }
+        }
+        self.report_pending(mem)
+    }
+
+    fn report_pending(&mut self, mem: &GuestMemoryMmap) -> bool {
+        if self.pending.is_empty() {
+            return false;
+        }
+        match mem.read_obj::<u64>(self.read_ack_address()) {
+            Ok(ack) if ack & READ_ACK != 0 => (),
+            Ok(_) => return false,
+            Err(err) => {
+                error!("ghes: could not read the read ack register: {err}");
+                return false;
+            }
+        }
+
+        let page = self.pending.pop_front().unwrap();
+        let status = MemoryErrorStatus::new(page.0, GUEST_PAGE_SIZE);
+        let block_address = self.guest_address.unchecked_add(ERROR_STATUS_BLOCK_OFFSET);
+        if let Err(err) = mem
+            .write_slice(status.as_bytes(), block_address)
+            .and_then(|()| mem.write_obj(0u64, self.read_ack_address()))
+        {
+            error!("ghes: could not write the error status block: {err}");
+            return false;
+        }
+        true
+    }
+
+    /// Send a GED notification to the guest.
+    pub fn notify_guest(&self) -> Result<(), std::io::Error> {
+        self.interrupt_evt
+            .trigger()
+            .inspect_err(|err| error!("ghes: could not send guest notification: {err}"))
+    }
+}
+
+impl Drop for Ghes {
+    fn drop(&mut self) {
+        // Stop forwarding memory errors to this error source.
+        let _ = MEMORY_ERROR_EVT.compare_exchange(
+            self.memory_error_evt.as_raw_fd(),
+            -1,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        );
+    }
+}
+
+// Returns the guest page mapped at `host_addr`, if any.
+fn guest_page(mem: &GuestMemoryMmap, host_addr: u64) -> Option<GuestAddress> {
+    mem.iter().find_map(|region| {
+        let start = region.get_host_address(MemoryRegionAddress(0)).ok()? as u64;
+        let offset = host_addr
+            .checked_sub(start)
+            .filter(|offset| *offset < region.len())?;
+        Some(
+            region
+                .start_addr()
+                .unchecked_add(offset & !(GUEST_PAGE_SIZE - 1)),
+        )
+    })
+}
+
+/// Logic to save/restore the state of a GHES device
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct GhesState {
+    /// How the guest is notified of new errors.
+    pub notification: GhesNotification,
+    /// Memory address of the error source.
+    pub addr: u64,
+    /// GSI used for the GED notification.
+    pub gsi: Option<u32>,
+}
+
+impl<'a> Persist<'a> for Ghes {
+    type State = GhesState;
+    type ConstructorArgs = ();
+    type Error = Infallible;
+
+    fn save(&self) -> Self::State {
+        GhesState {
+            notification: self.notification,
+            addr: self.guest_address.0,
+            gsi: self.gsi,
+        }
+    }
+
+    fn restore(_: Self::ConstructorArgs, state: &Self::State) -> Result<Self, Self::Error> {
+        Ok(Self::from_parts(
+            state.notification,
+            GuestAddress(state.addr),
+            state.gsi,
+        ))
+    }
+}
+
+impl Aml for Ghes {
+    fn append_aml_bytes(&self, v: &mut Vec<u8>) -> Result<(), aml::AmlError> {
+        // The GED notifies the Hardware Error Device, which makes the guest read the errors.
+        if self.notification == GhesNotification::Ged {
+            aml::Device::new(
+                "_SB_.HED_".try_into()?,
+                vec![&aml::Name::new("_HID".try_into()?, &"PNP0C33")?],
+            )
+            .append_aml_bytes(v)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::single_region_mem;
+
+    #[test]
+    fn test_memory_errors() {
+        let mem = single_region_mem(0x10_0000);
+        let mut ghes = Ghes::from_parts(GhesNotification::Ged, GuestAddress(0x1000), Some(5));
+        ghes.activate(&mem).unwrap();
+        assert_eq!(mem.read_obj::<u64>(GuestAddress(0x1000)).unwrap(), 0x1010);
+        assert_eq!(mem.read_obj::<u64>(GuestAddress(0x1008)).unwrap(), 1);
+
+        let host_start = mem
+            .iter()
+            .next()
+            .unwrap()
+            .get_host_address(MemoryRegionAddress(0))
+            .unwrap() as u64;
+        assert!(queue_memory_error(host_start + 0x5123));
+        // The same address is only queued once.
+        assert!(queue_memory_error(host_start + 0x5123));
+        assert!(queue_memory_error(host_start + 0x7000));
+        assert!(ghes.process_memory_errors(&mem));
+
+        // The first error is reported, with the address of its page.
+        let mut status = [0u8; 172];
+        mem.read_slice(&mut status, GuestAddress(0x1010)).unwrap();
+        assert_eq!(&status[..4], &0x11u32.to_le_bytes());
+        assert_eq!(&status[108..116], &0x5000u64.to_le_bytes());
+        assert_eq!(mem.read_obj::<u64>(GuestAddress(0x1008)).unwrap(), 0);
+
+        // The second one waits for the guest to acknowledge the first one.
+        assert!(!ghes.process_memory_errors(&mem));
+        mem.write_obj(1u64, GuestAddress(0x1008)).unwrap();
+        assert!(ghes.process_memory_errors(&mem));
+        mem.read_slice(&mut status, GuestAddress(0x1010)).unwrap();
+        assert_eq!(&status[108..116], &0x7000u64.to_le_bytes());
+
+        // Pages are only reported once, and errors outside of guest memory are ignored.
+        mem.write_obj(1u64, GuestAddress(0x1008)).unwrap();
+        assert!(queue_memory_error(host_start + 0x5000));
+        assert!(queue_memory_error(host_start + 0x10_0000));
+        assert!(!ghes.process_memory_errors(&mem));
+
+        // Memory errors are no longer forwarded once the device is gone.
+        drop(ghes);
+        assert!(!queue_memory_error(host_start + 0x5000));
+    }
+}
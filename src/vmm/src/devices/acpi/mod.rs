@@ -1,6 +1,15 @@
 // Copyright 2024 Amazon.com, Inc. or its affiliates. All Rights Reserved.
 // SPDX-License-Identifier: Apache-2.0
 
+pub mod cppc;
+pub mod fw_cfg;
 mod generated;
+pub mod ghes;
+pub mod power_supply;
+pub mod processor_aggregator;
+pub mod pvpanic;
+pub mod s2idle;
+pub mod thermal_zone;
 pub mod vmclock;
 pub mod vmgenid;
+pub mod watchdog;
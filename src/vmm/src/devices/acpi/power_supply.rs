@@ -0,0 +1,386 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::convert::Infallible;
+
+use acpi_tables::{Aml, aml};
+use log::{debug, error};
+use serde::{Deserialize, Serialize};
+use vm_memory::GuestMemoryError;
+use vm_superio::Trigger;
+use vmm_sys_util::eventfd::EventFd;
+
+use super::super::legacy::EventFdTrigger;
+use crate::snapshot::Persist;
+use crate::vmm_config::power_supply::{BatteryState, PowerSupplyConfig, PowerSupplyUpdateConfig};
+use crate::vstate::memory::{Address, Bytes, GuestAddress, GuestMemoryMmap};
+use crate::vstate::resources::ResourceAllocator;
+
+/// Bytes of memory we allocate for the power supply registers.
+pub const POWER_SUPPLY_MEM_SIZE: u64 = 0x40;
+
+// Registers read by the AML of the devices, which are all 32 bits wide, along with the names of
+// their fields.
+const REGISTERS: [[u8; 4]; 11] = [
+    *b"ACON", // AC adapter plugged in.
+    *b"BPRS", // Battery present.
+    *b"BSTA", // Battery state, as reported by _BST.
+    *b"BRTE", // Battery present rate, in mW.
+    *b"BRCP", // Battery remaining capacity, in mWh.
+    *b"BPVT", // Battery present voltage, in mV.
+    *b"BDCP", // Battery design capacity, in mWh.
+    *b"BFCP", // Battery last full charge capacity, in mWh.
+    *b"BDVT", // Battery design voltage, in mV.
+    *b"BWRN", // Battery design capacity of warning, in mWh.
+    *b"BLOW", // Battery design capacity of low, in mWh.
+];
+
+// Battery state bits of _BST.
+const BATTERY_DISCHARGING: u32 = 1 << 0;
+const BATTERY_CHARGING: u32 = 1 << 1;
+const BATTERY_CRITICAL: u32 = 1 << 2;
+// Voltage of the battery, in mV.
+const BATTERY_VOLTAGE_MV: u32 = 12_000;
+/// Notification value for a change of the status of a power supply device.
+pub const NOTIFY_STATUS_CHANGED: usize = 0x80;
+/// Notification value for a change of the static information of the battery.
+pub const NOTIFY_INFORMATION_CHANGED: usize = 0x81;
+
+/// Battery and AC adapter devices
+///
+/// These devices expose a control method battery (`PNP0C0A`) and an AC adapter (`ACPI0003`) to
+/// the guest. Their status is held in guest memory and read by their AML methods, and the guest is
+/// notified through the GED whenever it is updated through the API.
+#[derive(Debug)]
+pub struct PowerSupply {
+    /// Current status of the devices.
+    pub config: PowerSupplyConfig,
+    /// Guest physical address of the registers.
+    pub guest_address: GuestAddress,
+    /// GSI number for the device.
+    pub gsi: u32,
+    /// Interrupt line for notifying the device about changes.
+    pub interrupt_evt: EventFdTrigger,
+}
+
+impl PowerSupply {
+    /// Create the devices from their registers address and GSI.
+    pub fn from_parts(config: PowerSupplyConfig, guest_address: GuestAddress, gsi: u32) -> Self {
+        debug!(
+            "power_supply: building devices. Address: {:#010x}. IRQ: {}",
+            guest_address.0, gsi
+        );
+        let interrupt_evt = EventFdTrigger::new(
+            EventFd::new(libc::EFD_NONBLOCK)
+                .expect("power_supply: Could not create EventFd for the power supply devices"),
+        );
+        Self {
+            config,
+            guest_address,
+            gsi,
+            interrupt_evt,
+        }
+    }
+
+    /// Create the devices
+    ///
+    /// Allocate memory for the registers and a GSI for the notifications.
+    pub fn new(
+        resource_allocator: &mut ResourceAllocator,
+        config: PowerSupplyConfig,
+    ) -> Result<Self, vm_allocator::Error> {
+        let gsi = resource_allocator.allocate_gsi_legacy(1)?;
+        let addr = resource_allocator.allocate_system_memory(
+            POWER_SUPPLY_MEM_SIZE,
+            8,
+            vm_allocator::AllocPolicy::LastMatch,
+        )?;
+        Ok(Self::from_parts(config, GuestAddress(addr), gsi[0]))
+    }
+
+    fn register_values(&self) -> [u32; REGISTERS.len()] {
+        let battery = &self.config.battery;
+        let design_capacity = battery.design_capacity_mwh;
+        let remaining_capacity =
+            u32::try_from(u64::from(design_capacity) * u64::from(battery.charge_percent) / 100)
+                .unwrap();
+        let low_capacity = design_capacity / 20;
+        let mut state = match battery.state {
+            BatteryState::Idle => 0,
+            BatteryState::Charging => BATTERY_CHARGING,
+            BatteryState::Discharging => BATTERY_DISCHARGING,
+        };
+        if remaining_capacity <= low_capacity {
+            state |= BATTERY_CRITICAL;
+        }
+        [
+            u32::from(self.config.ac_online),
+            u32::from(battery.present),
+            state,
+            battery.rate_mw,
+            remaining_capacity,
+            BATTERY_VOLTAGE_MV,
+            design_capacity,
+            design_capacity,
+            BATTERY_VOLTAGE_MV,
+            design_capacity / 10,
+            low_capacity,
+        ]
+    }
+
+    /// Write the current status of the devices to their registers.
+    pub fn activate(&self, mem: &GuestMemoryMmap) -> Result<(), GuestMemoryError> {
+        for (index, value) in self.register_values().into_iter().enumerate() {
+            let offset = u64::try_from(index * std::mem::size_of::<u32>()).unwrap();
+            mem.write_obj(value, self.guest_address.unchecked_add(offset))?;
+        }
+        Ok(())
+    }
+
+    /// Update the status of the devices and notify the guest about it.
+    pub fn update(
+        &mut self,
+        mem: &GuestMemoryMmap,
+        update: &PowerSupplyUpdateConfig,
+    ) -> Result<(), GuestMemoryError> {
+        self.config.apply(update);
+        self.activate(mem)?;
+        // The guest picks the new status up on its next poll if it misses the notification.
+        let _ = self.notify_guest();
+        Ok(())
+    }
+
+    /// Send a GED notification to the guest.
+    pub fn notify_guest(&self) -> Result<(), std::io::Error> {
+        self.interrupt_evt
+            .trigger()
+            .inspect_err(|err| error!("power_supply: could not send guest notification: {err}"))
+    }
+}
+
+/// Logic to save/restore the state of the power supply devices
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct PowerSupplyState {
+    /// Current status of the devices.
+    pub config: PowerSupplyConfig,
+    /// Memory address of the registers.
+    pub addr: u64,
+    /// GSI used for the notifications.
+    pub gsi: u32,
+}
+
+impl<'a> Persist<'a> for PowerSupply {
+    type State = PowerSupplyState;
+    type ConstructorArgs = ();
+    type Error = Infallible;
+
+    fn save(&self) -> Self::State {
+        PowerSupplyState {
+            config: self.config.clone(),
+            addr: self.guest_address.0,
+            gsi: self.gsi,
+        }
+    }
+
+    fn restore(_: Self::ConstructorArgs, state: &Self::State) -> Result<Self, Self::Error> {
+        Ok(Self::from_parts(
+            state.config.clone(),
+            GuestAddress(state.addr),
+            state.gsi,
+        ))
+    }
+}
+
+impl Aml for PowerSupply {
+    fn append_aml_bytes(&self, v: &mut Vec<u8>) -> Result<(), aml::AmlError> {
+        let region = aml::OpRegion::new(
+            "PWRR".try_into()?,
+            aml::OpRegionSpace::SystemMemory,
+            usize::try_from(self.guest_address.0).unwrap(),
+            usize::try_from(POWER_SUPPLY_MEM_SIZE).unwrap(),
+        );
+        let field = aml::Field::new(
+            "PWRR".try_into()?,
+            aml::FieldAccessType::DWord,
+            aml::FieldUpdateRule::Preserve,
+            REGISTERS
+                .iter()
+                .map(|name| aml::FieldEntry::Named(*name, 32))
+                .collect(),
+        );
+        let system_bus = aml::Path::new("\\_SB_")?;
+        let power_consumers = aml::Package::new(vec![&system_bus]);
+
+        let acon = aml::Path::new("ACON")?;
+        aml::Device::new(
+            "_SB_.ADP1".try_into()?,
+            vec![
+                &aml::Name::new("_HID".try_into()?, &"ACPI0003")?,
+                &aml::Name::new("_PCL".try_into()?, &power_consumers)?,
+                &region,
+                &field,
+                &aml::Method::new("_PSR".try_into()?, 0, false, vec![&aml::Return::new(&acon)]),
+            ],
+        )
+        .append_aml_bytes(v)?;
+
+        // _STA reports the battery slot, along with the battery when it is present.
+        let bprs = aml::Path::new("BPRS")?;
+        let battery_present = aml::Equal::new(&bprs, &aml::ONE);
+        let return_present = aml::Return::new(&0x1fusize);
+        let return_absent = aml::Return::new(&0x0fusize);
+
+        // _BIF and _BST fill the packages with the values of the registers.
+        let zero = 0usize;
+        let one = 1usize;
+        let battery_model: aml::AmlStr = "Virtual battery";
+        let battery_serial: aml::AmlStr = "0";
+        let battery_type: aml::AmlStr = "LION";
+        let battery_oem: aml::AmlStr = "clawdbox";
+        let bif_values = aml::Package::new(vec![
+            &zero, // Power unit: mW and mWh.
+            &zero, // Design capacity.
+            &zero, // Last full charge capacity.
+            &one,  // Battery technology: rechargeable.
+            &zero, // Design voltage.
+            &zero, // Design capacity of warning.
+            &zero, // Design capacity of low.
+            &one,  // Battery capacity granularity 1.
+            &one,  // Battery capacity granularity 2.
+            &battery_model,
+            &battery_serial,
+            &battery_type,
+            &battery_oem,
+        ]);
+        let bst_values = aml::Package::new(vec![&zero, &zero, &zero, &zero]);
+        let bifp = aml::Path::new("BIFP")?;
+        let bstp = aml::Path::new("BSTP")?;
+        let bif_fields = [
+            (1usize, "BDCP"),
+            (2, "BFCP"),
+            (4, "BDVT"),
+            (5, "BWRN"),
+            (6, "BLOW"),
+        ];
+        let bst_fields = [(0usize, "BSTA"), (1, "BRTE"), (2, "BRCP"), (3, "BPVT")];
+        let paths = bif_fields
+            .iter()
+            .chain(bst_fields.iter())
+            .map(|(index, name)| Ok((*index, aml::Path::new(name)?)))
+            .collect::<Result<Vec<_>, aml::AmlError>>()?;
+        let (bif_paths, bst_paths) = paths.split_at(bif_fields.len());
+        let bif_indexes: Vec<_> = bif_paths
+            .iter()
+            .map(|(index, _)| aml::Index::new(&aml::ZERO, &bifp, index))
+            .collect();
+        let bst_indexes: Vec<_> = bst_paths
+            .iter()
+            .map(|(index, _)| aml::Index::new(&aml::ZERO, &bstp, index))
+            .collect();
+        let bif_stores: Vec<_> = bif_paths
+            .iter()
+            .zip(&bif_indexes)
+            .map(|((_, field), index)| aml::Store::new(index, field))
+            .collect();
+        let bst_stores: Vec<_> = bst_paths
+            .iter()
+            .zip(&bst_indexes)
+            .map(|((_, field), index)| aml::Store::new(index, field))
+            .collect();
+        let return_bif = aml::Return::new(&bifp);
+        let return_bst = aml::Return::new(&bstp);
+        let mut bif_children: Vec<&dyn Aml> =
+            bif_stores.iter().map(|store| store as &dyn Aml).collect();
+        bif_children.push(&return_bif);
+        let mut bst_children: Vec<&dyn Aml> =
+            bst_stores.iter().map(|store| store as &dyn Aml).collect();
+        bst_children.push(&return_bst);
+
+        aml::Device::new(
+            "_SB_.BAT0".try_into()?,
+            vec![
+                &aml::Name::new("_HID".try_into()?, &aml::EisaName::new("PNP0C0A")?)?,
+                &aml::Name::new("_UID".try_into()?, &aml::ONE)?,
+                &aml::Name::new("_PCL".try_into()?, &power_consumers)?,
+                &region,
+                &field,
+                &aml::Name::new("BIFP".try_into()?, &bif_values)?,
+                &aml::Name::new("BSTP".try_into()?, &bst_values)?,
+                &aml::Method::new(
+                    "_STA".try_into()?,
+                    0,
+                    false,
+                    vec![
+                        &aml::If::new(&battery_present, vec![&return_present]),
+                        &return_absent,
+                    ],
+                ),
+                &aml::Method::new("_BIF".try_into()?, 0, true, bif_children),
+                &aml::Method::new("_BST".try_into()?, 0, true, bst_children),
+            ],
+        )
+        .append_aml_bytes(v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::single_region_mem;
+    use crate::vmm_config::power_supply::BatteryUpdateConfig;
+
+    fn registers(mem: &GuestMemoryMmap) -> Vec<u32> {
+        (0..REGISTERS.len() as u64)
+            .map(|index| mem.read_obj(GuestAddress(0x1000 + index * 4)).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_power_supply_registers() {
+        let mem = single_region_mem(0x10_0000);
+        let mut power_supply =
+            PowerSupply::from_parts(PowerSupplyConfig::default(), GuestAddress(0x1000), 5);
+        power_supply.activate(&mem).unwrap();
+        assert_eq!(
+            registers(&mem),
+            [
+                1, 1, 0, 0, 50_000, 12_000, 50_000, 50_000, 12_000, 5_000, 2_500
+            ]
+        );
+
+        let update = PowerSupplyUpdateConfig {
+            ac_online: Some(false),
+            battery: Some(BatteryUpdateConfig {
+                charge_percent: Some(3),
+                state: Some(BatteryState::Discharging),
+                rate_mw: Some(8_000),
+                ..Default::default()
+            }),
+        };
+        power_supply.update(&mem, &update).unwrap();
+        // The battery is critical below the low capacity.
+        let values = registers(&mem);
+        assert_eq!(
+            values[..5],
+            [0, 1, BATTERY_DISCHARGING | BATTERY_CRITICAL, 8_000, 1_500]
+        );
+        assert_eq!(power_supply.interrupt_evt.read().unwrap(), 1);
+
+        let state = power_supply.save();
+        let restored = PowerSupply::restore((), &state).unwrap();
+        assert_eq!(restored.config, power_supply.config);
+        assert_eq!(restored.guest_address, power_supply.guest_address);
+        assert_eq!(restored.gsi, 5);
+    }
+
+    #[test]
+    fn test_power_supply_aml() {
+        let power_supply =
+            PowerSupply::from_parts(PowerSupplyConfig::default(), GuestAddress(0x1000), 5);
+        let mut aml = Vec::new();
+        power_supply.append_aml_bytes(&mut aml).unwrap();
+        for name in ["ADP1", "BAT0", "_PSR", "_BIF", "_BST", "BIFP", "BSTP"] {
+            assert!(aml.windows(4).any(|window| window == name.as_bytes()));
+        }
+    }
+}
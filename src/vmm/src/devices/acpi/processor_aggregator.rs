@@ -0,0 +1,272 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::convert::Infallible;
+
+use acpi_tables::{Aml, aml};
+use log::{debug, error};
+use serde::{Deserialize, Serialize};
+use vm_memory::GuestMemoryError;
+use vm_superio::Trigger;
+use vmm_sys_util::eventfd::EventFd;
+
+use super::super::legacy::EventFdTrigger;
+use crate::snapshot::Persist;
+use crate::vstate::memory::{Address, Bytes, GuestAddress, GuestMemoryMmap};
+use crate::vstate::resources::ResourceAllocator;
+
+/// Bytes of memory we allocate for the processor aggregator registers.
+pub const PROCESSOR_AGGREGATOR_MEM_SIZE: u64 = 0x8;
+
+// Number of vCPUs the guest is asked to idle, returned by _PUR.
+const REQUESTED_IDLE_CPUS_OFFSET: u64 = 0;
+// Number of vCPUs the guest reported as idled through _OST.
+const IDLE_CPUS_OFFSET: u64 = 4;
+
+/// Notification value asking the guest to evaluate _PUR again.
+pub const NOTIFY_PUR_CHANGED: usize = 0x80;
+
+/// Status of the processor aggregator device, as reported by the API.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ProcessorAggregatorStatus {
+    /// Number of vCPUs the guest is asked to idle.
+    pub requested_idle_cpus: u32,
+    /// Number of vCPUs the guest reported as idled.
+    pub idle_cpus: u32,
+}
+
+/// Processor aggregator device
+///
+/// This device exposes an ACPI processor aggregator (`ACPI000C`) to the guest, whose `_PUR`
+/// method returns the number of logical processors the guest should idle. The guest is notified
+/// through the GED whenever that number is updated through the API, and reports back how many
+/// processors it idled through `_OST`.
+#[derive(Debug)]
+pub struct ProcessorAggregator {
+    /// Number of vCPUs the guest is asked to idle.
+    pub requested_idle_cpus: u32,
+    /// Guest physical address of the registers.
+    pub guest_address: GuestAddress,
+    /// GSI number for the device.
+    pub gsi: u32,
+    /// Interrupt line for notifying the device about changes.
+    pub interrupt_evt: EventFdTrigger,
+}
+
+impl ProcessorAggregator {
+    /// Create the device from its registers address and GSI.
+    pub fn from_parts(requested_idle_cpus: u32, guest_address: GuestAddress, gsi: u32) -> Self {
+        debug!(
+            "processor_aggregator: building device. Address: {:#010x}. IRQ: {}",
+            guest_address.0, gsi
+        );
+        let interrupt_evt = EventFdTrigger::new(EventFd::new(libc::EFD_NONBLOCK).expect(
+            "processor_aggregator: Could not create EventFd for the processor aggregator device",
+        ));
+        Self {
+            requested_idle_cpus,
+            guest_address,
+            gsi,
+            interrupt_evt,
+        }
+    }
+
+    /// Create the device
+    ///
+    /// Allocate memory for the registers and a GSI for the notifications.
+    pub fn new(
+        resource_allocator: &mut ResourceAllocator,
+        requested_idle_cpus: u32,
+    ) -> Result<Self, vm_allocator::Error> {
+        let gsi = resource_allocator.allocate_gsi_legacy(1)?;
+        let addr = resource_allocator.allocate_system_memory(
+            PROCESSOR_AGGREGATOR_MEM_SIZE,
+            8,
+            vm_allocator::AllocPolicy::LastMatch,
+        )?;
+        Ok(Self::from_parts(
+            requested_idle_cpus,
+            GuestAddress(addr),
+            gsi[0],
+        ))
+    }
+
+    /// Write the requested number of idle vCPUs to the registers.
+    pub fn activate(&self, mem: &GuestMemoryMmap) -> Result<(), GuestMemoryError> {
+        mem.write_obj(
+            self.requested_idle_cpus,
+            self.guest_address.unchecked_add(REQUESTED_IDLE_CPUS_OFFSET),
+        )
+    }
+
+    /// Ask the guest to idle `idle_cpus` vCPUs.
+    pub fn update(
+        &mut self,
+        mem: &GuestMemoryMmap,
+        idle_cpus: u32,
+    ) -> Result<(), GuestMemoryError> {
+        self.requested_idle_cpus = idle_cpus;
+        self.activate(mem)?;
+        // Without the notification, the guest keeps the vCPUs it idled last.
+        let _ = self.notify_guest();
+        Ok(())
+    }
+
+    /// Returns the requested and idled number of vCPUs.
+    pub fn status(
+        &self,
+        mem: &GuestMemoryMmap,
+    ) -> Result<ProcessorAggregatorStatus, GuestMemoryError> {
+        Ok(ProcessorAggregatorStatus {
+            requested_idle_cpus: self.requested_idle_cpus,
+            idle_cpus: mem.read_obj(self.guest_address.unchecked_add(IDLE_CPUS_OFFSET))?,
+        })
+    }
+
+    /// Send a GED notification to the guest.
+    pub fn notify_guest(&self) -> Result<(), std::io::Error> {
+        self.interrupt_evt.trigger().inspect_err(|err| {
+            error!("processor_aggregator: could not send guest notification: {err}")
+        })
+    }
+}
+
+/// Logic to save/restore the state of the processor aggregator device
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessorAggregatorState {
+    /// Number of vCPUs the guest is asked to idle.
+    pub requested_idle_cpus: u32,
+    /// Memory address of the registers.
+    pub addr: u64,
+    /// GSI used for the notifications.
+    pub gsi: u32,
+}
+
+impl<'a> Persist<'a> for ProcessorAggregator {
+    type State = ProcessorAggregatorState;
+    type ConstructorArgs = ();
+    type Error = Infallible;
+
+    fn save(&self) -> Self::State {
+        ProcessorAggregatorState {
+            requested_idle_cpus: self.requested_idle_cpus,
+            addr: self.guest_address.0,
+            gsi: self.gsi,
+        }
+    }
+
+    fn restore(_: Self::ConstructorArgs, state: &Self::State) -> Result<Self, Self::Error> {
+        Ok(Self::from_parts(
+            state.requested_idle_cpus,
+            GuestAddress(state.addr),
+            state.gsi,
+        ))
+    }
+}
+
+impl Aml for ProcessorAggregator {
+    fn append_aml_bytes(&self, v: &mut Vec<u8>) -> Result<(), aml::AmlError> {
+        let region = aml::OpRegion::new(
+            "PADR".try_into()?,
+            aml::OpRegionSpace::SystemMemory,
+            usize::try_from(self.guest_address.0).unwrap(),
+            usize::try_from(PROCESSOR_AGGREGATOR_MEM_SIZE).unwrap(),
+        );
+        let field = aml::Field::new(
+            "PADR".try_into()?,
+            aml::FieldAccessType::DWord,
+            aml::FieldUpdateRule::Preserve,
+            vec![
+                aml::FieldEntry::Named(*b"PURN", 32),
+                aml::FieldEntry::Named(*b"PIDL", 32),
+            ],
+        );
+
+        // _PUR returns a revision 1 package with the number of processors to idle.
+        let pur_values = aml::Package::new(vec![&aml::ONE, &aml::ZERO]);
+        let purp = aml::Path::new("PURP")?;
+        let purn = aml::Path::new("PURN")?;
+        let pur_count = aml::Index::new(&aml::ZERO, &purp, &aml::ONE);
+
+        // _OST reports the number of processors the guest idled in the first DWord of Arg2.
+        let idle = aml::Path::new("IDLE")?;
+        let pidl = aml::Path::new("PIDL")?;
+
+        aml::Device::new(
+            "_SB_.PAD0".try_into()?,
+            vec![
+                &aml::Name::new("_HID".try_into()?, &"ACPI000C")?,
+                &region,
+                &field,
+                &aml::Name::new("PURP".try_into()?, &pur_values)?,
+                &aml::Method::new(
+                    "_PUR".try_into()?,
+                    0,
+                    true,
+                    vec![
+                        &aml::Store::new(&pur_count, &purn),
+                        &aml::Return::new(&purp),
+                    ],
+                ),
+                &aml::Method::new(
+                    "_OST".try_into()?,
+                    3,
+                    true,
+                    vec![
+                        &aml::CreateField::<u32>::new(
+                            &aml::Arg(2),
+                            &aml::ZERO,
+                            aml::Path::new("IDLE")?,
+                        ),
+                        &aml::Store::new(&pidl, &idle),
+                    ],
+                ),
+            ],
+        )
+        .append_aml_bytes(v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::single_region_mem;
+
+    #[test]
+    fn test_processor_aggregator_registers() {
+        let mem = single_region_mem(0x10_0000);
+        let mut aggregator = ProcessorAggregator::from_parts(1, GuestAddress(0x1000), 5);
+        aggregator.activate(&mem).unwrap();
+        assert_eq!(mem.read_obj::<u32>(GuestAddress(0x1000)).unwrap(), 1);
+
+        aggregator.update(&mem, 3).unwrap();
+        assert_eq!(mem.read_obj::<u32>(GuestAddress(0x1000)).unwrap(), 3);
+        assert_eq!(aggregator.interrupt_evt.read().unwrap(), 1);
+
+        // The guest reports the number of idled vCPUs through _OST.
+        mem.write_obj(2u32, GuestAddress(0x1004)).unwrap();
+        assert_eq!(
+            aggregator.status(&mem).unwrap(),
+            ProcessorAggregatorStatus {
+                requested_idle_cpus: 3,
+                idle_cpus: 2,
+            }
+        );
+
+        let state = aggregator.save();
+        let restored = ProcessorAggregator::restore((), &state).unwrap();
+        assert_eq!(restored.requested_idle_cpus, 3);
+        assert_eq!(restored.guest_address, aggregator.guest_address);
+        assert_eq!(restored.gsi, 5);
+    }
+
+    #[test]
+    fn test_processor_aggregator_aml() {
+        let aggregator = ProcessorAggregator::from_parts(0, GuestAddress(0x1000), 5);
+        let mut aml = Vec::new();
+        aggregator.append_aml_bytes(&mut aml).unwrap();
+        for name in ["PAD0", "_PUR", "_OST", "PURN", "PIDL"] {
+            assert!(aml.windows(4).any(|window| window == name.as_bytes()));
+        }
+    }
+}
@@ -0,0 +1,149 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::convert::Infallible;
+use std::sync::{Arc, Barrier};
+
+use acpi_tables::{Aml, aml};
+use serde::{Deserialize, Serialize};
+use vmm_sys_util::eventfd::EventFd;
+
+use crate::logger::{IncMetric, METRICS, warn};
+use crate::snapshot::Persist;
+use crate::vstate::bus::BusDevice;
+
+/// Size of the MMIO region holding the pvpanic register.
+pub const PVPANIC_MMIO_SIZE: u64 = 0x1000;
+
+// Events of the guest, as bits of the register. Reading the register returns the events the
+// device supports, writing it reports them.
+const PVPANIC_PANICKED: u8 = 1 << 0;
+const PVPANIC_CRASH_LOADED: u8 = 1 << 1;
+
+/// pvpanic device
+///
+/// This device exposes the pvpanic register (`QEMU0001`) to the guest, through which its kernel
+/// reports that it panicked, or that it is about to run a crash kernel. A panic is signalled to
+/// the VMM through `panic_evt`.
+#[derive(Debug)]
+pub struct PvPanic {
+    /// Guest address of the register.
+    pub mmio_addr: u64,
+    /// Signalled when the guest reports a panic.
+    pub panic_evt: EventFd,
+}
+
+impl PvPanic {
+    /// Create a new [`PvPanic`] device, whose register is at `mmio_addr`.
+    pub fn new(mmio_addr: u64) -> PvPanic {
+        PvPanic {
+            mmio_addr,
+            panic_evt: EventFd::new(libc::EFD_NONBLOCK)
+                .expect("pvpanic: Could not create EventFd for panics"),
+        }
+    }
+}
+
+impl BusDevice for PvPanic {
+    fn read(&mut self, _base: u64, offset: u64, data: &mut [u8]) {
+        data.fill(0);
+        if offset == 0
+            && let Some(events) = data.first_mut()
+        {
+            *events = PVPANIC_PANICKED | PVPANIC_CRASH_LOADED;
+        }
+    }
+
+    fn write(&mut self, _base: u64, offset: u64, data: &[u8]) -> Option<Arc<Barrier>> {
+        let events = match (offset, data.first()) {
+            (0, Some(events)) => *events,
+            _ => return None,
+        };
+        if events & PVPANIC_PANICKED != 0 {
+            METRICS.scheduled_actions.guest_panics.inc();
+            if let Err(err) = self.panic_evt.write(1) {
+                warn!("pvpanic: Failed to signal the panic of the guest: {err}");
+            }
+        } else if events & PVPANIC_CRASH_LOADED != 0 {
+            warn!("pvpanic: The guest is running its crash kernel");
+        }
+        None
+    }
+}
+
+impl Aml for PvPanic {
+    fn append_aml_bytes(&self, v: &mut Vec<u8>) -> Result<(), aml::AmlError> {
+        let mmio_addr = u32::try_from(self.mmio_addr).unwrap();
+        aml::Device::new(
+            "_SB_.PEVT".try_into()?,
+            vec![
+                &aml::Name::new("_HID".try_into()?, &"QEMU0001")?,
+                &aml::Name::new("_UID".try_into()?, &aml::ZERO)?,
+                &aml::Name::new(
+                    "_CRS".try_into()?,
+                    &aml::ResourceTemplate::new(vec![&aml::Memory32Fixed::new(true, mmio_addr, 1)]),
+                )?,
+                &aml::Method::new(
+                    "_STA".try_into()?,
+                    0,
+                    false,
+                    vec![&aml::Return::new(&0x0fu8)],
+                ),
+            ],
+        )
+        .append_aml_bytes(v)
+    }
+}
+
+/// Logic to save/restore the state of a [`PvPanic`] device.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PvPanicState {
+    mmio_addr: u64,
+}
+
+impl<'a> Persist<'a> for PvPanic {
+    type State = PvPanicState;
+    type ConstructorArgs = ();
+    type Error = Infallible;
+
+    fn save(&self) -> Self::State {
+        PvPanicState {
+            mmio_addr: self.mmio_addr,
+        }
+    }
+
+    fn restore(_: Self::ConstructorArgs, state: &Self::State) -> Result<Self, Self::Error> {
+        Ok(PvPanic::new(state.mmio_addr))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pvpanic() {
+        let mut pvpanic = PvPanic::new(0xd000_0000);
+        let mut data = [0xffu8; 1];
+        pvpanic.read(0, 0, &mut data);
+        assert_eq!(data, [PVPANIC_PANICKED | PVPANIC_CRASH_LOADED]);
+        pvpanic.read(0, 1, &mut data);
+        assert_eq!(data, [0]);
+
+        // Only panics are signalled.
+        pvpanic.write(0, 0, &[PVPANIC_CRASH_LOADED]);
+        pvpanic.panic_evt.read().unwrap_err();
+        let panics = METRICS.scheduled_actions.guest_panics.count();
+        pvpanic.write(0, 0, &[PVPANIC_PANICKED]);
+        assert_eq!(pvpanic.panic_evt.read().unwrap(), 1);
+        assert_eq!(METRICS.scheduled_actions.guest_panics.count(), panics + 1);
+
+        let restored = PvPanic::restore((), &pvpanic.save()).unwrap();
+        assert_eq!(restored.mmio_addr, 0xd000_0000);
+
+        let mut aml = Vec::new();
+        pvpanic.append_aml_bytes(&mut aml).unwrap();
+        assert!(aml.windows(4).any(|name| name == b"PEVT"));
+        assert!(aml.windows(8).any(|hid| hid == b"QEMU0001"));
+    }
+}
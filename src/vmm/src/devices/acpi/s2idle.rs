@@ -0,0 +1,305 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::convert::Infallible;
+use std::sync::{Arc, Barrier};
+use std::time::{Duration, Instant};
+
+use acpi_tables::{AccessSize, AddressSpace, Aml, GenericAddressStructure, LpiNativeCState, aml};
+use serde::{Deserialize, Serialize};
+
+use crate::logger::{IncMetric, METRICS, StoreMetric};
+use crate::snapshot::Persist;
+use crate::vstate::bus::BusDevice;
+
+/// Size of the MMIO region holding the suspend-to-idle registers.
+pub const S2IDLE_MMIO_SIZE: u64 = 0x1000;
+
+// Time spent in suspend-to-idle, in microseconds. 64 bits wide and read-only.
+const REG_RESIDENCY: u64 = 0x0;
+// Reads 1 while the guest is in suspend-to-idle. The LPS0 device writes 1 when the guest enters
+// it and 0 when it leaves it. 32 bits wide.
+const REG_SLEEPING: u64 = 0x8;
+
+/// Frequency of the residency counter, which counts microseconds.
+pub const S2IDLE_COUNTER_FREQUENCY: u64 = 1_000_000;
+// Latency to enter and leave suspend-to-idle, and minimum residency worth it, in microseconds.
+const S2IDLE_LATENCY_US: u32 = 100;
+const S2IDLE_MIN_RESIDENCY_US: u32 = 1000;
+
+// UUID of the Intel Low Power S0 Idle _DSM, c4eb40a0-6cd2-11e2-bcfd-0800200c9a66.
+const LPS0_DSM_UUID: [u8; 16] = [
+    0xa0, 0x40, 0xeb, 0xc4, 0xd2, 0x6c, 0xe2, 0x11, 0xbc, 0xfd, 0x08, 0x00, 0x20, 0x0c, 0x9a, 0x66,
+];
+// _DSM functions notifying the entry in and the exit from suspend-to-idle.
+const LPS0_DSM_ENTRY: u8 = 5;
+const LPS0_DSM_EXIT: u8 = 6;
+// Functions supported by the _DSM, as returned by its function 0.
+const LPS0_DSM_FUNCTIONS: u8 = (1 << 0) | (1 << LPS0_DSM_ENTRY) | (1 << LPS0_DSM_EXIT);
+
+/// Suspend-to-idle device
+///
+/// This device exposes a Low Power S0 Idle device (`PNP0D80`) to the guest, whose `_DSM` is
+/// evaluated by the guest right after it halted every vCPU but the one suspending, and right
+/// before it wakes them up again. The time in between is counted by a residency counter,
+/// described to the guest through the LPIT ACPI table. The counter stops while the microVM is
+/// paused.
+#[derive(Debug)]
+pub struct S2idle {
+    /// Guest address of the registers.
+    pub mmio_addr: u64,
+    sleeping: bool,
+    paused: bool,
+    /// Time spent in suspend-to-idle, up to `since` when it is set.
+    residency: Duration,
+    /// When the counter last started, while the guest sleeps and the microVM is not paused.
+    since: Option<Instant>,
+}
+
+impl S2idle {
+    /// Create a new [`S2idle`] device, whose registers are at `mmio_addr`.
+    ///
+    /// The device starts as if the microVM was paused, until [`S2idle::resume`] is called.
+    pub fn new(mmio_addr: u64) -> S2idle {
+        S2idle {
+            mmio_addr,
+            sleeping: false,
+            paused: true,
+            residency: Duration::ZERO,
+            since: None,
+        }
+    }
+
+    fn residency(&self) -> Duration {
+        self.residency + self.since.map_or(Duration::ZERO, |since| since.elapsed())
+    }
+
+    fn update_counter(&mut self) {
+        self.residency = self.residency();
+        self.since = (self.sleeping && !self.paused).then(Instant::now);
+        METRICS
+            .s2idle
+            .residency_us
+            .store(u64::try_from(self.residency.as_micros()).unwrap_or(u64::MAX));
+    }
+
+    /// Stops the residency counter while the microVM is paused.
+    pub fn pause(&mut self) {
+        self.paused = true;
+        self.update_counter();
+    }
+
+    /// Resumes the residency counter along with the microVM.
+    pub fn resume(&mut self) {
+        self.paused = false;
+        self.update_counter();
+    }
+
+    /// Returns the LPIT entry describing suspend-to-idle and its residency counter to the guest.
+    pub fn lpit_state(&self) -> LpiNativeCState {
+        let counter = GenericAddressStructure::new(
+            AddressSpace::SystemMemory,
+            64,
+            0,
+            AccessSize::QWord,
+            self.mmio_addr + REG_RESIDENCY,
+        )
+        .unwrap();
+        LpiNativeCState::new(
+            0,
+            // The guest enters suspend-to-idle by halting its vCPUs, there is no register for it.
+            GenericAddressStructure::default(),
+            S2IDLE_MIN_RESIDENCY_US,
+            S2IDLE_LATENCY_US,
+            counter,
+            S2IDLE_COUNTER_FREQUENCY,
+        )
+    }
+}
+
+impl BusDevice for S2idle {
+    fn read(&mut self, _base: u64, offset: u64, data: &mut [u8]) {
+        match (offset, data.len()) {
+            (REG_RESIDENCY, 8) => {
+                let residency = u64::try_from(self.residency().as_micros()).unwrap_or(u64::MAX);
+                data.copy_from_slice(&residency.to_le_bytes());
+            }
+            (REG_SLEEPING, 4) => data.copy_from_slice(&u32::from(self.sleeping).to_le_bytes()),
+            _ => data.fill(0),
+        }
+    }
+
+    fn write(&mut self, _base: u64, offset: u64, data: &[u8]) -> Option<Arc<Barrier>> {
+        let Ok(data) = <[u8; 4]>::try_from(data) else {
+            return None;
+        };
+        if offset == REG_SLEEPING {
+            let sleeping = u32::from_le_bytes(data) & 1 != 0;
+            if sleeping != self.sleeping {
+                if sleeping {
+                    METRICS.s2idle.entries.inc();
+                }
+                self.sleeping = sleeping;
+                self.update_counter();
+            }
+        }
+        None
+    }
+}
+
+impl Aml for S2idle {
+    fn append_aml_bytes(&self, v: &mut Vec<u8>) -> Result<(), aml::AmlError> {
+        let region = aml::OpRegion::new(
+            "LPSR".try_into()?,
+            aml::OpRegionSpace::SystemMemory,
+            usize::try_from(self.mmio_addr + REG_SLEEPING).unwrap(),
+            4,
+        );
+        let field = aml::Field::new(
+            "LPSR".try_into()?,
+            aml::FieldAccessType::DWord,
+            aml::FieldUpdateRule::Preserve,
+            vec![aml::FieldEntry::Named(*b"LPSE", 32)],
+        );
+
+        // The _DSM only implements the functions of the Intel UUID which the guest uses around
+        // suspend-to-idle, and reports them through its function 0.
+        let uuid = aml::Buffer::new(LPS0_DSM_UUID.to_vec());
+        let functions = aml::Buffer::new(vec![LPS0_DSM_FUNCTIONS]);
+        let unsupported = aml::Buffer::new(vec![0]);
+        let lpse = aml::Path::new("LPSE")?;
+        let uuid_equal = aml::Equal::new(&aml::Arg(0), &uuid);
+        let query_equal = aml::Equal::new(&aml::Arg(2), &aml::ZERO);
+        let entry_equal = aml::Equal::new(&aml::Arg(2), &LPS0_DSM_ENTRY);
+        let exit_equal = aml::Equal::new(&aml::Arg(2), &LPS0_DSM_EXIT);
+        let query_return = aml::Return::new(&functions);
+        let entry_store = aml::Store::new(&lpse, &aml::ONE);
+        let exit_store = aml::Store::new(&lpse, &aml::ZERO);
+        let query_if = aml::If::new(&query_equal, vec![&query_return]);
+        let entry_if = aml::If::new(&entry_equal, vec![&entry_store]);
+        let exit_if = aml::If::new(&exit_equal, vec![&exit_store]);
+
+        aml::Device::new(
+            "_SB_.LPS0".try_into()?,
+            vec![
+                &aml::Name::new("_HID".try_into()?, &aml::EisaName::new("PNP0D80")?)?,
+                &aml::Name::new("_UID".try_into()?, &aml::ZERO)?,
+                &region,
+                &field,
+                &aml::Method::new(
+                    "_DSM".try_into()?,
+                    4,
+                    true,
+                    vec![
+                        &aml::If::new(&uuid_equal, vec![&query_if, &entry_if, &exit_if]),
+                        &aml::Return::new(&unsupported),
+                    ],
+                ),
+                &aml::Method::new(
+                    "_STA".try_into()?,
+                    0,
+                    false,
+                    vec![&aml::Return::new(&0x0fu8)],
+                ),
+            ],
+        )
+        .append_aml_bytes(v)
+    }
+}
+
+/// Logic to save/restore the state of a [`S2idle`] device.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct S2idleState {
+    mmio_addr: u64,
+    sleeping: bool,
+    residency_us: u64,
+}
+
+impl<'a> Persist<'a> for S2idle {
+    type State = S2idleState;
+    type ConstructorArgs = ();
+    type Error = Infallible;
+
+    fn save(&self) -> Self::State {
+        S2idleState {
+            mmio_addr: self.mmio_addr,
+            sleeping: self.sleeping,
+            residency_us: u64::try_from(self.residency().as_micros()).unwrap_or(u64::MAX),
+        }
+    }
+
+    fn restore(_: Self::ConstructorArgs, state: &Self::State) -> Result<Self, Self::Error> {
+        let mut s2idle = S2idle::new(state.mmio_addr);
+        s2idle.sleeping = state.sleeping;
+        s2idle.residency = Duration::from_micros(state.residency_us);
+        Ok(s2idle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_residency(s2idle: &mut S2idle) -> u64 {
+        let mut data = [0u8; 8];
+        s2idle.read(0, REG_RESIDENCY, &mut data);
+        u64::from_le_bytes(data)
+    }
+
+    fn write_sleeping(s2idle: &mut S2idle, value: u32) {
+        s2idle.write(0, REG_SLEEPING, &value.to_le_bytes());
+    }
+
+    #[test]
+    fn test_residency() {
+        let mut s2idle = S2idle::new(0xd000_0000);
+        s2idle.resume();
+        assert_eq!(read_residency(&mut s2idle), 0);
+
+        let entries = METRICS.s2idle.entries.count();
+        write_sleeping(&mut s2idle, 1);
+        assert_eq!(METRICS.s2idle.entries.count(), entries + 1);
+        std::thread::sleep(Duration::from_millis(10));
+        let residency = read_residency(&mut s2idle);
+        assert!(residency >= 10_000);
+
+        // The counter stops while the microVM is paused.
+        s2idle.pause();
+        let paused_residency = read_residency(&mut s2idle);
+        std::thread::sleep(Duration::from_millis(10));
+        assert_eq!(read_residency(&mut s2idle), paused_residency);
+        s2idle.resume();
+
+        // And once the guest left suspend-to-idle.
+        write_sleeping(&mut s2idle, 0);
+        let residency = read_residency(&mut s2idle);
+        std::thread::sleep(Duration::from_millis(10));
+        assert_eq!(read_residency(&mut s2idle), residency);
+        assert_eq!(METRICS.s2idle.entries.count(), entries + 1);
+
+        // Accesses of the wrong size are ignored.
+        s2idle.write(0, REG_SLEEPING, &[1]);
+        let mut data = [0xffu8; 4];
+        s2idle.read(0, REG_RESIDENCY, &mut data);
+        assert_eq!(data, [0; 4]);
+        s2idle.read(0, REG_SLEEPING, &mut data);
+        assert_eq!(data, [0; 4]);
+
+        // The state is restored paused.
+        let mut restored = S2idle::restore((), &s2idle.save()).unwrap();
+        assert!(restored.since.is_none());
+        assert_eq!(read_residency(&mut restored), residency);
+    }
+
+    #[test]
+    fn test_s2idle_aml() {
+        let s2idle = S2idle::new(0xd000_0000);
+        let mut aml = Vec::new();
+        s2idle.append_aml_bytes(&mut aml).unwrap();
+        assert!(aml.windows(4).any(|name| name == b"LPS0"));
+        assert!(aml.windows(16).any(|uuid| uuid == LPS0_DSM_UUID));
+
+        let lpit = acpi_tables::Lpit::new(*b"FOOBAR", *b"FOOBARLP", 0, vec![s2idle.lpit_state()]);
+        assert_eq!(acpi_tables::Sdt::len(&lpit), 36 + 56);
+    }
+}
@@ -0,0 +1,383 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::time::Duration;
+
+use acpi_tables::{Aml, aml};
+use log::{debug, error, warn};
+use serde::{Deserialize, Serialize};
+use utils::time::TimerFd;
+use vm_memory::GuestMemoryError;
+use vm_superio::Trigger;
+use vmm_sys_util::eventfd::EventFd;
+
+use super::super::legacy::EventFdTrigger;
+use crate::snapshot::Persist;
+use crate::vmm_config::thermal_zone::{ThermalZoneConfig, ThermalZoneUpdateConfig};
+use crate::vstate::memory::{Bytes, GuestAddress, GuestMemoryMmap};
+use crate::vstate::resources::ResourceAllocator;
+
+/// Bytes of memory we allocate for the thermal zone registers.
+pub const THERMAL_ZONE_MEM_SIZE: u64 = 0x4;
+/// Notification value asking the guest to evaluate the temperature again.
+pub const NOTIFY_TEMPERATURE_CHANGED: usize = 0x80;
+
+// Absolute zero, in millidegrees Celsius.
+const ABSOLUTE_ZERO_MC: i64 = -273_150;
+
+/// Errors associated with the thermal zone device.
+#[derive(Debug, thiserror::Error, displaydoc::Display)]
+pub enum ThermalZoneError {
+    /// Could not allocate the resources of the thermal zone: {0}
+    Allocator(#[from] vm_allocator::Error),
+    /// Could not open the host sensor: {0}
+    HostSensor(#[from] std::io::Error),
+    /// Could not write the temperature to guest memory: {0}
+    GuestMemory(#[from] GuestMemoryError),
+}
+
+/// Converts a temperature in millidegrees Celsius to tenths of Kelvin, the unit of ACPI.
+fn deci_kelvin(temperature_mc: i32) -> u32 {
+    u32::try_from((i64::from(temperature_mc) - ABSOLUTE_ZERO_MC) / 100).unwrap_or(0)
+}
+
+/// Thermal zone device
+///
+/// This device exposes an ACPI thermal zone to the guest, whose `_TMP` method reads the
+/// temperature from guest memory. The temperature is either read from a host hwmon sensor at a
+/// regular interval, or set through the API. The guest is notified through the GED whenever the
+/// temperature crosses one of the trip points of the thermal zone.
+#[derive(Debug)]
+pub struct ThermalZone {
+    /// Configuration of the thermal zone.
+    pub config: ThermalZoneConfig,
+    /// Current temperature, in millidegrees Celsius.
+    pub temperature_mc: i32,
+    /// Guest physical address of the registers.
+    pub guest_address: GuestAddress,
+    /// GSI number for the device.
+    pub gsi: u32,
+    /// Interrupt line for notifying the device about changes.
+    pub interrupt_evt: EventFdTrigger,
+    sensor: Option<File>,
+    timer: TimerFd,
+}
+
+impl ThermalZone {
+    /// Create the device from its registers address and GSI, opening its host sensor.
+    pub fn from_parts(
+        config: ThermalZoneConfig,
+        temperature_mc: i32,
+        guest_address: GuestAddress,
+        gsi: u32,
+    ) -> Result<Self, ThermalZoneError> {
+        debug!(
+            "thermal_zone: building device. Address: {:#010x}. IRQ: {}",
+            guest_address.0, gsi
+        );
+        let sensor = config.host_sensor.as_ref().map(File::open).transpose()?;
+        let interrupt_evt = EventFdTrigger::new(
+            EventFd::new(libc::EFD_NONBLOCK)
+                .expect("thermal_zone: Could not create EventFd for the thermal zone device"),
+        );
+        Ok(Self {
+            config,
+            temperature_mc,
+            guest_address,
+            gsi,
+            interrupt_evt,
+            sensor,
+            timer: TimerFd::new(),
+        })
+    }
+
+    /// Create the device
+    ///
+    /// Allocate memory for the registers and a GSI for the notifications.
+    pub fn new(
+        resource_allocator: &mut ResourceAllocator,
+        config: ThermalZoneConfig,
+    ) -> Result<Self, ThermalZoneError> {
+        let gsi = resource_allocator.allocate_gsi_legacy(1)?;
+        let addr = resource_allocator.allocate_system_memory(
+            THERMAL_ZONE_MEM_SIZE,
+            4,
+            vm_allocator::AllocPolicy::LastMatch,
+        )?;
+        let temperature_mc = config.temperature_mc;
+        Self::from_parts(config, temperature_mc, GuestAddress(addr), gsi[0])
+    }
+
+    // Reads the temperature of the host sensor, which hwmon reports in millidegrees Celsius.
+    fn read_sensor(&mut self) -> Option<Result<i32, std::io::Error>> {
+        let sensor = self.sensor.as_mut()?;
+        let mut value = String::new();
+        let result = sensor
+            .seek(SeekFrom::Start(0))
+            .and_then(|_| sensor.read_to_string(&mut value))
+            .and_then(|_| {
+                value
+                    .trim()
+                    .parse()
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))
+            });
+        Some(result)
+    }
+
+    // Number of trip points at or below `temperature_mc`.
+    fn trip_level(&self, temperature_mc: i32) -> usize {
+        self.config
+            .trip_points()
+            .filter(|trip_point| temperature_mc >= *trip_point)
+            .count()
+    }
+
+    /// Write the current temperature to the registers, and start reading the host sensor.
+    pub fn activate(&mut self, mem: &GuestMemoryMmap) -> Result<(), ThermalZoneError> {
+        if let Some(temperature_mc) = self.read_sensor().transpose()? {
+            self.temperature_mc = temperature_mc;
+        }
+        mem.write_obj(deci_kelvin(self.temperature_mc), self.guest_address)?;
+        if self.sensor.is_some() {
+            let interval = Duration::from_millis(self.config.poll_interval_ms);
+            self.timer.arm(interval, Some(interval));
+        }
+        Ok(())
+    }
+
+    // Updates the temperature, notifying the guest when it crossed a trip point.
+    fn set_temperature(
+        &mut self,
+        mem: &GuestMemoryMmap,
+        temperature_mc: i32,
+    ) -> Result<(), GuestMemoryError> {
+        let crossed = self.trip_level(temperature_mc) != self.trip_level(self.temperature_mc);
+        self.temperature_mc = temperature_mc;
+        mem.write_obj(deci_kelvin(temperature_mc), self.guest_address)?;
+        if crossed {
+            // The guest picks the new temperature up on its next read if it misses the
+            // notification.
+            let _ = self.notify_guest();
+        }
+        Ok(())
+    }
+
+    /// Update the temperature set through the API.
+    pub fn update(
+        &mut self,
+        mem: &GuestMemoryMmap,
+        update: &ThermalZoneUpdateConfig,
+    ) -> Result<(), GuestMemoryError> {
+        self.config.temperature_mc = update.temperature_mc;
+        self.set_temperature(mem, update.temperature_mc)
+    }
+
+    /// Read the host sensor after the timer expired.
+    pub fn process_timer(&mut self, mem: &GuestMemoryMmap) {
+        self.timer.read();
+        match self.read_sensor() {
+            Some(Ok(temperature_mc)) => {
+                if let Err(err) = self.set_temperature(mem, temperature_mc) {
+                    error!("thermal_zone: Could not write the temperature: {err}");
+                }
+            }
+            Some(Err(err)) => warn!("thermal_zone: Could not read the host sensor: {err}"),
+            None => (),
+        }
+    }
+
+    /// Send a GED notification to the guest.
+    pub fn notify_guest(&self) -> Result<(), std::io::Error> {
+        self.interrupt_evt
+            .trigger()
+            .inspect_err(|err| error!("thermal_zone: could not send guest notification: {err}"))
+    }
+}
+
+impl AsRawFd for ThermalZone {
+    fn as_raw_fd(&self) -> RawFd {
+        self.timer.as_raw_fd()
+    }
+}
+
+/// Logic to save/restore the state of the thermal zone device
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct ThermalZoneState {
+    /// Configuration of the thermal zone.
+    pub config: ThermalZoneConfig,
+    /// Current temperature, in millidegrees Celsius.
+    pub temperature_mc: i32,
+    /// Memory address of the registers.
+    pub addr: u64,
+    /// GSI used for the notifications.
+    pub gsi: u32,
+}
+
+impl<'a> Persist<'a> for ThermalZone {
+    type State = ThermalZoneState;
+    type ConstructorArgs = ();
+    type Error = ThermalZoneError;
+
+    fn save(&self) -> Self::State {
+        ThermalZoneState {
+            config: self.config.clone(),
+            temperature_mc: self.temperature_mc,
+            addr: self.guest_address.0,
+            gsi: self.gsi,
+        }
+    }
+
+    fn restore(_: Self::ConstructorArgs, state: &Self::State) -> Result<Self, Self::Error> {
+        Self::from_parts(
+            state.config.clone(),
+            state.temperature_mc,
+            GuestAddress(state.addr),
+            state.gsi,
+        )
+    }
+}
+
+impl Aml for ThermalZone {
+    fn append_aml_bytes(&self, v: &mut Vec<u8>) -> Result<(), aml::AmlError> {
+        let region = aml::OpRegion::new(
+            "TZRR".try_into()?,
+            aml::OpRegionSpace::SystemMemory,
+            usize::try_from(self.guest_address.0).unwrap(),
+            usize::try_from(THERMAL_ZONE_MEM_SIZE).unwrap(),
+        );
+        let field = aml::Field::new(
+            "TZRR".try_into()?,
+            aml::FieldAccessType::DWord,
+            aml::FieldUpdateRule::Preserve,
+            vec![aml::FieldEntry::Named(*b"TEMP", 32)],
+        );
+        let temp = aml::Path::new("TEMP")?;
+        let tmp = aml::Method::new("_TMP".try_into()?, 0, false, vec![&aml::Return::new(&temp)]);
+        // The guest doesn't need to poll the temperature, it is notified of the crossed trip
+        // points.
+        let tzp = aml::Name::new("_TZP".try_into()?, &aml::ZERO)?;
+        let hot = self
+            .config
+            .hot_mc
+            .map(|hot_mc| aml::Name::new("_HOT".try_into()?, &deci_kelvin(hot_mc)))
+            .transpose()?;
+        let crt = self
+            .config
+            .critical_mc
+            .map(|critical_mc| aml::Name::new("_CRT".try_into()?, &deci_kelvin(critical_mc)))
+            .transpose()?;
+
+        let mut children: Vec<&dyn Aml> = vec![&region, &field, &tmp, &tzp];
+        children.extend(hot.as_ref().map(|hot| hot as &dyn Aml));
+        children.extend(crt.as_ref().map(|crt| crt as &dyn Aml));
+        aml::ThermalZone::new("_TZ_.TZ00".try_into()?, children).append_aml_bytes(v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vmm_sys_util::tempfile::TempFile;
+
+    use super::*;
+    use crate::test_utils::single_region_mem;
+
+    fn temperature(mem: &GuestMemoryMmap) -> u32 {
+        mem.read_obj(GuestAddress(0x1000)).unwrap()
+    }
+
+    #[test]
+    fn test_thermal_zone_update() {
+        let mem = single_region_mem(0x10_0000);
+        let config = ThermalZoneConfig {
+            hot_mc: Some(80_000),
+            critical_mc: Some(95_000),
+            ..Default::default()
+        };
+        let mut thermal_zone =
+            ThermalZone::from_parts(config, 40_000, GuestAddress(0x1000), 5).unwrap();
+        thermal_zone.activate(&mem).unwrap();
+        assert_eq!(temperature(&mem), 3131);
+        assert!(!thermal_zone.timer.is_armed());
+
+        // Only crossing a trip point notifies the guest.
+        let update = ThermalZoneUpdateConfig {
+            temperature_mc: 60_000,
+        };
+        thermal_zone.update(&mem, &update).unwrap();
+        assert_eq!(temperature(&mem), 3331);
+        thermal_zone.interrupt_evt.read().unwrap_err();
+        let update = ThermalZoneUpdateConfig {
+            temperature_mc: 85_000,
+        };
+        thermal_zone.update(&mem, &update).unwrap();
+        assert_eq!(temperature(&mem), 3581);
+        assert_eq!(thermal_zone.interrupt_evt.read().unwrap(), 1);
+        let update = ThermalZoneUpdateConfig {
+            temperature_mc: 70_000,
+        };
+        thermal_zone.update(&mem, &update).unwrap();
+        assert_eq!(thermal_zone.interrupt_evt.read().unwrap(), 1);
+
+        let state = thermal_zone.save();
+        let restored = ThermalZone::restore((), &state).unwrap();
+        assert_eq!(restored.config, thermal_zone.config);
+        assert_eq!(restored.config.temperature_mc, 70_000);
+        assert_eq!(restored.temperature_mc, 70_000);
+        assert_eq!(restored.guest_address, thermal_zone.guest_address);
+        assert_eq!(restored.gsi, 5);
+    }
+
+    #[test]
+    fn test_thermal_zone_host_sensor() {
+        let mem = single_region_mem(0x10_0000);
+        let sensor = TempFile::new().unwrap();
+        std::fs::write(sensor.as_path(), b"45000\n").unwrap();
+        let config = ThermalZoneConfig {
+            host_sensor: Some(sensor.as_path().to_path_buf()),
+            critical_mc: Some(90_000),
+            ..Default::default()
+        };
+        let mut thermal_zone =
+            ThermalZone::from_parts(config, 40_000, GuestAddress(0x1000), 5).unwrap();
+        thermal_zone.activate(&mem).unwrap();
+        assert_eq!(thermal_zone.temperature_mc, 45_000);
+        assert_eq!(temperature(&mem), 3181);
+        assert!(thermal_zone.timer.is_armed());
+
+        std::fs::write(sensor.as_path(), b"91000\n").unwrap();
+        thermal_zone.process_timer(&mem);
+        assert_eq!(thermal_zone.temperature_mc, 91_000);
+        assert_eq!(temperature(&mem), 3641);
+        assert_eq!(thermal_zone.interrupt_evt.read().unwrap(), 1);
+
+        // The temperature is left untouched when the sensor cannot be read.
+        std::fs::write(sensor.as_path(), b"").unwrap();
+        thermal_zone.process_timer(&mem);
+        assert_eq!(thermal_zone.temperature_mc, 91_000);
+
+        let config = ThermalZoneConfig {
+            host_sensor: Some("/nonexistent/temp1_input".into()),
+            ..Default::default()
+        };
+        ThermalZone::from_parts(config, 40_000, GuestAddress(0x1000), 5).unwrap_err();
+    }
+
+    #[test]
+    fn test_thermal_zone_aml() {
+        let config = ThermalZoneConfig {
+            critical_mc: Some(95_000),
+            ..Default::default()
+        };
+        let thermal_zone =
+            ThermalZone::from_parts(config, 40_000, GuestAddress(0x1000), 5).unwrap();
+        let mut aml = Vec::new();
+        thermal_zone.append_aml_bytes(&mut aml).unwrap();
+        for name in ["TZ00", "_TMP", "_TZP", "_CRT", "TEMP"] {
+            assert!(aml.windows(4).any(|window| window == name.as_bytes()));
+        }
+        assert!(!aml.windows(4).any(|window| window == b"_HOT"));
+    }
+}
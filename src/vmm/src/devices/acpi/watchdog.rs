@@ -0,0 +1,458 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::convert::Infallible;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::sync::{Arc, Barrier};
+use std::time::{Duration, Instant};
+
+use acpi_tables::wdat::*;
+use acpi_tables::{AccessSize, AddressSpace, GenericAddressStructure, WdatEntry};
+use serde::{Deserialize, Serialize};
+use utils::time::TimerFd;
+
+use crate::persist::VmInfo;
+use crate::snapshot::Persist;
+use crate::vmm_config::watchdog::{WatchdogAction, WatchdogConfig};
+use crate::vstate::bus::BusDevice;
+
+/// Size of the MMIO region holding the watchdog registers.
+pub const WATCHDOG_MMIO_SIZE: u64 = 0x1000;
+
+// Registers of the watchdog, which are all 32 bits wide.
+// Reads 1 while the watchdog is running. Writing 1 starts it, writing 0 stops it.
+const REG_RUNNING: u64 = 0x0;
+// The count the watchdog is restarted with.
+const REG_COUNTDOWN: u64 = 0x4;
+// The remaining count before the watchdog expires. Read-only.
+const REG_CURRENT_COUNTDOWN: u64 = 0x8;
+// Writing 1 restarts the countdown. Write-only.
+const REG_PING: u64 = 0xc;
+// Reads 1 once the watchdog expired. Writing 0 clears it.
+const REG_STATUS: u64 = 0x10;
+
+/// Duration of a watchdog count, in milliseconds.
+pub const WATCHDOG_PERIOD_MS: u32 = 1000;
+/// Minimum count the guest can set.
+pub const WATCHDOG_MIN_COUNT: u32 = 1;
+/// Maximum count the guest can set.
+pub const WATCHDOG_MAX_COUNT: u32 = 3600;
+// The count the watchdog starts with, until the guest sets its own.
+const WATCHDOG_DEFAULT_COUNT: u32 = 30;
+
+/// Status of the watchdog, as reported by the API.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct WatchdogStatus {
+    /// Action taken when the watchdog expires.
+    pub action: WatchdogAction,
+    /// Whether the guest started the watchdog.
+    pub running: bool,
+    /// Timeout set by the guest, in seconds.
+    pub timeout_s: u32,
+    /// Number of times the watchdog expired.
+    pub expirations: u64,
+}
+
+/// Watchdog device
+///
+/// This device emulates a watchdog described to the guest through the WDAT ACPI table, so that
+/// the generic driver of the guest (`wdat_wdt` on Linux) can use it. Its countdown stops while
+/// the microVM is paused. When the guest lets it expire, the VMM takes the configured action.
+#[derive(Debug)]
+pub struct Watchdog {
+    /// Guest address of the watchdog registers.
+    pub mmio_addr: u64,
+    /// Configuration of the watchdog.
+    pub config: WatchdogConfig,
+    /// Information on the microVM, for the snapshots taken when the watchdog expires.
+    pub vm_info: VmInfo,
+    running: bool,
+    paused: bool,
+    countdown: u32,
+    status: bool,
+    expirations: u64,
+    /// When the watchdog expires, while it is running and the microVM is not paused.
+    deadline: Option<Instant>,
+    /// Remaining time before the watchdog expires, while there is no deadline.
+    remaining: Duration,
+    timer: TimerFd,
+}
+
+impl Watchdog {
+    /// Create a new [`Watchdog`] device, whose registers are at `mmio_addr`.
+    ///
+    /// The device starts as if the microVM was paused, until [`Watchdog::resume`] is called.
+    pub fn new(mmio_addr: u64, config: WatchdogConfig, vm_info: VmInfo) -> Watchdog {
+        Watchdog {
+            mmio_addr,
+            config,
+            vm_info,
+            running: false,
+            paused: true,
+            countdown: WATCHDOG_DEFAULT_COUNT,
+            status: false,
+            expirations: 0,
+            deadline: None,
+            remaining: Duration::ZERO,
+            timer: TimerFd::new(),
+        }
+    }
+
+    fn period(&self) -> Duration {
+        Duration::from_millis(u64::from(WATCHDOG_PERIOD_MS) * u64::from(self.countdown))
+    }
+
+    fn remaining(&self) -> Duration {
+        self.deadline.map_or(self.remaining, |deadline| {
+            deadline.saturating_duration_since(Instant::now())
+        })
+    }
+
+    fn update_timer(&mut self, remaining: Duration) {
+        self.remaining = remaining;
+        if self.running && !self.paused {
+            self.deadline = Some(Instant::now() + remaining);
+            self.timer.arm(remaining, None);
+        } else {
+            self.deadline = None;
+            self.timer.arm(Duration::ZERO, None);
+        }
+    }
+
+    /// Stops the countdown while the microVM is paused.
+    pub fn pause(&mut self) {
+        let remaining = self.remaining();
+        self.paused = true;
+        self.update_timer(remaining);
+    }
+
+    /// Resumes the countdown along with the microVM.
+    pub fn resume(&mut self) {
+        let remaining = self.remaining();
+        self.paused = false;
+        self.update_timer(remaining);
+    }
+
+    /// Handles an event on the timer, returning whether the watchdog expired.
+    ///
+    /// The countdown restarts on expiry, so that the expiry is reported again if the guest
+    /// stays unresponsive.
+    pub fn process_timer(&mut self) -> bool {
+        // The timer was re-armed since it fired if there is nothing to read.
+        if self.timer.read() == 0 || self.deadline.is_none() {
+            return false;
+        }
+        self.status = true;
+        self.expirations += 1;
+        self.update_timer(self.period());
+        true
+    }
+
+    /// Returns the status of the watchdog.
+    pub fn status(&self) -> WatchdogStatus {
+        WatchdogStatus {
+            action: self.config.action,
+            running: self.running,
+            timeout_s: self.countdown * WATCHDOG_PERIOD_MS / 1000,
+            expirations: self.expirations,
+        }
+    }
+
+    /// Returns the WDAT entries describing the watchdog actions to the guest.
+    pub fn wdat_entries(&self) -> Vec<WdatEntry> {
+        let register = |offset| {
+            GenericAddressStructure::new(
+                AddressSpace::SystemMemory,
+                32,
+                0,
+                AccessSize::DWord,
+                self.mmio_addr + offset,
+            )
+            .unwrap()
+        };
+        [
+            (
+                WDAT_ACTION_RESET,
+                WDAT_INSTRUCTION_WRITE_VALUE,
+                REG_PING,
+                1,
+                1,
+            ),
+            (
+                WDAT_ACTION_QUERY_CURRENT_COUNTDOWN_PERIOD,
+                WDAT_INSTRUCTION_READ_COUNTDOWN,
+                REG_CURRENT_COUNTDOWN,
+                0,
+                u32::MAX,
+            ),
+            (
+                WDAT_ACTION_QUERY_COUNTDOWN_PERIOD,
+                WDAT_INSTRUCTION_READ_COUNTDOWN,
+                REG_COUNTDOWN,
+                0,
+                u32::MAX,
+            ),
+            (
+                WDAT_ACTION_SET_COUNTDOWN_PERIOD,
+                WDAT_INSTRUCTION_WRITE_COUNTDOWN,
+                REG_COUNTDOWN,
+                0,
+                u32::MAX,
+            ),
+            (
+                WDAT_ACTION_QUERY_RUNNING_STATE,
+                WDAT_INSTRUCTION_READ_VALUE,
+                REG_RUNNING,
+                1,
+                1,
+            ),
+            (
+                WDAT_ACTION_SET_RUNNING_STATE,
+                WDAT_INSTRUCTION_WRITE_VALUE,
+                REG_RUNNING,
+                1,
+                1,
+            ),
+            (
+                WDAT_ACTION_QUERY_STOPPED_STATE,
+                WDAT_INSTRUCTION_READ_VALUE,
+                REG_RUNNING,
+                0,
+                1,
+            ),
+            (
+                WDAT_ACTION_SET_STOPPED_STATE,
+                WDAT_INSTRUCTION_WRITE_VALUE,
+                REG_RUNNING,
+                0,
+                1,
+            ),
+            (
+                WDAT_ACTION_QUERY_WATCHDOG_STATUS,
+                WDAT_INSTRUCTION_READ_VALUE,
+                REG_STATUS,
+                1,
+                1,
+            ),
+            (
+                WDAT_ACTION_SET_WATCHDOG_STATUS,
+                WDAT_INSTRUCTION_WRITE_VALUE,
+                REG_STATUS,
+                0,
+                1,
+            ),
+        ]
+        .into_iter()
+        .map(|(action, instruction, offset, value, mask)| {
+            WdatEntry::new(action, instruction, register(offset), value, mask)
+        })
+        .collect()
+    }
+}
+
+impl AsRawFd for Watchdog {
+    fn as_raw_fd(&self) -> RawFd {
+        self.timer.as_raw_fd()
+    }
+}
+
+impl BusDevice for Watchdog {
+    fn read(&mut self, _base: u64, offset: u64, data: &mut [u8]) {
+        if data.len() != 4 {
+            return;
+        }
+        let value = match offset {
+            REG_RUNNING => u32::from(self.running),
+            REG_COUNTDOWN => self.countdown,
+            REG_CURRENT_COUNTDOWN if self.running => {
+                let period = Duration::from_millis(u64::from(WATCHDOG_PERIOD_MS));
+                let counts = self.remaining().as_nanos().div_ceil(period.as_nanos());
+                u32::try_from(counts).unwrap_or(u32::MAX)
+            }
+            REG_STATUS => u32::from(self.status),
+            _ => 0,
+        };
+        data.copy_from_slice(&value.to_le_bytes());
+    }
+
+    fn write(&mut self, _base: u64, offset: u64, data: &[u8]) -> Option<Arc<Barrier>> {
+        let Ok(data) = <[u8; 4]>::try_from(data) else {
+            return None;
+        };
+        let value = u32::from_le_bytes(data);
+        match offset {
+            REG_RUNNING => {
+                let running = value & 1 != 0;
+                if running != self.running {
+                    self.running = running;
+                    self.update_timer(self.period());
+                }
+            }
+            REG_COUNTDOWN => {
+                self.countdown = value.clamp(WATCHDOG_MIN_COUNT, WATCHDOG_MAX_COUNT);
+            }
+            REG_PING if self.running => self.update_timer(self.period()),
+            REG_STATUS => self.status = value & 1 != 0,
+            _ => (),
+        }
+        None
+    }
+}
+
+/// Logic to save/restore the state of a [`Watchdog`] device.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct WatchdogState {
+    mmio_addr: u64,
+    config: WatchdogConfig,
+    vm_info: VmInfo,
+    running: bool,
+    countdown: u32,
+    status: bool,
+    expirations: u64,
+    remaining_ms: u64,
+}
+
+impl<'a> Persist<'a> for Watchdog {
+    type State = WatchdogState;
+    type ConstructorArgs = ();
+    type Error = Infallible;
+
+    fn save(&self) -> Self::State {
+        WatchdogState {
+            mmio_addr: self.mmio_addr,
+            config: self.config.clone(),
+            vm_info: self.vm_info.clone(),
+            running: self.running,
+            countdown: self.countdown,
+            status: self.status,
+            expirations: self.expirations,
+            remaining_ms: u64::try_from(self.remaining().as_millis()).unwrap(),
+        }
+    }
+
+    fn restore(_: Self::ConstructorArgs, state: &Self::State) -> Result<Self, Self::Error> {
+        let mut watchdog =
+            Watchdog::new(state.mmio_addr, state.config.clone(), state.vm_info.clone());
+        watchdog.running = state.running;
+        watchdog.countdown = state.countdown;
+        watchdog.status = state.status;
+        watchdog.expirations = state.expirations;
+        watchdog.remaining = Duration::from_millis(state.remaining_ms);
+        Ok(watchdog)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_reg(watchdog: &mut Watchdog, offset: u64) -> u32 {
+        let mut data = [0u8; 4];
+        watchdog.read(0, offset, &mut data);
+        u32::from_le_bytes(data)
+    }
+
+    fn write_reg(watchdog: &mut Watchdog, offset: u64, value: u32) {
+        watchdog.write(0, offset, &value.to_le_bytes());
+    }
+
+    #[test]
+    fn test_registers() {
+        let mut watchdog = Watchdog::new(0xd000_0000, WatchdogConfig::default(), VmInfo::default());
+        watchdog.resume();
+        assert_eq!(read_reg(&mut watchdog, REG_RUNNING), 0);
+        assert_eq!(
+            read_reg(&mut watchdog, REG_COUNTDOWN),
+            WATCHDOG_DEFAULT_COUNT
+        );
+        assert_eq!(read_reg(&mut watchdog, REG_CURRENT_COUNTDOWN), 0);
+        assert!(!watchdog.timer.is_armed());
+
+        // The countdown is clamped to the supported range.
+        write_reg(&mut watchdog, REG_COUNTDOWN, 0);
+        assert_eq!(read_reg(&mut watchdog, REG_COUNTDOWN), WATCHDOG_MIN_COUNT);
+        write_reg(&mut watchdog, REG_COUNTDOWN, 100_000);
+        assert_eq!(read_reg(&mut watchdog, REG_COUNTDOWN), WATCHDOG_MAX_COUNT);
+        write_reg(&mut watchdog, REG_COUNTDOWN, 60);
+
+        // Pings are ignored until the watchdog is started.
+        write_reg(&mut watchdog, REG_PING, 1);
+        assert!(!watchdog.timer.is_armed());
+        write_reg(&mut watchdog, REG_RUNNING, 1);
+        assert_eq!(read_reg(&mut watchdog, REG_RUNNING), 1);
+        assert_eq!(read_reg(&mut watchdog, REG_CURRENT_COUNTDOWN), 60);
+        assert!(watchdog.timer.is_armed());
+
+        // The countdown stops while the microVM is paused.
+        watchdog.pause();
+        assert!(!watchdog.timer.is_armed());
+        assert_eq!(read_reg(&mut watchdog, REG_CURRENT_COUNTDOWN), 60);
+        watchdog.resume();
+        assert!(watchdog.timer.is_armed());
+
+        write_reg(&mut watchdog, REG_RUNNING, 0);
+        assert!(!watchdog.timer.is_armed());
+        assert_eq!(
+            watchdog.status(),
+            WatchdogStatus {
+                action: WatchdogAction::Reset,
+                running: false,
+                timeout_s: 60,
+                expirations: 0,
+            }
+        );
+
+        // Accesses other than dword ones are ignored.
+        watchdog.write(0, REG_RUNNING, &[1]);
+        assert_eq!(read_reg(&mut watchdog, REG_RUNNING), 0);
+    }
+
+    #[test]
+    fn test_expiry() {
+        let mut watchdog = Watchdog::new(0xd000_0000, WatchdogConfig::default(), VmInfo::default());
+        watchdog.resume();
+        // Nothing expired yet.
+        assert!(!watchdog.process_timer());
+
+        write_reg(&mut watchdog, REG_RUNNING, 1);
+        // Make the timer fire right away.
+        watchdog.update_timer(Duration::from_millis(1));
+        std::thread::sleep(Duration::from_millis(10));
+        assert!(watchdog.process_timer());
+        assert_eq!(read_reg(&mut watchdog, REG_STATUS), 1);
+        assert_eq!(watchdog.status().expirations, 1);
+        // The countdown restarted.
+        assert!(watchdog.timer.is_armed());
+        assert_eq!(
+            read_reg(&mut watchdog, REG_CURRENT_COUNTDOWN),
+            WATCHDOG_DEFAULT_COUNT
+        );
+
+        // The guest clears the status once it has seen it.
+        write_reg(&mut watchdog, REG_STATUS, 0);
+        assert_eq!(read_reg(&mut watchdog, REG_STATUS), 0);
+
+        // The state is restored paused.
+        let restored = Watchdog::restore((), &watchdog.save()).unwrap();
+        assert!(restored.running);
+        assert!(!restored.timer.is_armed());
+        assert_eq!(restored.status().expirations, 1);
+    }
+
+    #[test]
+    fn test_wdat_entries() {
+        let watchdog = Watchdog::new(0xd000_0000, WatchdogConfig::default(), VmInfo::default());
+        let entries = watchdog.wdat_entries();
+        assert_eq!(entries.len(), 10);
+        let wdat = acpi_tables::Wdat::new(
+            *b"FOOBAR",
+            *b"FOOBARWD",
+            0,
+            WATCHDOG_PERIOD_MS,
+            WATCHDOG_MIN_COUNT,
+            WATCHDOG_MAX_COUNT,
+            entries,
+        );
+        assert_eq!(acpi_tables::Sdt::len(&wdat), 36 + 32 + 10 * 24);
+    }
+}
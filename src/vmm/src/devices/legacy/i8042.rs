@@ -258,8 +258,9 @@ impl BusDevice for I8042Device {
         match offset {
             OFS_STATUS if data[0] == CMD_RESET_CPU => {
                 // The guest wants to assert the CPU reset line. We handle that by triggering
-                // our exit event fd. Meaning clawdbox will be exiting as soon as the VMM
-                // thread wakes up to handle this event.
+                // our exit event fd. Meaning clawdbox will be resetting the microVM or exiting,
+                // depending on the reboot policy, as soon as the VMM thread wakes up to handle
+                // this event.
                 if let Err(err) = self.reset_evt.write(1) {
                     error!("Failed to trigger i8042 reset event: {:?}", err);
                     METRICS.error_count.inc();
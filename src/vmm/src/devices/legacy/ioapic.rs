@@ -0,0 +1,342 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::{Arc, Barrier};
+
+use kvm_bindings::kvm_msi;
+use vmm_sys_util::eventfd::EventFd;
+
+use crate::Vm;
+use crate::devices::legacy::iommu::remappable_msi_address;
+use crate::logger::error;
+use crate::vstate::bus::BusDevice;
+use crate::vstate::interrupts::MsixVectorConfig;
+
+/// Number of pins of the IOAPIC, one per legacy GSI.
+pub const IOAPIC_NUM_PINS: usize = 24;
+/// Size of the MMIO region holding the IOAPIC registers.
+pub const IOAPIC_MMIO_SIZE: u64 = 0x1000;
+
+// The guest selects a register by writing its index to IOREGSEL, then accesses it through IOWIN.
+const IOREGSEL: u64 = 0x00;
+const IOWIN: u64 = 0x10;
+
+// Register indexes.
+const IOAPICID: u32 = 0x00;
+const IOAPICVER: u32 = 0x01;
+const IOAPICARB: u32 = 0x02;
+const IOREDTBL: u32 = 0x10;
+const IOREDTBL_END: u32 = IOREDTBL + 2 * IOAPIC_NUM_PINS as u32;
+
+// Version 0x11, as the IOAPIC of KVM, with the index of the last redirection table entry.
+const IOAPIC_VERSION: u32 = 0x11 | ((IOAPIC_NUM_PINS as u32 - 1) << 16);
+const IOAPIC_ID_MASK: u32 = 0x0f00_0000;
+
+// Redirection table entry fields. The vector and the delivery mode are at the same place as in
+// the data of an MSI message.
+const RTE_VECTOR_DELIVERY_MODE: u64 = 0x7ff;
+const RTE_VECTOR: u64 = 0xff;
+const RTE_DEST_MODE_LOGICAL: u64 = 1 << 11;
+const RTE_DELIVERY_STATUS: u64 = 1 << 12;
+const RTE_REMOTE_IRR: u64 = 1 << 14;
+const RTE_TRIGGER_LEVEL: u64 = 1 << 15;
+const RTE_MASKED: u64 = 1 << 16;
+const RTE_DEST_SHIFT: u64 = 56;
+// A remappable entry holds the handle of its interrupt remapping table entry instead of its
+// destination, with bit 15 of the handle in place of the destination mode.
+const RTE_REMAPPABLE: u64 = 1 << 48;
+const RTE_HANDLE_SHIFT: u64 = 49;
+// Fields only the IOAPIC updates.
+const RTE_READ_ONLY: u64 = RTE_DELIVERY_STATUS | RTE_REMOTE_IRR;
+
+// MSI message fields, see the Intel SDM, Vol. 3A, 11.11 "Message Signalled Interrupts".
+const MSI_ADDRESS: u32 = 0xfee0_0000;
+const MSI_ADDRESS_DEST_SHIFT: u32 = 12;
+const MSI_ADDRESS_DEST_MODE_LOGICAL: u32 = 1 << 2;
+const MSI_DATA_LEVEL_ASSERT: u32 = 1 << 14;
+const MSI_DATA_TRIGGER_LEVEL: u32 = 1 << 15;
+
+/// Returns the MSI message delivering the interrupt a redirection table entry describes.
+///
+/// The message of a remappable entry is in remappable format, and carries the handle of the entry.
+fn msi_message(rte: u64) -> MsixVectorConfig {
+    if rte & RTE_REMAPPABLE != 0 {
+        let mut handle = u16::try_from(rte >> RTE_HANDLE_SHIFT).unwrap();
+        if rte & RTE_DEST_MODE_LOGICAL != 0 {
+            handle |= 1 << 15;
+        }
+        return MsixVectorConfig {
+            low_addr: remappable_msi_address(handle),
+            ..Default::default()
+        };
+    }
+    let dest = u32::try_from(rte >> RTE_DEST_SHIFT).unwrap();
+    let mut low_addr = MSI_ADDRESS | (dest << MSI_ADDRESS_DEST_SHIFT);
+    if rte & RTE_DEST_MODE_LOGICAL != 0 {
+        low_addr |= MSI_ADDRESS_DEST_MODE_LOGICAL;
+    }
+    let mut data = u32::try_from(rte & RTE_VECTOR_DELIVERY_MODE).unwrap();
+    // KVM tracks the end of the interrupts whose message is level-triggered.
+    if rte & RTE_TRIGGER_LEVEL != 0 {
+        data |= MSI_DATA_TRIGGER_LEVEL | MSI_DATA_LEVEL_ASSERT;
+    }
+    MsixVectorConfig {
+        high_addr: 0,
+        low_addr,
+        data,
+        devid: 0,
+    }
+}
+
+/// IOAPIC emulated in userspace
+///
+/// With a split irqchip, KVM only emulates the local APICs and this device emulates the IOAPIC
+/// the legacy interrupts go through. Every unmasked pin is routed to the MSI message its
+/// redirection table entry describes, so that the interrupts devices signal through irqfds reach
+/// the guest without leaving KVM. When the guest enables interrupt remapping, the messages of
+/// remappable entries are translated like the ones of the MSI capable devices.
+///
+/// KVM exits to userspace when the guest acknowledges the vector of a level-triggered pin. The
+/// IOAPIC then clears the remote IRR of the pin, notifies its resample event and delivers the
+/// interrupt again if the line is still asserted.
+#[derive(Debug)]
+pub struct Ioapic {
+    vm: Arc<Vm>,
+    id: u32,
+    reg_sel: u32,
+    redirection_table: [u64; IOAPIC_NUM_PINS],
+    /// Lines asserted through [`Ioapic::set_irq`], one bit per pin.
+    asserted: u32,
+    /// Notified when the guest acknowledges the level-triggered interrupt of a pin.
+    resample_evts: [Option<EventFd>; IOAPIC_NUM_PINS],
+}
+
+impl Ioapic {
+    /// Create a new [`Ioapic`], with all its pins masked.
+    pub fn new(vm: Arc<Vm>) -> Ioapic {
+        Ioapic {
+            vm,
+            id: 0,
+            reg_sel: 0,
+            redirection_table: [RTE_MASKED; IOAPIC_NUM_PINS],
+            asserted: 0,
+            resample_evts: std::array::from_fn(|_| None),
+        }
+    }
+
+    /// Notifies `evt` whenever the guest acknowledges the level-triggered interrupt of `pin`.
+    pub fn set_resample_evt(&mut self, pin: usize, evt: EventFd) {
+        self.resample_evts[pin] = Some(evt);
+    }
+
+    /// Asserts or deasserts the line of `pin`.
+    ///
+    /// An edge-triggered pin delivers its interrupt when its line gets asserted. A level-triggered
+    /// pin delivers it as long as its line is asserted, every time the guest acknowledges it.
+    pub fn set_irq(&mut self, pin: usize, asserted: bool) {
+        let bit = 1 << pin;
+        if !asserted {
+            self.asserted &= !bit;
+            return;
+        }
+        let rising = self.asserted & bit == 0;
+        self.asserted |= bit;
+        if rising || self.redirection_table[pin] & RTE_TRIGGER_LEVEL != 0 {
+            self.deliver(pin);
+        }
+    }
+
+    /// Handles the acknowledgement of `vector` by the guest.
+    pub fn end_of_interrupt(&mut self, vector: u8) {
+        for pin in 0..IOAPIC_NUM_PINS {
+            let rte = self.redirection_table[pin];
+            if rte & RTE_VECTOR != u64::from(vector) || rte & RTE_TRIGGER_LEVEL == 0 {
+                continue;
+            }
+            self.redirection_table[pin] &= !RTE_REMOTE_IRR;
+            if let Some(evt) = &self.resample_evts[pin]
+                && let Err(err) = evt.write(1)
+            {
+                error!("ioapic: Failed to notify the end of interrupt of pin {pin}: {err}");
+            }
+            if self.asserted & (1 << pin) != 0 {
+                self.deliver(pin);
+            }
+        }
+    }
+
+    fn deliver(&mut self, pin: usize) {
+        let rte = self.redirection_table[pin];
+        if rte & (RTE_MASKED | RTE_REMOTE_IRR) != 0 {
+            return;
+        }
+        let Some(config) = self.vm.remap_msi(msi_message(rte)) else {
+            return;
+        };
+        let msi = kvm_msi {
+            address_lo: config.low_addr,
+            address_hi: config.high_addr,
+            data: config.data,
+            ..Default::default()
+        };
+        match self.vm.fd().signal_msi(msi) {
+            Ok(_) if rte & RTE_TRIGGER_LEVEL != 0 => {
+                self.redirection_table[pin] |= RTE_REMOTE_IRR;
+            }
+            Ok(_) => (),
+            Err(err) => error!("ioapic: Failed to deliver the interrupt of pin {pin}: {err}"),
+        }
+    }
+
+    fn read_register(&self) -> u32 {
+        match self.reg_sel {
+            IOAPICID | IOAPICARB => self.id,
+            IOAPICVER => IOAPIC_VERSION,
+            IOREDTBL..IOREDTBL_END => {
+                let index = (self.reg_sel - IOREDTBL) as usize;
+                let rte = self.redirection_table[index / 2];
+                if index % 2 == 0 {
+                    rte as u32
+                } else {
+                    (rte >> 32) as u32
+                }
+            }
+            _ => 0,
+        }
+    }
+
+    fn write_register(&mut self, value: u32) {
+        match self.reg_sel {
+            IOAPICID => self.id = value & IOAPIC_ID_MASK,
+            IOREDTBL..IOREDTBL_END => {
+                let index = (self.reg_sel - IOREDTBL) as usize;
+                let pin = index / 2;
+                let (value, written) = if index % 2 == 0 {
+                    (u64::from(value), u64::from(u32::MAX))
+                } else {
+                    (u64::from(value) << 32, u64::from(u32::MAX) << 32)
+                };
+                let writable = written & !RTE_READ_ONLY;
+                let mut rte = (self.redirection_table[pin] & !writable) | (value & writable);
+                // Only level-triggered interrupts wait for their acknowledgement.
+                if rte & RTE_TRIGGER_LEVEL == 0 {
+                    rte &= !RTE_REMOTE_IRR;
+                }
+                self.redirection_table[pin] = rte;
+
+                let config = (rte & RTE_MASKED == 0).then(|| msi_message(rte));
+                if let Err(err) = self
+                    .vm
+                    .route_ioapic_pin(u32::try_from(pin).unwrap(), config)
+                {
+                    error!("ioapic: Failed to route pin {pin}: {err}");
+                }
+                // A level-triggered line asserted while its pin was masked is delivered now.
+                if self.asserted & (1 << pin) != 0 && rte & RTE_TRIGGER_LEVEL != 0 {
+                    self.deliver(pin);
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+impl BusDevice for Ioapic {
+    fn read(&mut self, _base: u64, offset: u64, data: &mut [u8]) {
+        let value = match offset {
+            IOREGSEL => self.reg_sel,
+            IOWIN => self.read_register(),
+            _ => 0,
+        };
+        match <&mut [u8; 4]>::try_from(data) {
+            Ok(data) => *data = value.to_le_bytes(),
+            Err(_) => data.fill(0),
+        }
+    }
+
+    fn write(&mut self, _base: u64, offset: u64, data: &[u8]) -> Option<Arc<Barrier>> {
+        let Ok(data) = <[u8; 4]>::try_from(data) else {
+            return None;
+        };
+        let value = u32::from_le_bytes(data);
+        match offset {
+            IOREGSEL => self.reg_sel = value & 0xff,
+            IOWIN => self.write_register(value),
+            _ => (),
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vstate::vm::tests::setup_vm_with_memory;
+
+    fn write_register(ioapic: &mut Ioapic, reg: u32, value: u32) {
+        ioapic.write(0, IOREGSEL, &reg.to_le_bytes());
+        ioapic.write(0, IOWIN, &value.to_le_bytes());
+    }
+
+    fn read_register(ioapic: &mut Ioapic, reg: u32) -> u32 {
+        let mut data = [0u8; 4];
+        ioapic.write(0, IOREGSEL, &reg.to_le_bytes());
+        ioapic.read(0, IOWIN, &mut data);
+        u32::from_le_bytes(data)
+    }
+
+    #[test]
+    fn test_msi_message() {
+        // Vector 0x30, fixed delivery, physical destination 2, edge-triggered.
+        let config = msi_message(0x0200_0000_0000_0030);
+        assert_eq!(config.low_addr, 0xfee0_2000);
+        assert_eq!(config.high_addr, 0);
+        assert_eq!(config.data, 0x30);
+
+        // Vector 0x41, lowest priority delivery, logical destination 0xf, level-triggered.
+        let config = msi_message(0x0f00_0000_0000_8941);
+        assert_eq!(config.low_addr, 0xfee0_f004);
+        assert_eq!(config.data, 0xc141);
+
+        // Remappable entry with handle 0x8003, whose bit 15 is in place of the destination mode.
+        let config = msi_message(0x0007_0000_0000_0830);
+        assert_eq!(config.low_addr, 0xfee0_0074);
+        assert_eq!(config.high_addr, 0);
+    }
+
+    #[test]
+    fn test_ioapic_registers() {
+        let (_, mut vm) = setup_vm_with_memory(0x1000);
+        vm.set_split_irqchip(true);
+        // The interrupts are delivered to the local APIC of the vCPU.
+        let (_vcpus, _) = vm.create_vcpus(1).unwrap();
+        let mut ioapic = Ioapic::new(Arc::new(vm));
+
+        assert_eq!(read_register(&mut ioapic, IOAPICVER), 0x17_0011);
+        write_register(&mut ioapic, IOAPICID, 0xffff_ffff);
+        assert_eq!(read_register(&mut ioapic, IOAPICID), IOAPIC_ID_MASK);
+
+        // All the pins start masked.
+        assert_eq!(read_register(&mut ioapic, IOREDTBL), 1 << 16);
+        // Route pin 4 to vector 0x30 of the vCPU, level-triggered.
+        write_register(&mut ioapic, IOREDTBL + 8, 0xc030);
+        assert_eq!(ioapic.redirection_table[4], 0x8030);
+        assert!(ioapic.vm.common.interrupts.lock().unwrap().contains_key(&4));
+
+        // The remote IRR is set while the guest has not acknowledged the interrupt.
+        ioapic.set_resample_evt(4, EventFd::new(libc::EFD_NONBLOCK).unwrap());
+        ioapic.set_irq(4, true);
+        assert_ne!(read_register(&mut ioapic, IOREDTBL + 8) & (1 << 14), 0);
+        // The guest cannot clear it.
+        write_register(&mut ioapic, IOREDTBL + 8, 0x8030);
+        assert_ne!(read_register(&mut ioapic, IOREDTBL + 8) & (1 << 14), 0);
+        ioapic.set_irq(4, false);
+        ioapic.end_of_interrupt(0x30);
+        assert_eq!(read_register(&mut ioapic, IOREDTBL + 8) & (1 << 14), 0);
+        assert_eq!(ioapic.resample_evts[4].as_ref().unwrap().read().unwrap(), 1);
+
+        // Masking the pin removes its route.
+        write_register(&mut ioapic, IOREDTBL + 8, 0x1_8030);
+        assert!(!ioapic.vm.common.interrupts.lock().unwrap().contains_key(&4));
+    }
+}
@@ -0,0 +1,561 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::{Arc, Barrier};
+
+use crate::Vm;
+use crate::logger::error;
+use crate::vstate::bus::BusDevice;
+use crate::vstate::interrupts::MsixVectorConfig;
+use crate::vstate::memory::{Address, Bytes, GuestAddress, GuestMemoryMmap};
+
+/// Size of the MMIO region holding the registers of the remapping unit.
+pub const IOMMU_MMIO_SIZE: u64 = 0x1000;
+/// Width of the guest physical addresses the remapping unit reports, in bits.
+pub const IOMMU_ADDRESS_WIDTH: u8 = 39;
+
+// Register offsets, see the Intel VT-d specification, 11.4 "Register Descriptions". The 32-bit
+// registers are accessed as halves of the 64-bit aligned words holding them.
+const VER: u64 = 0x00;
+const CAP: u64 = 0x08;
+const ECAP: u64 = 0x10;
+// Global command register, then global status register.
+const GCMD_GSTS: u64 = 0x18;
+// Fault status register, in the upper half.
+const FSTS: u64 = 0x30;
+const IQH: u64 = 0x80;
+const IQT: u64 = 0x88;
+const IQA: u64 = 0x90;
+// Invalidation completion status register, in the upper half.
+const ICS: u64 = 0x98;
+const IRTA: u64 = 0xb8;
+// The IOTLB registers and the fault recording registers come after the architectural ones. They
+// read as zero: invalidations complete at once, and faults are not recorded.
+const IOTLB_REGISTERS: u64 = 0x200;
+const FAULT_RECORDING_REGISTERS: u64 = 0x220;
+
+// Version 1.0.
+const VERSION: u64 = 0x10;
+// No second-level translation is supported, so that guests only use the unit for interrupt
+// remapping.
+const CAPABILITIES: u64 =
+    ((IOMMU_ADDRESS_WIDTH as u64 - 1) << 16) | ((FAULT_RECORDING_REGISTERS / 16) << 24);
+const ECAP_COHERENT: u64 = 1 << 0;
+const ECAP_QUEUED_INVALIDATION: u64 = 1 << 1;
+const ECAP_INTERRUPT_REMAPPING: u64 = 1 << 3;
+const ECAP_EXTENDED_INTERRUPT_MODE: u64 = 1 << 4;
+const EXTENDED_CAPABILITIES: u64 = ECAP_COHERENT
+    | ECAP_QUEUED_INVALIDATION
+    | ECAP_INTERRUPT_REMAPPING
+    | ECAP_EXTENDED_INTERRUPT_MODE
+    | ((IOTLB_REGISTERS / 16) << 8);
+
+// Commands, and the status bits reporting them at the same place.
+const GCMD_QUEUED_INVALIDATION: u32 = 1 << 26;
+const GCMD_INTERRUPT_REMAPPING: u32 = 1 << 25;
+const GCMD_SET_INTERRUPT_TABLE: u32 = 1 << 24;
+const GCMD_COMPATIBILITY_FORMAT: u32 = 1 << 23;
+const GCMD_ENABLES: u32 =
+    GCMD_QUEUED_INVALIDATION | GCMD_INTERRUPT_REMAPPING | GCMD_COMPATIBILITY_FORMAT;
+
+// Invalidation queue error, and invalidation wait descriptor complete. Both are cleared by
+// writing 1 to them.
+const FSTS_QUEUE_ERROR: u32 = 1 << 4;
+const ICS_WAIT_COMPLETE: u32 = 1 << 0;
+
+const PAGE_MASK: u64 = !0xfff;
+// The queue is made of 2^QS pages of 128-bit descriptors, and its head and tail are the offsets
+// of descriptors in it.
+const IQA_SIZE: u64 = 0x7;
+const IQ_OFFSET_MASK: u64 = 0x7fff0;
+const DESCRIPTOR_SIZE: u64 = 16;
+const DESCRIPTOR_TYPE: u64 = 0xf;
+const DESCRIPTOR_CONTEXT_CACHE: u64 = 0x1;
+const DESCRIPTOR_IOTLB: u64 = 0x2;
+const DESCRIPTOR_DEVICE_TLB: u64 = 0x3;
+const DESCRIPTOR_INTERRUPT_ENTRY_CACHE: u64 = 0x4;
+const DESCRIPTOR_WAIT: u64 = 0x5;
+const WAIT_INTERRUPT: u64 = 1 << 4;
+const WAIT_STATUS_WRITE: u64 = 1 << 5;
+const WAIT_STATUS_ADDRESS: u64 = !0x3;
+
+// The table holds 2^(S+1) entries, whose destination is an x2APIC ID in extended interrupt mode.
+const IRTA_EXTENDED_INTERRUPT_MODE: u64 = 1 << 11;
+const IRTA_SIZE: u64 = 0xf;
+const IRTA_WRITABLE: u64 = PAGE_MASK | IRTA_EXTENDED_INTERRUPT_MODE | IRTA_SIZE;
+
+// Interrupt remapping table entry fields, see the Intel VT-d specification, 9.10 "Interrupt
+// Remapping Table Entry (IRTE) for Remapped Interrupts".
+const IRTE_SIZE: u64 = 16;
+const IRTE_PRESENT: u64 = 1 << 0;
+const IRTE_DEST_MODE_LOGICAL: u64 = 1 << 2;
+const IRTE_REDIRECTION_HINT: u64 = 1 << 3;
+const IRTE_TRIGGER_LEVEL: u64 = 1 << 4;
+const IRTE_DELIVERY_MODE: u64 = 0x7 << 5;
+const IRTE_POSTED: u64 = 1 << 15;
+const IRTE_VECTOR_SHIFT: u64 = 16;
+const IRTE_DEST_SHIFT: u64 = 32;
+
+// MSI message fields, see the Intel SDM, Vol. 3A, 11.11 "Message Signalled Interrupts", and the
+// Intel VT-d specification, 5.1.2.2 "Interrupt Requests in Remappable Format".
+const MSI_ADDRESS: u32 = 0xfee0_0000;
+const MSI_ADDRESS_DEST_SHIFT: u32 = 12;
+const MSI_ADDRESS_REDIRECTION_HINT: u32 = 1 << 3;
+const MSI_ADDRESS_DEST_MODE_LOGICAL: u32 = 1 << 2;
+const MSI_ADDRESS_REMAPPABLE: u32 = 1 << 4;
+const MSI_ADDRESS_SUBHANDLE_VALID: u32 = 1 << 3;
+const MSI_ADDRESS_HANDLE_SHIFT: u32 = 5;
+const MSI_ADDRESS_HANDLE_15: u32 = 1 << 2;
+const MSI_DATA_LEVEL_ASSERT: u32 = 1 << 14;
+const MSI_DATA_TRIGGER_LEVEL: u32 = 1 << 15;
+
+/// Returns the address of the MSI message in remappable format whose interrupt is described by
+/// the entry `handle` of the interrupt remapping table.
+pub fn remappable_msi_address(handle: u16) -> u32 {
+    let handle = u32::from(handle);
+    let mut address =
+        MSI_ADDRESS | MSI_ADDRESS_REMAPPABLE | ((handle & 0x7fff) << MSI_ADDRESS_HANDLE_SHIFT);
+    if handle & (1 << 15) != 0 {
+        address |= MSI_ADDRESS_HANDLE_15;
+    }
+    address
+}
+
+/// Interrupt remapping table the guest enabled interrupt remapping with
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InterruptRemappingTable {
+    /// Value of the IRTA register when the guest set the table pointer.
+    address: u64,
+    /// Whether interrupts in compatibility format are delivered as they are.
+    compatibility_format: bool,
+}
+
+impl InterruptRemappingTable {
+    /// Returns the MSI message the table translates `config` to, or `None` if the table blocks
+    /// the interrupt.
+    ///
+    /// The destination of the translated message is written as the x2APIC API of KVM expects it,
+    /// with bits 31:8 of the x2APIC ID in the high address.
+    pub fn remap(
+        &self,
+        mem: &GuestMemoryMmap,
+        config: MsixVectorConfig,
+    ) -> Option<MsixVectorConfig> {
+        if config.low_addr & MSI_ADDRESS_REMAPPABLE == 0 {
+            return self.compatibility_format.then_some(config);
+        }
+        let mut handle = (config.low_addr >> MSI_ADDRESS_HANDLE_SHIFT) & 0x7fff;
+        if config.low_addr & MSI_ADDRESS_HANDLE_15 != 0 {
+            handle |= 1 << 15;
+        }
+        if config.low_addr & MSI_ADDRESS_SUBHANDLE_VALID != 0 {
+            handle += config.data & 0xffff;
+        }
+        if handle >= 2u32 << (self.address & IRTA_SIZE) {
+            return None;
+        }
+        let entry_addr = (self.address & PAGE_MASK).checked_add(u64::from(handle) * IRTE_SIZE)?;
+        let entry: u64 = mem.read_obj(GuestAddress(entry_addr)).ok()?;
+        // Posted interrupts are not supported.
+        if entry & IRTE_PRESENT == 0 || entry & IRTE_POSTED != 0 {
+            return None;
+        }
+
+        let mut dest = u32::try_from(entry >> IRTE_DEST_SHIFT).unwrap();
+        // Out of extended interrupt mode, the destination is the xAPIC ID in bits 15:8.
+        if self.address & IRTA_EXTENDED_INTERRUPT_MODE == 0 {
+            dest = (dest >> 8) & 0xff;
+        }
+        let mut low_addr = MSI_ADDRESS | ((dest & 0xff) << MSI_ADDRESS_DEST_SHIFT);
+        if entry & IRTE_REDIRECTION_HINT != 0 {
+            low_addr |= MSI_ADDRESS_REDIRECTION_HINT;
+        }
+        if entry & IRTE_DEST_MODE_LOGICAL != 0 {
+            low_addr |= MSI_ADDRESS_DEST_MODE_LOGICAL;
+        }
+        let mut data = u32::try_from(
+            ((entry >> IRTE_VECTOR_SHIFT) & 0xff) | ((entry & IRTE_DELIVERY_MODE) << 3),
+        )
+        .unwrap();
+        if entry & IRTE_TRIGGER_LEVEL != 0 {
+            data |= MSI_DATA_TRIGGER_LEVEL | MSI_DATA_LEVEL_ASSERT;
+        }
+        Some(MsixVectorConfig {
+            high_addr: dest & 0xffff_ff00,
+            low_addr,
+            data,
+            devid: config.devid,
+        })
+    }
+}
+
+/// VT-d remapping unit only implementing interrupt remapping
+///
+/// With a split irqchip, the guest can remap the interrupts of the IOAPIC and of the MSI capable
+/// devices through this unit, which lets it target x2APIC IDs that do not fit the destination of
+/// the messages in compatibility format. The unit does not translate DMA requests: it reports no
+/// supported address width for second-level translation.
+///
+/// Once the guest enables interrupt remapping, the routes of the VM are translated through the
+/// interrupt remapping table, and translated again whenever the guest invalidates the interrupt
+/// entry cache. Invalidations are only taken through the invalidation queue, which guests have to
+/// use for interrupt remapping. Completion of invalidation wait descriptors is reported through
+/// their status write and the invalidation completion status register, without raising any
+/// interrupt.
+#[derive(Debug)]
+pub struct Iommu {
+    vm: Arc<Vm>,
+    status: u32,
+    fault_status: u32,
+    queue_head: u64,
+    queue_tail: u64,
+    queue_address: u64,
+    completion_status: u32,
+    irta: u64,
+    /// Value of the IRTA register latched by the last table pointer command.
+    table_address: u64,
+}
+
+impl Iommu {
+    /// Create a new [`Iommu`], with interrupt remapping disabled.
+    pub fn new(vm: Arc<Vm>) -> Iommu {
+        Iommu {
+            vm,
+            status: 0,
+            fault_status: 0,
+            queue_head: 0,
+            queue_tail: 0,
+            queue_address: 0,
+            completion_status: 0,
+            irta: 0,
+            table_address: 0,
+        }
+    }
+
+    fn command(&mut self, command: u32) {
+        let mut status = (self.status & GCMD_SET_INTERRUPT_TABLE) | (command & GCMD_ENABLES);
+        if command & GCMD_SET_INTERRUPT_TABLE != 0 {
+            self.table_address = self.irta;
+            status |= GCMD_SET_INTERRUPT_TABLE;
+        }
+        // The queue restarts from its first descriptor whenever it gets enabled.
+        if status & !self.status & GCMD_QUEUED_INVALIDATION != 0 {
+            self.queue_head = 0;
+        }
+        let remapping = GCMD_INTERRUPT_REMAPPING | GCMD_COMPATIBILITY_FORMAT;
+        let update =
+            command & GCMD_SET_INTERRUPT_TABLE != 0 || (status ^ self.status) & remapping != 0;
+        self.status = status;
+        if update {
+            self.update_interrupt_remapping();
+        }
+    }
+
+    /// Translates the routes of the VM through the interrupt remapping table, or leaves them as
+    /// they are if interrupt remapping is disabled.
+    fn update_interrupt_remapping(&self) {
+        let table =
+            (self.status & GCMD_INTERRUPT_REMAPPING != 0).then_some(InterruptRemappingTable {
+                address: self.table_address,
+                compatibility_format: self.status & GCMD_COMPATIBILITY_FORMAT != 0,
+            });
+        self.vm.set_interrupt_remapping(table);
+        if let Err(err) = self.vm.set_gsi_routes() {
+            error!("iommu: Failed to remap the interrupt routes: {err}");
+        }
+    }
+
+    /// Processes the descriptors of the invalidation queue up to its tail.
+    fn process_invalidation_queue(&mut self) {
+        if self.status & GCMD_QUEUED_INVALIDATION == 0 || self.fault_status & FSTS_QUEUE_ERROR != 0
+        {
+            return;
+        }
+        let size = 0x1000u64 << (self.queue_address & IQA_SIZE);
+        if self.queue_tail >= size {
+            self.fault_status |= FSTS_QUEUE_ERROR;
+            return;
+        }
+        while self.queue_head != self.queue_tail {
+            let Some(descriptor) = self.read_descriptor() else {
+                self.fault_status |= FSTS_QUEUE_ERROR;
+                return;
+            };
+            match descriptor[0] & DESCRIPTOR_TYPE {
+                DESCRIPTOR_CONTEXT_CACHE | DESCRIPTOR_IOTLB | DESCRIPTOR_DEVICE_TLB => (),
+                DESCRIPTOR_INTERRUPT_ENTRY_CACHE => self.update_interrupt_remapping(),
+                DESCRIPTOR_WAIT => self.complete_wait(descriptor),
+                _ => {
+                    // The head stays on the invalid descriptor.
+                    self.fault_status |= FSTS_QUEUE_ERROR;
+                    return;
+                }
+            }
+            self.queue_head = (self.queue_head + DESCRIPTOR_SIZE) % size;
+        }
+    }
+
+    fn read_descriptor(&self) -> Option<[u64; 2]> {
+        let addr = (self.queue_address & PAGE_MASK).checked_add(self.queue_head)?;
+        let addr = GuestAddress(addr);
+        let mem = self.vm.guest_memory();
+        Some([
+            mem.read_obj(addr).ok()?,
+            mem.read_obj(addr.checked_add(8)?).ok()?,
+        ])
+    }
+
+    fn complete_wait(&mut self, descriptor: [u64; 2]) {
+        if descriptor[0] & WAIT_STATUS_WRITE != 0 {
+            let status = u32::try_from(descriptor[0] >> 32).unwrap();
+            let addr = GuestAddress(descriptor[1] & WAIT_STATUS_ADDRESS);
+            if let Err(err) = self.vm.guest_memory().write_obj(status, addr) {
+                error!("iommu: Failed to write the status of an invalidation wait: {err}");
+            }
+        }
+        if descriptor[0] & WAIT_INTERRUPT != 0 {
+            self.completion_status |= ICS_WAIT_COMPLETE;
+        }
+    }
+
+    fn read_register(&self, offset: u64) -> u64 {
+        match offset {
+            VER => VERSION,
+            CAP => CAPABILITIES,
+            ECAP => EXTENDED_CAPABILITIES,
+            GCMD_GSTS => u64::from(self.status) << 32,
+            FSTS => u64::from(self.fault_status) << 32,
+            IQH => self.queue_head,
+            IQT => self.queue_tail,
+            IQA => self.queue_address,
+            ICS => u64::from(self.completion_status) << 32,
+            IRTA => self.irta,
+            _ => 0,
+        }
+    }
+
+    /// Writes the bits of `mask` of the register at `offset`.
+    fn write_register(&mut self, offset: u64, value: u64, mask: u64) {
+        let merge =
+            |old: u64, writable: u64| (old & !(mask & writable)) | (value & mask & writable);
+        match offset {
+            GCMD_GSTS if mask & u64::from(u32::MAX) != 0 => {
+                self.command(u32::try_from(value & u64::from(u32::MAX)).unwrap())
+            }
+            FSTS => {
+                self.fault_status &= !u32::try_from((value & mask) >> 32).unwrap();
+                self.process_invalidation_queue();
+            }
+            IQT => {
+                self.queue_tail = merge(self.queue_tail, IQ_OFFSET_MASK);
+                self.process_invalidation_queue();
+            }
+            IQA => self.queue_address = merge(self.queue_address, PAGE_MASK | IQA_SIZE),
+            ICS => self.completion_status &= !u32::try_from((value & mask) >> 32).unwrap(),
+            IRTA => self.irta = merge(self.irta, IRTA_WRITABLE),
+            _ => (),
+        }
+    }
+}
+
+impl BusDevice for Iommu {
+    fn read(&mut self, _base: u64, offset: u64, data: &mut [u8]) {
+        let shift = (offset % 8) * 8;
+        let value = (self.read_register(offset - offset % 8) >> shift).to_le_bytes();
+        match data.len() {
+            4 | 8 if offset % data.len() as u64 == 0 => data.copy_from_slice(&value[..data.len()]),
+            _ => data.fill(0),
+        }
+    }
+
+    fn write(&mut self, _base: u64, offset: u64, data: &[u8]) -> Option<Arc<Barrier>> {
+        let value = match data.len() {
+            4 if offset % 4 == 0 => u64::from(u32::from_le_bytes(data.try_into().unwrap())),
+            8 if offset % 8 == 0 => u64::from_le_bytes(data.try_into().unwrap()),
+            _ => return None,
+        };
+        let shift = (offset % 8) * 8;
+        let mask = (u64::MAX >> (64 - 8 * data.len())) << shift;
+        self.write_register(offset - offset % 8, value << shift, mask);
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::single_region_mem;
+    use crate::vstate::vm::tests::setup_vm_with_memory;
+
+    fn write_register(iommu: &mut Iommu, offset: u64, value: u64) {
+        iommu.write(0, offset, &value.to_le_bytes());
+    }
+
+    fn read_register(iommu: &mut Iommu, offset: u64) -> u64 {
+        let mut data = [0u8; 8];
+        iommu.read(0, offset, &mut data);
+        u64::from_le_bytes(data)
+    }
+
+    #[test]
+    fn test_remappable_msi_address() {
+        assert_eq!(remappable_msi_address(0), 0xfee0_0010);
+        assert_eq!(remappable_msi_address(3), 0xfee0_0070);
+        assert_eq!(remappable_msi_address(0x8001), 0xfee0_0034);
+    }
+
+    #[test]
+    fn test_remap() {
+        let mem = single_region_mem(0x10000);
+        // Entry 1: vector 0x30, fixed delivery, physical destination 0x1234, level-triggered.
+        mem.write_obj(0x0000_1234_0030_0011u64, GuestAddress(0x1010))
+            .unwrap();
+        // Entry 2: vector 0x41, lowest priority delivery, logical destination 0xf.
+        mem.write_obj(0x0000_000f_0041_0025u64, GuestAddress(0x1020))
+            .unwrap();
+        // Entry 3 is not present.
+        let table = InterruptRemappingTable {
+            address: 0x1000 | IRTA_EXTENDED_INTERRUPT_MODE | 1,
+            compatibility_format: false,
+        };
+
+        let config = MsixVectorConfig {
+            low_addr: remappable_msi_address(1),
+            devid: 8,
+            ..Default::default()
+        };
+        let remapped = table.remap(&mem, config).unwrap();
+        assert_eq!(remapped.low_addr, 0xfee3_4000);
+        assert_eq!(remapped.high_addr, 0x1200);
+        assert_eq!(remapped.data, 0xc030);
+        assert_eq!(remapped.devid, 8);
+
+        // The subhandle in the data is added to the handle.
+        let config = MsixVectorConfig {
+            low_addr: remappable_msi_address(0) | MSI_ADDRESS_SUBHANDLE_VALID,
+            data: 2,
+            ..Default::default()
+        };
+        let remapped = table.remap(&mem, config).unwrap();
+        assert_eq!(remapped.low_addr, 0xfee0_f004);
+        assert_eq!(remapped.high_addr, 0);
+        assert_eq!(remapped.data, 0x141);
+
+        // Out of extended interrupt mode, the destination is an xAPIC ID.
+        let xapic_table = InterruptRemappingTable {
+            address: 0x1000 | 1,
+            ..table
+        };
+        let config = MsixVectorConfig {
+            low_addr: remappable_msi_address(1),
+            ..Default::default()
+        };
+        let remapped = xapic_table.remap(&mem, config).unwrap();
+        assert_eq!(remapped.low_addr, 0xfee1_2000);
+        assert_eq!(remapped.high_addr, 0);
+
+        // Entries which are not present or out of the table block their interrupts.
+        let config = MsixVectorConfig {
+            low_addr: remappable_msi_address(3),
+            ..Default::default()
+        };
+        assert!(table.remap(&mem, config).is_none());
+        let config = MsixVectorConfig {
+            low_addr: remappable_msi_address(4),
+            ..Default::default()
+        };
+        assert!(table.remap(&mem, config).is_none());
+
+        // Interrupts in compatibility format go through only if the guest allows them.
+        let config = MsixVectorConfig {
+            low_addr: 0xfee0_2000,
+            data: 0x30,
+            ..Default::default()
+        };
+        assert!(table.remap(&mem, config).is_none());
+        let compatibility_table = InterruptRemappingTable {
+            compatibility_format: true,
+            ..table
+        };
+        let remapped = compatibility_table.remap(&mem, config).unwrap();
+        assert_eq!(remapped.low_addr, 0xfee0_2000);
+        assert_eq!(remapped.data, 0x30);
+    }
+
+    #[test]
+    fn test_iommu_registers() {
+        let (_, mut vm) = setup_vm_with_memory(0x10000);
+        vm.set_split_irqchip(true);
+        let (_vcpus, _) = vm.create_vcpus(1).unwrap();
+        let mut iommu = Iommu::new(Arc::new(vm));
+
+        assert_eq!(read_register(&mut iommu, VER), 0x10);
+        assert_eq!(read_register(&mut iommu, ECAP) & 0x1b, 0x1b);
+        // No second-level translation.
+        assert_eq!(read_register(&mut iommu, CAP) & 0x1f00, 0);
+
+        // Set the table pointer, then enable interrupt remapping.
+        write_register(&mut iommu, IRTA, 0x2000 | IRTA_EXTENDED_INTERRUPT_MODE | 7);
+        iommu.write(0, GCMD_GSTS, &GCMD_SET_INTERRUPT_TABLE.to_le_bytes());
+        let mut status = [0u8; 4];
+        iommu.read(0, GCMD_GSTS + 4, &mut status);
+        assert_eq!(u32::from_le_bytes(status), GCMD_SET_INTERRUPT_TABLE);
+        iommu.write(0, GCMD_GSTS, &GCMD_INTERRUPT_REMAPPING.to_le_bytes());
+        assert_eq!(
+            read_register(&mut iommu, GCMD_GSTS) >> 32,
+            u64::from(GCMD_SET_INTERRUPT_TABLE | GCMD_INTERRUPT_REMAPPING)
+        );
+        // Interrupts in compatibility format are blocked.
+        let config = MsixVectorConfig {
+            low_addr: 0xfee0_0000,
+            data: 0x30,
+            ..Default::default()
+        };
+        assert!(iommu.vm.remap_msi(config).is_none());
+
+        // Enable the invalidation queue, at 0x3000, and submit an interrupt entry cache
+        // invalidation followed by a wait descriptor writing its status at 0x4000.
+        write_register(&mut iommu, IQA, 0x3000);
+        iommu.write(
+            0,
+            GCMD_GSTS,
+            &(GCMD_INTERRUPT_REMAPPING | GCMD_QUEUED_INVALIDATION).to_le_bytes(),
+        );
+        let mem = iommu.vm.guest_memory().clone();
+        mem.write_obj(DESCRIPTOR_INTERRUPT_ENTRY_CACHE, GuestAddress(0x3000))
+            .unwrap();
+        mem.write_obj(
+            0x1234_5678_0000_0000 | WAIT_STATUS_WRITE | WAIT_INTERRUPT | DESCRIPTOR_WAIT,
+            GuestAddress(0x3010),
+        )
+        .unwrap();
+        mem.write_obj(0x4000u64, GuestAddress(0x3018)).unwrap();
+        write_register(&mut iommu, IQT, 0x20);
+        assert_eq!(read_register(&mut iommu, IQH), 0x20);
+        assert_eq!(
+            mem.read_obj::<u32>(GuestAddress(0x4000)).unwrap(),
+            0x1234_5678
+        );
+        assert_eq!(read_register(&mut iommu, ICS) >> 32, 1);
+        write_register(&mut iommu, ICS, 1 << 32);
+        assert_eq!(read_register(&mut iommu, ICS), 0);
+
+        // An invalid descriptor stops the queue until the guest clears the error.
+        mem.write_obj(0xfu64, GuestAddress(0x3020)).unwrap();
+        write_register(&mut iommu, IQT, 0x30);
+        assert_eq!(read_register(&mut iommu, IQH), 0x20);
+        assert_eq!(
+            read_register(&mut iommu, FSTS) >> 32,
+            u64::from(FSTS_QUEUE_ERROR)
+        );
+        mem.write_obj(DESCRIPTOR_IOTLB, GuestAddress(0x3020))
+            .unwrap();
+        write_register(&mut iommu, FSTS, u64::from(FSTS_QUEUE_ERROR) << 32);
+        assert_eq!(read_register(&mut iommu, FSTS), 0);
+        assert_eq!(read_register(&mut iommu, IQH), 0x30);
+
+        // Disabling interrupt remapping stops translating the interrupts.
+        iommu.write(0, GCMD_GSTS, &GCMD_QUEUED_INVALIDATION.to_le_bytes());
+        assert_eq!(iommu.vm.remap_msi(config).unwrap().low_addr, 0xfee0_0000);
+    }
+}
@@ -7,9 +7,16 @@
 
 //! Implements legacy devices (UART, RTC etc).
 mod i8042;
+#[cfg(target_arch = "x86_64")]
+pub mod ioapic;
+#[cfg(target_arch = "x86_64")]
+pub mod iommu;
+#[cfg(target_arch = "x86_64")]
+pub mod reset_register;
 #[cfg(target_arch = "aarch64")]
 pub mod rtc_pl031;
 pub mod serial;
+mod serial_socket;
 
 use std::io;
 use std::ops::Deref;
@@ -20,11 +27,18 @@ use vm_superio::Trigger;
 use vmm_sys_util::eventfd::EventFd;
 
 pub use self::i8042::{I8042Device, I8042Error as I8042DeviceError};
+#[cfg(target_arch = "x86_64")]
+pub use self::ioapic::Ioapic;
+#[cfg(target_arch = "x86_64")]
+pub use self::iommu::Iommu;
+#[cfg(target_arch = "x86_64")]
+pub use self::reset_register::ResetRegister;
 #[cfg(target_arch = "aarch64")]
 pub use self::rtc_pl031::RTCDevice;
 pub use self::serial::{
     IER_RDA_BIT, IER_RDA_OFFSET, SerialDevice, SerialEventsWrapper, SerialWrapper,
 };
+pub use self::serial_socket::SerialSocket;
 
 /// Wrapper for implementing the trigger functionality for `EventFd`.
 ///
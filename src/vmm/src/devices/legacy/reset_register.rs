@@ -0,0 +1,67 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! The ACPI reset register, which the FADT points guests to for rebooting the platform.
+
+use std::sync::{Arc, Barrier};
+
+use vmm_sys_util::eventfd::EventFd;
+
+use crate::logger::error;
+use crate::vstate::bus::BusDevice;
+
+/// Value the guest writes to the reset register to reset the platform.
+pub const RESET_VALUE: u8 = 0x06;
+
+/// Port I/O register that resets the platform when the guest writes [`RESET_VALUE`] to it.
+#[derive(Debug)]
+pub struct ResetRegister {
+    /// Platform reset eventfd, set when the guest writes the reset value.
+    reset_evt: EventFd,
+}
+
+impl ResetRegister {
+    /// Constructs a reset register that signals `reset_evt` on reset.
+    pub fn new(reset_evt: EventFd) -> Self {
+        Self { reset_evt }
+    }
+}
+
+impl BusDevice for ResetRegister {
+    fn read(&mut self, _base: u64, _offset: u64, data: &mut [u8]) {
+        data.fill(0);
+    }
+
+    fn write(&mut self, _base: u64, offset: u64, data: &[u8]) -> Option<Arc<Barrier>> {
+        if offset == 0
+            && data == [RESET_VALUE]
+            && let Err(err) = self.reset_evt.write(1)
+        {
+            error!("Failed to trigger the platform reset event: {:?}", err);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reset_register() {
+        let mut reset_register = ResetRegister::new(EventFd::new(libc::EFD_NONBLOCK).unwrap());
+
+        let mut data = [0xff];
+        reset_register.read(0x0, 0, &mut data);
+        assert_eq!(data, [0]);
+
+        // Other values, offsets and sizes are ignored.
+        reset_register.write(0x0, 0, &[RESET_VALUE + 1]);
+        reset_register.write(0x0, 1, &[RESET_VALUE]);
+        reset_register.write(0x0, 0, &[RESET_VALUE, 0]);
+        reset_register.reset_evt.read().unwrap_err();
+
+        reset_register.write(0x0, 0, &[RESET_VALUE]);
+        assert_eq!(reset_register.reset_evt.read().unwrap(), 1);
+    }
+}
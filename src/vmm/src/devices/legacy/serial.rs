@@ -10,6 +10,7 @@ use std::fmt::Debug;
 use std::fs::File;
 use std::io::{self, Read, Stdin, Write};
 use std::os::unix::io::{AsRawFd, RawFd};
+use std::os::unix::net::UnixStream;
 use std::sync::{Arc, Barrier};
 
 use event_manager::{EventOps, Events, MutEventSubscriber};
@@ -22,6 +23,7 @@ use vmm_sys_util::epoll::EventSet;
 use vmm_sys_util::eventfd::EventFd;
 
 use crate::devices::legacy::EventFdTrigger;
+use crate::devices::legacy::serial_socket::SerialSocket;
 use crate::logger::{IncMetric, SharedIncMetric};
 use crate::vstate::bus::BusDevice;
 
@@ -31,6 +33,21 @@ pub const IER_RDA_BIT: u8 = 0b0000_0001;
 /// Received Data Available interrupt offset
 pub const IER_RDA_OFFSET: u8 = 1;
 
+// THR empty interrupt, raised when the guest can write the next byte to transmit.
+const IER_THR_EMPTY_BIT: u8 = 0b0000_0010;
+// Line control register, and its bit switching the IER offset to the divisor latch.
+const LCR_OFFSET: u8 = 3;
+const LCR_DLAB_BIT: u8 = 0b1000_0000;
+// Line status register, and its bits reporting the transmitter as empty.
+const LSR_OFFSET: u8 = 5;
+const LSR_THR_EMPTY_BITS: u8 = 0b0110_0000;
+
+// Events of a serial socket client. Edge triggered so that the client is only reported writable
+// after some output could not be sent to it, and input is read until it is drained.
+const SOCKET_CLIENT_EVENTS: EventSet = EventSet::IN
+    .union(EventSet::OUT)
+    .union(EventSet::EDGE_TRIGGERED);
+
 /// Metrics specific to the UART device.
 #[derive(Debug, Serialize, Default)]
 pub struct SerialDeviceMetrics {
@@ -132,6 +149,7 @@ pub enum SerialOut {
     Sink,
     Stdout(std::io::Stdout),
     File(File),
+    Socket(SerialSocket),
 }
 impl std::io::Write for SerialOut {
     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
@@ -139,6 +157,7 @@ impl std::io::Write for SerialOut {
             Self::Sink => Ok(buf.len()),
             Self::Stdout(stdout) => stdout.write(buf),
             Self::File(file) => file.write(buf),
+            Self::Socket(socket) => socket.write(buf),
         }
     }
     fn flush(&mut self) -> std::io::Result<()> {
@@ -146,6 +165,7 @@ impl std::io::Write for SerialOut {
             Self::Sink => Ok(()),
             Self::Stdout(stdout) => stdout.flush(),
             Self::File(file) => file.flush(),
+            Self::Socket(socket) => socket.flush(),
         }
     }
 }
@@ -224,6 +244,140 @@ impl<I: Read + AsRawFd + Send + Debug> SerialWrapper<EventFdTrigger, SerialEvent
             .as_ref()
             .map_or(Ok(0), |buf_ready| buf_ready.read())
     }
+
+    fn socket(&mut self) -> Option<&mut SerialSocket> {
+        match self.serial.writer_mut() {
+            SerialOut::Socket(socket) => Some(socket),
+            _ => None,
+        }
+    }
+
+    /// Whether the guest is held back because the serial socket client is behind.
+    fn is_socket_full(&self) -> bool {
+        matches!(self.serial.writer(), SerialOut::Socket(socket) if socket.is_full())
+    }
+
+    /// Moves input of the serial socket client to the serial FIFO, until either is exhausted.
+    fn recv_socket_bytes(&mut self) -> io::Result<()> {
+        loop {
+            let avail_cap = self.serial.fifo_capacity();
+            let Some(socket) = self.socket() else {
+                return Ok(());
+            };
+            if avail_cap == 0 {
+                return Ok(());
+            }
+
+            let mut out = vec![0u8; avail_cap];
+            let count = match socket.read_input(&mut out) {
+                Ok(0) => return Err(io::Error::from(io::ErrorKind::UnexpectedEof)),
+                Ok(count) => count,
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => return Ok(()),
+                Err(err) => return Err(err),
+            };
+            self.serial
+                .raw_input(&out[..count])
+                .map_err(|_| io::Error::from_raw_os_error(libc::ENOBUFS))?;
+        }
+    }
+
+    /// Sends buffered output to the serial socket client, letting the guest transmit again if it
+    /// was held back.
+    fn flush_socket(&mut self) -> io::Result<()> {
+        let was_full = self.is_socket_full();
+        let result = self.socket().map_or(Ok(()), SerialSocket::flush_buffer);
+        if was_full && !self.is_socket_full() {
+            self.retrigger_thr_empty_interrupt();
+        }
+        result
+    }
+
+    /// While the transmitter was reported busy, the guest ignored the THR empty interrupts it
+    /// received, so raise a new one by enabling the interrupt anew.
+    fn retrigger_thr_empty_interrupt(&mut self) {
+        if self.serial.read(LCR_OFFSET) & LCR_DLAB_BIT != 0 {
+            return;
+        }
+        let ier = self.serial.read(IER_RDA_OFFSET);
+        if ier & IER_THR_EMPTY_BIT == 0 {
+            return;
+        }
+        if let Err(err) = self
+            .serial
+            .write(IER_RDA_OFFSET, ier & !IER_THR_EMPTY_BIT)
+            .and_then(|()| self.serial.write(IER_RDA_OFFSET, ier))
+        {
+            error!("Failed to raise the serial THR empty interrupt: {:?}", err);
+            METRICS.error_count.inc();
+        }
+    }
+
+    fn connect_socket_client(&mut self, client: UnixStream, ops: &mut EventOps) {
+        self.disconnect_socket_client(ops);
+        if let Err(err) = ops.add(Events::new(&client, SOCKET_CLIENT_EVENTS)) {
+            error!("Failed to register the serial socket client: {}", err);
+            return;
+        }
+        if let Some(socket) = self.socket() {
+            socket.connect(client);
+        }
+        // Send the scrollback, and take any input already sent by the client.
+        if let Err(err) = self.flush_socket().and_then(|()| self.recv_socket_bytes()) {
+            warn!("Disconnected the serial socket client: {}", err);
+            self.disconnect_socket_client(ops);
+        }
+    }
+
+    fn disconnect_socket_client(&mut self, ops: &mut EventOps) {
+        let was_full = self.is_socket_full();
+        let Some(socket) = self.socket() else {
+            return;
+        };
+        if let Some(client_fd) = socket.client_fd() {
+            if let Err(err) = ops.remove(Events::new(&client_fd, SOCKET_CLIENT_EVENTS)) {
+                error!("Could not unregister the serial socket client: {}", err);
+            }
+            socket.disconnect();
+        }
+        // Output is kept as scrollback from now on, the guest is not held back anymore.
+        if was_full {
+            self.retrigger_thr_empty_interrupt();
+        }
+    }
+
+    /// Handles the events of a serial device served on a Unix socket.
+    fn process_socket(&mut self, event: Events, ops: &mut EventOps) {
+        let Some(socket) = self.socket() else {
+            return;
+        };
+        let listener_fd = socket.listener_fd();
+        let client_fd = socket.client_fd();
+
+        if event.fd() == listener_fd {
+            match socket.accept() {
+                Ok(Some(client)) => self.connect_socket_client(client, ops),
+                Ok(None) => (),
+                Err(err) => error!("Failed to accept a serial socket client: {}", err),
+            }
+        } else if Some(event.fd()) == client_fd {
+            let result = self.flush_socket().and_then(|()| self.recv_socket_bytes());
+            if let Err(err) = result {
+                warn!("Disconnected the serial socket client: {}", err);
+                self.disconnect_socket_client(ops);
+            } else if event.event_set().contains(EventSet::HANG_UP) {
+                self.disconnect_socket_client(ops);
+            }
+        } else if event.fd() == self.buffer_ready_evt_fd() {
+            if let Err(err) = self.consume_buffer_ready_event() {
+                error!("Failed to consume the serial buffer ready event: {:?}", err);
+            }
+            // The guest emptied the FIFO, take the input left by the client.
+            if let Err(err) = self.recv_socket_bytes() {
+                warn!("Disconnected the serial socket client: {}", err);
+                self.disconnect_socket_client(ops);
+            }
+        }
+    }
 }
 
 /// Type for representing a serial device.
@@ -254,6 +408,11 @@ impl<I: Read + AsRawFd + Send + Debug> MutEventSubscriber
 {
     /// Handle events on the serial input fd.
     fn process(&mut self, event: Events, ops: &mut EventOps) {
+        if matches!(self.serial.writer(), SerialOut::Socket(_)) {
+            self.process_socket(event, ops);
+            return;
+        }
+
         #[inline]
         fn unregister_source<T: AsRawFd + Debug>(ops: &mut EventOps, source: &T) {
             match ops.remove(Events::new(source, EventSet::IN)) {
@@ -324,6 +483,19 @@ impl<I: Read + AsRawFd + Send + Debug> MutEventSubscriber
     /// Initial registration of pollable objects.
     /// If serial input is present, register the serial input FD as readable.
     fn init(&mut self, ops: &mut EventOps) {
+        let buf_ready_evt = self.buffer_ready_evt_fd();
+        if let Some(socket) = self.socket() {
+            if let Err(err) = ops.add(Events::new(&socket.listener_fd(), EventSet::IN)) {
+                warn!("Failed to register the serial socket: {}", err);
+            }
+            if buf_ready_evt >= 0
+                && let Err(err) = ops.add(Events::new(&buf_ready_evt, EventSet::IN))
+            {
+                warn!("Failed to register serial buffer ready event: {}", err);
+            }
+            return;
+        }
+
         if self.input.is_some() && self.serial.events().buffer_ready_event_fd.is_some() {
             let serial_fd = self.serial_input_fd();
             let buf_ready_evt = self.buffer_ready_evt_fd();
@@ -371,6 +543,13 @@ where
     fn read(&mut self, _base: u64, offset: u64, data: &mut [u8]) {
         if let (Ok(offset), 1) = (u8::try_from(offset), data.len()) {
             data[0] = self.serial.read(offset);
+            // Report the transmitter as busy while the serial socket client is behind, so that
+            // the guest waits for it rather than its output being dropped.
+            if offset == LSR_OFFSET
+                && matches!(self.serial.writer(), SerialOut::Socket(socket) if socket.is_full())
+            {
+                data[0] &= !LSR_THR_EMPTY_BITS;
+            }
         } else {
             METRICS.missed_read_count.inc();
         }
@@ -433,6 +612,57 @@ mod tests {
         assert_eq!(invalid_reads_after_2, invalid_reads_after);
     }
 
+    #[test]
+    fn test_serial_socket_back_pressure() {
+        let dir = vmm_sys_util::tempdir::TempDir::new().unwrap();
+        let path = dir.as_path().join("serial.sock");
+        let mut serial = SerialDevice::new(
+            None,
+            SerialOut::Socket(SerialSocket::new(&path, 16).unwrap()),
+        )
+        .unwrap();
+        let mut client = UnixStream::connect(&path).unwrap();
+        let accepted = serial.socket().unwrap().accept().unwrap().unwrap();
+        serial.socket().unwrap().connect(accepted);
+
+        let read_lsr = |serial: &mut SerialDevice| {
+            let mut lsr = [0u8];
+            serial.read(0x0, u64::from(LSR_OFFSET), &mut lsr);
+            lsr[0]
+        };
+        assert_eq!(
+            read_lsr(&mut serial) & LSR_THR_EMPTY_BITS,
+            LSR_THR_EMPTY_BITS
+        );
+
+        // Fill the client socket, then the buffer, until the transmitter is reported busy.
+        let mut written = 0;
+        while read_lsr(&mut serial) & LSR_THR_EMPTY_BITS != 0 {
+            serial.write(0x0, 0, b"x");
+            written += 1;
+        }
+        assert!(serial.is_socket_full());
+
+        // Nothing was dropped, and the guest can transmit again once the client caught up.
+        let mut output = vec![0u8; written];
+        client.set_nonblocking(true).unwrap();
+        let mut received = 0;
+        while received < written {
+            match client.read(&mut output[received..]) {
+                Ok(count) => received += count,
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                    serial.flush_socket().unwrap()
+                }
+                Err(err) => panic!("{err}"),
+            }
+        }
+        assert!(output.iter().all(|byte| *byte == b'x'));
+        assert_eq!(
+            read_lsr(&mut serial) & LSR_THR_EMPTY_BITS,
+            LSR_THR_EMPTY_BITS
+        );
+    }
+
     #[test]
     fn test_is_fifo() {
         // invalid file descriptors arent fifos
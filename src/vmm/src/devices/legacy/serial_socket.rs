@@ -0,0 +1,178 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Serial console served on a Unix socket.
+//!
+//! One client is connected at a time, a new connection replaces the previous one. While no client
+//! is connected, the most recent output is kept as scrollback and sent to the next client. While
+//! one is connected but does not keep up, output is buffered up to the same bound, past which the
+//! UART reports its transmitter as busy to hold the guest back.
+
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::Path;
+
+use super::serial::METRICS;
+use crate::logger::IncMetric;
+use crate::utils::usize_to_u64;
+
+/// Unix socket serving the serial console.
+#[derive(Debug)]
+pub struct SerialSocket {
+    listener: UnixListener,
+    client: Option<UnixStream>,
+    /// Output not sent to the client yet, or kept as scrollback while there is none.
+    buffer: VecDeque<u8>,
+    capacity: usize,
+}
+
+impl SerialSocket {
+    /// Listens on `path`, keeping up to `capacity` bytes of output not sent to a client.
+    pub fn new(path: &Path, capacity: usize) -> io::Result<Self> {
+        let listener = UnixListener::bind(path)?;
+        listener.set_nonblocking(true)?;
+        Ok(SerialSocket {
+            listener,
+            client: None,
+            buffer: VecDeque::with_capacity(capacity),
+            capacity,
+        })
+    }
+
+    /// Returns the file descriptor of the listening socket.
+    pub fn listener_fd(&self) -> RawFd {
+        self.listener.as_raw_fd()
+    }
+
+    /// Returns the file descriptor of the connected client, if any.
+    pub fn client_fd(&self) -> Option<RawFd> {
+        self.client.as_ref().map(|client| client.as_raw_fd())
+    }
+
+    /// Whether the connected client fell behind enough for the guest to be held back.
+    pub fn is_full(&self) -> bool {
+        self.client.is_some() && self.buffer.len() >= self.capacity
+    }
+
+    /// Accepts a pending connection, or returns `None` if there is none.
+    pub fn accept(&mut self) -> io::Result<Option<UnixStream>> {
+        match self.listener.accept() {
+            Ok((client, _)) => {
+                client.set_nonblocking(true)?;
+                Ok(Some(client))
+            }
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Makes `client` the current client, replacing the previous one.
+    pub fn connect(&mut self, client: UnixStream) {
+        self.client = Some(client);
+    }
+
+    /// Drops the current client, keeping what it was not sent as scrollback.
+    pub fn disconnect(&mut self) {
+        self.client = None;
+    }
+
+    /// Sends as much of the buffered output to the client as it accepts without blocking.
+    pub fn flush_buffer(&mut self) -> io::Result<()> {
+        let Some(client) = self.client.as_mut() else {
+            return Ok(());
+        };
+        while !self.buffer.is_empty() {
+            let (front, _) = self.buffer.as_slices();
+            match client.write(front) {
+                Ok(0) => return Err(io::Error::from(io::ErrorKind::WriteZero)),
+                Ok(count) => drop(self.buffer.drain(..count)),
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads input sent by the client. Fails with `ENOTCONN` if there is none.
+    pub fn read_input(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.client.as_mut() {
+            Some(client) => client.read(buf),
+            None => Err(io::Error::from_raw_os_error(libc::ENOTCONN)),
+        }
+    }
+}
+
+impl Write for SerialSocket {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend(buf);
+        if let Some(overflow) = self.buffer.len().checked_sub(self.capacity) {
+            self.buffer.drain(..overflow);
+            // Only a guest ignoring the busy transmitter loses output while a client is connected.
+            if self.client.is_some() && overflow > 0 {
+                METRICS.missed_write_count.add(usize_to_u64(overflow));
+            }
+        }
+        // Errors are left to the event loop, which notices the client going away.
+        _ = self.flush_buffer();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vmm_sys_util::tempdir::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn test_serial_socket() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.as_path().join("serial.sock");
+        let mut socket = SerialSocket::new(&path, 8).unwrap();
+        assert!(socket.accept().unwrap().is_none());
+        assert_eq!(
+            socket.read_input(&mut [0; 1]).unwrap_err().raw_os_error(),
+            Some(libc::ENOTCONN)
+        );
+
+        // Only the most recent output is kept while no client is connected.
+        socket.write_all(b"0123456789").unwrap();
+        assert!(!socket.is_full());
+
+        let mut client = UnixStream::connect(&path).unwrap();
+        let accepted = socket.accept().unwrap().unwrap();
+        let fd = accepted.as_raw_fd();
+        socket.connect(accepted);
+        assert_eq!(socket.client_fd(), Some(fd));
+        socket.flush_buffer().unwrap();
+        let mut scrollback = [0u8; 8];
+        client.read_exact(&mut scrollback).unwrap();
+        assert_eq!(&scrollback, b"23456789");
+
+        socket.write_all(b"abc").unwrap();
+        let mut output = [0u8; 3];
+        client.read_exact(&mut output).unwrap();
+        assert_eq!(&output, b"abc");
+
+        client.write_all(b"ls\n").unwrap();
+        let mut input = [0u8; 3];
+        assert_eq!(socket.read_input(&mut input).unwrap(), 3);
+        assert_eq!(&input, b"ls\n");
+
+        // Output is kept as scrollback again once the client is gone, until the next one.
+        socket.disconnect();
+        assert!(socket.client_fd().is_none());
+        socket.write_all(b"def").unwrap();
+        let mut reconnected = UnixStream::connect(&path).unwrap();
+        socket.connect(socket.accept().unwrap().unwrap());
+        socket.flush_buffer().unwrap();
+        reconnected.read_exact(&mut output).unwrap();
+        assert_eq!(&output, b"def");
+    }
+}
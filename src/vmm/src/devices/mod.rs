@@ -13,8 +13,10 @@ use std::io;
 
 pub mod acpi;
 pub mod legacy;
+pub mod nvme;
 pub mod pci;
 pub mod pseudo;
+pub mod usb;
 pub mod virtio;
 
 use log::error;
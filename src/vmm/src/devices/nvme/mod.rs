@@ -0,0 +1,1351 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Emulated NVMe controller, exposing a disk image as its single namespace.
+//!
+//! This lets guests without virtio drivers, such as the installers of stock operating systems,
+//! use a disk. The emulation covers the NVMe 1.4 admin commands used by the Linux and Windows
+//! drivers, and the Read, Write and Flush I/O commands, whose data moves through the same IO
+//! engines as the virtio block device. Doorbell writes only record the new tails, the queues
+//! are processed by the VMM thread.
+
+mod queue;
+
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::io::{Seek, SeekFrom};
+use std::sync::{Arc, Barrier, Mutex};
+
+use event_manager::{EventOps, Events, MutEventSubscriber};
+use log::{debug, error, warn};
+use pci::{PciBdf, PciClassCode, PciMassStorageSubclass};
+use vm_allocator::AllocPolicy;
+use vmm_sys_util::epoll::EventSet;
+use vmm_sys_util::eventfd::EventFd;
+
+use self::queue::*;
+use crate::devices::virtio::block::CacheType;
+use crate::devices::virtio::block::virtio::io::{BlockIoError, FileEngine, FileEngineOk};
+use crate::devices::virtio::block::virtio::{IO_URING_NUM_ENTRIES, SECTOR_SHIFT};
+use crate::pci::PciDevice;
+use crate::pci::configuration::PciConfiguration;
+use crate::pci::msix::{MsixCap, MsixConfig};
+use crate::vmm_config::nvme::NvmeDeviceConfig;
+use crate::vstate::bus::BusDevice;
+use crate::vstate::interrupts::InterruptError;
+use crate::vstate::memory::{Bytes, GuestMemoryMmap};
+use crate::vstate::vm::Vm;
+
+// IDs of the NVMe controller of QEMU, whose quirks guest drivers already know about.
+const NVME_VENDOR_ID: u16 = 0x1b36;
+const NVME_DEVICE_ID: u16 = 0x0010;
+const NVME_PROG_IF: u8 = 0x02;
+
+/// Size of the BAR of the controller.
+pub const NVME_BAR_SIZE: u64 = 0x4000;
+
+// Layout of the BAR.
+const DOORBELL_OFFSET: u64 = 0x1000;
+const MSIX_TABLE_OFFSET: u64 = 0x2000;
+const MSIX_PBA_OFFSET: u64 = 0x3000;
+const MSIX_PBA_SIZE: u64 = 0x8;
+
+/// Number of I/O queue pairs.
+const MAX_IO_QUEUES: u16 = 8;
+/// One MSI-X vector for the admin queue and one per I/O queue.
+const MSIX_VECTORS: u16 = MAX_IO_QUEUES + 1;
+const MSIX_TABLE_SIZE: u64 = MSIX_VECTORS as u64 * 16;
+/// Maximum number of entries of a queue.
+const MAX_QUEUE_ENTRIES: u32 = 1024;
+/// Maximum data transfer size, as a power of two of the page size.
+const MDTS: u8 = 5;
+const MAX_TRANSFER_SIZE: u64 = PAGE_SIZE << MDTS;
+/// Maximum number of outstanding Asynchronous Event Requests.
+const MAX_ASYNC_EVENTS: u32 = 4;
+/// Identifier of the namespace.
+const NSID: u32 = 1;
+
+// Controller registers.
+const CAP_LO: u64 = 0x00;
+const CAP_HI: u64 = 0x04;
+const VS: u64 = 0x08;
+const INTMS: u64 = 0x0c;
+const INTMC: u64 = 0x10;
+const CC: u64 = 0x14;
+const CSTS: u64 = 0x1c;
+const AQA: u64 = 0x24;
+const ASQ_LO: u64 = 0x28;
+const ASQ_HI: u64 = 0x2c;
+const ACQ_LO: u64 = 0x30;
+const ACQ_HI: u64 = 0x34;
+
+const CAP_CQR: u64 = 1 << 16;
+// Timeout of the transitions of CSTS.RDY, in units of 500 ms.
+const CAP_TIMEOUT: u64 = 0x10 << 24;
+const CAP_CSS_NVM: u64 = 1 << 37;
+const CAP: u64 = (MAX_QUEUE_ENTRIES as u64 - 1) | CAP_CQR | CAP_TIMEOUT | CAP_CSS_NVM;
+const VERSION: u32 = 0x0001_0400;
+
+const CC_EN: u32 = 1 << 0;
+const CC_CSS_SHIFT: u32 = 4;
+const CC_MPS_SHIFT: u32 = 7;
+const CC_SHN_MASK: u32 = 0x3 << 14;
+
+const CSTS_RDY: u32 = 1 << 0;
+const CSTS_CFS: u32 = 1 << 1;
+const CSTS_SHST_MASK: u32 = 0x3 << 2;
+const CSTS_SHST_OCCURRING: u32 = 0x1 << 2;
+const CSTS_SHST_COMPLETE: u32 = 0x2 << 2;
+
+// Admin commands.
+const ADMIN_DELETE_SQ: u8 = 0x00;
+const ADMIN_CREATE_SQ: u8 = 0x01;
+const ADMIN_GET_LOG_PAGE: u8 = 0x02;
+const ADMIN_DELETE_CQ: u8 = 0x04;
+const ADMIN_CREATE_CQ: u8 = 0x05;
+const ADMIN_IDENTIFY: u8 = 0x06;
+const ADMIN_ABORT: u8 = 0x08;
+const ADMIN_SET_FEATURES: u8 = 0x09;
+const ADMIN_GET_FEATURES: u8 = 0x0a;
+const ADMIN_ASYNC_EVENT_REQUEST: u8 = 0x0c;
+const ADMIN_KEEP_ALIVE: u8 = 0x18;
+
+// I/O commands.
+const IO_FLUSH: u8 = 0x00;
+const IO_WRITE: u8 = 0x01;
+const IO_READ: u8 = 0x02;
+
+// Identify data structures.
+const IDENTIFY_NAMESPACE: u32 = 0x00;
+const IDENTIFY_CONTROLLER: u32 = 0x01;
+const IDENTIFY_ACTIVE_NAMESPACES: u32 = 0x02;
+const IDENTIFY_NAMESPACE_DESCRIPTORS: u32 = 0x03;
+const IDENTIFY_SIZE: usize = 4096;
+
+// Log pages.
+const LOG_ERROR_INFORMATION: u32 = 0x01;
+const LOG_SMART: u32 = 0x02;
+const LOG_FIRMWARE_SLOT: u32 = 0x03;
+
+// Features.
+const FEATURE_VOLATILE_WRITE_CACHE: u8 = 0x06;
+const FEATURE_NUMBER_OF_QUEUES: u8 = 0x07;
+const SUPPORTED_FEATURES: [u8; 10] = [0x01, 0x02, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b];
+
+// Status codes, with the status code type in the upper byte.
+const SC_SUCCESS: u16 = 0x00;
+const SC_INVALID_OPCODE: u16 = 0x01;
+const SC_INVALID_FIELD: u16 = 0x02;
+const SC_DATA_TRANSFER_ERROR: u16 = 0x04;
+const SC_INTERNAL_ERROR: u16 = 0x06;
+const SC_INVALID_NAMESPACE: u16 = 0x0b;
+const SC_NAMESPACE_WRITE_PROTECTED: u16 = 0x20;
+const SC_LBA_OUT_OF_RANGE: u16 = 0x80;
+const SC_COMPLETION_QUEUE_INVALID: u16 = 0x100;
+const SC_INVALID_QUEUE_IDENTIFIER: u16 = 0x101;
+const SC_INVALID_QUEUE_SIZE: u16 = 0x102;
+const SC_ASYNC_EVENT_LIMIT_EXCEEDED: u16 = 0x105;
+const SC_INVALID_INTERRUPT_VECTOR: u16 = 0x108;
+const SC_INVALID_LOG_PAGE: u16 = 0x109;
+const SC_INVALID_QUEUE_DELETION: u16 = 0x10c;
+
+// Sources of the events of the controller.
+const PROCESS_DOORBELL: u32 = 0;
+const PROCESS_ENGINE_COMPLETION: u32 = 1;
+
+/// Errors of the NVMe controller.
+#[derive(Debug, thiserror::Error, displaydoc::Display)]
+pub enum NvmeError {
+    /// Failed to open the disk image {1}: {0}
+    BackingFile(std::io::Error, String),
+    /// Failed to create the IO engine: {0}
+    FileEngine(BlockIoError),
+    /// Failed to create the doorbell event: {0}
+    EventFd(std::io::Error),
+    /// Failed to create the interrupts of the controller: {0}
+    Interrupts(#[from] InterruptError),
+    /// Failed to allocate the BAR of the controller: {0}
+    Bar(#[from] vm_allocator::Error),
+}
+
+/// Result of a command: its command specific result, or its status code.
+type CommandResult = Result<u32, u16>;
+
+/// What became of an I/O command.
+enum Submission {
+    /// The command completed right away.
+    Completed(CommandResult),
+    /// The command waits for operations of the IO engine.
+    Inflight,
+    /// The IO engine is busy, the command has to be fetched again later.
+    Busy,
+}
+
+/// I/O command whose operations are being executed by the IO engine.
+#[derive(Debug)]
+struct InflightCommand {
+    sqid: u16,
+    cid: u16,
+    /// Number of operations still pending.
+    pending: u32,
+    status: u16,
+}
+
+/// Emulated NVMe controller, exposing a disk image as its namespace.
+#[derive(Debug)]
+pub struct NvmeController {
+    id: String,
+    configuration: PciConfiguration,
+    msix_config: Arc<Mutex<MsixConfig>>,
+    bar_address: u64,
+    mem: GuestMemoryMmap,
+
+    /// IO engine of the disk image, whose operations carry the tag of their command.
+    engine: FileEngine<u64>,
+    nsectors: u64,
+    read_only: bool,
+    cache_type: CacheType,
+    /// Signaled by the doorbells, for the VMM thread to process the submission queues.
+    doorbell_evt: EventFd,
+
+    cc: u32,
+    csts: u32,
+    intms: u32,
+    aqa: u32,
+    asq: u64,
+    acq: u64,
+
+    /// Queues indexed by their identifier, the admin queues first.
+    sqs: Vec<Option<SubmissionQueue>>,
+    cqs: Vec<Option<CompletionQueue>>,
+    features: BTreeMap<u8, u32>,
+    async_events: u32,
+    inflight: BTreeMap<u64, InflightCommand>,
+    next_tag: u64,
+    /// Number of operations submitted to the IO engine and not completed yet.
+    engine_ops: u32,
+}
+
+impl NvmeController {
+    /// Creates the controller, opening its disk image and allocating its BAR and interrupts.
+    pub fn new(vm: &Arc<Vm>, bdf: PciBdf, config: &NvmeDeviceConfig) -> Result<Self, NvmeError> {
+        let path = &config.path_on_host;
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(!config.is_read_only)
+            .open(path)
+            .map_err(|err| NvmeError::BackingFile(err, path.clone()))?;
+        let size = file
+            .seek(SeekFrom::End(0))
+            .map_err(|err| NvmeError::BackingFile(err, path.clone()))?;
+        if size % (1 << SECTOR_SHIFT) != 0 {
+            warn!(
+                "nvme: the size of {path} is not a multiple of the sector size, the remainder \
+                 will not be visible to the guest"
+            );
+        }
+        let engine =
+            FileEngine::from_file(file, config.file_engine_type).map_err(NvmeError::FileEngine)?;
+
+        let vectors = Vm::create_msix_group(vm.clone(), MSIX_VECTORS)?;
+        let msix_config = Arc::new(Mutex::new(MsixConfig::new(Arc::new(vectors), bdf.into())));
+        let mut configuration = PciConfiguration::new_type0(
+            NVME_VENDOR_ID,
+            NVME_DEVICE_ID,
+            0x2,
+            PciClassCode::MassStorage,
+            &PciMassStorageSubclass::NvmController,
+            NVME_VENDOR_ID,
+            NVME_DEVICE_ID,
+            Some(msix_config.clone()),
+        );
+        configuration.set_programming_interface(NVME_PROG_IF);
+
+        let bar_address = vm.resource_allocator().allocate_64bit_mmio_memory(
+            NVME_BAR_SIZE,
+            NVME_BAR_SIZE,
+            AllocPolicy::FirstMatch,
+        )?;
+        configuration.add_pci_bar(0, bar_address, NVME_BAR_SIZE);
+        configuration.add_capability(&MsixCap::new(
+            0,
+            MSIX_VECTORS,
+            u32::try_from(MSIX_TABLE_OFFSET).unwrap(),
+            0,
+            u32::try_from(MSIX_PBA_OFFSET).unwrap(),
+        ));
+
+        let queues = usize::from(MAX_IO_QUEUES) + 1;
+        Ok(NvmeController {
+            id: config.id.clone(),
+            configuration,
+            msix_config,
+            bar_address,
+            mem: vm.guest_memory().clone(),
+            engine,
+            nsectors: size >> SECTOR_SHIFT,
+            read_only: config.is_read_only,
+            cache_type: config.cache_type,
+            doorbell_evt: EventFd::new(libc::EFD_NONBLOCK).map_err(NvmeError::EventFd)?,
+            cc: 0,
+            csts: 0,
+            intms: 0,
+            aqa: 0,
+            asq: 0,
+            acq: 0,
+            sqs: (0..queues).map(|_| None).collect(),
+            cqs: (0..queues).map(|_| None).collect(),
+            features: BTreeMap::new(),
+            async_events: 0,
+            inflight: BTreeMap::new(),
+            next_tag: 0,
+            engine_ops: 0,
+        })
+    }
+
+    /// Address of the BAR of the controller.
+    pub fn bar_address(&self) -> u64 {
+        self.bar_address
+    }
+
+    fn read_register(&self, offset: u64) -> u32 {
+        match offset {
+            CAP_LO => (CAP & 0xffff_ffff) as u32,
+            CAP_HI => (CAP >> 32) as u32,
+            VS => VERSION,
+            INTMS | INTMC => self.intms,
+            CC => self.cc,
+            CSTS => self.csts,
+            AQA => self.aqa,
+            ASQ_LO => (self.asq & 0xffff_ffff) as u32,
+            ASQ_HI => (self.asq >> 32) as u32,
+            ACQ_LO => (self.acq & 0xffff_ffff) as u32,
+            ACQ_HI => (self.acq >> 32) as u32,
+            _ => 0,
+        }
+    }
+
+    fn write_register(&mut self, offset: u64, value: u32) {
+        match offset {
+            // The interrupt mask registers only apply to pin-based interrupts, which the
+            // controller doesn't use.
+            INTMS => self.intms |= value,
+            INTMC => self.intms &= !value,
+            CC => self.write_cc(value),
+            AQA => self.aqa = value,
+            ASQ_LO => self.asq = (self.asq & !0xffff_ffff) | u64::from(value),
+            ASQ_HI => self.asq = (self.asq & 0xffff_ffff) | (u64::from(value) << 32),
+            ACQ_LO => self.acq = (self.acq & !0xffff_ffff) | u64::from(value),
+            ACQ_HI => self.acq = (self.acq & 0xffff_ffff) | (u64::from(value) << 32),
+            o if (DOORBELL_OFFSET..DOORBELL_OFFSET + 8 * (u64::from(MAX_IO_QUEUES) + 1))
+                .contains(&o) =>
+            {
+                self.write_doorbell(o, value)
+            }
+            _ => debug!("nvme: ignoring write of {value:#x} at {offset:#x}"),
+        }
+    }
+
+    fn write_cc(&mut self, value: u32) {
+        let enabled = self.cc & CC_EN != 0;
+        self.cc = value;
+        if value & CC_EN != 0 && !enabled {
+            self.enable();
+        } else if value & CC_EN == 0 && enabled {
+            self.reset();
+        }
+
+        // The disk image is flushed by the VMM thread, which then reports the shutdown as
+        // complete.
+        if value & CC_SHN_MASK != 0 && self.csts & CSTS_SHST_MASK == 0 {
+            self.csts |= CSTS_SHST_OCCURRING;
+            self.notify_vmm_thread();
+        }
+    }
+
+    fn enable(&mut self) {
+        let sq_size = (self.aqa & 0xfff) + 1;
+        let cq_size = ((self.aqa >> 16) & 0xfff) + 1;
+        if (self.cc >> CC_MPS_SHIFT) & 0xf != 0
+            || (self.cc >> CC_CSS_SHIFT) & 0x7 != 0
+            || sq_size < 2
+            || cq_size < 2
+        {
+            warn!(
+                "nvme: invalid configuration of the controller: {:#x}",
+                self.cc
+            );
+            self.csts |= CSTS_CFS;
+            return;
+        }
+        self.sqs[0] = Some(SubmissionQueue::new(
+            self.asq & !(PAGE_SIZE - 1),
+            u16::try_from(sq_size).unwrap(),
+            0,
+        ));
+        self.cqs[0] = Some(CompletionQueue::new(
+            self.acq & !(PAGE_SIZE - 1),
+            u16::try_from(cq_size).unwrap(),
+            Some(0),
+        ));
+        self.csts = CSTS_RDY;
+    }
+
+    // Operations of the IO engine still in flight complete later on, and are dropped as their
+    // tags don't match any command anymore.
+    fn reset(&mut self) {
+        self.sqs.iter_mut().for_each(|sq| *sq = None);
+        self.cqs.iter_mut().for_each(|cq| *cq = None);
+        self.features.clear();
+        self.async_events = 0;
+        self.inflight.clear();
+        self.csts = 0;
+    }
+
+    fn notify_vmm_thread(&self) {
+        if let Err(err) = self.doorbell_evt.write(1) {
+            error!("nvme: failed to signal the doorbell event: {err}");
+        }
+    }
+
+    fn write_doorbell(&mut self, offset: u64, value: u32) {
+        if self.csts & CSTS_RDY == 0 {
+            return;
+        }
+        let index = (offset - DOORBELL_OFFSET) / 4;
+        let qid = usize::try_from(index / 2).unwrap();
+        let value = (value & 0xffff) as u16;
+        if index % 2 == 0 {
+            match self.sqs[qid].as_mut() {
+                Some(sq) if value < sq.size => {
+                    sq.tail = value;
+                    self.notify_vmm_thread();
+                }
+                _ => warn!("nvme: invalid tail {value} of submission queue {qid}"),
+            }
+        } else {
+            match self.cqs[qid].as_mut() {
+                Some(cq) if value < cq.size => {
+                    cq.set_head(&self.mem, value);
+                    self.signal_completions();
+                }
+                _ => warn!("nvme: invalid head {value} of completion queue {qid}"),
+            }
+        }
+    }
+
+    /// Processes the commands queued by the guest, from the VMM thread.
+    fn process_doorbells(&mut self) {
+        if let Err(err) = self.doorbell_evt.read() {
+            error!("nvme: failed to read the doorbell event: {err}");
+        }
+        if self.csts & CSTS_SHST_MASK == CSTS_SHST_OCCURRING {
+            self.shutdown();
+        }
+        self.process_submission_queues();
+    }
+
+    fn shutdown(&mut self) {
+        let result = match self.cache_type {
+            CacheType::Writeback => self.engine.drain_and_flush(false),
+            CacheType::Unsafe => self.engine.drain(false),
+        };
+        if let Err(err) = result {
+            error!("nvme: failed to flush the disk image: {err}");
+        }
+        self.process_engine_completions();
+        self.csts = (self.csts & !CSTS_SHST_MASK) | CSTS_SHST_COMPLETE;
+    }
+
+    fn process_submission_queues(&mut self) {
+        for sqid in 0..=MAX_IO_QUEUES {
+            self.process_submission_queue(sqid);
+        }
+        if let FileEngine::Async(engine) = &mut self.engine
+            && let Err(err) = engine.kick_submission_queue()
+        {
+            error!("nvme: failed to submit the IO operations: {err}");
+        }
+        self.signal_completions();
+    }
+
+    fn process_submission_queue(&mut self, sqid: u16) {
+        loop {
+            let Some(sq) = self.sqs[usize::from(sqid)].as_mut() else {
+                return;
+            };
+            let command = match sq.peek(&self.mem) {
+                Ok(Some(command)) => command,
+                Ok(None) => return,
+                Err(err) => {
+                    error!("nvme: failed to fetch a command of queue {sqid}: {err}");
+                    self.csts |= CSTS_CFS;
+                    return;
+                }
+            };
+
+            if sqid == 0 {
+                sq.pop();
+                if let Some(result) = self.admin_command(&command) {
+                    self.complete(0, command.cid, result);
+                }
+                continue;
+            }
+            match self.io_command(sqid, &command) {
+                Submission::Busy => return,
+                Submission::Inflight => self.pop(sqid),
+                Submission::Completed(result) => {
+                    self.pop(sqid);
+                    self.complete(sqid, command.cid, result);
+                }
+            }
+        }
+    }
+
+    fn pop(&mut self, sqid: u16) {
+        if let Some(sq) = self.sqs[usize::from(sqid)].as_mut() {
+            sq.pop();
+        }
+    }
+
+    /// Posts the completion of a command to the completion queue of its submission queue.
+    fn complete(&mut self, sqid: u16, cid: u16, result: CommandResult) {
+        // The submission queue may have been deleted while the command was in flight.
+        let Some(sq) = self.sqs[usize::from(sqid)].as_ref() else {
+            return;
+        };
+        let completion = Completion {
+            result: result.unwrap_or(0),
+            sqid,
+            cid,
+            status: result.err().unwrap_or(SC_SUCCESS),
+        };
+        if let Some(cq) = self.cqs[usize::from(sq.cqid)].as_mut() {
+            cq.post(&self.mem, completion, sq.head);
+        }
+    }
+
+    fn signal_completions(&mut self) {
+        let mut config = self.msix_config.lock().expect("Poisoned lock");
+        for cq in self.cqs.iter_mut().flatten() {
+            if !std::mem::take(&mut cq.needs_interrupt) {
+                continue;
+            }
+            // Pin-based interrupts are not supported.
+            let Some(vector) = cq.vector else {
+                continue;
+            };
+            if !config.enabled {
+                continue;
+            }
+            if config.masked || config.table_entries[usize::from(vector)].masked() {
+                config.set_pba_bit(vector, false);
+                continue;
+            }
+            if let Err(err) = config.vectors.trigger(usize::from(vector)) {
+                error!("nvme: failed to signal the interrupt of vector {vector}: {err}");
+            }
+        }
+    }
+
+    /// Executes an admin command, returning its result unless it completes later on.
+    fn admin_command(&mut self, command: &Command) -> Option<CommandResult> {
+        let result = match command.opcode {
+            ADMIN_DELETE_SQ => self.delete_sq(command),
+            ADMIN_CREATE_SQ => self.create_sq(command),
+            ADMIN_GET_LOG_PAGE => self.get_log_page(command),
+            ADMIN_DELETE_CQ => self.delete_cq(command),
+            ADMIN_CREATE_CQ => self.create_cq(command),
+            ADMIN_IDENTIFY => self.identify(command),
+            // Commands complete too quickly to be aborted.
+            ADMIN_ABORT => Ok(1),
+            ADMIN_SET_FEATURES => self.set_features(command),
+            ADMIN_GET_FEATURES => self.get_features(command),
+            // No asynchronous event is ever reported, so the requests stay outstanding.
+            ADMIN_ASYNC_EVENT_REQUEST if self.async_events < MAX_ASYNC_EVENTS => {
+                self.async_events += 1;
+                return None;
+            }
+            ADMIN_ASYNC_EVENT_REQUEST => Err(SC_ASYNC_EVENT_LIMIT_EXCEEDED),
+            ADMIN_KEEP_ALIVE => Ok(0),
+            opcode => {
+                debug!("nvme: unsupported admin command {opcode:#x}");
+                Err(SC_INVALID_OPCODE)
+            }
+        };
+        Some(result)
+    }
+
+    fn create_cq(&mut self, command: &Command) -> CommandResult {
+        let qid = usize::from((command.cdw10 & 0xffff) as u16);
+        let size = (command.cdw10 >> 16) + 1;
+        let contiguous = command.cdw11 & 0x1 != 0;
+        let interrupts = command.cdw11 & 0x2 != 0;
+        let vector = (command.cdw11 >> 16) as u16;
+        if qid == 0 || qid >= self.cqs.len() || self.cqs[qid].is_some() {
+            return Err(SC_INVALID_QUEUE_IDENTIFIER);
+        }
+        if !(2..=MAX_QUEUE_ENTRIES).contains(&size) {
+            return Err(SC_INVALID_QUEUE_SIZE);
+        }
+        if !contiguous || command.prp1 % PAGE_SIZE != 0 {
+            return Err(SC_INVALID_FIELD);
+        }
+        if vector >= MSIX_VECTORS {
+            return Err(SC_INVALID_INTERRUPT_VECTOR);
+        }
+        self.cqs[qid] = Some(CompletionQueue::new(
+            command.prp1,
+            u16::try_from(size).unwrap(),
+            interrupts.then_some(vector),
+        ));
+        Ok(0)
+    }
+
+    fn create_sq(&mut self, command: &Command) -> CommandResult {
+        let qid = usize::from((command.cdw10 & 0xffff) as u16);
+        let size = (command.cdw10 >> 16) + 1;
+        let contiguous = command.cdw11 & 0x1 != 0;
+        let cqid = (command.cdw11 >> 16) as u16;
+        if qid == 0 || qid >= self.sqs.len() || self.sqs[qid].is_some() {
+            return Err(SC_INVALID_QUEUE_IDENTIFIER);
+        }
+        if !(2..=MAX_QUEUE_ENTRIES).contains(&size) {
+            return Err(SC_INVALID_QUEUE_SIZE);
+        }
+        if !contiguous || command.prp1 % PAGE_SIZE != 0 {
+            return Err(SC_INVALID_FIELD);
+        }
+        if cqid == 0 || self.cqs.get(usize::from(cqid)).is_none_or(Option::is_none) {
+            return Err(SC_COMPLETION_QUEUE_INVALID);
+        }
+        self.sqs[qid] = Some(SubmissionQueue::new(
+            command.prp1,
+            u16::try_from(size).unwrap(),
+            cqid,
+        ));
+        Ok(0)
+    }
+
+    fn delete_sq(&mut self, command: &Command) -> CommandResult {
+        let qid = usize::from((command.cdw10 & 0xffff) as u16);
+        if qid == 0 || self.sqs.get(qid).is_none_or(Option::is_none) {
+            return Err(SC_INVALID_QUEUE_IDENTIFIER);
+        }
+        self.sqs[qid] = None;
+        Ok(0)
+    }
+
+    fn delete_cq(&mut self, command: &Command) -> CommandResult {
+        let qid = (command.cdw10 & 0xffff) as u16;
+        let index = usize::from(qid);
+        if qid == 0 || self.cqs.get(index).is_none_or(Option::is_none) {
+            return Err(SC_INVALID_QUEUE_IDENTIFIER);
+        }
+        if self.sqs.iter().flatten().any(|sq| sq.cqid == qid) {
+            return Err(SC_INVALID_QUEUE_DELETION);
+        }
+        self.cqs[index] = None;
+        Ok(0)
+    }
+
+    fn identify(&self, command: &Command) -> CommandResult {
+        let cns = command.cdw10 & 0xff;
+        let mut data = vec![0u8; IDENTIFY_SIZE];
+        match cns {
+            IDENTIFY_NAMESPACE if command.nsid == NSID => self.identify_namespace(&mut data),
+            IDENTIFY_CONTROLLER => self.identify_controller(&mut data),
+            IDENTIFY_ACTIVE_NAMESPACES if command.nsid < NSID => {
+                data[..4].copy_from_slice(&NSID.to_le_bytes())
+            }
+            IDENTIFY_ACTIVE_NAMESPACES => (),
+            // The namespace has no identifier other than its NSID.
+            IDENTIFY_NAMESPACE_DESCRIPTORS if command.nsid == NSID => (),
+            IDENTIFY_NAMESPACE | IDENTIFY_NAMESPACE_DESCRIPTORS => {
+                return Err(SC_INVALID_NAMESPACE);
+            }
+            _ => return Err(SC_INVALID_FIELD),
+        }
+        self.write_data(command, &data)?;
+        Ok(0)
+    }
+
+    fn identify_controller(&self, data: &mut [u8]) {
+        fn put_string(field: &mut [u8], value: &str) {
+            field.fill(b' ');
+            let length = value.len().min(field.len());
+            field[..length].copy_from_slice(&value.as_bytes()[..length]);
+        }
+
+        data[0..2].copy_from_slice(&NVME_VENDOR_ID.to_le_bytes());
+        data[2..4].copy_from_slice(&NVME_VENDOR_ID.to_le_bytes());
+        put_string(&mut data[4..24], &self.id);
+        put_string(&mut data[24..64], "Firecracker NVMe Controller");
+        put_string(&mut data[64..72], "1.0");
+        // Maximum data transfer size.
+        data[77] = MDTS;
+        data[80..84].copy_from_slice(&VERSION.to_le_bytes());
+        // I/O controller.
+        data[111] = 1;
+        // Abort command limit and asynchronous event request limit, both 0's based.
+        data[258] = 3;
+        data[259] = u8::try_from(MAX_ASYNC_EVENTS - 1).unwrap();
+        // A single firmware slot, which is read only.
+        data[260] = 0x3;
+        // Submission and completion queue entry sizes, as powers of two.
+        data[512] = 0x66;
+        data[513] = 0x44;
+        data[516..520].copy_from_slice(&NSID.to_le_bytes());
+        // Volatile write cache.
+        data[525] = u8::from(self.cache_type == CacheType::Writeback);
+        // Subsystem NQN, which differs between controllers so that guests don't merge them.
+        let nqn = format!("nqn.2025-01.io.github.firecracker-microvm:nvme:{}", self.id);
+        let length = nqn.len().min(255);
+        data[768..768 + length].copy_from_slice(&nqn.as_bytes()[..length]);
+        // Maximum power of the only power state, in centiwatts.
+        data[2048..2050].copy_from_slice(&2500u16.to_le_bytes());
+    }
+
+    fn identify_namespace(&self, data: &mut [u8]) {
+        // Size, capacity and utilization of the namespace.
+        for field in data[..24].chunks_mut(8) {
+            field.copy_from_slice(&self.nsectors.to_le_bytes());
+        }
+        // Attributes: write protected.
+        data[99] = u8::from(self.read_only);
+        // A single LBA format, with 512 bytes sectors.
+        data[130] = SECTOR_SHIFT;
+    }
+
+    fn get_log_page(&self, command: &Command) -> CommandResult {
+        let lid = command.cdw10 & 0xff;
+        let dwords = u64::from(command.cdw10 >> 16) | (u64::from(command.cdw11 & 0xffff) << 16);
+        let length = usize::try_from(((dwords + 1) * 4).min(PAGE_SIZE)).unwrap();
+        let mut data = vec![0u8; length];
+        match lid {
+            LOG_ERROR_INFORMATION | LOG_FIRMWARE_SLOT => (),
+            LOG_SMART => {
+                // Composite temperature, in kelvins.
+                let temperature = 293u16.to_le_bytes();
+                let end = length.min(3);
+                data[1..end].copy_from_slice(&temperature[..end - 1]);
+            }
+            _ => return Err(SC_INVALID_LOG_PAGE),
+        }
+        self.write_data(command, &data)?;
+        Ok(0)
+    }
+
+    fn set_features(&mut self, command: &Command) -> CommandResult {
+        let fid = (command.cdw10 & 0xff) as u8;
+        match fid {
+            FEATURE_NUMBER_OF_QUEUES => Ok(self.number_of_queues()),
+            FEATURE_VOLATILE_WRITE_CACHE if self.cache_type != CacheType::Writeback => {
+                Err(SC_INVALID_FIELD)
+            }
+            fid if SUPPORTED_FEATURES.contains(&fid) => {
+                self.features.insert(fid, command.cdw11);
+                Ok(0)
+            }
+            _ => Err(SC_INVALID_FIELD),
+        }
+    }
+
+    fn get_features(&self, command: &Command) -> CommandResult {
+        let fid = (command.cdw10 & 0xff) as u8;
+        match fid {
+            FEATURE_NUMBER_OF_QUEUES => Ok(self.number_of_queues()),
+            FEATURE_VOLATILE_WRITE_CACHE => Ok(self
+                .features
+                .get(&fid)
+                .copied()
+                .unwrap_or(u32::from(self.cache_type == CacheType::Writeback))),
+            fid if SUPPORTED_FEATURES.contains(&fid) => {
+                Ok(self.features.get(&fid).copied().unwrap_or(0))
+            }
+            _ => Err(SC_INVALID_FIELD),
+        }
+    }
+
+    // All the I/O queues are available whatever the guest asks for, as 0's based counts of
+    // submission and completion queues.
+    fn number_of_queues(&self) -> u32 {
+        let queues = u32::from(MAX_IO_QUEUES - 1);
+        queues | (queues << 16)
+    }
+
+    /// Copies data to the buffer of a command.
+    fn write_data(&self, command: &Command, data: &[u8]) -> Result<(), u16> {
+        let segments = prp_segments(&self.mem, command.prp1, command.prp2, data.len() as u64)
+            .map_err(|_| SC_DATA_TRANSFER_ERROR)?;
+        let mut offset = 0;
+        for (addr, length) in segments {
+            let length = length as usize;
+            self.mem
+                .write_slice(&data[offset..offset + length], addr)
+                .map_err(|_| SC_DATA_TRANSFER_ERROR)?;
+            offset += length;
+        }
+        Ok(())
+    }
+
+    fn io_command(&mut self, sqid: u16, command: &Command) -> Submission {
+        if command.nsid != NSID {
+            return Submission::Completed(Err(SC_INVALID_NAMESPACE));
+        }
+        let segments = match command.opcode {
+            IO_FLUSH if self.cache_type == CacheType::Unsafe => {
+                return Submission::Completed(Ok(0));
+            }
+            IO_FLUSH => Vec::new(),
+            IO_READ | IO_WRITE => {
+                let slba = u64::from(command.cdw10) | (u64::from(command.cdw11) << 32);
+                let nlb = u64::from(command.cdw12 & 0xffff) + 1;
+                let length = nlb << SECTOR_SHIFT;
+                if command.opcode == IO_WRITE && self.read_only {
+                    return Submission::Completed(Err(SC_NAMESPACE_WRITE_PROTECTED));
+                }
+                if length > MAX_TRANSFER_SIZE {
+                    return Submission::Completed(Err(SC_INVALID_FIELD));
+                }
+                if slba.checked_add(nlb).is_none_or(|end| end > self.nsectors) {
+                    return Submission::Completed(Err(SC_LBA_OUT_OF_RANGE));
+                }
+                match prp_segments(&self.mem, command.prp1, command.prp2, length) {
+                    Ok(segments) => segments,
+                    Err(_) => return Submission::Completed(Err(SC_DATA_TRANSFER_ERROR)),
+                }
+            }
+            opcode => {
+                debug!("nvme: unsupported I/O command {opcode:#x}");
+                return Submission::Completed(Err(SC_INVALID_OPCODE));
+            }
+        };
+
+        let ops = u32::try_from(segments.len().max(1)).unwrap();
+        if matches!(self.engine, FileEngine::Async(_))
+            && self.engine_ops + ops > u32::from(IO_URING_NUM_ENTRIES)
+        {
+            return Submission::Busy;
+        }
+
+        let tag = self.next_tag;
+        self.next_tag += 1;
+        let mut inflight = InflightCommand {
+            sqid,
+            cid: command.cid,
+            pending: 0,
+            status: SC_SUCCESS,
+        };
+        let results = if command.opcode == IO_FLUSH {
+            vec![self.engine.flush(tag)]
+        } else {
+            let mut offset = u64::from(command.cdw10) | (u64::from(command.cdw11) << 32);
+            offset <<= SECTOR_SHIFT;
+            let mut results = Vec::with_capacity(segments.len());
+            for (addr, length) in segments {
+                results.push(if command.opcode == IO_READ {
+                    self.engine.read(offset, &self.mem, addr, length, tag)
+                } else {
+                    self.engine.write(offset, &self.mem, addr, length, tag)
+                });
+                offset += u64::from(length);
+            }
+            results
+        };
+        for result in results {
+            match result {
+                Ok(FileEngineOk::Submitted) => {
+                    inflight.pending += 1;
+                    self.engine_ops += 1;
+                }
+                Ok(FileEngineOk::Executed(_)) => (),
+                Err(err) => {
+                    error!("nvme: failed to execute an I/O command: {}", err.error);
+                    inflight.status = SC_INTERNAL_ERROR;
+                }
+            }
+        }
+
+        if inflight.pending == 0 {
+            return Submission::Completed(match inflight.status {
+                SC_SUCCESS => Ok(0),
+                status => Err(status),
+            });
+        }
+        self.inflight.insert(tag, inflight);
+        Submission::Inflight
+    }
+
+    fn process_engine_completions(&mut self) {
+        let FileEngine::Async(engine) = &mut self.engine else {
+            return;
+        };
+        if let Err(err) = engine.completion_evt().read() {
+            error!("nvme: failed to read the completion event of the IO engine: {err}");
+        }
+        let mut completed = Vec::new();
+        loop {
+            let cqe = match engine.pop(&self.mem) {
+                Ok(Some(cqe)) => cqe,
+                Ok(None) => break,
+                Err(err) => {
+                    error!("nvme: failed to read a completed IO operation: {err}");
+                    break;
+                }
+            };
+            self.engine_ops -= 1;
+            let result = cqe.result();
+            let tag = cqe.user_data();
+            let Some(inflight) = self.inflight.get_mut(&tag) else {
+                continue;
+            };
+            if let Err(err) = result {
+                error!("nvme: failed to execute an I/O command: {err}");
+                inflight.status = SC_INTERNAL_ERROR;
+            }
+            inflight.pending -= 1;
+            if inflight.pending == 0 {
+                completed.push(tag);
+            }
+        }
+
+        for tag in completed {
+            let inflight = self.inflight.remove(&tag).unwrap();
+            let result = match inflight.status {
+                SC_SUCCESS => Ok(0),
+                status => Err(status),
+            };
+            self.complete(inflight.sqid, inflight.cid, result);
+        }
+    }
+}
+
+impl PciDevice for NvmeController {
+    fn write_config_register(
+        &mut self,
+        reg_idx: usize,
+        offset: u64,
+        data: &[u8],
+    ) -> Option<Arc<Barrier>> {
+        self.configuration
+            .write_config_register(reg_idx, offset, data);
+        None
+    }
+
+    fn read_config_register(&mut self, reg_idx: usize) -> u32 {
+        self.configuration.read_reg(reg_idx)
+    }
+
+    fn detect_bar_reprogramming(
+        &mut self,
+        reg_idx: usize,
+        data: &[u8],
+    ) -> Option<crate::pci::BarReprogrammingParams> {
+        self.configuration.detect_bar_reprogramming(reg_idx, data)
+    }
+
+    fn read_bar(&mut self, _base: u64, offset: u64, data: &mut [u8]) {
+        if (MSIX_TABLE_OFFSET..MSIX_TABLE_OFFSET + MSIX_TABLE_SIZE).contains(&offset) {
+            let config = self.msix_config.lock().expect("Poisoned lock");
+            config.read_table(offset - MSIX_TABLE_OFFSET, data);
+            return;
+        }
+        if (MSIX_PBA_OFFSET..MSIX_PBA_OFFSET + MSIX_PBA_SIZE).contains(&offset) {
+            let config = self.msix_config.lock().expect("Poisoned lock");
+            config.read_pba(offset - MSIX_PBA_OFFSET, data);
+            return;
+        }
+
+        // 64-bit registers are accessed as two dwords, smaller accesses pick bytes of a dword.
+        let mut bytes = [0u8; 8];
+        let aligned = offset & !0x3;
+        for (index, chunk) in bytes.chunks_mut(4).enumerate() {
+            let value = self.read_register(aligned + 4 * index as u64);
+            chunk.copy_from_slice(&value.to_le_bytes());
+        }
+        let start = usize::try_from(offset - aligned).unwrap();
+        let end = (start + data.len()).min(bytes.len());
+        data[..end - start].copy_from_slice(&bytes[start..end]);
+    }
+
+    fn write_bar(&mut self, _base: u64, offset: u64, data: &[u8]) -> Option<Arc<Barrier>> {
+        if (MSIX_TABLE_OFFSET..MSIX_TABLE_OFFSET + MSIX_TABLE_SIZE).contains(&offset) {
+            let mut config = self.msix_config.lock().expect("Poisoned lock");
+            config.write_table(offset - MSIX_TABLE_OFFSET, data);
+            return None;
+        }
+        if (MSIX_PBA_OFFSET..MSIX_PBA_OFFSET + MSIX_PBA_SIZE).contains(&offset) {
+            let mut config = self.msix_config.lock().expect("Poisoned lock");
+            config.write_pba(offset - MSIX_PBA_OFFSET, data);
+            return None;
+        }
+
+        match data.len() {
+            4 => self.write_register(offset, u32::from_le_bytes(data.try_into().unwrap())),
+            8 => {
+                self.write_register(offset, u32::from_le_bytes(data[..4].try_into().unwrap()));
+                self.write_register(
+                    offset + 4,
+                    u32::from_le_bytes(data[4..].try_into().unwrap()),
+                );
+            }
+            len => warn!("nvme: unsupported write of {len} bytes at {offset:#x}"),
+        }
+        None
+    }
+}
+
+impl BusDevice for NvmeController {
+    fn read(&mut self, base: u64, offset: u64, data: &mut [u8]) {
+        self.read_bar(base, offset, data)
+    }
+
+    fn write(&mut self, base: u64, offset: u64, data: &[u8]) -> Option<Arc<Barrier>> {
+        self.write_bar(base, offset, data)
+    }
+}
+
+impl MutEventSubscriber for NvmeController {
+    fn process(&mut self, events: Events, _ops: &mut EventOps) {
+        match events.data() {
+            PROCESS_DOORBELL => self.process_doorbells(),
+            PROCESS_ENGINE_COMPLETION => {
+                self.process_engine_completions();
+                // Commands waiting for room in the IO engine may go on.
+                self.process_submission_queues();
+            }
+            source => warn!("nvme: unexpected event source {source}"),
+        }
+    }
+
+    fn init(&mut self, ops: &mut EventOps) {
+        if let Err(err) = ops.add(Events::with_data(
+            &self.doorbell_evt,
+            PROCESS_DOORBELL,
+            EventSet::IN,
+        )) {
+            error!("nvme: failed to register the doorbell event: {err}");
+        }
+        if let FileEngine::Async(engine) = &self.engine
+            && let Err(err) = ops.add(Events::with_data(
+                engine.completion_evt(),
+                PROCESS_ENGINE_COMPLETION,
+                EventSet::IN,
+            ))
+        {
+            error!("nvme: failed to register the completion event of the IO engine: {err}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::os::unix::fs::FileExt;
+
+    use vmm_sys_util::tempfile::TempFile;
+
+    use super::*;
+    use crate::devices::virtio::block::virtio::device::FileEngineType;
+    use crate::vstate::memory::GuestAddress;
+    use crate::vstate::vm::tests::setup_vm_with_memory;
+
+    const ASQ: u64 = 0x1000;
+    const ACQ: u64 = 0x2000;
+    const IO_SQ: u64 = 0x3000;
+    const IO_CQ: u64 = 0x4000;
+    const BUFFER: u64 = 0x10000;
+    const PRP_LIST: u64 = 0x20000;
+
+    fn write32(nvme: &mut NvmeController, offset: u64, value: u32) {
+        nvme.write_bar(0, offset, &value.to_le_bytes());
+    }
+
+    fn read32(nvme: &mut NvmeController, offset: u64) -> u32 {
+        let mut data = [0u8; 4];
+        nvme.read_bar(0, offset, &mut data);
+        u32::from_le_bytes(data)
+    }
+
+    fn controller(engine: FileEngineType, cache_type: CacheType) -> (TempFile, NvmeController) {
+        let (_, vm) = setup_vm_with_memory(0x100000);
+        let vm = Arc::new(vm);
+        let disk = TempFile::new().unwrap();
+        disk.as_file().write_all(&[0x5a; 0x10000]).unwrap();
+        let config = NvmeDeviceConfig {
+            id: "disk".to_string(),
+            path_on_host: disk.as_path().to_str().unwrap().to_string(),
+            is_read_only: false,
+            cache_type,
+            file_engine_type: engine,
+        };
+        let nvme = NvmeController::new(&vm, PciBdf::new(0, 0, 1, 0), &config).unwrap();
+        (disk, nvme)
+    }
+
+    // Enables the controller with admin queues of 4 entries, as done by the guest driver.
+    fn enable(nvme: &mut NvmeController) {
+        write32(nvme, AQA, 3 | (3 << 16));
+        write32(nvme, ASQ_LO, u32::try_from(ASQ).unwrap());
+        write32(nvme, ACQ_LO, u32::try_from(ACQ).unwrap());
+        write32(nvme, CC, CC_EN | (6 << 16) | (4 << 20));
+        assert_eq!(read32(nvme, CSTS), CSTS_RDY);
+    }
+
+    // Queues a command and rings the doorbell of its submission queue.
+    fn submit(nvme: &mut NvmeController, sqid: u16, command: Command) {
+        let sq = nvme.sqs[usize::from(sqid)].as_ref().unwrap();
+        let (addr, tail, size) = (sq.addr, sq.tail, sq.size);
+        nvme.mem
+            .write_slice(
+                &command.to_bytes(),
+                GuestAddress(addr + u64::from(tail) * COMMAND_SIZE),
+            )
+            .unwrap();
+        let tail = (tail + 1) % size;
+        write32(nvme, DOORBELL_OFFSET + 8 * u64::from(sqid), u32::from(tail));
+        nvme.process_doorbells();
+    }
+
+    // Runs a command of a queue pair, returning the result and the status of its completion,
+    // which the guest then consumes.
+    fn run(nvme: &mut NvmeController, qid: u16, command: Command) -> (u32, u16) {
+        let cq = nvme.cqs[usize::from(qid)].as_ref().unwrap();
+        let (addr, index, phase, size) = (cq.addr, cq.tail, cq.phase, cq.size);
+        submit(nvme, qid, command);
+        if let FileEngine::Async(engine) = &mut nvme.engine {
+            engine.drain(false).unwrap();
+            nvme.process_engine_completions();
+        }
+
+        let entry: [u32; 4] = nvme
+            .mem
+            .read_obj(GuestAddress(addr + u64::from(index) * COMPLETION_SIZE))
+            .unwrap();
+        assert_eq!(entry[3] & (1 << 16) != 0, phase, "missing completion");
+        let head = (index + 1) % size;
+        write32(
+            nvme,
+            DOORBELL_OFFSET + 8 * u64::from(qid) + 4,
+            u32::from(head),
+        );
+        (entry[0], (entry[3] >> 17) as u16)
+    }
+
+    fn admin(nvme: &mut NvmeController, command: Command) -> (u32, u16) {
+        run(nvme, 0, command)
+    }
+
+    fn create_io_queues(nvme: &mut NvmeController) {
+        let create_cq = Command {
+            opcode: ADMIN_CREATE_CQ,
+            prp1: IO_CQ,
+            cdw10: 1 | (15 << 16),
+            cdw11: 0x3 | (1 << 16),
+            ..Default::default()
+        };
+        assert_eq!(admin(nvme, create_cq), (0, SC_SUCCESS));
+        let create_sq = Command {
+            opcode: ADMIN_CREATE_SQ,
+            prp1: IO_SQ,
+            cdw10: 1 | (15 << 16),
+            cdw11: 0x1 | (1 << 16),
+            ..Default::default()
+        };
+        assert_eq!(admin(nvme, create_sq), (0, SC_SUCCESS));
+    }
+
+    fn io(nvme: &mut NvmeController, opcode: u8, slba: u64, prp2: u64, blocks: u32) -> u16 {
+        let command = Command {
+            opcode,
+            nsid: NSID,
+            prp1: BUFFER,
+            prp2,
+            cdw10: (slba & 0xffff_ffff) as u32,
+            cdw11: (slba >> 32) as u32,
+            cdw12: blocks - 1,
+            ..Default::default()
+        };
+        run(nvme, 1, command).1
+    }
+
+    #[test]
+    fn test_registers() {
+        let (_disk, mut nvme) = controller(FileEngineType::Sync, CacheType::Unsafe);
+        assert_eq!(read32(&mut nvme, CAP_LO), 0x1001_03ff);
+        assert_eq!(read32(&mut nvme, CAP_HI), 0x20);
+        assert_eq!(read32(&mut nvme, VS), VERSION);
+        assert_eq!(read32(&mut nvme, CSTS), 0);
+
+        // Doorbells are ignored while the controller is disabled.
+        write32(&mut nvme, DOORBELL_OFFSET, 1);
+        assert!(nvme.doorbell_evt.read().is_err());
+
+        enable(&mut nvme);
+        assert_eq!(nvme.sqs[0].as_ref().unwrap().size, 4);
+        assert_eq!(nvme.cqs[0].as_ref().unwrap().addr, ACQ);
+
+        // Shutdown is reported as complete once the VMM thread flushed the disk image.
+        write32(&mut nvme, CC, read32(&mut nvme, CC) | (1 << 14));
+        assert_eq!(read32(&mut nvme, CSTS), CSTS_RDY | CSTS_SHST_OCCURRING);
+        nvme.process_doorbells();
+        assert_eq!(read32(&mut nvme, CSTS), CSTS_RDY | CSTS_SHST_COMPLETE);
+
+        write32(&mut nvme, CC, 0);
+        assert_eq!(read32(&mut nvme, CSTS), 0);
+        assert!(nvme.sqs.iter().all(Option::is_none));
+
+        // Only 4 KiB pages are supported.
+        write32(&mut nvme, CC, CC_EN | (1 << CC_MPS_SHIFT));
+        assert_eq!(read32(&mut nvme, CSTS), CSTS_CFS);
+    }
+
+    #[test]
+    fn test_admin_commands() {
+        let (_disk, mut nvme) = controller(FileEngineType::Sync, CacheType::Writeback);
+        enable(&mut nvme);
+
+        let identify = Command {
+            opcode: ADMIN_IDENTIFY,
+            prp1: BUFFER,
+            cdw10: IDENTIFY_CONTROLLER,
+            ..Default::default()
+        };
+        assert_eq!(admin(&mut nvme, identify), (0, SC_SUCCESS));
+        let mut data = [0u8; IDENTIFY_SIZE];
+        nvme.mem
+            .read_slice(&mut data, GuestAddress(BUFFER))
+            .unwrap();
+        assert_eq!(&data[4..24], b"disk                ");
+        assert_eq!(data[77], MDTS);
+        assert_eq!(data[525], 1);
+
+        let identify = Command {
+            nsid: NSID,
+            cdw10: IDENTIFY_NAMESPACE,
+            ..identify
+        };
+        assert_eq!(admin(&mut nvme, identify), (0, SC_SUCCESS));
+        let nsze: u64 = nvme.mem.read_obj(GuestAddress(BUFFER)).unwrap();
+        assert_eq!(nsze, 0x80);
+        let identify = Command {
+            nsid: 2,
+            ..identify
+        };
+        assert_eq!(admin(&mut nvme, identify), (0, SC_INVALID_NAMESPACE));
+
+        let queues = Command {
+            opcode: ADMIN_SET_FEATURES,
+            cdw10: u32::from(FEATURE_NUMBER_OF_QUEUES),
+            cdw11: 0x1f_001f,
+            ..Default::default()
+        };
+        assert_eq!(admin(&mut nvme, queues), (0x7_0007, SC_SUCCESS));
+
+        // A completion queue can't be deleted while a submission queue uses it.
+        create_io_queues(&mut nvme);
+        let delete_cq = Command {
+            opcode: ADMIN_DELETE_CQ,
+            cdw10: 1,
+            ..Default::default()
+        };
+        assert_eq!(admin(&mut nvme, delete_cq), (0, SC_INVALID_QUEUE_DELETION));
+        let delete_sq = Command {
+            opcode: ADMIN_DELETE_SQ,
+            cdw10: 1,
+            ..Default::default()
+        };
+        assert_eq!(admin(&mut nvme, delete_sq), (0, SC_SUCCESS));
+        assert_eq!(admin(&mut nvme, delete_cq), (0, SC_SUCCESS));
+
+        // Asynchronous events are never reported, their requests stay outstanding.
+        let aer = Command {
+            opcode: ADMIN_ASYNC_EVENT_REQUEST,
+            ..Default::default()
+        };
+        submit(&mut nvme, 0, aer);
+        assert_eq!(nvme.async_events, 1);
+        let unknown = Command {
+            opcode: 0x7f,
+            ..Default::default()
+        };
+        assert_eq!(admin(&mut nvme, unknown), (0, SC_INVALID_OPCODE));
+    }
+
+    fn check_io(engine: FileEngineType) {
+        let (disk, mut nvme) = controller(engine, CacheType::Writeback);
+        enable(&mut nvme);
+        create_io_queues(&mut nvme);
+
+        // Write 3 pages through a PRP list, then read them back.
+        nvme.mem
+            .write_obj(0x12000u64, GuestAddress(PRP_LIST))
+            .unwrap();
+        nvme.mem
+            .write_obj(0x18000u64, GuestAddress(PRP_LIST + 8))
+            .unwrap();
+        for page in [0x10000, 0x12000, 0x18000] {
+            nvme.mem
+                .write_slice(&[0x01; 0x1000], GuestAddress(page))
+                .unwrap();
+        }
+        assert_eq!(io(&mut nvme, IO_WRITE, 8, PRP_LIST, 24), SC_SUCCESS);
+        let mut data = vec![0u8; 0x4000];
+        std::fs::File::open(disk.as_path())
+            .unwrap()
+            .read_exact_at(&mut data, 0x800)
+            .unwrap();
+        assert!(data[..0x800].iter().all(|byte| *byte == 0x5a));
+        assert!(data[0x800..0x3800].iter().all(|byte| *byte == 0x01));
+        assert!(data[0x3800..].iter().all(|byte| *byte == 0x5a));
+
+        nvme.mem
+            .write_slice(&[0u8; 0x1000], GuestAddress(BUFFER))
+            .unwrap();
+        assert_eq!(io(&mut nvme, IO_READ, 0, 0, 8), SC_SUCCESS);
+        let mut data = [0u8; 0x1000];
+        nvme.mem
+            .read_slice(&mut data, GuestAddress(BUFFER))
+            .unwrap();
+        assert!(data.iter().all(|byte| *byte == 0x5a));
+
+        assert_eq!(io(&mut nvme, IO_FLUSH, 0, 0, 1), SC_SUCCESS);
+        assert_eq!(io(&mut nvme, IO_READ, 0x7f, 0, 2), SC_LBA_OUT_OF_RANGE);
+        assert_eq!(io(&mut nvme, IO_READ, 0, 0, 0x101), SC_INVALID_FIELD);
+        assert!(nvme.inflight.is_empty());
+        assert_eq!(nvme.engine_ops, 0);
+    }
+
+    #[test]
+    fn test_io_sync() {
+        check_io(FileEngineType::Sync);
+    }
+
+    #[test]
+    fn test_io_async() {
+        check_io(FileEngineType::Async);
+    }
+
+    #[test]
+    fn test_read_only() {
+        let (disk, _) = controller(FileEngineType::Sync, CacheType::Unsafe);
+        let (_, vm) = setup_vm_with_memory(0x100000);
+        let config = NvmeDeviceConfig {
+            id: "disk".to_string(),
+            path_on_host: disk.as_path().to_str().unwrap().to_string(),
+            is_read_only: true,
+            ..Default::default()
+        };
+        let mut nvme =
+            NvmeController::new(&Arc::new(vm), PciBdf::new(0, 0, 1, 0), &config).unwrap();
+        enable(&mut nvme);
+        create_io_queues(&mut nvme);
+        assert_eq!(
+            io(&mut nvme, IO_WRITE, 0, 0, 1),
+            SC_NAMESPACE_WRITE_PROTECTED
+        );
+        // Without a volatile write cache, flushes complete right away.
+        assert_eq!(io(&mut nvme, IO_FLUSH, 0, 0, 1), SC_SUCCESS);
+    }
+}
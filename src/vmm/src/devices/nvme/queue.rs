@@ -0,0 +1,322 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::VecDeque;
+
+use log::error;
+use vm_memory::GuestMemoryError;
+
+use crate::vstate::memory::{Bytes, GuestAddress, GuestMemoryMmap};
+
+/// Size of the memory pages, as the controller only supports 4 KiB pages.
+pub const PAGE_SIZE: u64 = 0x1000;
+/// Size of a submission queue entry.
+pub const COMMAND_SIZE: u64 = 64;
+/// Size of a completion queue entry.
+pub const COMPLETION_SIZE: u64 = 16;
+
+/// Command fetched from a submission queue.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Command {
+    pub opcode: u8,
+    pub cid: u16,
+    pub nsid: u32,
+    pub prp1: u64,
+    pub prp2: u64,
+    pub cdw10: u32,
+    pub cdw11: u32,
+    pub cdw12: u32,
+    pub cdw13: u32,
+    pub cdw14: u32,
+    pub cdw15: u32,
+}
+
+impl Command {
+    pub fn from_bytes(bytes: &[u8; COMMAND_SIZE as usize]) -> Self {
+        let dword =
+            |index: usize| u32::from_le_bytes(bytes[index * 4..index * 4 + 4].try_into().unwrap());
+        let qword = |index: usize| u64::from(dword(index)) | (u64::from(dword(index + 1)) << 32);
+        Command {
+            opcode: bytes[0],
+            cid: u16::from_le_bytes([bytes[2], bytes[3]]),
+            nsid: dword(1),
+            prp1: qword(6),
+            prp2: qword(8),
+            cdw10: dword(10),
+            cdw11: dword(11),
+            cdw12: dword(12),
+            cdw13: dword(13),
+            cdw14: dword(14),
+            cdw15: dword(15),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn to_bytes(self) -> [u8; COMMAND_SIZE as usize] {
+        let mut bytes = [0u8; COMMAND_SIZE as usize];
+        bytes[0] = self.opcode;
+        bytes[2..4].copy_from_slice(&self.cid.to_le_bytes());
+        bytes[4..8].copy_from_slice(&self.nsid.to_le_bytes());
+        bytes[24..32].copy_from_slice(&self.prp1.to_le_bytes());
+        bytes[32..40].copy_from_slice(&self.prp2.to_le_bytes());
+        for (index, dword) in [
+            self.cdw10, self.cdw11, self.cdw12, self.cdw13, self.cdw14, self.cdw15,
+        ]
+        .iter()
+        .enumerate()
+        {
+            bytes[40 + index * 4..44 + index * 4].copy_from_slice(&dword.to_le_bytes());
+        }
+        bytes
+    }
+}
+
+/// Completion of a command, before it is posted to a completion queue.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Completion {
+    /// Command specific result.
+    pub result: u32,
+    pub sqid: u16,
+    pub cid: u16,
+    /// Status code, with the status code type in the upper byte.
+    pub status: u16,
+}
+
+/// Submission queue, which the guest fills with commands.
+#[derive(Debug)]
+pub struct SubmissionQueue {
+    pub addr: u64,
+    pub size: u16,
+    pub head: u16,
+    pub tail: u16,
+    pub cqid: u16,
+}
+
+impl SubmissionQueue {
+    pub fn new(addr: u64, size: u16, cqid: u16) -> Self {
+        SubmissionQueue {
+            addr,
+            size,
+            head: 0,
+            tail: 0,
+            cqid,
+        }
+    }
+
+    /// Reads the command at the head of the queue, without consuming it.
+    pub fn peek(&self, mem: &GuestMemoryMmap) -> Result<Option<Command>, GuestMemoryError> {
+        if self.head == self.tail {
+            return Ok(None);
+        }
+        let mut bytes = [0u8; COMMAND_SIZE as usize];
+        let addr = self.addr + u64::from(self.head) * COMMAND_SIZE;
+        mem.read_slice(&mut bytes, GuestAddress(addr))?;
+        Ok(Some(Command::from_bytes(&bytes)))
+    }
+
+    pub fn pop(&mut self) {
+        self.head = (self.head + 1) % self.size;
+    }
+}
+
+/// Completion queue, which the controller fills with the completions of the commands.
+#[derive(Debug)]
+pub struct CompletionQueue {
+    pub addr: u64,
+    pub size: u16,
+    pub head: u16,
+    pub tail: u16,
+    pub phase: bool,
+    /// MSI-X vector signaled when completions are posted, if interrupts are enabled.
+    pub vector: Option<u16>,
+    /// Whether completions were posted since the vector was last signaled.
+    pub needs_interrupt: bool,
+    /// Completions waiting for the guest to make room in the queue.
+    pub overflow: VecDeque<(Completion, u16)>,
+}
+
+impl CompletionQueue {
+    pub fn new(addr: u64, size: u16, vector: Option<u16>) -> Self {
+        CompletionQueue {
+            addr,
+            size,
+            head: 0,
+            tail: 0,
+            phase: true,
+            vector,
+            needs_interrupt: false,
+            overflow: VecDeque::new(),
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        (self.tail + 1) % self.size == self.head
+    }
+
+    /// Posts a completion, along with the head of its submission queue, or keeps it until there
+    /// is room in the queue.
+    pub fn post(&mut self, mem: &GuestMemoryMmap, completion: Completion, sq_head: u16) {
+        if self.is_full() || !self.overflow.is_empty() {
+            self.overflow.push_back((completion, sq_head));
+            return;
+        }
+        self.write(mem, completion, sq_head);
+    }
+
+    /// Updates the head of the queue, posting the completions that now fit.
+    pub fn set_head(&mut self, mem: &GuestMemoryMmap, head: u16) {
+        self.head = head;
+        while !self.is_full()
+            && let Some((completion, sq_head)) = self.overflow.pop_front()
+        {
+            self.write(mem, completion, sq_head);
+        }
+    }
+
+    fn write(&mut self, mem: &GuestMemoryMmap, completion: Completion, sq_head: u16) {
+        let mut entry = [0u32; 4];
+        entry[0] = completion.result;
+        entry[2] = u32::from(sq_head) | (u32::from(completion.sqid) << 16);
+        entry[3] = u32::from(completion.cid)
+            | (u32::from(self.phase) << 16)
+            | (u32::from(completion.status) << 17);
+        let addr = self.addr + u64::from(self.tail) * COMPLETION_SIZE;
+        let bytes: Vec<u8> = entry.iter().flat_map(|dword| dword.to_le_bytes()).collect();
+        // The phase tag is in the last dword, write it last so that the guest never sees a
+        // partial entry.
+        if let Err(err) = mem
+            .write_slice(&bytes[..12], GuestAddress(addr))
+            .and_then(|()| mem.write_slice(&bytes[12..], GuestAddress(addr + 12)))
+        {
+            error!("nvme: failed to post a completion: {err}");
+            return;
+        }
+        self.tail += 1;
+        if self.tail == self.size {
+            self.tail = 0;
+            self.phase = !self.phase;
+        }
+        self.needs_interrupt = true;
+    }
+}
+
+fn push_segment(segments: &mut Vec<(GuestAddress, u32)>, addr: u64, length: u64) {
+    let length = u32::try_from(length).unwrap();
+    match segments.last_mut() {
+        Some((last, last_length)) if last.0 + u64::from(*last_length) == addr => {
+            *last_length += length
+        }
+        _ => segments.push((GuestAddress(addr), length)),
+    }
+}
+
+/// Guest memory ranges described by the PRP entries of a command, with the contiguous ones
+/// merged.
+pub fn prp_segments(
+    mem: &GuestMemoryMmap,
+    prp1: u64,
+    prp2: u64,
+    length: u64,
+) -> Result<Vec<(GuestAddress, u32)>, GuestMemoryError> {
+    let mut segments = Vec::new();
+    let first = length.min(PAGE_SIZE - (prp1 % PAGE_SIZE));
+    push_segment(&mut segments, prp1, first);
+    let mut remaining = length - first;
+    if remaining == 0 {
+        return Ok(segments);
+    }
+    if remaining <= PAGE_SIZE {
+        push_segment(&mut segments, prp2, remaining);
+        return Ok(segments);
+    }
+
+    // PRP2 points to a list of entries, whose last entry in a page points to the next page of
+    // the list when more entries are needed.
+    let mut list = prp2;
+    let mut links = 0;
+    while remaining > 0 {
+        let entry: u64 = mem.read_obj(GuestAddress(list))?;
+        if list % PAGE_SIZE == PAGE_SIZE - 8 && remaining > PAGE_SIZE {
+            links += 1;
+            if links > length / PAGE_SIZE {
+                return Err(GuestMemoryError::InvalidGuestAddress(GuestAddress(entry)));
+            }
+            list = entry;
+            continue;
+        }
+        let length = remaining.min(PAGE_SIZE);
+        push_segment(&mut segments, entry, length);
+        remaining -= length;
+        list += 8;
+    }
+    Ok(segments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::single_region_mem;
+
+    #[test]
+    fn test_prp_segments() {
+        let mem = single_region_mem(0x10000);
+
+        // Within a page, and spanning two pages.
+        assert_eq!(
+            prp_segments(&mem, 0x1200, 0, 0x200).unwrap(),
+            [(GuestAddress(0x1200), 0x200)]
+        );
+        assert_eq!(
+            prp_segments(&mem, 0x1800, 0x5000, 0x1000).unwrap(),
+            [(GuestAddress(0x1800), 0x800), (GuestAddress(0x5000), 0x800)]
+        );
+        assert_eq!(
+            prp_segments(&mem, 0x1000, 0x2000, 0x2000).unwrap(),
+            [(GuestAddress(0x1000), 0x2000)]
+        );
+
+        // PRP list spanning two pages of entries.
+        let list = 0x8000 + PAGE_SIZE - 16;
+        mem.write_obj(0x3000u64, GuestAddress(list)).unwrap();
+        mem.write_obj(0x9000u64, GuestAddress(list + 8)).unwrap();
+        mem.write_obj(0x4000u64, GuestAddress(0x9000)).unwrap();
+        mem.write_obj(0x6000u64, GuestAddress(0x9008)).unwrap();
+        assert_eq!(
+            prp_segments(&mem, 0x2000, list, 0x4000).unwrap(),
+            [
+                (GuestAddress(0x2000), 0x3000),
+                (GuestAddress(0x6000), 0x1000)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_completion_queue() {
+        let mem = single_region_mem(0x10000);
+        let mut queue = CompletionQueue::new(0x1000, 2, Some(0));
+        let completion = Completion {
+            result: 1,
+            sqid: 2,
+            cid: 3,
+            status: 0x102,
+        };
+        queue.post(&mem, completion, 4);
+        let entry: [u32; 4] = mem.read_obj(GuestAddress(0x1000)).unwrap();
+        assert_eq!(entry, [1, 0, 4 | (2 << 16), 3 | (1 << 16) | (0x102 << 17)]);
+        assert!(queue.needs_interrupt);
+
+        // The queue is full until the guest consumes the first entry.
+        queue.post(&mem, Completion::default(), 5);
+        assert_eq!(queue.overflow.len(), 1);
+        queue.set_head(&mem, 1);
+        assert!(queue.overflow.is_empty());
+        let entry: [u32; 4] = mem.read_obj(GuestAddress(0x1010)).unwrap();
+        assert_eq!(entry, [0, 0, 5, 1 << 16]);
+
+        // The phase flips once the queue wraps.
+        queue.set_head(&mem, 0);
+        queue.post(&mem, Completion::default(), 6);
+        let entry: [u32; 4] = mem.read_obj(GuestAddress(0x1000)).unwrap();
+        assert_eq!(entry, [0, 0, 6, 0]);
+    }
+}
@@ -42,6 +42,8 @@ pub struct PciSegment {
     pub(crate) pci_devices_down: u32,
     // List of allocated IRQs for each PCI slot.
     pub(crate) pci_irq_slots: [u8; 32],
+    // Proximity domain of the device of each PCI slot, for the devices with a known locality.
+    pub(crate) slot_proximity_domains: [Option<u32>; 32],
 
     // Device memory covered by this segment
     pub(crate) start_of_mem32_area: u64,
@@ -60,6 +62,7 @@ impl std::fmt::Debug for PciSegment {
             .field("pci_devices_up", &self.pci_devices_up)
             .field("pci_devices_down", &self.pci_devices_down)
             .field("pci_irq_slots", &self.pci_irq_slots)
+            .field("slot_proximity_domains", &self.slot_proximity_domains)
             .field("start_of_mem32_area", &self.start_of_mem32_area)
             .field("end_of_mem32_area", &self.end_of_mem32_area)
             .field("start_of_mem64_area", &self.start_of_mem64_area)
@@ -105,6 +108,7 @@ impl PciSegment {
             start_of_mem64_area,
             end_of_mem64_area,
             pci_irq_slots: *pci_irq_slots,
+            slot_proximity_domains: [None; 32],
         };
 
         Ok(segment)
@@ -178,96 +182,22 @@ impl PciSegment {
             0,
         ))
     }
-}
-
-#[cfg(target_arch = "x86_64")]
-struct PciDevSlot {
-    device_id: u8,
-}
-
-#[cfg(target_arch = "x86_64")]
-impl Aml for PciDevSlot {
-    fn append_aml_bytes(&self, v: &mut Vec<u8>) -> Result<(), aml::AmlError> {
-        let sun = self.device_id;
-        let adr: u32 = (self.device_id as u32) << 16;
-        aml::Device::new(
-            format!("S{:03}", self.device_id).as_str().try_into()?,
-            vec![
-                &aml::Name::new("_SUN".try_into()?, &sun)?,
-                &aml::Name::new("_ADR".try_into()?, &adr)?,
-                &aml::Method::new(
-                    "_EJ0".try_into()?,
-                    1,
-                    true,
-                    vec![&aml::MethodCall::new(
-                        "\\_SB_.PHPR.PCEJ".try_into()?,
-                        vec![&aml::Path::new("_SUN")?, &aml::Path::new("_SEG")?],
-                    )],
-                ),
-            ],
-        )
-        .append_aml_bytes(v)
-    }
-}
 
-#[cfg(target_arch = "x86_64")]
-struct PciDevSlotNotify {
-    device_id: u8,
-}
-
-#[cfg(target_arch = "x86_64")]
-impl Aml for PciDevSlotNotify {
-    fn append_aml_bytes(&self, v: &mut Vec<u8>) -> Result<(), aml::AmlError> {
-        let device_id_mask: u32 = 1 << self.device_id;
-        let object = aml::Path::new(&format!("S{:03}", self.device_id))?;
-        aml::And::new(&aml::Local(0), &aml::Arg(0), &device_id_mask).append_aml_bytes(v)?;
-        aml::If::new(
-            &aml::Equal::new(&aml::Local(0), &device_id_mask),
-            vec![&aml::Notify::new(&object, &aml::Arg(1))],
-        )
-        .append_aml_bytes(v)
-    }
-}
-
-#[cfg(target_arch = "x86_64")]
-struct PciDevSlotMethods {}
-
-#[cfg(target_arch = "x86_64")]
-impl Aml for PciDevSlotMethods {
-    fn append_aml_bytes(&self, v: &mut Vec<u8>) -> Result<(), aml::AmlError> {
-        let mut device_notifies = Vec::new();
-        for device_id in 0..32 {
-            device_notifies.push(PciDevSlotNotify { device_id });
-        }
-
-        let mut device_notifies_refs: Vec<&dyn Aml> = Vec::new();
-        for device_notify in device_notifies.iter() {
-            device_notifies_refs.push(device_notify);
-        }
-
-        aml::Method::new("DVNT".try_into()?, 2, true, device_notifies_refs).append_aml_bytes(v)?;
-        aml::Method::new(
-            "PCNT".try_into()?,
-            0,
-            true,
-            vec![
-                &aml::Acquire::new("\\_SB_.PHPR.BLCK".try_into()?, 0xffff),
-                &aml::Store::new(
-                    &aml::Path::new("\\_SB_.PHPR.PSEG")?,
-                    &aml::Path::new("_SEG")?,
-                ),
-                &aml::MethodCall::new(
-                    "DVNT".try_into()?,
-                    vec![&aml::Path::new("\\_SB_.PHPR.PCIU")?, &aml::ONE],
-                ),
-                &aml::MethodCall::new(
-                    "DVNT".try_into()?,
-                    vec![&aml::Path::new("\\_SB_.PHPR.PCID")?, &3usize],
-                ),
-                &aml::Release::new("\\_SB_.PHPR.BLCK".try_into()?),
-            ],
-        )
-        .append_aml_bytes(v)
+    /// BDFs of the devices attached to the segment, the host bridge excepted, ordered by slot.
+    #[cfg(target_arch = "x86_64")]
+    pub(crate) fn device_bdfs(&self) -> Vec<PciBdf> {
+        let pci_bus = self.pci_bus.lock().expect("Poisoned lock");
+        let mut slots: Vec<u32> = pci_bus
+            .devices
+            .keys()
+            .copied()
+            .filter(|&slot| slot != 0)
+            .collect();
+        slots.sort_unstable();
+        slots
+            .into_iter()
+            .map(|slot| PciBdf::new(self.id, 0, slot.try_into().unwrap(), 0))
+            .collect()
     }
 }
 
@@ -418,14 +348,18 @@ impl Aml for PciSegment {
 
         let mut pci_devices = Vec::new();
         for device_id in 0..32 {
-            let pci_device = PciDevSlot { device_id };
-            pci_devices.push(pci_device);
+            let pci_device = aml::PciSlot::new(device_id);
+            // Overrides the proximity domain of the segment for the device of this slot.
+            pci_devices.push(match self.slot_proximity_domains[device_id as usize] {
+                Some(proximity_domain) => pci_device.proximity_domain(proximity_domain),
+                None => pci_device,
+            });
         }
         for pci_device in pci_devices.iter() {
             pci_dsdt_inner_data.push(pci_device);
         }
 
-        let pci_device_methods = PciDevSlotMethods {};
+        let pci_device_methods = aml::PciSlotNotifications::new(&pci_devices);
         pci_dsdt_inner_data.push(&pci_device_methods);
 
         // Build PCI routing table, listing IRQs assigned to PCI devices.
@@ -495,6 +429,20 @@ mod tests {
         assert_eq!(pci_segment.pci_devices_up, 0);
         assert_eq!(pci_segment.pci_devices_down, 0);
         assert_eq!(pci_segment.pci_irq_slots, [0u8; 32]);
+        assert_eq!(pci_segment.slot_proximity_domains, [None; 32]);
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_slot_proximity_domain() {
+        let slot = aml::PciSlot::new(3).to_aml_bytes().unwrap();
+        assert!(!slot.windows(4).any(|name| name == b"_PXM"));
+
+        let slot = aml::PciSlot::new(3)
+            .proximity_domain(1)
+            .to_aml_bytes()
+            .unwrap();
+        assert!(slot.windows(4).any(|name| name == b"_PXM"));
     }
 
     #[cfg(target_arch = "x86_64")]
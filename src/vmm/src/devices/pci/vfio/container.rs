@@ -0,0 +1,193 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::os::fd::AsRawFd;
+use std::path::Path;
+use std::sync::Arc;
+
+use log::debug;
+use vmm_sys_util::ioctl::{ioctl, ioctl_with_mut_ref, ioctl_with_ref, ioctl_with_val};
+
+use super::VfioError;
+use super::bindings::*;
+use crate::vstate::memory::{Address, GuestMemory, GuestMemoryMmap};
+
+const VFIO_CONTAINER_PATH: &str = "/dev/vfio/vfio";
+const VFIO_GROUP_DIR: &str = "/dev/vfio";
+
+/// VFIO container, holding the IOMMU context shared by the groups of all the VFIO devices of
+/// the guest.
+#[derive(Debug)]
+pub struct VfioContainer {
+    container: File,
+    groups: HashMap<u32, Arc<File>>,
+}
+
+impl VfioContainer {
+    /// Opens a new VFIO container and checks it supports the type1v2 IOMMU.
+    pub fn new() -> Result<Self, VfioError> {
+        let container = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(VFIO_CONTAINER_PATH)
+            .map_err(VfioError::OpenContainer)?;
+
+        // SAFETY: Safe because the fd is valid and the ioctl takes no argument.
+        let version = unsafe { ioctl(&container, VFIO_GET_API_VERSION()) };
+        if version != VFIO_API_VERSION {
+            return Err(VfioError::ApiVersion(version));
+        }
+
+        // SAFETY: Safe because the fd is valid and the ioctl takes its argument by value.
+        let supported =
+            unsafe { ioctl_with_val(&container, VFIO_CHECK_EXTENSION(), VFIO_TYPE1v2_IOMMU) };
+        if supported != 1 {
+            return Err(VfioError::Type1v2Unsupported);
+        }
+
+        Ok(VfioContainer {
+            container,
+            groups: HashMap::new(),
+        })
+    }
+
+    /// Returns the IOMMU group of the device, opening it and adding it to the container if the
+    /// group is not part of it yet.
+    ///
+    /// The guest memory is mapped for DMA once the first group is added to the container.
+    pub fn group(
+        &mut self,
+        device_path: &Path,
+        mem: &GuestMemoryMmap,
+    ) -> Result<Arc<File>, VfioError> {
+        let group_id = Self::iommu_group_id(device_path)?;
+        if let Some(group) = self.groups.get(&group_id) {
+            return Ok(group.clone());
+        }
+
+        let group = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(Path::new(VFIO_GROUP_DIR).join(group_id.to_string()))
+            .map_err(|err| VfioError::OpenGroup(group_id, err))?;
+
+        let mut status = vfio_group_status {
+            argsz: u32::try_from(std::mem::size_of::<vfio_group_status>()).unwrap(),
+            flags: 0,
+        };
+        // SAFETY: Safe because the fd is valid and status lives for the duration of the call.
+        if unsafe { ioctl_with_mut_ref(&group, VFIO_GROUP_GET_STATUS(), &mut status) } < 0 {
+            return Err(VfioError::GroupStatus(group_id, io::Error::last_os_error()));
+        }
+        if status.flags & VFIO_GROUP_FLAGS_VIABLE == 0 {
+            return Err(VfioError::GroupNotViable(group_id));
+        }
+
+        let container_fd = self.container.as_raw_fd();
+        // SAFETY: Safe because both fds are valid, and the fd lives for the duration of the call.
+        if unsafe { ioctl_with_ref(&group, VFIO_GROUP_SET_CONTAINER(), &container_fd) } < 0 {
+            return Err(VfioError::SetContainer(
+                group_id,
+                io::Error::last_os_error(),
+            ));
+        }
+
+        // The IOMMU can only be set once a group is part of the container.
+        if self.groups.is_empty() {
+            // SAFETY: Safe because the fd is valid and the ioctl takes its argument by value.
+            if unsafe { ioctl_with_val(&self.container, VFIO_SET_IOMMU(), VFIO_TYPE1v2_IOMMU) } < 0
+            {
+                return Err(VfioError::SetIommu(io::Error::last_os_error()));
+            }
+            self.map_guest_memory(mem)?;
+        }
+
+        debug!("vfio: added group {group_id} to the container");
+        let group = Arc::new(group);
+        self.groups.insert(group_id, group.clone());
+        Ok(group)
+    }
+
+    // Maps the plugged guest memory for DMA, with guest physical addresses as IO virtual
+    // addresses. This pins the guest memory.
+    fn map_guest_memory(&self, mem: &GuestMemoryMmap) -> Result<(), VfioError> {
+        mem.iter()
+            .flat_map(|region| region.plugged_slots())
+            .try_for_each(|mem_slot| {
+                self.dma_map(
+                    mem_slot.guest_addr.raw_value(),
+                    mem_slot.slice.len() as u64,
+                    mem_slot.slice.ptr_guard().as_ptr() as u64,
+                )
+            })
+    }
+
+    /// Maps `size` bytes of host memory at `host_addr` for DMA at `iova`.
+    pub fn dma_map(&self, iova: u64, size: u64, host_addr: u64) -> Result<(), VfioError> {
+        let dma_map = vfio_iommu_type1_dma_map {
+            argsz: u32::try_from(std::mem::size_of::<vfio_iommu_type1_dma_map>()).unwrap(),
+            flags: VFIO_DMA_MAP_FLAG_READ | VFIO_DMA_MAP_FLAG_WRITE,
+            vaddr: host_addr,
+            iova,
+            size,
+        };
+        // SAFETY: Safe because the fd is valid and dma_map lives for the duration of the call.
+        if unsafe { ioctl_with_ref(&self.container, VFIO_IOMMU_MAP_DMA(), &dma_map) } < 0 {
+            return Err(VfioError::DmaMap(io::Error::last_os_error()));
+        }
+        Ok(())
+    }
+
+    // Reads the id of the IOMMU group of a device from sysfs, where the `iommu_group` entry of
+    // the device links to /sys/kernel/iommu_groups/<id>.
+    fn iommu_group_id(device_path: &Path) -> Result<u32, VfioError> {
+        let group_path = std::fs::read_link(device_path.join("iommu_group"))
+            .map_err(|err| VfioError::IommuGroup(device_path.to_path_buf(), err))?;
+        group_path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.parse::<u32>().ok())
+            .ok_or_else(|| VfioError::InvalidIommuGroup(device_path.to_path_buf()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::fs::symlink;
+
+    use vmm_sys_util::tempdir::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn test_iommu_group_id() {
+        let device_dir = TempDir::new().unwrap();
+        let device_path = device_dir.as_path();
+
+        assert!(matches!(
+            VfioContainer::iommu_group_id(device_path),
+            Err(VfioError::IommuGroup(..))
+        ));
+
+        symlink(
+            "../../../kernel/iommu_groups/42",
+            device_path.join("iommu_group"),
+        )
+        .unwrap();
+        assert_eq!(VfioContainer::iommu_group_id(device_path).unwrap(), 42);
+
+        std::fs::remove_file(device_path.join("iommu_group")).unwrap();
+        symlink(
+            "../../../kernel/iommu_groups/x",
+            device_path.join("iommu_group"),
+        )
+        .unwrap();
+        assert!(matches!(
+            VfioContainer::iommu_group_id(device_path),
+            Err(VfioError::InvalidIommuGroup(_))
+        ));
+    }
+}
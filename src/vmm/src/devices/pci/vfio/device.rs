@@ -0,0 +1,270 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::ffi::CString;
+use std::fs::File;
+use std::io;
+use std::os::fd::{AsRawFd, FromRawFd};
+use std::os::unix::fs::FileExt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use log::{error, warn};
+use vmm_sys_util::eventfd::EventFd;
+use vmm_sys_util::ioctl::{ioctl, ioctl_with_mut_ref, ioctl_with_ptr, ioctl_with_ref};
+
+use super::bindings::*;
+use super::{VfioContainer, VfioError};
+use crate::vstate::memory::GuestMemoryMmap;
+
+// Information on a region of a VFIO device.
+#[derive(Debug, Clone, Copy, Default)]
+pub(super) struct VfioRegion {
+    pub flags: u32,
+    pub size: u64,
+    // Offset of the region in the file of the VFIO device.
+    pub offset: u64,
+}
+
+/// Host device, opened through VFIO.
+#[derive(Debug)]
+pub struct VfioDevice {
+    path: PathBuf,
+    device: File,
+    // The group is kept open for as long as the device is used.
+    _group: Arc<File>,
+    regions: Vec<VfioRegion>,
+}
+
+impl VfioDevice {
+    /// Opens the PCI device whose sysfs directory is `path`, e.g.
+    /// /sys/bus/pci/devices/0000:01:00.0, and resets it if supported.
+    pub fn new(
+        path: &Path,
+        container: &mut VfioContainer,
+        mem: &GuestMemoryMmap,
+    ) -> Result<Self, VfioError> {
+        let name = path
+            .file_name()
+            .and_then(|name| CString::new(name.as_encoded_bytes()).ok())
+            .ok_or_else(|| VfioError::InvalidDevicePath(path.to_path_buf()))?;
+        let group = container.group(path, mem)?;
+
+        // SAFETY: Safe because the fd is valid and name is a NUL-terminated string living for
+        // the duration of the call.
+        let fd = unsafe { ioctl_with_ptr(&*group, VFIO_GROUP_GET_DEVICE_FD(), name.as_ptr()) };
+        if fd < 0 {
+            return Err(VfioError::GetDevice(
+                path.to_path_buf(),
+                io::Error::last_os_error(),
+            ));
+        }
+        // SAFETY: Safe because the fd was just returned by the kernel and is owned by nobody else.
+        let device = unsafe { File::from_raw_fd(fd) };
+
+        let mut info = vfio_device_info {
+            argsz: u32::try_from(std::mem::size_of::<vfio_device_info>()).unwrap(),
+            ..Default::default()
+        };
+        // SAFETY: Safe because the fd is valid and info lives for the duration of the call.
+        if unsafe { ioctl_with_mut_ref(&device, VFIO_DEVICE_GET_INFO(), &mut info) } < 0 {
+            return Err(VfioError::DeviceInfo(io::Error::last_os_error()));
+        }
+        if info.flags & VFIO_DEVICE_FLAGS_PCI == 0 {
+            return Err(VfioError::NotPciDevice);
+        }
+
+        let regions = (0..info.num_regions)
+            .map(|index| Self::region_info(&device, index))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if info.flags & VFIO_DEVICE_FLAGS_RESET != 0 {
+            // SAFETY: Safe because the fd is valid and the ioctl takes no argument.
+            if unsafe { ioctl(&device, VFIO_DEVICE_RESET()) } < 0 {
+                warn!(
+                    "vfio: failed to reset device {path:?}: {}",
+                    io::Error::last_os_error()
+                );
+            }
+        }
+
+        Ok(VfioDevice {
+            path: path.to_path_buf(),
+            device,
+            _group: group,
+            regions,
+        })
+    }
+
+    fn region_info(device: &File, index: u32) -> Result<VfioRegion, VfioError> {
+        let mut info = vfio_region_info {
+            argsz: u32::try_from(std::mem::size_of::<vfio_region_info>()).unwrap(),
+            index,
+            ..Default::default()
+        };
+        // SAFETY: Safe because the fd is valid and info lives for the duration of the call.
+        if unsafe { ioctl_with_mut_ref(device, VFIO_DEVICE_GET_REGION_INFO(), &mut info) } < 0 {
+            return Err(VfioError::RegionInfo(index, io::Error::last_os_error()));
+        }
+        Ok(VfioRegion {
+            flags: info.flags,
+            size: info.size,
+            offset: info.offset,
+        })
+    }
+
+    /// Path of the device in sysfs.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// NUMA node of the host the device is attached to, if known.
+    pub fn numa_node(&self) -> Option<u32> {
+        read_numa_node(&self.path)
+    }
+
+    pub(super) fn region(&self, index: u32) -> Option<VfioRegion> {
+        self.regions.get(index as usize).copied()
+    }
+
+    /// Reads from a region of the device. The data is filled with ones on failure, as a PCI
+    /// device would do for a failed transaction.
+    pub fn read_region(&self, index: u32, offset: u64, data: &mut [u8]) {
+        let Some(region) = self.region(index) else {
+            data.fill(0xff);
+            return;
+        };
+        if let Err(err) = self.device.read_exact_at(data, region.offset + offset) {
+            error!("vfio: failed to read region {index} at offset {offset:#x}: {err}");
+            data.fill(0xff);
+        }
+    }
+
+    /// Writes to a region of the device.
+    pub fn write_region(&self, index: u32, offset: u64, data: &[u8]) {
+        let Some(region) = self.region(index) else {
+            return;
+        };
+        if let Err(err) = self.device.write_all_at(data, region.offset + offset) {
+            error!("vfio: failed to write region {index} at offset {offset:#x}: {err}");
+        }
+    }
+
+    /// Reads a 32-bit register of the configuration space of the device.
+    pub fn read_config_dword(&self, offset: u64) -> u32 {
+        let mut data = [0u8; 4];
+        self.read_region(VFIO_PCI_CONFIG_REGION_INDEX, offset, &mut data);
+        u32::from_le_bytes(data)
+    }
+
+    /// Number of interrupts of the given type supported by the device.
+    pub fn irq_count(&self, index: u32) -> Result<u32, VfioError> {
+        let mut info = vfio_irq_info {
+            argsz: u32::try_from(std::mem::size_of::<vfio_irq_info>()).unwrap(),
+            index,
+            ..Default::default()
+        };
+        // SAFETY: Safe because the fd is valid and info lives for the duration of the call.
+        if unsafe { ioctl_with_mut_ref(&self.device, VFIO_DEVICE_GET_IRQ_INFO(), &mut info) } < 0 {
+            return Err(VfioError::IrqInfo(index, io::Error::last_os_error()));
+        }
+        Ok(info.count)
+    }
+
+    /// Makes the device signal the interrupts of the given type through the event fds.
+    pub fn enable_irqs(&self, index: u32, event_fds: &[&EventFd]) -> Result<(), VfioError> {
+        // `struct vfio_irq_set` is followed by one fd per interrupt.
+        let header_len = std::mem::size_of::<vfio_irq_set>() / std::mem::size_of::<u32>();
+        let mut buf = vec![0u32; header_len + event_fds.len()];
+        let header = vfio_irq_set {
+            argsz: u32::try_from(buf.len() * std::mem::size_of::<u32>()).unwrap(),
+            flags: VFIO_IRQ_SET_DATA_EVENTFD | VFIO_IRQ_SET_ACTION_TRIGGER,
+            index,
+            start: 0,
+            count: u32::try_from(event_fds.len()).unwrap(),
+        };
+        buf[..header_len].copy_from_slice(&[
+            header.argsz,
+            header.flags,
+            header.index,
+            header.start,
+            header.count,
+        ]);
+        for (slot, event_fd) in buf[header_len..].iter_mut().zip(event_fds) {
+            *slot = u32::try_from(event_fd.as_raw_fd()).unwrap();
+        }
+
+        // SAFETY: Safe because the fd is valid and buf holds argsz bytes, living for the
+        // duration of the call.
+        if unsafe { ioctl_with_ptr(&self.device, VFIO_DEVICE_SET_IRQS(), buf.as_ptr()) } < 0 {
+            return Err(VfioError::SetIrqs(io::Error::last_os_error()));
+        }
+        Ok(())
+    }
+
+    /// Stops the device from signaling the interrupts of the given type.
+    pub fn disable_irqs(&self, index: u32) -> Result<(), VfioError> {
+        let irq_set = vfio_irq_set {
+            argsz: u32::try_from(std::mem::size_of::<vfio_irq_set>()).unwrap(),
+            flags: VFIO_IRQ_SET_DATA_NONE | VFIO_IRQ_SET_ACTION_TRIGGER,
+            index,
+            start: 0,
+            count: 0,
+        };
+        // SAFETY: Safe because the fd is valid and irq_set lives for the duration of the call.
+        if unsafe { ioctl_with_ref(&self.device, VFIO_DEVICE_SET_IRQS(), &irq_set) } < 0 {
+            return Err(VfioError::SetIrqs(io::Error::last_os_error()));
+        }
+        Ok(())
+    }
+
+    /// Maps a region of the device in the address space of the process, returning its host
+    /// address.
+    pub fn mmap_region(&self, index: u32) -> Result<u64, VfioError> {
+        let region = self
+            .region(index)
+            .ok_or_else(|| VfioError::MmapBar(index, io::Error::from_raw_os_error(libc::EINVAL)))?;
+        // SAFETY: Safe because the fd is valid, and the kernel checks the offset and size against
+        // the region of the device.
+        let addr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                usize::try_from(region.size).unwrap(),
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                self.device.as_raw_fd(),
+                libc::off_t::try_from(region.offset).unwrap(),
+            )
+        };
+        if addr == libc::MAP_FAILED {
+            return Err(VfioError::MmapBar(index, io::Error::last_os_error()));
+        }
+        Ok(addr as u64)
+    }
+}
+
+// The kernel reports -1 when the platform doesn't describe the locality of the device.
+fn read_numa_node(device_path: &Path) -> Option<u32> {
+    std::fs::read_to_string(device_path.join("numa_node"))
+        .ok()
+        .and_then(|node| node.trim().parse::<u32>().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use vmm_sys_util::tempdir::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn test_read_numa_node() {
+        let device_dir = TempDir::new().unwrap();
+        let device_path = device_dir.as_path();
+
+        assert_eq!(read_numa_node(device_path), None);
+        std::fs::write(device_path.join("numa_node"), "1\n").unwrap();
+        assert_eq!(read_numa_node(device_path), Some(1));
+        std::fs::write(device_path.join("numa_node"), "-1\n").unwrap();
+        assert_eq!(read_numa_node(device_path), None);
+    }
+}
@@ -0,0 +1,84 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Passthrough of host PCI devices to the guest, through the VFIO framework of the host kernel.
+//!
+//! The devices of an IOMMU group are attached to a VFIO container, in which the whole guest
+//! memory is mapped for DMA, guest physical addresses being used as IO virtual addresses. The
+//! configuration space of the devices is forwarded to the host kernel, except for the BARs,
+//! which are relocated in the guest physical address space, and for the MSI and MSI-X
+//! capabilities, whose interrupts are routed to the guest through KVM irqfds.
+//!
+//! The devices are listed in the DSDT like the other PCI devices. With a split irqchip, they are
+//! also listed as PCI endpoints in the DMAR table, under the interrupt remapping unit of the
+//! guest, so that their MSIs are remapped. This unit is a VT-d one on both vendors, hence no IVRS
+//! table, which describes AMD IOMMUs.
+
+mod bindings;
+mod container;
+mod device;
+mod pci;
+pub mod sriov;
+
+use std::io;
+use std::path::PathBuf;
+
+pub use container::VfioContainer;
+pub use device::VfioDevice;
+pub use pci::VfioPciDevice;
+
+use crate::vstate::interrupts::InterruptError;
+use crate::vstate::vm::VmError;
+
+/// Errors related to the VFIO devices.
+#[derive(Debug, thiserror::Error, displaydoc::Display)]
+pub enum VfioError {
+    /// Failed to open the VFIO container: {0}
+    OpenContainer(io::Error),
+    /// Unsupported VFIO API version: {0}
+    ApiVersion(i32),
+    /// The type1v2 IOMMU is not supported by the host
+    Type1v2Unsupported,
+    /// Failed to set the IOMMU of the VFIO container: {0}
+    SetIommu(io::Error),
+    /// Failed to map guest memory for DMA: {0}
+    DmaMap(io::Error),
+    /// Failed to find the IOMMU group of {0:?}: {1}
+    IommuGroup(PathBuf, io::Error),
+    /// Invalid IOMMU group of {0:?}
+    InvalidIommuGroup(PathBuf),
+    /// Failed to open VFIO group {0}: {1}
+    OpenGroup(u32, io::Error),
+    /// Failed to get the status of VFIO group {0}: {1}
+    GroupStatus(u32, io::Error),
+    /// VFIO group {0} is not viable, all of its devices must be bound to vfio-pci
+    GroupNotViable(u32),
+    /// Failed to add VFIO group {0} to the container: {1}
+    SetContainer(u32, io::Error),
+    /// Invalid VFIO device path {0:?}
+    InvalidDevicePath(PathBuf),
+    /// Failed to get the VFIO device {0:?}: {1}
+    GetDevice(PathBuf, io::Error),
+    /// Failed to get the VFIO device information: {0}
+    DeviceInfo(io::Error),
+    /// The VFIO device is not a PCI device
+    NotPciDevice,
+    /// Failed to get the information of region {0}: {1}
+    RegionInfo(u32, io::Error),
+    /// Failed to get the information of interrupt {0}: {1}
+    IrqInfo(u32, io::Error),
+    /// Failed to set the interrupts of the VFIO device: {0}
+    SetIrqs(io::Error),
+    /// Failed to read the configuration space of the VFIO device: {0}
+    ReadConfig(io::Error),
+    /// Failed to map BAR {0} of the VFIO device: {1}
+    MmapBar(u32, io::Error),
+    /// No KVM memory slot available to map BAR {0} of the VFIO device
+    NoKvmSlotAvailable(u32),
+    /// Failed to register BAR {0} of the VFIO device with KVM: {1}
+    SetUserMemoryRegion(u32, VmError),
+    /// Failed to allocate BAR {0} of the VFIO device: {1}
+    AllocateBar(u32, vm_allocator::Error),
+    /// Failed to create the interrupts of the VFIO device: {0}
+    Interrupts(#[from] InterruptError),
+}
@@ -0,0 +1,491 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::{Arc, Barrier};
+
+use kvm_bindings::kvm_userspace_memory_region;
+use log::{debug, error, warn};
+use pci::PciBdf;
+use vm_allocator::AllocPolicy;
+
+use super::bindings::*;
+use super::{VfioDevice, VfioError};
+use crate::arch::host_page_size;
+use crate::pci::PciDevice;
+use crate::pci::msix::MsixConfig;
+use crate::vstate::bus::BusDevice;
+use crate::vstate::interrupts::{MsixVectorConfig, MsixVectorGroup};
+use crate::vstate::vm::Vm;
+
+const NUM_BARS: usize = 6;
+const BAR0_REG: usize = 4;
+const ROM_BAR_REG: usize = 12;
+const INTERRUPT_REG: usize = 15;
+
+const BAR_IO_SPACE: u32 = 1 << 0;
+const BAR_MEM_TYPE_MASK: u32 = 0b110;
+const BAR_MEM_TYPE_64: u32 = 0b100;
+const BAR_FLAGS_MASK: u32 = 0xf;
+
+// Interrupt Pin field of the Interrupt register. Legacy interrupts are not forwarded.
+const INTERRUPT_PIN_MASK: u32 = 0xff00;
+
+const CAPABILITY_LIST_OFFSET: u64 = 0x34;
+const STATUS_CAPABILITY_LIST: u32 = 1 << 20;
+const CAP_ID_MSI: u32 = 0x05;
+const CAP_ID_MSIX: u32 = 0x11;
+
+// Bits of the Message Control register of the MSI capability.
+const MSI_CTL_ENABLE: u16 = 1 << 0;
+const MSI_CTL_MULTI_MSG_ENABLE_SHIFT: u16 = 4;
+const MSI_CTL_64_BIT: u16 = 1 << 7;
+const MSI_MAX_VECTORS: u32 = 32;
+
+const MSIX_CTL_TABLE_SIZE_MASK: u16 = 0x7ff;
+const MSIX_CTL_ENABLE: u16 = 1 << 15;
+const MSIX_TABLE_ENTRY_SIZE: u64 = 16;
+const MSIX_BIR_MASK: u32 = 0x7;
+
+// Memory BAR of the device, relocated in the guest physical address space.
+#[derive(Debug)]
+struct VfioBar {
+    index: u32,
+    addr: u64,
+    size: u64,
+    // Host address where the BAR is mapped, if the guest accesses it directly rather than
+    // through the MMIO bus.
+    host_addr: Option<u64>,
+}
+
+#[derive(Debug)]
+struct VfioMsi {
+    cap_offset: u64,
+    vectors: Arc<MsixVectorGroup>,
+    enabled: bool,
+}
+
+#[derive(Debug)]
+struct VfioMsix {
+    cap_offset: u64,
+    config: MsixConfig,
+    table_bar: u32,
+    table_offset: u64,
+    table_size: u64,
+    pba_bar: u32,
+    pba_offset: u64,
+    pba_size: u64,
+}
+
+impl VfioMsix {
+    fn table_range(&self, bar: u32, offset: u64) -> Option<u64> {
+        (bar == self.table_bar
+            && (self.table_offset..self.table_offset + self.table_size).contains(&offset))
+        .then(|| offset - self.table_offset)
+    }
+
+    fn pba_range(&self, bar: u32, offset: u64) -> Option<u64> {
+        (bar == self.pba_bar
+            && (self.pba_offset..self.pba_offset + self.pba_size).contains(&offset))
+        .then(|| offset - self.pba_offset)
+    }
+}
+
+/// PCI device of the guest backed by a host device passed through with VFIO.
+#[derive(Debug)]
+pub struct VfioPciDevice {
+    id: String,
+    bdf: PciBdf,
+    device: VfioDevice,
+    bars: Vec<VfioBar>,
+    // BAR registers as seen by the guest, and the bits of these the guest can write to.
+    bar_regs: [u32; NUM_BARS],
+    bar_masks: [u32; NUM_BARS],
+    msi: Option<VfioMsi>,
+    msix: Option<VfioMsix>,
+}
+
+impl VfioPciDevice {
+    /// Creates the PCI device, allocating its BARs in the guest physical address space and its
+    /// interrupts.
+    pub fn new(
+        id: String,
+        vm: &Arc<Vm>,
+        device: VfioDevice,
+        bdf: PciBdf,
+    ) -> Result<Self, VfioError> {
+        let mut vfio_pci_device = VfioPciDevice {
+            id,
+            bdf,
+            device,
+            bars: Vec::new(),
+            bar_regs: [0; NUM_BARS],
+            bar_masks: [0; NUM_BARS],
+            msi: None,
+            msix: None,
+        };
+        vfio_pci_device.parse_capabilities(vm)?;
+        vfio_pci_device.allocate_bars(vm)?;
+        Ok(vfio_pci_device)
+    }
+
+    /// Identifier of the device.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// BARs of the device which are emulated, as (address, size) pairs. These have to be
+    /// registered on the MMIO bus.
+    pub fn trapped_bars(&self) -> impl Iterator<Item = (u64, u64)> + '_ {
+        self.bars
+            .iter()
+            .filter(|bar| bar.host_addr.is_none())
+            .map(|bar| (bar.addr, bar.size))
+    }
+
+    fn parse_capabilities(&mut self, vm: &Arc<Vm>) -> Result<(), VfioError> {
+        if self.device.read_config_dword(4) & STATUS_CAPABILITY_LIST == 0 {
+            return Ok(());
+        }
+
+        let mut cap_offset =
+            u64::from(self.device.read_config_dword(CAPABILITY_LIST_OFFSET) & 0xfc);
+        // Bound the walk, in case of a malformed capability list.
+        let mut remaining = 48;
+        while cap_offset != 0 && remaining > 0 {
+            let header = self.device.read_config_dword(cap_offset);
+            // The Message Control register of both MSI and MSI-X follows the capability header.
+            let msg_ctl = (header >> 16) as u16;
+            match header & 0xff {
+                CAP_ID_MSI => {
+                    let count = self
+                        .device
+                        .irq_count(VFIO_PCI_MSI_IRQ_INDEX)?
+                        .min(MSI_MAX_VECTORS);
+                    if count > 0 {
+                        let vectors =
+                            Vm::create_msix_group(vm.clone(), u16::try_from(count).unwrap())?;
+                        self.msi = Some(VfioMsi {
+                            cap_offset,
+                            vectors: Arc::new(vectors),
+                            enabled: false,
+                        });
+                    }
+                }
+                CAP_ID_MSIX => {
+                    let num_vectors = (msg_ctl & MSIX_CTL_TABLE_SIZE_MASK) + 1;
+                    let table = self.device.read_config_dword(cap_offset + 4);
+                    let pba = self.device.read_config_dword(cap_offset + 8);
+                    let vectors = Vm::create_msix_group(vm.clone(), num_vectors)?;
+                    self.msix = Some(VfioMsix {
+                        cap_offset,
+                        config: MsixConfig::new(Arc::new(vectors), self.bdf.into()),
+                        table_bar: table & MSIX_BIR_MASK,
+                        table_offset: u64::from(table & !MSIX_BIR_MASK),
+                        table_size: u64::from(num_vectors) * MSIX_TABLE_ENTRY_SIZE,
+                        pba_bar: pba & MSIX_BIR_MASK,
+                        pba_offset: u64::from(pba & !MSIX_BIR_MASK),
+                        pba_size: u64::from(num_vectors).div_ceil(64) * 8,
+                    });
+                }
+                _ => (),
+            }
+            cap_offset = u64::from((header >> 8) & 0xfc);
+            remaining -= 1;
+        }
+        Ok(())
+    }
+
+    fn allocate_bars(&mut self, vm: &Vm) -> Result<(), VfioError> {
+        let page_size = host_page_size() as u64;
+        let mut bar_idx = 0;
+        while bar_idx < NUM_BARS {
+            let index = u32::try_from(bar_idx).unwrap();
+            let bar_reg = self.device.read_config_dword(0x10 + 4 * u64::from(index));
+            let is_64bit = bar_reg & BAR_MEM_TYPE_MASK == BAR_MEM_TYPE_64;
+            let next_bar_idx = if is_64bit { bar_idx + 2 } else { bar_idx + 1 };
+
+            let size = self.device.region(index).map_or(0, |region| region.size);
+            if size == 0 {
+                bar_idx = next_bar_idx;
+                continue;
+            }
+            if bar_reg & BAR_IO_SPACE != 0 {
+                warn!("vfio: skipping IO BAR {index} of device {}", self.id);
+                bar_idx += 1;
+                continue;
+            }
+
+            let alignment = size.max(page_size);
+            let addr = if is_64bit {
+                vm.resource_allocator().allocate_64bit_mmio_memory(
+                    size,
+                    alignment,
+                    AllocPolicy::FirstMatch,
+                )
+            } else {
+                vm.resource_allocator().allocate_32bit_mmio_memory(
+                    size,
+                    alignment,
+                    AllocPolicy::FirstMatch,
+                )
+            }
+            .map_err(|err| VfioError::AllocateBar(index, err))?;
+            debug!(
+                "vfio: BAR {index} of device {} at {addr:#x}, size {size:#x}",
+                self.id
+            );
+
+            let size_mask = !(size - 1);
+            self.bar_regs[bar_idx] = (addr as u32) | (bar_reg & BAR_FLAGS_MASK);
+            self.bar_masks[bar_idx] = (size_mask as u32) & !BAR_FLAGS_MASK;
+            if is_64bit {
+                self.bar_regs[bar_idx + 1] = (addr >> 32) as u32;
+                self.bar_masks[bar_idx + 1] = (size_mask >> 32) as u32;
+            }
+
+            let host_addr = if self.can_map_bar(index, size) {
+                Some(self.map_bar(vm, index, addr, size)?)
+            } else {
+                None
+            };
+            self.bars.push(VfioBar {
+                index,
+                addr,
+                size,
+                host_addr,
+            });
+
+            bar_idx = next_bar_idx;
+        }
+        Ok(())
+    }
+
+    // BARs are mapped in the guest when the host kernel allows it, unless they hold the MSI-X
+    // table or PBA, whose accesses need to be emulated.
+    fn can_map_bar(&self, index: u32, size: u64) -> bool {
+        let mappable = self
+            .device
+            .region(index)
+            .is_some_and(|region| region.flags & VFIO_REGION_INFO_FLAG_MMAP != 0);
+        let holds_msix = self
+            .msix
+            .as_ref()
+            .is_some_and(|msix| msix.table_bar == index || msix.pba_bar == index);
+        mappable && !holds_msix && size % host_page_size() as u64 == 0
+    }
+
+    fn map_bar(&self, vm: &Vm, index: u32, addr: u64, size: u64) -> Result<u64, VfioError> {
+        let host_addr = self.device.mmap_region(index)?;
+        let slot = vm
+            .next_kvm_slot(1)
+            .ok_or(VfioError::NoKvmSlotAvailable(index))?;
+        let memory_region = kvm_userspace_memory_region {
+            slot,
+            guest_phys_addr: addr,
+            memory_size: size,
+            userspace_addr: host_addr,
+            flags: 0,
+        };
+        vm.set_user_memory_region(memory_region)
+            .map_err(|err| VfioError::SetUserMemoryRegion(index, err))?;
+        Ok(host_addr)
+    }
+
+    fn write_bar_reg(&mut self, bar_idx: usize, offset: u64, data: &[u8]) {
+        let mut bytes = self.bar_regs[bar_idx].to_le_bytes();
+        let Some(dst) = bytes.get_mut(offset as usize..offset as usize + data.len()) else {
+            return;
+        };
+        dst.copy_from_slice(data);
+        let mask = self.bar_masks[bar_idx];
+        self.bar_regs[bar_idx] =
+            (self.bar_regs[bar_idx] & !mask) | (u32::from_le_bytes(bytes) & mask);
+    }
+
+    fn update_msi(&mut self) {
+        let Some(msi) = self.msi.as_mut() else {
+            return;
+        };
+        let msg_ctl = (self.device.read_config_dword(msi.cap_offset) >> 16) as u16;
+        let enabled = msg_ctl & MSI_CTL_ENABLE != 0;
+
+        if enabled {
+            let low_addr = self.device.read_config_dword(msi.cap_offset + 4);
+            let (high_addr, data_offset) = if msg_ctl & MSI_CTL_64_BIT != 0 {
+                (self.device.read_config_dword(msi.cap_offset + 8), 12)
+            } else {
+                (0, 8)
+            };
+            let data = self.device.read_config_dword(msi.cap_offset + data_offset) & 0xffff;
+            let count = (1u32 << ((msg_ctl >> MSI_CTL_MULTI_MSG_ENABLE_SHIFT) & 0x7))
+                .min(u32::from(msi.vectors.num_vectors()));
+
+            // With multiple messages, the device sets the low bits of the data to the vector.
+            for vector in 0..count {
+                let config = MsixVectorConfig {
+                    high_addr,
+                    low_addr,
+                    data: data | vector,
+                    devid: self.bdf.into(),
+                };
+                if let Err(err) = msi.vectors.update(vector as usize, config, false, true) {
+                    error!("vfio: failed to update MSI vector {vector}: {err}");
+                }
+            }
+
+            if !msi.enabled {
+                let event_fds = (0..count as usize)
+                    .filter_map(|vector| msi.vectors.notifier(vector))
+                    .collect::<Vec<_>>();
+                if let Err(err) = self.device.enable_irqs(VFIO_PCI_MSI_IRQ_INDEX, &event_fds) {
+                    error!("vfio: failed to enable MSI of device {}: {err}", self.id);
+                }
+            }
+        } else if msi.enabled {
+            if let Err(err) = self.device.disable_irqs(VFIO_PCI_MSI_IRQ_INDEX) {
+                error!("vfio: failed to disable MSI of device {}: {err}", self.id);
+            }
+            if let Err(err) = msi.vectors.disable() {
+                error!("vfio: failed to disable MSI vectors: {err}");
+            }
+        }
+        msi.enabled = enabled;
+    }
+
+    fn update_msix(&mut self) {
+        let Some(msix) = self.msix.as_mut() else {
+            return;
+        };
+        // vfio-pci virtualizes the Message Control register, which reads back as written.
+        let msg_ctl = (self.device.read_config_dword(msix.cap_offset) >> 16) as u16;
+        let was_enabled = msix.config.enabled;
+        msix.config.set_msg_ctl(msg_ctl);
+
+        if msg_ctl & MSIX_CTL_ENABLE != 0 && !was_enabled {
+            let vectors = &msix.config.vectors;
+            let event_fds = (0..vectors.num_vectors() as usize)
+                .filter_map(|vector| vectors.notifier(vector))
+                .collect::<Vec<_>>();
+            if let Err(err) = self.device.enable_irqs(VFIO_PCI_MSIX_IRQ_INDEX, &event_fds) {
+                error!("vfio: failed to enable MSI-X of device {}: {err}", self.id);
+            }
+        } else if msg_ctl & MSIX_CTL_ENABLE == 0 && was_enabled {
+            if let Err(err) = self.device.disable_irqs(VFIO_PCI_MSIX_IRQ_INDEX) {
+                error!("vfio: failed to disable MSI-X of device {}: {err}", self.id);
+            }
+        }
+    }
+
+    fn find_bar(&self, base: u64) -> Option<&VfioBar> {
+        self.bars.iter().find(|bar| bar.addr == base)
+    }
+}
+
+impl Drop for VfioPciDevice {
+    fn drop(&mut self) {
+        for bar in &self.bars {
+            if let Some(host_addr) = bar.host_addr {
+                // SAFETY: The BAR was mapped with this address and size in `map_bar`.
+                unsafe {
+                    _ = libc::munmap(host_addr as *mut libc::c_void, bar.size as usize);
+                }
+            }
+        }
+    }
+}
+
+impl PciDevice for VfioPciDevice {
+    fn write_config_register(
+        &mut self,
+        reg_idx: usize,
+        offset: u64,
+        data: &[u8],
+    ) -> Option<Arc<Barrier>> {
+        match reg_idx {
+            r if (BAR0_REG..BAR0_REG + NUM_BARS).contains(&r) => {
+                // BARs can't be moved, but the guest still needs to size them.
+                self.write_bar_reg(r - BAR0_REG, offset, data);
+            }
+            ROM_BAR_REG => (),
+            _ => {
+                let config_offset = reg_idx as u64 * 4 + offset;
+                self.device
+                    .write_region(VFIO_PCI_CONFIG_REGION_INDEX, config_offset, data);
+
+                let reg_offset = reg_idx as u64 * 4;
+                if self
+                    .msix
+                    .as_ref()
+                    .is_some_and(|msix| msix.cap_offset == reg_offset)
+                {
+                    self.update_msix();
+                }
+                // The MSI capability spans up to 6 registers, any of which may reconfigure the
+                // vectors.
+                if self
+                    .msi
+                    .as_ref()
+                    .is_some_and(|msi| (msi.cap_offset..msi.cap_offset + 24).contains(&reg_offset))
+                {
+                    self.update_msi();
+                }
+            }
+        }
+        None
+    }
+
+    fn read_config_register(&mut self, reg_idx: usize) -> u32 {
+        match reg_idx {
+            r if (BAR0_REG..BAR0_REG + NUM_BARS).contains(&r) => self.bar_regs[r - BAR0_REG],
+            ROM_BAR_REG => 0,
+            INTERRUPT_REG => {
+                self.device.read_config_dword(reg_idx as u64 * 4) & !INTERRUPT_PIN_MASK
+            }
+            _ => self.device.read_config_dword(reg_idx as u64 * 4),
+        }
+    }
+
+    fn read_bar(&mut self, base: u64, offset: u64, data: &mut [u8]) {
+        let Some(index) = self.find_bar(base).map(|bar| bar.index) else {
+            data.fill(0xff);
+            return;
+        };
+        if let Some(msix) = &self.msix {
+            if let Some(table_offset) = msix.table_range(index, offset) {
+                msix.config.read_table(table_offset, data);
+                return;
+            }
+            if let Some(pba_offset) = msix.pba_range(index, offset) {
+                msix.config.read_pba(pba_offset, data);
+                return;
+            }
+        }
+        self.device.read_region(index, offset, data);
+    }
+
+    fn write_bar(&mut self, base: u64, offset: u64, data: &[u8]) -> Option<Arc<Barrier>> {
+        let index = self.find_bar(base)?.index;
+        if let Some(msix) = &mut self.msix {
+            if let Some(table_offset) = msix.table_range(index, offset) {
+                msix.config.write_table(table_offset, data);
+                return None;
+            }
+            if let Some(pba_offset) = msix.pba_range(index, offset) {
+                msix.config.write_pba(pba_offset, data);
+                return None;
+            }
+        }
+        self.device.write_region(index, offset, data);
+        None
+    }
+}
+
+impl BusDevice for VfioPciDevice {
+    fn read(&mut self, base: u64, offset: u64, data: &mut [u8]) {
+        self.read_bar(base, offset, data)
+    }
+
+    fn write(&mut self, base: u64, offset: u64, data: &[u8]) -> Option<Arc<Barrier>> {
+        self.write_bar(base, offset, data)
+    }
+}
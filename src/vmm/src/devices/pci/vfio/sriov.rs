@@ -0,0 +1,143 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Helpers to pass the SR-IOV virtual functions of a host physical function through to the
+//! guest, binding them to vfio-pci through sysfs.
+
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+use log::info;
+use pci::PciBdf;
+
+/// Sysfs directory holding the PCI devices of the host.
+pub const SYSFS_PCI_DEVICES: &str = "/sys/bus/pci/devices";
+const SYSFS_PCI_DRIVERS_PROBE: &str = "/sys/bus/pci/drivers_probe";
+const VFIO_PCI_DRIVER: &str = "vfio-pci";
+
+/// Errors related to the SR-IOV virtual functions.
+#[derive(Debug, thiserror::Error, displaydoc::Display)]
+pub enum SriovError {
+    /// Failed to find virtual function {1} of {0}: {2}
+    VirtualFunction(PciBdf, u32, io::Error),
+    /// Failed to bind {0:?} to vfio-pci: {1}
+    Bind(PathBuf, io::Error),
+}
+
+/// Returns the sysfs directory of virtual function `vf_index` of the physical function `pf`,
+/// found under `sysfs_devices`.
+pub fn virtual_function_path(
+    sysfs_devices: &Path,
+    pf: PciBdf,
+    vf_index: u32,
+) -> Result<PathBuf, SriovError> {
+    let map_err = |err| SriovError::VirtualFunction(pf, vf_index, err);
+    // The virtfn<N> entry of the physical function links to the directory of the virtual
+    // function, named after its BDF.
+    let link = fs::read_link(
+        sysfs_devices
+            .join(pf.to_string())
+            .join(format!("virtfn{vf_index}")),
+    )
+    .map_err(map_err)?;
+    let vf_bdf = link
+        .file_name()
+        .ok_or_else(|| map_err(io::Error::from(io::ErrorKind::InvalidData)))?;
+    Ok(sysfs_devices.join(vf_bdf))
+}
+
+/// Binds the PCI device whose sysfs directory is `path` to vfio-pci, unbinding it from its
+/// current driver first. Nothing is done if the device is already bound to vfio-pci.
+pub fn bind_vfio_pci(path: &Path, drivers_probe: &Path) -> Result<(), SriovError> {
+    let map_err = |err| SriovError::Bind(path.to_path_buf(), err);
+    let bdf = path
+        .file_name()
+        .ok_or_else(|| map_err(io::Error::from(io::ErrorKind::InvalidInput)))?;
+
+    let driver = path.join("driver");
+    if let Ok(current) = fs::read_link(&driver) {
+        if current
+            .file_name()
+            .is_some_and(|name| name == VFIO_PCI_DRIVER)
+        {
+            return Ok(());
+        }
+        fs::write(driver.join("unbind"), bdf.as_encoded_bytes()).map_err(map_err)?;
+    }
+
+    // The override makes the next probe pick vfio-pci, whatever the IDs of the device.
+    fs::write(path.join("driver_override"), VFIO_PCI_DRIVER).map_err(map_err)?;
+    fs::write(drivers_probe, bdf.as_encoded_bytes()).map_err(map_err)?;
+    info!("vfio: bound {path:?} to {VFIO_PCI_DRIVER}");
+    Ok(())
+}
+
+/// Binds virtual function `vf_index` of the physical function `pf` to vfio-pci, returning its
+/// sysfs directory.
+pub fn bind_virtual_function(pf: PciBdf, vf_index: u32) -> Result<PathBuf, SriovError> {
+    let path = virtual_function_path(Path::new(SYSFS_PCI_DEVICES), pf, vf_index)?;
+    bind_vfio_pci(&path, Path::new(SYSFS_PCI_DRIVERS_PROBE))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::fs::symlink;
+
+    use vmm_sys_util::tempdir::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn test_virtual_function_path() {
+        let sysfs = TempDir::new().unwrap();
+        let devices = sysfs.as_path();
+        let pf: PciBdf = "0000:3b:00.0".parse().unwrap();
+        fs::create_dir(devices.join("0000:3b:00.0")).unwrap();
+        symlink(
+            "../0000:3b:02.1",
+            devices.join("0000:3b:00.0").join("virtfn9"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            virtual_function_path(devices, pf, 9).unwrap(),
+            devices.join("0000:3b:02.1")
+        );
+        assert!(matches!(
+            virtual_function_path(devices, pf, 10),
+            Err(SriovError::VirtualFunction(_, 10, _))
+        ));
+    }
+
+    #[test]
+    fn test_bind_vfio_pci() {
+        let sysfs = TempDir::new().unwrap();
+        let vf = sysfs.as_path().join("0000:3b:02.1");
+        let driver = sysfs.as_path().join("ixgbevf");
+        let drivers_probe = sysfs.as_path().join("drivers_probe");
+        fs::create_dir(&vf).unwrap();
+        fs::create_dir(&driver).unwrap();
+        symlink(&driver, vf.join("driver")).unwrap();
+
+        bind_vfio_pci(&vf, &drivers_probe).unwrap();
+        assert_eq!(
+            fs::read_to_string(driver.join("unbind")).unwrap(),
+            "0000:3b:02.1"
+        );
+        assert_eq!(
+            fs::read_to_string(vf.join("driver_override")).unwrap(),
+            VFIO_PCI_DRIVER
+        );
+        assert_eq!(fs::read_to_string(&drivers_probe).unwrap(), "0000:3b:02.1");
+
+        // Devices already bound to vfio-pci are left alone.
+        let vfio_pci = sysfs.as_path().join(VFIO_PCI_DRIVER);
+        fs::create_dir(&vfio_pci).unwrap();
+        fs::remove_file(vf.join("driver")).unwrap();
+        fs::remove_file(&drivers_probe).unwrap();
+        symlink(&vfio_pci, vf.join("driver")).unwrap();
+        bind_vfio_pci(&vf, &drivers_probe).unwrap();
+        assert!(!drivers_probe.exists());
+    }
+}
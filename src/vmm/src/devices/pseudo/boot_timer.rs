@@ -3,17 +3,36 @@
 
 use std::sync::{Arc, Barrier};
 
+use acpi_tables::{BasicBootTimestamps, Fbpt, Sdt};
 use utils::time::TimestampUs;
 
-use crate::logger::info;
+use crate::logger::{error, info};
 use crate::vstate::bus::BusDevice;
+use crate::vstate::memory::{GuestAddress, GuestMemoryMmap};
 
 const MAGIC_VALUE_SIGNAL_GUEST_BOOT_COMPLETE: u8 = 123;
 
+/// Boot milestones measured by the VMM while building the microVM.
+#[derive(Debug, Clone, Copy)]
+pub struct BootMilestones {
+    /// The VM and its vCPUs were created.
+    pub vm_created: TimestampUs,
+    /// The VMM started loading the kernel in guest memory.
+    pub kernel_load_start: TimestampUs,
+    /// The kernel and the initrd were loaded in guest memory.
+    pub kernel_load_end: TimestampUs,
+    /// The VMM started configuring the vCPUs and the boot parameters.
+    pub system_config_start: TimestampUs,
+    /// The vCPUs are about to run the kernel.
+    pub vcpus_start: TimestampUs,
+}
+
 /// Pseudo device to record the kernel boot time.
 #[derive(Debug, Clone)]
 pub struct BootTimer {
     start_ts: TimestampUs,
+    /// Address of the FBPT in guest memory, if the ACPI tables include one.
+    pub(crate) fbpt_addr: Option<GuestAddress>,
 }
 
 impl BusDevice for BootTimer {
@@ -46,6 +65,74 @@ impl BusDevice for BootTimer {
 impl BootTimer {
     /// Create a device at a certain point in time.
     pub fn new(start_ts: TimestampUs) -> BootTimer {
-        BootTimer { start_ts }
+        BootTimer {
+            start_ts,
+            fbpt_addr: None,
+        }
+    }
+
+    fn ns_since_start(&self, ts: TimestampUs) -> u64 {
+        ts.time_us.saturating_sub(self.start_ts.time_us) * 1000
+    }
+
+    /// Report the boot milestones to the guest, through the firmware basic boot performance
+    /// record of the FPDT.
+    ///
+    /// The timer of the record starts with the boot request, as for the guest boot time logged
+    /// by the device. As there is no firmware, the VMM stands in for it: the guest sees the VM
+    /// creation as the end of the reset, the kernel loading as the OS loader phases, and the
+    /// boot configuration as the exit from the boot services.
+    pub fn report_boot_milestones(&self, mem: &GuestMemoryMmap, milestones: &BootMilestones) {
+        let Some(fbpt_addr) = self.fbpt_addr else {
+            return;
+        };
+        let timestamps = BasicBootTimestamps {
+            reset_end: self.ns_since_start(milestones.vm_created),
+            os_loader_load_image_start: self.ns_since_start(milestones.kernel_load_start),
+            os_loader_start_image_start: self.ns_since_start(milestones.kernel_load_end),
+            exit_boot_services_entry: self.ns_since_start(milestones.system_config_start),
+            exit_boot_services_exit: self.ns_since_start(milestones.vcpus_start),
+        };
+        if let Err(err) = Fbpt::new(&timestamps).write_to_guest(mem, fbpt_addr) {
+            error!("Failed to report the boot milestones to the guest: {err}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vm_memory::Bytes;
+
+    use super::*;
+    use crate::test_utils::single_region_mem;
+
+    fn ts(time_us: u64) -> TimestampUs {
+        TimestampUs {
+            time_us,
+            cputime_us: 0,
+        }
+    }
+
+    #[test]
+    fn test_report_boot_milestones() {
+        let mem = single_region_mem(0x1000);
+        let milestones = BootMilestones {
+            vm_created: ts(1010),
+            kernel_load_start: ts(1020),
+            kernel_load_end: ts(1030),
+            system_config_start: ts(1040),
+            vcpus_start: ts(1050),
+        };
+        let mut boot_timer = BootTimer::new(ts(1000));
+
+        // Without an FBPT, there is nothing to report.
+        boot_timer.report_boot_milestones(&mem, &milestones);
+        let fbpt: [u64; 7] = mem.read_obj(GuestAddress(0x100)).unwrap();
+        assert_eq!(fbpt, [0; 7]);
+
+        boot_timer.fbpt_addr = Some(GuestAddress(0x100));
+        boot_timer.report_boot_milestones(&mem, &milestones);
+        let fbpt: [u64; 7] = mem.read_obj(GuestAddress(0x100)).unwrap();
+        assert_eq!(&fbpt[2..], &[10_000, 20_000, 30_000, 40_000, 50_000]);
     }
 }
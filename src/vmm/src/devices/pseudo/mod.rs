@@ -4,4 +4,4 @@
 //! Implements clawdbox specific devices (e.g. signal when boot is completed).
 mod boot_timer;
 
-pub use self::boot_timer::BootTimer;
+pub use self::boot_timer::{BootMilestones, BootTimer};
@@ -0,0 +1,147 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Subset of the usbfs and hidraw userspace APIs, as defined in
+//! include/uapi/linux/usbdevice_fs.h and include/uapi/linux/hidraw.h.
+
+#![allow(non_camel_case_types, non_upper_case_globals, missing_docs)]
+
+use std::os::raw::{c_int, c_uint, c_void};
+
+use vmm_sys_util::{ioctl_io_nr, ioctl_ior_nr, ioctl_iow_nr, ioctl_iowr_nr};
+
+const USBDEVFS_TYPE: c_uint = b'U' as c_uint;
+
+ioctl_iowr_nr!(USBDEVFS_CONTROL, USBDEVFS_TYPE, 0, usbdevfs_ctrltransfer);
+ioctl_ior_nr!(
+    USBDEVFS_SETINTERFACE,
+    USBDEVFS_TYPE,
+    4,
+    usbdevfs_setinterface
+);
+ioctl_ior_nr!(USBDEVFS_SETCONFIGURATION, USBDEVFS_TYPE, 5, c_uint);
+ioctl_ior_nr!(USBDEVFS_SUBMITURB, USBDEVFS_TYPE, 10, usbdevfs_urb);
+ioctl_io_nr!(USBDEVFS_DISCARDURB, USBDEVFS_TYPE, 11);
+ioctl_iow_nr!(USBDEVFS_REAPURBNDELAY, USBDEVFS_TYPE, 13, *mut c_void);
+ioctl_ior_nr!(USBDEVFS_CLAIMINTERFACE, USBDEVFS_TYPE, 15, c_uint);
+ioctl_ior_nr!(USBDEVFS_RELEASEINTERFACE, USBDEVFS_TYPE, 16, c_uint);
+ioctl_io_nr!(USBDEVFS_RESET, USBDEVFS_TYPE, 20);
+ioctl_ior_nr!(USBDEVFS_CLEAR_HALT, USBDEVFS_TYPE, 21, c_uint);
+ioctl_ior_nr!(
+    USBDEVFS_DISCONNECT_CLAIM,
+    USBDEVFS_TYPE,
+    27,
+    usbdevfs_disconnect_claim
+);
+ioctl_io_nr!(USBDEVFS_GET_SPEED, USBDEVFS_TYPE, 31);
+
+pub const USBDEVFS_URB_TYPE_ISO: u8 = 0;
+pub const USBDEVFS_URB_TYPE_INTERRUPT: u8 = 1;
+pub const USBDEVFS_URB_TYPE_CONTROL: u8 = 2;
+pub const USBDEVFS_URB_TYPE_BULK: u8 = 3;
+
+pub const USBDEVFS_DISCONNECT_CLAIM_EXCEPT_DRIVER: u32 = 0x02;
+
+// Values of enum usb_device_speed, returned by USBDEVFS_GET_SPEED.
+pub const USB_SPEED_LOW: c_int = 1;
+pub const USB_SPEED_FULL: c_int = 2;
+pub const USB_SPEED_HIGH: c_int = 3;
+pub const USB_SPEED_SUPER: c_int = 5;
+pub const USB_SPEED_SUPER_PLUS: c_int = 6;
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct usbdevfs_ctrltransfer {
+    pub bRequestType: u8,
+    pub bRequest: u8,
+    pub wValue: u16,
+    pub wIndex: u16,
+    pub wLength: u16,
+    pub timeout: u32,
+    pub data: *mut c_void,
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct usbdevfs_setinterface {
+    pub interface: c_uint,
+    pub altsetting: c_uint,
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct usbdevfs_disconnect_claim {
+    pub interface: c_uint,
+    pub flags: c_uint,
+    pub driver: [u8; 256],
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct usbdevfs_urb {
+    pub type_: u8,
+    pub endpoint: u8,
+    pub status: c_int,
+    pub flags: c_uint,
+    pub buffer: *mut c_void,
+    pub buffer_length: c_int,
+    pub actual_length: c_int,
+    pub start_frame: c_int,
+    pub number_of_packets: c_int,
+    pub error_count: c_int,
+    pub signr: c_uint,
+    pub usercontext: *mut c_void,
+}
+
+impl Default for usbdevfs_urb {
+    fn default() -> Self {
+        usbdevfs_urb {
+            type_: 0,
+            endpoint: 0,
+            status: 0,
+            flags: 0,
+            buffer: std::ptr::null_mut(),
+            buffer_length: 0,
+            actual_length: 0,
+            start_frame: 0,
+            number_of_packets: 0,
+            error_count: 0,
+            signr: 0,
+            usercontext: std::ptr::null_mut(),
+        }
+    }
+}
+
+const HIDRAW_TYPE: c_uint = b'H' as c_uint;
+
+pub const HID_MAX_DESCRIPTOR_SIZE: usize = 4096;
+
+ioctl_ior_nr!(HIDIOCGRDESCSIZE, HIDRAW_TYPE, 0x01, c_int);
+ioctl_ior_nr!(HIDIOCGRDESC, HIDRAW_TYPE, 0x02, hidraw_report_descriptor);
+ioctl_ior_nr!(HIDIOCGRAWINFO, HIDRAW_TYPE, 0x03, hidraw_devinfo);
+// HIDIOCGRAWNAME takes the length of the buffer, fixed here to 256 bytes.
+ioctl_ior_nr!(HIDIOCGRAWNAME, HIDRAW_TYPE, 0x04, [u8; 256]);
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct hidraw_report_descriptor {
+    pub size: u32,
+    pub value: [u8; HID_MAX_DESCRIPTOR_SIZE],
+}
+
+impl Default for hidraw_report_descriptor {
+    fn default() -> Self {
+        hidraw_report_descriptor {
+            size: 0,
+            value: [0; HID_MAX_DESCRIPTOR_SIZE],
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct hidraw_devinfo {
+    pub bustype: u32,
+    pub vendor: i16,
+    pub product: i16,
+}
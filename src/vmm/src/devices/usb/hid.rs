@@ -0,0 +1,624 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Emulated USB HID device, forwarding the reports of a host hidraw node.
+//!
+//! The guest sees a full speed device with a single HID interface, using the report descriptor
+//! of the host device, an interrupt IN endpoint for input reports and an interrupt OUT endpoint
+//! for output reports. This is enough for security keys, which only exchange raw reports, without
+//! detaching the host device from its kernel driver.
+
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::os::fd::{AsRawFd, RawFd};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+
+use log::{debug, error, warn};
+use vmm_sys_util::epoll::EventSet;
+use vmm_sys_util::ioctl::ioctl_with_mut_ref;
+
+use super::bindings::*;
+use super::*;
+
+const INTERRUPT_IN_ENDPOINT: u8 = 0x81;
+const INTERRUPT_OUT_ENDPOINT: u8 = 0x02;
+const MAX_PACKET_SIZE: u8 = 64;
+const CONFIGURATION_VALUE: u8 = 1;
+
+const USB_DT_DEVICE: u8 = 0x01;
+const USB_DT_CONFIG: u8 = 0x02;
+const USB_DT_STRING: u8 = 0x03;
+const USB_DT_INTERFACE: u8 = 0x04;
+const USB_DT_ENDPOINT: u8 = 0x05;
+const HID_DT_HID: u8 = 0x21;
+const HID_DT_REPORT: u8 = 0x22;
+
+const USB_CLASS_HID: u8 = 0x03;
+const USB_ENDPOINT_XFER_INT: u8 = 0x03;
+
+const HID_REQ_GET_IDLE: u8 = 0x02;
+const HID_REQ_GET_PROTOCOL: u8 = 0x03;
+const HID_REQ_SET_REPORT: u8 = 0x09;
+const HID_REQ_SET_IDLE: u8 = 0x0a;
+const HID_REQ_SET_PROTOCOL: u8 = 0x0b;
+const HID_REPORT_TYPE_OUTPUT: u8 = 0x02;
+// Report protocol, as opposed to the boot protocol of keyboards and mice.
+const HID_PROTOCOL_REPORT: u8 = 1;
+
+const PRODUCT_STRING_INDEX: u8 = 1;
+// US English, the only language of the string descriptors.
+const LANGUAGE_ID: u16 = 0x0409;
+
+// Input reports are queued while the guest has no transfer pending on the IN endpoint, up to
+// this limit, after which the oldest ones are dropped.
+const MAX_QUEUED_REPORTS: usize = 64;
+const MAX_REPORT_SIZE: usize = 4096;
+
+/// USB HID device of the guest, backed by a hidraw node of the host.
+#[derive(Debug)]
+pub struct HidrawDevice {
+    path: PathBuf,
+    file: File,
+    name: String,
+    vendor_id: u16,
+    product_id: u16,
+    report_descriptor: Vec<u8>,
+    // Whether the reports start with a report ID, which hidraw expects for output reports.
+    numbered_reports: bool,
+    configuration: u8,
+    pending_in: Option<UsbTransfer>,
+    reports: VecDeque<Vec<u8>>,
+}
+
+impl HidrawDevice {
+    /// Opens the hidraw node at `path`, e.g. /dev/hidraw0, and reads the report descriptor of
+    /// the device.
+    pub fn open(path: &Path) -> Result<Self, UsbError> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(path)
+            .map_err(|err| UsbError::Open(path.to_path_buf(), err))?;
+        let map_err = |err| UsbError::HidInfo(path.to_path_buf(), err);
+
+        let mut info = hidraw_devinfo::default();
+        // SAFETY: Safe because the fd is valid and info lives for the duration of the call.
+        if unsafe { ioctl_with_mut_ref(&file, HIDIOCGRAWINFO(), &mut info) } < 0 {
+            return Err(map_err(io::Error::last_os_error()));
+        }
+
+        let mut descriptor_size: libc::c_int = 0;
+        // SAFETY: Safe because the fd is valid and the size lives for the duration of the call.
+        if unsafe { ioctl_with_mut_ref(&file, HIDIOCGRDESCSIZE(), &mut descriptor_size) } < 0 {
+            return Err(map_err(io::Error::last_os_error()));
+        }
+        let mut descriptor = hidraw_report_descriptor {
+            size: u32::try_from(descriptor_size)
+                .map_err(|_| map_err(io::Error::from(io::ErrorKind::InvalidData)))?,
+            ..Default::default()
+        };
+        // SAFETY: Safe because the fd is valid and descriptor lives for the duration of the call.
+        if unsafe { ioctl_with_mut_ref(&file, HIDIOCGRDESC(), &mut descriptor) } < 0 {
+            return Err(map_err(io::Error::last_os_error()));
+        }
+        let report_descriptor = descriptor
+            .value
+            .get(..descriptor.size as usize)
+            .ok_or_else(|| map_err(io::Error::from(io::ErrorKind::InvalidData)))?
+            .to_vec();
+
+        let mut raw_name = [0u8; 256];
+        // SAFETY: Safe because the fd is valid and the name lives for the duration of the call.
+        let name = if unsafe { ioctl_with_mut_ref(&file, HIDIOCGRAWNAME(), &mut raw_name) } > 0 {
+            let len = raw_name
+                .iter()
+                .position(|&b| b == 0)
+                .unwrap_or(raw_name.len());
+            String::from_utf8_lossy(&raw_name[..len]).into_owned()
+        } else {
+            String::from("HID device")
+        };
+        debug!("usb: opened HID device {path:?}: {name}");
+
+        Ok(HidrawDevice {
+            path: path.to_path_buf(),
+            file,
+            name,
+            vendor_id: info.vendor as u16,
+            product_id: info.product as u16,
+            numbered_reports: has_report_ids(&report_descriptor),
+            report_descriptor,
+            configuration: 0,
+            pending_in: None,
+            reports: VecDeque::new(),
+        })
+    }
+
+    fn device_descriptor(&self) -> Vec<u8> {
+        let vendor_id = self.vendor_id.to_le_bytes();
+        let product_id = self.product_id.to_le_bytes();
+        vec![
+            18,
+            USB_DT_DEVICE,
+            0x00,
+            0x02, // USB 2.0
+            0,
+            0,
+            0, // Class defined by the interface
+            MAX_PACKET_SIZE,
+            vendor_id[0],
+            vendor_id[1],
+            product_id[0],
+            product_id[1],
+            0x00,
+            0x01, // Device release 1.0
+            0,
+            PRODUCT_STRING_INDEX,
+            0,
+            1, // Number of configurations
+        ]
+    }
+
+    fn hid_descriptor(&self) -> [u8; 9] {
+        let report_len = u16::try_from(self.report_descriptor.len())
+            .unwrap()
+            .to_le_bytes();
+        [
+            9,
+            HID_DT_HID,
+            0x11,
+            0x01, // HID 1.11
+            0,
+            1, // Number of class descriptors
+            HID_DT_REPORT,
+            report_len[0],
+            report_len[1],
+        ]
+    }
+
+    fn config_descriptor(&self) -> Vec<u8> {
+        let interface = [9, USB_DT_INTERFACE, 0, 0, 2, USB_CLASS_HID, 0, 0, 0];
+        let endpoint_in = [
+            7,
+            USB_DT_ENDPOINT,
+            INTERRUPT_IN_ENDPOINT,
+            USB_ENDPOINT_XFER_INT,
+            MAX_PACKET_SIZE,
+            0,
+            1, // Polled every frame
+        ];
+        let endpoint_out = [
+            7,
+            USB_DT_ENDPOINT,
+            INTERRUPT_OUT_ENDPOINT,
+            USB_ENDPOINT_XFER_INT,
+            MAX_PACKET_SIZE,
+            0,
+            1,
+        ];
+        let total_len = 9 + interface.len() + 9 + endpoint_in.len() + endpoint_out.len();
+        let total_len = u16::try_from(total_len).unwrap().to_le_bytes();
+
+        let mut descriptor = vec![
+            9,
+            USB_DT_CONFIG,
+            total_len[0],
+            total_len[1],
+            1, // Number of interfaces
+            CONFIGURATION_VALUE,
+            0,
+            0x80, // Bus powered
+            50,   // 100 mA
+        ];
+        descriptor.extend_from_slice(&interface);
+        descriptor.extend_from_slice(&self.hid_descriptor());
+        descriptor.extend_from_slice(&endpoint_in);
+        descriptor.extend_from_slice(&endpoint_out);
+        descriptor
+    }
+
+    fn string_descriptor(&self, index: u8) -> Option<Vec<u8>> {
+        match index {
+            0 => {
+                let language = LANGUAGE_ID.to_le_bytes();
+                Some(vec![4, USB_DT_STRING, language[0], language[1]])
+            }
+            PRODUCT_STRING_INDEX => Some(string_descriptor(&self.name)),
+            _ => None,
+        }
+    }
+
+    // Returns the data of the IN control requests, or None if the request stalls.
+    fn control_in(&self, setup: &SetupPacket) -> Option<Vec<u8>> {
+        let [descriptor_index, descriptor_type] = setup.value.to_le_bytes();
+        if setup.is_standard(USB_RECIP_DEVICE) {
+            match setup.request {
+                USB_REQ_GET_DESCRIPTOR => match descriptor_type {
+                    USB_DT_DEVICE => Some(self.device_descriptor()),
+                    USB_DT_CONFIG => Some(self.config_descriptor()),
+                    USB_DT_STRING => self.string_descriptor(descriptor_index),
+                    _ => None,
+                },
+                USB_REQ_GET_CONFIGURATION => Some(vec![self.configuration]),
+                USB_REQ_GET_STATUS => Some(vec![0, 0]),
+                _ => None,
+            }
+        } else if setup.is_standard(USB_RECIP_INTERFACE) {
+            match (setup.request, descriptor_type) {
+                (USB_REQ_GET_DESCRIPTOR, HID_DT_REPORT) => Some(self.report_descriptor.clone()),
+                (USB_REQ_GET_DESCRIPTOR, HID_DT_HID) => Some(self.hid_descriptor().to_vec()),
+                (USB_REQ_GET_INTERFACE, _) => Some(vec![0]),
+                (USB_REQ_GET_STATUS, _) => Some(vec![0, 0]),
+                _ => None,
+            }
+        } else if setup.is_standard(USB_RECIP_ENDPOINT) && setup.request == USB_REQ_GET_STATUS {
+            Some(vec![0, 0])
+        } else if setup.request_type & USB_TYPE_MASK == USB_TYPE_CLASS {
+            match setup.request {
+                HID_REQ_GET_IDLE => Some(vec![0]),
+                HID_REQ_GET_PROTOCOL => Some(vec![HID_PROTOCOL_REPORT]),
+                // GET_REPORT is not supported, as hidraw only reads the input reports as they come.
+                _ => None,
+            }
+        } else {
+            None
+        }
+    }
+
+    // Handles the OUT control requests, returning whether they succeeded.
+    fn control_out(&mut self, setup: &SetupPacket, data: &[u8]) -> bool {
+        if setup.request_type & USB_TYPE_MASK == USB_TYPE_CLASS {
+            return match setup.request {
+                HID_REQ_SET_IDLE | HID_REQ_SET_PROTOCOL => true,
+                HID_REQ_SET_REPORT if setup.value >> 8 == u16::from(HID_REPORT_TYPE_OUTPUT) => {
+                    self.write_report(data)
+                }
+                _ => false,
+            };
+        }
+        match setup.request {
+            USB_REQ_SET_ADDRESS | USB_REQ_CLEAR_FEATURE | USB_REQ_SET_FEATURE => true,
+            USB_REQ_SET_CONFIGURATION => {
+                self.configuration = setup.value as u8;
+                true
+            }
+            USB_REQ_SET_INTERFACE => setup.value == 0,
+            _ => false,
+        }
+    }
+
+    fn control(&mut self, transfer: UsbTransfer, setup: SetupPacket) -> UsbCompletion {
+        if setup.is_in() {
+            match self.control_in(&setup) {
+                Some(data) => {
+                    let mut transfer = transfer;
+                    let len = data.len().min(transfer.data.len());
+                    transfer.data[..len].copy_from_slice(&data[..len]);
+                    transfer.complete(TransferStatus::Completed, len)
+                }
+                None => {
+                    debug!("usb: unsupported HID request {setup:?}");
+                    transfer.complete(TransferStatus::Stall, 0)
+                }
+            }
+        } else if self.control_out(&setup, &transfer.data) {
+            let len = transfer.data.len();
+            transfer.complete(TransferStatus::Completed, len)
+        } else {
+            debug!("usb: unsupported HID request {setup:?}");
+            transfer.complete(TransferStatus::Stall, 0)
+        }
+    }
+
+    // Writes an output report to the host device. hidraw expects the report ID first, which is
+    // 0 for devices not numbering their reports.
+    fn write_report(&mut self, data: &[u8]) -> bool {
+        let result = if self.numbered_reports {
+            self.file.write(data)
+        } else {
+            let mut report = Vec::with_capacity(data.len() + 1);
+            report.push(0);
+            report.extend_from_slice(data);
+            self.file.write(&report)
+        };
+        match result {
+            Ok(_) => true,
+            Err(err) => {
+                error!("usb: failed to write report to {:?}: {err}", self.path);
+                false
+            }
+        }
+    }
+
+    fn complete_pending_in(&mut self) -> Option<UsbCompletion> {
+        if self.reports.is_empty() {
+            return None;
+        }
+        let mut transfer = self.pending_in.take()?;
+        let report = self.reports.pop_front().unwrap();
+        let len = report.len().min(transfer.data.len());
+        transfer.data[..len].copy_from_slice(&report[..len]);
+        Some(transfer.complete(TransferStatus::Completed, len))
+    }
+}
+
+impl UsbDevice for HidrawDevice {
+    fn speed(&self) -> UsbSpeed {
+        UsbSpeed::Full
+    }
+
+    fn reset(&mut self) {
+        self.configuration = 0;
+        self.pending_in = None;
+    }
+
+    fn submit(&mut self, transfer: UsbTransfer) -> Option<UsbCompletion> {
+        if let Some(setup) = transfer.setup {
+            return Some(self.control(transfer, setup));
+        }
+        match transfer.endpoint {
+            INTERRUPT_IN_ENDPOINT => {
+                if self.pending_in.is_some() {
+                    warn!("usb: HID input transfer already pending, dropping the previous one");
+                }
+                self.pending_in = Some(transfer);
+                self.complete_pending_in()
+            }
+            INTERRUPT_OUT_ENDPOINT => {
+                let status = if self.write_report(&transfer.data) {
+                    TransferStatus::Completed
+                } else {
+                    TransferStatus::Error
+                };
+                let len = transfer.data.len();
+                Some(transfer.complete(status, len))
+            }
+            _ => Some(transfer.complete(TransferStatus::Stall, 0)),
+        }
+    }
+
+    fn cancel(&mut self, endpoint: u8) {
+        if endpoint == INTERRUPT_IN_ENDPOINT {
+            self.pending_in = None;
+        }
+    }
+
+    fn event_source(&self) -> Option<(RawFd, EventSet)> {
+        Some((self.file.as_raw_fd(), EventSet::IN))
+    }
+
+    fn process_events(&mut self) -> Vec<UsbCompletion> {
+        let mut buf = [0u8; MAX_REPORT_SIZE];
+        loop {
+            match self.file.read(&mut buf) {
+                Ok(0) => break,
+                Ok(len) => {
+                    if self.reports.len() == MAX_QUEUED_REPORTS {
+                        self.reports.pop_front();
+                    }
+                    self.reports.push_back(buf[..len].to_vec());
+                }
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
+                Err(err) => {
+                    error!("usb: failed to read report from {:?}: {err}", self.path);
+                    break;
+                }
+            }
+        }
+        self.complete_pending_in().into_iter().collect()
+    }
+}
+
+// Encodes a string descriptor, in UTF-16 as USB requires.
+fn string_descriptor(string: &str) -> Vec<u8> {
+    // The length of the descriptor has to fit in a byte.
+    let chars: Vec<u16> = string.encode_utf16().take(126).collect();
+    let mut descriptor = vec![u8::try_from(2 + 2 * chars.len()).unwrap(), USB_DT_STRING];
+    for c in chars {
+        descriptor.extend_from_slice(&c.to_le_bytes());
+    }
+    descriptor
+}
+
+// Whether a report descriptor declares report IDs, walking its items as described in section
+// 6.2.2 of the HID specification.
+fn has_report_ids(descriptor: &[u8]) -> bool {
+    // Report ID global item, of any data size.
+    const REPORT_ID_PREFIX: u8 = 0x84;
+    const LONG_ITEM_PREFIX: u8 = 0xfe;
+
+    let mut offset = 0;
+    while let Some(&prefix) = descriptor.get(offset) {
+        if prefix == LONG_ITEM_PREFIX {
+            let data_len = descriptor.get(offset + 1).copied().unwrap_or(0) as usize;
+            offset += 3 + data_len;
+            continue;
+        }
+        if prefix & 0xfc == REPORT_ID_PREFIX {
+            return true;
+        }
+        let data_len = match prefix & 0x3 {
+            3 => 4,
+            size => size as usize,
+        };
+        offset += 1 + data_len;
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use vmm_sys_util::tempfile::TempFile;
+
+    use super::*;
+
+    // Report descriptor of a FIDO U2F device, without report IDs.
+    const FIDO_REPORT_DESCRIPTOR: [u8; 34] = [
+        0x06, 0xd0, 0xf1, 0x09, 0x01, 0xa1, 0x01, 0x09, 0x20, 0x15, 0x00, 0x26, 0xff, 0x00, 0x75,
+        0x08, 0x95, 0x40, 0x81, 0x02, 0x09, 0x21, 0x15, 0x00, 0x26, 0xff, 0x00, 0x75, 0x08, 0x95,
+        0x40, 0x91, 0x02, 0xc0,
+    ];
+
+    fn hid_device(file: File) -> HidrawDevice {
+        HidrawDevice {
+            path: PathBuf::from("/dev/hidraw0"),
+            file,
+            name: String::from("Key"),
+            vendor_id: 0x1050,
+            product_id: 0x0407,
+            report_descriptor: FIDO_REPORT_DESCRIPTOR.to_vec(),
+            numbered_reports: false,
+            configuration: 0,
+            pending_in: None,
+            reports: VecDeque::new(),
+        }
+    }
+
+    fn control(device: &mut HidrawDevice, setup: SetupPacket, data: Vec<u8>) -> UsbCompletion {
+        device
+            .submit(UsbTransfer {
+                endpoint: 0,
+                kind: TransferKind::Control,
+                setup: Some(setup),
+                data,
+            })
+            .unwrap()
+    }
+
+    fn get_descriptor(request_type: u8, value: u16, length: u16) -> SetupPacket {
+        SetupPacket {
+            request_type,
+            request: USB_REQ_GET_DESCRIPTOR,
+            value,
+            index: 0,
+            length,
+        }
+    }
+
+    #[test]
+    fn test_control_requests() {
+        let report_file = TempFile::new().unwrap();
+        let mut device = hid_device(report_file.as_file().try_clone().unwrap());
+
+        let completion = control(
+            &mut device,
+            get_descriptor(USB_DIR_IN, u16::from(USB_DT_DEVICE) << 8, 64),
+            vec![0; 64],
+        );
+        assert_eq!(completion.status, TransferStatus::Completed);
+        assert_eq!(completion.actual_length, 18);
+        assert_eq!(&completion.data[8..12], &[0x50, 0x10, 0x07, 0x04]);
+
+        // The guest first reads the header of the configuration descriptor, for its total length.
+        let completion = control(
+            &mut device,
+            get_descriptor(USB_DIR_IN, u16::from(USB_DT_CONFIG) << 8, 9),
+            vec![0; 9],
+        );
+        assert_eq!(completion.actual_length, 9);
+        assert_eq!(&completion.data[2..4], &[41, 0]);
+
+        let completion = control(
+            &mut device,
+            get_descriptor(
+                USB_DIR_IN | USB_RECIP_INTERFACE,
+                u16::from(HID_DT_REPORT) << 8,
+                256,
+            ),
+            vec![0; 256],
+        );
+        assert_eq!(
+            &completion.data[..completion.actual_length],
+            &FIDO_REPORT_DESCRIPTOR
+        );
+
+        let completion = control(
+            &mut device,
+            get_descriptor(USB_DIR_IN, (u16::from(USB_DT_STRING) << 8) | 1, 255),
+            vec![0; 255],
+        );
+        assert_eq!(
+            &completion.data[..completion.actual_length],
+            &string_descriptor("Key")
+        );
+
+        // Unknown descriptors stall.
+        let completion = control(
+            &mut device,
+            get_descriptor(USB_DIR_IN, 0x0f00, 5),
+            vec![0; 5],
+        );
+        assert_eq!(completion.status, TransferStatus::Stall);
+
+        let set_configuration = SetupPacket {
+            request: USB_REQ_SET_CONFIGURATION,
+            value: u16::from(CONFIGURATION_VALUE),
+            ..Default::default()
+        };
+        let completion = control(&mut device, set_configuration, Vec::new());
+        assert_eq!(completion.status, TransferStatus::Completed);
+        assert_eq!(device.configuration, CONFIGURATION_VALUE);
+    }
+
+    #[test]
+    fn test_interrupt_transfers() {
+        let report_file = TempFile::new().unwrap();
+        let mut device = hid_device(report_file.as_file().try_clone().unwrap());
+
+        // Output reports are prefixed with the report ID 0.
+        let completion = device
+            .submit(UsbTransfer {
+                endpoint: INTERRUPT_OUT_ENDPOINT,
+                kind: TransferKind::Interrupt,
+                setup: None,
+                data: vec![1, 2, 3],
+            })
+            .unwrap();
+        assert_eq!(completion.status, TransferStatus::Completed);
+        assert_eq!(completion.actual_length, 3);
+        assert_eq!(
+            std::fs::read(report_file.as_path()).unwrap(),
+            vec![0, 1, 2, 3]
+        );
+
+        // Input transfers wait for a report.
+        let input = UsbTransfer {
+            endpoint: INTERRUPT_IN_ENDPOINT,
+            kind: TransferKind::Interrupt,
+            setup: None,
+            data: vec![0; 64],
+        };
+        assert!(device.submit(input.clone()).is_none());
+        device.reports.push_back(vec![5; 64]);
+        let completion = device.complete_pending_in().unwrap();
+        assert_eq!(completion.actual_length, 64);
+        assert_eq!(completion.data, vec![5; 64]);
+
+        // Cancelled transfers don't complete.
+        assert!(device.submit(input).is_none());
+        device.cancel(INTERRUPT_IN_ENDPOINT);
+        device.reports.push_back(vec![6; 64]);
+        assert!(device.complete_pending_in().is_none());
+    }
+
+    #[test]
+    fn test_has_report_ids() {
+        assert!(!has_report_ids(&FIDO_REPORT_DESCRIPTOR));
+        // Usage Page, Report ID 1, End Collection.
+        assert!(has_report_ids(&[0x05, 0x01, 0x85, 0x01, 0xc0]));
+        // A long item whose data looks like a Report ID.
+        assert!(!has_report_ids(&[0xfe, 0x02, 0x10, 0x85, 0x01, 0xc0]));
+    }
+
+    #[test]
+    fn test_string_descriptor() {
+        assert_eq!(
+            string_descriptor("Key"),
+            vec![8, USB_DT_STRING, b'K', 0, b'e', 0, b'y', 0]
+        );
+        assert_eq!(string_descriptor(&"x".repeat(300)).len(), 254);
+    }
+}
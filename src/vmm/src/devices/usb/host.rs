@@ -0,0 +1,451 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Passthrough of host USB devices to the guest, through usbfs.
+//!
+//! The interfaces of the device are detached from their host kernel drivers and claimed, and the
+//! transfers of the guest are submitted as URBs, which are reaped once the usbfs file signals
+//! their completion. The standard requests changing the state of the device, which usbfs needs to
+//! track, are translated to the matching usbfs ioctls rather than forwarded.
+
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read};
+use std::os::fd::{AsRawFd, RawFd};
+use std::path::{Path, PathBuf};
+
+use log::{debug, error, warn};
+use vmm_sys_util::epoll::EventSet;
+use vmm_sys_util::ioctl::{ioctl, ioctl_with_mut_ref, ioctl_with_ptr, ioctl_with_ref};
+
+use super::bindings::*;
+use super::*;
+
+const USB_DT_DEVICE: u8 = 0x01;
+const USB_DT_CONFIG: u8 = 0x02;
+const USB_DT_INTERFACE: u8 = 0x04;
+const DEVICE_DESCRIPTOR_SIZE: usize = 18;
+
+// Timeout of the control transfers done synchronously when opening the device.
+const CONTROL_TIMEOUT_MS: u32 = 1000;
+
+// URB submitted to usbfs, boxed so that its address stays valid until it is reaped.
+#[derive(Debug)]
+struct PendingUrb {
+    urb: usbdevfs_urb,
+    buffer: Vec<u8>,
+    endpoint: u8,
+    is_control: bool,
+    cancelled: bool,
+}
+
+// SAFETY: The raw pointers of the URB point to the buffer owned along with it.
+unsafe impl Send for PendingUrb {}
+
+/// Host USB device, passed through to the guest with usbfs.
+#[derive(Debug)]
+pub struct HostUsbDevice {
+    // Declared first to be dropped first: closing the usbfs file kills the pending URBs, whose
+    // buffers can only be freed afterwards.
+    file: File,
+    path: PathBuf,
+    speed: UsbSpeed,
+    // Device descriptor, followed by all the configuration descriptors.
+    descriptors: Vec<u8>,
+    claimed_interfaces: Vec<u8>,
+    pending: HashMap<usize, Box<PendingUrb>>,
+}
+
+impl HostUsbDevice {
+    /// Opens the usbfs node at `path`, e.g. /dev/bus/usb/001/004, and claims the interfaces of
+    /// the active configuration of the device.
+    pub fn open(path: &Path) -> Result<Self, UsbError> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(path)
+            .map_err(|err| UsbError::Open(path.to_path_buf(), err))?;
+
+        let mut descriptors = Vec::new();
+        file.read_to_end(&mut descriptors)
+            .map_err(|err| UsbError::Descriptors(path.to_path_buf(), err))?;
+        if descriptors.len() < DEVICE_DESCRIPTOR_SIZE || descriptors[1] != USB_DT_DEVICE {
+            return Err(UsbError::InvalidDescriptors(path.to_path_buf()));
+        }
+
+        // SAFETY: Safe because the fd is valid and the ioctl takes no argument.
+        let speed = unsafe { ioctl(&file, USBDEVFS_GET_SPEED()) };
+        if speed < 0 {
+            return Err(UsbError::Speed(
+                path.to_path_buf(),
+                io::Error::last_os_error(),
+            ));
+        }
+
+        let mut device = HostUsbDevice {
+            file,
+            path: path.to_path_buf(),
+            speed: usb_speed(speed),
+            descriptors,
+            claimed_interfaces: Vec::new(),
+            pending: HashMap::new(),
+        };
+        let configuration = device.active_configuration()?;
+        device.claim_interfaces(configuration)?;
+        debug!(
+            "usb: opened host device {path:?}, speed {:?}, configuration {configuration}",
+            device.speed
+        );
+        Ok(device)
+    }
+
+    fn active_configuration(&self) -> Result<u8, UsbError> {
+        let mut configuration = 0u8;
+        let mut transfer = usbdevfs_ctrltransfer {
+            bRequestType: USB_DIR_IN,
+            bRequest: USB_REQ_GET_CONFIGURATION,
+            wValue: 0,
+            wIndex: 0,
+            wLength: 1,
+            timeout: CONTROL_TIMEOUT_MS,
+            data: (&mut configuration as *mut u8).cast(),
+        };
+        // SAFETY: Safe because the fd is valid, and both the transfer and the byte it points to
+        // live for the duration of the call.
+        if unsafe { ioctl_with_mut_ref(&self.file, USBDEVFS_CONTROL(), &mut transfer) } < 0 {
+            return Err(UsbError::Descriptors(
+                self.path.clone(),
+                io::Error::last_os_error(),
+            ));
+        }
+        Ok(configuration)
+    }
+
+    // Detaches the interfaces of a configuration from their host drivers, and claims them.
+    fn claim_interfaces(&mut self, configuration: u8) -> Result<(), UsbError> {
+        for interface in configuration_interfaces(&self.descriptors, configuration) {
+            let claim = usbdevfs_disconnect_claim {
+                interface: u32::from(interface),
+                flags: 0,
+                driver: [0; 256],
+            };
+            // SAFETY: Safe because the fd is valid and claim lives for the duration of the call.
+            if unsafe { ioctl_with_ref(&self.file, USBDEVFS_DISCONNECT_CLAIM(), &claim) } < 0 {
+                return Err(UsbError::ClaimInterface(
+                    self.path.clone(),
+                    interface,
+                    io::Error::last_os_error(),
+                ));
+            }
+            self.claimed_interfaces.push(interface);
+        }
+        Ok(())
+    }
+
+    fn release_interfaces(&mut self) {
+        for interface in self.claimed_interfaces.drain(..) {
+            let interface = u32::from(interface);
+            // SAFETY: Safe because the fd is valid and interface lives for the duration of the
+            // call.
+            if unsafe { ioctl_with_ref(&self.file, USBDEVFS_RELEASEINTERFACE(), &interface) } < 0 {
+                warn!(
+                    "usb: failed to release interface {interface} of {:?}: {}",
+                    self.path,
+                    io::Error::last_os_error()
+                );
+            }
+        }
+    }
+
+    fn set_configuration(&mut self, configuration: u8) -> io::Result<()> {
+        self.release_interfaces();
+        let value = u32::from(configuration);
+        // SAFETY: Safe because the fd is valid and value lives for the duration of the call.
+        if unsafe { ioctl_with_ref(&self.file, USBDEVFS_SETCONFIGURATION(), &value) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        self.claim_interfaces(configuration)
+            .map_err(|err| io::Error::other(err.to_string()))
+    }
+
+    fn set_interface(&self, interface: u16, altsetting: u16) -> io::Result<()> {
+        let setinterface = usbdevfs_setinterface {
+            interface: u32::from(interface),
+            altsetting: u32::from(altsetting),
+        };
+        // SAFETY: Safe because the fd is valid and setinterface lives for the duration of the
+        // call.
+        if unsafe { ioctl_with_ref(&self.file, USBDEVFS_SETINTERFACE(), &setinterface) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    fn clear_halt(&self, endpoint: u16) -> io::Result<()> {
+        let endpoint = u32::from(endpoint & 0xff);
+        // SAFETY: Safe because the fd is valid and endpoint lives for the duration of the call.
+        if unsafe { ioctl_with_ref(&self.file, USBDEVFS_CLEAR_HALT(), &endpoint) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    // Handles the standard requests whose effect usbfs needs to know about. Returns None for the
+    // requests to forward to the device.
+    fn intercept_control(&mut self, setup: &SetupPacket) -> Option<io::Result<()>> {
+        if setup.is_standard(USB_RECIP_DEVICE) {
+            match setup.request {
+                // The host already addressed the device.
+                USB_REQ_SET_ADDRESS => Some(Ok(())),
+                USB_REQ_SET_CONFIGURATION => Some(self.set_configuration(setup.value as u8)),
+                _ => None,
+            }
+        } else if setup.is_standard(USB_RECIP_INTERFACE) && setup.request == USB_REQ_SET_INTERFACE {
+            Some(self.set_interface(setup.index, setup.value))
+        } else if setup.is_standard(USB_RECIP_ENDPOINT)
+            && setup.request == USB_REQ_CLEAR_FEATURE
+            && setup.value == USB_ENDPOINT_HALT
+        {
+            Some(self.clear_halt(setup.index))
+        } else {
+            None
+        }
+    }
+
+    fn submit_urb(&mut self, transfer: UsbTransfer) -> Option<UsbCompletion> {
+        let (urb_type, buffer) = match (transfer.kind, transfer.setup) {
+            (TransferKind::Control, Some(setup)) => {
+                // Control URBs start with the setup packet.
+                let mut buffer = setup.to_bytes().to_vec();
+                buffer.extend_from_slice(&transfer.data);
+                (USBDEVFS_URB_TYPE_CONTROL, buffer)
+            }
+            (TransferKind::Bulk, None) => (USBDEVFS_URB_TYPE_BULK, transfer.data.clone()),
+            (TransferKind::Interrupt, None) => (USBDEVFS_URB_TYPE_INTERRUPT, transfer.data.clone()),
+            _ => {
+                warn!("usb: unsupported {:?} transfer", transfer.kind);
+                return Some(transfer.complete(TransferStatus::Error, 0));
+            }
+        };
+
+        let mut pending = Box::new(PendingUrb {
+            urb: usbdevfs_urb::default(),
+            buffer,
+            endpoint: transfer.endpoint,
+            is_control: transfer.setup.is_some(),
+            cancelled: false,
+        });
+        pending.urb = usbdevfs_urb {
+            type_: urb_type,
+            endpoint: transfer.endpoint,
+            buffer: pending.buffer.as_mut_ptr().cast(),
+            buffer_length: i32::try_from(pending.buffer.len()).unwrap(),
+            ..Default::default()
+        };
+
+        // SAFETY: Safe because the fd is valid, and the URB and its buffer are kept alive until
+        // the URB is reaped.
+        if unsafe { ioctl_with_ptr(&self.file, USBDEVFS_SUBMITURB(), &pending.urb) } < 0 {
+            let err = io::Error::last_os_error();
+            error!("usb: failed to submit URB to {:?}: {err}", self.path);
+            return Some(transfer.complete(TransferStatus::Error, 0));
+        }
+        self.pending
+            .insert(&pending.urb as *const usbdevfs_urb as usize, pending);
+        None
+    }
+}
+
+impl UsbDevice for HostUsbDevice {
+    fn speed(&self) -> UsbSpeed {
+        self.speed
+    }
+
+    fn reset(&mut self) {
+        for pending in self.pending.values_mut() {
+            pending.cancelled = true;
+        }
+        // SAFETY: Safe because the fd is valid and the ioctl takes no argument.
+        if unsafe { ioctl(&self.file, USBDEVFS_RESET()) } < 0 {
+            warn!(
+                "usb: failed to reset {:?}: {}",
+                self.path,
+                io::Error::last_os_error()
+            );
+        }
+    }
+
+    fn submit(&mut self, transfer: UsbTransfer) -> Option<UsbCompletion> {
+        if let Some(setup) = transfer.setup {
+            if let Some(result) = self.intercept_control(&setup) {
+                let status = match result {
+                    Ok(()) => TransferStatus::Completed,
+                    Err(err) => {
+                        warn!("usb: request {setup:?} to {:?} failed: {err}", self.path);
+                        TransferStatus::Stall
+                    }
+                };
+                return Some(transfer.complete(status, 0));
+            }
+        }
+        self.submit_urb(transfer)
+    }
+
+    fn cancel(&mut self, endpoint: u8) {
+        for pending in self.pending.values_mut() {
+            if pending.endpoint != endpoint || pending.cancelled {
+                continue;
+            }
+            // The URB is still reaped afterwards, with an error status.
+            // SAFETY: Safe because the fd is valid and the URB was submitted to it.
+            if unsafe { ioctl_with_ptr(&self.file, USBDEVFS_DISCARDURB(), &pending.urb) } < 0 {
+                debug!(
+                    "usb: failed to discard URB of {:?}: {}",
+                    self.path,
+                    io::Error::last_os_error()
+                );
+            }
+            pending.cancelled = true;
+        }
+    }
+
+    fn event_source(&self) -> Option<(RawFd, EventSet)> {
+        // usbfs signals the completed URBs as the file being writable.
+        Some((self.file.as_raw_fd(), EventSet::OUT))
+    }
+
+    fn process_events(&mut self) -> Vec<UsbCompletion> {
+        let mut completions = Vec::new();
+        loop {
+            let mut urb: *mut usbdevfs_urb = std::ptr::null_mut();
+            // SAFETY: Safe because the fd is valid and urb lives for the duration of the call.
+            if unsafe { ioctl_with_mut_ref(&self.file, USBDEVFS_REAPURBNDELAY(), &mut urb) } < 0 {
+                let err = io::Error::last_os_error();
+                if err.raw_os_error() != Some(libc::EAGAIN) {
+                    error!("usb: failed to reap URB of {:?}: {err}", self.path);
+                }
+                break;
+            }
+            let Some(mut pending) = self.pending.remove(&(urb as usize)) else {
+                error!("usb: reaped unknown URB of {:?}", self.path);
+                continue;
+            };
+            if pending.cancelled {
+                continue;
+            }
+
+            let status = match -pending.urb.status {
+                0 => TransferStatus::Completed,
+                libc::EPIPE => TransferStatus::Stall,
+                errno => {
+                    debug!(
+                        "usb: URB of {:?} failed: {}",
+                        self.path,
+                        io::Error::from_raw_os_error(errno)
+                    );
+                    TransferStatus::Error
+                }
+            };
+            let data = if pending.is_control {
+                pending.buffer.split_off(8)
+            } else {
+                std::mem::take(&mut pending.buffer)
+            };
+            completions.push(UsbCompletion {
+                endpoint: pending.endpoint,
+                status,
+                actual_length: usize::try_from(pending.urb.actual_length)
+                    .unwrap_or(0)
+                    .min(data.len()),
+                data,
+            });
+        }
+        completions
+    }
+}
+
+impl Drop for HostUsbDevice {
+    fn drop(&mut self) {
+        self.release_interfaces();
+    }
+}
+
+fn usb_speed(speed: libc::c_int) -> UsbSpeed {
+    match speed {
+        USB_SPEED_LOW => UsbSpeed::Low,
+        USB_SPEED_FULL => UsbSpeed::Full,
+        USB_SPEED_SUPER | USB_SPEED_SUPER_PLUS => UsbSpeed::Super,
+        _ => UsbSpeed::High,
+    }
+}
+
+// Returns the numbers of the interfaces of a configuration, from the descriptors read from
+// usbfs: the device descriptor followed by all the configuration descriptors, each followed by
+// its interface and endpoint descriptors.
+fn configuration_interfaces(descriptors: &[u8], configuration: u8) -> Vec<u8> {
+    let mut interfaces = Vec::new();
+    let mut in_configuration = false;
+    let mut offset = 0;
+    while let Some(&[len, descriptor_type]) = descriptors.get(offset..offset + 2) {
+        if len < 2 {
+            break;
+        }
+        let descriptor = descriptors
+            .get(offset..offset + len as usize)
+            .unwrap_or_default();
+        match descriptor_type {
+            USB_DT_CONFIG => {
+                in_configuration = descriptor.get(5) == Some(&configuration);
+            }
+            USB_DT_INTERFACE if in_configuration => {
+                if let Some(&interface) = descriptor.get(2)
+                    && !interfaces.contains(&interface)
+                {
+                    interfaces.push(interface);
+                }
+            }
+            _ => (),
+        }
+        offset += len as usize;
+    }
+    interfaces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_configuration_interfaces() {
+        let mut descriptors = vec![0u8; DEVICE_DESCRIPTOR_SIZE];
+        descriptors[0] = DEVICE_DESCRIPTOR_SIZE as u8;
+        descriptors[1] = USB_DT_DEVICE;
+        // Configuration 1, with interface 0 and its alternate setting, and interface 1.
+        descriptors.extend_from_slice(&[9, USB_DT_CONFIG, 0, 0, 2, 1, 0, 0x80, 50]);
+        descriptors.extend_from_slice(&[9, USB_DT_INTERFACE, 0, 0, 1, 3, 0, 0, 0]);
+        descriptors.extend_from_slice(&[7, 0x05, 0x81, 3, 8, 0, 10]);
+        descriptors.extend_from_slice(&[9, USB_DT_INTERFACE, 0, 1, 0, 3, 0, 0, 0]);
+        descriptors.extend_from_slice(&[9, USB_DT_INTERFACE, 1, 0, 0, 3, 0, 0, 0]);
+        // Configuration 2, with interface 2.
+        descriptors.extend_from_slice(&[9, USB_DT_CONFIG, 0, 0, 1, 2, 0, 0x80, 50]);
+        descriptors.extend_from_slice(&[9, USB_DT_INTERFACE, 2, 0, 0, 3, 0, 0, 0]);
+
+        assert_eq!(configuration_interfaces(&descriptors, 1), vec![0, 1]);
+        assert_eq!(configuration_interfaces(&descriptors, 2), vec![2]);
+        // The device is not configured.
+        assert!(configuration_interfaces(&descriptors, 0).is_empty());
+        // Truncated descriptors.
+        assert_eq!(
+            configuration_interfaces(&descriptors[..descriptors.len() - 4], 2),
+            Vec::<u8>::new()
+        );
+    }
+
+    #[test]
+    fn test_usb_speed() {
+        assert_eq!(usb_speed(USB_SPEED_LOW), UsbSpeed::Low);
+        assert_eq!(usb_speed(USB_SPEED_FULL), UsbSpeed::Full);
+        assert_eq!(usb_speed(USB_SPEED_HIGH), UsbSpeed::High);
+        assert_eq!(usb_speed(USB_SPEED_SUPER_PLUS), UsbSpeed::Super);
+    }
+}
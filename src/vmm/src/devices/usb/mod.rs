@@ -0,0 +1,285 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! USB devices of the guest, attached to the ports of an emulated xHCI controller.
+//!
+//! The controller sits on the PCI segment and hands the transfers queued by the guest driver to
+//! the devices connected to its root hub ports. Devices are either host USB devices passed
+//! through with usbfs, or emulated HID devices backed by a host hidraw node.
+
+mod bindings;
+pub mod hid;
+pub mod host;
+pub mod xhci;
+
+use std::fmt::Debug;
+use std::io;
+use std::os::fd::RawFd;
+use std::path::PathBuf;
+
+use vmm_sys_util::epoll::EventSet;
+
+/// Standard requests of the USB specification, chapter 9.4.
+pub const USB_REQ_GET_STATUS: u8 = 0x00;
+/// Clears a feature of the device, interface or endpoint.
+pub const USB_REQ_CLEAR_FEATURE: u8 = 0x01;
+/// Sets a feature of the device, interface or endpoint.
+pub const USB_REQ_SET_FEATURE: u8 = 0x03;
+/// Sets the address of the device.
+pub const USB_REQ_SET_ADDRESS: u8 = 0x05;
+/// Returns a descriptor.
+pub const USB_REQ_GET_DESCRIPTOR: u8 = 0x06;
+/// Returns the active configuration.
+pub const USB_REQ_GET_CONFIGURATION: u8 = 0x08;
+/// Selects the active configuration.
+pub const USB_REQ_SET_CONFIGURATION: u8 = 0x09;
+/// Returns the alternate setting of an interface.
+pub const USB_REQ_GET_INTERFACE: u8 = 0x0a;
+/// Selects the alternate setting of an interface.
+pub const USB_REQ_SET_INTERFACE: u8 = 0x0b;
+
+/// Direction bit of the request type and of the endpoint addresses.
+pub const USB_DIR_IN: u8 = 0x80;
+/// Mask of the type of the request: standard, class or vendor.
+pub const USB_TYPE_MASK: u8 = 0x60;
+/// Standard request type.
+pub const USB_TYPE_STANDARD: u8 = 0x00;
+/// Class request type.
+pub const USB_TYPE_CLASS: u8 = 0x20;
+/// Mask of the recipient of the request.
+pub const USB_RECIP_MASK: u8 = 0x1f;
+/// The device is the recipient of the request.
+pub const USB_RECIP_DEVICE: u8 = 0x00;
+/// An interface is the recipient of the request.
+pub const USB_RECIP_INTERFACE: u8 = 0x01;
+/// An endpoint is the recipient of the request.
+pub const USB_RECIP_ENDPOINT: u8 = 0x02;
+
+/// Feature selector of the halt condition of an endpoint.
+pub const USB_ENDPOINT_HALT: u16 = 0;
+
+/// Errors related to the USB devices.
+#[derive(Debug, thiserror::Error, displaydoc::Display)]
+pub enum UsbError {
+    /// Failed to open the USB device {0:?}: {1}
+    Open(PathBuf, io::Error),
+    /// Failed to read the descriptors of the USB device {0:?}: {1}
+    Descriptors(PathBuf, io::Error),
+    /// Invalid descriptors of the USB device {0:?}
+    InvalidDescriptors(PathBuf),
+    /// Failed to get the speed of the USB device {0:?}: {1}
+    Speed(PathBuf, io::Error),
+    /// Failed to claim interface {1} of the USB device {0:?}: {2}
+    ClaimInterface(PathBuf, u8, io::Error),
+    /// Failed to query the HID device {0:?}: {1}
+    HidInfo(PathBuf, io::Error),
+}
+
+/// Speed of a USB device, as reported in the Port Speed field of the xHCI port registers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UsbSpeed {
+    /// USB 1.1 full speed, 12 Mb/s.
+    Full = 1,
+    /// USB 1.1 low speed, 1.5 Mb/s.
+    Low = 2,
+    /// USB 2.0 high speed, 480 Mb/s.
+    High = 3,
+    /// USB 3.0 SuperSpeed, 5 Gb/s.
+    Super = 4,
+}
+
+impl UsbSpeed {
+    /// Whether the device needs a USB 3 port of the controller.
+    pub fn is_usb3(self) -> bool {
+        self == UsbSpeed::Super
+    }
+}
+
+/// Setup packet starting a control transfer.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SetupPacket {
+    /// Direction, type and recipient of the request.
+    pub request_type: u8,
+    /// Request code.
+    pub request: u8,
+    /// Request specific value.
+    pub value: u16,
+    /// Request specific index, usually an interface or endpoint number.
+    pub index: u16,
+    /// Number of bytes of the data stage.
+    pub length: u16,
+}
+
+impl SetupPacket {
+    /// Decodes the setup packet from its wire format.
+    pub fn from_bytes(bytes: [u8; 8]) -> Self {
+        SetupPacket {
+            request_type: bytes[0],
+            request: bytes[1],
+            value: u16::from_le_bytes([bytes[2], bytes[3]]),
+            index: u16::from_le_bytes([bytes[4], bytes[5]]),
+            length: u16::from_le_bytes([bytes[6], bytes[7]]),
+        }
+    }
+
+    /// Encodes the setup packet in its wire format.
+    pub fn to_bytes(self) -> [u8; 8] {
+        let value = self.value.to_le_bytes();
+        let index = self.index.to_le_bytes();
+        let length = self.length.to_le_bytes();
+        [
+            self.request_type,
+            self.request,
+            value[0],
+            value[1],
+            index[0],
+            index[1],
+            length[0],
+            length[1],
+        ]
+    }
+
+    /// Whether the data stage moves data from the device to the host.
+    pub fn is_in(&self) -> bool {
+        self.request_type & USB_DIR_IN != 0
+    }
+
+    /// Whether this is a standard request, addressed to the given recipient.
+    pub fn is_standard(&self, recipient: u8) -> bool {
+        self.request_type & USB_TYPE_MASK == USB_TYPE_STANDARD
+            && self.request_type & USB_RECIP_MASK == recipient
+    }
+}
+
+/// Type of a USB transfer, given by the type of its endpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferKind {
+    /// Transfer on the default control endpoint.
+    Control,
+    /// Isochronous transfer.
+    Isochronous,
+    /// Bulk transfer.
+    Bulk,
+    /// Interrupt transfer.
+    Interrupt,
+}
+
+/// Transfer submitted to a USB device.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UsbTransfer {
+    /// Address of the endpoint, whose bit 7 is set for IN endpoints.
+    pub endpoint: u8,
+    /// Type of the transfer.
+    pub kind: TransferKind,
+    /// Setup packet of control transfers.
+    pub setup: Option<SetupPacket>,
+    /// Data sent to the device, or buffer of the requested length for data received from it.
+    pub data: Vec<u8>,
+}
+
+impl UsbTransfer {
+    /// Whether the transfer moves data from the device to the host.
+    pub fn is_in(&self) -> bool {
+        match self.setup {
+            Some(setup) => setup.is_in(),
+            None => self.endpoint & USB_DIR_IN != 0,
+        }
+    }
+
+    /// Completes the transfer with the given status, having moved `actual_length` bytes.
+    pub fn complete(self, status: TransferStatus, actual_length: usize) -> UsbCompletion {
+        UsbCompletion {
+            endpoint: self.endpoint,
+            status,
+            actual_length: actual_length.min(self.data.len()),
+            data: self.data,
+        }
+    }
+}
+
+/// Outcome of a transfer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferStatus {
+    /// The transfer completed, possibly moving less data than requested.
+    Completed,
+    /// The endpoint stalled.
+    Stall,
+    /// The transfer failed.
+    Error,
+}
+
+/// Transfer completed by a USB device.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UsbCompletion {
+    /// Address of the endpoint of the transfer.
+    pub endpoint: u8,
+    /// Outcome of the transfer.
+    pub status: TransferStatus,
+    /// Data of the transfer, of which the first `actual_length` bytes were received for IN
+    /// transfers.
+    pub data: Vec<u8>,
+    /// Number of bytes moved.
+    pub actual_length: usize,
+}
+
+/// Device connected to a port of the xHCI controller.
+pub trait UsbDevice: Debug + Send {
+    /// Speed of the device.
+    fn speed(&self) -> UsbSpeed;
+
+    /// Resets the device, when the guest resets its port.
+    fn reset(&mut self);
+
+    /// Starts a transfer. The completion is returned right away if the transfer completes
+    /// synchronously, otherwise it is returned by `process_events` later on.
+    fn submit(&mut self, transfer: UsbTransfer) -> Option<UsbCompletion>;
+
+    /// Cancels the transfers pending on an endpoint, whose completions are dropped.
+    fn cancel(&mut self, endpoint: u8);
+
+    /// File descriptor and events signaling that transfers completed asynchronously, if any.
+    fn event_source(&self) -> Option<(RawFd, EventSet)>;
+
+    /// Handles the events of the event source, returning the completed transfers.
+    fn process_events(&mut self) -> Vec<UsbCompletion>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_setup_packet() {
+        let bytes = [
+            0x80,
+            USB_REQ_GET_DESCRIPTOR,
+            0x00,
+            0x01,
+            0x00,
+            0x00,
+            0x12,
+            0x00,
+        ];
+        let setup = SetupPacket::from_bytes(bytes);
+        assert_eq!(setup.value, 0x0100);
+        assert_eq!(setup.length, 0x12);
+        assert!(setup.is_in());
+        assert!(setup.is_standard(USB_RECIP_DEVICE));
+        assert!(!setup.is_standard(USB_RECIP_INTERFACE));
+        assert_eq!(setup.to_bytes(), bytes);
+    }
+
+    #[test]
+    fn test_transfer_complete() {
+        let transfer = UsbTransfer {
+            endpoint: 0x81,
+            kind: TransferKind::Interrupt,
+            setup: None,
+            data: vec![0; 8],
+        };
+        assert!(transfer.is_in());
+        let completion = transfer.complete(TransferStatus::Completed, 16);
+        assert_eq!(completion.actual_length, 8);
+        assert_eq!(completion.endpoint, 0x81);
+    }
+}
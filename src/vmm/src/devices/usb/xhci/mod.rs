@@ -0,0 +1,1819 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Emulated xHCI controller, exposing a root hub with USB 2 and USB 3 ports.
+//!
+//! The emulation covers what the Linux and Windows xHCI drivers use: a single interrupter
+//! signaled with MSI-X, the command ring, and one transfer ring per endpoint with at most one TD
+//! in flight. Streams, isochronous transfers and the debug capability are not supported.
+
+mod ring;
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Barrier, Mutex};
+use std::time::Instant;
+
+use event_manager::{EventOps, Events, MutEventSubscriber};
+use log::{debug, error, warn};
+use pci::{PciBdf, PciClassCode, PciSerialBusSubClass};
+use vm_allocator::AllocPolicy;
+use vmm_sys_util::epoll::EventSet;
+
+use self::ring::*;
+use super::{
+    SetupPacket, TransferKind, TransferStatus, USB_DIR_IN, UsbCompletion, UsbDevice, UsbTransfer,
+};
+use crate::pci::PciDevice;
+use crate::pci::configuration::PciConfiguration;
+use crate::pci::msix::{MsixCap, MsixConfig};
+use crate::vstate::bus::BusDevice;
+use crate::vstate::interrupts::InterruptError;
+use crate::vstate::memory::{Bytes, GuestAddress, GuestMemoryMmap};
+use crate::vstate::vm::Vm;
+
+// IDs of the xHCI controller of QEMU, whose quirks guest drivers already know about.
+const XHCI_VENDOR_ID: u16 = 0x1b36;
+const XHCI_DEVICE_ID: u16 = 0x000d;
+const XHCI_PROG_IF: u8 = 0x30;
+
+/// Size of the BAR of the controller.
+pub const XHCI_BAR_SIZE: u64 = 0x10000;
+
+// Layout of the BAR.
+const CAP_LENGTH: u64 = 0x40;
+const OP_REGS_OFFSET: u64 = CAP_LENGTH;
+const PORT_REGS_OFFSET: u64 = OP_REGS_OFFSET + 0x400;
+const PORT_REGS_SIZE: u64 = 0x10;
+const RUNTIME_REGS_OFFSET: u64 = 0x1000;
+const INTERRUPTER_REGS_OFFSET: u64 = RUNTIME_REGS_OFFSET + 0x20;
+const DOORBELL_OFFSET: u64 = 0x2000;
+const EXT_CAPS_OFFSET: u64 = 0x3000;
+const MSIX_TABLE_OFFSET: u64 = 0x4000;
+const MSIX_TABLE_SIZE: u64 = 0x10;
+const MSIX_PBA_OFFSET: u64 = 0x5000;
+const MSIX_PBA_SIZE: u64 = 0x8;
+
+/// Number of device slots of the controller.
+const MAX_SLOTS: u8 = 16;
+/// Number of USB 2 ports, which come first, followed by as many USB 3 ports.
+const USB2_PORTS: u8 = 4;
+/// Number of USB 3 ports.
+const USB3_PORTS: u8 = 4;
+const MAX_PORTS: u8 = USB2_PORTS + USB3_PORTS;
+/// Maximum number of entries of the Event Ring Segment Table, as a power of two.
+const ERST_MAX: u32 = 4;
+
+// Capability registers.
+const HCIVERSION: u32 = 0x0100;
+const HCSPARAMS1: u32 = MAX_SLOTS as u32 | (1 << 8) | ((MAX_PORTS as u32) << 24);
+const HCSPARAMS2: u32 = ERST_MAX << 4;
+const HCCPARAMS1_AC64: u32 = 1 << 0;
+const HCCPARAMS1: u32 = HCCPARAMS1_AC64 | (((EXT_CAPS_OFFSET / 4) as u32) << 16);
+
+// Operational registers, relative to OP_REGS_OFFSET.
+const USBCMD: u64 = 0x00;
+const USBSTS: u64 = 0x04;
+const PAGESIZE: u64 = 0x08;
+const DNCTRL: u64 = 0x14;
+const CRCR_LO: u64 = 0x18;
+const CRCR_HI: u64 = 0x1c;
+const DCBAAP_LO: u64 = 0x30;
+const DCBAAP_HI: u64 = 0x34;
+const CONFIG: u64 = 0x38;
+
+const USBCMD_RUN: u32 = 1 << 0;
+const USBCMD_RESET: u32 = 1 << 1;
+const USBCMD_INTE: u32 = 1 << 2;
+
+const USBSTS_HCH: u32 = 1 << 0;
+const USBSTS_HSE: u32 = 1 << 2;
+const USBSTS_EINT: u32 = 1 << 3;
+const USBSTS_PCD: u32 = 1 << 4;
+const USBSTS_SRE: u32 = 1 << 10;
+const USBSTS_RW1C: u32 = USBSTS_HSE | USBSTS_EINT | USBSTS_PCD | USBSTS_SRE;
+
+const CRCR_RCS: u64 = 1 << 0;
+const CRCR_CS: u64 = 1 << 1;
+const CRCR_CA: u64 = 1 << 2;
+const CRCR_CRR: u64 = 1 << 3;
+
+// Port registers, relative to the registers of the port.
+const PORTSC: u64 = 0x0;
+
+const PORTSC_CCS: u32 = 1 << 0;
+const PORTSC_PED: u32 = 1 << 1;
+const PORTSC_PR: u32 = 1 << 4;
+const PORTSC_PLS_SHIFT: u32 = 5;
+const PORTSC_PLS_MASK: u32 = 0xf << PORTSC_PLS_SHIFT;
+const PORTSC_PP: u32 = 1 << 9;
+const PORTSC_SPEED_SHIFT: u32 = 10;
+const PORTSC_LWS: u32 = 1 << 16;
+const PORTSC_CSC: u32 = 1 << 17;
+const PORTSC_PEC: u32 = 1 << 18;
+const PORTSC_WRC: u32 = 1 << 19;
+const PORTSC_OCC: u32 = 1 << 20;
+const PORTSC_PRC: u32 = 1 << 21;
+const PORTSC_PLC: u32 = 1 << 22;
+const PORTSC_CEC: u32 = 1 << 23;
+const PORTSC_WAKE_MASK: u32 = 0x7 << 25;
+const PORTSC_WPR: u32 = 1 << 31;
+const PORTSC_CHANGE_MASK: u32 =
+    PORTSC_CSC | PORTSC_PEC | PORTSC_WRC | PORTSC_OCC | PORTSC_PRC | PORTSC_PLC | PORTSC_CEC;
+
+// Port link states.
+const PLS_U0: u32 = 0;
+const PLS_U3: u32 = 3;
+const PLS_RX_DETECT: u32 = 5;
+const PLS_POLLING: u32 = 7;
+const PLS_RESUME: u32 = 15;
+
+// Interrupter registers, relative to INTERRUPTER_REGS_OFFSET.
+const IMAN: u64 = 0x00;
+const IMOD: u64 = 0x04;
+const ERSTSZ: u64 = 0x08;
+const ERSTBA_LO: u64 = 0x10;
+const ERSTBA_HI: u64 = 0x14;
+const ERDP_LO: u64 = 0x18;
+const ERDP_HI: u64 = 0x1c;
+
+const IMAN_IP: u32 = 1 << 0;
+const IMAN_IE: u32 = 1 << 1;
+const IMOD_DEFAULT: u32 = 4000;
+const ERDP_EHB: u64 = 1 << 3;
+
+// Completion codes.
+const CC_SUCCESS: u32 = 1;
+const CC_USB_TRANSACTION_ERROR: u32 = 4;
+const CC_TRB_ERROR: u32 = 5;
+const CC_STALL_ERROR: u32 = 6;
+const CC_NO_SLOTS_AVAILABLE: u32 = 9;
+const CC_SLOT_NOT_ENABLED: u32 = 11;
+const CC_SHORT_PACKET: u32 = 13;
+const CC_CONTEXT_STATE_ERROR: u32 = 19;
+const CC_COMMAND_RING_STOPPED: u32 = 24;
+
+// Contexts, of 32 bytes as HCCPARAMS1.CSZ is cleared.
+const CONTEXT_SIZE: u64 = 32;
+const SLOT_STATE_SHIFT: u32 = 27;
+const SLOT_STATE_DEFAULT: u32 = 1;
+const SLOT_STATE_ADDRESSED: u32 = 2;
+const SLOT_STATE_CONFIGURED: u32 = 3;
+const SLOT_ROOT_HUB_PORT_SHIFT: u32 = 16;
+const EP_TYPE_SHIFT: u32 = 3;
+const EP_TYPE_ISOCH_OUT: u32 = 1;
+const EP_TYPE_BULK_OUT: u32 = 2;
+const EP_TYPE_INTERRUPT_OUT: u32 = 3;
+const EP_TYPE_CONTROL: u32 = 4;
+const EP_TYPE_ISOCH_IN: u32 = 5;
+const EP_TYPE_BULK_IN: u32 = 6;
+const EP_TYPE_INTERRUPT_IN: u32 = 7;
+const EP_DEQUEUE_CYCLE: u64 = 1 << 0;
+
+// Flags of the commands.
+const TRB_BLOCK_SET_ADDRESS: u32 = 1 << 9;
+const TRB_DECONFIGURE: u32 = 1 << 9;
+
+/// Upper bound of the number of TRBs of a TD, which protects against rings made of a single
+/// endless chain.
+const MAX_TD_TRBS: usize = 256;
+
+/// Errors of the xHCI controller.
+#[derive(Debug, thiserror::Error, displaydoc::Display)]
+pub enum XhciError {
+    /// Failed to allocate the interrupts of the xHCI controller: {0}
+    Interrupts(#[from] InterruptError),
+    /// Failed to allocate the BAR of the xHCI controller: {0}
+    Bar(#[from] vm_allocator::Error),
+    /// No free {0} port left for the USB device
+    NoFreePort(&'static str),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EndpointState {
+    Running = 1,
+    Halted = 2,
+    Stopped = 3,
+}
+
+// TD handed to the device, which completes it later on.
+#[derive(Debug)]
+struct InflightTd {
+    // Position of the ring at the first TRB of the TD, where the ring restarts from when the
+    // endpoint stops before the TD completes.
+    start: RingReader,
+    trbs: Vec<(u64, Trb)>,
+    is_in: bool,
+}
+
+#[derive(Debug)]
+struct Endpoint {
+    state: EndpointState,
+    kind: TransferKind,
+    ring: RingReader,
+    inflight: Option<InflightTd>,
+}
+
+#[derive(Debug, Default)]
+struct Slot {
+    enabled: bool,
+    // Index of the port of the device, once addressed.
+    port: Option<usize>,
+    // Enabled endpoints, by Device Context Index.
+    endpoints: BTreeMap<u8, Endpoint>,
+}
+
+#[derive(Debug)]
+struct Port {
+    usb3: bool,
+    portsc: u32,
+    device: Option<Box<dyn UsbDevice>>,
+}
+
+impl Port {
+    fn new(usb3: bool, device: Option<Box<dyn UsbDevice>>) -> Self {
+        let mut port = Port {
+            usb3,
+            portsc: 0,
+            device,
+        };
+        port.power_on();
+        port
+    }
+
+    // State of the port right after it is powered, with a connect change if a device is
+    // attached.
+    fn power_on(&mut self) {
+        self.portsc = PORTSC_PP;
+        match self.device.as_ref().map(|device| device.speed()) {
+            Some(speed) => {
+                self.portsc |= PORTSC_CCS | PORTSC_CSC | ((speed as u32) << PORTSC_SPEED_SHIFT);
+                // USB 3 ports train their link on their own, USB 2 ports wait for a reset.
+                if self.usb3 {
+                    self.portsc |= PORTSC_PED;
+                    self.set_link_state(PLS_U0);
+                } else {
+                    self.set_link_state(PLS_POLLING);
+                }
+            }
+            None => self.set_link_state(PLS_RX_DETECT),
+        }
+    }
+
+    fn link_state(&self) -> u32 {
+        (self.portsc & PORTSC_PLS_MASK) >> PORTSC_PLS_SHIFT
+    }
+
+    fn set_link_state(&mut self, state: u32) {
+        self.portsc = (self.portsc & !PORTSC_PLS_MASK) | (state << PORTSC_PLS_SHIFT);
+    }
+}
+
+/// Emulated xHCI controller, to which USB devices are attached.
+#[derive(Debug)]
+pub struct XhciController {
+    configuration: PciConfiguration,
+    msix_config: Arc<Mutex<MsixConfig>>,
+    bar_address: u64,
+    mem: GuestMemoryMmap,
+    start_time: Instant,
+
+    usbcmd: u32,
+    usbsts: u32,
+    dnctrl: u32,
+    crcr: u64,
+    command_ring: Option<RingReader>,
+    dcbaap: u64,
+    config: u32,
+
+    iman: u32,
+    imod: u32,
+    erstsz: u32,
+    erstba: u64,
+    erdp: u64,
+    event_ring: EventRing,
+
+    ports: Vec<Port>,
+    slots: Vec<Slot>,
+}
+
+impl XhciController {
+    /// Creates the controller, allocating its BAR and its interrupt, and connects the devices to
+    /// the ports matching their speed.
+    pub fn new(
+        vm: &Arc<Vm>,
+        bdf: PciBdf,
+        devices: Vec<Box<dyn UsbDevice>>,
+    ) -> Result<Self, XhciError> {
+        let mut ports: Vec<Port> = (0..MAX_PORTS)
+            .map(|index| Port::new(index >= USB2_PORTS, None))
+            .collect();
+        for device in devices {
+            let usb3 = device.speed().is_usb3();
+            let port = ports
+                .iter_mut()
+                .find(|port| port.usb3 == usb3 && port.device.is_none())
+                .ok_or(XhciError::NoFreePort(if usb3 { "USB 3" } else { "USB 2" }))?;
+            *port = Port::new(usb3, Some(device));
+        }
+
+        let vectors = Vm::create_msix_group(vm.clone(), 1)?;
+        let msix_config = Arc::new(Mutex::new(MsixConfig::new(Arc::new(vectors), bdf.into())));
+        let mut configuration = PciConfiguration::new_type0(
+            XHCI_VENDOR_ID,
+            XHCI_DEVICE_ID,
+            0x1,
+            PciClassCode::SerialBusController,
+            &PciSerialBusSubClass::Usb,
+            XHCI_VENDOR_ID,
+            XHCI_DEVICE_ID,
+            Some(msix_config.clone()),
+        );
+        configuration.set_programming_interface(XHCI_PROG_IF);
+
+        let bar_address = vm.resource_allocator().allocate_64bit_mmio_memory(
+            XHCI_BAR_SIZE,
+            XHCI_BAR_SIZE,
+            AllocPolicy::FirstMatch,
+        )?;
+        configuration.add_pci_bar(0, bar_address, XHCI_BAR_SIZE);
+        configuration.add_capability(&MsixCap::new(
+            0,
+            1,
+            u32::try_from(MSIX_TABLE_OFFSET).unwrap(),
+            0,
+            u32::try_from(MSIX_PBA_OFFSET).unwrap(),
+        ));
+
+        Ok(XhciController {
+            configuration,
+            msix_config,
+            bar_address,
+            mem: vm.guest_memory().clone(),
+            start_time: Instant::now(),
+            usbcmd: 0,
+            usbsts: USBSTS_HCH,
+            dnctrl: 0,
+            crcr: 0,
+            command_ring: None,
+            dcbaap: 0,
+            config: 0,
+            iman: 0,
+            imod: IMOD_DEFAULT,
+            erstsz: 0,
+            erstba: 0,
+            erdp: 0,
+            event_ring: EventRing::default(),
+            ports,
+            slots: (0..MAX_SLOTS).map(|_| Slot::default()).collect(),
+        })
+    }
+
+    /// Guest physical address of the BAR of the controller.
+    pub fn bar_address(&self) -> u64 {
+        self.bar_address
+    }
+
+    fn reset(&mut self) {
+        for slot_id in 1..=MAX_SLOTS {
+            self.stop_endpoints(slot_id, |_| true);
+        }
+        for port in &mut self.ports {
+            if let Some(device) = &mut port.device {
+                device.reset();
+            }
+            port.power_on();
+        }
+        self.usbcmd = 0;
+        self.usbsts = USBSTS_HCH;
+        self.dnctrl = 0;
+        self.crcr = 0;
+        self.command_ring = None;
+        self.dcbaap = 0;
+        self.config = 0;
+        self.iman = 0;
+        self.imod = IMOD_DEFAULT;
+        self.erstsz = 0;
+        self.erstba = 0;
+        self.erdp = 0;
+        self.event_ring.reset();
+        self.slots = (0..MAX_SLOTS).map(|_| Slot::default()).collect();
+    }
+
+    fn running(&self) -> bool {
+        self.usbcmd & USBCMD_RUN != 0
+    }
+
+    fn read_capability(&self, offset: u64) -> u32 {
+        match offset {
+            0x00 => (HCIVERSION << 16) | CAP_LENGTH as u32,
+            0x04 => HCSPARAMS1,
+            0x08 => HCSPARAMS2,
+            0x10 => HCCPARAMS1,
+            0x14 => DOORBELL_OFFSET as u32,
+            0x18 => RUNTIME_REGS_OFFSET as u32,
+            _ => 0,
+        }
+    }
+
+    // Supported Protocol capabilities, describing which ports are USB 2 and which are USB 3.
+    fn read_extended_capability(&self, offset: u64) -> u32 {
+        // "USB " in little endian.
+        const NAME_STRING: u32 = 0x2042_5355;
+        const SUPPORTED_PROTOCOL: u32 = 2;
+        const CAP_SIZE: u64 = 0x10;
+        let (major, first_port, count, next) = match offset / CAP_SIZE {
+            0 => (2, 1, USB2_PORTS, CAP_SIZE / 4),
+            1 => (3, USB2_PORTS + 1, USB3_PORTS, 0),
+            _ => return 0,
+        };
+        match offset % CAP_SIZE {
+            0x0 => SUPPORTED_PROTOCOL | ((next as u32) << 8) | (major << 24),
+            0x4 => NAME_STRING,
+            0x8 => u32::from(first_port) | (u32::from(count) << 8),
+            _ => 0,
+        }
+    }
+
+    fn read_operational(&self, offset: u64) -> u32 {
+        match offset {
+            USBCMD => self.usbcmd,
+            USBSTS => self.usbsts,
+            // 4 KiB pages only.
+            PAGESIZE => 1,
+            DNCTRL => self.dnctrl,
+            // Only the Command Ring Running bit reads back.
+            CRCR_LO => {
+                if self.command_ring.is_some() && self.running() {
+                    CRCR_CRR as u32
+                } else {
+                    0
+                }
+            }
+            DCBAAP_LO => self.dcbaap as u32,
+            DCBAAP_HI => (self.dcbaap >> 32) as u32,
+            CONFIG => self.config,
+            _ => 0,
+        }
+    }
+
+    fn write_operational(&mut self, offset: u64, value: u32) {
+        match offset {
+            USBCMD => self.write_usbcmd(value),
+            USBSTS => self.usbsts &= !(value & USBSTS_RW1C),
+            DNCTRL => self.dnctrl = value & 0xffff,
+            CRCR_LO => self.write_crcr((self.crcr & !0xffff_ffff) | u64::from(value)),
+            CRCR_HI => {
+                self.write_crcr((self.crcr & 0xffff_ffff) | (u64::from(value) << 32));
+            }
+            DCBAAP_LO => {
+                self.dcbaap = (self.dcbaap & !0xffff_ffff) | u64::from(value & !0x3f);
+            }
+            DCBAAP_HI => {
+                self.dcbaap = (self.dcbaap & 0xffff_ffff) | (u64::from(value) << 32);
+            }
+            CONFIG => self.config = value & 0xff,
+            _ => (),
+        }
+    }
+
+    fn write_usbcmd(&mut self, value: u32) {
+        if value & USBCMD_RESET != 0 {
+            debug!("xhci: controller reset");
+            self.reset();
+            return;
+        }
+
+        let was_running = self.running();
+        self.usbcmd = value;
+        if self.running() && !was_running {
+            self.usbsts &= !USBSTS_HCH;
+            // Report the devices connected while the controller was halted.
+            for index in 0..self.ports.len() {
+                if self.ports[index].portsc & PORTSC_CHANGE_MASK != 0 {
+                    self.port_status_change(index);
+                }
+            }
+        } else if !self.running() && was_running {
+            self.usbsts |= USBSTS_HCH;
+        }
+    }
+
+    fn write_crcr(&mut self, value: u64) {
+        // The pointer and cycle state can only change while the command ring is stopped.
+        if self.command_ring.is_some() {
+            if value & (CRCR_CS | CRCR_CA) != 0 {
+                let ring = self.command_ring.take().unwrap();
+                self.crcr = ring.dequeue | u64::from(ring.cycle);
+                let event = Trb::new(
+                    TRB_COMMAND_COMPLETION,
+                    ring.dequeue,
+                    CC_COMMAND_RING_STOPPED << 24,
+                    0,
+                );
+                self.send_event(event);
+            }
+            return;
+        }
+        self.crcr = value & !(CRCR_CS | CRCR_CA | CRCR_CRR);
+    }
+
+    fn read_port(&self, offset: u64) -> u32 {
+        let index = usize::try_from((offset - PORT_REGS_OFFSET) / PORT_REGS_SIZE).unwrap();
+        match (
+            self.ports.get(index),
+            (offset - PORT_REGS_OFFSET) % PORT_REGS_SIZE,
+        ) {
+            (Some(port), PORTSC) => port.portsc,
+            _ => 0,
+        }
+    }
+
+    fn write_port(&mut self, offset: u64, value: u32) {
+        let index = usize::try_from((offset - PORT_REGS_OFFSET) / PORT_REGS_SIZE).unwrap();
+        if index >= self.ports.len() || (offset - PORT_REGS_OFFSET) % PORT_REGS_SIZE != PORTSC {
+            return;
+        }
+
+        let port = &mut self.ports[index];
+        port.portsc &= !(value & PORTSC_CHANGE_MASK);
+        port.portsc = (port.portsc & !PORTSC_WAKE_MASK) | (value & PORTSC_WAKE_MASK);
+        // Writing 1 to Port Enabled disables the port.
+        if value & PORTSC_PED != 0 {
+            port.portsc &= !PORTSC_PED;
+        }
+
+        let warm_reset = port.usb3 && value & PORTSC_WPR != 0;
+        if value & PORTSC_PR != 0 || warm_reset {
+            self.reset_port(index, warm_reset);
+            return;
+        }
+
+        if value & PORTSC_LWS != 0 && port.portsc & PORTSC_CCS != 0 {
+            let state = (value & PORTSC_PLS_MASK) >> PORTSC_PLS_SHIFT;
+            let resuming = matches!(port.link_state(), PLS_U3 | PLS_RESUME);
+            match state {
+                PLS_U0 if resuming => {
+                    port.set_link_state(PLS_U0);
+                    port.portsc |= PORTSC_PLC;
+                    self.port_status_change(index);
+                }
+                PLS_U0 | PLS_U3 | PLS_RESUME => port.set_link_state(state),
+                _ => debug!(
+                    "xhci: unsupported link state {state} for port {}",
+                    index + 1
+                ),
+            }
+        }
+    }
+
+    fn reset_port(&mut self, index: usize, warm_reset: bool) {
+        let slot_ids: Vec<u8> = self.slots_on_port(index).collect();
+        for slot_id in slot_ids {
+            self.stop_endpoints(slot_id, |_| true);
+        }
+
+        let port = &mut self.ports[index];
+        let Some(device) = &mut port.device else {
+            return;
+        };
+        device.reset();
+        port.portsc |= PORTSC_PED | PORTSC_PRC;
+        if warm_reset {
+            port.portsc |= PORTSC_WRC;
+        }
+        port.set_link_state(PLS_U0);
+        self.port_status_change(index);
+    }
+
+    // Disconnects the device of a port, after its backend failed.
+    fn disconnect_port(&mut self, index: usize) {
+        let slot_ids: Vec<u8> = self.slots_on_port(index).collect();
+        for slot_id in slot_ids {
+            self.stop_endpoints(slot_id, |_| true);
+        }
+
+        let port = &mut self.ports[index];
+        port.device = None;
+        port.portsc = PORTSC_PP | PORTSC_CSC | (port.portsc & PORTSC_CHANGE_MASK);
+        port.set_link_state(PLS_RX_DETECT);
+        self.port_status_change(index);
+    }
+
+    fn port_status_change(&mut self, index: usize) {
+        self.usbsts |= USBSTS_PCD;
+        let port_id = u64::try_from(index + 1).unwrap();
+        self.send_event(Trb::new(
+            TRB_PORT_STATUS_CHANGE,
+            port_id << 24,
+            CC_SUCCESS << 24,
+            0,
+        ));
+    }
+
+    fn read_interrupter(&self, offset: u64) -> u32 {
+        match offset {
+            IMAN => self.iman,
+            IMOD => self.imod,
+            ERSTSZ => self.erstsz,
+            ERSTBA_LO => self.erstba as u32,
+            ERSTBA_HI => (self.erstba >> 32) as u32,
+            ERDP_LO => self.erdp as u32,
+            ERDP_HI => (self.erdp >> 32) as u32,
+            _ => 0,
+        }
+    }
+
+    fn write_interrupter(&mut self, offset: u64, value: u32) {
+        match offset {
+            IMAN => {
+                // Interrupt Pending is RW1C.
+                self.iman = (self.iman & IMAN_IP & !value) | (value & IMAN_IE);
+            }
+            IMOD => self.imod = value,
+            ERSTSZ => self.erstsz = value & 0xffff,
+            ERSTBA_LO => {
+                self.erstba = (self.erstba & !0xffff_ffff) | u64::from(value & !0x3f);
+                self.configure_event_ring();
+            }
+            ERSTBA_HI => {
+                self.erstba = (self.erstba & 0xffff_ffff) | (u64::from(value) << 32);
+                self.configure_event_ring();
+            }
+            ERDP_LO => {
+                let busy = self.erdp & ERDP_EHB != 0 && u64::from(value) & ERDP_EHB == 0;
+                self.erdp = (self.erdp & !0xffff_ffff) | u64::from(value & !0xf);
+                if busy {
+                    self.erdp |= ERDP_EHB;
+                }
+                self.event_ring.set_dequeue(self.erdp);
+                // Events queued while the guest was handling the previous ones.
+                if !busy && !self.event_ring.is_empty() {
+                    self.raise_interrupt();
+                }
+            }
+            ERDP_HI => {
+                self.erdp = (self.erdp & 0xffff_ffff) | (u64::from(value) << 32);
+                self.event_ring.set_dequeue(self.erdp);
+            }
+            _ => (),
+        }
+    }
+
+    fn configure_event_ring(&mut self) {
+        let size = self.erstsz.min(1 << ERST_MAX);
+        if let Err(err) = self.event_ring.configure(&self.mem, self.erstba, size) {
+            error!("xhci: failed to configure the event ring: {err}");
+            self.usbsts |= USBSTS_HSE;
+        }
+    }
+
+    fn send_event(&mut self, event: Trb) {
+        if !self.running() {
+            return;
+        }
+        match self.event_ring.push(&self.mem, event) {
+            Ok(()) => self.raise_interrupt(),
+            Err(err) => {
+                warn!("xhci: dropped event: {err}");
+                if !matches!(err, RingError::EventRingFull) {
+                    self.usbsts |= USBSTS_HSE;
+                }
+            }
+        }
+    }
+
+    fn raise_interrupt(&mut self) {
+        // The guest is still handling the previous events, it reads the new ones along with
+        // them.
+        if self.erdp & ERDP_EHB != 0 {
+            return;
+        }
+        self.erdp |= ERDP_EHB;
+        self.iman |= IMAN_IP;
+        self.usbsts |= USBSTS_EINT;
+        if self.iman & IMAN_IE == 0 || self.usbcmd & USBCMD_INTE == 0 {
+            return;
+        }
+
+        let mut config = self.msix_config.lock().expect("Poisoned lock");
+        if !config.enabled {
+            return;
+        }
+        if config.masked || config.table_entries[0].masked() {
+            config.set_pba_bit(0, false);
+            return;
+        }
+        if let Err(err) = config.vectors.trigger(0) {
+            error!("xhci: failed to signal the interrupt: {err}");
+        }
+    }
+
+    fn write_doorbell(&mut self, offset: u64, value: u32) {
+        let slot_id = u8::try_from((offset - DOORBELL_OFFSET) / 4).unwrap_or(u8::MAX);
+        if !self.running() {
+            return;
+        }
+        if slot_id == 0 {
+            self.process_commands();
+        } else if slot_id <= MAX_SLOTS {
+            self.kick_endpoint(slot_id, (value & 0xff) as u8);
+        }
+    }
+
+    fn process_commands(&mut self) {
+        let mut ring = self
+            .command_ring
+            .unwrap_or_else(|| RingReader::new(self.crcr & !0x3f, self.crcr & CRCR_RCS != 0));
+        loop {
+            let (addr, trb) = match ring.next(&self.mem) {
+                Ok(Some(command)) => command,
+                Ok(None) => break,
+                Err(err) => {
+                    error!("xhci: failed to read the command ring: {err}");
+                    self.usbsts |= USBSTS_HSE;
+                    break;
+                }
+            };
+            let (completion_code, slot_id) = self.command(trb);
+            self.send_event(Trb::new(
+                TRB_COMMAND_COMPLETION,
+                addr,
+                completion_code << 24,
+                u32::from(slot_id) << 24,
+            ));
+        }
+        self.command_ring = Some(ring);
+    }
+
+    // Runs a command, returning its completion code and slot.
+    fn command(&mut self, trb: Trb) -> (u32, u8) {
+        let slot_id = trb.slot_id();
+        if trb.trb_type() == TRB_ENABLE_SLOT {
+            return match self.slots.iter().position(|slot| !slot.enabled) {
+                Some(index) => {
+                    self.slots[index] = Slot {
+                        enabled: true,
+                        ..Default::default()
+                    };
+                    (CC_SUCCESS, u8::try_from(index + 1).unwrap())
+                }
+                None => (CC_NO_SLOTS_AVAILABLE, 0),
+            };
+        }
+        if trb.trb_type() == TRB_NOOP_COMMAND {
+            return (CC_SUCCESS, 0);
+        }
+        if slot_id == 0 || slot_id > MAX_SLOTS || !self.slot(slot_id).enabled {
+            return (CC_SLOT_NOT_ENABLED, slot_id);
+        }
+
+        let result = match trb.trb_type() {
+            TRB_DISABLE_SLOT => {
+                self.stop_endpoints(slot_id, |_| true);
+                *self.slot_mut(slot_id) = Slot::default();
+                Ok(CC_SUCCESS)
+            }
+            TRB_ADDRESS_DEVICE => self.address_device(slot_id, trb),
+            TRB_CONFIGURE_ENDPOINT => self.configure_endpoints(slot_id, trb),
+            TRB_EVALUATE_CONTEXT => Ok(CC_SUCCESS),
+            TRB_RESET_ENDPOINT => self.reset_endpoint(slot_id, trb.endpoint_id()),
+            TRB_STOP_ENDPOINT => self.stop_endpoint(slot_id, trb.endpoint_id()),
+            TRB_SET_TR_DEQUEUE => self.set_tr_dequeue(slot_id, trb.endpoint_id(), trb.parameter),
+            TRB_RESET_DEVICE => self.reset_device(slot_id),
+            trb_type => {
+                warn!("xhci: unsupported command {trb_type}");
+                Ok(CC_TRB_ERROR)
+            }
+        };
+        match result {
+            Ok(completion_code) => (completion_code, slot_id),
+            Err(err) => {
+                error!("xhci: failed to access the contexts of slot {slot_id}: {err}");
+                (CC_TRB_ERROR, slot_id)
+            }
+        }
+    }
+
+    fn slot(&self, slot_id: u8) -> &Slot {
+        &self.slots[usize::from(slot_id) - 1]
+    }
+
+    fn slot_mut(&mut self, slot_id: u8) -> &mut Slot {
+        &mut self.slots[usize::from(slot_id) - 1]
+    }
+
+    fn slots_on_port(&self, index: usize) -> impl Iterator<Item = u8> + '_ {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter(move |(_, slot)| slot.enabled && slot.port == Some(index))
+            .map(|(slot_index, _)| u8::try_from(slot_index + 1).unwrap())
+    }
+
+    fn device_context(&self, slot_id: u8) -> Result<u64, RingError> {
+        let addr = self.dcbaap + u64::from(slot_id) * 8;
+        Ok(self.mem.read_obj::<u64>(GuestAddress(addr))? & !0x3f)
+    }
+
+    fn read_context(&self, addr: u64) -> Result<[u32; 8], RingError> {
+        let mut context = [0u32; 8];
+        for (index, dword) in context.iter_mut().enumerate() {
+            *dword = self.mem.read_obj(GuestAddress(addr + 4 * index as u64))?;
+        }
+        Ok(context)
+    }
+
+    fn write_context(&self, addr: u64, context: &[u32; 8]) -> Result<(), RingError> {
+        for (index, dword) in context.iter().enumerate() {
+            self.mem
+                .write_obj(*dword, GuestAddress(addr + 4 * index as u64))?;
+        }
+        Ok(())
+    }
+
+    // Updates the state and dequeue pointer of an endpoint in the output device context.
+    fn write_endpoint_context(&self, slot_id: u8, dci: u8) -> Result<(), RingError> {
+        let Some(endpoint) = self.slot(slot_id).endpoints.get(&dci) else {
+            return Ok(());
+        };
+        let addr = self.device_context(slot_id)? + u64::from(dci) * CONTEXT_SIZE;
+        let mut context = self.read_context(addr)?;
+        context[0] = (context[0] & !0x7) | endpoint.state as u32;
+        let dequeue = endpoint.ring.dequeue | u64::from(endpoint.ring.cycle);
+        context[2] = dequeue as u32;
+        context[3] = (dequeue >> 32) as u32;
+        self.write_context(addr, &context)
+    }
+
+    // Loads an endpoint from its context in the input context.
+    fn load_endpoint(&mut self, slot_id: u8, dci: u8, input: u64) -> Result<(), RingError> {
+        let mut context = self.read_context(input + u64::from(dci + 1) * CONTEXT_SIZE)?;
+        let kind = match (context[1] >> EP_TYPE_SHIFT) & 0x7 {
+            EP_TYPE_CONTROL => TransferKind::Control,
+            EP_TYPE_ISOCH_OUT | EP_TYPE_ISOCH_IN => TransferKind::Isochronous,
+            EP_TYPE_BULK_OUT | EP_TYPE_BULK_IN => TransferKind::Bulk,
+            EP_TYPE_INTERRUPT_OUT | EP_TYPE_INTERRUPT_IN => TransferKind::Interrupt,
+            _ => TransferKind::Bulk,
+        };
+        let dequeue = u64::from(context[2]) | (u64::from(context[3]) << 32);
+        let ring = RingReader::new(dequeue & !0xf, dequeue & EP_DEQUEUE_CYCLE != 0);
+
+        context[0] = (context[0] & !0x7) | EndpointState::Running as u32;
+        let output = self.device_context(slot_id)? + u64::from(dci) * CONTEXT_SIZE;
+        self.write_context(output, &context)?;
+        self.slot_mut(slot_id).endpoints.insert(
+            dci,
+            Endpoint {
+                state: EndpointState::Running,
+                kind,
+                ring,
+                inflight: None,
+            },
+        );
+        Ok(())
+    }
+
+    fn address_device(&mut self, slot_id: u8, trb: Trb) -> Result<u32, RingError> {
+        let input = trb.parameter & !0xf;
+        let mut slot_context = self.read_context(input + CONTEXT_SIZE)?;
+        let port_id = (slot_context[1] >> SLOT_ROOT_HUB_PORT_SHIFT) & 0xff;
+        let index = usize::try_from(port_id).unwrap().wrapping_sub(1);
+        if self
+            .ports
+            .get(index)
+            .is_none_or(|port| port.device.is_none())
+        {
+            warn!("xhci: address device on empty port {port_id}");
+            return Ok(CC_USB_TRANSACTION_ERROR);
+        }
+
+        self.stop_endpoints(slot_id, |_| true);
+        let slot = self.slot_mut(slot_id);
+        slot.port = Some(index);
+        slot.endpoints.clear();
+
+        // The device gets the slot ID as its address, which isn't forwarded to it.
+        let (state, address) = if trb.has_flag(TRB_BLOCK_SET_ADDRESS) {
+            (SLOT_STATE_DEFAULT, 0)
+        } else {
+            (SLOT_STATE_ADDRESSED, u32::from(slot_id))
+        };
+        slot_context[3] = (state << SLOT_STATE_SHIFT) | address;
+        let output = self.device_context(slot_id)?;
+        self.write_context(output, &slot_context)?;
+        self.load_endpoint(slot_id, 1, input)?;
+        Ok(CC_SUCCESS)
+    }
+
+    fn configure_endpoints(&mut self, slot_id: u8, trb: Trb) -> Result<u32, RingError> {
+        let output = self.device_context(slot_id)?;
+        let mut slot_context = self.read_context(output)?;
+
+        if trb.has_flag(TRB_DECONFIGURE) {
+            self.stop_endpoints(slot_id, |dci| dci > 1);
+            self.slot_mut(slot_id).endpoints.retain(|dci, _| *dci == 1);
+            slot_context[3] = (slot_context[3] & !(0x1f << SLOT_STATE_SHIFT))
+                | (SLOT_STATE_ADDRESSED << SLOT_STATE_SHIFT);
+            self.write_context(output, &slot_context)?;
+            return Ok(CC_SUCCESS);
+        }
+
+        let input = trb.parameter & !0xf;
+        let control = self.read_context(input)?;
+        let (drop_flags, add_flags) = (control[0], control[1]);
+        for dci in 2..32u8 {
+            if drop_flags & (1 << dci) != 0 || add_flags & (1 << dci) != 0 {
+                self.stop_endpoints(slot_id, |index| index == dci);
+                self.slot_mut(slot_id).endpoints.remove(&dci);
+                let addr = output + u64::from(dci) * CONTEXT_SIZE;
+                let mut context = self.read_context(addr)?;
+                context[0] &= !0x7;
+                self.write_context(addr, &context)?;
+            }
+            if add_flags & (1 << dci) != 0 {
+                self.load_endpoint(slot_id, dci, input)?;
+            }
+        }
+
+        // Context Entries of the input slot context, keeping the rest of the output one.
+        let input_slot = self.read_context(input + CONTEXT_SIZE)?;
+        slot_context[0] = (slot_context[0] & !(0x1f << 27)) | (input_slot[0] & (0x1f << 27));
+        slot_context[3] = (slot_context[3] & !(0x1f << SLOT_STATE_SHIFT))
+            | (SLOT_STATE_CONFIGURED << SLOT_STATE_SHIFT);
+        self.write_context(output, &slot_context)?;
+        Ok(CC_SUCCESS)
+    }
+
+    fn reset_endpoint(&mut self, slot_id: u8, dci: u8) -> Result<u32, RingError> {
+        let Some(endpoint) = self.slot_mut(slot_id).endpoints.get_mut(&dci) else {
+            return Ok(CC_CONTEXT_STATE_ERROR);
+        };
+        if endpoint.state != EndpointState::Halted {
+            return Ok(CC_CONTEXT_STATE_ERROR);
+        }
+        endpoint.state = EndpointState::Stopped;
+        self.write_endpoint_context(slot_id, dci)?;
+        Ok(CC_SUCCESS)
+    }
+
+    fn stop_endpoint(&mut self, slot_id: u8, dci: u8) -> Result<u32, RingError> {
+        if !self.slot(slot_id).endpoints.contains_key(&dci) {
+            return Ok(CC_CONTEXT_STATE_ERROR);
+        }
+        self.stop_endpoints(slot_id, |index| index == dci);
+        let endpoint = self.slot_mut(slot_id).endpoints.get_mut(&dci).unwrap();
+        if endpoint.state == EndpointState::Running {
+            endpoint.state = EndpointState::Stopped;
+        }
+        self.write_endpoint_context(slot_id, dci)?;
+        Ok(CC_SUCCESS)
+    }
+
+    fn set_tr_dequeue(&mut self, slot_id: u8, dci: u8, dequeue: u64) -> Result<u32, RingError> {
+        let Some(endpoint) = self.slot_mut(slot_id).endpoints.get_mut(&dci) else {
+            return Ok(CC_CONTEXT_STATE_ERROR);
+        };
+        if endpoint.state == EndpointState::Running {
+            return Ok(CC_CONTEXT_STATE_ERROR);
+        }
+        endpoint.ring = RingReader::new(dequeue & !0xf, dequeue & EP_DEQUEUE_CYCLE != 0);
+        self.write_endpoint_context(slot_id, dci)?;
+        Ok(CC_SUCCESS)
+    }
+
+    fn reset_device(&mut self, slot_id: u8) -> Result<u32, RingError> {
+        self.stop_endpoints(slot_id, |_| true);
+        self.slot_mut(slot_id).endpoints.retain(|dci, _| *dci == 1);
+        let output = self.device_context(slot_id)?;
+        let mut slot_context = self.read_context(output)?;
+        slot_context[3] = SLOT_STATE_DEFAULT << SLOT_STATE_SHIFT;
+        self.write_context(output, &slot_context)?;
+        Ok(CC_SUCCESS)
+    }
+
+    // Cancels the TDs in flight on the endpoints of a slot, rewinding their rings to the start
+    // of these TDs.
+    fn stop_endpoints(&mut self, slot_id: u8, filter: impl Fn(u8) -> bool) {
+        let slot = &mut self.slots[usize::from(slot_id) - 1];
+        let mut device = slot
+            .port
+            .and_then(|index| self.ports[index].device.as_mut());
+        for (dci, endpoint) in slot.endpoints.iter_mut() {
+            if !filter(*dci) {
+                continue;
+            }
+            if let Some(td) = endpoint.inflight.take() {
+                endpoint.ring = td.start;
+                if let Some(device) = device.as_mut() {
+                    device.cancel(dci_to_address(*dci));
+                }
+            }
+        }
+    }
+
+    // Hands the next TDs of an endpoint to the device.
+    fn kick_endpoint(&mut self, slot_id: u8, dci: u8) {
+        // Ringing the doorbell of a stopped endpoint restarts it.
+        if let Some(endpoint) = self.slot_mut(slot_id).endpoints.get_mut(&dci)
+            && endpoint.state == EndpointState::Stopped
+        {
+            endpoint.state = EndpointState::Running;
+            self.update_endpoint_context(slot_id, dci);
+        }
+
+        loop {
+            let slot = &mut self.slots[usize::from(slot_id) - 1];
+            let Some(port) = slot.port else {
+                return;
+            };
+            let Some(endpoint) = slot.endpoints.get_mut(&dci) else {
+                return;
+            };
+            if endpoint.state != EndpointState::Running || endpoint.inflight.is_some() {
+                return;
+            }
+
+            let start = endpoint.ring;
+            let trbs = match fetch_td(&self.mem, &mut endpoint.ring) {
+                Ok(Some(trbs)) => trbs,
+                Ok(None) => {
+                    endpoint.ring = start;
+                    return;
+                }
+                Err(err) => {
+                    error!("xhci: failed to read the transfer ring of slot {slot_id}: {err}");
+                    endpoint.ring = start;
+                    endpoint.state = EndpointState::Halted;
+                    self.update_endpoint_context(slot_id, dci);
+                    return;
+                }
+            };
+            let transfer = match build_transfer(&self.mem, dci, endpoint.kind, &trbs) {
+                Ok(transfer) => transfer,
+                Err(err) => {
+                    error!("xhci: invalid TD on slot {slot_id}: {err}");
+                    let (addr, _) = trbs[0];
+                    endpoint.ring = start;
+                    endpoint.state = EndpointState::Halted;
+                    self.update_endpoint_context(slot_id, dci);
+                    self.send_event(transfer_event(slot_id, dci, addr, CC_TRB_ERROR, 0));
+                    return;
+                }
+            };
+            endpoint.inflight = Some(InflightTd {
+                start,
+                trbs,
+                is_in: transfer.is_in(),
+            });
+
+            let Some(device) = self.ports[port].device.as_mut() else {
+                return;
+            };
+            match device.submit(transfer) {
+                Some(completion) => self.complete_transfer(slot_id, dci, completion),
+                None => return,
+            }
+        }
+    }
+
+    // Same as write_endpoint_context, for the transfer paths which have no command to fail.
+    fn update_endpoint_context(&self, slot_id: u8, dci: u8) {
+        if let Err(err) = self.write_endpoint_context(slot_id, dci) {
+            error!("xhci: failed to write the endpoint context of slot {slot_id}: {err}");
+        }
+    }
+
+    // Handles the completion of a transfer by the device of a port.
+    fn complete_device_transfer(&mut self, index: usize, completion: UsbCompletion) {
+        let dci = address_to_dci(completion.endpoint);
+        let Some(slot_id) = self.slots_on_port(index).find(|slot_id| {
+            self.slot(*slot_id)
+                .endpoints
+                .get(&dci)
+                .is_some_and(|endpoint| endpoint.inflight.is_some())
+        }) else {
+            debug!(
+                "xhci: dropped completion on endpoint {:#x}",
+                completion.endpoint
+            );
+            return;
+        };
+        self.complete_transfer(slot_id, dci, completion);
+        self.kick_endpoint(slot_id, dci);
+    }
+
+    // Scatters the data of a completed TD and posts its transfer events.
+    fn complete_transfer(&mut self, slot_id: u8, dci: u8, completion: UsbCompletion) {
+        let slot = &mut self.slots[usize::from(slot_id) - 1];
+        let Some(endpoint) = slot.endpoints.get_mut(&dci) else {
+            return;
+        };
+        let Some(td) = endpoint.inflight.take() else {
+            return;
+        };
+
+        let mut events = Vec::new();
+        if completion.status != TransferStatus::Completed {
+            // The error is reported on the first data TRB, or on the last TRB of TDs without
+            // data, after which the endpoint halts on the TD.
+            let completion_code = match completion.status {
+                TransferStatus::Stall => CC_STALL_ERROR,
+                _ => CC_USB_TRANSACTION_ERROR,
+            };
+            let (addr, trb) = td
+                .trbs
+                .iter()
+                .find(|(_, trb)| is_data_trb(trb))
+                .unwrap_or(td.trbs.last().unwrap());
+            let residual = if is_data_trb(trb) {
+                trb.transfer_length()
+            } else {
+                0
+            };
+            events.push(transfer_event(
+                slot_id,
+                dci,
+                *addr,
+                completion_code,
+                residual,
+            ));
+            endpoint.ring = td.start;
+            endpoint.state = EndpointState::Halted;
+        } else {
+            let mut offset = 0;
+            let mut remaining = completion.actual_length;
+            // Whether the TD ended with a short packet, and whether it was reported already.
+            let mut short = false;
+            let mut short_reported = false;
+            for (addr, trb) in &td.trbs {
+                let trb_type = trb.trb_type();
+                if is_data_trb(trb) {
+                    let length = trb.transfer_length() as usize;
+                    if short {
+                        if !short_reported && trb.has_flag(TRB_INTERRUPT_ON_COMPLETION) {
+                            events.push(transfer_event(
+                                slot_id,
+                                dci,
+                                *addr,
+                                CC_SHORT_PACKET,
+                                trb.transfer_length(),
+                            ));
+                            short_reported = true;
+                        }
+                        continue;
+                    }
+                    let moved = length.min(remaining);
+                    if td.is_in
+                        && moved > 0
+                        && !trb.has_flag(TRB_IMMEDIATE_DATA)
+                        && let Some(data) = completion.data.get(offset..offset + moved)
+                        && let Err(err) = self.mem.write_slice(data, GuestAddress(trb.parameter))
+                    {
+                        error!("xhci: failed to write the data of a transfer: {err}");
+                    }
+                    offset += moved;
+                    remaining -= moved;
+                    let residual = u32::try_from(length - moved).unwrap();
+                    if residual > 0 {
+                        short = true;
+                        if trb.has_flag(TRB_INTERRUPT_ON_SHORT_PACKET)
+                            || trb.has_flag(TRB_INTERRUPT_ON_COMPLETION)
+                        {
+                            events.push(transfer_event(
+                                slot_id,
+                                dci,
+                                *addr,
+                                CC_SHORT_PACKET,
+                                residual,
+                            ));
+                            short_reported = true;
+                        }
+                    } else if trb.has_flag(TRB_INTERRUPT_ON_COMPLETION) {
+                        events.push(transfer_event(slot_id, dci, *addr, CC_SUCCESS, 0));
+                    }
+                } else if trb_type == TRB_EVENT_DATA {
+                    if trb.has_flag(TRB_INTERRUPT_ON_COMPLETION) {
+                        let completion_code = if short { CC_SHORT_PACKET } else { CC_SUCCESS };
+                        let mut event = transfer_event(
+                            slot_id,
+                            dci,
+                            trb.parameter,
+                            completion_code,
+                            u32::try_from(offset).unwrap() & 0xff_ffff,
+                        );
+                        event.control |= TRB_EVENT_DATA_FLAG;
+                        events.push(event);
+                    }
+                } else if trb.has_flag(TRB_INTERRUPT_ON_COMPLETION) {
+                    events.push(transfer_event(slot_id, dci, *addr, CC_SUCCESS, 0));
+                }
+            }
+        }
+
+        if endpoint.state == EndpointState::Halted {
+            self.update_endpoint_context(slot_id, dci);
+        }
+        for event in events {
+            self.send_event(event);
+        }
+    }
+}
+
+/// Reads the TRBs of the next TD of a transfer ring, or None if the TD is not fully queued yet.
+fn fetch_td(
+    mem: &GuestMemoryMmap,
+    ring: &mut RingReader,
+) -> Result<Option<Vec<(u64, Trb)>>, RingError> {
+    let mut trbs = Vec::new();
+    while trbs.len() < MAX_TD_TRBS {
+        let Some((addr, trb)) = ring.next(mem)? else {
+            return Ok(None);
+        };
+        trbs.push((addr, trb));
+        if !trb.has_flag(TRB_CHAIN) {
+            return Ok(Some(trbs));
+        }
+    }
+    Err(RingError::TooManyLinks)
+}
+
+/// Errors of the TDs queued by the guest.
+#[derive(Debug, thiserror::Error, displaydoc::Display)]
+enum TdError {
+    /// Control TD without a setup stage
+    MissingSetup,
+    /// Failed to read the data of the TD: {0}
+    Memory(#[from] vm_memory::GuestMemoryError),
+}
+
+fn is_data_trb(trb: &Trb) -> bool {
+    matches!(trb.trb_type(), TRB_NORMAL | TRB_DATA_STAGE | TRB_ISOCH)
+}
+
+/// Builds the transfer described by the TRBs of a TD.
+fn build_transfer(
+    mem: &GuestMemoryMmap,
+    dci: u8,
+    kind: TransferKind,
+    trbs: &[(u64, Trb)],
+) -> Result<UsbTransfer, TdError> {
+    let endpoint = dci_to_address(dci);
+    let setup = if kind == TransferKind::Control {
+        let (_, trb) = trbs
+            .iter()
+            .find(|(_, trb)| trb.trb_type() == TRB_SETUP_STAGE)
+            .ok_or(TdError::MissingSetup)?;
+        Some(SetupPacket::from_bytes(trb.parameter.to_le_bytes()))
+    } else {
+        None
+    };
+    let is_in = match setup {
+        Some(setup) => setup.is_in(),
+        None => endpoint & USB_DIR_IN != 0,
+    };
+
+    let mut data = Vec::new();
+    for (_, trb) in trbs.iter().filter(|(_, trb)| is_data_trb(trb)) {
+        let length = trb.transfer_length() as usize;
+        let start = data.len();
+        data.resize(start + length, 0);
+        if is_in {
+            continue;
+        }
+        if trb.has_flag(TRB_IMMEDIATE_DATA) {
+            let bytes = trb.parameter.to_le_bytes();
+            let length = length.min(bytes.len());
+            data[start..start + length].copy_from_slice(&bytes[..length]);
+            data.truncate(start + length);
+        } else {
+            mem.read_slice(&mut data[start..], GuestAddress(trb.parameter))?;
+        }
+    }
+
+    Ok(UsbTransfer {
+        endpoint,
+        kind,
+        setup,
+        data,
+    })
+}
+
+fn transfer_event(slot_id: u8, dci: u8, addr: u64, completion_code: u32, residual: u32) -> Trb {
+    Trb::new(
+        TRB_TRANSFER_EVENT,
+        addr,
+        (completion_code << 24) | (residual & 0xff_ffff),
+        (u32::from(slot_id) << 24) | (u32::from(dci) << 16),
+    )
+}
+
+/// Address of the endpoint with the given Device Context Index.
+fn dci_to_address(dci: u8) -> u8 {
+    match dci {
+        1 => 0,
+        dci if dci % 2 == 1 => (dci / 2) | USB_DIR_IN,
+        dci => dci / 2,
+    }
+}
+
+/// Device Context Index of the endpoint with the given address.
+fn address_to_dci(address: u8) -> u8 {
+    match address & 0xf {
+        0 => 1,
+        number => number * 2 + u8::from(address & USB_DIR_IN != 0),
+    }
+}
+
+impl PciDevice for XhciController {
+    fn write_config_register(
+        &mut self,
+        reg_idx: usize,
+        offset: u64,
+        data: &[u8],
+    ) -> Option<Arc<Barrier>> {
+        self.configuration
+            .write_config_register(reg_idx, offset, data);
+        None
+    }
+
+    fn read_config_register(&mut self, reg_idx: usize) -> u32 {
+        self.configuration.read_reg(reg_idx)
+    }
+
+    fn detect_bar_reprogramming(
+        &mut self,
+        reg_idx: usize,
+        data: &[u8],
+    ) -> Option<crate::pci::BarReprogrammingParams> {
+        self.configuration.detect_bar_reprogramming(reg_idx, data)
+    }
+
+    fn read_bar(&mut self, _base: u64, offset: u64, data: &mut [u8]) {
+        if (MSIX_TABLE_OFFSET..MSIX_TABLE_OFFSET + MSIX_TABLE_SIZE).contains(&offset) {
+            let config = self.msix_config.lock().expect("Poisoned lock");
+            config.read_table(offset - MSIX_TABLE_OFFSET, data);
+            return;
+        }
+        if (MSIX_PBA_OFFSET..MSIX_PBA_OFFSET + MSIX_PBA_SIZE).contains(&offset) {
+            let config = self.msix_config.lock().expect("Poisoned lock");
+            config.read_pba(offset - MSIX_PBA_OFFSET, data);
+            return;
+        }
+
+        // 64-bit registers are accessed as two dwords, smaller accesses pick bytes of a dword.
+        let mut bytes = [0u8; 8];
+        let aligned = offset & !0x3;
+        for (index, chunk) in bytes.chunks_mut(4).enumerate() {
+            let value = self.read_register(aligned + 4 * index as u64);
+            chunk.copy_from_slice(&value.to_le_bytes());
+        }
+        let start = usize::try_from(offset - aligned).unwrap();
+        let end = (start + data.len()).min(bytes.len());
+        data[..end - start].copy_from_slice(&bytes[start..end]);
+    }
+
+    fn write_bar(&mut self, _base: u64, offset: u64, data: &[u8]) -> Option<Arc<Barrier>> {
+        if (MSIX_TABLE_OFFSET..MSIX_TABLE_OFFSET + MSIX_TABLE_SIZE).contains(&offset) {
+            let mut config = self.msix_config.lock().expect("Poisoned lock");
+            config.write_table(offset - MSIX_TABLE_OFFSET, data);
+            return None;
+        }
+        if (MSIX_PBA_OFFSET..MSIX_PBA_OFFSET + MSIX_PBA_SIZE).contains(&offset) {
+            let mut config = self.msix_config.lock().expect("Poisoned lock");
+            config.write_pba(offset - MSIX_PBA_OFFSET, data);
+            return None;
+        }
+
+        match data.len() {
+            4 => self.write_register(offset, u32::from_le_bytes(data.try_into().unwrap())),
+            8 => {
+                self.write_register(offset, u32::from_le_bytes(data[..4].try_into().unwrap()));
+                self.write_register(
+                    offset + 4,
+                    u32::from_le_bytes(data[4..].try_into().unwrap()),
+                );
+            }
+            len => warn!("xhci: unsupported write of {len} bytes at {offset:#x}"),
+        }
+        None
+    }
+}
+
+impl XhciController {
+    fn read_register(&self, offset: u64) -> u32 {
+        match offset {
+            o if o < CAP_LENGTH => self.read_capability(o),
+            o if (PORT_REGS_OFFSET..PORT_REGS_OFFSET + u64::from(MAX_PORTS) * PORT_REGS_SIZE)
+                .contains(&o) =>
+            {
+                self.read_port(o)
+            }
+            o if (OP_REGS_OFFSET..PORT_REGS_OFFSET).contains(&o) => {
+                self.read_operational(o - OP_REGS_OFFSET)
+            }
+            // Microframe index, in units of 125 µs.
+            RUNTIME_REGS_OFFSET => {
+                let microframes = self.start_time.elapsed().as_micros() / 125;
+                (microframes & 0x3fff) as u32
+            }
+            o if (INTERRUPTER_REGS_OFFSET..INTERRUPTER_REGS_OFFSET + 0x20).contains(&o) => {
+                self.read_interrupter(o - INTERRUPTER_REGS_OFFSET)
+            }
+            o if (EXT_CAPS_OFFSET..MSIX_TABLE_OFFSET).contains(&o) => {
+                self.read_extended_capability(o - EXT_CAPS_OFFSET)
+            }
+            _ => 0,
+        }
+    }
+
+    fn write_register(&mut self, offset: u64, value: u32) {
+        match offset {
+            o if (PORT_REGS_OFFSET..PORT_REGS_OFFSET + u64::from(MAX_PORTS) * PORT_REGS_SIZE)
+                .contains(&o) =>
+            {
+                self.write_port(o, value)
+            }
+            o if (OP_REGS_OFFSET..PORT_REGS_OFFSET).contains(&o) => {
+                self.write_operational(o - OP_REGS_OFFSET, value)
+            }
+            o if (INTERRUPTER_REGS_OFFSET..INTERRUPTER_REGS_OFFSET + 0x20).contains(&o) => {
+                self.write_interrupter(o - INTERRUPTER_REGS_OFFSET, value)
+            }
+            o if (DOORBELL_OFFSET..DOORBELL_OFFSET + 4 * (u64::from(MAX_SLOTS) + 1))
+                .contains(&o) =>
+            {
+                self.write_doorbell(o, value)
+            }
+            _ => (),
+        }
+    }
+}
+
+impl BusDevice for XhciController {
+    fn read(&mut self, base: u64, offset: u64, data: &mut [u8]) {
+        self.read_bar(base, offset, data)
+    }
+
+    fn write(&mut self, base: u64, offset: u64, data: &[u8]) -> Option<Arc<Barrier>> {
+        self.write_bar(base, offset, data)
+    }
+}
+
+impl MutEventSubscriber for XhciController {
+    fn process(&mut self, events: Events, ops: &mut EventOps) {
+        let index = events.data() as usize;
+        if events
+            .event_set()
+            .intersects(EventSet::ERROR | EventSet::HANG_UP)
+        {
+            error!("xhci: the USB device of port {} failed", index + 1);
+            if let Err(err) = ops.remove(events) {
+                error!("xhci: failed to unregister the USB device: {err}");
+            }
+            self.disconnect_port(index);
+            return;
+        }
+
+        let Some(device) = self
+            .ports
+            .get_mut(index)
+            .and_then(|port| port.device.as_mut())
+        else {
+            return;
+        };
+        for completion in device.process_events() {
+            self.complete_device_transfer(index, completion);
+        }
+    }
+
+    fn init(&mut self, ops: &mut EventOps) {
+        for (index, port) in self.ports.iter().enumerate() {
+            let Some((fd, event_set)) = port.device.as_ref().and_then(|d| d.event_source()) else {
+                continue;
+            };
+            let data = u32::try_from(index).unwrap();
+            if let Err(err) = ops.add(Events::with_data(&fd, data, event_set)) {
+                error!(
+                    "xhci: failed to register the USB device of port {}: {err}",
+                    index + 1
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use std::os::fd::RawFd;
+
+    use super::*;
+    use crate::devices::usb::{USB_REQ_GET_DESCRIPTOR, UsbSpeed};
+    use crate::vstate::vm::tests::setup_vm_with_memory;
+
+    // Device answering control transfers right away, and interrupt transfers when told to.
+    #[derive(Debug, Default)]
+    struct FakeDevice {
+        pending: VecDeque<UsbTransfer>,
+        cancelled: Vec<u8>,
+    }
+
+    impl UsbDevice for FakeDevice {
+        fn speed(&self) -> UsbSpeed {
+            UsbSpeed::High
+        }
+
+        fn reset(&mut self) {
+            self.pending.clear();
+        }
+
+        fn submit(&mut self, mut transfer: UsbTransfer) -> Option<UsbCompletion> {
+            if transfer.kind != TransferKind::Control {
+                self.pending.push_back(transfer);
+                return None;
+            }
+            transfer.data.fill(0xab);
+            let length = transfer.data.len().min(4);
+            Some(transfer.complete(TransferStatus::Completed, length))
+        }
+
+        fn cancel(&mut self, endpoint: u8) {
+            self.cancelled.push(endpoint);
+            self.pending
+                .retain(|transfer| transfer.endpoint != endpoint);
+        }
+
+        fn event_source(&self) -> Option<(RawFd, EventSet)> {
+            None
+        }
+
+        fn process_events(&mut self) -> Vec<UsbCompletion> {
+            self.pending
+                .drain(..)
+                .map(|mut transfer| {
+                    transfer.data.fill(0xcd);
+                    let length = transfer.data.len();
+                    transfer.complete(TransferStatus::Completed, length)
+                })
+                .collect()
+        }
+    }
+
+    const DCBAA: u64 = 0x1000;
+    const COMMAND_RING: u64 = 0x2000;
+    const ERST: u64 = 0x3000;
+    const EVENT_RING: u64 = 0x4000;
+    const INPUT_CONTEXT: u64 = 0x5000;
+    const DEVICE_CONTEXT: u64 = 0x6000;
+    const EP0_RING: u64 = 0x7000;
+    const EP1_IN_RING: u64 = 0x8000;
+    const BUFFER: u64 = 0x9000;
+
+    fn write32(xhci: &mut XhciController, offset: u64, value: u32) {
+        xhci.write_bar(0, offset, &value.to_le_bytes());
+    }
+
+    fn read32(xhci: &mut XhciController, offset: u64) -> u32 {
+        let mut data = [0u8; 4];
+        xhci.read_bar(0, offset, &mut data);
+        u32::from_le_bytes(data)
+    }
+
+    // Runs the controller, as set up by the guest driver.
+    fn start(xhci: &mut XhciController) {
+        let mem = xhci.mem.clone();
+        mem.write_obj(EVENT_RING, GuestAddress(ERST)).unwrap();
+        mem.write_obj(16u32, GuestAddress(ERST + 8)).unwrap();
+        mem.write_obj(DEVICE_CONTEXT, GuestAddress(DCBAA + 8))
+            .unwrap();
+
+        write32(xhci, OP_REGS_OFFSET + DCBAAP_LO, DCBAA as u32);
+        write32(xhci, OP_REGS_OFFSET + CRCR_LO, COMMAND_RING as u32 | 1);
+        write32(xhci, INTERRUPTER_REGS_OFFSET + ERSTSZ, 1);
+        write32(xhci, INTERRUPTER_REGS_OFFSET + ERSTBA_LO, ERST as u32);
+        write32(xhci, INTERRUPTER_REGS_OFFSET + ERDP_LO, EVENT_RING as u32);
+        write32(xhci, OP_REGS_OFFSET + USBCMD, USBCMD_RUN | USBCMD_INTE);
+    }
+
+    fn event(xhci: &XhciController, index: u64) -> Trb {
+        Trb::read(&xhci.mem, EVENT_RING + index * TRB_SIZE).unwrap()
+    }
+
+    fn command(xhci: &mut XhciController, index: u64, trb: Trb) {
+        trb.write(&xhci.mem, COMMAND_RING + index * TRB_SIZE)
+            .unwrap();
+        write32(xhci, DOORBELL_OFFSET, 0);
+    }
+
+    fn controller() -> XhciController {
+        let (_, vm) = setup_vm_with_memory(0x10_0000);
+        let vm = Arc::new(vm);
+        let device: Box<dyn UsbDevice> = Box::new(FakeDevice::default());
+        XhciController::new(&vm, PciBdf::new(0, 0, 1, 0), vec![device]).unwrap()
+    }
+
+    #[test]
+    fn test_registers() {
+        let mut xhci = controller();
+        assert_eq!(read32(&mut xhci, 0) & 0xff, CAP_LENGTH as u32);
+        assert_eq!(read32(&mut xhci, 0x4), HCSPARAMS1);
+        assert_eq!(
+            read32(&mut xhci, OP_REGS_OFFSET + USBSTS) & USBSTS_HCH,
+            USBSTS_HCH
+        );
+        assert_eq!(xhci.configuration.read_reg(2) >> 8, 0x0c_0330);
+
+        // The device is on the first USB 2 port, waiting for a reset.
+        let portsc = read32(&mut xhci, PORT_REGS_OFFSET);
+        assert_eq!(
+            portsc & (PORTSC_CCS | PORTSC_CSC | PORTSC_PED),
+            PORTSC_CCS | PORTSC_CSC
+        );
+        assert_eq!(
+            read32(&mut xhci, PORT_REGS_OFFSET + PORT_REGS_SIZE) & PORTSC_CCS,
+            0
+        );
+
+        // Supported Protocol capabilities.
+        assert_eq!(read32(&mut xhci, EXT_CAPS_OFFSET) >> 24, 2);
+        assert_eq!(read32(&mut xhci, EXT_CAPS_OFFSET + 0x8), 0x0401);
+        assert_eq!(read32(&mut xhci, EXT_CAPS_OFFSET + 0x18), 0x0405);
+
+        start(&mut xhci);
+        assert_eq!(read32(&mut xhci, OP_REGS_OFFSET + USBSTS) & USBSTS_HCH, 0);
+        // The connection is reported once the controller runs.
+        let psc = event(&xhci, 0);
+        assert_eq!(psc.trb_type(), TRB_PORT_STATUS_CHANGE);
+        assert_eq!(psc.parameter >> 24, 1);
+
+        // Reset the port and clear the change bits.
+        write32(&mut xhci, PORT_REGS_OFFSET, PORTSC_PR | PORTSC_CSC);
+        let portsc = read32(&mut xhci, PORT_REGS_OFFSET);
+        assert_eq!(
+            portsc & (PORTSC_PED | PORTSC_PRC | PORTSC_CSC),
+            PORTSC_PED | PORTSC_PRC
+        );
+        assert_eq!(event(&xhci, 1).trb_type(), TRB_PORT_STATUS_CHANGE);
+
+        // Host controller reset.
+        write32(&mut xhci, OP_REGS_OFFSET + USBCMD, USBCMD_RESET);
+        assert_eq!(
+            read32(&mut xhci, OP_REGS_OFFSET + USBSTS) & USBSTS_HCH,
+            USBSTS_HCH
+        );
+        assert_eq!(read32(&mut xhci, PORT_REGS_OFFSET) & PORTSC_PED, 0);
+    }
+
+    #[test]
+    fn test_transfers() {
+        let mut xhci = controller();
+        start(&mut xhci);
+        let mem = xhci.mem.clone();
+
+        command(&mut xhci, 0, Trb::new(TRB_ENABLE_SLOT, 0, 0, TRB_CYCLE));
+        let completion = event(&xhci, 1);
+        assert_eq!(completion.trb_type(), TRB_COMMAND_COMPLETION);
+        assert_eq!(completion.status >> 24, CC_SUCCESS);
+        assert_eq!(completion.slot_id(), 1);
+
+        // Input context adding the slot and EP0 of the device on port 1.
+        mem.write_obj(0b11u32, GuestAddress(INPUT_CONTEXT + 4))
+            .unwrap();
+        mem.write_obj(1u32 << 16, GuestAddress(INPUT_CONTEXT + 0x20 + 4))
+            .unwrap();
+        mem.write_obj(
+            EP_TYPE_CONTROL << EP_TYPE_SHIFT,
+            GuestAddress(INPUT_CONTEXT + 0x40 + 4),
+        )
+        .unwrap();
+        mem.write_obj(EP0_RING | 1, GuestAddress(INPUT_CONTEXT + 0x40 + 8))
+            .unwrap();
+        command(
+            &mut xhci,
+            1,
+            Trb::new(TRB_ADDRESS_DEVICE, INPUT_CONTEXT, 0, TRB_CYCLE | (1 << 24)),
+        );
+        assert_eq!(event(&xhci, 2).status >> 24, CC_SUCCESS);
+        let slot_state: u32 = mem.read_obj(GuestAddress(DEVICE_CONTEXT + 12)).unwrap();
+        assert_eq!(slot_state >> SLOT_STATE_SHIFT, SLOT_STATE_ADDRESSED);
+
+        // GET_DESCRIPTOR of 18 bytes, of which the device returns 4.
+        let setup = SetupPacket {
+            request_type: USB_DIR_IN,
+            request: USB_REQ_GET_DESCRIPTOR,
+            value: 0x0100,
+            index: 0,
+            length: 18,
+        };
+        let setup = u64::from_le_bytes(setup.to_bytes());
+        Trb::new(TRB_SETUP_STAGE, setup, 8, TRB_CYCLE | TRB_IMMEDIATE_DATA)
+            .write(&mem, EP0_RING)
+            .unwrap();
+        Trb::new(
+            TRB_DATA_STAGE,
+            BUFFER,
+            18,
+            TRB_CYCLE | TRB_DATA_STAGE_IN | TRB_INTERRUPT_ON_SHORT_PACKET,
+        )
+        .write(&mem, EP0_RING + 0x10)
+        .unwrap();
+        Trb::new(
+            TRB_STATUS_STAGE,
+            0,
+            0,
+            TRB_CYCLE | TRB_INTERRUPT_ON_COMPLETION,
+        )
+        .write(&mem, EP0_RING + 0x20)
+        .unwrap();
+        write32(&mut xhci, DOORBELL_OFFSET + 4, 1);
+
+        let short = event(&xhci, 3);
+        assert_eq!(short.trb_type(), TRB_TRANSFER_EVENT);
+        assert_eq!(short.parameter, EP0_RING + 0x10);
+        assert_eq!(short.status, (CC_SHORT_PACKET << 24) | 14);
+        let status = event(&xhci, 4);
+        assert_eq!(status.parameter, EP0_RING + 0x20);
+        assert_eq!(status.status >> 24, CC_SUCCESS);
+        let data: u32 = mem.read_obj(GuestAddress(BUFFER)).unwrap();
+        assert_eq!(data, 0xabab_abab);
+
+        // Configure the interrupt IN endpoint 1, of DCI 3.
+        mem.write_obj(0u32, GuestAddress(INPUT_CONTEXT)).unwrap();
+        mem.write_obj(0b1001u32, GuestAddress(INPUT_CONTEXT + 4))
+            .unwrap();
+        mem.write_obj(
+            EP_TYPE_INTERRUPT_IN << EP_TYPE_SHIFT,
+            GuestAddress(INPUT_CONTEXT + 0x80 + 4),
+        )
+        .unwrap();
+        mem.write_obj(EP1_IN_RING | 1, GuestAddress(INPUT_CONTEXT + 0x80 + 8))
+            .unwrap();
+        command(
+            &mut xhci,
+            2,
+            Trb::new(
+                TRB_CONFIGURE_ENDPOINT,
+                INPUT_CONTEXT,
+                0,
+                TRB_CYCLE | (1 << 24),
+            ),
+        );
+        assert_eq!(event(&xhci, 5).status >> 24, CC_SUCCESS);
+
+        // The interrupt transfer completes asynchronously.
+        Trb::new(
+            TRB_NORMAL,
+            BUFFER,
+            8,
+            TRB_CYCLE | TRB_INTERRUPT_ON_COMPLETION,
+        )
+        .write(&mem, EP1_IN_RING)
+        .unwrap();
+        write32(&mut xhci, DOORBELL_OFFSET + 4, 3);
+        assert_eq!(event(&xhci, 6).trb_type(), 0);
+
+        let completions = xhci.ports[0].device.as_mut().unwrap().process_events();
+        for completion in completions {
+            xhci.complete_device_transfer(0, completion);
+        }
+        let transfer = event(&xhci, 6);
+        assert_eq!(transfer.parameter, EP1_IN_RING);
+        assert_eq!(transfer.status, CC_SUCCESS << 24);
+        assert_eq!(transfer.control >> 16, (1 << 8) | 3);
+        let data: u64 = mem.read_obj(GuestAddress(BUFFER)).unwrap();
+        assert_eq!(data, 0xcdcd_cdcd_cdcd_cdcd);
+
+        // Stopping the endpoint cancels the transfer in flight.
+        Trb::new(
+            TRB_NORMAL,
+            BUFFER,
+            8,
+            TRB_CYCLE | TRB_INTERRUPT_ON_COMPLETION,
+        )
+        .write(&mem, EP1_IN_RING + 0x10)
+        .unwrap();
+        write32(&mut xhci, DOORBELL_OFFSET + 4, 3);
+        command(
+            &mut xhci,
+            3,
+            Trb::new(TRB_STOP_ENDPOINT, 0, 0, TRB_CYCLE | (3 << 16) | (1 << 24)),
+        );
+        assert_eq!(event(&xhci, 7).status >> 24, CC_SUCCESS);
+        let dequeue: u64 = mem
+            .read_obj(GuestAddress(DEVICE_CONTEXT + 0x60 + 8))
+            .unwrap();
+        assert_eq!(dequeue, (EP1_IN_RING + 0x10) | 1);
+        let device = format!("{:?}", xhci.ports[0].device.as_ref().unwrap());
+        assert!(device.contains("cancelled: [129]"));
+    }
+
+    #[test]
+    fn test_endpoint_addresses() {
+        assert_eq!(address_to_dci(0), 1);
+        assert_eq!(address_to_dci(0x81), 3);
+        assert_eq!(address_to_dci(0x02), 4);
+        for dci in 1..32 {
+            assert_eq!(address_to_dci(dci_to_address(dci)), dci);
+        }
+    }
+}
@@ -0,0 +1,361 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Transfer Request Blocks, and the rings exchanging them with the guest driver, as described in
+//! section 4.9 of the xHCI specification.
+
+use vm_memory::GuestMemoryError;
+
+use crate::vstate::memory::{Bytes, GuestAddress, GuestMemoryMmap};
+
+/// Size of a TRB.
+pub const TRB_SIZE: u64 = 16;
+
+// TRB types, from table 6-91 of the xHCI specification.
+pub const TRB_NORMAL: u32 = 1;
+pub const TRB_SETUP_STAGE: u32 = 2;
+pub const TRB_DATA_STAGE: u32 = 3;
+pub const TRB_STATUS_STAGE: u32 = 4;
+pub const TRB_ISOCH: u32 = 5;
+pub const TRB_LINK: u32 = 6;
+pub const TRB_EVENT_DATA: u32 = 7;
+pub const TRB_NOOP: u32 = 8;
+pub const TRB_ENABLE_SLOT: u32 = 9;
+pub const TRB_DISABLE_SLOT: u32 = 10;
+pub const TRB_ADDRESS_DEVICE: u32 = 11;
+pub const TRB_CONFIGURE_ENDPOINT: u32 = 12;
+pub const TRB_EVALUATE_CONTEXT: u32 = 13;
+pub const TRB_RESET_ENDPOINT: u32 = 14;
+pub const TRB_STOP_ENDPOINT: u32 = 15;
+pub const TRB_SET_TR_DEQUEUE: u32 = 16;
+pub const TRB_RESET_DEVICE: u32 = 17;
+pub const TRB_NOOP_COMMAND: u32 = 23;
+pub const TRB_TRANSFER_EVENT: u32 = 32;
+pub const TRB_COMMAND_COMPLETION: u32 = 33;
+pub const TRB_PORT_STATUS_CHANGE: u32 = 34;
+
+// Flags of the control field.
+pub const TRB_CYCLE: u32 = 1 << 0;
+pub const TRB_LINK_TOGGLE_CYCLE: u32 = 1 << 1;
+pub const TRB_INTERRUPT_ON_SHORT_PACKET: u32 = 1 << 2;
+pub const TRB_CHAIN: u32 = 1 << 4;
+pub const TRB_INTERRUPT_ON_COMPLETION: u32 = 1 << 5;
+pub const TRB_IMMEDIATE_DATA: u32 = 1 << 6;
+pub const TRB_EVENT_DATA_FLAG: u32 = 1 << 2;
+// Block Set Address Request flag of the Address Device command, Deconfigure flag of the
+// Configure Endpoint command.
+pub const TRB_COMMAND_FLAG: u32 = 1 << 9;
+pub const TRB_DATA_STAGE_IN: u32 = 1 << 16;
+
+// A malicious guest could build a loop of link TRBs, so walking them is bounded.
+const MAX_CONSECUTIVE_LINKS: usize = 16;
+
+/// Errors related to the rings.
+#[derive(Debug, thiserror::Error, displaydoc::Display)]
+pub enum RingError {
+    /// Failed to access the ring in guest memory: {0}
+    Memory(#[from] GuestMemoryError),
+    /// Too many consecutive link TRBs
+    TooManyLinks,
+    /// The event ring is full
+    EventRingFull,
+    /// The event ring is not configured
+    EventRingNotConfigured,
+}
+
+/// Transfer Request Block.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Trb {
+    /// Parameter field, usually a pointer.
+    pub parameter: u64,
+    /// Status field, usually a length.
+    pub status: u32,
+    /// Control field, holding the type, the flags and the cycle bit.
+    pub control: u32,
+}
+
+impl Trb {
+    /// Creates a TRB of the given type, leaving the cycle bit clear.
+    pub fn new(trb_type: u32, parameter: u64, status: u32, control: u32) -> Self {
+        Trb {
+            parameter,
+            status,
+            control: (trb_type << 10) | control,
+        }
+    }
+
+    /// Reads the TRB at `addr`.
+    pub fn read(mem: &GuestMemoryMmap, addr: u64) -> Result<Self, GuestMemoryError> {
+        let mut bytes = [0u8; TRB_SIZE as usize];
+        mem.read_slice(&mut bytes, GuestAddress(addr))?;
+        Ok(Trb {
+            parameter: u64::from_le_bytes(bytes[0..8].try_into().unwrap()),
+            status: u32::from_le_bytes(bytes[8..12].try_into().unwrap()),
+            control: u32::from_le_bytes(bytes[12..16].try_into().unwrap()),
+        })
+    }
+
+    /// Writes the TRB at `addr`, the control field holding the cycle bit being written last.
+    pub fn write(&self, mem: &GuestMemoryMmap, addr: u64) -> Result<(), GuestMemoryError> {
+        let mut bytes = [0u8; 12];
+        bytes[0..8].copy_from_slice(&self.parameter.to_le_bytes());
+        bytes[8..12].copy_from_slice(&self.status.to_le_bytes());
+        mem.write_slice(&bytes, GuestAddress(addr))?;
+        mem.write_obj(self.control, GuestAddress(addr + 12))
+    }
+
+    /// Type of the TRB.
+    pub fn trb_type(&self) -> u32 {
+        (self.control >> 10) & 0x3f
+    }
+
+    /// Cycle bit of the TRB, telling whether it belongs to the producer or the consumer.
+    pub fn cycle(&self) -> bool {
+        self.control & TRB_CYCLE != 0
+    }
+
+    /// Whether the given flag of the control field is set.
+    pub fn has_flag(&self, flag: u32) -> bool {
+        self.control & flag != 0
+    }
+
+    /// Transfer length of transfer TRBs.
+    pub fn transfer_length(&self) -> u32 {
+        self.status & 0x1_ffff
+    }
+
+    /// Slot ID of commands.
+    pub fn slot_id(&self) -> u8 {
+        (self.control >> 24) as u8
+    }
+
+    /// Endpoint ID of the endpoint commands, i.e. the Device Context Index of the endpoint.
+    pub fn endpoint_id(&self) -> u8 {
+        ((self.control >> 16) & 0x1f) as u8
+    }
+}
+
+/// Consumer side of the command ring and of the transfer rings.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RingReader {
+    /// Address of the next TRB to consume.
+    pub dequeue: u64,
+    /// Consumer Cycle State.
+    pub cycle: bool,
+}
+
+impl RingReader {
+    /// Creates a reader starting at the TRB at `dequeue`.
+    pub fn new(dequeue: u64, cycle: bool) -> Self {
+        RingReader { dequeue, cycle }
+    }
+
+    /// Returns the next TRB and its address, following the link TRBs, or None if the producer
+    /// didn't queue any TRB past the dequeue pointer.
+    pub fn next(&mut self, mem: &GuestMemoryMmap) -> Result<Option<(u64, Trb)>, RingError> {
+        for _ in 0..MAX_CONSECUTIVE_LINKS {
+            let trb = Trb::read(mem, self.dequeue)?;
+            if trb.cycle() != self.cycle {
+                return Ok(None);
+            }
+            if trb.trb_type() == TRB_LINK {
+                if trb.has_flag(TRB_LINK_TOGGLE_CYCLE) {
+                    self.cycle = !self.cycle;
+                }
+                self.dequeue = trb.parameter & !0xf;
+                continue;
+            }
+            let addr = self.dequeue;
+            self.dequeue += TRB_SIZE;
+            return Ok(Some((addr, trb)));
+        }
+        Err(RingError::TooManyLinks)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct EventRingSegment {
+    base: u64,
+    size: u32,
+}
+
+/// Producer side of the event ring of an interrupter.
+#[derive(Debug, Default)]
+pub struct EventRing {
+    segments: Vec<EventRingSegment>,
+    segment: usize,
+    index: u32,
+    cycle: bool,
+    dequeue: u64,
+}
+
+impl EventRing {
+    /// Reads the Event Ring Segment Table, of `size` entries at `base`, and starts producing
+    /// events at the beginning of the first segment.
+    pub fn configure(
+        &mut self,
+        mem: &GuestMemoryMmap,
+        base: u64,
+        size: u32,
+    ) -> Result<(), RingError> {
+        let mut segments = Vec::with_capacity(size as usize);
+        for entry in 0..u64::from(size) {
+            let mut bytes = [0u8; 16];
+            mem.read_slice(&mut bytes, GuestAddress(base + entry * 16))?;
+            let segment_base = u64::from_le_bytes(bytes[0..8].try_into().unwrap()) & !0x3f;
+            let segment_size = u32::from_le_bytes(bytes[8..12].try_into().unwrap()) & 0xffff;
+            if segment_size > 0 {
+                segments.push(EventRingSegment {
+                    base: segment_base,
+                    size: segment_size,
+                });
+            }
+        }
+        self.dequeue = segments.first().map_or(0, |segment| segment.base);
+        self.segments = segments;
+        self.segment = 0;
+        self.index = 0;
+        self.cycle = true;
+        Ok(())
+    }
+
+    /// Forgets the configuration of the ring.
+    pub fn reset(&mut self) {
+        *self = EventRing::default();
+    }
+
+    /// Sets the dequeue pointer, up to which the guest consumed the events.
+    pub fn set_dequeue(&mut self, dequeue: u64) {
+        self.dequeue = dequeue & !0xf;
+    }
+
+    /// Whether the guest consumed all the events.
+    pub fn is_empty(&self) -> bool {
+        self.enqueue_addr() == Some(self.dequeue)
+    }
+
+    fn enqueue_addr(&self) -> Option<u64> {
+        let segment = self.segments.get(self.segment)?;
+        Some(segment.base + u64::from(self.index) * TRB_SIZE)
+    }
+
+    fn advance(&mut self) {
+        self.index += 1;
+        if self.index == self.segments[self.segment].size {
+            self.index = 0;
+            self.segment += 1;
+            if self.segment == self.segments.len() {
+                self.segment = 0;
+                self.cycle = !self.cycle;
+            }
+        }
+    }
+
+    /// Writes an event to the ring, setting its cycle bit.
+    pub fn push(&mut self, mem: &GuestMemoryMmap, event: Trb) -> Result<(), RingError> {
+        let addr = self
+            .enqueue_addr()
+            .ok_or(RingError::EventRingNotConfigured)?;
+
+        // One slot is always left free, to tell a full ring from an empty one.
+        let (segment, index, cycle) = (self.segment, self.index, self.cycle);
+        self.advance();
+        if self.enqueue_addr() == Some(self.dequeue) {
+            (self.segment, self.index, self.cycle) = (segment, index, cycle);
+            return Err(RingError::EventRingFull);
+        }
+
+        let mut event = event;
+        event.control = (event.control & !TRB_CYCLE) | u32::from(cycle);
+        event.write(mem, addr)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::single_region_mem;
+
+    #[test]
+    fn test_ring_reader() {
+        let mem = single_region_mem(0x10000);
+        // Two TRBs, a link back to the start toggling the cycle, owned by the consumer.
+        Trb::new(TRB_NORMAL, 0x100, 8, TRB_CYCLE)
+            .write(&mem, 0x1000)
+            .unwrap();
+        Trb::new(TRB_NORMAL, 0x200, 8, TRB_CYCLE)
+            .write(&mem, 0x1010)
+            .unwrap();
+        Trb::new(TRB_LINK, 0x1000, 0, TRB_CYCLE | TRB_LINK_TOGGLE_CYCLE)
+            .write(&mem, 0x1020)
+            .unwrap();
+
+        let mut reader = RingReader::new(0x1000, true);
+        let (addr, trb) = reader.next(&mem).unwrap().unwrap();
+        assert_eq!((addr, trb.parameter), (0x1000, 0x100));
+        let (addr, trb) = reader.next(&mem).unwrap().unwrap();
+        assert_eq!((addr, trb.parameter), (0x1010, 0x200));
+        // Past the link TRB, the TRBs still have the old cycle bit.
+        assert!(reader.next(&mem).unwrap().is_none());
+        assert_eq!(reader, RingReader::new(0x1000, false));
+
+        // The producer wraps around.
+        Trb::new(TRB_NOOP, 0, 0, 0).write(&mem, 0x1000).unwrap();
+        let (addr, trb) = reader.next(&mem).unwrap().unwrap();
+        assert_eq!((addr, trb.trb_type()), (0x1000, TRB_NOOP));
+
+        // Links pointing to themselves don't hang the consumer.
+        Trb::new(TRB_LINK, 0x2000, 0, TRB_CYCLE)
+            .write(&mem, 0x2000)
+            .unwrap();
+        assert!(matches!(
+            RingReader::new(0x2000, true).next(&mem),
+            Err(RingError::TooManyLinks)
+        ));
+    }
+
+    #[test]
+    fn test_event_ring() {
+        let mem = single_region_mem(0x10000);
+        let mut ring = EventRing::default();
+        assert!(matches!(
+            ring.push(&mem, Trb::default()),
+            Err(RingError::EventRingNotConfigured)
+        ));
+
+        // Two segments of 2 TRBs.
+        mem.write_obj(0x2000u64, GuestAddress(0x1000)).unwrap();
+        mem.write_obj(2u32, GuestAddress(0x1008)).unwrap();
+        mem.write_obj(0x3000u64, GuestAddress(0x1010)).unwrap();
+        mem.write_obj(2u32, GuestAddress(0x1018)).unwrap();
+        ring.configure(&mem, 0x1000, 2).unwrap();
+        assert!(ring.is_empty());
+
+        for i in 0..3 {
+            ring.push(&mem, Trb::new(TRB_PORT_STATUS_CHANGE, i, 0, 0))
+                .unwrap();
+        }
+        assert!(!ring.is_empty());
+        let event = Trb::read(&mem, 0x3000).unwrap();
+        assert_eq!(event.parameter, 2);
+        assert_eq!(event.trb_type(), TRB_PORT_STATUS_CHANGE);
+        assert!(event.cycle());
+
+        // The last free slot is kept empty.
+        assert!(matches!(
+            ring.push(&mem, Trb::default()),
+            Err(RingError::EventRingFull)
+        ));
+
+        // Once the guest consumed the events, the producer wraps around and toggles the cycle.
+        ring.set_dequeue(0x3010);
+        ring.push(&mem, Trb::new(TRB_PORT_STATUS_CHANGE, 3, 0, 0))
+            .unwrap();
+        ring.push(&mem, Trb::new(TRB_PORT_STATUS_CHANGE, 4, 0, 0))
+            .unwrap();
+        let event = Trb::read(&mem, 0x2000).unwrap();
+        assert_eq!(event.parameter, 4);
+        assert!(!event.cycle());
+    }
+}
@@ -8,6 +8,7 @@ use std::time::Duration;
 use log::{debug, error, info, warn};
 use serde::{Deserialize, Serialize};
 use utils::time::TimerFd;
+use vm_memory::GuestMemoryError;
 use vmm_sys_util::eventfd::EventFd;
 
 use super::super::ActivateError;
@@ -27,12 +28,13 @@ use super::{
     VIRTIO_BALLOON_S_MEMFREE, VIRTIO_BALLOON_S_MEMTOT, VIRTIO_BALLOON_S_MINFLT,
     VIRTIO_BALLOON_S_OOM_KILL, VIRTIO_BALLOON_S_SWAP_IN, VIRTIO_BALLOON_S_SWAP_OUT,
 };
+use crate::arch::host_page_size;
 use crate::devices::virtio::balloon::BalloonError;
 use crate::devices::virtio::device::{ActiveState, VirtioDeviceType};
 use crate::devices::virtio::generated::virtio_config::VIRTIO_F_VERSION_1;
 use crate::devices::virtio::queue::InvalidAvailIdx;
 use crate::devices::virtio::transport::{VirtioInterrupt, VirtioInterruptType};
-use crate::logger::{IncMetric, log_dev_preview_warning};
+use crate::logger::{IncMetric, StoreMetric, log_dev_preview_warning};
 use crate::utils::u64_to_usize;
 use crate::vstate::memory::{
     Address, ByteValued, Bytes, GuestAddress, GuestMemoryExtension, GuestMemoryMmap,
@@ -54,6 +56,13 @@ fn pages_to_mib(amount_pages: u32) -> u32 {
     amount_pages / MIB_TO_4K_PAGES
 }
 
+// Guest memory deduplicated by KSM, which the kernel reports per process.
+fn ksm_merging_bytes() -> Option<u64> {
+    let pages = std::fs::read_to_string("/proc/self/ksm_merging_pages").ok()?;
+    let pages = pages.trim().parse::<u64>().ok()?;
+    pages.checked_mul(host_page_size() as u64)
+}
+
 #[repr(C)]
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub(crate) struct ConfigSpace {
@@ -120,6 +129,35 @@ struct BalloonStat {
 // SAFETY: Safe because BalloonStat only contains plain data.
 unsafe impl ByteValued for BalloonStat {}
 
+/// How the host gives back the guest pages reported free by the hinting and reporting drivers.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FreePageAdvice {
+    /// Release the pages right away with MADV_DONTNEED.
+    #[default]
+    DontNeed,
+    /// Release the pages lazily with MADV_FREE, only when the host runs short of memory.
+    Free,
+}
+
+impl FreePageAdvice {
+    fn release(
+        self,
+        mem: &GuestMemoryMmap,
+        addr: GuestAddress,
+        len: usize,
+    ) -> Result<(), GuestMemoryError> {
+        match self {
+            FreePageAdvice::DontNeed => mem.discard_range(addr, len),
+            FreePageAdvice::Free => {
+                mem.free_range(addr, len)?;
+                METRICS.free_page_lazy_freed.add(len as u64);
+                Ok(())
+            }
+        }
+    }
+}
+
 /// Holds configuration details for the balloon device.
 #[derive(Clone, Default, Debug, PartialEq, Eq, Serialize)]
 pub struct BalloonConfig {
@@ -135,6 +173,12 @@ pub struct BalloonConfig {
     /// Free page reporting enabled
     #[serde(default)]
     pub free_page_reporting: bool,
+    /// How hinted and reported pages are given back to the host
+    #[serde(default)]
+    pub free_page_advice: FreePageAdvice,
+    /// Guest memory is advised as mergeable by KSM
+    #[serde(default)]
+    pub ksm: bool,
 }
 
 /// BalloonStats holds statistics returned from the stats_queue.
@@ -203,6 +247,10 @@ pub struct BalloonStats {
     /// Amount of memory reclaimed directly. since linux v6.12.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub direct_reclaim: Option<u64>,
+    /// Guest memory deduplicated by KSM on the host (in bytes), when KSM
+    /// is enabled. since linux v6.1 on the host.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ksm_merging_bytes: Option<u64>,
 }
 
 impl BalloonStats {
@@ -259,6 +307,10 @@ pub struct Balloon {
 
     // Holds state for free page hinting
     pub(crate) hinting_state: HintingState,
+
+    // Host side reclaim of the guest memory.
+    pub(crate) free_page_advice: FreePageAdvice,
+    pub(crate) ksm: bool,
 }
 
 impl Balloon {
@@ -322,9 +374,18 @@ impl Balloon {
             latest_stats: BalloonStats::default(),
             pfn_buffer: [0u32; MAX_PAGE_COMPACT_BUFFER],
             hinting_state: Default::default(),
+            free_page_advice: FreePageAdvice::default(),
+            ksm: false,
         })
     }
 
+    /// Sets how the host reclaims the guest memory: how the pages reported free by the guest are
+    /// given back, and whether the guest memory is advised as mergeable by KSM on activation.
+    pub fn set_host_reclaim(&mut self, free_page_advice: FreePageAdvice, ksm: bool) {
+        self.free_page_advice = free_page_advice;
+        self.ksm = ksm;
+    }
+
     pub(crate) fn process_inflate_queue_event(&mut self) -> Result<(), BalloonError> {
         self.queue_evts[INFLATE_INDEX]
             .read()
@@ -503,6 +564,12 @@ impl Balloon {
                     .map_err(|_| BalloonError::MalformedDescriptor)?;
                 self.latest_stats.update_with_stat(&stat);
             }
+            if self.ksm {
+                self.latest_stats.ksm_merging_bytes = ksm_merging_bytes();
+                if let Some(bytes) = self.latest_stats.ksm_merging_bytes {
+                    METRICS.ksm_merging_bytes.store(bytes);
+                }
+            }
 
             self.stats_desc_index = Some(head.index);
         }
@@ -520,6 +587,7 @@ impl Balloon {
         let idx = self.free_page_hinting_idx();
         let queue = &mut self.queues[idx];
         let host_cmd = self.hinting_state.host_cmd;
+        let advice = self.free_page_advice;
         let mut needs_interrupt = false;
         let mut complete = false;
 
@@ -567,7 +635,7 @@ impl Balloon {
                 }
 
                 METRICS.free_page_hint_count.inc();
-                if let Err(err) = mem.discard_range(desc.addr, desc.len as usize) {
+                if let Err(err) = advice.release(mem, desc.addr, desc.len as usize) {
                     METRICS.free_page_hint_fails.inc();
                     error!("balloon hinting: failed to remove range: {err:?}");
                 } else {
@@ -601,6 +669,7 @@ impl Balloon {
 
         let idx = self.free_page_reporting_idx();
         let queue = &mut self.queues[idx];
+        let advice = self.free_page_advice;
         let mut needs_interrupt = false;
 
         while let Some(head) = queue.pop()? {
@@ -609,7 +678,7 @@ impl Balloon {
             let mut last_desc = Some(head);
             while let Some(desc) = last_desc {
                 METRICS.free_page_report_count.inc();
-                if let Err(err) = mem.discard_range(desc.addr, desc.len as usize) {
+                if let Err(err) = advice.release(mem, desc.addr, desc.len as usize) {
                     METRICS.free_page_report_fails.inc();
                     error!("balloon: failed to remove range: {err:?}");
                 } else {
@@ -844,6 +913,8 @@ impl Balloon {
             stats_polling_interval_s: self.stats_polling_interval_s(),
             free_page_hinting: self.free_page_hinting(),
             free_page_reporting: self.free_page_reporting(),
+            free_page_advice: self.free_page_advice,
+            ksm: self.ksm,
         }
     }
 
@@ -929,6 +1000,13 @@ impl VirtioDevice for Balloon {
                 .map_err(ActivateError::QueueMemoryError)?;
         }
 
+        // The microVM still works without KSM, only with a lower memory density.
+        if self.ksm
+            && let Err(err) = mem.mark_mergeable()
+        {
+            error!("balloon: failed to advise guest memory as mergeable: {err:?}");
+        }
+
         self.device_state = DeviceState::Activated(ActiveState { mem, interrupt });
         if self.activate_evt.write(1).is_err() {
             METRICS.activate_fails.inc();
@@ -947,6 +1025,11 @@ impl VirtioDevice for Balloon {
         self.device_state.is_activated()
     }
 
+    fn reset(&mut self) -> Option<(Arc<dyn VirtioInterrupt>, Vec<EventFd>)> {
+        self.acked_features = 0;
+        self.device_state.reset(&self.queue_evts)
+    }
+
     fn kick(&mut self) {
         if self.is_activated() {
             if self.free_page_hinting() {
@@ -1054,6 +1137,7 @@ pub(crate) mod tests {
             direct_scan: None,
             async_reclaim: None,
             direct_reclaim: None,
+            ksm_merging_bytes: None,
         };
 
         let mut stat = BalloonStat {
@@ -1158,6 +1242,8 @@ pub(crate) mod tests {
             stats_polling_interval_s: 0,
             free_page_hinting: false,
             free_page_reporting: false,
+            free_page_advice: FreePageAdvice::DontNeed,
+            ksm: false,
         };
         assert_eq!(balloon.config(), cfg);
 
@@ -1592,6 +1678,32 @@ pub(crate) mod tests {
         );
     }
 
+    #[test]
+    fn test_process_reporting_lazily() {
+        let mem = create_virtio_mem();
+        let mut balloon = Balloon::new(0, true, 0, false, true).unwrap();
+        balloon.set_host_reclaim(FreePageAdvice::Free, false);
+        let mut th = VirtioTestHelper::<Balloon>::new(&mem, balloon);
+
+        th.activate_device(&mem);
+
+        let page_size = host_page_size() as u64;
+
+        // This has to be u32 for the scatter gather
+        #[allow(clippy::cast_possible_truncation)]
+        let page_size_chain = page_size as u32;
+        let reporting_idx = th.device().free_page_reporting_idx();
+
+        let safe_addr = align_up(th.data_address(), page_size);
+
+        th.add_scatter_gather(reporting_idx, 0, &[(0, safe_addr, page_size_chain, 0)]);
+        check_metric_after_block!(
+            METRICS.free_page_lazy_freed,
+            page_size,
+            invoke_handler_for_queue_event(&mut th.device(), reporting_idx)
+        );
+    }
+
     struct HintingTestHelper<'a> {
         mem: &'a GuestMemoryMmap,
         th: VirtioTestHelper<'a, Balloon>,
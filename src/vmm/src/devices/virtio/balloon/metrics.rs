@@ -29,14 +29,16 @@
 //!   clawdboxDeviceMetrics.
 //! * Rely on `serde` to provide the actual serialization for writing the metrics.
 //!
-//! The system implements 1 type of metrics:
+//! The system implements 2 types of metrics:
 //! * Shared Incremental Metrics (SharedIncMetrics) - dedicated for the metrics which need a counter
 //!   (i.e the number of times an API request failed). These metrics are reset upon flush.
+//! * Shared Store Metrics (SharedStoreMetrics) - dedicated for the metrics which need to store a
+//!   value (i.e the guest memory deduplicated by KSM). These metrics are not reset upon flush.
 
 use serde::ser::SerializeMap;
 use serde::{Serialize, Serializer};
 
-use crate::logger::SharedIncMetric;
+use crate::logger::{SharedIncMetric, SharedStoreMetric};
 
 /// Stores aggregated balloon metrics
 pub(super) static METRICS: BalloonDeviceMetrics = BalloonDeviceMetrics::new();
@@ -75,6 +77,10 @@ pub(super) struct BalloonDeviceMetrics {
     pub free_page_hint_freed: SharedIncMetric,
     /// Number of errors occurred while hinting
     pub free_page_hint_fails: SharedIncMetric,
+    /// Memory freed by the hinting and reporting drivers that the host reclaims lazily
+    pub free_page_lazy_freed: SharedIncMetric,
+    /// Guest memory deduplicated by KSM, as of the latest statistics update
+    pub ksm_merging_bytes: SharedStoreMetric,
 }
 impl BalloonDeviceMetrics {
     /// Const default construction.
@@ -92,6 +98,8 @@ impl BalloonDeviceMetrics {
             free_page_hint_count: SharedIncMetric::new(),
             free_page_hint_freed: SharedIncMetric::new(),
             free_page_hint_fails: SharedIncMetric::new(),
+            free_page_lazy_freed: SharedIncMetric::new(),
+            ksm_merging_bytes: SharedStoreMetric::new(),
         }
     }
 }
@@ -12,7 +12,7 @@ mod util;
 
 use log::error;
 
-pub use self::device::{Balloon, BalloonConfig, BalloonStats};
+pub use self::device::{Balloon, BalloonConfig, BalloonStats, FreePageAdvice};
 use super::queue::{InvalidAvailIdx, QueueError};
 use crate::devices::virtio::balloon::metrics::METRICS;
 use crate::devices::virtio::queue::clawdbox_MAX_QUEUE_SIZE;
@@ -91,6 +91,7 @@ impl BalloonStatsState {
             direct_scan: self.direct_scan,
             async_reclaim: self.async_reclaim,
             direct_reclaim: self.direct_reclaim,
+            ksm_merging_bytes: None,
         }
     }
 }
@@ -104,6 +105,8 @@ pub struct BalloonState {
     latest_stats: BalloonStatsState,
     config_space: BalloonConfigSpaceState,
     hinting_state: HintingState,
+    free_page_advice: FreePageAdvice,
+    ksm: bool,
     pub virtio_state: VirtioDeviceState,
 }
 
@@ -125,6 +128,8 @@ impl Persist<'_> for Balloon {
             stats_desc_index: self.stats_desc_index,
             latest_stats: BalloonStatsState::from_stats(&self.latest_stats),
             hinting_state: self.hinting_state,
+            free_page_advice: self.free_page_advice,
+            ksm: self.ksm,
             config_space: BalloonConfigSpaceState {
                 num_pages: self.config_space.num_pages,
                 actual_pages: self.config_space.actual_pages,
@@ -187,6 +192,15 @@ impl Persist<'_> for Balloon {
             free_page_hint_cmd_id: FREE_PAGE_HINT_DONE,
         };
         balloon.hinting_state = state.hinting_state;
+        balloon.set_host_reclaim(state.free_page_advice, state.ksm);
+
+        // The guest memory is mapped anew on restore, so it has to be advised again.
+        if state.virtio_state.activated
+            && state.ksm
+            && let Err(err) = constructor_args.mem.mark_mergeable()
+        {
+            log::error!("balloon: failed to advise guest memory as mergeable: {err:?}");
+        }
 
         if state.virtio_state.activated && balloon.stats_enabled() {
             // Restore the stats descriptor.
@@ -214,7 +228,8 @@ mod tests {
         let mut mem = vec![0; 4096];
 
         // Create and save the balloon device.
-        let balloon = Balloon::new(0x42, false, 2, false, false).unwrap();
+        let mut balloon = Balloon::new(0x42, false, 2, false, false).unwrap();
+        balloon.set_host_reclaim(FreePageAdvice::Free, true);
 
         Snapshot::new(balloon.save())
             .save(&mut mem.as_mut_slice())
@@ -255,5 +270,7 @@ mod tests {
         );
         assert_eq!(restored_balloon.stats_desc_index, balloon.stats_desc_index);
         assert_eq!(restored_balloon.latest_stats, balloon.latest_stats);
+        assert_eq!(restored_balloon.free_page_advice, FreePageAdvice::Free);
+        assert!(restored_balloon.ksm);
     }
 }
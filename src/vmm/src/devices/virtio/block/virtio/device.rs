@@ -674,6 +674,17 @@ impl VirtioDevice for VirtioBlock {
     fn is_activated(&self) -> bool {
         self.device_state.is_activated()
     }
+
+    fn reset(&mut self) -> Option<(Arc<dyn VirtioInterrupt>, Vec<EventFd>)> {
+        // Wait for in-flight requests before the guest memory is reset, and drop their
+        // completions.
+        if self.is_activated() {
+            self.drain_and_flush(true);
+        }
+        self.is_io_engine_throttled = false;
+        self.acked_features = 0;
+        self.device_state.reset(&self.queue_evts)
+    }
 }
 
 impl Drop for VirtioBlock {
@@ -34,31 +34,31 @@ pub enum AsyncIoError {
 }
 
 #[derive(Debug)]
-pub struct AsyncFileEngine {
+pub struct AsyncFileEngine<T = PendingRequest> {
     file: File,
-    ring: IoUring<WrappedRequest>,
+    ring: IoUring<WrappedRequest<T>>,
     completion_evt: EventFd,
 }
 
 #[derive(Debug)]
-pub struct WrappedRequest {
+pub struct WrappedRequest<T> {
     addr: Option<GuestAddress>,
-    req: PendingRequest,
+    req: T,
 }
 
-impl WrappedRequest {
-    fn new(req: PendingRequest) -> Self {
+impl<T> WrappedRequest<T> {
+    fn new(req: T) -> Self {
         WrappedRequest { addr: None, req }
     }
 
-    fn new_with_dirty_tracking(addr: GuestAddress, req: PendingRequest) -> Self {
+    fn new_with_dirty_tracking(addr: GuestAddress, req: T) -> Self {
         WrappedRequest {
             addr: Some(addr),
             req,
         }
     }
 
-    fn mark_dirty_mem_and_unwrap(self, mem: &GuestMemoryMmap, count: u32) -> PendingRequest {
+    fn mark_dirty_mem_and_unwrap(self, mem: &GuestMemoryMmap, count: u32) -> T {
         if let Some(addr) = self.addr {
             mem.mark_dirty(addr, count as usize)
         }
@@ -67,11 +67,11 @@ impl WrappedRequest {
     }
 }
 
-impl AsyncFileEngine {
+impl<T: Debug> AsyncFileEngine<T> {
     fn new_ring(
         file: &File,
         completion_fd: RawFd,
-    ) -> Result<IoUring<WrappedRequest>, IoUringError> {
+    ) -> Result<IoUring<WrappedRequest<T>>, IoUringError> {
         IoUring::new(
             u32::from(IO_URING_NUM_ENTRIES),
             vec![file],
@@ -87,7 +87,7 @@ impl AsyncFileEngine {
         )
     }
 
-    pub fn from_file(file: File) -> Result<AsyncFileEngine, AsyncIoError> {
+    pub fn from_file(file: File) -> Result<AsyncFileEngine<T>, AsyncIoError> {
         log_dev_preview_warning("Async file IO", Option::None);
 
         let completion_evt = EventFd::new(libc::EFD_NONBLOCK).map_err(AsyncIoError::EventFd)?;
@@ -125,8 +125,8 @@ impl AsyncFileEngine {
         mem: &GuestMemoryMmap,
         addr: GuestAddress,
         count: u32,
-        req: PendingRequest,
-    ) -> Result<(), RequestError<AsyncIoError>> {
+        req: T,
+    ) -> Result<(), RequestError<AsyncIoError, T>> {
         let buf = match mem.get_slice(addr, count as usize) {
             Ok(slice) => slice.ptr_guard_mut().as_ptr(),
             Err(err) => {
@@ -159,8 +159,8 @@ impl AsyncFileEngine {
         mem: &GuestMemoryMmap,
         addr: GuestAddress,
         count: u32,
-        req: PendingRequest,
-    ) -> Result<(), RequestError<AsyncIoError>> {
+        req: T,
+    ) -> Result<(), RequestError<AsyncIoError, T>> {
         let buf = match mem.get_slice(addr, count as usize) {
             Ok(slice) => slice.ptr_guard_mut().as_ptr(),
             Err(err) => {
@@ -187,7 +187,7 @@ impl AsyncFileEngine {
             })
     }
 
-    pub fn push_flush(&mut self, req: PendingRequest) -> Result<(), RequestError<AsyncIoError>> {
+    pub fn push_flush(&mut self, req: T) -> Result<(), RequestError<AsyncIoError, T>> {
         let wrapped_user_data = WrappedRequest::new(req);
 
         self.ring
@@ -230,14 +230,11 @@ impl AsyncFileEngine {
         Ok(())
     }
 
-    fn do_pop(&mut self) -> Result<Option<Cqe<WrappedRequest>>, AsyncIoError> {
+    fn do_pop(&mut self) -> Result<Option<Cqe<WrappedRequest<T>>>, AsyncIoError> {
         self.ring.pop().map_err(AsyncIoError::IoUring)
     }
 
-    pub fn pop(
-        &mut self,
-        mem: &GuestMemoryMmap,
-    ) -> Result<Option<Cqe<PendingRequest>>, AsyncIoError> {
+    pub fn pop(&mut self, mem: &GuestMemoryMmap) -> Result<Option<Cqe<T>>, AsyncIoError> {
         let cqe = self.do_pop()?.map(|cqe| {
             let count = cqe.count();
             cqe.map_user_data(|wrapped_user_data| {
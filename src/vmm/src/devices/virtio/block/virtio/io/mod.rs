@@ -14,15 +14,15 @@ use crate::devices::virtio::block::virtio::device::FileEngineType;
 use crate::vstate::memory::{GuestAddress, GuestMemoryMmap};
 
 #[derive(Debug)]
-pub struct RequestOk {
-    pub req: PendingRequest,
+pub struct RequestOk<T = PendingRequest> {
+    pub req: T,
     pub count: u32,
 }
 
 #[derive(Debug)]
-pub enum FileEngineOk {
+pub enum FileEngineOk<T = PendingRequest> {
     Submitted,
-    Executed(RequestOk),
+    Executed(RequestOk<T>),
 }
 
 #[derive(Debug, thiserror::Error, displaydoc::Display)]
@@ -43,21 +43,26 @@ impl BlockIoError {
 }
 
 #[derive(Debug)]
-pub struct RequestError<E> {
-    pub req: PendingRequest,
+pub struct RequestError<E, T = PendingRequest> {
+    pub req: T,
     pub error: E,
 }
 
+/// Engine doing the IO of a block device. `T` is the request data handed back along with the
+/// result of each operation.
 #[allow(clippy::large_enum_variant)]
 #[derive(Debug)]
-pub enum FileEngine {
+pub enum FileEngine<T = PendingRequest> {
     #[allow(unused)]
-    Async(AsyncFileEngine),
+    Async(AsyncFileEngine<T>),
     Sync(SyncFileEngine),
 }
 
-impl FileEngine {
-    pub fn from_file(file: File, engine_type: FileEngineType) -> Result<FileEngine, BlockIoError> {
+impl<T: Debug> FileEngine<T> {
+    pub fn from_file(
+        file: File,
+        engine_type: FileEngineType,
+    ) -> Result<FileEngine<T>, BlockIoError> {
         match engine_type {
             FileEngineType::Async => Ok(FileEngine::Async(
                 AsyncFileEngine::from_file(file).map_err(BlockIoError::Async)?,
@@ -89,8 +94,8 @@ impl FileEngine {
         mem: &GuestMemoryMmap,
         addr: GuestAddress,
         count: u32,
-        req: PendingRequest,
-    ) -> Result<FileEngineOk, RequestError<BlockIoError>> {
+        req: T,
+    ) -> Result<FileEngineOk<T>, RequestError<BlockIoError, T>> {
         match self {
             FileEngine::Async(engine) => match engine.push_read(offset, mem, addr, count, req) {
                 Ok(_) => Ok(FileEngineOk::Submitted),
@@ -115,8 +120,8 @@ impl FileEngine {
         mem: &GuestMemoryMmap,
         addr: GuestAddress,
         count: u32,
-        req: PendingRequest,
-    ) -> Result<FileEngineOk, RequestError<BlockIoError>> {
+        req: T,
+    ) -> Result<FileEngineOk<T>, RequestError<BlockIoError, T>> {
         match self {
             FileEngine::Async(engine) => match engine.push_write(offset, mem, addr, count, req) {
                 Ok(_) => Ok(FileEngineOk::Submitted),
@@ -135,10 +140,7 @@ impl FileEngine {
         }
     }
 
-    pub fn flush(
-        &mut self,
-        req: PendingRequest,
-    ) -> Result<FileEngineOk, RequestError<BlockIoError>> {
+    pub fn flush(&mut self, req: T) -> Result<FileEngineOk<T>, RequestError<BlockIoError, T>> {
         match self {
             FileEngine::Async(engine) => match engine.push_flush(req) {
                 Ok(_) => Ok(FileEngineOk::Submitted),
@@ -51,6 +51,24 @@ impl DeviceState {
             DeviceState::Inactive => None,
         }
     }
+
+    /// Deactivates the device as part of a reset, handing back its queue events and the
+    /// interrupt it was using if it was activated.
+    pub fn reset(
+        &mut self,
+        queue_events: &[EventFd],
+    ) -> Option<(Arc<dyn VirtioInterrupt>, Vec<EventFd>)> {
+        let queue_events = queue_events
+            .iter()
+            .map(EventFd::try_clone)
+            .collect::<Result<Vec<_>, _>>()
+            .inspect_err(|err| error!("Failed to clone queue events on device reset: {err}"))
+            .ok()?;
+        match std::mem::replace(self, DeviceState::Inactive) {
+            DeviceState::Activated(state) => Some((state.interrupt, queue_events)),
+            DeviceState::Inactive => None,
+        }
+    }
 }
 
 /// Type of a virtio device
@@ -13,7 +13,7 @@ use std::ops::Deref;
 use std::sync::{Arc, Mutex};
 
 use libc::{EAGAIN, iovec};
-use log::{error, info};
+use log::{error, info, warn};
 use vmm_sys_util::eventfd::EventFd;
 
 use super::NET_QUEUE_MAX_SIZE;
@@ -31,6 +31,7 @@ use crate::devices::virtio::iovec::{
 };
 use crate::devices::virtio::net::metrics::{NetDeviceMetrics, NetMetricsPerDevice};
 use crate::devices::virtio::net::tap::Tap;
+use crate::devices::virtio::net::vhost::{VhostNetError, VhostNetHandle};
 use crate::devices::virtio::net::{
     MAX_BUFFER_SIZE, NET_QUEUE_SIZES, NetError, NetQueue, RX_INDEX, TX_INDEX, generated,
 };
@@ -273,6 +274,11 @@ pub struct Net {
 
     tx_buffer: IoVecBuffer,
     pub(crate) rx_buffer: RxBuffers,
+
+    /// Whether to offload the dataplane to vhost-net when the device is activated.
+    pub(crate) vhost: bool,
+    /// The vhost-net instance processing the queues, unless the userspace dataplane is used.
+    pub(crate) vhost_handle: Option<VhostNetHandle>,
 }
 
 impl Net {
@@ -334,6 +340,8 @@ impl Net {
             metrics: NetMetricsPerDevice::alloc(id),
             tx_buffer: Default::default(),
             rx_buffer: RxBuffers::new()?,
+            vhost: false,
+            vhost_handle: None,
         })
     }
 
@@ -354,6 +362,30 @@ impl Net {
         Self::new_with_tap(id, tap, guest_mac, rx_rate_limiter, tx_rate_limiter)
     }
 
+    /// Hands the queues over to vhost-net, unless the device relies on the userspace dataplane.
+    fn activate_vhost(
+        &self,
+        mem: &GuestMemoryMmap,
+        interrupt: &dyn VirtioInterrupt,
+    ) -> Result<VhostNetHandle, VhostNetError> {
+        if self.mmds_ns.is_some() {
+            return Err(VhostNetError::Unsupported("MMDS"));
+        }
+        if self.rx_rate_limiter != RateLimiter::default()
+            || self.tx_rate_limiter != RateLimiter::default()
+        {
+            return Err(VhostNetError::Unsupported("rate limiting"));
+        }
+        VhostNetHandle::new(
+            mem,
+            self.acked_features,
+            &self.queues,
+            &self.queue_evts,
+            interrupt,
+            &self.tap,
+        )
+    }
+
     /// Provides the MAC of this net device.
     pub fn guest_mac(&self) -> Option<&MacAddr> {
         self.guest_mac.as_ref()
@@ -1083,6 +1115,16 @@ impl VirtioDevice for Net {
 
         self.rx_buffer.min_buffer_size = self.minimum_rx_buffer_size();
 
+        if self.vhost {
+            match self.activate_vhost(&mem, interrupt.as_ref()) {
+                Ok(handle) => self.vhost_handle = Some(handle),
+                Err(err) => {
+                    warn!("{}: Using the userspace dataplane: {err}", self.id());
+                    self.metrics.vhost_fallbacks.inc();
+                }
+            }
+        }
+
         if self.activate_evt.write(1).is_err() {
             self.metrics.activate_fails.inc();
             return Err(ActivateError::EventFd);
@@ -1095,6 +1137,31 @@ impl VirtioDevice for Net {
         self.device_state.is_activated()
     }
 
+    fn reset(&mut self) -> Option<(Arc<dyn VirtioInterrupt>, Vec<EventFd>)> {
+        // Drop the RX descriptors parsed from the old queue layout.
+        self.rx_buffer = RxBuffers::new()
+            .inspect_err(|err| error!("{}: Failed to reset RX buffers: {err}", self.id()))
+            .ok()?;
+        self.acked_features = 0;
+        // Closing vhost-net stops it from processing the queues.
+        self.vhost_handle = None;
+        self.device_state.reset(&self.queue_evts)
+    }
+
+    fn kick(&mut self) {
+        if !self.is_activated() {
+            return;
+        }
+        // Give the queues back to vhost-net if they were taken from it to save them.
+        if let Some(vhost_handle) = self.vhost_handle.as_mut()
+            && let Err(err) = vhost_handle.start(&self.queues, &self.tap)
+        {
+            error!("{}: Failed to restart vhost-net: {err}", self.id);
+            self.metrics.event_fails.inc();
+        }
+        self.notify_queue_events();
+    }
+
     /// Prepare saving state
     fn prepare_save(&mut self) {
         // We shouldn't be messing with the queue if the device is not activated.
@@ -1104,6 +1171,16 @@ impl VirtioDevice for Net {
             return;
         }
 
+        // vhost-net keeps processing the queues until it is stopped, which brings them up to
+        // date. It is restarted when the microVM resumes.
+        if let Some(vhost_handle) = self.vhost_handle.as_mut() {
+            if let Err(err) = vhost_handle.stop(&mut self.queues) {
+                error!("{}: Failed to stop vhost-net: {err}", self.id);
+                self.metrics.event_fails.inc();
+            }
+            return;
+        }
+
         // Give potential deferred RX frame to guest
         self.rx_buffer.finish_frame(&mut self.queues[RX_INDEX]);
         // Reset the parsed available descriptors, so we will re-parse them
@@ -2515,6 +2592,19 @@ pub mod tests {
         assert!(th.net().tx_rate_limiter.ops().is_none());
     }
 
+    #[test]
+    fn test_vhost_fallback() {
+        let mem = single_region_mem(2 * MAX_BUFFER_SIZE);
+        let mut th = TestHelper::get_default(&mem);
+        th.net().vhost = true;
+        let fallbacks = th.net().metrics.vhost_fallbacks.count();
+
+        // The default test device serves MMDS, which only the userspace dataplane handles.
+        th.activate_net();
+        assert!(th.net().vhost_handle.is_none());
+        assert_eq!(th.net().metrics.vhost_fallbacks.count(), fallbacks + 1);
+    }
+
     #[test]
     fn test_virtio_device() {
         let mem = single_region_mem(2 * MAX_BUFFER_SIZE);
@@ -18,6 +18,10 @@ impl Net {
     const PROCESS_TX_RATE_LIMITER: u32 = 5;
 
     fn register_runtime_events(&self, ops: &mut EventOps) {
+        // vhost-net handles the queue events and the tap on its own.
+        if self.vhost_handle.is_some() {
+            return;
+        }
         if let Err(err) = ops.add(Events::with_data(
             &self.queue_evts[RX_INDEX],
             Self::PROCESS_VIRTQ_RX,
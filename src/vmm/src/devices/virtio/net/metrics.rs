@@ -199,6 +199,8 @@ pub struct NetDeviceMetrics {
     pub tx_spoofed_mac_count: SharedIncMetric,
     /// Number of remaining requests in the TX queue.
     pub tx_remaining_reqs_count: SharedIncMetric,
+    /// Number of activations using the userspace dataplane as vhost-net could not be used.
+    pub vhost_fallbacks: SharedIncMetric,
 }
 
 impl NetDeviceMetrics {
@@ -260,6 +262,7 @@ impl NetDeviceMetrics {
             .add(other.tx_spoofed_mac_count.fetch_diff());
         self.tx_remaining_reqs_count
             .add(other.tx_remaining_reqs_count.fetch_diff());
+        self.vhost_fallbacks.add(other.vhost_fallbacks.fetch_diff());
     }
 }
 
@@ -23,6 +23,7 @@ pub mod metrics;
 pub mod persist;
 mod tap;
 pub mod test_utils;
+pub mod vhost;
 
 mod generated;
 
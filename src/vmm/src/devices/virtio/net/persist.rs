@@ -40,9 +40,25 @@ pub struct NetState {
     /// The associated MMDS network stack.
     pub mmds_ns: Option<MmdsNetworkStackState>,
     config_space: NetConfigSpaceState,
+    /// Whether the dataplane is offloaded to vhost-net.
+    vhost: bool,
     pub virtio_state: VirtioDeviceState,
 }
 
+impl NetState {
+    /// Replaces the MAC address exposed to the guest, if the device exposes one.
+    /// Returns whether the address was replaced.
+    pub fn replace_guest_mac(&mut self, mac: MacAddr) -> bool {
+        match self.config_space.guest_mac.as_mut() {
+            Some(guest_mac) => {
+                *guest_mac = mac;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
 /// Auxiliary structure for creating a device when resuming from a snapshot.
 #[derive(Debug)]
 pub struct NetConstructorArgs {
@@ -82,6 +98,7 @@ impl Persist<'_> for Net {
             config_space: NetConfigSpaceState {
                 guest_mac: self.guest_mac,
             },
+            vhost: self.vhost,
             virtio_state: VirtioDeviceState::from_device(self),
         }
     }
@@ -124,6 +141,7 @@ impl Persist<'_> for Net {
             NET_NUM_QUEUES,
             NET_QUEUE_MAX_SIZE,
         )?;
+        net.vhost = state.vhost;
         net.avail_features = state.virtio_state.avail_features;
         net.acked_features = state.virtio_state.acked_features;
 
@@ -215,4 +233,18 @@ mod tests {
         // data store. This will return an error.
         validate_save_and_restore(default_net(), None);
     }
+
+    #[test]
+    fn test_replace_guest_mac() {
+        let net = default_net();
+        let mut state = net.save();
+        let mac = MacAddr::from_bytes_unchecked(&[0x02, 0, 0, 0, 0, 0x01]);
+
+        assert!(state.replace_guest_mac(mac));
+        assert_eq!(state.config_space.guest_mac, Some(mac));
+
+        state.config_space.guest_mac = None;
+        assert!(!state.replace_guest_mac(mac));
+        assert_eq!(state.config_space.guest_mac, None);
+    }
 }
@@ -180,6 +180,11 @@ impl Tap {
         Ok(())
     }
 
+    /// Returns the file of the tap, to hand it over to vhost-net.
+    pub(crate) fn as_file(&self) -> &File {
+        &self.tap_file
+    }
+
     /// Write an `IoVecBuffer` to tap
     pub(crate) fn write_iovec(&mut self, buffer: &IoVecBuffer) -> Result<usize, IoError> {
         let iovcnt = i32::try_from(buffer.iovec_count()).unwrap();
@@ -0,0 +1,180 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Offload of the virtio-net dataplane to the kernel vhost-net module.
+//!
+//! The kernel then exchanges frames between the queues and the TAP device by itself: the guest
+//! kicks it through the ioeventfds of the queues, and it notifies the guest through the irqfds
+//! of the transport, without any exit to the VMM on the way.
+
+use std::num::Wrapping;
+use std::os::fd::AsRawFd;
+use std::sync::Arc;
+
+use vhost::net::VhostNet;
+use vhost::vhost_kern::net::Net as VhostKernNet;
+use vhost::{VhostBackend, VhostUserMemoryRegionInfo, VringConfigData};
+use vm_memory::{Address, GuestMemory, GuestMemoryRegion};
+use vmm_sys_util::eventfd::EventFd;
+
+use super::Tap;
+use crate::devices::virtio::generated::virtio_config::VIRTIO_F_VERSION_1;
+use crate::devices::virtio::generated::virtio_net::VIRTIO_NET_F_MRG_RXBUF;
+use crate::devices::virtio::generated::virtio_ring::VIRTIO_RING_F_EVENT_IDX;
+use crate::devices::virtio::queue::Queue;
+use crate::devices::virtio::transport::{VirtioInterrupt, VirtioInterruptType};
+use crate::vstate::memory::GuestMemoryMmap;
+
+/// Features implemented by vhost-net rather than the TAP device, which handles the offloads.
+const VHOST_NET_FEATURES: u64 =
+    (1 << VIRTIO_F_VERSION_1) | (1 << VIRTIO_NET_F_MRG_RXBUF) | (1 << VIRTIO_RING_F_EVENT_IDX);
+
+/// vhost-net error.
+#[derive(Debug, thiserror::Error, displaydoc::Display)]
+pub enum VhostNetError {
+    /// Cannot open the vhost-net device: {0}
+    Open(vhost::Error),
+    /// vhost-net does not support the features acked by the guest: {0:#x}
+    UnsupportedFeatures(u64),
+    /// The {0} of the interface is only supported by the userspace dataplane
+    Unsupported(&'static str),
+    /// The transport does not provide an interrupt notifier for queue {0}
+    NoNotifier(usize),
+    /// vhost-net request failed: {0}
+    Vhost(#[from] vhost::Error),
+}
+
+/// Handle to the vhost-net instance processing the queues of a net device.
+pub struct VhostNetHandle {
+    vhost: VhostKernNet<Arc<GuestMemoryMmap>>,
+    /// Whether the TAP is attached to the queues.
+    running: bool,
+}
+
+impl std::fmt::Debug for VhostNetHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VhostNetHandle")
+            .field("running", &self.running)
+            .finish()
+    }
+}
+
+impl VhostNetHandle {
+    /// Hands `queues` over to vhost-net, which then exchanges their frames with `tap`.
+    pub fn new(
+        mem: &GuestMemoryMmap,
+        acked_features: u64,
+        queues: &[Queue],
+        queue_evts: &[EventFd],
+        interrupt: &dyn VirtioInterrupt,
+        tap: &Tap,
+    ) -> Result<Self, VhostNetError> {
+        let vhost = VhostKernNet::new(Arc::new(mem.clone())).map_err(VhostNetError::Open)?;
+        vhost.set_owner()?;
+
+        let features = acked_features & VHOST_NET_FEATURES;
+        let unsupported = features & !vhost.get_features()?;
+        if unsupported != 0 {
+            return Err(VhostNetError::UnsupportedFeatures(unsupported));
+        }
+        vhost.set_features(features)?;
+        vhost.set_mem_table(&memory_regions(mem))?;
+
+        for (index, (queue, queue_evt)) in queues.iter().zip(queue_evts).enumerate() {
+            vhost.set_vring_num(index, queue.size)?;
+            // vhost-net translates the guest addresses of the rings itself.
+            vhost.set_vring_addr(
+                index,
+                &VringConfigData {
+                    queue_max_size: queue.max_size,
+                    queue_size: queue.size,
+                    flags: 0,
+                    desc_table_addr: queue.desc_table_address.raw_value(),
+                    used_ring_addr: queue.used_ring_address.raw_value(),
+                    avail_ring_addr: queue.avail_ring_address.raw_value(),
+                    log_addr: None,
+                },
+            )?;
+            let notifier = u16::try_from(index)
+                .ok()
+                .and_then(|index| interrupt.notifier(VirtioInterruptType::Queue(index)))
+                .ok_or(VhostNetError::NoNotifier(index))?;
+            vhost.set_vring_call(index, notifier)?;
+            vhost.set_vring_kick(index, queue_evt)?;
+        }
+
+        let mut handle = VhostNetHandle {
+            vhost,
+            running: false,
+        };
+        handle.start(queues, tap)?;
+        Ok(handle)
+    }
+
+    /// Attaches `tap` to the queues, which vhost-net then processes from where `queues` are.
+    pub fn start(&mut self, queues: &[Queue], tap: &Tap) -> Result<(), VhostNetError> {
+        if self.running {
+            return Ok(());
+        }
+        for (index, queue) in queues.iter().enumerate() {
+            self.vhost.set_vring_base(index, queue.next_avail.0)?;
+            self.vhost.set_backend(index, Some(tap.as_file()))?;
+        }
+        self.running = true;
+        Ok(())
+    }
+
+    /// Detaches the TAP from the queues, and brings `queues` to where vhost-net left them.
+    pub fn stop(&mut self, queues: &mut [Queue]) -> Result<(), VhostNetError> {
+        if !self.running {
+            return Ok(());
+        }
+        for (index, queue) in queues.iter_mut().enumerate() {
+            self.vhost.set_backend(index, None)?;
+            // The low 16 bits hold the next available index of split queues.
+            let base = self.vhost.get_vring_base(index)? & 0xffff;
+            queue.next_avail = Wrapping(u16::try_from(base).unwrap());
+            queue.next_used = Wrapping(queue.used_ring_idx_get());
+            queue.num_added = Wrapping(0);
+        }
+        self.running = false;
+        Ok(())
+    }
+}
+
+/// Describes the guest memory to vhost-net, which maps it through the VMM address space.
+fn memory_regions(mem: &GuestMemoryMmap) -> Vec<VhostUserMemoryRegionInfo> {
+    mem.iter()
+        .map(|region| {
+            let (mmap_handle, mmap_offset) = region.file_offset().map_or((-1, 0), |offset| {
+                (offset.file().as_raw_fd(), offset.start())
+            });
+            VhostUserMemoryRegionInfo {
+                guest_phys_addr: region.start_addr().raw_value(),
+                memory_size: region.len(),
+                userspace_addr: region.inner.as_ptr() as u64,
+                mmap_offset,
+                mmap_handle,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::single_region_mem;
+
+    #[test]
+    fn test_memory_regions() {
+        let mem = single_region_mem(0x10000);
+        let regions = memory_regions(&mem);
+        assert_eq!(regions.len(), 1);
+        assert_eq!(regions[0].guest_phys_addr, 0);
+        assert_eq!(regions[0].memory_size, 0x10000);
+        assert_eq!(
+            regions[0].userspace_addr,
+            mem.get_host_address(vm_memory::GuestAddress(0)).unwrap() as u64
+        );
+    }
+}
@@ -402,6 +402,11 @@ impl VirtioDevice for Pmem {
         self.device_state.is_activated()
     }
 
+    fn reset(&mut self) -> Option<(Arc<dyn VirtioInterrupt>, Vec<EventFd>)> {
+        self.acked_features = 0;
+        self.device_state.reset(&self.queue_events)
+    }
+
     fn kick(&mut self) {
         if self.is_activated() {
             info!("kick pmem {}.", self.config.id);
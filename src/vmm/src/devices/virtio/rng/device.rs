@@ -295,6 +295,11 @@ impl VirtioDevice for Entropy {
         self.device_state.is_activated()
     }
 
+    fn reset(&mut self) -> Option<(Arc<dyn VirtioInterrupt>, Vec<EventFd>)> {
+        self.acked_features = 0;
+        self.device_state.reset(&self.queue_events)
+    }
+
     fn activate(
         &mut self,
         mem: GuestMemoryMmap,
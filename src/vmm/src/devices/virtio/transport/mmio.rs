@@ -141,6 +141,19 @@ impl MmioTransport {
         }
     }
 
+    /// Resets the device and the transport as on a platform reset, leaving them waiting for a
+    /// driver to initialize them. Returns `false` if the device does not support being reset.
+    pub fn system_reset(&mut self) -> bool {
+        {
+            let mut locked_device = self.locked_device();
+            if locked_device.is_activated() && locked_device.reset().is_none() {
+                return false;
+            }
+        }
+        self.reset();
+        true
+    }
+
     fn reset(&mut self) {
         if self.locked_device().is_activated() {
             warn!("reset device while it's still in active state");
@@ -1095,6 +1108,11 @@ pub(crate) mod tests {
         d.write(0x0, 0x70, &buf[..]);
         assert_eq!(d.device_status, 0x8f);
         assert!(d.locked_device().is_activated());
+
+        // Neither does a platform reset.
+        assert!(!d.system_reset());
+        assert_eq!(d.device_status, 0x8f);
+        assert!(d.locked_device().is_activated());
     }
 
     #[test]
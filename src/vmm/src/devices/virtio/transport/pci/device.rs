@@ -596,6 +596,31 @@ impl VirtioPciDevice {
         self.device.clone()
     }
 
+    /// Resets the device and the transport as on a platform reset, leaving them waiting for a
+    /// driver to initialize them. Returns `false` if the device does not support being reset.
+    pub fn system_reset(&mut self) -> bool {
+        if self.device_activated.load(Ordering::SeqCst) && !self.reset_device() {
+            return false;
+        }
+        self.common_config.driver_status = DEVICE_INIT;
+        true
+    }
+
+    fn reset_device(&mut self) -> bool {
+        let mut device = self.device.lock().unwrap();
+        // The interrupt handed back by the device is the one we keep for the next activation.
+        if device.reset().is_none() {
+            return false;
+        }
+        self.device_activated.store(false, Ordering::SeqCst);
+
+        // Reset queue readiness (changes queue_enable), queue sizes
+        // and selected_queue as per spec for reset
+        device.queues_mut().iter_mut().for_each(Queue::reset);
+        self.common_config.queue_select = 0;
+        true
+    }
+
     fn needs_activation(&self) -> bool {
         !self.device_activated.load(Ordering::SeqCst) && self.is_driver_ready()
     }
@@ -906,32 +931,14 @@ impl PciDevice for VirtioPciDevice {
         }
 
         // Device has been reset by the driver
-        if self.device_activated.load(Ordering::SeqCst) && self.is_driver_init() {
-            let mut device = self.device.lock().unwrap();
-            let reset_result = device.reset();
-            match reset_result {
-                Some(_) => {
-                    // Upon reset the device returns its interrupt EventFD
-                    self.virtio_interrupt = None;
-                    self.device_activated.store(false, Ordering::SeqCst);
-
-                    // Reset queue readiness (changes queue_enable), queue sizes
-                    // and selected_queue as per spec for reset
-                    self.virtio_device()
-                        .lock()
-                        .unwrap()
-                        .queues_mut()
-                        .iter_mut()
-                        .for_each(Queue::reset);
-                    self.common_config.queue_select = 0;
-                }
-                None => {
-                    error!("Attempt to reset device when not implemented in underlying device");
-                    // TODO: currently we don't support device resetting, but we still
-                    // follow the spec and set the status field to 0.
-                    self.common_config.driver_status = DEVICE_INIT;
-                }
-            }
+        if self.device_activated.load(Ordering::SeqCst)
+            && self.is_driver_init()
+            && !self.reset_device()
+        {
+            error!("Attempt to reset device when not implemented in underlying device");
+            // TODO: currently we don't support device resetting, but we still
+            // follow the spec and set the status field to 0.
+            self.common_config.driver_status = DEVICE_INIT;
         }
         None
     }
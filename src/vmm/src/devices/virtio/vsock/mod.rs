@@ -84,8 +84,15 @@ mod defs {
         /// Vsock packet type.
         /// Defined in `/include/uapi/linux/virtio_vsock.h`.
         ///
-        /// Stream / connection-oriented packet (the only currently valid type).
+        /// Stream / connection-oriented packet.
         pub const VSOCK_TYPE_STREAM: u16 = 1;
+        /// Datagram / connectionless packet.
+        pub const VSOCK_TYPE_DGRAM: u16 = 3;
+
+        /// Vsock feature bits.
+        ///
+        /// The device supports datagram / connectionless packets.
+        pub const VIRTIO_VSOCK_F_DGRAM: u32 = 3;
 
         pub const VSOCK_HOST_CID: u64 = 2;
     }
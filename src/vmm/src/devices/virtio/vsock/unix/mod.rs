@@ -8,6 +8,7 @@
 /// handling vsock connection states.
 /// Check out `muxer.rs` for a more detailed explanation of the inner workings of this backend.
 mod muxer;
+mod muxer_dgram;
 mod muxer_killq;
 mod muxer_rxq;
 
@@ -24,6 +25,9 @@ mod defs {
 
     /// Size of the muxer connection kill queue.
     pub const MUXER_KILLQ_SIZE: u32 = 128;
+
+    /// Maximum number of datagram flows that we keep open.
+    pub const MAX_DGRAM_FLOWS: usize = 256;
 }
 
 /// Vsock backend related errors.
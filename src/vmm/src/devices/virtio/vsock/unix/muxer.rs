@@ -23,14 +23,20 @@
 ///       the host is ready to issue a vsock connection request, informing us of the
 ///       destination port to which it wants to connect);
 ///    3. Some event was triggered for a connected Unix socket, that belongs to a
-///       `VsockConnection`.
+///       `VsockConnection`;
+///    4. A datagram is ready to be read from a Unix datagram socket, that belongs to a
+///       `DgramFlow`.
 ///
 ///  The muxer gets notified about all of these events, because, as a `VsockEpollListener`
 ///  implementor, it gets to register a nested epoll FD into the main VMM epolling loop. All
 ///  other pollable FDs are then registered under this nested epoll FD.
 ///  To route all these events to their handlers, the muxer uses another `HashMap` object,
 ///  mapping `RawFd`s to `EpollListener`s.
-use std::collections::{HashMap, HashSet};
+///
+///  Datagrams don't go through connections: the muxer forwards them straight through the
+///  `DgramFlow` of their (host_port, guest_port) tuple, creating it on the first guest
+/// datagram.
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt::Debug;
 use std::io::Read;
 use std::os::unix::io::{AsRawFd, RawFd};
@@ -42,9 +48,11 @@ use vmm_sys_util::epoll::{ControlOperation, Epoll, EpollEvent, EventSet};
 use super::super::csm::ConnState;
 use super::super::defs::uapi;
 use super::super::{VsockBackend, VsockChannel, VsockEpollListener, VsockError};
+use super::muxer_dgram::DgramFlow;
 use super::muxer_killq::MuxerKillQ;
 use super::muxer_rxq::MuxerRxQ;
 use super::{MuxerConnection, VsockUnixBackendError, defs};
+use crate::devices::virtio::vsock::defs::MAX_PKT_BUF_SIZE;
 use crate::devices::virtio::vsock::metrics::METRICS;
 use crate::devices::virtio::vsock::packet::{VsockPacketRx, VsockPacketTx};
 use crate::logger::IncMetric;
@@ -78,6 +86,9 @@ enum EpollListener {
     /// A listener interested in reading host `connect <port>` commands from a freshly
     /// connected host socket.
     LocalStream(UnixStream),
+    /// A listener interested in datagrams sent by the host through the `DgramFlow` identified
+    /// by this key.
+    Dgram(ConnMapKey),
 }
 
 /// The vsock connection multiplexer.
@@ -108,6 +119,13 @@ pub struct VsockMuxer {
     local_port_set: HashSet<u32>,
     /// The last used host-side port.
     local_port_last: u32,
+    /// A hash map used to store the open datagram flows.
+    dgram_map: HashMap<ConnMapKey, DgramFlow>,
+    /// The datagram flows that have datagrams ready to be delivered to the guest. The epoll
+    /// listener of a flow is removed while it is in this queue, so a flow is queued at most once.
+    dgram_rxq: VecDeque<ConnMapKey>,
+    /// Sequence number of the last forwarded guest datagram.
+    dgram_seq: u64,
 }
 
 impl VsockChannel for VsockMuxer {
@@ -179,6 +197,14 @@ impl VsockChannel for VsockMuxer {
             }
         }
 
+        // Connections have nothing left to say, so we can look for pending datagrams.
+        while let Some(key) = self.dgram_rxq.pop_front() {
+            if self.recv_dgram(key, pkt) {
+                debug!("vsock muxer: RX dgram: {:?}", pkt.hdr);
+                return Ok(());
+            }
+        }
+
         Err(VsockError::NoData)
     }
 
@@ -202,6 +228,16 @@ impl VsockChannel for VsockMuxer {
             pkt.hdr
         );
 
+        // Datagrams are connectionless, so they are never answered with an RST.
+        if pkt.hdr.type_() == uapi::VSOCK_TYPE_DGRAM {
+            if pkt.hdr.dst_cid() == uapi::VSOCK_HOST_CID && pkt.hdr.op() == uapi::VSOCK_OP_RW {
+                self.send_dgram(pkt);
+            } else {
+                info!("vsock: dropping unexpected guest datagram: {:?}", pkt.hdr);
+            }
+            return Ok(());
+        }
+
         // If this packet has an unsupported type (!=stream), we must send back an RST.
         //
         if pkt.hdr.type_() != uapi::VSOCK_TYPE_STREAM {
@@ -253,7 +289,7 @@ impl VsockChannel for VsockMuxer {
     /// Check if the muxer has any pending RX data, with which to fill a guest-provided RX
     /// buffer.
     fn has_pending_rx(&self) -> bool {
-        !self.rxq.is_empty() || !self.rxq.is_synced()
+        !self.rxq.is_empty() || !self.rxq.is_synced() || !self.dgram_rxq.is_empty()
     }
 }
 
@@ -321,6 +357,9 @@ impl VsockMuxer {
             killq: MuxerKillQ::new(),
             local_port_last: (1u32 << 30) - 1,
             local_port_set: HashSet::with_capacity(defs::MAX_CONNECTIONS),
+            dgram_map: HashMap::with_capacity(defs::MAX_DGRAM_FLOWS),
+            dgram_rxq: VecDeque::with_capacity(defs::MAX_DGRAM_FLOWS),
+            dgram_seq: 0,
         };
 
         // Listen on the host initiated socket, for incoming connections.
@@ -411,6 +450,14 @@ impl VsockMuxer {
                 }
             }
 
+            // A datagram is ready to be read from a datagram flow. We'll stop listening on it
+            // until the datagram gets delivered to the guest, when there's room in the RX queue.
+            Some(EpollListener::Dgram(key)) => {
+                let key_copy = *key;
+                self.remove_listener(fd);
+                self.dgram_rxq.push_back(key_copy);
+            }
+
             _ => {
                 info!(
                     "vsock: unexpected event: fd={:?}, evset={:?}",
@@ -550,6 +597,7 @@ impl VsockMuxer {
             EpollListener::Connection { evset, .. } => evset,
             EpollListener::LocalStream(_) => EventSet::IN,
             EpollListener::HostSock => EventSet::IN,
+            EpollListener::Dgram(_) => EventSet::IN,
         };
 
         self.epoll
@@ -767,6 +815,125 @@ impl VsockMuxer {
         }
     }
 
+    /// Forward a guest datagram to the host software listening on its destination port,
+    /// creating the datagram flow if needed.
+    ///
+    /// Datagram delivery is unreliable, so failures are only logged, and the datagram dropped.
+    fn send_dgram(&mut self, pkt: &VsockPacketTx) {
+        let key = ConnMapKey {
+            local_port: pkt.hdr.dst_port(),
+            peer_port: pkt.hdr.src_port(),
+        };
+
+        if !self.dgram_map.contains_key(&key)
+            && let Err(err) = self.add_dgram_flow(key)
+        {
+            info!("vsock: unable to open datagram flow: {:?}", err);
+            METRICS.tx_write_fails.inc();
+            return;
+        }
+
+        let mut buf = vec![0u8; pkt.hdr.len() as usize];
+        if let Err(err) = pkt.write_from_offset_to(&mut buf.as_mut_slice(), 0, pkt.hdr.len()) {
+            warn!("vsock: unable to read guest datagram: {:?}", err);
+            METRICS.tx_write_fails.inc();
+            return;
+        }
+
+        self.dgram_seq += 1;
+        // It's safe to unwrap here, since the flow has just been looked up or added.
+        let flow = self.dgram_map.get_mut(&key).unwrap();
+        flow.last_used = self.dgram_seq;
+        match flow.send(&buf) {
+            Ok(_) => (),
+            Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => {
+                info!("vsock: host datagram socket full; dropping guest datagram");
+                METRICS.tx_write_fails.inc();
+            }
+            Err(err) => {
+                // The host software might have gone away, in which case the flow needs to
+                // connect again the next time the guest sends a datagram.
+                info!("vsock: unable to forward guest datagram: {:?}", err);
+                METRICS.tx_write_fails.inc();
+                self.remove_dgram_flow(key);
+            }
+        }
+    }
+
+    /// Fill in `pkt` with a datagram read from the datagram flow identified by `key`.
+    ///
+    /// Returns whether a datagram has been read.
+    fn recv_dgram(&mut self, key: ConnMapKey, pkt: &mut VsockPacketRx) -> bool {
+        let Some(flow) = self.dgram_map.get(&key) else {
+            return false;
+        };
+
+        let fd = flow.as_raw_fd();
+        let mut buf = vec![0u8; pkt.buf_size().min(MAX_PKT_BUF_SIZE) as usize];
+        let res = flow.recv(&mut buf);
+        // We're done reading from the flow, so we can listen on it again.
+        if let Err(err) = self.add_listener(fd, EpollListener::Dgram(key)) {
+            warn!("vsock: unable to listen on datagram flow: {:?}", err);
+            METRICS.muxer_event_fails.inc();
+            self.remove_dgram_flow(key);
+            return false;
+        }
+
+        let len = match res {
+            Ok(len) => u32::try_from(len).unwrap(),
+            Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => return false,
+            Err(err) => {
+                info!("vsock: unable to read host datagram: {:?}", err);
+                METRICS.rx_read_fails.inc();
+                return false;
+            }
+        };
+        if let Err(err) = pkt.read_at_offset_from(&mut &buf[..], 0, len) {
+            warn!("vsock: unable to write host datagram: {:?}", err);
+            METRICS.rx_read_fails.inc();
+            return false;
+        }
+        pkt.hdr
+            .set_op(uapi::VSOCK_OP_RW)
+            .set_src_cid(uapi::VSOCK_HOST_CID)
+            .set_dst_cid(self.cid)
+            .set_src_port(key.local_port)
+            .set_dst_port(key.peer_port)
+            .set_len(len)
+            .set_type(uapi::VSOCK_TYPE_DGRAM)
+            .set_flags(0)
+            .set_buf_alloc(0)
+            .set_fwd_cnt(0);
+        true
+    }
+
+    /// Open a new datagram flow, evicting the least recently used one if the flow limit is
+    /// reached.
+    fn add_dgram_flow(&mut self, key: ConnMapKey) -> Result<(), VsockUnixBackendError> {
+        if self.dgram_map.len() >= defs::MAX_DGRAM_FLOWS
+            && let Some(lru_key) = self
+                .dgram_map
+                .iter()
+                .min_by_key(|(_, flow)| flow.last_used)
+                .map(|(key, _)| *key)
+        {
+            self.remove_dgram_flow(lru_key);
+        }
+
+        let flow = DgramFlow::new(&self.host_sock_path, key.local_port, key.peer_port)?;
+        self.add_listener(flow.as_raw_fd(), EpollListener::Dgram(key))?;
+        self.dgram_map.insert(key, flow);
+        Ok(())
+    }
+
+    /// Close a datagram flow.
+    fn remove_dgram_flow(&mut self, key: ConnMapKey) {
+        if let Some(flow) = self.dgram_map.remove(&key) {
+            self.remove_listener(flow.as_raw_fd());
+            self.dgram_rxq.retain(|queued| *queued != key);
+        }
+    }
+
     /// Enqueue an RST packet into `self.rxq`.
     ///
     /// Enqueue errors aren't propagated up the call chain, since there is nothing we can do to
@@ -790,7 +957,7 @@ impl VsockMuxer {
 mod tests {
     use std::io::{Read, Write};
     use std::ops::Drop;
-    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::os::unix::net::{UnixDatagram, UnixListener, UnixStream};
     use std::path::{Path, PathBuf};
 
     use vmm_sys_util::tempfile::TempFile;
@@ -1027,15 +1194,15 @@ mod tests {
     fn test_bad_peer_pkt() {
         const LOCAL_PORT: u32 = 1026;
         const PEER_PORT: u32 = 1025;
-        const SOCK_DGRAM: u16 = 2;
+        const SOCK_SEQPACKET: u16 = 2;
 
         let mut ctx = MuxerTestContext::new("bad_peer_pkt");
         let tx_pkt = ctx.init_tx_pkt(LOCAL_PORT, PEER_PORT, uapi::VSOCK_OP_REQUEST);
-        tx_pkt.hdr.set_type(SOCK_DGRAM);
+        tx_pkt.hdr.set_type(SOCK_SEQPACKET);
         ctx.send();
 
-        // The guest sent a SOCK_DGRAM packet. Per the vsock spec, we need to reply with an RST
-        // packet, since vsock only supports stream sockets.
+        // The guest sent a SOCK_SEQPACKET packet. Per the vsock spec, we need to reply with an RST
+        // packet, since the muxer doesn't support seqpacket sockets.
         assert!(ctx.muxer.has_pending_rx());
         ctx.recv();
         assert_eq!(ctx.rx_pkt.hdr.op(), uapi::VSOCK_OP_RST);
@@ -1138,6 +1305,87 @@ mod tests {
         assert!(!ctx.muxer.has_pending_rx());
     }
 
+    #[test]
+    fn test_peer_datagrams() {
+        const LOCAL_PORT: u32 = 1026;
+        const PEER_PORT: u32 = 1025;
+
+        let mut ctx = MuxerTestContext::new("peer_datagrams");
+        let key = ConnMapKey {
+            local_port: LOCAL_PORT,
+            peer_port: PEER_PORT,
+        };
+
+        // Datagrams are dropped, without an RST, when nobody listens on the host port.
+        let data = [1u8, 2, 3, 4];
+        ctx.init_data_tx_pkt(LOCAL_PORT, PEER_PORT, &data)
+            .hdr
+            .set_type(uapi::VSOCK_TYPE_DGRAM);
+        ctx.send();
+        assert!(ctx.muxer.dgram_map.is_empty());
+        assert!(!ctx.muxer.has_pending_rx());
+
+        // Test guest -> host datagram flow.
+        let host_path = format!("{}_dgram_{}", ctx.muxer.host_sock_path, LOCAL_PORT);
+        let host = UnixDatagram::bind(&host_path).unwrap();
+        ctx.send();
+        assert!(ctx.muxer.dgram_map.contains_key(&key));
+        let mut buf = [0u8; 16];
+        let (len, addr) = host.recv_from(&mut buf).unwrap();
+        assert_eq!(&buf[..len], data);
+        let flow_path = addr.as_pathname().unwrap().to_path_buf();
+
+        // Test host -> guest datagram flow, through the address of the flow.
+        let data = [5u8, 6, 7, 8];
+        host.send_to(&data, &flow_path).unwrap();
+        ctx.notify_muxer();
+        assert_eq!(ctx.muxer.dgram_rxq, [key]);
+        assert!(ctx.muxer.has_pending_rx());
+        ctx.recv();
+        assert_eq!(ctx.rx_pkt.hdr.op(), uapi::VSOCK_OP_RW);
+        assert_eq!(ctx.rx_pkt.hdr.type_(), uapi::VSOCK_TYPE_DGRAM);
+        assert_eq!(ctx.rx_pkt.hdr.len(), 4);
+        assert_eq!(ctx.rx_pkt.hdr.src_cid(), uapi::VSOCK_HOST_CID);
+        assert_eq!(ctx.rx_pkt.hdr.dst_cid(), PEER_CID);
+        assert_eq!(ctx.rx_pkt.hdr.src_port(), LOCAL_PORT);
+        assert_eq!(ctx.rx_pkt.hdr.dst_port(), PEER_PORT);
+        let buf = test_utils::read_packet_data(&ctx.tx_pkt, 4);
+        assert_eq!(&buf, &data);
+        assert!(!ctx.muxer.has_pending_rx());
+
+        // Once the host software goes away, the flow is closed.
+        drop(host);
+        std::fs::remove_file(&host_path).unwrap();
+        ctx.init_data_tx_pkt(LOCAL_PORT, PEER_PORT, &data)
+            .hdr
+            .set_type(uapi::VSOCK_TYPE_DGRAM);
+        ctx.send();
+        assert!(ctx.muxer.dgram_map.is_empty());
+        assert!(!flow_path.exists());
+    }
+
+    #[test]
+    fn test_dgram_flow_limit() {
+        let mut ctx = MuxerTestContext::new("dgram_flow_limit");
+        let host_path = format!("{}_dgram_{}", ctx.muxer.host_sock_path, 52);
+        let _host = UnixDatagram::bind(&host_path).unwrap();
+
+        for peer_port in 0..=u32::try_from(defs::MAX_DGRAM_FLOWS).unwrap() {
+            ctx.init_data_tx_pkt(52, peer_port, &[0])
+                .hdr
+                .set_type(uapi::VSOCK_TYPE_DGRAM);
+            ctx.send();
+        }
+
+        // The least recently used flow got evicted to make room for the last one.
+        assert_eq!(ctx.muxer.dgram_map.len(), defs::MAX_DGRAM_FLOWS);
+        assert!(!ctx.muxer.dgram_map.contains_key(&ConnMapKey {
+            local_port: 52,
+            peer_port: 0,
+        }));
+        std::fs::remove_file(&host_path).unwrap();
+    }
+
     #[test]
     fn test_local_connection() {
         // Test guest -> host data flow.
@@ -0,0 +1,130 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+//
+
+/// `DgramFlow` implements the host end of the datagrams exchanged between a guest-side vsock
+/// port and a host-side vsock port.
+///
+/// Since datagrams are connectionless, there is no handshake the muxer could use to set up a
+/// flow. Instead, a flow is created the first time the guest sends a datagram from a given
+/// port to a given host port. The flow owns a Unix datagram socket, bound at
+/// `"<uds_path>_dgram_<host port>.<guest port>"` and connected to the host software expected
+/// to be bound at `"<uds_path>_dgram_<host port>"`. This way:
+/// - the guest datagrams are forwarded to the host software, whose reads yield the address of
+///   the flow socket as the source address; and
+/// - the host software can answer by sending datagrams to that address, which the muxer then
+///   forwards to the guest port.
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::os::unix::net::UnixDatagram;
+
+use log::warn;
+
+use super::VsockUnixBackendError;
+
+/// A datagram flow, identified by its (host port, guest port) tuple.
+#[derive(Debug)]
+pub struct DgramFlow {
+    /// The socket through which datagrams are exchanged with the host software.
+    sock: UnixDatagram,
+    /// The file system path the socket is bound at.
+    path: String,
+    /// The value of the muxer datagram sequence number when this flow was last used, so that
+    /// the least recently used flow can be evicted when the flow limit is reached.
+    pub last_used: u64,
+}
+
+impl DgramFlow {
+    /// Create the flow between `local_port` (on the host side) and `peer_port` (on the guest
+    /// side).
+    pub fn new(
+        host_sock_path: &str,
+        local_port: u32,
+        peer_port: u32,
+    ) -> Result<Self, VsockUnixBackendError> {
+        let path = format!("{}_dgram_{}.{}", host_sock_path, local_port, peer_port);
+
+        // The socket file might be left over by a previous instance that didn't get the chance to
+        // clean up after itself.
+        let _ = std::fs::remove_file(&path);
+        let sock = UnixDatagram::bind(&path).map_err(VsockUnixBackendError::UnixBind)?;
+        // From now on, dropping the flow removes the socket file.
+        let flow = Self {
+            sock,
+            path,
+            last_used: 0,
+        };
+
+        flow.sock
+            .connect(format!("{}_dgram_{}", host_sock_path, local_port))
+            .and_then(|_| flow.sock.set_nonblocking(true))
+            .map_err(VsockUnixBackendError::UnixConnect)?;
+        Ok(flow)
+    }
+
+    /// Forward a guest datagram to the host software.
+    pub fn send(&self, buf: &[u8]) -> std::io::Result<usize> {
+        self.sock.send(buf)
+    }
+
+    /// Read a datagram sent by the host software. Datagrams larger than `buf` are truncated.
+    pub fn recv(&self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.sock.recv(buf)
+    }
+}
+
+impl AsRawFd for DgramFlow {
+    fn as_raw_fd(&self) -> RawFd {
+        self.sock.as_raw_fd()
+    }
+}
+
+impl Drop for DgramFlow {
+    fn drop(&mut self) {
+        std::fs::remove_file(&self.path).unwrap_or_else(|err| {
+            warn!(
+                "vsock: unable to remove datagram socket {}: {:?}",
+                self.path, err
+            );
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use vmm_sys_util::tempdir::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn test_dgram_flow() {
+        let dir = TempDir::new().unwrap();
+        let host_sock_path = format!("{}/vsock", dir.as_path().to_str().unwrap());
+
+        // Nobody is listening on the host port yet.
+        DgramFlow::new(&host_sock_path, 52, 1024).unwrap_err();
+        let flow_path = format!("{}_dgram_52.1024", host_sock_path);
+        assert!(!Path::new(&flow_path).exists());
+
+        let host = UnixDatagram::bind(format!("{}_dgram_52", host_sock_path)).unwrap();
+        let flow = DgramFlow::new(&host_sock_path, 52, 1024).unwrap();
+        assert_eq!(flow.send(b"ping").unwrap(), 4);
+        let mut buf = [0u8; 16];
+        let (len, addr) = host.recv_from(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"ping");
+        assert_eq!(addr.as_pathname().unwrap(), Path::new(&flow_path));
+
+        // Answers are truncated to the size of the buffer.
+        host.send_to(b"pong", &flow_path).unwrap();
+        assert_eq!(flow.recv(&mut buf[..2]).unwrap(), 2);
+        assert_eq!(&buf[..2], b"po");
+        assert_eq!(
+            flow.recv(&mut buf).unwrap_err().kind(),
+            std::io::ErrorKind::WouldBlock
+        );
+
+        drop(flow);
+        assert!(!Path::new(&flow_path).exists());
+    }
+}
@@ -0,0 +1,396 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! The guest agent is a daemon, running inside the guest, that listens on vsock port
+//! [`GUEST_AGENT_PORT`]. The host reaches it through the Unix socket backing the vsock device,
+//! by issuing a `CONNECT <port>\n` request, and then exchanges newline-delimited JSON messages
+//! with it, one request and one response per connection:
+//! - the request is an object of the form `{"execute": <command>, "arguments": <object>}`;
+//! - the response is either `{"return": <object>}`, or `{"error": {"desc": <string>}}`.
+//!
+//! The agent implements the following commands:
+//! - `guest-info`, with empty arguments, returns a [`GuestInfo`] object;
+//! - `guest-exec`, with a [`GuestExecConfig`] object as arguments, runs a command until it exits or
+//!   its timeout elapses, and returns a [`GuestExecOutput`] object. Binary data, i.e. the input and
+//!   the outputs of the command, is base64-encoded.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::os::unix::net::UnixStream;
+use std::time::Duration;
+
+use base64::Engine;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// The vsock port the guest agent listens on.
+pub const GUEST_AGENT_PORT: u32 = 1024;
+/// Timeout of the exchanges with the guest agent, on top of the timeout of the commands.
+pub const GUEST_AGENT_TIMEOUT: Duration = Duration::from_secs(5);
+/// Maximum timeout of a command run by the guest agent, in seconds. API requests are served
+/// one at a time, so a command blocks the API until it exits.
+pub const MAX_EXEC_TIMEOUT_S: u64 = 60;
+/// Maximum size of a guest agent response.
+pub const MAX_RESPONSE_SIZE: u64 = 4 << 20;
+
+/// Errors associated with the guest agent.
+#[derive(Debug, thiserror::Error, displaydoc::Display)]
+pub enum GuestAgentError {
+    /// The microVM has no vsock device to reach the guest agent through.
+    NoVsockDevice,
+    /// The microVM is not running.
+    NotRunning,
+    /// Unable to connect to the vsock Unix socket: {0}
+    Connect(std::io::Error),
+    /// The guest agent is not listening on vsock port {0}.
+    Unreachable(u32),
+    /// Unable to communicate with the guest agent: {0}
+    Io(std::io::Error),
+    /// The guest agent response exceeds {0} bytes.
+    ResponseTooLarge(u64),
+    /// Invalid guest agent response: {0}
+    InvalidResponse(serde_json::Error),
+    /// The guest agent failed to execute the request: {0}
+    Agent(String),
+    /// Invalid command timeout {0}: it must be between 1 and 60 seconds.
+    InvalidTimeout(u64),
+    /// Invalid command input data: {0}
+    InvalidInputData(base64::DecodeError),
+}
+
+fn default_exec_timeout() -> u64 {
+    10
+}
+
+/// Command to be run by the guest agent.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct GuestExecConfig {
+    /// Path of the executable, in the guest.
+    pub path: String,
+    /// Arguments of the command.
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Environment variables of the command, as `NAME=value` strings, on top of the environment
+    /// of the guest agent.
+    #[serde(default)]
+    pub env: Vec<String>,
+    /// Base64-encoded data written to the standard input of the command.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_data: Option<String>,
+    /// Number of seconds after which the command gets killed.
+    #[serde(default = "default_exec_timeout")]
+    pub timeout_s: u64,
+}
+
+impl GuestExecConfig {
+    /// Checks that the command can be sent to the guest agent.
+    pub fn validate(&self) -> Result<(), GuestAgentError> {
+        if self.timeout_s == 0 || self.timeout_s > MAX_EXEC_TIMEOUT_S {
+            return Err(GuestAgentError::InvalidTimeout(self.timeout_s));
+        }
+        if let Some(input_data) = &self.input_data {
+            base64::engine::general_purpose::STANDARD
+                .decode(input_data)
+                .map_err(GuestAgentError::InvalidInputData)?;
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of a command run by the guest agent.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct GuestExecOutput {
+    /// Exit code of the command, if it exited.
+    pub exit_code: Option<i32>,
+    /// Signal that terminated the command, if any.
+    pub signal: Option<i32>,
+    /// Whether the command got killed because its timeout elapsed.
+    #[serde(default)]
+    pub timed_out: bool,
+    /// Base64-encoded standard output of the command.
+    #[serde(default)]
+    pub out_data: String,
+    /// Base64-encoded standard error of the command.
+    #[serde(default)]
+    pub err_data: String,
+}
+
+/// Health information reported by the guest agent.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct GuestInfo {
+    /// Version of the guest agent.
+    pub agent_version: String,
+    /// Host name of the guest.
+    pub hostname: String,
+    /// Release of the guest kernel.
+    pub kernel_release: String,
+    /// Number of seconds since the guest booted.
+    pub uptime_s: u64,
+    /// Load averages of the guest, over 1, 5 and 15 minutes.
+    pub load_average: [f64; 3],
+    /// Total memory of the guest, in KiB.
+    pub memory_total_kib: u64,
+    /// Memory available to new applications in the guest, in KiB.
+    pub memory_available_kib: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct AgentRequest<'a, A> {
+    execute: &'a str,
+    arguments: A,
+}
+
+#[derive(Debug, Deserialize)]
+struct AgentError {
+    desc: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum AgentResponse<R> {
+    Return(R),
+    Error(AgentError),
+}
+
+#[derive(Debug, Serialize)]
+struct NoArguments {}
+
+/// Connection to the guest agent.
+#[derive(Debug)]
+pub struct GuestAgentClient {
+    stream: BufReader<UnixStream>,
+}
+
+impl GuestAgentClient {
+    /// Connects to the guest agent, through the Unix socket at `uds_path` backing the vsock
+    /// device.
+    pub fn connect(uds_path: &str) -> Result<Self, GuestAgentError> {
+        let stream = UnixStream::connect(uds_path).map_err(GuestAgentError::Connect)?;
+        stream
+            .set_read_timeout(Some(GUEST_AGENT_TIMEOUT))
+            .and_then(|_| stream.set_write_timeout(Some(GUEST_AGENT_TIMEOUT)))
+            .map_err(GuestAgentError::Connect)?;
+
+        let mut client = Self {
+            stream: BufReader::new(stream),
+        };
+        client.write_line(&format!("CONNECT {}", GUEST_AGENT_PORT))?;
+        // The connection is reset, without any answer, when the agent isn't listening.
+        match client.read_line() {
+            Ok(line) if line.starts_with("OK ") => Ok(client),
+            Ok(_) | Err(GuestAgentError::Io(_)) => {
+                Err(GuestAgentError::Unreachable(GUEST_AGENT_PORT))
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Fetches the health information of the guest.
+    pub fn info(&mut self) -> Result<GuestInfo, GuestAgentError> {
+        self.call("guest-info", NoArguments {})
+    }
+
+    /// Runs a command in the guest, and waits for it to exit.
+    pub fn exec(&mut self, config: &GuestExecConfig) -> Result<GuestExecOutput, GuestAgentError> {
+        config.validate()?;
+        self.stream
+            .get_ref()
+            .set_read_timeout(Some(
+                GUEST_AGENT_TIMEOUT + Duration::from_secs(config.timeout_s),
+            ))
+            .map_err(GuestAgentError::Io)?;
+        self.call("guest-exec", config)
+    }
+
+    fn call<A: Serialize, R: DeserializeOwned>(
+        &mut self,
+        command: &str,
+        arguments: A,
+    ) -> Result<R, GuestAgentError> {
+        let request = AgentRequest {
+            execute: command,
+            arguments,
+        };
+        // Serializing our own request types can't fail.
+        self.write_line(&serde_json::to_string(&request).unwrap())?;
+        let line = self.read_line()?;
+        match serde_json::from_str(&line).map_err(GuestAgentError::InvalidResponse)? {
+            AgentResponse::Return(value) => Ok(value),
+            AgentResponse::Error(err) => Err(GuestAgentError::Agent(err.desc)),
+        }
+    }
+
+    fn write_line(&mut self, line: &str) -> Result<(), GuestAgentError> {
+        let stream = self.stream.get_mut();
+        stream
+            .write_all(line.as_bytes())
+            .and_then(|_| stream.write_all(b"\n"))
+            .map_err(GuestAgentError::Io)
+    }
+
+    // The guest is untrusted, so the size of its answers is bounded.
+    fn read_line(&mut self) -> Result<String, GuestAgentError> {
+        let mut line = String::new();
+        (&mut self.stream)
+            .take(MAX_RESPONSE_SIZE)
+            .read_line(&mut line)
+            .map_err(GuestAgentError::Io)?;
+        if !line.ends_with('\n') {
+            if u64::try_from(line.len()).unwrap() == MAX_RESPONSE_SIZE {
+                return Err(GuestAgentError::ResponseTooLarge(MAX_RESPONSE_SIZE));
+            }
+            return Err(GuestAgentError::Io(std::io::Error::from(
+                std::io::ErrorKind::UnexpectedEof,
+            )));
+        }
+        line.pop();
+        Ok(line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::net::UnixListener;
+    use std::thread;
+
+    use vmm_sys_util::tempdir::TempDir;
+
+    use super::*;
+
+    // Serves a single connection to the Unix socket backing the vsock device, on behalf of a
+    // guest agent answering `response` to the request it expects.
+    fn serve(
+        listener: UnixListener,
+        listening: bool,
+        request: &'static str,
+        response: &'static str,
+    ) -> thread::JoinHandle<()> {
+        thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut stream = BufReader::new(stream);
+            let mut line = String::new();
+            stream.read_line(&mut line).unwrap();
+            assert_eq!(line, format!("CONNECT {}\n", GUEST_AGENT_PORT));
+            if !listening {
+                return;
+            }
+            stream.get_mut().write_all(b"OK 1073741824\n").unwrap();
+
+            line.clear();
+            stream.read_line(&mut line).unwrap();
+            assert_eq!(line.trim_end(), request);
+            stream.get_mut().write_all(response.as_bytes()).unwrap();
+        })
+    }
+
+    fn listen(dir: &TempDir, name: &str) -> (String, UnixListener) {
+        let path = format!("{}/{}", dir.as_path().to_str().unwrap(), name);
+        let listener = UnixListener::bind(&path).unwrap();
+        (path, listener)
+    }
+
+    #[test]
+    fn test_exec_config() {
+        let config: GuestExecConfig = serde_json::from_str(r#"{"path": "/bin/true"}"#).unwrap();
+        assert_eq!(config.timeout_s, 10);
+        assert!(config.args.is_empty());
+        config.validate().unwrap();
+
+        let mut invalid = config.clone();
+        invalid.timeout_s = 0;
+        assert!(matches!(
+            invalid.validate(),
+            Err(GuestAgentError::InvalidTimeout(0))
+        ));
+        invalid.timeout_s = MAX_EXEC_TIMEOUT_S + 1;
+        invalid.validate().unwrap_err();
+
+        let mut invalid = config;
+        invalid.input_data = Some("not base64!".to_string());
+        assert!(matches!(
+            invalid.validate(),
+            Err(GuestAgentError::InvalidInputData(_))
+        ));
+    }
+
+    #[test]
+    fn test_exec() {
+        let dir = TempDir::new().unwrap();
+        let (path, listener) = listen(&dir, "vsock");
+        let agent = serve(
+            listener,
+            true,
+            r#"{"execute":"guest-exec","arguments":{"path":"/bin/echo","args":["hi"],"env":[],"timeout_s":10}}"#,
+            "{\"return\": {\"exit_code\": 0, \"signal\": null, \"out_data\": \"aGkK\"}}\n",
+        );
+        let config = GuestExecConfig {
+            path: "/bin/echo".to_string(),
+            args: vec!["hi".to_string()],
+            env: vec![],
+            input_data: None,
+            timeout_s: 10,
+        };
+        let output = GuestAgentClient::connect(&path)
+            .unwrap()
+            .exec(&config)
+            .unwrap();
+        agent.join().unwrap();
+        assert_eq!(
+            output,
+            GuestExecOutput {
+                exit_code: Some(0),
+                out_data: "aGkK".to_string(),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_info() {
+        let dir = TempDir::new().unwrap();
+        let (path, listener) = listen(&dir, "vsock");
+        let agent = serve(
+            listener,
+            true,
+            r#"{"execute":"guest-info","arguments":{}}"#,
+            "{\"error\": {\"desc\": \"not implemented\"}}\n",
+        );
+        let mut client = GuestAgentClient::connect(&path).unwrap();
+        match client.info() {
+            Err(GuestAgentError::Agent(desc)) => assert_eq!(desc, "not implemented"),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        agent.join().unwrap();
+
+        // The agent goes away before completing its response.
+        let (path, listener) = listen(&dir, "vsock_truncated");
+        let agent = serve(
+            listener,
+            true,
+            r#"{"execute":"guest-info","arguments":{}}"#,
+            "{}",
+        );
+        assert!(matches!(
+            GuestAgentClient::connect(&path).unwrap().info(),
+            Err(GuestAgentError::Io(_))
+        ));
+        agent.join().unwrap();
+    }
+
+    #[test]
+    fn test_unreachable() {
+        let dir = TempDir::new().unwrap();
+        assert!(matches!(
+            GuestAgentClient::connect(&format!("{}/missing", dir.as_path().to_str().unwrap())),
+            Err(GuestAgentError::Connect(_))
+        ));
+
+        let (path, listener) = listen(&dir, "vsock");
+        let agent = serve(listener, false, "", "");
+        assert!(matches!(
+            GuestAgentClient::connect(&path),
+            Err(GuestAgentError::Unreachable(GUEST_AGENT_PORT))
+        ));
+        agent.join().unwrap();
+    }
+}
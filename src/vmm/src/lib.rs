@@ -70,11 +70,11 @@ pub mod io_uring;
 pub mod rate_limiter;
 
 /// Module for handling ACPI tables.
-/// Currently, we only use ACPI on x86 microVMs.
-#[cfg(target_arch = "x86_64")]
 pub mod acpi;
 /// Handles setup and initialization a `Vmm` object.
 pub mod builder;
+/// Core dumps of the guest for offline debugging.
+pub mod core_dump;
 /// Types for guest configuration.
 pub mod cpu_config;
 pub(crate) mod device_manager;
@@ -86,6 +86,8 @@ pub mod dumbo;
 /// Support for GDB debugging the guest
 #[cfg(feature = "gdb")]
 pub mod gdb;
+/// Host side of the guest agent protocol.
+pub mod guest_agent;
 /// Logger
 pub mod logger;
 /// microVM Metadata Service MMDS
@@ -94,10 +96,15 @@ pub mod mmds;
 pub mod pci;
 /// Save/restore utilities.
 pub mod persist;
+/// Warm reset of the microVM when the guest reboots.
+#[cfg(target_arch = "x86_64")]
+pub mod reset;
 /// Resource store for configured microVM resources.
 pub mod resources;
 /// microVM RPC API adapters.
 pub mod rpc_interface;
+/// Actions taken on timers and events of the guest.
+pub mod scheduled_actions;
 /// Seccomp filter utilities.
 pub mod seccomp;
 /// Signal handling utilities.
@@ -118,7 +125,7 @@ pub mod initrd;
 
 use std::collections::HashMap;
 use std::io;
-use std::os::unix::io::AsRawFd;
+use std::os::unix::io::{AsRawFd, RawFd};
 use std::sync::mpsc::RecvTimeoutError;
 use std::sync::{Arc, Barrier, Mutex};
 use std::time::Duration;
@@ -134,7 +141,11 @@ use vmm_sys_util::terminal::Terminal;
 use vstate::kvm::Kvm;
 use vstate::vcpu::{self, StartThreadedError, VcpuSendEventError};
 
+#[cfg(target_arch = "x86_64")]
+use crate::acpi::AcpiTablesDump;
 use crate::cpu_config::templates::CpuConfiguration;
+use crate::devices::acpi::processor_aggregator::ProcessorAggregatorStatus;
+use crate::devices::acpi::watchdog::WatchdogStatus;
 use crate::devices::virtio::balloon::device::{HintingStatus, StartHintingCmd};
 use crate::devices::virtio::balloon::{
     BALLOON_DEV_ID, Balloon, BalloonConfig, BalloonError, BalloonStats,
@@ -146,7 +157,12 @@ use crate::devices::virtio::net::Net;
 use crate::logger::{METRICS, MetricsError, error, info, warn};
 use crate::persist::{MicrovmState, MicrovmStateError, VmInfo};
 use crate::rate_limiter::BucketUpdate;
+use crate::vmm_config::apei::GhesNotification;
 use crate::vmm_config::instance_info::{InstanceInfo, VmState};
+use crate::vmm_config::power_supply::PowerSupplyUpdateConfig;
+use crate::vmm_config::snapshot::{CreateSnapshotParams, SnapshotType};
+use crate::vmm_config::thermal_zone::ThermalZoneUpdateConfig;
+use crate::vmm_config::watchdog::WatchdogAction;
 use crate::vstate::memory::{GuestMemory, GuestMemoryMmap, GuestMemoryRegion};
 use crate::vstate::vcpu::VcpuState;
 pub use crate::vstate::vcpu::{Vcpu, VcpuConfig, VcpuEvent, VcpuHandle, VcpuResponse};
@@ -187,6 +203,8 @@ pub enum FcExitCode {
     BadConfiguration = 152,
     /// Command line arguments parsing error.
     ArgParsing = 153,
+    /// clawdbox was shut down after the guest watchdog expired.
+    WatchdogReset = 158,
 }
 
 /// Timeout used in recv_timeout, when waiting for a vcpu response on
@@ -214,6 +232,9 @@ pub enum VmmError {
     /// I8042 error: {0}
     I8042Error(devices::legacy::I8042DeviceError),
     #[cfg(target_arch = "x86_64")]
+    /// The legacy-free platform has no i8042 controller.
+    NoI8042,
+    #[cfg(target_arch = "x86_64")]
     /// Cannot add devices to the legacy I/O Bus. {0}
     LegacyIOBus(device_manager::legacy::LegacyDeviceError),
     /// Metrics error: {0}
@@ -240,6 +261,8 @@ pub enum VmmError {
     VcpuResume,
     /// Failed to message the vCPUs.
     VcpuMessage,
+    /// vCPU {0} does not exist.
+    InvalidVcpu(u8),
     /// Cannot spawn Vcpu thread: {0}
     VcpuSpawn(io::Error),
     /// Vm error: {0}
@@ -254,6 +277,23 @@ pub enum VmmError {
     Balloon(#[from] BalloonError),
     /// Failed to create memory hotplug device: {0}
     VirtioMem(#[from] VirtioMemError),
+    /// The watchdog is not enabled.
+    WatchdogNotEnabled,
+    /// The power supply devices are not enabled.
+    PowerSupplyNotEnabled,
+    /// Cannot update the power supply devices: {0}
+    PowerSupply(vm_memory::GuestMemoryError),
+    /// The processor aggregator device is not enabled.
+    ProcessorAggregatorNotEnabled,
+    /// Cannot access the processor aggregator device: {0}
+    ProcessorAggregator(vm_memory::GuestMemoryError),
+    /// The thermal zone is not enabled.
+    ThermalZoneNotEnabled,
+    /// Cannot update the thermal zone: {0}
+    ThermalZone(vm_memory::GuestMemoryError),
+    #[cfg(target_arch = "x86_64")]
+    /// Cannot read the ACPI tables of the guest: {0}
+    AcpiTables(crate::acpi::AcpiError),
 }
 
 /// Shorthand type for KVM dirty page bitmap.
@@ -311,6 +351,11 @@ pub struct Vmm {
     vcpus_exit_evt: EventFd,
     // Device manager
     device_manager: DeviceManager,
+    // State the microVM is reset to when the guest reboots, if it is reset in place.
+    #[cfg(target_arch = "x86_64")]
+    boot_image: Option<reset::BootImage>,
+    // Actions taken on timers and events of the guest.
+    scheduled_actions: Option<scheduled_actions::ScheduledActions>,
 }
 
 impl Vmm {
@@ -361,6 +406,12 @@ impl Vmm {
             vcpu.set_mmio_bus(self.vm.common.mmio_bus.clone());
             #[cfg(target_arch = "x86_64")]
             vcpu.kvm_vcpu.set_pio_bus(self.vm.pio_bus.clone());
+            #[cfg(target_arch = "x86_64")]
+            {
+                vcpu.kvm_vcpu.peripherals.reset_on_triple_fault = self.boot_image.is_some();
+                vcpu.kvm_vcpu.peripherals.ioapic = self.device_manager.ioapic.clone();
+                vcpu.kvm_vcpu.peripherals.cppc = self.device_manager.acpi_devices.cppc.clone();
+            }
 
             self.vcpus_handles.push(vcpu.start_threaded(
                 &self.vm,
@@ -395,6 +446,13 @@ impl Vmm {
             return Err(VmmError::VcpuMessage);
         }
 
+        if let Some(watchdog) = &self.device_manager.acpi_devices.watchdog {
+            watchdog.lock().expect("Poisoned lock").resume();
+        }
+        if let Some(s2idle) = &self.device_manager.acpi_devices.s2idle {
+            s2idle.lock().expect("Poisoned lock").resume();
+        }
+
         self.instance_info.state = VmState::Running;
         Ok(())
     }
@@ -417,6 +475,13 @@ impl Vmm {
             return Err(VmmError::VcpuMessage);
         }
 
+        if let Some(watchdog) = &self.device_manager.acpi_devices.watchdog {
+            watchdog.lock().expect("Poisoned lock").pause();
+        }
+        if let Some(s2idle) = &self.device_manager.acpi_devices.s2idle {
+            s2idle.lock().expect("Poisoned lock").pause();
+        }
+
         self.instance_info.state = VmState::Paused;
         Ok(())
     }
@@ -427,12 +492,33 @@ impl Vmm {
         self.device_manager
             .legacy_devices
             .i8042
+            .as_ref()
+            .ok_or(VmmError::NoI8042)?
             .lock()
             .expect("i8042 lock was poisoned")
             .trigger_ctrl_alt_del()
             .map_err(VmmError::I8042Error)
     }
 
+    /// Injects an NMI in the given vCPU, or in all of them when none is given.
+    ///
+    /// The NMI of a paused vCPU is delivered once it is resumed.
+    #[cfg(target_arch = "x86_64")]
+    pub fn inject_nmi(&mut self, vcpu: Option<u8>) -> Result<(), VmmError> {
+        let handles = match vcpu {
+            Some(index) => std::slice::from_mut(
+                self.vcpus_handles
+                    .get_mut(usize::from(index))
+                    .ok_or(VmmError::InvalidVcpu(index))?,
+            ),
+            None => self.vcpus_handles.as_mut_slice(),
+        };
+        handles
+            .iter_mut()
+            .try_for_each(|handle| handle.send_event(VcpuEvent::InjectNmi))
+            .map_err(|_| VmmError::VcpuMessage)
+    }
+
     /// Saves the state of a paused Microvm.
     pub fn save_state(&mut self, vm_info: &VmInfo) -> Result<MicrovmState, MicrovmStateError> {
         use self::MicrovmStateError::SaveVmState;
@@ -610,6 +696,181 @@ impl Vmm {
         Ok(())
     }
 
+    /// Returns the ACPI tables written in guest memory.
+    #[cfg(target_arch = "x86_64")]
+    pub fn acpi_tables(&self) -> Result<AcpiTablesDump, VmmError> {
+        crate::acpi::dump_acpi_tables(self.vm.guest_memory()).map_err(VmmError::AcpiTables)
+    }
+
+    /// Returns the status of the watchdog.
+    pub fn watchdog_status(&self) -> Result<WatchdogStatus, VmmError> {
+        self.device_manager
+            .acpi_devices
+            .watchdog
+            .as_ref()
+            .map(|watchdog| watchdog.lock().expect("Poisoned lock").status())
+            .ok_or(VmmError::WatchdogNotEnabled)
+    }
+
+    /// Updates the status of the battery and AC adapter and notifies the guest about it.
+    pub fn update_power_supply(
+        &mut self,
+        update: &PowerSupplyUpdateConfig,
+    ) -> Result<(), VmmError> {
+        let power_supply = self
+            .device_manager
+            .acpi_devices
+            .power_supply
+            .as_mut()
+            .ok_or(VmmError::PowerSupplyNotEnabled)?;
+        power_supply
+            .update(self.vm.guest_memory(), update)
+            .map_err(VmmError::PowerSupply)
+    }
+
+    /// Returns the status of the processor aggregator device.
+    pub fn processor_aggregator_status(&self) -> Result<ProcessorAggregatorStatus, VmmError> {
+        self.device_manager
+            .acpi_devices
+            .processor_aggregator
+            .as_ref()
+            .ok_or(VmmError::ProcessorAggregatorNotEnabled)?
+            .status(self.vm.guest_memory())
+            .map_err(VmmError::ProcessorAggregator)
+    }
+
+    /// Asks the guest to idle `idle_cpus` vCPUs through the processor aggregator device.
+    pub fn update_processor_aggregator(&mut self, idle_cpus: u32) -> Result<(), VmmError> {
+        let aggregator = self
+            .device_manager
+            .acpi_devices
+            .processor_aggregator
+            .as_mut()
+            .ok_or(VmmError::ProcessorAggregatorNotEnabled)?;
+        aggregator
+            .update(self.vm.guest_memory(), idle_cpus)
+            .map_err(VmmError::ProcessorAggregator)
+    }
+
+    /// Sets the temperature of the thermal zone and notifies the guest if it crossed a trip point.
+    pub fn update_thermal_zone(
+        &mut self,
+        update: &ThermalZoneUpdateConfig,
+    ) -> Result<(), VmmError> {
+        let thermal_zone = self
+            .device_manager
+            .acpi_devices
+            .thermal_zone
+            .as_mut()
+            .ok_or(VmmError::ThermalZoneNotEnabled)?;
+        thermal_zone
+            .update(self.vm.guest_memory(), update)
+            .map_err(VmmError::ThermalZone)
+    }
+
+    fn watchdog_fd(&self) -> Option<RawFd> {
+        self.device_manager
+            .acpi_devices
+            .watchdog
+            .as_ref()
+            .map(|watchdog| watchdog.lock().expect("Poisoned lock").as_raw_fd())
+    }
+
+    fn thermal_zone_fd(&self) -> Option<RawFd> {
+        self.device_manager
+            .acpi_devices
+            .thermal_zone
+            .as_ref()
+            .map(AsRawFd::as_raw_fd)
+    }
+
+    fn panic_fd(&self) -> Option<RawFd> {
+        self.device_manager
+            .acpi_devices
+            .pvpanic
+            .as_ref()
+            .map(|pvpanic| pvpanic.lock().expect("Poisoned lock").panic_evt.as_raw_fd())
+    }
+
+    fn memory_error_fd(&self) -> Option<RawFd> {
+        self.device_manager
+            .acpi_devices
+            .ghes
+            .as_ref()
+            .map(|ghes| ghes.memory_error_evt.as_raw_fd())
+    }
+
+    /// Reports the memory errors queued by the `SIGBUS` handler to the guest.
+    fn handle_memory_errors(&mut self) {
+        let Some(ghes) = &mut self.device_manager.acpi_devices.ghes else {
+            return;
+        };
+        if !ghes.process_memory_errors(self.vm.guest_memory()) {
+            return;
+        }
+
+        match ghes.notification {
+            GhesNotification::Ged => {
+                let _ = ghes.notify_guest();
+            }
+            GhesNotification::Nmi => {
+                #[cfg(target_arch = "x86_64")]
+                if let Some(handle) = self.vcpus_handles.first_mut()
+                    && let Err(err) = handle.send_event(VcpuEvent::InjectNmi)
+                {
+                    error!("Failed to notify the guest of a memory error: {err}");
+                }
+            }
+        }
+    }
+
+    /// Takes the configured action after the guest let the watchdog expire.
+    fn handle_watchdog_expiry(&mut self) {
+        let Some(watchdog) = self.device_manager.acpi_devices.watchdog.clone() else {
+            return;
+        };
+        let mut watchdog = watchdog.lock().expect("Poisoned lock");
+        if !watchdog.process_timer() {
+            return;
+        }
+        let config = watchdog.config.clone();
+        let vm_info = watchdog.vm_info.clone();
+        // Pausing the microVM also pauses the watchdog.
+        drop(watchdog);
+
+        warn!("Guest watchdog expired, taking action: {:?}", config.action);
+        match config.action {
+            WatchdogAction::Reset => self.stop(FcExitCode::WatchdogReset),
+            WatchdogAction::Pause => {
+                if let Err(err) = self.pause_vm() {
+                    error!("Failed to pause the microVM after the watchdog expired: {err}");
+                }
+            }
+            WatchdogAction::Notify => (),
+            WatchdogAction::Snapshot => {
+                if let Err(err) = self.pause_vm() {
+                    error!("Failed to pause the microVM after the watchdog expired: {err}");
+                    return;
+                }
+                // Validation guarantees that both paths are set.
+                let params = CreateSnapshotParams {
+                    snapshot_type: SnapshotType::Full,
+                    snapshot_path: config.snapshot_path.unwrap_or_default(),
+                    mem_file_path: config.mem_file_path.unwrap_or_default(),
+                };
+                match persist::create_snapshot(self, &vm_info, &params) {
+                    Ok(()) => info!(
+                        "Saved the microVM to {} after the watchdog expired",
+                        params.snapshot_path.display()
+                    ),
+                    Err(err) => {
+                        error!("Failed to snapshot the microVM after the watchdog expired: {err}")
+                    }
+                }
+            }
+        }
+    }
+
     /// Returns the current state of the memory hotplug device.
     pub fn memory_hotplug_status(&self) -> Result<VirtioMemStatus, VmmError> {
         self.device_manager
@@ -725,10 +986,12 @@ impl MutEventSubscriber for Vmm {
         let event_set = event.event_set();
 
         if source == self.vcpus_exit_evt.as_raw_fd() && event_set == EventSet::IN {
-            // Exit event handling should never do anything more than call 'self.stop()'.
+            // Exit event handling should never do anything more than call 'self.stop()', or
+            // reset the microVM if the guest asked for it.
             let _ = self.vcpus_exit_evt.read();
 
-            let exit_code = 'exit_code: {
+            let mut exit_code = None;
+            'exit_code: {
                 // Query each vcpu for their exit_code.
                 for handle in &self.vcpus_handles {
                     // Drain all vcpu responses that are pending from this vcpu until we find an
@@ -738,17 +1001,45 @@ impl MutEventSubscriber for Vmm {
                             // It could be that some vcpus exited successfully while others
                             // errored out. Thus make sure that error exits from one vcpu always
                             // takes precedence over "ok" exits
+                            exit_code = Some(status);
                             if status != FcExitCode::Ok {
-                                break 'exit_code status;
+                                break 'exit_code;
                             }
                         }
                     }
                 }
+            }
 
-                // No CPUs exited with error status code, report "Ok"
-                FcExitCode::Ok
-            };
-            self.stop(exit_code);
+            // Without an exit status, the event is a platform reset request from the guest.
+            #[cfg(target_arch = "x86_64")]
+            if exit_code.is_none() && self.boot_image.is_some() {
+                if let Err(err) = self.warm_reset() {
+                    error!("Failed to reset the microVM: {}", err);
+                    self.stop(FcExitCode::GenericError);
+                }
+                return;
+            }
+            // No CPUs exited with error status code, report "Ok"
+            self.stop(exit_code.unwrap_or(FcExitCode::Ok));
+        } else if Some(source) == self.watchdog_fd() {
+            self.handle_watchdog_expiry();
+        } else if Some(source) == self.memory_error_fd() {
+            self.handle_memory_errors();
+        } else if Some(source) == self.panic_fd() {
+            if let Some(pvpanic) = &self.device_manager.acpi_devices.pvpanic {
+                let _ = pvpanic.lock().expect("Poisoned lock").panic_evt.read();
+            }
+            self.handle_guest_panic();
+        } else if Some(source) == self.thermal_zone_fd() {
+            if let Some(thermal_zone) = &mut self.device_manager.acpi_devices.thermal_zone {
+                thermal_zone.process_timer(self.vm.guest_memory());
+            }
+        } else if self
+            .scheduled_actions
+            .as_ref()
+            .is_some_and(|actions| actions.owns(source))
+        {
+            self.handle_scheduled_actions(source);
         } else {
             error!("Spurious EventManager event for handler: Vmm");
         }
@@ -758,5 +1049,34 @@ impl MutEventSubscriber for Vmm {
         if let Err(err) = ops.add(Events::new(&self.vcpus_exit_evt, EventSet::IN)) {
             error!("Failed to register vmm exit event: {}", err);
         }
+        if let Some(watchdog) = &self.device_manager.acpi_devices.watchdog
+            && let Err(err) = ops.add(Events::new(
+                &*watchdog.lock().expect("Poisoned lock"),
+                EventSet::IN,
+            ))
+        {
+            error!("Failed to register watchdog timer event: {}", err);
+        }
+        if let Some(ghes) = &self.device_manager.acpi_devices.ghes
+            && let Err(err) = ops.add(Events::new(&ghes.memory_error_evt, EventSet::IN))
+        {
+            error!("Failed to register memory error event: {}", err);
+        }
+        if let Some(pvpanic) = &self.device_manager.acpi_devices.pvpanic
+            && let Err(err) = ops.add(Events::new(
+                &pvpanic.lock().expect("Poisoned lock").panic_evt,
+                EventSet::IN,
+            ))
+        {
+            error!("Failed to register guest panic event: {}", err);
+        }
+        if let Some(thermal_zone) = &self.device_manager.acpi_devices.thermal_zone
+            && let Err(err) = ops.add(Events::new(thermal_zone, EventSet::IN))
+        {
+            error!("Failed to register thermal zone timer event: {}", err);
+        }
+        if let Some(scheduled_actions) = &self.scheduled_actions {
+            scheduled_actions.register(ops);
+        }
     }
 }
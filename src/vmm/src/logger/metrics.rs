@@ -363,6 +363,16 @@ pub struct GetRequestsMetrics {
     pub vmm_version_count: SharedIncMetric,
     /// Number of GETs for getting hotpluggable memory status.
     pub hotplug_memory_count: SharedIncMetric,
+    /// Number of GETs for getting the effective guest CPU features.
+    pub cpu_features_count: SharedIncMetric,
+    /// Number of GETs for getting information from the guest agent.
+    pub guest_info_count: SharedIncMetric,
+    /// Number of GETs for getting the watchdog status.
+    pub watchdog_count: SharedIncMetric,
+    /// Number of GETs for getting the processor aggregator status.
+    pub processor_aggregator_count: SharedIncMetric,
+    /// Number of GETs for dumping the ACPI tables of the guest.
+    pub acpi_tables_count: SharedIncMetric,
 }
 impl GetRequestsMetrics {
     /// Const default construction.
@@ -373,6 +383,11 @@ impl GetRequestsMetrics {
             mmds_count: SharedIncMetric::new(),
             vmm_version_count: SharedIncMetric::new(),
             hotplug_memory_count: SharedIncMetric::new(),
+            cpu_features_count: SharedIncMetric::new(),
+            guest_info_count: SharedIncMetric::new(),
+            watchdog_count: SharedIncMetric::new(),
+            processor_aggregator_count: SharedIncMetric::new(),
+            acpi_tables_count: SharedIncMetric::new(),
         }
     }
 }
@@ -424,6 +439,22 @@ pub struct PutRequestsMetrics {
     pub pmem_count: SharedIncMetric,
     /// Number of failures in attaching a pmem device.
     pub pmem_fails: SharedIncMetric,
+    /// Number of PUTs triggering a VFIO device attach.
+    pub vfio_count: SharedIncMetric,
+    /// Number of failures in attaching a VFIO device.
+    pub vfio_fails: SharedIncMetric,
+    /// Number of PUTs triggering an SR-IOV virtual function attach.
+    pub vfio_vf_count: SharedIncMetric,
+    /// Number of failures in attaching an SR-IOV virtual function.
+    pub vfio_vf_fails: SharedIncMetric,
+    /// Number of PUTs triggering a USB device attach.
+    pub usb_count: SharedIncMetric,
+    /// Number of failures in attaching a USB device.
+    pub usb_fails: SharedIncMetric,
+    /// Number of PUTs triggering an NVMe controller attach.
+    pub nvme_count: SharedIncMetric,
+    /// Number of failures in attaching an NVMe controller.
+    pub nvme_fails: SharedIncMetric,
     /// Number of PUTs to /serial
     pub serial_count: SharedIncMetric,
     /// Number of failed PUTs to /serial
@@ -432,6 +463,70 @@ pub struct PutRequestsMetrics {
     pub hotplug_memory_count: SharedIncMetric,
     /// Number of failed PUTs to /hotplug/memory
     pub hotplug_memory_fails: SharedIncMetric,
+    /// Number of PUTs to /acpi/tables
+    pub acpi_tables_count: SharedIncMetric,
+    /// Number of failed PUTs to /acpi/tables
+    pub acpi_tables_fails: SharedIncMetric,
+    /// Number of PUTs running a command through the guest agent.
+    pub guest_exec_count: SharedIncMetric,
+    /// Number of failures in running a command through the guest agent.
+    pub guest_exec_fails: SharedIncMetric,
+    /// Number of PUTs to /watchdog
+    pub watchdog_count: SharedIncMetric,
+    /// Number of failed PUTs to /watchdog
+    pub watchdog_fails: SharedIncMetric,
+    /// Number of PUTs to /apei
+    pub apei_count: SharedIncMetric,
+    /// Number of failed PUTs to /apei
+    pub apei_fails: SharedIncMetric,
+    /// Number of PUTs to /reboot
+    pub reboot_count: SharedIncMetric,
+    /// Number of failed PUTs to /reboot
+    pub reboot_fails: SharedIncMetric,
+    /// Number of PUTs to /power-supply
+    pub power_supply_count: SharedIncMetric,
+    /// Number of failed PUTs to /power-supply
+    pub power_supply_fails: SharedIncMetric,
+    /// Number of PUTs to /processor-aggregator
+    pub processor_aggregator_count: SharedIncMetric,
+    /// Number of failed PUTs to /processor-aggregator
+    pub processor_aggregator_fails: SharedIncMetric,
+    /// Number of PUTs to /smbios
+    pub smbios_count: SharedIncMetric,
+    /// Number of failed PUTs to /smbios
+    pub smbios_fails: SharedIncMetric,
+    /// Number of PUTs to /memory-map
+    pub memory_map_count: SharedIncMetric,
+    /// Number of failed PUTs to /memory-map
+    pub memory_map_fails: SharedIncMetric,
+    /// Number of PUTs to /sgx
+    pub sgx_count: SharedIncMetric,
+    /// Number of failed PUTs to /sgx
+    pub sgx_fails: SharedIncMetric,
+    /// Number of PUTs to /s2idle
+    pub s2idle_count: SharedIncMetric,
+    /// Number of failed PUTs to /s2idle
+    pub s2idle_fails: SharedIncMetric,
+    /// Number of PUTs to /pmu
+    pub pmu_count: SharedIncMetric,
+    /// Number of failed PUTs to /pmu
+    pub pmu_fails: SharedIncMetric,
+    /// Number of PUTs to /cppc
+    pub cppc_count: SharedIncMetric,
+    /// Number of failed PUTs to /cppc
+    pub cppc_fails: SharedIncMetric,
+    /// Number of PUTs to /thermal-zone
+    pub thermal_zone_count: SharedIncMetric,
+    /// Number of failed PUTs to /thermal-zone
+    pub thermal_zone_fails: SharedIncMetric,
+    /// Number of PUTs to /fw-cfg
+    pub fw_cfg_count: SharedIncMetric,
+    /// Number of failed PUTs to /fw-cfg
+    pub fw_cfg_fails: SharedIncMetric,
+    /// Number of PUTs to /scheduled-actions
+    pub scheduled_actions_count: SharedIncMetric,
+    /// Number of failed PUTs to /scheduled-actions
+    pub scheduled_actions_fails: SharedIncMetric,
 }
 impl PutRequestsMetrics {
     /// Const default construction.
@@ -459,10 +554,50 @@ impl PutRequestsMetrics {
             vsock_fails: SharedIncMetric::new(),
             pmem_count: SharedIncMetric::new(),
             pmem_fails: SharedIncMetric::new(),
+            vfio_count: SharedIncMetric::new(),
+            vfio_fails: SharedIncMetric::new(),
+            vfio_vf_count: SharedIncMetric::new(),
+            vfio_vf_fails: SharedIncMetric::new(),
+            usb_count: SharedIncMetric::new(),
+            usb_fails: SharedIncMetric::new(),
+            nvme_count: SharedIncMetric::new(),
+            nvme_fails: SharedIncMetric::new(),
             serial_count: SharedIncMetric::new(),
             serial_fails: SharedIncMetric::new(),
             hotplug_memory_count: SharedIncMetric::new(),
             hotplug_memory_fails: SharedIncMetric::new(),
+            acpi_tables_count: SharedIncMetric::new(),
+            acpi_tables_fails: SharedIncMetric::new(),
+            guest_exec_count: SharedIncMetric::new(),
+            guest_exec_fails: SharedIncMetric::new(),
+            watchdog_count: SharedIncMetric::new(),
+            watchdog_fails: SharedIncMetric::new(),
+            apei_count: SharedIncMetric::new(),
+            apei_fails: SharedIncMetric::new(),
+            reboot_count: SharedIncMetric::new(),
+            reboot_fails: SharedIncMetric::new(),
+            power_supply_count: SharedIncMetric::new(),
+            power_supply_fails: SharedIncMetric::new(),
+            processor_aggregator_count: SharedIncMetric::new(),
+            processor_aggregator_fails: SharedIncMetric::new(),
+            smbios_count: SharedIncMetric::new(),
+            smbios_fails: SharedIncMetric::new(),
+            memory_map_count: SharedIncMetric::new(),
+            memory_map_fails: SharedIncMetric::new(),
+            sgx_count: SharedIncMetric::new(),
+            sgx_fails: SharedIncMetric::new(),
+            s2idle_count: SharedIncMetric::new(),
+            s2idle_fails: SharedIncMetric::new(),
+            pmu_count: SharedIncMetric::new(),
+            pmu_fails: SharedIncMetric::new(),
+            cppc_count: SharedIncMetric::new(),
+            cppc_fails: SharedIncMetric::new(),
+            thermal_zone_count: SharedIncMetric::new(),
+            thermal_zone_fails: SharedIncMetric::new(),
+            fw_cfg_count: SharedIncMetric::new(),
+            fw_cfg_fails: SharedIncMetric::new(),
+            scheduled_actions_count: SharedIncMetric::new(),
+            scheduled_actions_fails: SharedIncMetric::new(),
         }
     }
 }
@@ -490,6 +625,18 @@ pub struct PatchRequestsMetrics {
     pub hotplug_memory_count: SharedIncMetric,
     /// Number of failed PATCHes to /hotplug/memory
     pub hotplug_memory_fails: SharedIncMetric,
+    /// Number of PATCHes to /power-supply
+    pub power_supply_count: SharedIncMetric,
+    /// Number of failed PATCHes to /power-supply
+    pub power_supply_fails: SharedIncMetric,
+    /// Number of PATCHes to /processor-aggregator
+    pub processor_aggregator_count: SharedIncMetric,
+    /// Number of failed PATCHes to /processor-aggregator
+    pub processor_aggregator_fails: SharedIncMetric,
+    /// Number of PATCHes to /thermal-zone
+    pub thermal_zone_count: SharedIncMetric,
+    /// Number of failed PATCHes to /thermal-zone
+    pub thermal_zone_fails: SharedIncMetric,
 }
 impl PatchRequestsMetrics {
     /// Const default construction.
@@ -505,6 +652,12 @@ impl PatchRequestsMetrics {
             mmds_fails: SharedIncMetric::new(),
             hotplug_memory_count: SharedIncMetric::new(),
             hotplug_memory_fails: SharedIncMetric::new(),
+            power_supply_count: SharedIncMetric::new(),
+            power_supply_fails: SharedIncMetric::new(),
+            processor_aggregator_count: SharedIncMetric::new(),
+            processor_aggregator_fails: SharedIncMetric::new(),
+            thermal_zone_count: SharedIncMetric::new(),
+            thermal_zone_fails: SharedIncMetric::new(),
         }
     }
 }
@@ -680,6 +833,8 @@ pub struct SignalMetrics {
     pub sighup: SharedStoreMetric,
     /// Number of times that SIGILL was handled.
     pub sigill: SharedStoreMetric,
+    /// Number of memory errors reported with SIGBUS and forwarded to the guest.
+    pub sigbus_memory_errors: SharedIncMetric,
 }
 impl SignalMetrics {
     /// Const default construction.
@@ -692,6 +847,7 @@ impl SignalMetrics {
             sigpipe: SharedIncMetric::new(),
             sighup: SharedStoreMetric::new(),
             sigill: SharedStoreMetric::new(),
+            sigbus_memory_errors: SharedIncMetric::new(),
         }
     }
 }
@@ -833,6 +989,70 @@ impl InterruptMetrics {
     }
 }
 
+/// Suspend-to-idle related metrics.
+#[derive(Debug, Default, Serialize)]
+pub struct S2idleMetrics {
+    /// Number of times the guest entered suspend-to-idle.
+    pub entries: SharedIncMetric,
+    /// Time the guest spent in suspend-to-idle, in microseconds, updated when it enters or leaves
+    /// it and when the microVM is paused or resumed.
+    pub residency_us: SharedStoreMetric,
+}
+
+impl S2idleMetrics {
+    /// Const default construction.
+    pub const fn new() -> Self {
+        Self {
+            entries: SharedIncMetric::new(),
+            residency_us: SharedStoreMetric::new(),
+        }
+    }
+}
+
+/// CPPC related metrics.
+#[derive(Debug, Default, Serialize)]
+pub struct CppcMetrics {
+    /// Number of performance requests of the guest.
+    pub perf_requests: SharedIncMetric,
+    /// Number of performance requests which could not be applied to the vCPU threads.
+    pub hint_fails: SharedIncMetric,
+}
+
+impl CppcMetrics {
+    /// Const default construction.
+    pub const fn new() -> Self {
+        Self {
+            perf_requests: SharedIncMetric::new(),
+            hint_fails: SharedIncMetric::new(),
+        }
+    }
+}
+
+/// Scheduled actions related metrics.
+#[derive(Debug, Default, Serialize)]
+pub struct ScheduledActionsMetrics {
+    /// Number of kernel panics reported by the guest.
+    pub guest_panics: SharedIncMetric,
+    /// Number of runs of the OOM killer of the guest noticed through the balloon statistics.
+    pub guest_ooms: SharedIncMetric,
+    /// Number of actions taken.
+    pub actions: SharedIncMetric,
+    /// Number of actions which failed.
+    pub action_fails: SharedIncMetric,
+}
+
+impl ScheduledActionsMetrics {
+    /// Const default construction.
+    pub const fn new() -> Self {
+        Self {
+            guest_panics: SharedIncMetric::new(),
+            guest_ooms: SharedIncMetric::new(),
+            actions: SharedIncMetric::new(),
+            action_fails: SharedIncMetric::new(),
+        }
+    }
+}
+
 /// Metrics specific to the machine manager as a whole.
 #[derive(Debug, Default, Serialize)]
 pub struct VmmMetrics {
@@ -948,6 +1168,12 @@ pub struct clawdboxMetrics {
     pub vhost_user_ser: VhostUserMetricsSerializeProxy,
     /// Interrupt related metrics
     pub interrupts: InterruptMetrics,
+    /// Suspend-to-idle related metrics.
+    pub s2idle: S2idleMetrics,
+    /// CPPC related metrics.
+    pub cppc: CppcMetrics,
+    /// Scheduled actions related metrics.
+    pub scheduled_actions: ScheduledActionsMetrics,
     #[serde(flatten)]
     /// Virtio-mem device related metrics (memory hotplugging)
     pub memory_hotplug_ser: MemoryHotplugSerializeProxy,
@@ -978,6 +1204,9 @@ impl clawdboxMetrics {
             pmem_ser: PmemMetricsSerializeProxy {},
             vhost_user_ser: VhostUserMetricsSerializeProxy {},
             interrupts: InterruptMetrics::new(),
+            s2idle: S2idleMetrics::new(),
+            cppc: CppcMetrics::new(),
+            scheduled_actions: ScheduledActionsMetrics::new(),
             memory_hotplug_ser: MemoryHotplugSerializeProxy {},
         }
     }
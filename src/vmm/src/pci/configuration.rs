@@ -229,6 +229,11 @@ impl PciConfiguration {
         }
     }
 
+    /// Sets the programming interface, refining the class of the device.
+    pub fn set_programming_interface(&mut self, prog_if: u8) {
+        self.write_byte_internal(9, prog_if, false);
+    }
+
     /// Add the [addr, addr + size) BAR region.
     ///
     /// Configures the specified BAR to report this region and size to the guest kernel.
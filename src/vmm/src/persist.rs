@@ -27,9 +27,11 @@ use crate::cpu_config::x86_64::cpuid::CpuidTrait;
 use crate::cpu_config::x86_64::cpuid::common::get_vendor_id_from_host;
 use crate::device_manager::{DevicePersistError, DevicesState};
 use crate::logger::{info, warn};
+use crate::mmds::data_store::MmdsDatastoreError;
 use crate::resources::VmResources;
 use crate::seccomp::BpfThreadMap;
 use crate::snapshot::Snapshot;
+use crate::utils::net::mac::MacAddr;
 use crate::utils::u64_to_usize;
 use crate::vmm_config::boot_source::BootSourceConfig;
 use crate::vmm_config::instance_info::InstanceInfo;
@@ -144,10 +146,22 @@ pub enum CreateSnapshotError {
     SerializeMicrovmState(#[from] crate::snapshot::SnapshotError),
     /// Cannot perform {0} on the snapshot backing file: {1}
     SnapshotBackingFile(&'static str, io::Error),
+    /// Cannot snapshot a microVM with VFIO devices, whose state lives in the host devices
+    VfioDevicesAttached,
+    /// Cannot snapshot a microVM with USB devices, whose state lives in the host devices
+    UsbDevicesAttached,
+    /// Cannot snapshot a microVM with NVMe controllers, whose state is not saved
+    NvmeDevicesAttached,
+    /// Cannot snapshot a microVM exposing an SGX enclave page cache, whose contents cannot be read
+    SgxEpcExposed,
+    /// Cannot snapshot a microVM with nested virtualization, whose nested guest state is not saved
+    NestedVirtualization,
+    /// Cannot snapshot a microVM with a split irqchip, whose IOAPIC state is not saved
+    SplitIrqchip,
 }
 
 /// Snapshot version
-pub const SNAPSHOT_VERSION: Version = Version::new(8, 0, 0);
+pub const SNAPSHOT_VERSION: Version = Version::new(9, 0, 0);
 
 /// Creates a Microvm snapshot.
 pub fn create_snapshot(
@@ -155,6 +169,24 @@ pub fn create_snapshot(
     vm_info: &VmInfo,
     params: &CreateSnapshotParams,
 ) -> Result<(), CreateSnapshotError> {
+    if !vmm.device_manager.pci_devices.vfio_devices.is_empty() {
+        return Err(CreateSnapshotError::VfioDevicesAttached);
+    }
+    if vmm.device_manager.pci_devices.xhci_controller.is_some() {
+        return Err(CreateSnapshotError::UsbDevicesAttached);
+    }
+    if !vmm.device_manager.pci_devices.nvme_controllers.is_empty() {
+        return Err(CreateSnapshotError::NvmeDevicesAttached);
+    }
+    #[cfg(target_arch = "x86_64")]
+    if vmm.vm.sgx_epc().is_some() {
+        return Err(CreateSnapshotError::SgxEpcExposed);
+    }
+    #[cfg(target_arch = "x86_64")]
+    if vmm.vm.split_irqchip() {
+        return Err(CreateSnapshotError::SplitIrqchip);
+    }
+
     let microvm_state = vmm
         .save_state(vm_info)
         .map_err(CreateSnapshotError::MicrovmState)?;
@@ -330,6 +362,10 @@ pub enum RestoreFromSnapshotError {
     GuestMemory(#[from] RestoreFromSnapshotGuestMemoryError),
     /// Failed to build microVM from snapshot: {0}
     Build(#[from] BuildMicrovmFromSnapshotError),
+    /// Failed to generate a MAC address for the clone: {0}
+    CloneMac(aws_lc_rs::error::Unspecified),
+    /// Failed to publish the clone identity to MMDS: {0}
+    CloneMmds(MmdsDatastoreError),
 }
 /// Sub-Error type for [`restore_from_snapshot`] to contain either [`GuestMemoryFromFileError`] or
 /// [`GuestMemoryFromUffdError`] within [`RestoreFromSnapshotError`].
@@ -369,6 +405,10 @@ pub fn restore_from_snapshot(
             .map(|device_state| device_state.tap_if_name.clone_from(&entry.host_dev_name))
             .ok_or(SnapshotStateFromFileError::UnknownNetworkDevice)?;
     }
+    let clone_macs = match params.clone {
+        Some(_) => regenerate_guest_macs(&mut microvm_state)?,
+        None => Vec::new(),
+    };
     let track_dirty_pages = params.track_dirty_pages;
 
     let vcpu_count = microvm_state
@@ -386,6 +426,11 @@ pub fn restore_from_snapshot(
             cpu_template: Some(microvm_state.vm_info.cpu_template),
             track_dirty_pages: Some(track_dirty_pages),
             huge_pages: Some(microvm_state.vm_info.huge_pages),
+            // MicroVMs with nested virtualization cannot be snapshotted.
+            nested_virtualization: Some(false),
+            // MicroVMs with a split irqchip cannot be snapshotted either.
+            split_irqchip: Some(false),
+            legacy_free: Some(false),
             #[cfg(feature = "gdb")]
             gdb_socket_path: None,
         })
@@ -419,7 +464,7 @@ pub fn restore_from_snapshot(
         )
         .map_err(RestoreFromSnapshotGuestMemoryError::Uffd)?,
     };
-    builder::build_microvm_from_snapshot(
+    let vmm = builder::build_microvm_from_snapshot(
         instance_info,
         event_manager,
         microvm_state,
@@ -427,8 +472,86 @@ pub fn restore_from_snapshot(
         uffd,
         seccomp_filters,
         vm_resources,
-    )
-    .map_err(RestoreFromSnapshotError::Build)
+    )?;
+
+    // The generation ID was already regenerated and announced to the guest when restoring the
+    // VMGenID device, and the entropy device carries no state, so only the network identity and
+    // the MMDS contents need to change for a clone.
+    if let Some(clone) = &params.clone {
+        publish_clone_identity(vm_resources, &clone.clone_id, &clone_macs)?;
+        info!("Restored microVM as clone {}", clone.clone_id);
+    }
+
+    Ok(vmm)
+}
+
+/// Gives every network device that exposes a MAC address to the guest a fresh, random,
+/// locally administered unicast one. Returns the new addresses keyed by interface ID.
+fn regenerate_guest_macs(
+    microvm_state: &mut MicrovmState,
+) -> Result<Vec<(String, MacAddr)>, RestoreFromSnapshotError> {
+    let net_states = microvm_state
+        .device_states
+        .mmio_state
+        .net_devices
+        .iter_mut()
+        .map(|device| &mut device.device_state)
+        .chain(
+            microvm_state
+                .device_states
+                .pci_state
+                .net_devices
+                .iter_mut()
+                .map(|device| &mut device.device_state),
+        );
+
+    let mut macs = Vec::new();
+    for net_state in net_states {
+        let mut bytes = [0u8; 6];
+        aws_lc_rs::rand::fill(&mut bytes).map_err(RestoreFromSnapshotError::CloneMac)?;
+        // Clear the multicast bit and set the locally administered one.
+        bytes[0] = (bytes[0] & !0x01) | 0x02;
+        let mac = MacAddr::from(bytes);
+        if net_state.replace_guest_mac(mac) {
+            macs.push((net_state.id.clone(), mac));
+        }
+    }
+    Ok(macs)
+}
+
+/// Merges the clone identity into the MMDS data store, if the microVM has one, under the
+/// `clone` key.
+fn publish_clone_identity(
+    vm_resources: &VmResources,
+    clone_id: &str,
+    macs: &[(String, MacAddr)],
+) -> Result<(), RestoreFromSnapshotError> {
+    let Some(mmds) = vm_resources.mmds.as_ref() else {
+        return Ok(());
+    };
+
+    let interfaces: serde_json::Map<String, serde_json::Value> = macs
+        .iter()
+        .map(|(iface_id, mac)| {
+            (
+                iface_id.clone(),
+                serde_json::json!({ "mac": mac.to_string() }),
+            )
+        })
+        .collect();
+    let identity = serde_json::json!({
+        "clone": {
+            "id": clone_id,
+            "network_interfaces": interfaces,
+        }
+    });
+
+    let mut mmds = mmds.lock().expect("Poisoned lock");
+    match mmds.patch_data(identity.clone()) {
+        Err(MmdsDatastoreError::NotInitialized) => mmds.put_data(identity),
+        res => res,
+    }
+    .map_err(RestoreFromSnapshotError::CloneMmds)
 }
 
 /// Error type for [`snapshot_state_from_file`]
@@ -617,7 +740,7 @@ mod tests {
     use crate::construct_kvm_mpidrs;
     use crate::devices::virtio::block::CacheType;
     use crate::snapshot::Persist;
-    use crate::vmm_config::balloon::BalloonDeviceConfig;
+    use crate::vmm_config::balloon::{BalloonDeviceConfig, FreePageAdvice};
     use crate::vmm_config::net::NetworkInterfaceConfig;
     use crate::vmm_config::vsock::tests::default_config;
     use crate::vstate::memory::{GuestMemoryRegionState, GuestRegionType};
@@ -634,6 +757,8 @@ mod tests {
             stats_polling_interval_s: 0,
             free_page_hinting: false,
             free_page_reporting: false,
+            free_page_advice: FreePageAdvice::DontNeed,
+            ksm: false,
         };
         insert_balloon_device(&mut vmm, &mut cmdline, &mut event_manager, balloon_config);
 
@@ -655,6 +780,7 @@ mod tests {
             guest_mac: None,
             rx_rate_limiter: None,
             tx_rate_limiter: None,
+            vhost: false,
         };
         insert_net_device(
             &mut vmm,
@@ -784,4 +910,43 @@ mod tests {
 
         assert_eq!(uffd_regions, deserialized);
     }
+
+    #[test]
+    fn test_publish_clone_identity() {
+        let mac = MacAddr::from_bytes_unchecked(&[0x02, 0x11, 0x22, 0x33, 0x44, 0x55]);
+        let macs = vec![(String::from("eth0"), mac)];
+
+        // Without an MMDS data store there is nothing to publish.
+        let mut vm_resources = VmResources::default();
+        publish_clone_identity(&vm_resources, "clone-1", &macs).unwrap();
+        assert!(vm_resources.mmds.is_none());
+
+        // The identity is written to an empty data store ...
+        vm_resources.mmds_size_limit = 51200;
+        let mmds = vm_resources.mmds_or_default().unwrap().clone();
+        publish_clone_identity(&vm_resources, "clone-1", &macs).unwrap();
+        assert_eq!(
+            mmds.lock().unwrap().data_store_value(),
+            serde_json::json!({
+                "clone": {
+                    "id": "clone-1",
+                    "network_interfaces": { "eth0": { "mac": "02:11:22:33:44:55" } }
+                }
+            })
+        );
+
+        // ... and merged into an existing one.
+        mmds.lock()
+            .unwrap()
+            .put_data(serde_json::json!({ "latest": { "ami-id": "a" } }))
+            .unwrap();
+        publish_clone_identity(&vm_resources, "clone-2", &[]).unwrap();
+        assert_eq!(
+            mmds.lock().unwrap().data_store_value(),
+            serde_json::json!({
+                "latest": { "ami-id": "a" },
+                "clone": { "id": "clone-2", "network_interfaces": {} }
+            })
+        );
+    }
 }
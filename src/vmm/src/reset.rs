@@ -0,0 +1,158 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Warm reset of the microVM when the guest reboots.
+//!
+//! Right before the vCPUs are started for the first time, the state of the vCPUs and of the
+//! interrupt controllers, together with the guest pages populated by the VMM (kernel, initrd,
+//! boot parameters, ACPI tables...), are captured in a [`BootImage`]. When the guest then asks
+//! for a platform reset, the VMM resets the VirtIO devices, drops all guest memory, writes the
+//! boot pages back and restores the captured state, so that the guest boots again from scratch
+//! without the clawdbox process exiting.
+
+use std::sync::Arc;
+
+use vm_memory::GuestMemoryError;
+
+use crate::arch::{ArchVmError, VmState, host_page_size};
+use crate::logger::info;
+use crate::vstate::memory::{
+    Address, Bytes, GuestAddress, GuestMemory, GuestMemoryExtension, GuestMemoryMmap,
+};
+use crate::vstate::vcpu::{KvmVcpuError, Vcpu, VcpuError, VcpuEvent, VcpuResponse, VcpuState};
+use crate::vstate::vm::{Vm, VmError, mincore_bitmap};
+use crate::{RECV_TIMEOUT_SEC, Vmm, VmmError};
+
+/// Errors associated with resetting the microVM in place.
+#[derive(Debug, thiserror::Error, displaydoc::Display)]
+pub enum ResetError {
+    /// Cannot save the boot state of a vCPU: {0}
+    SaveVcpuState(KvmVcpuError),
+    /// Cannot save the boot state of the VM: {0}
+    SaveVmState(ArchVmError),
+    /// Cannot get the resident guest pages: {0}
+    ResidentPages(#[from] VmError),
+    /// Cannot pause the microVM: {0}
+    Pause(VmmError),
+    /// Cannot resume the microVM: {0}
+    Resume(VmmError),
+    /// Device {0} does not support being reset
+    UnsupportedDevice(String),
+    /// Cannot reset the guest memory: {0}
+    Memory(#[from] GuestMemoryError),
+    /// Cannot restore the interrupt controllers: {0}
+    RestoreVmState(ArchVmError),
+    /// Cannot signal a vCPU: {0}
+    SignalVcpu(vmm_sys_util::errno::Error),
+    /// Cannot restore the state of a vCPU: {0}
+    RestoreVcpuState(VcpuError),
+    /// Unexpected response from a vCPU
+    UnexpectedVcpuResponse,
+}
+
+/// State of the microVM right before its vCPUs first run, which a reset goes back to.
+#[derive(Debug)]
+pub struct BootImage {
+    vcpu_states: Vec<Arc<VcpuState>>,
+    vm_state: VmState,
+    pages: Vec<(GuestAddress, Vec<u8>)>,
+}
+
+impl BootImage {
+    /// Captures the boot state of a microVM whose vCPUs are configured but not started yet.
+    pub fn capture(vm: &Vm, vcpus: &[Vcpu]) -> Result<Self, ResetError> {
+        let vcpu_states = vcpus
+            .iter()
+            .map(|vcpu| vcpu.kvm_vcpu.save_state().map(Arc::new))
+            .collect::<Result<_, _>>()
+            .map_err(ResetError::SaveVcpuState)?;
+        let vm_state = vm.save_state().map_err(ResetError::SaveVmState)?;
+        let pages = resident_pages(vm.guest_memory())?;
+
+        Ok(Self {
+            vcpu_states,
+            vm_state,
+            pages,
+        })
+    }
+}
+
+// Copies the runs of resident pages of the plugged memory slots. Right after the VMM has
+// configured the microVM, these are exactly the pages it wrote to.
+fn resident_pages(mem: &GuestMemoryMmap) -> Result<Vec<(GuestAddress, Vec<u8>)>, ResetError> {
+    let page_size = host_page_size();
+    let mut pages = Vec::new();
+
+    for slot in mem.iter().flat_map(|region| region.plugged_slots()) {
+        let bitmap = mincore_bitmap(slot.slice.ptr_guard_mut().as_ptr(), slot.slice.len())?;
+        let page_count = slot.slice.len() / page_size;
+        let mut run_start = None;
+
+        for page in 0..=page_count {
+            let resident = page < page_count && (bitmap[page / 64] >> (page % 64)) & 1 != 0;
+            match (resident, run_start) {
+                (true, None) => run_start = Some(page),
+                (false, Some(start)) => {
+                    let offset = start * page_size;
+                    let mut data = vec![0u8; (page - start) * page_size];
+                    slot.slice.subslice(offset, data.len())?.copy_to(&mut data);
+                    pages.push((slot.guest_addr.unchecked_add(offset as u64), data));
+                    run_start = None;
+                }
+                _ => (),
+            }
+        }
+    }
+
+    Ok(pages)
+}
+
+impl Vmm {
+    /// Resets the microVM to its boot state, so that the guest boots again.
+    pub fn warm_reset(&mut self) -> Result<(), ResetError> {
+        let image = self
+            .boot_image
+            .take()
+            .expect("Reset requested without a boot image");
+        let result = self.reset_to(&image);
+        self.boot_image = Some(image);
+        result
+    }
+
+    fn reset_to(&mut self, image: &BootImage) -> Result<(), ResetError> {
+        self.pause_vm().map_err(ResetError::Pause)?;
+
+        self.device_manager
+            .reset_virtio_devices()
+            .map_err(ResetError::UnsupportedDevice)?;
+
+        let mem = self.vm.guest_memory();
+        for slot in mem.iter().flat_map(|region| region.plugged_slots()) {
+            mem.discard_range(slot.guest_addr, slot.slice.len())?;
+        }
+        for (addr, data) in &image.pages {
+            mem.write_slice(data, *addr)?;
+        }
+
+        self.vm
+            .restore_irqchip_state(&image.vm_state)
+            .map_err(ResetError::RestoreVmState)?;
+
+        for (handle, state) in self.vcpus_handles.iter_mut().zip(&image.vcpu_states) {
+            handle
+                .send_event(VcpuEvent::RestoreState(state.clone()))
+                .map_err(ResetError::SignalVcpu)?;
+        }
+        for handle in &self.vcpus_handles {
+            match handle.response_receiver().recv_timeout(RECV_TIMEOUT_SEC) {
+                Ok(VcpuResponse::RestoredState) => (),
+                Ok(VcpuResponse::Error(err)) => return Err(ResetError::RestoreVcpuState(err)),
+                _ => return Err(ResetError::UnexpectedVcpuResponse),
+            }
+        }
+
+        self.resume_vm().map_err(ResetError::Resume)?;
+        info!("Reset the microVM");
+        Ok(())
+    }
+}
@@ -16,21 +16,44 @@ use crate::mmds::data_store::{Mmds, MmdsVersion};
 use crate::mmds::ns::MmdsNetworkStack;
 use crate::utils::mib_to_bytes;
 use crate::utils::net::ipv4addr::is_link_local_valid;
+use crate::vmm_config::acpi::{AcpiTablesBuilder, AcpiTablesConfig, AcpiTablesConfigError};
+use crate::vmm_config::apei::{ApeiConfig, ApeiConfigError};
 use crate::vmm_config::balloon::*;
 use crate::vmm_config::boot_source::{
     BootConfig, BootSource, BootSourceConfig, BootSourceConfigError,
 };
+use crate::vmm_config::cppc::{CppcConfig, CppcConfigError};
 use crate::vmm_config::drive::*;
 use crate::vmm_config::entropy::*;
+use crate::vmm_config::fw_cfg::{FwCfgBuilder, FwCfgConfig, FwCfgConfigError};
 use crate::vmm_config::instance_info::InstanceInfo;
 use crate::vmm_config::machine_config::{MachineConfig, MachineConfigError, MachineConfigUpdate};
 use crate::vmm_config::memory_hotplug::{MemoryHotplugConfig, MemoryHotplugConfigError};
+use crate::vmm_config::memory_map::{MemoryMapConfig, MemoryMapConfigError, MemoryMapRegion};
 use crate::vmm_config::metrics::{MetricsConfig, MetricsConfigError, init_metrics};
 use crate::vmm_config::mmds::{MmdsConfig, MmdsConfigError};
 use crate::vmm_config::net::*;
+use crate::vmm_config::numa::{NumaConfigError, NumaNodeConfig, validate_numa_nodes};
+use crate::vmm_config::nvme::{NvmeConfigError, NvmeDeviceConfig, insert_nvme_config};
 use crate::vmm_config::pmem::{PmemBuilder, PmemConfig, PmemConfigError};
-use crate::vmm_config::serial::SerialConfig;
+use crate::vmm_config::pmu::{PmuConfig, PmuConfigError};
+use crate::vmm_config::power_supply::{PowerSupplyConfig, PowerSupplyConfigError};
+use crate::vmm_config::processor_aggregator::{
+    ProcessorAggregatorConfig, ProcessorAggregatorConfigError,
+};
+use crate::vmm_config::reboot::{RebootConfig, RebootConfigError, RebootPolicy};
+use crate::vmm_config::s2idle::{S2idleConfig, S2idleConfigError};
+use crate::vmm_config::scheduled_actions::{
+    ScheduledActionKind, ScheduledActionsConfig, ScheduledActionsConfigError,
+};
+use crate::vmm_config::serial::{SerialConfig, SerialConfigError};
+use crate::vmm_config::sgx::{SgxConfig, SgxConfigError};
+use crate::vmm_config::smbios::{SmbiosConfig, SmbiosConfigError};
+use crate::vmm_config::thermal_zone::{ThermalZoneConfig, ThermalZoneConfigError};
+use crate::vmm_config::usb::{UsbConfigError, UsbDeviceConfig, insert_usb_config};
+use crate::vmm_config::vfio::{VfioConfig, VfioConfigError, VfioVfConfig, insert_vfio_config};
 use crate::vmm_config::vsock::*;
+use crate::vmm_config::watchdog::{WatchdogConfig, WatchdogConfigError};
 use crate::vstate::memory;
 use crate::vstate::memory::{GuestRegionMmap, MemoryError};
 
@@ -47,6 +70,10 @@ pub enum ResourcesError {
     File(#[from] std::io::Error),
     /// Invalid JSON: {0}
     InvalidJson(#[from] serde_json::Error),
+    /// Invalid YAML: {0}
+    InvalidYaml(#[from] serde_yaml::Error),
+    /// Invalid configuration: {0}
+    InvalidConfig(ConfigErrors),
     /// Logger error: {0}
     Logger(#[from] crate::logger::LoggerUpdateError),
     /// Metrics error: {0}
@@ -67,6 +94,90 @@ pub enum ResourcesError {
     PmemDevice(#[from] PmemConfigError),
     /// Memory hotplug config error: {0}
     MemoryHotplugConfig(#[from] MemoryHotplugConfigError),
+    /// ACPI tables config error: {0}
+    AcpiTablesConfig(#[from] AcpiTablesConfigError),
+    /// fw_cfg config error: {0}
+    FwCfgConfig(#[from] FwCfgConfigError),
+    /// VFIO device config error: {0}
+    VfioConfig(#[from] VfioConfigError),
+    /// USB device config error: {0}
+    UsbConfig(#[from] UsbConfigError),
+    /// NVMe controller config error: {0}
+    NvmeConfig(#[from] NvmeConfigError),
+    /// Watchdog config error: {0}
+    WatchdogConfig(#[from] WatchdogConfigError),
+    /// APEI config error: {0}
+    ApeiConfig(#[from] ApeiConfigError),
+    /// Reboot config error: {0}
+    RebootConfig(#[from] RebootConfigError),
+    /// Power supply config error: {0}
+    PowerSupplyConfig(#[from] PowerSupplyConfigError),
+    /// Processor aggregator config error: {0}
+    ProcessorAggregatorConfig(#[from] ProcessorAggregatorConfigError),
+    /// SMBIOS config error: {0}
+    SmbiosConfig(#[from] SmbiosConfigError),
+    /// Memory map config error: {0}
+    MemoryMapConfig(#[from] MemoryMapConfigError),
+    /// SGX config error: {0}
+    SgxConfig(#[from] SgxConfigError),
+    /// Suspend-to-idle config error: {0}
+    S2idleConfig(#[from] S2idleConfigError),
+    /// PMU config error: {0}
+    PmuConfig(#[from] PmuConfigError),
+    /// CPPC config error: {0}
+    CppcConfig(#[from] CppcConfigError),
+    /// Thermal zone config error: {0}
+    ThermalZoneConfig(#[from] ThermalZoneConfigError),
+    /// Scheduled actions config error: {0}
+    ScheduledActionsConfig(#[from] ScheduledActionsConfigError),
+    /// Serial config error: {0}
+    SerialConfig(#[from] SerialConfigError),
+    /// NUMA config error: {0}
+    NumaConfig(#[from] NumaConfigError),
+}
+
+// An error in a section of a configuration file.
+/// {section}: {error}
+#[derive(Debug, thiserror::Error, displaydoc::Display)]
+pub struct ConfigSectionError {
+    /// The section the error is in, such as `drives[rootfs]`.
+    pub section: String,
+    /// The error.
+    pub error: ResourcesError,
+}
+
+/// The errors found in the sections of a configuration file.
+#[derive(Debug, Default)]
+pub struct ConfigErrors(pub Vec<ConfigSectionError>);
+
+impl ConfigErrors {
+    /// Records the error of `result`, if any, as found in `section`.
+    fn check<T, E: Into<ResourcesError>>(
+        &mut self,
+        section: impl Into<String>,
+        result: Result<T, E>,
+    ) -> Option<T> {
+        result
+            .map_err(|error| {
+                self.0.push(ConfigSectionError {
+                    section: section.into(),
+                    error: error.into(),
+                })
+            })
+            .ok()
+    }
+}
+
+impl std::fmt::Display for ConfigErrors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (index, error) in self.0.iter().enumerate() {
+            if index > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{error}")?;
+        }
+        Ok(())
+    }
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Eq, Debug)]
@@ -97,6 +208,38 @@ pub struct VmmConfig {
     #[serde(skip)]
     serial_config: Option<SerialConfig>,
     memory_hotplug: Option<MemoryHotplugConfig>,
+    acpi_tables: Option<AcpiTablesConfig>,
+    fw_cfg: Option<FwCfgConfig>,
+    #[serde(default, rename = "vfio")]
+    vfio_devices: Vec<VfioConfig>,
+    #[serde(default, rename = "vfio-vf")]
+    vfio_vfs: Vec<VfioVfConfig>,
+    #[serde(default, rename = "usb")]
+    usb_devices: Vec<UsbDeviceConfig>,
+    #[serde(default, rename = "nvme")]
+    nvme_devices: Vec<NvmeDeviceConfig>,
+    watchdog: Option<WatchdogConfig>,
+    apei: Option<ApeiConfig>,
+    reboot: Option<RebootConfig>,
+    power_supply: Option<PowerSupplyConfig>,
+    processor_aggregator: Option<ProcessorAggregatorConfig>,
+    smbios: Option<SmbiosConfig>,
+    memory_map: Option<MemoryMapConfig>,
+    sgx: Option<SgxConfig>,
+    s2idle: Option<S2idleConfig>,
+    pmu: Option<PmuConfig>,
+    cppc: Option<CppcConfig>,
+    thermal_zone: Option<ThermalZoneConfig>,
+    #[serde(default)]
+    numa_nodes: Vec<NumaNodeConfig>,
+    scheduled_actions: Option<ScheduledActionsConfig>,
+}
+
+impl VmmConfig {
+    /// Returns the configuration of the vsock device, if any.
+    pub fn vsock(&self) -> Option<&VsockDeviceConfig> {
+        self.vsock.as_ref()
+    }
 }
 
 /// A data structure that encapsulates the device configurations
@@ -121,6 +264,44 @@ pub struct VmResources {
     pub pmem: PmemBuilder,
     /// The memory hotplug configuration.
     pub memory_hotplug: Option<MemoryHotplugConfig>,
+    /// User-supplied ACPI tables.
+    pub acpi_tables: AcpiTablesBuilder,
+    /// Files of the fw_cfg device.
+    pub fw_cfg: FwCfgBuilder,
+    /// The host PCI devices passed through with VFIO.
+    pub vfio_devices: Vec<VfioConfig>,
+    /// The USB devices attached to the xHCI controller.
+    pub usb_devices: Vec<UsbDeviceConfig>,
+    /// The emulated NVMe controllers.
+    pub nvme_devices: Vec<NvmeDeviceConfig>,
+    /// The watchdog configuration.
+    pub watchdog: Option<WatchdogConfig>,
+    /// The configuration of the forwarding of host memory errors to the guest.
+    pub apei: Option<ApeiConfig>,
+    /// The action taken when the guest reboots.
+    pub reboot: Option<RebootConfig>,
+    /// The battery and AC adapter configuration.
+    pub power_supply: Option<PowerSupplyConfig>,
+    /// The processor aggregator configuration.
+    pub processor_aggregator: Option<ProcessorAggregatorConfig>,
+    /// The SMBIOS configuration.
+    pub smbios: Option<SmbiosConfig>,
+    /// The regions declared in the guest memory map.
+    pub memory_map: Option<MemoryMapConfig>,
+    /// The SGX enclave page cache configuration.
+    pub sgx: Option<SgxConfig>,
+    /// The suspend-to-idle configuration.
+    pub s2idle: Option<S2idleConfig>,
+    /// The virtual PMU configuration.
+    pub pmu: Option<PmuConfig>,
+    /// The CPPC performance controls configuration.
+    pub cppc: Option<CppcConfig>,
+    /// The thermal zone configuration.
+    pub thermal_zone: Option<ThermalZoneConfig>,
+    /// The NUMA nodes of the guest, in the order their memory is laid out in.
+    pub numa_nodes: Vec<NumaNodeConfig>,
+    /// The actions taken on timers and events of the guest.
+    pub scheduled_actions: Option<ScheduledActionsConfig>,
     /// The optional Mmds data store.
     // This is initialised on demand (if ever used), so that we don't allocate it unless it's
     // actually used.
@@ -132,25 +313,33 @@ pub struct VmResources {
     /// Whether or not to use PCIe transport for VirtIO devices.
     pub pci_enabled: bool,
     /// Where serial console output should be written to
-    pub serial_out_path: Option<PathBuf>,
+    pub serial: SerialConfig,
 }
 
 impl VmResources {
     /// Configures Vmm resources as described by the `config_json` param.
+    ///
+    /// Documents which are not JSON objects are parsed as YAML. Every section of the document is
+    /// validated, so that all the errors in it are reported at once.
     pub fn from_json(
         config_json: &str,
         instance_info: &InstanceInfo,
         mmds_size_limit: usize,
         metadata_json: Option<&str>,
     ) -> Result<Self, ResourcesError> {
-        let vmm_config = serde_json::from_str::<VmmConfig>(config_json)?;
+        let vmm_config = if config_json.trim_start().starts_with('{') {
+            serde_json::from_str::<VmmConfig>(config_json)?
+        } else {
+            serde_yaml::from_str::<VmmConfig>(config_json)?
+        };
+        let mut errors = ConfigErrors::default();
 
         if let Some(logger_config) = vmm_config.logger {
-            crate::logger::LOGGER.update(logger_config)?;
+            errors.check("logger", crate::logger::LOGGER.update(logger_config));
         }
 
         if let Some(metrics) = vmm_config.metrics {
-            init_metrics(metrics)?;
+            errors.check("metrics", init_metrics(metrics));
         }
 
         let mut resources: Self = Self {
@@ -159,39 +348,47 @@ impl VmResources {
         };
         if let Some(machine_config) = vmm_config.machine_config {
             let machine_config = MachineConfigUpdate::from(machine_config);
-            resources.update_machine_config(&machine_config)?;
+            errors.check(
+                "machine-config",
+                resources.update_machine_config(&machine_config),
+            );
         }
 
         if let Some(either) = vmm_config.cpu_config {
-            match either {
-                CustomCpuTemplateOrPath::Path(path) => {
-                    let cpu_config_json =
-                        std::fs::read_to_string(path).map_err(ResourcesError::File)?;
-                    let cpu_template = CustomCpuTemplate::try_from(cpu_config_json.as_str())?;
-                    resources.set_custom_cpu_template(cpu_template);
-                }
-                CustomCpuTemplateOrPath::Template(template) => {
-                    resources.set_custom_cpu_template(template)
-                }
+            let cpu_template = match either {
+                CustomCpuTemplateOrPath::Path(path) => std::fs::read_to_string(path)
+                    .map_err(ResourcesError::File)
+                    .and_then(|cpu_config_json| {
+                        Ok(CustomCpuTemplate::try_from(cpu_config_json.as_str())?)
+                    }),
+                CustomCpuTemplateOrPath::Template(template) => Ok(template),
+            };
+            if let Some(cpu_template) = errors.check("cpu-config", cpu_template) {
+                resources.set_custom_cpu_template(cpu_template);
             }
         }
 
-        resources.build_boot_source(vmm_config.boot_source)?;
+        errors.check(
+            "boot-source",
+            resources.build_boot_source(vmm_config.boot_source),
+        );
 
         for drive_config in vmm_config.drives.into_iter() {
-            resources.set_block_device(drive_config)?;
+            let section = format!("drives[{}]", drive_config.drive_id);
+            errors.check(section, resources.set_block_device(drive_config));
         }
 
         for net_config in vmm_config.network_interfaces.into_iter() {
-            resources.build_net_device(net_config)?;
+            let section = format!("network-interfaces[{}]", net_config.iface_id);
+            errors.check(section, resources.build_net_device(net_config));
         }
 
         if let Some(vsock_config) = vmm_config.vsock {
-            resources.set_vsock_device(vsock_config)?;
+            errors.check("vsock", resources.set_vsock_device(vsock_config));
         }
 
         if let Some(balloon_config) = vmm_config.balloon {
-            resources.set_balloon_device(balloon_config)?;
+            errors.check("balloon", resources.set_balloon_device(balloon_config));
         }
 
         // Init the data store from file, if present.
@@ -203,25 +400,140 @@ impl VmResources {
         }
 
         if let Some(mmds_config) = vmm_config.mmds_config {
-            resources.set_mmds_config(mmds_config, &instance_info.id)?;
+            errors.check(
+                "mmds-config",
+                resources.set_mmds_config(mmds_config, &instance_info.id),
+            );
         }
 
         if let Some(entropy_device_config) = vmm_config.entropy {
-            resources.build_entropy_device(entropy_device_config)?;
+            errors.check(
+                "entropy",
+                resources.build_entropy_device(entropy_device_config),
+            );
         }
 
         for pmem_config in vmm_config.pmem_devices.into_iter() {
-            resources.build_pmem_device(pmem_config)?;
+            let section = format!("pmem[{}]", pmem_config.id);
+            errors.check(section, resources.build_pmem_device(pmem_config));
         }
 
         if let Some(serial_cfg) = vmm_config.serial_config {
-            resources.serial_out_path = serial_cfg.serial_out_path;
+            errors.check("serial", resources.set_serial_config(serial_cfg));
         }
 
         if let Some(memory_hotplug_config) = vmm_config.memory_hotplug {
-            resources.set_memory_hotplug_config(memory_hotplug_config)?;
+            errors.check(
+                "memory-hotplug",
+                resources.set_memory_hotplug_config(memory_hotplug_config),
+            );
         }
 
+        if let Some(acpi_tables_config) = vmm_config.acpi_tables {
+            errors.check("acpi-tables", resources.set_acpi_tables(acpi_tables_config));
+        }
+
+        if let Some(fw_cfg_config) = vmm_config.fw_cfg {
+            errors.check("fw-cfg", resources.set_fw_cfg(fw_cfg_config));
+        }
+
+        for vfio_config in vmm_config.vfio_devices.into_iter() {
+            let section = format!("vfio[{}]", vfio_config.id);
+            errors.check(section, resources.set_vfio_device(vfio_config));
+        }
+
+        for vfio_vf_config in vmm_config.vfio_vfs.into_iter() {
+            let section = format!("vfio-vf[{}]", vfio_vf_config.id);
+            errors.check(section, resources.set_vfio_vf(vfio_vf_config));
+        }
+
+        for usb_config in vmm_config.usb_devices.into_iter() {
+            let section = format!("usb[{}]", usb_config.id);
+            errors.check(section, resources.set_usb_device(usb_config));
+        }
+
+        for nvme_config in vmm_config.nvme_devices.into_iter() {
+            let section = format!("nvme[{}]", nvme_config.id);
+            errors.check(section, resources.set_nvme_device(nvme_config));
+        }
+
+        if let Some(watchdog_config) = vmm_config.watchdog {
+            errors.check("watchdog", resources.set_watchdog_config(watchdog_config));
+        }
+
+        if let Some(apei_config) = vmm_config.apei {
+            errors.check("apei", resources.set_apei_config(apei_config));
+        }
+
+        if let Some(reboot_config) = vmm_config.reboot {
+            errors.check("reboot", resources.set_reboot_config(reboot_config));
+        }
+
+        if let Some(power_supply_config) = vmm_config.power_supply {
+            errors.check(
+                "power-supply",
+                resources.set_power_supply_config(power_supply_config),
+            );
+        }
+
+        if let Some(aggregator_config) = vmm_config.processor_aggregator {
+            errors.check(
+                "processor-aggregator",
+                resources.set_processor_aggregator_config(aggregator_config),
+            );
+        }
+
+        if let Some(smbios_config) = vmm_config.smbios {
+            errors.check("smbios", resources.set_smbios_config(smbios_config));
+        }
+
+        if let Some(memory_map_config) = vmm_config.memory_map {
+            errors.check(
+                "memory-map",
+                resources.set_memory_map_config(memory_map_config),
+            );
+        }
+
+        if let Some(sgx_config) = vmm_config.sgx {
+            errors.check("sgx", resources.set_sgx_config(sgx_config));
+        }
+
+        if let Some(s2idle_config) = vmm_config.s2idle {
+            errors.check("s2idle", resources.set_s2idle_config(s2idle_config));
+        }
+
+        if let Some(pmu_config) = vmm_config.pmu {
+            errors.check("pmu", resources.set_pmu_config(pmu_config));
+        }
+
+        if let Some(cppc_config) = vmm_config.cppc {
+            errors.check("cppc", resources.set_cppc_config(cppc_config));
+        }
+
+        if let Some(thermal_zone_config) = vmm_config.thermal_zone {
+            errors.check(
+                "thermal-zone",
+                resources.set_thermal_zone_config(thermal_zone_config),
+            );
+        }
+
+        // The topology is checked against the vCPUs, memory and memory hotplug set above.
+        errors.check(
+            "numa-nodes",
+            resources.set_numa_nodes(vmm_config.numa_nodes),
+        );
+
+        // The actions are checked against the balloon and machine configuration set above.
+        if let Some(scheduled_actions_config) = vmm_config.scheduled_actions {
+            errors.check(
+                "scheduled-actions",
+                resources.set_scheduled_actions_config(scheduled_actions_config),
+            );
+        }
+
+        if !errors.0.is_empty() {
+            return Err(ResourcesError::InvalidConfig(errors));
+        }
         Ok(resources)
     }
 
@@ -264,6 +576,18 @@ impl VmResources {
             return Err(MachineConfigError::IncompatibleBalloonSize);
         }
 
+        // The vCPUs and memory of the VM must remain those of its NUMA nodes, if any.
+        if validate_numa_nodes(
+            &self.numa_nodes,
+            updated.vcpu_count,
+            updated.mem_size_mib,
+            self.memory_hotplug.is_some(),
+        )
+        .is_err()
+        {
+            return Err(MachineConfigError::IncompatibleNumaTopology);
+        }
+
         self.machine_config = updated;
 
         Ok(())
@@ -387,6 +711,202 @@ impl VmResources {
         Ok(())
     }
 
+    /// Sets the user-supplied ACPI tables to be appended to the XSDT when the VM starts.
+    pub fn set_acpi_tables(
+        &mut self,
+        config: AcpiTablesConfig,
+    ) -> Result<(), AcpiTablesConfigError> {
+        self.acpi_tables.set(config)
+    }
+
+    /// Sets the files exposed to the guest through the fw_cfg device when the VM starts.
+    pub fn set_fw_cfg(&mut self, config: FwCfgConfig) -> Result<(), FwCfgConfigError> {
+        self.fw_cfg.set(config)
+    }
+
+    /// Sets a host PCI device to be passed through to the guest when the VM starts.
+    pub fn set_vfio_device(&mut self, config: VfioConfig) -> Result<(), VfioConfigError> {
+        insert_vfio_config(&mut self.vfio_devices, config)
+    }
+
+    /// Binds an SR-IOV virtual function of the host to vfio-pci, and sets it to be passed
+    /// through to the guest when the VM starts.
+    pub fn set_vfio_vf(&mut self, config: VfioVfConfig) -> Result<(), VfioConfigError> {
+        let vfio_config = config.bind()?;
+        self.set_vfio_device(vfio_config)
+    }
+
+    /// Sets a USB device to be attached to the xHCI controller when the VM starts.
+    pub fn set_usb_device(&mut self, config: UsbDeviceConfig) -> Result<(), UsbConfigError> {
+        insert_usb_config(&mut self.usb_devices, config)
+    }
+
+    /// Sets an NVMe controller to be attached when the VM starts.
+    pub fn set_nvme_device(&mut self, config: NvmeDeviceConfig) -> Result<(), NvmeConfigError> {
+        insert_nvme_config(&mut self.nvme_devices, config)
+    }
+
+    /// Sets the watchdog configuration.
+    pub fn set_watchdog_config(
+        &mut self,
+        config: WatchdogConfig,
+    ) -> Result<(), WatchdogConfigError> {
+        config.validate()?;
+        self.watchdog = Some(config);
+        Ok(())
+    }
+
+    /// Sets the configuration of the forwarding of host memory errors to the guest.
+    pub fn set_apei_config(&mut self, config: ApeiConfig) -> Result<(), ApeiConfigError> {
+        config.validate()?;
+        self.apei = Some(config);
+        Ok(())
+    }
+
+    /// Sets the action taken when the guest reboots.
+    pub fn set_reboot_config(&mut self, config: RebootConfig) -> Result<(), RebootConfigError> {
+        config.validate()?;
+        self.reboot = Some(config);
+        Ok(())
+    }
+
+    /// Sets the configuration of the battery and AC adapter devices.
+    pub fn set_power_supply_config(
+        &mut self,
+        config: PowerSupplyConfig,
+    ) -> Result<(), PowerSupplyConfigError> {
+        config.validate()?;
+        self.power_supply = Some(config);
+        Ok(())
+    }
+
+    /// Sets the configuration of the processor aggregator device.
+    pub fn set_processor_aggregator_config(
+        &mut self,
+        config: ProcessorAggregatorConfig,
+    ) -> Result<(), ProcessorAggregatorConfigError> {
+        config.validate(self.machine_config.vcpu_count)?;
+        self.processor_aggregator = Some(config);
+        Ok(())
+    }
+
+    /// Sets the values reported to the guest through the SMBIOS tables.
+    pub fn set_smbios_config(&mut self, config: SmbiosConfig) -> Result<(), SmbiosConfigError> {
+        config.validate()?;
+        self.smbios = Some(config);
+        Ok(())
+    }
+
+    /// Sets the regions declared in the guest memory map.
+    pub fn set_memory_map_config(
+        &mut self,
+        config: MemoryMapConfig,
+    ) -> Result<(), MemoryMapConfigError> {
+        config.validate()?;
+        self.memory_map = Some(config);
+        Ok(())
+    }
+
+    /// Sets the SGX enclave page cache exposed to the guest.
+    pub fn set_sgx_config(&mut self, config: SgxConfig) -> Result<(), SgxConfigError> {
+        config.validate()?;
+        self.sgx = Some(config);
+        Ok(())
+    }
+
+    /// Sets where the serial console is written to.
+    pub fn set_serial_config(&mut self, config: SerialConfig) -> Result<(), SerialConfigError> {
+        config.validate()?;
+        self.serial = config;
+        Ok(())
+    }
+
+    /// Enables suspend-to-idle in the guest.
+    pub fn set_s2idle_config(&mut self, config: S2idleConfig) -> Result<(), S2idleConfigError> {
+        config.validate()?;
+        self.s2idle = Some(config);
+        Ok(())
+    }
+
+    /// Sets the virtual PMU exposed to the guest.
+    pub fn set_pmu_config(&mut self, config: PmuConfig) -> Result<(), PmuConfigError> {
+        config.validate()?;
+        self.pmu = Some(config);
+        Ok(())
+    }
+
+    /// Enables the CPPC performance controls of the guest.
+    pub fn set_cppc_config(&mut self, config: CppcConfig) -> Result<(), CppcConfigError> {
+        config.validate()?;
+        self.cppc = Some(config);
+        Ok(())
+    }
+
+    /// Sets the thermal zone exposed to the guest.
+    pub fn set_thermal_zone_config(
+        &mut self,
+        config: ThermalZoneConfig,
+    ) -> Result<(), ThermalZoneConfigError> {
+        config.validate()?;
+        self.thermal_zone = Some(config);
+        Ok(())
+    }
+
+    /// Sets the NUMA topology of the guest.
+    pub fn set_numa_nodes(&mut self, nodes: Vec<NumaNodeConfig>) -> Result<(), NumaConfigError> {
+        self.numa_nodes = validate_numa_nodes(
+            &nodes,
+            self.machine_config.vcpu_count,
+            self.machine_config.mem_size_mib,
+            self.memory_hotplug.is_some(),
+        )?;
+        Ok(())
+    }
+
+    /// Sets the actions taken on timers and events of the guest.
+    pub fn set_scheduled_actions_config(
+        &mut self,
+        config: ScheduledActionsConfig,
+    ) -> Result<(), ScheduledActionsConfigError> {
+        config.validate()?;
+        let balloon = self.balloon.get_config().ok();
+        for (index, action) in config.actions.iter().enumerate() {
+            if action.needs_balloon_stats() {
+                match &balloon {
+                    None => return Err(ScheduledActionsConfigError::MissingBalloon(index)),
+                    Some(balloon) if balloon.stats_polling_interval_s == 0 => {
+                        return Err(ScheduledActionsConfigError::MissingBalloonStats(index));
+                    }
+                    Some(_) => (),
+                }
+            }
+            if action.action == ScheduledActionKind::DiffSnapshot
+                && !self.machine_config.track_dirty_pages
+            {
+                return Err(ScheduledActionsConfigError::DirtyPageTrackingDisabled(
+                    index,
+                ));
+            }
+        }
+        self.scheduled_actions = Some(config);
+        Ok(())
+    }
+
+    /// Returns the regions declared in the guest memory map.
+    pub fn memory_map_regions(&self) -> &[MemoryMapRegion] {
+        self.memory_map
+            .as_ref()
+            .map(|config| config.regions.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Whether guest reboots reset the microVM in place.
+    pub fn reset_on_reboot(&self) -> bool {
+        self.reboot
+            .as_ref()
+            .is_some_and(|config| config.policy == RebootPolicy::Reset)
+    }
+
     /// Setter for mmds config.
     pub fn set_mmds_config(
         &mut self,
@@ -536,6 +1056,27 @@ impl From<&VmResources> for VmmConfig {
             // serial_config is marked serde(skip) so that it doesnt end up in snapshots.
             serial_config: None,
             memory_hotplug: resources.memory_hotplug.clone(),
+            acpi_tables: resources.acpi_tables.config(),
+            fw_cfg: resources.fw_cfg.config(),
+            vfio_devices: resources.vfio_devices.clone(),
+            // Virtual functions are resolved to the VFIO devices passing them through.
+            vfio_vfs: Vec::new(),
+            usb_devices: resources.usb_devices.clone(),
+            nvme_devices: resources.nvme_devices.clone(),
+            watchdog: resources.watchdog.clone(),
+            apei: resources.apei.clone(),
+            reboot: resources.reboot.clone(),
+            power_supply: resources.power_supply.clone(),
+            processor_aggregator: resources.processor_aggregator.clone(),
+            smbios: resources.smbios.clone(),
+            memory_map: resources.memory_map.clone(),
+            sgx: resources.sgx.clone(),
+            s2idle: resources.s2idle.clone(),
+            pmu: resources.pmu.clone(),
+            cppc: resources.cppc.clone(),
+            thermal_zone: resources.thermal_zone.clone(),
+            numa_nodes: resources.numa_nodes.clone(),
+            scheduled_actions: resources.scheduled_actions.clone(),
         }
     }
 }
@@ -583,6 +1124,7 @@ mod tests {
             guest_mac: Some(MacAddr::from_str("01:23:45:67:89:0a").unwrap()),
             rx_rate_limiter: Some(RateLimiterConfig::default()),
             tx_rate_limiter: Some(RateLimiterConfig::default()),
+            vhost: false,
         }
     }
 
@@ -634,6 +1176,16 @@ mod tests {
         }
     }
 
+    // Returns the error of the single section of a configuration file found to be invalid.
+    fn section_error(error: ResourcesError) -> ResourcesError {
+        match error {
+            ResourcesError::InvalidConfig(mut errors) if errors.0.len() == 1 => {
+                errors.0.pop().unwrap().error
+            }
+            error => panic!("{error:?}"),
+        }
+    }
+
     fn default_vm_resources() -> VmResources {
         VmResources {
             machine_config: MachineConfig::default(),
@@ -648,8 +1200,27 @@ mod tests {
             entropy: Default::default(),
             pmem: Default::default(),
             pci_enabled: false,
-            serial_out_path: None,
+            serial: Default::default(),
             memory_hotplug: Default::default(),
+            acpi_tables: Default::default(),
+            fw_cfg: Default::default(),
+            vfio_devices: Default::default(),
+            usb_devices: Default::default(),
+            nvme_devices: Default::default(),
+            watchdog: Default::default(),
+            apei: Default::default(),
+            reboot: Default::default(),
+            power_supply: Default::default(),
+            processor_aggregator: Default::default(),
+            smbios: Default::default(),
+            memory_map: Default::default(),
+            sgx: Default::default(),
+            s2idle: Default::default(),
+            pmu: Default::default(),
+            cppc: Default::default(),
+            thermal_zone: Default::default(),
+            numa_nodes: Default::default(),
+            scheduled_actions: Default::default(),
         }
     }
 
@@ -668,7 +1239,7 @@ mod tests {
 
         // Invalid JSON string must yield a `serde_json` error.
         let error =
-            VmResources::from_json(r#"}"#, &default_instance_info, HTTP_MAX_PAYLOAD_SIZE, None)
+            VmResources::from_json(r#"{"#, &default_instance_info, HTTP_MAX_PAYLOAD_SIZE, None)
                 .unwrap_err();
         assert!(
             matches!(error, ResourcesError::InvalidJson(_)),
@@ -676,6 +1247,16 @@ mod tests {
             error
         );
 
+        // Documents which are not JSON objects are parsed as YAML.
+        let error =
+            VmResources::from_json(r#"}"#, &default_instance_info, HTTP_MAX_PAYLOAD_SIZE, None)
+                .unwrap_err();
+        assert!(
+            matches!(error, ResourcesError::InvalidYaml(_)),
+            "{:?}",
+            error
+        );
+
         // Valid JSON string without the configuration for kernel or rootfs
         // result in an invalid JSON error.
         let error =
@@ -713,6 +1294,7 @@ mod tests {
             None,
         )
         .unwrap_err();
+        let error = section_error(error);
         assert!(
             matches!(
                 error,
@@ -748,6 +1330,7 @@ mod tests {
             None,
         )
         .unwrap_err();
+        let error = section_error(error);
         assert!(
             matches!(
                 error,
@@ -830,6 +1413,7 @@ mod tests {
             None,
         )
         .unwrap_err();
+        let error = section_error(error);
         assert!(
             matches!(
                 error,
@@ -869,6 +1453,7 @@ mod tests {
             None,
         )
         .unwrap_err();
+        let error = section_error(error);
         assert!(
             matches!(
                 error,
@@ -908,6 +1493,7 @@ mod tests {
             None,
         )
         .unwrap_err();
+        let error = section_error(error);
         assert!(
             matches!(
                 error,
@@ -954,6 +1540,7 @@ mod tests {
             None,
         )
         .unwrap_err();
+        let error = section_error(error);
 
         assert!(
             matches!(
@@ -1076,6 +1663,146 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_from_yaml() {
+        let kernel_file = TempFile::new().unwrap();
+        let rootfs_file = TempFile::new().unwrap();
+        let default_instance_info = InstanceInfo::default();
+
+        // Every invalid section of the document is reported, not only the first one.
+        let yaml = format!(
+            r#"
+boot-source:
+  kernel_image_path: {}
+  boot_args: console=ttyS0 reboot=k panic=1 pci=off
+drives:
+  - drive_id: rootfs
+    path_on_host: /invalid/path
+    is_root_device: true
+    is_read_only: false
+machine-config:
+  vcpu_count: 2
+  mem_size_mib: 0
+"#,
+            kernel_file.as_path().to_str().unwrap(),
+        );
+        let error = VmResources::from_json(
+            yaml.as_str(),
+            &default_instance_info,
+            HTTP_MAX_PAYLOAD_SIZE,
+            None,
+        )
+        .unwrap_err();
+        let ResourcesError::InvalidConfig(errors) = error else {
+            panic!("{error:?}");
+        };
+        let sections: Vec<_> = errors
+            .0
+            .iter()
+            .map(|error| error.section.as_str())
+            .collect();
+        assert_eq!(sections, ["machine-config", "drives[rootfs]"]);
+
+        let yaml = format!(
+            r#"
+boot-source:
+  kernel_image_path: {}
+  boot_args: console=ttyS0 reboot=k panic=1 pci=off
+drives:
+  - drive_id: rootfs
+    path_on_host: {}
+    is_root_device: true
+    is_read_only: false
+machine-config:
+  vcpu_count: 2
+  mem_size_mib: 256
+  smt: false
+"#,
+            kernel_file.as_path().to_str().unwrap(),
+            rootfs_file.as_path().to_str().unwrap(),
+        );
+        let resources = VmResources::from_json(
+            yaml.as_str(),
+            &default_instance_info,
+            HTTP_MAX_PAYLOAD_SIZE,
+            None,
+        )
+        .unwrap();
+        assert_eq!(resources.machine_config.vcpu_count, 2);
+        assert!(resources.block.has_root_device());
+    }
+
+    #[test]
+    #[cfg(target_arch = "x86_64")]
+    fn test_numa_nodes() {
+        let kernel_file = TempFile::new().unwrap();
+        let rootfs_file = TempFile::new().unwrap();
+        let default_instance_info = InstanceInfo::default();
+
+        let config = |node1_mem_size_mib: usize| {
+            format!(
+                r#"
+boot-source:
+  kernel_image_path: {}
+drives:
+  - drive_id: rootfs
+    path_on_host: {}
+    is_root_device: true
+    is_read_only: false
+machine-config:
+  vcpu_count: 2
+  mem_size_mib: 256
+numa-nodes:
+  - node_id: 1
+    vcpus: [1]
+    mem_size_mib: {node1_mem_size_mib}
+  - node_id: 0
+    vcpus: [0]
+    mem_size_mib: 128
+"#,
+                kernel_file.as_path().to_str().unwrap(),
+                rootfs_file.as_path().to_str().unwrap(),
+            )
+        };
+
+        let mut resources = VmResources::from_json(
+            config(128).as_str(),
+            &default_instance_info,
+            HTTP_MAX_PAYLOAD_SIZE,
+            None,
+        )
+        .unwrap();
+        let node_ids: Vec<_> = resources
+            .numa_nodes
+            .iter()
+            .map(|node| node.node_id)
+            .collect();
+        assert_eq!(node_ids, [0, 1]);
+
+        // The vCPUs and memory cannot be changed away from those of the nodes afterwards.
+        let update = MachineConfigUpdate {
+            vcpu_count: Some(4),
+            ..Default::default()
+        };
+        assert_eq!(
+            resources.update_machine_config(&update),
+            Err(MachineConfigError::IncompatibleNumaTopology)
+        );
+
+        let error = VmResources::from_json(
+            config(64).as_str(),
+            &default_instance_info,
+            HTTP_MAX_PAYLOAD_SIZE,
+            None,
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid configuration: numa-nodes: NUMA config error: The NUMA nodes hold 192 MiB of \
+             memory in total, but the microVM has 256 MiB"
+        );
+    }
+
     #[test]
     fn test_cpu_config_from_invalid_json() {
         // Invalid cpu config file path.
@@ -1111,6 +1838,7 @@ mod tests {
             None,
         )
         .unwrap_err();
+        let error = section_error(error);
         assert!(matches!(error, ResourcesError::File(_)), "{:?}", error);
     }
 
@@ -1409,6 +2137,9 @@ mod tests {
             cpu_template: Some(StaticCpuTemplate::V1N1),
             track_dirty_pages: Some(false),
             huge_pages: Some(HugePageConfig::None),
+            nested_virtualization: Some(false),
+            split_irqchip: Some(false),
+            legacy_free: Some(false),
             #[cfg(feature = "gdb")]
             gdb_socket_path: None,
         };
@@ -1470,6 +2201,8 @@ mod tests {
                 stats_polling_interval_s: 0,
                 free_page_hinting: false,
                 free_page_reporting: false,
+                free_page_advice: FreePageAdvice::DontNeed,
+                ksm: false,
             })
             .unwrap();
         aux_vm_config.mem_size_mib = Some(90);
@@ -1510,6 +2243,8 @@ mod tests {
             stats_polling_interval_s: 0,
             free_page_hinting: false,
             free_page_reporting: false,
+            free_page_advice: FreePageAdvice::DontNeed,
+            ksm: false,
         };
         assert!(vm_resources.balloon.get().is_none());
         vm_resources
@@ -1535,6 +2270,53 @@ mod tests {
             .unwrap_err();
     }
 
+    #[test]
+    fn test_set_scheduled_actions_config() {
+        let mut vm_resources = default_vm_resources();
+        vm_resources.balloon = BalloonBuilder::new();
+        let config: ScheduledActionsConfig = serde_json::from_str(
+            r#"{"actions": [{"trigger": "guest_oom", "action": "pause"},
+                {"trigger": "timer", "interval_s": 60, "action": "diff_snapshot",
+                 "snapshot_path": "vmstate", "mem_file_path": "mem"}]}"#,
+        )
+        .unwrap();
+
+        // The OOM trigger relies on the statistics of the balloon.
+        assert_eq!(
+            vm_resources.set_scheduled_actions_config(config.clone()),
+            Err(ScheduledActionsConfigError::MissingBalloon(0))
+        );
+        let mut balloon_cfg = BalloonDeviceConfig {
+            amount_mib: 0,
+            deflate_on_oom: false,
+            stats_polling_interval_s: 0,
+            free_page_hinting: false,
+            free_page_reporting: false,
+            free_page_advice: FreePageAdvice::DontNeed,
+            ksm: false,
+        };
+        vm_resources
+            .set_balloon_device(balloon_cfg.clone())
+            .unwrap();
+        assert_eq!(
+            vm_resources.set_scheduled_actions_config(config.clone()),
+            Err(ScheduledActionsConfigError::MissingBalloonStats(0))
+        );
+        balloon_cfg.stats_polling_interval_s = 1;
+        vm_resources.set_balloon_device(balloon_cfg).unwrap();
+
+        // Diff snapshots rely on dirty page tracking.
+        assert_eq!(
+            vm_resources.set_scheduled_actions_config(config.clone()),
+            Err(ScheduledActionsConfigError::DirtyPageTrackingDisabled(1))
+        );
+        vm_resources.machine_config.track_dirty_pages = true;
+        vm_resources
+            .set_scheduled_actions_config(config.clone())
+            .unwrap();
+        assert_eq!(vm_resources.scheduled_actions, Some(config));
+    }
+
     #[test]
     fn test_set_entropy_device() {
         let mut vm_resources = default_vm_resources();
@@ -12,8 +12,14 @@ use super::persist::{create_snapshot, restore_from_snapshot};
 use super::resources::VmResources;
 use super::{Vmm, VmmError};
 use crate::EventManager;
-use crate::builder::StartMicrovmError;
+#[cfg(target_arch = "x86_64")]
+use crate::acpi::AcpiTablesDump;
+use crate::builder::{StartMicrovmError, build_guest_cpu_config};
+use crate::core_dump::{CoreDumpError, dump_guest_core};
+use crate::cpu_config::features::{CpuFeatures, CpuFeaturesError};
 use crate::cpu_config::templates::{CustomCpuTemplate, GuestConfigError};
+use crate::devices::acpi::processor_aggregator::ProcessorAggregatorStatus;
+use crate::devices::acpi::watchdog::WatchdogStatus;
 use crate::devices::virtio::balloon::device::{HintingStatus, StartHintingCmd};
 use crate::devices::virtio::mem::VirtioMemStatus;
 use crate::logger::{LoggerConfig, info, warn, *};
@@ -21,27 +27,54 @@ use crate::mmds::data_store::{self, Mmds};
 use crate::persist::{CreateSnapshotError, RestoreFromSnapshotError, VmInfo};
 use crate::resources::VmmConfig;
 use crate::seccomp::BpfThreadMap;
+use crate::vmm_config::acpi::{AcpiTablesConfig, AcpiTablesConfigError};
+use crate::vmm_config::apei::{ApeiConfig, ApeiConfigError};
 use crate::vmm_config::balloon::{
     BalloonConfigError, BalloonDeviceConfig, BalloonStats, BalloonUpdateConfig,
     BalloonUpdateStatsConfig,
 };
 use crate::vmm_config::boot_source::{BootSourceConfig, BootSourceConfigError};
+use crate::vmm_config::core_dump::CoreDumpParams;
+use crate::vmm_config::cppc::{CppcConfig, CppcConfigError};
 use crate::vmm_config::drive::{BlockDeviceConfig, BlockDeviceUpdateConfig, DriveError};
 use crate::vmm_config::entropy::{EntropyDeviceConfig, EntropyDeviceError};
+use crate::vmm_config::fw_cfg::{FwCfgConfig, FwCfgConfigError};
 use crate::vmm_config::instance_info::InstanceInfo;
 use crate::vmm_config::machine_config::{MachineConfig, MachineConfigError, MachineConfigUpdate};
 use crate::vmm_config::memory_hotplug::{
     MemoryHotplugConfig, MemoryHotplugConfigError, MemoryHotplugSizeUpdate,
 };
+use crate::vmm_config::memory_map::{MemoryMapConfig, MemoryMapConfigError};
 use crate::vmm_config::metrics::{MetricsConfig, MetricsConfigError};
 use crate::vmm_config::mmds::{MmdsConfig, MmdsConfigError};
 use crate::vmm_config::net::{
     NetworkInterfaceConfig, NetworkInterfaceError, NetworkInterfaceUpdateConfig,
 };
+#[cfg(target_arch = "x86_64")]
+use crate::vmm_config::nmi::InjectNmiParams;
+use crate::vmm_config::nvme::{NvmeConfigError, NvmeDeviceConfig};
 use crate::vmm_config::pmem::{PmemConfig, PmemConfigError};
-use crate::vmm_config::serial::SerialConfig;
+use crate::vmm_config::pmu::{PmuConfig, PmuConfigError};
+use crate::vmm_config::power_supply::{
+    PowerSupplyConfig, PowerSupplyConfigError, PowerSupplyUpdateConfig,
+};
+use crate::vmm_config::processor_aggregator::{
+    ProcessorAggregatorConfig, ProcessorAggregatorConfigError, ProcessorAggregatorUpdateConfig,
+};
+use crate::vmm_config::reboot::{RebootConfig, RebootConfigError};
+use crate::vmm_config::s2idle::{S2idleConfig, S2idleConfigError};
+use crate::vmm_config::scheduled_actions::{ScheduledActionsConfig, ScheduledActionsConfigError};
+use crate::vmm_config::serial::{SerialConfig, SerialConfigError};
+use crate::vmm_config::sgx::{SgxConfig, SgxConfigError};
+use crate::vmm_config::smbios::{SmbiosConfig, SmbiosConfigError};
 use crate::vmm_config::snapshot::{CreateSnapshotParams, LoadSnapshotParams, SnapshotType};
+use crate::vmm_config::thermal_zone::{
+    ThermalZoneConfig, ThermalZoneConfigError, ThermalZoneUpdateConfig,
+};
+use crate::vmm_config::usb::{UsbConfigError, UsbDeviceConfig};
+use crate::vmm_config::vfio::{VfioConfig, VfioConfigError, VfioVfConfig};
 use crate::vmm_config::vsock::{VsockConfigError, VsockDeviceConfig};
+use crate::vmm_config::watchdog::{WatchdogConfig, WatchdogConfigError};
 use crate::vmm_config::{self, RateLimiterUpdate};
 
 /// This enum represents the public interface of the VMM. Each action contains various
@@ -62,10 +95,16 @@ pub enum VmmAction {
     /// Create a snapshot using as input the `CreateSnapshotParams`. This action can only be called
     /// after the microVM has booted and only when the microVM is in `Paused` state.
     CreateSnapshot(CreateSnapshotParams),
+    /// Write an ELF core dump of the guest memory and vCPU registers using as input the
+    /// `CoreDumpParams`. This action can only be called after the microVM has booted.
+    DumpGuestCore(CoreDumpParams),
     /// Get the balloon device configuration.
     GetBalloonConfig,
     /// Get the ballon device latest statistics.
     GetBalloonStats,
+    /// Get the effective guest CPU configuration, after the CPU template is applied. After the
+    /// microVM has booted, this action can only be called when the microVM is in `Paused` state.
+    GetCpuFeatures,
     /// Get complete microVM configuration in JSON format.
     GetFullVmConfig,
     /// Get MMDS contents.
@@ -83,6 +122,21 @@ pub enum VmmAction {
     InsertBlockDevice(BlockDeviceConfig),
     /// Add a virtio-pmem device.
     InsertPmemDevice(PmemConfig),
+    /// Pass a host PCI device through to the guest with VFIO, or update one that was already
+    /// added, using `VfioConfig` as input. This action can only be called before the microVM has
+    /// booted.
+    InsertVfioDevice(VfioConfig),
+    /// Bind an SR-IOV virtual function of the host to vfio-pci and pass it through to the guest,
+    /// using `VfioVfConfig` as input. This action can only be called before the microVM has
+    /// booted.
+    InsertVfioVf(VfioVfConfig),
+    /// Attach a USB device to the xHCI controller of the guest, or update one that was already
+    /// added, using `UsbDeviceConfig` as input. This action can only be called before the microVM
+    /// has booted.
+    InsertUsbDevice(UsbDeviceConfig),
+    /// Add a new emulated NVMe controller or update one that already exists using the
+    /// `NvmeDeviceConfig` as input. This action can only be called before the microVM has booted.
+    InsertNvmeDevice(NvmeDeviceConfig),
     /// Add a new network interface config or update one that already exists using the
     /// `NetworkInterfaceConfig` as input. This action can only be called before the microVM has
     /// booted.
@@ -114,6 +168,12 @@ pub enum VmmAction {
     /// Set the entropy device using `EntropyDeviceConfig` as input. This action can only be called
     /// before the microVM has booted.
     SetEntropyDevice(EntropyDeviceConfig),
+    /// Set the user-supplied ACPI tables using `AcpiTablesConfig` as input. This action can only be
+    /// called before the microVM has booted.
+    SetAcpiTables(AcpiTablesConfig),
+    /// Set the files exposed through the fw_cfg device using `FwCfgConfig` as input. This action
+    /// can only be called before the microVM has booted.
+    SetFwCfg(FwCfgConfig),
     /// Get the memory hotplug device configuration and status.
     GetMemoryHotplugStatus,
     /// Set the memory hotplug device using `MemoryHotplugConfig` as input. This action can only be
@@ -122,12 +182,66 @@ pub enum VmmAction {
     /// Updates the memory hotplug device using `MemoryHotplugConfigUpdate` as input. This action
     /// can only be called after the microVM has booted.
     UpdateMemoryHotplugSize(MemoryHotplugSizeUpdate),
+    /// Get the status of the watchdog. This action can only be called after the microVM has
+    /// booted.
+    GetWatchdogStatus,
+    /// Get the ACPI tables written in guest memory. This action can only be called after the
+    /// microVM has booted.
+    #[cfg(target_arch = "x86_64")]
+    GetAcpiTables,
+    /// Set the watchdog using `WatchdogConfig` as input. This action can only be called before
+    /// the microVM has booted.
+    SetWatchdog(WatchdogConfig),
+    /// Set the forwarding of host memory errors to the guest using `ApeiConfig` as input. This
+    /// action can only be called before the microVM has booted.
+    SetApei(ApeiConfig),
+    /// Set the action taken when the guest reboots using `RebootConfig` as input. This action can
+    /// only be called before the microVM has booted.
+    SetReboot(RebootConfig),
+    /// Set the battery and AC adapter devices using `PowerSupplyConfig` as input. This action can
+    /// only be called before the microVM has booted.
+    SetPowerSupply(PowerSupplyConfig),
+    /// Get the status of the processor aggregator device. This action can only be called after
+    /// the microVM has booted.
+    GetProcessorAggregatorStatus,
+    /// Set the processor aggregator device using `ProcessorAggregatorConfig` as input. This action
+    /// can only be called before the microVM has booted.
+    SetProcessorAggregator(ProcessorAggregatorConfig),
+    /// Set the values reported through the SMBIOS tables using `SmbiosConfig` as input. This
+    /// action can only be called before the microVM has booted.
+    SetSmbios(SmbiosConfig),
+    /// Set the regions declared in the guest memory map using `MemoryMapConfig` as input. This
+    /// action can only be called before the microVM has booted.
+    SetMemoryMap(MemoryMapConfig),
+    /// Set the SGX enclave page cache exposed to the guest using `SgxConfig` as input. This
+    /// action can only be called before the microVM has booted.
+    SetSgx(SgxConfig),
+    /// Enable suspend-to-idle in the guest using `S2idleConfig` as input. This action can only be
+    /// called before the microVM has booted.
+    SetS2idle(S2idleConfig),
+    /// Expose the virtual PMU to the guest using `PmuConfig` as input. This action can only be
+    /// called before the microVM has booted.
+    SetPmu(PmuConfig),
+    /// Enable the CPPC performance controls of the guest using `CppcConfig` as input. This
+    /// action can only be called before the microVM has booted.
+    SetCppc(CppcConfig),
+    /// Set the thermal zone exposed to the guest using `ThermalZoneConfig` as input. This action
+    /// can only be called before the microVM has booted.
+    SetThermalZone(ThermalZoneConfig),
+    /// Set the actions taken on timers and events of the guest using `ScheduledActionsConfig`
+    /// as input. This action can only be called before the microVM has booted.
+    SetScheduledActions(ScheduledActionsConfig),
     /// Launch the microVM. This action can only be called before the microVM has booted.
     StartMicroVm,
     /// Send CTRL+ALT+DEL to the microVM, using the i8042 keyboard function. If an AT-keyboard
     /// driver is listening on the guest end, this can be used to shut down the microVM gracefully.
     #[cfg(target_arch = "x86_64")]
     SendCtrlAltDel,
+    /// Inject an NMI in one or all vCPUs using `InjectNmiParams` as input. Guests usually react
+    /// by panicking or by running their watchdog handlers. This action can only be called after
+    /// the microVM has booted.
+    #[cfg(target_arch = "x86_64")]
+    InjectNmi(InjectNmiParams),
     /// Update the balloon size, after microVM start.
     UpdateBalloon(BalloonUpdateConfig),
     /// Update the balloon statistics polling interval, after microVM start.
@@ -143,6 +257,16 @@ pub enum VmmAction {
     /// Update a network interface, after microVM start. Currently, the only updatable properties
     /// are the RX and TX rate limiters.
     UpdateNetworkInterface(NetworkInterfaceUpdateConfig),
+    /// Update the status of the battery and AC adapter using `PowerSupplyUpdateConfig` as input.
+    /// This action can only be called after the microVM has booted.
+    UpdatePowerSupply(PowerSupplyUpdateConfig),
+    /// Update the number of vCPUs the guest is asked to idle using
+    /// `ProcessorAggregatorUpdateConfig` as input. This action can only be called after the
+    /// microVM has booted.
+    UpdateProcessorAggregator(ProcessorAggregatorUpdateConfig),
+    /// Update the temperature of the thermal zone using `ThermalZoneUpdateConfig` as input. This
+    /// action can only be called after the microVM has booted.
+    UpdateThermalZone(ThermalZoneUpdateConfig),
     /// Update the microVM configuration (memory & vcpu) using `VmUpdateConfig` as input. This
     /// action can only be called before the microVM has booted.
     UpdateMachineConfiguration(MachineConfigUpdate),
@@ -151,6 +275,10 @@ pub enum VmmAction {
 /// Wrapper for all errors associated with VMM actions.
 #[derive(Debug, thiserror::Error, displaydoc::Display)]
 pub enum VmmActionError {
+    /// ACPI tables config error: {0}
+    AcpiTablesConfig(#[from] AcpiTablesConfigError),
+    /// APEI config error: {0}
+    ApeiConfig(#[from] ApeiConfigError),
     /// Balloon config error: {0}
     BalloonConfig(#[from] BalloonConfigError),
     /// Balloon update error: {0}
@@ -159,14 +287,26 @@ pub enum VmmActionError {
     BootSource(#[from] BootSourceConfigError),
     /// Create snapshot error: {0}
     CreateSnapshot(#[from] CreateSnapshotError),
+    /// Core dump error: {0}
+    CoreDump(#[from] CoreDumpError),
     /// Configure CPU error: {0}
     ConfigureCpu(#[from] GuestConfigError),
+    /// CPU features error: {0}
+    CpuFeatures(#[from] CpuFeaturesError),
     /// Drive config error: {0}
     DriveConfig(#[from] DriveError),
     /// Entropy device error: {0}
     EntropyDevice(#[from] EntropyDeviceError),
+    /// fw_cfg config error: {0}
+    FwCfgConfig(#[from] FwCfgConfigError),
     /// Pmem device error: {0}
     PmemDevice(#[from] PmemConfigError),
+    /// VFIO device error: {0}
+    VfioDevice(#[from] VfioConfigError),
+    /// USB device error: {0}
+    UsbDevice(#[from] UsbConfigError),
+    /// NVMe controller error: {0}
+    NvmeDevice(#[from] NvmeConfigError),
     /// Memory hotplug config error: {0}
     MemoryHotplugConfig(#[from] MemoryHotplugConfigError),
     /// Memory hotplug update error: {0}
@@ -197,10 +337,42 @@ pub enum VmmActionError {
     OperationNotSupportedPostBoot,
     /// The requested operation is not supported before starting the microVM.
     OperationNotSupportedPreBoot,
+    /// Power supply config error: {0}
+    PowerSupplyConfig(#[from] PowerSupplyConfigError),
+    /// Power supply update error: {0}
+    PowerSupplyUpdate(VmmError),
+    /// Processor aggregator config error: {0}
+    ProcessorAggregatorConfig(#[from] ProcessorAggregatorConfigError),
+    /// Processor aggregator update error: {0}
+    ProcessorAggregatorUpdate(VmmError),
+    /// Reboot config error: {0}
+    RebootConfig(#[from] RebootConfigError),
+    /// SMBIOS config error: {0}
+    SmbiosConfig(#[from] SmbiosConfigError),
+    /// Memory map config error: {0}
+    MemoryMapConfig(#[from] MemoryMapConfigError),
+    /// SGX config error: {0}
+    SgxConfig(#[from] SgxConfigError),
+    /// Suspend-to-idle config error: {0}
+    S2idleConfig(#[from] S2idleConfigError),
+    /// PMU config error: {0}
+    PmuConfig(#[from] PmuConfigError),
+    /// CPPC config error: {0}
+    CppcConfig(#[from] CppcConfigError),
+    /// Thermal zone config error: {0}
+    ThermalZoneConfig(#[from] ThermalZoneConfigError),
+    /// Thermal zone update error: {0}
+    ThermalZoneUpdate(VmmError),
+    /// Scheduled actions config error: {0}
+    ScheduledActionsConfig(#[from] ScheduledActionsConfigError),
+    /// Serial config error: {0}
+    SerialConfig(#[from] SerialConfigError),
     /// Start microvm error: {0}
     StartMicrovm(#[from] StartMicrovmError),
     /// Vsock config error: {0}
     VsockConfig(#[from] VsockConfigError),
+    /// Watchdog config error: {0}
+    WatchdogConfig(#[from] WatchdogConfigError),
 }
 
 /// The enum represents the response sent by the VMM in case of success. The response is either
@@ -212,6 +384,8 @@ pub enum VmmData {
     BalloonConfig(BalloonDeviceConfig),
     /// The latest balloon device statistics.
     BalloonStats(BalloonStats),
+    /// The effective guest CPU configuration.
+    CpuFeatures(CpuFeatures),
     /// No data is sent on the channel.
     Empty,
     /// The complete microVM configuration in JSON format.
@@ -228,6 +402,13 @@ pub enum VmmData {
     VirtioMemStatus(VirtioMemStatus),
     /// The status of the virtio-balloon hinting run
     HintingStatus(HintingStatus),
+    /// The status of the watchdog.
+    WatchdogStatus(WatchdogStatus),
+    /// The status of the processor aggregator device.
+    ProcessorAggregatorStatus(ProcessorAggregatorStatus),
+    /// The ACPI tables written in guest memory.
+    #[cfg(target_arch = "x86_64")]
+    AcpiTables(AcpiTablesDump),
 }
 
 /// Trait used for deduplicating the MMDS request handling across the two ApiControllers.
@@ -445,11 +626,15 @@ impl<'a> PrebootApiController<'a> {
             ConfigureMetrics(metrics_cfg) => vmm_config::metrics::init_metrics(metrics_cfg)
                 .map(|()| VmmData::Empty)
                 .map_err(VmmActionError::Metrics),
-            ConfigureSerial(serial_cfg) => {
-                self.vm_resources.serial_out_path = serial_cfg.serial_out_path;
-                Ok(VmmData::Empty)
-            }
+            ConfigureSerial(serial_cfg) => self
+                .vm_resources
+                .set_serial_config(serial_cfg)
+                .map(|()| VmmData::Empty)
+                .map_err(VmmActionError::SerialConfig),
             GetBalloonConfig => self.balloon_config(),
+            GetCpuFeatures => build_guest_cpu_config(self.vm_resources)
+                .map(|cpu_config| VmmData::CpuFeatures((&cpu_config).into()))
+                .map_err(|err| VmmActionError::CpuFeatures(err.into())),
             GetFullVmConfig => {
                 warn!(
                     "If the VM was restored from snapshot, boot-source, machine-config.smt, and \
@@ -465,6 +650,10 @@ impl<'a> PrebootApiController<'a> {
             GetVmmVersion => Ok(VmmData::VmmVersion(self.instance_info.vmm_version.clone())),
             InsertBlockDevice(config) => self.insert_block_device(config),
             InsertPmemDevice(config) => self.insert_pmem_device(config),
+            InsertVfioDevice(config) => self.insert_vfio_device(config),
+            InsertVfioVf(config) => self.insert_vfio_vf(config),
+            InsertUsbDevice(config) => self.insert_usb_device(config),
+            InsertNvmeDevice(config) => self.insert_nvme_device(config),
             InsertNetworkDevice(config) => self.insert_net_device(config),
             LoadSnapshot(config) => self
                 .load_snapshot(&config)
@@ -481,8 +670,24 @@ impl<'a> PrebootApiController<'a> {
             UpdateMachineConfiguration(config) => self.update_machine_config(config),
             SetEntropyDevice(config) => self.set_entropy_device(config),
             SetMemoryHotplugDevice(config) => self.set_memory_hotplug_device(config),
+            SetAcpiTables(config) => self.set_acpi_tables(config),
+            SetFwCfg(config) => self.set_fw_cfg(config),
+            SetWatchdog(config) => self.set_watchdog(config),
+            SetApei(config) => self.set_apei(config),
+            SetReboot(config) => self.set_reboot(config),
+            SetPowerSupply(config) => self.set_power_supply(config),
+            SetProcessorAggregator(config) => self.set_processor_aggregator(config),
+            SetSmbios(config) => self.set_smbios(config),
+            SetMemoryMap(config) => self.set_memory_map(config),
+            SetSgx(config) => self.set_sgx(config),
+            SetS2idle(config) => self.set_s2idle(config),
+            SetPmu(config) => self.set_pmu(config),
+            SetCppc(config) => self.set_cppc(config),
+            SetThermalZone(config) => self.set_thermal_zone(config),
+            SetScheduledActions(config) => self.set_scheduled_actions(config),
             // Operations not allowed pre-boot.
             CreateSnapshot(_)
+            | DumpGuestCore(_)
             | FlushMetrics
             | Pause
             | Resume
@@ -493,11 +698,18 @@ impl<'a> PrebootApiController<'a> {
             | UpdateBlockDevice(_)
             | UpdateMemoryHotplugSize(_)
             | UpdateNetworkInterface(_)
+            | UpdatePowerSupply(_)
+            | UpdateProcessorAggregator(_)
+            | UpdateThermalZone(_)
+            | GetProcessorAggregatorStatus
             | StartFreePageHinting(_)
             | GetFreePageHintingStatus
+            | GetWatchdogStatus
             | StopFreePageHinting => Err(VmmActionError::OperationNotSupportedPreBoot),
             #[cfg(target_arch = "x86_64")]
-            SendCtrlAltDel => Err(VmmActionError::OperationNotSupportedPreBoot),
+            SendCtrlAltDel | InjectNmi(_) | GetAcpiTables => {
+                Err(VmmActionError::OperationNotSupportedPreBoot)
+            }
         }
     }
 
@@ -536,6 +748,38 @@ impl<'a> PrebootApiController<'a> {
             .map_err(VmmActionError::PmemDevice)
     }
 
+    fn insert_vfio_device(&mut self, cfg: VfioConfig) -> Result<VmmData, VmmActionError> {
+        self.boot_path = true;
+        self.vm_resources
+            .set_vfio_device(cfg)
+            .map(|()| VmmData::Empty)
+            .map_err(VmmActionError::VfioDevice)
+    }
+
+    fn insert_vfio_vf(&mut self, cfg: VfioVfConfig) -> Result<VmmData, VmmActionError> {
+        self.boot_path = true;
+        self.vm_resources
+            .set_vfio_vf(cfg)
+            .map(|()| VmmData::Empty)
+            .map_err(VmmActionError::VfioDevice)
+    }
+
+    fn insert_usb_device(&mut self, cfg: UsbDeviceConfig) -> Result<VmmData, VmmActionError> {
+        self.boot_path = true;
+        self.vm_resources
+            .set_usb_device(cfg)
+            .map(|()| VmmData::Empty)
+            .map_err(VmmActionError::UsbDevice)
+    }
+
+    fn insert_nvme_device(&mut self, cfg: NvmeDeviceConfig) -> Result<VmmData, VmmActionError> {
+        self.boot_path = true;
+        self.vm_resources
+            .set_nvme_device(cfg)
+            .map(|()| VmmData::Empty)
+            .map_err(VmmActionError::NvmeDevice)
+    }
+
     fn set_balloon_device(&mut self, cfg: BalloonDeviceConfig) -> Result<VmmData, VmmActionError> {
         self.boot_path = true;
         self.vm_resources
@@ -602,6 +846,102 @@ impl<'a> PrebootApiController<'a> {
         Ok(VmmData::Empty)
     }
 
+    fn set_acpi_tables(&mut self, cfg: AcpiTablesConfig) -> Result<VmmData, VmmActionError> {
+        self.boot_path = true;
+        self.vm_resources.set_acpi_tables(cfg)?;
+        Ok(VmmData::Empty)
+    }
+
+    fn set_fw_cfg(&mut self, cfg: FwCfgConfig) -> Result<VmmData, VmmActionError> {
+        self.boot_path = true;
+        self.vm_resources.set_fw_cfg(cfg)?;
+        Ok(VmmData::Empty)
+    }
+
+    fn set_watchdog(&mut self, cfg: WatchdogConfig) -> Result<VmmData, VmmActionError> {
+        self.boot_path = true;
+        self.vm_resources.set_watchdog_config(cfg)?;
+        Ok(VmmData::Empty)
+    }
+
+    fn set_apei(&mut self, cfg: ApeiConfig) -> Result<VmmData, VmmActionError> {
+        self.boot_path = true;
+        self.vm_resources.set_apei_config(cfg)?;
+        Ok(VmmData::Empty)
+    }
+
+    fn set_reboot(&mut self, cfg: RebootConfig) -> Result<VmmData, VmmActionError> {
+        self.boot_path = true;
+        self.vm_resources.set_reboot_config(cfg)?;
+        Ok(VmmData::Empty)
+    }
+
+    fn set_power_supply(&mut self, cfg: PowerSupplyConfig) -> Result<VmmData, VmmActionError> {
+        self.boot_path = true;
+        self.vm_resources.set_power_supply_config(cfg)?;
+        Ok(VmmData::Empty)
+    }
+
+    fn set_processor_aggregator(
+        &mut self,
+        cfg: ProcessorAggregatorConfig,
+    ) -> Result<VmmData, VmmActionError> {
+        self.boot_path = true;
+        self.vm_resources.set_processor_aggregator_config(cfg)?;
+        Ok(VmmData::Empty)
+    }
+
+    fn set_smbios(&mut self, cfg: SmbiosConfig) -> Result<VmmData, VmmActionError> {
+        self.boot_path = true;
+        self.vm_resources.set_smbios_config(cfg)?;
+        Ok(VmmData::Empty)
+    }
+
+    fn set_memory_map(&mut self, cfg: MemoryMapConfig) -> Result<VmmData, VmmActionError> {
+        self.boot_path = true;
+        self.vm_resources.set_memory_map_config(cfg)?;
+        Ok(VmmData::Empty)
+    }
+
+    fn set_sgx(&mut self, cfg: SgxConfig) -> Result<VmmData, VmmActionError> {
+        self.boot_path = true;
+        self.vm_resources.set_sgx_config(cfg)?;
+        Ok(VmmData::Empty)
+    }
+
+    fn set_s2idle(&mut self, cfg: S2idleConfig) -> Result<VmmData, VmmActionError> {
+        self.boot_path = true;
+        self.vm_resources.set_s2idle_config(cfg)?;
+        Ok(VmmData::Empty)
+    }
+
+    fn set_pmu(&mut self, cfg: PmuConfig) -> Result<VmmData, VmmActionError> {
+        self.boot_path = true;
+        self.vm_resources.set_pmu_config(cfg)?;
+        Ok(VmmData::Empty)
+    }
+
+    fn set_cppc(&mut self, cfg: CppcConfig) -> Result<VmmData, VmmActionError> {
+        self.boot_path = true;
+        self.vm_resources.set_cppc_config(cfg)?;
+        Ok(VmmData::Empty)
+    }
+
+    fn set_thermal_zone(&mut self, cfg: ThermalZoneConfig) -> Result<VmmData, VmmActionError> {
+        self.boot_path = true;
+        self.vm_resources.set_thermal_zone_config(cfg)?;
+        Ok(VmmData::Empty)
+    }
+
+    fn set_scheduled_actions(
+        &mut self,
+        cfg: ScheduledActionsConfig,
+    ) -> Result<VmmData, VmmActionError> {
+        self.boot_path = true;
+        self.vm_resources.set_scheduled_actions_config(cfg)?;
+        Ok(VmmData::Empty)
+    }
+
     // On success, this command will end the pre-boot stage and this controller
     // will be replaced by a runtime controller.
     fn start_microvm(&mut self) -> Result<VmmData, VmmActionError> {
@@ -688,6 +1028,7 @@ impl RuntimeApiController {
         match request {
             // Supported operations allowed post-boot.
             CreateSnapshot(snapshot_create_cfg) => self.create_snapshot(&snapshot_create_cfg),
+            DumpGuestCore(params) => self.dump_guest_core(&params),
             FlushMetrics => self.flush_metrics(),
             GetBalloonConfig => self
                 .vmm
@@ -703,6 +1044,7 @@ impl RuntimeApiController {
                 .latest_balloon_stats()
                 .map(VmmData::BalloonStats)
                 .map_err(VmmActionError::InternalVmm),
+            GetCpuFeatures => self.cpu_features(),
             GetFullVmConfig => Ok(VmmData::FullVmConfig((&self.vm_resources).into())),
             GetMemoryHotplugStatus => self
                 .vmm
@@ -715,6 +1057,13 @@ impl RuntimeApiController {
             GetVmMachineConfig => Ok(VmmData::MachineConfiguration(
                 self.vm_resources.machine_config.clone(),
             )),
+            GetWatchdogStatus => self
+                .vmm
+                .lock()
+                .expect("Poisoned lock")
+                .watchdog_status()
+                .map(VmmData::WatchdogStatus)
+                .map_err(VmmActionError::InternalVmm),
             GetVmInstanceInfo => Ok(VmmData::InstanceInformation(
                 self.vmm.lock().expect("Poisoned lock").instance_info(),
             )),
@@ -727,6 +1076,16 @@ impl RuntimeApiController {
             Resume => self.resume(),
             #[cfg(target_arch = "x86_64")]
             SendCtrlAltDel => self.send_ctrl_alt_del(),
+            #[cfg(target_arch = "x86_64")]
+            InjectNmi(params) => self.inject_nmi(&params),
+            #[cfg(target_arch = "x86_64")]
+            GetAcpiTables => self
+                .vmm
+                .lock()
+                .expect("Poisoned lock")
+                .acpi_tables()
+                .map(VmmData::AcpiTables)
+                .map_err(VmmActionError::InternalVmm),
             UpdateBalloon(balloon_update) => self
                 .vmm
                 .lock()
@@ -771,6 +1130,16 @@ impl RuntimeApiController {
                 .update_memory_hotplug_size(cfg.requested_size_mib)
                 .map(|_| VmmData::Empty)
                 .map_err(VmmActionError::MemoryHotplugUpdate),
+            UpdatePowerSupply(update) => self.update_power_supply(update),
+            GetProcessorAggregatorStatus => self
+                .vmm
+                .lock()
+                .expect("Poisoned lock")
+                .processor_aggregator_status()
+                .map(VmmData::ProcessorAggregatorStatus)
+                .map_err(VmmActionError::ProcessorAggregatorUpdate),
+            UpdateProcessorAggregator(update) => self.update_processor_aggregator(update),
+            UpdateThermalZone(update) => self.update_thermal_zone(update),
             // Operations not allowed post-boot.
             ConfigureBootSource(_)
             | ConfigureLogger(_)
@@ -778,6 +1147,10 @@ impl RuntimeApiController {
             | ConfigureSerial(_)
             | InsertBlockDevice(_)
             | InsertPmemDevice(_)
+            | InsertVfioDevice(_)
+            | InsertVfioVf(_)
+            | InsertUsbDevice(_)
+            | InsertNvmeDevice(_)
             | InsertNetworkDevice(_)
             | LoadSnapshot(_)
             | PutCpuConfiguration(_)
@@ -786,6 +1159,21 @@ impl RuntimeApiController {
             | SetMmdsConfiguration(_)
             | SetEntropyDevice(_)
             | SetMemoryHotplugDevice(_)
+            | SetAcpiTables(_)
+            | SetFwCfg(_)
+            | SetWatchdog(_)
+            | SetApei(_)
+            | SetReboot(_)
+            | SetPowerSupply(_)
+            | SetProcessorAggregator(_)
+            | SetSmbios(_)
+            | SetMemoryMap(_)
+            | SetSgx(_)
+            | SetS2idle(_)
+            | SetPmu(_)
+            | SetCppc(_)
+            | SetThermalZone(_)
+            | SetScheduledActions(_)
             | StartMicroVm
             | UpdateMachineConfiguration(_) => Err(VmmActionError::OperationNotSupportedPostBoot),
         }
@@ -822,6 +1210,18 @@ impl RuntimeApiController {
         Ok(VmmData::Empty)
     }
 
+    /// Reports the CPU configuration of the first vCPU. The vCPUs need to be paused.
+    fn cpu_features(&mut self) -> Result<VmmData, VmmActionError> {
+        let cpu_configs = self
+            .vmm
+            .lock()
+            .expect("Poisoned lock")
+            .dump_cpu_config()
+            .map_err(|err| VmmActionError::CpuFeatures(err.into()))?;
+        // A microVM always has at least one vCPU.
+        Ok(VmmData::CpuFeatures((&cpu_configs[0]).into()))
+    }
+
     /// Write the metrics on user demand (flush). We use the word `flush` here to highlight the fact
     /// that the metrics will be written immediately.
     /// Defer to inner Vmm. We'll move to a variant where the Vmm simply exposes functionality like
@@ -847,6 +1247,17 @@ impl RuntimeApiController {
             .map_err(VmmActionError::InternalVmm)
     }
 
+    /// Injects an NMI in one or all vCPUs of the inner Vmm.
+    #[cfg(target_arch = "x86_64")]
+    fn inject_nmi(&mut self, params: &InjectNmiParams) -> Result<VmmData, VmmActionError> {
+        self.vmm
+            .lock()
+            .expect("Poisoned lock")
+            .inject_nmi(params.vcpu)
+            .map(|()| VmmData::Empty)
+            .map_err(VmmActionError::InternalVmm)
+    }
+
     fn create_snapshot(
         &mut self,
         create_params: &CreateSnapshotParams,
@@ -854,6 +1265,9 @@ impl RuntimeApiController {
         if create_params.snapshot_type == SnapshotType::Diff {
             log_dev_preview_warning("Virtual machine diff snapshots", None);
         }
+        if self.vm_resources.machine_config.nested_virtualization {
+            return Err(CreateSnapshotError::NestedVirtualization.into());
+        }
 
         let mut locked_vmm = self.vmm.lock().unwrap();
         let vm_info = VmInfo::from(&self.vm_resources);
@@ -886,6 +1300,12 @@ impl RuntimeApiController {
         Ok(VmmData::Empty)
     }
 
+    fn dump_guest_core(&mut self, params: &CoreDumpParams) -> Result<VmmData, VmmActionError> {
+        let mut locked_vmm = self.vmm.lock().expect("Poisoned lock");
+        dump_guest_core(&mut locked_vmm, params)?;
+        Ok(VmmData::Empty)
+    }
+
     /// Updates block device properties:
     ///  - path of the host file backing the emulated block device, update the disk image on the
     ///    device and its virtio configuration
@@ -937,6 +1357,60 @@ impl RuntimeApiController {
             .map_err(NetworkInterfaceError::DeviceUpdate)
             .map_err(VmmActionError::NetworkConfig)
     }
+
+    /// Updates the status of the battery and AC adapter as described in `update`.
+    fn update_power_supply(
+        &mut self,
+        update: PowerSupplyUpdateConfig,
+    ) -> Result<VmmData, VmmActionError> {
+        update.validate()?;
+        self.vmm
+            .lock()
+            .expect("Poisoned lock")
+            .update_power_supply(&update)
+            .map_err(VmmActionError::PowerSupplyUpdate)?;
+        // Keep the configuration reported by GET /vm/config in sync with the devices.
+        if let Some(config) = &mut self.vm_resources.power_supply {
+            config.apply(&update);
+        }
+        Ok(VmmData::Empty)
+    }
+
+    /// Asks the guest to idle the number of vCPUs described in `update`.
+    fn update_processor_aggregator(
+        &mut self,
+        update: ProcessorAggregatorUpdateConfig,
+    ) -> Result<VmmData, VmmActionError> {
+        update.validate(self.vm_resources.machine_config.vcpu_count)?;
+        self.vmm
+            .lock()
+            .expect("Poisoned lock")
+            .update_processor_aggregator(update.idle_cpus)
+            .map_err(VmmActionError::ProcessorAggregatorUpdate)?;
+        if let Some(config) = &mut self.vm_resources.processor_aggregator {
+            config.idle_cpus = update.idle_cpus;
+        }
+        Ok(VmmData::Empty)
+    }
+
+    /// Sets the temperature of the thermal zone as described in `update`.
+    fn update_thermal_zone(
+        &mut self,
+        update: ThermalZoneUpdateConfig,
+    ) -> Result<VmmData, VmmActionError> {
+        if let Some(config) = &self.vm_resources.thermal_zone {
+            update.validate(config)?;
+        }
+        self.vmm
+            .lock()
+            .expect("Poisoned lock")
+            .update_thermal_zone(&update)
+            .map_err(VmmActionError::ThermalZoneUpdate)?;
+        if let Some(config) = &mut self.vm_resources.thermal_zone {
+            config.temperature_mc = update.temperature_mc;
+        }
+        Ok(VmmData::Empty)
+    }
 }
 
 #[cfg(test)]
@@ -1223,6 +1697,12 @@ mod tests {
             Default::default(),
         )));
         check_unsupported(preboot_request(VmmAction::GetFreePageHintingStatus));
+        check_unsupported(preboot_request(VmmAction::GetWatchdogStatus));
+        #[cfg(target_arch = "x86_64")]
+        check_unsupported(preboot_request(VmmAction::GetAcpiTables));
+        check_unsupported(preboot_request(VmmAction::DumpGuestCore(
+            CoreDumpParams::default(),
+        )));
         check_unsupported(preboot_request(VmmAction::StopFreePageHinting));
         check_unsupported(preboot_request(VmmAction::UpdateBalloonStatistics(
             BalloonUpdateStatsConfig {
@@ -1248,11 +1728,25 @@ mod tests {
         )));
         #[cfg(target_arch = "x86_64")]
         check_unsupported(preboot_request(VmmAction::SendCtrlAltDel));
+        #[cfg(target_arch = "x86_64")]
+        check_unsupported(preboot_request(VmmAction::InjectNmi(
+            InjectNmiParams::default(),
+        )));
         check_unsupported(preboot_request(VmmAction::UpdateMemoryHotplugSize(
             MemoryHotplugSizeUpdate {
                 requested_size_mib: 0,
             },
         )));
+        check_unsupported(preboot_request(VmmAction::UpdatePowerSupply(
+            PowerSupplyUpdateConfig::default(),
+        )));
+        check_unsupported(preboot_request(VmmAction::GetProcessorAggregatorStatus));
+        check_unsupported(preboot_request(VmmAction::UpdateProcessorAggregator(
+            ProcessorAggregatorUpdateConfig { idle_cpus: 0 },
+        )));
+        check_unsupported(preboot_request(VmmAction::UpdateThermalZone(
+            ThermalZoneUpdateConfig::default(),
+        )));
     }
 
     fn runtime_request(request: VmmAction) -> Result<VmmData, VmmActionError> {
@@ -1329,6 +1823,7 @@ mod tests {
                 guest_mac: None,
                 rx_rate_limiter: None,
                 tx_rate_limiter: None,
+                vhost: false,
             },
         )));
         check_unsupported(runtime_request(VmmAction::SetVsockDevice(
@@ -1369,6 +1864,7 @@ mod tests {
                 track_dirty_pages: false,
                 resume_vm: false,
                 network_overrides: vec![],
+                clone: None,
             },
         )));
         check_unsupported(runtime_request(VmmAction::SetEntropyDevice(
@@ -1380,8 +1876,55 @@ mod tests {
             root_device: false,
             read_only: false,
         })));
+        check_unsupported(runtime_request(VmmAction::InsertVfioDevice(
+            VfioConfig::default(),
+        )));
+        check_unsupported(runtime_request(VmmAction::InsertVfioVf(
+            VfioVfConfig::default(),
+        )));
+        check_unsupported(runtime_request(VmmAction::SetWatchdog(
+            WatchdogConfig::default(),
+        )));
+        check_unsupported(runtime_request(VmmAction::SetApei(ApeiConfig::default())));
+        check_unsupported(runtime_request(VmmAction::SetReboot(
+            RebootConfig::default(),
+        )));
+        check_unsupported(runtime_request(VmmAction::SetPowerSupply(
+            PowerSupplyConfig::default(),
+        )));
+        check_unsupported(runtime_request(VmmAction::SetProcessorAggregator(
+            ProcessorAggregatorConfig::default(),
+        )));
+        check_unsupported(runtime_request(VmmAction::SetSmbios(
+            SmbiosConfig::default(),
+        )));
+        check_unsupported(runtime_request(VmmAction::SetMemoryMap(
+            MemoryMapConfig::default(),
+        )));
+        check_unsupported(runtime_request(VmmAction::SetSgx(SgxConfig::default())));
+        check_unsupported(runtime_request(VmmAction::SetS2idle(
+            S2idleConfig::default(),
+        )));
+        check_unsupported(runtime_request(VmmAction::SetPmu(PmuConfig::default())));
+        check_unsupported(runtime_request(VmmAction::SetCppc(CppcConfig::default())));
+        check_unsupported(runtime_request(VmmAction::SetThermalZone(
+            ThermalZoneConfig::default(),
+        )));
+        check_unsupported(runtime_request(VmmAction::SetScheduledActions(
+            ScheduledActionsConfig::default(),
+        )));
+        check_unsupported(runtime_request(VmmAction::InsertUsbDevice(
+            UsbDeviceConfig::default(),
+        )));
+        check_unsupported(runtime_request(VmmAction::InsertNvmeDevice(
+            NvmeDeviceConfig::default(),
+        )));
         check_unsupported(runtime_request(VmmAction::SetMemoryHotplugDevice(
             MemoryHotplugConfig::default(),
         )));
+        check_unsupported(runtime_request(VmmAction::SetAcpiTables(
+            AcpiTablesConfig::default(),
+        )));
+        check_unsupported(runtime_request(VmmAction::SetFwCfg(FwCfgConfig::default())));
     }
 }
@@ -0,0 +1,348 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Actions taken by the VMM on timers and on events of the guest.
+//!
+//! Each action with a timer trigger gets its own periodic timer, which only takes the action
+//! while the microVM is running. Panics are reported by the guest through the pvpanic device.
+//! Runs of the OOM killer are noticed by polling the balloon statistics at their own interval,
+//! as an increase of the OOM kill counter of the guest.
+
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::time::Duration;
+
+use event_manager::{EventOps, Events};
+use utils::time::TimerFd;
+use vmm_sys_util::epoll::EventSet;
+
+use crate::logger::{IncMetric, METRICS, error, info, warn};
+use crate::persist::{self, CreateSnapshotError, VmInfo};
+use crate::vmm_config::instance_info::VmState;
+use crate::vmm_config::scheduled_actions::{
+    ActionTrigger, ScheduledAction, ScheduledActionKind, ScheduledActionsConfig,
+};
+use crate::vmm_config::snapshot::{CreateSnapshotParams, SnapshotType};
+use crate::{Vmm, VmmError};
+
+/// Errors associated with taking a scheduled action.
+#[derive(Debug, thiserror::Error, displaydoc::Display)]
+pub enum ScheduledActionError {
+    /// Cannot pause the microVM: {0}
+    Pause(VmmError),
+    /// Cannot resume the microVM: {0}
+    Resume(VmmError),
+    /// Cannot snapshot the microVM: {0}
+    Snapshot(#[from] CreateSnapshotError),
+    /// Cannot adjust the balloon: {0}
+    Balloon(VmmError),
+    /// The guest did not report its available memory
+    MissingAvailableMemory,
+}
+
+#[derive(Debug)]
+struct ActionTimer {
+    /// Index of the action in the configuration.
+    index: usize,
+    timer: TimerFd,
+}
+
+/// Timers and state of the scheduled actions of a microVM.
+#[derive(Debug)]
+pub struct ScheduledActions {
+    config: ScheduledActionsConfig,
+    /// Information on the microVM, for the snapshots taken by the actions.
+    vm_info: VmInfo,
+    timers: Vec<ActionTimer>,
+    /// Polls the balloon statistics, when an action is triggered by the OOM killer.
+    oom_timer: Option<TimerFd>,
+    /// OOM kill counter of the guest, as of the last poll.
+    oom_kills: Option<u64>,
+}
+
+impl ScheduledActions {
+    /// Arms the timers of the actions. The balloon statistics are polled every
+    /// `stats_polling_interval_s` seconds to notice runs of the OOM killer.
+    pub fn new(
+        config: ScheduledActionsConfig,
+        vm_info: VmInfo,
+        stats_polling_interval_s: u16,
+    ) -> Self {
+        let timers = config
+            .actions
+            .iter()
+            .enumerate()
+            .filter_map(|(index, action)| {
+                let interval = Duration::from_secs(action.interval_s?);
+                let mut timer = TimerFd::new();
+                timer.arm(interval, Some(interval));
+                Some(ActionTimer { index, timer })
+            })
+            .collect();
+        let oom_timer = config
+            .actions
+            .iter()
+            .any(|action| action.trigger == ActionTrigger::GuestOom)
+            .then(|| {
+                let interval = Duration::from_secs(u64::from(stats_polling_interval_s.max(1)));
+                let mut timer = TimerFd::new();
+                timer.arm(interval, Some(interval));
+                timer
+            });
+        ScheduledActions {
+            config,
+            vm_info,
+            timers,
+            oom_timer,
+            oom_kills: None,
+        }
+    }
+
+    /// Registers the timers with the event manager.
+    pub fn register(&self, ops: &mut EventOps) {
+        let timers = self.timers.iter().map(|timer| &timer.timer);
+        for timer in timers.chain(&self.oom_timer) {
+            if let Err(err) = ops.add(Events::new(timer, EventSet::IN)) {
+                error!("Failed to register scheduled action timer event: {}", err);
+            }
+        }
+    }
+
+    /// Whether `fd` is one of the timers.
+    pub fn owns(&self, fd: RawFd) -> bool {
+        let timers = self.timers.iter().map(|timer| &timer.timer);
+        timers
+            .chain(&self.oom_timer)
+            .any(|timer| timer.as_raw_fd() == fd)
+    }
+
+    /// Handles an event on the timer `fd`, returning the triggered timer action if any.
+    fn process_timer(&mut self, fd: RawFd) -> Option<ScheduledAction> {
+        let timer = self
+            .timers
+            .iter_mut()
+            .find(|timer| timer.timer.as_raw_fd() == fd)?;
+        if timer.timer.read() == 0 {
+            return None;
+        }
+        Some(self.config.actions[timer.index].clone())
+    }
+
+    /// Handles an event on the OOM timer, returning whether it is time to poll the balloon.
+    fn process_oom_timer(&mut self, fd: RawFd) -> bool {
+        match &mut self.oom_timer {
+            Some(timer) if timer.as_raw_fd() == fd => timer.read() != 0,
+            _ => false,
+        }
+    }
+
+    /// Records the OOM kill counter reported by the guest, returning whether it went up since
+    /// the last report.
+    fn update_oom_kills(&mut self, oom_kills: Option<u64>) -> bool {
+        let Some(oom_kills) = oom_kills else {
+            return false;
+        };
+        // The first report only sets the baseline, as the counter may come from before a
+        // snapshot was restored.
+        self.oom_kills
+            .replace(oom_kills)
+            .is_some_and(|previous| oom_kills > previous)
+    }
+
+    /// Returns the actions with `trigger`.
+    fn triggered_by(&self, trigger: ActionTrigger) -> Vec<ScheduledAction> {
+        self.config
+            .actions
+            .iter()
+            .filter(|action| action.trigger == trigger)
+            .cloned()
+            .collect()
+    }
+}
+
+/// Returns the balloon size bringing the available memory of the guest a step closer to
+/// `target_mib`, or `None` if it is close enough.
+fn balloon_step(
+    amount_mib: u32,
+    available_mib: u32,
+    target_mib: u32,
+    step_mib: u32,
+) -> Option<u32> {
+    if available_mib < target_mib {
+        // Deflating gives memory back to the guest.
+        Some(amount_mib.saturating_sub(step_mib)).filter(|&amount| amount != amount_mib)
+    } else if available_mib - target_mib >= step_mib {
+        // Inflating only overshoots the target when the guest did not report all its memory.
+        Some(amount_mib.saturating_add(step_mib))
+    } else {
+        None
+    }
+}
+
+impl Vmm {
+    /// Takes the actions whose timer fired, or which the guest triggered.
+    pub(crate) fn handle_scheduled_actions(&mut self, fd: RawFd) {
+        let Some(mut scheduled_actions) = self.scheduled_actions.take() else {
+            return;
+        };
+        let mut actions = Vec::new();
+        if let Some(action) = scheduled_actions.process_timer(fd) {
+            // Timer actions are only taken while the microVM is running.
+            if self.instance_info.state == VmState::Running {
+                actions.push(action);
+            }
+        } else if scheduled_actions.process_oom_timer(fd) {
+            let oom_kills = self
+                .latest_balloon_stats()
+                .ok()
+                .and_then(|stats| stats.oom_kill);
+            if scheduled_actions.update_oom_kills(oom_kills) {
+                METRICS.scheduled_actions.guest_ooms.inc();
+                warn!("The OOM killer of the guest ran");
+                actions = scheduled_actions.triggered_by(ActionTrigger::GuestOom);
+            }
+        }
+        let vm_info = scheduled_actions.vm_info.clone();
+        self.scheduled_actions = Some(scheduled_actions);
+
+        for action in actions {
+            self.take_scheduled_action(&action, &vm_info);
+        }
+    }
+
+    /// Takes the actions triggered by a panic of the guest.
+    pub(crate) fn handle_guest_panic(&mut self) {
+        warn!("The guest panicked");
+        let Some(scheduled_actions) = &self.scheduled_actions else {
+            return;
+        };
+        let actions = scheduled_actions.triggered_by(ActionTrigger::GuestPanic);
+        let vm_info = scheduled_actions.vm_info.clone();
+        for action in actions {
+            self.take_scheduled_action(&action, &vm_info);
+        }
+    }
+
+    fn take_scheduled_action(&mut self, action: &ScheduledAction, vm_info: &VmInfo) {
+        METRICS.scheduled_actions.actions.inc();
+        if let Err(err) = self.try_scheduled_action(action, vm_info) {
+            METRICS.scheduled_actions.action_fails.inc();
+            error!(
+                "Failed to take the {:?} action on {:?}: {err}",
+                action.action, action.trigger
+            );
+        }
+    }
+
+    fn try_scheduled_action(
+        &mut self,
+        action: &ScheduledAction,
+        vm_info: &VmInfo,
+    ) -> Result<(), ScheduledActionError> {
+        let running = self.instance_info.state == VmState::Running;
+        match action.action {
+            ScheduledActionKind::Pause => {
+                if running {
+                    self.pause_vm().map_err(ScheduledActionError::Pause)?;
+                    info!("Paused the microVM on {:?}", action.trigger);
+                }
+            }
+            ScheduledActionKind::Snapshot | ScheduledActionKind::DiffSnapshot => {
+                if running {
+                    self.pause_vm().map_err(ScheduledActionError::Pause)?;
+                }
+                // Validation guarantees that both paths are set.
+                let params = CreateSnapshotParams {
+                    snapshot_type: match action.action {
+                        ScheduledActionKind::DiffSnapshot => SnapshotType::Diff,
+                        _ => SnapshotType::Full,
+                    },
+                    snapshot_path: action.snapshot_path.clone().unwrap_or_default(),
+                    mem_file_path: action.mem_file_path.clone().unwrap_or_default(),
+                };
+                let snapshot = persist::create_snapshot(self, vm_info, &params);
+                // The microVM is resumed even if the snapshot failed.
+                if running {
+                    self.resume_vm().map_err(ScheduledActionError::Resume)?;
+                }
+                snapshot?;
+                info!(
+                    "Saved the microVM to {} on {:?}",
+                    params.snapshot_path.display(),
+                    action.trigger
+                );
+            }
+            ScheduledActionKind::BalloonAdjust => {
+                let stats = self
+                    .latest_balloon_stats()
+                    .map_err(ScheduledActionError::Balloon)?;
+                let available_mib = stats
+                    .available_memory
+                    .ok_or(ScheduledActionError::MissingAvailableMemory)?
+                    >> 20;
+                let amount_mib = self
+                    .balloon_config()
+                    .map_err(ScheduledActionError::Balloon)?
+                    .amount_mib;
+                // Validation guarantees that both sizes are set.
+                if let Some(amount_mib) = balloon_step(
+                    amount_mib,
+                    u32::try_from(available_mib).unwrap_or(u32::MAX),
+                    action.target_available_mib.unwrap_or_default(),
+                    action.step_mib.unwrap_or_default(),
+                ) {
+                    self.update_balloon_config(amount_mib)
+                        .map_err(ScheduledActionError::Balloon)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_balloon_step() {
+        // Deflates when the guest is short of memory, down to an empty balloon.
+        assert_eq!(balloon_step(100, 200, 256, 64), Some(36));
+        assert_eq!(balloon_step(36, 200, 256, 64), Some(0));
+        assert_eq!(balloon_step(0, 200, 256, 64), None);
+        // Inflates when the guest has at least a step of memory to spare.
+        assert_eq!(balloon_step(100, 320, 256, 64), Some(164));
+        assert_eq!(balloon_step(100, 300, 256, 64), None);
+    }
+
+    #[test]
+    fn test_scheduled_actions() {
+        let config: ScheduledActionsConfig = serde_json::from_str(
+            r#"{"actions": [{"trigger": "guest_oom", "action": "pause"},
+                {"trigger": "timer", "interval_s": 60, "action": "pause"},
+                {"trigger": "guest_panic", "action": "pause"}]}"#,
+        )
+        .unwrap();
+        let mut scheduled_actions = ScheduledActions::new(config, VmInfo::default(), 1);
+        assert_eq!(scheduled_actions.timers.len(), 1);
+        assert_eq!(scheduled_actions.timers[0].index, 1);
+        let timer_fd = scheduled_actions.timers[0].timer.as_raw_fd();
+        let oom_fd = scheduled_actions.oom_timer.as_ref().unwrap().as_raw_fd();
+        assert!(scheduled_actions.owns(timer_fd));
+        assert!(scheduled_actions.owns(oom_fd));
+        assert!(!scheduled_actions.owns(-1));
+
+        // Nothing is triggered before the timers fire.
+        assert!(scheduled_actions.process_timer(timer_fd).is_none());
+        assert!(!scheduled_actions.process_oom_timer(oom_fd));
+
+        let actions = scheduled_actions.triggered_by(ActionTrigger::GuestPanic);
+        assert_eq!(actions.len(), 1);
+        assert_eq!(actions[0].trigger, ActionTrigger::GuestPanic);
+
+        // The first report of the OOM kill counter only sets the baseline.
+        assert!(!scheduled_actions.update_oom_kills(None));
+        assert!(!scheduled_actions.update_oom_kills(Some(2)));
+        assert!(!scheduled_actions.update_oom_kills(Some(2)));
+        assert!(scheduled_actions.update_oom_kills(Some(3)));
+    }
+}
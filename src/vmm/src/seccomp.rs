@@ -68,6 +68,63 @@ pub enum InstallationError {
 /// The maximum seccomp-BPF program length allowed by the linux kernel.
 pub const BPF_MAX_LEN: usize = 4096;
 
+// Mask of the instruction class part of a BPF opcode.
+const BPF_CLASS_MASK: u64 = 0x07;
+// Class of the BPF return instructions.
+const BPF_RET: u64 = 0x06;
+// Opcode of `BPF_RET | BPF_K`, which returns the constant in the `k` field of the instruction.
+const BPF_RET_K: u64 = 0x06;
+// Mask of the action part of a seccomp return value.
+const SECCOMP_RET_ACTION_FULL: u64 = 0xffff_0000;
+// Seccomp return value that allows the syscall.
+const SECCOMP_RET_ALLOW: u64 = 0x7fff_0000;
+// Seccomp return value that allows the syscall after logging it.
+const SECCOMP_RET_LOG: u64 = 0x7ffc_0000;
+
+/// Filter validation errors.
+#[derive(Debug, thiserror::Error, displaydoc::Display, PartialEq, Eq)]
+pub enum ValidationError {
+    /// Filter length of {0} instructions exceeds the maximum size of {BPF_MAX_LEN:} instructions
+    FilterTooLarge(usize),
+    /// Filter does not end with a return instruction
+    MissingReturn,
+}
+
+/// Check that a filter can be installed, without installing it.
+pub fn validate_filter(bpf_filter: BpfProgramRef) -> Result<(), ValidationError> {
+    // Empty programs are never installed, so they are always valid.
+    let Some(last) = bpf_filter.last() else {
+        return Ok(());
+    };
+    if BPF_MAX_LEN < bpf_filter.len() {
+        return Err(ValidationError::FilterTooLarge(bpf_filter.len()));
+    }
+    // The kernel rejects programs whose last instruction is not a return.
+    if last & BPF_CLASS_MASK != BPF_RET {
+        return Err(ValidationError::MissingReturn);
+    }
+    Ok(())
+}
+
+/// Build the log-only counterpart of a filter, in which every action other than allow is
+/// replaced with log. The resulting filter lets all syscalls through, but the ones that the
+/// original filter would have denied are reported in the kernel audit log.
+pub fn to_log_only(bpf_filter: BpfProgramRef) -> BpfProgram {
+    bpf_filter
+        .iter()
+        .map(|&insn| {
+            // An instruction is laid out as `code: u16, jt: u8, jf: u8, k: u32`.
+            let code = insn & 0xffff;
+            let k = insn >> 32;
+            if code == BPF_RET_K && k & SECCOMP_RET_ACTION_FULL != SECCOMP_RET_ALLOW {
+                (insn & 0xffff_ffff) | (SECCOMP_RET_LOG << 32)
+            } else {
+                insn
+            }
+        })
+        .collect()
+}
+
 /// BPF structure definition for filter array.
 /// See /usr/include/linux/filter.h .
 #[repr(C)]
@@ -160,6 +217,40 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_validate_filter() {
+        let ret_allow = BPF_RET_K | (SECCOMP_RET_ALLOW << 32);
+
+        validate_filter(&[]).unwrap();
+        validate_filter(&[ret_allow]).unwrap();
+        assert_eq!(
+            validate_filter(&vec![ret_allow; BPF_MAX_LEN + 1]).unwrap_err(),
+            ValidationError::FilterTooLarge(BPF_MAX_LEN + 1)
+        );
+        // `BPF_LD | BPF_W | BPF_ABS` as the last instruction.
+        assert_eq!(
+            validate_filter(&[ret_allow, 0x20]).unwrap_err(),
+            ValidationError::MissingReturn
+        );
+    }
+
+    #[test]
+    fn test_to_log_only() {
+        let load = 0x0000_0004_0000_0020;
+        let jump = 0x0000_0001_0102_0015;
+        let ret_allow = BPF_RET_K | (SECCOMP_RET_ALLOW << 32);
+        let ret_kill = BPF_RET_K;
+        let ret_trap = BPF_RET_K | (0x0003_0000 << 32);
+        let ret_errno = BPF_RET_K | (0x0005_0001 << 32);
+        let ret_log = BPF_RET_K | (SECCOMP_RET_LOG << 32);
+
+        assert_eq!(
+            to_log_only(&[load, jump, ret_allow, ret_kill, ret_trap, ret_errno]),
+            vec![load, jump, ret_allow, ret_log, ret_log, ret_log]
+        );
+        assert!(to_log_only(&[]).is_empty());
+    }
+
     #[test]
     fn test_filter_apply() {
         // Test filter too large.
@@ -7,6 +7,7 @@ use libc::{
 use log::error;
 
 use crate::FcExitCode;
+use crate::devices::acpi::ghes;
 use crate::logger::{IncMetric, METRICS, StoreMetric};
 use crate::utils::signal::register_signal_handler;
 
@@ -19,6 +20,13 @@ const SI_OFF_SYSCALL: isize = 6;
 
 const SYS_SECCOMP_CODE: i32 = 1;
 
+// `si_code` values of a `SIGBUS` raised for a hardware memory error.
+// See /usr/include/asm-generic/siginfo.h for the definitions.
+// Action required: the memory error was consumed by the thread receiving the signal.
+const BUS_MCEERR_AR: i32 = 4;
+// Action optional: the memory error was detected, but not consumed yet.
+const BUS_MCEERR_AO: i32 = 5;
+
 #[inline]
 fn exit_with_code(exit_code: FcExitCode) {
     // Write the metrics before exiting.
@@ -92,7 +100,7 @@ generate_handler!(
 );
 
 generate_handler!(
-    sigbus_handler,
+    sigbus_exit_handler,
     SIGBUS,
     SIGBUS,
     METRICS.signals.sigbus,
@@ -130,6 +138,27 @@ generate_handler!(
     empty_fn
 );
 
+#[inline(always)]
+extern "C" fn sigbus_handler(num: c_int, info: *mut siginfo_t, unused: *mut c_void) {
+    // SAFETY: Safe because we're just reading some fields from a supposedly valid argument.
+    let si_code = unsafe { (*info).si_code };
+
+    // A memory error is forwarded to the guest if it is either not consumed yet, or it was
+    // consumed by the guest itself while running a vCPU. Anything else is fatal.
+    let is_guest_memory_error =
+        si_code == BUS_MCEERR_AO || (si_code == BUS_MCEERR_AR && crate::vstate::vcpu::in_kvm_run());
+    if num == SIGBUS && is_guest_memory_error {
+        // SAFETY: Safe because `si_addr` is set for all `SIGBUS` signals.
+        let addr = unsafe { (*info).si_addr() } as u64;
+        if ghes::queue_memory_error(addr) {
+            METRICS.signals.sigbus_memory_errors.inc();
+            return;
+        }
+    }
+
+    sigbus_exit_handler(num, info, unused);
+}
+
 #[inline(always)]
 extern "C" fn sigpipe_handler(num: c_int, info: *mut siginfo_t, _unused: *mut c_void) {
     // Just record the metric and allow the process to continue, the EPIPE error needs
@@ -0,0 +1,167 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::path::PathBuf;
+
+use acpi_tables::Ssdt;
+use serde::{Deserialize, Serialize};
+
+/// Configuration of a single user-supplied ACPI table.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct AcpiTableConfig {
+    /// Path on the host of the compiled table (e.g. the `.aml` output of `iasl`).
+    pub path_on_host: String,
+}
+
+/// The body of a PUT /acpi/tables request.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct AcpiTablesConfig {
+    /// Tables to be appended to the XSDT, in order.
+    pub tables: Vec<AcpiTableConfig>,
+}
+
+/// Errors associated with user-supplied ACPI tables.
+#[derive(Debug, thiserror::Error, displaydoc::Display)]
+pub enum AcpiTablesConfigError {
+    /// Unable to read ACPI table {0:?}: {1}
+    ReadTable(PathBuf, std::io::Error),
+    /// Invalid ACPI table {0:?}: {1}
+    InvalidTable(PathBuf, acpi_tables::AcpiError),
+    /// Custom ACPI tables are only supported on x86_64
+    UnsupportedArch,
+}
+
+/// Holds the validated user-supplied ACPI tables along with the configuration they were
+/// built from.
+#[derive(Debug, Default)]
+pub struct AcpiTablesBuilder {
+    config: Option<AcpiTablesConfig>,
+    tables: Vec<Ssdt>,
+}
+
+impl AcpiTablesBuilder {
+    /// Reads and validates all the tables of `config`, replacing any previous configuration.
+    ///
+    /// Nothing is changed if any of the tables fails validation.
+    pub fn set(&mut self, config: AcpiTablesConfig) -> Result<(), AcpiTablesConfigError> {
+        if cfg!(not(target_arch = "x86_64")) {
+            return Err(AcpiTablesConfigError::UnsupportedArch);
+        }
+
+        let tables = config
+            .tables
+            .iter()
+            .map(|table| {
+                let path = PathBuf::from(&table.path_on_host);
+                let bytes = std::fs::read(&path)
+                    .map_err(|err| AcpiTablesConfigError::ReadTable(path.clone(), err))?;
+                Ssdt::from_bytes(&bytes)
+                    .map_err(|err| AcpiTablesConfigError::InvalidTable(path, err))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        self.config = Some(config);
+        self.tables = tables;
+        Ok(())
+    }
+
+    /// Returns the configuration the tables were built from, if any.
+    pub fn config(&self) -> Option<AcpiTablesConfig> {
+        self.config.clone()
+    }
+
+    /// Returns the validated tables.
+    pub fn tables(&self) -> &[Ssdt] {
+        &self.tables
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use vmm_sys_util::tempfile::TempFile;
+
+    use super::*;
+
+    // A minimal, valid SSDT with an empty definition block.
+    const EMPTY_SSDT: [u8; 36] = [
+        b'S', b'S', b'D', b'T', 36, 0, 0, 0, 2, 0x2e, b'F', b'C', b'T', b'E', b'S', b'T', b'F',
+        b'C', b'T', b'S', b'S', b'S', b'D', b'T', 0, 0, 0, 0, b'I', b'N', b'T', b'L', 0, 0, 0, 0,
+    ];
+
+    fn table_file(bytes: &[u8]) -> TempFile {
+        let file = TempFile::new().unwrap();
+        file.as_file().write_all(bytes).unwrap();
+        file
+    }
+
+    fn config_for(files: &[&TempFile]) -> AcpiTablesConfig {
+        AcpiTablesConfig {
+            tables: files
+                .iter()
+                .map(|file| AcpiTableConfig {
+                    path_on_host: file.as_path().to_str().unwrap().to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    #[cfg(target_arch = "x86_64")]
+    fn test_set_acpi_tables() {
+        let valid = table_file(&EMPTY_SSDT);
+        let mut builder = AcpiTablesBuilder::default();
+        assert!(builder.config().is_none());
+
+        let config = config_for(&[&valid, &valid]);
+        builder.set(config.clone()).unwrap();
+        assert_eq!(builder.config().unwrap(), config);
+        assert_eq!(builder.tables().len(), 2);
+
+        // A table with a bad checksum is rejected and the previous configuration is kept.
+        let mut corrupted_bytes = EMPTY_SSDT;
+        corrupted_bytes[9] = 0;
+        let corrupted = table_file(&corrupted_bytes);
+        let err = builder.set(config_for(&[&corrupted])).unwrap_err();
+        assert!(
+            matches!(
+                err,
+                AcpiTablesConfigError::InvalidTable(_, acpi_tables::AcpiError::InvalidChecksum)
+            ),
+            "{err:?}"
+        );
+        assert_eq!(builder.config().unwrap(), config);
+        assert_eq!(builder.tables().len(), 2);
+
+        let err = builder
+            .set(AcpiTablesConfig {
+                tables: vec![AcpiTableConfig {
+                    path_on_host: "/invalid/path".to_string(),
+                }],
+            })
+            .unwrap_err();
+        assert!(
+            matches!(err, AcpiTablesConfigError::ReadTable(_, _)),
+            "{err:?}"
+        );
+
+        // An empty list clears the tables.
+        builder.set(AcpiTablesConfig::default()).unwrap();
+        assert!(builder.tables().is_empty());
+    }
+
+    #[test]
+    #[cfg(target_arch = "aarch64")]
+    fn test_set_acpi_tables_unsupported() {
+        let valid = table_file(&EMPTY_SSDT);
+        let mut builder = AcpiTablesBuilder::default();
+        let err = builder.set(config_for(&[&valid])).unwrap_err();
+        assert!(
+            matches!(err, AcpiTablesConfigError::UnsupportedArch),
+            "{err:?}"
+        );
+    }
+}
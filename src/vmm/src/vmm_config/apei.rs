@@ -0,0 +1,60 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use serde::{Deserialize, Serialize};
+
+/// Errors associated with the configuration of APEI.
+#[derive(Debug, thiserror::Error, displaydoc::Display, PartialEq, Eq)]
+pub enum ApeiConfigError {
+    /// APEI is only supported on x86_64
+    UnsupportedArch,
+}
+
+/// How the guest is notified of the memory errors reported to it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GhesNotification {
+    /// An interrupt of the Generic Event Device, forwarded to the Hardware Error Device.
+    #[default]
+    Ged,
+    /// A non-maskable interrupt on the first vCPU.
+    Nmi,
+}
+
+/// The body of a PUT /apei request.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ApeiConfig {
+    /// How the guest is notified of memory errors.
+    #[serde(default)]
+    pub notification: GhesNotification,
+}
+
+impl ApeiConfig {
+    /// Validates the configuration.
+    pub fn validate(&self) -> Result<(), ApeiConfigError> {
+        if cfg!(not(target_arch = "x86_64")) {
+            return Err(ApeiConfigError::UnsupportedArch);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apei_config() {
+        let config: ApeiConfig = serde_json::from_str("{}").unwrap();
+        assert_eq!(config.notification, GhesNotification::Ged);
+        let config: ApeiConfig = serde_json::from_str(r#"{"notification": "nmi"}"#).unwrap();
+        assert_eq!(config.notification, GhesNotification::Nmi);
+        serde_json::from_str::<ApeiConfig>(r#"{"notification": "sci"}"#).unwrap_err();
+
+        #[cfg(target_arch = "x86_64")]
+        config.validate().unwrap();
+        #[cfg(target_arch = "aarch64")]
+        assert_eq!(config.validate(), Err(ApeiConfigError::UnsupportedArch));
+    }
+}
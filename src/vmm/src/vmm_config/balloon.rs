@@ -5,8 +5,8 @@ use std::sync::{Arc, Mutex};
 
 use serde::{Deserialize, Serialize};
 
-pub use crate::devices::virtio::balloon::BALLOON_DEV_ID;
 pub use crate::devices::virtio::balloon::device::BalloonStats;
+pub use crate::devices::virtio::balloon::{BALLOON_DEV_ID, FreePageAdvice};
 use crate::devices::virtio::balloon::{Balloon, BalloonConfig};
 
 type MutexBalloon = Arc<Mutex<Balloon>>;
@@ -40,6 +40,12 @@ pub struct BalloonDeviceConfig {
     /// Free page reporting enabled
     #[serde(default)]
     pub free_page_reporting: bool,
+    /// How hinted and reported pages are given back to the host
+    #[serde(default)]
+    pub free_page_advice: FreePageAdvice,
+    /// Advise guest memory as mergeable by KSM
+    #[serde(default)]
+    pub ksm: bool,
 }
 
 impl From<BalloonConfig> for BalloonDeviceConfig {
@@ -50,6 +56,8 @@ impl From<BalloonConfig> for BalloonDeviceConfig {
             stats_polling_interval_s: state.stats_polling_interval_s,
             free_page_hinting: state.free_page_hinting,
             free_page_reporting: state.free_page_reporting,
+            free_page_advice: state.free_page_advice,
+            ksm: state.ksm,
         }
     }
 }
@@ -90,13 +98,15 @@ impl BalloonBuilder {
     /// Inserts a Balloon device in the store.
     /// If an entry already exists, it will overwrite it.
     pub fn set(&mut self, cfg: BalloonDeviceConfig) -> Result<(), BalloonConfigError> {
-        self.inner = Some(Arc::new(Mutex::new(Balloon::new(
+        let mut balloon = Balloon::new(
             cfg.amount_mib,
             cfg.deflate_on_oom,
             cfg.stats_polling_interval_s,
             cfg.free_page_hinting,
             cfg.free_page_reporting,
-        )?)));
+        )?;
+        balloon.set_host_reclaim(cfg.free_page_advice, cfg.ksm);
+        self.inner = Some(Arc::new(Mutex::new(balloon)));
 
         Ok(())
     }
@@ -140,6 +150,8 @@ pub(crate) mod tests {
             stats_polling_interval_s: 0,
             free_page_hinting: false,
             free_page_reporting: false,
+            free_page_advice: FreePageAdvice::DontNeed,
+            ksm: false,
         }
     }
 
@@ -152,6 +164,8 @@ pub(crate) mod tests {
             stats_polling_interval_s: 0,
             free_page_hinting: false,
             free_page_reporting: false,
+            free_page_advice: FreePageAdvice::DontNeed,
+            ksm: false,
         };
         assert_eq!(default_balloon_config, balloon_config);
         let mut builder = BalloonBuilder::new();
@@ -175,6 +189,8 @@ pub(crate) mod tests {
             stats_polling_interval_s: 3,
             free_page_hinting: false,
             free_page_reporting: false,
+            free_page_advice: FreePageAdvice::Free,
+            ksm: true,
         };
 
         let actual_balloon_config = BalloonDeviceConfig::from(BalloonConfig {
@@ -183,9 +199,18 @@ pub(crate) mod tests {
             stats_polling_interval_s: 3,
             free_page_hinting: false,
             free_page_reporting: false,
+            free_page_advice: FreePageAdvice::Free,
+            ksm: true,
         });
 
         assert_eq!(expected_balloon_config, actual_balloon_config);
+
+        let balloon_config: BalloonDeviceConfig = serde_json::from_str(
+            r#"{"amount_mib": 5, "deflate_on_oom": false, "stats_polling_interval_s": 3,
+                "free_page_advice": "free", "ksm": true}"#,
+        )
+        .unwrap();
+        assert_eq!(balloon_config, expected_balloon_config);
     }
 
     #[test]
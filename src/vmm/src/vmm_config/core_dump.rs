@@ -0,0 +1,17 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+/// Stores the configuration that will be used for writing a core dump of the guest.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct CoreDumpParams {
+    /// Path to the file that will contain the core dump.
+    pub dump_path: PathBuf,
+    /// Whether to leave out the guest pages released through the balloon device.
+    #[serde(default)]
+    pub exclude_balloon_pages: bool,
+}
@@ -0,0 +1,44 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use serde::{Deserialize, Serialize};
+
+/// Errors associated with the configuration of the CPPC performance controls.
+#[derive(Debug, thiserror::Error, displaydoc::Display, PartialEq, Eq)]
+pub enum CppcConfigError {
+    /// CPPC is only supported on x86_64
+    UnsupportedArch,
+}
+
+/// The body of a PUT /cppc request.
+///
+/// The CPPC performance controls have nothing to configure yet, configuring them enables them.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct CppcConfig {}
+
+impl CppcConfig {
+    /// Validates the configuration.
+    pub fn validate(&self) -> Result<(), CppcConfigError> {
+        if cfg!(not(target_arch = "x86_64")) {
+            return Err(CppcConfigError::UnsupportedArch);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cppc_config() {
+        let config: CppcConfig = serde_json::from_str("{}").unwrap();
+        serde_json::from_str::<CppcConfig>(r#"{"highest_perf": 1024}"#).unwrap_err();
+
+        #[cfg(target_arch = "x86_64")]
+        config.validate().unwrap();
+        #[cfg(target_arch = "aarch64")]
+        assert_eq!(config.validate(), Err(CppcConfigError::UnsupportedArch));
+    }
+}
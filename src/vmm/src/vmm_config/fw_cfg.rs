@@ -0,0 +1,173 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::devices::acpi::fw_cfg::{FW_CFG_MAX_FILES, FW_CFG_MAX_NAME_LEN, FwCfgFile};
+
+/// Configuration of a single file of the fw_cfg device.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct FwCfgFileConfig {
+    /// Name the guest reads the file under, such as `opt/com.coreos/config`.
+    pub name: String,
+    /// Path on the host of the contents of the file.
+    pub path_on_host: String,
+}
+
+/// The body of a PUT /fw-cfg request.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct FwCfgConfig {
+    /// Files exposed to the guest, in the order of their selectors.
+    pub files: Vec<FwCfgFileConfig>,
+}
+
+/// Errors associated with the fw_cfg device.
+#[derive(Debug, thiserror::Error, displaydoc::Display)]
+pub enum FwCfgConfigError {
+    /// The fw_cfg device has too many files
+    TooManyFiles,
+    /// Invalid fw_cfg file name {0:?}: it must have 1 to 55 printable ASCII characters
+    InvalidName(String),
+    /// Duplicate fw_cfg file name {0:?}
+    DuplicateName(String),
+    /// Unable to read fw_cfg file {0:?}: {1}
+    ReadFile(PathBuf, std::io::Error),
+    /// The fw_cfg file {0:?} is larger than 4 GiB
+    FileTooLarge(PathBuf),
+}
+
+/// Holds the files of the fw_cfg device along with the configuration they were read from.
+#[derive(Debug, Default)]
+pub struct FwCfgBuilder {
+    config: Option<FwCfgConfig>,
+    files: Vec<FwCfgFile>,
+}
+
+impl FwCfgBuilder {
+    /// Reads all the files of `config`, replacing any previous configuration.
+    ///
+    /// Nothing is changed if any of the files is invalid.
+    pub fn set(&mut self, config: FwCfgConfig) -> Result<(), FwCfgConfigError> {
+        if config.files.len() > FW_CFG_MAX_FILES {
+            return Err(FwCfgConfigError::TooManyFiles);
+        }
+
+        let mut names = HashSet::new();
+        let files = config
+            .files
+            .iter()
+            .map(|file| {
+                let name = &file.name;
+                if name.is_empty()
+                    || name.len() > FW_CFG_MAX_NAME_LEN
+                    || !name.bytes().all(|byte| byte.is_ascii_graphic())
+                {
+                    return Err(FwCfgConfigError::InvalidName(name.clone()));
+                }
+                if !names.insert(name) {
+                    return Err(FwCfgConfigError::DuplicateName(name.clone()));
+                }
+                let path = PathBuf::from(&file.path_on_host);
+                let data = std::fs::read(&path)
+                    .map_err(|err| FwCfgConfigError::ReadFile(path.clone(), err))?;
+                if u32::try_from(data.len()).is_err() {
+                    return Err(FwCfgConfigError::FileTooLarge(path));
+                }
+                Ok(FwCfgFile {
+                    name: name.clone(),
+                    data,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        self.config = Some(config);
+        self.files = files;
+        Ok(())
+    }
+
+    /// Returns the configuration the files were read from, if any.
+    pub fn config(&self) -> Option<FwCfgConfig> {
+        self.config.clone()
+    }
+
+    /// Returns the files of the device.
+    pub fn files(&self) -> &[FwCfgFile] {
+        &self.files
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use vmm_sys_util::tempfile::TempFile;
+
+    use super::*;
+
+    fn file_config(name: &str, file: &TempFile) -> FwCfgFileConfig {
+        FwCfgFileConfig {
+            name: name.to_string(),
+            path_on_host: file.as_path().to_str().unwrap().to_string(),
+        }
+    }
+
+    #[test]
+    fn test_set_fw_cfg() {
+        let file = TempFile::new().unwrap();
+        file.as_file().write_all(b"{}").unwrap();
+        let mut builder = FwCfgBuilder::default();
+        assert!(builder.config().is_none());
+
+        let config = FwCfgConfig {
+            files: vec![
+                file_config("opt/com.coreos/config", &file),
+                file_config("bootorder", &file),
+            ],
+        };
+        builder.set(config.clone()).unwrap();
+        assert_eq!(builder.config().unwrap(), config);
+        assert_eq!(builder.files().len(), 2);
+        assert_eq!(builder.files()[0].name, "opt/com.coreos/config");
+        assert_eq!(builder.files()[0].data, b"{}");
+
+        // Invalid configurations are rejected and the previous one is kept.
+        let long_name = "a".repeat(FW_CFG_MAX_NAME_LEN + 1);
+        for name in ["", "opt/with space", long_name.as_str()] {
+            let err = builder
+                .set(FwCfgConfig {
+                    files: vec![file_config(name, &file)],
+                })
+                .unwrap_err();
+            assert!(matches!(err, FwCfgConfigError::InvalidName(_)), "{err:?}");
+        }
+        let err = builder
+            .set(FwCfgConfig {
+                files: vec![
+                    file_config("bootorder", &file),
+                    file_config("bootorder", &file),
+                ],
+            })
+            .unwrap_err();
+        assert!(matches!(err, FwCfgConfigError::DuplicateName(_)), "{err:?}");
+        let err = builder
+            .set(FwCfgConfig {
+                files: vec![FwCfgFileConfig {
+                    name: "bootorder".to_string(),
+                    path_on_host: "/invalid/path".to_string(),
+                }],
+            })
+            .unwrap_err();
+        assert!(matches!(err, FwCfgConfigError::ReadFile(_, _)), "{err:?}");
+        assert_eq!(builder.config().unwrap(), config);
+        assert_eq!(builder.files().len(), 2);
+
+        // An empty list clears the files.
+        builder.set(FwCfgConfig::default()).unwrap();
+        assert!(builder.files().is_empty());
+    }
+}
@@ -18,6 +18,8 @@ pub const MAX_SUPPORTED_VCPUS: u8 = 32;
 pub enum MachineConfigError {
     /// The memory size (MiB) is smaller than the previously set balloon device target size.
     IncompatibleBalloonSize,
+    /// The number of vCPUs or the memory size (MiB) does not match the previously set NUMA topology.
+    IncompatibleNumaTopology,
     /// The memory size (MiB) is either 0, or not a multiple of the configured page size.
     InvalidMemorySize,
     /// The number of vCPUs must be greater than 0, less than {MAX_SUPPORTED_VCPUS:} and must be 1 or an even number if SMT is enabled.
@@ -27,6 +29,17 @@ pub enum MachineConfigError {
     /// Enabling simultaneous multithreading is not supported on aarch64.
     #[cfg(target_arch = "aarch64")]
     SmtNotSupported,
+    /// Enabling nested virtualization is not supported on aarch64.
+    #[cfg(target_arch = "aarch64")]
+    NestedVirtualizationNotSupported,
+    /// Splitting the irqchip is not supported on aarch64.
+    #[cfg(target_arch = "aarch64")]
+    SplitIrqchipNotSupported,
+    /// The legacy-free platform is not supported on aarch64.
+    #[cfg(target_arch = "aarch64")]
+    LegacyFreeNotSupported,
+    /// The legacy-free platform requires a split irqchip, as KVM always emulates the PIC otherwise.
+    LegacyFreeRequiresSplitIrqchip,
     /// Could not determine host kernel version when checking hugetlbfs compatibility
     KernelVersion,
 }
@@ -113,6 +126,19 @@ pub struct MachineConfig {
     /// Configures what page size clawdbox should use to back guest memory.
     #[serde(default)]
     pub huge_pages: HugePageConfig,
+    /// Exposes hardware virtualization (VMX or SVM) to the guest, so that it can run its own
+    /// guests. MicroVMs with nested virtualization cannot be snapshotted.
+    #[serde(default)]
+    pub nested_virtualization: bool,
+    /// Emulates the IOAPIC in clawdbox instead of KVM, which only keeps the local APICs, along
+    /// with an interrupt remapping unit. There is no PIC nor PIT then. MicroVMs with a split
+    /// irqchip cannot be snapshotted.
+    #[serde(default)]
+    pub split_irqchip: bool,
+    /// Removes the PIT, the PIC and the i8042 controller from the platform, which requires a split
+    /// irqchip. The ACPI tables tell the guest they are missing.
+    #[serde(default)]
+    pub legacy_free: bool,
     /// GDB socket address.
     #[cfg(feature = "gdb")]
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -155,6 +181,9 @@ impl Default for MachineConfig {
             cpu_template: None,
             track_dirty_pages: false,
             huge_pages: HugePageConfig::None,
+            nested_virtualization: false,
+            split_irqchip: false,
+            legacy_free: false,
             #[cfg(feature = "gdb")]
             gdb_socket_path: None,
         }
@@ -188,6 +217,15 @@ pub struct MachineConfigUpdate {
     /// Configures what page size clawdbox should use to back guest memory.
     #[serde(default)]
     pub huge_pages: Option<HugePageConfig>,
+    /// Exposes hardware virtualization (VMX or SVM) to the guest.
+    #[serde(default)]
+    pub nested_virtualization: Option<bool>,
+    /// Emulates the IOAPIC in clawdbox instead of KVM.
+    #[serde(default)]
+    pub split_irqchip: Option<bool>,
+    /// Removes the PIT, the PIC and the i8042 controller from the platform.
+    #[serde(default)]
+    pub legacy_free: Option<bool>,
     /// GDB socket address.
     #[cfg(feature = "gdb")]
     #[serde(default)]
@@ -212,6 +250,9 @@ impl From<MachineConfig> for MachineConfigUpdate {
             cpu_template: cfg.static_template(),
             track_dirty_pages: Some(cfg.track_dirty_pages),
             huge_pages: Some(cfg.huge_pages),
+            nested_virtualization: Some(cfg.nested_virtualization),
+            split_irqchip: Some(cfg.split_irqchip),
+            legacy_free: Some(cfg.legacy_free),
             #[cfg(feature = "gdb")]
             gdb_socket_path: cfg.gdb_socket_path,
         }
@@ -249,6 +290,33 @@ impl MachineConfig {
             return Err(MachineConfigError::SmtNotSupported);
         }
 
+        let nested_virtualization = update
+            .nested_virtualization
+            .unwrap_or(self.nested_virtualization);
+
+        #[cfg(target_arch = "aarch64")]
+        if nested_virtualization {
+            return Err(MachineConfigError::NestedVirtualizationNotSupported);
+        }
+
+        let split_irqchip = update.split_irqchip.unwrap_or(self.split_irqchip);
+
+        #[cfg(target_arch = "aarch64")]
+        if split_irqchip {
+            return Err(MachineConfigError::SplitIrqchipNotSupported);
+        }
+
+        let legacy_free = update.legacy_free.unwrap_or(self.legacy_free);
+
+        #[cfg(target_arch = "aarch64")]
+        if legacy_free {
+            return Err(MachineConfigError::LegacyFreeNotSupported);
+        }
+
+        if legacy_free && !split_irqchip {
+            return Err(MachineConfigError::LegacyFreeRequiresSplitIrqchip);
+        }
+
         if vcpu_count == 0 || vcpu_count > MAX_SUPPORTED_VCPUS {
             return Err(MachineConfigError::InvalidVcpuCount);
         }
@@ -279,6 +347,9 @@ impl MachineConfig {
             cpu_template,
             track_dirty_pages: update.track_dirty_pages.unwrap_or(self.track_dirty_pages),
             huge_pages: page_config,
+            nested_virtualization,
+            split_irqchip,
+            legacy_free,
             #[cfg(feature = "gdb")]
             gdb_socket_path: update.gdb_socket_path.clone(),
         })
@@ -288,7 +359,7 @@ impl MachineConfig {
 #[cfg(test)]
 mod tests {
     use crate::cpu_config::templates::{CpuTemplateType, CustomCpuTemplate, StaticCpuTemplate};
-    use crate::vmm_config::machine_config::MachineConfig;
+    use crate::vmm_config::machine_config::{MachineConfig, MachineConfigUpdate};
 
     // Ensure the special (de)serialization logic for the cpu_template field works:
     // only static cpu templates can be specified via the machine-config endpoint, but
@@ -335,4 +406,72 @@ mod tests {
 
         assert!(deserialized.cpu_template.is_none());
     }
+
+    #[test]
+    fn test_update_nested_virtualization() {
+        let update = MachineConfigUpdate {
+            nested_virtualization: Some(true),
+            ..Default::default()
+        };
+        let result = MachineConfig::default().update(&update);
+        #[cfg(target_arch = "x86_64")]
+        {
+            let config = result.unwrap();
+            assert!(config.nested_virtualization);
+            // Updates which do not mention the flag leave it as it is.
+            let config = config.update(&MachineConfigUpdate::default()).unwrap();
+            assert!(config.nested_virtualization);
+        }
+        #[cfg(target_arch = "aarch64")]
+        assert_eq!(
+            result,
+            Err(super::MachineConfigError::NestedVirtualizationNotSupported)
+        );
+    }
+
+    #[test]
+    fn test_update_split_irqchip() {
+        let update = MachineConfigUpdate {
+            split_irqchip: Some(true),
+            ..Default::default()
+        };
+        let result = MachineConfig::default().update(&update);
+        #[cfg(target_arch = "x86_64")]
+        assert!(result.unwrap().split_irqchip);
+        #[cfg(target_arch = "aarch64")]
+        assert_eq!(
+            result,
+            Err(super::MachineConfigError::SplitIrqchipNotSupported)
+        );
+    }
+
+    #[test]
+    fn test_update_legacy_free() {
+        let update = MachineConfigUpdate {
+            legacy_free: Some(true),
+            ..Default::default()
+        };
+        #[cfg(target_arch = "x86_64")]
+        {
+            assert_eq!(
+                MachineConfig::default().update(&update),
+                Err(super::MachineConfigError::LegacyFreeRequiresSplitIrqchip)
+            );
+            let update = MachineConfigUpdate {
+                split_irqchip: Some(true),
+                ..update
+            };
+            assert!(
+                MachineConfig::default()
+                    .update(&update)
+                    .unwrap()
+                    .legacy_free
+            );
+        }
+        #[cfg(target_arch = "aarch64")]
+        assert_eq!(
+            MachineConfig::default().update(&update),
+            Err(super::MachineConfigError::LegacyFreeNotSupported)
+        );
+    }
 }
@@ -0,0 +1,179 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use serde::{Deserialize, Serialize};
+
+use crate::arch::GUEST_PAGE_SIZE;
+use crate::utils::usize_to_u64;
+
+/// Errors associated with the configuration of the guest memory map.
+#[derive(Debug, thiserror::Error, displaydoc::Display, PartialEq, Eq)]
+pub enum MemoryMapConfigError {
+    /// Custom memory map regions are only supported on x86_64
+    UnsupportedArch,
+    /// Memory map region {0:#x} is empty
+    EmptyRegion(u64),
+    /// Memory map region {0:#x} is not aligned to the guest page size
+    Unaligned(u64),
+    /// Memory map region {0:#x} overflows the guest physical address space
+    Overflow(u64),
+    /// Memory map region {0:#x} overlaps the boot structures in the first MiB of guest memory
+    LowMemory(u64),
+    /// Memory map regions {0:#x} and {1:#x} overlap
+    Overlap(u64, u64),
+}
+
+/// The type a memory map region is reported with to the guest.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MemoryMapRegionType {
+    /// Range the guest must not use.
+    Reserved,
+    /// Range the guest must not use and must preserve across sleep states.
+    Nvs,
+    /// Range of guest memory the guest uses as persistent memory.
+    Pmem,
+}
+
+/// A range declared in the guest physical memory map.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct MemoryMapRegion {
+    /// Guest physical address of the start of the range.
+    pub guest_address: u64,
+    /// Size of the range in bytes.
+    pub size: u64,
+    /// Type of the range.
+    #[serde(rename = "type")]
+    pub region_type: MemoryMapRegionType,
+}
+
+impl MemoryMapRegion {
+    /// Returns the first guest physical address past the range.
+    pub fn end(&self) -> u64 {
+        self.guest_address + self.size
+    }
+}
+
+/// The body of a PUT /memory-map request.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct MemoryMapConfig {
+    /// Ranges declared in the guest physical memory map, on top of guest memory and devices.
+    pub regions: Vec<MemoryMapRegion>,
+}
+
+impl MemoryMapConfig {
+    /// Validates the configuration.
+    ///
+    /// Whether the ranges fit the layout of the guest memory and devices is only known when
+    /// building the microVM, so it is checked then.
+    pub fn validate(&self) -> Result<(), MemoryMapConfigError> {
+        if cfg!(not(target_arch = "x86_64")) {
+            return Err(MemoryMapConfigError::UnsupportedArch);
+        }
+
+        let page_size = usize_to_u64(GUEST_PAGE_SIZE);
+        for region in &self.regions {
+            let addr = region.guest_address;
+            if region.size == 0 {
+                return Err(MemoryMapConfigError::EmptyRegion(addr));
+            }
+            if addr % page_size != 0 || region.size % page_size != 0 {
+                return Err(MemoryMapConfigError::Unaligned(addr));
+            }
+            if addr.checked_add(region.size).is_none() {
+                return Err(MemoryMapConfigError::Overflow(addr));
+            }
+            #[cfg(target_arch = "x86_64")]
+            if addr < crate::arch::x86_64::layout::HIMEM_START {
+                return Err(MemoryMapConfigError::LowMemory(addr));
+            }
+        }
+
+        let mut regions: Vec<_> = self.regions.iter().collect();
+        regions.sort_by_key(|region| region.guest_address);
+        for pair in regions.windows(2) {
+            if pair[0].end() > pair[1].guest_address {
+                return Err(MemoryMapConfigError::Overlap(
+                    pair[0].guest_address,
+                    pair[1].guest_address,
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn region(guest_address: u64, size: u64) -> MemoryMapRegion {
+        MemoryMapRegion {
+            guest_address,
+            size,
+            region_type: MemoryMapRegionType::Reserved,
+        }
+    }
+
+    #[test]
+    fn test_memory_map_config() {
+        let config: MemoryMapConfig = serde_json::from_str(
+            r#"{"regions": [{"guest_address": 1048576, "size": 4096, "type": "nvs"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(config.regions[0].region_type, MemoryMapRegionType::Nvs);
+        serde_json::from_str::<MemoryMapConfig>(
+            r#"{"regions": [{"guest_address": 1048576, "size": 4096, "type": "ram"}]}"#,
+        )
+        .unwrap_err();
+
+        #[cfg(target_arch = "x86_64")]
+        {
+            config.validate().unwrap();
+            MemoryMapConfig::default().validate().unwrap();
+
+            let config = MemoryMapConfig {
+                regions: vec![region(0x10_0000, 0)],
+            };
+            assert_eq!(
+                config.validate(),
+                Err(MemoryMapConfigError::EmptyRegion(0x10_0000))
+            );
+            let config = MemoryMapConfig {
+                regions: vec![region(0x10_0800, 0x1000)],
+            };
+            assert_eq!(
+                config.validate(),
+                Err(MemoryMapConfigError::Unaligned(0x10_0800))
+            );
+            let config = MemoryMapConfig {
+                regions: vec![region(0xffff_ffff_ffff_f000, 0x2000)],
+            };
+            assert_eq!(
+                config.validate(),
+                Err(MemoryMapConfigError::Overflow(0xffff_ffff_ffff_f000))
+            );
+            let config = MemoryMapConfig {
+                regions: vec![region(0xf_0000, 0x1000)],
+            };
+            assert_eq!(
+                config.validate(),
+                Err(MemoryMapConfigError::LowMemory(0xf_0000))
+            );
+            let config = MemoryMapConfig {
+                regions: vec![region(0x20_0000, 0x1000), region(0x10_0000, 0x10_1000)],
+            };
+            assert_eq!(
+                config.validate(),
+                Err(MemoryMapConfigError::Overlap(0x10_0000, 0x20_0000))
+            );
+        }
+        #[cfg(target_arch = "aarch64")]
+        assert_eq!(
+            config.validate(),
+            Err(MemoryMapConfigError::UnsupportedArch)
+        );
+    }
+}
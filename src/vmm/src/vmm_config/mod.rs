@@ -8,33 +8,75 @@ use serde::{Deserialize, Serialize};
 
 use crate::rate_limiter::{BucketUpdate, RateLimiter, TokenBucket};
 
+/// Wrapper for configuring user-supplied ACPI tables.
+pub mod acpi;
+/// Wrapper for configuring the forwarding of host memory errors to the guest.
+pub mod apei;
 /// Wrapper for configuring the balloon device.
 pub mod balloon;
 /// Wrapper for configuring the microVM boot source.
 pub mod boot_source;
+/// Configurations used for core dumps of the guest.
+pub mod core_dump;
+/// Wrapper for configuring the CPPC performance controls.
+pub mod cppc;
 /// Wrapper for configuring the block devices.
 pub mod drive;
 /// Wrapper for configuring the entropy device attached to the microVM.
 pub mod entropy;
+/// Wrapper for configuring the fw_cfg device.
+pub mod fw_cfg;
 /// Wrapper over the microVM general information attached to the microVM.
 pub mod instance_info;
 /// Wrapper for configuring the memory and CPU of the microVM.
 pub mod machine_config;
 /// Wrapper for configuring memory hotplug.
 pub mod memory_hotplug;
+/// Wrapper for configuring custom regions of the guest memory map.
+pub mod memory_map;
 /// Wrapper for configuring the metrics.
 pub mod metrics;
 /// Wrapper for configuring the MMDS.
 pub mod mmds;
 /// Wrapper for configuring the network devices attached to the microVM.
 pub mod net;
+/// Wrapper for the parameters of NMI injections.
+pub mod nmi;
+/// Wrapper for configuring the NUMA topology of the microVM.
+pub mod numa;
+/// Wrapper for configuring the NVMe controllers attached to the microVM.
+pub mod nvme;
 /// Wrapper for configuring the pmem devises attached to the microVM.
 pub mod pmem;
+/// Wrapper for configuring the virtual PMU exposed to the microVM.
+pub mod pmu;
+/// Wrapper for configuring the battery and AC adapter devices.
+pub mod power_supply;
+/// Wrapper for configuring the processor aggregator device.
+pub mod processor_aggregator;
+/// Wrapper for configuring guest reboots.
+pub mod reboot;
+/// Wrapper for configuring suspend-to-idle.
+pub mod s2idle;
+/// Wrapper for configuring the actions taken on timers and events of the guest.
+pub mod scheduled_actions;
 /// Wrapper for configuring microVM snapshots and the microVM state.
 pub mod serial;
+/// Wrapper for configuring the SGX enclave page cache exposed to the microVM.
+pub mod sgx;
+/// Wrapper for configuring the SMBIOS tables.
+pub mod smbios;
 pub mod snapshot;
+/// Wrapper for configuring the thermal zone exposed to the microVM.
+pub mod thermal_zone;
+/// Wrapper for configuring the USB devices attached to the microVM.
+pub mod usb;
+/// Wrapper for configuring the host PCI devices passed through to the microVM.
+pub mod vfio;
 /// Wrapper for configuring the vsock devices attached to the microVM.
 pub mod vsock;
+/// Wrapper for configuring the watchdog of the microVM.
+pub mod watchdog;
 
 // TODO: Migrate the VMM public-facing code (i.e. interface) to use stateless structures,
 // for receiving data/args, such as the below `RateLimiterConfig` and `TokenBucketConfig`.
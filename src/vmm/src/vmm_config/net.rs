@@ -28,6 +28,10 @@ pub struct NetworkInterfaceConfig {
     pub rx_rate_limiter: Option<RateLimiterConfig>,
     /// Rate Limiter for transmitted packages.
     pub tx_rate_limiter: Option<RateLimiterConfig>,
+    /// Offload the dataplane to the kernel vhost-net module. The userspace dataplane is used
+    /// instead when vhost-net is not available, or with MMDS or rate limiters.
+    #[serde(default)]
+    pub vhost: bool,
 }
 
 impl From<&Net> for NetworkInterfaceConfig {
@@ -40,6 +44,7 @@ impl From<&Net> for NetworkInterfaceConfig {
             guest_mac: net.guest_mac().copied(),
             rx_rate_limiter: rx_rl.into_option(),
             tx_rate_limiter: tx_rl.into_option(),
+            vhost: net.vhost,
         }
     }
 }
@@ -151,14 +156,16 @@ impl NetBuilder {
             .map_err(NetworkInterfaceError::CreateRateLimiter)?;
 
         // Create and return the Net device
-        crate::devices::virtio::net::Net::new(
+        let mut net = crate::devices::virtio::net::Net::new(
             cfg.iface_id,
             &cfg.host_dev_name,
             cfg.guest_mac,
             rx_rate_limiter.unwrap_or_default(),
             tx_rate_limiter.unwrap_or_default(),
         )
-        .map_err(NetworkInterfaceError::CreateNetworkDevice)
+        .map_err(NetworkInterfaceError::CreateNetworkDevice)?;
+        net.vhost = cfg.vhost;
+        Ok(net)
     }
 
     /// Returns a vec with the structures used to configure the net devices.
@@ -191,6 +198,7 @@ mod tests {
             guest_mac: Some(MacAddr::from_str(mac).unwrap()),
             rx_rate_limiter: RateLimiterConfig::default().into_option(),
             tx_rate_limiter: RateLimiterConfig::default().into_option(),
+            vhost: false,
         }
     }
 
@@ -202,6 +210,7 @@ mod tests {
                 guest_mac: self.guest_mac,
                 rx_rate_limiter: None,
                 tx_rate_limiter: None,
+                vhost: self.vhost,
             }
         }
     }
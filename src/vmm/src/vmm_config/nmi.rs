@@ -0,0 +1,13 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use serde::{Deserialize, Serialize};
+
+/// Stores the parameters used for injecting an NMI in the guest.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct InjectNmiParams {
+    /// Index of the vCPU the NMI is injected in. All vCPUs get one when not set.
+    #[serde(default)]
+    pub vcpu: Option<u8>,
+}
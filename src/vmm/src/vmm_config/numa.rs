@@ -0,0 +1,172 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Errors associated with the NUMA topology of the guest.
+#[derive(Debug, thiserror::Error, displaydoc::Display, PartialEq, Eq)]
+pub enum NumaConfigError {
+    /// NUMA topologies are only supported on x86_64
+    UnsupportedArch,
+    /// NUMA node {0} is declared more than once
+    DuplicateNode(u32),
+    /// NUMA node {0} has neither vCPUs nor memory
+    EmptyNode(u32),
+    /// NUMA node {1} holds vCPU {0}, but the microVM only has {2} vCPUs
+    UnknownVcpu(u8, u32, u8),
+    /// vCPU {0} is assigned to both NUMA nodes {1} and {2}
+    SharedVcpu(u8, u32, u32),
+    /// vCPU {0} is not assigned to any NUMA node
+    UnassignedVcpu(u8),
+    /// The NUMA nodes hold {0} MiB of memory in total, but the microVM has {1} MiB
+    MemorySizeMismatch(usize, usize),
+    /// Hotplugged memory is attached to both NUMA nodes {0} and {1}
+    SharedMemoryHotplug(u32, u32),
+    /// NUMA node {0} is declared to hold hotplugged memory, but memory hotplug is not configured
+    MemoryHotplugNotConfigured(u32),
+}
+
+/// A NUMA node of the guest.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct NumaNodeConfig {
+    /// Identifier of the node, reported to the guest as its proximity domain.
+    pub node_id: u32,
+    /// Indexes of the vCPUs belonging to the node.
+    #[serde(default)]
+    pub vcpus: Vec<u8>,
+    /// Memory of the node in MiB. The memory of the nodes is laid out in the order of their
+    /// identifiers.
+    #[serde(default)]
+    pub mem_size_mib: usize,
+    /// Whether the memory hotplugged into the microVM belongs to the node.
+    #[serde(default)]
+    pub memory_hotplug: bool,
+}
+
+/// Validates the NUMA topology against the vCPUs and memory of the microVM, returning its nodes
+/// in the order their memory is laid out in.
+pub fn validate_numa_nodes(
+    nodes: &[NumaNodeConfig],
+    vcpu_count: u8,
+    mem_size_mib: usize,
+    memory_hotplug: bool,
+) -> Result<Vec<NumaNodeConfig>, NumaConfigError> {
+    if nodes.is_empty() {
+        return Ok(Vec::new());
+    }
+    if cfg!(not(target_arch = "x86_64")) {
+        return Err(NumaConfigError::UnsupportedArch);
+    }
+
+    let mut sorted = BTreeMap::new();
+    for node in nodes {
+        if sorted.insert(node.node_id, node.clone()).is_some() {
+            return Err(NumaConfigError::DuplicateNode(node.node_id));
+        }
+        if node.vcpus.is_empty() && node.mem_size_mib == 0 && !node.memory_hotplug {
+            return Err(NumaConfigError::EmptyNode(node.node_id));
+        }
+    }
+
+    let mut vcpu_nodes = vec![None; usize::from(vcpu_count)];
+    let mut hotplug_node = None;
+    for node in sorted.values() {
+        for &vcpu in &node.vcpus {
+            match vcpu_nodes.get_mut(usize::from(vcpu)) {
+                None => return Err(NumaConfigError::UnknownVcpu(vcpu, node.node_id, vcpu_count)),
+                Some(Some(other)) => {
+                    return Err(NumaConfigError::SharedVcpu(vcpu, *other, node.node_id));
+                }
+                Some(slot) => *slot = Some(node.node_id),
+            }
+        }
+        if node.memory_hotplug {
+            if !memory_hotplug {
+                return Err(NumaConfigError::MemoryHotplugNotConfigured(node.node_id));
+            }
+            if let Some(other) = hotplug_node.replace(node.node_id) {
+                return Err(NumaConfigError::SharedMemoryHotplug(other, node.node_id));
+            }
+        }
+    }
+    if let Some(vcpu) = vcpu_nodes.iter().position(Option::is_none) {
+        return Err(NumaConfigError::UnassignedVcpu(u8::try_from(vcpu).unwrap()));
+    }
+
+    let nodes_mem_size_mib = sorted.values().map(|node| node.mem_size_mib).sum();
+    if nodes_mem_size_mib != mem_size_mib {
+        return Err(NumaConfigError::MemorySizeMismatch(
+            nodes_mem_size_mib,
+            mem_size_mib,
+        ));
+    }
+    Ok(sorted.into_values().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(node_id: u32, vcpus: &[u8], mem_size_mib: usize) -> NumaNodeConfig {
+        NumaNodeConfig {
+            node_id,
+            vcpus: vcpus.to_vec(),
+            mem_size_mib,
+            memory_hotplug: false,
+        }
+    }
+
+    #[test]
+    #[cfg(target_arch = "x86_64")]
+    fn test_validate_numa_nodes() {
+        assert_eq!(validate_numa_nodes(&[], 2, 256, false), Ok(Vec::new()));
+
+        let nodes = [node(1, &[1], 128), node(0, &[0], 128)];
+        let sorted = validate_numa_nodes(&nodes, 2, 256, false).unwrap();
+        assert_eq!(sorted, [nodes[1].clone(), nodes[0].clone()]);
+
+        assert_eq!(
+            validate_numa_nodes(&[node(0, &[0], 128), node(0, &[1], 128)], 2, 256, false),
+            Err(NumaConfigError::DuplicateNode(0))
+        );
+        assert_eq!(
+            validate_numa_nodes(&[node(0, &[0, 1], 256), node(1, &[], 0)], 2, 256, false),
+            Err(NumaConfigError::EmptyNode(1))
+        );
+        assert_eq!(
+            validate_numa_nodes(&[node(0, &[0, 2], 256)], 2, 256, false),
+            Err(NumaConfigError::UnknownVcpu(2, 0, 2))
+        );
+        assert_eq!(
+            validate_numa_nodes(&[node(0, &[0, 1], 128), node(1, &[1], 128)], 2, 256, false),
+            Err(NumaConfigError::SharedVcpu(1, 0, 1))
+        );
+        assert_eq!(
+            validate_numa_nodes(&[node(0, &[0], 128), node(1, &[], 128)], 2, 256, false),
+            Err(NumaConfigError::UnassignedVcpu(1))
+        );
+        assert_eq!(
+            validate_numa_nodes(&[node(0, &[0], 128), node(1, &[1], 64)], 2, 256, false),
+            Err(NumaConfigError::MemorySizeMismatch(192, 256))
+        );
+
+        // A node may hold nothing but the hotplugged memory.
+        let mut hotplug = node(1, &[], 0);
+        hotplug.memory_hotplug = true;
+        let nodes = [node(0, &[0, 1], 256), hotplug.clone()];
+        validate_numa_nodes(&nodes, 2, 256, true).unwrap();
+        assert_eq!(
+            validate_numa_nodes(&nodes, 2, 256, false),
+            Err(NumaConfigError::MemoryHotplugNotConfigured(1))
+        );
+        let mut other = hotplug.clone();
+        other.node_id = 2;
+        assert_eq!(
+            validate_numa_nodes(&[node(0, &[0, 1], 256), hotplug, other], 2, 256, true),
+            Err(NumaConfigError::SharedMemoryHotplug(1, 2))
+        );
+    }
+}
@@ -0,0 +1,96 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use serde::{Deserialize, Serialize};
+
+use crate::devices::virtio::block::CacheType;
+use crate::devices::virtio::block::virtio::device::FileEngineType;
+
+/// Errors associated with the configuration of NVMe controllers.
+#[derive(Debug, thiserror::Error, displaydoc::Display)]
+pub enum NvmeConfigError {
+    /// The disk image {0} is already attached to the guest
+    DuplicatePath(String),
+}
+
+/// Use this structure to attach an emulated NVMe controller, exposing a disk image as its
+/// namespace, to the guest.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NvmeDeviceConfig {
+    /// Unique identifier of the controller, also reported as its serial number.
+    pub id: String,
+    /// Path of the disk image.
+    pub path_on_host: String,
+    /// If set to true, the disk image is opened in read-only mode.
+    #[serde(default)]
+    pub is_read_only: bool,
+    /// If set to Writeback, the controller reports a volatile write cache and flushes the disk
+    /// image when the guest asks for it.
+    #[serde(default)]
+    pub cache_type: CacheType,
+    /// The type of IO engine used by the controller.
+    #[serde(default, rename = "io_engine")]
+    pub file_engine_type: FileEngineType,
+}
+
+/// Inserts an NVMe controller configuration, replacing the one with the same identifier if any.
+pub fn insert_nvme_config(
+    configs: &mut Vec<NvmeDeviceConfig>,
+    config: NvmeDeviceConfig,
+) -> Result<(), NvmeConfigError> {
+    if configs
+        .iter()
+        .any(|other| other.id != config.id && other.path_on_host == config.path_on_host)
+    {
+        return Err(NvmeConfigError::DuplicatePath(config.path_on_host));
+    }
+    match configs.iter_mut().find(|other| other.id == config.id) {
+        Some(other) => *other = config,
+        None => configs.push(config),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(id: &str, path_on_host: &str) -> NvmeDeviceConfig {
+        NvmeDeviceConfig {
+            id: id.to_string(),
+            path_on_host: path_on_host.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_insert_nvme_config() {
+        let mut configs = Vec::new();
+        insert_nvme_config(&mut configs, config("system", "/disks/system.img")).unwrap();
+        insert_nvme_config(&mut configs, config("data", "/disks/data.img")).unwrap();
+        assert_eq!(configs.len(), 2);
+
+        // Updating a controller keeps its position.
+        let mut update = config("system", "/disks/other.img");
+        update.is_read_only = true;
+        insert_nvme_config(&mut configs, update.clone()).unwrap();
+        assert_eq!(configs[0], update);
+
+        assert!(matches!(
+            insert_nvme_config(&mut configs, config("copy", "/disks/data.img")),
+            Err(NvmeConfigError::DuplicatePath(_))
+        ));
+        assert_eq!(configs.len(), 2);
+    }
+
+    #[test]
+    fn test_nvme_config_defaults() {
+        let config: NvmeDeviceConfig =
+            serde_json::from_str(r#"{"id": "system", "path_on_host": "/disks/system.img"}"#)
+                .unwrap();
+        assert_eq!(config, self::config("system", "/disks/system.img"));
+        assert_eq!(config.cache_type, CacheType::Unsafe);
+        assert_eq!(config.file_engine_type, FileEngineType::Sync);
+    }
+}
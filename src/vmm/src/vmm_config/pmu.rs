@@ -0,0 +1,103 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use serde::{Deserialize, Serialize};
+
+/// Maximum number of events in the allow-list, as accepted by KVM.
+pub const MAX_PMU_EVENTS: usize = 300;
+
+/// Errors associated with the configuration of the virtual PMU.
+#[derive(Debug, thiserror::Error, displaydoc::Display, PartialEq, Eq)]
+pub enum PmuConfigError {
+    /// The virtual PMU is only supported on x86_64
+    UnsupportedArch,
+    /// The allow-list cannot hold more than 300 events
+    TooManyEvents,
+    /// Invalid event select {0:#x}: it has at most 12 bits
+    InvalidEventSelect(u16),
+}
+
+/// A performance monitoring event, as programmed in the event select registers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct PmuEvent {
+    /// Event select, 8 bits on Intel and 12 bits on AMD.
+    pub event_select: u16,
+    /// Unit mask qualifying the event.
+    #[serde(default)]
+    pub unit_mask: u8,
+}
+
+/// The body of a PUT /pmu request.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct PmuConfig {
+    /// Events the guest is allowed to count. All of them are when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_events: Option<Vec<PmuEvent>>,
+}
+
+impl PmuConfig {
+    /// Validates the configuration.
+    ///
+    /// Whether the host KVM exposes a PMU is only known when building the microVM, so it is
+    /// checked then.
+    pub fn validate(&self) -> Result<(), PmuConfigError> {
+        if cfg!(not(target_arch = "x86_64")) {
+            return Err(PmuConfigError::UnsupportedArch);
+        }
+        let events = self.allowed_events.as_deref().unwrap_or_default();
+        if events.len() > MAX_PMU_EVENTS {
+            return Err(PmuConfigError::TooManyEvents);
+        }
+        if let Some(event) = events.iter().find(|event| event.event_select > 0xfff) {
+            return Err(PmuConfigError::InvalidEventSelect(event.event_select));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pmu_config() {
+        let config: PmuConfig = serde_json::from_str(
+            r#"{"allowed_events": [{"event_select": 60}, {"event_select": 46, "unit_mask": 79}]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.allowed_events.as_ref().unwrap()[1],
+            PmuEvent {
+                event_select: 0x2e,
+                unit_mask: 0x4f
+            }
+        );
+        serde_json::from_str::<PmuConfig>(r#"{"denied_events": []}"#).unwrap_err();
+
+        #[cfg(target_arch = "x86_64")]
+        {
+            config.validate().unwrap();
+            PmuConfig::default().validate().unwrap();
+
+            let event = PmuEvent {
+                event_select: 0x1000,
+                unit_mask: 0,
+            };
+            let config = PmuConfig {
+                allowed_events: Some(vec![event]),
+            };
+            assert_eq!(
+                config.validate(),
+                Err(PmuConfigError::InvalidEventSelect(0x1000))
+            );
+            let config = PmuConfig {
+                allowed_events: Some(vec![event; MAX_PMU_EVENTS + 1]),
+            };
+            assert_eq!(config.validate(), Err(PmuConfigError::TooManyEvents));
+        }
+        #[cfg(target_arch = "aarch64")]
+        assert_eq!(config.validate(), Err(PmuConfigError::UnsupportedArch));
+    }
+}
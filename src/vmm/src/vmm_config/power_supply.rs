@@ -0,0 +1,239 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use serde::{Deserialize, Serialize};
+
+/// Design capacity of the battery when none is configured, in mWh.
+pub const DEFAULT_DESIGN_CAPACITY_MWH: u32 = 50_000;
+
+/// Errors associated with the configuration of the power supply devices.
+#[derive(Debug, thiserror::Error, displaydoc::Display, PartialEq, Eq)]
+pub enum PowerSupplyConfigError {
+    /// The power supply devices are only supported on x86_64
+    UnsupportedArch,
+    /// The battery charge must be a percentage, got {0}
+    ChargePercent(u8),
+    /// The battery design capacity must be greater than zero
+    DesignCapacity,
+}
+
+/// Charging state of the battery.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatteryState {
+    /// The battery is neither charging nor discharging.
+    #[default]
+    Idle,
+    /// The battery is charging.
+    Charging,
+    /// The battery is discharging.
+    Discharging,
+}
+
+/// Configuration of the battery.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct BatteryConfig {
+    /// Whether the battery is inserted.
+    #[serde(default = "default_true")]
+    pub present: bool,
+    /// Design capacity of the battery, in mWh.
+    #[serde(default = "default_design_capacity_mwh")]
+    pub design_capacity_mwh: u32,
+    /// Remaining charge of the battery, in percent of its design capacity.
+    #[serde(default = "default_charge_percent")]
+    pub charge_percent: u8,
+    /// Charging state of the battery.
+    #[serde(default)]
+    pub state: BatteryState,
+    /// Rate at which the battery is charging or discharging, in mW.
+    #[serde(default)]
+    pub rate_mw: u32,
+}
+
+impl Default for BatteryConfig {
+    fn default() -> Self {
+        Self {
+            present: true,
+            design_capacity_mwh: DEFAULT_DESIGN_CAPACITY_MWH,
+            charge_percent: 100,
+            state: BatteryState::default(),
+            rate_mw: 0,
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_design_capacity_mwh() -> u32 {
+    DEFAULT_DESIGN_CAPACITY_MWH
+}
+
+fn default_charge_percent() -> u8 {
+    100
+}
+
+/// The body of a PUT /power-supply request.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct PowerSupplyConfig {
+    /// Whether the AC adapter is plugged in.
+    #[serde(default = "default_true")]
+    pub ac_online: bool,
+    /// Configuration of the battery.
+    #[serde(default)]
+    pub battery: BatteryConfig,
+}
+
+impl Default for PowerSupplyConfig {
+    fn default() -> Self {
+        Self {
+            ac_online: true,
+            battery: BatteryConfig::default(),
+        }
+    }
+}
+
+impl PowerSupplyConfig {
+    /// Validates the configuration.
+    pub fn validate(&self) -> Result<(), PowerSupplyConfigError> {
+        if cfg!(not(target_arch = "x86_64")) {
+            return Err(PowerSupplyConfigError::UnsupportedArch);
+        }
+        if self.battery.design_capacity_mwh == 0 {
+            return Err(PowerSupplyConfigError::DesignCapacity);
+        }
+        validate_charge_percent(self.battery.charge_percent)
+    }
+
+    /// Applies the values set by `update`.
+    pub fn apply(&mut self, update: &PowerSupplyUpdateConfig) {
+        if let Some(ac_online) = update.ac_online {
+            self.ac_online = ac_online;
+        }
+        let Some(battery) = &update.battery else {
+            return;
+        };
+        if let Some(present) = battery.present {
+            self.battery.present = present;
+        }
+        if let Some(charge_percent) = battery.charge_percent {
+            self.battery.charge_percent = charge_percent;
+        }
+        if let Some(state) = battery.state {
+            self.battery.state = state;
+        }
+        if let Some(rate_mw) = battery.rate_mw {
+            self.battery.rate_mw = rate_mw;
+        }
+    }
+}
+
+fn validate_charge_percent(charge_percent: u8) -> Result<(), PowerSupplyConfigError> {
+    if charge_percent > 100 {
+        return Err(PowerSupplyConfigError::ChargePercent(charge_percent));
+    }
+    Ok(())
+}
+
+/// Values of the battery to update.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct BatteryUpdateConfig {
+    /// Whether the battery is inserted.
+    pub present: Option<bool>,
+    /// Remaining charge of the battery, in percent of its design capacity.
+    pub charge_percent: Option<u8>,
+    /// Charging state of the battery.
+    pub state: Option<BatteryState>,
+    /// Rate at which the battery is charging or discharging, in mW.
+    pub rate_mw: Option<u32>,
+}
+
+/// The body of a PATCH /power-supply request.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct PowerSupplyUpdateConfig {
+    /// Whether the AC adapter is plugged in.
+    pub ac_online: Option<bool>,
+    /// Values of the battery to update.
+    pub battery: Option<BatteryUpdateConfig>,
+}
+
+impl PowerSupplyUpdateConfig {
+    /// Validates the update.
+    pub fn validate(&self) -> Result<(), PowerSupplyConfigError> {
+        match self
+            .battery
+            .as_ref()
+            .and_then(|battery| battery.charge_percent)
+        {
+            Some(charge_percent) => validate_charge_percent(charge_percent),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_power_supply_config() {
+        let config: PowerSupplyConfig = serde_json::from_str("{}").unwrap();
+        assert_eq!(config, PowerSupplyConfig::default());
+        assert!(config.ac_online);
+        assert!(config.battery.present);
+        assert_eq!(config.battery.charge_percent, 100);
+
+        let config: PowerSupplyConfig =
+            serde_json::from_str(r#"{"battery": {"charge_percent": 101}}"#).unwrap();
+        #[cfg(target_arch = "x86_64")]
+        assert_eq!(
+            config.validate(),
+            Err(PowerSupplyConfigError::ChargePercent(101))
+        );
+        let config: PowerSupplyConfig =
+            serde_json::from_str(r#"{"battery": {"design_capacity_mwh": 0}}"#).unwrap();
+        #[cfg(target_arch = "x86_64")]
+        assert_eq!(
+            config.validate(),
+            Err(PowerSupplyConfigError::DesignCapacity)
+        );
+        serde_json::from_str::<PowerSupplyConfig>(r#"{"battery": {"state": "full"}}"#).unwrap_err();
+
+        #[cfg(target_arch = "x86_64")]
+        PowerSupplyConfig::default().validate().unwrap();
+        #[cfg(target_arch = "aarch64")]
+        assert_eq!(
+            PowerSupplyConfig::default().validate(),
+            Err(PowerSupplyConfigError::UnsupportedArch)
+        );
+    }
+
+    #[test]
+    fn test_power_supply_update() {
+        let mut config = PowerSupplyConfig::default();
+        let update: PowerSupplyUpdateConfig = serde_json::from_str(
+            r#"{"ac_online": false, "battery": {"charge_percent": 40, "state": "discharging"}}"#,
+        )
+        .unwrap();
+        update.validate().unwrap();
+        config.apply(&update);
+        assert!(!config.ac_online);
+        assert_eq!(config.battery.charge_percent, 40);
+        assert_eq!(config.battery.state, BatteryState::Discharging);
+        // Values which are not set are left untouched.
+        assert!(config.battery.present);
+        assert_eq!(config.battery.rate_mw, 0);
+
+        let update: PowerSupplyUpdateConfig =
+            serde_json::from_str(r#"{"battery": {"charge_percent": 200}}"#).unwrap();
+        assert_eq!(
+            update.validate(),
+            Err(PowerSupplyConfigError::ChargePercent(200))
+        );
+    }
+}
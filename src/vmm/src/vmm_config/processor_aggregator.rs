@@ -0,0 +1,99 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use serde::{Deserialize, Serialize};
+
+/// Errors associated with the configuration of the processor aggregator device.
+#[derive(Debug, thiserror::Error, displaydoc::Display, PartialEq, Eq)]
+pub enum ProcessorAggregatorConfigError {
+    /// The processor aggregator device is only supported on x86_64
+    UnsupportedArch,
+    /// Cannot idle {0} of the {1} vCPUs, at least one vCPU must stay online
+    IdleCpus(u32, u8),
+}
+
+/// The body of a PUT /processor-aggregator request.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProcessorAggregatorConfig {
+    /// Number of vCPUs the guest is asked to idle when it boots.
+    #[serde(default)]
+    pub idle_cpus: u32,
+}
+
+impl ProcessorAggregatorConfig {
+    /// Validates the configuration against the number of vCPUs of the microVM.
+    pub fn validate(&self, vcpu_count: u8) -> Result<(), ProcessorAggregatorConfigError> {
+        if cfg!(not(target_arch = "x86_64")) {
+            return Err(ProcessorAggregatorConfigError::UnsupportedArch);
+        }
+        validate_idle_cpus(self.idle_cpus, vcpu_count)
+    }
+}
+
+/// The body of a PATCH /processor-aggregator request.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProcessorAggregatorUpdateConfig {
+    /// Number of vCPUs the guest is asked to idle.
+    pub idle_cpus: u32,
+}
+
+impl ProcessorAggregatorUpdateConfig {
+    /// Validates the update against the number of vCPUs of the microVM.
+    pub fn validate(&self, vcpu_count: u8) -> Result<(), ProcessorAggregatorConfigError> {
+        validate_idle_cpus(self.idle_cpus, vcpu_count)
+    }
+}
+
+fn validate_idle_cpus(
+    idle_cpus: u32,
+    vcpu_count: u8,
+) -> Result<(), ProcessorAggregatorConfigError> {
+    if idle_cpus >= u32::from(vcpu_count) {
+        return Err(ProcessorAggregatorConfigError::IdleCpus(
+            idle_cpus, vcpu_count,
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_processor_aggregator_config() {
+        let config: ProcessorAggregatorConfig = serde_json::from_str("{}").unwrap();
+        assert_eq!(config, ProcessorAggregatorConfig::default());
+        serde_json::from_str::<ProcessorAggregatorConfig>(r#"{"idle_cpus": -1}"#).unwrap_err();
+
+        #[cfg(target_arch = "x86_64")]
+        {
+            config.validate(1).unwrap();
+            let config = ProcessorAggregatorConfig { idle_cpus: 2 };
+            config.validate(4).unwrap();
+            assert_eq!(
+                config.validate(2),
+                Err(ProcessorAggregatorConfigError::IdleCpus(2, 2))
+            );
+        }
+        #[cfg(target_arch = "aarch64")]
+        assert_eq!(
+            config.validate(1),
+            Err(ProcessorAggregatorConfigError::UnsupportedArch)
+        );
+    }
+
+    #[test]
+    fn test_processor_aggregator_update() {
+        serde_json::from_str::<ProcessorAggregatorUpdateConfig>("{}").unwrap_err();
+        let update: ProcessorAggregatorUpdateConfig =
+            serde_json::from_str(r#"{"idle_cpus": 3}"#).unwrap();
+        update.validate(4).unwrap();
+        assert_eq!(
+            update.validate(3),
+            Err(ProcessorAggregatorConfigError::IdleCpus(3, 3))
+        );
+    }
+}
@@ -0,0 +1,61 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use serde::{Deserialize, Serialize};
+
+/// Errors associated with the configuration of guest reboots.
+#[derive(Debug, thiserror::Error, displaydoc::Display, PartialEq, Eq)]
+pub enum RebootConfigError {
+    /// Resetting the microVM in place is only supported on x86_64
+    UnsupportedArch,
+}
+
+/// Action taken by the VMM when the guest reboots.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RebootPolicy {
+    /// Shut down the microVM.
+    #[default]
+    Shutdown,
+    /// Reset the microVM in place and boot it again.
+    Reset,
+}
+
+/// The body of a PUT /reboot request.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct RebootConfig {
+    /// Action taken when the guest reboots.
+    #[serde(default)]
+    pub policy: RebootPolicy,
+}
+
+impl RebootConfig {
+    /// Validates the configuration.
+    pub fn validate(&self) -> Result<(), RebootConfigError> {
+        if cfg!(not(target_arch = "x86_64")) && self.policy == RebootPolicy::Reset {
+            return Err(RebootConfigError::UnsupportedArch);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reboot_config() {
+        let config: RebootConfig = serde_json::from_str("{}").unwrap();
+        assert_eq!(config.policy, RebootPolicy::Shutdown);
+        config.validate().unwrap();
+        let config: RebootConfig = serde_json::from_str(r#"{"policy": "reset"}"#).unwrap();
+        assert_eq!(config.policy, RebootPolicy::Reset);
+        serde_json::from_str::<RebootConfig>(r#"{"policy": "halt"}"#).unwrap_err();
+
+        #[cfg(target_arch = "x86_64")]
+        config.validate().unwrap();
+        #[cfg(target_arch = "aarch64")]
+        assert_eq!(config.validate(), Err(RebootConfigError::UnsupportedArch));
+    }
+}
@@ -0,0 +1,44 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use serde::{Deserialize, Serialize};
+
+/// Errors associated with the configuration of suspend-to-idle.
+#[derive(Debug, thiserror::Error, displaydoc::Display, PartialEq, Eq)]
+pub enum S2idleConfigError {
+    /// Suspend-to-idle is only supported on x86_64
+    UnsupportedArch,
+}
+
+/// The body of a PUT /s2idle request.
+///
+/// Suspend-to-idle has nothing to configure yet, configuring it enables it.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct S2idleConfig {}
+
+impl S2idleConfig {
+    /// Validates the configuration.
+    pub fn validate(&self) -> Result<(), S2idleConfigError> {
+        if cfg!(not(target_arch = "x86_64")) {
+            return Err(S2idleConfigError::UnsupportedArch);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_s2idle_config() {
+        let config: S2idleConfig = serde_json::from_str("{}").unwrap();
+        serde_json::from_str::<S2idleConfig>(r#"{"residency": 0}"#).unwrap_err();
+
+        #[cfg(target_arch = "x86_64")]
+        config.validate().unwrap();
+        #[cfg(target_arch = "aarch64")]
+        assert_eq!(config.validate(), Err(S2idleConfigError::UnsupportedArch));
+    }
+}
@@ -0,0 +1,271 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+/// Errors associated with the configuration of the scheduled actions.
+#[derive(Debug, thiserror::Error, displaydoc::Display, PartialEq, Eq)]
+pub enum ScheduledActionsConfigError {
+    /// Action {0}: the timer trigger requires a non-zero `interval_s`
+    MissingInterval(usize),
+    /// Action {0}: `interval_s` is only allowed with the timer trigger
+    UnexpectedInterval(usize),
+    /// Action {0}: snapshot actions require both `snapshot_path` and `mem_file_path`
+    MissingSnapshotPaths(usize),
+    /// Action {0}: `snapshot_path` and `mem_file_path` are only allowed with snapshot actions
+    UnexpectedSnapshotPaths(usize),
+    /// Action {0}: the balloon_adjust action requires `target_available_mib` and a non-zero
+    /// `step_mib`
+    MissingBalloonTarget(usize),
+    /// Action {0}: `target_available_mib` and `step_mib` are only allowed with the balloon_adjust
+    /// action
+    UnexpectedBalloonTarget(usize),
+    /// Action {0}: the balloon_adjust action is only supported with the timer trigger
+    UntimedBalloonAdjust(usize),
+    /// Action {0}: the guest_panic trigger is only supported on x86_64
+    UnsupportedArch(usize),
+    /// Action {0}: the balloon device must be configured first
+    MissingBalloon(usize),
+    /// Action {0}: the statistics of the balloon device must be enabled first
+    MissingBalloonStats(usize),
+    /// Action {0}: diff snapshots require dirty page tracking to be enabled
+    DirtyPageTrackingDisabled(usize),
+}
+
+/// Event triggering a scheduled action.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActionTrigger {
+    /// Every `interval_s` seconds while the microVM is running.
+    Timer,
+    /// When the guest reports a kernel panic through the pvpanic device.
+    GuestPanic,
+    /// When the OOM killer of the guest runs, as reported by the balloon statistics.
+    GuestOom,
+}
+
+/// Action taken by the VMM when its trigger fires.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScheduledActionKind {
+    /// Pause the microVM.
+    Pause,
+    /// Take a full snapshot of the microVM, overwriting the previous one.
+    Snapshot,
+    /// Take a diff snapshot of the microVM, overwriting the previous one.
+    DiffSnapshot,
+    /// Inflate or deflate the balloon by a step, towards a target of available guest memory.
+    BalloonAdjust,
+}
+
+/// An action taken by the VMM on a timer or on an event of the guest.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScheduledAction {
+    /// What triggers the action.
+    pub trigger: ActionTrigger,
+    /// Period of the timer trigger, in seconds.
+    pub interval_s: Option<u64>,
+    /// The action taken.
+    pub action: ScheduledActionKind,
+    /// Path of the file the microVM state is saved to, with snapshot actions.
+    pub snapshot_path: Option<PathBuf>,
+    /// Path of the file the guest memory is saved to, with snapshot actions.
+    pub mem_file_path: Option<PathBuf>,
+    /// Memory the guest should have available, in MiB, with the balloon_adjust action.
+    pub target_available_mib: Option<u32>,
+    /// Amount the balloon is inflated or deflated by at once, in MiB, with the balloon_adjust
+    /// action.
+    pub step_mib: Option<u32>,
+}
+
+impl ScheduledAction {
+    /// Validates the action, which is the `index`th one of the configuration.
+    pub fn validate(&self, index: usize) -> Result<(), ScheduledActionsConfigError> {
+        match (self.trigger, self.interval_s) {
+            (ActionTrigger::Timer, Some(interval_s)) if interval_s > 0 => (),
+            (ActionTrigger::Timer, _) => {
+                return Err(ScheduledActionsConfigError::MissingInterval(index));
+            }
+            (_, Some(_)) => return Err(ScheduledActionsConfigError::UnexpectedInterval(index)),
+            (ActionTrigger::GuestPanic, None) if cfg!(not(target_arch = "x86_64")) => {
+                return Err(ScheduledActionsConfigError::UnsupportedArch(index));
+            }
+            (_, None) => (),
+        }
+
+        let has_paths = (self.snapshot_path.is_some(), self.mem_file_path.is_some());
+        match (self.action, has_paths) {
+            (ScheduledActionKind::Snapshot | ScheduledActionKind::DiffSnapshot, (true, true)) => (),
+            (ScheduledActionKind::Snapshot | ScheduledActionKind::DiffSnapshot, _) => {
+                return Err(ScheduledActionsConfigError::MissingSnapshotPaths(index));
+            }
+            (_, (false, false)) => (),
+            (_, _) => return Err(ScheduledActionsConfigError::UnexpectedSnapshotPaths(index)),
+        }
+
+        let has_target = (self.target_available_mib.is_some(), self.step_mib.is_some());
+        match (self.action, has_target) {
+            (ScheduledActionKind::BalloonAdjust, (true, true)) if self.step_mib != Some(0) => {
+                if self.trigger != ActionTrigger::Timer {
+                    return Err(ScheduledActionsConfigError::UntimedBalloonAdjust(index));
+                }
+            }
+            (ScheduledActionKind::BalloonAdjust, _) => {
+                return Err(ScheduledActionsConfigError::MissingBalloonTarget(index));
+            }
+            (_, (false, false)) => (),
+            (_, _) => return Err(ScheduledActionsConfigError::UnexpectedBalloonTarget(index)),
+        }
+        Ok(())
+    }
+
+    /// Whether the action relies on the statistics of the balloon device.
+    pub fn needs_balloon_stats(&self) -> bool {
+        self.trigger == ActionTrigger::GuestOom || self.action == ScheduledActionKind::BalloonAdjust
+    }
+}
+
+/// The body of a PUT /scheduled-actions request.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScheduledActionsConfig {
+    /// The actions, taken in this order when several of them fire at once.
+    #[serde(default)]
+    pub actions: Vec<ScheduledAction>,
+}
+
+impl ScheduledActionsConfig {
+    /// Validates the configuration.
+    pub fn validate(&self) -> Result<(), ScheduledActionsConfigError> {
+        for (index, action) in self.actions.iter().enumerate() {
+            action.validate(index)?;
+        }
+        Ok(())
+    }
+
+    /// Whether an action is triggered by a panic of the guest, which then needs a pvpanic
+    /// device to report it.
+    pub fn has_panic_trigger(&self) -> bool {
+        self.actions
+            .iter()
+            .any(|action| action.trigger == ActionTrigger::GuestPanic)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn action(trigger: ActionTrigger, action: ScheduledActionKind) -> ScheduledAction {
+        ScheduledAction {
+            trigger,
+            interval_s: None,
+            action,
+            snapshot_path: None,
+            mem_file_path: None,
+            target_available_mib: None,
+            step_mib: None,
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        let config: ScheduledActionsConfig = serde_json::from_str(
+            r#"{"actions": [{"trigger": "timer", "interval_s": 60, "action": "diff_snapshot",
+                "snapshot_path": "vmstate", "mem_file_path": "mem"}]}"#,
+        )
+        .unwrap();
+        config.validate().unwrap();
+        assert!(!config.has_panic_trigger());
+
+        let pause = action(ActionTrigger::GuestOom, ScheduledActionKind::Pause);
+        pause.validate(0).unwrap();
+        assert!(pause.needs_balloon_stats());
+
+        let timer = action(ActionTrigger::Timer, ScheduledActionKind::Pause);
+        assert_eq!(
+            timer.validate(1),
+            Err(ScheduledActionsConfigError::MissingInterval(1))
+        );
+        let invalid = ScheduledAction {
+            interval_s: Some(0),
+            ..timer.clone()
+        };
+        assert_eq!(
+            invalid.validate(1),
+            Err(ScheduledActionsConfigError::MissingInterval(1))
+        );
+        let invalid = ScheduledAction {
+            interval_s: Some(10),
+            ..pause.clone()
+        };
+        assert_eq!(
+            invalid.validate(0),
+            Err(ScheduledActionsConfigError::UnexpectedInterval(0))
+        );
+
+        let snapshot = action(ActionTrigger::GuestPanic, ScheduledActionKind::Snapshot);
+        #[cfg(target_arch = "aarch64")]
+        assert_eq!(
+            snapshot.validate(0),
+            Err(ScheduledActionsConfigError::UnsupportedArch(0))
+        );
+        #[cfg(target_arch = "x86_64")]
+        {
+            assert_eq!(
+                snapshot.validate(0),
+                Err(ScheduledActionsConfigError::MissingSnapshotPaths(0))
+            );
+            let snapshot = ScheduledAction {
+                snapshot_path: Some(PathBuf::from("vmstate")),
+                mem_file_path: Some(PathBuf::from("mem")),
+                ..snapshot
+            };
+            snapshot.validate(0).unwrap();
+            let invalid = ScheduledAction {
+                action: ScheduledActionKind::Pause,
+                ..snapshot
+            };
+            assert_eq!(
+                invalid.validate(0),
+                Err(ScheduledActionsConfigError::UnexpectedSnapshotPaths(0))
+            );
+        }
+
+        let adjust = ScheduledAction {
+            interval_s: Some(5),
+            target_available_mib: Some(256),
+            ..action(ActionTrigger::Timer, ScheduledActionKind::BalloonAdjust)
+        };
+        assert_eq!(
+            adjust.validate(0),
+            Err(ScheduledActionsConfigError::MissingBalloonTarget(0))
+        );
+        let adjust = ScheduledAction {
+            step_mib: Some(32),
+            ..adjust
+        };
+        adjust.validate(0).unwrap();
+        assert!(adjust.needs_balloon_stats());
+        let invalid = ScheduledAction {
+            trigger: ActionTrigger::GuestOom,
+            interval_s: None,
+            ..adjust.clone()
+        };
+        assert_eq!(
+            invalid.validate(0),
+            Err(ScheduledActionsConfigError::UntimedBalloonAdjust(0))
+        );
+        let invalid = ScheduledAction {
+            action: ScheduledActionKind::Pause,
+            ..adjust
+        };
+        assert_eq!(
+            invalid.validate(0),
+            Err(ScheduledActionsConfigError::UnexpectedBalloonTarget(0))
+        );
+    }
+}
@@ -5,10 +5,91 @@ use std::path::PathBuf;
 
 use serde::Deserialize;
 
+/// Default amount of serial output kept while no client is connected to the serial socket.
+pub const DEFAULT_SERIAL_SCROLLBACK_BYTES: usize = 64 << 10;
+
+/// Errors associated with the configuration of the serial console.
+#[derive(Debug, thiserror::Error, displaydoc::Display, PartialEq, Eq)]
+pub enum SerialConfigError {
+    /// The serial console cannot be written to both a file and a Unix socket
+    ConflictingOutputs,
+    /// The serial scrollback can only be set along with a serial socket
+    ScrollbackWithoutSocket,
+    /// The serial scrollback cannot be empty
+    EmptyScrollback,
+}
+
 /// The body of a PUT /serial request.
-#[derive(Debug, PartialEq, Eq, Deserialize)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct SerialConfig {
     /// Named pipe or file used as output for guest serial console.
     pub serial_out_path: Option<PathBuf>,
+    /// Unix socket on which to serve the guest serial console, to one client at a time.
+    pub serial_socket_path: Option<PathBuf>,
+    /// Bytes of output kept while no client is connected to the serial socket, and sent to the
+    /// next one. Defaults to [`DEFAULT_SERIAL_SCROLLBACK_BYTES`].
+    pub scrollback_bytes: Option<usize>,
+}
+
+impl SerialConfig {
+    /// Validates the configuration.
+    pub fn validate(&self) -> Result<(), SerialConfigError> {
+        if self.serial_out_path.is_some() && self.serial_socket_path.is_some() {
+            return Err(SerialConfigError::ConflictingOutputs);
+        }
+        match self.scrollback_bytes {
+            Some(_) if self.serial_socket_path.is_none() => {
+                Err(SerialConfigError::ScrollbackWithoutSocket)
+            }
+            Some(0) => Err(SerialConfigError::EmptyScrollback),
+            _ => Ok(()),
+        }
+    }
+
+    /// Bytes of output kept while no client is connected to the serial socket.
+    pub fn scrollback_bytes(&self) -> usize {
+        self.scrollback_bytes
+            .unwrap_or(DEFAULT_SERIAL_SCROLLBACK_BYTES)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serial_config() {
+        let config: SerialConfig =
+            serde_json::from_str(r#"{"serial_socket_path": "serial.sock"}"#).unwrap();
+        config.validate().unwrap();
+        assert_eq!(config.scrollback_bytes(), DEFAULT_SERIAL_SCROLLBACK_BYTES);
+
+        let config = SerialConfig {
+            serial_out_path: Some(PathBuf::from("serial.log")),
+            serial_socket_path: Some(PathBuf::from("serial.sock")),
+            scrollback_bytes: None,
+        };
+        assert_eq!(
+            config.validate(),
+            Err(SerialConfigError::ConflictingOutputs)
+        );
+
+        let config = SerialConfig {
+            serial_out_path: Some(PathBuf::from("serial.log")),
+            serial_socket_path: None,
+            scrollback_bytes: Some(4096),
+        };
+        assert_eq!(
+            config.validate(),
+            Err(SerialConfigError::ScrollbackWithoutSocket)
+        );
+
+        let config = SerialConfig {
+            serial_out_path: None,
+            serial_socket_path: Some(PathBuf::from("serial.sock")),
+            scrollback_bytes: Some(0),
+        };
+        assert_eq!(config.validate(), Err(SerialConfigError::EmptyScrollback));
+    }
 }
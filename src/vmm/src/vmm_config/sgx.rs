@@ -0,0 +1,65 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use serde::{Deserialize, Serialize};
+
+/// Errors associated with the configuration of SGX.
+#[derive(Debug, thiserror::Error, displaydoc::Display, PartialEq, Eq)]
+pub enum SgxConfigError {
+    /// SGX is only supported on x86_64
+    UnsupportedArch,
+    /// The size of the enclave page cache cannot be 0
+    EmptyEpc,
+}
+
+/// The body of a PUT /sgx request.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct SgxConfig {
+    /// Size in MiB of the enclave page cache (EPC) section exposed to the guest.
+    pub epc_size_mib: usize,
+    /// Whether to populate the EPC section when the microVM is built, rather than when the guest
+    /// first touches it.
+    #[serde(default)]
+    pub prefault: bool,
+}
+
+impl SgxConfig {
+    /// Validates the configuration.
+    ///
+    /// Whether the host can back the EPC section is only known when building the microVM, so it
+    /// is checked then.
+    pub fn validate(&self) -> Result<(), SgxConfigError> {
+        if cfg!(not(target_arch = "x86_64")) {
+            return Err(SgxConfigError::UnsupportedArch);
+        }
+        if self.epc_size_mib == 0 {
+            return Err(SgxConfigError::EmptyEpc);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sgx_config() {
+        let config: SgxConfig = serde_json::from_str(r#"{"epc_size_mib": 64}"#).unwrap();
+        assert!(!config.prefault);
+        serde_json::from_str::<SgxConfig>(r#"{"epc_size_mib": 64, "sections": 2}"#).unwrap_err();
+
+        #[cfg(target_arch = "x86_64")]
+        {
+            config.validate().unwrap();
+            let config = SgxConfig {
+                epc_size_mib: 0,
+                prefault: true,
+            };
+            assert_eq!(config.validate(), Err(SgxConfigError::EmptyEpc));
+        }
+        #[cfg(target_arch = "aarch64")]
+        assert_eq!(config.validate(), Err(SgxConfigError::UnsupportedArch));
+    }
+}
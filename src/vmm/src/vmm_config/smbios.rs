@@ -0,0 +1,129 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Errors associated with the configuration of the SMBIOS tables.
+#[derive(Debug, thiserror::Error, displaydoc::Display, PartialEq, Eq)]
+pub enum SmbiosConfigError {
+    /// SMBIOS tables are only supported on x86_64
+    UnsupportedArch,
+    /// Invalid system UUID: {0}
+    Uuid(String),
+    /// The {0} string cannot be empty nor contain NUL characters
+    InvalidString(&'static str),
+}
+
+/// The body of a PUT /smbios request.
+///
+/// Every value which is not set is reported to the guest with a default value, and the system
+/// UUID is randomly generated.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct SmbiosConfig {
+    /// Manufacturer of the system, baseboard and chassis.
+    pub manufacturer: Option<String>,
+    /// Product name of the system and baseboard.
+    pub product_name: Option<String>,
+    /// Version of the system.
+    pub version: Option<String>,
+    /// Serial number of the system, baseboard and chassis.
+    pub serial_number: Option<String>,
+    /// UUID of the system.
+    pub uuid: Option<String>,
+    /// SKU number of the system.
+    pub sku_number: Option<String>,
+    /// Family of the system.
+    pub family: Option<String>,
+    /// Asset tag of the chassis.
+    pub asset_tag: Option<String>,
+}
+
+impl SmbiosConfig {
+    /// Validates the configuration.
+    pub fn validate(&self) -> Result<(), SmbiosConfigError> {
+        if cfg!(not(target_arch = "x86_64")) {
+            return Err(SmbiosConfigError::UnsupportedArch);
+        }
+        self.uuid()?;
+        for (name, value) in [
+            ("manufacturer", &self.manufacturer),
+            ("product_name", &self.product_name),
+            ("version", &self.version),
+            ("serial_number", &self.serial_number),
+            ("sku_number", &self.sku_number),
+            ("family", &self.family),
+            ("asset_tag", &self.asset_tag),
+        ] {
+            if let Some(value) = value
+                && (value.is_empty() || value.contains('\0'))
+            {
+                return Err(SmbiosConfigError::InvalidString(name));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the configured system UUID, if any.
+    pub fn uuid(&self) -> Result<Option<Uuid>, SmbiosConfigError> {
+        self.uuid
+            .as_deref()
+            .map(Uuid::parse_str)
+            .transpose()
+            .map_err(|err| SmbiosConfigError::Uuid(err.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_smbios_config() {
+        let config: SmbiosConfig = serde_json::from_str("{}").unwrap();
+        assert_eq!(config, SmbiosConfig::default());
+        serde_json::from_str::<SmbiosConfig>(r#"{"bios_vendor": "x"}"#).unwrap_err();
+
+        #[cfg(target_arch = "x86_64")]
+        {
+            config.validate().unwrap();
+            assert_eq!(config.uuid(), Ok(None));
+
+            let config: SmbiosConfig = serde_json::from_str(
+                r#"{"uuid": "e5c937d0-3553-4d7a-9117-ea4d19c3434d", "serial_number": "1234"}"#,
+            )
+            .unwrap();
+            config.validate().unwrap();
+            assert_eq!(
+                config.uuid().unwrap().unwrap().to_string(),
+                "e5c937d0-3553-4d7a-9117-ea4d19c3434d"
+            );
+
+            let config = SmbiosConfig {
+                uuid: Some("not-a-uuid".to_string()),
+                ..Default::default()
+            };
+            assert!(matches!(config.validate(), Err(SmbiosConfigError::Uuid(_))));
+
+            let config = SmbiosConfig {
+                family: Some(String::new()),
+                ..Default::default()
+            };
+            assert_eq!(
+                config.validate(),
+                Err(SmbiosConfigError::InvalidString("family"))
+            );
+            let config = SmbiosConfig {
+                product_name: Some("a\0b".to_string()),
+                ..Default::default()
+            };
+            assert_eq!(
+                config.validate(),
+                Err(SmbiosConfigError::InvalidString("product_name"))
+            );
+        }
+        #[cfg(target_arch = "aarch64")]
+        assert_eq!(config.validate(), Err(SmbiosConfigError::UnsupportedArch));
+    }
+}
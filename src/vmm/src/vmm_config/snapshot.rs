@@ -57,6 +57,17 @@ pub struct NetworkOverride {
     pub host_dev_name: String,
 }
 
+/// Identity given to a microVM that is cloned from a snapshot shared with other microVMs.
+///
+/// On load, every network interface that exposes a MAC address to the guest gets a fresh
+/// locally administered one, and the clone identity is published to the guest through MMDS.
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CloneConfig {
+    /// Identifier of the clone, unique among the microVMs restored from the same snapshot.
+    pub clone_id: String,
+}
+
 /// Stores the configuration that will be used for loading a snapshot.
 #[derive(Debug, PartialEq, Eq)]
 pub struct LoadSnapshotParams {
@@ -72,6 +83,8 @@ pub struct LoadSnapshotParams {
     pub resume_vm: bool,
     /// The network devices to override on load.
     pub network_overrides: Vec<NetworkOverride>,
+    /// When set, the microVM is restored as a clone with a regenerated identity.
+    pub clone: Option<CloneConfig>,
 }
 
 /// Stores the configuration for loading a snapshot that is provided by the user.
@@ -101,6 +114,9 @@ pub struct LoadSnapshotConfig {
     /// The network devices to override on load.
     #[serde(default)]
     pub network_overrides: Vec<NetworkOverride>,
+    /// Restore the microVM as a clone with a regenerated identity.
+    #[serde(default)]
+    pub clone: Option<CloneConfig>,
 }
 
 /// Stores the configuration used for managing snapshot memory.
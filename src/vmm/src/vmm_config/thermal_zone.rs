@@ -0,0 +1,195 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+/// Temperature reported when none is configured, in millidegrees Celsius.
+pub const DEFAULT_TEMPERATURE_MC: i32 = 40_000;
+/// Interval at which the host sensor is read when none is configured, in milliseconds.
+pub const DEFAULT_POLL_INTERVAL_MS: u64 = 1000;
+// Absolute zero, in millidegrees Celsius.
+const ABSOLUTE_ZERO_MC: i32 = -273_150;
+
+/// Errors associated with the configuration of the thermal zone.
+#[derive(Debug, thiserror::Error, displaydoc::Display, PartialEq, Eq)]
+pub enum ThermalZoneConfigError {
+    /// The thermal zone is only supported on x86_64
+    UnsupportedArch,
+    /// The temperature must be above absolute zero, got {0} millidegrees Celsius
+    Temperature(i32),
+    /// The hot trip point must be below the critical one
+    TripPointOrder,
+    /// The host sensor poll interval must be greater than zero
+    PollInterval,
+    /// The temperature is read from the host sensor, it cannot be set
+    HostSensor,
+}
+
+/// The body of a PUT /thermal-zone request.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ThermalZoneConfig {
+    /// Host hwmon temperature input the temperature is read from, such as
+    /// `/sys/class/hwmon/hwmon0/temp1_input`. The temperature is set through the API when unset.
+    pub host_sensor: Option<PathBuf>,
+    /// Interval at which the host sensor is read, in milliseconds.
+    #[serde(default = "default_poll_interval_ms")]
+    pub poll_interval_ms: u64,
+    /// Temperature of the thermal zone, in millidegrees Celsius, when it has no host sensor.
+    #[serde(default = "default_temperature_mc")]
+    pub temperature_mc: i32,
+    /// Temperature at which the guest is expected to put itself to sleep, in millidegrees
+    /// Celsius.
+    pub hot_mc: Option<i32>,
+    /// Temperature at which the guest is expected to shut down, in millidegrees Celsius.
+    pub critical_mc: Option<i32>,
+}
+
+impl Default for ThermalZoneConfig {
+    fn default() -> Self {
+        Self {
+            host_sensor: None,
+            poll_interval_ms: DEFAULT_POLL_INTERVAL_MS,
+            temperature_mc: DEFAULT_TEMPERATURE_MC,
+            hot_mc: None,
+            critical_mc: None,
+        }
+    }
+}
+
+fn default_poll_interval_ms() -> u64 {
+    DEFAULT_POLL_INTERVAL_MS
+}
+
+fn default_temperature_mc() -> i32 {
+    DEFAULT_TEMPERATURE_MC
+}
+
+fn validate_temperature(temperature_mc: i32) -> Result<(), ThermalZoneConfigError> {
+    if temperature_mc < ABSOLUTE_ZERO_MC {
+        return Err(ThermalZoneConfigError::Temperature(temperature_mc));
+    }
+    Ok(())
+}
+
+impl ThermalZoneConfig {
+    /// Validates the configuration.
+    pub fn validate(&self) -> Result<(), ThermalZoneConfigError> {
+        if cfg!(not(target_arch = "x86_64")) {
+            return Err(ThermalZoneConfigError::UnsupportedArch);
+        }
+        for temperature_mc in [Some(self.temperature_mc), self.hot_mc, self.critical_mc]
+            .into_iter()
+            .flatten()
+        {
+            validate_temperature(temperature_mc)?;
+        }
+        if let (Some(hot_mc), Some(critical_mc)) = (self.hot_mc, self.critical_mc)
+            && hot_mc >= critical_mc
+        {
+            return Err(ThermalZoneConfigError::TripPointOrder);
+        }
+        if self.host_sensor.is_some() && self.poll_interval_ms == 0 {
+            return Err(ThermalZoneConfigError::PollInterval);
+        }
+        Ok(())
+    }
+
+    /// Returns the trip points of the thermal zone, in increasing order.
+    pub fn trip_points(&self) -> impl Iterator<Item = i32> {
+        [self.hot_mc, self.critical_mc].into_iter().flatten()
+    }
+}
+
+/// The body of a PATCH /thermal-zone request.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ThermalZoneUpdateConfig {
+    /// Temperature of the thermal zone, in millidegrees Celsius.
+    pub temperature_mc: i32,
+}
+
+impl ThermalZoneUpdateConfig {
+    /// Validates the update against the configuration of the thermal zone.
+    pub fn validate(&self, config: &ThermalZoneConfig) -> Result<(), ThermalZoneConfigError> {
+        if config.host_sensor.is_some() {
+            return Err(ThermalZoneConfigError::HostSensor);
+        }
+        validate_temperature(self.temperature_mc)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_thermal_zone_config() {
+        let config: ThermalZoneConfig = serde_json::from_str("{}").unwrap();
+        assert_eq!(config, ThermalZoneConfig::default());
+        assert_eq!(config.trip_points().count(), 0);
+        serde_json::from_str::<ThermalZoneConfig>(r#"{"passive_mc": 50000}"#).unwrap_err();
+
+        let config: ThermalZoneConfig =
+            serde_json::from_str(r#"{"hot_mc": 80000, "critical_mc": 95000}"#).unwrap();
+        assert_eq!(config.trip_points().collect::<Vec<_>>(), [80_000, 95_000]);
+
+        #[cfg(target_arch = "x86_64")]
+        {
+            config.validate().unwrap();
+            let config = ThermalZoneConfig {
+                hot_mc: Some(95_000),
+                critical_mc: Some(95_000),
+                ..Default::default()
+            };
+            assert_eq!(
+                config.validate(),
+                Err(ThermalZoneConfigError::TripPointOrder)
+            );
+            let config = ThermalZoneConfig {
+                temperature_mc: -300_000,
+                ..Default::default()
+            };
+            assert_eq!(
+                config.validate(),
+                Err(ThermalZoneConfigError::Temperature(-300_000))
+            );
+            let config = ThermalZoneConfig {
+                host_sensor: Some(PathBuf::from("/sys/class/hwmon/hwmon0/temp1_input")),
+                poll_interval_ms: 0,
+                ..Default::default()
+            };
+            assert_eq!(config.validate(), Err(ThermalZoneConfigError::PollInterval));
+        }
+        #[cfg(target_arch = "aarch64")]
+        assert_eq!(
+            config.validate(),
+            Err(ThermalZoneConfigError::UnsupportedArch)
+        );
+    }
+
+    #[test]
+    fn test_thermal_zone_update() {
+        let mut config = ThermalZoneConfig::default();
+        let update: ThermalZoneUpdateConfig =
+            serde_json::from_str(r#"{"temperature_mc": 85000}"#).unwrap();
+        update.validate(&config).unwrap();
+        serde_json::from_str::<ThermalZoneUpdateConfig>(r#"{"hot_mc": 85000}"#).unwrap_err();
+
+        let update = ThermalZoneUpdateConfig {
+            temperature_mc: -280_000,
+        };
+        assert_eq!(
+            update.validate(&config),
+            Err(ThermalZoneConfigError::Temperature(-280_000))
+        );
+
+        config.host_sensor = Some(PathBuf::from("/sys/class/hwmon/hwmon0/temp1_input"));
+        assert_eq!(
+            update.validate(&config),
+            Err(ThermalZoneConfigError::HostSensor)
+        );
+    }
+}
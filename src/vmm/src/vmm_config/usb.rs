@@ -0,0 +1,119 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+/// Maximum number of USB devices, one per port of the xHCI controller.
+pub const MAX_USB_DEVICES: usize = 8;
+
+/// Errors associated with the configuration of USB devices.
+#[derive(Debug, thiserror::Error, displaydoc::Display)]
+pub enum UsbConfigError {
+    /// Exactly one of usbfs_path and hidraw_path must be set
+    InvalidBackend,
+    /// The host device {0:?} is already attached to the guest
+    DuplicatePath(PathBuf),
+    /// Too many USB devices, at most {MAX_USB_DEVICES} are supported
+    TooManyDevices,
+}
+
+/// Use this structure to attach a USB device to the xHCI controller of the guest.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UsbDeviceConfig {
+    /// Unique identifier of the device.
+    pub id: String,
+    /// usbfs node of a host USB device passed through to the guest, e.g. /dev/bus/usb/001/004.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usbfs_path: Option<PathBuf>,
+    /// hidraw node of a host HID device, e.g. /dev/hidraw0, exposed to the guest as an emulated
+    /// USB HID device.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hidraw_path: Option<PathBuf>,
+}
+
+impl UsbDeviceConfig {
+    /// Path of the host node backing the device.
+    pub fn path(&self) -> Option<&PathBuf> {
+        self.usbfs_path.as_ref().or(self.hidraw_path.as_ref())
+    }
+}
+
+/// Inserts a USB device configuration, replacing the one with the same identifier if any.
+pub fn insert_usb_config(
+    configs: &mut Vec<UsbDeviceConfig>,
+    config: UsbDeviceConfig,
+) -> Result<(), UsbConfigError> {
+    let path = match (&config.usbfs_path, &config.hidraw_path) {
+        (Some(path), None) | (None, Some(path)) => path,
+        _ => return Err(UsbConfigError::InvalidBackend),
+    };
+    if configs
+        .iter()
+        .any(|other| other.id != config.id && other.path() == Some(path))
+    {
+        return Err(UsbConfigError::DuplicatePath(path.clone()));
+    }
+    match configs.iter_mut().find(|other| other.id == config.id) {
+        Some(other) => *other = config,
+        None if configs.len() == MAX_USB_DEVICES => return Err(UsbConfigError::TooManyDevices),
+        None => configs.push(config),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(id: &str, usbfs_path: &str) -> UsbDeviceConfig {
+        UsbDeviceConfig {
+            id: id.to_string(),
+            usbfs_path: Some(PathBuf::from(usbfs_path)),
+            hidraw_path: None,
+        }
+    }
+
+    #[test]
+    fn test_insert_usb_config() {
+        let mut configs = Vec::new();
+        insert_usb_config(&mut configs, config("key", "/dev/bus/usb/001/004")).unwrap();
+        insert_usb_config(&mut configs, config("dongle", "/dev/bus/usb/001/005")).unwrap();
+        assert_eq!(configs.len(), 2);
+
+        // Updating a device keeps its position.
+        let hid = UsbDeviceConfig {
+            id: "key".to_string(),
+            usbfs_path: None,
+            hidraw_path: Some(PathBuf::from("/dev/hidraw0")),
+        };
+        insert_usb_config(&mut configs, hid.clone()).unwrap();
+        assert_eq!(configs[0], hid);
+
+        assert!(matches!(
+            insert_usb_config(&mut configs, config("other", "/dev/bus/usb/001/005")),
+            Err(UsbConfigError::DuplicatePath(_))
+        ));
+        let mut both = config("both", "/dev/bus/usb/001/006");
+        both.hidraw_path = Some(PathBuf::from("/dev/hidraw1"));
+        assert!(matches!(
+            insert_usb_config(&mut configs, both),
+            Err(UsbConfigError::InvalidBackend)
+        ));
+        assert!(matches!(
+            insert_usb_config(&mut configs, UsbDeviceConfig::default()),
+            Err(UsbConfigError::InvalidBackend)
+        ));
+
+        for index in configs.len()..MAX_USB_DEVICES {
+            let path = format!("/dev/bus/usb/002/{index:03}");
+            insert_usb_config(&mut configs, config(&index.to_string(), &path)).unwrap();
+        }
+        assert!(matches!(
+            insert_usb_config(&mut configs, config("last", "/dev/bus/usb/003/001")),
+            Err(UsbConfigError::TooManyDevices)
+        ));
+    }
+}
@@ -0,0 +1,159 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::path::PathBuf;
+
+use pci::PciBdf;
+use serde::{Deserialize, Serialize};
+
+use crate::devices::pci::vfio::sriov::{SriovError, bind_virtual_function};
+
+/// Errors associated with the configuration of VFIO devices.
+#[derive(Debug, thiserror::Error, displaydoc::Display)]
+pub enum VfioConfigError {
+    /// Invalid VFIO device path {0:?}, expected the sysfs directory of a PCI device
+    InvalidPath(PathBuf),
+    /// The host device {0:?} is already passed through to the guest
+    DuplicatePath(PathBuf),
+    /// Invalid BDF of the physical function: {0}
+    InvalidPfBdf(String),
+    /// SR-IOV error: {0}
+    Sriov(#[from] SriovError),
+}
+
+/// Use this structure to pass a host PCI device through to the guest with VFIO.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VfioConfig {
+    /// Unique identifier of the device.
+    pub id: String,
+    /// Sysfs directory of the host PCI device, e.g. /sys/bus/pci/devices/0000:01:00.0. The
+    /// device needs to be bound to the vfio-pci driver.
+    pub path: PathBuf,
+}
+
+impl VfioConfig {
+    /// Checks the path names a PCI device.
+    pub fn validate(&self) -> Result<(), VfioConfigError> {
+        self.path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.parse::<PciBdf>().ok())
+            .map(|_| ())
+            .ok_or_else(|| VfioConfigError::InvalidPath(self.path.clone()))
+    }
+}
+
+/// Use this structure to pass a virtual function of an SR-IOV capable host device through to the
+/// guest with VFIO.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VfioVfConfig {
+    /// Unique identifier of the device.
+    pub id: String,
+    /// BDF of the physical function on the host, e.g. 0000:3b:00.0.
+    pub pf_bdf: String,
+    /// Index of the virtual function of the physical function.
+    pub vf_index: u32,
+}
+
+impl VfioVfConfig {
+    /// Binds the virtual function to vfio-pci, and returns the configuration of the VFIO device
+    /// passing it through.
+    pub fn bind(&self) -> Result<VfioConfig, VfioConfigError> {
+        let pf = self
+            .pf_bdf
+            .parse::<PciBdf>()
+            .map_err(|_| VfioConfigError::InvalidPfBdf(self.pf_bdf.clone()))?;
+        let path = bind_virtual_function(pf, self.vf_index)?;
+        Ok(VfioConfig {
+            id: self.id.clone(),
+            path,
+        })
+    }
+}
+
+/// Inserts a VFIO device configuration, replacing the one with the same identifier if any.
+pub fn insert_vfio_config(
+    configs: &mut Vec<VfioConfig>,
+    config: VfioConfig,
+) -> Result<(), VfioConfigError> {
+    config.validate()?;
+    if configs
+        .iter()
+        .any(|other| other.id != config.id && other.path == config.path)
+    {
+        return Err(VfioConfigError::DuplicatePath(config.path));
+    }
+    match configs.iter_mut().find(|other| other.id == config.id) {
+        Some(other) => *other = config,
+        None => configs.push(config),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(id: &str, path: &str) -> VfioConfig {
+        VfioConfig {
+            id: id.to_string(),
+            path: PathBuf::from(path),
+        }
+    }
+
+    #[test]
+    fn test_insert_vfio_config() {
+        let mut configs = Vec::new();
+        insert_vfio_config(
+            &mut configs,
+            config("gpu0", "/sys/bus/pci/devices/0000:01:00.0"),
+        )
+        .unwrap();
+        insert_vfio_config(
+            &mut configs,
+            config("gpu1", "/sys/bus/pci/devices/0000:02:00.0"),
+        )
+        .unwrap();
+        assert_eq!(configs.len(), 2);
+
+        // Updating a device by its identifier.
+        insert_vfio_config(
+            &mut configs,
+            config("gpu1", "/sys/bus/pci/devices/0000:03:00.0"),
+        )
+        .unwrap();
+        assert_eq!(configs.len(), 2);
+        assert_eq!(
+            configs[1].path,
+            PathBuf::from("/sys/bus/pci/devices/0000:03:00.0")
+        );
+
+        assert!(matches!(
+            insert_vfio_config(
+                &mut configs,
+                config("gpu2", "/sys/bus/pci/devices/0000:01:00.0")
+            ),
+            Err(VfioConfigError::DuplicatePath(_))
+        ));
+        assert!(matches!(
+            insert_vfio_config(&mut configs, config("gpu2", "/dev/vfio/12")),
+            Err(VfioConfigError::InvalidPath(_))
+        ));
+        assert_eq!(configs.len(), 2);
+    }
+
+    #[test]
+    fn test_bind_vf_invalid_pf() {
+        let vf_config = VfioVfConfig {
+            id: "nic0".to_string(),
+            pf_bdf: "3b:00.0".to_string(),
+            vf_index: 0,
+        };
+        assert!(matches!(
+            vf_config.bind(),
+            Err(VfioConfigError::InvalidPfBdf(_))
+        ));
+    }
+}
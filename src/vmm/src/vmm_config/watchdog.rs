@@ -0,0 +1,102 @@
+// Copyright 2025 Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+/// Errors associated with the configuration of the watchdog.
+#[derive(Debug, thiserror::Error, displaydoc::Display, PartialEq, Eq)]
+pub enum WatchdogConfigError {
+    /// The snapshot action requires both `snapshot_path` and `mem_file_path`
+    MissingSnapshotPaths,
+    /// `snapshot_path` and `mem_file_path` are only allowed with the snapshot action
+    UnexpectedSnapshotPaths,
+    /// The watchdog is only supported on x86_64
+    UnsupportedArch,
+}
+
+/// Action taken by the VMM when the guest lets the watchdog expire.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WatchdogAction {
+    /// Shut down the microVM, as for a reset requested by the guest, with a dedicated exit code.
+    #[default]
+    Reset,
+    /// Pause the microVM.
+    Pause,
+    /// Only report the expiry, through the logs and the watchdog status.
+    Notify,
+    /// Pause the microVM and take a full snapshot of it.
+    Snapshot,
+}
+
+/// The body of a PUT /watchdog request.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct WatchdogConfig {
+    /// Action taken when the watchdog expires.
+    #[serde(default)]
+    pub action: WatchdogAction,
+    /// Path of the file the microVM state is saved to, with the snapshot action.
+    pub snapshot_path: Option<PathBuf>,
+    /// Path of the file the guest memory is saved to, with the snapshot action.
+    pub mem_file_path: Option<PathBuf>,
+}
+
+impl WatchdogConfig {
+    /// Validates the configuration.
+    pub fn validate(&self) -> Result<(), WatchdogConfigError> {
+        if cfg!(not(target_arch = "x86_64")) {
+            return Err(WatchdogConfigError::UnsupportedArch);
+        }
+
+        let has_paths = (self.snapshot_path.is_some(), self.mem_file_path.is_some());
+        match (self.action, has_paths) {
+            (WatchdogAction::Snapshot, (true, true)) => Ok(()),
+            (WatchdogAction::Snapshot, _) => Err(WatchdogConfigError::MissingSnapshotPaths),
+            (_, (false, false)) => Ok(()),
+            (_, _) => Err(WatchdogConfigError::UnexpectedSnapshotPaths),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        let config: WatchdogConfig = serde_json::from_str("{}").unwrap();
+        assert_eq!(config.action, WatchdogAction::Reset);
+
+        let config = WatchdogConfig {
+            action: WatchdogAction::Snapshot,
+            snapshot_path: Some(PathBuf::from("vmstate")),
+            mem_file_path: None,
+        };
+        #[cfg(target_arch = "x86_64")]
+        assert_eq!(
+            config.validate(),
+            Err(WatchdogConfigError::MissingSnapshotPaths)
+        );
+        let config = WatchdogConfig {
+            mem_file_path: Some(PathBuf::from("mem")),
+            ..config
+        };
+        #[cfg(target_arch = "x86_64")]
+        config.validate().unwrap();
+        #[cfg(target_arch = "aarch64")]
+        assert_eq!(config.validate(), Err(WatchdogConfigError::UnsupportedArch));
+
+        let config = WatchdogConfig {
+            action: WatchdogAction::Pause,
+            ..config
+        };
+        #[cfg(target_arch = "x86_64")]
+        assert_eq!(
+            config.validate(),
+            Err(WatchdogConfigError::UnexpectedSnapshotPaths)
+        );
+    }
+}
@@ -394,6 +394,52 @@ impl GuestRegionMmapExt {
             }
         }
     }
+
+    /// Lazily frees a range: the host reclaims the pages only under memory pressure, and the
+    /// guest finds them untouched if it writes them back before that.
+    ///
+    /// MADV_FREE only applies to private anonymous mappings, so any other mapping falls back to
+    /// [`GuestRegionMmapExt::discard_range`].
+    pub(crate) fn free_range(
+        &self,
+        caddr: MemoryRegionAddress,
+        len: usize,
+    ) -> Result<(), GuestMemoryError> {
+        if self.inner.file_offset().is_some() || self.inner.flags() & libc::MAP_PRIVATE == 0 {
+            return self.discard_range(caddr, len);
+        }
+
+        let phys_address = self.get_host_address(caddr)?;
+        // SAFETY: The address and length are known to be valid.
+        let ret = unsafe { libc::madvise(phys_address.cast(), len, libc::MADV_FREE) };
+        if ret < 0 {
+            let os_error = std::io::Error::last_os_error();
+            error!("free_range: madvise failed: {:?}", os_error);
+            Err(GuestMemoryError::IOError(os_error))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Marks the whole region as a candidate for kernel samepage merging.
+    pub(crate) fn mark_mergeable(&self) -> Result<(), GuestMemoryError> {
+        let phys_address = self.get_host_address(MemoryRegionAddress(0))?;
+        // SAFETY: The address and length are those of the mapping of the region.
+        let ret = unsafe {
+            libc::madvise(
+                phys_address.cast(),
+                u64_to_usize(self.len()),
+                libc::MADV_MERGEABLE,
+            )
+        };
+        if ret < 0 {
+            let os_error = std::io::Error::last_os_error();
+            error!("mark_mergeable: madvise failed: {:?}", os_error);
+            Err(GuestMemoryError::IOError(os_error))
+        } else {
+            Ok(())
+        }
+    }
 }
 
 impl Deref for GuestRegionMmapExt {
@@ -582,6 +628,12 @@ where
 
     /// Discards a memory range, freeing up memory pages
     fn discard_range(&self, addr: GuestAddress, range_len: usize) -> Result<(), GuestMemoryError>;
+
+    /// Lazily frees a memory range, letting the host reclaim its pages under memory pressure
+    fn free_range(&self, addr: GuestAddress, range_len: usize) -> Result<(), GuestMemoryError>;
+
+    /// Marks all memory regions as candidates for kernel samepage merging
+    fn mark_mergeable(&self) -> Result<(), GuestMemoryError>;
 }
 
 /// State of a guest memory region saved to file/buffer.
@@ -759,6 +811,16 @@ impl GuestMemoryExtension for GuestMemoryMmap {
             region.discard_range(start, len)
         })
     }
+
+    fn free_range(&self, addr: GuestAddress, range_len: usize) -> Result<(), GuestMemoryError> {
+        self.try_for_each_region_in_range(addr, range_len, |region, start, len| {
+            region.free_range(start, len)
+        })
+    }
+
+    fn mark_mergeable(&self) -> Result<(), GuestMemoryError> {
+        self.iter().try_for_each(|region| region.mark_mergeable())
+    }
 }
 
 fn create_memfd(
@@ -1330,4 +1392,59 @@ mod tests {
             GuestMemoryError::IOError(_)
         );
     }
+
+    #[test]
+    fn test_free_range() {
+        let page_size: usize = 0x1000;
+        let mem = single_region_mem(2 * page_size);
+        mem.write(&vec![1u8; 2 * page_size], GuestAddress(0))
+            .unwrap();
+
+        // The content of a lazily freed page depends on the memory pressure of the host.
+        mem.free_range(GuestAddress(0), page_size).unwrap();
+        let mut actual_page = vec![0u8; page_size];
+        mem.read(actual_page.as_mut_slice(), GuestAddress(page_size as u64))
+            .unwrap();
+        assert_eq!(vec![1u8; page_size], actual_page);
+
+        assert_match!(
+            mem.free_range(GuestAddress(0x10000), 0x10).unwrap_err(),
+            GuestMemoryError::InvalidGuestAddress(_)
+        );
+        assert_match!(
+            mem.free_range(GuestAddress(0x20), page_size).unwrap_err(),
+            GuestMemoryError::IOError(_)
+        );
+
+        // Private file mappings fall back to discarding the range, which zeroes it.
+        let mut memory_file = TempFile::new().unwrap().into_file();
+        memory_file.set_len(2 * page_size as u64).unwrap();
+        memory_file.write_all(&vec![2u8; 2 * page_size]).unwrap();
+        let mem = into_region_ext(
+            snapshot_file(
+                memory_file,
+                std::iter::once((GuestAddress(0), 2 * page_size)),
+                false,
+            )
+            .unwrap(),
+        );
+        mem.free_range(GuestAddress(0), page_size).unwrap();
+        mem.read(actual_page.as_mut_slice(), GuestAddress(0))
+            .unwrap();
+        assert_eq!(vec![0u8; page_size], actual_page);
+    }
+
+    #[test]
+    fn test_mark_mergeable() {
+        let mem = single_region_mem(0x2000);
+        // Without KSM support in the host kernel, madvise fails with EINVAL.
+        if std::path::Path::new("/sys/kernel/mm/ksm").exists() {
+            mem.mark_mergeable().unwrap();
+        } else {
+            assert_match!(
+                mem.mark_mergeable().unwrap_err(),
+                GuestMemoryError::IOError(_)
+            );
+        }
+    }
 }
@@ -33,6 +33,22 @@ fn allocate_many_ids(
     Ok(ids)
 }
 
+/// A range of guest memory the guest must preserve, reported to it as ACPI NVS.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AcpiNvsRegion {
+    /// Guest physical address of the start of the range.
+    pub guest_address: u64,
+    /// Size of the range in bytes.
+    pub size: u64,
+}
+
+impl AcpiNvsRegion {
+    /// Returns the first guest physical address past the range.
+    pub fn end(&self) -> u64 {
+        self.guest_address + self.size
+    }
+}
+
 /// A resource manager for (de)allocating interrupt lines (GSIs) and guest memory
 ///
 /// At the moment, we support:
@@ -54,6 +70,8 @@ pub struct ResourceAllocator {
     pub past_mmio64_memory: AddressAllocator,
     /// Memory allocator for system data
     pub system_memory: AddressAllocator,
+    /// Ranges of guest memory reported to the guest as ACPI NVS
+    pub acpi_nvs_regions: Vec<AcpiNvsRegion>,
 }
 
 impl Default for ResourceAllocator {
@@ -88,6 +106,7 @@ impl ResourceAllocator {
             .unwrap(),
             system_memory: AddressAllocator::new(arch::SYSTEM_MEM_START, arch::SYSTEM_MEM_SIZE)
                 .unwrap(),
+            acpi_nvs_regions: Vec::new(),
         }
     }
 
@@ -171,6 +190,36 @@ impl ResourceAllocator {
             .allocate(size, alignment, policy)?
             .start())
     }
+
+    /// Allocate a memory range for system data the guest must preserve, such as the memory it
+    /// shares with the VMM, and mark it as ACPI NVS
+    ///
+    /// If it succeeds, it returns the first address of the allocated range
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - The size in bytes of the memory to allocate
+    /// * `alignment` - The alignment of the address of the first byte
+    /// * `policy` - A [`vm_allocator::AllocPolicy`] variant for determining the allocation policy
+    pub fn allocate_acpi_nvs_memory(
+        &mut self,
+        size: u64,
+        alignment: u64,
+        policy: AllocPolicy,
+    ) -> Result<u64, vm_allocator::Error> {
+        let guest_address = self.allocate_system_memory(size, alignment, policy)?;
+        self.mark_acpi_nvs(guest_address, size);
+        Ok(guest_address)
+    }
+
+    /// Mark a range of guest memory as ACPI NVS, so that it is reported as such to the guest and
+    /// saved in snapshots
+    pub fn mark_acpi_nvs(&mut self, guest_address: u64, size: u64) {
+        self.acpi_nvs_regions.push(AcpiNvsRegion {
+            guest_address,
+            size,
+        });
+    }
 }
 
 impl<'a> Persist<'a> for ResourceAllocator {
@@ -194,7 +243,7 @@ impl<'a> Persist<'a> for ResourceAllocator {
 mod tests {
     use vm_allocator::AllocPolicy;
 
-    use super::ResourceAllocator;
+    use super::{AcpiNvsRegion, ResourceAllocator};
     use crate::arch::{self, GSI_LEGACY_NUM, GSI_LEGACY_START, GSI_MSI_NUM, GSI_MSI_START};
     use crate::snapshot::{Persist, Snapshot};
 
@@ -321,6 +370,16 @@ mod tests {
             .allocate_system_memory(0x42, 1, AllocPolicy::FirstMatch)
             .unwrap();
         assert_eq!(system_mem, arch::SYSTEM_MEM_START);
+        let nvs_mem = allocator1
+            .allocate_acpi_nvs_memory(0x42, 1, AllocPolicy::LastMatch)
+            .unwrap();
+        assert_eq!(
+            allocator1.acpi_nvs_regions,
+            [AcpiNvsRegion {
+                guest_address: nvs_mem,
+                size: 0x42,
+            }]
+        );
 
         let mut allocator2 = clone_allocator(&allocator1);
         allocator2
@@ -332,6 +391,7 @@ mod tests {
         allocator2
             .allocate_system_memory(0x42, 1, AllocPolicy::ExactMatch(system_mem))
             .unwrap_err();
+        assert_eq!(allocator2.acpi_nvs_regions, allocator1.acpi_nvs_regions);
 
         let irq_2 = allocator2.allocate_gsi_legacy(1).unwrap()[0];
         assert_eq!(irq_2, GSI_LEGACY_START + 2);
@@ -5,10 +5,13 @@
 // Use of this source code is governed by a BSD-style license that can be
 // found in the THIRD-PARTY file.
 
+use std::cell::Cell;
+use std::fs::File;
+use std::io::Write;
 use std::os::fd::AsRawFd;
 use std::sync::atomic::{Ordering, fence};
 use std::sync::mpsc::{Receiver, Sender, TryRecvError, channel};
-use std::sync::{Arc, Barrier};
+use std::sync::{Arc, Barrier, OnceLock};
 use std::{fmt, io, thread};
 
 use kvm_bindings::{KVM_SYSTEM_EVENT_RESET, KVM_SYSTEM_EVENT_SHUTDOWN};
@@ -33,6 +36,37 @@ use crate::vstate::vm::Vm;
 /// Signal number (SIGRTMIN) used to kick Vcpus.
 pub const VCPU_RTSIG_OFFSET: i32 = 0;
 
+thread_local! {
+    // Whether the current thread is a vCPU thread inside `KVM_RUN`.
+    static IN_KVM_RUN: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Returns whether the current thread is a vCPU thread inside `KVM_RUN`.
+///
+/// This is used by signal handlers to tell the signals sent by KVM on behalf of the guest.
+pub(crate) fn in_kvm_run() -> bool {
+    IN_KVM_RUN.get()
+}
+
+// `cgroup.threads` file of the cgroup the vCPU threads move into when they start.
+static VCPU_CGROUP: OnceLock<File> = OnceLock::new();
+
+/// Sets the `cgroup.threads` file of the cgroup the vCPU threads move into when they start.
+///
+/// The file is usually opened by the jailer, as the cgroup hierarchy is not reachable from
+/// inside the jail. Only the first call has an effect.
+pub fn set_vcpu_cgroup(cgroup_threads: File) {
+    if VCPU_CGROUP.set(cgroup_threads).is_err() {
+        warn!("The vCPU cgroup is already set");
+    }
+}
+
+// Moves the calling thread into the cgroup owning `cgroup_threads`.
+fn join_cgroup(mut cgroup_threads: &File) -> io::Result<()> {
+    // Writing 0 to `cgroup.threads` stands for the writing thread.
+    cgroup_threads.write_all(b"0")
+}
+
 /// Errors associated with the wrappers over KVM ioctls.
 #[derive(Debug, thiserror::Error, displaydoc::Display)]
 pub enum VcpuError {
@@ -62,6 +96,8 @@ pub struct VcpuConfig {
     pub vcpu_count: u8,
     /// Enable simultaneous multithreading in the CPUID configuration.
     pub smt: bool,
+    /// Expose the virtual PMU in the CPUID configuration.
+    pub pmu: bool,
     /// Configuration for vCPU
     pub cpu_config: CpuConfiguration,
 }
@@ -181,6 +217,14 @@ impl Vcpu {
             .name(format!("fc_vcpu {}", self.kvm_vcpu.index))
             .spawn(move || {
                 let filter = &*seccomp_filter;
+                if let Some(cgroup_threads) = VCPU_CGROUP.get()
+                    && let Err(err) = join_cgroup(cgroup_threads)
+                {
+                    panic!(
+                        "Failed to move vCPU {} to its cgroup: Error: {}",
+                        self.kvm_vcpu.index, err
+                    );
+                }
                 self.register_kick_signal_handler();
                 // Synchronization to make sure thread local data is initialized.
                 barrier.wait();
@@ -202,6 +246,14 @@ impl Vcpu {
     /// Note that the state of the VCPU and associated VM must be setup first for this to do
     /// anything useful.
     pub fn run(&mut self, seccomp_filter: BpfProgramRef) {
+        // Let the CPPC device apply the performance requests of the guest to this thread.
+        #[cfg(target_arch = "x86_64")]
+        if let Some(cppc) = &self.kvm_vcpu.peripherals.cppc {
+            cppc.lock()
+                .expect("Poisoned lock")
+                .register_vcpu_thread(self.kvm_vcpu.index);
+        }
+
         // Load seccomp filters for this vCPU thread.
         // Execution panics if filters cannot be loaded, use --no-seccomp if skipping filters
         // altogether is the desired behaviour.
@@ -230,6 +282,8 @@ impl Vcpu {
                 // specific. On x86 the i8042 emulation signals the main thread
                 // directly without calling Vcpu::exit().
                 Ok(VcpuEmulation::Stopped) => return self.exit(FcExitCode::Ok),
+                // The guest asked for a platform reset, which the VMM carries out.
+                Ok(VcpuEmulation::Reset) => return self.request_reset(),
                 // If the emulation requests a pause lets do this
                 #[cfg(feature = "gdb")]
                 Ok(VcpuEmulation::Paused) => {
@@ -281,6 +335,17 @@ impl Vcpu {
                     )))
                     .expect("vcpu channel unexpectedly closed");
             }
+            #[cfg(target_arch = "x86_64")]
+            Ok(VcpuEvent::InjectNmi) => self.inject_nmi(),
+            // RestoreState cannot be performed on a running Vcpu.
+            #[cfg(target_arch = "x86_64")]
+            Ok(VcpuEvent::RestoreState(_)) => {
+                self.response_sender
+                    .send(VcpuResponse::NotAllowed(String::from(
+                        "restoring the state is unavailable while running",
+                    )))
+                    .expect("vcpu channel unexpectedly closed");
+            }
             Ok(VcpuEvent::Finish) => return StateMachine::finish(),
             // Unhandled exit of the other end.
             Err(TryRecvError::Disconnected) => {
@@ -351,6 +416,24 @@ impl Vcpu {
 
                 StateMachine::next(Self::paused)
             }
+            // The NMI is delivered once the Vcpu is resumed.
+            #[cfg(target_arch = "x86_64")]
+            Ok(VcpuEvent::InjectNmi) => {
+                self.inject_nmi();
+                StateMachine::next(Self::paused)
+            }
+            #[cfg(target_arch = "x86_64")]
+            Ok(VcpuEvent::RestoreState(state)) => {
+                let response = match self.kvm_vcpu.restore_state(&state) {
+                    Ok(()) => VcpuResponse::RestoredState,
+                    Err(err) => VcpuResponse::Error(VcpuError::VcpuResponse(err)),
+                };
+                self.response_sender
+                    .send(response)
+                    .expect("vcpu channel unexpectedly closed");
+
+                StateMachine::next(Self::paused)
+            }
             Ok(VcpuEvent::Finish) => StateMachine::finish(),
             // Unhandled exit of the other end.
             Err(_) => {
@@ -360,23 +443,46 @@ impl Vcpu {
         }
     }
 
+    #[cfg(target_arch = "x86_64")]
+    fn inject_nmi(&self) {
+        if let Err(err) = self.kvm_vcpu.fd.nmi() {
+            METRICS.vcpu.failures.inc();
+            error!(
+                "Failed to inject NMI in vcpu {}: {}",
+                self.kvm_vcpu.index, err
+            );
+        }
+    }
+
+    // Signal the VMM that the guest asked for a platform reset and wait for it in the paused
+    // state. Unlike `exit()`, no exit code is reported, which is how the VMM tells the two apart.
+    fn request_reset(&mut self) -> StateMachine<Self> {
+        if let Err(err) = self.exit_evt.write(1) {
+            METRICS.vcpu.failures.inc();
+            error!("Failed signaling vcpu reset request: {}", err);
+        }
+        StateMachine::next(Self::paused)
+    }
+
     // Transition to the exited state and finish on command.
     // Note that this function isn't called when the guest asks for a CPU
     // reset via the i8042 controller on x86.
     fn exit(&mut self, exit_code: FcExitCode) -> StateMachine<Self> {
+        // Queue the exit code before signaling, so that the VMM never mistakes the exit for a
+        // reset request.
+        self.response_sender
+            .send(VcpuResponse::Exited(exit_code))
+            .expect("vcpu channel unexpectedly closed");
         if let Err(err) = self.exit_evt.write(1) {
             METRICS.vcpu.failures.inc();
             error!("Failed signaling vcpu exit event: {}", err);
         }
         // From this state we only accept going to finished.
-        loop {
+        // Wait for and only accept 'VcpuEvent::Finish'.
+        while !matches!(self.event_receiver.recv(), Ok(VcpuEvent::Finish)) {
             self.response_sender
                 .send(VcpuResponse::Exited(exit_code))
                 .expect("vcpu channel unexpectedly closed");
-            // Wait for and only accept 'VcpuEvent::Finish'.
-            if let Ok(VcpuEvent::Finish) = self.event_receiver.recv() {
-                break;
-            }
         }
         StateMachine::finish()
     }
@@ -391,7 +497,10 @@ impl Vcpu {
             return Ok(VcpuEmulation::Interrupted);
         }
 
-        match self.kvm_vcpu.fd.run() {
+        IN_KVM_RUN.set(true);
+        let emulation_result = self.kvm_vcpu.fd.run();
+        IN_KVM_RUN.set(false);
+        match emulation_result {
             Err(ref err) if err.errno() == libc::EINTR => {
                 self.kvm_vcpu.fd.set_kvm_immediate_exit(0);
                 // Notify that this KVM_RUN was interrupted.
@@ -521,6 +630,12 @@ pub enum VcpuEvent {
     SaveState,
     /// Event to dump CPU configuration of a paused Vcpu.
     DumpCpuConfig,
+    /// Inject an NMI in the Vcpu.
+    #[cfg(target_arch = "x86_64")]
+    InjectNmi,
+    /// Event to restore the state of a paused Vcpu.
+    #[cfg(target_arch = "x86_64")]
+    RestoreState(Arc<VcpuState>),
 }
 
 /// List of responses that the Vcpu reports.
@@ -537,6 +652,8 @@ pub enum VcpuResponse {
     Resumed,
     /// Vcpu state is saved.
     SavedState(Box<VcpuState>),
+    /// Vcpu state is restored.
+    RestoredState,
     /// Vcpu is in the state where CPU config is dumped.
     DumpedCpuConfig(Box<CpuConfiguration>),
 }
@@ -549,6 +666,7 @@ impl fmt::Debug for VcpuResponse {
             Resumed => write!(f, "VcpuResponse::Resumed"),
             Exited(code) => write!(f, "VcpuResponse::Exited({:?})", code),
             SavedState(_) => write!(f, "VcpuResponse::SavedState"),
+            RestoredState => write!(f, "VcpuResponse::RestoredState"),
             Error(err) => write!(f, "VcpuResponse::Error({:?})", err),
             NotAllowed(reason) => write!(f, "VcpuResponse::NotAllowed({})", reason),
             DumpedCpuConfig(_) => write!(f, "VcpuResponse::DumpedCpuConfig"),
@@ -644,6 +762,8 @@ pub enum VcpuEmulation {
     Interrupted,
     /// Stopped.
     Stopped,
+    /// The guest asked for a platform reset.
+    Reset,
     /// Pause request
     #[cfg(feature = "gdb")]
     Paused,
@@ -660,6 +780,7 @@ pub(crate) mod tests {
 
     use linux_loader::loader::KernelLoader;
     use vmm_sys_util::errno;
+    use vmm_sys_util::tempfile::TempFile;
 
     use super::*;
     use crate::RECV_TIMEOUT_SEC;
@@ -699,6 +820,14 @@ pub(crate) mod tests {
             Err(EmulationError::UnhandledKvmExit(s)) if s == "Shutdown",
         ));
 
+        #[cfg(target_arch = "x86_64")]
+        {
+            vcpu.kvm_vcpu.peripherals.reset_on_triple_fault = true;
+            let res = handle_kvm_exit(&mut vcpu.kvm_vcpu.peripherals, Ok(VcpuExit::Shutdown));
+            assert_eq!(res.unwrap(), VcpuEmulation::Reset);
+            vcpu.kvm_vcpu.peripherals.reset_on_triple_fault = false;
+        }
+
         let res = handle_kvm_exit(
             &mut vcpu.kvm_vcpu.peripherals,
             Ok(VcpuExit::FailEntry(0, 0)),
@@ -889,6 +1018,7 @@ pub(crate) mod tests {
                     &VcpuConfig {
                         vcpu_count: 1,
                         smt: false,
+                        pmu: false,
                         cpu_config: CpuConfiguration {
                             cpuid: Cpuid::try_from(kvm.supported_cpuid.clone()).unwrap(),
                             msrs: BTreeMap::new(),
@@ -906,6 +1036,7 @@ pub(crate) mod tests {
                 &VcpuConfig {
                     vcpu_count: 1,
                     smt: false,
+                    pmu: false,
                     cpu_config: crate::cpu_config::aarch64::CpuConfiguration::default(),
                 },
                 &kvm.optional_capabilities(),
@@ -1123,4 +1254,18 @@ pub(crate) mod tests {
     fn test_vcpu_rtsig_offset() {
         validate_signal_num(sigrtmin() + VCPU_RTSIG_OFFSET).unwrap();
     }
+
+    #[test]
+    fn test_join_cgroup() {
+        let cgroup_threads = TempFile::new().unwrap();
+        join_cgroup(cgroup_threads.as_file()).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(cgroup_threads.as_path()).unwrap(),
+            "0"
+        );
+
+        // A read-only file stands for a cgroup the thread is not allowed to move to.
+        let cgroup_threads = File::open(cgroup_threads.as_path()).unwrap();
+        join_cgroup(&cgroup_threads).unwrap_err();
+    }
 }
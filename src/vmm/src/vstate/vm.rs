@@ -397,6 +397,12 @@ impl Vm {
     pub fn register_irq(&self, fd: &EventFd, gsi: u32) -> Result<(), errno::Error> {
         self.common.fd.register_irqfd(fd, gsi)?;
 
+        // With a split irqchip, the userspace IOAPIC routes its pins as the guest programs them.
+        #[cfg(target_arch = "x86_64")]
+        if self.split_irqchip() {
+            return Ok(());
+        }
+
         let mut entry = kvm_irq_routing_entry {
             gsi,
             type_: KVM_IRQ_ROUTING_IRQCHIP,
@@ -469,6 +475,42 @@ impl Vm {
         Ok(())
     }
 
+    /// Routes a pin of the userspace IOAPIC to the MSI message its redirection table entry
+    /// describes, or removes its route if the entry is masked.
+    #[cfg(target_arch = "x86_64")]
+    pub fn route_ioapic_pin(
+        &self,
+        pin: u32,
+        config: Option<MsixVectorConfig>,
+    ) -> Result<(), InterruptError> {
+        {
+            let mut interrupts = self.common.interrupts.lock().expect("Poisoned lock");
+            match config {
+                Some(config) => {
+                    let mut entry = kvm_irq_routing_entry {
+                        gsi: pin,
+                        type_: KVM_IRQ_ROUTING_MSI,
+                        ..Default::default()
+                    };
+                    entry.u.msi.address_lo = config.low_addr;
+                    entry.u.msi.address_hi = config.high_addr;
+                    entry.u.msi.data = config.data;
+                    interrupts.insert(
+                        pin,
+                        RoutingEntry {
+                            entry,
+                            masked: false,
+                        },
+                    );
+                }
+                None => {
+                    interrupts.remove(&pin);
+                }
+            }
+        }
+        self.set_gsi_routes()
+    }
+
     /// Create a group of MSI-X interrupts
     pub fn create_msix_group(vm: Arc<Vm>, count: u16) -> Result<MsixVectorGroup, InterruptError> {
         debug!("Creating new MSI group with {count} vectors");
@@ -493,7 +535,15 @@ impl Vm {
             if entry.masked {
                 continue;
             }
-            routes.push(entry.entry)?;
+            // KVM delivers MSI messages as they are, so the ones the guest remaps are routed to
+            // their translation.
+            #[cfg(target_arch = "x86_64")]
+            let Some(entry) = self.remap_route(entry.entry) else {
+                continue;
+            };
+            #[cfg(target_arch = "aarch64")]
+            let entry = entry.entry;
+            routes.push(entry)?;
         }
 
         self.common.fd.set_gsi_routing(&routes)?;
@@ -503,7 +553,7 @@ impl Vm {
 
 /// Use `mincore(2)` to overapproximate the dirty bitmap for the given memslot. To be used
 /// if a diff snapshot is requested, but dirty page tracking wasn't enabled.
-fn mincore_bitmap(addr: *mut u8, len: usize) -> Result<Vec<u64>, VmError> {
+pub(crate) fn mincore_bitmap(addr: *mut u8, len: usize) -> Result<Vec<u64>, VmError> {
     // TODO: Once Host 5.10 goes out of support, we can make this more robust and work on
     // swap-enabled systems, by doing mlock2(MLOCK_ONFAULT)/munlock() in this function (to
     // force swapped-out pages to get paged in, so that mincore will consider them incore).
@@ -302,6 +302,7 @@ fn verify_load_snapshot(snapshot_file: TempFile, memory_file: TempFile) {
             track_dirty_pages: false,
             resume_vm: true,
             network_overrides: vec![],
+            clone: None,
         }))
         .unwrap();
 
@@ -386,6 +387,7 @@ fn verify_load_snap_disallowed_after_boot_resources(res: VmmAction, res_name: &s
         track_dirty_pages: false,
         resume_vm: false,
         network_overrides: vec![],
+        clone: None,
     });
     let err = preboot_api_controller.handle_preboot_request(req);
     assert!(
@@ -432,6 +434,7 @@ fn test_preboot_load_snap_disallowed_after_boot_resources() {
         guest_mac: None,
         rx_rate_limiter: None,
         tx_rate_limiter: None,
+        vhost: false,
     });
     verify_load_snap_disallowed_after_boot_resources(req, "InsertNetworkDevice");
 
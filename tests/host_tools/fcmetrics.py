@@ -125,6 +125,7 @@ def validate_fc_metrics(metrics):
         "tx_rate_limiter_throttled",
         "tx_spoofed_mac_count",
         "tx_remaining_reqs_count",
+        "vhost_fallbacks",
         {"tap_write_agg": latency_agg_metrics_fields},
     ]
     clawdbox_metrics = {
@@ -146,6 +147,8 @@ def validate_fc_metrics(metrics):
             "free_page_hint_count",
             "free_page_hint_freed",
             "free_page_hint_fails",
+            "free_page_lazy_freed",
+            "ksm_merging_bytes",
         ],
         "block": block_metrics,
         "deprecated_api": [
@@ -157,6 +160,11 @@ def validate_fc_metrics(metrics):
             "mmds_count",
             "vmm_version_count",
             "hotplug_memory_count",
+            "cpu_features_count",
+            "guest_info_count",
+            "watchdog_count",
+            "processor_aggregator_count",
+            "acpi_tables_count",
         ],
         "i8042": [
             "error_count",
@@ -210,6 +218,12 @@ def validate_fc_metrics(metrics):
             "mmds_fails",
             "hotplug_memory_count",
             "hotplug_memory_fails",
+            "power_supply_count",
+            "power_supply_fails",
+            "processor_aggregator_count",
+            "processor_aggregator_fails",
+            "thermal_zone_count",
+            "thermal_zone_fails",
         ],
         "put_api_requests": [
             "actions_count",
@@ -234,10 +248,50 @@ def validate_fc_metrics(metrics):
             "vsock_fails",
             "pmem_count",
             "pmem_fails",
+            "vfio_count",
+            "vfio_fails",
+            "vfio_vf_count",
+            "vfio_vf_fails",
+            "usb_count",
+            "usb_fails",
+            "nvme_count",
+            "nvme_fails",
             "serial_count",
             "serial_fails",
             "hotplug_memory_count",
             "hotplug_memory_fails",
+            "acpi_tables_count",
+            "acpi_tables_fails",
+            "guest_exec_count",
+            "guest_exec_fails",
+            "watchdog_count",
+            "watchdog_fails",
+            "apei_count",
+            "apei_fails",
+            "reboot_count",
+            "reboot_fails",
+            "power_supply_count",
+            "power_supply_fails",
+            "processor_aggregator_count",
+            "processor_aggregator_fails",
+            "smbios_count",
+            "smbios_fails",
+            "memory_map_count",
+            "memory_map_fails",
+            "sgx_count",
+            "sgx_fails",
+            "s2idle_count",
+            "s2idle_fails",
+            "pmu_count",
+            "pmu_fails",
+            "cppc_count",
+            "cppc_fails",
+            "thermal_zone_count",
+            "thermal_zone_fails",
+            "fw_cfg_count",
+            "fw_cfg_fails",
+            "scheduled_actions_count",
+            "scheduled_actions_fails",
         ],
         "seccomp": [
             "num_faults",
@@ -273,6 +327,7 @@ def validate_fc_metrics(metrics):
             "sigpipe",
             "sighup",
             "sigill",
+            "sigbus_memory_errors",
         ],
         "vsock": [
             "activate_fails",
@@ -306,6 +361,14 @@ def validate_fc_metrics(metrics):
             "rate_limiter_event_count",
         ],
         "interrupts": ["triggers", "config_updates"],
+        "s2idle": ["entries", "residency_us"],
+        "cppc": ["perf_requests", "hint_fails"],
+        "scheduled_actions": [
+            "guest_panics",
+            "guest_ooms",
+            "actions",
+            "action_fails",
+        ],
         "pmem": [
             "activate_fails",
             "cfg_fails",
@@ -1163,6 +1163,9 @@ def test_get_full_config_after_restoring_snapshot(microvm_factory, uvm_nano):
         "smt": True,
         "track_dirty_pages": False,
         "huge_pages": "None",
+        "nested_virtualization": False,
+        "split_irqchip": False,
+        "legacy_free": False,
     }
 
     if cpu_vendor == utils_cpuid.CpuVendor.ARM:
@@ -1212,6 +1215,8 @@ def test_get_full_config_after_restoring_snapshot(microvm_factory, uvm_nano):
         "stats_polling_interval_s": 0,
         "free_page_reporting": False,
         "free_page_hinting": False,
+        "free_page_advice": "dontneed",
+        "ksm": False,
     }
 
     # Add a vsock device.
@@ -1303,6 +1308,9 @@ def test_get_full_config(uvm_plain):
         "smt": False,
         "track_dirty_pages": False,
         "huge_pages": "None",
+        "nested_virtualization": False,
+        "split_irqchip": False,
+        "legacy_free": False,
     }
     expected_cfg["cpu-config"] = None
     expected_cfg["boot-source"] = {
@@ -1347,6 +1355,8 @@ def test_get_full_config(uvm_plain):
         "stats_polling_interval_s": 0,
         "free_page_reporting": False,
         "free_page_hinting": False,
+        "free_page_advice": "dontneed",
+        "ksm": False,
     }
 
     # Add a vsock device.